@@ -76,6 +76,9 @@ async fn main() -> std::io::Result<()> {
         Commands::Dkim(command) => command.exec(client).await,
         Commands::Queue(command) => command.exec(client).await,
         Commands::Report(command) => command.exec(client).await,
+        Commands::Principal(command) => command.exec(client).await,
+        Commands::Purge(command) => command.exec(client).await,
+        Commands::Job(command) => command.exec(client).await,
     }
 
     Ok(())
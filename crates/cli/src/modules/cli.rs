@@ -72,6 +72,18 @@ pub enum Commands {
     /// Manage SMTP DMARC/TLS report queue
     #[clap(subcommand)]
     Report(ReportCommands),
+
+    /// Manage principals in bulk
+    #[clap(subcommand)]
+    Principal(PrincipalCommands),
+
+    /// Purge stores and accounts
+    #[clap(subcommand)]
+    Purge(PurgeCommands),
+
+    /// Manage background jobs
+    #[clap(subcommand)]
+    Job(JobCommands),
 }
 
 pub struct Client {
@@ -456,7 +468,7 @@ pub enum ServerCommands {
 
     /// Perform Healthcheck
     Healthcheck {
-        /// Status `ready` (default) or `live` to check for
+        /// Status `ready` (default), `live` or `deep` to check for
         check: Option<String>
     },
 }
@@ -543,6 +555,52 @@ pub enum QueueCommands {
         // Cancel one or multiple message ids
         ids: Vec<String>,
     },
+
+    /// Search queued messages by sender or recipient address
+    Search {
+        /// Text to search for in sender and recipient addresses
+        text: String,
+        /// Only include messages with recipients in this queue
+        #[clap(short, long)]
+        queue: Option<String>,
+        /// Number of items to show per page
+        #[clap(short, long)]
+        page_size: Option<usize>,
+    },
+
+    /// Hold delivery until a certain datetime
+    Hold {
+        /// Hold delivery until this datetime
+        #[clap(short, long)]
+        #[arg(value_parser = parse_datetime)]
+        until: DateTime,
+        /// Apply to messages matching a sender address
+        #[clap(short, long)]
+        sender: Option<String>,
+        /// Apply to specific recipients or domains
+        #[clap(short, long)]
+        rcpt: Option<String>,
+        /// Show the affected messages without holding them
+        #[clap(long)]
+        dry_run: bool,
+        // Hold one or multiple message ids
+        ids: Vec<String>,
+    },
+
+    /// Release held messages for immediate delivery
+    Release {
+        /// Apply to messages matching a sender address
+        #[clap(short, long)]
+        sender: Option<String>,
+        /// Apply to specific recipients or domains
+        #[clap(short, long)]
+        rcpt: Option<String>,
+        /// Show the affected messages without releasing them
+        #[clap(long)]
+        dry_run: bool,
+        // Release one or multiple message ids
+        ids: Vec<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -584,6 +642,186 @@ pub enum ReportFormat {
     Tls,
 }
 
+#[derive(Subcommand)]
+pub enum PrincipalCommands {
+    /// List principals
+    List {
+        /// Filter by principal type
+        #[clap(short, long)]
+        #[clap(value_enum)]
+        r#type: Option<PrincipalType>,
+        /// Filter principals by keywords
+        #[clap(short, long)]
+        filter: Option<String>,
+        /// Maximum number of principals to list
+        #[clap(short, long)]
+        limit: Option<usize>,
+        /// Page number
+        #[clap(short, long)]
+        page: Option<usize>,
+    },
+
+    /// Update multiple principals at once
+    Update {
+        /// Apply to principals of this type
+        #[clap(short, long)]
+        #[clap(value_enum)]
+        r#type: Option<PrincipalType>,
+        /// Apply to principals matching these keywords
+        #[clap(short, long)]
+        filter: Option<String>,
+        /// Set the quota in bytes
+        #[clap(short, long)]
+        quota: Option<u64>,
+        /// Set the description
+        #[clap(short, long)]
+        description: Option<String>,
+        /// Add the principals to these groups or lists
+        #[clap(long)]
+        add_member_of: Vec<String>,
+        /// Remove the principals from these groups or lists
+        #[clap(long)]
+        remove_member_of: Vec<String>,
+        /// Add these roles to the principals
+        #[clap(long)]
+        add_role: Vec<String>,
+        /// Remove these roles from the principals
+        #[clap(long)]
+        remove_role: Vec<String>,
+        /// Show the affected principals without updating them
+        #[clap(long)]
+        dry_run: bool,
+        /// Principal names to update
+        names: Vec<String>,
+    },
+
+    /// Delete multiple principals at once
+    Delete {
+        /// Delete all principals of this type in a background job
+        #[clap(short, long)]
+        #[clap(value_enum)]
+        r#type: Option<PrincipalType>,
+        /// Only delete principals matching these keywords
+        #[clap(short, long)]
+        filter: Option<String>,
+        /// Show the affected principals without deleting them
+        #[clap(long)]
+        dry_run: bool,
+        /// Principal names to delete
+        names: Vec<String>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum PrincipalType {
+    /// User accounts
+    Individual,
+    /// Groups
+    Group,
+    /// Mailing lists
+    List,
+    /// Resources
+    Resource,
+    /// Locations
+    Location,
+    /// Domains
+    Domain,
+    /// Tenants
+    Tenant,
+    /// Roles
+    Role,
+    /// API keys
+    ApiKey,
+    /// OAuth clients
+    OauthClient,
+}
+
+#[derive(Subcommand)]
+pub enum PurgeCommands {
+    /// Remove unreferenced blobs from the blob store
+    Blobs {
+        /// Show the request without executing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Purge expired data from a data store
+    Data {
+        /// Store id, defaults to the main data store
+        store: Option<String>,
+        /// Show the request without executing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Purge keys from an in-memory store
+    InMemory {
+        /// Store id, defaults to the main in-memory store
+        #[clap(short, long)]
+        store: Option<String>,
+        /// Key prefix to purge (i.e. rate-smtp, greylist, bayes-account)
+        prefix: Option<String>,
+        /// Account name, only used with the bayes-account prefix
+        account: Option<String>,
+        /// Show the request without executing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Purge deleted messages and expired changes from accounts
+    Account {
+        /// Account name, defaults to all accounts
+        name: Option<String>,
+        /// Show the request without executing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum JobCommands {
+    /// List background jobs
+    List {
+        /// Filter by job status
+        #[clap(short, long)]
+        #[clap(value_enum)]
+        status: Option<JobStatus>,
+        /// Maximum number of jobs to list
+        #[clap(short, long)]
+        limit: Option<usize>,
+        /// Page number
+        #[clap(short, long)]
+        page: Option<usize>,
+    },
+
+    /// Displays details about a background job
+    Status {
+        #[clap(required = true)]
+        ids: Vec<u64>,
+    },
+
+    /// Cancel a pending or running background job
+    Cancel {
+        #[clap(required = true)]
+        ids: Vec<u64>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    /// Waiting to be executed
+    Pending,
+    /// Currently executing
+    Running,
+    /// Completed successfully
+    Completed,
+    /// Failed after all retry attempts
+    Failed,
+    /// Cancelled by an administrator
+    Cancelled,
+}
+
 fn parse_datetime(arg: &str) -> Result<DateTime, &'static str> {
     if arg.contains('T') {
         DateTime::parse_rfc3339(arg).ok_or("Failed to parse RFC3339 datetime")
@@ -0,0 +1,205 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{
+    List,
+    cli::{Client, JobCommands, JobStatus},
+};
+use mail_parser::DateTime;
+use prettytable::{Attr, Cell, Row, Table, format::Alignment};
+use reqwest::Method;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: u64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub created: u64,
+    pub updated: u64,
+    pub due: u64,
+    pub attempts: u32,
+    pub items: Vec<u32>,
+    pub done: usize,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    FtsReindex,
+    #[serde(rename_all = "camelCase")]
+    DeletePrincipals {
+        principal_type: String,
+    },
+    KeywordCleanup,
+}
+
+impl JobCommands {
+    pub async fn exec(self, client: Client) {
+        match self {
+            JobCommands::List {
+                status,
+                limit,
+                page,
+            } => {
+                let mut query = form_urlencoded::Serializer::new("/api/jobs?".to_string());
+                if let Some(status) = status {
+                    query.append_pair("status", status.as_str());
+                }
+                if let Some(limit) = limit {
+                    query.append_pair("limit", &limit.to_string());
+                }
+                if let Some(page) = page {
+                    query.append_pair("page", &page.to_string());
+                }
+
+                let results = client
+                    .http_request::<List<Job>, String>(Method::GET, &query.finish(), None)
+                    .await;
+
+                if !results.items.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(Row::new(
+                        ["ID", "Job", "Status", "Progress", "Updated"]
+                            .iter()
+                            .map(|p| Cell::new(p).with_style(Attr::Bold))
+                            .collect(),
+                    ));
+
+                    for job in &results.items {
+                        table.add_row(Row::new(vec![
+                            Cell::new(&job.id.to_string()),
+                            Cell::new(&job.kind.to_string()),
+                            Cell::new(job.status.as_str()),
+                            Cell::new(&format!("{}/{}", job.done, job.items.len())),
+                            Cell::new(&DateTime::from_timestamp(job.updated as i64).to_rfc822()),
+                        ]));
+                    }
+
+                    eprintln!();
+                    table.printstd();
+                    eprintln!();
+                }
+
+                eprintln!(
+                    "\n\n{} job{} found.\n",
+                    results.total,
+                    if results.total == 1 { "" } else { "s" }
+                );
+            }
+            JobCommands::Status { ids } => {
+                for id in ids {
+                    let job = client
+                        .try_http_request::<Job, String>(
+                            Method::GET,
+                            &format!("/api/jobs/{id}"),
+                            None,
+                        )
+                        .await;
+                    let mut table = Table::new();
+                    table.add_row(Row::new(vec![
+                        Cell::new("ID").with_style(Attr::Bold),
+                        Cell::new(&id.to_string()),
+                    ]));
+
+                    if let Some(job) = job {
+                        for (name, value) in [
+                            ("Job", job.kind.to_string()),
+                            ("Status", job.status.as_str().to_string()),
+                            (
+                                "Progress",
+                                format!("{}/{} item(s)", job.done, job.items.len()),
+                            ),
+                            ("Attempts", job.attempts.to_string()),
+                            (
+                                "Created",
+                                DateTime::from_timestamp(job.created as i64).to_rfc822(),
+                            ),
+                            (
+                                "Updated",
+                                DateTime::from_timestamp(job.updated as i64).to_rfc822(),
+                            ),
+                            ("Due", DateTime::from_timestamp(job.due as i64).to_rfc822()),
+                        ] {
+                            table.add_row(Row::new(vec![
+                                Cell::new(name).with_style(Attr::Bold),
+                                Cell::new(&value),
+                            ]));
+                        }
+                        if let Some(error) = &job.error {
+                            table.add_row(Row::new(vec![
+                                Cell::new("Error").with_style(Attr::Bold),
+                                Cell::new(error),
+                            ]));
+                        }
+                    } else {
+                        table.add_row(Row::new(vec![
+                            Cell::new_align("-- Not found --", Alignment::CENTER).with_hspan(2),
+                        ]));
+                    }
+
+                    eprintln!();
+                    table.printstd();
+                    eprintln!();
+                }
+            }
+            JobCommands::Cancel { ids } => {
+                let mut success_count = 0;
+                let mut failed_list = vec![];
+
+                for id in ids {
+                    if client
+                        .try_http_request::<bool, String>(
+                            Method::DELETE,
+                            &format!("/api/jobs/{id}"),
+                            None,
+                        )
+                        .await
+                        .unwrap_or(false)
+                    {
+                        success_count += 1;
+                    } else {
+                        failed_list.push(id.to_string());
+                    }
+                }
+
+                eprint!("\nCancelled {success_count} job(s).");
+                if !failed_list.is_empty() {
+                    eprint!(" Unable to cancel job id(s): {}.", failed_list.join(", "));
+                }
+                eprintln!();
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobKind::FtsReindex => write!(f, "Full-text reindex"),
+            JobKind::DeletePrincipals { principal_type } => {
+                write!(f, "Delete principals ({principal_type})")
+            }
+            JobKind::KeywordCleanup => write!(f, "Keyword cleanup"),
+        }
+    }
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
@@ -20,7 +20,10 @@ pub mod domain;
 pub mod export;
 pub mod group;
 pub mod import;
+pub mod job;
 pub mod list;
+pub mod principal;
+pub mod purge;
 pub mod queue;
 pub mod report;
 
@@ -99,6 +102,8 @@ pub enum PrincipalField {
     MemberOf,
     #[serde(rename = "members")]
     Members,
+    #[serde(rename = "roles")]
+    Roles,
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize, Default)]
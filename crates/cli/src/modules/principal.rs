@@ -0,0 +1,307 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{
+    List, PrincipalField, PrincipalUpdate, PrincipalValue,
+    cli::{Client, PrincipalCommands, PrincipalType},
+};
+use prettytable::{Attr, Cell, Row, Table};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Debug, Deserialize)]
+pub struct PrincipalSummary {
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub typ: String,
+    #[serde(default)]
+    pub emails: Option<PrincipalValue>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl PrincipalCommands {
+    pub async fn exec(self, client: Client) {
+        match self {
+            PrincipalCommands::List {
+                r#type,
+                filter,
+                limit,
+                page,
+            } => {
+                let results = client
+                    .query_principals(r#type, &filter, limit, page, "name,type,emails,description")
+                    .await;
+                client.display_principals(&results.items);
+                eprintln!(
+                    "\n\n{} principal{} found.\n",
+                    results.total,
+                    if results.total == 1 { "" } else { "s" }
+                );
+            }
+            PrincipalCommands::Update {
+                r#type,
+                filter,
+                quota,
+                description,
+                add_member_of,
+                remove_member_of,
+                add_role,
+                remove_role,
+                dry_run,
+                names,
+            } => {
+                let mut changes = Vec::new();
+                if let Some(quota) = quota {
+                    changes.push(PrincipalUpdate::set(
+                        PrincipalField::Quota,
+                        PrincipalValue::Integer(quota),
+                    ));
+                }
+                if let Some(description) = description {
+                    changes.push(PrincipalUpdate::set(
+                        PrincipalField::Description,
+                        PrincipalValue::String(description),
+                    ));
+                }
+                for (field, values, is_add) in [
+                    (PrincipalField::MemberOf, add_member_of, true),
+                    (PrincipalField::MemberOf, remove_member_of, false),
+                    (PrincipalField::Roles, add_role, true),
+                    (PrincipalField::Roles, remove_role, false),
+                ] {
+                    for value in values {
+                        changes.push(if is_add {
+                            PrincipalUpdate::add_item(field, PrincipalValue::String(value))
+                        } else {
+                            PrincipalUpdate::remove_item(field, PrincipalValue::String(value))
+                        });
+                    }
+                }
+                if changes.is_empty() {
+                    eprintln!("No changes to apply.");
+                    std::process::exit(1);
+                }
+
+                let names = client.select_principals(r#type, &filter, names).await;
+                if dry_run {
+                    eprintln!(
+                        "Dry run, the following {} principal(s) would be updated: {}.",
+                        names.len(),
+                        names.join(", ")
+                    );
+                    return;
+                }
+
+                let mut success_count = 0;
+                let mut failed_list = vec![];
+                for name in names {
+                    if client
+                        .try_http_request::<Value, _>(
+                            Method::PATCH,
+                            &format!("/api/principal/{name}"),
+                            Some(&changes),
+                        )
+                        .await
+                        .is_some()
+                    {
+                        success_count += 1;
+                    } else {
+                        failed_list.push(name);
+                    }
+                }
+
+                eprint!("\nSuccessfully updated {success_count} principal(s).");
+                if !failed_list.is_empty() {
+                    eprint!(" Unable to find principal(s): {}.", failed_list.join(", "));
+                }
+                eprintln!();
+            }
+            PrincipalCommands::Delete {
+                r#type,
+                filter,
+                dry_run,
+                names,
+            } => {
+                if names.is_empty() {
+                    // Deleting by type is performed by the server in a background job
+                    let Some(typ) = r#type else {
+                        eprintln!("Specify the principal names or type to delete.");
+                        std::process::exit(1);
+                    };
+                    if dry_run {
+                        let names = client.select_principals(r#type, &filter, names).await;
+                        eprintln!(
+                            "Dry run, the following {} principal(s) would be deleted: {}.",
+                            names.len(),
+                            names.join(", ")
+                        );
+                        return;
+                    }
+
+                    let mut query = form_urlencoded::Serializer::new("/api/principal?".to_string());
+                    query.append_pair("type", typ.as_str());
+                    query.append_pair("confirm", "true");
+                    if let Some(filter) = &filter {
+                        query.append_pair("filter", filter);
+                    }
+
+                    if client
+                        .http_request::<bool, String>(Method::DELETE, &query.finish(), None)
+                        .await
+                    {
+                        eprintln!("Deletion job submitted, use 'job list' to follow its progress.");
+                    } else {
+                        eprintln!("No principals were found.");
+                    }
+                    return;
+                }
+
+                if dry_run {
+                    eprintln!(
+                        "Dry run, the following {} principal(s) would be deleted: {}.",
+                        names.len(),
+                        names.join(", ")
+                    );
+                    return;
+                }
+
+                let mut success_count = 0;
+                let mut failed_list = vec![];
+                for name in names {
+                    if client
+                        .try_http_request::<Value, String>(
+                            Method::DELETE,
+                            &format!("/api/principal/{name}"),
+                            None,
+                        )
+                        .await
+                        .is_some()
+                    {
+                        success_count += 1;
+                    } else {
+                        failed_list.push(name);
+                    }
+                }
+
+                eprint!("\nSuccessfully deleted {success_count} principal(s).");
+                if !failed_list.is_empty() {
+                    eprint!(" Unable to find principal(s): {}.", failed_list.join(", "));
+                }
+                eprintln!();
+            }
+        }
+    }
+}
+
+impl Client {
+    async fn query_principals(
+        &self,
+        typ: Option<PrincipalType>,
+        filter: &Option<String>,
+        limit: Option<usize>,
+        page: Option<usize>,
+        fields: &str,
+    ) -> List<PrincipalSummary> {
+        let mut query = form_urlencoded::Serializer::new("/api/principal?".to_string());
+
+        if let Some(typ) = typ {
+            query.append_pair("types", typ.as_str());
+        }
+        if let Some(filter) = filter {
+            query.append_pair("filter", filter);
+        }
+        if let Some(limit) = limit {
+            query.append_pair("limit", &limit.to_string());
+        }
+        if let Some(page) = page {
+            query.append_pair("page", &page.to_string());
+        }
+        query.append_pair("fields", fields);
+
+        self.http_request::<List<PrincipalSummary>, String>(Method::GET, &query.finish(), None)
+            .await
+    }
+
+    async fn select_principals(
+        &self,
+        typ: Option<PrincipalType>,
+        filter: &Option<String>,
+        names: Vec<String>,
+    ) -> Vec<String> {
+        let names = if !names.is_empty() {
+            names
+        } else if typ.is_some() || filter.is_some() {
+            self.query_principals(typ, filter, None, None, "name")
+                .await
+                .items
+                .into_iter()
+                .map(|principal| principal.name)
+                .filter(|name| !name.is_empty())
+                .collect()
+        } else {
+            vec![]
+        };
+
+        if names.is_empty() {
+            eprintln!("No principals were found.");
+            std::process::exit(1);
+        }
+
+        names
+    }
+
+    fn display_principals(&self, principals: &[PrincipalSummary]) {
+        if principals.is_empty() {
+            return;
+        }
+
+        let mut table = Table::new();
+        table.add_row(Row::new(
+            ["Name", "Type", "E-mail address(es)", "Description"]
+                .iter()
+                .map(|p| Cell::new(p).with_style(Attr::Bold))
+                .collect(),
+        ));
+
+        for principal in principals {
+            table.add_row(Row::new(vec![
+                Cell::new(&principal.name),
+                Cell::new(&principal.typ),
+                Cell::new(&match &principal.emails {
+                    Some(PrincipalValue::String(email)) => email.clone(),
+                    Some(PrincipalValue::StringList(emails)) => emails.join(", "),
+                    _ => String::new(),
+                }),
+                Cell::new(principal.description.as_deref().unwrap_or_default()),
+            ]));
+        }
+
+        eprintln!();
+        table.printstd();
+        eprintln!();
+    }
+}
+
+impl PrincipalType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrincipalType::Individual => "individual",
+            PrincipalType::Group => "group",
+            PrincipalType::List => "list",
+            PrincipalType::Resource => "resource",
+            PrincipalType::Location => "location",
+            PrincipalType::Domain => "domain",
+            PrincipalType::Tenant => "tenant",
+            PrincipalType::Role => "role",
+            PrincipalType::ApiKey => "apiKey",
+            PrincipalType::OauthClient => "oauthClient",
+        }
+    }
+}
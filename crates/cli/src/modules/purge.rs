@@ -0,0 +1,94 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::cli::{Client, PurgeCommands};
+use reqwest::Method;
+use serde_json::Value;
+
+impl PurgeCommands {
+    pub async fn exec(self, client: Client) {
+        let (url, description, dry_run) = match self {
+            PurgeCommands::Blobs { dry_run } => (
+                "/api/store/purge/blob".to_string(),
+                "unreferenced blobs".to_string(),
+                dry_run,
+            ),
+            PurgeCommands::Data { store, dry_run } => {
+                let store = store.unwrap_or_else(|| "default".to_string());
+                (
+                    format!("/api/store/purge/data/{store}"),
+                    format!("expired data from store {store:?}"),
+                    dry_run,
+                )
+            }
+            PurgeCommands::InMemory {
+                store,
+                prefix,
+                account,
+                dry_run,
+            } => {
+                let store = store.unwrap_or_else(|| "default".to_string());
+                let mut url = format!("/api/store/purge/in-memory/{store}");
+                let mut description = format!("in-memory store {store:?}");
+                if let Some(prefix) = prefix {
+                    url.push('/');
+                    url.push_str(&prefix);
+                    description = format!("{prefix:?} keys from {description}");
+                    if let Some(account) = account {
+                        url.push('/');
+                        url.push_str(&account);
+                        description = format!("{description} for account {account:?}");
+                    }
+                } else if account.is_some() {
+                    eprintln!("An account can only be specified together with a prefix.");
+                    std::process::exit(1);
+                } else {
+                    description = format!("all keys from {description}");
+                }
+                (url, description, dry_run)
+            }
+            PurgeCommands::Account { name, dry_run } => {
+                if let Some(name) = name {
+                    // Make sure the account exists before scheduling the purge
+                    if dry_run
+                        && client
+                            .try_http_request::<Value, String>(
+                                Method::GET,
+                                &format!("/api/principal/{name}"),
+                                None,
+                            )
+                            .await
+                            .is_none()
+                    {
+                        eprintln!("Account {name:?} not found.");
+                        std::process::exit(1);
+                    }
+
+                    (
+                        format!("/api/store/purge/account/{name}"),
+                        format!("account {name:?}"),
+                        dry_run,
+                    )
+                } else {
+                    (
+                        "/api/store/purge/account".to_string(),
+                        "all accounts".to_string(),
+                        dry_run,
+                    )
+                }
+            }
+        };
+
+        if dry_run {
+            eprintln!("Dry run, would purge {description} using GET {url}.");
+        } else {
+            client
+                .http_request::<Value, String>(Method::GET, &url, None)
+                .await;
+            eprintln!("Successfully requested purge of {description}.");
+        }
+    }
+}
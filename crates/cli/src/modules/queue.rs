@@ -39,6 +39,8 @@ pub struct Recipient {
     #[serde(deserialize_with = "deserialize_maybe_datetime", default)]
     pub next_retry: Option<DateTime>,
     #[serde(deserialize_with = "deserialize_maybe_datetime", default)]
+    pub held_until: Option<DateTime>,
+    #[serde(deserialize_with = "deserialize_maybe_datetime", default)]
     pub next_notify: Option<DateTime>,
     #[serde(deserialize_with = "deserialize_maybe_datetime", default)]
     pub expires: Option<DateTime>,
@@ -68,88 +70,8 @@ impl QueueCommands {
                 after,
                 page_size,
             } => {
-                let stdout = Term::buffered_stdout();
                 let ids = client.query_messages(&sender, &rcpt, &before, &after).await;
-                let ids_len = ids.len();
-                let page_size = page_size.map(|p| std::cmp::max(p, 1)).unwrap_or(20);
-                let pages_total = (ids_len as f64 / page_size as f64).ceil() as usize;
-                for (page_num, chunk) in ids.chunks(page_size).enumerate() {
-                    // Build table
-                    let mut table = Table::new();
-                    table.add_row(Row::new(
-                        ["ID", "Delivery Due", "Sender", "Recipients", "Size"]
-                            .iter()
-                            .map(|p| Cell::new(p).with_style(Attr::Bold))
-                            .collect(),
-                    ));
-                    for id in chunk {
-                        let message = client
-                            .http_request::<Message, String>(
-                                Method::GET,
-                                &format!("/api/queue/messages/{id}"),
-                                None,
-                            )
-                            .await;
-
-                        let mut rcpts = String::new();
-                        let mut deliver_at = i64::MAX;
-                        let mut deliver_pos = 0;
-
-                        for (pos, rcpt) in message.recipients.iter().enumerate() {
-                            if let Some(next_retry) = &rcpt.next_retry {
-                                let ts = next_retry.to_timestamp();
-                                if ts < deliver_at {
-                                    deliver_at = ts;
-                                    deliver_pos = pos;
-                                }
-                            }
-                            if !rcpts.is_empty() {
-                                rcpts.push('\n');
-                            }
-                            rcpts.push_str(&rcpt.address);
-                            rcpts.push_str(" (");
-                            rcpts.push_str(rcpt.status.status_short());
-                            rcpts.push(')');
-                        }
-
-                        let mut cells = Vec::new();
-                        cells.push(Cell::new(&format!("{id:X}")));
-                        cells.push(if deliver_at != i64::MAX {
-                            Cell::new(
-                                &message.recipients[deliver_pos]
-                                    .next_retry
-                                    .as_ref()
-                                    .unwrap()
-                                    .to_rfc822(),
-                            )
-                        } else {
-                            Cell::new("None")
-                        });
-                        cells.push(Cell::new(if !message.return_path.is_empty() {
-                            &message.return_path
-                        } else {
-                            "<>"
-                        }));
-                        cells.push(Cell::new(&rcpts));
-                        cells.push(Cell::new(
-                            &SpecificSize::new(message.size as u32, Byte)
-                                .unwrap()
-                                .to_string(),
-                        ));
-                        table.add_row(Row::new(cells));
-                    }
-
-                    eprintln!();
-                    table.printstd();
-                    eprintln!();
-                    if page_num + 1 != pages_total {
-                        eprintln!("\n--- Press any key to continue or 'q' to exit ---");
-                        if let Ok('q' | 'Q') = stdout.read_char() {
-                            break;
-                        }
-                    }
-                }
-                eprintln!("\n{ids_len} queued message(s) found.")
+                client.display_messages(&ids, page_size).await;
             }
             QueueCommands::Status { ids } => {
                 for (uid, id) in parse_ids(&ids).into_iter().zip(ids) {
@@ -224,6 +146,12 @@ impl QueueCommands {
                                     Cell::new(&dt.to_rfc822()),
                                 ]));
                             }
+                            if let Some(dt) = &rcpt.held_until {
+                                table.add_row(Row::new(vec![
+                                    Cell::new("Held until").with_style(Attr::Bold),
+                                    Cell::new(&dt.to_rfc822()),
+                                ]));
+                            }
                             if let Some(dt) = &rcpt.next_notify {
                                 table.add_row(Row::new(vec![
                                     Cell::new("Notify at").with_style(Attr::Bold),
@@ -364,11 +292,149 @@ impl QueueCommands {
                 }
                 eprintln!();
             }
+            QueueCommands::Search {
+                text,
+                queue,
+                page_size,
+            } => {
+                let ids = client.search_messages(&text, &queue).await;
+                client.display_messages(&ids, page_size).await;
+            }
+            QueueCommands::Hold {
+                until,
+                sender,
+                rcpt,
+                dry_run,
+                ids,
+            } => {
+                let ids = client.select_messages(&sender, &rcpt, ids).await;
+                if dry_run {
+                    client.display_messages(&ids, None).await;
+                    eprintln!("Dry run, no messages were held.");
+                    return;
+                }
+
+                let (success_count, failed_list) =
+                    client.reschedule_messages(&ids, &rcpt, Some(&until)).await;
+                eprint!(
+                    "\nHolding {success_count} message(s) until {}.",
+                    until.to_rfc822()
+                );
+                if !failed_list.is_empty() {
+                    eprint!(" Unable to hold id(s): {}.", failed_list.join(", "));
+                }
+                eprintln!();
+            }
+            QueueCommands::Release {
+                sender,
+                rcpt,
+                dry_run,
+                ids,
+            } => {
+                let ids = client.select_messages(&sender, &rcpt, ids).await;
+                if dry_run {
+                    client.display_messages(&ids, None).await;
+                    eprintln!("Dry run, no messages were released.");
+                    return;
+                }
+
+                let (success_count, failed_list) =
+                    client.reschedule_messages(&ids, &rcpt, None).await;
+                eprint!("\nReleased {success_count} message(s) for delivery.");
+                if !failed_list.is_empty() {
+                    eprint!(" Unable to release id(s): {}.", failed_list.join(", "));
+                }
+                eprintln!();
+            }
         }
     }
 }
 
 impl Client {
+    async fn display_messages(&self, ids: &[u64], page_size: Option<usize>) {
+        let stdout = Term::buffered_stdout();
+        let ids_len = ids.len();
+        let page_size = page_size.map(|p| std::cmp::max(p, 1)).unwrap_or(20);
+        let pages_total = (ids_len as f64 / page_size as f64).ceil() as usize;
+        for (page_num, chunk) in ids.chunks(page_size).enumerate() {
+            // Build table
+            let mut table = Table::new();
+            table.add_row(Row::new(
+                ["ID", "Delivery Due", "Sender", "Recipients", "Size"]
+                    .iter()
+                    .map(|p| Cell::new(p).with_style(Attr::Bold))
+                    .collect(),
+            ));
+            for id in chunk {
+                let message = self
+                    .http_request::<Message, String>(
+                        Method::GET,
+                        &format!("/api/queue/messages/{id}"),
+                        None,
+                    )
+                    .await;
+
+                let mut rcpts = String::new();
+                let mut deliver_at = i64::MAX;
+                let mut deliver_pos = 0;
+
+                for (pos, rcpt) in message.recipients.iter().enumerate() {
+                    if let Some(next_retry) = &rcpt.next_retry {
+                        let ts = next_retry.to_timestamp();
+                        if ts < deliver_at {
+                            deliver_at = ts;
+                            deliver_pos = pos;
+                        }
+                    }
+                    if !rcpts.is_empty() {
+                        rcpts.push('\n');
+                    }
+                    rcpts.push_str(&rcpt.address);
+                    rcpts.push_str(" (");
+                    rcpts.push_str(rcpt.status.status_short());
+                    rcpts.push(')');
+                }
+
+                let mut cells = Vec::new();
+                cells.push(Cell::new(&format!("{id:X}")));
+                cells.push(if deliver_at != i64::MAX {
+                    Cell::new(
+                        &message.recipients[deliver_pos]
+                            .next_retry
+                            .as_ref()
+                            .unwrap()
+                            .to_rfc822(),
+                    )
+                } else {
+                    Cell::new("None")
+                });
+                cells.push(Cell::new(if !message.return_path.is_empty() {
+                    &message.return_path
+                } else {
+                    "<>"
+                }));
+                cells.push(Cell::new(&rcpts));
+                cells.push(Cell::new(
+                    &SpecificSize::new(message.size as u32, Byte)
+                        .unwrap()
+                        .to_string(),
+                ));
+                table.add_row(Row::new(cells));
+            }
+
+            eprintln!();
+            table.printstd();
+            eprintln!();
+            if page_num + 1 != pages_total {
+                eprintln!("\n--- Press any key to continue or 'q' to exit ---");
+                if let Ok('q' | 'Q') = stdout.read_char() {
+                    break;
+                }
+            }
+        }
+        eprintln!("\n{ids_len} queued message(s) found.")
+    }
+
     async fn query_messages(
         &self,
         from: &Option<String>,
@@ -395,6 +461,73 @@ impl Client {
             .await
             .items
     }
+
+    async fn search_messages(&self, text: &str, queue: &Option<String>) -> Vec<u64> {
+        let mut query = form_urlencoded::Serializer::new("/api/queue/messages?".to_string());
+        query.append_pair("text", text);
+        if let Some(queue) = queue {
+            query.append_pair("queue", queue);
+        }
+
+        self.http_request::<List<u64>, String>(Method::GET, &query.finish(), None)
+            .await
+            .items
+    }
+
+    async fn select_messages(
+        &self,
+        sender: &Option<String>,
+        rcpt: &Option<String>,
+        ids: Vec<String>,
+    ) -> Vec<u64> {
+        let ids = if !ids.is_empty() {
+            parse_ids(&ids)
+        } else if sender.is_some() || rcpt.is_some() {
+            self.query_messages(sender, rcpt, &None, &None).await
+        } else {
+            vec![]
+        };
+
+        if ids.is_empty() {
+            eprintln!("No messages were found.");
+            std::process::exit(1);
+        }
+
+        ids
+    }
+
+    async fn reschedule_messages(
+        &self,
+        ids: &[u64],
+        rcpt: &Option<String>,
+        at: Option<&DateTime>,
+    ) -> (usize, Vec<String>) {
+        let mut success_count = 0;
+        let mut failed_list = vec![];
+
+        for id in ids {
+            let mut query = form_urlencoded::Serializer::new(format!("/api/queue/messages/{id}?"));
+
+            if let Some(filter) = rcpt {
+                query.append_pair("filter", filter);
+            }
+            if let Some(at) = at {
+                query.append_pair("at", &at.to_rfc3339());
+            }
+
+            if self
+                .try_http_request::<bool, String>(Method::PATCH, &query.finish(), None)
+                .await
+                .unwrap_or(false)
+            {
+                success_count += 1;
+            } else {
+                failed_list.push(format!("{id:X}"));
+            }
+        }
+
+        (success_count, failed_list)
+    }
 }
 
 fn deserialize_maybe_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime>, D::Error>
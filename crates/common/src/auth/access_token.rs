@@ -7,12 +7,14 @@
 use super::{AccessToken, ResourceToken, TenantInfo, roles::RolePermissions};
 use crate::{
     Server,
+    config::groupware::ItipAutoAdd,
     ipc::BroadcastEvent,
     listener::limiter::{ConcurrencyLimiter, LimiterResult},
 };
 use ahash::AHashSet;
 use directory::{
-    Permission, Principal, PrincipalData, QueryParams, Type,
+    Permission, Principal, PrincipalData, QueryParams, ROLE_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
+    Type,
     backend::internal::{
         lookup::DirectoryStore,
         manage::{ChangedPrincipals, ManageDirectory},
@@ -25,9 +27,12 @@ use std::{
 use store::{query::acl::AclQuery, rand};
 use trc::AddContext;
 use types::{acl::Acl, collection::Collection};
-use utils::map::{
-    bitmap::{Bitmap, BitmapItem},
-    vec_map::VecMap,
+use utils::{
+    config::utils::ParseValue,
+    map::{
+        bitmap::{Bitmap, BitmapItem},
+        vec_map::VecMap,
+    },
 };
 
 pub enum PrincipalOrId {
@@ -44,8 +49,24 @@ impl Server {
         let mut role_permissions = RolePermissions::default();
 
         // Apply role permissions
+        let mut session_timeout = None;
+        let mut bandwidth_limit = None;
+        let has_role_overrides =
+            !self.core.imap.timeout_roles.is_empty() || !self.core.imap.bandwidth_roles.is_empty();
         for role_id in principal.roles() {
             role_permissions.union(self.get_role_permissions(*role_id).await?.as_ref());
+
+            if has_role_overrides && let Some(role_name) = self.role_name(*role_id).await? {
+                // Use the longest session timeout override among the principal's roles
+                if let Some(timeout) = self.core.imap.timeout_roles.get(&role_name) {
+                    session_timeout = session_timeout.max(Some(*timeout));
+                }
+
+                // Use the most generous bandwidth limit among the principal's roles
+                if let Some(rate) = self.core.imap.bandwidth_roles.get(&role_name) {
+                    bandwidth_limit = bandwidth_limit.max(Some(*rate));
+                }
+            }
         }
 
         // Add principal permissions
@@ -61,7 +82,6 @@ impl Server {
         let mut permissions = role_permissions.finalize();
         let mut tenant = None;
 
-
         // Build member of and e-mail addresses
         let primary_id = principal.id();
         let member_of = principal
@@ -98,6 +118,13 @@ impl Server {
                     None
                 }
             }),
+            itip_auto_add: principal.data.iter().find_map(|data| {
+                if let PrincipalData::CalendarAutoAdd(v) = data {
+                    ItipAutoAdd::parse_value(v).ok()
+                } else {
+                    None
+                }
+            }),
             permissions,
             concurrent_imap_requests: self.core.imap.rate_concurrent.map(ConcurrencyLimiter::new),
             concurrent_http_requests: self
@@ -110,6 +137,8 @@ impl Server {
                 .jmap
                 .upload_max_concurrent
                 .map(ConcurrencyLimiter::new),
+            session_timeout,
+            bandwidth_limit: bandwidth_limit.or(self.core.imap.bandwidth_default),
             obj_size: 0,
             revision,
         };
@@ -158,6 +187,19 @@ impl Server {
         Ok(access_token.update_size())
     }
 
+    async fn role_name(&self, role_id: u32) -> trc::Result<Option<String>> {
+        match role_id {
+            ROLE_ADMIN => Ok(Some("admin".to_string())),
+            ROLE_TENANT_ADMIN => Ok(Some("tenant-admin".to_string())),
+            ROLE_USER => Ok(Some("user".to_string())),
+            role_id => self
+                .store()
+                .get_principal_name(role_id)
+                .await
+                .caused_by(trc::location!()),
+        }
+    }
+
     async fn build_access_token(&self, account_id: u32, revision: u64) -> trc::Result<AccessToken> {
         let err = match self
             .directory()
@@ -304,6 +346,8 @@ impl Server {
 
         // Invalidate access tokens in cluster
         if !changed_ids.is_empty() {
+            self.core.storage.clear_unknown_principals();
+
             let mut ids = Vec::with_capacity(changed_ids.len());
             for id in changed_ids {
                 self.inner.cache.permissions.remove(&id);
@@ -0,0 +1,272 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::{AHashMap, AHashSet};
+use directory::{
+    QueryParams,
+    backend::internal::{
+        PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue, lookup::DirectoryStore,
+        manage,
+    },
+};
+use trc::AddContext;
+use utils::config::Config;
+
+use crate::Server;
+
+#[derive(Debug, Clone, Default)]
+pub struct AttributeSchema {
+    pub attributes: AHashMap<String, AttributeDefinition>,
+    pub max_attributes: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeDefinition {
+    pub typ: AttributeType,
+    pub max_length: usize,
+    pub max_items: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    String,
+    Integer,
+    Boolean,
+    List,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    List(Vec<String>),
+}
+
+impl AttributeSchema {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut attributes = AHashMap::new();
+
+        for name in config.sub_keys("principal.attribute", ".type") {
+            let typ = match config
+                .value(("principal.attribute", name.as_str(), "type"))
+                .unwrap_or_default()
+            {
+                "string" => AttributeType::String,
+                "integer" => AttributeType::Integer,
+                "boolean" => AttributeType::Boolean,
+                "list" => AttributeType::List,
+                typ => {
+                    let err = format!("Invalid attribute type {typ:?}");
+                    config.new_parse_error(("principal.attribute", name.as_str(), "type"), err);
+                    continue;
+                }
+            };
+
+            attributes.insert(
+                name.clone(),
+                AttributeDefinition {
+                    typ,
+                    max_length: config
+                        .property_or_default(
+                            ("principal.attribute", name.as_str(), "max-length"),
+                            "256",
+                        )
+                        .unwrap_or(256),
+                    max_items: config
+                        .property_or_default(
+                            ("principal.attribute", name.as_str(), "max-items"),
+                            "32",
+                        )
+                        .unwrap_or(32),
+                },
+            );
+        }
+
+        AttributeSchema {
+            attributes,
+            max_attributes: config
+                .property_or_default("principal.attribute-limit.count", "32")
+                .unwrap_or(32),
+        }
+    }
+
+    pub fn validate(&self, item: &str) -> trc::Result<()> {
+        let (name, value) = item
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .ok_or_else(|| {
+                manage::error(
+                    "Invalid attributes value",
+                    format!("Expected 'name=value', found {item:?}").into(),
+                )
+            })?;
+        let definition = self.attributes.get(name).ok_or_else(|| {
+            manage::error(
+                "Invalid attributes value",
+                format!("Attribute {name:?} is not defined in the schema").into(),
+            )
+        })?;
+
+        let is_valid = match definition.typ {
+            AttributeType::String => value.len() <= definition.max_length,
+            AttributeType::Integer => value.parse::<i64>().is_ok(),
+            AttributeType::Boolean => parse_boolean(value).is_some(),
+            AttributeType::List => {
+                let items = split_list(value);
+                items.len() <= definition.max_items
+                    && items.iter().all(|item| item.len() <= definition.max_length)
+            }
+        };
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(manage::error(
+                "Invalid attributes value",
+                format!("Value {value:?} is not a valid {name:?} attribute").into(),
+            ))
+        }
+    }
+
+    pub fn typed_value(&self, name: &str, value: &str) -> Option<AttributeValue> {
+        match self.attributes.get(name)?.typ {
+            AttributeType::String => AttributeValue::String(value.to_string()).into(),
+            AttributeType::Integer => value.parse().ok().map(AttributeValue::Integer),
+            AttributeType::Boolean => parse_boolean(value).map(AttributeValue::Boolean),
+            AttributeType::List => AttributeValue::List(
+                split_list(value)
+                    .into_iter()
+                    .map(|item| item.to_string())
+                    .collect(),
+            )
+            .into(),
+        }
+    }
+}
+
+impl Server {
+    // Validates the attributes included in a new principal
+    pub fn assert_attribute_schema(&self, items: &[String]) -> trc::Result<()> {
+        for item in items {
+            self.core.attributes.validate(item)?;
+        }
+
+        self.assert_attribute_count(items)
+    }
+
+    // Validates the attribute changes included in a principal update
+    pub async fn assert_attribute_update_schema(
+        &self,
+        account_id: u32,
+        changes: &[PrincipalUpdate],
+    ) -> trc::Result<()> {
+        let mut current: Option<Vec<String>> = None;
+
+        for change in changes
+            .iter()
+            .filter(|change| change.field == PrincipalField::Attributes)
+        {
+            match (&change.action, &change.value) {
+                (PrincipalAction::Set, PrincipalValue::StringList(items)) => {
+                    self.assert_attribute_schema(items)?;
+                    current = Some(items.clone());
+                }
+                (PrincipalAction::AddItem, PrincipalValue::String(item)) => {
+                    self.core.attributes.validate(item)?;
+
+                    let mut items = if let Some(items) = current.take() {
+                        items
+                    } else {
+                        self.store()
+                            .query(QueryParams::id(account_id).with_return_member_of(false))
+                            .await
+                            .caused_by(trc::location!())?
+                            .map(|principal| {
+                                principal
+                                    .attributes()
+                                    .iter()
+                                    .map(|attribute| {
+                                        format!("{}={}", attribute.name, attribute.value)
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default()
+                    };
+                    items.push(item.clone());
+                    self.assert_attribute_count(&items)?;
+                    current = Some(items);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn assert_attribute_count(&self, items: &[String]) -> trc::Result<()> {
+        let names = items
+            .iter()
+            .map(|item| item.split_once('=').map_or("", |(name, _)| name.trim()))
+            .collect::<AHashSet<_>>();
+
+        if names.len() <= self.core.attributes.max_attributes {
+            Ok(())
+        } else {
+            Err(manage::error(
+                "Invalid attributes value",
+                format!(
+                    "Principals cannot have more than {} attributes",
+                    self.core.attributes.max_attributes
+                )
+                .into(),
+            ))
+        }
+    }
+
+    pub async fn principal_attribute(
+        &self,
+        directory: &str,
+        address: &str,
+        name: &str,
+        session_id: u64,
+    ) -> trc::Result<Option<AttributeValue>> {
+        let directory = self.get_directory_or_default(directory, session_id);
+        let Some(account_id) = self
+            .email_to_id(directory, address, session_id)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(None);
+        };
+
+        Ok(directory
+            .query(QueryParams::id(account_id).with_return_member_of(false))
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|principal| {
+                principal
+                    .attribute(name)
+                    .and_then(|value| self.core.attributes.typed_value(name, value))
+            }))
+    }
+}
+
+fn parse_boolean(value: &str) -> Option<bool> {
+    match value {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
@@ -0,0 +1,188 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{net::IpAddr, sync::Arc};
+
+use directory::{Permission, Principal, QueryParams};
+use sha2::{Digest, Sha256};
+use utils::config::utils::ParseValue;
+use x509_parser::{extensions::GeneralName, parse_x509_certificate};
+
+use crate::Server;
+
+use super::AccessToken;
+
+// Identity details extracted from a verified TLS client certificate
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientCertificate {
+    pub fingerprint: String,
+    pub emails: Vec<String>,
+    pub common_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClientCertPolicy {
+    #[default]
+    Disabled,
+    Optional,
+    Required,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClientCertMapping {
+    // Map the SAN email addresses to the principal owning them
+    #[default]
+    Email,
+    // Require the certificate fingerprint to be stored in the principal's secrets
+    Fingerprint,
+}
+
+impl ClientCertificate {
+    pub fn parse(der: &[u8]) -> Option<Self> {
+        let (_, cert) = parse_x509_certificate(der).ok()?;
+        let mut emails = Vec::new();
+
+        if let Ok(Some(san)) = cert.subject_alternative_name() {
+            for name in &san.value.general_names {
+                if let GeneralName::RFC822Name(email) = name {
+                    emails.push(email.trim().to_lowercase());
+                }
+            }
+        }
+
+        // Fall back to the legacy emailAddress attribute of the subject
+        if emails.is_empty() {
+            emails.extend(
+                cert.subject()
+                    .iter_email()
+                    .filter_map(|email| email.as_str().ok())
+                    .map(|email| email.trim().to_lowercase()),
+            );
+        }
+
+        Some(ClientCertificate {
+            fingerprint: Sha256::digest(der)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+            emails,
+            common_name: cert
+                .subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(|cn| cn.to_string()),
+        })
+    }
+}
+
+impl Server {
+    pub async fn authenticate_certificate(
+        &self,
+        certificate: &ClientCertificate,
+        mapping: ClientCertMapping,
+        authzid: Option<&str>,
+        session_id: u64,
+        remote_ip: IpAddr,
+    ) -> trc::Result<Arc<AccessToken>> {
+        let authzid = authzid.map(|id| id.trim().to_lowercase());
+        let principal = match mapping {
+            ClientCertMapping::Email => {
+                let mut result = None;
+                for email in &certificate.emails {
+                    if let Some(principal) = self.certificate_principal(email).await?
+                        && authzid.as_ref().is_none_or(|authzid| {
+                            principal.name() == authzid || principal.emails.contains(authzid)
+                        })
+                    {
+                        result = Some(principal);
+                        break;
+                    }
+                }
+                result
+            }
+            ClientCertMapping::Fingerprint => {
+                // An authorization identity restricts the lookup to that principal
+                let names = if let Some(authzid) = &authzid {
+                    vec![authzid]
+                } else {
+                    certificate
+                        .emails
+                        .iter()
+                        .chain(certificate.common_name.iter())
+                        .collect()
+                };
+                let mut result = None;
+                for name in names {
+                    if let Some(principal) = self.certificate_principal(name).await?
+                        && principal.has_client_certificate(&certificate.fingerprint)
+                    {
+                        result = Some(principal);
+                        break;
+                    }
+                }
+                result
+            }
+        };
+
+        if let Some(principal) = principal {
+            trc::event!(
+                Auth(trc::AuthEvent::Success),
+                AccountName = principal.name().to_string(),
+                AccountId = principal.id(),
+                SpanId = session_id,
+                Id = certificate.fingerprint.clone(),
+            );
+
+            self.get_access_token(principal).await.and_then(|token| {
+                token
+                    .assert_has_permission(Permission::Authenticate)
+                    .map(|_| token)
+            })
+        } else {
+            Err(trc::AuthEvent::Failed
+                .into_err()
+                .ctx(trc::Key::RemoteIp, remote_ip)
+                .ctx_opt(trc::Key::AccountName, authzid)
+                .id(certificate.fingerprint.clone())
+                .details("Client certificate does not map to any principal"))
+        }
+    }
+
+    async fn certificate_principal(&self, name: &str) -> trc::Result<Option<Principal>> {
+        let directory = &self.core.storage.directory;
+        if let Some(principal) = directory.query(QueryParams::name(name)).await? {
+            Ok(Some(principal))
+        } else if name.contains('@')
+            && let Some(id) = directory.email_to_id(name).await?
+        {
+            directory.query(QueryParams::id(id)).await
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl ParseValue for ClientCertPolicy {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "none" | "disable" | "disabled" | "false" => Ok(ClientCertPolicy::Disabled),
+            "optional" | "request" => Ok(ClientCertPolicy::Optional),
+            "required" | "require" | "true" => Ok(ClientCertPolicy::Required),
+            _ => Err(format!("Invalid client certificate policy {value:?}.")),
+        }
+    }
+}
+
+impl ParseValue for ClientCertMapping {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "email" | "san" => Ok(ClientCertMapping::Email),
+            "fingerprint" => Ok(ClientCertMapping::Fingerprint),
+            _ => Err(format!("Invalid client certificate mapping {value:?}.")),
+        }
+    }
+}
@@ -4,14 +4,17 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use crate::{Server, listener::limiter::ConcurrencyLimiter};
+use crate::{
+    Server, config::groupware::ItipAutoAdd, listener::limiter::ConcurrencyLimiter,
+    telemetry::metrics::labels::LabeledMetric,
+};
 use directory::{
     Directory, FALLBACK_ADMIN_ID, Permission, Permissions, Principal, QueryParams, Type,
     backend::internal::lookup::DirectoryStore, core::secret::verify_secret_hash,
 };
 use mail_send::Credentials;
 use oauth::GrantType;
-use std::{net::IpAddr, sync::Arc};
+use std::{net::IpAddr, sync::Arc, time::Duration};
 use types::collection::Collection;
 use utils::{
     cache::CacheItemWeight,
@@ -19,10 +22,14 @@ use utils::{
 };
 
 pub mod access_token;
+pub mod attributes;
+pub mod certificate;
 pub mod oauth;
+pub mod password;
 pub mod rate_limit;
 pub mod roles;
 pub mod sasl;
+pub mod sessions;
 
 #[derive(Debug, Default)]
 pub struct AccessToken {
@@ -32,6 +39,7 @@ pub struct AccessToken {
     pub name: String,
     pub description: Option<String>,
     pub locale: Option<String>,
+    pub itip_auto_add: Option<ItipAutoAdd>,
     pub emails: Vec<String>,
     pub quota: u64,
     pub permissions: Permissions,
@@ -39,6 +47,8 @@ pub struct AccessToken {
     pub concurrent_http_requests: Option<ConcurrencyLimiter>,
     pub concurrent_imap_requests: Option<ConcurrencyLimiter>,
     pub concurrent_uploads: Option<ConcurrencyLimiter>,
+    pub session_timeout: Option<Duration>,
+    pub bandwidth_limit: Option<u64>,
     pub revision: u64,
     pub obj_size: u64,
 }
@@ -62,7 +72,10 @@ pub struct AuthRequest<'x> {
     remote_ip: IpAddr,
     return_member_of: bool,
     allow_api_access: bool,
+    allow_expired_password: bool,
     directory: Option<&'x Directory>,
+    protocol: Option<&'static str>,
+    user_agent: Option<&'x str>,
 }
 
 impl Server {
@@ -71,7 +84,7 @@ impl Server {
         let directory = req.directory.unwrap_or(&self.core.storage.directory);
 
         // Validate credentials
-        match &req.credentials {
+        let token = match &req.credentials {
             Credentials::OAuthBearer { token } if !directory.has_bearer_token_support() => {
                 match self
                     .validate_access_token(GrantType::AccessToken.into(), token)
@@ -90,7 +103,23 @@ impl Server {
             token
                 .assert_has_permission(Permission::Authenticate)
                 .map(|_| token)
-        })
+        })?;
+
+        // Bearer tokens are tracked by the token endpoint when issued
+        if let Some(protocol) = req.protocol
+            && !matches!(req.credentials, Credentials::OAuthBearer { .. })
+            && let Err(err) = self
+                .track_login_session(token.primary_id, protocol, req.user_agent, req.remote_ip)
+                .await
+        {
+            trc::error!(
+                err.account_id(token.primary_id)
+                    .span_id(req.session_id)
+                    .details("Failed to track login session")
+            );
+        }
+
+        Ok(token)
     }
 
     async fn authenticate_credentials(
@@ -107,6 +136,17 @@ impl Server {
             .await
         {
             Ok(Some(principal)) => {
+                // Reject expired passwords, except when changing them
+                if !req.allow_expired_password
+                    && matches!(req.credentials, Credentials::Plain { .. })
+                    && self.is_password_expired(principal.id()).await?
+                {
+                    return Err(trc::AuthEvent::PasswordExpired
+                        .into_err()
+                        .account_id(principal.id())
+                        .details(principal.name().to_string()));
+                }
+
                 trc::event!(
                     Auth(trc::AuthEvent::Success),
                     AccountName = principal.name().to_string(),
@@ -223,6 +263,17 @@ impl Server {
             _ => (),
         };
 
+        if result.is_ok() {
+            self.record_labeled_metric(
+                LabeledMetric::AuthFailures,
+                req.credentials
+                    .login()
+                    .and_then(|login| login.rsplit_once('@'))
+                    .map(|(_, domain)| domain),
+                None,
+            );
+        }
+
         if let Err(err) = result {
             Err(err)
         } else if self.has_auth_fail2ban() {
@@ -261,6 +312,9 @@ impl<'x> AuthRequest<'x> {
             return_member_of: true,
             directory: None,
             allow_api_access: false,
+            allow_expired_password: false,
+            protocol: None,
+            user_agent: None,
         }
     }
 
@@ -290,10 +344,21 @@ impl<'x> AuthRequest<'x> {
         self
     }
 
+    pub fn with_session(mut self, protocol: &'static str, user_agent: Option<&'x str>) -> Self {
+        self.protocol = Some(protocol);
+        self.user_agent = user_agent;
+        self
+    }
+
     pub fn with_api_access(mut self, allow_api_access: bool) -> Self {
         self.allow_api_access = allow_api_access;
         self
     }
+
+    pub fn with_expired_password(mut self, allow_expired_password: bool) -> Self {
+        self.allow_expired_password = allow_expired_password;
+        self
+    }
 }
 
 impl CacheItemWeight for AccessToken {
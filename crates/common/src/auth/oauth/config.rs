@@ -18,10 +18,11 @@ use biscuit::{
 use ring::signature::{self, KeyPair};
 use rsa::{RsaPublicKey, pkcs1::DecodeRsaPublicKey, traits::PublicKeyParts};
 use store::rand::{Rng, distr::Alphanumeric, rng};
-use utils::config::Config;
+use utils::{config::Config, glob::GlobPattern};
 use x509_parser::num_bigint::BigUint;
 
 use crate::{
+    auth::oauth::registration::{ClientRegistrationRule, ClientTokenExpiry},
     config::{build_ecdsa_pem, build_rsa_keypair},
     manager::webadmin::Resource,
 };
@@ -38,6 +39,9 @@ pub struct OAuthConfig {
 
     pub allow_anonymous_client_registration: bool,
     pub require_client_authentication: bool,
+    pub client_registration_rules: Vec<ClientRegistrationRule>,
+    pub client_registration_review: bool,
+    pub client_registration_review_expiry: u64,
 
     pub oidc_expiry_id_token: u64,
     pub oidc_signing_secret: Secret,
@@ -188,6 +192,14 @@ impl OAuthConfig {
             require_client_authentication: config
                 .property_or_default("oauth.client-registration.require", "false")
                 .unwrap_or(true),
+            client_registration_rules: parse_registration_rules(config),
+            client_registration_review: config
+                .property_or_default("oauth.client-registration.review.enable", "false")
+                .unwrap_or(false),
+            client_registration_review_expiry: config
+                .property_or_default::<Duration>("oauth.client-registration.review.expiry", "7d")
+                .unwrap_or_else(|| Duration::from_secs(7 * 24 * 60 * 60))
+                .as_secs(),
             oidc_signing_secret,
             oidc_signature_algorithm,
             oidc_jwks,
@@ -195,6 +207,47 @@ impl OAuthConfig {
     }
 }
 
+fn parse_registration_rules(config: &mut Config) -> Vec<ClientRegistrationRule> {
+    let mut rules = Vec::new();
+
+    for id in config.sub_keys("oauth.client-registration.rule", "") {
+        let redirect_uris = config
+            .values((
+                "oauth.client-registration.rule",
+                id.as_str(),
+                "redirect-uri",
+            ))
+            .map(|(_, pattern)| GlobPattern::compile(pattern, false))
+            .collect::<Vec<_>>();
+        if redirect_uris.is_empty() {
+            continue;
+        }
+
+        rules.push(ClientRegistrationRule {
+            redirect_uris,
+            expiry: ClientTokenExpiry {
+                token: config
+                    .property::<Duration>((
+                        "oauth.client-registration.rule",
+                        id.as_str(),
+                        "expiry.token",
+                    ))
+                    .map(|expiry| expiry.as_secs()),
+                refresh_token: config
+                    .property::<Duration>((
+                        "oauth.client-registration.rule",
+                        id.as_str(),
+                        "expiry.refresh-token",
+                    ))
+                    .map(|expiry| expiry.as_secs()),
+            },
+            id,
+        });
+    }
+
+    rules
+}
+
 impl Default for OAuthConfig {
     fn default() -> Self {
         Self {
@@ -208,6 +261,9 @@ impl Default for OAuthConfig {
             oidc_expiry_id_token: Default::default(),
             allow_anonymous_client_registration: Default::default(),
             require_client_authentication: Default::default(),
+            client_registration_rules: Default::default(),
+            client_registration_review: Default::default(),
+            client_registration_review_expiry: Default::default(),
             oidc_signing_secret: Secret::Bytes("secret".to_string().into_bytes()),
             oidc_signature_algorithm: SignatureAlgorithm::HS256,
             oidc_jwks: Resource {
@@ -4,10 +4,19 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use directory::{
+    Type,
+    backend::internal::{PrincipalField, PrincipalSet, manage::ManageDirectory},
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, net::IpAddr};
+use store::{dispatch::lookup::KeyValue, write::now};
+use trc::AddContext;
+use utils::glob::GlobPattern;
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+use crate::{KV_OAUTH_CLIENT, KV_OAUTH_REGISTRATION, Server};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct ClientRegistrationRequest {
     pub redirect_uris: Vec<String>,
@@ -156,21 +165,21 @@ pub struct ClientRegistrationResponse {
     pub request: ClientRegistrationRequest,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum ApplicationType {
     Web,
     Native,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum SubjectType {
     Pairwise,
     Public,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum TokenEndpointAuthMethod {
     ClientSecretPost,
@@ -179,3 +188,340 @@ pub enum TokenEndpointAuthMethod {
     PrivateKeyJwt,
     None,
 }
+
+#[derive(Debug, Clone)]
+pub struct ClientRegistrationRule {
+    pub id: String,
+    pub redirect_uris: Vec<GlobPattern>,
+    pub expiry: ClientTokenExpiry,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ClientTokenExpiry {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<u64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingClientRegistration {
+    pub client_id: String,
+    pub created_at: u64,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_ip: Option<IpAddr>,
+    pub request: ClientRegistrationRequest,
+}
+
+pub enum ClientRegistrationDecision<'x> {
+    Approve(Option<&'x ClientRegistrationRule>),
+    Review,
+    Reject,
+}
+
+impl ClientRegistrationRule {
+    pub fn matches(&self, request: &ClientRegistrationRequest) -> bool {
+        !request.redirect_uris.is_empty()
+            && request.redirect_uris.iter().all(|uri| {
+                self.redirect_uris
+                    .iter()
+                    .any(|pattern| pattern.matches(uri))
+            })
+    }
+}
+
+impl ClientTokenExpiry {
+    pub fn is_empty(&self) -> bool {
+        self.token.is_none() && self.refresh_token.is_none()
+    }
+}
+
+impl Server {
+    // Registrations are approved when they match an auto-approval rule,
+    // otherwise they are either queued for review or rejected.
+    pub fn client_registration_decision(
+        &self,
+        request: &ClientRegistrationRequest,
+    ) -> ClientRegistrationDecision<'_> {
+        let oauth = &self.core.oauth;
+
+        if let Some(rule) = oauth
+            .client_registration_rules
+            .iter()
+            .find(|rule| rule.matches(request))
+        {
+            ClientRegistrationDecision::Approve(Some(rule))
+        } else if oauth.client_registration_review {
+            ClientRegistrationDecision::Review
+        } else if oauth.client_registration_rules.is_empty() {
+            ClientRegistrationDecision::Approve(None)
+        } else {
+            ClientRegistrationDecision::Reject
+        }
+    }
+
+    pub async fn create_oauth_client(
+        &self,
+        client_id: &str,
+        request: &ClientRegistrationRequest,
+        expiry: ClientTokenExpiry,
+    ) -> trc::Result<()> {
+        self.store()
+            .create_principal(
+                PrincipalSet::new(u32::MAX, Type::OauthClient)
+                    .with_field(PrincipalField::Name, client_id.to_string())
+                    .with_field(PrincipalField::Urls, request.redirect_uris.clone())
+                    .with_opt_field(PrincipalField::Description, request.client_name.clone())
+                    .with_field(PrincipalField::Emails, request.contacts.clone())
+                    .with_opt_field(PrincipalField::Picture, request.logo_uri.clone()),
+                None,
+                None,
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        if !expiry.is_empty() {
+            self.in_memory_store()
+                .key_set(KeyValue::with_prefix(
+                    KV_OAUTH_CLIENT,
+                    client_id.as_bytes(),
+                    serde_json::to_vec(&expiry).unwrap_or_default(),
+                ))
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+
+    // Returns the token lifetimes assigned to a client by an auto-approval rule
+    pub async fn oauth_client_expiry(&self, client_id: &str) -> trc::Result<ClientTokenExpiry> {
+        if self
+            .core
+            .oauth
+            .client_registration_rules
+            .iter()
+            .all(|rule| rule.expiry.is_empty())
+        {
+            return Ok(ClientTokenExpiry::default());
+        }
+
+        self.in_memory_store()
+            .key_get::<String>(KeyValue::<()>::build_key(
+                KV_OAUTH_CLIENT,
+                client_id.as_bytes(),
+            ))
+            .await
+            .caused_by(trc::location!())
+            .map(|value| {
+                value
+                    .and_then(|value| serde_json::from_str(&value).ok())
+                    .unwrap_or_default()
+            })
+    }
+
+    pub async fn queue_client_registration(
+        &self,
+        client_id: &str,
+        request: ClientRegistrationRequest,
+        remote_ip: IpAddr,
+    ) -> trc::Result<()> {
+        let pending = PendingClientRegistration {
+            client_id: client_id.to_string(),
+            created_at: now(),
+            remote_ip: Some(remote_ip),
+            request,
+        };
+
+        self.in_memory_store()
+            .key_set(
+                KeyValue::with_prefix(
+                    KV_OAUTH_REGISTRATION,
+                    client_id.as_bytes(),
+                    serde_json::to_vec(&pending).unwrap_or_default(),
+                )
+                .expires(self.core.oauth.client_registration_review_expiry),
+            )
+            .await
+            .caused_by(trc::location!())
+    }
+
+    pub async fn pending_client_registrations(
+        &self,
+    ) -> trc::Result<Vec<PendingClientRegistration>> {
+        let mut pending = self
+            .in_memory_store()
+            .key_get_prefix(&[KV_OAUTH_REGISTRATION])
+            .await
+            .caused_by(trc::location!())?
+            .into_iter()
+            .filter_map(|(_, value)| {
+                serde_json::from_slice::<PendingClientRegistration>(&value).ok()
+            })
+            .collect::<Vec<_>>();
+        pending.sort_unstable_by_key(|registration| registration.created_at);
+
+        Ok(pending)
+    }
+
+    pub async fn take_pending_client_registration(
+        &self,
+        client_id: &str,
+    ) -> trc::Result<Option<PendingClientRegistration>> {
+        let key = KeyValue::<()>::build_key(KV_OAUTH_REGISTRATION, client_id.as_bytes());
+        let pending = self
+            .in_memory_store()
+            .key_get::<String>(key.as_slice())
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|value| serde_json::from_str::<PendingClientRegistration>(&value).ok());
+
+        if pending.is_some() {
+            self.in_memory_store()
+                .key_delete(key)
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::config::Config;
+
+    use super::ClientTokenExpiry;
+    #[cfg(feature = "test_mode")]
+    use super::{ClientRegistrationDecision, ClientRegistrationRequest};
+    use crate::auth::oauth::config::OAuthConfig;
+    #[cfg(feature = "test_mode")]
+    use crate::{Core, Server};
+
+    #[test]
+    fn client_registration_rules() {
+        let mut config = Config::new(
+            r#"
+[oauth.client-registration.rule.local]
+redirect-uri = ["http://localhost*", "http://127.0.0.1*"]
+expiry.token = "10m"
+
+[oauth.client-registration.rule.partner]
+redirect-uri = "https://*.partner.example.org/callback"
+expiry.token = "1h"
+expiry.refresh-token = "1d"
+
+[oauth.client-registration.rule.empty]
+expiry.token = "1h"
+"#,
+        )
+        .unwrap();
+        let oauth = OAuthConfig::parse(&mut config);
+        assert_eq!(
+            oauth
+                .client_registration_rules
+                .iter()
+                .map(|rule| (rule.id.as_str(), rule.expiry))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "local",
+                    ClientTokenExpiry {
+                        token: Some(600),
+                        refresh_token: None
+                    }
+                ),
+                (
+                    "partner",
+                    ClientTokenExpiry {
+                        token: Some(3600),
+                        refresh_token: Some(86400)
+                    }
+                ),
+            ]
+        );
+        assert!(!oauth.client_registration_review);
+        assert_eq!(oauth.client_registration_review_expiry, 7 * 86400);
+    }
+
+    #[cfg(feature = "test_mode")]
+    #[test]
+    fn client_registration_decision() {
+        let mut config = Config::new(
+            r#"
+[oauth.client-registration.rule.local]
+redirect-uri = ["http://localhost*", "http://127.0.0.1*"]
+
+[oauth.client-registration.rule.partner]
+redirect-uri = "https://*.partner.example.org/callback"
+"#,
+        )
+        .unwrap();
+        let mut server = Server {
+            core: Core {
+                oauth: OAuthConfig::parse(&mut config),
+                ..Default::default()
+            }
+            .into(),
+            inner: Default::default(),
+        };
+
+        for (redirect_uris, expected) in [
+            (vec!["http://localhost:8080/cb"], Some("local")),
+            (
+                vec!["http://localhost/cb", "http://127.0.0.1:9000/cb"],
+                Some("local"),
+            ),
+            (
+                vec!["https://app.partner.example.org/callback"],
+                Some("partner"),
+            ),
+            (
+                vec!["http://localhost/cb", "https://evil.example.com/cb"],
+                None,
+            ),
+            (vec!["https://app.partner.example.org/other"], None),
+            (vec![], None),
+        ] {
+            let request = ClientRegistrationRequest {
+                redirect_uris: redirect_uris.iter().map(|uri| uri.to_string()).collect(),
+                ..Default::default()
+            };
+            match (server.client_registration_decision(&request), expected) {
+                (ClientRegistrationDecision::Approve(Some(rule)), Some(expected)) => {
+                    assert_eq!(rule.id, expected, "{redirect_uris:?}");
+                }
+                (ClientRegistrationDecision::Reject, None) => {}
+                _ => panic!("Unexpected decision for {redirect_uris:?}"),
+            }
+        }
+
+        // Unmatched registrations are queued when review is enabled
+        let mut oauth = server.core.oauth.clone();
+        oauth.client_registration_review = true;
+        server.core = Core {
+            oauth,
+            ..Default::default()
+        }
+        .into();
+        let request = ClientRegistrationRequest {
+            redirect_uris: vec!["https://evil.example.com/cb".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            server.client_registration_decision(&request),
+            ClientRegistrationDecision::Review
+        ));
+
+        // Without rules or review all registrations are approved
+        server.core = Core::default().into();
+        assert!(matches!(
+            server.client_registration_decision(&request),
+            ClientRegistrationDecision::Approve(None)
+        ));
+    }
+}
@@ -0,0 +1,689 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{fmt::Display, time::Duration};
+
+use ahash::AHashSet;
+use directory::{
+    QueryParams,
+    backend::internal::{
+        PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue, SpecialSecrets,
+        lookup::DirectoryStore,
+    },
+    core::secret::{is_secret_hash, verify_secret_hash},
+};
+use sha1::{Digest, Sha1};
+use store::{dispatch::lookup::KeyValue, write::now};
+use trc::AddContext;
+use utils::config::Config;
+
+use crate::{KV_PASSWORD_HISTORY, Server};
+
+#[derive(Debug, Clone, Default)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: Option<usize>,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    pub min_classes: usize,
+    pub banned: AHashSet<String>,
+    pub pwned: Option<PwnedPasswords>,
+    pub history: usize,
+    pub max_age: Option<Duration>,
+    pub allow_hashed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PwnedPasswords {
+    pub url: String,
+    pub timeout: Duration,
+    pub max_count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum PasswordViolation {
+    TooShort { min: usize },
+    TooLong { max: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSpecial,
+    TooFewClasses { min: usize },
+    Banned,
+    Pwned { count: u64 },
+    Reused,
+    HashNotAllowed,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct PasswordHistory {
+    #[serde(rename = "changedAt")]
+    changed_at: u64,
+    #[serde(default)]
+    hashes: Vec<String>,
+}
+
+impl PasswordPolicy {
+    pub fn parse(config: &mut Config) -> Self {
+        let pwned = config
+            .property_or_default::<bool>("authentication.password.pwned.enable", "false")
+            .unwrap_or(false)
+            .then(|| PwnedPasswords {
+                url: config
+                    .value("authentication.password.pwned.url")
+                    .unwrap_or("https://api.pwnedpasswords.com/range/")
+                    .to_string(),
+                timeout: config
+                    .property_or_default::<Duration>("authentication.password.pwned.timeout", "5s")
+                    .unwrap_or_else(|| Duration::from_secs(5)),
+                max_count: config
+                    .property_or_default("authentication.password.pwned.max-count", "0")
+                    .unwrap_or(0),
+            });
+
+        PasswordPolicy {
+            min_length: config
+                .property_or_default("authentication.password.length.min", "0")
+                .unwrap_or(0),
+            max_length: config
+                .property_or_default::<Option<usize>>("authentication.password.length.max", "false")
+                .unwrap_or_default(),
+            require_lowercase: config
+                .property_or_default("authentication.password.require.lowercase", "false")
+                .unwrap_or(false),
+            require_uppercase: config
+                .property_or_default("authentication.password.require.uppercase", "false")
+                .unwrap_or(false),
+            require_digit: config
+                .property_or_default("authentication.password.require.digit", "false")
+                .unwrap_or(false),
+            require_special: config
+                .property_or_default("authentication.password.require.special", "false")
+                .unwrap_or(false),
+            min_classes: config
+                .property_or_default("authentication.password.require.min-classes", "0")
+                .unwrap_or(0),
+            banned: config
+                .values("authentication.password.banned")
+                .map(|(_, value)| value.to_lowercase())
+                .collect(),
+            pwned,
+            history: config
+                .property_or_default("authentication.password.history", "0")
+                .unwrap_or(0),
+            max_age: config
+                .property_or_default::<Option<Duration>>("authentication.password.max-age", "false")
+                .unwrap_or_default(),
+            allow_hashed: config
+                .property_or_default("authentication.password.allow-hashed", "true")
+                .unwrap_or(true),
+        }
+    }
+
+    pub fn has_content_rules(&self) -> bool {
+        self.min_length > 0
+            || self.max_length.is_some()
+            || self.require_lowercase
+            || self.require_uppercase
+            || self.require_digit
+            || self.require_special
+            || self.min_classes > 0
+            || !self.banned.is_empty()
+            || self.pwned.is_some()
+    }
+
+    pub fn is_tracked(&self) -> bool {
+        self.history > 0 || self.max_age.is_some()
+    }
+
+    pub fn check_content(&self, password: &str) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            violations.push(PasswordViolation::TooShort {
+                min: self.min_length,
+            });
+        }
+        if let Some(max) = self.max_length
+            && length > max
+        {
+            violations.push(PasswordViolation::TooLong { max });
+        }
+
+        let has_lowercase = password.chars().any(|ch| ch.is_lowercase());
+        let has_uppercase = password.chars().any(|ch| ch.is_uppercase());
+        let has_digit = password.chars().any(|ch| ch.is_numeric());
+        let has_special = password.chars().any(|ch| !ch.is_alphanumeric());
+        for (required, present, violation) in [
+            (
+                self.require_lowercase,
+                has_lowercase,
+                PasswordViolation::MissingLowercase,
+            ),
+            (
+                self.require_uppercase,
+                has_uppercase,
+                PasswordViolation::MissingUppercase,
+            ),
+            (
+                self.require_digit,
+                has_digit,
+                PasswordViolation::MissingDigit,
+            ),
+            (
+                self.require_special,
+                has_special,
+                PasswordViolation::MissingSpecial,
+            ),
+        ] {
+            if required && !present {
+                violations.push(violation);
+            }
+        }
+
+        let classes = [has_lowercase, has_uppercase, has_digit, has_special]
+            .into_iter()
+            .filter(|present| *present)
+            .count();
+        if classes < self.min_classes {
+            violations.push(PasswordViolation::TooFewClasses {
+                min: self.min_classes,
+            });
+        }
+
+        if !self.banned.is_empty() && self.banned.contains(&password.to_lowercase()) {
+            violations.push(PasswordViolation::Banned);
+        }
+
+        violations
+    }
+}
+
+impl Server {
+    // Validates a new password against the configured policy, including the
+    // breached password check and the account's password history.
+    pub async fn validate_password(
+        &self,
+        account_id: Option<u32>,
+        password: &str,
+    ) -> trc::Result<Vec<PasswordViolation>> {
+        let policy = &self.core.password;
+
+        if is_secret_hash(password) {
+            return Ok(if policy.allow_hashed || !policy.has_content_rules() {
+                vec![]
+            } else {
+                vec![PasswordViolation::HashNotAllowed]
+            });
+        }
+
+        let mut violations = policy.check_content(password);
+
+        if let Some(pwned) = &policy.pwned {
+            match pwned.count(password).await {
+                Ok(count) if count > pwned.max_count => {
+                    violations.push(PasswordViolation::Pwned { count });
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    trc::error!(err.caused_by(trc::location!()));
+                }
+            }
+        }
+
+        if policy.history > 0
+            && let Some(account_id) = account_id
+        {
+            for hash in self.password_history(account_id).await?.hashes {
+                if verify_secret_hash(&hash, password).await.unwrap_or(false) {
+                    violations.push(PasswordViolation::Reused);
+                    break;
+                }
+            }
+        }
+
+        Ok(violations)
+    }
+
+    pub async fn assert_password_policy(
+        &self,
+        account_id: Option<u32>,
+        password: &str,
+    ) -> trc::Result<()> {
+        let violations = self.validate_password(account_id, password).await?;
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            trc::event!(
+                Auth(trc::AuthEvent::PasswordPolicyViolation),
+                AccountId = account_id,
+                Details = violations
+                    .iter()
+                    .map(|violation| violation.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+
+            Err(trc::ManageEvent::Error
+                .ctx(
+                    trc::Key::Details,
+                    "Password does not meet the policy requirements",
+                )
+                .ctx(
+                    trc::Key::Reason,
+                    violations
+                        .iter()
+                        .map(|violation| violation.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ))
+        }
+    }
+
+    // Validates the new passwords included in a principal update, returning
+    // true if the update changes the account password.
+    pub async fn assert_principal_update_policy(
+        &self,
+        account_id: Option<u32>,
+        changes: &[PrincipalUpdate],
+    ) -> trc::Result<bool> {
+        let mut has_password = false;
+
+        for change in changes.iter().filter(|change| {
+            change.field == PrincipalField::Secrets
+                && matches!(
+                    change.action,
+                    PrincipalAction::Set | PrincipalAction::AddItem
+                )
+        }) {
+            let secrets = match &change.value {
+                PrincipalValue::String(value) => std::slice::from_ref(value),
+                PrincipalValue::StringList(values) => values.as_slice(),
+                PrincipalValue::Integer(_) | PrincipalValue::IntegerList(_) => continue,
+            };
+
+            for secret in secrets
+                .iter()
+                .filter(|secret| !secret.is_empty() && secret.is_password())
+            {
+                has_password = true;
+                self.assert_password_policy(account_id, secret).await?;
+            }
+        }
+
+        Ok(has_password)
+    }
+
+    // Returns the current password hashes of an account when they need
+    // to be added to the password history.
+    pub async fn password_secrets(&self, account_id: u32) -> trc::Result<Vec<String>> {
+        if self.core.password.history > 0 {
+            Ok(self
+                .store()
+                .query(QueryParams::id(account_id).with_return_member_of(false))
+                .await
+                .caused_by(trc::location!())?
+                .map(|principal| principal.secrets)
+                .unwrap_or_default())
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    // Records a password change, keeping the previous password hashes
+    // so they cannot be reused.
+    pub async fn record_password_change(
+        &self,
+        account_id: u32,
+        previous_secrets: &[String],
+    ) -> trc::Result<()> {
+        let policy = &self.core.password;
+        if !policy.is_tracked() {
+            return Ok(());
+        }
+
+        let mut history = self.password_history(account_id).await?;
+        history.changed_at = now();
+        if policy.history > 0 {
+            for secret in previous_secrets
+                .iter()
+                .filter(|secret| secret.is_password())
+            {
+                if !history.hashes.contains(secret) {
+                    history.hashes.insert(0, secret.clone());
+                }
+            }
+            history.hashes.truncate(policy.history);
+        } else {
+            history.hashes.clear();
+        }
+
+        self.in_memory_store()
+            .key_set(KeyValue::with_prefix(
+                KV_PASSWORD_HISTORY,
+                account_id.to_be_bytes(),
+                serde_json::to_vec(&history).unwrap_or_default(),
+            ))
+            .await
+            .caused_by(trc::location!())
+    }
+
+    // Returns true if the password is older than the configured maximum age,
+    // accounts without a recorded change start their clock on first login.
+    pub async fn is_password_expired(&self, account_id: u32) -> trc::Result<bool> {
+        let Some(max_age) = self.core.password.max_age else {
+            return Ok(false);
+        };
+
+        let history = self.password_history(account_id).await?;
+        if history.changed_at != 0 {
+            Ok(history.changed_at + max_age.as_secs() < now())
+        } else {
+            self.record_password_change(account_id, &[])
+                .await
+                .map(|_| false)
+        }
+    }
+
+    async fn password_history(&self, account_id: u32) -> trc::Result<PasswordHistory> {
+        self.in_memory_store()
+            .key_get::<String>(KeyValue::<()>::build_key(
+                KV_PASSWORD_HISTORY,
+                account_id.to_be_bytes(),
+            ))
+            .await
+            .caused_by(trc::location!())
+            .map(|value| {
+                value
+                    .and_then(|value| serde_json::from_str(&value).ok())
+                    .unwrap_or_default()
+            })
+    }
+}
+
+impl PwnedPasswords {
+    // Looks up the password using the k-anonymity range API, only the first
+    // five characters of the SHA-1 hash are sent to the remote server.
+    pub async fn count(&self, password: &str) -> trc::Result<u64> {
+        let hash = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<String>();
+        let (prefix, suffix) = hash.split_at(5);
+
+        let response = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map(|client| {
+                client
+                    .get(format!("{}{prefix}", self.url))
+                    .header("Add-Padding", "true")
+            })
+            .map_err(|err| {
+                trc::AuthEvent::Error
+                    .into_err()
+                    .reason(err)
+                    .details("Failed to build breached password client")
+            })?
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| {
+                trc::AuthEvent::Error
+                    .into_err()
+                    .reason(err)
+                    .ctx(trc::Key::Url, self.url.clone())
+                    .details("Breached password lookup failed")
+            })?
+            .text()
+            .await
+            .map_err(|err| {
+                trc::AuthEvent::Error
+                    .into_err()
+                    .reason(err)
+                    .ctx(trc::Key::Url, self.url.clone())
+                    .details("Failed to read breached password response")
+            })?;
+
+        Ok(response
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+            .and_then(|(_, count)| count.trim().parse().ok())
+            .unwrap_or(0))
+    }
+}
+
+impl Display for PasswordViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasswordViolation::TooShort { min } => {
+                write!(f, "Password must be at least {min} characters long")
+            }
+            PasswordViolation::TooLong { max } => {
+                write!(f, "Password must be at most {max} characters long")
+            }
+            PasswordViolation::MissingLowercase => {
+                write!(f, "Password must contain a lowercase letter")
+            }
+            PasswordViolation::MissingUppercase => {
+                write!(f, "Password must contain an uppercase letter")
+            }
+            PasswordViolation::MissingDigit => write!(f, "Password must contain a digit"),
+            PasswordViolation::MissingSpecial => {
+                write!(f, "Password must contain a special character")
+            }
+            PasswordViolation::TooFewClasses { min } => write!(
+                f,
+                "Password must contain at least {min} of lowercase, uppercase, digits and special characters"
+            ),
+            PasswordViolation::Banned => write!(f, "Password is not allowed"),
+            PasswordViolation::Pwned { count } => write!(
+                f,
+                "Password has appeared {count} times in known data breaches"
+            ),
+            PasswordViolation::Reused => write!(f, "Password was used recently"),
+            PasswordViolation::HashNotAllowed => {
+                write!(f, "Passwords must be submitted in plain text")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use utils::config::Config;
+
+    use super::{PasswordPolicy, PasswordViolation, PwnedPasswords};
+
+    #[test]
+    fn password_policy_content() {
+        let mut config = Config::new(
+            r#"
+[authentication.password]
+length.min = 8
+length.max = 16
+require.lowercase = true
+require.uppercase = true
+require.digit = true
+require.min-classes = 3
+banned = ["Password123", "Summer2024!"]
+history = 3
+max-age = "90d"
+allow-hashed = false
+"#,
+        )
+        .unwrap();
+        let policy = PasswordPolicy::parse(&mut config);
+        assert!(config.errors.is_empty(), "{:?}", config.errors);
+        assert_eq!(policy.min_length, 8);
+        assert_eq!(policy.max_length, Some(16));
+        assert_eq!(policy.history, 3);
+        assert_eq!(policy.max_age, Some(Duration::from_secs(90 * 86400)));
+        assert!(!policy.allow_hashed);
+        assert!(policy.pwned.is_none());
+        assert!(policy.has_content_rules());
+        assert!(policy.is_tracked());
+
+        for (password, expected) in [
+            ("Secret123", vec![]),
+            ("Sécret123", vec![]),
+            ("Sh0rt", vec![PasswordViolation::TooShort { min: 8 }]),
+            (
+                "ThisIsAVeryLong1Password",
+                vec![PasswordViolation::TooLong { max: 16 }],
+            ),
+            (
+                "secret123",
+                vec![
+                    PasswordViolation::MissingUppercase,
+                    PasswordViolation::TooFewClasses { min: 3 },
+                ],
+            ),
+            (
+                "SECRETPASS",
+                vec![
+                    PasswordViolation::MissingLowercase,
+                    PasswordViolation::MissingDigit,
+                    PasswordViolation::TooFewClasses { min: 3 },
+                ],
+            ),
+            ("Secret!!", vec![PasswordViolation::MissingDigit]),
+            (
+                "password123",
+                vec![
+                    PasswordViolation::MissingUppercase,
+                    PasswordViolation::TooFewClasses { min: 3 },
+                    PasswordViolation::Banned,
+                ],
+            ),
+            (
+                "PASSWORD123",
+                vec![
+                    PasswordViolation::MissingLowercase,
+                    PasswordViolation::TooFewClasses { min: 3 },
+                    PasswordViolation::Banned,
+                ],
+            ),
+            ("Summer2024!", vec![PasswordViolation::Banned]),
+        ] {
+            assert_eq!(policy.check_content(password), expected, "{password}");
+        }
+
+        // Special characters only count as a class when required
+        let mut config = Config::new(
+            r#"
+[authentication.password]
+require.special = true
+require.min-classes = 2
+"#,
+        )
+        .unwrap();
+        let policy = PasswordPolicy::parse(&mut config);
+        assert!(!policy.is_tracked());
+        assert_eq!(
+            policy.check_content("secret"),
+            vec![
+                PasswordViolation::MissingSpecial,
+                PasswordViolation::TooFewClasses { min: 2 }
+            ]
+        );
+        assert_eq!(policy.check_content("secret!"), vec![]);
+
+        // Empty policies accept everything
+        let policy = PasswordPolicy::parse(&mut Config::new("").unwrap());
+        assert!(!policy.has_content_rules());
+        assert!(!policy.is_tracked());
+        assert!(policy.allow_hashed);
+        assert_eq!(policy.check_content(""), vec![]);
+    }
+
+    #[tokio::test]
+    async fn pwned_password_lookup() {
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/range/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let bytes_read = stream.read(&mut buf).await.unwrap();
+                    if bytes_read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..bytes_read]);
+                }
+                let request = String::from_utf8(request).unwrap();
+                let body = if request.starts_with("GET /range/5BAA6 ") {
+                    concat!(
+                        "003D68EB55068C33ACE09247EE4C639306B:3\r\n",
+                        "1E4C9B93F3F0682250B6CF8331B7EE68FD8:3861493\r\n",
+                        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF:0\r\n"
+                    )
+                } else {
+                    "003D68EB55068C33ACE09247EE4C639306B:3\r\n"
+                };
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .await
+                    .unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+
+        let pwned = PwnedPasswords {
+            url,
+            timeout: Duration::from_secs(5),
+            max_count: 0,
+        };
+        assert_eq!(pwned.count("password").await.unwrap(), 3861493);
+        assert_eq!(pwned.count("Correct-Horse-Battery-9").await.unwrap(), 0);
+
+        // Only the hash prefix is sent, with padding requested
+        let requests = server.await.unwrap();
+        assert!(
+            requests[0].starts_with("GET /range/5BAA6 "),
+            "{}",
+            requests[0]
+        );
+        for request in &requests {
+            assert!(!request.contains("1E4C9B93F3F0682250B6CF8331B7EE68FD8"));
+            assert!(
+                request.to_lowercase().contains("add-padding: true"),
+                "{request}"
+            );
+        }
+
+        // Lookup failures are reported as errors
+        let pwned = PwnedPasswords {
+            url: "http://127.0.0.1:1/range/".to_string(),
+            timeout: Duration::from_secs(1),
+            max_count: 0,
+        };
+        assert!(pwned.count("password").await.is_err());
+    }
+}
@@ -0,0 +1,331 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{net::IpAddr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use store::{
+    blake3,
+    dispatch::lookup::KeyValue,
+    rand::{Rng, rng},
+    write::now,
+};
+use trc::AddContext;
+use utils::config::Config;
+
+use crate::{KV_SESSION, KV_SESSION_TOKEN, Server};
+
+// Session ids are exposed through the management API as JSON numbers
+const SESSION_ID_MASK: u64 = (1 << 53) - 1;
+const LAST_SEEN_INTERVAL: u64 = 5 * 60;
+const MAX_CLIENT_LEN: usize = 255;
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionRegistry {
+    pub enable: bool,
+    pub retention: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSession {
+    pub id: u64,
+    pub protocol: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    pub remote_ip: IpAddr,
+    pub created_at: u64,
+    pub last_seen: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RefreshTokenBinding {
+    account_id: u32,
+    session_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefreshTokenSession {
+    // Token issued before the registry was enabled
+    Unbound,
+    Active(DeviceSession),
+    Revoked,
+}
+
+impl SessionRegistry {
+    pub fn parse(config: &mut Config) -> Self {
+        SessionRegistry {
+            enable: config
+                .property_or_default("authentication.session.enable", "true")
+                .unwrap_or(true),
+            retention: config
+                .property_or_default::<Duration>("authentication.session.retention", "30d")
+                .unwrap_or_else(|| Duration::from_secs(30 * 24 * 60 * 60))
+                .as_secs(),
+        }
+    }
+}
+
+impl Server {
+    pub async fn track_login_session(
+        &self,
+        account_id: u32,
+        protocol: &str,
+        user_agent: Option<&str>,
+        remote_ip: IpAddr,
+    ) -> trc::Result<()> {
+        let registry = &self.core.sessions;
+        if !registry.enable {
+            return Ok(());
+        }
+
+        // Logins from the same client and address share a single entry
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(protocol.as_bytes());
+        hasher.update(user_agent.unwrap_or_default().as_bytes());
+        hasher.update(remote_ip.to_string().as_bytes());
+        let id = u64::from_be_bytes(hasher.finalize().as_bytes()[..8].try_into().unwrap())
+            & SESSION_ID_MASK;
+
+        let now = now();
+        let session = match self.device_session(account_id, id).await? {
+            Some(session) if session.last_seen + LAST_SEEN_INTERVAL > now => {
+                return Ok(());
+            }
+            Some(mut session) => {
+                session.last_seen = now;
+                session.expires_at = now + registry.retention;
+                session
+            }
+            None => DeviceSession {
+                id,
+                protocol: protocol.to_string(),
+                client_id: None,
+                user_agent: user_agent.map(truncate_client),
+                remote_ip,
+                created_at: now,
+                last_seen: now,
+                expires_at: now + registry.retention,
+            },
+        };
+
+        self.save_device_session(account_id, &session).await
+    }
+
+    pub async fn register_oauth_session(
+        &self,
+        account_id: u32,
+        session: Option<DeviceSession>,
+        client_id: &str,
+        user_agent: Option<&str>,
+        remote_ip: IpAddr,
+        refresh_token: Option<&str>,
+    ) -> trc::Result<()> {
+        let registry = &self.core.sessions;
+        if !registry.enable {
+            return Ok(());
+        }
+
+        // Entries must outlive the refresh tokens bound to them, otherwise
+        // valid tokens would be reported as revoked
+        let expires_in = self
+            .oauth_client_expiry(client_id)
+            .await?
+            .refresh_token
+            .unwrap_or(self.core.oauth.oauth_expiry_refresh_token)
+            .max(registry.retention);
+        let now = now();
+        let session = if let Some(mut session) = session {
+            session.remote_ip = remote_ip;
+            session.last_seen = now;
+            session.expires_at = now + expires_in;
+            session
+        } else {
+            DeviceSession {
+                id: rng().random::<u64>() & SESSION_ID_MASK,
+                protocol: "oauth".to_string(),
+                client_id: Some(truncate_client(client_id)),
+                user_agent: user_agent.map(truncate_client),
+                remote_ip,
+                created_at: now,
+                last_seen: now,
+                expires_at: now + expires_in,
+            }
+        };
+
+        self.save_device_session(account_id, &session).await?;
+
+        if let Some(refresh_token) = refresh_token {
+            self.in_memory_store()
+                .key_set(
+                    KeyValue::with_prefix(
+                        KV_SESSION_TOKEN,
+                        refresh_token_hash(refresh_token),
+                        serde_json::to_vec(&RefreshTokenBinding {
+                            account_id,
+                            session_id: session.id,
+                        })
+                        .unwrap_or_default(),
+                    )
+                    .expires(expires_in),
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn refresh_token_session(
+        &self,
+        account_id: u32,
+        refresh_token: &str,
+    ) -> trc::Result<RefreshTokenSession> {
+        let Some(binding) = self
+            .in_memory_store()
+            .key_get::<String>(KeyValue::<()>::build_key(
+                KV_SESSION_TOKEN,
+                refresh_token_hash(refresh_token),
+            ))
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|value| serde_json::from_str::<RefreshTokenBinding>(&value).ok())
+        else {
+            return Ok(RefreshTokenSession::Unbound);
+        };
+
+        if binding.account_id == account_id
+            && let Some(session) = self.device_session(account_id, binding.session_id).await?
+        {
+            Ok(RefreshTokenSession::Active(session))
+        } else {
+            Ok(RefreshTokenSession::Revoked)
+        }
+    }
+
+    pub async fn list_device_sessions(&self, account_id: u32) -> trc::Result<Vec<DeviceSession>> {
+        let mut sessions = self
+            .in_memory_store()
+            .key_get_prefix(&KeyValue::<()>::build_key(
+                KV_SESSION,
+                account_id.to_be_bytes(),
+            ))
+            .await
+            .caused_by(trc::location!())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice::<DeviceSession>(&value).ok())
+            .collect::<Vec<_>>();
+        sessions.sort_unstable_by(|a, b| b.last_seen.cmp(&a.last_seen));
+
+        Ok(sessions)
+    }
+
+    pub async fn device_session(
+        &self,
+        account_id: u32,
+        session_id: u64,
+    ) -> trc::Result<Option<DeviceSession>> {
+        self.in_memory_store()
+            .key_get::<String>(session_key(account_id, session_id))
+            .await
+            .caused_by(trc::location!())
+            .map(|value| value.and_then(|value| serde_json::from_str(&value).ok()))
+    }
+
+    pub async fn revoke_device_session(
+        &self,
+        account_id: u32,
+        session_id: u64,
+    ) -> trc::Result<bool> {
+        if self.device_session(account_id, session_id).await?.is_some() {
+            // Bound refresh tokens are rejected once their entry is gone
+            self.in_memory_store()
+                .key_delete(session_key(account_id, session_id))
+                .await
+                .caused_by(trc::location!())?;
+
+            trc::event!(
+                Auth(trc::AuthEvent::SessionRevoked),
+                AccountId = account_id,
+                Id = session_id,
+            );
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn revoke_device_sessions(&self, account_id: u32) -> trc::Result<usize> {
+        let sessions = self.list_device_sessions(account_id).await?;
+        if !sessions.is_empty() {
+            self.in_memory_store()
+                .key_delete_prefix(&KeyValue::<()>::build_key(
+                    KV_SESSION,
+                    account_id.to_be_bytes(),
+                ))
+                .await
+                .caused_by(trc::location!())?;
+
+            trc::event!(
+                Auth(trc::AuthEvent::SessionRevoked),
+                AccountId = account_id,
+                Total = sessions.len(),
+            );
+        }
+
+        Ok(sessions.len())
+    }
+
+    async fn save_device_session(
+        &self,
+        account_id: u32,
+        session: &DeviceSession,
+    ) -> trc::Result<()> {
+        let value = serde_json::to_vec(session).map_err(|err| {
+            trc::StoreEvent::UnexpectedError
+                .into_err()
+                .reason(err)
+                .caused_by(trc::location!())
+        })?;
+
+        self.in_memory_store()
+            .key_set(
+                KeyValue::new(session_key(account_id, session.id), value)
+                    .expires(session.expires_at.saturating_sub(now())),
+            )
+            .await
+            .caused_by(trc::location!())
+    }
+}
+
+fn session_key(account_id: u32, session_id: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + 4 + 8);
+    key.push(KV_SESSION);
+    key.extend_from_slice(&account_id.to_be_bytes());
+    key.extend_from_slice(&session_id.to_be_bytes());
+    key
+}
+
+fn refresh_token_hash(refresh_token: &str) -> [u8; 16] {
+    blake3::hash(refresh_token.as_bytes()).as_bytes()[..16]
+        .try_into()
+        .unwrap()
+}
+
+fn truncate_client(client: &str) -> String {
+    client
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .take(MAX_CLIENT_LEN)
+        .collect()
+}
@@ -4,9 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use ahash::AHashMap;
 use std::{str::FromStr, time::Duration};
 
-use utils::{config::Config, template::Template};
+use utils::{
+    config::{Config, utils::ParseValue},
+    template::Template,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct GroupwareConfig {
@@ -32,7 +36,7 @@ pub struct GroupwareConfig {
     pub alarms_from_email: Option<String>,
     pub alarms_template: Template<CalendarTemplateVariable>,
     pub itip_enabled: bool,
-    pub itip_auto_add: bool,
+    pub itip_auto_add: ItipAutoAdd,
     pub itip_inbound_max_ical_size: usize,
     pub itip_outbound_max_recipients: usize,
     pub itip_http_rsvp_url: Option<String>,
@@ -44,6 +48,8 @@ pub struct GroupwareConfig {
     pub max_vcard_size: usize,
     pub default_addressbook_name: Option<String>,
     pub default_addressbook_display_name: Option<String>,
+    pub vcard_normalize: ContactNormalizeConfig,
+    pub vcard_normalize_tenants: AHashMap<u32, ContactNormalizeConfig>,
 
     // File storage settings
     pub max_file_size: usize,
@@ -52,6 +58,26 @@ pub struct GroupwareConfig {
     pub max_shares_per_item: usize,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ContactNormalizeConfig {
+    pub normalize_phones: bool,
+    pub phone_country_code: Option<String>,
+    pub photo_max_size: usize,
+    pub photo_max_dimension: u32,
+    pub photo_quality: u8,
+    pub duplicate_hints: bool,
+}
+
+// Controls whether invitations for events not yet in the user's calendar
+// are added automatically or left in the scheduling inbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItipAutoAdd {
+    Always,
+    #[default]
+    KnownSenders,
+    Never,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, Hash)]
 pub enum CalendarTemplateVariable {
     #[default]
@@ -77,6 +103,35 @@ pub enum CalendarTemplateVariable {
 
 impl GroupwareConfig {
     pub fn parse(config: &mut Config) -> Self {
+        // Contact normalization settings can be overridden for each tenant
+        let vcard_normalize = ContactNormalizeConfig::parse(
+            config,
+            "contacts",
+            &ContactNormalizeConfig {
+                normalize_phones: false,
+                phone_country_code: None,
+                photo_max_size: 128 * 1024,
+                photo_max_dimension: 512,
+                photo_quality: 85,
+                duplicate_hints: true,
+            },
+        );
+        let mut vcard_normalize_tenants = AHashMap::new();
+        for tenant_id in config.sub_keys("contacts.tenant", "") {
+            if let Ok(id) = tenant_id.parse::<u32>() {
+                let prefix = format!("contacts.tenant.{tenant_id}");
+                vcard_normalize_tenants.insert(
+                    id,
+                    ContactNormalizeConfig::parse(config, &prefix, &vcard_normalize),
+                );
+            } else {
+                config.new_parse_error(
+                    ("contacts.tenant", tenant_id.as_str()),
+                    "Expected a numeric tenant id",
+                );
+            }
+        }
+
         GroupwareConfig {
             max_request_size: config
                 .property("dav.request.max-size")
@@ -120,6 +175,8 @@ impl GroupwareConfig {
                 .property("calendar.max-attendees-per-instance")
                 .unwrap_or(20),
             max_vcard_size: config.property("contacts.max-size").unwrap_or(512 * 1024),
+            vcard_normalize,
+            vcard_normalize_tenants,
             max_file_size: config
                 .property("file-storage.max-size")
                 .unwrap_or(25 * 1024 * 1024),
@@ -148,7 +205,7 @@ impl GroupwareConfig {
                 .unwrap_or(true),
             itip_auto_add: config
                 .property("calendar.scheduling.inbound.auto-add")
-                .unwrap_or(false),
+                .unwrap_or_default(),
             itip_inbound_max_ical_size: config
                 .property("calendar.scheduling.inbound.max-size")
                 .unwrap_or(512 * 1024),
@@ -193,6 +250,41 @@ impl GroupwareConfig {
             .expect("Failed to parse calendar template"),
         }
     }
+
+    pub fn vcard_normalize_for(&self, tenant_id: Option<u32>) -> &ContactNormalizeConfig {
+        tenant_id
+            .and_then(|tenant_id| self.vcard_normalize_tenants.get(&tenant_id))
+            .unwrap_or(&self.vcard_normalize)
+    }
+}
+
+impl ContactNormalizeConfig {
+    fn parse(config: &mut Config, prefix: &str, defaults: &ContactNormalizeConfig) -> Self {
+        ContactNormalizeConfig {
+            normalize_phones: config
+                .property((prefix, "normalize.phone-numbers"))
+                .unwrap_or(defaults.normalize_phones),
+            phone_country_code: config
+                .value((prefix, "normalize.country-code"))
+                .map(|v| v.trim().trim_start_matches('+'))
+                .filter(|v| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit()))
+                .map(|v| format!("+{v}"))
+                .or_else(|| defaults.phone_country_code.clone()),
+            photo_max_size: config
+                .property((prefix, "photo.max-size"))
+                .unwrap_or(defaults.photo_max_size),
+            photo_max_dimension: config
+                .property((prefix, "photo.max-dimension"))
+                .unwrap_or(defaults.photo_max_dimension),
+            photo_quality: config
+                .property::<u16>((prefix, "photo.quality"))
+                .map(|quality| quality.clamp(10, 100) as u8)
+                .unwrap_or(defaults.photo_quality),
+            duplicate_hints: config
+                .property((prefix, "duplicate-hints"))
+                .unwrap_or(defaults.duplicate_hints),
+        }
+    }
 }
 
 impl FromStr for CalendarTemplateVariable {
@@ -222,3 +314,14 @@ impl FromStr for CalendarTemplateVariable {
         }
     }
 }
+
+impl ParseValue for ItipAutoAdd {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "always" | "true" => Ok(ItipAutoAdd::Always),
+            "known" | "known-senders" | "false" => Ok(ItipAutoAdd::KnownSenders),
+            "never" => Ok(ItipAutoAdd::Never),
+            _ => Err(format!("Invalid auto-add value {value:?}.")),
+        }
+    }
+}
@@ -6,6 +6,7 @@
 
 use std::time::Duration;
 
+use ahash::AHashMap;
 use utils::config::{Config, Rate};
 
 #[derive(Default, Clone)]
@@ -13,13 +14,21 @@ pub struct ImapConfig {
     pub max_request_size: usize,
     pub max_auth_failures: u32,
     pub allow_plain_auth: bool,
+    pub literal_minus: bool,
 
     pub timeout_auth: Duration,
     pub timeout_unauth: Duration,
     pub timeout_idle: Duration,
+    pub timeout_roles: AHashMap<String, Duration>,
+
+    pub bandwidth_default: Option<u64>,
+    pub bandwidth_roles: AHashMap<String, u64>,
 
     pub rate_requests: Option<Rate>,
     pub rate_concurrent: Option<u64>,
+
+    pub metadata_max_entry_size: usize,
+    pub metadata_max_entries: usize,
 }
 
 impl ImapConfig {
@@ -40,6 +49,25 @@ impl ImapConfig {
             timeout_idle: config
                 .property_or_default("imap.timeout.idle", "30m")
                 .unwrap_or_else(|| Duration::from_secs(1800)),
+            timeout_roles: config
+                .properties::<Duration>("imap.timeout.role")
+                .into_iter()
+                .filter_map(|(key, timeout)| {
+                    key.strip_prefix("imap.timeout.role.")
+                        .map(|role| (role.to_string(), timeout))
+                })
+                .collect(),
+            bandwidth_default: config
+                .property::<Option<u64>>("imap.bandwidth.default")
+                .unwrap_or_default(),
+            bandwidth_roles: config
+                .properties::<u64>("imap.bandwidth.role")
+                .into_iter()
+                .filter_map(|(key, rate)| {
+                    key.strip_prefix("imap.bandwidth.role.")
+                        .map(|role| (role.to_string(), rate))
+                })
+                .collect(),
             rate_requests: config
                 .property_or_default::<Option<Rate>>("imap.rate-limit.requests", "2000/1m")
                 .unwrap_or_default(),
@@ -49,6 +77,15 @@ impl ImapConfig {
             allow_plain_auth: config
                 .property_or_default("imap.auth.allow-plain-text", "false")
                 .unwrap_or(false),
+            literal_minus: config
+                .property_or_default("imap.protocol.literal-minus", "false")
+                .unwrap_or(false),
+            metadata_max_entry_size: config
+                .property_or_default("imap.metadata.max-entry-size", "8192")
+                .unwrap_or(8192),
+            metadata_max_entries: config
+                .property_or_default("imap.metadata.max-entries", "256")
+                .unwrap_or(256),
         }
     }
 }
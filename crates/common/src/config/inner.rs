@@ -9,19 +9,24 @@ use crate::{
     CacheSwap, Caches, Data, DavResource, DavResources, MailboxCache, MessageStoreCache,
     MessageUidCache, TlsConnectors,
     auth::{AccessToken, roles::RolePermissions},
-    config::smtp::resolver::{Policy, Tlsa},
+    config::smtp::{
+        resolver::{Policy, Tlsa},
+        session::{EhloValidationResult, SenderDomainPolicy},
+    },
+    ipc::{IpcChannel, IpcOverflow, IpcPolicies, IpcPolicy},
     listener::blocked::BlockedIps,
-    manager::webadmin::WebAdminManager,
+    manager::{reload::ConfigFingerprint, webadmin::WebAdminManager},
 };
 use ahash::{AHashMap, AHashSet};
 use arc_swap::ArcSwap;
 use mail_auth::{MX, Parameters, Txt};
 use mail_send::smtp::tls::build_tls_connector;
 use nlp::bayes::{TokenHash, Weights};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     sync::Arc,
+    time::Duration,
 };
 use utils::{
     cache::{Cache, CacheWithTtl},
@@ -71,6 +76,12 @@ impl Data {
             logos: Default::default(),
             smtp_connectors: TlsConnectors::default(),
             asn_geo_data: Default::default(),
+            connected_clients: Default::default(),
+            labeled_metrics: Default::default(),
+            blob_scrub: Default::default(),
+            config_fingerprint: Mutex::new(ConfigFingerprint::new(config)),
+            directory_failures: Default::default(),
+            health_report: Default::default(),
         }
     }
 }
@@ -192,6 +203,18 @@ impl Caches {
                 MB_5,
                 ((std::mem::size_of::<Ipv4Addr>() + 255) * 2) as u64,
             ),
+            helo_validation: CacheWithTtl::from_config(
+                config,
+                "helo-validation",
+                MB_1,
+                (std::mem::size_of::<EhloValidationResult>() + 255) as u64,
+            ),
+            sender_domain: CacheWithTtl::from_config(
+                config,
+                "sender-domain",
+                MB_1,
+                (std::mem::size_of::<SenderDomainPolicy>() + 255) as u64,
+            ),
         }
     }
 
@@ -220,6 +243,56 @@ impl Caches {
     }
 }
 
+impl IpcPolicies {
+    pub fn parse(config: &mut Config) -> Self {
+        IpcPolicies {
+            state: IpcPolicy::parse(config, IpcChannel::State),
+            housekeeper: IpcPolicy::parse(config, IpcChannel::Housekeeper),
+            queue: IpcPolicy::parse(config, IpcChannel::Queue),
+            report: IpcPolicy::parse(config, IpcChannel::Report),
+            broadcast: IpcPolicy::parse(config, IpcChannel::Broadcast),
+        }
+    }
+}
+
+impl IpcPolicy {
+    fn parse(config: &mut Config, channel: IpcChannel) -> Self {
+        let prefix = ("ipc", channel.name());
+        let capacity = config
+            .property_or_default::<usize>((prefix.0, prefix.1, "capacity"), "1024")
+            .filter(|capacity| *capacity > 0)
+            .unwrap_or(crate::IPC_CHANNEL_BUFFER);
+        let overflow = config
+            .value((prefix.0, prefix.1, "overflow"))
+            .unwrap_or("block")
+            .to_string();
+        let overflow = match overflow.as_str() {
+            "block" => IpcOverflow::Block {
+                timeout: config
+                    .property_or_default::<Option<Duration>>(
+                        (prefix.0, prefix.1, "timeout"),
+                        "false",
+                    )
+                    .unwrap_or_default(),
+            },
+            "drop" => IpcOverflow::Drop,
+            "buffer" => IpcOverflow::Buffer {
+                limit: config
+                    .property_or_default::<usize>((prefix.0, prefix.1, "buffer-limit"), "100000")
+                    .filter(|limit| *limit > 0)
+                    .unwrap_or(100000),
+            },
+            other => {
+                let err = format!("Invalid overflow policy {other:?}");
+                config.new_parse_error((prefix.0, prefix.1, "overflow"), err);
+                IpcOverflow::Block { timeout: None }
+            }
+        };
+
+        IpcPolicy { capacity, overflow }
+    }
+}
+
 impl Default for Data {
     fn default() -> Self {
         Self {
@@ -234,6 +307,12 @@ impl Default for Data {
             logos: Default::default(),
             smtp_connectors: Default::default(),
             asn_geo_data: Default::default(),
+            connected_clients: Default::default(),
+            labeled_metrics: Default::default(),
+            blob_scrub: Default::default(),
+            config_fingerprint: Default::default(),
+            directory_failures: Default::default(),
+            health_report: Default::default(),
         }
     }
 }
@@ -7,8 +7,9 @@
 use super::settings::JmapConfig;
 use ahash::AHashSet;
 use jmap_proto::request::capability::{
-    BlobCapabilities, Capabilities, Capability, CoreCapabilities, EmptyCapabilities,
-    MailCapabilities, SieveAccountCapabilities, SieveSessionCapabilities, SubmissionCapabilities,
+    ArchiveCapabilities, BlobCapabilities, Capabilities, Capability, CoreCapabilities,
+    EmptyCapabilities, MailCapabilities, SettingsCapabilities, SieveAccountCapabilities,
+    SieveSessionCapabilities, SubmissionCapabilities,
 };
 use types::type_state::DataType;
 use utils::{config::Config, map::vec_map::VecMap};
@@ -172,5 +173,31 @@ impl JmapConfig {
             Capability::Quota,
             Capabilities::Empty(EmptyCapabilities::default()),
         );
+
+        // Add account settings capabilities
+        self.capabilities.session.append(
+            Capability::Settings,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Settings,
+            Capabilities::Settings(SettingsCapabilities {
+                max_size: self.settings_max_size,
+                max_namespaces: self.settings_max_namespaces,
+            }),
+        );
+
+        // Add attachment archiving capabilities
+        self.capabilities.session.append(
+            Capability::Archive,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Archive,
+            Capabilities::Archive(ArchiveCapabilities {
+                min_attachment_size: self.mail_archive_min_size,
+                keeps_originals: self.mail_archive_store.is_some(),
+            }),
+        );
     }
 }
@@ -0,0 +1,117 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use utils::{
+    config::Config,
+    template::{Template, TemplateItem, Variables},
+};
+
+// Server-managed signatures applied to JMAP identities
+#[derive(Debug, Clone)]
+pub struct SignatureTemplate {
+    pub domains: Vec<String>,
+    pub text: Option<Template<String>>,
+    pub html: Option<Template<String>>,
+    pub mode: SignatureMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureMode {
+    ReadOnly,
+    Default,
+}
+
+impl SignatureTemplate {
+    pub fn parse(config: &mut Config) -> Vec<Self> {
+        let mut templates = Vec::new();
+
+        for id in config.sub_keys("email.identity.signature", "") {
+            let id = id.as_str();
+            let text = parse_template(config, id, "text", false);
+            let html = parse_template(config, id, "html", true);
+            if text.is_none() && html.is_none() {
+                config.new_parse_error(
+                    ("email.identity.signature", id),
+                    "Either a text or HTML signature must be specified",
+                );
+                continue;
+            }
+
+            let mode = match config
+                .value(("email.identity.signature", id, "mode"))
+                .unwrap_or("default")
+            {
+                "read-only" => SignatureMode::ReadOnly,
+                "default" => SignatureMode::Default,
+                value => {
+                    let err = format!("Invalid signature mode {value:?}");
+                    config.new_parse_error(("email.identity.signature", id, "mode"), err);
+                    continue;
+                }
+            };
+
+            templates.push(SignatureTemplate {
+                domains: config
+                    .values(("email.identity.signature", id, "domains"))
+                    .map(|(_, domain)| domain.trim().to_lowercase())
+                    .filter(|domain| !domain.is_empty())
+                    .collect(),
+                text,
+                html,
+                mode,
+            });
+        }
+
+        templates
+    }
+
+    pub fn matches(&self, email: &str) -> bool {
+        self.domains.is_empty()
+            || email.rsplit_once('@').is_some_and(|(_, domain)| {
+                self.domains.iter().any(|d| d.eq_ignore_ascii_case(domain))
+            })
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.mode == SignatureMode::ReadOnly
+    }
+
+    pub fn render_text(&self, variables: &Variables<String, String>) -> Option<String> {
+        self.text.as_ref().map(|template| template.eval(variables))
+    }
+
+    pub fn render_html(&self, variables: &Variables<String, String>) -> Option<String> {
+        self.html.as_ref().map(|template| template.eval(variables))
+    }
+}
+
+fn parse_template(
+    config: &mut Config,
+    id: &str,
+    typ: &str,
+    escape: bool,
+) -> Option<Template<String>> {
+    let value = config
+        .value(("email.identity.signature", id, typ))?
+        .to_string();
+    match Template::<String>::parse(&value) {
+        Ok(mut template) => {
+            // Plain text signatures are not HTML escaped
+            if !escape {
+                for item in &mut template.items {
+                    if let TemplateItem::Variable { escape, .. } = item {
+                        *escape = false;
+                    }
+                }
+            }
+            Some(template)
+        }
+        Err(err) => {
+            config.new_parse_error(("email.identity.signature", id, typ), err);
+            None
+        }
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use types::special_use::SpecialUse;
+use utils::config::Config;
+
+// Message count and size caps applied to individual folders
+#[derive(Debug, Clone)]
+pub struct MailboxLimit {
+    pub path: Option<String>,
+    pub role: Option<SpecialUse>,
+    pub max_messages: Option<usize>,
+    pub max_size: Option<u64>,
+    pub overflow: MailboxOverflow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailboxOverflow {
+    Reject,
+    Folder(String),
+    ExpireOldest,
+}
+
+impl MailboxLimit {
+    pub fn parse(config: &mut Config) -> Vec<Self> {
+        let mut limits = Vec::new();
+
+        for id in config.sub_keys("email.folder-limit", "") {
+            let id = id.as_str();
+            let path = config
+                .value(("email.folder-limit", id, "path"))
+                .map(|path| path.trim().to_string())
+                .filter(|path| !path.is_empty());
+            let role = config.property::<SpecialUse>(("email.folder-limit", id, "role"));
+            if path.is_none() && role.is_none() {
+                config.new_parse_error(
+                    ("email.folder-limit", id),
+                    "Either a folder path or role must be specified",
+                );
+                continue;
+            }
+
+            let max_messages = config.property::<usize>(("email.folder-limit", id, "max-messages"));
+            let max_size = config.property::<u64>(("email.folder-limit", id, "max-size"));
+            if max_messages.is_none() && max_size.is_none() {
+                config.new_parse_error(
+                    ("email.folder-limit", id),
+                    "Either a message count or size limit must be specified",
+                );
+                continue;
+            }
+
+            let overflow = match config
+                .value(("email.folder-limit", id, "overflow"))
+                .unwrap_or("reject")
+            {
+                "reject" => MailboxOverflow::Reject,
+                "expire" => MailboxOverflow::ExpireOldest,
+                "folder" => {
+                    if let Some(folder) = config
+                        .value_require(("email.folder-limit", id, "overflow-folder"))
+                        .map(|folder| folder.trim().to_string())
+                    {
+                        MailboxOverflow::Folder(folder)
+                    } else {
+                        continue;
+                    }
+                }
+                value => {
+                    let err = format!("Invalid overflow action {value:?}");
+                    config.new_parse_error(("email.folder-limit", id, "overflow"), err);
+                    continue;
+                }
+            };
+
+            limits.push(MailboxLimit {
+                path,
+                role,
+                max_messages,
+                max_size,
+                overflow,
+            });
+        }
+
+        limits
+    }
+
+    pub fn matches(&self, path: &str, role: SpecialUse) -> bool {
+        self.role.is_some_and(|limit_role| limit_role == role)
+            || self
+                .path
+                .as_ref()
+                .is_some_and(|limit_path| limit_path.eq_ignore_ascii_case(path))
+    }
+
+    pub fn is_within(&self, total_messages: usize, total_size: u64) -> bool {
+        self.max_messages
+            .is_none_or(|max_messages| total_messages <= max_messages)
+            && self.max_size.is_none_or(|max_size| total_size <= max_size)
+    }
+}
@@ -0,0 +1,117 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_parser::{Message, PartType};
+use trc::MessageIngestEvent;
+use utils::config::Config;
+
+// Structural limits enforced on parsed messages to guard against MIME bombs
+#[derive(Debug, Clone)]
+pub struct MimeLimits {
+    pub max_parts: usize,
+    pub max_depth: usize,
+    pub max_headers: usize,
+    pub max_expansion: usize,
+}
+
+#[derive(Default)]
+struct MimeStats {
+    parts: usize,
+    decoded_size: usize,
+}
+
+impl MimeLimits {
+    pub fn parse(config: &mut Config) -> Self {
+        MimeLimits {
+            max_parts: config
+                .property_or_default("email.limits.mime-parts", "1000")
+                .unwrap_or(1000),
+            max_depth: config
+                .property_or_default("email.limits.mime-depth", "20")
+                .unwrap_or(20),
+            max_headers: config
+                .property_or_default("email.limits.headers", "1000")
+                .unwrap_or(1000),
+            max_expansion: config
+                .property_or_default("email.limits.expansion-ratio", "10")
+                .unwrap_or(10),
+        }
+    }
+
+    // Returns the event describing the first limit exceeded by the message
+    pub fn check(&self, message: &Message<'_>, raw_len: usize) -> Result<(), MessageIngestEvent> {
+        let mut stats = MimeStats::default();
+        self.check_message(message, 0, &mut stats)?;
+
+        if stats.decoded_size > raw_len.max(1).saturating_mul(self.max_expansion) {
+            Err(MessageIngestEvent::MimeExpansionExceeded)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_message(
+        &self,
+        message: &Message<'_>,
+        depth: usize,
+        stats: &mut MimeStats,
+    ) -> Result<(), MessageIngestEvent> {
+        stats.parts += message.parts.len();
+        if stats.parts > self.max_parts {
+            return Err(MessageIngestEvent::MimePartsExceeded);
+        }
+
+        self.check_part(message, 0, depth, stats)
+    }
+
+    fn check_part(
+        &self,
+        message: &Message<'_>,
+        part_id: usize,
+        depth: usize,
+        stats: &mut MimeStats,
+    ) -> Result<(), MessageIngestEvent> {
+        if depth > self.max_depth {
+            return Err(MessageIngestEvent::MimeDepthExceeded);
+        }
+        let Some(part) = message.parts.get(part_id) else {
+            return Ok(());
+        };
+        if part.headers.len() > self.max_headers {
+            return Err(MessageIngestEvent::MimeHeadersExceeded);
+        }
+
+        match &part.body {
+            PartType::Text(text) | PartType::Html(text) => {
+                stats.decoded_size += text.len();
+            }
+            PartType::Binary(bytes) | PartType::InlineBinary(bytes) => {
+                stats.decoded_size += bytes.len();
+            }
+            PartType::Message(nested) => {
+                self.check_message(nested, depth + 1, stats)?;
+            }
+            PartType::Multipart(part_ids) => {
+                for part_id in part_ids {
+                    self.check_part(message, *part_id as usize, depth + 1, stats)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MimeLimits {
+    fn default() -> Self {
+        MimeLimits {
+            max_parts: 1000,
+            max_depth: 20,
+            max_headers: 1000,
+            max_expansion: 10,
+        }
+    }
+}
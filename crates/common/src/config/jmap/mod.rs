@@ -5,4 +5,8 @@
  */
 
 pub mod capabilities;
+pub mod identity;
+pub mod mailbox;
+pub mod mime;
+pub mod push;
 pub mod settings;
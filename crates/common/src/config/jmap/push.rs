@@ -0,0 +1,229 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use ahash::AHashMap;
+use biscuit::{
+    ClaimsSet, JWT, RegisteredClaims, SingleOrMultiple,
+    jwa::SignatureAlgorithm,
+    jws::{RegisteredHeader, Secret},
+};
+use ring::signature;
+use serde::{Deserialize, Serialize};
+use store::write::now;
+use utils::config::Config;
+
+use crate::config::{build_ecdsa_pem, build_rsa_keypair};
+
+pub const FCM_DEFAULT_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+
+// Push bridges deliver state changes to mobile devices through
+// the Apple and Google notification services.
+pub struct PushBridge {
+    pub id: String,
+    pub provider: PushProvider,
+    secret: Secret,
+}
+
+pub enum PushProvider {
+    Apns {
+        key_id: String,
+        team_id: String,
+        topic: String,
+        sandbox: bool,
+    },
+    Fcm {
+        project_id: String,
+        client_email: String,
+        token_url: String,
+    },
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct BridgeClaims {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+pub(crate) fn parse_push_bridges(config: &mut Config) -> AHashMap<String, Arc<PushBridge>> {
+    let mut bridges = AHashMap::new();
+
+    for id in config.sub_keys("jmap.push.bridge", ".type") {
+        if let Some(bridge) = PushBridge::parse(config, &id) {
+            bridges.insert(id, Arc::new(bridge));
+        }
+    }
+
+    bridges
+}
+
+impl PushBridge {
+    fn parse(config: &mut Config, id: &str) -> Option<Self> {
+        let (provider, secret) = match config.value_require(("jmap.push.bridge", id, "type"))? {
+            "apns" => {
+                let key = build_ecdsa_pem(
+                    &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+                    config.value_require(("jmap.push.bridge", id, "private-key"))?,
+                )
+                .map_err(|err| {
+                    config.new_build_error(
+                        ("jmap.push.bridge", id, "private-key"),
+                        format!("Failed to build APNs signing key: {err}"),
+                    )
+                })
+                .ok()?;
+
+                (
+                    PushProvider::Apns {
+                        key_id: config
+                            .value_require(("jmap.push.bridge", id, "key-id"))?
+                            .to_string(),
+                        team_id: config
+                            .value_require(("jmap.push.bridge", id, "team-id"))?
+                            .to_string(),
+                        topic: config
+                            .value_require(("jmap.push.bridge", id, "topic"))?
+                            .to_string(),
+                        sandbox: config
+                            .property_or_default(("jmap.push.bridge", id, "sandbox"), "false")
+                            .unwrap_or(false),
+                    },
+                    Secret::EcdsaKeyPair(key.into()),
+                )
+            }
+            "fcm" => {
+                let key = build_rsa_keypair(config.value_require((
+                    "jmap.push.bridge",
+                    id,
+                    "private-key",
+                ))?)
+                .map_err(|err| {
+                    config.new_build_error(
+                        ("jmap.push.bridge", id, "private-key"),
+                        format!("Failed to build FCM signing key: {err}"),
+                    )
+                })
+                .ok()?;
+
+                (
+                    PushProvider::Fcm {
+                        project_id: config
+                            .value_require(("jmap.push.bridge", id, "project-id"))?
+                            .to_string(),
+                        client_email: config
+                            .value_require(("jmap.push.bridge", id, "client-email"))?
+                            .to_string(),
+                        token_url: config
+                            .value(("jmap.push.bridge", id, "token-url"))
+                            .unwrap_or(FCM_DEFAULT_TOKEN_URL)
+                            .to_string(),
+                    },
+                    Secret::RsaKeyPair(key.into()),
+                )
+            }
+            other => {
+                let other = other.to_string();
+                config.new_parse_error(
+                    ("jmap.push.bridge", id, "type"),
+                    format!("Unknown push bridge type {other:?}"),
+                );
+                return None;
+            }
+        };
+
+        Some(PushBridge {
+            id: id.to_string(),
+            provider,
+            secret,
+        })
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        match self.provider {
+            PushProvider::Apns { .. } => "apns",
+            PushProvider::Fcm { .. } => "fcm",
+        }
+    }
+
+    // Issues the JWT used to authenticate with APNs, or the assertion
+    // exchanged for an OAuth access token in the case of FCM.
+    pub fn issue_token(&self, expiry: u64) -> trc::Result<String> {
+        let now = now() as i64;
+        let (algorithm, key_id, registered, claims) = match &self.provider {
+            PushProvider::Apns {
+                key_id, team_id, ..
+            } => (
+                SignatureAlgorithm::ES256,
+                Some(key_id.clone()),
+                RegisteredClaims {
+                    issuer: Some(team_id.clone()),
+                    issued_at: Some(now.into()),
+                    ..Default::default()
+                },
+                BridgeClaims::default(),
+            ),
+            PushProvider::Fcm {
+                client_email,
+                token_url,
+                ..
+            } => (
+                SignatureAlgorithm::RS256,
+                None,
+                RegisteredClaims {
+                    issuer: Some(client_email.clone()),
+                    audience: Some(SingleOrMultiple::Single(token_url.clone())),
+                    issued_at: Some(now.into()),
+                    expiry: Some((now + expiry as i64).into()),
+                    ..Default::default()
+                },
+                BridgeClaims {
+                    scope: Some(FCM_SCOPE.to_string()),
+                },
+            ),
+        };
+
+        JWT::new_decoded(
+            From::from(RegisteredHeader {
+                algorithm,
+                key_id,
+                ..Default::default()
+            }),
+            ClaimsSet::<BridgeClaims> {
+                registered,
+                private: claims,
+            },
+        )
+        .into_encoded(&self.secret)
+        .map(|token| token.unwrap_encoded().to_string())
+        .map_err(|err| {
+            trc::EventType::PushSubscription(trc::PushSubscriptionEvent::Error)
+                .into_err()
+                .reason(err)
+                .details("Failed to encode push bridge token")
+        })
+    }
+}
+
+// Parses a push subscription url of the form <apns|fcm>://<bridge-id>/<device-token>
+pub fn parse_bridge_url(url: &str) -> Option<(&str, &str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let (bridge_id, device_token) = rest.split_once('/')?;
+
+    if matches!(scheme, "apns" | "fcm")
+        && !bridge_id.is_empty()
+        && !device_token.is_empty()
+        && device_token
+            .bytes()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, b'-' | b'_' | b':'))
+    {
+        Some((scheme, bridge_id, device_token))
+    } else {
+        None
+    }
+}
@@ -4,10 +4,20 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use super::{
+    identity::SignatureTemplate,
+    mailbox::MailboxLimit,
+    mime::MimeLimits,
+    push::{PushBridge, parse_bridge_url, parse_push_bridges},
+};
+use ahash::AHashMap;
 use jmap_proto::request::capability::BaseCapabilities;
 use nlp::language::Language;
-use std::{str::FromStr, time::Duration};
-use types::special_use::SpecialUse;
+use std::{str::FromStr, sync::Arc, time::Duration};
+use types::{
+    keyword::{Keyword, OTHER},
+    special_use::SpecialUse,
+};
 use utils::config::{Config, Rate, cron::SimpleCron, utils::ParseValue};
 
 #[derive(Default, Clone)]
@@ -18,6 +28,7 @@ pub struct JmapConfig {
 
     pub changes_max_results: Option<usize>,
     pub changes_max_history: Option<usize>,
+    pub changes_vanished_retention: Option<Duration>,
 
     pub request_max_size: usize,
     pub request_max_calls: usize,
@@ -35,13 +46,34 @@ pub struct JmapConfig {
 
     pub mailbox_max_depth: usize,
     pub mailbox_name_max_len: usize,
+    pub mailbox_limits: Vec<MailboxLimit>,
+    pub identity_signatures: Vec<SignatureTemplate>,
     pub mail_attachments_max_size: usize,
     pub mail_parse_max_items: usize,
+    pub mail_parse_max_depth: usize,
     pub mail_max_size: usize,
     pub mail_autoexpunge_after: Option<u64>,
+    pub mail_dumpster_retention: Option<u64>,
+    pub mail_dumpster_max_size: Option<u64>,
+    pub mail_dumpster_count_quota: bool,
+    pub mail_mime_limits: MimeLimits,
+    pub mail_convert_tnef: bool,
+    pub mail_convert_uuencode: bool,
+    pub mail_archive_min_size: usize,
+    pub mail_archive_store: Option<String>,
+    pub mail_archive_on_move: bool,
+    pub mail_keywords_max_per_message: usize,
+    pub mail_keywords_max_custom: usize,
+    pub mail_keywords_private: Vec<Keyword>,
 
     pub sieve_max_script_name: usize,
     pub sieve_max_scripts: usize,
+    pub sieve_duplicate_account_wide: bool,
+    pub sieve_duplicate_max_expiry: Option<u64>,
+    pub sieve_duplicate_max_ids: Option<usize>,
+
+    pub settings_max_size: usize,
+    pub settings_max_namespaces: usize,
 
     pub rate_authenticated: Option<Rate>,
     pub rate_anonymous: Option<Rate>,
@@ -54,10 +86,12 @@ pub struct JmapConfig {
     pub push_timeout: Duration,
     pub push_verify_timeout: Duration,
     pub push_throttle: Duration,
+    pub push_bridges: AHashMap<String, Arc<PushBridge>>,
 
     pub web_socket_throttle: Duration,
     pub web_socket_timeout: Duration,
     pub web_socket_heartbeat: Duration,
+    pub web_socket_max_in_flight: usize,
 
     pub fallback_admin: Option<(String, String)>,
     pub master_user: Option<(String, String)>,
@@ -221,6 +255,9 @@ impl JmapConfig {
             changes_max_history: config
                 .property_or_default::<Option<usize>>("changes.max-history", "10000")
                 .unwrap_or_default(),
+            changes_vanished_retention: config
+                .property_or_default::<Option<Duration>>("changes.vanished.retention", "30d")
+                .unwrap_or_default(),
             snippet_max_results: config
                 .property("jmap.protocol.search-snippet.max-results")
                 .unwrap_or(100),
@@ -259,21 +296,78 @@ impl JmapConfig {
             mailbox_name_max_len: config
                 .property("jmap.mailbox.max-name-length")
                 .unwrap_or(255),
+            mailbox_limits: MailboxLimit::parse(config),
+            identity_signatures: SignatureTemplate::parse(config),
             mail_attachments_max_size: config
                 .property("jmap.email.max-attachment-size")
                 .unwrap_or(50000000),
             mail_max_size: config.property("jmap.email.max-size").unwrap_or(75000000),
             mail_parse_max_items: config.property("jmap.email.parse.max-items").unwrap_or(10),
+            mail_parse_max_depth: config.property("jmap.email.parse.max-depth").unwrap_or(3),
             mail_autoexpunge_after: config
                 .property_or_default::<Option<Duration>>("email.auto-expunge", "30d")
                 .map(|d| d.map(|d| d.as_secs()))
                 .unwrap_or_default(),
+            mail_dumpster_retention: config
+                .property_or_default::<Option<Duration>>("email.dumpster.retention", "false")
+                .map(|d| d.map(|d| d.as_secs()))
+                .unwrap_or_default(),
+            mail_dumpster_max_size: config
+                .property_or_default::<Option<u64>>("email.dumpster.max-size", "false")
+                .unwrap_or_default(),
+            mail_dumpster_count_quota: config
+                .property_or_default("email.dumpster.count-quota", "false")
+                .unwrap_or(false),
+            mail_mime_limits: MimeLimits::parse(config),
+            mail_convert_tnef: config
+                .property_or_default("email.convert.tnef", "false")
+                .unwrap_or(false),
+            mail_convert_uuencode: config
+                .property_or_default("email.convert.uuencode", "false")
+                .unwrap_or(false),
+            mail_archive_min_size: config
+                .property("email.archive.min-attachment-size")
+                .unwrap_or(1048576),
+            mail_archive_store: config
+                .value("email.archive.blob-store")
+                .map(|s| s.to_string()),
+            mail_archive_on_move: config
+                .property_or_default("email.archive.strip-on-move", "false")
+                .unwrap_or(false),
+            mail_keywords_max_per_message: config
+                .property("email.keywords.max-per-message")
+                .unwrap_or(64),
+            mail_keywords_max_custom: config
+                .property::<usize>("email.keywords.max-custom")
+                .unwrap_or(128 - OTHER)
+                .min(128 - OTHER),
+            mail_keywords_private: config
+                .values("email.keywords.private")
+                .map(|(_, value)| Keyword::parse(value))
+                .collect(),
             sieve_max_script_name: config
                 .property("sieve.untrusted.limits.name-length")
                 .unwrap_or(512),
             sieve_max_scripts: config
                 .property("sieve.untrusted.limits.max-scripts")
                 .unwrap_or(256),
+            sieve_duplicate_account_wide: config
+                .property_or_default("sieve.untrusted.duplicate.account-wide", "false")
+                .unwrap_or(false),
+            sieve_duplicate_max_expiry: config
+                .property_or_default::<Option<Duration>>(
+                    "sieve.untrusted.duplicate.max-window",
+                    "false",
+                )
+                .map(|d| d.map(|d| d.as_secs()))
+                .unwrap_or_default(),
+            sieve_duplicate_max_ids: config
+                .property_or_default::<Option<usize>>("sieve.untrusted.duplicate.max-ids", "false")
+                .unwrap_or_default(),
+            settings_max_size: config.property("jmap.settings.max-size").unwrap_or(65536),
+            settings_max_namespaces: config
+                .property("jmap.settings.max-namespaces")
+                .unwrap_or(32),
             capabilities: BaseCapabilities::default(),
             rate_authenticated: config
                 .property_or_default::<Option<Rate>>("http.rate-limit.account", "1000/1m")
@@ -293,6 +387,10 @@ impl JmapConfig {
             web_socket_heartbeat: config
                 .property_or_default("jmap.web-socket.heartbeat", "1m")
                 .unwrap_or_else(|| Duration::from_secs(60)),
+            web_socket_max_in_flight: config
+                .property_or_default::<usize>("jmap.web-socket.max-in-flight", "4")
+                .unwrap_or(4)
+                .max(1),
             push_max_total: config
                 .property_or_default("jmap.push.max-total", "100")
                 .unwrap_or(100),
@@ -322,6 +420,7 @@ impl JmapConfig {
             push_throttle: config
                 .property_or_default("jmap.push.throttle", "1s")
                 .unwrap_or_else(|| Duration::from_secs(1)),
+            push_bridges: parse_push_bridges(config),
             account_purge_frequency: config
                 .property_or_default::<SimpleCron>("account.purge.frequency", "0 0 *")
                 .unwrap_or_else(|| SimpleCron::parse_value("0 0 *").unwrap()),
@@ -345,4 +444,20 @@ impl JmapConfig {
         jmap.add_capabilities(config);
         jmap
     }
+
+    // Resolves the bridge and device token of an apns:// or fcm:// push url
+    pub fn push_bridge<'x>(&self, url: &'x str) -> Option<(Arc<PushBridge>, &'x str)> {
+        let (scheme, bridge_id, device_token) = parse_bridge_url(url)?;
+        self.push_bridges
+            .get(bridge_id)
+            .filter(|bridge| bridge.scheme() == scheme)
+            .map(|bridge| (bridge.clone(), device_token))
+    }
+
+    // Returns the server-managed signature applicable to an identity address
+    pub fn identity_signature(&self, email: &str) -> Option<&SignatureTemplate> {
+        self.identity_signatures
+            .iter()
+            .find(|template| template.matches(email))
+    }
 }
@@ -9,12 +9,18 @@ use self::{
     storage::Storage,
 };
 use crate::{
-    Core, Network, Security, auth::oauth::config::OAuthConfig, expr::*,
-    listener::tls::AcmeProviders, manager::config::ConfigManager,
+    Core, Network, Security,
+    auth::{
+        attributes::AttributeSchema, oauth::config::OAuthConfig, password::PasswordPolicy,
+        sessions::SessionRegistry,
+    },
+    expr::*,
+    listener::tls::AcmeProviders,
+    manager::config::ConfigManager,
 };
 use arc_swap::ArcSwap;
 use base64::{Engine, engine::general_purpose};
-use directory::{Directories, Directory};
+use directory::{Directories, Directory, DirectoryInner};
 use groupware::GroupwareConfig;
 use hyper::{
     HeaderMap,
@@ -22,7 +28,7 @@ use hyper::{
 };
 use ring::signature::{EcdsaKeyPair, RsaKeyPair};
 use spamfilter::SpamFilterConfig;
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 use store::{BlobBackend, BlobStore, FtsStore, InMemoryStore, Store, Stores};
 use telemetry::Metrics;
 use utils::config::{Config, utils::AsKey};
@@ -39,7 +45,7 @@ pub mod spamfilter;
 pub mod storage;
 pub mod telemetry;
 
-pub(crate) const CONNECTION_VARS: &[u32; 9] = &[
+pub(crate) const CONNECTION_VARS: &[u32; 15] = &[
     V_LISTENER,
     V_REMOTE_IP,
     V_REMOTE_PORT,
@@ -49,6 +55,12 @@ pub(crate) const CONNECTION_VARS: &[u32; 9] = &[
     V_TLS,
     V_ASN,
     V_COUNTRY,
+    V_PROXY_ALPN,
+    V_PROXY_SNI,
+    V_PROXY_CLIENT_CERT,
+    V_PROXY_CLIENT_CN,
+    V_TLS_JA3,
+    V_TLS_JA4,
 ];
 
 impl Core {
@@ -86,12 +98,32 @@ impl Core {
                 }
             })
             .unwrap_or_default();
+        // Non-critical backends that failed to start are replaced by fallbacks
+        // backed by the data store until they become available again
+        let degraded_boot = config
+            .property_or_default::<bool>("storage.degraded.enable", "true")
+            .unwrap_or(true)
+            && !matches!(data, Store::None);
+        let degraded_retry = config
+            .property_or_default::<Duration>("storage.degraded.retry-interval", "30s")
+            .unwrap_or(Duration::from_secs(30));
+        let mut degraded = Vec::new();
+
         let mut lookup = config
             .value_require("storage.lookup")
             .map(|id| id.to_string())
             .and_then(|id| {
                 if let Some(store) = stores.in_memory_stores.get(&id) {
                     store.clone().into()
+                } else if degraded_boot && config.contains_key(("store", id.as_str(), "type")) {
+                    config.new_build_warning(
+                        "storage.lookup",
+                        format!(
+                            "In-memory store {id:?} is unavailable, using the data store until it recovers"
+                        ),
+                    );
+                    degraded.push("storage.lookup");
+                    InMemoryStore::Store(data.clone()).into()
                 } else {
                     config.new_parse_error(
                         "storage.lookup",
@@ -139,6 +171,21 @@ impl Core {
             .and_then(|id| {
                 if let Some(directory) = directories.directories.get(&id) {
                     directory.clone().into()
+                } else if degraded_boot && config.contains_key(("directory", id.as_str(), "type")) {
+                    config.new_build_warning(
+                        "storage.directory",
+                        format!(
+                            "Directory {id:?} is unavailable, lookups will fail temporarily until it recovers"
+                        ),
+                    );
+                    degraded.push("storage.directory");
+                    let directory = Arc::new(Directory {
+                        store: DirectoryInner::Unavailable,
+                        cache: None,
+                        rehash_secrets: None,
+                    });
+                    directories.directories.insert(id, directory.clone());
+                    directory.into()
                 } else {
                     config.new_parse_error(
                         "storage.directory",
@@ -168,6 +215,16 @@ impl Core {
             )
         }
 
+        if !degraded.is_empty() {
+            trc::event!(
+                Server(trc::ServerEvent::Degraded),
+                Details = degraded.clone(),
+            );
+        }
+
+        // Validate user-defined expression functions
+        custom::validate_custom_functions(config);
+
         Self {
             sieve: Scripting::parse(config, &stores).await,
             network: Network::parse(config),
@@ -175,6 +232,9 @@ impl Core {
             jmap: JmapConfig::parse(config),
             imap: ImapConfig::parse(config),
             oauth: OAuthConfig::parse(config),
+            password: PasswordPolicy::parse(config),
+            sessions: SessionRegistry::parse(config),
+            attributes: AttributeSchema::parse(config),
             acme: AcmeProviders::parse(config),
             metrics: Metrics::parse(config),
             spam: SpamFilterConfig::parse(config).await,
@@ -189,6 +249,8 @@ impl Core {
                 directories: directories.directories,
                 purge_schedules: stores.purge_schedules,
                 config: config_manager,
+                degraded,
+                degraded_retry,
                 stores: stores.stores,
                 lookups: stores.in_memory_stores,
                 blobs: stores.blob_stores,
@@ -7,7 +7,9 @@
 use std::time::Duration;
 
 use crate::expr::{if_block::IfBlock, tokenizer::TokenMap};
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use types::blob_hash::BlobHash;
 
 use utils::config::{Config, Rate};
 
@@ -24,6 +26,37 @@ pub struct Network {
     pub http_response_url: IfBlock,
     pub http_allowed_endpoint: IfBlock,
     pub asn_geo_lookup: AsnGeoLookupConfig,
+    pub branding: AHashMap<String, Branding>,
+    pub avatar: AvatarSettings,
+    pub deep_health_check: Option<DeepHealthSettings>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DeepHealthSettings {
+    pub auth: Option<String>,
+    pub cache: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct AvatarSettings {
+    pub max_size: usize,
+    pub max_dimension: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Branding {
+    pub id: String,
+    pub product_name: Option<String>,
+    pub support_url: Option<String>,
+    pub primary_color: Option<String>,
+    pub accent_color: Option<String>,
+    pub logo: Option<BrandingLogo>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BrandingLogo {
+    pub hash: BlobHash,
+    pub content_type: String,
 }
 
 #[derive(Clone)]
@@ -102,6 +135,12 @@ impl Default for Network {
             ),
             http_allowed_endpoint: IfBlock::new::<()>("http.allowed-endpoint", [], "200"),
             asn_geo_lookup: AsnGeoLookupConfig::Disabled,
+            branding: AHashMap::new(),
+            avatar: AvatarSettings {
+                max_size: 256 * 1024,
+                max_dimension: 1024,
+            },
+            deep_health_check: None,
             server_name: Default::default(),
             report_domain: Default::default(),
             roles: ClusterRoles {
@@ -115,6 +154,31 @@ impl Default for Network {
     }
 }
 
+impl DeepHealthSettings {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        // Deep checks write to every store and disclose backend ids, keep them opt-in
+        if !config
+            .property_or_default::<bool>("http.health.deep.enable", "false")
+            .unwrap_or_default()
+        {
+            return None;
+        }
+
+        Some(DeepHealthSettings {
+            auth: config
+                .value("http.health.deep.auth.username")
+                .and_then(|user| {
+                    config
+                        .value("http.health.deep.auth.secret")
+                        .map(|secret| STANDARD.encode(format!("{user}:{secret}")))
+                }),
+            cache: config
+                .property_or_default("http.health.deep.cache", "10s")
+                .unwrap_or_else(|| Duration::from_secs(10)),
+        })
+    }
+}
+
 impl ContactForm {
     pub fn parse(config: &mut Config) -> Option<Self> {
         if !config
@@ -205,6 +269,16 @@ impl Network {
             security: Security::parse(config),
             contact_form: ContactForm::parse(config),
             asn_geo_lookup: AsnGeoLookupConfig::parse(config).unwrap_or_default(),
+            branding: Branding::parse(config),
+            avatar: AvatarSettings {
+                max_size: config
+                    .property_or_default("avatar.max-size", "262144")
+                    .unwrap_or(256 * 1024),
+                max_dimension: config
+                    .property_or_default("avatar.max-dimension", "1024")
+                    .unwrap_or(1024),
+            },
+            deep_health_check: DeepHealthSettings::parse(config),
             ..Default::default()
         };
         let token_map = &TokenMap::default().with_variables(HTTP_VARS);
@@ -252,6 +326,84 @@ impl Network {
     }
 }
 
+impl Branding {
+    pub fn parse(config: &mut Config) -> AHashMap<String, Branding> {
+        let mut branding = AHashMap::new();
+
+        for id in config.sub_keys("branding", "") {
+            let id = id.as_str();
+            let logo = if let Some(hash) = config.value(("branding", id, "logo.hash")) {
+                if let Some(hash) = BlobHash::try_from_hex(hash) {
+                    Some(BrandingLogo {
+                        hash,
+                        content_type: config
+                            .value(("branding", id, "logo.content-type"))
+                            .unwrap_or("image/png")
+                            .to_string(),
+                    })
+                } else {
+                    config.new_parse_error(("branding", id, "logo.hash"), "Invalid blob hash");
+                    None
+                }
+            } else {
+                None
+            };
+            let entry = Branding {
+                id: id.to_string(),
+                product_name: config
+                    .value(("branding", id, "name"))
+                    .map(|v| v.to_string()),
+                support_url: config
+                    .value(("branding", id, "support-url"))
+                    .map(|v| v.to_string()),
+                primary_color: config
+                    .value(("branding", id, "color.primary"))
+                    .map(|v| v.to_string()),
+                accent_color: config
+                    .value(("branding", id, "color.accent"))
+                    .map(|v| v.to_string()),
+                logo,
+            };
+
+            for (_, domain) in config.values(("branding", id, "domain")) {
+                branding.insert(domain.trim().to_lowercase(), entry.clone());
+            }
+        }
+
+        branding
+    }
+}
+
+impl AvatarSettings {
+    // Returns the content type of a valid avatar image
+    pub fn validate(&self, contents: &[u8]) -> Result<&'static str, &'static str> {
+        if contents.len() > self.max_size {
+            return Err("Avatar exceeds the maximum allowed size");
+        }
+
+        let content_type = match imagesize::image_type(contents) {
+            Ok(imagesize::ImageType::Png) => "image/png",
+            Ok(imagesize::ImageType::Jpeg) => "image/jpeg",
+            Ok(imagesize::ImageType::Gif) => "image/gif",
+            Ok(imagesize::ImageType::Webp) => "image/webp",
+            _ => return Err("Avatars must be PNG, JPEG, GIF or WebP images"),
+        };
+
+        match imagesize::blob_size(contents) {
+            Ok(size)
+                if size.width > 0
+                    && size.height > 0
+                    && size.width <= self.max_dimension as usize
+                    && size.height <= self.max_dimension as usize =>
+            {
+                Ok(content_type)
+            }
+            Ok(_) => Err("Avatar dimensions exceed the maximum allowed"),
+            Err(_) => Err("Failed to read avatar dimensions"),
+        }
+    }
+}
+
 impl AsnGeoLookupConfig {
     pub fn parse(config: &mut Config) -> Option<Self> {
         match config.value("asn.type")? {
@@ -7,9 +7,14 @@
 use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use rustls::{
-    ALL_VERSIONS, ServerConfig, SupportedCipherSuite,
-    crypto::ring::{ALL_CIPHER_SUITES, default_provider},
+    ALL_VERSIONS, RootCertStore, ServerConfig, SupportedCipherSuite,
+    crypto::{
+        CryptoProvider,
+        ring::{ALL_CIPHER_SUITES, default_provider},
+    },
+    server::{WebPkiClientVerifier, danger::ClientCertVerifier},
 };
+use rustls_pki_types::{CertificateDer, pem::PemObject};
 
 use tokio::net::TcpSocket;
 use tokio_rustls::TlsAcceptor;
@@ -23,7 +28,12 @@ use utils::{
 
 use crate::{
     Inner,
-    listener::{TcpAcceptor, tls::CertificateResolver},
+    auth::certificate::{ClientCertMapping, ClientCertPolicy},
+    listener::{
+        TcpAcceptor,
+        limiter::{BufferLimiter, MessageLimiter},
+        tls::{CertificateResolver, KeyLogWriter},
+    },
 };
 
 use super::{
@@ -41,6 +51,10 @@ impl Listeners {
                     .map(SnowflakeIdGenerator::with_node_id)
                     .unwrap_or_default(),
             ),
+            message_limiter: BufferLimiter::new(
+                config.property::<u64>("server.limits.messages"),
+                config.property::<u64>("server.limits.buffer"),
+            ),
             ..Default::default()
         };
 
@@ -201,6 +215,13 @@ impl Listeners {
                     "8192",
                 )
                 .unwrap_or(8192),
+            message_limiter: MessageLimiter {
+                global: self.message_limiter.clone(),
+                listener: BufferLimiter::new(
+                    config.property::<u64>(("server.listener", id, "limits.messages")),
+                    config.property::<u64>(("server.listener", id, "limits.buffer")),
+                ),
+            },
             id: id_,
             protocol,
             listeners,
@@ -243,6 +264,26 @@ impl Listeners {
                     );
                 }
 
+                // Parse minimum protocol version
+                match config.value_or_else(
+                    ("server.listener", id, "tls.min-version"),
+                    "server.tls.min-version",
+                ) {
+                    Some("TLSv1.2" | "0x0303") | None => {}
+                    Some("TLSv1.3" | "0x0304") => tls_v2 = false,
+                    Some(version) => {
+                        let err = format!("Unsupported minimum TLS version {version:?}");
+                        config.new_parse_error(("server.listener", id, "tls.min-version"), err);
+                    }
+                }
+                if !tls_v2 && !tls_v3 {
+                    config.new_build_error(
+                        ("server.listener", id, "tls"),
+                        "All TLS protocol versions are disabled",
+                    );
+                    return;
+                }
+
                 // Parse cipher suites
                 let mut disabled_ciphers: Vec<SupportedCipherSuite> = Vec::new();
                 let cipher_keys =
@@ -255,18 +296,64 @@ impl Listeners {
                     disabled_ciphers.push(protocol);
                 }
 
+                // Parse cipher suite allow-list, listed in order of preference
+                let cipher_keys = if config.has_prefix(("server.listener", id, "tls.ciphers")) {
+                    ("server.listener", id, "tls.ciphers").as_key()
+                } else {
+                    "server.tls.ciphers".as_key()
+                };
+                let allowed_ciphers = config
+                    .properties::<SupportedCipherSuite>(cipher_keys)
+                    .into_iter()
+                    .map(|(_, suite)| suite)
+                    .collect::<Vec<_>>();
+
                 // Build cert provider
                 let mut provider = default_provider();
-                if !disabled_ciphers.is_empty() {
+                if !allowed_ciphers.is_empty() {
+                    provider.cipher_suites = allowed_ciphers
+                        .into_iter()
+                        .filter(|suite| !disabled_ciphers.contains(suite))
+                        .collect();
+                } else if !disabled_ciphers.is_empty() {
                     provider.cipher_suites = ALL_CIPHER_SUITES
                         .iter()
                         .filter(|suite| !disabled_ciphers.contains(suite))
                         .copied()
                         .collect();
                 }
+                if provider.cipher_suites.is_empty() {
+                    config.new_build_error(
+                        ("server.listener", id, "tls"),
+                        "No TLS cipher suites are enabled",
+                    );
+                    return;
+                }
+
+                // Build client certificate verifier
+                let provider = Arc::new(provider);
+                let client_cert_policy = config
+                    .property_or_default::<ClientCertPolicy>(
+                        ("server.listener", id, "tls.client-cert.policy"),
+                        "none",
+                    )
+                    .unwrap_or_default();
+                let client_cert_verifier = if client_cert_policy != ClientCertPolicy::Disabled {
+                    match build_client_cert_verifier(
+                        config,
+                        id,
+                        provider.clone(),
+                        client_cert_policy,
+                    ) {
+                        Some(verifier) => Some(verifier),
+                        None => return,
+                    }
+                } else {
+                    None
+                };
 
                 // Build server config
-                let mut server_config = match ServerConfig::builder_with_provider(provider.into())
+                let mut server_config = match ServerConfig::builder_with_provider(provider)
                     .with_protocol_versions(if tls_v3 == tls_v2 {
                         ALL_VERSIONS
                     } else if tls_v3 {
@@ -274,9 +361,12 @@ impl Listeners {
                     } else {
                         TLS12_VERSION
                     }) {
-                    Ok(server_config) => server_config
-                        .with_no_client_auth()
-                        .with_cert_resolver(resolver.clone()),
+                    Ok(server_config) => if let Some(verifier) = &client_cert_verifier {
+                        server_config.with_client_cert_verifier(verifier.clone())
+                    } else {
+                        server_config.with_no_client_auth()
+                    }
+                    .with_cert_resolver(resolver.clone()),
                     Err(err) => {
                         config.new_build_error(
                             ("server.listener", id, "tls"),
@@ -294,6 +384,37 @@ impl Listeners {
                     )
                     .unwrap_or(true);
 
+                // Parse ALPN protocols
+                server_config.alpn_protocols = config
+                    .values_or_else(("server.listener", id, "tls.alpn"), "server.tls.alpn")
+                    .map(|(_, protocol)| protocol.as_bytes().to_vec())
+                    .collect();
+
+                // Key logging for debugging purposes
+                if let Some(path) = config
+                    .value_or_else(("server.listener", id, "tls.key-log"), "server.tls.key-log")
+                    .filter(|path| !path.is_empty())
+                    .map(|path| path.to_string())
+                {
+                    match KeyLogWriter::open(&path) {
+                        Ok(key_log) => {
+                            server_config.key_log = Arc::new(key_log);
+                            config.new_build_warning(
+                                ("server.listener", id, "tls.key-log"),
+                                format!(
+                                    "TLS secrets are logged to {path:?}, use for debugging only"
+                                ),
+                            );
+                        }
+                        Err(err) => {
+                            config.new_build_error(
+                                ("server.listener", id, "tls.key-log"),
+                                format!("Failed to open TLS key log file {path:?}: {err}"),
+                            );
+                        }
+                    }
+                }
+
                 // Build acceptor
                 let default_config = Arc::new(server_config);
                 TcpAcceptor::Tls {
@@ -302,6 +423,17 @@ impl Listeners {
                     implicit: config
                         .property_or_default(("server.listener", id, "tls.implicit"), "false")
                         .unwrap_or(false),
+                    client_cert: client_cert_verifier.is_some().then(|| {
+                        config
+                            .property_or_default::<ClientCertMapping>(
+                                ("server.listener", id, "tls.client-cert.mapping"),
+                                "email",
+                            )
+                            .unwrap_or_default()
+                    }),
+                    fingerprint: config
+                        .property_or_default(("server.listener", id, "tls.fingerprint"), "false")
+                        .unwrap_or(false),
                 }
             } else {
                 TcpAcceptor::Plain
@@ -312,6 +444,59 @@ impl Listeners {
     }
 }
 
+fn build_client_cert_verifier(
+    config: &mut Config,
+    id: &str,
+    provider: Arc<CryptoProvider>,
+    policy: ClientCertPolicy,
+) -> Option<Arc<dyn ClientCertVerifier>> {
+    let key = ("server.listener", id, "tls.client-cert.ca-certificate");
+    let pem = if let Some(pem) = config
+        .value(key)
+        .or_else(|| config.value("server.tls.client-cert.ca-certificate"))
+    {
+        pem.to_string()
+    } else {
+        config.new_parse_error(
+            key,
+            "A CA certificate is required to verify client certificates",
+        );
+        return None;
+    };
+
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_slice_iter(pem.as_bytes()) {
+        if let Err(err) = cert.map_err(|err| err.to_string()).and_then(|cert| {
+            roots
+                .add(cert)
+                .map_err(|err| format!("Failed to add CA certificate: {err}"))
+        }) {
+            config.new_parse_error(key, err);
+            return None;
+        }
+    }
+    if roots.is_empty() {
+        config.new_parse_error(key, "No certificates found in PEM data");
+        return None;
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+    match if policy == ClientCertPolicy::Optional {
+        builder.allow_unauthenticated().build()
+    } else {
+        builder.build()
+    } {
+        Ok(verifier) => Some(verifier),
+        Err(err) => {
+            config.new_build_error(
+                key,
+                format!("Failed to build client certificate verifier: {err}"),
+            );
+            None
+        }
+    }
+}
+
 impl ParseValue for ServerProtocol {
     fn parse_value(value: &str) -> Result<Self, String> {
         if value.eq_ignore_ascii_case("smtp") {
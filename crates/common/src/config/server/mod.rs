@@ -11,7 +11,10 @@ use serde::{Deserialize, Serialize};
 use tokio::net::TcpSocket;
 use utils::{config::ipmask::IpAddrMask, snowflake::SnowflakeIdGenerator};
 
-use crate::listener::TcpAcceptor;
+use crate::listener::{
+    TcpAcceptor,
+    limiter::{BufferLimiter, MessageLimiter},
+};
 
 pub mod listener;
 pub mod tls;
@@ -21,6 +24,7 @@ pub struct Listeners {
     pub servers: Vec<Listener>,
     pub tcp_acceptors: AHashMap<String, TcpAcceptor>,
     pub span_id_gen: Arc<SnowflakeIdGenerator>,
+    pub message_limiter: BufferLimiter,
 }
 
 #[derive(Debug, Default)]
@@ -30,6 +34,7 @@ pub struct Listener {
     pub listeners: Vec<TcpListener>,
     pub proxy_networks: Vec<IpAddrMask>,
     pub max_connections: u64,
+    pub message_limiter: MessageLimiter,
     pub span_id_gen: Arc<SnowflakeIdGenerator>,
 }
 
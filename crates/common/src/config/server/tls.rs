@@ -33,11 +33,15 @@ use x509_parser::{
     extensions::{GeneralName, ParsedExtension},
 };
 
-use crate::listener::{
-    acme::{
-        AcmeProvider, ChallengeSettings, EabSettings, directory::LETS_ENCRYPT_PRODUCTION_DIRECTORY,
+use crate::{
+    config::parse_http_headers,
+    listener::{
+        acme::{
+            AcmeProvider, ChallengeSettings, EabSettings, Http01Delegate,
+            directory::LETS_ENCRYPT_PRODUCTION_DIRECTORY,
+        },
+        tls::AcmeProviders,
     },
-    tls::AcmeProviders,
 };
 
 pub static TLS13_VERSION: &[&SupportedProtocolVersion] = &[&TLS13];
@@ -82,7 +86,12 @@ impl AcmeProviders {
                 .unwrap_or("tls-alpn-01")
             {
                 "tls-alpn-01" => ChallengeSettings::TlsAlpn01,
-                "http-01" => ChallengeSettings::Http01,
+                "http-01" => match parse_http01_delegate(config, acme_id) {
+                    Ok(delegate) => ChallengeSettings::Http01 { delegate },
+                    Err(()) => {
+                        continue;
+                    }
+                },
                 "dns-01" => match build_dns_updater(config, acme_id) {
                     Some(updater) => ChallengeSettings::Dns01 {
                         updater,
@@ -185,6 +194,55 @@ impl AcmeProviders {
 }
 
 #[allow(clippy::unnecessary_to_owned)]
+fn parse_http01_delegate(config: &mut Config, acme_id: &str) -> Result<Option<Http01Delegate>, ()> {
+    match config
+        .value(("acme", acme_id, "http-01.delegate"))
+        .unwrap_or("none")
+    {
+        "none" => Ok(None),
+        "push" => {
+            let url = config
+                .value_require(("acme", acme_id, "http-01.url"))
+                .ok_or(())?
+                .trim()
+                .to_string();
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                config.new_parse_error(
+                    ("acme", acme_id, "http-01.url"),
+                    "Only HTTP and HTTPS URLs are supported",
+                );
+                return Err(());
+            }
+
+            Ok(Some(Http01Delegate::Push {
+                url,
+                headers: parse_http_headers(config, ("acme", acme_id, "http-01")),
+                timeout: config
+                    .property_or_default(("acme", acme_id, "http-01.timeout"), "30s")
+                    .unwrap_or_else(|| Duration::from_secs(30)),
+                tls_allow_invalid_certs: config
+                    .property_or_default(("acme", acme_id, "http-01.allow-invalid-certs"), "false")
+                    .unwrap_or_default(),
+            }))
+        }
+        "alias" => Ok(Some(Http01Delegate::Alias {
+            hostname: config
+                .value_require(("acme", acme_id, "http-01.alias"))
+                .ok_or(())?
+                .trim()
+                .trim_end_matches('.')
+                .to_lowercase(),
+        })),
+        _ => {
+            config.new_parse_error(
+                ("acme", acme_id, "http-01.delegate"),
+                "Invalid HTTP-01 delegation method",
+            );
+            Err(())
+        }
+    }
+}
+
 fn build_dns_updater(config: &mut Config, acme_id: &str) -> Option<DnsUpdater> {
     let timeout = config
         .property_or_default(("acme", acme_id, "timeout"), "30s")
@@ -52,6 +52,9 @@ pub struct DkimAuthConfig {
     pub verify: IfBlock,
     pub sign: IfBlock,
     pub strict: bool,
+    pub reject_unsigned_body: bool,
+    pub reject_unsigned_headers: bool,
+    pub policy_override: IfBlock,
 }
 
 #[derive(Clone)]
@@ -64,11 +67,13 @@ pub struct ArcAuthConfig {
 pub struct SpfAuthConfig {
     pub verify_ehlo: IfBlock,
     pub verify_mail_from: IfBlock,
+    pub policy_override: IfBlock,
 }
 
 #[derive(Clone)]
 pub struct DmarcAuthConfig {
     pub verify: IfBlock,
+    pub policy_override: IfBlock,
 }
 
 #[derive(Clone)]
@@ -84,6 +89,14 @@ pub enum VerifyStrategy {
     Disable,
 }
 
+// Changes the outcome of a failed authentication check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthOverride {
+    Accept,
+    Quarantine,
+    Reject,
+}
+
 #[derive(Debug, Clone)]
 pub struct DkimCanonicalization {
     pub headers: Canonicalization,
@@ -114,6 +127,9 @@ impl Default for MailAuthConfig {
                     "false",
                 ),
                 strict: true,
+                reject_unsigned_body: false,
+                reject_unsigned_headers: false,
+                policy_override: IfBlock::empty("auth.dkim.override"),
             },
             arc: ArcAuthConfig {
                 verify: IfBlock::new::<VerifyStrategy>("auth.arc.verify", [], "relaxed"),
@@ -140,6 +156,7 @@ impl Default for MailAuthConfig {
                     #[cfg(feature = "test_mode")]
                     "relaxed",
                 ),
+                policy_override: IfBlock::empty("auth.spf.override"),
             },
             dmarc: DmarcAuthConfig {
                 verify: IfBlock::new::<VerifyStrategy>(
@@ -150,6 +167,7 @@ impl Default for MailAuthConfig {
                     #[cfg(feature = "test_mode")]
                     "relaxed",
                 ),
+                policy_override: IfBlock::empty("auth.dmarc.override"),
             },
             iprev: IpRevAuthConfig {
                 verify: IfBlock::new::<VerifyStrategy>(
@@ -174,6 +192,12 @@ impl MailAuthConfig {
         let conn_vars = TokenMap::default()
             .with_variables(CONNECTION_VARS)
             .with_constants::<VerifyStrategy>();
+        let mail_from_override_vars = TokenMap::default()
+            .with_variables(SMTP_MAIL_FROM_VARS)
+            .with_constants::<AuthOverride>();
+        let rcpt_override_vars = TokenMap::default()
+            .with_variables(SMTP_RCPT_TO_VARS)
+            .with_constants::<AuthOverride>();
         let mut mail_auth = Self::default();
 
         for (value, key, token_map) in [
@@ -193,6 +217,21 @@ impl MailAuthConfig {
             ),
             (&mut mail_auth.dmarc.verify, "auth.dmarc.verify", &rcpt_vars),
             (&mut mail_auth.iprev.verify, "auth.iprev.verify", &conn_vars),
+            (
+                &mut mail_auth.dkim.policy_override,
+                "auth.dkim.override",
+                &rcpt_override_vars,
+            ),
+            (
+                &mut mail_auth.spf.policy_override,
+                "auth.spf.override",
+                &mail_from_override_vars,
+            ),
+            (
+                &mut mail_auth.dmarc.policy_override,
+                "auth.dmarc.override",
+                &rcpt_override_vars,
+            ),
         ] {
             if let Some(if_block) = IfBlock::try_parse(config, key, token_map) {
                 *value = if_block;
@@ -201,6 +240,12 @@ impl MailAuthConfig {
         mail_auth.dkim.strict = config
             .property_or_default("auth.dkim.strict", "true")
             .unwrap_or(true);
+        mail_auth.dkim.reject_unsigned_body = config
+            .property_or_default("auth.dkim.reject.unsigned-body", "false")
+            .unwrap_or(false);
+        mail_auth.dkim.reject_unsigned_headers = config
+            .property_or_default("auth.dkim.reject.unsigned-headers", "false")
+            .unwrap_or(false);
 
         // Parse signatures
         let mut signatures: AHashMap<&str, Config> = AHashMap::new();
@@ -483,6 +528,62 @@ impl ConstantValue for VerifyStrategy {
     }
 }
 
+impl<'x> TryFrom<expr::Variable<'x>> for AuthOverride {
+    type Error = ();
+
+    fn try_from(value: expr::Variable<'x>) -> Result<Self, Self::Error> {
+        match value {
+            expr::Variable::Integer(c) => match c {
+                0 => Ok(AuthOverride::Accept),
+                1 => Ok(AuthOverride::Quarantine),
+                2 => Ok(AuthOverride::Reject),
+                _ => Err(()),
+            },
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<AuthOverride> for Constant {
+    fn from(value: AuthOverride) -> Self {
+        Constant::Integer(match value {
+            AuthOverride::Accept => 0,
+            AuthOverride::Quarantine => 1,
+            AuthOverride::Reject => 2,
+        })
+    }
+}
+
+impl ParseValue for AuthOverride {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "accept" => Ok(AuthOverride::Accept),
+            "quarantine" => Ok(AuthOverride::Quarantine),
+            "reject" => Ok(AuthOverride::Reject),
+            _ => Err(format!("Invalid value {:?}.", value)),
+        }
+    }
+}
+
+impl ConstantValue for AuthOverride {
+    fn add_constants(token_map: &mut TokenMap) {
+        token_map
+            .add_constant("accept", AuthOverride::Accept)
+            .add_constant("quarantine", AuthOverride::Quarantine)
+            .add_constant("reject", AuthOverride::Reject);
+    }
+}
+
+impl AuthOverride {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthOverride::Accept => "accept",
+            AuthOverride::Quarantine => "quarantine",
+            AuthOverride::Reject => "reject",
+        }
+    }
+}
+
 impl ParseValue for DkimCanonicalization {
     fn parse_value(value: &str) -> Result<Self, String> {
         if let Some((headers, body)) = value.split_once('/') {
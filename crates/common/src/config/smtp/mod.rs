@@ -53,7 +53,7 @@ pub const THROTTLE_HELO_DOMAIN: u16 = 1 << 9;
 
 pub(crate) const RCPT_DOMAIN_VARS: &[u32; 1] = &[V_RECIPIENT_DOMAIN];
 
-pub(crate) const SMTP_EHLO_VARS: &[u32; 10] = &[
+pub(crate) const SMTP_EHLO_VARS: &[u32; 17] = &[
     V_LISTENER,
     V_REMOTE_IP,
     V_REMOTE_PORT,
@@ -62,10 +62,17 @@ pub(crate) const SMTP_EHLO_VARS: &[u32; 10] = &[
     V_PROTOCOL,
     V_TLS,
     V_HELO_DOMAIN,
+    V_HELO_SCORE,
     V_ASN,
     V_COUNTRY,
+    V_PROXY_ALPN,
+    V_PROXY_SNI,
+    V_PROXY_CLIENT_CERT,
+    V_PROXY_CLIENT_CN,
+    V_TLS_JA3,
+    V_TLS_JA4,
 ];
-pub(crate) const SMTP_MAIL_FROM_VARS: &[u32; 12] = &[
+pub(crate) const SMTP_MAIL_FROM_VARS: &[u32; 21] = &[
     V_LISTENER,
     V_REMOTE_IP,
     V_REMOTE_PORT,
@@ -76,10 +83,19 @@ pub(crate) const SMTP_MAIL_FROM_VARS: &[u32; 12] = &[
     V_SENDER,
     V_SENDER_DOMAIN,
     V_AUTHENTICATED_AS,
+    V_HELO_SCORE,
     V_ASN,
     V_COUNTRY,
+    V_PROXY_ALPN,
+    V_PROXY_SNI,
+    V_PROXY_CLIENT_CERT,
+    V_PROXY_CLIENT_CN,
+    V_TLS_JA3,
+    V_TLS_JA4,
+    V_SPF_RESULT,
+    V_IPREV_RESULT,
 ];
-pub(crate) const SMTP_RCPT_TO_VARS: &[u32; 17] = &[
+pub(crate) const SMTP_RCPT_TO_VARS: &[u32; 31] = &[
     V_SENDER,
     V_SENDER_DOMAIN,
     V_RECIPIENTS,
@@ -95,10 +111,24 @@ pub(crate) const SMTP_RCPT_TO_VARS: &[u32; 17] = &[
     V_TLS,
     V_PRIORITY,
     V_HELO_DOMAIN,
+    V_HELO_SCORE,
     V_ASN,
     V_COUNTRY,
+    V_PROXY_ALPN,
+    V_PROXY_SNI,
+    V_PROXY_CLIENT_CERT,
+    V_PROXY_CLIENT_CN,
+    V_TLS_JA3,
+    V_TLS_JA4,
+    V_SPF_RESULT,
+    V_DKIM_RESULT,
+    V_DKIM_DOMAINS,
+    V_ARC_RESULT,
+    V_DMARC_RESULT,
+    V_DMARC_DOMAIN,
+    V_IPREV_RESULT,
 ];
-pub(crate) const SMTP_QUEUE_HOST_VARS: &[u32; 20] = &[
+pub(crate) const SMTP_QUEUE_HOST_VARS: &[u32; 21] = &[
     V_SENDER,
     V_SENDER_DOMAIN,
     V_RECIPIENT_DOMAIN,
@@ -113,6 +143,7 @@ pub(crate) const SMTP_QUEUE_HOST_VARS: &[u32; 20] = &[
     V_QUEUE_EXPIRES_IN,
     V_QUEUE_LAST_STATUS,
     V_QUEUE_LAST_ERROR,
+    V_QUEUE_BOUNCE_CATEGORY,
     V_QUEUE_NAME,
     V_QUEUE_AGE,
     V_RECEIVED_FROM_IP,
@@ -120,7 +151,7 @@ pub(crate) const SMTP_QUEUE_HOST_VARS: &[u32; 20] = &[
     V_SOURCE,
     V_SIZE,
 ];
-pub(crate) const SMTP_QUEUE_RCPT_VARS: &[u32; 17] = &[
+pub(crate) const SMTP_QUEUE_RCPT_VARS: &[u32; 18] = &[
     V_RECIPIENT,
     V_RECIPIENT_DOMAIN,
     V_RECIPIENTS,
@@ -132,6 +163,7 @@ pub(crate) const SMTP_QUEUE_RCPT_VARS: &[u32; 17] = &[
     V_QUEUE_EXPIRES_IN,
     V_QUEUE_LAST_STATUS,
     V_QUEUE_LAST_ERROR,
+    V_QUEUE_BOUNCE_CATEGORY,
     V_QUEUE_NAME,
     V_QUEUE_AGE,
     V_RECEIVED_FROM_IP,
@@ -139,7 +171,7 @@ pub(crate) const SMTP_QUEUE_RCPT_VARS: &[u32; 17] = &[
     V_SOURCE,
     V_SIZE,
 ];
-pub(crate) const SMTP_QUEUE_SENDER_VARS: &[u32; 8] = &[
+pub(crate) const SMTP_QUEUE_SENDER_VARS: &[u32; 9] = &[
     V_SENDER,
     V_SENDER_DOMAIN,
     V_PRIORITY,
@@ -148,6 +180,26 @@ pub(crate) const SMTP_QUEUE_SENDER_VARS: &[u32; 8] = &[
     V_QUEUE_EXPIRES_IN,
     V_QUEUE_LAST_STATUS,
     V_QUEUE_LAST_ERROR,
+    V_QUEUE_BOUNCE_CATEGORY,
+];
+pub(crate) const SMTP_SENDING_QUOTA_VARS: &[u32; 10] = &[
+    V_SENDER,
+    V_SENDER_DOMAIN,
+    V_RECIPIENTS,
+    V_AUTHENTICATED_AS,
+    V_TENANT_ID,
+    V_LISTENER,
+    V_REMOTE_IP,
+    V_LOCAL_IP,
+    V_PROTOCOL,
+    V_PRIORITY,
+];
+pub(crate) const SMTP_ABUSE_REPORT_VARS: &[u32; 5] = &[
+    V_SENDER,
+    V_SENDER_DOMAIN,
+    V_RECIPIENT,
+    V_RECIPIENT_DOMAIN,
+    V_REMOTE_IP,
 ];
 
 impl SmtpConfig {
@@ -13,10 +13,12 @@ use crate::{
 use ahash::AHashMap;
 use mail_auth::IpLookupStrategy;
 use mail_send::Credentials;
+use regex::{Regex, RegexBuilder};
 use std::{
     fmt::Display,
     hash::{Hash, Hasher},
     net::IpAddr,
+    path::PathBuf,
     time::Duration,
 };
 use throttle::parse_queue_rate_limiter_key;
@@ -48,6 +50,9 @@ pub struct QueueConfig {
     pub connection: IfBlock,
     pub tls: IfBlock,
 
+    // Fair-share scheduling
+    pub fair_share: FairShare,
+
     // DSN
     pub dsn: Dsn,
 
@@ -55,6 +60,7 @@ pub struct QueueConfig {
     pub inbound_limiters: QueueRateLimiters,
     pub outbound_limiters: QueueRateLimiters,
     pub quota: QueueQuotas,
+    pub sending_quotas: Vec<SendingQuota>,
 
     // Strategies
     pub queue_strategy: AHashMap<String, QueueStrategy>,
@@ -62,6 +68,35 @@ pub struct QueueConfig {
     pub routing_strategy: AHashMap<String, RoutingStrategy>,
     pub tls_strategy: AHashMap<String, TlsStrategy>,
     pub virtual_queues: AHashMap<QueueName, VirtualQueue>,
+
+    // Bounce classification
+    pub bounce_rules: Vec<BounceRule>,
+
+    // Message body storage
+    pub spool: QueueSpool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum QueueSpool {
+    // Bodies are kept in the blob store, the path is only read from
+    // when migrating messages that were spooled to disk
+    #[default]
+    BlobStore,
+    BlobStoreFromDisk {
+        path: PathBuf,
+    },
+    // Bodies are only readable by the node that spooled them, so this
+    // mode is refused when a cluster coordinator is configured
+    Disk {
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone)]
+pub struct FairShare {
+    pub key: IfBlock,
+    pub weight: IfBlock,
+    pub max_in_flight: IfBlock,
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
@@ -69,6 +104,8 @@ pub enum RoutingStrategy {
     Local,
     Mx(MxConfig),
     Relay(RelayConfig),
+    Unix(UnixConfig),
+    Pipe(PipeConfig),
 }
 
 #[derive(Clone, Debug)]
@@ -88,6 +125,16 @@ pub struct Dsn {
 #[derive(Clone, Debug)]
 pub struct VirtualQueue {
     pub threads: usize,
+    pub autoscale: Option<QueueAutoscale>,
+}
+
+#[derive(Clone, Debug)]
+pub struct QueueAutoscale {
+    pub min_threads: usize,
+    pub max_threads: usize,
+    pub scale_up_depth: usize,
+    pub scale_up_age: u64,
+    pub scale_down_after: Duration,
 }
 
 #[derive(Clone, Debug)]
@@ -137,6 +184,16 @@ pub struct ConnectionStrategy {
     pub timeout_mail: Duration,
     pub timeout_rcpt: Duration,
     pub timeout_data: Duration,
+
+    pub downgrade_8bit: bool,
+    pub downgrade_utf8: Utf8Downgrade,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Downgrade {
+    Encode,
+    Reject,
+    Disable,
 }
 
 #[derive(Clone, Debug)]
@@ -168,6 +225,49 @@ pub struct QueueQuota {
     pub messages: Option<u64>,
 }
 
+#[derive(Clone)]
+pub struct SendingQuota {
+    pub id: String,
+    pub expr: Expression,
+    pub key: IfBlock,
+    pub hourly: SendingLimit,
+    pub daily: SendingLimit,
+    pub notify: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SendingLimit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipients: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SendingQuotaOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hourly: Option<SendingLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<SendingLimit>,
+    #[serde(default)]
+    pub exempt: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BounceCategory {
+    BadAddress,
+    MailboxFull,
+    ReputationBlock,
+    Policy,
+    TransientInfra,
+}
+
+#[derive(Debug, Clone)]
+pub struct BounceRule {
+    pub category: BounceCategory,
+    pub pattern: Regex,
+}
+
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub struct RelayConfig {
     pub address: String,
@@ -178,6 +278,20 @@ pub struct RelayConfig {
     pub tls_allow_invalid_certs: bool,
 }
 
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct UnixConfig {
+    pub path: String,
+    pub protocol: ServerProtocol,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct PipeConfig {
+    pub command: String,
+    pub arguments: Vec<String>,
+    pub timeout: Duration,
+    pub temp_fail_codes: Vec<i32>,
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub enum RequireOptional {
     #[default]
@@ -221,6 +335,11 @@ impl Default for QueueConfig {
                 [],
                 "'default'",
             ),
+            fair_share: FairShare {
+                key: IfBlock::new::<()>("queue.fair-share.key", [], "sender_domain"),
+                weight: IfBlock::new::<()>("queue.fair-share.weight", [], "1"),
+                max_in_flight: IfBlock::new::<()>("queue.fair-share.max-in-flight", [], "0"),
+            },
             dsn: Dsn {
                 name: IfBlock::new::<()>("report.dsn.from-name", [], "'Mail Delivery Subsystem'"),
                 address: IfBlock::new::<()>(
@@ -237,11 +356,14 @@ impl Default for QueueConfig {
             inbound_limiters: QueueRateLimiters::default(),
             outbound_limiters: QueueRateLimiters::default(),
             quota: QueueQuotas::default(),
+            sending_quotas: Vec::new(),
             queue_strategy: Default::default(),
             virtual_queues: Default::default(),
             connection_strategy: Default::default(),
             routing_strategy: Default::default(),
             tls_strategy: Default::default(),
+            bounce_rules: Default::default(),
+            spool: Default::default(),
         }
     }
 }
@@ -269,6 +391,21 @@ impl QueueConfig {
                 &sender_vars,
             ),
             (&mut queue.dsn.sign, "report.dsn.sign", &sender_vars),
+            (
+                &mut queue.fair_share.key,
+                "queue.fair-share.key",
+                &sender_vars,
+            ),
+            (
+                &mut queue.fair_share.weight,
+                "queue.fair-share.weight",
+                &sender_vars,
+            ),
+            (
+                &mut queue.fair_share.max_in_flight,
+                "queue.fair-share.max-in-flight",
+                &sender_vars,
+            ),
         ] {
             if let Some(if_block) = IfBlock::try_parse(config, key, token_map) {
                 *value = if_block;
@@ -286,8 +423,181 @@ impl QueueConfig {
         queue.inbound_limiters = parse_inbound_rate_limiters(config);
         queue.outbound_limiters = parse_outbound_rate_limiters(config);
         queue.quota = parse_queue_quota(config);
+        queue.sending_quotas = parse_sending_quotas(config);
+
+        // Parse bounce classification rules
+        queue.bounce_rules = parse_bounce_rules(config);
+
+        // Parse message body storage
+        queue.spool = parse_queue_spool(config);
         queue
     }
+
+    // Classifies an SMTP reply using the configured rules, then the enhanced
+    // status code and finally common reply texts
+    pub fn classify_reply(&self, code: u16, esc: [u8; 3], message: &str) -> BounceCategory {
+        if !self.bounce_rules.is_empty() {
+            let reply = format!("{code} {}.{}.{} {message}", esc[0], esc[1], esc[2]);
+            if let Some(rule) = self
+                .bounce_rules
+                .iter()
+                .find(|rule| rule.pattern.is_match(&reply))
+            {
+                return rule.category;
+            }
+        }
+
+        // Enhanced status codes that identify the failure take priority
+        match esc {
+            [_, 1, _] | [5, 2, 1] => return BounceCategory::BadAddress,
+            [_, 2, 2] => return BounceCategory::MailboxFull,
+            [_, 7, _] | [_, 3, 4] => return BounceCategory::Policy,
+            _ => {}
+        }
+
+        let message = message.to_lowercase();
+        for (category, keywords) in [
+            (
+                BounceCategory::BadAddress,
+                &[
+                    "user unknown",
+                    "unknown user",
+                    "no such user",
+                    "does not exist",
+                    "invalid recipient",
+                    "recipient not found",
+                    "address rejected",
+                ][..],
+            ),
+            (
+                BounceCategory::MailboxFull,
+                &[
+                    "mailbox full",
+                    "mailbox is full",
+                    "quota",
+                    "insufficient storage",
+                ][..],
+            ),
+            (
+                BounceCategory::ReputationBlock,
+                &[
+                    "blocklist",
+                    "blacklist",
+                    "blocked",
+                    "reputation",
+                    "spamhaus",
+                    "rbl",
+                    "spam",
+                ][..],
+            ),
+        ] {
+            if keywords.iter().any(|keyword| message.contains(keyword)) {
+                return category;
+            }
+        }
+
+        match esc {
+            [4, _, _] => BounceCategory::TransientInfra,
+            [5, _, _] => BounceCategory::Policy,
+            _ if code < 500 => BounceCategory::TransientInfra,
+            _ => BounceCategory::Policy,
+        }
+    }
+}
+
+fn parse_queue_spool(config: &mut Config) -> QueueSpool {
+    let path = config
+        .value("queue.spool.path")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
+
+    match config
+        .value("queue.spool.type")
+        .unwrap_or("store")
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "store" | "blob-store" => path
+            .map(|path| QueueSpool::BlobStoreFromDisk { path })
+            .unwrap_or_default(),
+        "disk" => {
+            let Some(path) = path else {
+                config.new_parse_error(
+                    "queue.spool.path",
+                    "A spool path is required when storing queued messages on disk",
+                );
+                return QueueSpool::default();
+            };
+
+            // Any node may pick up a queued message, keep bodies in the shared blob store
+            if config.value("cluster.coordinator").is_some() {
+                config.new_build_error(
+                    "queue.spool.type",
+                    "Disk spooling is not supported in clustered deployments, using the blob store",
+                );
+                return QueueSpool::BlobStoreFromDisk { path };
+            }
+
+            if let Err(err) = std::fs::create_dir_all(&path) {
+                config.new_parse_error(
+                    "queue.spool.path",
+                    format!("Failed to create spool directory {}: {err}", path.display()),
+                );
+                QueueSpool::default()
+            } else {
+                QueueSpool::Disk { path }
+            }
+        }
+        other => {
+            let err = format!("Invalid queue spool type {other:?}");
+            config.new_parse_error("queue.spool.type", err);
+            QueueSpool::default()
+        }
+    }
+}
+
+impl QueueSpool {
+    pub fn path(&self) -> Option<&PathBuf> {
+        match self {
+            QueueSpool::BlobStore => None,
+            QueueSpool::BlobStoreFromDisk { path } | QueueSpool::Disk { path } => Some(path),
+        }
+    }
+
+    pub fn is_disk(&self) -> bool {
+        matches!(self, QueueSpool::Disk { .. })
+    }
+}
+
+fn parse_bounce_rules(config: &mut Config) -> Vec<BounceRule> {
+    let mut rules = Vec::new();
+
+    for id in config.sub_keys("queue.bounce.rule", ".category") {
+        let id = id.as_str();
+        let Some(category) =
+            config.property_require::<BounceCategory>(("queue.bounce.rule", id, "category"))
+        else {
+            continue;
+        };
+        let Some(pattern) = config
+            .value_require(("queue.bounce.rule", id, "pattern"))
+            .map(|pattern| pattern.to_string())
+        else {
+            continue;
+        };
+
+        match RegexBuilder::new(&pattern).case_insensitive(true).build() {
+            Ok(pattern) => rules.push(BounceRule { category, pattern }),
+            Err(err) => {
+                config.new_parse_error(
+                    ("queue.bounce.rule", id, "pattern"),
+                    format!("Invalid regular expression: {err}"),
+                );
+            }
+        }
+    }
+
+    rules
 }
 
 fn parse_queue_strategies(
@@ -388,10 +698,58 @@ fn parse_virtual_queues(config: &mut Config) -> AHashMap<QueueName, VirtualQueue
 }
 
 fn parse_virtual_queue(config: &mut Config, id: &str) -> Option<VirtualQueue> {
+    let threads = config
+        .property_require::<usize>(("queue.virtual", id, "threads-per-node"))
+        .unwrap_or(1);
+    let autoscale = if config
+        .property_or_default::<bool>(("queue.virtual", id, "autoscale.enable"), "false")
+        .unwrap_or_default()
+    {
+        let min_threads = config
+            .property::<usize>(("queue.virtual", id, "autoscale.min-threads"))
+            .unwrap_or(threads)
+            .max(1);
+        let max_threads = config
+            .property::<usize>(("queue.virtual", id, "autoscale.max-threads"))
+            .unwrap_or(threads * 4);
+        if max_threads < min_threads {
+            config.new_parse_error(
+                ("queue.virtual", id, "autoscale.max-threads"),
+                "Maximum threads must be greater than or equal to minimum threads.".to_string(),
+            );
+            return None;
+        }
+
+        Some(QueueAutoscale {
+            min_threads,
+            max_threads,
+            scale_up_depth: config
+                .property::<usize>(("queue.virtual", id, "autoscale.scale-up.depth"))
+                .unwrap_or(threads)
+                .max(1),
+            scale_up_age: config
+                .property_or_default::<Duration>(
+                    ("queue.virtual", id, "autoscale.scale-up.age"),
+                    "1m",
+                )
+                .unwrap_or(Duration::from_secs(60))
+                .as_secs(),
+            scale_down_after: config
+                .property_or_default::<Duration>(
+                    ("queue.virtual", id, "autoscale.scale-down.idle"),
+                    "5m",
+                )
+                .unwrap_or(Duration::from_secs(300)),
+        })
+    } else {
+        None
+    };
+
     Some(VirtualQueue {
-        threads: config
-            .property_require::<usize>(("queue.virtual", id, "threads-per-node"))
-            .unwrap_or(1),
+        threads: autoscale.as_ref().map_or(threads, |autoscale| {
+            threads.clamp(autoscale.min_threads, autoscale.max_threads)
+        }),
+        autoscale,
     })
 }
 
@@ -432,6 +790,41 @@ fn parse_route(config: &mut Config, id: &str) -> Option<RoutingStrategy> {
         })
         .into(),
         "local" => RoutingStrategy::Local.into(),
+        "unix" => RoutingStrategy::Unix(UnixConfig {
+            path: config
+                .value_require_non_empty(("queue.route", id, "path"))?
+                .to_string(),
+            protocol: config
+                .property(("queue.route", id, "protocol"))
+                .unwrap_or(ServerProtocol::Lmtp),
+        })
+        .into(),
+        "pipe" => RoutingStrategy::Pipe(PipeConfig {
+            command: config
+                .value_require_non_empty(("queue.route", id, "command"))?
+                .to_string(),
+            arguments: config
+                .values(("queue.route", id, "arguments"))
+                .map(|(_, v)| v.to_string())
+                .collect(),
+            timeout: config
+                .property_or_default(("queue.route", id, "timeout"), "5m")
+                .unwrap_or(Duration::from_secs(300)),
+            temp_fail_codes: {
+                let codes = config
+                    .properties::<i32>(("queue.route", id, "exit-codes.temp-fail"))
+                    .into_iter()
+                    .map(|(_, code)| code)
+                    .collect::<Vec<_>>();
+                if codes.is_empty() {
+                    // EX_TEMPFAIL (sysexits.h)
+                    vec![75]
+                } else {
+                    codes
+                }
+            },
+        })
+        .into(),
         "mx" => RoutingStrategy::Mx(MxConfig {
             max_mx: config
                 .property(("queue.route", id, "limits.mx"))
@@ -445,8 +838,9 @@ fn parse_route(config: &mut Config, id: &str) -> Option<RoutingStrategy> {
         })
         .into(),
         invalid => {
-            let details =
-                format!("Invalid route type: {invalid:?}. Expected 'relay', 'local', or 'mx'.");
+            let details = format!(
+                "Invalid route type: {invalid:?}. Expected 'relay', 'local', 'mx', 'unix' or 'pipe'."
+            );
             config.new_parse_error(("queue.route", id, "type"), details);
             None
         }
@@ -507,6 +901,8 @@ fn parse_connection_strategies(config: &mut Config) -> AHashMap<String, Connecti
             ".timeout.rcpt-to",
             ".timeout.data",
             ".ehlo-hostname",
+            ".downgrade.8bitmime",
+            ".downgrade.smtputf8",
         ],
     ) {
         if let Some(strategy) = parse_connection(config, &key) {
@@ -555,6 +951,12 @@ fn parse_connection(config: &mut Config, id: &str) -> Option<ConnectionStrategy>
         timeout_data: config
             .property::<Duration>(("queue.connection", id, "timeout.data"))
             .unwrap_or(Duration::from_secs(10 * 60)),
+        downgrade_8bit: config
+            .property::<bool>(("queue.connection", id, "downgrade.8bitmime"))
+            .unwrap_or(false),
+        downgrade_utf8: config
+            .property::<Utf8Downgrade>(("queue.connection", id, "downgrade.smtputf8"))
+            .unwrap_or(Utf8Downgrade::Disable),
     })
 }
 
@@ -750,6 +1152,66 @@ fn parse_queue_quota_item(config: &mut Config, prefix: impl AsKey, id: &str) ->
     }
 }
 
+fn parse_sending_quotas(config: &mut Config) -> Vec<SendingQuota> {
+    let token_map = TokenMap::default().with_variables(SMTP_SENDING_QUOTA_VARS);
+    let mut quotas = Vec::new();
+
+    for quota_id in config.sub_keys("queue.sending-quota", "") {
+        let prefix = ("queue.sending-quota", quota_id.as_str()).as_key();
+
+        // Skip disabled quotas
+        if !config
+            .property::<bool>((prefix.as_str(), "enable"))
+            .unwrap_or(true)
+        {
+            continue;
+        }
+
+        let mut parse_limit = |period: &str| SendingLimit {
+            messages: config
+                .property::<Option<u64>>((prefix.as_str(), period, "messages"))
+                .filter(|&v| v.as_ref().is_some_and(|v| *v > 0))
+                .unwrap_or_default(),
+            recipients: config
+                .property::<Option<u64>>((prefix.as_str(), period, "recipients"))
+                .filter(|&v| v.as_ref().is_some_and(|v| *v > 0))
+                .unwrap_or_default(),
+        };
+        let hourly = parse_limit("hourly");
+        let daily = parse_limit("daily");
+
+        // Validate
+        if hourly == SendingLimit::default() && daily == SendingLimit::default() {
+            config.new_parse_error(
+                prefix.as_str(),
+                concat!(
+                    "Sending quota needs to define a valid 'hourly' ",
+                    "and/or 'daily' message or recipient limit."
+                )
+                .to_string(),
+            );
+            continue;
+        }
+
+        quotas.push(SendingQuota {
+            expr: Expression::try_parse(config, (prefix.as_str(), "match"), &token_map)
+                .unwrap_or_default(),
+            key: IfBlock::try_parse(config, (prefix.as_str(), "key"), &token_map).unwrap_or_else(
+                || IfBlock::new::<()>(format!("{prefix}.key"), [], "sender_domain"),
+            ),
+            hourly,
+            daily,
+            notify: config
+                .property_or_default::<u64>((prefix.as_str(), "notify"), "80")
+                .unwrap_or(80)
+                .min(100),
+            id: quota_id,
+        });
+    }
+
+    quotas
+}
+
 impl ParseValue for RequireOptional {
     fn parse_value(value: &str) -> Result<Self, String> {
         match value {
@@ -761,6 +1223,48 @@ impl ParseValue for RequireOptional {
     }
 }
 
+impl ParseValue for BounceCategory {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "bad-address" => Ok(BounceCategory::BadAddress),
+            "mailbox-full" => Ok(BounceCategory::MailboxFull),
+            "reputation-block" => Ok(BounceCategory::ReputationBlock),
+            "policy" => Ok(BounceCategory::Policy),
+            "transient-infra" => Ok(BounceCategory::TransientInfra),
+            _ => Err(format!("Invalid bounce category {:?}.", value,)),
+        }
+    }
+}
+
+impl BounceCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BounceCategory::BadAddress => "bad-address",
+            BounceCategory::MailboxFull => "mailbox-full",
+            BounceCategory::ReputationBlock => "reputation-block",
+            BounceCategory::Policy => "policy",
+            BounceCategory::TransientInfra => "transient-infra",
+        }
+    }
+}
+
+impl Display for BounceCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ParseValue for Utf8Downgrade {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "encode" => Ok(Utf8Downgrade::Encode),
+            "reject" => Ok(Utf8Downgrade::Reject),
+            "disable" | "disabled" | "none" | "false" => Ok(Utf8Downgrade::Disable),
+            _ => Err(format!("Invalid SMTPUTF8 downgrade option {:?}.", value,)),
+        }
+    }
+}
+
 impl<'x> TryFrom<Variable<'x>> for RequireOptional {
     type Error = ();
 
@@ -978,3 +1482,174 @@ impl AsRef<[u8]> for QueueName {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use utils::config::Config;
+
+    use super::{BounceCategory, QueueConfig, QueueSpool, parse_bounce_rules, parse_queue_spool};
+
+    #[test]
+    fn disk_spool_requires_single_node() {
+        let path = std::env::temp_dir().join("stalwart_queue_spool_test");
+        let toml = format!(
+            "[queue.spool]\ntype = \"disk\"\npath = {:?}\n",
+            path.to_string_lossy()
+        );
+
+        let mut config = Config::new(&toml).unwrap();
+        assert_eq!(
+            parse_queue_spool(&mut config),
+            QueueSpool::Disk { path: path.clone() }
+        );
+        assert!(config.errors.is_empty(), "{:?}", config.errors);
+
+        // Clustered nodes keep reading old spool files but store new bodies in the blob store
+        let mut config =
+            Config::new(format!("{toml}\n[cluster]\ncoordinator = \"redis\"\n")).unwrap();
+        assert_eq!(
+            parse_queue_spool(&mut config),
+            QueueSpool::BlobStoreFromDisk { path: path.clone() }
+        );
+        assert!(config.errors.contains_key("queue.spool.type"));
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn classify_bounce_replies() {
+        let config = QueueConfig::default();
+
+        // Reply texts are matched when the status code is not specific
+        for (category, messages) in [
+            (
+                BounceCategory::BadAddress,
+                &[
+                    "User unknown",
+                    "Unknown user",
+                    "No such user here",
+                    "Mailbox does not exist",
+                    "Invalid recipient",
+                    "Recipient not found",
+                    "Recipient address rejected",
+                ][..],
+            ),
+            (
+                BounceCategory::MailboxFull,
+                &[
+                    "Mailbox full",
+                    "Mailbox is full",
+                    "Quota exceeded",
+                    "Insufficient storage",
+                ][..],
+            ),
+            (
+                BounceCategory::ReputationBlock,
+                &[
+                    "Listed on blocklist",
+                    "Listed on blacklist",
+                    "Message blocked",
+                    "Poor sender reputation",
+                    "See https://www.spamhaus.org",
+                    "Rejected by RBL",
+                    "Looks like spam",
+                ][..],
+            ),
+        ] {
+            for message in messages {
+                for (code, esc) in [(550, [5, 0, 0]), (450, [4, 0, 0]), (554, [0, 0, 0])] {
+                    assert_eq!(
+                        config.classify_reply(code, esc, message),
+                        category,
+                        "{code} {esc:?} {message}"
+                    );
+                }
+            }
+        }
+
+        // Unknown reply texts fall back to the status code class
+        for (code, esc, category) in [
+            (550, [5, 0, 0], BounceCategory::Policy),
+            (554, [5, 5, 0], BounceCategory::Policy),
+            (451, [4, 0, 0], BounceCategory::TransientInfra),
+            (421, [4, 4, 2], BounceCategory::TransientInfra),
+            (450, [0, 0, 0], BounceCategory::TransientInfra),
+            (550, [0, 0, 0], BounceCategory::Policy),
+        ] {
+            assert_eq!(
+                config.classify_reply(code, esc, "Something went wrong"),
+                category,
+                "{code} {esc:?}"
+            );
+        }
+
+        // Enhanced status codes take priority over the reply text
+        for (code, esc, message, category) in [
+            (550, [5, 1, 1], "Mailbox full", BounceCategory::BadAddress),
+            (550, [5, 2, 1], "Blocked", BounceCategory::BadAddress),
+            (452, [4, 2, 2], "User unknown", BounceCategory::MailboxFull),
+            (550, [5, 7, 1], "Listed on spamhaus", BounceCategory::Policy),
+            (552, [5, 3, 4], "Quota exceeded", BounceCategory::Policy),
+        ] {
+            assert_eq!(
+                config.classify_reply(code, esc, message),
+                category,
+                "{code} {esc:?} {message}"
+            );
+        }
+    }
+
+    #[test]
+    fn classify_bounce_rules() {
+        let mut config = Config::new(
+            r#"
+[queue.bounce.rule."spamhaus"]
+category = "reputation-block"
+pattern = "^550 5\\.7\\.1 .*spamhaus"
+
+[queue.bounce.rule."over-quota"]
+category = "mailbox-full"
+pattern = "over quota"
+
+[queue.bounce.rule."invalid"]
+category = "mailbox-full"
+pattern = "(unclosed"
+"#,
+        )
+        .unwrap();
+        let queue = QueueConfig {
+            bounce_rules: parse_bounce_rules(&mut config),
+            ..Default::default()
+        };
+        assert_eq!(queue.bounce_rules.len(), 2);
+        assert!(
+            config
+                .errors
+                .contains_key("queue.bounce.rule.invalid.pattern")
+        );
+
+        // Configured rules take priority and are matched case-insensitively
+        for (code, esc, message, category) in [
+            (
+                550,
+                [5, 7, 1],
+                "Listed on SPAMHAUS",
+                BounceCategory::ReputationBlock,
+            ),
+            (550, [5, 7, 1], "Not authorized", BounceCategory::Policy),
+            (
+                550,
+                [5, 1, 1],
+                "User is Over Quota",
+                BounceCategory::MailboxFull,
+            ),
+            (550, [5, 1, 1], "User unknown", BounceCategory::BadAddress),
+        ] {
+            assert_eq!(
+                queue.classify_reply(code, esc, message),
+                category,
+                "{code} {esc:?} {message}"
+            );
+        }
+    }
+}
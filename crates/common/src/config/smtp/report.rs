@@ -20,6 +20,8 @@ pub struct ReportConfig {
     pub dkim: Report,
     pub spf: Report,
     pub dmarc: Report,
+    pub dmarc_privacy: FailureReportPrivacy,
+    pub abuse: AbuseReport,
     pub dmarc_aggregate: AggregateReport,
     pub tls: AggregateReport,
 }
@@ -29,6 +31,15 @@ pub struct ReportAnalysis {
     pub addresses: Vec<AddressMatch>,
     pub forward: bool,
     pub store: Option<Duration>,
+    pub feedback: FeedbackLoop,
+}
+
+#[derive(Clone)]
+pub struct FeedbackLoop {
+    pub trusted_sources: Vec<String>,
+    pub suppress: Option<Duration>,
+    pub max_complaints: u64,
+    pub window: Duration,
 }
 
 #[derive(Clone)]
@@ -58,6 +69,22 @@ pub struct Report {
     pub send: IfBlock,
 }
 
+#[derive(Clone)]
+pub struct FailureReportPrivacy {
+    pub strip_body: bool,
+    pub strip_subject: bool,
+    pub hash_recipients: bool,
+}
+
+#[derive(Clone)]
+pub struct AbuseReport {
+    pub name: IfBlock,
+    pub address: IfBlock,
+    pub subject: IfBlock,
+    pub sign: IfBlock,
+    pub to: IfBlock,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum AggregateFrequency {
     Hourly,
@@ -91,10 +118,38 @@ impl ReportConfig {
                 store: config
                     .property_or_default::<Option<Duration>>("report.analysis.store", "30d")
                     .unwrap_or_default(),
+                feedback: FeedbackLoop {
+                    trusted_sources: config
+                        .values("report.analysis.feedback.trusted-sources")
+                        .map(|(_, domain)| domain.trim().to_lowercase())
+                        .filter(|domain| !domain.is_empty())
+                        .collect(),
+                    suppress: config
+                        .property::<Option<Duration>>("report.analysis.feedback.suppress")
+                        .unwrap_or_default(),
+                    max_complaints: config
+                        .property_or_default("report.analysis.feedback.max-complaints", "0")
+                        .unwrap_or_default(),
+                    window: config
+                        .property_or_default("report.analysis.feedback.window", "1d")
+                        .unwrap_or_else(|| Duration::from_secs(86400)),
+                },
             },
             dkim: Report::parse(config, "dkim", &rcpt_vars),
             spf: Report::parse(config, "spf", &sender_vars),
             dmarc: Report::parse(config, "dmarc", &rcpt_vars),
+            dmarc_privacy: FailureReportPrivacy {
+                strip_body: config
+                    .property_or_default("report.dmarc.privacy.strip-body", "true")
+                    .unwrap_or(true),
+                strip_subject: config
+                    .property_or_default("report.dmarc.privacy.strip-subject", "false")
+                    .unwrap_or(false),
+                hash_recipients: config
+                    .property_or_default("report.dmarc.privacy.hash-recipients", "false")
+                    .unwrap_or(false),
+            },
+            abuse: AbuseReport::parse(config),
             dmarc_aggregate: AggregateReport::parse(
                 config,
                 "dmarc",
@@ -151,6 +206,42 @@ impl Report {
     }
 }
 
+impl AbuseReport {
+    pub fn parse(config: &mut Config) -> Self {
+        let token_map = TokenMap::default().with_variables(SMTP_ABUSE_REPORT_VARS);
+        let mut report = Self {
+            name: IfBlock::new::<()>("report.abuse.from-name", [], "'Report Subsystem'"),
+            address: IfBlock::new::<()>(
+                "report.abuse.from-address",
+                [],
+                "'noreply-abuse@' + config_get('report.domain')",
+            ),
+            subject: IfBlock::new::<()>("report.abuse.subject", [], "'Abuse Report'"),
+            sign: IfBlock::new::<()>(
+                "report.abuse.sign",
+                [],
+                "['rsa-' + config_get('report.domain'), 'ed25519-' + config_get('report.domain')]",
+            ),
+            // Reports are not sent unless recipients are configured, for example
+            // ['abuse@' + sender_domain]
+            to: IfBlock::empty("report.abuse.to"),
+        };
+        for (value, key) in [
+            (&mut report.name, "from-name"),
+            (&mut report.address, "from-address"),
+            (&mut report.subject, "subject"),
+            (&mut report.sign, "sign"),
+            (&mut report.to, "to"),
+        ] {
+            if let Some(if_block) = IfBlock::try_parse(config, ("report.abuse", key), &token_map) {
+                *value = if_block;
+            }
+        }
+
+        report
+    }
+}
+
 impl AggregateReport {
     pub fn parse(config: &mut Config, id: &str, token_map: &TokenMap) -> Self {
         let rcpt_vars = TokenMap::default().with_variables(RCPT_DOMAIN_VARS);
@@ -10,7 +10,7 @@ use std::{
     time::Duration,
 };
 
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use base64::{Engine, engine::general_purpose::STANDARD};
 
 use hyper::{
@@ -18,7 +18,10 @@ use hyper::{
     header::{AUTHORIZATION, CONTENT_TYPE, HeaderName, HeaderValue},
 };
 use smtp_proto::*;
-use utils::config::{Config, utils::ParseValue};
+use utils::{
+    cache::CacheItemWeight,
+    config::{Config, utils::ParseValue},
+};
 
 use crate::{
     config::CONNECTION_VARS,
@@ -43,9 +46,12 @@ pub struct SessionConfig {
     pub data: Data,
     pub extensions: Extensions,
     pub mta_sts_policy: Option<Policy>,
+    pub transcript: Transcript,
 
     pub milters: Vec<Milter>,
     pub hooks: Vec<MTAHook>,
+    pub icap: Vec<IcapService>,
+    pub icap_upload: IcapUpload,
 }
 
 #[derive(Clone)]
@@ -53,6 +59,17 @@ pub struct Connect {
     pub hostname: IfBlock,
     pub script: IfBlock,
     pub greeting: IfBlock,
+    pub fingerprint_allow: AHashSet<String>,
+    pub fingerprint_deny: AHashSet<String>,
+}
+
+#[derive(Clone)]
+pub struct Transcript {
+    pub enable: IfBlock,
+    pub max_body_size: usize,
+    pub max_size: usize,
+    pub redact: bool,
+    pub retention: Duration,
 }
 
 #[derive(Clone)]
@@ -60,8 +77,37 @@ pub struct Ehlo {
     pub script: IfBlock,
     pub require: IfBlock,
     pub reject_non_fqdn: IfBlock,
+    pub validate: IfBlock,
+    pub validation: EhloValidation,
+}
+
+#[derive(Clone, Debug)]
+pub struct EhloValidation {
+    pub score_not_fqdn: f64,
+    pub score_bare_ip: f64,
+    pub score_ip_mismatch: f64,
+    pub score_no_address: f64,
+    pub score_address_mismatch: f64,
+    pub score_ptr_mismatch: f64,
+    pub score_no_fcrdns: f64,
+    pub reject_score: Option<f64>,
+    pub cache_ttl: Duration,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EhloValidationResult {
+    pub checks: u8,
+    pub score: f64,
+}
+
+pub const HELO_NOT_FQDN: u8 = 1 << 0;
+pub const HELO_BARE_IP: u8 = 1 << 1;
+pub const HELO_IP_MISMATCH: u8 = 1 << 2;
+pub const HELO_NO_ADDRESS: u8 = 1 << 3;
+pub const HELO_ADDRESS_MISMATCH: u8 = 1 << 4;
+pub const HELO_PTR_MISMATCH: u8 = 1 << 5;
+pub const HELO_NO_FCRDNS: u8 = 1 << 6;
+
 #[derive(Clone)]
 pub struct Extensions {
     pub pipelining: IfBlock,
@@ -91,6 +137,15 @@ pub struct Mail {
     pub script: IfBlock,
     pub rewrite: IfBlock,
     pub is_allowed: IfBlock,
+    pub reject_non_sending: IfBlock,
+    pub non_sending_cache_ttl: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderDomainPolicy {
+    Sending,
+    NullMx,
+    SpfRejectAll,
 }
 
 #[derive(Clone)]
@@ -106,10 +161,20 @@ pub struct Rcpt {
 
     // Limits
     pub max_recipients: IfBlock,
+    pub max_expansion_depth: IfBlock,
 
     // Catch-all and sub-addressing
     pub catch_all: AddressMapping,
     pub subaddressing: AddressMapping,
+
+    // Directories that fail are skipped until they pass a health check
+    pub health_check: Option<DirectoryHealthCheck>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DirectoryHealthCheck {
+    pub interval: Duration,
+    pub max_failures: u32,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -129,6 +194,7 @@ pub struct Data {
     pub max_messages: IfBlock,
     pub max_message_size: IfBlock,
     pub max_received_headers: IfBlock,
+    pub quota_check: IfBlock,
 
     // Headers
     pub add_received: IfBlock,
@@ -138,6 +204,25 @@ pub struct Data {
     pub add_message_id: IfBlock,
     pub add_date: IfBlock,
     pub add_delivered_to: bool,
+
+    // Tracing
+    pub trace: IfBlock,
+    pub trace_retention: Duration,
+
+    // Shadow delivery
+    pub shadow_rcpt: IfBlock,
+    pub shadow_rate: f64,
+
+    // Header privacy
+    pub privacy: IfBlock,
+    pub privacy_remove_headers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaCheck {
+    Disable,
+    Defer,
+    Reject,
 }
 
 #[derive(Clone)]
@@ -158,6 +243,7 @@ pub struct Milter {
     pub flags_actions: Option<u32>,
     pub flags_protocol: Option<u32>,
     pub run_on_stage: AHashSet<Stage>,
+    pub macros: AHashMap<MilterMacroStage, Vec<String>>,
 }
 
 #[derive(Clone, Copy)]
@@ -166,6 +252,17 @@ pub enum MilterVersion {
     V6,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MilterMacroStage {
+    Connect,
+    Helo,
+    Mail,
+    Rcpt,
+    Data,
+    EndOfHeaders,
+    EndOfMessage,
+}
+
 #[derive(Clone)]
 pub struct MTAHook {
     pub enable: IfBlock,
@@ -179,6 +276,48 @@ pub struct MTAHook {
     pub max_response_size: usize,
 }
 
+#[derive(Clone)]
+pub struct IcapService {
+    pub enable: IfBlock,
+    pub id: Arc<String>,
+    pub addrs: Vec<SocketAddr>,
+    pub hostname: String,
+    pub port: u16,
+    pub service: String,
+    pub tls: bool,
+    pub tls_allow_invalid_certs: bool,
+    pub timeout_connect: Duration,
+    pub timeout_data: Duration,
+    pub preview: Option<usize>,
+    pub max_message_size: usize,
+    pub max_response_size: usize,
+    pub action_threat: IcapAction,
+    pub action_modified: IcapAction,
+    pub fail_open: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct IcapUpload {
+    pub jmap: Option<IcapUploadPolicy>,
+    pub dav: Option<IcapUploadPolicy>,
+}
+
+#[derive(Clone)]
+pub struct IcapUploadPolicy {
+    pub services: Vec<usize>,
+    pub max_size: usize,
+    pub oversize_fail_open: bool,
+    pub quarantine: Option<Duration>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IcapAction {
+    Accept,
+    Reject,
+    Discard,
+    Quarantine,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Stage {
     Connect,
@@ -197,10 +336,21 @@ impl SessionConfig {
         let has_rcpt_vars = TokenMap::default().with_variables(SMTP_RCPT_TO_VARS);
         let mt_priority_vars = has_sender_vars.clone().with_constants::<MtPriority>();
         let mechanisms_vars = has_ehlo_hars.clone().with_constants::<Mechanism>();
+        let quota_check_vars = has_rcpt_vars.clone().with_constants::<QuotaCheck>();
 
         let mut session = SessionConfig::default();
         session.rcpt.catch_all = AddressMapping::parse(config, "session.rcpt.catch-all");
         session.rcpt.subaddressing = AddressMapping::parse(config, "session.rcpt.sub-addressing");
+        session.rcpt.health_check = config
+            .property_or_default::<Option<Duration>>("session.rcpt.health-check.interval", "false")
+            .unwrap_or_default()
+            .map(|interval| DirectoryHealthCheck {
+                interval,
+                max_failures: config
+                    .property_or_default::<u32>("session.rcpt.health-check.failures", "3")
+                    .unwrap_or(3)
+                    .max(1),
+            });
         session.milters = config
             .sub_keys("session.milter", ".hostname")
             .into_iter()
@@ -211,7 +361,24 @@ impl SessionConfig {
             .into_iter()
             .filter_map(|id| parse_hooks(config, &id, &has_rcpt_vars))
             .collect();
+        session.icap = config
+            .sub_keys("session.icap", ".url")
+            .into_iter()
+            .filter_map(|id| parse_icap(config, &id, &has_rcpt_vars))
+            .collect();
+        session.icap_upload = IcapUpload {
+            jmap: parse_icap_upload(config, "jmap", &session.icap),
+            dav: parse_icap_upload(config, "dav", &session.icap),
+        };
         session.mta_sts_policy = Policy::try_parse(config);
+        session.connect.fingerprint_allow = config
+            .values("session.connect.tls-fingerprint.allow")
+            .map(|(_, value)| value.trim().to_lowercase())
+            .collect();
+        session.connect.fingerprint_deny = config
+            .values("session.connect.tls-fingerprint.deny")
+            .map(|(_, value)| value.trim().to_lowercase())
+            .collect();
 
         for (value, key, token_map) in [
             (&mut session.duration, "session.duration", &has_conn_vars),
@@ -236,6 +403,11 @@ impl SessionConfig {
                 "session.connect.greeting",
                 &has_conn_vars,
             ),
+            (
+                &mut session.transcript.enable,
+                "session.transcript.enable",
+                &has_conn_vars,
+            ),
             (
                 &mut session.extensions.pipelining,
                 "session.extensions.pipelining",
@@ -301,6 +473,11 @@ impl SessionConfig {
                 "session.ehlo.reject-non-fqdn",
                 &has_conn_vars,
             ),
+            (
+                &mut session.ehlo.validate,
+                "session.ehlo.validation.enable",
+                &has_conn_vars,
+            ),
             (
                 &mut session.auth.directory,
                 "session.auth.directory",
@@ -346,6 +523,11 @@ impl SessionConfig {
                 "session.mail.is-allowed",
                 &has_sender_vars,
             ),
+            (
+                &mut session.mail.reject_non_sending,
+                "session.mail.non-sending-domain.reject",
+                &has_sender_vars,
+            ),
             (
                 &mut session.rcpt.script,
                 "session.rcpt.script",
@@ -376,6 +558,11 @@ impl SessionConfig {
                 "session.rcpt.max-recipients",
                 &has_sender_vars,
             ),
+            (
+                &mut session.rcpt.max_expansion_depth,
+                "session.rcpt.max-expansion-depth",
+                &has_sender_vars,
+            ),
             (
                 &mut session.rcpt.rewrite,
                 "session.rcpt.rewrite",
@@ -401,6 +588,11 @@ impl SessionConfig {
                 "session.data.limits.received-headers",
                 &has_rcpt_vars,
             ),
+            (
+                &mut session.data.quota_check,
+                "session.data.limits.quota",
+                &quota_check_vars,
+            ),
             (
                 &mut session.data.spam_filter,
                 "session.data.spam-filter",
@@ -436,6 +628,21 @@ impl SessionConfig {
                 "session.data.add-headers.date",
                 &has_rcpt_vars,
             ),
+            (
+                &mut session.data.trace,
+                "session.data.trace.enable",
+                &has_rcpt_vars,
+            ),
+            (
+                &mut session.data.shadow_rcpt,
+                "session.data.shadow.recipient",
+                &has_rcpt_vars,
+            ),
+            (
+                &mut session.data.privacy,
+                "session.data.privacy.enable",
+                &has_rcpt_vars,
+            ),
         ] {
             if let Some(if_block) = IfBlock::try_parse(config, key, token_map) {
                 *value = if_block;
@@ -444,10 +651,125 @@ impl SessionConfig {
         session.data.add_delivered_to = config
             .property_or_default("session.data.add-headers.delivered-to", "true")
             .unwrap_or(true);
+        session.transcript.max_body_size = config
+            .property_or_default("session.transcript.max-body-size", "1024")
+            .unwrap_or(1024);
+        session.transcript.max_size = config
+            .property_or_default("session.transcript.max-size", "1048576")
+            .unwrap_or(1048576);
+        session.transcript.redact = config
+            .property_or_default("session.transcript.redact", "true")
+            .unwrap_or(true);
+        session.transcript.retention = config
+            .property_or_default("session.transcript.retention", "1d")
+            .unwrap_or(Duration::from_secs(86400));
+        session.data.trace_retention = config
+            .property_or_default("session.data.trace.retention", "7d")
+            .unwrap_or(Duration::from_secs(7 * 86400));
+        session.data.shadow_rate = config
+            .property_or_default::<f64>("session.data.shadow.rate", "100")
+            .unwrap_or(100.0)
+            .clamp(0.0, 100.0);
+        let remove_headers = config
+            .values("session.data.privacy.remove-headers")
+            .map(|(_, name)| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        if !remove_headers.is_empty() {
+            session.data.privacy_remove_headers = remove_headers;
+        }
+        session.mail.non_sending_cache_ttl = config
+            .property_or_default("session.mail.non-sending-domain.cache-ttl", "1h")
+            .unwrap_or_else(|| Duration::from_secs(3600));
+        session.ehlo.validation = EhloValidation::parse(config);
         session
     }
 }
 
+impl EhloValidation {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut score = |check: &str, default: &str| {
+            config
+                .property_or_default::<f64>(("session.ehlo.validation.score", check), default)
+                .unwrap_or_default()
+        };
+
+        EhloValidation {
+            score_not_fqdn: score("not-fqdn", "3.0"),
+            score_bare_ip: score("bare-ip", "2.0"),
+            score_ip_mismatch: score("ip-mismatch", "3.0"),
+            score_no_address: score("no-address", "2.0"),
+            score_address_mismatch: score("address-mismatch", "1.0"),
+            score_ptr_mismatch: score("ptr-mismatch", "0.5"),
+            score_no_fcrdns: score("no-fcrdns", "1.5"),
+            reject_score: config.property("session.ehlo.validation.reject-score"),
+            cache_ttl: config
+                .property_or_default("session.ehlo.validation.cache-ttl", "1h")
+                .unwrap_or_else(|| Duration::from_secs(3600)),
+        }
+    }
+
+    pub fn score(&self, checks: u8) -> f64 {
+        [
+            (HELO_NOT_FQDN, self.score_not_fqdn),
+            (HELO_BARE_IP, self.score_bare_ip),
+            (HELO_IP_MISMATCH, self.score_ip_mismatch),
+            (HELO_NO_ADDRESS, self.score_no_address),
+            (HELO_ADDRESS_MISMATCH, self.score_address_mismatch),
+            (HELO_PTR_MISMATCH, self.score_ptr_mismatch),
+            (HELO_NO_FCRDNS, self.score_no_fcrdns),
+        ]
+        .into_iter()
+        .filter(|(check, _)| checks & check != 0)
+        .map(|(_, score)| score)
+        .sum()
+    }
+}
+
+impl EhloValidationResult {
+    pub fn has(&self, check: u8) -> bool {
+        self.checks & check != 0
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (HELO_NOT_FQDN, "not-fqdn"),
+            (HELO_BARE_IP, "bare-ip"),
+            (HELO_IP_MISMATCH, "ip-mismatch"),
+            (HELO_NO_ADDRESS, "no-address"),
+            (HELO_ADDRESS_MISMATCH, "address-mismatch"),
+            (HELO_PTR_MISMATCH, "ptr-mismatch"),
+            (HELO_NO_FCRDNS, "no-fcrdns"),
+        ]
+        .into_iter()
+        .filter(|(check, _)| self.has(*check))
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+impl CacheItemWeight for EhloValidationResult {
+    fn weight(&self) -> u64 {
+        std::mem::size_of::<EhloValidationResult>() as u64
+    }
+}
+
+impl SenderDomainPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SenderDomainPolicy::Sending => "sending",
+            SenderDomainPolicy::NullMx => "null-mx",
+            SenderDomainPolicy::SpfRejectAll => "spf-reject-all",
+        }
+    }
+}
+
+impl CacheItemWeight for SenderDomainPolicy {
+    fn weight(&self) -> u64 {
+        std::mem::size_of::<SenderDomainPolicy>() as u64
+    }
+}
+
 fn parse_milter(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<Milter> {
     let hostname = config
         .value_require(("session.milter", id, "hostname"))?
@@ -512,9 +834,43 @@ fn parse_milter(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<M
         flags_actions: config.property(("session.milter", id, "options.flags.actions")),
         flags_protocol: config.property(("session.milter", id, "options.flags.protocol")),
         run_on_stage: parse_stages(config, "session.milter", id),
+        macros: parse_milter_macros(config, id),
     })
 }
 
+fn parse_milter_macros(config: &mut Config, id: &str) -> AHashMap<MilterMacroStage, Vec<String>> {
+    let mut macros = AHashMap::new();
+
+    for (stage, name) in [
+        (MilterMacroStage::Connect, "connect"),
+        (MilterMacroStage::Helo, "helo"),
+        (MilterMacroStage::Mail, "mail"),
+        (MilterMacroStage::Rcpt, "rcpt"),
+        (MilterMacroStage::Data, "data"),
+        (MilterMacroStage::EndOfHeaders, "eoh"),
+        (MilterMacroStage::EndOfMessage, "eom"),
+    ] {
+        let names = config
+            .values(("session.milter", id, "macros", name))
+            .map(|(_, value)| {
+                // Single character macros are sent as-is, long names within braces
+                let value = value.trim().trim_start_matches('{').trim_end_matches('}');
+                if value.len() == 1 {
+                    value.to_string()
+                } else {
+                    format!("{{{value}}}")
+                }
+            })
+            .filter(|value| value.len() > 2 || !value.starts_with('{'))
+            .collect::<Vec<_>>();
+        if !names.is_empty() {
+            macros.insert(stage, names);
+        }
+    }
+
+    macros
+}
+
 fn parse_hooks(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<MTAHook> {
     let mut headers = HeaderMap::new();
 
@@ -589,6 +945,165 @@ fn parse_hooks(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<MT
     })
 }
 
+fn parse_icap(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<IcapService> {
+    // Parse icap[s]://hostname[:port]/service
+    let url = config
+        .value_require(("session.icap", id, "url"))?
+        .to_string();
+    let Some((tls, hostname, port, service)) = url
+        .split_once("://")
+        .and_then(|(scheme, rest)| {
+            let tls = match scheme.to_ascii_lowercase().as_str() {
+                "icap" => false,
+                "icaps" => true,
+                _ => return None,
+            };
+            let (authority, service) = rest.split_once('/').unwrap_or((rest, ""));
+            let (hostname, port) = match authority.rsplit_once(':') {
+                Some((hostname, port)) => (hostname, port.parse::<u16>().ok()?),
+                None => (authority, if tls { 11344 } else { 1344 }),
+            };
+            Some((tls, hostname.to_string(), port, format!("/{service}")))
+        })
+        .filter(|(_, hostname, _, _)| !hostname.is_empty())
+    else {
+        config.new_parse_error(
+            ("session.icap", id, "url"),
+            format!("Invalid ICAP URL {url:?}"),
+        );
+        return None;
+    };
+
+    Some(IcapService {
+        enable: IfBlock::try_parse(config, ("session.icap", id, "enable"), token_map)
+            .unwrap_or_else(|| {
+                IfBlock::new::<()>(format!("session.icap.{id}.enable"), [], "false")
+            }),
+        id: Arc::new(id.into()),
+        addrs: format!("{}:{}", hostname, port)
+            .to_socket_addrs()
+            .map_err(|err| {
+                config.new_build_error(
+                    ("session.icap", id, "url"),
+                    format!("Unable to resolve ICAP hostname {hostname}: {err}"),
+                )
+            })
+            .ok()?
+            .collect(),
+        hostname,
+        port,
+        service,
+        tls,
+        tls_allow_invalid_certs: config
+            .property_or_default(("session.icap", id, "allow-invalid-certs"), "false")
+            .unwrap_or_default(),
+        timeout_connect: config
+            .property_or_default(("session.icap", id, "timeout.connect"), "30s")
+            .unwrap_or_else(|| Duration::from_secs(30)),
+        timeout_data: config
+            .property_or_default(("session.icap", id, "timeout.data"), "60s")
+            .unwrap_or_else(|| Duration::from_secs(60)),
+        preview: config.property(("session.icap", id, "options.preview")),
+        max_message_size: config
+            .property_or_default(("session.icap", id, "options.max-message-size"), "52428800")
+            .unwrap_or(52428800),
+        max_response_size: config
+            .property_or_default(("session.icap", id, "options.max-response-size"), "65536")
+            .unwrap_or(65536),
+        action_threat: config
+            .property_or_default(("session.icap", id, "action.threat"), "reject")
+            .unwrap_or(IcapAction::Reject),
+        action_modified: config
+            .property_or_default(("session.icap", id, "action.modified"), "reject")
+            .unwrap_or(IcapAction::Reject),
+        fail_open: match config
+            .value(("session.icap", id, "failure-policy"))
+            .unwrap_or("fail-closed")
+        {
+            "fail-open" => true,
+            "fail-closed" => false,
+            other => {
+                let other = other.to_string();
+                config.new_parse_error(
+                    ("session.icap", id, "failure-policy"),
+                    format!("Invalid failure policy {other:?}"),
+                );
+                false
+            }
+        },
+    })
+}
+
+fn parse_icap_upload(
+    config: &mut Config,
+    source: &str,
+    services: &[IcapService],
+) -> Option<IcapUploadPolicy> {
+    let ids = config
+        .values(("upload.scan", source, "services"))
+        .map(|(_, id)| id.to_string())
+        .collect::<Vec<_>>();
+    let mut policy = IcapUploadPolicy {
+        services: Vec::with_capacity(ids.len()),
+        max_size: config
+            .property_or_default(("upload.scan", source, "max-size"), "52428800")
+            .unwrap_or(52428800),
+        oversize_fail_open: match config
+            .value(("upload.scan", source, "oversize-policy"))
+            .unwrap_or("fail-closed")
+        {
+            "fail-open" => true,
+            "fail-closed" => false,
+            other => {
+                let other = other.to_string();
+                config.new_parse_error(
+                    ("upload.scan", source, "oversize-policy"),
+                    format!("Invalid oversize policy {other:?}"),
+                );
+                false
+            }
+        },
+        quarantine: if config
+            .property_or_default(("upload.scan", source, "quarantine.enable"), "true")
+            .unwrap_or(true)
+        {
+            config
+                .property_or_default(("upload.scan", source, "quarantine.retention"), "30d")
+                .or_else(|| Some(Duration::from_secs(30 * 86400)))
+        } else {
+            None
+        },
+    };
+
+    for id in ids {
+        if let Some(idx) = services
+            .iter()
+            .position(|service| service.id.as_str() == id)
+        {
+            policy.services.push(idx);
+        } else {
+            config.new_parse_error(
+                ("upload.scan", source, "services"),
+                format!("ICAP service {id:?} does not exist"),
+            );
+        }
+    }
+
+    (!policy.services.is_empty()).then_some(policy)
+}
+
+impl ParseValue for IcapAction {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "accept" => Ok(IcapAction::Accept),
+            "reject" => Ok(IcapAction::Reject),
+            "discard" => Ok(IcapAction::Discard),
+            "quarantine" => Ok(IcapAction::Quarantine),
+            _ => Err(format!("Invalid ICAP action {value:?}")),
+        }
+    }
+}
+
 fn parse_stages(config: &mut Config, prefix: &str, id: &str) -> AHashSet<Stage> {
     let mut stages = AHashSet::default();
     let mut invalid = Vec::new();
@@ -641,6 +1156,8 @@ impl Default for SessionConfig {
                     [],
                     "config_get('server.hostname') + ' Stalwart ESMTP at your service'",
                 ),
+                fingerprint_allow: AHashSet::new(),
+                fingerprint_deny: AHashSet::new(),
             },
             ehlo: Ehlo {
                 script: IfBlock::empty("session.ehlo.script"),
@@ -650,6 +1167,18 @@ impl Default for SessionConfig {
                     [("local_port == 25", "true")],
                     "false",
                 ),
+                validate: IfBlock::new::<()>("session.ehlo.validation.enable", [], "false"),
+                validation: EhloValidation {
+                    score_not_fqdn: 3.0,
+                    score_bare_ip: 2.0,
+                    score_ip_mismatch: 3.0,
+                    score_no_address: 2.0,
+                    score_address_mismatch: 1.0,
+                    score_ptr_mismatch: 0.5,
+                    score_no_fcrdns: 1.5,
+                    reject_score: None,
+                    cache_ttl: Duration::from_secs(3600),
+                },
             },
             auth: Auth {
                 directory: IfBlock::new::<()>(
@@ -691,6 +1220,12 @@ impl Default for SessionConfig {
                     [],
                     "!is_empty(authenticated_as) || !key_exists('blocked-domains', sender_domain)",
                 ),
+                reject_non_sending: IfBlock::new::<()>(
+                    "session.mail.non-sending-domain.reject",
+                    [],
+                    "false",
+                ),
+                non_sending_cache_ttl: Duration::from_secs(3600),
             },
             rcpt: Rcpt {
                 script: IfBlock::empty("session.rcpt.script"),
@@ -711,8 +1246,14 @@ impl Default for SessionConfig {
                 errors_max: IfBlock::new::<()>("session.rcpt.errors.total", [], "5"),
                 errors_wait: IfBlock::new::<()>("session.rcpt.errors.wait", [], "5s"),
                 max_recipients: IfBlock::new::<()>("session.rcpt.max-recipients", [], "100"),
+                max_expansion_depth: IfBlock::new::<()>(
+                    "session.rcpt.max-expansion-depth",
+                    [],
+                    "5",
+                ),
                 catch_all: AddressMapping::Enable,
                 subaddressing: AddressMapping::Enable,
+                health_check: None,
             },
             data: Data {
                 script: IfBlock::empty("session.data.script"),
@@ -724,6 +1265,7 @@ impl Default for SessionConfig {
                     [],
                     "50",
                 ),
+                quota_check: IfBlock::new::<QuotaCheck>("session.data.limits.quota", [], "disable"),
                 add_received: IfBlock::new::<()>(
                     "session.data.add-headers.received",
                     [("local_port == 25", "true")],
@@ -755,6 +1297,25 @@ impl Default for SessionConfig {
                     "false",
                 ),
                 add_delivered_to: false,
+                trace: IfBlock::new::<()>(
+                    "session.data.trace.enable",
+                    [("!is_empty(authenticated_as)", "true")],
+                    "false",
+                ),
+                trace_retention: Duration::from_secs(7 * 86400),
+                shadow_rcpt: IfBlock::empty("session.data.shadow.recipient"),
+                shadow_rate: 100.0,
+                privacy: IfBlock::new::<()>("session.data.privacy.enable", [], "false"),
+                privacy_remove_headers: [
+                    "User-Agent",
+                    "X-Mailer",
+                    "X-Originating-IP",
+                    "X-Forwarded-For",
+                    "X-Real-IP",
+                ]
+                .into_iter()
+                .map(String::from)
+                .collect(),
             },
             extensions: Extensions {
                 pipelining: IfBlock::new::<()>("session.extensions.pipelining", [], "true"),
@@ -793,8 +1354,17 @@ impl Default for SessionConfig {
                 ),
             },
             mta_sts_policy: None,
+            transcript: Transcript {
+                enable: IfBlock::new::<()>("session.transcript.enable", [], "false"),
+                max_body_size: 1024,
+                max_size: 1048576,
+                redact: true,
+                retention: Duration::from_secs(86400),
+            },
             milters: Default::default(),
             hooks: Default::default(),
+            icap: Default::default(),
+            icap_upload: Default::default(),
         }
     }
 }
@@ -809,6 +1379,7 @@ impl ParseValue for Mechanism {
             "PLAIN" => AUTH_PLAIN,
             "XOAUTH2" => AUTH_XOAUTH2,
             "OAUTHBEARER" => AUTH_OAUTHBEARER,
+            "EXTERNAL" => AUTH_EXTERNAL,
             /*"SCRAM-SHA-256-PLUS" => AUTH_SCRAM_SHA_256_PLUS,
             "SCRAM-SHA-256" => AUTH_SCRAM_SHA_256,
             "SCRAM-SHA-1-PLUS" => AUTH_SCRAM_SHA_1_PLUS,
@@ -824,7 +1395,6 @@ impl ParseValue for Mechanism {
             "EAP-AES128-PLUS" => AUTH_EAP_AES128_PLUS,
             "ECDH-X25519-CHALLENGE" => AUTH_ECDH_X25519_CHALLENGE,
             "ECDSA-NIST256P-CHALLENGE" => AUTH_ECDSA_NIST256P_CHALLENGE,
-            "EXTERNAL" => AUTH_EXTERNAL,
             "GS2-KRB5" => AUTH_GS2_KRB5,
             "GS2-KRB5-PLUS" => AUTH_GS2_KRB5_PLUS,
             "GSS-SPNEGO" => AUTH_GSS_SPNEGO,
@@ -887,7 +1457,8 @@ impl ConstantValue for Mechanism {
             .add_constant("login", Mechanism(AUTH_LOGIN))
             .add_constant("plain", Mechanism(AUTH_PLAIN))
             .add_constant("xoauth2", Mechanism(AUTH_XOAUTH2))
-            .add_constant("oauthbearer", Mechanism(AUTH_OAUTHBEARER));
+            .add_constant("oauthbearer", Mechanism(AUTH_OAUTHBEARER))
+            .add_constant("external", Mechanism(AUTH_EXTERNAL));
     }
 }
 
@@ -938,3 +1509,50 @@ impl ConstantValue for MtPriority {
             .add_constant("nsep", MtPriority::Nsep);
     }
 }
+
+impl<'x> TryFrom<Variable<'x>> for QuotaCheck {
+    type Error = ();
+
+    fn try_from(value: Variable<'x>) -> Result<Self, Self::Error> {
+        match value {
+            Variable::Integer(value) => match value {
+                0 => Ok(QuotaCheck::Disable),
+                1 => Ok(QuotaCheck::Defer),
+                2 => Ok(QuotaCheck::Reject),
+                _ => Err(()),
+            },
+            Variable::String(value) => QuotaCheck::parse_value(value.as_str()).map_err(|_| ()),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<QuotaCheck> for Constant {
+    fn from(value: QuotaCheck) -> Self {
+        Constant::Integer(match value {
+            QuotaCheck::Disable => 0,
+            QuotaCheck::Defer => 1,
+            QuotaCheck::Reject => 2,
+        })
+    }
+}
+
+impl ParseValue for QuotaCheck {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "disable" | "disabled" | "false" => Ok(QuotaCheck::Disable),
+            "defer" | "tempfail" => Ok(QuotaCheck::Defer),
+            "reject" => Ok(QuotaCheck::Reject),
+            _ => Err(format!("Invalid quota check action {:?}.", value)),
+        }
+    }
+}
+
+impl ConstantValue for QuotaCheck {
+    fn add_constants(token_map: &mut TokenMap) {
+        token_map
+            .add_constant("disable", QuotaCheck::Disable)
+            .add_constant("defer", QuotaCheck::Defer)
+            .add_constant("reject", QuotaCheck::Reject);
+    }
+}
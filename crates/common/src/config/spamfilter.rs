@@ -16,7 +16,7 @@ use tokio::net::lookup_host;
 use utils::{
     cache::CacheItemWeight,
     config::{Config, utils::ParseValue},
-    glob::GlobMap,
+    glob::{GlobMap, GlobSet},
 };
 
 use super::{Variable, functions::ResolveVariable, if_block::IfBlock, tokenizer::TokenMap};
@@ -29,11 +29,14 @@ pub struct SpamFilterConfig {
     pub rules: SpamFilterRules,
     pub lists: SpamFilterLists,
     pub pyzor: Option<PyzorConfig>,
+    pub fuzzy: Option<FuzzyConfig>,
     pub reputation: Option<ReputationConfig>,
+    pub sender_reputation: Option<SenderReputationConfig>,
     pub bayes: Option<BayesConfig>,
     pub scores: SpamFilterScoreConfig,
     pub expiry: SpamFilterExpiryConfig,
     pub headers: SpamFilterHeaderConfig,
+    pub trusted_arc_sealers: GlobSet,
 }
 
 #[derive(Debug, Clone)]
@@ -105,6 +108,18 @@ pub struct ReputationConfig {
     pub sender_weight: f64,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct SenderReputationConfig {
+    pub expiry: u64,
+    pub half_life: u64,
+    pub min_count: f64,
+    pub feedback_weight: f64,
+    pub delivery_weight: f64,
+    pub good_threshold: f64,
+    pub bad_threshold: f64,
+    pub score: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct PyzorConfig {
     pub address: SocketAddr,
@@ -114,6 +129,24 @@ pub struct PyzorConfig {
     pub ratio: f64,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct FuzzyConfig {
+    pub expiry: u64,
+    pub min_similarity: f64,
+    pub min_reports: u32,
+    pub min_length: usize,
+    pub auto_learn: bool,
+    pub remote: Option<FuzzyRemoteConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzyRemoteConfig {
+    pub url: String,
+    pub auth: Option<String>,
+    pub timeout: Duration,
+    pub report: bool,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct SpamFilterRules {
     pub url: Vec<IfBlock>,
@@ -187,13 +220,27 @@ impl SpamFilterConfig {
             rules: SpamFilterRules::parse(config),
             lists: SpamFilterLists::parse(config),
             pyzor: PyzorConfig::parse(config).await,
+            fuzzy: FuzzyConfig::parse(config),
             reputation: ReputationConfig::parse(config),
+            sender_reputation: SenderReputationConfig::parse(config),
             bayes: BayesConfig::parse(config),
             scores: SpamFilterScoreConfig::parse(config),
             expiry: SpamFilterExpiryConfig::parse(config),
             headers: SpamFilterHeaderConfig::parse(config),
+            trusted_arc_sealers: parse_trusted_arc_sealers(config),
+        }
+    }
+}
+
+fn parse_trusted_arc_sealers(config: &mut Config) -> GlobSet {
+    let mut sealers = GlobSet::new();
+    for (_, domain) in config.values("spam-filter.arc.trusted-sealers") {
+        let domain = domain.trim().to_lowercase();
+        if !domain.is_empty() {
+            sealers.insert(&domain);
         }
     }
+    sealers
 }
 
 impl SpamFilterRules {
@@ -415,6 +462,17 @@ impl SpamFilterLists {
             config.new_parse_error(key, error);
         }
 
+        // Tags added by built-in modules score by default unless a rule set overrides them
+        for (tag, score) in [
+            ("FUZZY_SPAM_HIGH", 5.0),
+            ("FUZZY_SPAM_MEDIUM", 3.5),
+            ("FUZZY_SPAM_LOW", 1.5),
+        ] {
+            if lists.scores.get(tag).is_none() {
+                lists.scores.insert(tag, SpamFilterAction::Allow(score));
+            }
+        }
+
         lists
     }
 }
@@ -474,6 +532,53 @@ impl PyzorConfig {
     }
 }
 
+impl FuzzyConfig {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        if !config
+            .property_or_default("spam-filter.fuzzy.enable", "false")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        FuzzyConfig {
+            expiry: config
+                .property_or_default::<Duration>("spam-filter.fuzzy.expiry", "7d")
+                .map(|d| d.as_secs())
+                .unwrap_or(604800),
+            min_similarity: config
+                .property_or_default::<f64>("spam-filter.fuzzy.similarity", "0.85")
+                .unwrap_or(0.85)
+                .clamp(0.5, 1.0),
+            min_reports: config
+                .property_or_default("spam-filter.fuzzy.reports", "1")
+                .unwrap_or(1),
+            min_length: config
+                .property_or_default("spam-filter.fuzzy.min-length", "64")
+                .unwrap_or(64),
+            auto_learn: config
+                .property_or_default("spam-filter.fuzzy.auto-learn", "true")
+                .unwrap_or(true),
+            remote: config
+                .value("spam-filter.fuzzy.remote.url")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .map(|url| FuzzyRemoteConfig {
+                    url,
+                    auth: config
+                        .value("spam-filter.fuzzy.remote.auth.token")
+                        .map(|token| format!("Bearer {token}")),
+                    timeout: config
+                        .property_or_default("spam-filter.fuzzy.remote.timeout", "5s")
+                        .unwrap_or_else(|| Duration::from_secs(5)),
+                    report: config
+                        .property_or_default("spam-filter.fuzzy.remote.report", "false")
+                        .unwrap_or(false),
+                }),
+        }
+        .into()
+    }
+}
+
 impl ReputationConfig {
     pub fn parse(config: &mut Config) -> Option<Self> {
         if !config
@@ -511,6 +616,47 @@ impl ReputationConfig {
     }
 }
 
+impl SenderReputationConfig {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        if !config
+            .property_or_default("spam-filter.sender-reputation.enable", "false")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        SenderReputationConfig {
+            expiry: config
+                .property_or_default::<Duration>("spam-filter.sender-reputation.expiry", "180d")
+                .map(|d| d.as_secs())
+                .unwrap_or(15552000),
+            half_life: config
+                .property_or_default::<Duration>("spam-filter.sender-reputation.half-life", "30d")
+                .map(|d| d.as_secs().max(1))
+                .unwrap_or(2592000),
+            min_count: config
+                .property_or_default("spam-filter.sender-reputation.min-count", "5")
+                .unwrap_or(5.0),
+            feedback_weight: config
+                .property_or_default("spam-filter.sender-reputation.weight.feedback", "1.0")
+                .unwrap_or(1.0),
+            delivery_weight: config
+                .property_or_default("spam-filter.sender-reputation.weight.delivery", "0.1")
+                .unwrap_or(0.1),
+            good_threshold: config
+                .property_or_default("spam-filter.sender-reputation.threshold.good", "0.5")
+                .unwrap_or(0.5),
+            bad_threshold: config
+                .property_or_default("spam-filter.sender-reputation.threshold.bad", "-0.5")
+                .unwrap_or(-0.5),
+            score: config
+                .property_or_default("spam-filter.sender-reputation.score", "3.0")
+                .unwrap_or(3.0),
+        }
+        .into()
+    }
+}
+
 impl BayesConfig {
     pub fn parse(config: &mut Config) -> Option<Self> {
         if !config
@@ -696,6 +842,8 @@ pub const V_SPAM_SUBJECT_THREAD: u32 = 136;
 pub const V_SPAM_LOCATION: u32 = 137;
 pub const V_WORDS_SUBJECT: u32 = 138;
 pub const V_WORDS_BODY: u32 = 139;
+pub const V_SPAM_SENDER_IDENTITY: u32 = 140;
+pub const V_SPAM_SENDER_REPUTATION: u32 = 141;
 
 pub const V_RCPT_EMAIL: u32 = 0;
 pub const V_RCPT_NAME: u32 = 1;
@@ -770,6 +918,8 @@ impl Element {
             ("subject.thread", V_SPAM_SUBJECT_THREAD),
             ("subject.words", V_WORDS_SUBJECT),
             ("location", V_SPAM_LOCATION),
+            ("sender.identity", V_SPAM_SENDER_IDENTITY),
+            ("sender.reputation", V_SPAM_SENDER_REPUTATION),
         ]);
 
         match self {
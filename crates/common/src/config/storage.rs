@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use ahash::AHashMap;
 use directory::Directory;
@@ -23,9 +23,19 @@ pub struct Storage {
     pub directories: AHashMap<String, Arc<Directory>>,
     pub purge_schedules: Vec<PurgeSchedule>,
     pub config: ConfigManager,
+    pub degraded: Vec<&'static str>,
+    pub degraded_retry: Duration,
 
     pub stores: AHashMap<String, Store>,
     pub blobs: AHashMap<String, BlobStore>,
     pub lookups: AHashMap<String, InMemoryStore>,
     pub ftss: AHashMap<String, FtsStore>,
 }
+
+impl Storage {
+    pub fn clear_unknown_principals(&self) {
+        for directory in std::iter::once(&self.directory).chain(self.directories.values()) {
+            directory.clear_unknown_cache();
+        }
+    }
+}
@@ -5,6 +5,9 @@
  */
 
 use super::parse_http_headers;
+use crate::telemetry::metrics::labels::LabeledMetric;
+#[cfg(unix)]
+use crate::telemetry::tracers::journald::PriorityMappings;
 use ahash::{AHashMap, AHashSet};
 use base64::{Engine, engine::general_purpose::STANDARD};
 use hyper::{HeaderMap, header::CONTENT_TYPE};
@@ -21,7 +24,7 @@ use opentelemetry_semantic_conventions::resource::SERVICE_VERSION;
 use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
 use store::Stores;
 use trc::{EventType, Level, TelemetryEvent, ipc::subscriber::Interests};
-use utils::config::{Config, utils::ParseValue};
+use utils::config::{Config, cron::SimpleCron, utils::ParseValue};
 
 #[derive(Debug)]
 pub struct TelemetrySubscriber {
@@ -38,6 +41,7 @@ pub enum TelemetrySubscriberType {
     LogTracer(LogTracer),
     OtelTracer(OtelTracer),
     Webhook(WebhookTracer),
+    MemoryTracer(MemoryTracer),
     #[cfg(unix)]
     JournalTracer(crate::telemetry::tracers::journald::Subscriber),
 }
@@ -75,6 +79,11 @@ pub struct LogTracer {
     pub multiline: bool,
 }
 
+#[derive(Debug)]
+pub struct MemoryTracer {
+    pub capacity: usize,
+}
+
 #[derive(Debug)]
 pub struct WebhookTracer {
     pub url: String,
@@ -108,16 +117,41 @@ pub struct Tracers {
     pub subscribers: Vec<TelemetrySubscriber>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct Metrics {
     pub prometheus: Option<PrometheusMetrics>,
     pub otel: Option<Arc<OtelMetrics>>,
     pub log_path: Option<String>,
+    pub calculate_interval: Duration,
+    pub count_interval: Duration,
+    pub usage_report: Option<UsageReportConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageReportConfig {
+    pub frequency: SimpleCron,
+    pub top_accounts: usize,
+    pub from_address: Option<String>,
+    pub rcpt_to: Vec<String>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct PrometheusMetrics {
     pub auth: Option<String>,
+    pub labels: PrometheusLabels,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrometheusLabels {
+    pub domain: Option<LabelDimension>,
+    pub tenant: Option<LabelDimension>,
+    pub max_cardinality: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LabelDimension {
+    pub metrics: AHashSet<LabeledMetric>,
+    pub allow: AHashSet<String>,
 }
 
 impl Telemetry {
@@ -242,6 +276,25 @@ impl Tracers {
                         continue;
                     }
                 }
+                "memory" => {
+                    if !tracers
+                        .iter()
+                        .any(|t| matches!(t.typ, TelemetrySubscriberType::MemoryTracer(_)))
+                    {
+                        TelemetrySubscriberType::MemoryTracer(MemoryTracer {
+                            capacity: config
+                                .property_or_default::<usize>(("tracer", id, "capacity"), "10000")
+                                .unwrap_or(10000)
+                                .clamp(100, 1_000_000),
+                        })
+                    } else {
+                        config.new_build_error(
+                            ("tracer", id, "type"),
+                            "Only one memory tracer is allowed".to_string(),
+                        );
+                        continue;
+                    }
+                }
                 "otel" | "open-telemetry" => {
                     let timeout = config
                         .property::<Duration>(("tracer", id, "timeout"))
@@ -394,8 +447,32 @@ impl Tracers {
                             .any(|t| matches!(t.typ, TelemetrySubscriberType::JournalTracer(_)))
                         {
                             match crate::telemetry::tracers::journald::Subscriber::new() {
-                                Ok(subscriber) => {
-                                    TelemetrySubscriberType::JournalTracer(subscriber)
+                                Ok(mut subscriber) => {
+                                    if let Some(identifier) =
+                                        config.value(("tracer", id, "identifier"))
+                                    {
+                                        subscriber = subscriber
+                                            .with_syslog_identifier(identifier.to_string());
+                                    }
+
+                                    let mut mappings = PriorityMappings::new();
+                                    for (level, priority) in [
+                                        ("error", &mut mappings.error),
+                                        ("warn", &mut mappings.warn),
+                                        ("info", &mut mappings.info),
+                                        ("debug", &mut mappings.debug),
+                                        ("trace", &mut mappings.trace),
+                                    ] {
+                                        if let Some(value) =
+                                            config.property(("tracer", id, "priority", level))
+                                        {
+                                            *priority = value;
+                                        }
+                                    }
+
+                                    TelemetrySubscriberType::JournalTracer(
+                                        subscriber.with_priority_mappings(mappings),
+                                    )
                                 }
                                 Err(e) => {
                                     config.new_build_error(
@@ -454,7 +531,8 @@ impl Tracers {
 
             // Parse disabled events
             let exclude_event = match &tracer.typ {
-                TelemetrySubscriberType::ConsoleTracer(_) => None,
+                TelemetrySubscriberType::ConsoleTracer(_)
+                | TelemetrySubscriberType::MemoryTracer(_) => None,
                 TelemetrySubscriberType::LogTracer(_) => {
                     EventType::Telemetry(TelemetryEvent::LogError).into()
                 }
@@ -539,14 +617,86 @@ impl Tracers {
     }
 }
 
+impl LabelDimension {
+    fn parse(config: &mut Config, label: &str) -> Option<Self> {
+        let metrics = config
+            .properties::<LabeledMetric>(("metrics.prometheus.labels", label, "metrics"))
+            .into_iter()
+            .map(|(_, metric)| metric)
+            .collect::<AHashSet<_>>();
+
+        if !metrics.is_empty() {
+            Some(LabelDimension {
+                metrics,
+                allow: config
+                    .values(("metrics.prometheus.labels", label, "allow"))
+                    .map(|(_, value)| value.to_lowercase())
+                    .collect(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            prometheus: None,
+            otel: None,
+            log_path: None,
+            calculate_interval: Duration::from_secs(5 * 60),
+            count_interval: Duration::from_secs(86400),
+            usage_report: None,
+        }
+    }
+}
+
 impl Metrics {
     pub fn parse(config: &mut Config) -> Self {
         let mut metrics = Metrics {
             prometheus: None,
             otel: None,
             log_path: None,
+            calculate_interval: config
+                .property_or_default::<Duration>("metrics.calculate.interval", "5m")
+                .unwrap_or_else(|| Duration::from_secs(5 * 60))
+                .max(Duration::from_secs(1)),
+            count_interval: config
+                .property_or_default::<Duration>("metrics.calculate.count-interval", "1d")
+                .unwrap_or_else(|| Duration::from_secs(86400))
+                .max(Duration::from_secs(60)),
+            usage_report: None,
         };
 
+        // Usage report
+        if config
+            .property_or_default("metrics.usage-report.enable", "true")
+            .unwrap_or(true)
+        {
+            metrics.usage_report = Some(UsageReportConfig {
+                frequency: config
+                    .property_or_default::<SimpleCron>("metrics.usage-report.frequency", "0 3 *")
+                    .unwrap_or_else(|| SimpleCron::parse_value("0 3 *").unwrap()),
+                top_accounts: config
+                    .property_or_default("metrics.usage-report.top-accounts", "10")
+                    .unwrap_or(10),
+                from_address: config
+                    .value("metrics.usage-report.from-address")
+                    .map(|addr| addr.trim().to_lowercase()),
+                rcpt_to: config
+                    .values("metrics.usage-report.send-to")
+                    .filter_map(|(_, addr)| {
+                        if addr.contains('@') && addr.contains('.') {
+                            Some(addr.trim().to_lowercase())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect(),
+            });
+        }
+
         // Obtain log path
         for tracer_id in config.sub_keys("tracer", ".type") {
             let tracer_id = tracer_id.as_str();
@@ -579,6 +729,13 @@ impl Metrics {
                             .value("metrics.prometheus.auth.secret")
                             .map(|secret| STANDARD.encode(format!("{user}:{secret}")))
                     }),
+                labels: PrometheusLabels {
+                    domain: LabelDimension::parse(config, "domain"),
+                    tenant: LabelDimension::parse(config, "tenant"),
+                    max_cardinality: config
+                        .property_or_default("metrics.prometheus.labels.max-cardinality", "100")
+                        .unwrap_or(100),
+                },
             });
         }
 
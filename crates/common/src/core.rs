@@ -7,14 +7,19 @@
 use crate::{
     Inner, Server,
     auth::{AccessToken, ResourceToken, TenantInfo},
-    config::smtp::{
-        auth::{ArcSealer, DkimSigner, LazySignature, ResolvedSignature, build_signature},
-        queue::{
-            ConnectionStrategy, DEFAULT_QUEUE_NAME, MxConfig, QueueExpiry, QueueName,
-            QueueStrategy, RequireOptional, RoutingStrategy, TlsStrategy, VirtualQueue,
+    config::{
+        network::Branding,
+        smtp::{
+            auth::{ArcSealer, DkimSigner, LazySignature, ResolvedSignature, build_signature},
+            queue::{
+                ConnectionStrategy, DEFAULT_QUEUE_NAME, MxConfig, QueueExpiry, QueueName,
+                QueueStrategy, RequireOptional, RoutingStrategy, TlsStrategy, Utf8Downgrade,
+                VirtualQueue,
+            },
         },
     },
     ipc::{BroadcastEvent, StateEvent},
+    storage::dumpster::DumpsterEntry,
 };
 use directory::{Directory, QueryParams, Type, backend::internal::manage::ManageDirectory};
 use mail_auth::IpLookupStrategy;
@@ -37,8 +42,8 @@ use trc::AddContext;
 use types::{
     blob::{BlobClass, BlobId},
     blob_hash::BlobHash,
-    collection::{Collection, SyncCollection},
-    field::{EmailField, Field},
+    collection::{Collection, SyncCollection, VanishedCollection},
+    field::{EmailField, Field, PrincipalField},
     type_state::{DataType, StateChange},
 };
 
@@ -217,7 +222,10 @@ impl Server {
     }
 
     pub fn get_virtual_queue_or_default(&self, name: &QueueName) -> &VirtualQueue {
-        static DEFAULT_QUEUE: VirtualQueue = VirtualQueue { threads: 25 };
+        static DEFAULT_QUEUE: VirtualQueue = VirtualQueue {
+            threads: 25,
+            autoscale: None,
+        };
         self.core
             .smtp
             .queue
@@ -312,6 +320,8 @@ impl Server {
             timeout_mail: Duration::from_secs(5 * 60),
             timeout_rcpt: Duration::from_secs(5 * 60),
             timeout_data: Duration::from_secs(10 * 60),
+            downgrade_8bit: false,
+            downgrade_utf8: Utf8Downgrade::Disable,
         };
 
         self.core
@@ -379,6 +389,36 @@ impl Server {
             .await
             .caused_by(trc::location!())?;
 
+        // Exclude deleted items that no longer count towards the quota
+        self.store()
+            .iterate(
+                IterateParams::new(
+                    ValueKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        document_id: 0,
+                        class: ValueClass::Property(EmailField::Dumpster.into()),
+                    },
+                    ValueKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        document_id: u32::MAX,
+                        class: ValueClass::Property(EmailField::Dumpster.into()),
+                    },
+                )
+                .ascending(),
+                |_, value| {
+                    let entry = DumpsterEntry::deserialize(value)?;
+                    if entry.quota_released {
+                        quota -= entry.size as i64;
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
         let mut batch = BatchBuilder::new();
         batch
             .clear(DirectoryClass::UsedQuota(account_id))
@@ -566,6 +606,11 @@ impl Server {
         self.inner.ipc.task_tx.notify_one();
     }
 
+    #[inline(always)]
+    pub fn notify_job_manager(&self) {
+        self.inner.ipc.job_tx.notify_one();
+    }
+
     pub async fn total_queued_messages(&self) -> trc::Result<u64> {
         let mut total = 0;
         self.store()
@@ -625,6 +670,106 @@ impl Server {
         Ok(assigned_ids)
     }
 
+    pub async fn compact_vanished(&self, account_id: u32, retention: Duration) -> trc::Result<()> {
+        // Change ids are not time based, so periodic checkpoints mapping
+        // timestamps to change ids are used to find the retention horizon
+        let checkpoint_key = ValueKey {
+            account_id,
+            collection: Collection::Principal.into(),
+            document_id: 0,
+            class: ValueClass::Property(PrincipalField::VanishedCheckpoints.into()),
+        };
+        let mut checkpoints = self
+            .store()
+            .get_value::<VanishedCheckpoints>(checkpoint_key)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+            .0;
+
+        // Obtain the most recent change id
+        let mut last_change_id = None;
+        for vanished_collection in [
+            VanishedCollection::Email,
+            VanishedCollection::Calendar,
+            VanishedCollection::AddressBook,
+            VanishedCollection::FileNode,
+        ] {
+            if let Some(change_id) = self
+                .store()
+                .get_last_change_id(account_id, vanished_collection.into())
+                .await
+                .caused_by(trc::location!())?
+            {
+                last_change_id = Some(last_change_id.unwrap_or(0).max(change_id));
+            }
+        }
+
+        // Add a new checkpoint at most once a day
+        let now = now();
+        let mut has_changes = false;
+        if let Some(change_id) = last_change_id
+            && checkpoints.last().is_none_or(|(timestamp, last_id)| {
+                *timestamp + 86400 <= now && *last_id < change_id
+            })
+        {
+            checkpoints.push((now, change_id));
+            has_changes = true;
+        }
+
+        // Purge entries older than the newest expired checkpoint
+        let horizon = now.saturating_sub(retention.as_secs());
+        if let Some(pos) = checkpoints
+            .iter()
+            .rposition(|(timestamp, _)| *timestamp <= horizon)
+        {
+            let cutoff_change_id = checkpoints[pos].1;
+            for vanished_collection in [
+                VanishedCollection::Email,
+                VanishedCollection::Calendar,
+                VanishedCollection::AddressBook,
+                VanishedCollection::FileNode,
+            ] {
+                let collection = u8::from(vanished_collection);
+                self.store()
+                    .delete_range(
+                        LogKey {
+                            account_id,
+                            collection,
+                            change_id: 0,
+                        },
+                        LogKey {
+                            account_id,
+                            collection,
+                            change_id: cutoff_change_id,
+                        },
+                    )
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+            checkpoints.drain(..=pos);
+            has_changes = true;
+        }
+
+        if has_changes {
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Principal)
+                .update_document(0)
+                .set(
+                    PrincipalField::VanishedCheckpoints,
+                    VanishedCheckpoints(checkpoints).serialize(),
+                );
+            self.store()
+                .write(batch.build_all())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+
     pub async fn delete_changes(&self, account_id: u32, max_entries: usize) -> trc::Result<()> {
         for sync_collection in [
             SyncCollection::Email,
@@ -683,9 +828,11 @@ impl Server {
                     .await
                     .caused_by(trc::location!())?;
 
-                // Delete vanished items
-                if let Some(vanished_collection) =
-                    sync_collection.vanished_collection().map(u8::from)
+                // Delete vanished items, unless they are retained separately
+                if let Some(vanished_collection) = sync_collection
+                    .vanished_collection()
+                    .filter(|_| self.core.jmap.changes_vanished_retention.is_none())
+                    .map(u8::from)
                 {
                     self.store()
                         .delete_range(
@@ -842,12 +989,40 @@ impl Server {
             .caused_by(trc::location!())
     }
 
+    pub fn branding(&self, domain: &str) -> Option<&Branding> {
+        self.core.network.branding.get(domain).or_else(|| {
+            // Fall back to the branding of the parent domain
+            domain
+                .split_once('.')
+                .and_then(|(_, parent)| self.core.network.branding.get(parent))
+        })
+    }
+
+    pub async fn branding_logo(
+        &self,
+        domain: &str,
+    ) -> trc::Result<Option<crate::manager::webadmin::Resource<Vec<u8>>>> {
+        let Some(logo) = self.branding(domain).and_then(|b| b.logo.as_ref()) else {
+            return Ok(None);
+        };
+
+        self.blob_store()
+            .get_blob(logo.hash.as_slice(), 0..usize::MAX)
+            .await
+            .caused_by(trc::location!())
+            .map(|contents| {
+                contents.map(|contents| {
+                    crate::manager::webadmin::Resource::new(logo.content_type.clone(), contents)
+                })
+            })
+    }
+
     #[cfg(not(feature = "enterprise"))]
     pub async fn logo_resource(
         &self,
-        _: &str,
+        domain: &str,
     ) -> trc::Result<Option<crate::manager::webadmin::Resource<Vec<u8>>>> {
-        Ok(None)
+        self.branding_logo(domain).await
     }
 }
 
@@ -863,3 +1038,37 @@ impl BuildServer for Arc<Inner> {
         }
     }
 }
+
+#[derive(Default)]
+struct VanishedCheckpoints(Vec<(u64, u64)>);
+
+impl VanishedCheckpoints {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.len() * U64_LEN * 2);
+        for (timestamp, change_id) in &self.0 {
+            bytes.extend_from_slice(&timestamp.to_be_bytes());
+            bytes.extend_from_slice(&change_id.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+impl Deserialize for VanishedCheckpoints {
+    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
+        if bytes.len() % (U64_LEN * 2) != 0 {
+            return Err(trc::StoreEvent::DataCorruption.caused_by(trc::location!()));
+        }
+
+        Ok(VanishedCheckpoints(
+            bytes
+                .chunks_exact(U64_LEN * 2)
+                .map(|chunk| {
+                    (
+                        u64::from_be_bytes(chunk[..U64_LEN].try_into().unwrap()),
+                        u64::from_be_bytes(chunk[U64_LEN..].try_into().unwrap()),
+                    )
+                })
+                .collect(),
+        ))
+    }
+}
@@ -6,7 +6,10 @@
 
 use std::net::IpAddr;
 
-use mail_auth::{Error, IpLookupStrategy};
+use mail_auth::{
+    Error, IpLookupStrategy,
+    hickory_resolver::{Name, proto::rr::RecordType},
+};
 
 use crate::Server;
 
@@ -95,4 +98,39 @@ impl Server {
             Err(err) => Err(err.into()),
         }
     }
+
+    pub async fn dns_lookup_records(
+        &self,
+        entry: &str,
+        record_type: RecordType,
+    ) -> trc::Result<Vec<String>> {
+        let name = Name::from_str_relaxed(entry).map_err(Error::from)?;
+        match self
+            .core
+            .smtp
+            .resolvers
+            .dnssec
+            .resolver
+            .lookup(name, record_type)
+            .await
+        {
+            Ok(result) => Ok(result
+                .record_iter()
+                .filter(|record| record.record_type() == record_type)
+                .map(|record| {
+                    if let Some(txt) = record.data().as_txt() {
+                        txt.iter()
+                            .map(|part| String::from_utf8_lossy(part))
+                            .collect::<String>()
+                    } else {
+                        record.data().to_string()
+                    }
+                })
+                .collect()),
+            Err(err) => match Error::from(err) {
+                Error::DnsRecordNotFound(_) => Ok(vec![]),
+                err => Err(err.into()),
+            },
+        }
+    }
 }
@@ -0,0 +1,446 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{borrow::Cow, ops::Bound};
+
+use utils::config::Config;
+
+use super::functions::{ASYNC_FUNCTIONS, FUNCTIONS};
+
+const FUNCTION_PREFIX: &str = "expression.function.";
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+// User-defined functions are declared as:
+//
+// [expression.function.is_free_mail_domain]
+// args = ["domain"]
+// expr = "key_exists('free-domains', domain)"
+//
+// Functions without arguments act as includes of a shared expression. Calls are
+// expanded inline before tokenization, so they are re-evaluated on every reload.
+struct CustomFunction {
+    args: Vec<String>,
+    expr: String,
+}
+
+pub fn expand_custom_functions<'x>(config: &Config, expr: &'x str) -> Result<Cow<'x, str>, String> {
+    if expr.contains('(') && has_custom_functions(config) {
+        expand(config, expr, &mut Vec::new())
+            .map(|expanded| expanded.map(Cow::Owned).unwrap_or(Cow::Borrowed(expr)))
+    } else {
+        Ok(Cow::Borrowed(expr))
+    }
+}
+
+pub fn validate_custom_functions(config: &mut Config) {
+    if !has_custom_functions(config) {
+        return;
+    }
+
+    let mut errors = Vec::new();
+    for name in config.sub_keys("expression.function", ".expr") {
+        let key = format!("{FUNCTION_PREFIX}{name}.expr");
+
+        if is_reserved(&name) {
+            errors.push((key, format!("Function name {name:?} is reserved")));
+        } else if !name.bytes().all(is_ident) || !name.bytes().next().is_some_and(is_ident_start) {
+            errors.push((key, format!("Invalid function name {name:?}")));
+        } else if let Some(function) = CustomFunction::get(config, &name) {
+            let mut stack = vec![name];
+            if let Err(err) = expand(config, &function.expr, &mut stack) {
+                errors.push((key, err));
+            }
+        }
+    }
+
+    for (key, err) in errors {
+        config.new_parse_error(key, err);
+    }
+}
+
+impl CustomFunction {
+    fn get(config: &Config, name: &str) -> Option<Self> {
+        if is_reserved(name) {
+            return None;
+        }
+        let expr = config.value(("expression.function", name, "expr"))?;
+
+        Some(CustomFunction {
+            args: config
+                .values(("expression.function", name, "args"))
+                .map(|(_, arg)| arg.trim().to_string())
+                .collect(),
+            expr: expr.to_string(),
+        })
+    }
+}
+
+fn has_custom_functions(config: &Config) -> bool {
+    config
+        .keys
+        .range::<str, _>((Bound::Included(FUNCTION_PREFIX), Bound::Unbounded))
+        .next()
+        .is_some_and(|(key, _)| key.starts_with(FUNCTION_PREFIX))
+}
+
+fn is_reserved(name: &str) -> bool {
+    matches!(name, "matches" | "config_get" | "true" | "false")
+        || FUNCTIONS.iter().any(|(fnc, _, _)| *fnc == name)
+        || ASYNC_FUNCTIONS.iter().any(|(fnc, _, _)| *fnc == name)
+}
+
+fn expand(config: &Config, expr: &str, stack: &mut Vec<String>) -> Result<Option<String>, String> {
+    let bytes = expr.as_bytes();
+    let mut result = String::new();
+    let mut last_pos = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\"' | b'\'' => {
+                pos = skip_string(bytes, pos)?;
+            }
+            ch if is_ident_start(ch) => {
+                let start = pos;
+                while pos < bytes.len() && is_ident(bytes[pos]) {
+                    pos += 1;
+                }
+                let name = &expr[start..pos];
+                let open_pos = skip_whitespace(bytes, pos);
+                if bytes.get(open_pos) != Some(&b'(') {
+                    continue;
+                }
+                let Some(function) = CustomFunction::get(config, name) else {
+                    continue;
+                };
+
+                // Detect cycles
+                if stack.iter().any(|item| item == name) {
+                    return Err(format!(
+                        "Recursive call to function {name:?} ({} -> {name})",
+                        stack.join(" -> ")
+                    ));
+                } else if stack.len() >= MAX_EXPANSION_DEPTH {
+                    return Err(format!(
+                        "Too many nested function calls while expanding {name:?}"
+                    ));
+                }
+
+                // Expand arguments in the caller's context
+                let (args, end_pos) = split_args(expr, open_pos + 1)?;
+                if args.len() != function.args.len() {
+                    return Err(format!(
+                        "Function {name:?} expects {} arguments, got {}",
+                        function.args.len(),
+                        args.len()
+                    ));
+                }
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(expand(config, arg, stack)?.unwrap_or_else(|| arg.to_string()));
+                }
+
+                // Replace arguments and expand the function body
+                let body = substitute(&function.expr, &function.args, &values)?;
+                stack.push(name.to_string());
+                let body = expand(config, &body, stack)?.unwrap_or(body);
+                stack.pop();
+
+                result.push_str(&expr[last_pos..start]);
+                result.push('(');
+                result.push_str(&body);
+                result.push(')');
+                pos = end_pos;
+                last_pos = end_pos;
+            }
+            _ => {
+                pos += 1;
+            }
+        }
+    }
+
+    if last_pos > 0 {
+        result.push_str(&expr[last_pos..]);
+        Ok(Some(result))
+    } else {
+        Ok(None)
+    }
+}
+
+fn substitute(body: &str, params: &[String], values: &[String]) -> Result<String, String> {
+    let bytes = body.as_bytes();
+    let mut result = String::with_capacity(body.len());
+    let mut last_pos = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\"' | b'\'' => {
+                pos = skip_string(bytes, pos)?;
+            }
+            ch if is_ident_start(ch) => {
+                let start = pos;
+                while pos < bytes.len() && is_ident(bytes[pos]) {
+                    pos += 1;
+                }
+                if let Some(idx) = params.iter().position(|p| p == &body[start..pos]) {
+                    let value = &values[idx];
+                    result.push_str(&body[last_pos..start]);
+                    if is_atom(value) {
+                        result.push_str(value);
+                    } else {
+                        result.push('(');
+                        result.push_str(value);
+                        result.push(')');
+                    }
+                    last_pos = pos;
+                }
+            }
+            _ => {
+                pos += 1;
+            }
+        }
+    }
+
+    result.push_str(&body[last_pos..]);
+    Ok(result)
+}
+
+fn split_args(expr: &str, start_pos: usize) -> Result<(Vec<&str>, usize), String> {
+    let bytes = expr.as_bytes();
+    let mut args = Vec::new();
+    let mut arg_start = start_pos;
+    let mut depth = 0u32;
+    let mut pos = start_pos;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\"' | b'\'' => {
+                pos = skip_string(bytes, pos)?;
+                continue;
+            }
+            b'(' | b'[' => {
+                depth += 1;
+            }
+            b')' if depth == 0 => {
+                let arg = expr[arg_start..pos].trim();
+                if !arg.is_empty() || !args.is_empty() {
+                    args.push(arg);
+                }
+                return Ok((args, pos + 1));
+            }
+            b')' | b']' => {
+                depth = depth.saturating_sub(1);
+            }
+            b',' if depth == 0 => {
+                args.push(expr[arg_start..pos].trim());
+                arg_start = pos + 1;
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+
+    Err("Unmatched open parenthesis".to_string())
+}
+
+fn skip_string(bytes: &[u8], start_pos: usize) -> Result<usize, String> {
+    let stop_ch = bytes[start_pos];
+    let mut pos = start_pos + 1;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => {
+                pos += 2;
+            }
+            ch if ch == stop_ch => {
+                return Ok(pos + 1);
+            }
+            _ => {
+                pos += 1;
+            }
+        }
+    }
+
+    Err("Unterminated string".to_string())
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while bytes.get(pos).is_some_and(|ch| ch.is_ascii_whitespace()) {
+        pos += 1;
+    }
+    pos
+}
+
+fn is_atom(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    match bytes.first() {
+        Some(b'\"' | b'\'') => skip_string(bytes, 0).is_ok_and(|end| end == bytes.len()),
+        Some(_) => bytes.iter().all(|&ch| is_ident(ch)),
+        None => false,
+    }
+}
+
+#[inline(always)]
+fn is_ident_start(ch: u8) -> bool {
+    matches!(ch, b'A'..=b'Z' | b'a'..=b'z' | b'_' | b'$')
+}
+
+#[inline(always)]
+fn is_ident(ch: u8) -> bool {
+    matches!(ch, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'$' | b'.')
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::config::Config;
+
+    use super::{MAX_EXPANSION_DEPTH, expand_custom_functions, validate_custom_functions};
+
+    const CONFIG: &str = r#"
+[expression.function.is_free_mail_domain]
+args = ["domain"]
+expr = "key_exists('free-domains', domain)"
+
+[expression.function.add]
+args = ["a", "b"]
+expr = "a + b"
+
+[expression.function.ping]
+expr = "pong()"
+
+[expression.function.pong]
+expr = "ping()"
+
+[expression.function.loop]
+expr = "loop() + 1"
+"#;
+
+    #[test]
+    fn expand_functions() {
+        let config = Config::new(CONFIG).unwrap();
+
+        for (expr, expected) in [
+            (
+                "is_free_mail_domain(sender_domain)",
+                "(key_exists('free-domains', sender_domain))",
+            ),
+            ("add(1, add(2, 3))", "(1 + ((2 + 3)))"),
+            ("'add(1, 2)'", "'add(1, 2)'"),
+            (
+                "is_local_domain('', rcpt_domain)",
+                "is_local_domain('', rcpt_domain)",
+            ),
+        ] {
+            assert_eq!(
+                expand_custom_functions(&config, expr).unwrap(),
+                expected,
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn expand_function_errors() {
+        let mut config = Config::new(CONFIG).unwrap();
+
+        for (expr, expected) in [
+            // Cycles are detected, including direct recursion
+            (
+                "ping()",
+                "Recursive call to function \"ping\" (ping -> pong -> ping)",
+            ),
+            (
+                "1 + pong()",
+                "Recursive call to function \"pong\" (pong -> ping -> pong)",
+            ),
+            (
+                "loop()",
+                "Recursive call to function \"loop\" (loop -> loop)",
+            ),
+            // Calls must match the number of declared arguments
+            ("add(1)", "Function \"add\" expects 2 arguments, got 1"),
+            (
+                "add(1, 2, 3)",
+                "Function \"add\" expects 2 arguments, got 3",
+            ),
+            (
+                "is_free_mail_domain()",
+                "Function \"is_free_mail_domain\" expects 1 arguments, got 0",
+            ),
+            (
+                "add(1, add(2))",
+                "Function \"add\" expects 2 arguments, got 1",
+            ),
+        ] {
+            assert_eq!(
+                expand_custom_functions(&config, expr).unwrap_err(),
+                expected,
+                "{expr}"
+            );
+        }
+
+        // Errors are reported when the configuration is validated
+        validate_custom_functions(&mut config);
+        for name in ["ping", "pong", "loop"] {
+            assert!(
+                config
+                    .errors
+                    .contains_key(&format!("expression.function.{name}.expr")),
+                "{name}: {:?}",
+                config.errors
+            );
+        }
+        for name in ["is_free_mail_domain", "add"] {
+            assert!(
+                !config
+                    .errors
+                    .contains_key(&format!("expression.function.{name}.expr")),
+                "{name}: {:?}",
+                config.errors
+            );
+        }
+    }
+
+    #[test]
+    fn expand_function_depth() {
+        // Each function calls the next one, the last one returns a constant
+        let chain = |len: usize| {
+            let mut toml = String::new();
+            for i in 0..len {
+                let expr = if i + 1 < len {
+                    format!("f{}()", i + 1)
+                } else {
+                    "1".to_string()
+                };
+                toml.push_str(&format!("[expression.function.f{i}]\nexpr = \"{expr}\"\n"));
+            }
+            Config::new(toml).unwrap()
+        };
+
+        let config = chain(MAX_EXPANSION_DEPTH);
+        assert_eq!(
+            expand_custom_functions(&config, "f0()").unwrap(),
+            format!(
+                "{}1{}",
+                "(".repeat(MAX_EXPANSION_DEPTH),
+                ")".repeat(MAX_EXPANSION_DEPTH)
+            )
+        );
+
+        let mut config = chain(MAX_EXPANSION_DEPTH + 1);
+        assert_eq!(
+            expand_custom_functions(&config, "f0()").unwrap_err(),
+            format!("Too many nested function calls while expanding \"f{MAX_EXPANSION_DEPTH}\"")
+        );
+        validate_custom_functions(&mut config);
+        assert!(
+            config.errors.contains_key("expression.function.f0.expr"),
+            "{:?}",
+            config.errors
+        );
+    }
+}
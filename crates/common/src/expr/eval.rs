@@ -139,6 +139,84 @@ impl Server {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct EvalTrace {
+    pub conditions: Vec<EvalTraceCondition>,
+    pub matched: Option<usize>,
+    pub result: Variable<'static>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EvalTraceCondition {
+    pub value: Variable<'static>,
+    pub matched: bool,
+}
+
+impl Server {
+    /// Evaluates an if-block recording the outcome of each condition,
+    /// used for testing expressions before they are deployed.
+    pub async fn eval_if_trace<'x, V: ResolveVariable>(
+        &'x self,
+        if_block: &'x IfBlock,
+        resolver: &'x V,
+        session_id: u64,
+    ) -> trc::Result<EvalTrace> {
+        let mut captures = Vec::new();
+        let mut conditions = Vec::with_capacity(if_block.if_then.len());
+
+        for (pos, if_then) in if_block.if_then.iter().enumerate() {
+            let value = (EvalContext {
+                resolver,
+                core: self,
+                expr: &if_then.expr,
+                captures: &mut captures,
+                session_id,
+            })
+            .eval()
+            .await?
+            .into_owned();
+            let matched = value.to_bool();
+            conditions.push(EvalTraceCondition { value, matched });
+
+            if matched {
+                let result = (EvalContext {
+                    resolver,
+                    core: self,
+                    expr: &if_then.then,
+                    captures: &mut captures,
+                    session_id,
+                })
+                .eval()
+                .await?
+                .into_owned();
+
+                return Ok(EvalTrace {
+                    conditions,
+                    matched: Some(pos),
+                    result,
+                });
+            }
+        }
+
+        let result = (EvalContext {
+            resolver,
+            core: self,
+            expr: &if_block.default,
+            captures: &mut captures,
+            session_id,
+        })
+        .eval()
+        .await?
+        .into_owned();
+
+        Ok(EvalTrace {
+            conditions,
+            matched: None,
+            result,
+        })
+    }
+}
+
 struct EvalContext<'x, V: ResolveVariable, T, C> {
     resolver: &'x V,
     core: &'x Server,
@@ -9,10 +9,13 @@ use std::{cmp::Ordering, net::IpAddr, vec::IntoIter};
 use compact_str::{CompactString, ToCompactString};
 use directory::backend::RcptType;
 use mail_auth::IpLookupStrategy;
-use store::{Deserialize, Rows, Value, dispatch::lookup::KeyValue};
+use store::{
+    Deserialize, Rows, Value,
+    dispatch::lookup::{KeyValue, LookupKey},
+};
 use trc::AddContext;
 
-use crate::{Server, expr::StringCow};
+use crate::{Server, auth::attributes::AttributeValue, expr::StringCow};
 
 use super::*;
 
@@ -66,6 +69,59 @@ impl Server {
                     .caused_by(trc::location!())
                     .map(|v| v.into())
             }
+            F_KEY_GET_MANY => {
+                let store = params.next_as_string();
+                let keys = match params.next() {
+                    Variable::Array(list) => list
+                        .into_iter()
+                        .map(|v| v.into_string())
+                        .collect::<Vec<_>>(),
+                    v => vec![v.into_string()],
+                };
+
+                // Empty keys are not looked up but keep their position in the result
+                let mut values = self
+                    .get_in_memory_store_or_default(store.as_str(), session_id)
+                    .key_get_many::<VariableWrapper>(
+                        keys.iter()
+                            .filter(|key| !key.is_empty())
+                            .map(|key| LookupKey::from(key.as_str()))
+                            .collect(),
+                    )
+                    .await
+                    .caused_by(trc::location!())?
+                    .into_iter();
+
+                Ok(keys
+                    .iter()
+                    .map(|key| {
+                        if !key.is_empty() {
+                            values
+                                .next()
+                                .flatten()
+                                .map(|v| v.into_inner())
+                                .unwrap_or_default()
+                        } else {
+                            Variable::default()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .into())
+            }
+            F_KEY_EXISTS_ANY => {
+                let store = params.next_as_string();
+                let keys = params.next_as_string_list();
+
+                self.get_in_memory_store_or_default(store.as_str(), session_id)
+                    .key_exists_any(
+                        keys.iter()
+                            .map(|key| LookupKey::from(key.as_str()))
+                            .collect(),
+                    )
+                    .await
+                    .caused_by(trc::location!())
+                    .map(|v| v.into())
+            }
             F_KEY_SET => {
                 let store = params.next_as_string();
                 let key = params.next_as_string();
@@ -102,6 +158,33 @@ impl Server {
                     .map(Variable::Integer)
                     .caused_by(trc::location!())
             }
+            F_PRINCIPAL_ATTRIBUTE => {
+                let directory = params.next_as_string();
+                let address = params.next_as_string();
+                let name = params.next_as_string();
+
+                self.principal_attribute(
+                    directory.as_ref(),
+                    address.as_ref(),
+                    name.as_ref(),
+                    session_id,
+                )
+                .await
+                .caused_by(trc::location!())
+                .map(|value| match value {
+                    Some(AttributeValue::String(value)) => {
+                        Variable::from(CompactString::from(value))
+                    }
+                    Some(AttributeValue::Integer(value)) => value.into(),
+                    Some(AttributeValue::Boolean(value)) => value.into(),
+                    Some(AttributeValue::List(values)) => values
+                        .into_iter()
+                        .map(|value| Variable::from(CompactString::from(value)))
+                        .collect::<Vec<_>>()
+                        .into(),
+                    None => Variable::default(),
+                })
+            }
             F_DNS_QUERY => self.dns_query(params).await,
             F_SQL_QUERY => self.sql_query(params, session_id).await,
             _ => Ok(Variable::default()),
@@ -301,6 +384,20 @@ impl<'x> FncParams<'x> {
         self.params.next().unwrap().into_string()
     }
 
+    pub fn next_as_string_list(&mut self) -> Vec<StringCow<'x>> {
+        match self.params.next().unwrap() {
+            Variable::Array(list) => list
+                .into_iter()
+                .map(|v| v.into_string())
+                .filter(|v| !v.is_empty())
+                .collect(),
+            v => {
+                let v = v.into_string();
+                if !v.is_empty() { vec![v] } else { vec![] }
+            }
+        }
+    }
+
     pub fn next_as_integer(&mut self) -> i64 {
         self.params.next().unwrap().to_integer().unwrap_or_default()
     }
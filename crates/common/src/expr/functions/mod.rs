@@ -90,15 +90,21 @@ pub const F_COUNTER_INCR: u32 = 5;
 pub const F_COUNTER_GET: u32 = 6;
 pub const F_SQL_QUERY: u32 = 7;
 pub const F_DNS_QUERY: u32 = 8;
+pub const F_KEY_GET_MANY: u32 = 9;
+pub const F_KEY_EXISTS_ANY: u32 = 10;
+pub const F_PRINCIPAL_ATTRIBUTE: u32 = 11;
 
 pub const ASYNC_FUNCTIONS: &[(&str, u32, u32)] = &[
     ("is_local_domain", F_IS_LOCAL_DOMAIN, 2),
     ("is_local_address", F_IS_LOCAL_ADDRESS, 2),
     ("key_get", F_KEY_GET, 2),
     ("key_exists", F_KEY_EXISTS, 2),
+    ("key_get_many", F_KEY_GET_MANY, 2),
+    ("key_exists_any", F_KEY_EXISTS_ANY, 2),
     ("key_set", F_KEY_SET, 3),
     ("counter_incr", F_COUNTER_INCR, 3),
     ("counter_get", F_COUNTER_GET, 2),
     ("dns_query", F_DNS_QUERY, 2),
     ("sql_query", F_SQL_QUERY, 3),
+    ("principal_attribute", F_PRINCIPAL_ATTRIBUTE, 3),
 ];
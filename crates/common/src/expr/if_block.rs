@@ -11,6 +11,7 @@ use crate::expr::{Constant, Expression};
 
 use super::{
     ConstantValue, ExpressionItem,
+    custom::expand_custom_functions,
     parser::ExpressionParser,
     tokenizer::{TokenMap, Tokenizer},
 };
@@ -72,16 +73,15 @@ impl Expression {
         key: impl AsKey,
         token_map: &TokenMap,
     ) -> Option<Expression> {
-        if let Some(expr) = config.value(key.as_key()) {
-            match ExpressionParser::new(Tokenizer::new(expr, token_map)).parse() {
-                Ok(expr) => Some(expr),
-                Err(err) => {
-                    config.new_parse_error(key, err);
-                    None
-                }
+        let expr = config.value(key.as_key())?;
+        match expand_custom_functions(config, expr)
+            .and_then(|expr| ExpressionParser::new(Tokenizer::new(&expr, token_map)).parse())
+        {
+            Ok(expr) => Some(expr),
+            Err(err) => {
+                config.new_parse_error(key, err);
+                None
             }
-        } else {
-            None
         }
     }
 
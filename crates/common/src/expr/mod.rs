@@ -44,6 +44,22 @@ pub const V_QUEUE_NAME: u32 = 29;
 pub const V_SOURCE: u32 = 30;
 pub const V_SIZE: u32 = 31;
 pub const V_QUEUE_AGE: u32 = 32;
+pub const V_HELO_SCORE: u32 = 33;
+pub const V_PROXY_ALPN: u32 = 34;
+pub const V_PROXY_SNI: u32 = 35;
+pub const V_PROXY_CLIENT_CERT: u32 = 36;
+pub const V_PROXY_CLIENT_CN: u32 = 37;
+pub const V_TLS_JA3: u32 = 38;
+pub const V_TLS_JA4: u32 = 39;
+pub const V_QUEUE_BOUNCE_CATEGORY: u32 = 40;
+pub const V_TENANT_ID: u32 = 41;
+pub const V_SPF_RESULT: u32 = 42;
+pub const V_DKIM_RESULT: u32 = 43;
+pub const V_DKIM_DOMAINS: u32 = 44;
+pub const V_ARC_RESULT: u32 = 45;
+pub const V_DMARC_RESULT: u32 = 46;
+pub const V_DMARC_DOMAIN: u32 = 47;
+pub const V_IPREV_RESULT: u32 = 48;
 
 pub const VARIABLES_MAP: &[(&str, u32)] = &[
     ("rcpt", V_RECIPIENT),
@@ -79,6 +95,22 @@ pub const VARIABLES_MAP: &[(&str, u32)] = &[
     ("source", V_SOURCE),
     ("size", V_SIZE),
     ("queue_age", V_QUEUE_AGE),
+    ("helo_score", V_HELO_SCORE),
+    ("proxy_alpn", V_PROXY_ALPN),
+    ("proxy_sni", V_PROXY_SNI),
+    ("proxy_client_cert", V_PROXY_CLIENT_CERT),
+    ("proxy_client_cn", V_PROXY_CLIENT_CN),
+    ("tls_ja3", V_TLS_JA3),
+    ("tls_ja4", V_TLS_JA4),
+    ("bounce_category", V_QUEUE_BOUNCE_CATEGORY),
+    ("tenant_id", V_TENANT_ID),
+    ("spf_result", V_SPF_RESULT),
+    ("dkim_result", V_DKIM_RESULT),
+    ("dkim_domains", V_DKIM_DOMAINS),
+    ("arc_result", V_ARC_RESULT),
+    ("dmarc_result", V_DMARC_RESULT),
+    ("dmarc_domain", V_DMARC_DOMAIN),
+    ("iprev_result", V_IPREV_RESULT),
 ];
 
 use compact_str::CompactString;
@@ -87,6 +119,7 @@ use utils::config::{Rate, utils::ParseValue};
 
 use self::tokenizer::TokenMap;
 
+pub mod custom;
 pub mod eval;
 pub mod functions;
 pub mod if_block;
@@ -367,6 +367,7 @@ impl TokenMap {
             V_QUEUE_EXPIRES_IN,
             V_QUEUE_LAST_STATUS,
             V_QUEUE_LAST_ERROR,
+            V_QUEUE_BOUNCE_CATEGORY,
             V_QUEUE_NAME,
             V_QUEUE_AGE,
             V_ASN,
@@ -375,6 +376,21 @@ impl TokenMap {
             V_RECEIVED_VIA_PORT,
             V_SOURCE,
             V_SIZE,
+            V_HELO_SCORE,
+            V_PROXY_ALPN,
+            V_PROXY_SNI,
+            V_PROXY_CLIENT_CERT,
+            V_PROXY_CLIENT_CN,
+            V_TLS_JA3,
+            V_TLS_JA4,
+            V_TENANT_ID,
+            V_SPF_RESULT,
+            V_DKIM_RESULT,
+            V_DKIM_DOMAINS,
+            V_ARC_RESULT,
+            V_DMARC_RESULT,
+            V_DMARC_DOMAIN,
+            V_IPREV_RESULT,
         ])
     }
 
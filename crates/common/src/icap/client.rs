@@ -0,0 +1,236 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Write;
+
+use crate::config::smtp::session::IcapService;
+use rustls_pki_types::ServerName;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_rustls::{TlsConnector, client::TlsStream};
+
+use super::{IcapClient, IcapResponse, Verdict};
+
+const ICAP_CHUNK_SIZE: usize = 65536;
+
+impl IcapClient<TcpStream> {
+    pub async fn connect(config: &IcapService) -> Result<Self, String> {
+        tokio::time::timeout(config.timeout_connect, async {
+            let mut last_err = "No addresses to connect to".to_string();
+            for addr in &config.addrs {
+                match TcpStream::connect(addr).await {
+                    Ok(stream) => {
+                        return Ok(IcapClient {
+                            stream,
+                            buf: Vec::with_capacity(1024),
+                            timeout_connect: config.timeout_connect,
+                            timeout_data: config.timeout_data,
+                            max_response_size: config.max_response_size,
+                        });
+                    }
+                    Err(err) => {
+                        last_err = format!("Failed to connect to {addr}: {err}");
+                    }
+                }
+            }
+            Err(last_err)
+        })
+        .await
+        .map_err(|_| "Connection timed out".to_string())?
+    }
+
+    pub async fn into_tls(
+        self,
+        tls_connector: &TlsConnector,
+        tls_hostname: &str,
+    ) -> Result<IcapClient<TlsStream<TcpStream>>, String> {
+        tokio::time::timeout(self.timeout_connect, async {
+            Ok(IcapClient {
+                stream: tls_connector
+                    .connect(
+                        ServerName::try_from(tls_hostname)
+                            .map_err(|_| format!("Invalid TLS name {tls_hostname:?}"))?
+                            .to_owned(),
+                        self.stream,
+                    )
+                    .await
+                    .map_err(|err| format!("TLS handshake failed: {err}"))?,
+                buf: self.buf,
+                timeout_connect: self.timeout_connect,
+                timeout_data: self.timeout_data,
+                max_response_size: self.max_response_size,
+            })
+        })
+        .await
+        .map_err(|_| "TLS handshake timed out".to_string())?
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> IcapClient<T> {
+    // Sends the message as the body of an encapsulated HTTP response (RFC 3507 RESPMOD).
+    // When a preview size is configured, the server may reach a verdict before the full
+    // message is streamed.
+    pub async fn respmod(
+        &mut self,
+        config: &IcapService,
+        message: &[u8],
+        client_ip: &str,
+    ) -> Result<Verdict, String> {
+        tokio::time::timeout(self.timeout_data, self.respmod_(config, message, client_ip))
+            .await
+            .map_err(|_| "ICAP request timed out".to_string())?
+    }
+
+    async fn respmod_(
+        &mut self,
+        config: &IcapService,
+        message: &[u8],
+        client_ip: &str,
+    ) -> Result<Verdict, String> {
+        let req_hdr = format!("GET /message HTTP/1.1\r\nHost: {}\r\n\r\n", config.hostname);
+        let res_hdr = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: message/rfc822\r\nContent-Length: {}\r\n\r\n",
+            message.len()
+        );
+        let preview = config.preview.map(|preview| preview.min(message.len()));
+
+        let mut request = String::with_capacity(512);
+        let _ = write!(
+            request,
+            "RESPMOD icap://{}:{}{} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\nX-Client-IP: {client_ip}\r\n",
+            config.hostname, config.port, config.service, config.hostname
+        );
+        if let Some(preview) = preview {
+            let _ = write!(request, "Preview: {preview}\r\n");
+        }
+        let _ = write!(
+            request,
+            "Encapsulated: req-hdr=0, res-hdr={}, res-body={}\r\n\r\n{req_hdr}{res_hdr}",
+            req_hdr.len(),
+            req_hdr.len() + res_hdr.len()
+        );
+        self.write(request.as_bytes()).await?;
+
+        let response = if let Some(preview) = preview {
+            // Send the preview, signaling the end of the message when it fits entirely
+            let (preview_bytes, remaining) = message.split_at(preview);
+            self.write_chunk(preview_bytes).await?;
+            if remaining.is_empty() {
+                self.write(b"0; ieof\r\n\r\n").await?;
+                self.read_response().await?
+            } else {
+                self.write(b"0\r\n\r\n").await?;
+                let response = self.read_response().await?;
+                if response.code == 100 {
+                    self.write_body(remaining).await?;
+                    self.read_response().await?
+                } else {
+                    response
+                }
+            }
+        } else {
+            self.write_body(message).await?;
+            self.read_response().await?
+        };
+
+        match response.code {
+            204 => Ok(Verdict::Clean),
+            200 => Ok(response
+                .threat()
+                .map(Verdict::Threat)
+                .unwrap_or(Verdict::Modified)),
+            code => Err(format!(
+                "ICAP server returned unexpected response {code} {}",
+                response.reason
+            )),
+        }
+    }
+
+    async fn write_body(&mut self, body: &[u8]) -> Result<(), String> {
+        for chunk in body.chunks(ICAP_CHUNK_SIZE) {
+            self.write_chunk(chunk).await?;
+        }
+        self.write(b"0\r\n\r\n").await
+    }
+
+    async fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), String> {
+        if !chunk.is_empty() {
+            self.write(format!("{:x}\r\n", chunk.len()).as_bytes())
+                .await?;
+            self.write(chunk).await?;
+            self.write(b"\r\n").await?;
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.stream
+            .write_all(bytes)
+            .await
+            .map_err(|err| format!("Failed to write to ICAP server: {err}"))?;
+        self.stream
+            .flush()
+            .await
+            .map_err(|err| format!("Failed to write to ICAP server: {err}"))
+    }
+
+    async fn read_response(&mut self) -> Result<IcapResponse, String> {
+        let mut buf = [0u8; 4096];
+        loop {
+            if let Some(pos) = self.buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                let response = parse_response(&self.buf[..pos])?;
+                self.buf.drain(..pos + 4);
+                return Ok(response);
+            } else if self.buf.len() > self.max_response_size {
+                return Err("ICAP response too large".to_string());
+            }
+
+            match self.stream.read(&mut buf).await {
+                Ok(0) => return Err("ICAP server closed the connection".to_string()),
+                Ok(bytes_read) => self.buf.extend_from_slice(&buf[..bytes_read]),
+                Err(err) => return Err(format!("Failed to read from ICAP server: {err}")),
+            }
+        }
+    }
+}
+
+fn parse_response(bytes: &[u8]) -> Result<IcapResponse, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "Invalid ICAP response".to_string())?;
+    let mut lines = text.split("\r\n");
+    let status = lines.next().unwrap_or_default();
+    let mut parts = status.splitn(3, ' ');
+
+    let (Some(version), Some(code)) = (parts.next(), parts.next()) else {
+        return Err(format!("Invalid ICAP status line {status:?}"));
+    };
+    if !version.starts_with("ICAP/") {
+        return Err(format!("Invalid ICAP status line {status:?}"));
+    }
+    let code = code
+        .parse::<u16>()
+        .map_err(|_| format!("Invalid ICAP status line {status:?}"))?;
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in lines {
+        if line.starts_with([' ', '\t']) {
+            // Folded header value
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    Ok(IcapResponse {
+        code,
+        reason: parts.next().unwrap_or_default().to_string(),
+        headers,
+    })
+}
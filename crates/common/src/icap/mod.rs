@@ -0,0 +1,89 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+pub mod client;
+pub mod upload;
+
+use std::time::Duration;
+
+use crate::{Server, config::smtp::session::IcapService};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+pub struct IcapClient<T: AsyncRead + AsyncWrite> {
+    stream: T,
+    buf: Vec<u8>,
+    timeout_connect: Duration,
+    timeout_data: Duration,
+    max_response_size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Clean,
+    Threat(String),
+    Modified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcapResponse {
+    pub code: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl IcapResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    // Obtains the threat name from the headers used by the most common AV vendors
+    pub fn threat(&self) -> Option<String> {
+        if let Some(value) = self.header("X-Infection-Found") {
+            Some(
+                value
+                    .split(';')
+                    .find_map(|part| part.trim().strip_prefix("Threat="))
+                    .unwrap_or(value)
+                    .trim()
+                    .to_string(),
+            )
+        } else {
+            ["X-Virus-ID", "X-Virus-Name", "X-Violations-Found"]
+                .iter()
+                .find_map(|name| self.header(name))
+                .map(|value| value.trim().to_string())
+        }
+    }
+}
+
+pub async fn icap_scan(
+    server: &Server,
+    service: &IcapService,
+    message: &[u8],
+    client_ip: &str,
+) -> Result<Verdict, String> {
+    let mut client = IcapClient::connect(service).await?;
+
+    if !service.tls {
+        client.respmod(service, message, client_ip).await
+    } else {
+        client
+            .into_tls(
+                if !service.tls_allow_invalid_certs {
+                    &server.inner.data.smtp_connectors.pki_verify
+                } else {
+                    &server.inner.data.smtp_connectors.dummy_verify
+                },
+                &service.hostname,
+            )
+            .await?
+            .respmod(service, message, client_ip)
+            .await
+    }
+}
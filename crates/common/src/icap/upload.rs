@@ -0,0 +1,317 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{net::IpAddr, time::Instant};
+
+use serde::{Deserialize, Serialize};
+use store::{
+    SerializeInfallible,
+    dispatch::lookup::KeyValue,
+    write::{BatchBuilder, BlobOp, now},
+};
+use trc::{AddContext, EventType, IcapEvent};
+use types::blob_hash::BlobHash;
+
+use crate::{KV_UPLOAD_QUARANTINE, Server, config::smtp::session::IcapAction};
+
+use super::{Verdict, icap_scan};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UploadSource {
+    Jmap,
+    Dav,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedUpload {
+    pub id: u64,
+    pub source: UploadSource,
+    pub account_id: u32,
+    pub service: String,
+    pub reason: String,
+    pub size: usize,
+    pub created: u64,
+    pub expires: u64,
+    pub blob_hash: String,
+}
+
+impl Server {
+    pub async fn scan_upload(
+        &self,
+        source: UploadSource,
+        account_id: u32,
+        data: &[u8],
+        remote_ip: IpAddr,
+        session_id: u64,
+    ) -> trc::Result<()> {
+        let config = &self.core.smtp.session;
+        let Some(policy) = (match source {
+            UploadSource::Jmap => config.icap_upload.jmap.as_ref(),
+            UploadSource::Dav => config.icap_upload.dav.as_ref(),
+        }) else {
+            return Ok(());
+        };
+
+        if data.len() > policy.max_size {
+            trc::event!(
+                Icap(IcapEvent::Skipped),
+                SpanId = session_id,
+                AccountId = account_id,
+                Size = data.len(),
+                Limit = policy.max_size,
+            );
+
+            return if policy.oversize_fail_open {
+                Ok(())
+            } else {
+                Err(EventType::Icap(IcapEvent::UploadRejected)
+                    .into_err()
+                    .span_id(session_id)
+                    .account_id(account_id)
+                    .ctx(trc::Key::Size, data.len())
+                    .ctx(trc::Key::Limit, policy.max_size)
+                    .details("Upload exceeds the maximum size allowed for scanning"))
+            };
+        }
+
+        let client_ip = remote_ip.to_string();
+        for service in policy
+            .services
+            .iter()
+            .filter_map(|idx| config.icap.get(*idx))
+        {
+            let time = Instant::now();
+            let (action, reason) = match icap_scan(self, service, data, &client_ip).await {
+                Ok(Verdict::Clean) => {
+                    trc::event!(
+                        Icap(IcapEvent::Clean),
+                        SpanId = session_id,
+                        AccountId = account_id,
+                        Id = service.id.to_string(),
+                        Elapsed = time.elapsed(),
+                    );
+                    continue;
+                }
+                Ok(Verdict::Threat(threat)) => {
+                    trc::event!(
+                        Icap(IcapEvent::ThreatFound),
+                        SpanId = session_id,
+                        AccountId = account_id,
+                        Id = service.id.to_string(),
+                        Details = threat.clone(),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    // Do not echo control characters back to the client
+                    let threat = threat
+                        .chars()
+                        .filter(|ch| !ch.is_control())
+                        .take(100)
+                        .collect::<String>();
+                    (
+                        service.action_threat,
+                        format!("Upload rejected, threat found: {threat}"),
+                    )
+                }
+                Ok(Verdict::Modified) => {
+                    trc::event!(
+                        Icap(IcapEvent::ContentModified),
+                        SpanId = session_id,
+                        AccountId = account_id,
+                        Id = service.id.to_string(),
+                        Elapsed = time.elapsed(),
+                    );
+                    (
+                        service.action_modified,
+                        "Upload rejected by content policy".to_string(),
+                    )
+                }
+                Err(err) => {
+                    trc::event!(
+                        Icap(IcapEvent::Error),
+                        SpanId = session_id,
+                        AccountId = account_id,
+                        Id = service.id.to_string(),
+                        Reason = err.clone(),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    if service.fail_open {
+                        continue;
+                    } else {
+                        return Err(EventType::Icap(IcapEvent::Error)
+                            .into_err()
+                            .id(service.id.to_string())
+                            .reason(err));
+                    }
+                }
+            };
+
+            if action == IcapAction::Accept {
+                continue;
+            }
+
+            // Keep a copy of the rejected upload for review
+            if let Some(retention) = policy.quarantine {
+                let id = self
+                    .quarantine_upload(
+                        source,
+                        account_id,
+                        service.id.to_string(),
+                        reason.clone(),
+                        data,
+                        retention.as_secs(),
+                    )
+                    .await?;
+
+                trc::event!(
+                    Icap(IcapEvent::UploadQuarantined),
+                    SpanId = session_id,
+                    AccountId = account_id,
+                    Id = service.id.to_string(),
+                    Key = id,
+                    Size = data.len(),
+                );
+            }
+
+            return Err(EventType::Icap(IcapEvent::UploadRejected)
+                .into_err()
+                .span_id(session_id)
+                .account_id(account_id)
+                .id(service.id.to_string())
+                .details(reason));
+        }
+
+        Ok(())
+    }
+}
+
+impl Server {
+    async fn quarantine_upload(
+        &self,
+        source: UploadSource,
+        account_id: u32,
+        service: String,
+        reason: String,
+        data: &[u8],
+        retention: u64,
+    ) -> trc::Result<u64> {
+        // Reserve the blob until the quarantine period ends, it is purged afterwards
+        let hash = BlobHash::generate(data);
+        let created = now();
+        let upload = QuarantinedUpload {
+            id: self.generate_snowflake_id(),
+            source,
+            account_id,
+            service,
+            reason,
+            size: data.len(),
+            created,
+            expires: created + retention,
+            blob_hash: hash.to_hex(),
+        };
+        let mut batch = BatchBuilder::new();
+        batch.set(
+            BlobOp::Reserve {
+                hash: hash.clone(),
+                until: upload.expires,
+            },
+            SerializeInfallible::serialize(&0u32),
+        );
+        self.store()
+            .write(batch.build_all())
+            .await
+            .caused_by(trc::location!())?;
+        self.blob_store()
+            .put_blob(hash.as_slice(), data)
+            .await
+            .caused_by(trc::location!())?;
+        let mut batch = BatchBuilder::new();
+        batch.set(BlobOp::Commit { hash }, Vec::new());
+        self.store()
+            .write(batch.build_all())
+            .await
+            .caused_by(trc::location!())?;
+
+        self.in_memory_store()
+            .key_set(
+                KeyValue::with_prefix(
+                    KV_UPLOAD_QUARANTINE,
+                    upload.id.to_be_bytes(),
+                    serde_json::to_vec(&upload).unwrap_or_default(),
+                )
+                .expires(retention),
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(upload.id)
+    }
+
+    pub async fn quarantined_uploads(&self) -> trc::Result<Vec<QuarantinedUpload>> {
+        self.in_memory_store()
+            .key_get_prefix(&[KV_UPLOAD_QUARANTINE])
+            .await
+            .caused_by(trc::location!())
+            .map(|entries| {
+                entries
+                    .into_iter()
+                    .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+                    .collect()
+            })
+    }
+
+    pub async fn quarantined_upload(&self, id: u64) -> trc::Result<Option<QuarantinedUpload>> {
+        Ok(self
+            .in_memory_store()
+            .key_get::<String>(KeyValue::<()>::build_key(
+                KV_UPLOAD_QUARANTINE,
+                id.to_be_bytes(),
+            ))
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|upload| serde_json::from_str(&upload).ok()))
+    }
+
+    pub async fn quarantined_upload_contents(
+        &self,
+        upload: &QuarantinedUpload,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        let Some(hash) = BlobHash::try_from_hex(&upload.blob_hash) else {
+            return Ok(None);
+        };
+
+        self.blob_store()
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .caused_by(trc::location!())
+    }
+
+    pub async fn delete_quarantined_upload(&self, upload: &QuarantinedUpload) -> trc::Result<()> {
+        // Releasing the reservation lets the blob purge remove the contents
+        if let Some(hash) = BlobHash::try_from_hex(&upload.blob_hash) {
+            let mut batch = BatchBuilder::new();
+            batch.clear(BlobOp::Reserve {
+                hash,
+                until: upload.expires,
+            });
+            self.store()
+                .write(batch.build_all())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        self.in_memory_store()
+            .key_delete(KeyValue::<()>::build_key(
+                KV_UPLOAD_QUARANTINE,
+                upload.id.to_be_bytes(),
+            ))
+            .await
+            .caused_by(trc::location!())
+    }
+}
@@ -4,10 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use crate::config::smtp::{
-    queue::QueueName,
-    report::AggregateFrequency,
-    resolver::{Policy, Tlsa},
+use crate::{
+    config::smtp::{
+        queue::QueueName,
+        report::AggregateFrequency,
+        resolver::{Policy, Tlsa},
+    },
+    manager::reload::ReloadScope,
 };
 use ahash::RandomState;
 use mail_auth::{
@@ -15,9 +18,17 @@ use mail_auth::{
     mta_sts::TlsRpt,
     report::{Record, tlsrpt::FailureDetails},
 };
-use std::{sync::Arc, time::Instant};
+use std::{
+    fmt::Display,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
 use store::{BlobStore, InMemoryStore, Store};
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use trc::{Collector, MetricType};
 use types::type_state::{DataType, StateChange};
 use utils::map::bitmap::Bitmap;
 
@@ -27,7 +38,8 @@ pub enum HousekeeperEvent {
         renew_at: Instant,
     },
     Purge(PurgeType),
-    ReloadSettings,
+    UsageReport,
+    ReloadSettings(Bitmap<ReloadScope>),
     Exit,
 }
 
@@ -42,6 +54,15 @@ pub enum PurgeType {
         prefix: Option<Vec<u8>>,
     },
     Account(Option<u32>),
+    BlobScrub {
+        store: Store,
+        blob_store: BlobStore,
+        sample_rate: u8,
+        replica: Option<BlobStore>,
+    },
+    Orphans {
+        repair: bool,
+    },
 }
 
 #[derive(Debug)]
@@ -72,6 +93,7 @@ pub enum BroadcastEvent {
     InvalidateDavCache(Vec<u32>),
     ReloadSettings,
     ReloadBlockedIps,
+    ReloadScopes(Bitmap<ReloadScope>),
 }
 
 #[derive(Debug)]
@@ -151,6 +173,235 @@ pub enum PolicyType {
     None,
 }
 
+pub struct IpcSender<T> {
+    tx: mpsc::Sender<T>,
+    channel: IpcChannel,
+    overflow: IpcOverflow,
+    buffer: Option<Arc<IpcBuffer<T>>>,
+}
+
+// In-memory overflow buffer, events are lost if the server stops before they are drained
+struct IpcBuffer<T> {
+    tx: mpsc::Sender<T>,
+    pending: Arc<AtomicUsize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcChannel {
+    State,
+    Housekeeper,
+    Queue,
+    Report,
+    Broadcast,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcOverflow {
+    Block { timeout: Option<Duration> },
+    Drop,
+    Buffer { limit: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct IpcPolicy {
+    pub capacity: usize,
+    pub overflow: IpcOverflow,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IpcPolicies {
+    pub state: IpcPolicy,
+    pub housekeeper: IpcPolicy,
+    pub queue: IpcPolicy,
+    pub report: IpcPolicy,
+    pub broadcast: IpcPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcSendError {
+    Closed,
+}
+
+impl<T: Send + 'static> IpcSender<T> {
+    pub fn new(channel: IpcChannel, policy: &IpcPolicy) -> (Self, mpsc::Receiver<T>) {
+        let (tx, rx) = mpsc::channel(policy.capacity);
+        let buffer = if let IpcOverflow::Buffer { limit } = policy.overflow {
+            let (buffer_tx, mut buffer_rx) = mpsc::channel::<T>(limit.max(1));
+            let pending = Arc::new(AtomicUsize::new(0));
+
+            // Drain buffered events into the channel as capacity becomes available,
+            // the task exits once all senders are dropped
+            let pending_ = pending.clone();
+            let tx_ = tx.clone();
+            tokio::spawn(async move {
+                while let Some(event) = buffer_rx.recv().await {
+                    let result = tx_.send(event).await;
+                    pending_.fetch_sub(1, Ordering::Relaxed);
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            Some(Arc::new(IpcBuffer {
+                tx: buffer_tx,
+                pending,
+            }))
+        } else {
+            None
+        };
+
+        (
+            IpcSender {
+                tx,
+                channel,
+                overflow: policy.overflow,
+                buffer,
+            },
+            rx,
+        )
+    }
+
+    /// Sends an event to the channel, applying the configured overflow policy when
+    /// the channel is full. Events discarded by the policy are reported and counted
+    /// but do not produce an error, only a closed channel does.
+    pub async fn send(&self, event: T) -> Result<(), IpcSendError> {
+        let result = match self.overflow {
+            IpcOverflow::Block { timeout: None } => {
+                self.tx.send(event).await.map_err(|_| IpcSendError::Closed)
+            }
+            IpcOverflow::Block {
+                timeout: Some(timeout),
+            } => match tokio::time::timeout(timeout, self.tx.send(event)).await {
+                Ok(result) => result.map_err(|_| IpcSendError::Closed),
+                Err(_) => {
+                    self.dropped("Timeout waiting for channel capacity");
+                    Ok(())
+                }
+            },
+            IpcOverflow::Drop => match self.tx.try_send(event) {
+                Ok(_) => Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    self.dropped("Channel full");
+                    Ok(())
+                }
+                Err(TrySendError::Closed(_)) => Err(IpcSendError::Closed),
+            },
+            IpcOverflow::Buffer { .. } => self.try_send_or_buffer(event),
+        };
+
+        Collector::update_gauge(self.channel.metric(), self.queued() as u64);
+
+        result
+    }
+
+    fn try_send_or_buffer(&self, event: T) -> Result<(), IpcSendError> {
+        let Some(buffer) = &self.buffer else {
+            return self.tx.try_send(event).map_err(|_| IpcSendError::Closed);
+        };
+
+        // Keep ordering by buffering while there are events pending in the buffer
+        let event = if buffer.pending.load(Ordering::Relaxed) == 0 {
+            match self.tx.try_send(event) {
+                Ok(_) => return Ok(()),
+                Err(TrySendError::Full(event)) => event,
+                Err(TrySendError::Closed(_)) => return Err(IpcSendError::Closed),
+            }
+        } else {
+            event
+        };
+
+        let pending = buffer.pending.fetch_add(1, Ordering::Relaxed);
+        match buffer.tx.try_send(event) {
+            Ok(_) => {
+                trc::event!(
+                    Server(trc::ServerEvent::IpcEventBuffered),
+                    Id = self.channel.name(),
+                    Total = pending + 1,
+                );
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                buffer.pending.fetch_sub(1, Ordering::Relaxed);
+                self.dropped("Overflow buffer full");
+                Ok(())
+            }
+            Err(TrySendError::Closed(_)) => {
+                buffer.pending.fetch_sub(1, Ordering::Relaxed);
+                Err(IpcSendError::Closed)
+            }
+        }
+    }
+
+    fn dropped(&self, reason: &'static str) {
+        trc::event!(
+            Server(trc::ServerEvent::IpcEventDropped),
+            Id = self.channel.name(),
+            Reason = reason,
+            Size = self.queued(),
+            Limit = self.tx.max_capacity(),
+        );
+    }
+
+    pub fn queued(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+            + self
+                .buffer
+                .as_ref()
+                .map_or(0, |buffer| buffer.pending.load(Ordering::Relaxed))
+    }
+}
+
+impl<T> Clone for IpcSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            channel: self.channel,
+            overflow: self.overflow,
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
+impl IpcChannel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IpcChannel::State => "state",
+            IpcChannel::Housekeeper => "housekeeper",
+            IpcChannel::Queue => "queue",
+            IpcChannel::Report => "report",
+            IpcChannel::Broadcast => "broadcast",
+        }
+    }
+
+    pub fn metric(&self) -> MetricType {
+        match self {
+            IpcChannel::State => MetricType::IpcStateQueued,
+            IpcChannel::Housekeeper => MetricType::IpcHousekeeperQueued,
+            IpcChannel::Queue => MetricType::IpcQueueQueued,
+            IpcChannel::Report => MetricType::IpcReportQueued,
+            IpcChannel::Broadcast => MetricType::IpcBroadcastQueued,
+        }
+    }
+}
+
+impl Default for IpcPolicy {
+    fn default() -> Self {
+        Self {
+            capacity: crate::IPC_CHANNEL_BUFFER,
+            overflow: IpcOverflow::Block { timeout: None },
+        }
+    }
+}
+
+impl Display for IpcSendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpcSendError::Closed => write!(f, "Channel closed"),
+        }
+    }
+}
+
 pub trait ToHash {
     fn to_hash(&self) -> u64;
 }
@@ -212,3 +463,92 @@ impl From<(&Option<Arc<Policy>>, &Option<Arc<Tlsa>>)> for PolicyType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{IpcChannel, IpcOverflow, IpcPolicy, IpcSendError, IpcSender};
+    use std::time::Duration;
+
+    fn policy(capacity: usize, overflow: IpcOverflow) -> IpcPolicy {
+        IpcPolicy { capacity, overflow }
+    }
+
+    #[tokio::test]
+    async fn ipc_overflow_drop() {
+        let (tx, mut rx) = IpcSender::new(IpcChannel::State, &policy(2, IpcOverflow::Drop));
+        for i in 0..5u32 {
+            assert_eq!(tx.send(i).await, Ok(()));
+        }
+        assert_eq!(tx.queued(), 2);
+        assert_eq!(rx.recv().await, Some(0));
+        assert_eq!(rx.recv().await, Some(1));
+        assert!(rx.try_recv().is_err());
+
+        drop(rx);
+        assert_eq!(tx.send(5).await, Err(IpcSendError::Closed));
+    }
+
+    #[tokio::test]
+    async fn ipc_overflow_block() {
+        let (tx, mut rx) = IpcSender::new(
+            IpcChannel::Queue,
+            &policy(
+                1,
+                IpcOverflow::Block {
+                    timeout: Some(Duration::from_millis(50)),
+                },
+            ),
+        );
+        assert_eq!(tx.send(0u32).await, Ok(()));
+
+        // Times out and discards the event while the channel is full
+        assert_eq!(tx.send(1).await, Ok(()));
+        assert_eq!(rx.recv().await, Some(0));
+        assert!(rx.try_recv().is_err());
+
+        // Waits for capacity to become available
+        assert_eq!(tx.send(2).await, Ok(()));
+        let tx_ = tx.clone();
+        let handle = tokio::spawn(async move { tx_.send(3).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(rx.recv().await, Some(2));
+        assert_eq!(handle.await.unwrap(), Ok(()));
+        assert_eq!(rx.recv().await, Some(3));
+
+        drop(rx);
+        assert_eq!(tx.send(4).await, Err(IpcSendError::Closed));
+    }
+
+    #[tokio::test]
+    async fn ipc_overflow_buffer() {
+        let (tx, mut rx) = IpcSender::new(
+            IpcChannel::Report,
+            &policy(2, IpcOverflow::Buffer { limit: 3 }),
+        );
+
+        // Two events fit in the channel, three in the buffer and the rest are dropped
+        for i in 0..8u32 {
+            assert_eq!(tx.send(i).await, Ok(()));
+        }
+        assert!(tx.queued() <= 5);
+
+        // Buffered events are delivered in order once capacity is available
+        let mut received = Vec::new();
+        for _ in 0..5 {
+            received.push(rx.recv().await.unwrap());
+        }
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), rx.recv())
+                .await
+                .is_err()
+        );
+        assert_eq!(tx.queued(), 0);
+
+        // The receiver is closed once all senders are dropped
+        assert_eq!(tx.send(8).await, Ok(()));
+        drop(tx);
+        assert_eq!(rx.recv().await, Some(8));
+        assert_eq!(rx.recv().await, None);
+    }
+}
@@ -8,7 +8,10 @@
 
 use ahash::{AHashMap, AHashSet};
 use arc_swap::ArcSwap;
-use auth::{AccessToken, oauth::config::OAuthConfig, roles::RolePermissions};
+use auth::{
+    AccessToken, attributes::AttributeSchema, oauth::config::OAuthConfig, password::PasswordPolicy,
+    roles::RolePermissions, sessions::SessionRegistry,
+};
 use calcard::common::timezone::Tz;
 use config::{
     groupware::GroupwareConfig,
@@ -19,15 +22,23 @@ use config::{
     smtp::{
         SmtpConfig,
         resolver::{Policy, Tlsa},
+        session::{EhloValidationResult, SenderDomainPolicy},
     },
     spamfilter::{IpResolver, SpamFilterConfig},
     storage::Storage,
     telemetry::Metrics,
 };
-use ipc::{BroadcastEvent, HousekeeperEvent, QueueEvent, ReportingEvent, StateEvent};
-use listener::{asn::AsnGeoLookupData, blocked::Security, tls::AcmeProviders};
+use ipc::{
+    BroadcastEvent, HousekeeperEvent, IpcSender, QueueEvent, ReportingEvent, StateEvent,
+};
+use listener::{
+    asn::AsnGeoLookupData, blocked::Security, clients::ConnectedClients, tls::AcmeProviders,
+};
 use mail_auth::{MX, Txt};
-use manager::webadmin::{Resource, WebAdminManager};
+use manager::{
+    reload::ConfigFingerprint,
+    webadmin::{Resource, WebAdminManager},
+};
 use nlp::bayes::{TokenHash, Weights};
 use parking_lot::{Mutex, RwLock};
 use rustls::sign::CertifiedKey;
@@ -37,8 +48,11 @@ use std::{
     sync::{Arc, atomic::AtomicBool},
     time::{Duration, Instant},
 };
+use storage::health::CachedHealthReport;
+use store::write::blob::BlobScrubStatus;
+use telemetry::metrics::labels::LabeledMetrics;
 use tinyvec::TinyVec;
-use tokio::sync::{Notify, Semaphore, mpsc};
+use tokio::sync::{Notify, Semaphore};
 use tokio_rustls::TlsConnector;
 use types::{acl::AclGrant, special_use::SpecialUse};
 use utils::{
@@ -53,6 +67,7 @@ pub mod core;
 pub mod dns;
 pub mod expr;
 pub mod i18n;
+pub mod icap;
 pub mod ipc;
 pub mod listener;
 pub mod manager;
@@ -114,6 +129,24 @@ pub const KV_LOCK_TASK: u8 = 23;
 pub const KV_LOCK_HOUSEKEEPER: u8 = 24;
 pub const KV_LOCK_DAV: u8 = 25;
 pub const KV_SIEVE_ID: u8 = 26;
+pub const KV_SPAM_FUZZY: u8 = 27;
+pub const KV_ABUSE_SUPPRESS: u8 = 28;
+pub const KV_ABUSE_COMPLAINTS: u8 = 29;
+pub const KV_HEALTH_PROBE: u8 = 30;
+pub const KV_SPAM_STATS: u8 = 31;
+pub const KV_MESSAGE_TRACE: u8 = 32;
+pub const KV_PASSWORD_HISTORY: u8 = 33;
+pub const KV_OAUTH_CLIENT: u8 = 34;
+pub const KV_OAUTH_REGISTRATION: u8 = 35;
+pub const KV_USAGE_REPORT: u8 = 36;
+pub const KV_SMTP_TRANSCRIPT: u8 = 37;
+pub const KV_SENDING_QUOTA: u8 = 40;
+pub const KV_SESSION: u8 = 41;
+pub const KV_SESSION_TOKEN: u8 = 42;
+pub const KV_SENDER_REPUTATION: u8 = 43;
+pub const KV_SENDER_REPUTATION_MSG: u8 = 44;
+pub const KV_ORPHAN_REPORT: u8 = 45;
+pub const KV_UPLOAD_QUARANTINE: u8 = 46;
 
 #[derive(Clone)]
 pub struct Server {
@@ -145,6 +178,13 @@ pub struct Data {
     pub logos: Mutex<AHashMap<String, Option<Resource<Vec<u8>>>>>,
 
     pub smtp_connectors: TlsConnectors,
+
+    pub connected_clients: ConnectedClients,
+    pub labeled_metrics: LabeledMetrics,
+    pub blob_scrub: BlobScrubStatus,
+    pub config_fingerprint: Mutex<ConfigFingerprint>,
+    pub directory_failures: Mutex<AHashMap<String, u32>>,
+    pub health_report: tokio::sync::Mutex<Option<CachedHealthReport>>,
 }
 
 pub struct Caches {
@@ -168,6 +208,9 @@ pub struct Caches {
     pub dns_tlsa: CacheWithTtl<String, Arc<Tlsa>>,
     pub dbs_mta_sts: CacheWithTtl<String, Arc<Policy>>,
     pub dns_rbl: CacheWithTtl<String, Option<Arc<IpResolver>>>,
+
+    pub helo_validation: CacheWithTtl<String, EhloValidationResult>,
+    pub sender_domain: CacheWithTtl<String, SenderDomainPolicy>,
 }
 
 #[derive(Debug, Clone)]
@@ -235,12 +278,13 @@ pub struct HttpAuthCache {
 }
 
 pub struct Ipc {
-    pub state_tx: mpsc::Sender<StateEvent>,
-    pub housekeeper_tx: mpsc::Sender<HousekeeperEvent>,
+    pub state_tx: IpcSender<StateEvent>,
+    pub housekeeper_tx: IpcSender<HousekeeperEvent>,
     pub task_tx: Arc<Notify>,
-    pub queue_tx: mpsc::Sender<QueueEvent>,
-    pub report_tx: mpsc::Sender<ReportingEvent>,
-    pub broadcast_tx: Option<mpsc::Sender<BroadcastEvent>>,
+    pub job_tx: Arc<Notify>,
+    pub queue_tx: IpcSender<QueueEvent>,
+    pub report_tx: IpcSender<ReportingEvent>,
+    pub broadcast_tx: Option<IpcSender<BroadcastEvent>>,
 }
 
 pub struct TlsConnectors {
@@ -328,6 +372,9 @@ pub struct Core {
     pub network: Network,
     pub acme: AcmeProviders,
     pub oauth: OAuthConfig,
+    pub password: PasswordPolicy,
+    pub sessions: SessionRegistry,
+    pub attributes: AttributeSchema,
     pub smtp: SmtpConfig,
     pub jmap: JmapConfig,
     pub groupware: GroupwareConfig,
@@ -473,6 +520,8 @@ impl Default for Caches {
             dns_ipv6: CacheWithTtl::new(1024, 10 * 1024 * 1024),
             dns_tlsa: CacheWithTtl::new(1024, 10 * 1024 * 1024),
             dbs_mta_sts: CacheWithTtl::new(1024, 10 * 1024 * 1024),
+            helo_validation: CacheWithTtl::new(1024, 10 * 1024 * 1024),
+            sender_domain: CacheWithTtl::new(1024, 10 * 1024 * 1024),
         }
     }
 }
@@ -480,12 +529,14 @@ impl Default for Caches {
 #[cfg(feature = "test_mode")]
 impl Default for Ipc {
     fn default() -> Self {
+        let policy = ipc::IpcPolicy::default();
         Self {
-            state_tx: mpsc::channel(IPC_CHANNEL_BUFFER).0,
-            housekeeper_tx: mpsc::channel(IPC_CHANNEL_BUFFER).0,
+            state_tx: IpcSender::new(ipc::IpcChannel::State, &policy).0,
+            housekeeper_tx: IpcSender::new(ipc::IpcChannel::Housekeeper, &policy).0,
             task_tx: Default::default(),
-            queue_tx: mpsc::channel(IPC_CHANNEL_BUFFER).0,
-            report_tx: mpsc::channel(IPC_CHANNEL_BUFFER).0,
+            job_tx: Default::default(),
+            queue_tx: IpcSender::new(ipc::IpcChannel::Queue, &policy).0,
+            report_tx: IpcSender::new(ipc::IpcChannel::Report, &policy).0,
             broadcast_tx: None,
         }
     }
@@ -0,0 +1,179 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use hyper::header::CONTENT_TYPE;
+use trc::AcmeEvent;
+
+use super::{AcmeProvider, Http01Delegate};
+
+impl Http01Delegate {
+    pub async fn publish(
+        &self,
+        provider: &AcmeProvider,
+        domain: &str,
+        token: &str,
+        proof: &[u8],
+    ) -> trc::Result<()> {
+        if let Http01Delegate::Push {
+            url,
+            headers,
+            timeout,
+            tls_allow_invalid_certs,
+        } = self
+        {
+            let url = token_url(url, token);
+            let mut headers = headers.clone();
+            headers.insert(CONTENT_TYPE, "text/plain".parse().unwrap());
+
+            let response = reqwest::Client::builder()
+                .timeout(*timeout)
+                .danger_accept_invalid_certs(*tls_allow_invalid_certs)
+                .build()
+                .map_err(|err| {
+                    trc::EventType::Acme(AcmeEvent::HttpTokenPushFailed)
+                        .into_err()
+                        .ctx(trc::Key::Id, provider.id.to_string())
+                        .reason(err)
+                })?
+                .put(&url)
+                .headers(headers)
+                .body(proof.to_vec())
+                .send()
+                .await
+                .map_err(|err| {
+                    trc::EventType::Acme(AcmeEvent::HttpTokenPushFailed)
+                        .into_err()
+                        .ctx(trc::Key::Id, provider.id.to_string())
+                        .ctx(trc::Key::Hostname, domain.to_string())
+                        .ctx(trc::Key::Url, url.clone())
+                        .reason(err)
+                })?;
+
+            if !response.status().is_success() {
+                return Err(trc::EventType::Acme(AcmeEvent::HttpTokenPushFailed)
+                    .into_err()
+                    .ctx(trc::Key::Id, provider.id.to_string())
+                    .ctx(trc::Key::Hostname, domain.to_string())
+                    .ctx(trc::Key::Url, url)
+                    .ctx(trc::Key::Code, response.status().as_u16()));
+            }
+
+            trc::event!(
+                Acme(AcmeEvent::HttpTokenPushed),
+                Id = provider.id.to_string(),
+                Hostname = domain.to_string(),
+                Url = url,
+            );
+        }
+
+        // Make sure the token can be fetched the same way the ACME server will,
+        // failures are only reported as the check might not be possible from here
+        if let Err(reason) = self.check(domain, token, proof).await {
+            trc::event!(
+                Acme(AcmeEvent::HttpTokenCheckFailed),
+                Id = provider.id.to_string(),
+                Hostname = domain.to_string(),
+                Reason = reason,
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn unpublish(&self, provider: &AcmeProvider, token: &str) {
+        if let Http01Delegate::Push {
+            url,
+            headers,
+            timeout,
+            tls_allow_invalid_certs,
+        } = self
+        {
+            let url = token_url(url, token);
+            let result = match reqwest::Client::builder()
+                .timeout(*timeout)
+                .danger_accept_invalid_certs(*tls_allow_invalid_certs)
+                .build()
+            {
+                Ok(client) => client
+                    .delete(&url)
+                    .headers(headers.clone())
+                    .send()
+                    .await
+                    .map_err(|err| err.to_string())
+                    .and_then(|response| {
+                        if response.status().is_success() {
+                            Ok(())
+                        } else {
+                            Err(format!(
+                                "Request failed with code {}",
+                                response.status().as_u16()
+                            ))
+                        }
+                    }),
+                Err(err) => Err(err.to_string()),
+            };
+
+            if let Err(reason) = result {
+                trc::event!(
+                    Acme(AcmeEvent::HttpTokenDeletionFailed),
+                    Id = provider.id.to_string(),
+                    Url = url,
+                    Reason = reason,
+                );
+            }
+        }
+    }
+
+    async fn check(&self, domain: &str, token: &str, proof: &[u8]) -> Result<(), String> {
+        let response = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|err| err.to_string())?
+            .get(format!(
+                "http://{domain}/.well-known/acme-challenge/{token}"
+            ))
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        if let Http01Delegate::Alias { hostname } = self
+            && response.url().host_str() != Some(hostname.as_str())
+        {
+            return Err(format!(
+                "Challenge request was not redirected to {hostname}, ended at {}",
+                response.url()
+            ));
+        }
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Challenge request to {} failed with code {}",
+                response.url(),
+                response.status().as_u16()
+            ));
+        }
+
+        let url = response.url().to_string();
+        let body = response.bytes().await.map_err(|err| err.to_string())?;
+        if body.trim_ascii() == proof {
+            Ok(())
+        } else {
+            Err(format!(
+                "Challenge request to {url} returned an unexpected token"
+            ))
+        }
+    }
+}
+
+fn token_url(url: &str, token: &str) -> String {
+    if url.contains("{token}") {
+        url.replace("{token}", token)
+    } else {
+        format!("{}/{token}", url.trim_end_matches('/'))
+    }
+}
@@ -5,6 +5,7 @@
  */
 
 pub mod cache;
+pub mod delegate;
 pub mod directory;
 pub mod jose;
 pub mod order;
@@ -14,6 +15,7 @@ use std::{fmt::Debug, sync::Arc, time::Duration};
 
 use arc_swap::ArcSwap;
 use dns_update::DnsUpdater;
+use hyper::HeaderMap;
 use rustls::sign::CertifiedKey;
 
 use crate::Server;
@@ -40,7 +42,9 @@ pub struct EabSettings {
 
 #[derive(Clone)]
 pub enum ChallengeSettings {
-    Http01,
+    Http01 {
+        delegate: Option<Http01Delegate>,
+    },
     TlsAlpn01,
     Dns01 {
         updater: DnsUpdater,
@@ -51,6 +55,21 @@ pub enum ChallengeSettings {
     },
 }
 
+#[derive(Clone)]
+pub enum Http01Delegate {
+    // Port 80 is terminated elsewhere, tokens are uploaded to that web server
+    Push {
+        url: String,
+        headers: HeaderMap,
+        timeout: Duration,
+        tls_allow_invalid_certs: bool,
+    },
+    // The web server terminating port 80 redirects challenges to this hostname
+    Alias {
+        hostname: String,
+    },
+}
+
 pub struct StaticResolver {
     pub key: Option<Arc<CertifiedKey>>,
 }
@@ -122,14 +141,14 @@ impl Server {
             .acme
             .providers
             .values()
-            .any(|p| matches!(p.challenge, ChallengeSettings::Http01))
+            .any(|p| matches!(p.challenge, ChallengeSettings::Http01 { .. }))
     }
 }
 
 impl ChallengeSettings {
     pub fn challenge_type(&self) -> ChallengeType {
         match self {
-            ChallengeSettings::Http01 => ChallengeType::Http01,
+            ChallengeSettings::Http01 { .. } => ChallengeType::Http01,
             ChallengeSettings::TlsAlpn01 => ChallengeType::TlsAlpn01,
             ChallengeSettings::Dns01 { .. } => ChallengeType::Dns01,
         }
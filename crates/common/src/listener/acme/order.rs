@@ -177,7 +177,7 @@ impl Server {
         url: &String,
     ) -> trc::Result<()> {
         let auth = account.auth(url).await?;
-        let (domain, challenge_url) = match auth.status {
+        let (domain, challenge_url, token) = match auth.status {
             AuthStatus::Pending => {
                 let Identifier::Dns(domain) = auth.identifier;
                 let challenge_type = provider.challenge.challenge_type();
@@ -223,17 +223,20 @@ impl Server {
                             )
                             .await?;
                     }
-                    ChallengeSettings::Http01 => {
+                    ChallengeSettings::Http01 { delegate } => {
+                        let proof = account.http_proof(challenge)?;
                         self.in_memory_store()
                             .key_set(
-                                KeyValue::with_prefix(
-                                    KV_ACME,
-                                    &challenge.token,
-                                    account.http_proof(challenge)?,
-                                )
-                                .expires(3600),
+                                KeyValue::with_prefix(KV_ACME, &challenge.token, proof.clone())
+                                    .expires(3600),
                             )
                             .await?;
+
+                        if let Some(delegate) = delegate {
+                            delegate
+                                .publish(provider, &domain, &challenge.token, &proof)
+                                .await?;
+                        }
                     }
                     ChallengeSettings::Dns01 {
                         updater,
@@ -343,7 +346,7 @@ impl Server {
                 }
 
                 account.challenge(&challenge.url).await?;
-                (domain, challenge.url.clone())
+                (domain, challenge.url.clone(), challenge.token.clone())
             }
             AuthStatus::Valid => return Ok(()),
             _ => {
@@ -354,6 +357,29 @@ impl Server {
             }
         };
 
+        let result = self
+            .poll_auth(provider, account, url, &domain, &challenge_url)
+            .await;
+
+        // Remove tokens uploaded to other web servers
+        if let ChallengeSettings::Http01 {
+            delegate: Some(delegate),
+        } = &provider.challenge
+        {
+            delegate.unpublish(provider, &token).await;
+        }
+
+        result
+    }
+
+    async fn poll_auth(
+        &self,
+        provider: &AcmeProvider,
+        account: &Account,
+        url: &str,
+        domain: &str,
+        challenge_url: &str,
+    ) -> trc::Result<()> {
         for i in 0u64..5 {
             tokio::time::sleep(Duration::from_secs(1u64 << i)).await;
             let auth = account.auth(url).await?;
@@ -366,7 +392,7 @@ impl Server {
                         Total = i,
                     );
 
-                    account.challenge(&challenge_url).await?
+                    account.challenge(challenge_url).await?
                 }
                 AuthStatus::Valid => {
                     trc::event!(
@@ -388,7 +414,7 @@ impl Server {
         Err(EventType::Acme(AcmeEvent::AuthTooManyAttempts)
             .into_err()
             .ctx(trc::Key::Id, provider.id.to_string())
-            .ctx(trc::Key::Hostname, domain))
+            .ctx(trc::Key::Hostname, domain.to_string()))
     }
 }
 
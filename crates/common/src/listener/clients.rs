@@ -0,0 +1,239 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    net::IpAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU8, AtomicU64, Ordering},
+    },
+};
+
+use ahash::AHashMap;
+use parking_lot::Mutex;
+use serde::Serialize;
+use store::write::now;
+use tokio::sync::Notify;
+
+use crate::{Server, config::server::ServerProtocol};
+
+// Registry of the IMAP and POP3 sessions connected to this node
+#[derive(Default)]
+pub struct ConnectedClients {
+    sessions: Mutex<AHashMap<u64, Arc<ConnectedClient>>>,
+}
+
+pub struct ConnectedClient {
+    pub session_id: u64,
+    pub protocol: ServerProtocol,
+    pub remote_ip: IpAddr,
+    pub connected_at: u64,
+    pub last_activity: AtomicU64,
+    state: AtomicU8,
+    account: Mutex<Option<ClientAccount>>,
+    mailbox: Mutex<Option<String>>,
+    disconnect: Notify,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientAccount {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClientState {
+    NotAuthenticated = 0,
+    Authenticated = 1,
+    Selected = 2,
+    Idle = 3,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectedClientInfo {
+    pub session_id: u64,
+    pub protocol: &'static str,
+    pub remote_ip: IpAddr,
+    pub account_id: Option<u32>,
+    pub account_name: Option<String>,
+    pub state: ClientState,
+    pub mailbox: Option<String>,
+    pub connected_at: u64,
+    pub idle_time: u64,
+}
+
+// Removes the session from the registry when dropped
+pub struct ClientHandle {
+    server: Server,
+    client: Arc<ConnectedClient>,
+}
+
+impl ConnectedClients {
+    pub fn get(&self, session_id: u64) -> Option<Arc<ConnectedClient>> {
+        self.sessions.lock().get(&session_id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<ConnectedClientInfo> {
+        let mut clients = self
+            .sessions
+            .lock()
+            .values()
+            .map(|client| client.info())
+            .collect::<Vec<_>>();
+        clients.sort_unstable_by_key(|client| client.connected_at);
+        clients
+    }
+
+    pub fn disconnect(&self, session_id: u64) -> bool {
+        if let Some(client) = self.get(session_id) {
+            client.disconnect();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Disconnects all sessions matching the filter, returning the number of sessions affected
+    pub fn disconnect_all(&self, filter: impl Fn(&ConnectedClient) -> bool) -> usize {
+        let sessions = self.sessions.lock();
+        let mut count = 0;
+        for client in sessions.values().filter(|client| filter(client)) {
+            client.disconnect();
+            count += 1;
+        }
+        count
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.lock().is_empty()
+    }
+}
+
+impl ConnectedClient {
+    pub fn state(&self) -> ClientState {
+        match self.state.load(Ordering::Relaxed) {
+            1 => ClientState::Authenticated,
+            2 => ClientState::Selected,
+            3 => ClientState::Idle,
+            _ => ClientState::NotAuthenticated,
+        }
+    }
+
+    pub fn account(&self) -> Option<ClientAccount> {
+        self.account.lock().clone()
+    }
+
+    pub fn mailbox(&self) -> Option<String> {
+        self.mailbox.lock().clone()
+    }
+
+    pub fn disconnect(&self) {
+        self.disconnect.notify_one();
+    }
+
+    pub fn info(&self) -> ConnectedClientInfo {
+        let account = self.account();
+        ConnectedClientInfo {
+            session_id: self.session_id,
+            protocol: self.protocol.as_str(),
+            remote_ip: self.remote_ip,
+            account_id: account.as_ref().map(|account| account.id),
+            account_name: account.map(|account| account.name),
+            state: self.state(),
+            mailbox: self.mailbox(),
+            connected_at: self.connected_at,
+            idle_time: now().saturating_sub(self.last_activity.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl ClientHandle {
+    pub fn new(
+        server: &Server,
+        session_id: u64,
+        protocol: ServerProtocol,
+        remote_ip: IpAddr,
+    ) -> Self {
+        let connected_at = now();
+        let client = Arc::new(ConnectedClient {
+            session_id,
+            protocol,
+            remote_ip,
+            connected_at,
+            last_activity: AtomicU64::new(connected_at),
+            state: AtomicU8::new(ClientState::NotAuthenticated as u8),
+            account: Mutex::new(None),
+            mailbox: Mutex::new(None),
+            disconnect: Notify::new(),
+        });
+        server
+            .inner
+            .data
+            .connected_clients
+            .sessions
+            .lock()
+            .insert(session_id, client.clone());
+
+        ClientHandle {
+            server: server.clone(),
+            client,
+        }
+    }
+
+    pub fn touch(&self) {
+        self.client.last_activity.store(now(), Ordering::Relaxed);
+    }
+
+    pub fn set_account(&self, id: u32, name: impl Into<String>) {
+        *self.client.account.lock() = Some(ClientAccount {
+            id,
+            name: name.into(),
+        });
+        self.set_state(ClientState::Authenticated);
+    }
+
+    pub fn clear_account(&self) {
+        *self.client.account.lock() = None;
+        *self.client.mailbox.lock() = None;
+        self.set_state(ClientState::NotAuthenticated);
+    }
+
+    pub fn set_state(&self, state: ClientState) {
+        self.client.state.store(state as u8, Ordering::Relaxed);
+    }
+
+    pub fn set_mailbox(&self, mailbox: Option<String>) {
+        self.set_state(if mailbox.is_some() {
+            ClientState::Selected
+        } else {
+            ClientState::Authenticated
+        });
+        *self.client.mailbox.lock() = mailbox;
+    }
+
+    // Resolves when an administrator requests the session to be closed
+    pub async fn disconnected(&self) {
+        self.client.disconnect.notified().await
+    }
+}
+
+impl Drop for ClientHandle {
+    fn drop(&mut self) {
+        self.server
+            .inner
+            .data
+            .connected_clients
+            .sessions
+            .lock()
+            .remove(&self.client.session_id);
+    }
+}
@@ -0,0 +1,441 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{fmt::Write, time::Duration};
+
+use ahash::AHashSet;
+use sha2::{Digest, Sha256};
+
+use crate::expr::{V_TLS_JA3, V_TLS_JA4, Variable};
+
+use super::{ServerInstance, SessionStream, TcpAcceptor};
+
+const MAX_CLIENT_HELLO_SIZE: usize = 16384;
+const PEEK_RETRIES: usize = 20;
+const PEEK_INTERVAL: Duration = Duration::from_millis(25);
+const PEEK_TIMEOUT: Duration = Duration::from_secs(5);
+
+const EXT_SERVER_NAME: u16 = 0x0000;
+const EXT_SUPPORTED_GROUPS: u16 = 0x000a;
+const EXT_EC_POINT_FORMATS: u16 = 0x000b;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 0x000d;
+const EXT_ALPN: u16 = 0x0010;
+const EXT_SUPPORTED_VERSIONS: u16 = 0x002b;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsFingerprint {
+    pub ja3: String,
+    pub ja4: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ClientHelloResult {
+    Complete(TlsFingerprint),
+    Incomplete,
+    Invalid,
+}
+
+#[derive(Default)]
+struct ClientHello {
+    version: u16,
+    ciphers: Vec<u16>,
+    extensions: Vec<u16>,
+    groups: Vec<u16>,
+    point_formats: Vec<u8>,
+    signature_algorithms: Vec<u16>,
+    supported_versions: Vec<u16>,
+    alpn: Option<Vec<u8>>,
+    has_sni: bool,
+}
+
+impl ServerInstance {
+    // Peeks at the ClientHello without consuming it, so the TLS handshake
+    // can proceed as usual afterwards.
+    pub async fn tls_fingerprint<T: SessionStream>(&self, stream: &T) -> Option<TlsFingerprint> {
+        if !matches!(
+            self.acceptor,
+            TcpAcceptor::Tls {
+                fingerprint: true,
+                ..
+            }
+        ) {
+            return None;
+        }
+
+        // Peeking waits for data, so a client that never sends its ClientHello
+        // must not hold the session here
+        tokio::time::timeout(PEEK_TIMEOUT, async {
+            let mut buf = vec![0u8; MAX_CLIENT_HELLO_SIZE];
+            let mut last_len = 0;
+            for _ in 0..PEEK_RETRIES {
+                let len = stream.peek_bytes(&mut buf).await.ok()?;
+                if len == 0 {
+                    return None;
+                } else if len != last_len {
+                    match TlsFingerprint::parse(&buf[..len]) {
+                        ClientHelloResult::Complete(fingerprint) => return Some(fingerprint),
+                        ClientHelloResult::Invalid => return None,
+                        ClientHelloResult::Incomplete if len == buf.len() => return None,
+                        ClientHelloResult::Incomplete => {}
+                    }
+                    last_len = len;
+                }
+
+                tokio::time::sleep(PEEK_INTERVAL).await;
+            }
+
+            None
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+}
+
+impl TlsFingerprint {
+    pub fn log(&self, instance: &ServerInstance, session_id: u64) {
+        trc::event!(
+            Tls(trc::TlsEvent::ClientFingerprint),
+            ListenerId = instance.id.clone(),
+            SpanId = session_id,
+            Id = self.ja4.clone(),
+            Details = self.ja3.clone(),
+        );
+    }
+
+    pub fn matches(&self, list: &AHashSet<String>) -> bool {
+        list.contains(&self.ja4) || list.contains(&self.ja3)
+    }
+
+    pub fn resolve_variable(&self, variable: u32) -> Variable<'_> {
+        match variable {
+            V_TLS_JA3 => self.ja3.as_str().into(),
+            V_TLS_JA4 => self.ja4.as_str().into(),
+            _ => Variable::default(),
+        }
+    }
+
+    fn parse(data: &[u8]) -> ClientHelloResult {
+        // Reassemble the handshake message from one or more TLS records
+        let mut message = Vec::new();
+        let mut pos = 0;
+        let mut message_len = None;
+
+        loop {
+            let Some(header) = data.get(pos..pos + 5) else {
+                return ClientHelloResult::Incomplete;
+            };
+            if header[0] != 0x16 || header[1] != 0x03 {
+                return ClientHelloResult::Invalid;
+            }
+            let record_len = u16::from_be_bytes([header[3], header[4]]) as usize;
+            let Some(record) = data.get(pos + 5..pos + 5 + record_len) else {
+                return ClientHelloResult::Incomplete;
+            };
+            message.extend_from_slice(record);
+            pos += 5 + record_len;
+
+            if message_len.is_none() && message.len() >= 4 {
+                if message[0] != 0x01 {
+                    return ClientHelloResult::Invalid;
+                }
+                message_len = Some(u32::from_be_bytes([0, message[1], message[2], message[3]]));
+            }
+            if let Some(message_len) = message_len
+                && message.len() >= message_len as usize + 4
+            {
+                message.truncate(message_len as usize + 4);
+                break;
+            }
+        }
+
+        ClientHello::parse(&message[4..])
+            .map(|hello| ClientHelloResult::Complete(hello.fingerprint()))
+            .unwrap_or(ClientHelloResult::Invalid)
+    }
+}
+
+impl ClientHello {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader { data, pos: 0 };
+        let mut hello = ClientHello {
+            version: reader.u16()?,
+            ..Default::default()
+        };
+
+        // Skip random and session id
+        reader.bytes(32)?;
+        let session_id_len = reader.u8()? as usize;
+        reader.bytes(session_id_len)?;
+
+        let ciphers_len = reader.u16()? as usize;
+        let mut ciphers = Reader {
+            data: reader.bytes(ciphers_len)?,
+            pos: 0,
+        };
+        while let Some(cipher) = ciphers.u16() {
+            if !is_grease(cipher) {
+                hello.ciphers.push(cipher);
+            }
+        }
+
+        let compression_len = reader.u8()? as usize;
+        reader.bytes(compression_len)?;
+
+        // Extensions are optional
+        let Some(extensions_len) = reader.u16() else {
+            return Some(hello);
+        };
+        let mut extensions = Reader {
+            data: reader.bytes(extensions_len as usize)?,
+            pos: 0,
+        };
+        while let Some(typ) = extensions.u16() {
+            let len = extensions.u16()? as usize;
+            let mut ext = Reader {
+                data: extensions.bytes(len)?,
+                pos: 0,
+            };
+            if is_grease(typ) {
+                continue;
+            }
+            hello.extensions.push(typ);
+
+            match typ {
+                EXT_SERVER_NAME => {
+                    hello.has_sni = true;
+                }
+                EXT_SUPPORTED_GROUPS => {
+                    ext.u16()?;
+                    while let Some(group) = ext.u16() {
+                        if !is_grease(group) {
+                            hello.groups.push(group);
+                        }
+                    }
+                }
+                EXT_EC_POINT_FORMATS => {
+                    let len = ext.u8()? as usize;
+                    hello.point_formats.extend_from_slice(ext.bytes(len)?);
+                }
+                EXT_SIGNATURE_ALGORITHMS => {
+                    ext.u16()?;
+                    while let Some(algorithm) = ext.u16() {
+                        if !is_grease(algorithm) {
+                            hello.signature_algorithms.push(algorithm);
+                        }
+                    }
+                }
+                EXT_ALPN => {
+                    ext.u16()?;
+                    let len = ext.u8()? as usize;
+                    hello.alpn = Some(ext.bytes(len)?.to_vec());
+                }
+                EXT_SUPPORTED_VERSIONS => {
+                    ext.u8()?;
+                    while let Some(version) = ext.u16() {
+                        if !is_grease(version) {
+                            hello.supported_versions.push(version);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(hello)
+    }
+
+    fn fingerprint(&self) -> TlsFingerprint {
+        TlsFingerprint {
+            ja3: self.ja3(),
+            ja4: self.ja4(),
+        }
+    }
+
+    fn ja3(&self) -> String {
+        let text = format!(
+            "{},{},{},{},{}",
+            self.version,
+            join(self.ciphers.iter(), "-"),
+            join(self.extensions.iter(), "-"),
+            join(self.groups.iter(), "-"),
+            join(self.point_formats.iter(), "-"),
+        );
+
+        format!("{:x}", md5::compute(text.as_bytes()))
+    }
+
+    fn ja4(&self) -> String {
+        let version = match self
+            .supported_versions
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(self.version)
+        {
+            0x0304 => "13",
+            0x0303 => "12",
+            0x0302 => "11",
+            0x0301 => "10",
+            0x0300 => "s3",
+            0x0002 => "s2",
+            0xfeff => "d1",
+            0xfefd => "d2",
+            0xfefc => "d3",
+            _ => "00",
+        };
+        let alpn = match self.alpn.as_deref() {
+            Some([first, .., last])
+                if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() =>
+            {
+                format!("{}{}", *first as char, *last as char)
+            }
+            Some([first, .., last]) => format!("{:x}{:x}", first >> 4, last & 0x0f),
+            Some([single]) if single.is_ascii_alphanumeric() => {
+                format!("{}{}", *single as char, *single as char)
+            }
+            Some([single]) => format!("{:x}{:x}", single >> 4, single & 0x0f),
+            _ => "00".to_string(),
+        };
+
+        let mut ciphers = self.ciphers.clone();
+        ciphers.sort_unstable();
+        let mut extensions = self
+            .extensions
+            .iter()
+            .copied()
+            .filter(|ext| *ext != EXT_SERVER_NAME && *ext != EXT_ALPN)
+            .collect::<Vec<_>>();
+        extensions.sort_unstable();
+
+        let mut extensions_text = join_hex(&extensions);
+        if !self.signature_algorithms.is_empty() {
+            extensions_text.push('_');
+            extensions_text.push_str(&join_hex(&self.signature_algorithms));
+        }
+
+        format!(
+            "t{version}{}{:02}{:02}{alpn}_{}_{}",
+            if self.has_sni { 'd' } else { 'i' },
+            self.ciphers.len().min(99),
+            self.extensions.len().min(99),
+            truncated_hash(&ciphers, &join_hex(&ciphers)),
+            truncated_hash(&extensions, &extensions_text),
+        )
+    }
+}
+
+struct Reader<'x> {
+    data: &'x [u8],
+    pos: usize,
+}
+
+impl<'x> Reader<'x> {
+    fn bytes(&mut self, len: usize) -> Option<&'x [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+fn is_grease(value: u16) -> bool {
+    value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+}
+
+fn join<T: std::fmt::Display>(items: impl Iterator<Item = T>, separator: &str) -> String {
+    let mut result = String::new();
+    for (pos, item) in items.enumerate() {
+        if pos > 0 {
+            result.push_str(separator);
+        }
+        let _ = write!(result, "{item}");
+    }
+    result
+}
+
+fn join_hex(items: &[u16]) -> String {
+    join(items.iter().map(|item| format!("{item:04x}")), ",")
+}
+
+fn truncated_hash(items: &[u16], text: &str) -> String {
+    if !items.is_empty() {
+        let mut hash = format!("{:x}", Sha256::digest(text.as_bytes()));
+        hash.truncate(12);
+        hash
+    } else {
+        "000000000000".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClientHelloResult, TlsFingerprint};
+
+    #[test]
+    fn tls_client_hello_fingerprint() {
+        let mut hello = Vec::new();
+        hello.extend_from_slice(&[0x03, 0x03]);
+        hello.extend_from_slice(&[0u8; 32]);
+        hello.push(0);
+        // Ciphers: GREASE, TLS_AES_128_GCM_SHA256, TLS_AES_256_GCM_SHA384
+        hello.extend_from_slice(&[0x00, 0x06, 0x0a, 0x0a, 0x13, 0x01, 0x13, 0x02]);
+        hello.extend_from_slice(&[0x01, 0x00]);
+
+        let mut extensions = Vec::new();
+        // SNI
+        extensions.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        // Supported groups: x25519
+        extensions.extend_from_slice(&[0x00, 0x0a, 0x00, 0x04, 0x00, 0x02, 0x00, 0x1d]);
+        // Point formats: uncompressed
+        extensions.extend_from_slice(&[0x00, 0x0b, 0x00, 0x02, 0x01, 0x00]);
+        // Signature algorithms: ecdsa_secp256r1_sha256
+        extensions.extend_from_slice(&[0x00, 0x0d, 0x00, 0x04, 0x00, 0x02, 0x04, 0x03]);
+        // ALPN: smtp
+        extensions.extend_from_slice(&[0x00, 0x10, 0x00, 0x07, 0x00, 0x05, 0x04]);
+        extensions.extend_from_slice(b"smtp");
+        // Supported versions: TLS 1.3, TLS 1.2
+        extensions.extend_from_slice(&[0x00, 0x2b, 0x00, 0x05, 0x04, 0x03, 0x04, 0x03, 0x03]);
+        hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        hello.extend_from_slice(&extensions);
+
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&((hello.len() + 4) as u16).to_be_bytes());
+        record.push(0x01);
+        record.extend_from_slice(&(hello.len() as u32).to_be_bytes()[1..]);
+        record.extend_from_slice(&hello);
+
+        assert_eq!(
+            TlsFingerprint::parse(&record[..record.len() - 1]),
+            ClientHelloResult::Incomplete
+        );
+        assert_eq!(
+            TlsFingerprint::parse(b"EHLO example.org\r\n"),
+            ClientHelloResult::Invalid
+        );
+
+        let ClientHelloResult::Complete(fingerprint) = TlsFingerprint::parse(&record) else {
+            panic!("Failed to parse ClientHello");
+        };
+        assert_eq!(
+            fingerprint.ja3,
+            format!("{:x}", md5::compute(b"771,4865-4866,0-10-11-13-16-43,29,0"))
+        );
+        assert!(
+            fingerprint.ja4.starts_with("t13d0206sp_"),
+            "{}",
+            fingerprint.ja4
+        );
+    }
+}
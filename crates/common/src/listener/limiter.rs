@@ -20,6 +20,25 @@ pub struct InFlight {
     concurrent: Arc<AtomicU64>,
 }
 
+#[derive(Debug, Clone)]
+pub struct BufferLimiter {
+    pub max_concurrent: u64,
+    pub max_bytes: u64,
+    pub concurrent: Arc<AtomicU64>,
+    pub bytes: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessageLimiter {
+    pub global: BufferLimiter,
+    pub listener: BufferLimiter,
+}
+
+pub struct BufferInFlight {
+    limiter: MessageLimiter,
+    bytes: u64,
+}
+
 pub enum LimiterResult {
     Allowed(InFlight),
     Forbidden,
@@ -61,6 +80,119 @@ impl ConcurrencyLimiter {
     }
 }
 
+impl BufferLimiter {
+    pub fn new(max_concurrent: Option<u64>, max_bytes: Option<u64>) -> Self {
+        BufferLimiter {
+            max_concurrent: max_concurrent.unwrap_or(u64::MAX),
+            max_bytes: max_bytes.unwrap_or(u64::MAX),
+            concurrent: Arc::new(0.into()),
+            bytes: Arc::new(0.into()),
+        }
+    }
+
+    pub fn num_concurrent(&self) -> u64 {
+        self.concurrent.load(Ordering::Relaxed)
+    }
+
+    pub fn num_bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    fn try_add(counter: &AtomicU64, max: u64, value: u64) -> bool {
+        if counter
+            .fetch_add(value, Ordering::Relaxed)
+            .saturating_add(value)
+            <= max
+        {
+            true
+        } else {
+            counter.fetch_sub(value, Ordering::Relaxed);
+            false
+        }
+    }
+}
+
+impl Default for BufferLimiter {
+    fn default() -> Self {
+        BufferLimiter::new(None, None)
+    }
+}
+
+impl MessageLimiter {
+    // Returns the exceeded limit if either the global or the listener budget is exhausted
+    pub fn is_allowed(&self, bytes: u64) -> Result<BufferInFlight, u64> {
+        for (pos, limiter) in [&self.global, &self.listener].into_iter().enumerate() {
+            if !BufferLimiter::try_add(&limiter.concurrent, limiter.max_concurrent, 1) {
+                if pos == 1 {
+                    self.global.concurrent.fetch_sub(1, Ordering::Relaxed);
+                }
+                return Err(limiter.max_concurrent);
+            }
+        }
+
+        let mut in_flight = BufferInFlight {
+            limiter: self.clone(),
+            bytes: 0,
+        };
+        in_flight.try_resize(bytes)?;
+        Ok(in_flight)
+    }
+}
+
+impl BufferInFlight {
+    pub fn try_resize(&mut self, bytes: u64) -> Result<(), u64> {
+        if bytes > self.bytes {
+            let delta = bytes - self.bytes;
+            let limiter = &self.limiter;
+            if !BufferLimiter::try_add(&limiter.global.bytes, limiter.global.max_bytes, delta) {
+                return Err(limiter.global.max_bytes);
+            } else if !BufferLimiter::try_add(
+                &limiter.listener.bytes,
+                limiter.listener.max_bytes,
+                delta,
+            ) {
+                limiter.global.bytes.fetch_sub(delta, Ordering::Relaxed);
+                return Err(limiter.listener.max_bytes);
+            }
+        } else {
+            let delta = self.bytes - bytes;
+            self.limiter
+                .global
+                .bytes
+                .fetch_sub(delta, Ordering::Relaxed);
+            self.limiter
+                .listener
+                .bytes
+                .fetch_sub(delta, Ordering::Relaxed);
+        }
+        self.bytes = bytes;
+        self.update_gauges();
+
+        Ok(())
+    }
+
+    fn update_gauges(&self) {
+        trc::Collector::update_gauge(
+            trc::MetricType::SmtpMessagesInFlight,
+            self.limiter.global.num_concurrent(),
+        );
+        trc::Collector::update_gauge(
+            trc::MetricType::SmtpMessagesBuffered,
+            self.limiter.global.num_bytes(),
+        );
+    }
+}
+
+impl Drop for BufferInFlight {
+    fn drop(&mut self) {
+        for limiter in [&self.limiter.global, &self.limiter.listener] {
+            limiter.concurrent.fetch_sub(1, Ordering::Relaxed);
+            limiter.bytes.fetch_sub(self.bytes, Ordering::Relaxed);
+        }
+        self.update_gauges();
+    }
+}
+
 impl InFlight {
     pub fn num_concurrent(&self) -> u64 {
         self.concurrent.load(Ordering::Relaxed)
@@ -76,3 +208,74 @@ impl From<LimiterResult> for Option<InFlight> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BufferLimiter, MessageLimiter};
+
+    #[test]
+    fn message_limiter_bytes() {
+        let limiter = MessageLimiter {
+            global: BufferLimiter::new(None, Some(100)),
+            listener: BufferLimiter::new(None, Some(50)),
+        };
+
+        // Growing and shrinking a buffer updates both budgets
+        let mut in_flight = limiter.is_allowed(10).unwrap();
+        assert_eq!(limiter.global.num_bytes(), 10);
+        assert_eq!(limiter.listener.num_bytes(), 10);
+        in_flight.try_resize(40).unwrap();
+        assert_eq!(limiter.global.num_bytes(), 40);
+        assert_eq!(limiter.listener.num_bytes(), 40);
+        in_flight.try_resize(20).unwrap();
+        assert_eq!(limiter.global.num_bytes(), 20);
+        assert_eq!(limiter.listener.num_bytes(), 20);
+
+        // Exceeding the listener budget leaves both counters untouched
+        assert_eq!(in_flight.try_resize(60), Err(50));
+        assert_eq!(limiter.global.num_bytes(), 20);
+        assert_eq!(limiter.listener.num_bytes(), 20);
+
+        // The global budget is shared by all listeners
+        let other_listener = MessageLimiter {
+            global: limiter.global.clone(),
+            listener: BufferLimiter::default(),
+        };
+        let mut other_in_flight = other_listener.is_allowed(70).unwrap();
+        assert_eq!(limiter.global.num_bytes(), 90);
+        assert_eq!(other_in_flight.try_resize(90), Err(100));
+        assert!(other_listener.is_allowed(20).is_err());
+        assert_eq!(limiter.global.num_concurrent(), 2);
+        assert_eq!(limiter.global.num_bytes(), 90);
+
+        // Dropping a buffer releases its budget
+        drop(other_in_flight);
+        assert_eq!(limiter.global.num_bytes(), 20);
+        assert_eq!(limiter.global.num_concurrent(), 1);
+        drop(in_flight);
+        assert_eq!(limiter.global.num_bytes(), 0);
+        assert_eq!(limiter.listener.num_bytes(), 0);
+        assert_eq!(limiter.global.num_concurrent(), 0);
+        assert_eq!(limiter.listener.num_concurrent(), 0);
+    }
+
+    #[test]
+    fn message_limiter_concurrency() {
+        let limiter = MessageLimiter {
+            global: BufferLimiter::new(Some(2), None),
+            listener: BufferLimiter::new(Some(1), None),
+        };
+
+        // The listener limit is reached first, the global slot is given back
+        let in_flight = limiter.is_allowed(0).unwrap();
+        assert_eq!(limiter.is_allowed(0).err(), Some(1));
+        assert_eq!(limiter.global.num_concurrent(), 1);
+        assert_eq!(limiter.listener.num_concurrent(), 1);
+
+        // Slots become available once released
+        drop(in_flight);
+        assert_eq!(limiter.global.num_concurrent(), 0);
+        let _in_flight = limiter.is_allowed(0).unwrap();
+        assert_eq!(limiter.listener.num_concurrent(), 1);
+    }
+}
@@ -42,6 +42,7 @@ impl Listener {
             protocol: self.protocol,
             proxy_networks: self.proxy_networks,
             limiter: ConcurrencyLimiter::new(self.max_connections),
+            message_limiter: self.message_limiter,
             acceptor,
             shutdown_rx,
             span_id_gen: self.span_id_gen,
@@ -139,6 +140,19 @@ impl Listener {
                                                                             .proxied_address()
                                                                             .map(|addr| addr.source)
                                                                             .unwrap_or(remote_addr);
+                                                    if let Some(info) = stream.proxy_info() {
+                                                        trc::event!(
+                                                            Network(trc::NetworkEvent::ProxyInfo),
+                                                            ListenerId = instance.id.clone(),
+                                                            RemoteIp = remote_addr.ip(),
+                                                            RemotePort = remote_addr.port(),
+                                                            Hostname = info.sni,
+                                                            Details = info.alpn,
+                                                            Tls = info.client_tls,
+                                                            Strict = info.client_cert_verified,
+                                                            Value = info.client_cert_cn,
+                                                        );
+                                                    }
                                                     if let Some(session) = instance.build_session(stream, local_addr, remote_addr, &server) {
                                                         // Spawn session
                                                         manager.spawn(session, is_tls, enable_acme, span_start, span_end);
@@ -246,6 +260,7 @@ impl BuildSession for Arc<ServerInstance> {
                 remote_port,
                 protocol: self.protocol,
                 instance: self.clone(),
+                tls_fingerprint: None,
             }
             .into()
         } else {
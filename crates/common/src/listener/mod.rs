@@ -6,7 +6,7 @@
 
 use std::{borrow::Cow, net::IpAddr, sync::Arc, time::Instant};
 
-use compact_str::ToCompactString;
+use compact_str::{CompactString, ToCompactString};
 use rustls::ServerConfig;
 use std::fmt::Debug;
 use tokio::{
@@ -19,17 +19,24 @@ use utils::{config::ipmask::IpAddrMask, snowflake::SnowflakeIdGenerator};
 
 use crate::{
     Server,
+    auth::certificate::{ClientCertMapping, ClientCertificate},
     config::server::ServerProtocol,
     expr::{functions::ResolveVariable, *},
 };
 
-use self::limiter::{ConcurrencyLimiter, InFlight};
+use self::{
+    fingerprint::TlsFingerprint,
+    limiter::{ConcurrencyLimiter, InFlight, MessageLimiter},
+};
 
 pub mod acme;
 pub mod asn;
 pub mod blocked;
+pub mod clients;
+pub mod fingerprint;
 pub mod limiter;
 pub mod listen;
+pub mod shaper;
 pub mod stream;
 pub mod tls;
 
@@ -38,6 +45,7 @@ pub struct ServerInstance {
     pub protocol: ServerProtocol,
     pub acceptor: TcpAcceptor,
     pub limiter: ConcurrencyLimiter,
+    pub message_limiter: MessageLimiter,
     pub proxy_networks: Vec<IpAddrMask>,
     pub shutdown_rx: watch::Receiver<bool>,
     pub span_id_gen: Arc<SnowflakeIdGenerator>,
@@ -49,6 +57,8 @@ pub enum TcpAcceptor {
         config: Arc<ServerConfig>,
         acceptor: TlsAcceptor,
         implicit: bool,
+        client_cert: Option<ClientCertMapping>,
+        fingerprint: bool,
     },
     #[default]
     Plain,
@@ -74,11 +84,35 @@ pub struct SessionData<T: SessionStream> {
     pub session_id: u64,
     pub in_flight: InFlight,
     pub instance: Arc<ServerInstance>,
+    pub tls_fingerprint: Option<TlsFingerprint>,
 }
 
 pub trait SessionStream: AsyncRead + AsyncWrite + Unpin + 'static + Sync + Send {
     fn is_tls(&self) -> bool;
     fn tls_version_and_cipher(&self) -> (Cow<'static, str>, Cow<'static, str>);
+    fn proxy_info(&self) -> Option<ProxyInfo> {
+        None
+    }
+    fn client_certificate(&self) -> Option<ClientCertificate> {
+        None
+    }
+    fn peek_bytes(
+        &self,
+        _buf: &mut [u8],
+    ) -> impl std::future::Future<Output = std::io::Result<usize>> + Send {
+        async { Ok(0) }
+    }
+}
+
+// Details reported by the edge proxy using PROXY protocol v2 TLVs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxyInfo {
+    pub alpn: Option<CompactString>,
+    pub sni: Option<CompactString>,
+    pub client_tls: bool,
+    pub client_cert: bool,
+    pub client_cert_verified: bool,
+    pub client_cert_cn: Option<CompactString>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -105,6 +139,8 @@ pub trait SessionManager: Sync + Send + 'static + Clone {
             let session_id;
 
             if is_tls {
+                let tls_fingerprint = session.instance.tls_fingerprint(&session.stream).await;
+
                 match session
                     .instance
                     .acceptor
@@ -130,6 +166,10 @@ pub trait SessionManager: Sync + Send + 'static + Clone {
                             )
                             .send_with_metrics();
 
+                            if let Some(fingerprint) = &tls_fingerprint {
+                                fingerprint.log(&session.instance, session_id);
+                            }
+
                             manager
                                 .handle(SessionData {
                                     stream,
@@ -141,6 +181,7 @@ pub trait SessionManager: Sync + Send + 'static + Clone {
                                     session_id: session.session_id,
                                     in_flight: session.in_flight,
                                     instance: session.instance,
+                                    tls_fingerprint,
                                 })
                                 .await;
                         }
@@ -231,6 +272,16 @@ impl<T: SessionStream> ResolveVariable for SessionData<T> {
             V_LISTENER => self.instance.id.as_str().into(),
             V_PROTOCOL => self.protocol.as_str().into(),
             V_TLS => self.stream.is_tls().into(),
+            V_TLS_JA3 | V_TLS_JA4 => self
+                .tls_fingerprint
+                .as_ref()
+                .map(|fingerprint| fingerprint.resolve_variable(variable))
+                .unwrap_or_default(),
+            V_PROXY_ALPN | V_PROXY_SNI | V_PROXY_CLIENT_CERT | V_PROXY_CLIENT_CN => self
+                .stream
+                .proxy_info()
+                .map(|info| info.resolve_variable(variable))
+                .unwrap_or_default(),
             _ => crate::expr::Variable::default(),
         }
     }
@@ -240,6 +291,18 @@ impl<T: SessionStream> ResolveVariable for SessionData<T> {
     }
 }
 
+impl ProxyInfo {
+    pub fn resolve_variable(self, variable: u32) -> Variable<'static> {
+        match variable {
+            V_PROXY_ALPN => self.alpn.unwrap_or_default().into(),
+            V_PROXY_SNI => self.sni.unwrap_or_default().into(),
+            V_PROXY_CLIENT_CERT => self.client_cert.into(),
+            V_PROXY_CLIENT_CN => self.client_cert_cn.unwrap_or_default().into(),
+            _ => Variable::default(),
+        }
+    }
+}
+
 impl Debug for TcpAcceptor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -0,0 +1,64 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+const MAX_CHUNK_SIZE: usize = 16 * 1024;
+const MIN_CHUNK_SIZE: usize = 512;
+
+// Paces the output of a session to a maximum number of bytes per second
+#[derive(Debug)]
+pub struct BandwidthShaper {
+    rate: u64,
+    chunk_size: usize,
+    next_write: Mutex<Instant>,
+}
+
+impl BandwidthShaper {
+    pub fn new(rate: u64) -> Self {
+        BandwidthShaper {
+            rate: rate.max(1),
+            chunk_size: (rate as usize).clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE),
+            next_write: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub async fn write_all<W: AsyncWrite + Unpin>(
+        &self,
+        stream: &mut W,
+        bytes: &[u8],
+    ) -> std::io::Result<()> {
+        for chunk in bytes.chunks(self.chunk_size) {
+            self.throttle(chunk.len()).await;
+            stream.write_all(chunk).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn throttle(&self, bytes: usize) {
+        // Idle time does not accumulate credit, bursts are limited to one chunk
+        let now = Instant::now();
+        let write_at = {
+            let mut next_write = self
+                .next_write
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            let write_at = (*next_write).max(now);
+            *next_write = write_at + Duration::from_secs_f64(bytes as f64 / self.rate as f64);
+            write_at
+        };
+
+        if write_at > now {
+            tokio::time::sleep_until(write_at.into()).await;
+        }
+    }
+}
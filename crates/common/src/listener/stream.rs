@@ -13,7 +13,9 @@ use tokio::{
 };
 use tokio_rustls::server::TlsStream;
 
-use super::SessionStream;
+use crate::auth::certificate::ClientCertificate;
+
+use super::{ProxyInfo, SessionStream};
 
 impl SessionStream for TcpStream {
     fn is_tls(&self) -> bool {
@@ -23,6 +25,13 @@ impl SessionStream for TcpStream {
     fn tls_version_and_cipher(&self) -> (Cow<'static, str>, Cow<'static, str>) {
         (Cow::Borrowed(""), Cow::Borrowed(""))
     }
+
+    fn peek_bytes(
+        &self,
+        buf: &mut [u8],
+    ) -> impl std::future::Future<Output = std::io::Result<usize>> + Send {
+        self.peek(buf)
+    }
 }
 
 impl<T: SessionStream> SessionStream for TlsStream<T> {
@@ -62,6 +71,18 @@ impl<T: SessionStream> SessionStream for TlsStream<T> {
             .into(),
         )
     }
+
+    fn proxy_info(&self) -> Option<ProxyInfo> {
+        self.get_ref().0.proxy_info()
+    }
+
+    fn client_certificate(&self) -> Option<ClientCertificate> {
+        self.get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| ClientCertificate::parse(cert.as_ref()))
+    }
 }
 
 impl SessionStream for ProxiedStream<TcpStream> {
@@ -82,6 +103,25 @@ impl SessionStream for ProxiedStream<TcpStream> {
             })
             .unwrap_or((Cow::Borrowed("unknown"), Cow::Borrowed("unknown")))
     }
+
+    fn proxy_info(&self) -> Option<ProxyInfo> {
+        let header = self.proxy_header();
+        let ssl = header.ssl();
+        let info = ProxyInfo {
+            alpn: header
+                .alpn()
+                .map(|alpn| String::from_utf8_lossy(alpn).into()),
+            sni: header.authority().map(|sni| sni.to_lowercase().into()),
+            client_tls: ssl.as_ref().is_some_and(|ssl| ssl.client_ssl()),
+            client_cert: ssl
+                .as_ref()
+                .is_some_and(|ssl| ssl.client_cert_conn() || ssl.client_cert_sess()),
+            client_cert_verified: ssl.as_ref().is_some_and(|ssl| ssl.verify() == 0),
+            client_cert_cn: ssl.as_ref().and_then(|ssl| ssl.cn()).map(Into::into),
+        };
+
+        (info != ProxyInfo::default()).then_some(info)
+    }
 }
 
 #[derive(Default)]
@@ -6,13 +6,16 @@
 
 use std::{
     cmp::Ordering,
-    fmt::{self, Formatter},
+    fmt::{self, Formatter, Write as _},
+    fs::{File, OpenOptions},
+    io::Write,
     sync::Arc,
 };
 
 use ahash::AHashMap;
+use parking_lot::Mutex;
 use rustls::{
-    SupportedProtocolVersion,
+    KeyLog, SupportedProtocolVersion,
     server::{ClientHello, ResolvesServerCert},
     sign::CertifiedKey,
     version::{TLS12, TLS13},
@@ -20,7 +23,7 @@ use rustls::{
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio_rustls::{Accept, LazyConfigAcceptor};
 
-use crate::{Inner, Server};
+use crate::{Inner, Server, auth::certificate::ClientCertMapping};
 
 use super::{
     ServerInstance, SessionStream, TcpAcceptor, TcpAcceptorResult,
@@ -114,6 +117,7 @@ impl TcpAcceptor {
                 config,
                 acceptor,
                 implicit,
+                ..
             } if *implicit => match enable_acme {
                 None => TcpAcceptorResult::Tls(acceptor.accept(stream)),
                 Some(core) => {
@@ -190,6 +194,13 @@ impl TcpAcceptor {
     pub fn is_tls(&self) -> bool {
         matches!(self, TcpAcceptor::Tls { .. })
     }
+
+    pub fn client_cert_mapping(&self) -> Option<ClientCertMapping> {
+        match self {
+            TcpAcceptor::Tls { client_cert, .. } => *client_cert,
+            TcpAcceptor::Plain => None,
+        }
+    }
 }
 
 impl<IO> TcpAcceptorResult<IO>
@@ -209,3 +220,46 @@ impl std::fmt::Debug for CertificateResolver {
         f.debug_struct("CertificateResolver").finish()
     }
 }
+
+// Writes TLS session secrets in the NSS key log format, used by tools
+// such as Wireshark to decrypt captured traffic.
+pub struct KeyLogWriter {
+    file: Mutex<File>,
+}
+
+impl KeyLogWriter {
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map(|file| KeyLogWriter {
+                file: Mutex::new(file),
+            })
+    }
+}
+
+impl KeyLog for KeyLogWriter {
+    fn log(&self, label: &str, client_random: &[u8], secret: &[u8]) {
+        let mut line =
+            String::with_capacity(label.len() + (client_random.len() + secret.len()) * 2 + 3);
+        line.push_str(label);
+        line.push(' ');
+        for byte in client_random {
+            let _ = write!(line, "{byte:02x}");
+        }
+        line.push(' ');
+        for byte in secret {
+            let _ = write!(line, "{byte:02x}");
+        }
+        line.push('\n');
+
+        let _ = self.file.lock().write_all(line.as_bytes());
+    }
+}
+
+impl std::fmt::Debug for KeyLogWriter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyLogWriter").finish()
+    }
+}
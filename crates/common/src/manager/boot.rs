@@ -11,10 +11,13 @@ use super::{
     console::store_console,
 };
 use crate::{
-    Caches, Core, Data, IPC_CHANNEL_BUFFER, Inner, Ipc,
+    Caches, Core, Data, Inner, Ipc,
     config::{network::AsnGeoLookupConfig, server::Listeners, telemetry::Telemetry},
     core::BuildServer,
-    ipc::{BroadcastEvent, HousekeeperEvent, QueueEvent, ReportingEvent, StateEvent},
+    ipc::{
+        BroadcastEvent, HousekeeperEvent, IpcChannel, IpcPolicies, IpcSender, QueueEvent,
+        ReportingEvent, StateEvent,
+    },
 };
 use arc_swap::ArcSwap;
 use pwhash::sha512_crypt;
@@ -39,6 +42,7 @@ pub struct BootManager {
     pub inner: Arc<Inner>,
     pub servers: Listeners,
     pub ipc_rxs: IpcReceivers,
+    pub migrate_dry_run: bool,
 }
 
 pub struct IpcReceivers {
@@ -62,6 +66,7 @@ Options:
   -i, --import <PATH>              Import store data from a specific path
   -o, --console                    Open the store console
   -I, --init <PATH>                Initialize a new server at a specific path
+  -m, --migrate-dry-run            Report pending database migrations without applying them
   -h, --help                       Print help
   -V, --version                    Print version
 "#
@@ -179,6 +184,7 @@ impl BootManager {
     pub async fn init() -> Self {
         let mut config_path = std::env::var("CONFIG_PATH").ok();
         let mut import_export = StoreOp::None;
+        let mut migrate_dry_run = false;
 
         if config_path.is_none() {
             let mut args = std::env::args().skip(1);
@@ -219,6 +225,9 @@ impl BootManager {
                     ("console" | "o", None) => {
                         import_export = StoreOp::Console;
                     }
+                    ("migrate-dry-run" | "m", None) => {
+                        migrate_dry_run = true;
+                    }
                     (_, None) => {
                         failed(&format!("Unrecognized command '{key}', try '--help'."));
                     }
@@ -470,7 +479,10 @@ impl BootManager {
                     core.network.asn_geo_lookup,
                     AsnGeoLookupConfig::Resource { .. }
                 );
-                let (ipc, ipc_rxs) = build_ipc(!core.storage.pubsub.is_none());
+                let (ipc, ipc_rxs) = build_ipc_with_policies(
+                    &IpcPolicies::parse(&mut config),
+                    !core.storage.pubsub.is_none(),
+                );
                 let inner = Arc::new(Inner {
                     shared_core: ArcSwap::from_pointee(core),
                     data,
@@ -494,6 +506,7 @@ impl BootManager {
                     config,
                     servers,
                     ipc_rxs,
+                    migrate_dry_run,
                 }
             }
             StoreOp::Export(path) => {
@@ -534,12 +547,18 @@ impl BootManager {
 }
 
 pub fn build_ipc(has_pubsub: bool) -> (Ipc, IpcReceivers) {
+    build_ipc_with_policies(&IpcPolicies::default(), has_pubsub)
+}
+
+pub fn build_ipc_with_policies(policies: &IpcPolicies, has_pubsub: bool) -> (Ipc, IpcReceivers) {
     // Build ipc receivers
-    let (state_tx, state_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
-    let (housekeeper_tx, housekeeper_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
-    let (queue_tx, queue_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
-    let (report_tx, report_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
-    let (broadcast_tx, broadcast_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
+    let (state_tx, state_rx) = IpcSender::new(IpcChannel::State, &policies.state);
+    let (housekeeper_tx, housekeeper_rx) =
+        IpcSender::new(IpcChannel::Housekeeper, &policies.housekeeper);
+    let (queue_tx, queue_rx) = IpcSender::new(IpcChannel::Queue, &policies.queue);
+    let (report_tx, report_rx) = IpcSender::new(IpcChannel::Report, &policies.report);
+    let (broadcast_tx, broadcast_rx) =
+        IpcSender::new(IpcChannel::Broadcast, &policies.broadcast);
     (
         Ipc {
             state_tx,
@@ -548,6 +567,7 @@ pub fn build_ipc(has_pubsub: bool) -> (Ipc, IpcReceivers) {
             report_tx,
             broadcast_tx: has_pubsub.then_some(broadcast_tx),
             task_tx: Arc::new(Notify::new()),
+            job_tx: Arc::new(Notify::new()),
         },
         IpcReceivers {
             state_rx: Some(state_rx),
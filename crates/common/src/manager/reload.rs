@@ -4,10 +4,15 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 use ahash::AHashMap;
 use arc_swap::ArcSwap;
 use store::Stores;
-use utils::config::Config;
+use utils::{
+    config::Config,
+    map::bitmap::{Bitmap, BitmapItem},
+};
 
 use crate::{
     Core, Server,
@@ -15,7 +20,8 @@ use crate::{
         server::{Listeners, tls::parse_certificates},
         telemetry::Telemetry,
     },
-    listener::blocked::{BLOCKED_IP_KEY, BlockedIps},
+    ipc::HousekeeperEvent,
+    listener::blocked::{ALLOWED_IP_KEY, BLOCKED_IP_KEY, BlockedIps},
 };
 
 use super::config::{ConfigManager, Patterns};
@@ -24,8 +30,35 @@ pub struct ReloadResult {
     pub config: Config,
     pub new_core: Option<Core>,
     pub tracers: Option<Telemetry>,
+    pub scopes: Bitmap<ReloadScope>,
+    pub fingerprint: Option<ConfigFingerprint>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ReloadScope {
+    Core = 0,
+    SpamFilter = 1,
+    Queue = 2,
+    Acme = 3,
+    Telemetry = 4,
+    Certificates = 5,
+    BlockedIps = 6,
+    Listeners = 7,
+    None = 8,
+}
+
+// Scopes whose settings are stored in the shared core
+const CORE_SCOPES: [ReloadScope; 5] = [
+    ReloadScope::Core,
+    ReloadScope::SpamFilter,
+    ReloadScope::Queue,
+    ReloadScope::Acme,
+    ReloadScope::Telemetry,
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigFingerprint([u64; ReloadScope::None as usize]);
+
 impl Server {
     pub async fn reload_blocked_ips(&self) -> trc::Result<ReloadResult> {
         let mut config = self
@@ -64,12 +97,37 @@ impl Server {
             config,
             new_core: core.into(),
             tracers: None,
+            scopes: Bitmap::from(ReloadScope::Core),
+            fingerprint: None,
         })
     }
 
     pub async fn reload(&self) -> trc::Result<ReloadResult> {
+        self.reload_scoped(None).await
+    }
+
+    pub async fn reload_scoped(
+        &self,
+        scopes: Option<Bitmap<ReloadScope>>,
+    ) -> trc::Result<ReloadResult> {
         let mut config = self.core.storage.config.build_config("").await?;
 
+        // Unless requested explicitly, only reload the subsystems whose settings changed
+        let mut fingerprint = ConfigFingerprint::new(&config);
+        let scopes = if let Some(scopes) = scopes {
+            // Subsystems left out keep their old fingerprint so their changes are not lost
+            let mut current = self.inner.data.config_fingerprint.lock().clone();
+            current.update(&fingerprint, scopes);
+            fingerprint = current;
+            scopes
+        } else {
+            self.inner
+                .data
+                .config_fingerprint
+                .lock()
+                .changed(&fingerprint)
+        };
+
         // Load stores
         let mut stores = Stores {
             stores: self.core.storage.stores.clone(),
@@ -89,55 +147,107 @@ impl Server {
             return Ok(config.into());
         }
 
-        // Build manager
-        let manager = ConfigManager {
-            cfg_local: ArcSwap::from_pointee(
-                self.core.storage.config.cfg_local.load().as_ref().clone(),
-            ),
-            cfg_local_path: self.core.storage.config.cfg_local_path.clone(),
-            cfg_local_patterns: Patterns::parse(&mut config).into(),
-            cfg_store: config
-                .value("storage.data")
-                .and_then(|id| stores.stores.get(id))
-                .cloned()
-                .unwrap_or_default(),
-        };
-
         // Parse settings and build shared core
-        let core = Box::pin(Core::parse(&mut config, stores, manager)).await;
-        if !config.errors.is_empty() {
-            return Ok(config.into());
-        }
+        let core = if scopes.contains_any(CORE_SCOPES.into_iter()) {
+            // Build manager
+            let manager = ConfigManager {
+                cfg_local: ArcSwap::from_pointee(
+                    self.core.storage.config.cfg_local.load().as_ref().clone(),
+                ),
+                cfg_local_path: self.core.storage.config.cfg_local_path.clone(),
+                cfg_local_patterns: Patterns::parse(&mut config).into(),
+                cfg_store: config
+                    .value("storage.data")
+                    .and_then(|id| stores.stores.get(id))
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+
+            let core = Box::pin(Core::parse(&mut config, stores, manager)).await;
+            if !config.errors.is_empty() {
+                return Ok(config.into());
+            }
+            Some(core)
+        } else {
+            None
+        };
 
         // Update TLS certificates
-        let mut new_certificates = AHashMap::new();
-        parse_certificates(&mut config, &mut new_certificates, &mut Default::default());
-        let mut current_certificates = self.inner.data.tls_certificates.load().as_ref().clone();
-        for (cert_id, cert) in new_certificates {
-            current_certificates.insert(cert_id, cert);
+        if scopes.contains(ReloadScope::Certificates) {
+            let mut new_certificates = AHashMap::new();
+            parse_certificates(&mut config, &mut new_certificates, &mut Default::default());
+            let mut current_certificates = self.inner.data.tls_certificates.load().as_ref().clone();
+            for (cert_id, cert) in new_certificates {
+                current_certificates.insert(cert_id, cert);
+            }
+            self.inner
+                .data
+                .tls_certificates
+                .store(current_certificates.into());
         }
-        self.inner
-            .data
-            .tls_certificates
-            .store(current_certificates.into());
 
         // Update blocked IPs
-        *self.inner.data.blocked_ips.write() = BlockedIps::parse(&mut config).blocked_ip_addresses;
+        if scopes.contains(ReloadScope::BlockedIps) {
+            *self.inner.data.blocked_ips.write() =
+                BlockedIps::parse(&mut config).blocked_ip_addresses;
+        }
 
         // Parser servers
-        let mut servers = Listeners::parse(&mut config);
-        servers.parse_tcp_acceptors(&mut config, self.inner.clone());
+        if scopes.contains(ReloadScope::Listeners) {
+            let mut servers = Listeners::parse(&mut config);
+            servers.parse_tcp_acceptors(&mut config, self.inner.clone());
+        }
 
         Ok(if config.errors.is_empty() {
             ReloadResult {
                 config,
-                new_core: core.into(),
-                tracers: tracers.into(),
+                new_core: core,
+                tracers: scopes.contains(ReloadScope::Telemetry).then_some(tracers),
+                scopes,
+                fingerprint: fingerprint.into(),
             }
         } else {
             config.into()
         })
     }
+
+    pub async fn apply_reload(&self, result: &mut ReloadResult) -> trc::Result<()> {
+        if let Some(core) = result.new_core.take() {
+            // Update core
+            self.inner.shared_core.store(core.into());
+        }
+
+        if let Some(tracers) = result.tracers.take() {
+            // Update tracers
+
+            #[cfg(not(feature = "enterprise"))]
+            tracers.update(false);
+        }
+
+        if let Some(fingerprint) = result.fingerprint.take() {
+            *self.inner.data.config_fingerprint.lock() = fingerprint;
+        }
+
+        if !result.scopes.is_empty() {
+            // Notify the housekeeper of the reloaded subsystems
+            self.inner
+                .ipc
+                .housekeeper_tx
+                .send(HousekeeperEvent::ReloadSettings(result.scopes))
+                .await
+                .map_err(|err| {
+                    trc::EventType::Server(trc::ServerEvent::ThreadError)
+                        .reason(err)
+                        .details(concat!(
+                            "Failed to send settings reload ",
+                            "event to housekeeper"
+                        ))
+                        .caused_by(trc::location!())
+                })?;
+        }
+
+        Ok(())
+    }
 }
 
 impl From<Config> for ReloadResult {
@@ -146,6 +256,122 @@ impl From<Config> for ReloadResult {
             config,
             new_core: None,
             tracers: None,
+            scopes: Bitmap::new(),
+            fingerprint: None,
+        }
+    }
+}
+
+impl ReloadScope {
+    pub fn from_key(key: &str) -> Self {
+        let prefix = key.split_once('.').map_or(key, |(prefix, _)| prefix);
+        match prefix {
+            "spam-filter" => ReloadScope::SpamFilter,
+            "queue" => ReloadScope::Queue,
+            "acme" => ReloadScope::Acme,
+            "tracer" | "tracing" | "metrics" | "webhook" => ReloadScope::Telemetry,
+            "certificate" => ReloadScope::Certificates,
+            "server" if key.starts_with(BLOCKED_IP_KEY) || key.starts_with(ALLOWED_IP_KEY) => {
+                ReloadScope::BlockedIps
+            }
+            "server"
+                if key.starts_with("server.listener.")
+                    || key.starts_with("server.socket.")
+                    || key.starts_with("server.tls.") =>
+            {
+                ReloadScope::Listeners
+            }
+            _ => ReloadScope::Core,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "core" => Some(ReloadScope::Core),
+            "spam-filter" => Some(ReloadScope::SpamFilter),
+            "queue" => Some(ReloadScope::Queue),
+            "acme" => Some(ReloadScope::Acme),
+            "telemetry" => Some(ReloadScope::Telemetry),
+            "certificate" => Some(ReloadScope::Certificates),
+            "blocked-ip" => Some(ReloadScope::BlockedIps),
+            "listener" => Some(ReloadScope::Listeners),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReloadScope::Core => "core",
+            ReloadScope::SpamFilter => "spam-filter",
+            ReloadScope::Queue => "queue",
+            ReloadScope::Acme => "acme",
+            ReloadScope::Telemetry => "telemetry",
+            ReloadScope::Certificates => "certificate",
+            ReloadScope::BlockedIps => "blocked-ip",
+            ReloadScope::Listeners => "listener",
+            ReloadScope::None => "none",
+        }
+    }
+}
+
+impl From<u64> for ReloadScope {
+    fn from(value: u64) -> Self {
+        match value {
+            0 => ReloadScope::Core,
+            1 => ReloadScope::SpamFilter,
+            2 => ReloadScope::Queue,
+            3 => ReloadScope::Acme,
+            4 => ReloadScope::Telemetry,
+            5 => ReloadScope::Certificates,
+            6 => ReloadScope::BlockedIps,
+            7 => ReloadScope::Listeners,
+            _ => ReloadScope::None,
+        }
+    }
+}
+
+impl From<ReloadScope> for u64 {
+    fn from(scope: ReloadScope) -> u64 {
+        scope as u64
+    }
+}
+
+impl BitmapItem for ReloadScope {
+    fn max() -> u64 {
+        ReloadScope::None as u64
+    }
+
+    fn is_valid(&self) -> bool {
+        !matches!(self, ReloadScope::None)
+    }
+}
+
+impl ConfigFingerprint {
+    pub fn new(config: &Config) -> Self {
+        let mut hashers: [DefaultHasher; ReloadScope::None as usize] =
+            std::array::from_fn(|_| DefaultHasher::new());
+        for (key, value) in &config.keys {
+            let hasher = &mut hashers[ReloadScope::from_key(key) as usize];
+            key.hash(hasher);
+            value.hash(hasher);
+        }
+
+        ConfigFingerprint(hashers.map(|hasher| hasher.finish()))
+    }
+
+    pub fn changed(&self, other: &ConfigFingerprint) -> Bitmap<ReloadScope> {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(idx, _)| ReloadScope::from(idx as u64))
+            .collect()
+    }
+
+    pub fn update(&mut self, other: &ConfigFingerprint, scopes: Bitmap<ReloadScope>) {
+        for scope in scopes {
+            self.0[scope as usize] = other.0[scope as usize];
         }
     }
 }
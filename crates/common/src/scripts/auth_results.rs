@@ -0,0 +1,300 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::borrow::Cow;
+
+use ahash::AHashMap;
+use mail_parser::Message;
+
+use crate::expr::{
+    V_ARC_RESULT, V_DKIM_DOMAINS, V_DKIM_RESULT, V_DMARC_DOMAIN, V_DMARC_RESULT, V_IPREV_RESULT,
+    V_SPF_RESULT, Variable,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthResults {
+    pub spf: Option<AuthVerdict>,
+    pub dkim: Vec<AuthVerdict>,
+    pub arc: Option<AuthVerdict>,
+    pub dmarc: Option<AuthVerdict>,
+    pub iprev: Option<AuthVerdict>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthVerdict {
+    pub result: String,
+    pub domain: String,
+}
+
+impl AuthResults {
+    // Only the topmost header is trusted, as it is the one added by this server
+    pub fn from_message(message: &Message<'_>, authserv_id: &str) -> Option<Self> {
+        let header = message
+            .root_part()
+            .headers()
+            .iter()
+            .find(|header| header.name().eq_ignore_ascii_case("Authentication-Results"))?;
+
+        AuthResults::parse(
+            &String::from_utf8_lossy(
+                message
+                    .raw_message()
+                    .get(header.offset_start as usize..header.offset_end as usize)?,
+            ),
+            authserv_id,
+        )
+    }
+
+    pub fn parse(header: &str, authserv_id: &str) -> Option<Self> {
+        let header = strip_comments(header);
+        let mut resinfos = header.split(';');
+        if !resinfos
+            .next()?
+            .split_ascii_whitespace()
+            .next()
+            .is_some_and(|id| id.eq_ignore_ascii_case(authserv_id))
+        {
+            return None;
+        }
+
+        let mut results = AuthResults::default();
+        for resinfo in resinfos {
+            let mut tokens = resinfo.split_ascii_whitespace();
+            let Some((method, result)) = tokens.next().and_then(|token| token.split_once('='))
+            else {
+                continue;
+            };
+            let method = method.split_once('/').map_or(method, |(method, _)| method);
+            let properties = tokens
+                .filter_map(|token| token.split_once('='))
+                .map(|(name, value)| (name, value.trim_matches('"')))
+                .collect::<Vec<_>>();
+            let property = |name: &str| {
+                properties
+                    .iter()
+                    .find(|(property, _)| property.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| *value)
+            };
+            let mut verdict = AuthVerdict {
+                result: result.to_ascii_lowercase(),
+                domain: String::new(),
+            };
+
+            match method.to_ascii_lowercase().as_str() {
+                "spf" => {
+                    // EHLO identity checks do not include the MAIL FROM address
+                    if let Some(mail_from) = property("smtp.mailfrom") {
+                        verdict.domain = domain_part(mail_from);
+                        results.spf = Some(verdict);
+                    }
+                }
+                "dkim" => {
+                    verdict.domain = property("header.d")
+                        .map(|domain| domain.to_ascii_lowercase())
+                        .or_else(|| property("header.i").map(domain_part))
+                        .unwrap_or_default();
+                    results.dkim.push(verdict);
+                }
+                "dmarc" => {
+                    verdict.domain = property("header.from").map(domain_part).unwrap_or_default();
+                    results.dmarc = Some(verdict);
+                }
+                "arc" => {
+                    results.arc = Some(verdict);
+                }
+                "iprev" => {
+                    results.iprev = Some(verdict);
+                }
+                _ => {}
+            }
+        }
+
+        Some(results)
+    }
+
+    pub fn dkim_result(&self) -> &str {
+        self.dkim
+            .iter()
+            .find(|verdict| verdict.result == "pass")
+            .or_else(|| self.dkim.first())
+            .map(|verdict| verdict.result.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn dkim_domains(&self) -> impl Iterator<Item = &str> {
+        self.dkim
+            .iter()
+            .filter(|verdict| verdict.result == "pass" && !verdict.domain.is_empty())
+            .map(|verdict| verdict.domain.as_str())
+    }
+
+    pub fn resolve_variable(&self, variable: u32) -> Variable<'_> {
+        match variable {
+            V_SPF_RESULT => verdict_result(&self.spf).into(),
+            V_DKIM_RESULT => self.dkim_result().into(),
+            V_DKIM_DOMAINS => self
+                .dkim_domains()
+                .map(Variable::from)
+                .collect::<Vec<_>>()
+                .into(),
+            V_ARC_RESULT => verdict_result(&self.arc).into(),
+            V_DMARC_RESULT => verdict_result(&self.dmarc).into(),
+            V_DMARC_DOMAIN => self
+                .dmarc
+                .as_ref()
+                .map(|verdict| verdict.domain.as_str())
+                .unwrap_or_default()
+                .into(),
+            V_IPREV_RESULT => verdict_result(&self.iprev).into(),
+            _ => Variable::default(),
+        }
+    }
+
+    pub fn sieve_variables(&self) -> AHashMap<Cow<'static, str>, sieve::runtime::Variable> {
+        let mut variables = AHashMap::with_capacity(8);
+        for (name, value) in [
+            ("spf.result", verdict_result(&self.spf)),
+            (
+                "spf.domain",
+                self.spf
+                    .as_ref()
+                    .map(|verdict| verdict.domain.as_str())
+                    .unwrap_or_default(),
+            ),
+            ("dkim.result", self.dkim_result()),
+            ("arc.result", verdict_result(&self.arc)),
+            ("dmarc.result", verdict_result(&self.dmarc)),
+            (
+                "dmarc.domain",
+                self.dmarc
+                    .as_ref()
+                    .map(|verdict| verdict.domain.as_str())
+                    .unwrap_or_default(),
+            ),
+            ("iprev.result", verdict_result(&self.iprev)),
+        ] {
+            variables.insert(Cow::Borrowed(name), value.to_string().into());
+        }
+        variables.insert(
+            Cow::Borrowed("dkim.domains"),
+            self.dkim_domains()
+                .map(|domain| sieve::runtime::Variable::from(domain.to_string()))
+                .collect::<Vec<_>>()
+                .into(),
+        );
+
+        variables
+    }
+}
+
+fn verdict_result(verdict: &Option<AuthVerdict>) -> &str {
+    verdict
+        .as_ref()
+        .map(|verdict| verdict.result.as_str())
+        .unwrap_or_default()
+}
+
+fn domain_part(address: &str) -> String {
+    address
+        .rsplit_once('@')
+        .map_or(address, |(_, domain)| domain)
+        .trim_end_matches('>')
+        .to_ascii_lowercase()
+}
+
+fn strip_comments(header: &str) -> String {
+    let mut result = String::with_capacity(header.len());
+    let mut depth = 0usize;
+    let mut in_quote = false;
+    let mut is_escaped = false;
+
+    for ch in header.chars() {
+        if is_escaped {
+            is_escaped = false;
+            if depth == 0 {
+                result.push(ch);
+            }
+            continue;
+        }
+
+        match ch {
+            '\\' if in_quote || depth > 0 => {
+                is_escaped = true;
+            }
+            '"' if depth == 0 => {
+                in_quote = !in_quote;
+                result.push(ch);
+            }
+            '(' if !in_quote => {
+                depth += 1;
+            }
+            ')' if !in_quote && depth > 0 => {
+                depth -= 1;
+                result.push(' ');
+            }
+            '\r' | '\n' | '\t' if depth == 0 => {
+                result.push(' ');
+            }
+            _ if depth == 0 => {
+                result.push(ch);
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthResults, AuthVerdict};
+
+    #[test]
+    fn parse_auth_results() {
+        let results = AuthResults::parse(
+            concat!(
+                " mx.example.org;\r\n",
+                "\tdkim=fail (bad signature) header.d=evil.org header.s=sel;\r\n",
+                "\tdkim=pass header.d=PayPal.com header.s=sel header.b=abc;\r\n",
+                "\tspf=pass (sender is authorized) smtp.mailfrom=\"bounce@paypal.com\"\r\n",
+                "\t smtp.helo=mail.paypal.com;\r\n",
+                "\tspf=none smtp.helo=mail.paypal.com;\r\n",
+                "\tdmarc=pass header.from=paypal.com policy.dmarc=reject;\r\n",
+                "\tiprev=pass policy.iprev=10.0.0.1\r\n"
+            ),
+            "mx.example.org",
+        )
+        .unwrap();
+
+        assert_eq!(
+            results.spf,
+            Some(AuthVerdict {
+                result: "pass".into(),
+                domain: "paypal.com".into(),
+            })
+        );
+        assert_eq!(results.dkim_result(), "pass");
+        assert_eq!(results.dkim_domains().collect::<Vec<_>>(), ["paypal.com"]);
+        assert_eq!(
+            results.dmarc,
+            Some(AuthVerdict {
+                result: "pass".into(),
+                domain: "paypal.com".into(),
+            })
+        );
+        assert_eq!(results.iprev.unwrap().result, "pass");
+        assert_eq!(results.arc, None);
+
+        // Headers added by other hosts are ignored
+        assert_eq!(
+            AuthResults::parse(
+                "mx.evil.org; dkim=pass header.d=paypal.com",
+                "mx.example.org"
+            ),
+            None
+        );
+    }
+}
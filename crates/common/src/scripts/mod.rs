@@ -12,6 +12,7 @@ use unicode_security::mixed_script::AugmentedScriptSet;
 
 use crate::IntoString;
 
+pub mod auth_results;
 pub mod functions;
 pub mod plugins;
 
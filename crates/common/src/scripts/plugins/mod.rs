@@ -10,6 +10,7 @@ pub mod headers;
 pub mod http;
 pub mod llm_prompt;
 pub mod lookup;
+pub mod principal;
 pub mod query;
 pub mod text;
 
@@ -31,7 +32,7 @@ pub struct PluginContext<'x> {
     pub arguments: Vec<Variable>,
 }
 
-const PLUGINS_REGISTER: [RegisterPluginFnc; 13] = [
+const PLUGINS_REGISTER: [RegisterPluginFnc; 14] = [
     query::register,
     exec::register,
     lookup::register,
@@ -45,6 +46,7 @@ const PLUGINS_REGISTER: [RegisterPluginFnc; 13] = [
     text::register_tokenize,
     text::register_domain_part,
     llm_prompt::register,
+    principal::register_attribute,
 ];
 
 pub trait RegisterSievePlugins {
@@ -93,6 +95,7 @@ impl Core {
             10 => text::exec_tokenize(ctx),
             11 => text::exec_domain_part(ctx),
             12 => llm_prompt::exec(ctx).await,
+            13 => principal::exec_attribute(ctx).await,
             _ => unreachable!(),
         };
 
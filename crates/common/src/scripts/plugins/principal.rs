@@ -0,0 +1,38 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use sieve::{FunctionMap, runtime::Variable};
+
+use crate::auth::attributes::AttributeValue;
+
+use super::PluginContext;
+
+pub fn register_attribute(plugin_id: u32, fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("principal_attribute", plugin_id, 2);
+}
+
+pub async fn exec_attribute(ctx: PluginContext<'_>) -> trc::Result<Variable> {
+    let address = ctx.arguments[0].to_string();
+    let name = ctx.arguments[1].to_string();
+
+    Ok(
+        match ctx
+            .server
+            .principal_attribute("", address.as_ref(), name.as_ref(), ctx.session_id)
+            .await?
+        {
+            Some(AttributeValue::String(value)) => Variable::from(value),
+            Some(AttributeValue::Integer(value)) => Variable::from(value),
+            Some(AttributeValue::Boolean(value)) => Variable::from(value),
+            Some(AttributeValue::List(values)) => values
+                .into_iter()
+                .map(Variable::from)
+                .collect::<Vec<_>>()
+                .into(),
+            None => Variable::default(),
+        },
+    )
+}
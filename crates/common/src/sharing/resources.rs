@@ -80,4 +80,22 @@ impl DavResources {
 
         account_acls
     }
+
+    pub fn container_grantees(&self, check_acl: Acl) -> RoaringBitmap {
+        let mut account_ids = RoaringBitmap::new();
+
+        for resource in &self.resources {
+            if resource.is_container()
+                && let Some(acls) = resource.acls()
+            {
+                for acl in acls {
+                    if acl.grants.contains(check_acl) {
+                        account_ids.insert(acl.account_id);
+                    }
+                }
+            }
+        }
+
+        account_ids
+    }
 }
@@ -0,0 +1,39 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use store::{Deserialize, SerializeInfallible, U32_LEN, U64_LEN};
+
+/// Deleted message retained in the dumpster until its retention expires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DumpsterEntry {
+    pub deleted_at: u64,
+    pub size: u32,
+    pub quota_released: bool,
+}
+
+impl SerializeInfallible for DumpsterEntry {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(U64_LEN + U32_LEN + 1);
+        bytes.extend_from_slice(&self.deleted_at.to_be_bytes());
+        bytes.extend_from_slice(&self.size.to_be_bytes());
+        bytes.push(self.quota_released as u8);
+        bytes
+    }
+}
+
+impl Deserialize for DumpsterEntry {
+    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
+        if bytes.len() == U64_LEN + U32_LEN + 1 {
+            Ok(DumpsterEntry {
+                deleted_at: u64::from_be_bytes(bytes[..U64_LEN].try_into().unwrap()),
+                size: u32::from_be_bytes(bytes[U64_LEN..U64_LEN + U32_LEN].try_into().unwrap()),
+                quota_released: bytes[U64_LEN + U32_LEN] != 0,
+            })
+        } else {
+            Err(trc::StoreEvent::DataCorruption.caused_by(trc::location!()))
+        }
+    }
+}
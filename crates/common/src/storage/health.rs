@@ -0,0 +1,38 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Instant};
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthCheck {
+    #[serde(rename = "type")]
+    pub typ: &'static str,
+    pub id: String,
+    pub status: HealthStatus,
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Error,
+    Timeout,
+}
+
+pub struct CachedHealthReport {
+    pub expires: Instant,
+    pub report: Arc<HealthReport>,
+}
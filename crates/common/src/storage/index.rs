@@ -35,6 +35,9 @@ pub enum IndexValue<'x> {
     Blob {
         value: BlobHash,
     },
+    BlobList {
+        value: Vec<BlobHash>,
+    },
     Quota {
         used: u32,
     },
@@ -380,6 +383,15 @@ fn build_index(batch: &mut BatchBuilder, item: IndexValue<'_>, tenant_id: Option
                 batch.clear(BlobOp::Link { hash: value });
             }
         }
+        IndexValue::BlobList { value } => {
+            for hash in value {
+                if set {
+                    batch.set(BlobOp::Link { hash }, vec![]);
+                } else {
+                    batch.clear(BlobOp::Link { hash });
+                }
+            }
+        }
         IndexValue::Acl { value } => {
             for item in value.as_ref() {
                 if set {
@@ -498,6 +510,21 @@ fn merge_index(
             batch.clear(BlobOp::Link { hash: old_hash });
             batch.set(BlobOp::Link { hash: new_hash }, vec![]);
         }
+        (
+            IndexValue::BlobList { value: old_hashes },
+            IndexValue::BlobList { value: new_hashes },
+        ) => {
+            for hash in &old_hashes {
+                if !new_hashes.contains(hash) {
+                    batch.clear(BlobOp::Link { hash: hash.clone() });
+                }
+            }
+            for hash in new_hashes {
+                if !old_hashes.contains(&hash) {
+                    batch.set(BlobOp::Link { hash }, vec![]);
+                }
+            }
+        }
         (IndexValue::Acl { value: old_acl }, IndexValue::Acl { value: new_acl }) => {
             match (!old_acl.is_empty(), !new_acl.is_empty()) {
                 (true, true) => {
@@ -5,5 +5,7 @@
  */
 
 pub mod blob;
+pub mod dumpster;
+pub mod health;
 pub mod index;
 pub mod state;
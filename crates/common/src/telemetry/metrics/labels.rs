@@ -0,0 +1,306 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::collections::hash_map::Entry;
+
+use ahash::AHashMap;
+use directory::backend::internal::manage::ManageDirectory;
+use parking_lot::Mutex;
+use prometheus::proto::{Counter, Gauge, LabelPair, Metric, MetricFamily, MetricType};
+use trc::AddContext;
+use utils::config::utils::ParseValue;
+
+use crate::{Server, config::telemetry::LabelDimension};
+
+pub const OTHER_LABEL: &str = "other";
+const OTHER_TENANT: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LabeledMetric {
+    MessagesIn,
+    MessagesOut,
+    AuthFailures,
+    StorageBytes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricLabel {
+    Domain,
+    Tenant,
+}
+
+// Counters broken out by domain or tenant, the number of distinct label
+// values is capped per metric and any excess is accounted under "other".
+#[derive(Default)]
+pub struct LabeledMetrics {
+    domains: Mutex<AHashMap<(LabeledMetric, String), u64>>,
+    tenants: Mutex<AHashMap<(LabeledMetric, u32), u64>>,
+}
+
+impl LabeledMetric {
+    pub const COUNTERS: [LabeledMetric; 3] = [
+        LabeledMetric::MessagesIn,
+        LabeledMetric::MessagesOut,
+        LabeledMetric::AuthFailures,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LabeledMetric::MessagesIn => "messages-in",
+            LabeledMetric::MessagesOut => "messages-out",
+            LabeledMetric::AuthFailures => "auth-failures",
+            LabeledMetric::StorageBytes => "storage-bytes",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            LabeledMetric::MessagesIn => "Messages received by local mailboxes",
+            LabeledMetric::MessagesOut => "Messages delivered by the outbound queue",
+            LabeledMetric::AuthFailures => "Failed authentication attempts",
+            LabeledMetric::StorageBytes => "Storage used in bytes",
+        }
+    }
+}
+
+impl MetricLabel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetricLabel::Domain => "domain",
+            MetricLabel::Tenant => "tenant",
+        }
+    }
+}
+
+impl ParseValue for LabeledMetric {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "messages-in" => Ok(LabeledMetric::MessagesIn),
+            "messages-out" => Ok(LabeledMetric::MessagesOut),
+            "auth-failures" => Ok(LabeledMetric::AuthFailures),
+            "storage-bytes" => Ok(LabeledMetric::StorageBytes),
+            _ => Err(format!("Invalid labeled metric {value:?}")),
+        }
+    }
+}
+
+impl LabeledMetrics {
+    fn increment_domain(&self, metric: LabeledMetric, domain: &str, max_cardinality: usize) {
+        let mut domains = self.domains.lock();
+        if let Some(value) = domains.get_mut(&(metric, domain.to_string())) {
+            *value += 1;
+        } else {
+            let label = if domains.keys().filter(|(m, _)| *m == metric).count() < max_cardinality {
+                domain.to_string()
+            } else {
+                OTHER_LABEL.to_string()
+            };
+            *domains.entry((metric, label)).or_default() += 1;
+        }
+    }
+
+    fn increment_tenant(&self, metric: LabeledMetric, tenant_id: u32, max_cardinality: usize) {
+        let mut tenants = self.tenants.lock();
+        if let Some(value) = tenants.get_mut(&(metric, tenant_id)) {
+            *value += 1;
+        } else {
+            let tenant_id =
+                if tenants.keys().filter(|(m, _)| *m == metric).count() < max_cardinality {
+                    tenant_id
+                } else {
+                    OTHER_TENANT
+                };
+            *tenants.entry((metric, tenant_id)).or_default() += 1;
+        }
+    }
+}
+
+impl Server {
+    pub fn record_labeled_metric(
+        &self,
+        metric: LabeledMetric,
+        domain: Option<&str>,
+        tenant_id: Option<u32>,
+    ) {
+        let Some(labels) = self
+            .core
+            .metrics
+            .prometheus
+            .as_ref()
+            .map(|prometheus| &prometheus.labels)
+        else {
+            return;
+        };
+        let metrics = &self.inner.data.labeled_metrics;
+
+        if let (Some(dimension), Some(domain)) = (&labels.domain, domain)
+            && dimension.metrics.contains(&metric)
+        {
+            let domain = domain.to_lowercase();
+            if dimension.is_allowed(&domain) {
+                metrics.increment_domain(metric, &domain, labels.max_cardinality);
+            }
+        }
+
+        if let (Some(dimension), Some(tenant_id)) = (&labels.tenant, tenant_id)
+            && dimension.metrics.contains(&metric)
+        {
+            metrics.increment_tenant(metric, tenant_id, labels.max_cardinality);
+        }
+    }
+
+    pub(crate) async fn export_labeled_metrics(
+        &self,
+        metrics: &mut Vec<MetricFamily>,
+    ) -> trc::Result<()> {
+        let Some(labels) = self
+            .core
+            .metrics
+            .prometheus
+            .as_ref()
+            .map(|prometheus| &prometheus.labels)
+        else {
+            return Ok(());
+        };
+
+        if let Some(dimension) = &labels.domain {
+            let domains = self.inner.data.labeled_metrics.domains.lock().clone();
+            for metric in LabeledMetric::COUNTERS {
+                if dimension.metrics.contains(&metric) {
+                    metrics.push(new_family(
+                        metric,
+                        MetricLabel::Domain,
+                        domains
+                            .iter()
+                            .filter(|((m, _), _)| *m == metric)
+                            .map(|((_, domain), value)| (domain.clone(), *value))
+                            .collect(),
+                    ));
+                }
+            }
+        }
+
+        if let Some(dimension) = &labels.tenant {
+            // Resolve tenant names, tenants outside the allowlist are reported as "other"
+            let tenants = self.inner.data.labeled_metrics.tenants.lock().clone();
+            let mut names = AHashMap::new();
+            for (_, tenant_id) in tenants.keys() {
+                if let Entry::Vacant(entry) = names.entry(*tenant_id) {
+                    entry.insert(self.tenant_label(dimension, *tenant_id).await?);
+                }
+            }
+
+            for metric in LabeledMetric::COUNTERS {
+                if dimension.metrics.contains(&metric) {
+                    let mut values: AHashMap<String, u64> = AHashMap::new();
+                    for ((_, tenant_id), value) in tenants.iter().filter(|((m, _), _)| *m == metric)
+                    {
+                        *values.entry(names[tenant_id].clone()).or_default() += *value;
+                    }
+                    metrics.push(new_family(
+                        metric,
+                        MetricLabel::Tenant,
+                        values.into_iter().collect(),
+                    ));
+                }
+            }
+
+            // Storage usage is only reported for allowlisted tenants
+            if dimension.metrics.contains(&LabeledMetric::StorageBytes) {
+                let mut values = Vec::new();
+                for name in dimension.allow.iter().take(labels.max_cardinality) {
+                    if let Some(tenant_id) = self
+                        .store()
+                        .get_principal_id(name)
+                        .await
+                        .caused_by(trc::location!())?
+                    {
+                        values.push((
+                            name.clone(),
+                            self.get_used_quota(tenant_id).await?.max(0) as u64,
+                        ));
+                    }
+                }
+                metrics.push(new_family(
+                    LabeledMetric::StorageBytes,
+                    MetricLabel::Tenant,
+                    values,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn tenant_label(
+        &self,
+        dimension: &LabelDimension,
+        tenant_id: u32,
+    ) -> trc::Result<String> {
+        if tenant_id != OTHER_TENANT
+            && let Some(name) = self
+                .store()
+                .get_principal_name(tenant_id)
+                .await
+                .caused_by(trc::location!())?
+            && dimension.is_allowed(&name)
+        {
+            Ok(name)
+        } else {
+            Ok(OTHER_LABEL.to_string())
+        }
+    }
+}
+
+impl LabelDimension {
+    pub fn is_allowed(&self, value: &str) -> bool {
+        self.allow.is_empty() || self.allow.contains(value)
+    }
+}
+
+fn new_family(
+    metric: LabeledMetric,
+    label: MetricLabel,
+    mut values: Vec<(String, u64)>,
+) -> MetricFamily {
+    values.sort_unstable();
+
+    let mut family = MetricFamily::default();
+    family.set_name(super::prometheus::metric_name(format!(
+        "{}.by-{}",
+        metric.name(),
+        label.name()
+    )));
+    family.set_help(metric.description().into());
+    family.set_field_type(if metric == LabeledMetric::StorageBytes {
+        MetricType::GAUGE
+    } else {
+        MetricType::COUNTER
+    });
+    family.set_metric(
+        values
+            .into_iter()
+            .map(|(label_value, value)| {
+                let mut m = Metric::default();
+                let mut label_pair = LabelPair::default();
+                label_pair.set_name(label.name().into());
+                label_pair.set_value(label_value);
+                m.set_label(vec![label_pair]);
+                if metric == LabeledMetric::StorageBytes {
+                    let mut gauge = Gauge::default();
+                    gauge.set_value(value as f64);
+                    m.set_gauge(gauge);
+                } else {
+                    let mut counter = Counter::default();
+                    counter.set_value(value as f64);
+                    m.set_counter(counter);
+                }
+                m
+            })
+            .collect(),
+    );
+    family
+}
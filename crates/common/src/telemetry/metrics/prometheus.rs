@@ -50,13 +50,16 @@ impl Server {
             metrics.push(metric);
         }
 
+        // Add metrics broken out by domain and tenant
+        self.export_labeled_metrics(&mut metrics).await?;
+
         TextEncoder::new().encode_to_string(&metrics).map_err(|e| {
             trc::EventType::Telemetry(trc::TelemetryEvent::OtelExporterError).reason(e)
         })
     }
 }
 
-fn metric_name(id: impl AsRef<str>) -> String {
+pub(crate) fn metric_name(id: impl AsRef<str>) -> String {
     let id = id.as_ref();
     let mut name = String::with_capacity(id.len());
     for c in id.chars() {
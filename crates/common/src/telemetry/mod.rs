@@ -9,6 +9,7 @@ pub mod tracers;
 pub mod webhooks;
 
 use tracers::log::spawn_log_tracer;
+use tracers::memory::{RecentEvents, spawn_memory_tracer};
 use tracers::otel::spawn_otel_tracer;
 use tracers::stdout::spawn_console_tracer;
 use trc::{Collector, ipc::subscriber::SubscriberBuilder};
@@ -49,9 +50,22 @@ impl Telemetry {
             }
         }
 
+        // Release the recent events buffer if its tracer was removed
+        if !self
+            .tracers
+            .subscribers
+            .iter()
+            .any(|tracer| matches!(tracer.typ, TelemetrySubscriberType::MemoryTracer(_)))
+        {
+            RecentEvents::set_capacity(0);
+        }
+
         // Activate new tracers or update existing ones
         for tracer in self.tracers.subscribers {
             if active_subscribers.contains(&tracer.id) {
+                if let TelemetrySubscriberType::MemoryTracer(settings) = &tracer.typ {
+                    RecentEvents::set_capacity(settings.capacity);
+                }
                 Collector::update_subscriber(tracer.id, tracer.interests, tracer.lossy);
             } else {
                 tracer.typ.spawn(
@@ -104,6 +118,9 @@ impl TelemetrySubscriberType {
             TelemetrySubscriberType::LogTracer(settings) => spawn_log_tracer(builder, settings),
             TelemetrySubscriberType::Webhook(settings) => spawn_webhook_tracer(builder, settings),
             TelemetrySubscriberType::OtelTracer(settings) => spawn_otel_tracer(builder, settings),
+            TelemetrySubscriberType::MemoryTracer(settings) => {
+                spawn_memory_tracer(builder, settings)
+            }
             #[cfg(unix)]
             TelemetrySubscriberType::JournalTracer(subscriber) => {
                 tracers::journald::spawn_journald_tracer(builder, subscriber)
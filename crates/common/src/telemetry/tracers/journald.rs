@@ -4,10 +4,10 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use ahash::AHashSet;
 use std::io::Write;
 use trc::ipc::subscriber::SubscriberBuilder;
-use trc::{Event, EventDetails, Level, TelemetryEvent};
+use trc::{Event, EventDetails, Level, TelemetryEvent, Value};
+use utils::config::utils::ParseValue;
 
 pub(crate) fn spawn_journald_tracer(builder: SubscriberBuilder, subscriber: Subscriber) {
     let (_, mut rx) = builder.register();
@@ -22,17 +22,21 @@ pub(crate) fn spawn_journald_tracer(builder: SubscriberBuilder, subscriber: Subs
 
 impl Subscriber {
     fn send_event(&self, event: &Event<EventDetails>) {
+        if let Err(err) = self.send_payload(&self.serialize_event(event)) {
+            trc::event!(
+                Telemetry(TelemetryEvent::JournalError),
+                Details = "Failed to send event to journald",
+                Reason = err.to_string()
+            );
+        }
+    }
+
+    fn serialize_event(&self, event: &Event<EventDetails>) -> Vec<u8> {
         let mut buf = Vec::with_capacity(256);
         put_field_wellformed(
             &mut buf,
             "PRIORITY",
-            &[match event.inner.level {
-                Level::Error => self.priority_mappings.error as u8,
-                Level::Warn => self.priority_mappings.warn as u8,
-                Level::Info => self.priority_mappings.info as u8,
-                Level::Debug => self.priority_mappings.debug as u8,
-                Level::Trace | Level::Disable => self.priority_mappings.trace as u8,
-            }],
+            &[self.priority_mappings.priority(event.inner.level) as u8],
         );
         put_field_length_encoded(&mut buf, "SYSLOG_IDENTIFIER", |buf| {
             write!(buf, "{}", self.syslog_identifier).unwrap()
@@ -40,22 +44,28 @@ impl Subscriber {
         put_field_length_encoded(&mut buf, "MESSAGE", |buf| {
             write!(buf, "{}", event.inner.typ.description()).unwrap()
         });
+        put_field_wellformed(&mut buf, "EVENT", event.inner.typ.name().as_bytes());
 
-        let mut seen_keys = AHashSet::new();
+        // Event keys are mapped to journal fields, repeated keys and arrays
+        // are sent as multiple values of the same field.
         for (key, value) in &event.keys {
-            if seen_keys.insert(*key) {
-                put_field_length_encoded(&mut buf, key.name(), |buf| {
-                    write!(buf, "{value}").unwrap()
-                });
-            }
+            put_value_fields(&mut buf, key.id(), value);
         }
 
-        if let Err(err) = self.send_payload(&buf) {
-            trc::event!(
-                Telemetry(TelemetryEvent::JournalError),
-                Details = "Failed to send event to journald",
-                Reason = err.to_string()
-            );
+        buf
+    }
+}
+
+fn put_value_fields(buf: &mut Vec<u8>, name: &str, value: &Value) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                put_value_fields(buf, name, value);
+            }
+        }
+        Value::None => {}
+        value => {
+            put_field_length_encoded(buf, name, |buf| write!(buf, "{value}").unwrap());
         }
     }
 }
@@ -293,6 +303,34 @@ impl PriorityMappings {
     }
 }
 
+impl PriorityMappings {
+    pub fn priority(&self, level: Level) -> Priority {
+        match level {
+            Level::Error => self.error,
+            Level::Warn => self.warn,
+            Level::Info => self.info,
+            Level::Debug => self.debug,
+            Level::Trace | Level::Disable => self.trace,
+        }
+    }
+}
+
+impl ParseValue for Priority {
+    fn parse_value(value: &str) -> std::result::Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "0" | "emerg" | "emergency" => Ok(Priority::Emergency),
+            "1" | "alert" => Ok(Priority::Alert),
+            "2" | "crit" | "critical" => Ok(Priority::Critical),
+            "3" | "err" | "error" => Ok(Priority::Error),
+            "4" | "warn" | "warning" => Ok(Priority::Warning),
+            "5" | "notice" => Ok(Priority::Notice),
+            "6" | "info" | "informational" => Ok(Priority::Informational),
+            "7" | "debug" => Ok(Priority::Debug),
+            _ => Err(format!("Invalid journald priority {value:?}")),
+        }
+    }
+}
+
 impl Default for PriorityMappings {
     fn default() -> Self {
         Self::new()
@@ -319,8 +357,13 @@ impl std::fmt::Debug for Subscriber {
 /// not delete from `buf`, but may append arbitrary data.  This function then determines the length
 /// of the data written and adds it in the appropriate place in `buf`.
 fn put_field_length_encoded(buf: &mut Vec<u8>, name: &str, write_value: impl FnOnce(&mut Vec<u8>)) {
-    for ch in name.as_bytes() {
-        buf.push(ch.to_ascii_uppercase());
+    // Journal field names may only contain uppercase letters, digits and underscores
+    for ch in name.trim_start_matches(['_', '-']).as_bytes() {
+        buf.push(if ch.is_ascii_alphanumeric() {
+            ch.to_ascii_uppercase()
+        } else {
+            b'_'
+        });
     }
     buf.push(b'\n');
     buf.extend_from_slice(&[0; 8]); // Length tag, to be populated
@@ -460,3 +503,96 @@ pub fn seal_fully(fd: RawFd) -> Result<()> {
     }
 }
 // SPDX-SnippetEnd
+
+#[cfg(test)]
+mod tests {
+    use trc::{Event, EventDetails, EventType, Key, Level, SmtpEvent, Value};
+    use utils::config::utils::ParseValue;
+
+    use super::{Priority, PriorityMappings, Subscriber};
+
+    fn parse_fields(mut buf: &[u8]) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        while !buf.is_empty() {
+            let name_end = buf.iter().position(|&ch| ch == b'\n').unwrap();
+            let name = std::str::from_utf8(&buf[..name_end]).unwrap().to_string();
+            buf = &buf[name_end + 1..];
+            let len = u64::from_le_bytes(buf[..8].try_into().unwrap()) as usize;
+            let value = std::str::from_utf8(&buf[8..8 + len]).unwrap().to_string();
+            assert_eq!(buf[8 + len], b'\n');
+            buf = &buf[8 + len + 1..];
+            fields.push((name, value));
+        }
+        fields
+    }
+
+    #[test]
+    fn journald_event_fields() {
+        let subscriber = Subscriber {
+            socket: std::os::unix::net::UnixDatagram::unbound().unwrap(),
+            syslog_identifier: "stalwart".to_string(),
+            priority_mappings: PriorityMappings {
+                info: Priority::Notice,
+                ..PriorityMappings::new()
+            },
+        };
+        let event = Event {
+            inner: EventDetails {
+                typ: EventType::Smtp(SmtpEvent::RcptTo),
+                timestamp: 0,
+                level: Level::Info,
+                span: None,
+            },
+            keys: vec![
+                (Key::SpanId, 7u64.into()),
+                (
+                    Key::To,
+                    Value::Array(vec!["jane@example.org".into(), "john@example.org".into()]),
+                ),
+                (Key::Reason, Value::None),
+                (Key::Details, "first".into()),
+                (Key::Details, "second\nline".into()),
+            ],
+        };
+
+        let fields = parse_fields(&subscriber.serialize_event(&event));
+        let field = |name: &str| {
+            fields
+                .iter()
+                .filter(|(field, _)| field == name)
+                .map(|(_, value)| value.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(field("PRIORITY"), ["5"]);
+        assert_eq!(field("SYSLOG_IDENTIFIER"), ["stalwart"]);
+        assert_eq!(
+            field("MESSAGE"),
+            [EventType::Smtp(SmtpEvent::RcptTo).description()]
+        );
+        assert_eq!(field("EVENT"), [EventType::Smtp(SmtpEvent::RcptTo).name()]);
+        assert_eq!(field("SPAN_ID"), ["7"]);
+        assert_eq!(field("TO"), ["jane@example.org", "john@example.org"]);
+        assert_eq!(field("DETAILS"), ["first", "second\nline"]);
+        assert!(field("REASON").is_empty());
+    }
+
+    #[test]
+    fn journald_priority_mappings() {
+        for (value, expected) in [
+            ("0", Priority::Emergency),
+            ("crit", Priority::Critical),
+            ("Error", Priority::Error),
+            ("warning", Priority::Warning),
+            ("notice", Priority::Notice),
+            ("7", Priority::Debug),
+        ] {
+            assert_eq!(Priority::parse_value(value), Ok(expected));
+        }
+        assert!(Priority::parse_value("verbose").is_err());
+
+        let mappings = PriorityMappings::new();
+        assert_eq!(mappings.priority(Level::Error), mappings.error);
+        assert_eq!(mappings.priority(Level::Disable), mappings.trace);
+    }
+}
@@ -0,0 +1,205 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, LazyLock},
+};
+
+use parking_lot::Mutex;
+use trc::{Event, EventDetails, EventType, Key, Value, ipc::subscriber::SubscriberBuilder};
+
+use crate::config::telemetry::MemoryTracer;
+
+static RECENT_EVENTS: LazyLock<Mutex<RecentEvents>> = LazyLock::new(|| {
+    Mutex::new(RecentEvents {
+        events: VecDeque::new(),
+        capacity: 0,
+    })
+});
+
+pub struct RecentEvents {
+    events: VecDeque<Arc<Event<EventDetails>>>,
+    capacity: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct RecentEventsFilter {
+    pub types: Vec<EventType>,
+    pub account_id: Option<u32>,
+    pub span_id: Option<u64>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+pub(crate) fn spawn_memory_tracer(builder: SubscriberBuilder, settings: MemoryTracer) {
+    RecentEvents::set_capacity(settings.capacity);
+    let (_, mut rx) = builder.register();
+    tokio::spawn(async move {
+        while let Some(events) = rx.recv().await {
+            let mut recent = RECENT_EVENTS.lock();
+            for event in events {
+                recent.push(event);
+            }
+        }
+    });
+}
+
+impl RecentEvents {
+    pub fn set_capacity(capacity: usize) {
+        let mut recent = RECENT_EVENTS.lock();
+        recent.capacity = capacity;
+        while recent.events.len() > capacity {
+            recent.events.pop_front();
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        RECENT_EVENTS.lock().capacity > 0
+    }
+
+    // Returns the matching events, newest first, along with the total number of matches
+    pub fn query(
+        filter: &RecentEventsFilter,
+        offset: usize,
+        limit: usize,
+    ) -> (usize, Vec<Arc<Event<EventDetails>>>) {
+        let recent = RECENT_EVENTS.lock();
+        let mut total = 0;
+        let mut results = Vec::with_capacity(limit.min(recent.events.len()));
+
+        for event in recent.events.iter().rev() {
+            if filter.matches(event) {
+                if total >= offset && results.len() < limit {
+                    results.push(event.clone());
+                }
+                total += 1;
+            }
+        }
+
+        (total, results)
+    }
+
+    fn push(&mut self, event: Arc<Event<EventDetails>>) {
+        if self.capacity > 0 {
+            if self.events.len() >= self.capacity {
+                self.events.pop_front();
+            }
+            self.events.push_back(event);
+        }
+    }
+}
+
+impl RecentEventsFilter {
+    pub fn matches(&self, event: &Event<EventDetails>) -> bool {
+        let span = event.inner.span.as_deref();
+
+        (self.types.is_empty() || self.types.contains(&event.inner.typ))
+            && self.from.is_none_or(|from| event.inner.timestamp >= from)
+            && self.to.is_none_or(|to| event.inner.timestamp <= to)
+            && self.span_id.is_none_or(|span_id| {
+                event.span_id() == Some(span_id)
+                    || span.is_some_and(|span| span.span_id() == Some(span_id))
+            })
+            && self.account_id.is_none_or(|account_id| {
+                // Session events inherit the account from their span
+                event
+                    .keys
+                    .iter()
+                    .chain(span.into_iter().flat_map(|span| span.keys.iter()))
+                    .any(|(key, value)| {
+                        *key == Key::AccountId
+                            && matches!(value, Value::UInt(id) if *id == account_id as u64)
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use trc::{Event, EventDetails, EventType, Key, Level, SmtpEvent};
+
+    use super::RecentEventsFilter;
+
+    fn event(typ: EventType, timestamp: u64, keys: Vec<(Key, trc::Value)>) -> Event<EventDetails> {
+        Event {
+            inner: EventDetails {
+                typ,
+                timestamp,
+                level: Level::Info,
+                span: None,
+            },
+            keys,
+        }
+    }
+
+    #[test]
+    fn recent_events_filter() {
+        let span = Arc::new(event(
+            EventType::Smtp(SmtpEvent::ConnectionStart),
+            100,
+            vec![(Key::SpanId, 7u64.into()), (Key::AccountId, 3u32.into())],
+        ));
+        let mut child = event(
+            EventType::Smtp(SmtpEvent::MailFrom),
+            105,
+            vec![(Key::SpanId, 7u64.into())],
+        );
+        child.inner.span = Some(span);
+
+        for (filter, expected) in [
+            (RecentEventsFilter::default(), true),
+            (
+                RecentEventsFilter {
+                    types: vec![EventType::Smtp(SmtpEvent::MailFrom)],
+                    ..Default::default()
+                },
+                true,
+            ),
+            (
+                RecentEventsFilter {
+                    types: vec![EventType::Smtp(SmtpEvent::RcptTo)],
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                RecentEventsFilter {
+                    account_id: Some(3),
+                    span_id: Some(7),
+                    ..Default::default()
+                },
+                true,
+            ),
+            (
+                RecentEventsFilter {
+                    account_id: Some(4),
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                RecentEventsFilter {
+                    from: Some(101),
+                    to: Some(110),
+                    ..Default::default()
+                },
+                true,
+            ),
+            (
+                RecentEventsFilter {
+                    to: Some(104),
+                    ..Default::default()
+                },
+                false,
+            ),
+        ] {
+            assert_eq!(filter.matches(&child), expected, "{filter:?}");
+        }
+    }
+}
@@ -7,6 +7,7 @@
 #[cfg(unix)]
 pub mod journald;
 pub mod log;
+pub mod memory;
 pub mod otel;
 pub mod stdout;
 
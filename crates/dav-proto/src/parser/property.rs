@@ -595,9 +595,19 @@ impl DavProperty {
             (Namespace::CalDav, Element::CalendarTimezoneId) => {
                 Some(DavProperty::CalDav(CalDavProperty::TimezoneId))
             }
+            (Namespace::Dav, Element::ShareAccess) => {
+                Some(DavProperty::WebDav(WebDavProperty::ShareAccess))
+            }
+            (Namespace::Dav, Element::Invite) => Some(DavProperty::WebDav(WebDavProperty::Invite)),
             (Namespace::CalendarServer, Element::Getctag) => {
                 Some(DavProperty::WebDav(WebDavProperty::GetCTag))
             }
+            (Namespace::CalendarServer, Element::Invite) => {
+                Some(DavProperty::WebDav(WebDavProperty::CalendarServerInvite))
+            }
+            (Namespace::CalendarServer, Element::AllowedSharingModes) => {
+                Some(DavProperty::WebDav(WebDavProperty::AllowedSharingModes))
+            }
             _ => None,
         }
     }
@@ -21,6 +21,7 @@ pub mod mkcol;
 pub mod propertyupdate;
 pub mod propfind;
 pub mod report;
+pub mod share;
 
 impl DavParser for DeadProperty {
     fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
@@ -205,7 +206,7 @@ impl Default for DeadProperty {
 mod tests {
     use crate::{
         parser::{tokenizer::Tokenizer, DavParser},
-        schema::request::{Acl, LockInfo, MkCol, PropFind, PropertyUpdate, Report},
+        schema::request::{Acl, LockInfo, MkCol, PropFind, PropertyUpdate, Report, Share},
     };
 
     #[test]
@@ -243,6 +244,8 @@ mod tests {
                     "acl" => {
                         serde_json::to_string_pretty(&Acl::parse(&mut tokenizer).unwrap()).unwrap()
                     }
+                    "share" => serde_json::to_string_pretty(&Share::parse(&mut tokenizer).unwrap())
+                        .unwrap(),
                     _ => {
                         panic!("Unknown method: {}", filename);
                     }
@@ -0,0 +1,234 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    parser::{tokenizer::Tokenizer, DavParser, Token},
+    schema::{
+        property::{InviteStatus, ShareAccess, Sharee},
+        request::{InviteReply, Share},
+        response::Href,
+        Element, NamedElement, Namespace,
+    },
+};
+
+impl DavParser for Share {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        match stream.unwrap_named_element()? {
+            NamedElement {
+                ns: Namespace::Dav,
+                element: Element::ShareResource,
+            } => {
+                let mut sharees = Vec::new();
+
+                loop {
+                    match stream.token()? {
+                        Token::ElementStart {
+                            name:
+                                NamedElement {
+                                    ns: Namespace::Dav,
+                                    element: Element::Sharee,
+                                },
+                            ..
+                        } => {
+                            if let Some(sharee) = Sharee::parse(stream, ShareAccess::NotShared)? {
+                                sharees.push(sharee);
+                            }
+                        }
+                        Token::ElementEnd | Token::Eof => {
+                            break;
+                        }
+                        Token::UnknownElement(_) => {
+                            stream.seek_element_end()?;
+                        }
+                        other => {
+                            return Err(other.into_unexpected());
+                        }
+                    }
+                }
+
+                Ok(Share::ShareResource(sharees))
+            }
+            NamedElement {
+                ns: Namespace::CalendarServer,
+                element: Element::Share,
+            } => {
+                let mut sharees = Vec::new();
+
+                loop {
+                    match stream.token()? {
+                        Token::ElementStart {
+                            name:
+                                NamedElement {
+                                    ns: Namespace::CalendarServer,
+                                    element: Element::Set,
+                                },
+                            ..
+                        } => {
+                            if let Some(sharee) = Sharee::parse(stream, ShareAccess::Read)? {
+                                sharees.push(sharee);
+                            }
+                        }
+                        Token::ElementStart {
+                            name:
+                                NamedElement {
+                                    ns: Namespace::CalendarServer,
+                                    element: Element::Remove,
+                                },
+                            ..
+                        } => {
+                            if let Some(mut sharee) = Sharee::parse(stream, ShareAccess::NoAccess)?
+                            {
+                                sharee.access = ShareAccess::NoAccess;
+                                sharees.push(sharee);
+                            }
+                        }
+                        Token::ElementEnd | Token::Eof => {
+                            break;
+                        }
+                        Token::UnknownElement(_) => {
+                            stream.seek_element_end()?;
+                        }
+                        other => {
+                            return Err(other.into_unexpected());
+                        }
+                    }
+                }
+
+                Ok(Share::ShareResource(sharees))
+            }
+            NamedElement {
+                ns: Namespace::Dav | Namespace::CalendarServer,
+                element: Element::InviteReply,
+            } => InviteReply::parse(stream).map(Share::InviteReply),
+            other => Err(other.into_unexpected()),
+        }
+    }
+}
+
+impl Sharee {
+    fn parse(
+        stream: &mut Tokenizer<'_>,
+        default_access: ShareAccess,
+    ) -> crate::parser::Result<Option<Self>> {
+        let mut href = None;
+        let mut common_name = None;
+        let mut comment = None;
+        let mut access = default_access;
+        let mut depth = 1;
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart { name, .. } => match (name.ns, name.element) {
+                    (Namespace::Dav, Element::Href) => {
+                        href = stream.collect_string_value()?.map(Href);
+                    }
+                    (Namespace::CalendarServer, Element::CommonName) => {
+                        common_name = stream.collect_string_value()?;
+                    }
+                    (Namespace::Dav, Element::Comment)
+                    | (Namespace::CalendarServer, Element::Summary) => {
+                        comment = stream.collect_string_value()?;
+                    }
+                    (Namespace::Dav, Element::ShareAccess) => {
+                        depth += 1;
+                    }
+                    (_, element) => {
+                        if let Some(share_access) = ShareAccess::from_element(element) {
+                            access = share_access;
+                            stream.expect_element_end()?;
+                        } else {
+                            stream.seek_element_end()?;
+                        }
+                    }
+                },
+                Token::ElementEnd => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                Token::UnknownElement(_) => {
+                    stream.seek_element_end()?;
+                }
+                other => {
+                    return Err(other.into_unexpected());
+                }
+            }
+        }
+
+        Ok(href.map(|href| Sharee {
+            href,
+            common_name,
+            comment,
+            access,
+            status: InviteStatus::NoResponse,
+        }))
+    }
+}
+
+impl DavParser for InviteReply {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        let mut reply = InviteReply {
+            href: None,
+            host_url: None,
+            in_reply_to: None,
+            accepted: false,
+        };
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart { name, .. } => match (name.ns, name.element) {
+                    (Namespace::Dav, Element::Href) => {
+                        reply.href = stream.collect_string_value()?.map(Href);
+                    }
+                    (_, Element::InviteAccepted) => {
+                        reply.accepted = true;
+                        stream.expect_element_end()?;
+                    }
+                    (_, Element::InviteDeclined) => {
+                        reply.accepted = false;
+                        stream.expect_element_end()?;
+                    }
+                    (_, Element::Hosturl) => {
+                        stream.expect_named_element(NamedElement::dav(Element::Href))?;
+                        reply.host_url = stream.collect_string_value()?.map(Href);
+                        stream.expect_element_end()?;
+                    }
+                    (_, Element::InReplyTo) => {
+                        reply.in_reply_to = stream.collect_string_value()?;
+                    }
+                    _ => {
+                        stream.seek_element_end()?;
+                    }
+                },
+                Token::ElementEnd | Token::Eof => {
+                    break;
+                }
+                Token::UnknownElement(_) => {
+                    stream.seek_element_end()?;
+                }
+                other => {
+                    return Err(other.into_unexpected());
+                }
+            }
+        }
+
+        Ok(reply)
+    }
+}
+
+impl ShareAccess {
+    pub fn from_element(element: Element) -> Option<Self> {
+        match element {
+            Element::SharedOwner => Some(ShareAccess::SharedOwner),
+            Element::NotShared => Some(ShareAccess::NotShared),
+            Element::Read => Some(ShareAccess::Read),
+            Element::ReadWrite => Some(ShareAccess::ReadWrite),
+            Element::NoAccess => Some(ShareAccess::NoAccess),
+            _ => None,
+        }
+    }
+}
@@ -7,9 +7,9 @@
 use super::{XmlCdataEscape, XmlEscape};
 use crate::schema::{
     property::{
-        ActiveLock, CalDavProperty, CardDavProperty, Comp, DavProperty, DavValue, LockDiscovery,
-        LockEntry, PrincipalProperty, Privilege, ReportSet, ResourceType, Rfc1123DateTime,
-        SupportedCollation, SupportedLock, WebDavProperty,
+        ActiveLock, CalDavProperty, CardDavProperty, Comp, DavProperty, DavValue, InviteStatus,
+        LockDiscovery, LockEntry, PrincipalProperty, Privilege, ReportSet, ResourceType,
+        Rfc1123DateTime, ShareAccess, Sharee, SupportedCollation, SupportedLock, WebDavProperty,
     },
     request::{DavPropertyValue, DeadProperty},
     response::{Ace, AclRestrictions, Href, List, PropResponse, SupportedPrivilege},
@@ -96,6 +96,15 @@ impl Display for DavValue {
             DavValue::Acl(v) => v.fmt(f),
             DavValue::AclRestrictions(v) => v.fmt(f),
             DavValue::DeadProperty(v) => v.fmt(f),
+            DavValue::ShareAccess(v) => v.fmt(f),
+            DavValue::Invite(v) => v.fmt(f),
+            DavValue::CalendarServerInvite(v) => {
+                for sharee in &v.0 {
+                    sharee.fmt_calendar_server(f)?;
+                }
+                Ok(())
+            }
+            DavValue::AllowedSharingModes => write!(f, "<C:can-be-shared/>"),
             DavValue::SupportedAddressData => {
                 write!(
                     f,
@@ -169,7 +178,11 @@ impl DavProperty {
                     WebDavProperty::AclRestrictions => "D:acl-restrictions",
                     WebDavProperty::InheritedAclSet => "D:inherited-acl-set",
                     WebDavProperty::PrincipalCollectionSet => "D:principal-collection-set",
+                    WebDavProperty::ShareAccess => "D:share-access",
+                    WebDavProperty::Invite => "D:invite",
                     WebDavProperty::GetCTag => "C:getctag",
+                    WebDavProperty::CalendarServerInvite => "C:invite",
+                    WebDavProperty::AllowedSharingModes => "C:allowed-sharing-modes",
                 },
                 DavProperty::CardDav(prop) => match prop {
                     CardDavProperty::AddressbookDescription => "B:addressbook-description",
@@ -221,7 +234,11 @@ impl DavProperty {
 
     pub fn namespace(&self) -> Namespace {
         match self {
-            DavProperty::WebDav(WebDavProperty::GetCTag) => Namespace::CalendarServer,
+            DavProperty::WebDav(
+                WebDavProperty::GetCTag
+                | WebDavProperty::CalendarServerInvite
+                | WebDavProperty::AllowedSharingModes,
+            ) => Namespace::CalendarServer,
             DavProperty::CardDav(_)
             | DavProperty::Principal(PrincipalProperty::AddressbookHomeSet) => Namespace::CardDav,
             DavProperty::CalDav(_)
@@ -243,6 +260,73 @@ impl AsRef<str> for DavProperty {
     }
 }
 
+impl Display for ShareAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShareAccess::SharedOwner => write!(f, "<D:shared-owner/>"),
+            ShareAccess::NotShared => write!(f, "<D:not-shared/>"),
+            ShareAccess::Read => write!(f, "<D:read/>"),
+            ShareAccess::ReadWrite => write!(f, "<D:read-write/>"),
+            ShareAccess::NoAccess => write!(f, "<D:no-access/>"),
+        }
+    }
+}
+
+impl Display for Sharee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<D:sharee>")?;
+        self.href.fmt(f)?;
+        if let Some(common_name) = &self.common_name {
+            write!(f, "<D:prop><D:displayname>")?;
+            common_name.write_escaped_to(f)?;
+            write!(f, "</D:displayname></D:prop>")?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(f, "<D:comment>")?;
+            comment.write_escaped_to(f)?;
+            write!(f, "</D:comment>")?;
+        }
+        write!(f, "<D:share-access>{}</D:share-access>", self.access)?;
+        match self.status {
+            InviteStatus::Accepted => write!(f, "<D:invite-accepted/>"),
+            InviteStatus::Declined => write!(f, "<D:invite-declined/>"),
+            InviteStatus::NoResponse => write!(f, "<D:invite-noresponse/>"),
+            InviteStatus::Invalid => write!(f, "<D:invite-invalid/>"),
+        }?;
+        write!(f, "</D:sharee>")
+    }
+}
+
+impl Sharee {
+    fn fmt_calendar_server(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<C:user>")?;
+        self.href.fmt(f)?;
+        if let Some(common_name) = &self.common_name {
+            write!(f, "<C:common-name>")?;
+            common_name.write_escaped_to(f)?;
+            write!(f, "</C:common-name>")?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(f, "<C:summary>")?;
+            comment.write_escaped_to(f)?;
+            write!(f, "</C:summary>")?;
+        }
+        match self.status {
+            InviteStatus::Accepted => write!(f, "<C:invite-accepted/>"),
+            InviteStatus::Declined => write!(f, "<C:invite-declined/>"),
+            InviteStatus::NoResponse => write!(f, "<C:invite-noresponse/>"),
+            InviteStatus::Invalid => write!(f, "<C:invite-invalid/>"),
+        }?;
+        match self.access {
+            ShareAccess::ReadWrite | ShareAccess::SharedOwner => {
+                write!(f, "<C:access><C:read-write/></C:access>")
+            }
+            _ => write!(f, "<C:access><C:read/></C:access>"),
+        }?;
+        write!(f, "</C:user>")
+    }
+}
+
 impl Display for ReportSet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("<D:supported-report><D:report>")?;
@@ -410,6 +494,18 @@ impl From<AclRestrictions> for DavValue {
     }
 }
 
+impl From<ShareAccess> for DavValue {
+    fn from(v: ShareAccess) -> Self {
+        DavValue::ShareAccess(v)
+    }
+}
+
+impl From<Vec<Sharee>> for DavValue {
+    fn from(v: Vec<Sharee>) -> Self {
+        DavValue::Invite(List(v))
+    }
+}
+
 impl From<DeadProperty> for DavValue {
     fn from(v: DeadProperty) -> Self {
         DavValue::DeadProperty(v)
@@ -87,6 +87,7 @@ impl AsRef<str> for Namespace {
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub enum Element {
     Abstract,
+    Access,
     Ace,
     Acl,
     AclPrincipalPropSet,
@@ -113,6 +114,7 @@ pub enum Element {
     AllowedAttendeeSchedulingObjectChange,
     AllowedOrganizerSchedulingObjectChange,
     AllowedPrincipal,
+    AllowedSharingModes,
     Allprop,
     AlternateUriSet,
     And,
@@ -147,6 +149,7 @@ pub enum Element {
     CalendarTimezoneId,
     CalendarUserAddressSet,
     CalendarUserType,
+    CanBeShared,
     Caseless,
     ChangedVersion,
     CheckedIn,
@@ -164,6 +167,7 @@ pub enum Element {
     Collection,
     Comment,
     CommonAncestor,
+    CommonName,
     Comp,
     CompFilter,
     CompareBaseline,
@@ -211,13 +215,21 @@ pub enum Element {
     GroupMembership,
     Gt,
     Gte,
+    Hosturl,
     Href,
     IgnorePreview,
+    InReplyTo,
     Include,
     IncludeVersions,
     Inherited,
     InheritedAclSet,
     Invert,
+    Invite,
+    InviteAccepted,
+    InviteDeclined,
+    InviteInvalid,
+    InviteNoresponse,
+    InviteReply,
     IsCollection,
     IsDefined,
     IsNotDefined,
@@ -279,6 +291,7 @@ pub enum Element {
     NeedPrivileges,
     New,
     NoAbstract,
+    NoAccess,
     NoAceConflict,
     NoAutoMerge,
     NoCheckout,
@@ -288,6 +301,7 @@ pub enum Element {
     NoProtectedAceConflict,
     NoUidConflict,
     Not,
+    NotShared,
     NotSupportedPrivilege,
     Nresults,
     Opaque,
@@ -342,6 +356,7 @@ pub enum Element {
     ReadAcl,
     ReadCurrentUserPrivilegeSet,
     ReadFreeBusy,
+    ReadWrite,
     Rebind,
     RebindResponse,
     Recipient,
@@ -384,13 +399,19 @@ pub enum Element {
     Selectable,
     Self_,
     Set,
+    Share,
+    ShareAccess,
+    ShareResource,
     Shared,
+    SharedOwner,
+    Sharee,
     Sortable,
     Source,
     Status,
     SubactivitySet,
     SubbaselineSet,
     SuccessorSet,
+    Summary,
     SupportedAddressData,
     SupportedCalendarComponentSet,
     SupportedCalendarData,
@@ -436,6 +457,7 @@ pub enum Element {
     Updateredirectref,
     UpdateredirectrefResponse,
     Url,
+    User,
     Username,
     ValidOrganizer,
     ValidScheduleDefaultCalendarUrl,
@@ -467,6 +489,7 @@ impl Element {
         hashify::map!(value,
             Element,
             "abstract" => Element::Abstract,
+            "access" => Element::Access,
             "ace" => Element::Ace,
             "acl" => Element::Acl,
             "acl-principal-prop-set" => Element::AclPrincipalPropSet,
@@ -493,6 +516,7 @@ impl Element {
             "allowed-attendee-scheduling-object-change" => Element::AllowedAttendeeSchedulingObjectChange,
             "allowed-organizer-scheduling-object-change" => Element::AllowedOrganizerSchedulingObjectChange,
             "allowed-principal" => Element::AllowedPrincipal,
+            "allowed-sharing-modes" => Element::AllowedSharingModes,
             "allprop" => Element::Allprop,
             "alternate-URI-set" => Element::AlternateUriSet,
             "and" => Element::And,
@@ -527,6 +551,7 @@ impl Element {
             "calendar-timezone-id" => Element::CalendarTimezoneId,
             "calendar-user-address-set" => Element::CalendarUserAddressSet,
             "calendar-user-type" => Element::CalendarUserType,
+            "can-be-shared" => Element::CanBeShared,
             "caseless" => Element::Caseless,
             "changed-version" => Element::ChangedVersion,
             "checked-in" => Element::CheckedIn,
@@ -544,6 +569,7 @@ impl Element {
             "collection" => Element::Collection,
             "comment" => Element::Comment,
             "common-ancestor" => Element::CommonAncestor,
+            "common-name" => Element::CommonName,
             "comp" => Element::Comp,
             "comp-filter" => Element::CompFilter,
             "compare-baseline" => Element::CompareBaseline,
@@ -591,13 +617,21 @@ impl Element {
             "group-membership" => Element::GroupMembership,
             "gt" => Element::Gt,
             "gte" => Element::Gte,
+            "hosturl" => Element::Hosturl,
             "href" => Element::Href,
             "ignore-preview" => Element::IgnorePreview,
+            "in-reply-to" => Element::InReplyTo,
             "include" => Element::Include,
             "include-versions" => Element::IncludeVersions,
             "inherited" => Element::Inherited,
             "inherited-acl-set" => Element::InheritedAclSet,
             "invert" => Element::Invert,
+            "invite" => Element::Invite,
+            "invite-accepted" => Element::InviteAccepted,
+            "invite-declined" => Element::InviteDeclined,
+            "invite-invalid" => Element::InviteInvalid,
+            "invite-noresponse" => Element::InviteNoresponse,
+            "invite-reply" => Element::InviteReply,
             "is-collection" => Element::IsCollection,
             "is-defined" => Element::IsDefined,
             "is-not-defined" => Element::IsNotDefined,
@@ -659,6 +693,7 @@ impl Element {
             "need-privileges" => Element::NeedPrivileges,
             "new" => Element::New,
             "no-abstract" => Element::NoAbstract,
+            "no-access" => Element::NoAccess,
             "no-ace-conflict" => Element::NoAceConflict,
             "no-auto-merge" => Element::NoAutoMerge,
             "no-checkout" => Element::NoCheckout,
@@ -668,6 +703,7 @@ impl Element {
             "no-protected-ace-conflict" => Element::NoProtectedAceConflict,
             "no-uid-conflict" => Element::NoUidConflict,
             "not" => Element::Not,
+            "not-shared" => Element::NotShared,
             "not-supported-privilege" => Element::NotSupportedPrivilege,
             "nresults" => Element::Nresults,
             "opaque" => Element::Opaque,
@@ -722,6 +758,7 @@ impl Element {
             "read-acl" => Element::ReadAcl,
             "read-current-user-privilege-set" => Element::ReadCurrentUserPrivilegeSet,
             "read-free-busy" => Element::ReadFreeBusy,
+            "read-write" => Element::ReadWrite,
             "rebind" => Element::Rebind,
             "rebind-response" => Element::RebindResponse,
             "recipient" => Element::Recipient,
@@ -764,13 +801,19 @@ impl Element {
             "selectable" => Element::Selectable,
             "self" => Element::Self_,
             "set" => Element::Set,
+            "share" => Element::Share,
+            "share-access" => Element::ShareAccess,
+            "share-resource" => Element::ShareResource,
             "shared" => Element::Shared,
+            "shared-owner" => Element::SharedOwner,
+            "sharee" => Element::Sharee,
             "sortable" => Element::Sortable,
             "source" => Element::Source,
             "status" => Element::Status,
             "subactivity-set" => Element::SubactivitySet,
             "subbaseline-set" => Element::SubbaselineSet,
             "successor-set" => Element::SuccessorSet,
+            "summary" => Element::Summary,
             "supported-address-data" => Element::SupportedAddressData,
             "supported-calendar-component-set" => Element::SupportedCalendarComponentSet,
             "supported-calendar-data" => Element::SupportedCalendarData,
@@ -816,6 +859,7 @@ impl Element {
             "updateredirectref" => Element::Updateredirectref,
             "updateredirectref-response" => Element::UpdateredirectrefResponse,
             "url" => Element::Url,
+            "user" => Element::User,
             "username" => Element::Username,
             "valid-organizer" => Element::ValidOrganizer,
             "valid-schedule-default-calendar-URL" => Element::ValidScheduleDefaultCalendarUrl,
@@ -848,6 +892,7 @@ impl AsRef<str> for Element {
     fn as_ref(&self) -> &str {
         match self {
             Element::Abstract => "abstract",
+            Element::Access => "access",
             Element::Ace => "ace",
             Element::Acl => "acl",
             Element::AclPrincipalPropSet => "acl-principal-prop-set",
@@ -878,6 +923,7 @@ impl AsRef<str> for Element {
                 "allowed-organizer-scheduling-object-change"
             }
             Element::AllowedPrincipal => "allowed-principal",
+            Element::AllowedSharingModes => "allowed-sharing-modes",
             Element::Allprop => "allprop",
             Element::AlternateUriSet => "alternate-URI-set",
             Element::And => "and",
@@ -912,6 +958,7 @@ impl AsRef<str> for Element {
             Element::CalendarTimezoneId => "calendar-timezone-id",
             Element::CalendarUserAddressSet => "calendar-user-address-set",
             Element::CalendarUserType => "calendar-user-type",
+            Element::CanBeShared => "can-be-shared",
             Element::Caseless => "caseless",
             Element::ChangedVersion => "changed-version",
             Element::CheckedIn => "checked-in",
@@ -929,6 +976,7 @@ impl AsRef<str> for Element {
             Element::Collection => "collection",
             Element::Comment => "comment",
             Element::CommonAncestor => "common-ancestor",
+            Element::CommonName => "common-name",
             Element::Comp => "comp",
             Element::CompFilter => "comp-filter",
             Element::CompareBaseline => "compare-baseline",
@@ -976,13 +1024,21 @@ impl AsRef<str> for Element {
             Element::GroupMembership => "group-membership",
             Element::Gt => "gt",
             Element::Gte => "gte",
+            Element::Hosturl => "hosturl",
             Element::Href => "href",
             Element::IgnorePreview => "ignore-preview",
+            Element::InReplyTo => "in-reply-to",
             Element::Include => "include",
             Element::IncludeVersions => "include-versions",
             Element::Inherited => "inherited",
             Element::InheritedAclSet => "inherited-acl-set",
             Element::Invert => "invert",
+            Element::Invite => "invite",
+            Element::InviteAccepted => "invite-accepted",
+            Element::InviteDeclined => "invite-declined",
+            Element::InviteInvalid => "invite-invalid",
+            Element::InviteNoresponse => "invite-noresponse",
+            Element::InviteReply => "invite-reply",
             Element::IsCollection => "is-collection",
             Element::IsDefined => "is-defined",
             Element::IsNotDefined => "is-not-defined",
@@ -1044,6 +1100,7 @@ impl AsRef<str> for Element {
             Element::NeedPrivileges => "need-privileges",
             Element::New => "new",
             Element::NoAbstract => "no-abstract",
+            Element::NoAccess => "no-access",
             Element::NoAceConflict => "no-ace-conflict",
             Element::NoAutoMerge => "no-auto-merge",
             Element::NoCheckout => "no-checkout",
@@ -1053,6 +1110,7 @@ impl AsRef<str> for Element {
             Element::NoProtectedAceConflict => "no-protected-ace-conflict",
             Element::NoUidConflict => "no-uid-conflict",
             Element::Not => "not",
+            Element::NotShared => "not-shared",
             Element::NotSupportedPrivilege => "not-supported-privilege",
             Element::Nresults => "nresults",
             Element::Opaque => "opaque",
@@ -1107,6 +1165,7 @@ impl AsRef<str> for Element {
             Element::ReadAcl => "read-acl",
             Element::ReadCurrentUserPrivilegeSet => "read-current-user-privilege-set",
             Element::ReadFreeBusy => "read-free-busy",
+            Element::ReadWrite => "read-write",
             Element::Rebind => "rebind",
             Element::RebindResponse => "rebind-response",
             Element::Recipient => "recipient",
@@ -1149,13 +1208,19 @@ impl AsRef<str> for Element {
             Element::Selectable => "selectable",
             Element::Self_ => "self",
             Element::Set => "set",
+            Element::Share => "share",
+            Element::ShareAccess => "share-access",
+            Element::ShareResource => "share-resource",
             Element::Shared => "shared",
+            Element::SharedOwner => "shared-owner",
+            Element::Sharee => "sharee",
             Element::Sortable => "sortable",
             Element::Source => "source",
             Element::Status => "status",
             Element::SubactivitySet => "subactivity-set",
             Element::SubbaselineSet => "subbaseline-set",
             Element::SuccessorSet => "successor-set",
+            Element::Summary => "summary",
             Element::SupportedAddressData => "supported-address-data",
             Element::SupportedCalendarComponentSet => "supported-calendar-component-set",
             Element::SupportedCalendarData => "supported-calendar-data",
@@ -1201,6 +1266,7 @@ impl AsRef<str> for Element {
             Element::Updateredirectref => "updateredirectref",
             Element::UpdateredirectrefResponse => "updateredirectref-response",
             Element::Url => "url",
+            Element::User => "user",
             Element::Username => "username",
             Element::ValidOrganizer => "valid-organizer",
             Element::ValidScheduleDefaultCalendarUrl => "valid-schedule-default-calendar-URL",
@@ -58,8 +58,13 @@ pub enum WebDavProperty {
     AclRestrictions,
     InheritedAclSet,
     PrincipalCollectionSet,
+    // Sharing properties
+    ShareAccess,
+    Invite,
     // Apple proprietary properties
     GetCTag,
+    CalendarServerInvite,
+    AllowedSharingModes,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -172,12 +177,45 @@ pub enum DavValue {
     AclRestrictions(AclRestrictions),
     Response(Response),
     DeadProperty(DeadProperty),
+    ShareAccess(ShareAccess),
+    Invite(List<Sharee>),
+    CalendarServerInvite(List<Sharee>),
+    AllowedSharingModes,
     SupportedAddressData,
     SupportedCalendarData,
     SupportedCalendarComponentSet,
     Null,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub enum ShareAccess {
+    SharedOwner,
+    NotShared,
+    Read,
+    ReadWrite,
+    NoAccess,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub enum InviteStatus {
+    Accepted,
+    Declined,
+    NoResponse,
+    Invalid,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct Sharee {
+    pub href: Href,
+    pub common_name: Option<String>,
+    pub comment: Option<String>,
+    pub access: ShareAccess,
+    pub status: InviteStatus,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub enum ReportSet {
@@ -12,8 +12,8 @@ use calcard::{
 use crate::Depth;
 
 use super::{
-    property::{DavProperty, DavValue, LockScope, LockType, TimeRange},
-    response::Ace,
+    property::{DavProperty, DavValue, LockScope, LockType, Sharee, TimeRange},
+    response::{Ace, Href},
     Collation, MatchType,
 };
 
@@ -251,6 +251,23 @@ pub struct PropertySearch {
     pub match_: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(test, serde(tag = "type", content = "data"))]
+pub enum Share {
+    ShareResource(Vec<Sharee>),
+    InviteReply(InviteReply),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct InviteReply {
+    pub href: Option<Href>,
+    pub host_url: Option<Href>,
+    pub in_reply_to: Option<String>,
+    pub accepted: bool,
+}
+
 impl From<&ArchivedDeadProperty> for DeadProperty {
     fn from(value: &ArchivedDeadProperty) -> Self {
         DeadProperty(value.0.iter().map(|tag| tag.into()).collect::<Vec<_>>())
@@ -490,6 +490,7 @@ async fn copy_card(
             .assign_document_ids(to_account_id, Collection::ContactCard, 1)
             .await
             .caused_by(trc::location!())?;
+        new_card.relink_photos(to_account_id, to_document_id);
         new_card
             .insert(access_token, to_account_id, to_document_id, &mut batch)
             .caused_by(trc::location!())?;
@@ -632,6 +633,7 @@ async fn move_card(
             .assign_document_ids(to_account_id, Collection::ContactCard, 1)
             .await
             .caused_by(trc::location!())?;
+        new_card.relink_photos(to_account_id, to_document_id);
         new_card
             .insert(access_token, to_account_id, to_document_id, &mut batch)
             .caused_by(trc::location!())?;
@@ -10,6 +10,7 @@ use dav_proto::schema::{
     property::{CardDavProperty, DavProperty, WebDavProperty},
     response::CardCondition,
 };
+use groupware::contact::ContactCard;
 use hyper::StatusCode;
 use store::query::Filter;
 use trc::AddContext;
@@ -103,3 +104,36 @@ pub(crate) async fn assert_is_unique_uid(
 
     Ok(())
 }
+
+pub(crate) async fn find_duplicate_cards(
+    server: &Server,
+    resources: &DavResources,
+    account_id: u32,
+    addressbook_id: u32,
+    card: &ContactCard,
+) -> crate::Result<Vec<String>> {
+    let mut hrefs = Vec::new();
+    for email in card.emails() {
+        let hits = server
+            .store()
+            .filter(
+                account_id,
+                Collection::ContactCard,
+                vec![Filter::eq(ContactField::Email, email.into_bytes())],
+            )
+            .await
+            .caused_by(trc::location!())?;
+        if !hits.results.is_empty() {
+            for path in resources.children(addressbook_id) {
+                if hits.results.contains(path.document_id()) {
+                    let href = resources.format_resource(path);
+                    if !hrefs.contains(&href) {
+                        hrefs.push(href);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(hrefs)
+}
@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::assert_is_unique_uid;
+use super::{assert_is_unique_uid, find_duplicate_cards};
 use crate::{
     DavError, DavErrorCondition, DavMethod,
     common::{
@@ -16,18 +16,28 @@ use crate::{
     fix_percent_encoding,
 };
 use calcard::{Entry, Parser};
-use common::{DavName, Server, auth::AccessToken};
+use common::{
+    DavName, Server,
+    auth::{AccessToken, AsTenantId},
+};
 use dav_proto::{
     RequestHeaders, Return,
     schema::{property::Rfc1123DateTime, response::CardCondition},
 };
-use groupware::{cache::GroupwareCache, contact::ContactCard};
+use groupware::{
+    cache::GroupwareCache,
+    contact::{
+        ContactCard,
+        normalize::{ContactNormalize, NormalizeError},
+    },
+};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
 use store::write::BatchBuilder;
 use trc::AddContext;
 use types::{
     acl::Acl,
+    blob_hash::BlobHash,
     collection::{Collection, SyncCollection},
 };
 
@@ -36,6 +46,7 @@ pub(crate) trait CardUpdateRequestHandler: Sync + Send {
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
+        base_url: &str,
         bytes: Vec<u8>,
         is_patch: bool,
     ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
@@ -46,6 +57,7 @@ impl CardUpdateRequestHandler for Server {
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
+        base_url: &str,
         bytes: Vec<u8>,
         _is_patch: bool,
     ) -> crate::Result<HttpResponse> {
@@ -81,7 +93,7 @@ impl CardUpdateRequestHandler for Server {
             )
         })?;
 
-        let vcard = match Parser::new(vcard_raw).strict().entry() {
+        let mut vcard = match Parser::new(vcard_raw).strict().entry() {
             Entry::VCard(vcard) => vcard,
             _ => {
                 return Err(DavError::Condition(
@@ -94,6 +106,27 @@ impl CardUpdateRequestHandler for Server {
             }
         };
 
+        // Normalize card
+        let normalize_config = self
+            .core
+            .groupware
+            .vcard_normalize_for(access_token.tenant_id());
+        let is_normalized = match vcard.normalize(normalize_config) {
+            Ok(is_normalized) => is_normalized,
+            Err(err) => {
+                return Err(DavError::Condition(
+                    DavErrorCondition::new(
+                        StatusCode::PRECONDITION_FAILED,
+                        CardCondition::ValidAddressData,
+                    )
+                    .with_details(match err {
+                        NormalizeError::InvalidPhoto => "Failed to decode contact photo.",
+                        NormalizeError::PhotoTooLarge => "Contact photo exceeds the maximum size.",
+                    }),
+                ));
+            }
+        };
+
         if let Some(resource) = resources.by_path(resource_name.as_ref()) {
             if resource.is_container() {
                 return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
@@ -164,9 +197,23 @@ impl CardUpdateRequestHandler for Server {
                 }
             }
 
+            // Build node
+            let mut new_card = card
+                .deserialize::<ContactCard>()
+                .caused_by(trc::location!())?;
+            new_card.card = vcard;
+            let photos = externalize_photos(
+                &mut new_card,
+                base_url,
+                account_id,
+                document_id,
+                &card.inner.photo_hashes(),
+            )?;
+            new_card.size = card_size(&new_card, &bytes, is_normalized, &photos);
+
             // Validate quota
             let extra_bytes =
-                (bytes.len() as u64).saturating_sub(u32::from(card.inner.size) as u64);
+                (new_card.size as u64).saturating_sub(u32::from(card.inner.size) as u64);
             if extra_bytes > 0 {
                 self.has_available_quota(
                     &self.get_resource_token(access_token, account_id).await?,
@@ -175,12 +222,12 @@ impl CardUpdateRequestHandler for Server {
                 .await?;
             }
 
-            // Build node
-            let mut new_card = card
-                .deserialize::<ContactCard>()
-                .caused_by(trc::location!())?;
-            new_card.size = bytes.len() as u32;
-            new_card.card = vcard;
+            // Store photos
+            for photo in &photos {
+                self.put_blob(account_id, photo, false)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
 
             // Prepare write batch
             let mut batch = BatchBuilder::new();
@@ -233,42 +280,99 @@ impl CardUpdateRequestHandler for Server {
             )
             .await?;
 
-            // Validate quota
-            if !bytes.is_empty() {
-                self.has_available_quota(
-                    &self.get_resource_token(access_token, account_id).await?,
-                    bytes.len() as u64,
-                )
-                .await?;
-            }
-
             // Build node
-            let card = ContactCard {
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::ContactCard, 1)
+                .await
+                .caused_by(trc::location!())?;
+            let mut card = ContactCard {
                 names: vec![DavName {
                     name: name.to_string(),
                     parent_id: parent.document_id(),
                 }],
                 card: vcard,
-                size: bytes.len() as u32,
                 ..Default::default()
             };
+            let photos = externalize_photos(&mut card, base_url, account_id, document_id, &[])?;
+            card.size = card_size(&card, &bytes, is_normalized, &photos);
+
+            // Validate quota
+            if card.size > 0 {
+                self.has_available_quota(
+                    &self.get_resource_token(access_token, account_id).await?,
+                    card.size as u64,
+                )
+                .await?;
+            }
+
+            // Look for cards sharing an e-mail address
+            let duplicates = if normalize_config.duplicate_hints {
+                find_duplicate_cards(self, &resources, account_id, parent.document_id(), &card)
+                    .await?
+            } else {
+                vec![]
+            };
+
+            // Store photos
+            for photo in &photos {
+                self.put_blob(account_id, photo, false)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
 
             // Prepare write batch
             let mut batch = BatchBuilder::new();
-            let document_id = self
-                .store()
-                .assign_document_ids(account_id, Collection::ContactCard, 1)
-                .await
-                .caused_by(trc::location!())?;
             let etag = card
                 .insert(access_token, account_id, document_id, &mut batch)
                 .caused_by(trc::location!())?
                 .etag();
             self.commit_batch(batch).await.caused_by(trc::location!())?;
 
-            Ok(HttpResponse::new(StatusCode::CREATED).with_etag_opt(etag))
+            let response = HttpResponse::new(StatusCode::CREATED).with_etag_opt(etag);
+            if !duplicates.is_empty() {
+                Ok(response.with_header(
+                    "Link",
+                    duplicates
+                        .iter()
+                        .map(|href| format!("<{href}>; rel=\"duplicate\""))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ))
+            } else {
+                Ok(response)
+            }
         } else {
             Err(DavError::Code(StatusCode::CONFLICT))?
         }
     }
 }
+
+fn externalize_photos(
+    card: &mut ContactCard,
+    base_url: &str,
+    account_id: u32,
+    document_id: u32,
+    current_hashes: &[BlobHash],
+) -> crate::Result<Vec<Vec<u8>>> {
+    if !card.has_foreign_photos(account_id, document_id, current_hashes) {
+        Ok(card.externalize_photos(base_url, account_id, document_id))
+    } else {
+        Err(DavError::Condition(
+            DavErrorCondition::new(
+                StatusCode::PRECONDITION_FAILED,
+                CardCondition::ValidAddressData,
+            )
+            .with_details("Contact photo references an unknown blob."),
+        ))
+    }
+}
+
+// Stored photos are accounted for in the card's size
+fn card_size(card: &ContactCard, bytes: &[u8], is_normalized: bool, photos: &[Vec<u8>]) -> u32 {
+    if is_normalized || !photos.is_empty() {
+        (card.card.to_string().len() + photos.iter().map(|photo| photo.len()).sum::<usize>()) as u32
+    } else {
+        bytes.len() as u32
+    }
+}
@@ -36,6 +36,7 @@ use uri::{OwnedUri, Urn};
 pub mod acl;
 pub mod lock;
 pub mod propfind;
+pub mod share;
 pub mod uri;
 
 #[derive(Debug)]
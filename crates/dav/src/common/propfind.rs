@@ -8,6 +8,7 @@ use super::{
     ArchivedResource, DavCollection, DavQuery, DavQueryFilter, ETag, SyncType,
     acl::{DavAclHandler, Privileges},
     lock::{LockData, build_lock_key},
+    share::DavShareHandler,
     uri::{UriResource, Urn},
 };
 use crate::{
@@ -31,6 +32,7 @@ use calcard::common::timezone::Tz;
 use common::{
     DavResourcePath, DavResources, Server,
     auth::{AccessToken, AsTenantId},
+    sharing::EffectiveAcl,
 };
 use dav_proto::{
     Depth, RequestHeaders,
@@ -39,8 +41,8 @@ use dav_proto::{
         Collation, Namespace,
         property::{
             ActiveLock, CalDavProperty, CardDavProperty, DavProperty, DavValue, PrincipalProperty,
-            Privilege, ReportSet, ResourceType, Rfc1123DateTime, SupportedCollation, SupportedLock,
-            WebDavProperty,
+            Privilege, ReportSet, ResourceType, Rfc1123DateTime, ShareAccess, SupportedCollation,
+            SupportedLock, WebDavProperty,
         },
         request::{DavPropertyValue, DeadProperty, PropFind},
         response::{
@@ -763,6 +765,75 @@ impl PropFindRequestHandler for Server {
                                 )],
                             ));
                         }
+                        WebDavProperty::ShareAccess => {
+                            if let (
+                                Some(acls),
+                                ArchivedResource::Calendar(_) | ArchivedResource::AddressBook(_),
+                            ) = (archive.acls(), &archive)
+                            {
+                                let access = if access_token.is_member(account_id) {
+                                    if acls.is_empty() {
+                                        ShareAccess::NotShared
+                                    } else {
+                                        ShareAccess::SharedOwner
+                                    }
+                                } else {
+                                    let acls = acls.effective_acl(access_token);
+                                    if acls.contains(Acl::AddItems)
+                                        || acls.contains(Acl::ModifyItems)
+                                    {
+                                        ShareAccess::ReadWrite
+                                    } else if acls.contains(Acl::ReadItems) {
+                                        ShareAccess::Read
+                                    } else {
+                                        ShareAccess::NoAccess
+                                    }
+                                };
+
+                                fields.push(DavPropertyValue::new(property.clone(), access));
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        WebDavProperty::Invite | WebDavProperty::CalendarServerInvite => {
+                            if matches!(
+                                archive,
+                                ArchivedResource::Calendar(_) | ArchivedResource::AddressBook(_)
+                            ) {
+                                let sharees = List(
+                                    self.resolve_sharees(access_token, account_id, &archive)
+                                        .await?,
+                                );
+
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    if matches!(dav_property, WebDavProperty::Invite) {
+                                        DavValue::Invite(sharees)
+                                    } else {
+                                        DavValue::CalendarServerInvite(sharees)
+                                    },
+                                ));
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                            if matches!(dav_property, WebDavProperty::CalendarServerInvite) {
+                                response.set_namespace(Namespace::CalendarServer);
+                            }
+                        }
+                        WebDavProperty::AllowedSharingModes => {
+                            if matches!(
+                                archive,
+                                ArchivedResource::Calendar(_) | ArchivedResource::AddressBook(_)
+                            ) {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    DavValue::AllowedSharingModes,
+                                ));
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                            response.set_namespace(Namespace::CalendarServer);
+                        }
                     },
                     DavProperty::DeadProperty(tag) => {
                         if let Some(value) =
@@ -0,0 +1,471 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::ArchivedResource;
+use crate::{DavError, DavErrorCondition, DavResourceName, common::uri::DavUriResource};
+use common::{Server, auth::AccessToken, sharing::EffectiveAcl};
+use dav_proto::{
+    RequestHeaders,
+    schema::{
+        property::{InviteStatus, ShareAccess, Sharee},
+        request::InviteReply,
+        response::{BaseCondition, Href},
+    },
+};
+use directory::{QueryParams, Type};
+use groupware::{
+    RFC_3986,
+    cache::GroupwareCache,
+    calendar::{CALENDAR_DEFAULT, CALENDAR_SUBSCRIBED, CALENDAR_VISIBLE, Calendar},
+    contact::AddressBook,
+};
+use http_proto::HttpResponse;
+use hyper::StatusCode;
+use store::write::BatchBuilder;
+use trc::AddContext;
+use types::{
+    acl::{Acl, AclGrant},
+    collection::Collection,
+};
+use utils::{map::bitmap::Bitmap, sanitize_email};
+
+pub(crate) trait DavShareHandler: Sync + Send {
+    fn handle_share_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        sharees: Vec<Sharee>,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn handle_invite_reply(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        reply: InviteReply,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn resolve_sharees(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        resource: &ArchivedResource<'_>,
+    ) -> impl Future<Output = crate::Result<Vec<Sharee>>> + Send;
+}
+
+impl DavShareHandler for Server {
+    async fn handle_share_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        sharees: Vec<Sharee>,
+    ) -> crate::Result<HttpResponse> {
+        // Validate URI
+        let resource_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let collection = resource_.collection;
+
+        if !matches!(collection, Collection::AddressBook | Collection::Calendar) {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, collection.into())
+            .await
+            .caused_by(trc::location!())?;
+        let resource = resource_
+            .resource
+            .and_then(|r| resources.by_path(r))
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        if !resource.resource.is_container() {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
+        // Fetch node
+        let archive = self
+            .get_archive(account_id, collection, resource.document_id())
+            .await
+            .caused_by(trc::location!())?
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let container =
+            ArchivedResource::from_archive(&archive, collection).caused_by(trc::location!())?;
+
+        // Validate ACL
+        let acls = container.acls().unwrap();
+        if !access_token.is_member(account_id)
+            && !acls.effective_acl(access_token).contains(Acl::Administer)
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
+        // Map sharees to grants
+        let mut grants = acls.iter().map(AclGrant::from).collect::<Vec<_>>();
+        let mut unshared = Vec::new();
+        for sharee in sharees {
+            let sharee_id = resolve_sharee(self, access_token, &sharee.href).await?;
+            if sharee_id == account_id {
+                continue;
+            }
+
+            let access = match sharee.access {
+                ShareAccess::Read => Bitmap::<Acl>::from_iter([Acl::Read, Acl::ReadItems]),
+                ShareAccess::ReadWrite => Bitmap::<Acl>::from_iter([
+                    Acl::Read,
+                    Acl::ReadItems,
+                    Acl::AddItems,
+                    Acl::ModifyItems,
+                    Acl::RemoveItems,
+                ]),
+                ShareAccess::NoAccess | ShareAccess::NotShared => {
+                    grants.retain(|grant| grant.account_id != sharee_id);
+                    unshared.push(sharee_id);
+                    continue;
+                }
+                ShareAccess::SharedOwner => {
+                    return Err(DavError::Condition(DavErrorCondition::new(
+                        StatusCode::FORBIDDEN,
+                        BaseCondition::NotSupportedPrivilege,
+                    )));
+                }
+            };
+
+            if let Some(grant) = grants
+                .iter_mut()
+                .find(|grant| grant.account_id == sharee_id)
+            {
+                grant.grants = access;
+            } else {
+                grants.push(AclGrant {
+                    account_id: sharee_id,
+                    grants: access,
+                });
+            }
+        }
+
+        if grants.len() != acls.len() || acls.iter().zip(grants.iter()).any(|(a, b)| a != b) {
+            // Refresh ACLs
+            self.refresh_archived_acls(&grants, acls).await;
+
+            let mut batch = BatchBuilder::new();
+            match container {
+                ArchivedResource::Calendar(calendar) => {
+                    let mut new_calendar = calendar
+                        .deserialize::<Calendar>()
+                        .caused_by(trc::location!())?;
+                    new_calendar.acls = grants;
+                    new_calendar
+                        .preferences
+                        .retain(|pref| !unshared.contains(&pref.account_id));
+                    new_calendar
+                        .update(
+                            access_token,
+                            calendar,
+                            account_id,
+                            resource.document_id(),
+                            &mut batch,
+                        )
+                        .caused_by(trc::location!())?;
+                }
+                ArchivedResource::AddressBook(book) => {
+                    let mut new_book = book
+                        .deserialize::<AddressBook>()
+                        .caused_by(trc::location!())?;
+                    new_book.acls = grants;
+                    new_book
+                        .subscribers
+                        .retain(|account_id| !unshared.contains(account_id));
+                    new_book
+                        .update(
+                            access_token,
+                            book,
+                            account_id,
+                            resource.document_id(),
+                            &mut batch,
+                        )
+                        .caused_by(trc::location!())?;
+                }
+                _ => unreachable!(),
+            }
+
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        Ok(HttpResponse::new(StatusCode::OK))
+    }
+
+    async fn handle_invite_reply(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        reply: InviteReply,
+    ) -> crate::Result<HttpResponse> {
+        // Replies are addressed to the shared collection, either through
+        // the request URI or the host URL included in the reply
+        let uri = reply
+            .host_url
+            .as_ref()
+            .map(|href| href.0.as_str())
+            .unwrap_or(headers.uri);
+        let resource_ = self
+            .validate_uri(access_token, uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let collection = resource_.collection;
+        let sharee_id = access_token.primary_id();
+
+        if !matches!(collection, Collection::AddressBook | Collection::Calendar)
+            || account_id == sharee_id
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, collection.into())
+            .await
+            .caused_by(trc::location!())?;
+        let resource = resource_
+            .resource
+            .and_then(|r| resources.by_path(r))
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        if !resource.resource.is_container() {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
+        // Fetch node
+        let archive = self
+            .get_archive(account_id, collection, resource.document_id())
+            .await
+            .caused_by(trc::location!())?
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let container =
+            ArchivedResource::from_archive(&archive, collection).caused_by(trc::location!())?;
+
+        // Only principals the collection was shared with can reply
+        let acls = container.acls().unwrap();
+        if !acls
+            .iter()
+            .any(|grant| u32::from(grant.account_id) == sharee_id)
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
+        let grants = (!reply.accepted).then(|| {
+            acls.iter()
+                .filter(|grant| u32::from(grant.account_id) != sharee_id)
+                .map(AclGrant::from)
+                .collect::<Vec<_>>()
+        });
+        if let Some(grants) = &grants {
+            // Declining an invite revokes access to the collection
+            self.refresh_archived_acls(grants, acls).await;
+        }
+
+        let mut batch = BatchBuilder::new();
+        match container {
+            ArchivedResource::Calendar(calendar) => {
+                let mut new_calendar = calendar
+                    .deserialize::<Calendar>()
+                    .caused_by(trc::location!())?;
+                if let Some(grants) = &grants {
+                    new_calendar.acls = grants.clone();
+                    new_calendar
+                        .preferences
+                        .retain(|pref| pref.account_id != sharee_id);
+                } else if let Some(pref) = new_calendar
+                    .preferences
+                    .iter_mut()
+                    .find(|pref| pref.account_id == sharee_id)
+                {
+                    pref.flags |= CALENDAR_SUBSCRIBED;
+                } else {
+                    let mut pref = new_calendar.preferences(account_id).clone();
+                    pref.account_id = sharee_id;
+                    pref.flags =
+                        (pref.flags & !CALENDAR_DEFAULT) | CALENDAR_SUBSCRIBED | CALENDAR_VISIBLE;
+                    new_calendar.preferences.push(pref);
+                }
+                new_calendar
+                    .update(
+                        access_token,
+                        calendar,
+                        account_id,
+                        resource.document_id(),
+                        &mut batch,
+                    )
+                    .caused_by(trc::location!())?;
+            }
+            ArchivedResource::AddressBook(book) => {
+                let mut new_book = book
+                    .deserialize::<AddressBook>()
+                    .caused_by(trc::location!())?;
+                if let Some(grants) = &grants {
+                    new_book.acls = grants.clone();
+                    new_book.subscribers.retain(|id| *id != sharee_id);
+                } else if !new_book.subscribers.contains(&sharee_id) {
+                    new_book.subscribers.push(sharee_id);
+                }
+                new_book
+                    .update(
+                        access_token,
+                        book,
+                        account_id,
+                        resource.document_id(),
+                        &mut batch,
+                    )
+                    .caused_by(trc::location!())?;
+            }
+            _ => unreachable!(),
+        }
+
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        Ok(HttpResponse::new(StatusCode::OK))
+    }
+
+    async fn resolve_sharees(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        resource: &ArchivedResource<'_>,
+    ) -> crate::Result<Vec<Sharee>> {
+        let Some(grants) = resource.acls() else {
+            return Ok(vec![]);
+        };
+        if !access_token.is_member(account_id)
+            && !grants.effective_acl(access_token).contains(Acl::Administer)
+        {
+            return Ok(vec![]);
+        }
+
+        let mut sharees = Vec::with_capacity(grants.len());
+        for grant in grants.iter() {
+            let grant_account_id = u32::from(grant.account_id);
+            let acls = Bitmap::<Acl>::from(&grant.grants);
+            let access = if acls.contains(Acl::AddItems) || acls.contains(Acl::ModifyItems) {
+                ShareAccess::ReadWrite
+            } else if acls.contains(Acl::ReadItems) {
+                ShareAccess::Read
+            } else {
+                continue;
+            };
+            let status = match resource {
+                ArchivedResource::Calendar(calendar)
+                    if calendar.inner.preferences.iter().any(|pref| {
+                        u32::from(pref.account_id) == grant_account_id
+                            && pref.flags.to_native() & CALENDAR_SUBSCRIBED != 0
+                    }) =>
+                {
+                    InviteStatus::Accepted
+                }
+                ArchivedResource::AddressBook(book)
+                    if book
+                        .inner
+                        .subscribers
+                        .iter()
+                        .any(|id| u32::from(*id) == grant_account_id) =>
+                {
+                    InviteStatus::Accepted
+                }
+                _ => InviteStatus::NoResponse,
+            };
+
+            let sharee = if let Some(principal) = self
+                .directory()
+                .query(QueryParams::id(grant_account_id).with_return_member_of(false))
+                .await
+                .caused_by(trc::location!())?
+            {
+                let href = principal
+                    .emails
+                    .iter()
+                    .find(|email| !email.starts_with('@'))
+                    .map(|email| format!("mailto:{email}"))
+                    .unwrap_or_else(|| {
+                        format!(
+                            "{}/{}/",
+                            DavResourceName::Principal.base_path(),
+                            percent_encoding::utf8_percent_encode(&principal.name, RFC_3986),
+                        )
+                    });
+
+                Sharee {
+                    href: Href(href),
+                    common_name: principal.description.or(Some(principal.name)),
+                    comment: None,
+                    access,
+                    status,
+                }
+            } else {
+                Sharee {
+                    href: Href(format!(
+                        "{}/_{grant_account_id}/",
+                        DavResourceName::Principal.base_path(),
+                    )),
+                    common_name: None,
+                    comment: None,
+                    access,
+                    status: InviteStatus::Invalid,
+                }
+            };
+
+            sharees.push(sharee);
+        }
+
+        Ok(sharees)
+    }
+}
+
+async fn resolve_sharee(
+    server: &Server,
+    access_token: &AccessToken,
+    href: &Href,
+) -> crate::Result<u32> {
+    let sharee_id = if let Some(email) = href.0.strip_prefix("mailto:") {
+        if let Some(email) = sanitize_email(email) {
+            server
+                .directory()
+                .email_to_id(&email)
+                .await
+                .caused_by(trc::location!())?
+        } else {
+            None
+        }
+    } else {
+        server
+            .validate_uri(access_token, &href.0)
+            .await
+            .ok()
+            .and_then(|uri| uri.account_id)
+    }
+    .ok_or_else(|| {
+        DavError::Condition(DavErrorCondition::new(
+            StatusCode::FORBIDDEN,
+            BaseCondition::AllowedPrincipal,
+        ))
+    })?;
+
+    // Verify that the sharee is a valid principal
+    let principal = server
+        .directory()
+        .query(QueryParams::id(sharee_id).with_return_member_of(false))
+        .await
+        .caused_by(trc::location!())?
+        .ok_or_else(|| {
+            DavError::Condition(DavErrorCondition::new(
+                StatusCode::FORBIDDEN,
+                BaseCondition::AllowedPrincipal,
+            ))
+        })?;
+    if matches!(principal.typ(), Type::Individual | Type::Group) {
+        Ok(sharee_id)
+    } else {
+        Err(DavError::Condition(DavErrorCondition::new(
+            StatusCode::FORBIDDEN,
+            BaseCondition::AllowedPrincipal,
+        )))
+    }
+}
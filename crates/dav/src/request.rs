@@ -24,6 +24,7 @@ use crate::{
         acl::DavAclHandler,
         lock::{LockRequest, LockRequestHandler},
         propfind::PropFindRequestHandler,
+        share::DavShareHandler,
         uri::DavUriResource,
     },
     file::{
@@ -33,7 +34,7 @@ use crate::{
     },
     principal::{matching::PrincipalMatching, propsearch::PrincipalPropSearch},
 };
-use common::{Server, auth::AccessToken};
+use common::{Server, auth::AccessToken, icap::upload::UploadSource};
 use compact_str::{CompactString, ToCompactString};
 use dav_proto::{
     RequestHeaders,
@@ -41,17 +42,17 @@ use dav_proto::{
     schema::{
         Namespace,
         property::WebDavProperty,
-        request::{Acl, LockInfo, MkCol, PropFind, PropertyUpdate, Report},
+        request::{Acl, LockInfo, MkCol, PropFind, PropertyUpdate, Report, Share},
         response::{
             BaseCondition, ErrorResponse, PrincipalSearchProperty, PrincipalSearchPropertySet,
         },
     },
 };
 use directory::Permission;
-use http_proto::{HttpRequest, HttpResponse, HttpSessionData, request::fetch_body};
+use http_proto::{HttpContext, HttpRequest, HttpResponse, HttpSessionData, request::fetch_body};
 use hyper::{StatusCode, header};
 use std::{sync::Arc, time::Instant};
-use trc::{EventType, LimitEvent, StoreEvent, WebDavEvent};
+use trc::{EventType, IcapEvent, LimitEvent, StoreEvent, WebDavEvent};
 use types::collection::Collection;
 
 pub trait DavRequestHandler: Sync + Send {
@@ -66,10 +67,12 @@ pub trait DavRequestHandler: Sync + Send {
 }
 
 pub(crate) trait DavRequestDispatcher: Sync + Send {
+    #[allow(clippy::too_many_arguments)]
     fn dispatch_dav_request(
         &self,
         request: &HttpRequest,
         headers: &RequestHeaders<'_>,
+        session: &HttpSessionData,
         access_token: Arc<AccessToken>,
         resource: DavResourceName,
         method: DavMethod,
@@ -82,6 +85,7 @@ impl DavRequestDispatcher for Server {
         &self,
         request: &HttpRequest,
         headers: &RequestHeaders<'_>,
+        session: &HttpSessionData,
         access_token: Arc<AccessToken>,
         resource: DavResourceName,
         method: DavMethod,
@@ -382,14 +386,44 @@ impl DavRequestDispatcher for Server {
                 }
                 DavResourceName::Principal => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
             },
+            DavMethod::POST
+                if matches!(resource, DavResourceName::Cal | DavResourceName::Card)
+                    && headers.content_type.is_some_and(|ct| ct.contains("xml")) =>
+            {
+                match Share::parse(&mut Tokenizer::new(&body))? {
+                    Share::ShareResource(sharees) => {
+                        // Validate permissions
+                        access_token.assert_has_permission(
+                            if matches!(resource, DavResourceName::Cal) {
+                                Permission::DavCalAcl
+                            } else {
+                                Permission::DavCardAcl
+                            },
+                        )?;
+
+                        self.handle_share_request(&access_token, headers, sharees)
+                            .await
+                    }
+                    Share::InviteReply(reply) => {
+                        self.handle_invite_reply(&access_token, headers, reply)
+                            .await
+                    }
+                }
+            }
             DavMethod::PUT | DavMethod::POST | DavMethod::PATCH => match resource {
                 DavResourceName::Card => {
                     // Validate permissions
                     access_token.assert_has_permission(Permission::DavCardPut)?;
 
+                    // Photos are referenced by their download URL
+                    let base_url = HttpContext::new(session, request)
+                        .resolve_response_url(self)
+                        .await;
+
                     self.handle_card_update_request(
                         &access_token,
                         headers,
+                        &base_url,
                         body,
                         matches!(method, DavMethod::PATCH),
                     )
@@ -587,10 +621,38 @@ impl DavRequestHandler for Server {
         }
 
         let start_time = Instant::now();
-        match self
-            .dispatch_dav_request(&request, &headers, access_token, resource, method, body)
-            .await
+        let result = if matches!(resource, DavResourceName::File)
+            && matches!(method, DavMethod::PUT | DavMethod::PATCH)
+            && !body.is_empty()
         {
+            self.scan_upload(
+                UploadSource::Dav,
+                access_token.primary_id(),
+                &body,
+                session.remote_ip,
+                session.session_id,
+            )
+            .await
+            .map_err(DavError::Internal)
+        } else {
+            Ok(())
+        };
+        let result = match result {
+            Ok(()) => {
+                self.dispatch_dav_request(
+                    &request,
+                    &headers,
+                    session,
+                    access_token,
+                    resource,
+                    method,
+                    body,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+        match result {
             Ok(response) => {
                 let event = WebDavEvent::from(method);
 
@@ -637,7 +699,9 @@ impl DavRequestHandler for Server {
                     EventType::Store(StoreEvent::AssertValueFailed) => {
                         HttpResponse::new(StatusCode::CONFLICT)
                     }
-                    EventType::Security(_) => HttpResponse::new(StatusCode::FORBIDDEN),
+                    EventType::Security(_) | EventType::Icap(IcapEvent::UploadRejected) => {
+                        HttpResponse::new(StatusCode::FORBIDDEN)
+                    }
                     _ => HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR),
                 }
             }
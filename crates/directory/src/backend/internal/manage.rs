@@ -9,9 +9,10 @@ use super::{
     SpecialSecrets, lookup::DirectoryStore,
 };
 use crate::{
-    FALLBACK_ADMIN_ID, MemberOf, Permission, PermissionGrant, Permissions, Principal,
-    PrincipalData, PrincipalQuota, QueryBy, QueryParams, ROLE_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
-    Type, backend::RcptType, core::principal::build_search_index,
+    ArchivedPrincipalData, FALLBACK_ADMIN_ID, MemberOf, Permission, PermissionGrant, Permissions,
+    Principal, PrincipalAttribute, PrincipalAvatar, PrincipalData, PrincipalQuota, QueryBy,
+    QueryParams, ROLE_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER, Type, backend::RcptType,
+    core::principal::build_search_index,
 };
 use ahash::{AHashMap, AHashSet};
 use compact_str::CompactString;
@@ -21,12 +22,12 @@ use store::{
     backend::MAX_TOKEN_LENGTH,
     roaring::RoaringBitmap,
     write::{
-        AlignedBytes, Archive, Archiver, BatchBuilder, DirectoryClass, ValueClass,
+        AlignedBytes, Archive, Archiver, BatchBuilder, BlobOp, DirectoryClass, ValueClass,
         key::DeserializeBigEndian,
     },
 };
 use trc::AddContext;
-use types::collection::Collection;
+use types::{blob_hash::BlobHash, collection::Collection};
 use utils::{DomainPart, sanitize_email};
 
 #[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -41,6 +42,7 @@ pub struct UpdatePrincipal<'x> {
     changes: Vec<PrincipalUpdate>,
     tenant_id: Option<u32>,
     create_domains: bool,
+    revision: Option<u32>,
 }
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -69,6 +71,12 @@ pub trait ManageDirectory: Sized {
     async fn get_principal_name(&self, principal_id: u32) -> trc::Result<Option<String>>;
     async fn get_member_of(&self, principal_id: u32) -> trc::Result<Vec<MemberOf>>;
     async fn get_members(&self, principal_id: u32) -> trc::Result<Vec<u32>>;
+    async fn get_principal_revision(&self, principal_id: u32) -> trc::Result<Option<u32>>;
+    async fn set_principal_avatar(
+        &self,
+        principal_id: u32,
+        avatar: Option<PrincipalAvatar>,
+    ) -> trc::Result<()>;
     async fn create_principal(
         &self,
         principal: PrincipalSet,
@@ -292,6 +300,20 @@ impl ManageDirectory for Store {
         if let Some(picture) = principal_set.take_str(PrincipalField::Locale) {
             principal_create.data.push(PrincipalData::Locale(picture));
         }
+        if let Some(value) = principal_set.take_str(PrincipalField::CalendarAutoAdd) {
+            validate_calendar_auto_add(&value)?;
+            principal_create
+                .data
+                .push(PrincipalData::CalendarAutoAdd(value));
+        }
+        if let Some(items) = principal_set.take_str_array(PrincipalField::Attributes) {
+            let attributes = parse_attributes(items)?;
+            if !attributes.is_empty() {
+                principal_create
+                    .data
+                    .push(PrincipalData::Attributes(attributes));
+            }
+        }
         if let Some(urls) = principal_set.take_str_array(PrincipalField::Urls) {
             principal_create.data.push(PrincipalData::Urls(urls));
         }
@@ -578,6 +600,16 @@ impl ManageDirectory for Store {
             .await
             .caused_by(trc::location!())?;
 
+        // Unlink avatar
+        for data in principal.data.iter() {
+            if let ArchivedPrincipalData::Avatar(avatar) = data {
+                batch.clear(BlobOp::LinkId {
+                    hash: BlobHash::from(&avatar.hash),
+                    id: avatar_link_id(principal_id),
+                });
+            }
+        }
+
         // Revoke ACLs, obtain all changed principals
         let mut changed_principals = ChangedPrincipals::default();
 
@@ -716,6 +748,13 @@ impl ManageDirectory for Store {
             .await
             .caused_by(trc::location!())?;
 
+        // Reject updates based on a stale copy of the principal
+        if let Some(revision) = params.revision
+            && revision != principal_revision(principal_.as_bytes())
+        {
+            return Err(trc::ManageEvent::AssertFailed.into_err());
+        }
+
         // Prepare changes
         let mut batch = BatchBuilder::new();
         let mut pinfo_name =
@@ -859,7 +898,10 @@ impl ManageDirectory for Store {
                     // Password changed, update changed principals
                     changed_principals.add_change(principal_id, principal_type, change.field);
 
-                    if secret.is_app_password() || secret.is_otp_auth() {
+                    if secret.is_app_password()
+                        || secret.is_otp_auth()
+                        || secret.is_client_certificate()
+                    {
                         principal
                             .secrets
                             .retain(|v| *v != secret && !v.starts_with(secret.as_str()));
@@ -881,9 +923,19 @@ impl ManageDirectory for Store {
                     }
                 }
                 (PrincipalAction::Set, PrincipalField::Picture, PrincipalValue::String(value)) => {
-                    principal
-                        .data
-                        .retain(|v| !matches!(v, PrincipalData::Picture(_)));
+                    // Uploaded avatars are returned as a picture URL, keep them when unchanged
+                    if let Some(avatar) = principal.avatar() {
+                        if avatar.url_path(principal_id) == value {
+                            continue;
+                        }
+                        batch.clear(BlobOp::LinkId {
+                            hash: avatar.hash.clone(),
+                            id: avatar_link_id(principal_id),
+                        });
+                    }
+                    principal.data.retain(|v| {
+                        !matches!(v, PrincipalData::Picture(_) | PrincipalData::Avatar(_))
+                    });
                     if !value.is_empty() {
                         principal.data.push(PrincipalData::Picture(value));
                     }
@@ -896,6 +948,66 @@ impl ManageDirectory for Store {
                         principal.data.push(PrincipalData::Locale(value));
                     }
                 }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::CalendarAutoAdd,
+                    PrincipalValue::String(value),
+                ) => {
+                    principal
+                        .data
+                        .retain(|v| !matches!(v, PrincipalData::CalendarAutoAdd(_)));
+                    if !value.is_empty() {
+                        validate_calendar_auto_add(&value)?;
+                        principal.data.push(PrincipalData::CalendarAutoAdd(value));
+                    }
+                }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::Attributes,
+                    PrincipalValue::StringList(items),
+                ) => {
+                    principal
+                        .data
+                        .retain(|v| !matches!(v, PrincipalData::Attributes(_)));
+                    let attributes = parse_attributes(items)?;
+                    if !attributes.is_empty() {
+                        principal.data.push(PrincipalData::Attributes(attributes));
+                    }
+                }
+                (
+                    PrincipalAction::AddItem,
+                    PrincipalField::Attributes,
+                    PrincipalValue::String(item),
+                ) => {
+                    let attribute = parse_attribute(item)?;
+                    if let Some(attributes) = principal.data.iter_mut().find_map(|v| {
+                        if let PrincipalData::Attributes(attributes) = v {
+                            Some(attributes)
+                        } else {
+                            None
+                        }
+                    }) {
+                        attributes.retain(|v| v.name != attribute.name);
+                        attributes.push(attribute);
+                    } else {
+                        principal
+                            .data
+                            .push(PrincipalData::Attributes(vec![attribute]));
+                    }
+                }
+                (
+                    PrincipalAction::RemoveItem,
+                    PrincipalField::Attributes,
+                    PrincipalValue::String(item),
+                ) => {
+                    let name = item.split_once('=').map_or(item.as_str(), |(name, _)| name);
+                    for data in &mut principal.data {
+                        if let PrincipalData::Attributes(attributes) = data {
+                            attributes.retain(|v| v.name != name);
+                            break;
+                        }
+                    }
+                }
                 (PrincipalAction::Set, PrincipalField::Quota, PrincipalValue::Integer(quota))
                     if matches!(
                         principal_type,
@@ -1631,11 +1743,20 @@ impl ManageDirectory for Store {
                         .serialize()
                         .caused_by(trc::location!())?,
                 );
+        } else if params.revision.is_some() {
+            batch.assert_value(
+                ValueClass::Directory(DirectoryClass::Principal(principal_id)),
+                prev_principal,
+            );
         }
 
-        self.write(batch.build_all())
-            .await
-            .caused_by(trc::location!())?;
+        self.write(batch.build_all()).await.map_err(|err| {
+            if params.revision.is_some() && err.is_assertion_failure() {
+                trc::ManageEvent::AssertFailed.into_err()
+            } else {
+                err.caused_by(trc::location!())
+            }
+        })?;
 
         Ok(changed_principals)
     }
@@ -1843,6 +1964,93 @@ impl ManageDirectory for Store {
         Ok(results)
     }
 
+    async fn get_principal_revision(&self, principal_id: u32) -> trc::Result<Option<u32>> {
+        if let Some(archive) = self
+            .get_value::<Archive<AlignedBytes>>(ValueKey::from(ValueClass::Directory(
+                DirectoryClass::Principal(principal_id),
+            )))
+            .await
+            .caused_by(trc::location!())?
+        {
+            Ok(Some(principal_revision(archive.as_bytes())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn set_principal_avatar(
+        &self,
+        principal_id: u32,
+        avatar: Option<PrincipalAvatar>,
+    ) -> trc::Result<()> {
+        let principal_ = self
+            .get_value::<Archive<AlignedBytes>>(ValueKey::from(ValueClass::Directory(
+                DirectoryClass::Principal(principal_id),
+            )))
+            .await
+            .caused_by(trc::location!())?
+            .ok_or_else(|| not_found(principal_id))?;
+        let prev_principal = principal_
+            .to_unarchived::<Principal>()
+            .caused_by(trc::location!())?;
+        let mut principal = prev_principal
+            .deserialize::<Principal>()
+            .caused_by(trc::location!())?;
+        principal.id = principal_id;
+
+        let mut batch = BatchBuilder::new();
+        let link_id = avatar_link_id(principal_id);
+        if let Some(prev_avatar) = principal.avatar()
+            && avatar
+                .as_ref()
+                .is_none_or(|avatar| avatar.hash != prev_avatar.hash)
+        {
+            batch.clear(BlobOp::LinkId {
+                hash: prev_avatar.hash.clone(),
+                id: link_id,
+            });
+        }
+
+        // Uploaded avatars replace any picture URL
+        principal
+            .data
+            .retain(|item| !matches!(item, PrincipalData::Avatar(_) | PrincipalData::Picture(_)));
+        if let Some(avatar) = avatar {
+            batch
+                .set(
+                    BlobOp::LinkId {
+                        hash: avatar.hash.clone(),
+                        id: link_id,
+                    },
+                    vec![],
+                )
+                .set(
+                    BlobOp::Commit {
+                        hash: avatar.hash.clone(),
+                    },
+                    vec![],
+                );
+            principal.data.push(PrincipalData::Avatar(avatar));
+        }
+
+        batch
+            .assert_value(
+                ValueClass::Directory(DirectoryClass::Principal(principal_id)),
+                prev_principal,
+            )
+            .set(
+                ValueClass::Directory(DirectoryClass::Principal(principal_id)),
+                Archiver::new(principal)
+                    .serialize()
+                    .caused_by(trc::location!())?,
+            );
+
+        self.write(batch.build_all())
+            .await
+            .caused_by(trc::location!())
+            .map(|_| ())
+    }
+
     async fn map_principal(
         &self,
         principal: Principal,
@@ -1931,11 +2139,32 @@ impl ManageDirectory for Store {
                         result.set(PrincipalField::Picture, compact_string);
                     }
                 }
+                PrincipalData::Avatar(avatar) => {
+                    if fields.is_empty() || fields.contains(&PrincipalField::Picture) {
+                        result.set(PrincipalField::Picture, avatar.url_path(principal.id));
+                    }
+                }
                 PrincipalData::Locale(compact_string) => {
                     if fields.is_empty() || fields.contains(&PrincipalField::Locale) {
                         result.set(PrincipalField::Locale, compact_string);
                     }
                 }
+                PrincipalData::CalendarAutoAdd(compact_string) => {
+                    if fields.is_empty() || fields.contains(&PrincipalField::CalendarAutoAdd) {
+                        result.set(PrincipalField::CalendarAutoAdd, compact_string);
+                    }
+                }
+                PrincipalData::Attributes(attributes) => {
+                    if fields.is_empty() || fields.contains(&PrincipalField::Attributes) {
+                        result.set(
+                            PrincipalField::Attributes,
+                            attributes
+                                .into_iter()
+                                .map(|attribute| format!("{}={}", attribute.name, attribute.value))
+                                .collect::<Vec<_>>(),
+                        );
+                    }
+                }
                 PrincipalData::ExternalMembers(compact_strings) => {
                     if fields.is_empty() || fields.contains(&PrincipalField::ExternalMembers) {
                         result.set(PrincipalField::ExternalMembers, compact_strings);
@@ -2131,6 +2360,7 @@ impl<'x> UpdatePrincipal<'x> {
             create_domains: false,
             tenant_id: None,
             allowed_permissions: None,
+            revision: None,
         }
     }
 
@@ -2141,6 +2371,7 @@ impl<'x> UpdatePrincipal<'x> {
             create_domains: false,
             tenant_id: None,
             allowed_permissions: None,
+            revision: None,
         }
     }
 
@@ -2163,6 +2394,24 @@ impl<'x> UpdatePrincipal<'x> {
         self.create_domains = true;
         self
     }
+
+    pub fn with_revision(mut self, revision: Option<u32>) -> Self {
+        self.revision = revision;
+        self
+    }
+}
+
+// Only covers the principal object, which is what updates assert on. Memberships
+// are stored under separate keys and are not part of the revision.
+fn principal_revision(principal: &[u8]) -> u32 {
+    store::xxhash_rust::xxh3::xxh3_64(principal) as u32
+}
+
+// Avatars are linked under an id derived from the principal, so that
+// principals sharing the same image do not unlink each other's avatar
+pub fn avatar_link_id(principal_id: u32) -> u64 {
+    let hash = BlobHash::generate(format!("avatar.{principal_id}"));
+    u64::from_be_bytes(hash.0[..8].try_into().unwrap()) >> 1
 }
 
 fn validate_member_of(
@@ -2369,6 +2618,48 @@ pub fn error(details: impl Into<trc::Value>, reason: Option<impl Into<trc::Value
         .ctx_opt(trc::Key::Reason, reason)
 }
 
+fn validate_calendar_auto_add(value: &str) -> trc::Result<()> {
+    if matches!(value, "always" | "known" | "never") {
+        Ok(())
+    } else {
+        Err(error(
+            "Invalid calendarAutoAdd value",
+            Some("Expected one of 'always', 'known' or 'never'"),
+        ))
+    }
+}
+
+fn parse_attribute(item: String) -> trc::Result<PrincipalAttribute> {
+    item.split_once('=')
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .filter(|(name, _)| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        })
+        .map(|(name, value)| PrincipalAttribute {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+        .ok_or_else(|| {
+            error(
+                "Invalid attributes value",
+                format!("Expected 'name=value', found {item:?}").into(),
+            )
+        })
+}
+
+fn parse_attributes(items: Vec<String>) -> trc::Result<Vec<PrincipalAttribute>> {
+    let mut attributes: Vec<PrincipalAttribute> = Vec::with_capacity(items.len());
+    for item in items {
+        let attribute = parse_attribute(item)?;
+        attributes.retain(|v| v.name != attribute.name);
+        attributes.push(attribute);
+    }
+    Ok(attributes)
+}
+
 impl From<PrincipalField> for trc::Value {
     fn from(value: PrincipalField) -> Self {
         trc::Value::String(CompactString::const_new(value.as_str()))
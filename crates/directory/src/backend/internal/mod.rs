@@ -97,6 +97,8 @@ pub enum PrincipalField {
     Urls,
     ExternalMembers,
     Locale,
+    CalendarAutoAdd,
+    Attributes,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -185,6 +187,8 @@ impl PrincipalField {
             PrincipalField::Urls => 15,
             PrincipalField::ExternalMembers => 16,
             PrincipalField::Locale => 17,
+            PrincipalField::CalendarAutoAdd => 18,
+            PrincipalField::Attributes => 19,
         }
     }
 
@@ -208,6 +212,8 @@ impl PrincipalField {
             15 => Some(PrincipalField::Urls),
             16 => Some(PrincipalField::ExternalMembers),
             17 => Some(PrincipalField::Locale),
+            18 => Some(PrincipalField::CalendarAutoAdd),
+            19 => Some(PrincipalField::Attributes),
             _ => None,
         }
     }
@@ -232,6 +238,8 @@ impl PrincipalField {
             PrincipalField::Urls => "urls",
             PrincipalField::ExternalMembers => "externalMembers",
             PrincipalField::Locale => "locale",
+            PrincipalField::CalendarAutoAdd => "calendarAutoAdd",
+            PrincipalField::Attributes => "attributes",
         }
     }
 
@@ -255,6 +263,8 @@ impl PrincipalField {
             "urls" => Some(PrincipalField::Urls),
             "externalMembers" => Some(PrincipalField::ExternalMembers),
             "locale" => Some(PrincipalField::Locale),
+            "calendarAutoAdd" => Some(PrincipalField::CalendarAutoAdd),
+            "attributes" => Some(PrincipalField::Attributes),
             _ => None,
         }
     }
@@ -263,6 +273,7 @@ impl PrincipalField {
 pub trait SpecialSecrets {
     fn is_otp_auth(&self) -> bool;
     fn is_app_password(&self) -> bool;
+    fn is_client_certificate(&self) -> bool;
     fn is_password(&self) -> bool;
 }
 
@@ -278,7 +289,11 @@ where
         self.as_ref().starts_with("$app$")
     }
 
+    fn is_client_certificate(&self) -> bool {
+        self.as_ref().starts_with("$cert$")
+    }
+
     fn is_password(&self) -> bool {
-        !self.is_otp_auth() && !self.is_app_password()
+        !self.is_otp_auth() && !self.is_app_password() && !self.is_client_certificate()
     }
 }
@@ -14,6 +14,7 @@ use crate::core::config::build_pool;
 
 use super::{
     AuthBind, Bind, LdapConnectionManager, LdapDirectory, LdapFilter, LdapFilterItem, LdapMappings,
+    Referrals, tls::build_tls_config,
 };
 
 impl LdapDirectory {
@@ -29,27 +30,58 @@ impl LdapDirectory {
             None
         };
 
+        let addresses = config
+            .values((&prefix, "url"))
+            .map(|(_, v)| v.to_string())
+            .collect::<Vec<_>>();
+        if addresses.is_empty() {
+            config.new_parse_error((&prefix, "url"), "Missing property");
+            return None;
+        }
+        let mut settings = LdapConnSettings::new()
+            .set_conn_timeout(
+                config
+                    .property_or_default((&prefix, "timeout"), "30s")
+                    .unwrap_or_else(|| Duration::from_secs(30)),
+            )
+            .set_starttls(
+                config
+                    .property_or_default((&prefix, "tls.enable"), "false")
+                    .unwrap_or_default(),
+            )
+            .set_no_tls_verify(
+                config
+                    .property_or_default((&prefix, "tls.allow-invalid-certs"), "false")
+                    .unwrap_or_default(),
+            );
+        if let Some(tls_config) = build_tls_config(config, &prefix)? {
+            settings = settings.set_config(tls_config);
+        }
+
         let manager = LdapConnectionManager::new(
-            config.value_require((&prefix, "url"))?.to_string(),
-            LdapConnSettings::new()
-                .set_conn_timeout(
-                    config
-                        .property_or_default((&prefix, "timeout"), "30s")
-                        .unwrap_or_else(|| Duration::from_secs(30)),
-                )
-                .set_starttls(
-                    config
-                        .property_or_default((&prefix, "tls.enable"), "false")
-                        .unwrap_or_default(),
-                )
-                .set_no_tls_verify(
-                    config
-                        .property_or_default((&prefix, "tls.allow-invalid-certs"), "false")
-                        .unwrap_or_default(),
-                ),
+            addresses,
+            settings,
             bind_dn,
+            config
+                .property_or_default::<Duration>((&prefix, "failover.cooldown"), "30s")
+                .unwrap_or_else(|| Duration::from_secs(30))
+                .as_secs(),
         );
 
+        let referrals = match config.value((&prefix, "referrals")).unwrap_or("ignore") {
+            "ignore" => Referrals::Ignore,
+            "follow" => Referrals::Follow {
+                max_hops: config
+                    .property_or_default((&prefix, "referrals.max-hops"), "3")
+                    .unwrap_or(3),
+            },
+            unknown => {
+                let err = format!("Unknown LDAP referral handling: {unknown}");
+                config.new_parse_error((&prefix, "referrals"), err);
+                return None;
+            }
+        };
+
         let mut mappings = LdapMappings {
             base_dn: config.value_require((&prefix, "base-dn"))?.to_string(),
             filter_name: LdapFilter::from_config(config, (&prefix, "filter.name")),
@@ -138,6 +170,7 @@ impl LdapDirectory {
                 })
                 .ok()?,
             auth_bind,
+            referrals,
             data_store,
         })
     }
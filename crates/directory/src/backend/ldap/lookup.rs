@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use ldap3::{Ldap, LdapConnAsync, ResultEntry, Scope, SearchEntry};
+use ldap3::{Ldap, ResultEntry, Scope, SearchEntry, parse_refs};
 use mail_send::Credentials;
 use store::xxhash_rust;
 use trc::AddContext;
@@ -20,7 +20,7 @@ use crate::{
     },
 };
 
-use super::{AuthBind, LdapDirectory, LdapMappings};
+use super::{AuthBind, LdapDirectory, LdapMappings, Referrals};
 
 impl LdapDirectory {
     pub async fn query(&self, by: QueryParams<'_>) -> trc::Result<Option<Principal>> {
@@ -75,14 +75,12 @@ impl LdapDirectory {
                         template,
                         can_search,
                     } => {
-                        let (auth_bind_conn, mut ldap) = LdapConnAsync::with_settings(
-                            self.pool.manager().settings.clone(),
-                            &self.pool.manager().address,
-                        )
-                        .await
-                        .map_err(|err| err.into_error().caused_by(trc::location!()))?;
-
-                        ldap3::drive!(auth_bind_conn);
+                        let mut ldap = self
+                            .pool
+                            .manager()
+                            .connect()
+                            .await
+                            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
 
                         let dn = template.build(username);
 
@@ -146,14 +144,12 @@ impl LdapDirectory {
                         let filter = self.mappings.filter_name.build(username);
                         if let Some(mut result) = self.find_principal(&mut conn, &filter).await? {
                             // Perform bind auth using the found dn
-                            let (auth_bind_conn, mut ldap) = LdapConnAsync::with_settings(
-                                self.pool.manager().settings.clone(),
-                                &self.pool.manager().address,
-                            )
-                            .await
-                            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
-
-                            ldap3::drive!(auth_bind_conn);
+                            let mut ldap = self
+                                .pool
+                                .manager()
+                                .connect()
+                                .await
+                                .map_err(|err| err.into_error().caused_by(trc::location!()))?;
 
                             if ldap
                                 .simple_bind(&result.dn, secret)
@@ -394,31 +390,124 @@ impl LdapDirectory {
         conn: &mut Ldap,
         filter: &str,
     ) -> trc::Result<Option<LdapResult>> {
-        conn.search(
-            &self.mappings.base_dn,
-            Scope::Subtree,
-            filter,
-            &self.mappings.attrs_principal,
-        )
-        .await
-        .map_err(|err| err.into_error().caused_by(trc::location!()))?
-        .success()
-        .map(|(rs, _)| {
-            trc::event!(
-                Store(trc::StoreEvent::LdapQuery),
-                Details = filter.to_string(),
-                Result = rs.first().map(result_to_trace).unwrap_or_default()
-            );
+        let (result, mut referrals) = self
+            .search_principal(conn, &self.mappings.base_dn, filter)
+            .await?;
+        if result.is_some() {
+            return Ok(result);
+        }
 
-            rs.into_iter().next().map(|entry| {
-                self.mappings
-                    .entry_to_principal(SearchEntry::construct(entry))
-            })
-        })
-        .map_err(|err| err.into_error().caused_by(trc::location!()))
+        // Chase search continuation references on the servers they point to
+        if let Referrals::Follow { max_hops } = self.referrals {
+            for _ in 0..max_hops {
+                let mut next_referrals = Vec::new();
+                for url in referrals {
+                    let Some((address, base_dn)) = parse_referral(&url) else {
+                        continue;
+                    };
+
+                    let mut conn = self
+                        .pool
+                        .manager()
+                        .connect_bound(&address)
+                        .await
+                        .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+                    let (result, referrals) = self
+                        .search_principal(
+                            &mut conn,
+                            base_dn.as_deref().unwrap_or(&self.mappings.base_dn),
+                            filter,
+                        )
+                        .await?;
+                    if result.is_some() {
+                        return Ok(result);
+                    }
+                    next_referrals.extend(referrals);
+                }
+
+                if next_referrals.is_empty() {
+                    break;
+                }
+                referrals = next_referrals;
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn search_principal(
+        &self,
+        conn: &mut Ldap,
+        base_dn: &str,
+        filter: &str,
+    ) -> trc::Result<(Option<LdapResult>, Vec<String>)> {
+        let (rs, _) = conn
+            .search(
+                base_dn,
+                Scope::Subtree,
+                filter,
+                &self.mappings.attrs_principal,
+            )
+            .await
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?
+            .success()
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+        trc::event!(
+            Store(trc::StoreEvent::LdapQuery),
+            Details = filter.to_string(),
+            Result = rs.first().map(result_to_trace).unwrap_or_default()
+        );
+
+        let mut referrals = Vec::new();
+        for entry in rs {
+            if entry.is_ref() {
+                referrals.extend(parse_refs(entry.0));
+            } else {
+                return Ok((
+                    Some(
+                        self.mappings
+                            .entry_to_principal(SearchEntry::construct(entry)),
+                    ),
+                    vec![],
+                ));
+            }
+        }
+
+        Ok((None, referrals))
     }
 }
 
+// Splits an LDAP URL such as ldap://host:389/ou=people,dc=example,dc=org??sub
+// into the server address and the (percent-decoded) base DN.
+fn parse_referral(url: &str) -> Option<(String, Option<String>)> {
+    let (scheme, rest) = url.split_once("://")?;
+    if !matches!(scheme.to_ascii_lowercase().as_str(), "ldap" | "ldaps") {
+        return None;
+    }
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if host.is_empty() {
+        return None;
+    }
+    let dn = path.split('?').next().unwrap_or_default();
+
+    let mut base_dn = Vec::with_capacity(dn.len());
+    let mut bytes = dn.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            base_dn.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            base_dn.push(byte);
+        }
+    }
+
+    Some((
+        format!("{scheme}://{host}"),
+        String::from_utf8(base_dn).ok().filter(|dn| !dn.is_empty()),
+    ))
+}
+
 struct LdapResult {
     dn: String,
     principal: Principal,
@@ -513,3 +602,49 @@ fn result_to_trace(rs: &ResultEntry) -> trc::Value {
         .collect::<Vec<_>>()
         .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_referral;
+
+    #[test]
+    fn ldap_referral_urls() {
+        for (url, expected) in [
+            (
+                "ldap://ldap2.example.org:389/ou=people,dc=example,dc=org??sub",
+                Some((
+                    "ldap://ldap2.example.org:389",
+                    Some("ou=people,dc=example,dc=org"),
+                )),
+            ),
+            (
+                "ldaps://ldap2.example.org/ou%3Dpeople%2Cdc%3Dexample%2Cdc%3Dorg",
+                Some((
+                    "ldaps://ldap2.example.org",
+                    Some("ou=people,dc=example,dc=org"),
+                )),
+            ),
+            (
+                "LDAP://ldap2.example.org",
+                Some(("LDAP://ldap2.example.org", None)),
+            ),
+            (
+                "ldap://ldap2.example.org/",
+                Some(("ldap://ldap2.example.org", None)),
+            ),
+            ("http://ldap2.example.org/dc=example", None),
+            ("ldap:///dc=example", None),
+            ("ldap://ldap2.example.org/dc%3", None),
+            ("ldap://ldap2.example.org/dc%zz", None),
+        ] {
+            assert_eq!(
+                parse_referral(url),
+                expected.map(|(address, base_dn)| (
+                    address.to_string(),
+                    base_dn.map(|dn| dn.to_string())
+                )),
+                "{url}"
+            );
+        }
+    }
+}
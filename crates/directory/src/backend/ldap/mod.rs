@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::sync::atomic::AtomicU64;
+
 use deadpool::managed::Pool;
 use ldap3::{LdapConnSettings, ldap_escape};
 use store::Store;
@@ -11,14 +13,22 @@ use store::Store;
 pub mod config;
 pub mod lookup;
 pub mod pool;
+pub mod tls;
 
 pub struct LdapDirectory {
     pool: Pool<LdapConnectionManager>,
     mappings: LdapMappings,
     auth_bind: AuthBind,
+    referrals: Referrals,
     pub(crate) data_store: Store,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Referrals {
+    Ignore,
+    Follow { max_hops: usize },
+}
+
 #[derive(Debug, Default)]
 pub struct LdapMappings {
     base_dn: String,
@@ -78,9 +88,16 @@ impl LdapFilter {
 }
 
 pub(crate) struct LdapConnectionManager {
-    address: String,
+    servers: Vec<LdapServer>,
     settings: LdapConnSettings,
     bind_dn: Option<Bind>,
+    failover_cooldown: u64,
+}
+
+// Servers that fail to connect are skipped until their cooldown expires
+pub(crate) struct LdapServer {
+    address: String,
+    failed_until: AtomicU64,
 }
 
 pub(crate) struct Bind {
@@ -89,11 +106,23 @@ pub(crate) struct Bind {
 }
 
 impl LdapConnectionManager {
-    pub fn new(address: String, settings: LdapConnSettings, bind_dn: Option<Bind>) -> Self {
+    pub fn new(
+        addresses: Vec<String>,
+        settings: LdapConnSettings,
+        bind_dn: Option<Bind>,
+        failover_cooldown: u64,
+    ) -> Self {
         Self {
-            address,
+            servers: addresses
+                .into_iter()
+                .map(|address| LdapServer {
+                    address,
+                    failed_until: AtomicU64::new(0),
+                })
+                .collect(),
             settings,
             bind_dn,
+            failover_cooldown,
         }
     }
 }
@@ -4,11 +4,14 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::sync::atomic::Ordering;
+
 use async_trait::async_trait;
 use deadpool::managed;
 use ldap3::{Ldap, LdapConnAsync, LdapError, exop::WhoAmI};
+use store::write::now;
 
-use super::LdapConnectionManager;
+use super::{LdapConnectionManager, LdapServer};
 
 #[async_trait]
 impl managed::Manager for LdapConnectionManager {
@@ -16,10 +19,7 @@ impl managed::Manager for LdapConnectionManager {
     type Error = LdapError;
 
     async fn create(&self) -> Result<Ldap, LdapError> {
-        let (conn, mut ldap) =
-            LdapConnAsync::with_settings(self.settings.clone(), &self.address).await?;
-
-        ldap3::drive!(conn);
+        let mut ldap = self.connect().await?;
 
         if let Some(bind) = &self.bind_dn {
             ldap.simple_bind(&bind.dn, &bind.password)
@@ -41,3 +41,154 @@ impl managed::Manager for LdapConnectionManager {
             .map_err(managed::RecycleError::Backend)
     }
 }
+
+impl LdapConnectionManager {
+    // Opens an unbound connection to the first reachable server, healthy
+    // servers are tried in the configured order before those in cooldown.
+    pub async fn connect(&self) -> Result<Ldap, LdapError> {
+        let now = now();
+        let (healthy, failed): (Vec<&LdapServer>, Vec<&LdapServer>) = self
+            .servers
+            .iter()
+            .partition(|server| server.failed_until.load(Ordering::Relaxed) <= now);
+        let mut last_err = None;
+
+        for server in healthy.into_iter().chain(failed) {
+            match self.connect_to(&server.address).await {
+                Ok(ldap) => {
+                    server.failed_until.store(0, Ordering::Relaxed);
+                    return Ok(ldap);
+                }
+                Err(err) => {
+                    trc::event!(
+                        Store(trc::StoreEvent::LdapWarning),
+                        Reason = "Failed to connect to LDAP server",
+                        Details = server.address.clone(),
+                        CausedBy = err.to_string(),
+                    );
+                    server
+                        .failed_until
+                        .store(now + self.failover_cooldown, Ordering::Relaxed);
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            LdapError::from(std::io::Error::other("No LDAP servers configured"))
+        }))
+    }
+
+    pub async fn connect_to(&self, address: &str) -> Result<Ldap, LdapError> {
+        let (conn, ldap) = LdapConnAsync::with_settings(self.settings.clone(), address).await?;
+
+        ldap3::drive!(conn);
+
+        Ok(ldap)
+    }
+
+    pub async fn connect_bound(&self, address: &str) -> Result<Ldap, LdapError> {
+        let mut ldap = self.connect_to(address).await?;
+
+        if let Some(bind) = &self.bind_dn {
+            ldap.simple_bind(&bind.dn, &bind.password)
+                .await?
+                .success()?;
+        }
+
+        Ok(ldap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use ldap3::LdapConnSettings;
+    use store::write::now;
+    use tokio::net::TcpListener;
+
+    use super::super::LdapConnectionManager;
+
+    // Accepts connections without speaking LDAP, which is enough for an unbound connect
+    async fn spawn_listener() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("ldap://{}", listener.local_addr().unwrap());
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let accepted_ = accepted.clone();
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                accepted_.fetch_add(1, Ordering::Relaxed);
+                streams.push(stream);
+            }
+        });
+        (address, accepted)
+    }
+
+    // Waits for the listeners to register the accepted connection
+    async fn accepted(counter: &AtomicUsize) -> usize {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        counter.load(Ordering::Relaxed)
+    }
+
+    async fn closed_address() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("ldap://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn ldap_failover() {
+        let (live, live_accepted) = spawn_listener().await;
+        let (standby, standby_accepted) = spawn_listener().await;
+        let dead = closed_address().await;
+
+        // Unreachable servers are skipped and put in cooldown
+        let manager = LdapConnectionManager::new(
+            vec![dead.clone(), live.clone(), standby.clone()],
+            LdapConnSettings::new(),
+            None,
+            60,
+        );
+        manager.connect().await.unwrap();
+        assert!(manager.servers[0].failed_until.load(Ordering::Relaxed) > now());
+        assert_eq!(manager.servers[1].failed_until.load(Ordering::Relaxed), 0);
+        assert_eq!(accepted(&live_accepted).await, 1);
+        assert_eq!(accepted(&standby_accepted).await, 0);
+
+        // Servers in cooldown are tried after the healthy ones
+        manager.servers[1]
+            .failed_until
+            .store(now() + 60, Ordering::Relaxed);
+        manager.connect().await.unwrap();
+        assert_eq!(accepted(&live_accepted).await, 1);
+        assert_eq!(accepted(&standby_accepted).await, 1);
+
+        // When all healthy servers fail, servers in cooldown are retried
+        // and marked healthy again once they respond
+        let manager = LdapConnectionManager::new(
+            vec![dead.clone(), live.clone()],
+            LdapConnSettings::new(),
+            None,
+            60,
+        );
+        manager.servers[1]
+            .failed_until
+            .store(now() + 60, Ordering::Relaxed);
+        manager.connect().await.unwrap();
+        assert_eq!(accepted(&live_accepted).await, 2);
+        assert_eq!(manager.servers[1].failed_until.load(Ordering::Relaxed), 0);
+
+        // No reachable servers
+        let manager =
+            LdapConnectionManager::new(vec![dead.clone()], LdapConnSettings::new(), None, 60);
+        assert!(manager.connect().await.is_err());
+        assert!(manager.servers[0].failed_until.load(Ordering::Relaxed) > now());
+    }
+}
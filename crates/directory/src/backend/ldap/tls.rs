@@ -0,0 +1,271 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use rustls::{
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::{
+        WebPkiServerVerifier,
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    },
+    crypto::{CryptoProvider, ring::default_provider},
+};
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime, pem::PemObject};
+use sha2::{Digest, Sha256};
+use utils::config::{Config, utils::AsKey};
+
+// Verifies the server certificate against a set of pinned SHA-256 fingerprints,
+// optionally also requiring a valid chain to the configured CA.
+#[derive(Debug)]
+struct PinnedVerifier {
+    pins: Vec<[u8; 32]>,
+    chain: Option<Arc<WebPkiServerVerifier>>,
+    provider: Arc<CryptoProvider>,
+}
+
+// Builds a custom TLS configuration when a CA certificate or certificate pins
+// are configured, otherwise the default settings of the LDAP client are used.
+pub(crate) fn build_tls_config(
+    config: &mut Config,
+    prefix: impl AsKey,
+) -> Option<Option<Arc<ClientConfig>>> {
+    let prefix = prefix.as_key();
+    let provider = Arc::new(default_provider());
+
+    let mut pins = Vec::new();
+    for (key, value) in config
+        .values((&prefix, "tls.pin"))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>()
+    {
+        match parse_fingerprint(&value) {
+            Some(pin) => pins.push(pin),
+            None => {
+                config.new_parse_error(
+                    key,
+                    "Invalid certificate fingerprint, expected a hex encoded SHA-256 hash",
+                );
+                return None;
+            }
+        }
+    }
+
+    let chain = if let Some(pem) = config
+        .value((&prefix, "tls.ca-certificate"))
+        .map(|pem| pem.to_string())
+    {
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_slice_iter(pem.as_bytes()) {
+            match cert.map_err(|err| err.to_string()).and_then(|cert| {
+                roots
+                    .add(cert)
+                    .map_err(|err| format!("Failed to add CA certificate: {err}"))
+            }) {
+                Ok(_) => {}
+                Err(err) => {
+                    config.new_parse_error((&prefix, "tls.ca-certificate"), err);
+                    return None;
+                }
+            }
+        }
+        if roots.is_empty() {
+            config.new_parse_error(
+                (&prefix, "tls.ca-certificate"),
+                "No certificates found in PEM data",
+            );
+            return None;
+        }
+
+        match WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build()
+        {
+            Ok(verifier) => Some(verifier),
+            Err(err) => {
+                config.new_build_error(
+                    (&prefix, "tls.ca-certificate"),
+                    format!("Failed to build certificate verifier: {err}"),
+                );
+                return None;
+            }
+        }
+    } else {
+        None
+    };
+
+    let verifier: Arc<dyn ServerCertVerifier> = match (pins.is_empty(), chain) {
+        (true, None) => return Some(None),
+        (true, Some(chain)) => chain,
+        (false, chain) => Arc::new(PinnedVerifier {
+            pins,
+            chain,
+            provider: provider.clone(),
+        }),
+    };
+
+    match ClientConfig::builder_with_provider(provider).with_safe_default_protocol_versions() {
+        Ok(builder) => Some(Some(Arc::new(
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(verifier)
+                .with_no_client_auth(),
+        ))),
+        Err(err) => {
+            config.new_build_error(
+                (&prefix, "tls"),
+                format!("Failed to build TLS configuration: {err}"),
+            );
+            None
+        }
+    }
+}
+
+fn parse_fingerprint(value: &str) -> Option<[u8; 32]> {
+    let hex = value
+        .trim()
+        .trim_start_matches("sha256:")
+        .chars()
+        .filter(|ch| *ch != ':')
+        .collect::<String>();
+    if hex.len() != 64 {
+        return None;
+    }
+
+    let mut pin = [0u8; 32];
+    for (idx, byte) in pin.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(idx * 2..idx * 2 + 2)?, 16).ok()?;
+    }
+    Some(pin)
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(chain) = &self.chain {
+            chain.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        }
+
+        let fingerprint: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+        if self.pins.contains(&fingerprint) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "Server certificate does not match any pinned fingerprint".to_string(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rustls::{client::danger::ServerCertVerifier, crypto::ring::default_provider};
+    use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+    use sha2::{Digest, Sha256};
+    use utils::config::Config;
+
+    use super::{PinnedVerifier, build_tls_config, parse_fingerprint};
+
+    #[test]
+    fn ldap_certificate_pinning() {
+        let cert = CertificateDer::from(b"pinned certificate".to_vec());
+        let pin: [u8; 32] = Sha256::digest(cert.as_ref()).into();
+        let fingerprint = pin
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(":");
+
+        // Fingerprint formats
+        assert_eq!(parse_fingerprint(&fingerprint), Some(pin));
+        assert_eq!(
+            parse_fingerprint(&format!(
+                "sha256:{}",
+                fingerprint.replace(':', "").to_lowercase()
+            )),
+            Some(pin)
+        );
+        assert_eq!(parse_fingerprint("AB:CD"), None);
+        assert_eq!(parse_fingerprint(&"zz".repeat(32)), None);
+
+        // The default TLS settings are used unless pins or a CA are configured
+        let mut config = Config::new("[ldap]\nurl = 'ldap://localhost'\n").unwrap();
+        assert!(matches!(build_tls_config(&mut config, "ldap"), Some(None)));
+        let mut config = Config::new(format!("[ldap.tls]\npin = ['{fingerprint}']\n")).unwrap();
+        assert!(matches!(
+            build_tls_config(&mut config, "ldap"),
+            Some(Some(_))
+        ));
+        assert!(config.errors.is_empty(), "{:?}", config.errors);
+        let mut config = Config::new("[ldap.tls]\npin = ['AB:CD']\n").unwrap();
+        assert!(build_tls_config(&mut config, "ldap").is_none());
+        assert!(!config.errors.is_empty());
+
+        // Only certificates matching a pin are accepted
+        let verifier = PinnedVerifier {
+            pins: vec![pin],
+            chain: None,
+            provider: Arc::new(default_provider()),
+        };
+        let server_name = ServerName::try_from("ldap.example.org").unwrap();
+        assert!(
+            verifier
+                .verify_server_cert(&cert, &[], &server_name, &[], UnixTime::now())
+                .is_ok()
+        );
+        assert!(
+            verifier
+                .verify_server_cert(
+                    &CertificateDer::from(b"other certificate".to_vec()),
+                    &[],
+                    &server_name,
+                    &[],
+                    UnixTime::now()
+                )
+                .is_err()
+        );
+    }
+}
@@ -9,6 +9,7 @@ pub mod internal;
 pub mod ldap;
 pub mod memory;
 pub mod oidc;
+pub mod rest;
 pub mod smtp;
 pub mod sql;
 
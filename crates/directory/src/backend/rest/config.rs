@@ -0,0 +1,228 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use base64::{Engine, engine::general_purpose};
+use reqwest::Method;
+use store::Store;
+use utils::{
+    cache::CacheWithTtl,
+    config::{Config, utils::AsKey},
+};
+
+use super::{
+    JsonPath, RestConfig, RestDirectory, RestMappings, RestRequest, RestTemplate, RestTemplateItem,
+};
+
+impl RestDirectory {
+    pub fn from_config(config: &mut Config, prefix: impl AsKey, data_store: Store) -> Option<Self> {
+        let prefix = prefix.as_key();
+
+        let mut headers = config
+            .values((&prefix, "headers"))
+            .filter_map(|(_, value)| {
+                value
+                    .split_once(':')
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            })
+            .collect::<Vec<_>>();
+        match config
+            .value((&prefix, "auth.method"))
+            .unwrap_or("none")
+            .to_string()
+            .as_str()
+        {
+            "basic" => {
+                let username = config
+                    .value_require((&prefix, "auth.username"))?
+                    .to_string();
+                let secret = config.value_require((&prefix, "auth.secret"))?;
+                headers.push((
+                    "Authorization".to_string(),
+                    format!(
+                        "Basic {}",
+                        general_purpose::STANDARD.encode(format!("{username}:{secret}").as_bytes())
+                    ),
+                ));
+            }
+            "token" => {
+                headers.push((
+                    "Authorization".to_string(),
+                    format!("Bearer {}", config.value_require((&prefix, "auth.token"))?),
+                ));
+            }
+            "none" => {}
+            _ => {
+                config.new_build_error(
+                    (&prefix, "auth.method"),
+                    "Invalid authentication method, must be 'basic', 'token' or 'none'",
+                );
+                return None;
+            }
+        }
+
+        let mappings = RestMappings {
+            name: parse_path(config, (&prefix, "fields.name")).or_else(|| {
+                config.new_parse_error((&prefix, "fields.name"), "Missing property");
+                None
+            })?,
+            typ: parse_path(config, (&prefix, "fields.class")),
+            description: parse_path(config, (&prefix, "fields.description")),
+            secret: parse_path(config, (&prefix, "fields.secret")),
+            email: parse_path(config, (&prefix, "fields.email")),
+            email_alias: parse_path(config, (&prefix, "fields.email-alias")),
+            quota: parse_path(config, (&prefix, "fields.quota")),
+            groups: parse_path(config, (&prefix, "fields.groups")),
+            members: parse_path(config, (&prefix, "fields.members")),
+        };
+
+        let Some(lookup_name) = RestRequest::from_config(config, (&prefix, "request.name")) else {
+            config.new_parse_error((&prefix, "request.name.url"), "Missing property");
+            return None;
+        };
+        let authenticate = RestRequest::from_config(config, (&prefix, "request.authenticate"));
+        if authenticate.is_none() && mappings.secret.is_none() {
+            config.new_build_warning(
+                (&prefix, "request.authenticate.url"),
+                "No authentication request or secret field configured, logins will fail",
+            );
+        } else if authenticate
+            .as_ref()
+            .is_some_and(|request| !request.has_secret())
+        {
+            config.new_build_error(
+                (&prefix, "request.authenticate"),
+                "Authentication request does not include the {secret} placeholder",
+            );
+            return None;
+        }
+
+        let members = RestRequest::from_config(config, (&prefix, "request.members"));
+        if members.is_some() && mappings.members.is_none() {
+            config.new_build_error(
+                (&prefix, "fields.members"),
+                "A members field is required when group expansion is enabled",
+            );
+            return None;
+        }
+
+        let cache = config
+            .property_or_default::<Option<u64>>((&prefix, "cache.size"), "1048576")
+            .unwrap_or_default()
+            .map(|size| CacheWithTtl::new(100, size));
+
+        Some(RestDirectory {
+            config: RestConfig {
+                lookup_name,
+                lookup_email: RestRequest::from_config(config, (&prefix, "request.email")),
+                authenticate,
+                members,
+                mappings,
+                headers,
+                timeout: config
+                    .property_or_default::<Duration>((&prefix, "timeout"), "30s")
+                    .unwrap_or_else(|| Duration::from_secs(30)),
+                allow_invalid_certs: config
+                    .property_or_default((&prefix, "tls.allow-invalid-certs"), "false")
+                    .unwrap_or_default(),
+                cache_ttl: config
+                    .property_or_default::<Duration>((&prefix, "cache.ttl.lookup"), "5m")
+                    .unwrap_or_else(|| Duration::from_secs(300)),
+            },
+            cache,
+            data_store,
+        })
+    }
+}
+
+impl RestRequest {
+    fn from_config(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let url = RestTemplate::from_config(config, (&prefix, "url"))?;
+        let body = RestTemplate::from_config(config, (&prefix, "body"));
+        let method = match config
+            .value((&prefix, "method"))
+            .unwrap_or(if body.is_some() { "POST" } else { "GET" })
+            .to_ascii_uppercase()
+            .as_str()
+        {
+            "GET" => Method::GET,
+            "POST" => Method::POST,
+            "PUT" => Method::PUT,
+            other => {
+                let err = format!("Unsupported HTTP method {other:?}");
+                config.new_parse_error((&prefix, "method"), err);
+                return None;
+            }
+        };
+
+        Some(RestRequest { method, url, body })
+    }
+
+    fn has_secret(&self) -> bool {
+        self.url.has_secret() || self.body.as_ref().is_some_and(|body| body.has_secret())
+    }
+}
+
+impl RestTemplate {
+    fn from_config(config: &mut Config, key: impl AsKey) -> Option<Self> {
+        let key = key.as_key();
+        let value = config.value(&key)?.to_string();
+        RestTemplate::parse(&value)
+            .map_err(|err| config.new_parse_error(&key, err))
+            .ok()
+    }
+
+    // Placeholders are alphabetic names enclosed in braces, any other brace
+    // is kept as is so JSON request bodies can be used as templates.
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        let mut items = Vec::new();
+        let mut token = String::new();
+        let mut chars = value.chars();
+
+        while let Some(ch) = chars.next() {
+            if ch == '{' {
+                let name = chars
+                    .clone()
+                    .take_while(|ch| ch.is_ascii_alphabetic())
+                    .collect::<String>();
+                if !name.is_empty() && chars.clone().nth(name.len()) == Some('}') {
+                    let item = match name.as_str() {
+                        "user" | "username" | "email" | "name" => RestTemplateItem::Full,
+                        "local" => RestTemplateItem::LocalPart,
+                        "domain" => RestTemplateItem::DomainPart,
+                        "secret" | "password" => RestTemplateItem::Secret,
+                        _ => {
+                            return Err(format!("Unknown request template placeholder: {name}"));
+                        }
+                    };
+                    if !token.is_empty() {
+                        items.push(RestTemplateItem::Static(std::mem::take(&mut token)));
+                    }
+                    items.push(item);
+                    chars.nth(name.len());
+                    continue;
+                }
+            }
+            token.push(ch);
+        }
+
+        if !token.is_empty() {
+            items.push(RestTemplateItem::Static(token));
+        }
+
+        Ok(RestTemplate { items })
+    }
+}
+
+fn parse_path(config: &mut Config, key: impl AsKey) -> Option<JsonPath> {
+    let key = key.as_key();
+    let value = config.value(&key)?.to_string();
+    JsonPath::parse(&value)
+        .map_err(|err| config.new_parse_error(&key, err))
+        .ok()
+}
@@ -0,0 +1,407 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_send::Credentials;
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+use trc::AddContext;
+
+use crate::{
+    Principal, PrincipalData, QueryBy, QueryParams, ROLE_ADMIN, ROLE_USER, Type,
+    backend::{
+        RcptType,
+        internal::{
+            lookup::DirectoryStore,
+            manage::{self, ManageDirectory, UpdatePrincipal},
+        },
+    },
+};
+
+use super::{RestDirectory, RestMappings, RestRequest, TemplateEncoding};
+
+impl RestDirectory {
+    pub async fn query(&self, by: QueryParams<'_>) -> trc::Result<Option<Principal>> {
+        let (mut external_principal, member_of, stored_principal) = match by.by {
+            QueryBy::Name(username) => {
+                if let Some(result) = self
+                    .fetch_principal(&self.config.lookup_name, username)
+                    .await?
+                {
+                    (result.principal, result.member_of, None)
+                } else {
+                    return Ok(None);
+                }
+            }
+            QueryBy::Id(uid) => {
+                if let Some(stored_principal) = self
+                    .data_store
+                    .query(QueryParams::id(uid).with_return_member_of(by.return_member_of))
+                    .await?
+                {
+                    if let Some(result) = self
+                        .fetch_principal(&self.config.lookup_name, stored_principal.name())
+                        .await?
+                    {
+                        (result.principal, result.member_of, Some(stored_principal))
+                    } else {
+                        return Ok(None);
+                    }
+                } else {
+                    return Ok(None);
+                }
+            }
+            QueryBy::Credentials(credentials) => {
+                let (username, secret) = match credentials {
+                    Credentials::Plain { username, secret } => (username, secret),
+                    Credentials::OAuthBearer { token } => (token, token),
+                    Credentials::XOauth2 { username, secret } => (username, secret),
+                };
+
+                if let Some(request) = &self.config.authenticate {
+                    // Let the remote API validate the credentials
+                    if self
+                        .send(request, username, Some(secret.as_str()), false)
+                        .await?
+                        .is_none()
+                    {
+                        trc::event!(
+                            Store(trc::StoreEvent::RestQuery),
+                            Reason = "Credentials rejected by authentication request",
+                            Details = username.to_string()
+                        );
+                        return Ok(None);
+                    }
+
+                    if let Some(result) = self
+                        .fetch_principal(&self.config.lookup_name, username)
+                        .await?
+                    {
+                        (result.principal, result.member_of, None)
+                    } else {
+                        return Ok(None);
+                    }
+                } else if let Some(result) = self
+                    .fetch_principal(&self.config.lookup_name, username)
+                    .await?
+                {
+                    if result.principal.verify_secret(secret, false).await? {
+                        (result.principal, result.member_of, None)
+                    } else {
+                        trc::event!(
+                            Store(trc::StoreEvent::RestQuery),
+                            Reason = "Password verification failed",
+                            Details = username.to_string()
+                        );
+                        return Ok(None);
+                    }
+                } else {
+                    return Ok(None);
+                }
+            }
+        };
+
+        // Map group names to internal ids
+        if !member_of.is_empty() && by.return_member_of {
+            let mut data = Vec::with_capacity(member_of.len());
+            for name in member_of {
+                data.push(
+                    self.data_store
+                        .get_or_create_principal_id(&name, Type::Group)
+                        .await
+                        .caused_by(trc::location!())?,
+                );
+            }
+
+            external_principal.data.push(PrincipalData::MemberOf(data));
+        }
+
+        // Obtain account ID if not available
+        let mut principal = if let Some(stored_principal) = stored_principal {
+            stored_principal
+        } else {
+            let id = self
+                .data_store
+                .get_or_create_principal_id(external_principal.name(), Type::Individual)
+                .await
+                .caused_by(trc::location!())?;
+
+            self.data_store
+                .query(QueryParams::id(id).with_return_member_of(by.return_member_of))
+                .await
+                .caused_by(trc::location!())?
+                .ok_or_else(|| manage::not_found(id).caused_by(trc::location!()))?
+        };
+
+        // Keep the internal store up to date with the REST directory
+        let changes = principal.update_external(external_principal, true);
+        if !changes.is_empty() {
+            self.data_store
+                .update_principal(
+                    UpdatePrincipal::by_id(principal.id)
+                        .with_updates(changes)
+                        .create_domains(),
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(Some(principal))
+    }
+
+    pub async fn email_to_id(&self, address: &str) -> trc::Result<Option<u32>> {
+        if let Some(request) = &self.config.lookup_email {
+            if let Some(result) = self.fetch_principal(request, address).await? {
+                return self
+                    .data_store
+                    .get_or_create_principal_id(result.principal.name(), Type::Individual)
+                    .await
+                    .map(Some);
+            }
+            Ok(None)
+        } else {
+            self.data_store.email_to_id(address).await
+        }
+    }
+
+    pub async fn rcpt(&self, address: &str) -> trc::Result<RcptType> {
+        if let Some(request) = &self.config.lookup_email
+            && self.fetch_principal(request, address).await?.is_some()
+        {
+            return Ok(RcptType::Mailbox);
+        }
+
+        let members = self.expand(address).await?;
+        if !members.is_empty() {
+            return Ok(RcptType::List(members));
+        }
+
+        self.data_store.rcpt(address).await.map(|result| {
+            if matches!(result, RcptType::List(_)) || self.config.lookup_email.is_none() {
+                result
+            } else {
+                RcptType::Invalid
+            }
+        })
+    }
+
+    pub async fn vrfy(&self, address: &str) -> trc::Result<Vec<String>> {
+        self.data_store.vrfy(address).await
+    }
+
+    pub async fn expn(&self, address: &str) -> trc::Result<Vec<String>> {
+        let members = self.expand(address).await?;
+        if !members.is_empty() {
+            Ok(members)
+        } else {
+            self.data_store.expn(address).await
+        }
+    }
+
+    pub async fn is_local_domain(&self, domain: &str) -> trc::Result<bool> {
+        self.data_store.is_local_domain(domain).await
+    }
+}
+
+impl RestDirectory {
+    async fn expand(&self, address: &str) -> trc::Result<Vec<String>> {
+        if let (Some(request), Some(members)) =
+            (&self.config.members, &self.config.mappings.members)
+            && let Some(response) = self.send(request, address, None, true).await?
+        {
+            Ok(members
+                .eval_strings(&response)
+                .into_iter()
+                .map(|member| member.to_lowercase())
+                .collect())
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    async fn fetch_principal(
+        &self,
+        request: &RestRequest,
+        value: &str,
+    ) -> trc::Result<Option<RestResult>> {
+        Ok(self
+            .send(request, value, None, true)
+            .await?
+            .and_then(|response| self.config.mappings.response_to_principal(&response)))
+    }
+
+    // Sends a templated request, returning None when the remote API
+    // reports that the entry does not exist or the credentials are invalid.
+    async fn send(
+        &self,
+        request: &RestRequest,
+        value: &str,
+        secret: Option<&str>,
+        use_cache: bool,
+    ) -> trc::Result<Option<serde_json::Value>> {
+        let url = request.url.build(value, secret, TemplateEncoding::Url);
+        let body = request
+            .body
+            .as_ref()
+            .map(|body| body.build(value, secret, TemplateEncoding::Json));
+
+        // Check cache, requests carrying credentials are never cached
+        let cache_key = format!("{} {url} {}", request.method, body.as_deref().unwrap_or(""));
+        let cache = self
+            .cache
+            .as_ref()
+            .filter(|_| use_cache && secret.is_none());
+        if let Some(cached) = cache.and_then(|cache| cache.get(&cache_key)) {
+            return Ok(if !cached.is_empty() {
+                serde_json::from_str(&cached).ok()
+            } else {
+                None
+            });
+        }
+
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(self.config.allow_invalid_certs)
+            .timeout(self.config.timeout)
+            .build()
+            .map_err(|err| {
+                trc::StoreEvent::RestError
+                    .into_err()
+                    .reason(err)
+                    .details("Failed to build client")
+            })?;
+        let mut builder = client.request(request.method.clone(), &url);
+        for (name, value) in &self.config.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = body {
+            builder = builder.header(CONTENT_TYPE, "application/json").body(body);
+        }
+
+        let response = builder.send().await.map_err(|err| {
+            trc::StoreEvent::RestError
+                .into_err()
+                .reason(err)
+                .ctx(trc::Key::Url, url.clone())
+                .details("HTTP request failed")
+        })?;
+
+        let status = response.status();
+        trc::event!(
+            Store(trc::StoreEvent::RestQuery),
+            Url = url.clone(),
+            Code = status.as_u16(),
+        );
+
+        match status {
+            status if status.is_success() => {
+                let bytes = response.bytes().await.map_err(|err| {
+                    trc::StoreEvent::RestError
+                        .into_err()
+                        .reason(err)
+                        .ctx(trc::Key::Url, url.clone())
+                        .details("Failed to read response")
+                })?;
+                let response = if !bytes.is_empty() {
+                    serde_json::from_slice::<serde_json::Value>(&bytes).map_err(|err| {
+                        trc::StoreEvent::RestError
+                            .into_err()
+                            .reason(err)
+                            .ctx(trc::Key::Url, url.clone())
+                            .details("Failed to deserialize response")
+                    })?
+                } else {
+                    serde_json::Value::Null
+                };
+
+                if let Some(cache) = cache {
+                    cache.insert(cache_key, response.to_string(), self.config.cache_ttl);
+                }
+
+                Ok(Some(response))
+            }
+            StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                if let Some(cache) = cache
+                    && status == StatusCode::NOT_FOUND
+                {
+                    cache.insert(cache_key, String::new(), self.config.cache_ttl);
+                }
+
+                Ok(None)
+            }
+            other => Err(trc::StoreEvent::RestError
+                .into_err()
+                .code(other.as_u16())
+                .ctx(trc::Key::Url, url)
+                .ctx(trc::Key::Reason, response.text().await.unwrap_or_default())
+                .details("Unexpected status code")),
+        }
+    }
+}
+
+struct RestResult {
+    principal: Principal,
+    member_of: Vec<String>,
+}
+
+impl RestMappings {
+    fn response_to_principal(&self, response: &serde_json::Value) -> Option<RestResult> {
+        let mut principal = Principal::new(0, Type::Individual);
+        let mut role = ROLE_USER;
+
+        principal.name = self.name.eval_first(response)?;
+        if let Some(path) = &self.email {
+            principal.emails = path
+                .eval_strings(response)
+                .into_iter()
+                .map(|email| email.to_lowercase())
+                .collect();
+        }
+        if let Some(path) = &self.email_alias {
+            principal.emails.extend(
+                path.eval_strings(response)
+                    .into_iter()
+                    .map(|email| email.to_lowercase()),
+            );
+        }
+        if let Some(path) = &self.secret {
+            principal.secrets = path.eval_strings(response);
+        }
+        if let Some(path) = &self.description {
+            principal.description = path.eval_first(response);
+        }
+        if let Some(quota) = self
+            .quota
+            .as_ref()
+            .and_then(|path| path.eval_first(response))
+            .and_then(|quota| quota.parse::<u64>().ok())
+        {
+            principal.quota = quota.into();
+        }
+        if let Some(path) = &self.typ {
+            for value in path.eval_strings(response) {
+                match value.to_ascii_lowercase().as_str() {
+                    "admin" | "administrator" | "root" | "superuser" => {
+                        role = ROLE_ADMIN;
+                        principal.typ = Type::Individual
+                    }
+                    "individual" | "person" | "user" => principal.typ = Type::Individual,
+                    "group" => principal.typ = Type::Group,
+                    _ => continue,
+                }
+                break;
+            }
+        }
+
+        principal.data.push(PrincipalData::Roles(vec![role]));
+
+        Some(RestResult {
+            principal,
+            member_of: self
+                .groups
+                .as_ref()
+                .map(|path| path.eval_strings(response))
+                .unwrap_or_default(),
+        })
+    }
+}
@@ -0,0 +1,332 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+pub mod config;
+pub mod lookup;
+
+use std::time::Duration;
+
+use reqwest::Method;
+use store::Store;
+use utils::cache::CacheWithTtl;
+
+pub struct RestDirectory {
+    config: RestConfig,
+    cache: Option<CacheWithTtl<String, String>>,
+    pub(crate) data_store: Store,
+}
+
+struct RestConfig {
+    lookup_name: RestRequest,
+    lookup_email: Option<RestRequest>,
+    authenticate: Option<RestRequest>,
+    members: Option<RestRequest>,
+    mappings: RestMappings,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    allow_invalid_certs: bool,
+    cache_ttl: Duration,
+}
+
+#[derive(Debug)]
+struct RestRequest {
+    method: Method,
+    url: RestTemplate,
+    body: Option<RestTemplate>,
+}
+
+#[derive(Debug, Default)]
+struct RestMappings {
+    name: JsonPath,
+    typ: Option<JsonPath>,
+    description: Option<JsonPath>,
+    secret: Option<JsonPath>,
+    email: Option<JsonPath>,
+    email_alias: Option<JsonPath>,
+    quota: Option<JsonPath>,
+    groups: Option<JsonPath>,
+    members: Option<JsonPath>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RestTemplate {
+    items: Vec<RestTemplateItem>,
+}
+
+#[derive(Debug)]
+enum RestTemplateItem {
+    Static(String),
+    Full,
+    LocalPart,
+    DomainPart,
+    Secret,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TemplateEncoding {
+    Url,
+    Json,
+}
+
+// Minimal JSONPath subset supporting member access, array indices and wildcards,
+// for example `$.data.users[0].mail` or `$.groups[*].name`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct JsonPath {
+    segments: Vec<JsonPathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum JsonPathSegment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl RestTemplate {
+    fn build(&self, value: &str, secret: Option<&str>, encoding: TemplateEncoding) -> String {
+        let mut result = String::with_capacity(value.len() + 32);
+
+        for item in &self.items {
+            let value = match item {
+                RestTemplateItem::Static(s) => {
+                    result.push_str(s);
+                    continue;
+                }
+                RestTemplateItem::Full => value,
+                RestTemplateItem::LocalPart => value
+                    .rsplit_once('@')
+                    .map(|(local, _)| local)
+                    .unwrap_or(value),
+                RestTemplateItem::DomainPart => value
+                    .rsplit_once('@')
+                    .map(|(_, domain)| domain)
+                    .unwrap_or_default(),
+                RestTemplateItem::Secret => secret.unwrap_or_default(),
+            };
+
+            match encoding {
+                TemplateEncoding::Url => {
+                    for byte in value.bytes() {
+                        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~')
+                        {
+                            result.push(byte as char);
+                        } else {
+                            result.push_str(&format!("%{byte:02X}"));
+                        }
+                    }
+                }
+                TemplateEncoding::Json => {
+                    let value = serde_json::Value::String(value.to_string()).to_string();
+                    result.push_str(&value[1..value.len() - 1]);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn has_secret(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item, RestTemplateItem::Secret))
+    }
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let trimmed = path.trim();
+        let mut chars = trimmed
+            .strip_prefix('$')
+            .unwrap_or(trimmed)
+            .chars()
+            .peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '.' => {
+                    let mut key = String::new();
+                    while let Some(&ch) = chars.peek() {
+                        if ch == '.' || ch == '[' {
+                            break;
+                        }
+                        key.push(ch);
+                        chars.next();
+                    }
+                    match key.as_str() {
+                        "" => return Err(format!("Empty member name in JSONPath {path:?}")),
+                        "*" => segments.push(JsonPathSegment::Wildcard),
+                        _ => segments.push(JsonPathSegment::Key(key)),
+                    }
+                }
+                '[' => {
+                    let mut selector = String::new();
+                    for ch in chars.by_ref() {
+                        if ch == ']' {
+                            break;
+                        }
+                        selector.push(ch);
+                    }
+                    let selector = selector.trim();
+                    if selector == "*" {
+                        segments.push(JsonPathSegment::Wildcard);
+                    } else if let Some(key) = selector
+                        .strip_prefix('\'')
+                        .and_then(|s| s.strip_suffix('\''))
+                        .or_else(|| selector.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+                    {
+                        segments.push(JsonPathSegment::Key(key.to_string()));
+                    } else if let Ok(index) = selector.parse() {
+                        segments.push(JsonPathSegment::Index(index));
+                    } else {
+                        return Err(format!(
+                            "Invalid selector {selector:?} in JSONPath {path:?}"
+                        ));
+                    }
+                }
+                _ if segments.is_empty() => {
+                    // Allow plain member names without a leading '$.'
+                    let mut key = String::from(ch);
+                    while let Some(&ch) = chars.peek() {
+                        if ch == '.' || ch == '[' {
+                            break;
+                        }
+                        key.push(ch);
+                        chars.next();
+                    }
+                    segments.push(JsonPathSegment::Key(key));
+                }
+                _ => return Err(format!("Unexpected character {ch:?} in JSONPath {path:?}")),
+            }
+        }
+
+        Ok(JsonPath { segments })
+    }
+
+    pub fn eval<'x>(&self, value: &'x serde_json::Value) -> Vec<&'x serde_json::Value> {
+        let mut nodes = vec![value];
+
+        for segment in &self.segments {
+            let mut next = Vec::with_capacity(nodes.len());
+            for node in nodes {
+                match (segment, node) {
+                    (JsonPathSegment::Key(key), serde_json::Value::Object(map)) => {
+                        next.extend(map.get(key));
+                    }
+                    (JsonPathSegment::Index(index), serde_json::Value::Array(items)) => {
+                        next.extend(items.get(*index));
+                    }
+                    (JsonPathSegment::Wildcard, serde_json::Value::Array(items)) => {
+                        next.extend(items.iter());
+                    }
+                    (JsonPathSegment::Wildcard, serde_json::Value::Object(map)) => {
+                        next.extend(map.values());
+                    }
+                    _ => {}
+                }
+            }
+            nodes = next;
+        }
+
+        nodes
+    }
+
+    // Returns all scalar values matched by the path, arrays are flattened
+    pub fn eval_strings(&self, value: &serde_json::Value) -> Vec<String> {
+        let mut values = Vec::new();
+        for node in self.eval(value) {
+            match node {
+                serde_json::Value::Array(items) => {
+                    values.extend(items.iter().filter_map(scalar_to_string));
+                }
+                node => values.extend(scalar_to_string(node)),
+            }
+        }
+        values
+    }
+
+    pub fn eval_first(&self, value: &serde_json::Value) -> Option<String> {
+        self.eval_strings(value).into_iter().next()
+    }
+}
+
+fn scalar_to_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(value) if !value.is_empty() => Some(value.clone()),
+        serde_json::Value::Number(value) => Some(value.to_string()),
+        serde_json::Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{JsonPath, RestTemplate, TemplateEncoding};
+
+    #[test]
+    fn rest_template() {
+        let url = RestTemplate::parse("https://api/users/{local}?domain={domain}").unwrap();
+        assert_eq!(
+            url.build("j doe@example.org", None, TemplateEncoding::Url),
+            "https://api/users/j%20doe?domain=example.org"
+        );
+        assert!(!url.has_secret());
+
+        let body = RestTemplate::parse(r#"{"user":"{name}","password":"{secret}"}"#).unwrap();
+        assert_eq!(
+            body.build("john", Some("pass\"word"), TemplateEncoding::Json),
+            r#"{"user":"john","password":"pass\"word"}"#
+        );
+        assert!(body.has_secret());
+
+        assert_eq!(
+            RestTemplate::parse("{}{ name}{{email}}").unwrap().build(
+                "a@b",
+                None,
+                TemplateEncoding::Json
+            ),
+            "{}{ name}{a@b}"
+        );
+        assert!(RestTemplate::parse("/users/{uid}").is_err());
+    }
+
+    #[test]
+    fn rest_json_path() {
+        let response = json!({
+            "data": {
+                "name": "john",
+                "mail": ["john@example.org", "jdoe@example.org"],
+                "quota": 1024,
+                "groups": [{"name": "sales"}, {"name": "support"}, {"id": 3}],
+                "key.with.dots": "value",
+            }
+        });
+
+        for (path, expected) in [
+            ("$.data.name", vec!["john"]),
+            ("data.name", vec!["john"]),
+            ("$.data.mail", vec!["john@example.org", "jdoe@example.org"]),
+            ("$.data.mail[1]", vec!["jdoe@example.org"]),
+            ("$.data.quota", vec!["1024"]),
+            ("$.data.groups[*].name", vec!["sales", "support"]),
+            ("$.data['key.with.dots']", vec!["value"]),
+            ("$.data.missing", vec![]),
+            ("$.data.mail[5]", vec![]),
+        ] {
+            assert_eq!(
+                JsonPath::parse(path).unwrap().eval_strings(&response),
+                expected,
+                "{path}"
+            );
+        }
+
+        for path in ["$.data..name", "$.data[abc]"] {
+            assert!(JsonPath::parse(path).is_err(), "{path}");
+        }
+    }
+}
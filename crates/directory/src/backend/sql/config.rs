@@ -57,6 +57,7 @@ impl SqlDirectory {
             ("emails", &mut mappings.query_emails),
             ("recipients", &mut mappings.query_recipients),
             ("secrets", &mut mappings.query_secrets),
+            ("update-secret", &mut mappings.query_update_secret),
         ] {
             *query = config
                 .value(("store", store_id.as_str(), "query", query_id))
@@ -256,6 +256,21 @@ impl SqlDirectory {
     pub async fn is_local_domain(&self, domain: &str) -> trc::Result<bool> {
         self.data_store.is_local_domain(domain).await
     }
+
+    pub async fn update_secret(&self, name: &str, secret: &str) -> trc::Result<()> {
+        self.sql_store
+            .sql_query::<usize>(
+                &self.mappings.query_update_secret,
+                vec![secret.to_string().into(), name.to_string().into()],
+            )
+            .await
+            .caused_by(trc::location!())
+            .map(|_| ())
+    }
+
+    pub fn has_update_secret_query(&self) -> bool {
+        !self.mappings.query_update_secret.is_empty()
+    }
 }
 
 impl SqlMappings {
@@ -4,8 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::Duration;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
+use ahash::AHashMap;
+use tokio::sync::{OwnedRwLockWriteGuard, RwLock};
 use utils::{
     cache::CacheWithTtl,
     config::{Config, utils::AsKey},
@@ -16,8 +21,17 @@ use crate::backend::RcptType;
 pub struct CachedDirectory {
     cached_domains: CacheWithTtl<String, bool>,
     cached_rcpts: CacheWithTtl<String, bool>,
+    cached_unknown: CacheWithTtl<String, bool>,
+    inflight: Mutex<AHashMap<String, Arc<RwLock<()>>>>,
     ttl_pos: Duration,
     ttl_neg: Duration,
+    ttl_unknown: Duration,
+}
+
+pub struct InflightLookup<'x> {
+    cache: &'x CachedDirectory,
+    key: String,
+    _guard: OwnedRwLockWriteGuard<()>,
 }
 
 impl CachedDirectory {
@@ -30,12 +44,17 @@ impl CachedDirectory {
         Some(CachedDirectory {
             cached_domains: CacheWithTtl::new(50, cached_size),
             cached_rcpts: CacheWithTtl::new(100, cached_size),
+            cached_unknown: CacheWithTtl::new(100, cached_size),
+            inflight: Mutex::new(AHashMap::new()),
             ttl_pos: config
                 .property((&prefix, "cache.ttl.positive"))
                 .unwrap_or(Duration::from_secs(86400)),
             ttl_neg: config
                 .property((&prefix, "cache.ttl.negative"))
                 .unwrap_or_else(|| Duration::from_secs(3600)),
+            ttl_unknown: config
+                .property((&prefix, "cache.ttl.unknown"))
+                .unwrap_or_else(|| Duration::from_secs(300)),
         })
     }
 
@@ -64,4 +83,153 @@ impl CachedDirectory {
             if exists { self.ttl_pos } else { self.ttl_neg },
         );
     }
+
+    pub fn is_unknown(&self, key: &str) -> bool {
+        self.cached_unknown.get(key).unwrap_or_default()
+    }
+
+    pub fn set_unknown(&self, key: &str) {
+        self.cached_unknown
+            .insert(key.to_string(), true, self.ttl_unknown);
+    }
+
+    pub fn clear_unknown(&self) {
+        self.cached_unknown.clear();
+    }
+
+    // Coalesces concurrent identical lookups. Returns a guard if the caller
+    // should perform the lookup, or waits for the in-flight lookup to complete
+    // and returns None, in which case the caller should check the cache again.
+    pub async fn begin_lookup(&self, key: String) -> Option<InflightLookup<'_>> {
+        let lock = {
+            let mut inflight = self.inflight.lock().unwrap_or_else(|err| err.into_inner());
+            if let Some(lock) = inflight.get(&key) {
+                lock.clone()
+            } else {
+                let lock = Arc::new(RwLock::new(()));
+                let guard = lock.clone().try_write_owned().ok()?;
+                inflight.insert(key.clone(), lock);
+                return Some(InflightLookup {
+                    cache: self,
+                    key,
+                    _guard: guard,
+                });
+            }
+        };
+
+        drop(lock.read().await);
+        None
+    }
+}
+
+impl Drop for InflightLookup<'_> {
+    fn drop(&mut self) {
+        self.cache
+            .inflight
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use utils::config::Config;
+
+    use super::CachedDirectory;
+
+    fn cache() -> Arc<CachedDirectory> {
+        Arc::new(
+            CachedDirectory::try_from_config(&mut Config::new("").unwrap(), "directory.test")
+                .unwrap(),
+        )
+    }
+
+    // Mimics a directory miss: returns whether the backend was queried
+    async fn lookup(cache: &CachedDirectory, key: &str, lookups: &AtomicUsize, fail: bool) -> bool {
+        if cache.is_unknown(key) {
+            return false;
+        }
+        let lookup = cache.begin_lookup(key.to_string()).await;
+        if lookup.is_none() && cache.is_unknown(key) {
+            return false;
+        }
+
+        lookups.fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        if !fail {
+            cache.set_unknown(key);
+        }
+        true
+    }
+
+    #[tokio::test]
+    async fn coalesce_unknown_lookups() {
+        let cache = cache();
+        let lookups = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..10)
+            .map(|_| {
+                let cache = cache.clone();
+                let lookups = lookups.clone();
+                tokio::spawn(async move { lookup(&cache, "n:unknown", &lookups, false).await })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(lookups.load(Ordering::Relaxed), 1);
+        assert!(cache.is_unknown("n:unknown"));
+        assert!(cache.inflight.lock().unwrap().is_empty());
+
+        // Different keys are not coalesced
+        assert!(lookup(&cache, "n:other", &lookups, false).await);
+        assert_eq!(lookups.load(Ordering::Relaxed), 2);
+
+        // Management changes clear unknown entries
+        cache.clear_unknown();
+        assert!(!cache.is_unknown("n:unknown"));
+        assert!(lookup(&cache, "n:unknown", &lookups, false).await);
+        assert_eq!(lookups.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn coalesce_failed_leader() {
+        let cache = cache();
+        let lookups = Arc::new(AtomicUsize::new(0));
+
+        // Leader holds the lookup while the waiters queue up
+        let leader = cache.begin_lookup("n:flaky".to_string()).await.unwrap();
+        let tasks = (0..5)
+            .map(|_| {
+                let cache = cache.clone();
+                let lookups = lookups.clone();
+                tokio::spawn(async move { lookup(&cache, "n:flaky", &lookups, true).await })
+            })
+            .collect::<Vec<_>>();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(lookups.load(Ordering::Relaxed), 0);
+
+        // The leading lookup fails without caching a result,
+        // so every waiter falls through to the backend
+        drop(leader);
+        for task in tasks {
+            assert!(task.await.unwrap());
+        }
+        assert_eq!(lookups.load(Ordering::Relaxed), 5);
+        assert!(!cache.is_unknown("n:flaky"));
+        assert!(cache.inflight.lock().unwrap().is_empty());
+
+        // A new lookup becomes the leader again
+        assert!(cache.begin_lookup("n:flaky".to_string()).await.is_some());
+    }
 }
@@ -18,11 +18,11 @@ use crate::{
     Directories, Directory, DirectoryInner,
     backend::{
         imap::ImapDirectory, ldap::LdapDirectory, memory::MemoryDirectory, oidc::OpenIdDirectory,
-        smtp::SmtpDirectory, sql::SqlDirectory,
+        rest::RestDirectory, smtp::SmtpDirectory, sql::SqlDirectory,
     },
 };
 
-use super::cache::CachedDirectory;
+use super::{cache::CachedDirectory, secret::SecretHashAlgorithm};
 
 impl Directories {
     pub async fn parse(
@@ -82,6 +82,8 @@ impl Directories {
                     .map(DirectoryInner::Memory),
                 "oidc" => OpenIdDirectory::from_config(config, prefix, data_store.clone())
                     .map(DirectoryInner::OpenId),
+                "rest" => RestDirectory::from_config(config, prefix, data_store.clone())
+                    .map(DirectoryInner::Rest),
                 unknown => {
                     let err = format!("Unknown directory type: {unknown:?}");
                     config.new_parse_error(("directory", id, "type"), err);
@@ -93,6 +95,7 @@ impl Directories {
             if let Some(store) = store {
 
                 let directory = Arc::new(Directory {
+                    rehash_secrets: parse_rehash(config, id, &store),
                     store,
                     cache: CachedDirectory::try_from_config(config, ("directory", id)),
                 });
@@ -106,6 +109,52 @@ impl Directories {
     }
 }
 
+fn parse_rehash(
+    config: &mut Config,
+    id: &str,
+    store: &DirectoryInner,
+) -> Option<SecretHashAlgorithm> {
+    if !config
+        .property_or_default::<bool>(("directory", id, "password.rehash.enable"), "false")
+        .unwrap_or(false)
+    {
+        return None;
+    }
+
+    let algorithm = config
+        .value(("directory", id, "password.rehash.algorithm"))
+        .unwrap_or("argon2id")
+        .to_string();
+    let Some(algorithm) = SecretHashAlgorithm::parse(&algorithm) else {
+        config.new_parse_error(
+            ("directory", id, "password.rehash.algorithm"),
+            format!(
+                "Invalid hash algorithm {algorithm:?}, expected argon2id, sha512-crypt or bcrypt"
+            ),
+        );
+        return None;
+    };
+
+    match store {
+        DirectoryInner::Internal(_) => Some(algorithm),
+        DirectoryInner::Sql(sql) if sql.has_update_secret_query() => Some(algorithm),
+        DirectoryInner::Sql(_) => {
+            config.new_build_warning(
+                ("directory", id, "password.rehash.enable"),
+                "Password rehashing requires an 'update-secret' query in the SQL store",
+            );
+            None
+        }
+        _ => {
+            config.new_build_warning(
+                ("directory", id, "password.rehash.enable"),
+                "Password rehashing is only supported by internal and SQL directories",
+            );
+            None
+        }
+    }
+}
+
 pub(crate) fn build_pool<M: Manager>(
     config: &mut Config,
     prefix: &str,
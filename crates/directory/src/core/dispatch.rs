@@ -4,16 +4,49 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use mail_send::Credentials;
 use trc::AddContext;
 
 use crate::{
-    Directory, DirectoryInner, Principal, QueryParams,
-    backend::{RcptType, internal::lookup::DirectoryStore},
+    Directory, DirectoryInner, Principal, QueryBy, QueryParams,
+    backend::{
+        RcptType,
+        internal::{
+            PrincipalField, PrincipalUpdate, PrincipalValue,
+            lookup::DirectoryStore,
+            manage::{ManageDirectory, UpdatePrincipal},
+        },
+    },
 };
 
+use super::{cache::CachedDirectory, secret::SecretHashAlgorithm};
+
 impl Directory {
     pub async fn query(&self, by: QueryParams<'_>) -> trc::Result<Option<Principal>> {
-        match &self.store {
+        let rehash = match (self.rehash_secrets, &by.by) {
+            (Some(algorithm), QueryBy::Credentials(Credentials::Plain { secret, .. })) => {
+                Some((algorithm, secret.as_str()))
+            }
+            _ => None,
+        };
+
+        // Skip lookups for recently missed names
+        let unknown = match (&by.by, self.unknown_cache()) {
+            (QueryBy::Name(name), Some(cache)) => {
+                let key = format!("n:{name}");
+                if cache.is_unknown(&key) {
+                    return Ok(None);
+                }
+                let lookup = cache.begin_lookup(key.clone()).await;
+                if lookup.is_none() && cache.is_unknown(&key) {
+                    return Ok(None);
+                }
+                Some((cache, key, lookup))
+            }
+            _ => None,
+        };
+
+        let mut principal = match &self.store {
             DirectoryInner::Internal(store) => store.query(by).await,
             DirectoryInner::Ldap(store) => store.query(by).await,
             DirectoryInner::Sql(store) => store.query(by).await,
@@ -21,12 +54,101 @@ impl Directory {
             DirectoryInner::Smtp(store) => store.query(by.by).await,
             DirectoryInner::Memory(store) => store.query(by).await,
             DirectoryInner::OpenId(store) => store.query(by).await,
+            DirectoryInner::Rest(store) => store.query(by).await,
+            DirectoryInner::Unavailable => Err(unavailable()),
         }
-        .caused_by(trc::location!())
+        .caused_by(trc::location!())?;
+
+        if principal.is_none()
+            && let Some((cache, key, _)) = &unknown
+        {
+            cache.set_unknown(key);
+        }
+
+        // Upgrade weak password hashes after a successful login
+        if let (Some((algorithm, secret)), Some(principal)) = (rehash, &mut principal)
+            && let Err(err) = self.rehash_secret(principal, secret, algorithm).await
+        {
+            trc::error!(
+                err.account_id(principal.id)
+                    .details("Failed to rehash secret")
+                    .caused_by(trc::location!())
+            );
+        }
+
+        Ok(principal)
     }
 
-    pub async fn email_to_id(&self, address: &str) -> trc::Result<Option<u32>> {
+    async fn rehash_secret(
+        &self,
+        principal: &mut Principal,
+        code: &str,
+        algorithm: SecretHashAlgorithm,
+    ) -> trc::Result<()> {
+        let Some((weak_secret, secret)) = principal.find_weak_secret(code).await? else {
+            return Ok(());
+        };
+        let weak_secret = weak_secret.to_string();
+        let new_secret = algorithm.hash(secret).await?;
+
         match &self.store {
+            DirectoryInner::Internal(store) => {
+                store
+                    .update_principal(UpdatePrincipal::by_id(principal.id).with_updates(vec![
+                        PrincipalUpdate::remove_item(
+                            PrincipalField::Secrets,
+                            PrincipalValue::String(weak_secret.clone()),
+                        ),
+                        PrincipalUpdate::add_item(
+                            PrincipalField::Secrets,
+                            PrincipalValue::String(new_secret.clone()),
+                        ),
+                    ]))
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+            DirectoryInner::Sql(store) => {
+                store
+                    .update_secret(principal.name(), &new_secret)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+            _ => return Ok(()),
+        }
+
+        for secret in principal.secrets.iter_mut() {
+            if *secret == weak_secret {
+                *secret = new_secret;
+                break;
+            }
+        }
+
+        trc::event!(
+            Auth(trc::AuthEvent::SecretRehashed),
+            AccountName = principal.name().to_string(),
+            AccountId = principal.id,
+        );
+
+        Ok(())
+    }
+
+    pub async fn email_to_id(&self, address: &str) -> trc::Result<Option<u32>> {
+        // Skip lookups for recently missed addresses
+        let unknown = if let Some(cache) = self.unknown_cache() {
+            let key = format!("i:{address}");
+            if cache.is_unknown(&key) {
+                return Ok(None);
+            }
+            let lookup = cache.begin_lookup(key.clone()).await;
+            if lookup.is_none() && cache.is_unknown(&key) {
+                return Ok(None);
+            }
+            Some((cache, key, lookup))
+        } else {
+            None
+        };
+
+        let result = match &self.store {
             DirectoryInner::Internal(store) => store.email_to_id(address).await,
             DirectoryInner::Ldap(store) => store.email_to_id(address).await,
             DirectoryInner::Sql(store) => store.email_to_id(address).await,
@@ -34,17 +156,38 @@ impl Directory {
             DirectoryInner::Smtp(store) => store.email_to_id(address).await,
             DirectoryInner::Memory(store) => store.email_to_id(address).await,
             DirectoryInner::OpenId(store) => store.email_to_id(address).await,
+            DirectoryInner::Rest(store) => store.email_to_id(address).await,
+            DirectoryInner::Unavailable => Err(unavailable()),
         }
-        .caused_by(trc::location!())
+        .caused_by(trc::location!())?;
+
+        if result.is_none()
+            && let Some((cache, key, _)) = &unknown
+        {
+            cache.set_unknown(key);
+        }
+
+        Ok(result)
     }
 
     pub async fn is_local_domain(&self, domain: &str) -> trc::Result<bool> {
         // Check cache
-        if let Some(cache) = &self.cache
-            && let Some(result) = cache.get_domain(domain)
-        {
-            return Ok(result);
-        }
+        let _lookup = if let Some(cache) = &self.cache {
+            if let Some(result) = cache.get_domain(domain) {
+                return Ok(result);
+            }
+
+            // Wait for any identical in-flight lookup
+            let lookup = cache.begin_lookup(format!("d:{domain}")).await;
+            if lookup.is_none()
+                && let Some(result) = cache.get_domain(domain)
+            {
+                return Ok(result);
+            }
+            lookup
+        } else {
+            None
+        };
 
         let result = match &self.store {
             DirectoryInner::Internal(store) => store.is_local_domain(domain).await,
@@ -54,6 +197,8 @@ impl Directory {
             DirectoryInner::Smtp(store) => store.is_local_domain(domain).await,
             DirectoryInner::Memory(store) => store.is_local_domain(domain).await,
             DirectoryInner::OpenId(store) => store.is_local_domain(domain).await,
+            DirectoryInner::Rest(store) => store.is_local_domain(domain).await,
+            DirectoryInner::Unavailable => Err(unavailable()),
         }
         .caused_by(trc::location!())?;
 
@@ -67,11 +212,22 @@ impl Directory {
 
     pub async fn rcpt(&self, email: &str) -> trc::Result<RcptType> {
         // Check cache
-        if let Some(cache) = &self.cache
-            && let Some(result) = cache.get_rcpt(email)
-        {
-            return Ok(result);
-        }
+        let _lookup = if let Some(cache) = &self.cache {
+            if let Some(result) = cache.get_rcpt(email) {
+                return Ok(result);
+            }
+
+            // Wait for any identical in-flight lookup
+            let lookup = cache.begin_lookup(format!("r:{email}")).await;
+            if lookup.is_none()
+                && let Some(result) = cache.get_rcpt(email)
+            {
+                return Ok(result);
+            }
+            lookup
+        } else {
+            None
+        };
 
         let result = match &self.store {
             DirectoryInner::Internal(store) => store.rcpt(email).await,
@@ -81,6 +237,8 @@ impl Directory {
             DirectoryInner::Smtp(store) => store.rcpt(email).await,
             DirectoryInner::Memory(store) => store.rcpt(email).await,
             DirectoryInner::OpenId(store) => store.rcpt(email).await,
+            DirectoryInner::Rest(store) => store.rcpt(email).await,
+            DirectoryInner::Unavailable => Err(unavailable()),
         }
         .caused_by(trc::location!())?;
 
@@ -101,6 +259,8 @@ impl Directory {
             DirectoryInner::Smtp(store) => store.vrfy(address).await,
             DirectoryInner::Memory(store) => store.vrfy(address).await,
             DirectoryInner::OpenId(store) => store.vrfy(address).await,
+            DirectoryInner::Rest(store) => store.vrfy(address).await,
+            DirectoryInner::Unavailable => Err(unavailable()),
         }
         .caused_by(trc::location!())
     }
@@ -114,10 +274,29 @@ impl Directory {
             DirectoryInner::Smtp(store) => store.expn(address).await,
             DirectoryInner::Memory(store) => store.expn(address).await,
             DirectoryInner::OpenId(store) => store.expn(address).await,
+            DirectoryInner::Rest(store) => store.expn(address).await,
+            DirectoryInner::Unavailable => Err(unavailable()),
         }
         .caused_by(trc::location!())
     }
 
+    // Unknown principals are only cached for remote backends, local
+    // lookups are cheap and new principals should be visible immediately.
+    fn unknown_cache(&self) -> Option<&CachedDirectory> {
+        match &self.store {
+            DirectoryInner::Internal(_) | DirectoryInner::Memory(_) => None,
+            _ => self.cache.as_ref(),
+        }
+    }
+
+    // Principals created or renamed after a miss would otherwise remain
+    // hidden until cache.ttl.unknown expires.
+    pub fn clear_unknown_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear_unknown();
+        }
+    }
+
     pub fn has_bearer_token_support(&self) -> bool {
         match &self.store {
             DirectoryInner::Internal(_)
@@ -125,7 +304,9 @@ impl Directory {
             | DirectoryInner::Sql(_)
             | DirectoryInner::Imap(_)
             | DirectoryInner::Smtp(_)
-            | DirectoryInner::Memory(_) => false,
+            | DirectoryInner::Memory(_)
+            | DirectoryInner::Rest(_)
+            | DirectoryInner::Unavailable => false,
             DirectoryInner::OpenId(_) => true,
         }
     }
@@ -136,3 +317,10 @@ impl DirectoryInner {
         false
     }
 }
+
+// Directories that could not be started are reported as temporarily unavailable
+fn unavailable() -> trc::Error {
+    trc::StoreEvent::PoolError
+        .into_err()
+        .details("Directory is unavailable")
+}
@@ -251,6 +251,23 @@ impl Permission {
             Permission::CalendarSchedulingReceive => {
                 "Receive calendar scheduling requests via e-mail"
             }
+            Permission::SessionList => "View connected IMAP and POP3 sessions",
+            Permission::SessionDisconnect => "Disconnect IMAP and POP3 sessions",
+            Permission::JobList => "View background jobs and their progress",
+            Permission::JobCancel => "Cancel background jobs",
+            Permission::UnifiedSearch => "Search e-mails, calendar events and contacts at once",
+            Permission::JmapSettingsGet => "Retrieve account settings via JMAP",
+            Permission::JmapSettingsSet => "Modify account settings via JMAP",
+            Permission::ImapGetMetadata => "Retrieve mailbox and server annotations via IMAP",
+            Permission::ImapSetMetadata => "Modify mailbox and server annotations via IMAP",
+            Permission::ManageDeviceSessions => "List and revoke own login sessions and devices",
+            Permission::DeviceSessionList => "View the login sessions and devices of any account",
+            Permission::DeviceSessionRevoke => {
+                "Revoke the login sessions and devices of any account"
+            }
+            Permission::QuarantineList => "View uploads rejected by content scanning",
+            Permission::QuarantineGet => "Download uploads rejected by content scanning",
+            Permission::QuarantineDelete => "Remove uploads from the quarantine",
         }
     }
 }
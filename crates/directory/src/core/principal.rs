@@ -5,8 +5,8 @@
  */
 
 use crate::{
-    ArchivedPrincipal, FALLBACK_ADMIN_ID, Permission, PermissionGrant, Principal, PrincipalData,
-    ROLE_ADMIN, Type,
+    ArchivedPrincipal, FALLBACK_ADMIN_ID, Permission, PermissionGrant, Principal,
+    PrincipalAttribute, PrincipalAvatar, PrincipalData, ROLE_ADMIN, Type,
     backend::internal::{PrincipalField, PrincipalSet, PrincipalUpdate, PrincipalValue},
 };
 use ahash::AHashSet;
@@ -176,6 +176,36 @@ impl Principal {
         })
     }
 
+    pub fn avatar(&self) -> Option<&PrincipalAvatar> {
+        self.data.iter().find_map(|item| {
+            if let PrincipalData::Avatar(avatar) = item {
+                avatar.into()
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn attributes(&self) -> &[PrincipalAttribute] {
+        self.data
+            .iter()
+            .find_map(|item| {
+                if let PrincipalData::Attributes(items) = item {
+                    items.as_slice().into()
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes()
+            .iter()
+            .find(|attribute| attribute.name == name)
+            .map(|attribute| attribute.value.as_str())
+    }
+
     pub fn picture_mut(&mut self) -> Option<&mut String> {
         self.data.iter_mut().find_map(|item| {
             if let PrincipalData::Picture(picture) = item {
@@ -338,7 +368,16 @@ impl Principal {
                         items.iter().map(|s| s.len()).sum::<usize>()
                     }
                     PrincipalData::PrincipalQuota(items) => items.len() * U32_LEN,
-                    PrincipalData::Picture(value) | PrincipalData::Locale(value) => value.len(),
+                    PrincipalData::Picture(value)
+                    | PrincipalData::Locale(value)
+                    | PrincipalData::CalendarAutoAdd(value) => value.len(),
+                    PrincipalData::Attributes(items) => items
+                        .iter()
+                        .map(|item| item.name.len() + item.value.len())
+                        .sum::<usize>(),
+                    PrincipalData::Avatar(avatar) => {
+                        avatar.hash.0.len() + avatar.content_type.len()
+                    }
                 })
                 .sum::<usize>()
     }
@@ -358,6 +397,12 @@ impl Principal {
     }
 }
 
+impl PrincipalAvatar {
+    pub fn url_path(&self, principal_id: u32) -> String {
+        format!("/avatar/{principal_id}/{}", self.hash.to_hex())
+    }
+}
+
 impl PrincipalSet {
     pub fn new(id: u32, typ: Type) -> Self {
         Self {
@@ -1133,7 +1178,8 @@ impl<'de> serde::Deserialize<'de> for PrincipalSet {
                         PrincipalField::Description
                         | PrincipalField::Tenant
                         | PrincipalField::Picture
-                        | PrincipalField::Locale => {
+                        | PrincipalField::Locale
+                        | PrincipalField::CalendarAutoAdd => {
                             if let Some(v) = map.next_value::<Option<String>>()? {
                                 if v.len() <= MAX_STRING_LEN {
                                     PrincipalValue::String(v)
@@ -1160,18 +1206,17 @@ impl<'de> serde::Deserialize<'de> for PrincipalSet {
                         | PrincipalField::EnabledPermissions
                         | PrincipalField::DisabledPermissions
                         | PrincipalField::Urls
-                        | PrincipalField::ExternalMembers => {
-                            match map.next_value::<StringOrMany>()? {
-                                StringOrMany::One(v) => PrincipalValue::StringList(vec![v]),
-                                StringOrMany::Many(v) => {
-                                    if !v.is_empty() {
-                                        PrincipalValue::StringList(v)
-                                    } else {
-                                        continue;
-                                    }
+                        | PrincipalField::ExternalMembers
+                        | PrincipalField::Attributes => match map.next_value::<StringOrMany>()? {
+                            StringOrMany::One(v) => PrincipalValue::StringList(vec![v]),
+                            StringOrMany::Many(v) => {
+                                if !v.is_empty() {
+                                    PrincipalValue::StringList(v)
+                                } else {
+                                    continue;
                                 }
                             }
-                        }
+                        },
                         PrincipalField::UsedQuota => {
                             // consume and ignore
                             map.next_value::<IgnoredAny>()?;
@@ -1448,6 +1493,12 @@ impl Permission {
                 | Permission::CalendarAlarms
                 | Permission::CalendarSchedulingSend
                 | Permission::CalendarSchedulingReceive
+                | Permission::UnifiedSearch
+                | Permission::JmapSettingsGet
+                | Permission::JmapSettingsSet
+                | Permission::ImapGetMetadata
+                | Permission::ImapSetMetadata
+                | Permission::ManageDeviceSessions
         )
     }
 
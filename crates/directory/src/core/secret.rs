@@ -8,7 +8,7 @@ use argon2::Argon2;
 use compact_str::ToCompactString;
 use mail_builder::encoders::base64::base64_encode;
 use mail_parser::decoders::base64::base64_decode;
-use password_hash::PasswordHash;
+use password_hash::{PasswordHash, PasswordHasher, SaltString};
 use pbkdf2::Pbkdf2;
 use pwhash::{bcrypt, bsdi_crypt, md5_crypt, sha1_crypt, sha256_crypt, sha512_crypt, unix_crypt};
 use scrypt::Scrypt;
@@ -22,6 +22,13 @@ use totp_rs::TOTP;
 use crate::Principal;
 use crate::backend::internal::SpecialSecrets;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretHashAlgorithm {
+    Argon2id,
+    Sha512Crypt,
+    Bcrypt,
+}
+
 impl Principal {
     pub async fn verify_secret(&self, mut code: &str, only_app_pass: bool) -> trc::Result<bool> {
         let mut totp_token = None;
@@ -63,6 +70,8 @@ impl Principal {
                         .check_current(totp_token)
                         .unwrap_or(false);
                 }
+            } else if secret.is_client_certificate() {
+                continue;
             } else if !is_authenticated && !is_app_authenticated {
                 if let Some((_, app_secret)) =
                     secret.strip_prefix("$app$").and_then(|s| s.split_once('$'))
@@ -108,6 +117,136 @@ impl Principal {
     }
 }
 
+impl Principal {
+    // Returns the weak password hash matching the supplied secret together with
+    // the plain text password, any trailing TOTP token is ignored.
+    pub async fn find_weak_secret<'x, 'y>(
+        &'x self,
+        code: &'y str,
+    ) -> trc::Result<Option<(&'x str, &'y str)>> {
+        let totp_stripped = code.rsplit_once('$').and_then(|(c, t)| {
+            (!c.is_empty()
+                && (6..=8).contains(&t.len())
+                && t.as_bytes().iter().all(|b| b.is_ascii_digit()))
+            .then_some(c)
+        });
+
+        for secret in self.secrets.iter() {
+            if secret.is_password() && is_weak_secret_hash(secret) {
+                for code in [Some(code), totp_stripped].into_iter().flatten() {
+                    if verify_secret_hash(secret, code).await? {
+                        return Ok(Some((secret.as_str(), code)));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Principal {
+    // Returns true if one of the client certificate fingerprints stored in the
+    // secrets list matches the supplied SHA-256 fingerprint.
+    pub fn has_client_certificate(&self, fingerprint: &str) -> bool {
+        self.secrets.iter().any(|secret| {
+            secret
+                .strip_prefix("$cert$")
+                .and_then(|s| s.rsplit_once('$'))
+                .is_some_and(|(_, pin)| {
+                    pin.chars()
+                        .filter(|ch| *ch != ':')
+                        .map(|ch| ch.to_ascii_lowercase())
+                        .eq(fingerprint.chars())
+                })
+        })
+    }
+}
+
+impl SecretHashAlgorithm {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "argon2" | "argon2id" => Some(SecretHashAlgorithm::Argon2id),
+            "sha512-crypt" | "sha512crypt" => Some(SecretHashAlgorithm::Sha512Crypt),
+            "bcrypt" => Some(SecretHashAlgorithm::Bcrypt),
+            _ => None,
+        }
+    }
+
+    pub async fn hash(&self, secret: &str) -> trc::Result<String> {
+        let (tx, rx) = oneshot::channel();
+        let algorithm = *self;
+        let secret = secret.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let result = match algorithm {
+                SecretHashAlgorithm::Argon2id => {
+                    SaltString::encode_b64(&store::rand::random::<[u8; 16]>())
+                        .and_then(|salt| {
+                            Argon2::default()
+                                .hash_password(secret.as_bytes(), &salt)
+                                .map(|hash| hash.to_string())
+                        })
+                        .map_err(|err| err.to_string())
+                }
+                SecretHashAlgorithm::Sha512Crypt => {
+                    sha512_crypt::hash(&secret).map_err(|err| err.to_string())
+                }
+                SecretHashAlgorithm::Bcrypt => bcrypt::hash(&secret).map_err(|err| err.to_string()),
+            };
+            tx.send(result).ok();
+        });
+
+        match rx.await {
+            Ok(Ok(hash)) => Ok(hash),
+            Ok(Err(err)) => Err(trc::AuthEvent::Error
+                .into_err()
+                .reason(err)
+                .details("Failed to hash secret")),
+            Err(err) => Err(trc::EventType::Server(trc::ServerEvent::ThreadError)
+                .caused_by(trc::location!())
+                .reason(err)),
+        }
+    }
+}
+
+// Returns true when the secret is already in one of the supported hash formats
+pub fn is_secret_hash(secret: &str) -> bool {
+    if let Some(secret) = secret.strip_prefix('{') {
+        secret.split_once('}').is_some_and(|(algo, hash)| {
+            !hash.is_empty()
+                && !matches!(algo, "PLAIN" | "plain" | "CLEAR" | "clear")
+                && algo.bytes().all(|b| b.is_ascii_alphanumeric())
+        })
+    } else {
+        [
+            "$argon2", "$pbkdf2", "$scrypt", "$2a$", "$2b$", "$2y$", "$6$", "$5$", "$sha1$", "$1$",
+        ]
+        .iter()
+        .any(|prefix| secret.starts_with(prefix))
+            || (secret.len() == 20 && secret.starts_with('_'))
+    }
+}
+
+// Unsalted digests, single round salted digests and the legacy crypt(3)
+// variants are considered weak and are eligible for rehashing.
+pub fn is_weak_secret_hash(hashed_secret: &str) -> bool {
+    if let Some(hashed_secret) = hashed_secret.strip_prefix('{') {
+        match hashed_secret.split_once('}') {
+            Some(("ARGON2" | "ARGON2I" | "ARGON2ID" | "PBKDF2", _)) => false,
+            Some(("CRYPT" | "crypt", hashed_secret)) => {
+                !hashed_secret.starts_with('$') || is_weak_secret_hash(hashed_secret)
+            }
+            Some(_) => true,
+            None => false,
+        }
+    } else if hashed_secret.starts_with('$') {
+        hashed_secret.starts_with("$1") || hashed_secret.starts_with("$sha1")
+    } else {
+        !hashed_secret.is_empty()
+    }
+}
+
 async fn verify_hash_prefix(hashed_secret: &str, secret: &str) -> trc::Result<bool> {
     if hashed_secret.starts_with("$argon2")
         || hashed_secret.starts_with("$pbkdf2")
@@ -270,3 +409,69 @@ pub async fn verify_secret_hash(hashed_secret: &str, secret: &str) -> trc::Resul
         Ok(false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Principal, Type};
+
+    use super::{SecretHashAlgorithm, is_weak_secret_hash};
+
+    // SHA-1 digest of "12345"
+    const WEAK_SECRET: &str = "{SHA}jLIjfQZ5yojbZGTqxg2pY0VROWQ=";
+
+    #[test]
+    fn weak_secret_hashes() {
+        for (hash, is_weak) in [
+            ("12345", true),
+            (WEAK_SECRET, true),
+            ("{SSHA}K1XUSzD/HZ6vXUTfQlPbYvkT+3N4cjh1", true),
+            ("{MD5}gnzLDuqKcGxMNKFokfhOew==", true),
+            ("$1$saltsalt$jXh3y7Nh4kFyGYvI1ZBU3.", true),
+            ("{CRYPT}saLtUEOgz9n4w", true),
+            ("{CRYPT}$6$salt$hash", false),
+            ("$6$salt$hash", false),
+            (
+                "$2y$05$bvIG6Nmid91Mu9RcmmWZfO5HJIMCT8riNW0hEp8f6/FuA2/mHZFpe",
+                false,
+            ),
+            ("$argon2id$v=19$m=19456,t=2,p=1$salt$hash", false),
+            ("{ARGON2ID}$argon2id$v=19$m=19456,t=2,p=1$salt$hash", false),
+            ("{PBKDF2}$pbkdf2-sha256$i=10000$salt$hash", false),
+        ] {
+            assert_eq!(is_weak_secret_hash(hash), is_weak, "{hash}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rehash_weak_secrets() {
+        let mut principal = Principal::new(1, Type::Individual);
+        principal.secrets = vec![WEAK_SECRET.to_string()];
+
+        // Only a matching password is returned, with any TOTP token removed
+        assert_eq!(
+            principal.find_weak_secret("12345").await.unwrap(),
+            Some((WEAK_SECRET, "12345"))
+        );
+        assert_eq!(
+            principal.find_weak_secret("12345$123456").await.unwrap(),
+            Some((WEAK_SECRET, "12345"))
+        );
+        assert_eq!(principal.find_weak_secret("54321").await.unwrap(), None);
+
+        // New hashes are strong and verify the same password
+        for algorithm in [
+            SecretHashAlgorithm::Argon2id,
+            SecretHashAlgorithm::Sha512Crypt,
+            SecretHashAlgorithm::Bcrypt,
+        ] {
+            let hash = algorithm.hash("12345").await.unwrap();
+            assert!(!is_weak_secret_hash(&hash), "{algorithm:?}: {hash}");
+
+            let mut principal = Principal::new(1, Type::Individual);
+            principal.secrets = vec![hash];
+            assert!(principal.verify_secret("12345", false).await.unwrap());
+            assert!(!principal.verify_secret("54321", false).await.unwrap());
+            assert_eq!(principal.find_weak_secret("12345").await.unwrap(), None);
+        }
+    }
+}
@@ -6,7 +6,7 @@
 
 #![warn(clippy::large_futures)]
 
-use core::cache::CachedDirectory;
+use core::{cache::CachedDirectory, secret::SecretHashAlgorithm};
 use std::{fmt::Debug, sync::Arc};
 
 use ahash::AHashMap;
@@ -24,6 +24,7 @@ use mail_send::Credentials;
 use proc_macros::EnumMethods;
 use store::Store;
 use trc::ipc::bitset::Bitset;
+use types::blob_hash::BlobHash;
 
 pub mod backend;
 pub mod core;
@@ -31,6 +32,7 @@ pub mod core;
 pub struct Directory {
     pub store: DirectoryInner,
     pub cache: Option<CachedDirectory>,
+    pub rehash_secrets: Option<SecretHashAlgorithm>,
 }
 
 pub const FALLBACK_ADMIN_ID: u32 = u32::MAX;
@@ -59,6 +61,21 @@ pub enum PrincipalData {
     Urls(Vec<String>),
     PrincipalQuota(Vec<PrincipalQuota>),
     Locale(String),
+    CalendarAutoAdd(String),
+    Attributes(Vec<PrincipalAttribute>),
+    Avatar(PrincipalAvatar),
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrincipalAvatar {
+    pub hash: BlobHash,
+    pub content_type: String,
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrincipalAttribute {
+    pub name: String,
+    pub value: String,
 }
 
 #[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Clone, PartialEq, Eq)]
@@ -379,6 +396,33 @@ pub enum Permission {
     CalendarAlarms,
     CalendarSchedulingSend,
     CalendarSchedulingReceive,
+
+    // Connected clients
+    SessionList,
+    SessionDisconnect,
+
+    // Background jobs
+    JobList,
+    JobCancel,
+
+    // Search
+    UnifiedSearch,
+
+    // Account settings
+    JmapSettingsGet,
+    JmapSettingsSet,
+    ImapGetMetadata,
+    ImapSetMetadata,
+
+    // Login sessions and devices
+    ManageDeviceSessions,
+    DeviceSessionList,
+    DeviceSessionRevoke,
+
+    // Upload quarantine
+    QuarantineList,
+    QuarantineGet,
+    QuarantineDelete,
     // WARNING: add new ids at the end (TODO: use static ids)
 }
 
@@ -397,6 +441,8 @@ pub enum DirectoryInner {
     Imap(ImapDirectory),
     Smtp(SmtpDirectory),
     Memory(MemoryDirectory),
+    Rest(backend::rest::RestDirectory),
+    Unavailable,
 }
 
 pub enum QueryBy<'x> {
@@ -416,6 +462,7 @@ impl Default for Directory {
         Self {
             store: DirectoryInner::Internal(Store::None),
             cache: None,
+            rehash_secrets: None,
         }
     }
 }
@@ -9,5 +9,6 @@ pub mod identity;
 pub mod mailbox;
 pub mod message;
 pub mod push;
+pub mod settings;
 pub mod sieve;
 pub mod submission;
@@ -0,0 +1,294 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::manage::MailboxFnc;
+use crate::{
+    cache::{MessageCacheFetch, email::MessageCacheAccess, mailbox::MailboxCacheAccess},
+    message::{delete::EmailDeletion, metadata::MessageData},
+};
+use common::{Server, config::jmap::mailbox::MailboxOverflow, storage::index::ObjectIndexBuilder};
+use std::future::Future;
+use store::{
+    Deserialize, IndexKeyPrefix, IterateParams, U32_LEN,
+    ahash::AHashMap,
+    roaring::RoaringBitmap,
+    write::{BatchBuilder, key::DeserializeBigEndian},
+};
+use trc::AddContext;
+use types::{collection::Collection, field::EmailField};
+
+pub trait MailboxLimits: Sync + Send {
+    fn enforce_mailbox_limits(
+        &self,
+        account_id: u32,
+        mailbox_ids: &mut Vec<u32>,
+        message_size: u64,
+        session_id: u64,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl MailboxLimits for Server {
+    async fn enforce_mailbox_limits(
+        &self,
+        account_id: u32,
+        mailbox_ids: &mut Vec<u32>,
+        message_size: u64,
+        session_id: u64,
+    ) -> trc::Result<()> {
+        if self.core.jmap.mailbox_limits.is_empty() {
+            return Ok(());
+        }
+
+        let cache = self
+            .get_cached_messages(account_id)
+            .await
+            .caused_by(trc::location!())?;
+
+        for mailbox_id in mailbox_ids.iter_mut() {
+            let Some(limit) = cache.mailbox_by_id(mailbox_id).and_then(|mailbox| {
+                self.core
+                    .jmap
+                    .mailbox_limits
+                    .iter()
+                    .find(|limit| limit.matches(&mailbox.path, mailbox.role))
+            }) else {
+                continue;
+            };
+
+            // Obtain the messages in the mailbox sorted by UID
+            let mut messages = cache
+                .in_mailbox(*mailbox_id)
+                .filter_map(|message| {
+                    message
+                        .mailboxes
+                        .iter()
+                        .find(|m| m.mailbox_id == *mailbox_id)
+                        .map(|m| (m.uid, message.document_id))
+                })
+                .collect::<Vec<_>>();
+            let sizes = if limit.max_size.is_some() {
+                self.message_sizes(
+                    account_id,
+                    &RoaringBitmap::from_iter(messages.iter().map(|(_, id)| *id)),
+                )
+                .await?
+            } else {
+                AHashMap::new()
+            };
+            let mut total_messages = messages.len() + 1;
+            let mut total_size = sizes.values().sum::<u64>() + message_size;
+            if limit.is_within(total_messages, total_size) {
+                continue;
+            }
+
+            match &limit.overflow {
+                MailboxOverflow::Reject => {
+                    return Err(trc::LimitEvent::Quota
+                        .into_err()
+                        .details("Mailbox limit exceeded")
+                        .ctx(trc::Key::MailboxId, *mailbox_id)
+                        .ctx(trc::Key::Total, total_messages)
+                        .ctx(trc::Key::Size, total_size));
+                }
+                MailboxOverflow::Folder(path) => {
+                    let overflow_id = if let Some(mailbox) = cache.mailbox_by_path(path) {
+                        mailbox.document_id
+                    } else {
+                        self.mailbox_create_path(account_id, path)
+                            .await
+                            .caused_by(trc::location!())?
+                            .ok_or_else(|| {
+                                trc::LimitEvent::Quota
+                                    .into_err()
+                                    .details("Failed to create overflow mailbox")
+                                    .ctx(trc::Key::MailboxId, *mailbox_id)
+                            })?
+                    };
+
+                    trc::event!(
+                        MessageIngest(trc::MessageIngestEvent::MailboxOverflow),
+                        SpanId = session_id,
+                        AccountId = account_id,
+                        MailboxId = *mailbox_id,
+                        To = overflow_id,
+                    );
+
+                    *mailbox_id = overflow_id;
+                }
+                MailboxOverflow::ExpireOldest => {
+                    if limit
+                        .max_size
+                        .is_some_and(|max_size| message_size > max_size)
+                    {
+                        return Err(trc::LimitEvent::Quota
+                            .into_err()
+                            .details("Message exceeds mailbox size limit")
+                            .ctx(trc::Key::MailboxId, *mailbox_id)
+                            .ctx(trc::Key::Size, message_size));
+                    }
+
+                    messages.sort_unstable();
+                    let mut expire_ids = RoaringBitmap::new();
+                    for (_, document_id) in messages {
+                        if limit.is_within(total_messages, total_size) {
+                            break;
+                        }
+                        total_messages -= 1;
+                        total_size = total_size
+                            .saturating_sub(sizes.get(&document_id).copied().unwrap_or_default());
+                        expire_ids.insert(document_id);
+                    }
+
+                    trc::event!(
+                        MessageIngest(trc::MessageIngestEvent::MailboxExpire),
+                        SpanId = session_id,
+                        AccountId = account_id,
+                        MailboxId = *mailbox_id,
+                        Total = expire_ids.len(),
+                    );
+
+                    self.mailbox_expire_messages(account_id, *mailbox_id, expire_ids)
+                        .await?;
+                }
+            }
+        }
+
+        // Overflow folders might already be among the destinations
+        let mut seen = Vec::with_capacity(mailbox_ids.len());
+        mailbox_ids.retain(|id| {
+            if seen.contains(id) {
+                false
+            } else {
+                seen.push(*id);
+                true
+            }
+        });
+
+        Ok(())
+    }
+}
+
+trait MailboxLimitsInternal {
+    fn message_sizes(
+        &self,
+        account_id: u32,
+        message_ids: &RoaringBitmap,
+    ) -> impl Future<Output = trc::Result<AHashMap<u32, u64>>> + Send;
+
+    fn mailbox_expire_messages(
+        &self,
+        account_id: u32,
+        mailbox_id: u32,
+        message_ids: RoaringBitmap,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl MailboxLimitsInternal for Server {
+    async fn message_sizes(
+        &self,
+        account_id: u32,
+        message_ids: &RoaringBitmap,
+    ) -> trc::Result<AHashMap<u32, u64>> {
+        let mut sizes = AHashMap::with_capacity(message_ids.len() as usize);
+        if message_ids.is_empty() {
+            return Ok(sizes);
+        }
+
+        self.store()
+            .iterate(
+                IterateParams::new(
+                    IndexKeyPrefix {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        field: EmailField::Size.into(),
+                    },
+                    IndexKeyPrefix {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        field: u8::from(EmailField::Size) + 1,
+                    },
+                )
+                .ascending()
+                .no_values(),
+                |key, _| {
+                    let id_pos = key.len() - U32_LEN;
+                    let document_id = key.deserialize_be_u32(id_pos)?;
+
+                    if message_ids.contains(document_id) {
+                        let size = key
+                            .get(IndexKeyPrefix::len()..id_pos)
+                            .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))
+                            .and_then(u32::deserialize)?;
+                        sizes.insert(document_id, size as u64);
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())
+            .map(|_| sizes)
+    }
+
+    async fn mailbox_expire_messages(
+        &self,
+        account_id: u32,
+        mailbox_id: u32,
+        message_ids: RoaringBitmap,
+    ) -> trc::Result<()> {
+        // Untag messages that are also in other mailboxes, delete the rest
+        let mut batch = BatchBuilder::new();
+        let mut destroy_ids = RoaringBitmap::new();
+        batch.with_account_id(account_id);
+
+        self.get_archives(
+            account_id,
+            Collection::Email,
+            &message_ids,
+            |message_id, message_data_| {
+                let prev_message_data = message_data_
+                    .to_unarchived::<MessageData>()
+                    .caused_by(trc::location!())?;
+                if prev_message_data.inner.mailboxes.len() == 1 {
+                    destroy_ids.insert(message_id);
+                    return Ok(true);
+                }
+
+                let mut new_message_data = prev_message_data
+                    .deserialize()
+                    .caused_by(trc::location!())?;
+                new_message_data
+                    .mailboxes
+                    .retain(|id| id.mailbox_id != mailbox_id);
+
+                batch
+                    .with_collection(Collection::Email)
+                    .update_document(message_id)
+                    .custom(
+                        ObjectIndexBuilder::new()
+                            .with_changes(new_message_data)
+                            .with_current(prev_message_data),
+                    )
+                    .caused_by(trc::location!())?
+                    .commit_point();
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        if !destroy_ids.is_empty() {
+            self.emails_tombstone(account_id, &mut batch, destroy_ids)
+                .await?;
+        }
+
+        if !batch.is_empty() {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ use types::{acl::AclGrant, special_use::SpecialUse};
 
 pub mod destroy;
 pub mod index;
+pub mod limit;
 pub mod manage;
 
 pub const INBOX_ID: u32 = 0;
@@ -8,7 +8,12 @@ use super::metadata::MessageMetadata;
 use common::Server;
 use mail_parser::Message;
 use spam_filter::{
-    SpamFilterInput, analysis::init::SpamFilterInit, modules::bayes::BayesClassifier,
+    SpamFilterInput,
+    analysis::{
+        fuzzy::SpamFilterAnalyzeFuzzy, init::SpamFilterInit,
+        sender_reputation::SpamFilterAnalyzeSenderReputation,
+    },
+    modules::bayes::BayesClassifier,
 };
 use std::future::Future;
 use store::write::{TaskQueueClass, now};
@@ -40,13 +45,19 @@ impl EmailBayesTrain for Server {
         message: Message<'_>,
         learn_spam: bool,
     ) {
-        self.bayes_train_if_balanced(
-            &self.spam_filter_init(SpamFilterInput::from_account_message(
-                &message, account_id, span_id,
-            )),
-            learn_spam,
-        )
-        .await
+        let ctx = self.spam_filter_init(SpamFilterInput::from_account_message(
+            &message, account_id, span_id,
+        ));
+        self.bayes_train_if_balanced(&ctx, learn_spam).await;
+
+        // Messages reported as junk are added to the fuzzy checksum database
+        if learn_spam {
+            self.spam_filter_fuzzy_learn(&ctx).await;
+        }
+
+        // Update the reputation of the sender the message was attributed to on delivery
+        self.spam_filter_sender_reputation_learn(&ctx, learn_spam)
+            .await;
     }
 
     async fn email_bayes_queue_task_build(
@@ -0,0 +1,219 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::icalendar::{
+    ICalendar, ICalendarComponentType, ICalendarParameter, ICalendarProperty, ICalendarValue,
+};
+use mail_parser::{Message, MimeHeaders, PartType};
+
+const MAX_EVENTS: usize = 10;
+const MAX_ATTENDEES: usize = 100;
+const MAX_TEXT_LENGTH: usize = 255;
+
+#[derive(rkyv::Serialize, rkyv::Deserialize, rkyv::Archive, Debug, Default, PartialEq, Eq)]
+pub struct CalendarPreview {
+    pub method: Option<String>,
+    pub events: Vec<CalendarEventPreview>,
+}
+
+#[derive(rkyv::Serialize, rkyv::Deserialize, rkyv::Archive, Debug, Default, PartialEq, Eq)]
+pub struct CalendarEventPreview {
+    pub uid: Option<String>,
+    pub title: Option<String>,
+    pub location: Option<String>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub organizer: Option<CalendarParticipantPreview>,
+    pub attendees: Vec<CalendarParticipantPreview>,
+}
+
+#[derive(rkyv::Serialize, rkyv::Deserialize, rkyv::Archive, Debug, Default, PartialEq, Eq)]
+pub struct CalendarParticipantPreview {
+    pub email: String,
+    pub name: Option<String>,
+    pub part_stat: Option<String>,
+    pub rsvp: bool,
+}
+
+impl CalendarPreview {
+    // Summarizes the first text/calendar part of a message
+    pub fn from_message(message: &Message<'_>) -> Option<Self> {
+        message
+            .parts
+            .iter()
+            .find_map(|part| match &part.body {
+                PartType::Text(text)
+                    if part.content_type().is_some_and(|ct| {
+                        ct.ctype().eq_ignore_ascii_case("text")
+                            && ct
+                                .subtype()
+                                .is_some_and(|st| st.eq_ignore_ascii_case("calendar"))
+                    }) =>
+                {
+                    Some(text.as_ref())
+                }
+                _ => None,
+            })
+            .and_then(CalendarPreview::parse)
+    }
+
+    pub fn parse(ical: &str) -> Option<Self> {
+        let ical = ICalendar::parse(ical).ok()?;
+        let mut tz_resolver = None;
+        let mut preview = CalendarPreview {
+            method: ical
+                .components
+                .first()
+                .and_then(|comp| comp.property(&ICalendarProperty::Method))
+                .and_then(|entry| entry.values.first())
+                .and_then(|value| match value {
+                    ICalendarValue::Method(method) => Some(method.as_str().to_string()),
+                    _ => None,
+                }),
+            events: Vec::new(),
+        };
+
+        for comp in ical.components.iter().filter(|comp| {
+            matches!(
+                comp.component_type,
+                ICalendarComponentType::VEvent | ICalendarComponentType::VTodo
+            )
+        }) {
+            let mut event = CalendarEventPreview::default();
+
+            for entry in &comp.entries {
+                match (&entry.name, entry.values.first()) {
+                    (ICalendarProperty::Uid, Some(value)) => {
+                        event.uid = value.as_text().map(trim_text);
+                    }
+                    (ICalendarProperty::Summary, Some(value)) => {
+                        event.title = value.as_text().map(trim_text);
+                    }
+                    (ICalendarProperty::Location, Some(value)) => {
+                        event.location = value.as_text().map(trim_text);
+                    }
+                    (
+                        ICalendarProperty::Dtstart | ICalendarProperty::Dtend,
+                        Some(ICalendarValue::PartialDateTime(dt)),
+                    ) => {
+                        let timestamp = dt
+                            .to_date_time_with_tz(
+                                tz_resolver
+                                    .get_or_insert_with(|| ical.build_tz_resolver())
+                                    .resolve(entry.tz_id()),
+                            )
+                            .map(|dt| dt.timestamp())
+                            .or_else(|| dt.to_timestamp());
+                        if entry.name == ICalendarProperty::Dtstart {
+                            event.start = timestamp;
+                        } else {
+                            event.end = timestamp;
+                        }
+                    }
+                    (ICalendarProperty::Organizer | ICalendarProperty::Attendee, Some(value)) => {
+                        let Some(email) = value
+                            .as_text()
+                            .map(|email| email.trim().trim_start_matches("mailto:"))
+                            .filter(|email| email.contains('@'))
+                        else {
+                            continue;
+                        };
+                        let mut participant = CalendarParticipantPreview {
+                            email: email.to_lowercase(),
+                            ..Default::default()
+                        };
+                        for param in &entry.params {
+                            match param {
+                                ICalendarParameter::Cn(name) => {
+                                    participant.name = Some(trim_text(name));
+                                }
+                                ICalendarParameter::Partstat(part_stat) => {
+                                    participant.part_stat = Some(part_stat.as_str().to_string());
+                                }
+                                ICalendarParameter::Rsvp(rsvp) => {
+                                    participant.rsvp = *rsvp;
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        if entry.name == ICalendarProperty::Organizer {
+                            event.organizer = Some(participant);
+                        } else if event.attendees.len() < MAX_ATTENDEES {
+                            event.attendees.push(participant);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            preview.events.push(event);
+            if preview.events.len() == MAX_EVENTS {
+                break;
+            }
+        }
+
+        (!preview.events.is_empty()).then_some(preview)
+    }
+}
+
+fn trim_text(text: &str) -> String {
+    let text = text.trim();
+    if text.len() > MAX_TEXT_LENGTH {
+        let mut end = MAX_TEXT_LENGTH;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text[..end].to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CalendarPreview;
+
+    #[test]
+    fn calendar_preview() {
+        let preview = CalendarPreview::parse(concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:-//Example//Example//EN\r\n",
+            "METHOD:REQUEST\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:123456@example.org\r\n",
+            "DTSTAMP:20250101T090000Z\r\n",
+            "DTSTART:20250102T100000Z\r\n",
+            "DTEND:20250102T110000Z\r\n",
+            "SUMMARY:Project review\r\n",
+            "LOCATION:Room 101\r\n",
+            "ORGANIZER;CN=Jane Doe:mailto:jane@example.org\r\n",
+            "ATTENDEE;CN=John Doe;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:John@example.org\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        ))
+        .unwrap();
+
+        assert_eq!(preview.method.as_deref(), Some("REQUEST"));
+        assert_eq!(preview.events.len(), 1);
+        let event = &preview.events[0];
+        assert_eq!(event.uid.as_deref(), Some("123456@example.org"));
+        assert_eq!(event.title.as_deref(), Some("Project review"));
+        assert_eq!(event.location.as_deref(), Some("Room 101"));
+        assert_eq!(event.start, Some(1735812000));
+        assert_eq!(event.end, Some(1735815600));
+        let organizer = event.organizer.as_ref().unwrap();
+        assert_eq!(organizer.email, "jane@example.org");
+        assert_eq!(organizer.name.as_deref(), Some("Jane Doe"));
+        let attendee = &event.attendees[0];
+        assert_eq!(attendee.email, "john@example.org");
+        assert_eq!(attendee.part_stat.as_deref(), Some("NEEDS-ACTION"));
+        assert!(attendee.rsvp);
+
+        assert!(CalendarPreview::parse("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n").is_none());
+    }
+}
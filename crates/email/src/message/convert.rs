@@ -0,0 +1,281 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::tnef::{TnefMessage, is_tnef_part};
+use mail_builder::{encoders::base64::base64_encode_mime, mime::make_boundary};
+use mail_parser::{HeaderName, Message, MimeHeaders, PartType};
+
+pub struct LegacyConversion {
+    pub raw_message: Vec<u8>,
+    pub tnef_attachments: usize,
+    pub uuencoded_attachments: usize,
+}
+
+struct DecodedAttachment {
+    name: Option<String>,
+    content_type: Option<String>,
+    contents: Vec<u8>,
+}
+
+pub trait ConvertLegacyAttachments {
+    fn convert_legacy_attachments(&self, tnef: bool, uuencode: bool) -> Option<LegacyConversion>;
+}
+
+impl ConvertLegacyAttachments for Message<'_> {
+    fn convert_legacy_attachments(&self, tnef: bool, uuencode: bool) -> Option<LegacyConversion> {
+        let mut tnef_attachments = Vec::new();
+        let mut uuencoded_attachments = Vec::new();
+
+        for part in &self.parts {
+            match &part.body {
+                PartType::Binary(contents) | PartType::InlineBinary(contents)
+                    if tnef && is_tnef_part(part) =>
+                {
+                    if let Some(message) = TnefMessage::parse(contents) {
+                        tnef_attachments.extend(message.attachments.into_iter().map(
+                            |attachment| DecodedAttachment {
+                                name: attachment.name,
+                                content_type: attachment.content_type,
+                                contents: attachment.contents,
+                            },
+                        ));
+                    }
+                }
+                PartType::Text(text) if uuencode && part.attachment_name().is_none() => {
+                    uuencoded_attachments.extend(uudecode_blocks(text));
+                }
+                _ => {}
+            }
+        }
+
+        if tnef_attachments.is_empty() && uuencoded_attachments.is_empty() {
+            return None;
+        }
+
+        // The original MIME structure is kept intact as the first part of a
+        // multipart/mixed container, followed by the decoded attachments
+        let root = self.root_part();
+        let raw_message = self.raw_message();
+        let mut output = Vec::with_capacity(raw_message.len() * 2);
+        let mut inner_headers = Vec::new();
+        for header in root.headers() {
+            (if header.name.is_mime_header() {
+                &mut inner_headers
+            } else {
+                &mut output
+            })
+            .extend_from_slice(
+                &raw_message[header.offset_field() as usize..header.offset_end() as usize],
+            );
+        }
+
+        if !root
+            .headers()
+            .iter()
+            .any(|header| header.name == HeaderName::MimeVersion)
+        {
+            output.extend_from_slice(b"MIME-Version: 1.0\r\n");
+        }
+        let boundary = make_boundary("_");
+        output.extend_from_slice(b"Content-Type: multipart/mixed;\r\n\tboundary=\"");
+        output.extend_from_slice(boundary.as_bytes());
+        output.extend_from_slice(b"\"\r\n\r\n--");
+        output.extend_from_slice(boundary.as_bytes());
+        output.extend_from_slice(b"\r\n");
+        if inner_headers.is_empty() {
+            output.extend_from_slice(b"Content-Type: text/plain; charset=\"us-ascii\"\r\n");
+        } else {
+            output.extend_from_slice(&inner_headers);
+        }
+        output.extend_from_slice(b"\r\n");
+        output.extend_from_slice(&raw_message[root.raw_body_offset() as usize..]);
+
+        let num_tnef = tnef_attachments.len();
+        let num_uuencoded = uuencoded_attachments.len();
+        for (num, attachment) in tnef_attachments
+            .into_iter()
+            .chain(uuencoded_attachments)
+            .enumerate()
+        {
+            let name = attachment
+                .name
+                .unwrap_or_else(|| format!("attachment-{}.bin", num + 1))
+                .replace(['"', '\\', '\r', '\n'], "_");
+            output.extend_from_slice(b"\r\n--");
+            output.extend_from_slice(boundary.as_bytes());
+            output.extend_from_slice(b"\r\nContent-Type: ");
+            output.extend_from_slice(
+                attachment
+                    .content_type
+                    .as_deref()
+                    .filter(|ct| ct.contains('/') && ct.is_ascii() && !ct.contains(['\r', '\n']))
+                    .unwrap_or("application/octet-stream")
+                    .as_bytes(),
+            );
+            output.extend_from_slice(b"; name=\"");
+            output.extend_from_slice(name.as_bytes());
+            output.extend_from_slice(b"\"\r\nContent-Disposition: attachment; filename=\"");
+            output.extend_from_slice(name.as_bytes());
+            output.extend_from_slice(b"\"\r\nContent-Transfer-Encoding: base64\r\n\r\n");
+            base64_encode_mime(&attachment.contents, &mut output, false).ok()?;
+        }
+        output.extend_from_slice(b"\r\n--");
+        output.extend_from_slice(boundary.as_bytes());
+        output.extend_from_slice(b"--\r\n");
+
+        Some(LegacyConversion {
+            raw_message: output,
+            tnef_attachments: num_tnef,
+            uuencoded_attachments: num_uuencoded,
+        })
+    }
+}
+
+// Extracts the files embedded in a text body between "begin <mode> <name>"
+// and "end" lines
+fn uudecode_blocks(text: &str) -> Vec<DecodedAttachment> {
+    let mut attachments = Vec::new();
+    let mut lines = text.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((mode, name)) = line
+            .strip_prefix("begin ")
+            .and_then(|line| line.split_once(' '))
+        else {
+            continue;
+        };
+        if !(3..=4).contains(&mode.len()) || !mode.bytes().all(|ch| (b'0'..=b'7').contains(&ch)) {
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        let mut is_complete = false;
+        for line in lines.by_ref() {
+            let line = line.trim_end().as_bytes();
+            if line == b"end" {
+                is_complete = true;
+                break;
+            } else if let Some((&len, data)) = line.split_first() {
+                let len = (len.wrapping_sub(b' ') & 0x3f) as usize;
+                let mut decoded = Vec::with_capacity(len + 2);
+                for chunk in data.chunks(4) {
+                    let mut group = [0u8; 4];
+                    for (pos, ch) in chunk.iter().enumerate() {
+                        group[pos] = ch.wrapping_sub(b' ') & 0x3f;
+                    }
+                    decoded.push((group[0] << 2) | (group[1] >> 4));
+                    decoded.push((group[1] << 4) | (group[2] >> 2));
+                    decoded.push((group[2] << 6) | group[3]);
+                }
+                decoded.truncate(len);
+                contents.extend_from_slice(&decoded);
+            }
+        }
+
+        if is_complete && !contents.is_empty() {
+            attachments.push(DecodedAttachment {
+                name: Some(name.trim().to_string()).filter(|name| !name.is_empty()),
+                content_type: None,
+                contents,
+            });
+        }
+    }
+
+    attachments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConvertLegacyAttachments;
+    use mail_parser::{MessageParser, MimeHeaders};
+
+    fn tnef_attribute(level: u8, id: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![level];
+        bytes.extend_from_slice(&id.to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        let checksum = data
+            .iter()
+            .fold(0u16, |acc, ch| acc.wrapping_add(*ch as u16));
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn convert_uuencoded() {
+        let raw_message = concat!(
+            "From: legacy@example.org\r\n",
+            "Subject: Report\r\n",
+            "\r\n",
+            "See attached.\r\n",
+            "\r\n",
+            "begin 644 hello.txt\r\n",
+            "<2&5L;&\\@9G)O;2!A(&QE9V%C>2!C;&EE;G0A\"@``\r\n",
+            "`\r\n",
+            "end\r\n",
+        );
+        let message = MessageParser::new().parse(raw_message).unwrap();
+        let conversion = message.convert_legacy_attachments(false, true).unwrap();
+        assert_eq!(conversion.uuencoded_attachments, 1);
+        assert_eq!(conversion.tnef_attachments, 0);
+
+        let converted = MessageParser::new().parse(&conversion.raw_message).unwrap();
+        assert_eq!(converted.subject(), Some("Report"));
+        assert!(converted.body_text(0).unwrap().contains("See attached."));
+        let attachment = converted.attachment(0).unwrap();
+        assert_eq!(attachment.attachment_name(), Some("hello.txt"));
+        assert_eq!(attachment.contents(), b"Hello from a legacy client!\n");
+
+        // Nothing to convert when uuencode decoding is disabled
+        assert!(message.convert_legacy_attachments(true, false).is_none());
+    }
+
+    #[test]
+    fn convert_tnef() {
+        let mut tnef = 0x223E9F78u32.to_le_bytes().to_vec();
+        tnef.extend_from_slice(&[0x01, 0x00]);
+        tnef.extend(tnef_attribute(0x01, 0x00018004, b"Quarterly numbers\0"));
+        tnef.extend(tnef_attribute(0x02, 0x00069002, &[0u8; 14]));
+        tnef.extend(tnef_attribute(0x02, 0x00018010, b"NUMBER~1.CSV\0"));
+        tnef.extend(tnef_attribute(0x02, 0x0006800F, b"q1,q2\r\n10,20\r\n"));
+
+        let mut raw_message = concat!(
+            "From: exchange@example.org\r\n",
+            "Subject: Numbers\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/mixed; boundary=\"b1\"\r\n",
+            "\r\n",
+            "--b1\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Numbers attached.\r\n",
+            "--b1\r\n",
+            "Content-Type: application/ms-tnef; name=\"winmail.dat\"\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+        )
+        .as_bytes()
+        .to_vec();
+        mail_builder::encoders::base64::base64_encode_mime(&tnef, &mut raw_message, false).unwrap();
+        raw_message.extend_from_slice(b"\r\n--b1--\r\n");
+
+        let message = MessageParser::new().parse(&raw_message).unwrap();
+        assert!(message.convert_legacy_attachments(false, true).is_none());
+        let conversion = message.convert_legacy_attachments(true, false).unwrap();
+        assert_eq!(conversion.tnef_attachments, 1);
+
+        let converted = MessageParser::new().parse(&conversion.raw_message).unwrap();
+        assert_eq!(converted.subject(), Some("Numbers"));
+        assert_eq!(converted.attachment_count(), 2);
+        assert_eq!(
+            converted.attachment(0).unwrap().attachment_name(),
+            Some("winmail.dat")
+        );
+        let attachment = converted.attachment(1).unwrap();
+        assert_eq!(attachment.attachment_name(), Some("NUMBER~1.CSV"));
+        assert_eq!(attachment.contents(), b"q1,q2\r\n10,20\r\n");
+    }
+}
@@ -5,14 +5,18 @@
  */
 
 use super::{
+    calendar::CalendarPreview,
     index::{MAX_ID_LENGTH, MAX_SORT_FIELD_LENGTH, TrimTextValue, VisitText},
     ingest::{EmailIngest, IngestedEmail, ThreadResult},
     metadata::{MessageData, MessageMetadata},
 };
-use crate::mailbox::UidMailbox;
+use crate::mailbox::{UidMailbox, limit::MailboxLimits};
 use common::{Server, auth::ResourceToken, storage::index::ObjectIndexBuilder};
 use mail_parser::{HeaderName, HeaderValue, parsers::fields::thread::thread_name};
-use store::write::{BatchBuilder, TaskQueueClass, ValueClass, now};
+use store::{
+    Serialize,
+    write::{Archiver, BatchBuilder, TaskQueueClass, ValueClass, now},
+};
 use trc::AddContext;
 use types::{
     blob::{BlobClass, BlobId},
@@ -47,7 +51,7 @@ impl EmailCopy for Server {
         from_account_id: u32,
         from_message_id: u32,
         resource_token: &ResourceToken,
-        mailboxes: Vec<u32>,
+        mut mailboxes: Vec<u32>,
         keywords: Vec<Keyword>,
         received_at: Option<u64>,
         session_id: u64,
@@ -70,11 +74,23 @@ impl EmailCopy for Server {
             return Ok(Err(CopyMessageError::NotFound));
         };
 
-        // Check quota
-        match self
+        // Check quota and folder limits
+        let result = match self
             .has_available_quota(resource_token, metadata.size as u64)
             .await
         {
+            Ok(_) => {
+                self.enforce_mailbox_limits(
+                    account_id,
+                    &mut mailboxes,
+                    metadata.size as u64,
+                    session_id,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+        match result {
             Ok(_) => (),
             Err(err) => {
                 if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota))
@@ -208,6 +224,28 @@ impl EmailCopy for Server {
             )
             .caused_by(trc::location!())?;
 
+        // Copy calendar summary
+        if let Some(calendar_preview) = self
+            .get_archive_by_property(
+                from_account_id,
+                Collection::Email,
+                from_message_id,
+                EmailField::CalendarPreview.into(),
+            )
+            .await?
+        {
+            batch.set(
+                EmailField::CalendarPreview,
+                Archiver::new(
+                    calendar_preview
+                        .deserialize::<CalendarPreview>()
+                        .caused_by(trc::location!())?,
+                )
+                .serialize()
+                .caused_by(trc::location!())?,
+            );
+        }
+
         // Insert and obtain ids
         let change_id = self
             .store()
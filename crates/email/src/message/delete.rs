@@ -4,9 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use super::dumpster::EmailDumpster;
 use super::metadata::MessageData;
 use crate::{cache::MessageCacheFetch, mailbox::*, message::metadata::MessageMetadata};
-use common::{KV_LOCK_PURGE_ACCOUNT, Server, storage::index::ObjectIndexBuilder};
+use common::{
+    KV_LOCK_PURGE_ACCOUNT, Server,
+    storage::{dumpster::DumpsterEntry, index::ObjectIndexBuilder},
+};
 use groupware::calendar::storage::ItipAutoExpunge;
 use std::future::Future;
 use store::rand::prelude::SliceRandom;
@@ -15,7 +19,9 @@ use store::write::now;
 use store::{
     BitmapKey, ValueKey,
     roaring::RoaringBitmap,
-    write::{AlignedBytes, Archive, BatchBuilder, BitmapClass, TagValue, ValueClass},
+    write::{
+        AlignedBytes, Archive, BatchBuilder, BitmapClass, DirectoryClass, TagValue, ValueClass,
+    },
 };
 use store::{IndexKey, IterateParams, SerializeInfallible, U32_LEN};
 use trc::AddContext;
@@ -46,6 +52,12 @@ pub trait EmailDeletion: Sync + Send {
         &self,
         account_id: u32,
     ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn emails_purge(
+        &self,
+        account_id: u32,
+        document_ids: RoaringBitmap,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
 }
 
 impl EmailDeletion for Server {
@@ -174,6 +186,16 @@ impl EmailDeletion for Server {
             );
         }
 
+        // Compact expunged items journal
+        if let Some(retention) = self.core.jmap.changes_vanished_retention
+            && let Err(err) = self.compact_vanished(account_id, retention).await
+        {
+            trc::error!(
+                err.details("Failed to compact vanished items.")
+                    .account_id(account_id)
+            );
+        }
+
         // Delete lock
         if let Err(err) = self
             .in_memory_store()
@@ -282,17 +304,35 @@ impl EmailDeletion for Server {
             return Ok(());
         }
 
+        // Keep recently deleted messages in the dumpster
+        let tombstoned_ids = if let Some(retention) = self.core.jmap.mail_dumpster_retention {
+            let purge_ids = self
+                .emails_dumpster_sweep(account_id, tombstoned_ids, retention)
+                .await
+                .caused_by(trc::location!())?;
+            if purge_ids.is_empty() {
+                return Ok(());
+            }
+            purge_ids
+        } else {
+            tombstoned_ids
+        };
+
         trc::event!(
             Purge(trc::PurgeEvent::TombstoneCleanup),
             AccountId = account_id,
             Total = tombstoned_ids.len(),
         );
 
+        self.emails_purge(account_id, tombstoned_ids).await
+    }
+
+    async fn emails_purge(&self, account_id: u32, document_ids: RoaringBitmap) -> trc::Result<()> {
         // Delete full-text index
         self.core
             .storage
             .fts
-            .remove(account_id, Collection::Email, &tombstoned_ids)
+            .remove(account_id, Collection::Email, &document_ids)
             .await?;
 
         // Obtain tenant id
@@ -307,13 +347,35 @@ impl EmailDeletion for Server {
         let mut batch = BatchBuilder::new();
         batch.with_account_id(account_id);
 
-        for document_id in tombstoned_ids {
+        for document_id in document_ids {
             batch
                 .with_collection(Collection::Email)
                 .delete_document(document_id)
                 .clear(EmailField::Archive)
                 .untag(EmailField::MailboxIds, TagValue::Id(TOMBSTONE_ID));
 
+            // Remove from dumpster, restoring any quota released on deletion
+            if let Some(entry) = self
+                .core
+                .storage
+                .data
+                .get_value::<DumpsterEntry>(ValueKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id,
+                    class: ValueClass::Property(EmailField::Dumpster.into()),
+                })
+                .await?
+            {
+                batch.clear(EmailField::Dumpster);
+                if entry.quota_released {
+                    batch.add(DirectoryClass::UsedQuota(account_id), entry.size as i64);
+                    if let Some(tenant_id) = tenant_id {
+                        batch.add(DirectoryClass::UsedQuota(tenant_id), entry.size as i64);
+                    }
+                }
+            }
+
             // Remove message metadata
             if let Some(metadata_) = self
                 .core
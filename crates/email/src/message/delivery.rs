@@ -22,6 +22,7 @@ pub struct IngestMessage {
     pub sender_authenticated: bool,
     pub recipients: Vec<String>,
     pub message_blob: BlobHash,
+    pub message_data: Option<Vec<u8>>,
     pub message_size: u64,
     pub session_id: u64,
 }
@@ -58,14 +59,18 @@ pub trait MailDelivery: Sync + Send {
 
 impl MailDelivery for Server {
     async fn deliver_message(&self, message: IngestMessage) -> LocalDeliveryResult {
-        // Read message
-        let raw_message = match self
-            .core
-            .storage
-            .blob
-            .get_blob(message.message_blob.as_slice(), 0..usize::MAX)
-            .await
-        {
+        // Read message, unless the caller already has it in memory
+        let raw_message = match message.message_data {
+            Some(raw_message) => Ok(Some(raw_message)),
+            None => {
+                self.core
+                    .storage
+                    .blob
+                    .get_blob(message.message_blob.as_slice(), 0..usize::MAX)
+                    .await
+            }
+        };
+        let raw_message = match raw_message {
             Ok(Some(raw_message)) => raw_message,
             Ok(None) => {
                 trc::event!(
@@ -102,6 +107,25 @@ impl MailDelivery for Server {
             }
         };
 
+        // Addresses this message was already delivered to, used to detect forwarding loops
+        let delivered_to = if self.core.smtp.session.data.add_delivered_to {
+            MessageParser::new()
+                .parse_headers(&raw_message)
+                .map(|message| {
+                    message
+                        .root_part()
+                        .headers()
+                        .iter()
+                        .filter(|header| header.name.as_str().eq_ignore_ascii_case("Delivered-To"))
+                        .filter_map(|header| header.value.as_text())
+                        .map(|address| address.trim().to_lowercase())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        } else {
+            vec![]
+        };
+
         // Obtain the UIDs for each recipient
         let mut uids: AHashMap<u32, usize> = AHashMap::with_capacity(message.recipients.len());
         let mut result = LocalDeliveryResult {
@@ -110,6 +134,23 @@ impl MailDelivery for Server {
         };
 
         for rcpt in message.recipients {
+            if delivered_to
+                .iter()
+                .any(|address| address.eq_ignore_ascii_case(&rcpt))
+            {
+                trc::event!(
+                    MessageIngest(trc::MessageIngestEvent::ForwardLoop),
+                    To = rcpt,
+                    SpanId = message.session_id,
+                );
+
+                result.status.push(LocalDeliveryStatus::PermanentFailure {
+                    code: [5, 4, 6],
+                    reason: "Mail forwarding loop detected.".into(),
+                });
+                continue;
+            }
+
             let uid = match self
                 .email_to_id(&self.core.storage.directory, &rcpt, message.session_id)
                 .await
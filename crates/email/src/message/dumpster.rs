@@ -0,0 +1,317 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{
+    copy::{CopyMessageError, EmailCopy},
+    delete::EmailDeletion,
+    ingest::IngestedEmail,
+    metadata::MessageMetadata,
+};
+use crate::{
+    cache::{MessageCacheFetch, mailbox::MailboxCacheAccess},
+    mailbox::INBOX_ID,
+};
+use common::{Server, storage::dumpster::DumpsterEntry};
+use mail_parser::{ArchivedHeaderName, core::rkyv::ArchivedGetHeader};
+use serde::Serialize;
+use std::future::Future;
+use store::{
+    Deserialize, IterateParams, SerializeInfallible, U32_LEN, ValueKey,
+    roaring::RoaringBitmap,
+    write::{
+        AlignedBytes, Archive, BatchBuilder, DirectoryClass, ValueClass, key::DeserializeBigEndian,
+        now,
+    },
+};
+use trc::AddContext;
+use types::{collection::Collection, field::EmailField};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DumpsterItem {
+    pub id: u32,
+    pub size: u32,
+    #[serde(rename = "receivedAt")]
+    pub received_at: u64,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: u64,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<u64>,
+    pub subject: Option<String>,
+    pub preview: String,
+}
+
+pub enum RestoreError {
+    NotFound,
+    MailboxNotFound,
+    OverQuota,
+}
+
+pub trait EmailDumpster: Sync + Send {
+    fn emails_dumpster_sweep(
+        &self,
+        account_id: u32,
+        tombstoned_ids: RoaringBitmap,
+        retention: u64,
+    ) -> impl Future<Output = trc::Result<RoaringBitmap>> + Send;
+
+    fn emails_dumpster_list(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<Vec<DumpsterItem>>> + Send;
+
+    fn emails_dumpster_restore(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        mailbox_id: Option<u32>,
+    ) -> impl Future<Output = trc::Result<Result<IngestedEmail, RestoreError>>> + Send;
+}
+
+impl EmailDumpster for Server {
+    async fn emails_dumpster_sweep(
+        &self,
+        account_id: u32,
+        tombstoned_ids: RoaringBitmap,
+        retention: u64,
+    ) -> trc::Result<RoaringBitmap> {
+        let now = now();
+        let mut purge_ids = RoaringBitmap::new();
+        let mut retained = Vec::with_capacity(tombstoned_ids.len() as usize);
+        let mut released_quota = 0i64;
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Email);
+
+        for document_id in tombstoned_ids {
+            let entry = if let Some(entry) = self
+                .core
+                .storage
+                .data
+                .get_value::<DumpsterEntry>(ValueKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id,
+                    class: ValueClass::Property(EmailField::Dumpster.into()),
+                })
+                .await
+                .caused_by(trc::location!())?
+            {
+                entry
+            } else if let Some(metadata_) = self
+                .core
+                .storage
+                .data
+                .get_value::<Archive<AlignedBytes>>(ValueKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id,
+                    class: ValueClass::Property(EmailField::Metadata.into()),
+                })
+                .await
+                .caused_by(trc::location!())?
+            {
+                // Newly deleted message, move it to the dumpster
+                let metadata = metadata_
+                    .unarchive::<MessageMetadata>()
+                    .caused_by(trc::location!())?;
+                let entry = DumpsterEntry {
+                    deleted_at: now,
+                    size: metadata.size.into(),
+                    quota_released: !self.core.jmap.mail_dumpster_count_quota,
+                };
+                batch
+                    .update_document(document_id)
+                    .set(EmailField::Dumpster, entry.serialize());
+                if entry.quota_released {
+                    released_quota += entry.size as i64;
+                }
+                entry
+            } else {
+                purge_ids.insert(document_id);
+                continue;
+            };
+
+            if entry.deleted_at.saturating_add(retention) <= now {
+                purge_ids.insert(document_id);
+            } else {
+                retained.push((document_id, entry));
+            }
+        }
+
+        // Enforce the dumpster size limit, keeping the most recently deleted items
+        if let Some(max_size) = self.core.jmap.mail_dumpster_max_size {
+            retained.sort_unstable_by(|a, b| b.1.deleted_at.cmp(&a.1.deleted_at));
+            let mut total_size = 0u64;
+            for (document_id, entry) in retained {
+                total_size += entry.size as u64;
+                if total_size > max_size {
+                    purge_ids.insert(document_id);
+                }
+            }
+        }
+
+        if released_quota != 0 {
+            batch.add(DirectoryClass::UsedQuota(account_id), -released_quota);
+            if let Some(tenant_id) = self
+                .get_access_token(account_id)
+                .await
+                .caused_by(trc::location!())?
+                .tenant
+                .map(|t| t.id)
+            {
+                batch.add(DirectoryClass::UsedQuota(tenant_id), -released_quota);
+            }
+        }
+
+        if !batch.is_empty() {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        Ok(purge_ids)
+    }
+
+    async fn emails_dumpster_list(&self, account_id: u32) -> trc::Result<Vec<DumpsterItem>> {
+        let mut entries = Vec::new();
+        self.core
+            .storage
+            .data
+            .iterate(
+                IterateParams::new(
+                    ValueKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        document_id: 0,
+                        class: ValueClass::Property(EmailField::Dumpster.into()),
+                    },
+                    ValueKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        document_id: u32::MAX,
+                        class: ValueClass::Property(EmailField::Dumpster.into()),
+                    },
+                )
+                .ascending(),
+                |key, value| {
+                    entries.push((
+                        key.deserialize_be_u32(key.len() - U32_LEN)?,
+                        DumpsterEntry::deserialize(value)?,
+                    ));
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        let retention = self.core.jmap.mail_dumpster_retention;
+        let mut items = Vec::with_capacity(entries.len());
+        for (document_id, entry) in entries {
+            let Some(metadata_) = self
+                .core
+                .storage
+                .data
+                .get_value::<Archive<AlignedBytes>>(ValueKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id,
+                    class: ValueClass::Property(EmailField::Metadata.into()),
+                })
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let metadata = metadata_
+                .unarchive::<MessageMetadata>()
+                .caused_by(trc::location!())?;
+
+            items.push(DumpsterItem {
+                id: document_id,
+                size: entry.size,
+                received_at: metadata.received_at.into(),
+                deleted_at: entry.deleted_at,
+                expires_at: retention.map(|retention| entry.deleted_at.saturating_add(retention)),
+                subject: metadata.contents[0]
+                    .root_part()
+                    .headers
+                    .header_value(&ArchivedHeaderName::Subject)
+                    .and_then(|header| header.as_text())
+                    .map(|subject| subject.to_string()),
+                preview: metadata.preview.to_string(),
+            });
+        }
+
+        Ok(items)
+    }
+
+    async fn emails_dumpster_restore(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        mailbox_id: Option<u32>,
+    ) -> trc::Result<Result<IngestedEmail, RestoreError>> {
+        if self
+            .core
+            .storage
+            .data
+            .get_value::<DumpsterEntry>(ValueKey {
+                account_id,
+                collection: Collection::Email.into(),
+                document_id,
+                class: ValueClass::Property(EmailField::Dumpster.into()),
+            })
+            .await
+            .caused_by(trc::location!())?
+            .is_none()
+        {
+            return Ok(Err(RestoreError::NotFound));
+        }
+
+        // Validate the destination mailbox
+        let mailbox_id = mailbox_id.unwrap_or(INBOX_ID);
+        if !self
+            .get_cached_messages(account_id)
+            .await
+            .caused_by(trc::location!())?
+            .has_mailbox_id(&mailbox_id)
+        {
+            return Ok(Err(RestoreError::MailboxNotFound));
+        }
+
+        // Copy the deleted message into the mailbox, keeping its original receivedAt
+        let resource_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?
+            .as_resource_token();
+        let ingested = match self
+            .copy_message(
+                account_id,
+                document_id,
+                &resource_token,
+                vec![mailbox_id],
+                vec![],
+                None,
+                0,
+            )
+            .await?
+        {
+            Ok(ingested) => ingested,
+            Err(CopyMessageError::NotFound) => return Ok(Err(RestoreError::NotFound)),
+            Err(CopyMessageError::OverQuota) => return Ok(Err(RestoreError::OverQuota)),
+        };
+
+        // Remove the deleted copy from the dumpster
+        let mut document_ids = RoaringBitmap::new();
+        document_ids.insert(document_id);
+        self.emails_purge(account_id, document_ids)
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(Ok(ingested))
+    }
+}
@@ -6,10 +6,13 @@
 
 use std::borrow::Cow;
 
-use super::metadata::{
-    ArchivedMessageData, ArchivedMessageMetadata, ArchivedMessageMetadataContents,
-    ArchivedMessageMetadataPart, ArchivedMetadataPartType, DecodedPartContent, MessageData,
-    MessageMetadata, MessageMetadataPart,
+use super::{
+    calendar::CalendarPreview,
+    metadata::{
+        ArchivedMessageData, ArchivedMessageMetadata, ArchivedMessageMetadataContents,
+        ArchivedMessageMetadataPart, ArchivedMetadataPartType, DecodedPartContent, MessageData,
+        MessageMetadata, MessageMetadataPart,
+    },
 };
 use common::storage::index::{IndexValue, IndexableObject, ObjectIndexBuilder};
 use mail_parser::{
@@ -268,6 +271,9 @@ impl ArchivedMessageMetadata {
                     EmailField::ReceivedAt,
                     u64::from(self.received_at).serialize(),
                 );
+            if self.contents[0].parts.iter().any(|part| part.is_calendar()) {
+                batch.clear(EmailField::CalendarPreview);
+            }
         }
 
         // Index properties
@@ -539,6 +545,9 @@ impl IndexMessage for BatchBuilder {
             }
         }
 
+        // Summarize calendar invitations
+        let calendar_preview = CalendarPreview::from_message(&message);
+
         // Build metadata
         let root_part = message.root_part();
         let metadata = MessageMetadata {
@@ -582,6 +591,14 @@ impl IndexMessage for BatchBuilder {
                 .serialize()
                 .caused_by(trc::location!())?,
         );
+        if let Some(calendar_preview) = calendar_preview {
+            self.set(
+                EmailField::CalendarPreview,
+                Archiver::new(calendar_preview)
+                    .serialize()
+                    .caused_by(trc::location!())?,
+            );
+        }
 
         Ok(self)
     }
@@ -5,19 +5,23 @@
  */
 
 use super::{
+    convert::ConvertLegacyAttachments,
     crypto::{EncryptMessage, EncryptMessageError},
     index::{MAX_SORT_FIELD_LENGTH, TrimTextValue},
 };
 use crate::{
     cache::{MessageCacheFetch, email::MessageCacheAccess},
-    mailbox::{INBOX_ID, JUNK_ID, UidMailbox},
+    mailbox::{INBOX_ID, JUNK_ID, UidMailbox, limit::MailboxLimits},
     message::{
         crypto::EncryptionParams,
         index::{IndexMessage, MAX_ID_LENGTH, VisitText},
         metadata::MessageData,
     },
 };
-use common::{Server, auth::AccessToken, storage::index::ObjectIndexBuilder};
+use common::{
+    Server, auth::AccessToken, storage::index::ObjectIndexBuilder,
+    telemetry::metrics::labels::LabeledMetric,
+};
 use directory::Permission;
 use groupware::{
     calendar::itip::{ItipIngest, ItipIngestError},
@@ -129,12 +133,66 @@ impl EmailIngest for Server {
             .caused_by(trc::location!())?;
 
         // Parse message
+        let converted_message: Vec<u8>;
         let mut raw_message = Cow::from(params.raw_message);
         let mut message = params.message.ok_or_else(|| {
             trc::EventType::MessageIngest(trc::MessageIngestEvent::Error)
                 .ctx(trc::Key::Code, 550)
                 .ctx(trc::Key::Reason, "Failed to parse e-mail message.")
         })?;
+        if let Err(event) = self
+            .core
+            .jmap
+            .mail_mime_limits
+            .check(&message, raw_message.len())
+        {
+            trc::event!(
+                MessageIngest(event),
+                SpanId = params.session_id,
+                AccountId = account_id,
+                Size = raw_message_len,
+            );
+
+            return Err(
+                trc::EventType::MessageIngest(trc::MessageIngestEvent::Error)
+                    .ctx(trc::Key::Code, 550)
+                    .ctx(trc::Key::Reason, event.explain()),
+            );
+        }
+
+        // Convert TNEF and uuencoded contents into MIME attachments
+        if params.source.is_smtp()
+            && (self.core.jmap.mail_convert_tnef || self.core.jmap.mail_convert_uuencode)
+            && let Some(conversion) = message.convert_legacy_attachments(
+                self.core.jmap.mail_convert_tnef,
+                self.core.jmap.mail_convert_uuencode,
+            )
+        {
+            trc::event!(
+                MessageIngest(MessageIngestEvent::AttachmentsConverted),
+                SpanId = params.session_id,
+                AccountId = account_id,
+                Size = conversion.raw_message.len(),
+                Total = conversion.tnef_attachments + conversion.uuencoded_attachments,
+            );
+
+            converted_message = conversion.raw_message;
+            raw_message = Cow::from(converted_message.as_slice());
+            raw_message_len = raw_message.len() as u64;
+            self.has_available_quota(&resource_token, raw_message_len)
+                .await
+                .caused_by(trc::location!())?;
+            message = MessageParser::default()
+                .parse(&converted_message)
+                .ok_or_else(|| {
+                    trc::EventType::MessageIngest(trc::MessageIngestEvent::Error)
+                        .ctx(trc::Key::Code, 550)
+                        .ctx(
+                            trc::Key::Reason,
+                            "Failed to parse converted e-mail message.",
+                        )
+                })?;
+        }
 
         let mut is_spam = false;
         let mut train_spam = None;
@@ -598,6 +656,17 @@ impl EmailIngest for Server {
             }
         }
 
+        // Enforce folder limits
+        if params.source != IngestSource::Restore {
+            self.enforce_mailbox_limits(
+                account_id,
+                &mut params.mailbox_ids,
+                raw_message_len,
+                params.session_id,
+            )
+            .await?;
+        }
+
         // Store blob
         let blob_id = self
             .put_blob(account_id, raw_message.as_ref(), false)
@@ -713,6 +782,19 @@ impl EmailIngest for Server {
             Elapsed = start_time.elapsed(),
         );
 
+        if matches!(params.source, IngestSource::Smtp { .. }) {
+            self.record_labeled_metric(
+                LabeledMetric::MessagesIn,
+                params
+                    .access_token
+                    .emails
+                    .first()
+                    .and_then(|email| email.rsplit_once('@'))
+                    .map(|(_, domain)| domain),
+                tenant_id,
+            );
+        }
+
         Ok(IngestedEmail {
             document_id,
             thread_id,
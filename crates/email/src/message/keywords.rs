@@ -0,0 +1,242 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::metadata::{ArchivedMessageData, MessageData};
+use crate::cache::{MessageCacheFetch, email::MessageCacheAccess};
+use common::{MessageStoreCache, Server, auth::AccessToken, storage::index::ObjectIndexBuilder};
+use std::future::Future;
+use store::{ahash::AHashSet, roaring::RoaringBitmap, write::BatchBuilder};
+use trc::AddContext;
+use types::{
+    acl::Acl,
+    collection::Collection,
+    keyword::{Keyword, OTHER},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordLimit {
+    PerMessage,
+    Custom,
+}
+
+pub struct KeywordPolicy<'x> {
+    max_per_message: usize,
+    max_custom: usize,
+    private: &'x [Keyword],
+    custom: AHashSet<String>,
+    private_allowed: Option<RoaringBitmap>,
+}
+
+impl<'x> KeywordPolicy<'x> {
+    pub fn new(
+        server: &'x Server,
+        cache: &MessageStoreCache,
+        access_token: &AccessToken,
+        account_id: u32,
+    ) -> Self {
+        let config = &server.core.jmap;
+
+        // Obtain the custom keywords currently in use by the account
+        let used = cache
+            .emails
+            .items
+            .iter()
+            .fold(0u128, |used, item| used | item.keywords);
+        let custom = cache
+            .emails
+            .keywords
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| used & (1 << (OTHER + idx)) != 0)
+            .map(|(_, keyword)| keyword.clone())
+            .collect();
+
+        // Sharees may only change private keywords on messages they administer
+        let private_allowed = (!config.mail_keywords_private.is_empty()
+            && access_token.is_shared(account_id))
+        .then(|| cache.shared_messages(access_token, Acl::Administer));
+
+        KeywordPolicy {
+            max_per_message: config.mail_keywords_max_per_message,
+            max_custom: config.mail_keywords_max_custom,
+            private: &config.mail_keywords_private,
+            custom,
+            private_allowed,
+        }
+    }
+
+    pub fn apply_update(
+        &mut self,
+        document_id: u32,
+        current: &ArchivedMessageData,
+        changes: &mut MessageData,
+    ) -> Result<(), KeywordLimit> {
+        // Revert changes to private keywords
+        if self
+            .private_allowed
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(document_id))
+        {
+            for keyword in self.private {
+                if current.keywords.iter().any(|k| k == keyword) {
+                    changes.add_keyword(keyword.clone());
+                } else {
+                    changes.remove_keyword(keyword);
+                }
+            }
+        }
+
+        // Existing keywords are never rejected, only new additions are checked
+        if changes.keywords.len() > self.max_per_message
+            && changes.keywords.len() > current.keywords.len()
+        {
+            return Err(KeywordLimit::PerMessage);
+        }
+
+        self.add_custom(changes.added_keywords(current))
+    }
+
+    pub fn apply_new(&mut self, keywords: &[Keyword]) -> Result<(), KeywordLimit> {
+        if keywords.len() > self.max_per_message {
+            Err(KeywordLimit::PerMessage)
+        } else {
+            self.add_custom(keywords.iter())
+        }
+    }
+
+    fn add_custom<'y>(
+        &mut self,
+        keywords: impl Iterator<Item = &'y Keyword>,
+    ) -> Result<(), KeywordLimit> {
+        let mut new_custom = Vec::new();
+        for keyword in keywords {
+            if let Keyword::Other(name) = keyword
+                && !self.custom.contains(name)
+                && !new_custom.contains(&name)
+            {
+                new_custom.push(name);
+            }
+        }
+
+        if self.custom.len() + new_custom.len() <= self.max_custom {
+            self.custom.extend(new_custom.into_iter().cloned());
+            Ok(())
+        } else {
+            Err(KeywordLimit::Custom)
+        }
+    }
+}
+
+impl KeywordLimit {
+    pub fn description(&self) -> &'static str {
+        match self {
+            KeywordLimit::PerMessage => "Too many keywords on message.",
+            KeywordLimit::Custom => "Too many custom keywords in account.",
+        }
+    }
+}
+
+pub trait KeywordCleanup: Sync + Send {
+    fn enforce_keyword_limits(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<usize>> + Send;
+}
+
+impl KeywordCleanup for Server {
+    async fn enforce_keyword_limits(&self, account_id: u32) -> trc::Result<usize> {
+        let cache = self
+            .get_cached_messages(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let max_per_message = self.core.jmap.mail_keywords_max_per_message;
+        let max_custom = self.core.jmap.mail_keywords_max_custom;
+
+        // Keep the most used custom keywords
+        let mut usage = vec![0usize; cache.emails.keywords.len()];
+        for item in &cache.emails.items {
+            for (idx, count) in usage.iter_mut().enumerate() {
+                if item.keywords & (1 << (OTHER + idx)) != 0 {
+                    *count += 1;
+                }
+            }
+        }
+        let mut usage = cache
+            .emails
+            .keywords
+            .iter()
+            .zip(usage)
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+        usage.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let allowed = usage
+            .into_iter()
+            .take(max_custom)
+            .map(|(name, _)| name.as_str())
+            .collect::<AHashSet<_>>();
+
+        let mut batch = BatchBuilder::new();
+        let mut changed = 0;
+        for document_id in cache.email_document_ids() {
+            let Some(data_) = self
+                .get_archive(account_id, Collection::Email, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let data = data_
+                .to_unarchived::<MessageData>()
+                .caused_by(trc::location!())?;
+            let mut new_data = data.deserialize().caused_by(trc::location!())?;
+
+            // Remove custom keywords over the account limit
+            new_data.keywords.retain(|keyword| match keyword {
+                Keyword::Other(name) => allowed.contains(name.as_str()),
+                _ => true,
+            });
+
+            // Remove the most recently added custom keywords over the message limit
+            while new_data.keywords.len() > max_per_message {
+                if let Some(pos) = new_data
+                    .keywords
+                    .iter()
+                    .rposition(|keyword| matches!(keyword, Keyword::Other(_)))
+                {
+                    new_data.keywords.remove(pos);
+                } else {
+                    break;
+                }
+            }
+
+            if new_data.has_keyword_changes(data.inner) {
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::Email)
+                    .update_document(document_id)
+                    .custom(
+                        ObjectIndexBuilder::new()
+                            .with_current(data)
+                            .with_changes(new_data),
+                    )
+                    .caused_by(trc::location!())?
+                    .commit_point();
+                changed += 1;
+
+                if batch.is_large_batch() {
+                    self.commit_batch(batch).await.caused_by(trc::location!())?;
+                    batch = BatchBuilder::new();
+                }
+            }
+        }
+
+        if !batch.is_empty() {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        Ok(changed)
+    }
+}
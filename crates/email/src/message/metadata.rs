@@ -490,6 +490,17 @@ impl ArchivedMessageMetadataPart {
             .and_then(|cd| cd.attribute("filename"))
             .or_else(|| self.content_type().and_then(|ct| ct.attribute("name")))
     }
+
+    pub fn is_calendar(&self) -> bool {
+        matches!(self.body, ArchivedMetadataPartType::Text)
+            && self.content_type().is_some_and(|ct| {
+                ct.c_type.as_ref().eq_ignore_ascii_case("text")
+                    && ct
+                        .c_subtype
+                        .as_ref()
+                        .is_some_and(|st| st.as_ref().eq_ignore_ascii_case("calendar"))
+            })
+    }
 }
 
 impl ArchivedMessageMetadataContents {
@@ -5,10 +5,16 @@
  */
 
 pub mod bayes;
+pub mod calendar;
+pub mod convert;
 pub mod copy;
 pub mod crypto;
 pub mod delete;
 pub mod delivery;
+pub mod dumpster;
 pub mod index;
 pub mod ingest;
+pub mod keywords;
 pub mod metadata;
+pub mod strip;
+pub mod tnef;
@@ -0,0 +1,259 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{
+    crypto::EncryptMessage,
+    delete::EmailDeletion,
+    ingest::{EmailIngest, IngestEmail, IngestSource, IngestedEmail},
+    metadata::{MessageData, MessageMetadata},
+};
+use common::{Server, auth::AccessToken};
+use mail_parser::{Message, MessageParser, MimeHeaders, PartType};
+use store::{roaring::RoaringBitmap, write::BatchBuilder};
+use trc::{AddContext, MessageIngestEvent, StoreEvent};
+use types::{blob_hash::BlobHash, collection::Collection, field::EmailField};
+
+pub struct StrippedEmail {
+    pub email: IngestedEmail,
+    pub stripped: usize,
+    pub archived: Option<BlobHash>,
+}
+
+pub enum StripAttachmentsError {
+    NotFound,
+    NothingToStrip,
+    OverQuota,
+}
+
+pub trait EmailStripAttachments: Sync + Send {
+    fn strip_attachments(
+        &self,
+        access_token: &AccessToken,
+        document_id: u32,
+        min_size: usize,
+        source: IngestSource<'static>,
+        session_id: u64,
+    ) -> impl Future<Output = trc::Result<Result<StrippedEmail, StripAttachmentsError>>> + Send;
+}
+
+impl EmailStripAttachments for Server {
+    async fn strip_attachments(
+        &self,
+        access_token: &AccessToken,
+        document_id: u32,
+        min_size: usize,
+        source: IngestSource<'static>,
+        session_id: u64,
+    ) -> trc::Result<Result<StrippedEmail, StripAttachmentsError>> {
+        // Obtain message data and metadata
+        let account_id = access_token.primary_id;
+        let Some(data_) = self
+            .get_archive(account_id, Collection::Email, document_id)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(Err(StripAttachmentsError::NotFound));
+        };
+        let data = data_
+            .deserialize::<MessageData>()
+            .caused_by(trc::location!())?;
+        let Some(metadata_) = self
+            .get_archive_by_property(
+                account_id,
+                Collection::Email,
+                document_id,
+                EmailField::Metadata.into(),
+            )
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(Err(StripAttachmentsError::NotFound));
+        };
+        let metadata = metadata_
+            .deserialize::<MessageMetadata>()
+            .caused_by(trc::location!())?;
+        let Some(raw_message) = self
+            .blob_store()
+            .get_blob(metadata.blob_hash.as_slice(), 0..usize::MAX)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(Err(StripAttachmentsError::NotFound));
+        };
+
+        // Encrypted messages are left untouched
+        let Some(message) = MessageParser::new()
+            .parse(&raw_message)
+            .filter(|message| !message.is_encrypted())
+        else {
+            return Ok(Err(StripAttachmentsError::NothingToStrip));
+        };
+
+        // Keep a copy of the original message in the archive store, if configured
+        let archived = if let Some(store_id) = &self.core.jmap.mail_archive_store {
+            let store = self.core.storage.blobs.get(store_id).ok_or_else(|| {
+                StoreEvent::NotConfigured
+                    .into_err()
+                    .details("Attachment archive store not found.")
+                    .ctx(trc::Key::Id, store_id.clone())
+                    .caused_by(trc::location!())
+            })?;
+            Some((store, &metadata.blob_hash))
+        } else {
+            None
+        };
+
+        let Some((stripped_message, stripped)) =
+            strip_message(&message, min_size, archived.as_ref().map(|(_, hash)| *hash))
+        else {
+            return Ok(Err(StripAttachmentsError::NothingToStrip));
+        };
+        if let Some((store, hash)) = &archived {
+            store
+                .put_blob(hash.as_slice(), &raw_message)
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        // Add the stripped copy before removing the original
+        let email = match self
+            .email_ingest(IngestEmail {
+                raw_message: &stripped_message,
+                message: MessageParser::new().parse(&stripped_message),
+                access_token,
+                mailbox_ids: data.mailboxes.iter().map(|m| m.mailbox_id).collect(),
+                keywords: data.keywords,
+                received_at: metadata.received_at.into(),
+                source,
+                spam_classify: false,
+                spam_train: false,
+                session_id,
+            })
+            .await
+        {
+            Ok(email) => email,
+            Err(err)
+                if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota))
+                    || err.matches(trc::EventType::Limit(trc::LimitEvent::TenantQuota)) =>
+            {
+                trc::error!(err.account_id(account_id).span_id(session_id));
+                return Ok(Err(StripAttachmentsError::OverQuota));
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut batch = BatchBuilder::new();
+        self.emails_tombstone(
+            account_id,
+            &mut batch,
+            RoaringBitmap::from_iter([document_id]),
+        )
+        .await
+        .caused_by(trc::location!())?;
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        trc::event!(
+            MessageIngest(MessageIngestEvent::AttachmentsStripped),
+            SpanId = session_id,
+            AccountId = account_id,
+            DocumentId = email.document_id,
+            Size = raw_message.len() - stripped_message.len(),
+            Total = stripped,
+            Details = archived
+                .as_ref()
+                .map_or(trc::Value::None, |(_, hash)| hash.to_hex().into()),
+        );
+
+        Ok(Ok(StrippedEmail {
+            email,
+            stripped,
+            archived: archived.map(|(_, hash)| hash.clone()),
+        }))
+    }
+}
+
+fn is_strippable(message: &Message<'_>, part_id: u32, min_size: usize) -> bool {
+    // The root part is never replaced, as it holds the message headers
+    part_id != 0
+        && message.parts.get(part_id as usize).is_some_and(|part| {
+            !matches!(part.body, PartType::Multipart(_))
+                && (part.offset_end - part.offset_header) as usize >= min_size
+        })
+}
+
+// Replaces each large attachment with a short text part describing what was removed,
+// leaving the remaining parts and the MIME structure intact
+fn strip_message(
+    message: &Message<'_>,
+    min_size: usize,
+    archived: Option<&BlobHash>,
+) -> Option<(Vec<u8>, usize)> {
+    let raw_message = message.raw_message();
+    let mut output = Vec::with_capacity(raw_message.len());
+    let mut last_offset = 0;
+    let mut stripped = 0;
+
+    for &part_id in &message.attachments {
+        if !is_strippable(message, part_id, min_size) {
+            continue;
+        }
+        let part = &message.parts[part_id as usize];
+        let name = part
+            .attachment_name()
+            .unwrap_or("unnamed")
+            .replace(['\r', '\n'], " ");
+        let content_type = part
+            .content_type()
+            .map(|ct| {
+                format!(
+                    "{}/{}",
+                    ct.c_type,
+                    ct.c_subtype.as_deref().unwrap_or("octet-stream")
+                )
+            })
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        output.extend_from_slice(&raw_message[last_offset..part.offset_header as usize]);
+        output.extend_from_slice(
+            concat!(
+                "Content-Type: text/plain; charset=\"utf-8\"\r\n",
+                "Content-Disposition: inline\r\n",
+                "Content-Transfer-Encoding: 8bit\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        output.extend_from_slice(
+            format!(
+                concat!(
+                    "The attachment \"{}\" ({}, {} bytes) was removed ",
+                    "from this message to reclaim storage space.\r\n"
+                ),
+                name,
+                content_type,
+                part.len()
+            )
+            .as_bytes(),
+        );
+        if let Some(hash) = archived {
+            output.extend_from_slice(
+                format!(
+                    "The original message was archived with reference {}.\r\n",
+                    hash.to_hex()
+                )
+                .as_bytes(),
+            );
+        }
+        last_offset = part.offset_end as usize;
+        stripped += 1;
+    }
+
+    if stripped > 0 {
+        output.extend_from_slice(&raw_message[last_offset..]);
+        Some((output, stripped))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,274 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_parser::{MessagePart, MimeHeaders};
+
+const TNEF_SIGNATURE: u32 = 0x223E9F78;
+
+const LVL_MESSAGE: u8 = 0x01;
+const LVL_ATTACHMENT: u8 = 0x02;
+
+const ATT_SUBJECT: u32 = 0x00018004;
+const ATT_BODY: u32 = 0x0002800C;
+const ATT_MAPI_PROPS: u32 = 0x00069003;
+const ATT_ATTACH_REND_DATA: u32 = 0x00069002;
+const ATT_ATTACH_TITLE: u32 = 0x00018010;
+const ATT_ATTACH_DATA: u32 = 0x0006800F;
+const ATT_ATTACHMENT: u32 = 0x00069005;
+
+const PR_BODY: u16 = 0x1000;
+const PR_BODY_HTML: u16 = 0x1013;
+const PR_ATTACH_LONG_FILENAME: u16 = 0x3707;
+const PR_ATTACH_MIME_TAG: u16 = 0x370E;
+
+const PT_STRING8: u16 = 0x001E;
+const PT_UNICODE: u16 = 0x001F;
+const PT_BINARY: u16 = 0x0102;
+const PT_OBJECT: u16 = 0x000D;
+const MV_FLAG: u16 = 0x1000;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TnefMessage {
+    pub subject: Option<String>,
+    pub text_body: Option<String>,
+    pub html_body: Option<String>,
+    pub attachments: Vec<TnefAttachment>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TnefAttachment {
+    pub name: Option<String>,
+    pub content_type: Option<String>,
+    pub contents: Vec<u8>,
+}
+
+pub fn is_tnef_part(part: &MessagePart<'_>) -> bool {
+    part.content_type().is_some_and(|ct| {
+        ct.ctype().eq_ignore_ascii_case("application")
+            && ct.subtype().is_some_and(|st| {
+                st.eq_ignore_ascii_case("ms-tnef") || st.eq_ignore_ascii_case("vnd.ms-tnef")
+            })
+    }) || part
+        .attachment_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case("winmail.dat"))
+}
+
+impl TnefMessage {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(bytes);
+        if reader.u32()? != TNEF_SIGNATURE {
+            return None;
+        }
+        reader.skip(2)?;
+
+        let mut message = TnefMessage::default();
+        while !reader.is_eof() {
+            let level = reader.u8()?;
+            let id = reader.u32()?;
+            let len = reader.u32()? as usize;
+            let data = reader.bytes(len)?;
+            reader.skip(2)?;
+
+            match (level, id) {
+                (LVL_MESSAGE, ATT_SUBJECT) => {
+                    message.subject = decode_string8(data);
+                }
+                (LVL_MESSAGE, ATT_BODY) => {
+                    message.text_body = decode_string8(data);
+                }
+                (LVL_MESSAGE, ATT_MAPI_PROPS) => {
+                    for (prop_id, value) in parse_mapi_props(data).unwrap_or_default() {
+                        match prop_id {
+                            PR_BODY if message.text_body.is_none() => {
+                                message.text_body = value.into_string();
+                            }
+                            PR_BODY_HTML => {
+                                message.html_body = value.into_string();
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                (LVL_ATTACHMENT, ATT_ATTACH_REND_DATA) => {
+                    message.attachments.push(TnefAttachment::default());
+                }
+                (LVL_ATTACHMENT, ATT_ATTACH_TITLE) => {
+                    if let Some(attachment) = message.attachments.last_mut()
+                        && attachment.name.is_none()
+                    {
+                        attachment.name = decode_string8(data);
+                    }
+                }
+                (LVL_ATTACHMENT, ATT_ATTACH_DATA) => {
+                    if let Some(attachment) = message.attachments.last_mut() {
+                        attachment.contents = data.to_vec();
+                    }
+                }
+                (LVL_ATTACHMENT, ATT_ATTACHMENT) => {
+                    if let Some(attachment) = message.attachments.last_mut() {
+                        for (prop_id, value) in parse_mapi_props(data).unwrap_or_default() {
+                            match prop_id {
+                                PR_ATTACH_LONG_FILENAME => {
+                                    if let Some(name) = value.into_string() {
+                                        attachment.name = Some(name);
+                                    }
+                                }
+                                PR_ATTACH_MIME_TAG => {
+                                    attachment.content_type = value.into_string();
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Attachments without contents are usually embedded OLE objects
+        message
+            .attachments
+            .retain(|attachment| !attachment.contents.is_empty());
+
+        Some(message)
+    }
+}
+
+enum MapiValue<'x> {
+    String8(&'x [u8]),
+    Unicode(&'x [u8]),
+    Binary(&'x [u8]),
+    Other,
+}
+
+impl MapiValue<'_> {
+    fn into_string(self) -> Option<String> {
+        match self {
+            MapiValue::String8(bytes) | MapiValue::Binary(bytes) => decode_string8(bytes),
+            MapiValue::Unicode(bytes) => {
+                let text = char::decode_utf16(
+                    bytes
+                        .chunks_exact(2)
+                        .map(|ch| u16::from_le_bytes([ch[0], ch[1]])),
+                )
+                .map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .take_while(|ch| *ch != '\0')
+                .collect::<String>();
+                Some(text).filter(|text| !text.is_empty())
+            }
+            MapiValue::Other => None,
+        }
+    }
+}
+
+fn parse_mapi_props(bytes: &[u8]) -> Option<Vec<(u16, MapiValue<'_>)>> {
+    let mut reader = Reader::new(bytes);
+    let count = reader.u32()?;
+    let mut props = Vec::new();
+
+    for _ in 0..count {
+        let prop_type = reader.u16()?;
+        let prop_id = reader.u16()?;
+
+        // Named properties are prefixed by their GUID and name
+        if prop_id >= 0x8000 {
+            reader.skip(16)?;
+            if reader.u32()? == 0 {
+                reader.skip(4)?;
+            } else {
+                let len = reader.u32()? as usize;
+                reader.skip(padded(len))?;
+            }
+        }
+
+        let base_type = prop_type & !MV_FLAG;
+        let is_variable = matches!(base_type, PT_STRING8 | PT_UNICODE | PT_BINARY | PT_OBJECT);
+        let num_values = if prop_type & MV_FLAG != 0 || is_variable {
+            reader.u32()?
+        } else {
+            1
+        };
+
+        for _ in 0..num_values {
+            if is_variable {
+                let len = reader.u32()? as usize;
+                let data = reader.bytes(len)?;
+                reader.skip(padded(len) - len)?;
+                props.push((
+                    prop_id,
+                    match base_type {
+                        PT_STRING8 => MapiValue::String8(data),
+                        PT_UNICODE => MapiValue::Unicode(data),
+                        PT_BINARY => MapiValue::Binary(data),
+                        _ => MapiValue::Other,
+                    },
+                ));
+            } else {
+                reader.skip(match base_type {
+                    0x0005 | 0x0006 | 0x0007 | 0x0014 | 0x0040 => 8,
+                    0x0048 => 16,
+                    _ => 4,
+                })?;
+            }
+        }
+    }
+
+    Some(props)
+}
+
+// TNEF strings are null terminated and encoded in the sender's code page,
+// fall back to Latin-1 when they are not valid UTF-8
+fn decode_string8(bytes: &[u8]) -> Option<String> {
+    let bytes = bytes.split(|ch| *ch == 0).next().unwrap_or_default();
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|ch| *ch as char).collect(),
+    };
+    Some(text).filter(|text| !text.trim().is_empty())
+}
+
+fn padded(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+struct Reader<'x> {
+    bytes: &'x [u8],
+    pos: usize,
+}
+
+impl<'x> Reader<'x> {
+    fn new(bytes: &'x [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'x [u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2)
+            .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
@@ -0,0 +1,193 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use std::future::Future;
+use store::{
+    Serialize,
+    write::{AlignedBytes, Archive, Archiver, BatchBuilder},
+};
+use trc::AddContext;
+use types::{
+    collection::Collection,
+    field::PrincipalField,
+    type_state::{DataType, StateChange},
+};
+
+// Per-account client settings, grouped by namespace (e.g. "webmail").
+// Values are stored as serialized JSON objects so they can roam between
+// clients without the server having to understand them.
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+pub struct AccountSettings {
+    pub namespaces: Vec<SettingsNamespace>,
+    pub change_id: u64,
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SettingsNamespace {
+    pub name: String,
+    pub value: String,
+}
+
+impl AccountSettings {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.namespaces
+            .iter()
+            .find(|ns| ns.name == name)
+            .map(|ns| ns.value.as_str())
+    }
+
+    pub fn set(&mut self, name: &str, value: String) {
+        if let Some(ns) = self.namespaces.iter_mut().find(|ns| ns.name == name) {
+            ns.value = value;
+        } else {
+            self.namespaces.push(SettingsNamespace {
+                name: name.to_string(),
+                value,
+            });
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.namespaces.len();
+        self.namespaces.retain(|ns| ns.name != name);
+        len != self.namespaces.len()
+    }
+
+    pub fn size(&self) -> usize {
+        self.namespaces
+            .iter()
+            .map(|ns| ns.name.len() + ns.value.len())
+            .sum()
+    }
+
+    // Sets or removes (None) string values inside a namespace, returning
+    // the number of keys left in it.
+    pub fn update_entries(
+        &mut self,
+        name: &str,
+        changes: impl IntoIterator<Item = (String, Option<String>)>,
+    ) -> usize {
+        let mut entries = self
+            .get(name)
+            .and_then(|value| {
+                serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(value).ok()
+            })
+            .unwrap_or_default();
+        for (key, value) in changes {
+            if let Some(value) = value {
+                entries.insert(key, serde_json::Value::String(value));
+            } else {
+                entries.remove(&key);
+            }
+        }
+
+        let num_entries = entries.len();
+        if entries.is_empty() {
+            self.remove(name);
+        } else if let Ok(entries) = serde_json::to_string(&entries) {
+            self.set(name, entries);
+        }
+        num_entries
+    }
+}
+
+impl ArchivedAccountSettings {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.namespaces
+            .iter()
+            .find(|ns| ns.name.as_str() == name)
+            .map(|ns| ns.value.as_str())
+    }
+
+    pub fn string_entries(&self, name: &str) -> Vec<(String, String)> {
+        self.get(name)
+            .and_then(|value| {
+                serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(value).ok()
+            })
+            .map(|entries| {
+                entries
+                    .into_iter()
+                    .filter_map(|(key, value)| match value {
+                        serde_json::Value::String(value) => Some((key, value)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub trait SettingsStore: Sync + Send {
+    fn get_account_settings(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<Option<Archive<AlignedBytes>>>> + Send;
+
+    fn write_account_settings(
+        &self,
+        account_id: u32,
+        current_settings: Option<&Archive<AlignedBytes>>,
+        settings: AccountSettings,
+    ) -> impl Future<Output = trc::Result<u64>> + Send;
+}
+
+impl SettingsStore for Server {
+    async fn get_account_settings(
+        &self,
+        account_id: u32,
+    ) -> trc::Result<Option<Archive<AlignedBytes>>> {
+        self.get_archive_by_property(
+            account_id,
+            Collection::Principal,
+            0,
+            PrincipalField::Settings.into(),
+        )
+        .await
+        .caused_by(trc::location!())
+    }
+
+    async fn write_account_settings(
+        &self,
+        account_id: u32,
+        current_settings: Option<&Archive<AlignedBytes>>,
+        mut settings: AccountSettings,
+    ) -> trc::Result<u64> {
+        // Bump the change id so JMAP and IMAP clients can detect modifications
+        settings.change_id += 1;
+        let change_id = settings.change_id;
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Principal)
+            .update_document(0);
+        if let Some(current_settings) = current_settings {
+            batch.assert_value(PrincipalField::Settings, current_settings);
+        } else {
+            batch.assert_value(PrincipalField::Settings, ());
+        }
+        batch.set(
+            PrincipalField::Settings,
+            Archiver::new(settings)
+                .serialize()
+                .caused_by(trc::location!())?,
+        );
+        self.store()
+            .write(batch.build_all())
+            .await
+            .caused_by(trc::location!())?;
+
+        // Notify clients on other sessions
+        self.broadcast_state_change(
+            StateChange::new(account_id, change_id).with_change(DataType::Settings),
+        )
+        .await;
+
+        Ok(change_id)
+    }
+}
@@ -0,0 +1,147 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::SeenIdHash;
+use common::Server;
+use serde::Serialize;
+use std::future::Future;
+use store::{
+    dispatch::lookup::KeyValue,
+    write::{key::DeserializeBigEndian, now},
+};
+use trc::AddContext;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateIdList {
+    pub total: usize,
+    #[serde(rename = "maxIds")]
+    pub max_ids: Option<usize>,
+    pub items: Vec<DuplicateIdItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateIdItem {
+    pub id: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: u64,
+}
+
+pub trait SieveDuplicateTracking: Sync + Send {
+    fn sieve_duplicate_exists(
+        &self,
+        id_hash: &SeenIdHash,
+    ) -> impl Future<Output = trc::Result<bool>> + Send;
+
+    fn sieve_duplicate_track(
+        &self,
+        id_hash: &SeenIdHash,
+        expiry: u64,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn sieve_duplicate_enforce_budget(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn sieve_duplicate_list(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<DuplicateIdList>> + Send;
+
+    fn sieve_duplicate_purge(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl SieveDuplicateTracking for Server {
+    async fn sieve_duplicate_exists(&self, id_hash: &SeenIdHash) -> trc::Result<bool> {
+        self.in_memory_store()
+            .key_get::<()>(id_hash.key())
+            .await
+            .caused_by(trc::location!())
+            .map(|result| result.is_some())
+    }
+
+    async fn sieve_duplicate_track(&self, id_hash: &SeenIdHash, expiry: u64) -> trc::Result<()> {
+        // Clamp the tracking window requested by the script
+        let expiry = self
+            .core
+            .jmap
+            .sieve_duplicate_max_expiry
+            .map_or(expiry, |max_expiry| expiry.min(max_expiry));
+
+        self.in_memory_store()
+            .key_set(
+                KeyValue::new(id_hash.key(), (now() + expiry).to_be_bytes().to_vec())
+                    .expires(expiry),
+            )
+            .await
+            .caused_by(trc::location!())
+    }
+
+    async fn sieve_duplicate_enforce_budget(&self, account_id: u32) -> trc::Result<()> {
+        let Some(max_ids) = self.core.jmap.sieve_duplicate_max_ids else {
+            return Ok(());
+        };
+
+        let mut entries = self
+            .in_memory_store()
+            .key_get_prefix(&SeenIdHash::account_prefix(account_id))
+            .await
+            .caused_by(trc::location!())?;
+        if entries.len() <= max_ids {
+            return Ok(());
+        }
+
+        // Evict the entries closest to expiring
+        entries
+            .sort_unstable_by_key(|(_, value)| value.as_slice().deserialize_be_u64(0).unwrap_or(0));
+        let evict = entries.len() - max_ids;
+        for (key, _) in entries.into_iter().take(evict) {
+            self.in_memory_store()
+                .key_delete(key)
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+
+    async fn sieve_duplicate_list(&self, account_id: u32) -> trc::Result<DuplicateIdList> {
+        let prefix = SeenIdHash::account_prefix(account_id);
+        let mut items = self
+            .in_memory_store()
+            .key_get_prefix(&prefix)
+            .await
+            .caused_by(trc::location!())?
+            .into_iter()
+            .map(|(key, value)| DuplicateIdItem {
+                id: key
+                    .get(prefix.len()..)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|byte| format!("{byte:02x}"))
+                    .collect(),
+                expires_at: value.as_slice().deserialize_be_u64(0).unwrap_or(0),
+            })
+            .collect::<Vec<_>>();
+        items.sort_unstable_by(|a, b| b.expires_at.cmp(&a.expires_at));
+
+        Ok(DuplicateIdList {
+            total: items.len(),
+            max_ids: self.core.jmap.sieve_duplicate_max_ids,
+            items,
+        })
+    }
+
+    async fn sieve_duplicate_purge(&self, account_id: u32) -> trc::Result<()> {
+        self.in_memory_store()
+            .key_delete_prefix(&SeenIdHash::account_prefix(account_id))
+            .await
+            .caused_by(trc::location!())
+    }
+}
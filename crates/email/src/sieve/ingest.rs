@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::{ActiveScript, SeenIdHash, SieveScript};
+use super::{ActiveScript, SeenIdHash, SieveScript, duplicate::SieveDuplicateTracking};
 use crate::{
     cache::{MessageCacheFetch, mailbox::MailboxCacheAccess},
     mailbox::{INBOX_ID, TRASH_ID, manage::MailboxFnc},
@@ -13,7 +13,11 @@ use crate::{
         ingest::{EmailIngest, IngestEmail, IngestSource, IngestedEmail},
     },
 };
-use common::{Server, auth::AccessToken, scripts::plugins::PluginContext};
+use common::{
+    Server,
+    auth::AccessToken,
+    scripts::{auth_results::AuthResults, plugins::PluginContext},
+};
 use directory::{Permission, QueryParams};
 use mail_parser::MessageParser;
 use sieve::{Envelope, Event, Input, Mailbox, Recipient, Sieve};
@@ -22,7 +26,6 @@ use std::{future::Future, str::FromStr};
 use store::{
     Deserialize, Serialize, SerializeInfallible,
     ahash::AHashMap,
-    dispatch::lookup::KeyValue,
     query::Filter,
     write::{AlignedBytes, Archive, ArchiveVersion, Archiver, BatchBuilder, BlobOp},
 };
@@ -102,8 +105,17 @@ impl SieveScriptIngest for Server {
             .await
             .caused_by(trc::location!())?;
 
+        // Expose the authentication results added by this server
+        let auth_results =
+            AuthResults::from_message(&message, &self.core.network.server_name).unwrap_or_default();
+
         // Create Sieve instance
-        let mut instance = self.core.sieve.untrusted_runtime.filter_parsed(message);
+        let mut instance = self
+            .core
+            .sieve
+            .untrusted_runtime
+            .filter_parsed(message)
+            .with_vars_env(auth_results.sieve_variables());
 
         // Set account name and email
         let mail_from = self
@@ -150,6 +162,7 @@ impl SieveScriptIngest for Server {
             imap_uids: Vec::new(),
         };
         let mut checked_ids: AHashMap<SeenIdHash, bool> = AHashMap::new();
+        let mut tracked_ids = false;
 
         while let Some(event) = instance.run(input) {
             match event {
@@ -247,24 +260,26 @@ impl SieveScriptIngest for Server {
                     Event::DuplicateId { id, expiry, last } => {
                         let id_hash = SeenIdHash::new(
                             account_id,
-                            active_script.version.hash().unwrap_or_default(),
+                            if self.core.jmap.sieve_duplicate_account_wide {
+                                0
+                            } else {
+                                active_script.version.hash().unwrap_or_default()
+                            },
                             &id,
                         );
                         if let Some(result) = checked_ids.get(&id_hash) {
                             input = (*result).into();
                         } else {
                             let exists = self
-                                .in_memory_store()
-                                .key_get::<()>(id_hash.key())
+                                .sieve_duplicate_exists(&id_hash)
                                 .await
-                                .caused_by(trc::location!())?
-                                .is_some();
+                                .caused_by(trc::location!())?;
 
                             if !exists || last {
-                                self.in_memory_store()
-                                    .key_set(KeyValue::new(id_hash.key(), vec![]).expires(expiry))
+                                self.sieve_duplicate_track(&id_hash, expiry)
                                     .await
                                     .caused_by(trc::location!())?;
+                                tracked_ids = true;
                             }
 
                             checked_ids.insert(id_hash, exists);
@@ -399,10 +414,23 @@ impl SieveScriptIngest for Server {
                                     SpanId = session_id
                                 );
 
+                                // Redirected messages carry the address they were delivered
+                                // to, which is used to detect forwarding loops
+                                let raw_message = if message_id == 0
+                                    && self.core.smtp.session.data.add_delivered_to
+                                {
+                                    let mut raw_message =
+                                        format!("Delivered-To: {envelope_to}\r\n").into_bytes();
+                                    raw_message.extend_from_slice(&message.raw_message);
+                                    raw_message
+                                } else {
+                                    message.raw_message.to_vec()
+                                };
+
                                 autogenerated.push(AutogeneratedMessage {
                                     sender_address: mail_from.clone(),
                                     recipients,
-                                    message: message.raw_message.to_vec(),
+                                    message: raw_message,
                                 });
                             } else {
                                 trc::event!(
@@ -478,6 +506,13 @@ impl SieveScriptIngest for Server {
             }
         }
 
+        // Enforce the duplicate tracking budget
+        if tracked_ids {
+            self.sieve_duplicate_enforce_budget(account_id)
+                .await
+                .caused_by(trc::location!())?;
+        }
+
         // Fail-safe, no discard and no keep seen, assume that something went wrong and file anyway.
         if !do_deliver && !do_discard {
             messages[0].file_into.push(INBOX_ID);
@@ -12,6 +12,7 @@ use types::blob_hash::BlobHash;
 
 pub mod activate;
 pub mod delete;
+pub mod duplicate;
 pub mod index;
 pub mod ingest;
 
@@ -89,8 +90,10 @@ impl SieveScript {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
-#[repr(transparent)]
-pub struct SeenIdHash(pub [u8; 32]);
+pub struct SeenIdHash {
+    pub account_id: u32,
+    pub hash: [u8; 32],
+}
 
 impl SeenIdHash {
     pub fn new(account_id: u32, hash: u32, id: &str) -> Self {
@@ -98,19 +101,28 @@ impl SeenIdHash {
         hasher.update(&account_id.to_be_bytes());
         hasher.update(&hash.to_be_bytes());
         hasher.update(id.as_bytes());
-        SeenIdHash(hasher.finalize().into())
+        SeenIdHash {
+            account_id,
+            hash: hasher.finalize().into(),
+        }
     }
 
     pub fn key(&self) -> Vec<u8> {
-        let mut result = Vec::with_capacity(self.0.len() + 1);
+        let mut result = Self::account_prefix(self.account_id);
+        result.extend_from_slice(&self.hash);
+        result
+    }
+
+    pub fn account_prefix(account_id: u32) -> Vec<u8> {
+        let mut result = Vec::with_capacity(32 + std::mem::size_of::<u32>() + 1);
         result.push(KV_SIEVE_ID);
-        result.extend_from_slice(&self.0);
+        result.extend_from_slice(&account_id.to_be_bytes());
         result
     }
 }
 
 impl AsRef<[u8]> for SeenIdHash {
     fn as_ref(&self) -> &[u8] {
-        &self.0
+        &self.hash
     }
 }
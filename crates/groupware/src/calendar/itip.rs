@@ -26,7 +26,7 @@ use calcard::{
 use common::{
     DavName, Server,
     auth::{AccessToken, ResourceToken, oauth::GrantType},
-    config::groupware::CalendarTemplateVariable,
+    config::groupware::{CalendarTemplateVariable, ItipAutoAdd},
     i18n,
 };
 use store::{
@@ -36,7 +36,8 @@ use store::{
 };
 use trc::AddContext;
 use types::{
-    collection::Collection,
+    acl::Acl,
+    collection::{Collection, SyncCollection},
     field::{CalendarField, ContactField},
 };
 use utils::{template::Variables, url_params::UrlParams};
@@ -132,11 +133,17 @@ impl ItipIngest for Server {
         }
 
         let itip_snapshots = itip_snapshot(&itip, access_token.emails.as_slice(), false)?;
-        if !itip_snapshots.sender_is_organizer_or_attendee(sender) {
+        let sender = if itip_snapshots.sender_is_organizer_or_attendee(sender) {
+            sender
+        } else if let Some(principal) = itip_snapshots.sent_by_principal(sender) {
+            // Message sent by a delegate on behalf of a participant (SENT-BY)
+            principal
+        } else {
             return Err(ItipIngestError::Message(
                 ItipError::SenderIsNotOrganizerNorAttendee,
             ));
-        }
+        };
+        let sender = sender.to_string();
 
         // Find event by UID
         let account_id = access_token.primary_id;
@@ -178,7 +185,7 @@ impl ItipIngest for Server {
                     snapshots,
                     &itip,
                     itip_snapshots,
-                    sender.to_string(),
+                    sender,
                 )? {
                     MergeResult::Actions(changes) => {
                         // Merge changes
@@ -220,6 +227,10 @@ impl ItipIngest for Server {
                             }
                         }
 
+                        // Forward a copy to the calendar delegates
+                        itip_forward_to_delegates(self, access_token, &itip, itip_message.len())
+                            .await?;
+
                         // Build event for schedule inbox
                         let itip_document_id = self
                             .store()
@@ -260,37 +271,66 @@ impl ItipIngest for Server {
                 Err(ItipIngestError::Message(ItipError::EventNotFound))
             }
         } else {
+            if itip_method(&itip)? != &ICalendarMethod::Request {
+                return Err(ItipIngestError::Message(ItipError::EventNotFound));
+            }
+
+            // Validate quota
+            if self
+                .has_available_quota(resource_token, itip_message.len() as u64)
+                .await
+                .is_err()
+            {
+                return Err(ItipIngestError::Message(ItipError::QuotaExceeded));
+            }
+
             // Verify that auto-adding invitations is allowed
-            if !self.core.groupware.itip_auto_add
-                && self
+            let auto_add = match access_token
+                .itip_auto_add
+                .unwrap_or(self.core.groupware.itip_auto_add)
+            {
+                ItipAutoAdd::Always => true,
+                ItipAutoAdd::KnownSenders => !self
                     .store()
                     .filter(
                         account_id,
                         Collection::ContactCard,
-                        vec![Filter::eq(ContactField::Email, sender.as_bytes().to_vec())],
+                        vec![Filter::eq(ContactField::Email, sender.into_bytes())],
                     )
                     .await
                     .caused_by(trc::location!())?
                     .results
-                    .is_empty()
-            {
-                return Err(ItipIngestError::Message(ItipError::AutoAddDisabled));
-            } else if itip_method(&itip)? != &ICalendarMethod::Request {
-                return Err(ItipIngestError::Message(ItipError::EventNotFound));
+                    .is_empty(),
+                ItipAutoAdd::Never => false,
+            };
+            // Forward a copy to the calendar delegates
+            itip_forward_to_delegates(self, access_token, &itip, itip_message.len()).await?;
+
+            if !auto_add {
+                // Leave the invitation in the scheduling inbox for the user to process
+                let itip_document_id = self
+                    .store()
+                    .assign_document_ids(account_id, Collection::CalendarScheduling, 1)
+                    .await
+                    .caused_by(trc::location!())?;
+                let mut batch = BatchBuilder::new();
+                CalendarScheduling {
+                    itip,
+                    event_id: None,
+                    size: itip_message.len() as u32,
+                    ..Default::default()
+                }
+                .insert(access_token, account_id, itip_document_id, &mut batch)
+                .caused_by(trc::location!())?;
+                self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+                return Ok(None);
             }
 
-            // Import the iTIP message
+            // Import the iTIP message as a tentative event
             let mut ical = itip.clone();
             itip_import_message(&mut ical)?;
-
-            // Validate quota
-            if self
-                .has_available_quota(resource_token, itip_message.len() as u64)
-                .await
-                .is_err()
-            {
-                return Err(ItipIngestError::Message(ItipError::QuotaExceeded));
-            }
+            itip_mark_tentative(&mut ical, access_token.emails.as_slice());
 
             // Obtain parent calendar
             let Some(parent_id) = self
@@ -505,6 +545,57 @@ struct RsvpResponse {
     partstat: ICalendarParticipationStatus,
 }
 
+async fn itip_forward_to_delegates(
+    server: &Server,
+    access_token: &AccessToken,
+    itip: &ICalendar,
+    size: usize,
+) -> trc::Result<()> {
+    // Replies are forwarded to delegates holding the reply grant, everything else
+    // to delegates holding the invite grant on any of the owner's calendars
+    let check_acl = if itip_method(itip).is_ok_and(|method| method == &ICalendarMethod::Reply) {
+        Acl::SchedulingReply
+    } else {
+        Acl::SchedulingInvite
+    };
+    let account_id = access_token.primary_id;
+    let delegate_ids = server
+        .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+        .await
+        .caused_by(trc::location!())?
+        .container_grantees(check_acl);
+
+    for delegate_id in delegate_ids {
+        if delegate_id == account_id {
+            continue;
+        }
+        let delegate_token = server
+            .get_access_token(delegate_id)
+            .await
+            .caused_by(trc::location!())?;
+        let itip_document_id = server
+            .store()
+            .assign_document_ids(delegate_id, Collection::CalendarScheduling, 1)
+            .await
+            .caused_by(trc::location!())?;
+        let mut batch = BatchBuilder::new();
+        CalendarScheduling {
+            itip: itip.clone(),
+            event_id: None,
+            size: size as u32,
+            ..Default::default()
+        }
+        .insert(&delegate_token, delegate_id, itip_document_id, &mut batch)
+        .caused_by(trc::location!())?;
+        server
+            .commit_batch(batch)
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
+}
+
 async fn decode_rsvp_response(server: &Server, query: &str) -> Option<RsvpResponse> {
     let params = UrlParams::new(query.into());
     let token = params.get("i")?;
@@ -656,3 +747,41 @@ impl From<trc::Error> for ItipIngestError {
         ItipIngestError::Internal(err)
     }
 }
+
+fn itip_mark_tentative(ical: &mut ICalendar, emails: &[String]) {
+    for comp in &mut ical.components {
+        if comp.component_type.is_scheduling_object() {
+            for entry in &mut comp.entries {
+                if entry.name == ICalendarProperty::Attendee
+                    && entry
+                        .values
+                        .first()
+                        .and_then(|v| v.as_text())
+                        .is_some_and(|v| {
+                            let v = v.strip_prefix("mailto:").unwrap_or(v);
+                            emails.iter().any(|email| email.eq_ignore_ascii_case(v))
+                        })
+                {
+                    match entry.params.iter_mut().find_map(|param| {
+                        if let ICalendarParameter::Partstat(partstat) = param {
+                            Some(partstat)
+                        } else {
+                            None
+                        }
+                    }) {
+                        Some(partstat) => {
+                            if *partstat == ICalendarParticipationStatus::NeedsAction {
+                                *partstat = ICalendarParticipationStatus::Tentative;
+                            }
+                        }
+                        None => {
+                            entry.params.push(ICalendarParameter::Partstat(
+                                ICalendarParticipationStatus::Tentative,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
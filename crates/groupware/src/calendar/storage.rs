@@ -11,6 +11,7 @@ use super::{
 use crate::{
     DavResourceName, DestroyArchive, RFC_3986,
     calendar::{ArchivedCalendarScheduling, CalendarScheduling},
+    fts::write_fts_task,
     scheduling::{ItipMessages, event_cancel::itip_cancel},
 };
 use calcard::common::timezone::Tz;
@@ -139,7 +140,10 @@ impl CalendarEvent {
                     .with_changes(new_event)
                     .with_tenant_id(access_token),
             )
-            .map(|b| b.commit_point())
+            .map(|batch| {
+                write_fts_task(batch, Collection::CalendarEvent, true);
+                batch.commit_point()
+            })
     }
 
     pub fn insert<'x>(
@@ -170,6 +174,7 @@ impl CalendarEvent {
                 if let Some(next_alarm) = next_alarm {
                     next_alarm.write_task(batch);
                 }
+                write_fts_task(batch, Collection::CalendarEvent, false);
 
                 batch.commit_point()
             })
@@ -376,6 +381,7 @@ impl DestroyArchive<Archive<&ArchivedCalendarEvent>> {
             } else {
                 // Delete event
                 batch.delete_document(document_id);
+                write_fts_task(batch, Collection::CalendarEvent, true);
 
                 // Remove next alarm if it exists
                 let now = now() as i64;
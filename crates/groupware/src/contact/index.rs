@@ -90,6 +90,9 @@ impl IndexableObject for ContactCard {
                 sync_collection: SyncCollection::AddressBook,
                 prefix: None,
             },
+            IndexValue::BlobList {
+                value: self.photo_hashes(),
+            },
         ]
         .into_iter()
     }
@@ -121,6 +124,9 @@ impl IndexableObject for &ArchivedContactCard {
                 sync_collection: SyncCollection::AddressBook,
                 prefix: None,
             },
+            IndexValue::BlobList {
+                value: self.photo_hashes(),
+            },
         ]
         .into_iter()
     }
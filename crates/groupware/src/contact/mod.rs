@@ -5,6 +5,8 @@
  */
 
 pub mod index;
+pub mod normalize;
+pub mod photo;
 pub mod storage;
 
 use calcard::vcard::VCard;
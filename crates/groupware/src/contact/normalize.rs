@@ -0,0 +1,280 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::vcard::{VCard, VCardProperty, VCardValue};
+use common::config::groupware::ContactNormalizeConfig;
+use image::{
+    DynamicImage, ImageReader, Limits, codecs::jpeg::JpegEncoder, error::ImageError,
+    imageops::FilterType,
+};
+use std::io::Cursor;
+
+// Photos larger than this multiple of the configured dimension are rejected
+// instead of being decoded and downscaled.
+const MAX_SOURCE_SCALE: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeError {
+    InvalidPhoto,
+    PhotoTooLarge,
+}
+
+pub trait ContactNormalize {
+    fn normalize(&mut self, config: &ContactNormalizeConfig) -> Result<bool, NormalizeError>;
+}
+
+impl ContactNormalize for VCard {
+    // Returns true when the card was modified
+    fn normalize(&mut self, config: &ContactNormalizeConfig) -> Result<bool, NormalizeError> {
+        let mut is_modified = false;
+
+        for entry in &mut self.entries {
+            match entry.name {
+                VCardProperty::Tel if config.normalize_phones => {
+                    for value in &mut entry.values {
+                        if let VCardValue::Text(text) = value
+                            && let Some(phone) =
+                                normalize_phone(text, config.phone_country_code.as_deref())
+                            && phone != *text
+                        {
+                            *text = phone;
+                            is_modified = true;
+                        }
+                    }
+                }
+                VCardProperty::Photo | VCardProperty::Logo => {
+                    for value in &mut entry.values {
+                        if let VCardValue::Binary(data) = value
+                            && let Some(photo) = normalize_photo(&data.data, config)?
+                        {
+                            data.data = photo;
+                            data.content_type = Some("image/jpeg".to_string());
+                            is_modified = true;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(is_modified)
+    }
+}
+
+// Formats phone numbers as E.164 when possible, keeping the "tel:" prefix
+// and any extension. Numbers that cannot be safely interpreted are left as-is.
+pub fn normalize_phone(value: &str, country_code: Option<&str>) -> Option<String> {
+    let (prefix, number) = if let Some(number) = value.strip_prefix("tel:") {
+        ("tel:", number)
+    } else {
+        ("", value)
+    };
+    let (number, extension) = match number.find([';', 'x', 'X']) {
+        Some(pos) => number.split_at(pos),
+        None => (number, ""),
+    };
+
+    let mut digits = String::with_capacity(number.len());
+    for (pos, ch) in number.trim().char_indices() {
+        match ch {
+            '0'..='9' => digits.push(ch),
+            '+' if pos == 0 => digits.push(ch),
+            ' ' | '-' | '.' | '(' | ')' | '/' | '\u{a0}' => {}
+            _ => return None,
+        }
+    }
+
+    let number = if let Some(number) = digits.strip_prefix("00") {
+        format!("+{number}")
+    } else if digits.starts_with('+') {
+        digits
+    } else if let (Some(country_code), Some(number)) = (country_code, digits.strip_prefix('0')) {
+        format!("{country_code}{number}")
+    } else {
+        return None;
+    };
+
+    // E.164 numbers have at most 15 digits
+    if (8..=16).contains(&number.len()) {
+        let extension = extension.trim();
+        let mut result = String::with_capacity(prefix.len() + number.len() + extension.len());
+        result.push_str(prefix);
+        result.push_str(&number);
+        if let Some(ext) = extension
+            .strip_prefix(['x', 'X'])
+            .map(|ext| ext.trim_start_matches(['.', ' ']))
+        {
+            result.push_str(";ext=");
+            result.push_str(ext);
+        } else {
+            result.push_str(extension);
+        }
+        Some(result)
+    } else {
+        None
+    }
+}
+
+fn normalize_photo(
+    data: &[u8],
+    config: &ContactNormalizeConfig,
+) -> Result<Option<Vec<u8>>, NormalizeError> {
+    let max_dimension = config.photo_max_dimension;
+    let is_oversized = match imagesize::blob_size(data) {
+        Ok(size) => size.width > max_dimension as usize || size.height > max_dimension as usize,
+        Err(_) if data.len() <= config.photo_max_size => return Ok(None),
+        Err(_) => return Err(NormalizeError::InvalidPhoto),
+    };
+    if !is_oversized && data.len() <= config.photo_max_size {
+        return Ok(None);
+    }
+
+    // Decode within limits derived from the configured dimension, so that
+    // the header cannot trigger allocations beyond the downscaling budget
+    let max_source = max_dimension.saturating_mul(MAX_SOURCE_SCALE);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_source);
+    limits.max_image_height = Some(max_source);
+    limits.max_alloc =
+        Some((max_source as u64 * max_source as u64 * 4).max(config.photo_max_size as u64));
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|_| NormalizeError::InvalidPhoto)?;
+    reader.limits(limits);
+
+    // Downscale and recompress, lowering the quality until the size cap is met
+    let mut image = reader.decode().map_err(|err| match err {
+        ImageError::Limits(_) => NormalizeError::PhotoTooLarge,
+        _ => NormalizeError::InvalidPhoto,
+    })?;
+    if is_oversized {
+        image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    }
+    let image = DynamicImage::ImageRgb8(image.to_rgb8());
+    let mut quality = config.photo_quality;
+    loop {
+        let mut photo = Vec::with_capacity(config.photo_max_size);
+        JpegEncoder::new_with_quality(&mut photo, quality)
+            .encode_image(&image)
+            .map_err(|_| NormalizeError::InvalidPhoto)?;
+        if photo.len() <= config.photo_max_size {
+            return Ok(Some(photo));
+        } else if quality <= 30 {
+            return Err(NormalizeError::PhotoTooLarge);
+        }
+        quality -= 15;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NormalizeError, normalize_phone, normalize_photo};
+    use common::config::groupware::ContactNormalizeConfig;
+    use image::{ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn photo_config() -> ContactNormalizeConfig {
+        ContactNormalizeConfig {
+            photo_max_size: 16 * 1024,
+            photo_max_dimension: 64,
+            photo_quality: 85,
+            ..Default::default()
+        }
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])
+        });
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn normalize_phone_e164() {
+        for (value, country_code, expected) in [
+            ("+1 (555) 123-4567", None, Some("+15551234567")),
+            ("0044 20 7946 0958", None, Some("+442079460958")),
+            ("020 7946 0958", Some("+44"), Some("+442079460958")),
+            ("tel:+49 30 901820", None, Some("tel:+4930901820")),
+            ("+1 555 123 4567 x123", None, Some("+15551234567;ext=123")),
+            ("+1 555 123 4567;ext=9", None, Some("+15551234567;ext=9")),
+            ("+15551234567", None, Some("+15551234567")),
+        ] {
+            assert_eq!(
+                normalize_phone(value, country_code).as_deref(),
+                expected,
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_phone_invalid() {
+        for (value, country_code) in [
+            // Local number without a configured country code
+            ("020 7946 0958", None),
+            // No trunk prefix to replace
+            ("7946 0958", Some("+44")),
+            // Letters and misplaced plus signs
+            ("+1 555 CALL NOW", None),
+            ("1+555 123 4567", None),
+            // Too short and too long for E.164
+            ("+1 234", None),
+            ("+1234567890123456789", None),
+            ("", None),
+        ] {
+            assert_eq!(normalize_phone(value, country_code), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn normalize_photo_sizes() {
+        let config = photo_config();
+
+        // Photos within limits are left untouched
+        assert_eq!(normalize_photo(&png(32, 32), &config), Ok(None));
+
+        // Oversized photos are downscaled and recompressed
+        let photo = normalize_photo(&png(256, 128), &config).unwrap().unwrap();
+        let size = imagesize::blob_size(&photo).unwrap();
+        assert_eq!((size.width, size.height), (64, 32));
+        assert!(photo.len() <= config.photo_max_size);
+        assert_eq!(
+            image::guess_format(&photo).unwrap(),
+            image::ImageFormat::Jpeg
+        );
+
+        // Photos beyond the decoding limits are rejected without decoding
+        assert_eq!(
+            normalize_photo(&png(64 * 8 + 1, 16), &config),
+            Err(NormalizeError::PhotoTooLarge)
+        );
+    }
+
+    #[test]
+    fn normalize_photo_invalid() {
+        let config = photo_config();
+
+        // Small non-image data is kept as-is
+        assert_eq!(normalize_photo(b"not an image", &config), Ok(None));
+
+        // Large non-image data and truncated images are rejected
+        assert_eq!(
+            normalize_photo(&vec![b'x'; config.photo_max_size + 1], &config),
+            Err(NormalizeError::InvalidPhoto)
+        );
+        let mut truncated = png(256, 256);
+        truncated.truncate(64);
+        assert_eq!(
+            normalize_photo(&truncated, &config),
+            Err(NormalizeError::InvalidPhoto)
+        );
+    }
+}
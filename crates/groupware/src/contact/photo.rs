@@ -0,0 +1,159 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{ArchivedContactCard, ContactCard};
+use calcard::vcard::{VCardParameter, VCardProperty, VCardValue, VCardValueType};
+use types::{
+    blob::{BlobClass, BlobId},
+    blob_hash::BlobHash,
+    collection::Collection,
+    id::Id,
+};
+
+// Photos and logos are kept in the blob store and referenced from the card
+// through their download URL, which is readable by anyone with access to the card.
+const DOWNLOAD_PATH: &str = "/jmap/download/";
+
+impl ContactCard {
+    // Replaces inline photos and logos with references to the blob store,
+    // returning the data that needs to be stored.
+    pub fn externalize_photos(
+        &mut self,
+        base_url: &str,
+        account_id: u32,
+        document_id: u32,
+    ) -> Vec<Vec<u8>> {
+        let mut photos = Vec::new();
+
+        for entry in &mut self.card.entries {
+            if !matches!(entry.name, VCardProperty::Photo | VCardProperty::Logo) {
+                continue;
+            }
+
+            let mut content_type = None;
+            for value in &mut entry.values {
+                if let VCardValue::Binary(data) = value {
+                    let hash = BlobHash::generate(&data.data);
+                    content_type = data.content_type.take();
+                    photos.push(std::mem::take(&mut data.data));
+                    *value = VCardValue::Text(photo_url(base_url, account_id, document_id, hash));
+                }
+            }
+
+            if let Some(content_type) = content_type {
+                entry.params.retain(|param| {
+                    !matches!(
+                        param,
+                        VCardParameter::Value(_) | VCardParameter::Mediatype(_)
+                    )
+                });
+                entry
+                    .params
+                    .push(VCardParameter::Value(vec![VCardValueType::Uri]));
+                entry.params.push(VCardParameter::Mediatype(content_type));
+            }
+        }
+
+        photos
+    }
+
+    // Points photo references at a new location after the card was copied or moved
+    pub fn relink_photos(&mut self, account_id: u32, document_id: u32) {
+        for entry in &mut self.card.entries {
+            if matches!(entry.name, VCardProperty::Photo | VCardProperty::Logo) {
+                for value in &mut entry.values {
+                    if let VCardValue::Text(text) = value
+                        && let Some((base_url, blob_id)) = parse_photo_url(text)
+                    {
+                        *text = photo_url(base_url, account_id, document_id, blob_id.hash);
+                    }
+                }
+            }
+        }
+    }
+
+    // Clients may only keep references to photos previously stored for this card,
+    // as any referenced blob is linked to it and becomes downloadable.
+    pub fn has_foreign_photos(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        current_hashes: &[BlobHash],
+    ) -> bool {
+        self.card
+            .properties(&VCardProperty::Photo)
+            .chain(self.card.properties(&VCardProperty::Logo))
+            .flat_map(|entry| entry.values.iter().filter_map(|v| v.as_text()))
+            .filter_map(|text| parse_photo_url(text).map(|(_, blob_id)| blob_id))
+            .any(|blob_id| {
+                !matches!(blob_id.class, BlobClass::Linked {
+                    account_id: blob_account_id,
+                    document_id: blob_document_id,
+                    ..
+                } if blob_account_id == account_id && blob_document_id == document_id)
+                    || !current_hashes.contains(&blob_id.hash)
+            })
+    }
+
+    pub fn photo_hashes(&self) -> Vec<BlobHash> {
+        unique_hashes(
+            self.card
+                .properties(&VCardProperty::Photo)
+                .chain(self.card.properties(&VCardProperty::Logo))
+                .flat_map(|entry| entry.values.iter().filter_map(|v| v.as_text())),
+        )
+    }
+}
+
+impl ArchivedContactCard {
+    pub fn photo_hashes(&self) -> Vec<BlobHash> {
+        unique_hashes(
+            self.card
+                .properties(&VCardProperty::Photo)
+                .chain(self.card.properties(&VCardProperty::Logo))
+                .flat_map(|entry| entry.values.iter().filter_map(|v| v.as_text())),
+        )
+    }
+}
+
+fn unique_hashes<'x>(urls: impl Iterator<Item = &'x str>) -> Vec<BlobHash> {
+    let mut hashes = Vec::new();
+    for (_, blob_id) in urls.filter_map(parse_photo_url) {
+        if !hashes.contains(&blob_id.hash) {
+            hashes.push(blob_id.hash);
+        }
+    }
+    hashes
+}
+
+fn photo_url(base_url: &str, account_id: u32, document_id: u32, hash: BlobHash) -> String {
+    format!(
+        "{base_url}{DOWNLOAD_PATH}{}/{}/photo",
+        Id::from(account_id),
+        BlobId::new(
+            hash,
+            BlobClass::Linked {
+                account_id,
+                collection: Collection::ContactCard.into(),
+                document_id,
+            },
+        )
+    )
+}
+
+fn parse_photo_url(url: &str) -> Option<(&str, BlobId)> {
+    let (base_url, path) = url.split_once(DOWNLOAD_PATH)?;
+    let blob_id = path.split('/').nth(1).and_then(BlobId::from_base32)?;
+
+    match blob_id.class {
+        BlobClass::Linked { collection, .. }
+            if collection == u8::from(Collection::ContactCard) && blob_id.section.is_none() =>
+        {
+            Some((base_url, blob_id))
+        }
+        _ => None,
+    }
+}
@@ -5,7 +5,7 @@
  */
 
 use super::{AddressBook, ArchivedAddressBook, ArchivedContactCard, ContactCard};
-use crate::DestroyArchive;
+use crate::{DestroyArchive, fts::write_fts_task};
 use common::{Server, auth::AccessToken, storage::index::ObjectIndexBuilder};
 use store::write::{Archive, BatchBuilder, now};
 use trc::AddContext;
@@ -36,7 +36,10 @@ impl ContactCard {
                     .with_changes(new_card)
                     .with_tenant_id(access_token),
             )
-            .map(|b| b.commit_point())
+            .map(|batch| {
+                write_fts_task(batch, Collection::ContactCard, true);
+                batch.commit_point()
+            })
     }
 
     pub fn insert<'x>(
@@ -62,7 +65,10 @@ impl ContactCard {
                     .with_changes(card)
                     .with_tenant_id(access_token),
             )
-            .map(|b| b.commit_point())
+            .map(|batch| {
+                write_fts_task(batch, Collection::ContactCard, false);
+                batch.commit_point()
+            })
     }
 }
 
@@ -235,6 +241,7 @@ impl DestroyArchive<Archive<&ArchivedContactCard>> {
                             .with_current(card),
                     )
                     .caused_by(trc::location!())?;
+                write_fts_task(batch, Collection::ContactCard, true);
             }
 
             if let Some(delete_path) = delete_path {
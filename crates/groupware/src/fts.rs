@@ -0,0 +1,161 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{calendar::ArchivedCalendarEvent, contact::ArchivedContactCard};
+use calcard::{
+    icalendar::{ArchivedICalendarParameter, ArchivedICalendarProperty},
+    vcard::VCardProperty,
+};
+use nlp::language::Language;
+use std::fmt::Display;
+use store::{
+    fts::{Field, index::FtsDocument},
+    write::{BatchBuilder, TaskQueueClass, ValueClass, now},
+};
+use types::collection::Collection;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupwareField {
+    Title,
+    Description,
+    Location,
+    Participant,
+    Name,
+    Email,
+    Organization,
+    Note,
+}
+
+pub trait IndexGroupwareText<'x>: Sized {
+    fn index_calendar_event(self, event: &'x ArchivedCalendarEvent) -> Self;
+    fn index_contact_card(self, card: &'x ArchivedContactCard) -> Self;
+}
+
+impl<'x> IndexGroupwareText<'x> for FtsDocument<'x, GroupwareField> {
+    fn index_calendar_event(mut self, event: &'x ArchivedCalendarEvent) -> Self {
+        for component in event.data.event.components.iter() {
+            for entry in component.entries.iter() {
+                let Some(text) = entry.values.first().and_then(|value| value.as_text()) else {
+                    continue;
+                };
+
+                match &entry.name {
+                    ArchivedICalendarProperty::Summary => {
+                        self.index(
+                            Field::Header(GroupwareField::Title),
+                            text,
+                            Language::Unknown,
+                        );
+                    }
+                    ArchivedICalendarProperty::Description => {
+                        self.index(
+                            Field::Header(GroupwareField::Description),
+                            text,
+                            Language::Unknown,
+                        );
+                    }
+                    ArchivedICalendarProperty::Location => {
+                        self.index_tokenized(Field::Header(GroupwareField::Location), text);
+                    }
+                    ArchivedICalendarProperty::Organizer | ArchivedICalendarProperty::Attendee => {
+                        self.index_tokenized(
+                            Field::Header(GroupwareField::Participant),
+                            text.strip_prefix("mailto:").unwrap_or(text),
+                        );
+                        for param in entry.params.iter() {
+                            if let ArchivedICalendarParameter::Cn(name) = param {
+                                self.index_tokenized(
+                                    Field::Header(GroupwareField::Participant),
+                                    name.as_str(),
+                                );
+                            }
+                        }
+                    }
+                    ArchivedICalendarProperty::Categories => {
+                        for value in entry.values.iter().filter_map(|value| value.as_text()) {
+                            self.index_keyword(Field::Keyword, value.to_lowercase());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self
+    }
+
+    fn index_contact_card(mut self, card: &'x ArchivedContactCard) -> Self {
+        for entry in card.card.entries.iter() {
+            let field = if entry.name == VCardProperty::Fn
+                || entry.name == VCardProperty::N
+                || entry.name == VCardProperty::Nickname
+            {
+                GroupwareField::Name
+            } else if entry.name == VCardProperty::Email {
+                GroupwareField::Email
+            } else if entry.name == VCardProperty::Org || entry.name == VCardProperty::Title {
+                GroupwareField::Organization
+            } else if entry.name == VCardProperty::Note {
+                GroupwareField::Note
+            } else {
+                continue;
+            };
+
+            for text in entry.values.iter().filter_map(|value| value.as_text()) {
+                if field == GroupwareField::Note {
+                    self.index(Field::Header(field), text, Language::Unknown);
+                } else {
+                    self.index_tokenized(Field::Header(field), text);
+                }
+            }
+        }
+
+        self
+    }
+}
+
+// Schedules a calendar event or contact card to be (re)indexed by the task manager,
+// updated documents have their previous terms removed before indexing and
+// deleted documents are removed from the index.
+pub fn write_fts_task(batch: &mut BatchBuilder, collection: Collection, is_update: bool) {
+    batch.set(
+        ValueClass::TaskQueue(TaskQueueClass::IndexGroupware {
+            due: now(),
+            collection: collection.into(),
+        }),
+        vec![is_update as u8],
+    );
+}
+
+impl From<GroupwareField> for u8 {
+    fn from(value: GroupwareField) -> Self {
+        match value {
+            GroupwareField::Title => 0,
+            GroupwareField::Description => 1,
+            GroupwareField::Location => 2,
+            GroupwareField::Participant => 3,
+            GroupwareField::Name => 4,
+            GroupwareField::Email => 5,
+            GroupwareField::Organization => 6,
+            GroupwareField::Note => 7,
+        }
+    }
+}
+
+impl Display for GroupwareField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GroupwareField::Title => "title",
+            GroupwareField::Description => "description",
+            GroupwareField::Location => "location",
+            GroupwareField::Participant => "participant",
+            GroupwareField::Name => "name",
+            GroupwareField::Email => "email",
+            GroupwareField::Organization => "organization",
+            GroupwareField::Note => "note",
+        })
+    }
+}
@@ -13,6 +13,7 @@ pub mod cache;
 pub mod calendar;
 pub mod contact;
 pub mod file;
+pub mod fts;
 pub mod scheduling;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -103,6 +103,7 @@ pub struct Organizer<'x> {
     pub entry_id: u16,
     pub email: Email,
     pub name: Option<&'x str>,
+    pub sent_by: Option<Email>,
     pub is_server_scheduling: bool,
     pub force_send: Option<&'x ICalendarScheduleForceSendValue>,
 }
@@ -74,6 +74,7 @@ pub fn itip_snapshot<'x, 'y>(
                                 is_server_scheduling: true,
                                 name: None,
                                 force_send: None,
+                                sent_by: None,
                             };
                             has_local_emails |= part.email.is_local;
 
@@ -86,6 +87,9 @@ pub fn itip_snapshot<'x, 'y>(
                                     ICalendarParameter::ScheduleForceSend(force_send) => {
                                         part.force_send = Some(force_send);
                                     }
+                                    ICalendarParameter::SentBy(value) => {
+                                        part.sent_by = Email::from_uri(value, account_emails);
+                                    }
                                     ICalendarParameter::Cn(name) => {
                                         part.name = Some(name.as_str());
                                     }
@@ -329,6 +333,27 @@ impl ItipSnapshots<'_> {
             })
     }
 
+    pub fn sent_by_principal(&self, email: &str) -> Option<&str> {
+        if self
+            .organizer
+            .sent_by
+            .as_ref()
+            .is_some_and(|sent_by| sent_by.email == email)
+        {
+            Some(self.organizer.email.email.as_str())
+        } else {
+            self.components.values().find_map(|snapshot| {
+                snapshot.attendees.iter().find_map(|attendee| {
+                    attendee
+                        .sent_by
+                        .as_ref()
+                        .is_some_and(|sent_by| sent_by.email == email)
+                        .then_some(attendee.email.email.as_str())
+                })
+            })
+        }
+    }
+
     pub fn main_instance(&self) -> Option<&ItipSnapshot<'_>> {
         self.components.get(&InstanceId::Main)
     }
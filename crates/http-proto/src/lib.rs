@@ -12,7 +12,7 @@ pub use form_urlencoded;
 
 use std::{net::IpAddr, sync::Arc};
 
-use common::listener::ServerInstance;
+use common::{auth::certificate::ClientCertificate, listener::ServerInstance};
 use hyper::StatusCode;
 
 pub type HttpRequest = hyper::Request<hyper::body::Incoming>;
@@ -55,12 +55,15 @@ pub struct HttpSessionData {
     pub remote_port: u16,
     pub is_tls: bool,
     pub session_id: u64,
+    pub client_cert: Option<Arc<ClientCertificate>>,
 }
 
 pub struct DownloadResponse {
     pub filename: String,
     pub content_type: String,
     pub blob: Vec<u8>,
+    pub etag: Option<String>,
+    pub range: Option<request::ByteRange>,
 }
 
 pub struct JsonProblemResponse(pub StatusCode);
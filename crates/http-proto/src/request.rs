@@ -6,8 +6,10 @@
 
 use std::borrow::Cow;
 
+use common::listener::limiter::BufferInFlight;
 use compact_str::ToCompactString;
 use http_body_util::BodyExt;
+use hyper::header;
 
 use crate::HttpRequest;
 
@@ -23,10 +25,38 @@ pub async fn fetch_body(
     max_size: usize,
     session_id: u64,
 ) -> Option<Vec<u8>> {
-    let mut bytes = Vec::with_capacity(1024);
+    read_body(req, max_size, session_id, None)
+        .await
+        .unwrap_or_default()
+}
+
+// Same as fetch_body, accounting the buffer against the listener's message budgets
+pub async fn fetch_body_with_budget(
+    req: &mut HttpRequest,
+    max_size: usize,
+    session_id: u64,
+    in_flight: &mut BufferInFlight,
+) -> trc::Result<Option<Vec<u8>>> {
+    read_body(req, max_size, session_id, Some(in_flight)).await
+}
+
+async fn read_body(
+    req: &mut HttpRequest,
+    max_size: usize,
+    session_id: u64,
+    mut in_flight: Option<&mut BufferInFlight>,
+) -> trc::Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::new();
+    match in_flight.as_deref_mut() {
+        Some(in_flight) => reserve_body(&mut bytes, 1024, in_flight)?,
+        None => bytes.reserve_exact(1024),
+    }
     while let Some(Ok(frame)) = req.frame().await {
         if let Some(data) = frame.data_ref() {
             if bytes.len() + data.len() <= max_size || max_size == 0 {
+                if let Some(in_flight) = in_flight.as_deref_mut() {
+                    reserve_body(&mut bytes, data.len(), in_flight)?;
+                }
                 bytes.extend_from_slice(data);
             } else {
                 trc::event!(
@@ -47,7 +77,7 @@ pub async fn fetch_body(
                     Limit = max_size,
                 );
 
-                return None;
+                return Ok(None);
             }
         }
     }
@@ -69,5 +99,123 @@ pub async fn fetch_body(
         Size = bytes.len(),
     );
 
-    bytes.into()
+    Ok(Some(bytes))
+}
+
+fn reserve_body(
+    bytes: &mut Vec<u8>,
+    additional: usize,
+    in_flight: &mut BufferInFlight,
+) -> trc::Result<()> {
+    let len = bytes.len();
+    let capacity = if len + additional > bytes.capacity() {
+        std::cmp::max(bytes.capacity() * 2, len + additional)
+    } else {
+        bytes.capacity()
+    };
+
+    in_flight.try_resize(capacity as u64).map_err(|limit| {
+        trc::LimitEvent::MessageBuffer
+            .into_err()
+            .ctx(trc::Key::Size, capacity)
+            .ctx(trc::Key::Limit, limit)
+    })?;
+    bytes.reserve_exact(capacity - len);
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    From(usize),
+    Bounded(usize, usize),
+    Suffix(usize),
+}
+
+impl ByteRange {
+    // Parses a single "bytes=" range, multiple ranges are not supported
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+        let (start, end) = (start.trim(), end.trim());
+
+        match (start.is_empty(), end.is_empty()) {
+            (false, true) => start.parse().ok().map(ByteRange::From),
+            (false, false) => {
+                let start = start.parse().ok()?;
+                let end = end.parse().ok()?;
+                (start <= end).then_some(ByteRange::Bounded(start, end))
+            }
+            (true, false) => end.parse().ok().map(ByteRange::Suffix),
+            (true, true) => None,
+        }
+    }
+
+    // Returns the inclusive byte positions for a resource of the given length
+    pub fn resolve(&self, len: usize) -> Option<(usize, usize)> {
+        match *self {
+            ByteRange::From(start) if start < len => Some((start, len - 1)),
+            ByteRange::Bounded(start, end) if start < len => Some((start, end.min(len - 1))),
+            ByteRange::Suffix(suffix) if suffix > 0 && len > 0 => {
+                Some((len - suffix.min(len), len - 1))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Returns true if the If-None-Match header matches the given entity tag
+pub fn if_none_match(req: &HttpRequest, etag: &str) -> bool {
+    req.headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.split(',').any(|tag| {
+                let tag = tag.trim();
+                tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag
+            })
+        })
+}
+
+// Returns the requested byte range, ignoring it if an If-Range precondition
+// does not match the given entity tag
+pub fn byte_range(req: &HttpRequest, etag: &str) -> Option<ByteRange> {
+    let range = ByteRange::parse(req.headers().get(header::RANGE)?.to_str().ok()?)?;
+
+    match req.headers().get(header::IF_RANGE) {
+        Some(if_range) if if_range.to_str().ok().map(|v| v.trim()) != Some(etag) => None,
+        _ => Some(range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteRange;
+
+    #[test]
+    fn parse_byte_range() {
+        for (header, expected, resolved) in [
+            (
+                "bytes=0-499",
+                Some(ByteRange::Bounded(0, 499)),
+                Some((0, 499)),
+            ),
+            ("bytes=500-", Some(ByteRange::From(500)), Some((500, 999))),
+            ("bytes=-200", Some(ByteRange::Suffix(200)), Some((800, 999))),
+            (
+                "bytes=900-2000",
+                Some(ByteRange::Bounded(900, 2000)),
+                Some((900, 999)),
+            ),
+            ("bytes=-5000", Some(ByteRange::Suffix(5000)), Some((0, 999))),
+            ("bytes=1000-", Some(ByteRange::From(1000)), None),
+            ("bytes=500-100", None, None),
+            ("bytes=0-1,5-10", None, None),
+            ("bytes=-", None, None),
+            ("items=0-10", None, None),
+        ] {
+            let range = ByteRange::parse(header);
+            assert_eq!(range, expected, "{header}");
+            assert_eq!(range.and_then(|r| r.resolve(1000)), resolved, "{header}");
+        }
+    }
 }
@@ -236,14 +236,38 @@ impl<T: serde::Serialize> ToHttpResponse for JsonResponse<T> {
 
 impl ToHttpResponse for DownloadResponse {
     fn into_http_response(self) -> HttpResponse {
-        HttpResponse::new(StatusCode::OK)
+        let total_len = self.blob.len();
+        let (response, blob) = match self.range.map(|range| range.resolve(total_len)) {
+            Some(Some((start, end))) => {
+                let mut blob = self.blob;
+                blob.truncate(end + 1);
+                blob.drain(..start);
+                (
+                    HttpResponse::new(StatusCode::PARTIAL_CONTENT).with_header(
+                        header::CONTENT_RANGE,
+                        format!("bytes {start}-{end}/{total_len}"),
+                    ),
+                    blob,
+                )
+            }
+            Some(None) => {
+                return HttpResponse::new(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .with_header(header::CONTENT_RANGE, format!("bytes */{total_len}"))
+                    .with_etag_opt(self.etag);
+            }
+            None => (HttpResponse::new(StatusCode::OK), self.blob),
+        };
+
+        response
             .with_content_type(self.content_type)
             .with_content_disposition(format!(
                 "attachment; filename=\"{}\"",
                 self.filename.replace('\"', "\\\"")
             ))
             .with_cache_control("private, immutable, max-age=31536000")
-            .with_binary_body(self.blob)
+            .with_header(header::ACCEPT_RANGES, "bytes")
+            .with_etag_opt(self.etag)
+            .with_binary_body(blob)
     }
 }
 
@@ -82,6 +82,9 @@ impl Authenticator for Server {
                     .caused_by(trc::location!()));
             };
 
+            // Expired passwords may only be used to set a new password
+            let is_password_change = req.uri().path() == "/api/account/auth";
+
             // Authenticate
             let access_token = self
                 .authenticate(
@@ -90,20 +93,48 @@ impl Authenticator for Server {
                         session.session_id,
                         session.remote_ip,
                     )
-                    .with_api_access(allow_api_access),
+                    .with_api_access(allow_api_access)
+                    .with_expired_password(is_password_change)
+                    .with_session(
+                        "http",
+                        req.headers()
+                            .get(header::USER_AGENT)
+                            .and_then(|ua| ua.to_str().ok()),
+                    ),
                 )
                 .await?;
 
             // Cache credentials
-            self.inner.cache.http_auth.insert(
-                token.to_string(),
-                HttpAuthCache {
-                    account_id: access_token.primary_id(),
-                    revision: access_token.revision,
-                    expires: Instant::now()
-                        + Duration::from_secs(self.core.oauth.oauth_expiry_token),
-                },
-            );
+            if !is_password_change {
+                self.inner.cache.http_auth.insert(
+                    token.to_string(),
+                    HttpAuthCache {
+                        account_id: access_token.primary_id(),
+                        revision: access_token.revision,
+                        expires: Instant::now()
+                            + Duration::from_secs(self.core.oauth.oauth_expiry_token),
+                    },
+                );
+            }
+
+            // Enforce authenticated rate limit
+            self.is_http_authenticated_request_allowed(&access_token)
+                .await
+                .map(|in_flight| (in_flight, access_token))
+        } else if let (Some(certificate), Some(mapping)) = (
+            &session.client_cert,
+            session.instance.acceptor.client_cert_mapping(),
+        ) {
+            // Authenticate using the TLS client certificate
+            let access_token = self
+                .authenticate_certificate(
+                    certificate,
+                    mapping,
+                    None,
+                    session.session_id,
+                    session.remote_ip,
+                )
+                .await?;
 
             // Enforce authenticated rate limit
             self.is_http_authenticated_request_allowed(&access_token)
@@ -8,15 +8,14 @@ use std::future::Future;
 
 use common::{
     Server,
-    auth::oauth::registration::{ClientRegistrationRequest, ClientRegistrationResponse},
-};
-
-use directory::{
-    Permission, QueryParams, Type,
-    backend::internal::{
-        PrincipalField, PrincipalSet, lookup::DirectoryStore, manage::ManageDirectory,
+    auth::oauth::registration::{
+        ClientRegistrationDecision, ClientRegistrationRequest, ClientRegistrationResponse,
     },
 };
+
+use directory::{Permission, QueryParams, Type, backend::internal::lookup::DirectoryStore};
+use hyper::StatusCode;
+use serde_json::json;
 use store::rand::{Rng, distr::Alphanumeric, rng};
 use trc::{AddContext, AuthEvent};
 
@@ -71,33 +70,73 @@ impl ClientRegistrationHandler for Server {
             .take(20)
             .map(|ch| char::from(ch.to_ascii_lowercase()))
             .collect::<String>();
-        self.store()
-            .create_principal(
-                PrincipalSet::new(u32::MAX, Type::OauthClient)
-                    .with_field(PrincipalField::Name, client_id.clone())
-                    .with_field(PrincipalField::Urls, request.redirect_uris.clone())
-                    .with_opt_field(PrincipalField::Description, request.client_name.clone())
-                    .with_field(PrincipalField::Emails, request.contacts.clone())
-                    .with_opt_field(PrincipalField::Picture, request.logo_uri.clone()),
-                None,
-                None,
-            )
-            .await
-            .caused_by(trc::location!())?;
-
-        trc::event!(
-            Auth(AuthEvent::ClientRegistration),
-            Id = client_id.to_string(),
-            RemoteIp = session.remote_ip
-        );
-
-        Ok(JsonResponse::new(ClientRegistrationResponse {
-            client_id,
-            request,
-            ..Default::default()
-        })
-        .no_cache()
-        .into_http_response())
+
+        match self.client_registration_decision(&request) {
+            ClientRegistrationDecision::Approve(rule) => {
+                self.create_oauth_client(
+                    &client_id,
+                    &request,
+                    rule.map(|rule| rule.expiry).unwrap_or_default(),
+                )
+                .await?;
+
+                trc::event!(
+                    Auth(AuthEvent::ClientRegistration),
+                    Id = client_id.to_string(),
+                    Details = rule.map(|rule| rule.id.clone()),
+                    RemoteIp = session.remote_ip
+                );
+
+                Ok(JsonResponse::new(ClientRegistrationResponse {
+                    client_id,
+                    request,
+                    ..Default::default()
+                })
+                .no_cache()
+                .into_http_response())
+            }
+            ClientRegistrationDecision::Review => {
+                // The client cannot be used until an administrator approves it
+                let response = ClientRegistrationResponse {
+                    client_id: client_id.clone(),
+                    request,
+                    ..Default::default()
+                };
+                self.queue_client_registration(
+                    &client_id,
+                    response.request.clone(),
+                    session.remote_ip,
+                )
+                .await?;
+
+                trc::event!(
+                    Auth(AuthEvent::ClientRegistrationPending),
+                    Id = client_id,
+                    RemoteIp = session.remote_ip
+                );
+
+                Ok(JsonResponse::with_status(StatusCode::ACCEPTED, response)
+                    .no_cache()
+                    .into_http_response())
+            }
+            ClientRegistrationDecision::Reject => {
+                trc::event!(
+                    Auth(AuthEvent::ClientRegistrationRejected),
+                    Details = request.redirect_uris.join(", "),
+                    RemoteIp = session.remote_ip
+                );
+
+                Ok(JsonResponse::with_status(
+                    StatusCode::BAD_REQUEST,
+                    json!({
+                        "error": "invalid_redirect_uri",
+                        "error_description": "The redirect URIs are not allowed by the registration policy",
+                    }),
+                )
+                .no_cache()
+                .into_http_response())
+            }
+        }
     }
 
     async fn validate_client_registration(
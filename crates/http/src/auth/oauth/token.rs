@@ -13,10 +13,11 @@ use common::{
     auth::{
         AccessToken,
         oauth::{GrantType, oidc::StandardClaims},
+        sessions::RefreshTokenSession,
     },
 };
 use http_proto::*;
-use hyper::StatusCode;
+use hyper::{StatusCode, header};
 use std::future::Future;
 use store::{
     dispatch::lookup::KeyValue,
@@ -57,10 +58,16 @@ impl TokenHandler for Server {
         session: HttpSessionData,
     ) -> trc::Result<HttpResponse> {
         // Parse form
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|ua| ua.to_str().ok())
+            .map(|ua| ua.to_string());
         let params = FormData::from_request(req, MAX_POST_LEN, session.session_id).await?;
         let grant_type = params.get("grant_type").unwrap_or_default();
 
         let mut response = TokenResponse::error(ErrorType::InvalidGrant);
+        let mut grant = None;
 
         let issuer = HttpContext::new(&session, req)
             .resolve_response_url(self)
@@ -112,6 +119,11 @@ impl TokenHandler for Server {
                                     .await?;
 
                                 // Issue token
+                                grant = Some((
+                                    u32::from(oauth.account_id),
+                                    oauth.client_id.to_string(),
+                                    None,
+                                ));
                                 self.issue_token(
                                     oauth.account_id.into(),
                                     &oauth.client_id,
@@ -184,6 +196,11 @@ impl TokenHandler for Server {
                                         .await?;
 
                                     // Issue token
+                                    grant = Some((
+                                        u32::from(oauth.account_id),
+                                        oauth.client_id.to_string(),
+                                        None,
+                                    ));
                                     self.issue_token(
                                         oauth.account_id.into(),
                                         &oauth.client_id,
@@ -218,24 +235,53 @@ impl TokenHandler for Server {
                     .validate_access_token(GrantType::RefreshToken.into(), refresh_token)
                     .await
                 {
-                    Ok(token_info) => self
-                        .issue_token(
-                            token_info.account_id,
-                            &token_info.client_id,
-                            issuer,
-                            None,
-                            token_info.expires_in
-                                <= self.core.oauth.oauth_expiry_refresh_token_renew,
-                            false,
-                        )
-                        .await
-                        .map(TokenResponse::Granted)
-                        .map_err(|err| {
-                            trc::AuthEvent::Error
-                                .into_err()
-                                .details(err)
-                                .caused_by(trc::location!())
-                        })?,
+                    Ok(token_info) => match self
+                        .refresh_token_session(token_info.account_id, refresh_token)
+                        .await?
+                    {
+                        RefreshTokenSession::Revoked => {
+                            trc::event!(
+                                Auth(trc::AuthEvent::Failed),
+                                AccountId = token_info.account_id,
+                                Id = token_info.client_id,
+                                Details = "Refresh token belongs to a revoked session",
+                                SpanId = session.session_id,
+                            );
+                            TokenResponse::error(ErrorType::InvalidGrant)
+                        }
+                        device_session => {
+                            // Tokens issued before the registry existed start a new session
+                            let response = self
+                                .issue_token(
+                                    token_info.account_id,
+                                    &token_info.client_id,
+                                    issuer,
+                                    None,
+                                    token_info.expires_in
+                                        <= self.core.oauth.oauth_expiry_refresh_token_renew,
+                                    false,
+                                )
+                                .await
+                                .map(TokenResponse::Granted)
+                                .map_err(|err| {
+                                    trc::AuthEvent::Error
+                                        .into_err()
+                                        .details(err)
+                                        .caused_by(trc::location!())
+                                })?;
+                            grant = Some((
+                                token_info.account_id,
+                                token_info.client_id,
+                                match device_session {
+                                    RefreshTokenSession::Active(device_session) => {
+                                        Some(device_session)
+                                    }
+                                    _ => None,
+                                },
+                            ));
+                            response
+                        }
+                    },
                     Err(err) => {
                         trc::error!(
                             err.caused_by(trc::location!())
@@ -250,6 +296,22 @@ impl TokenHandler for Server {
             }
         }
 
+        // Bind the issued refresh token to the client's session
+        if let (TokenResponse::Granted(granted), Some((account_id, client_id, device_session))) =
+            (&response, grant)
+        {
+            self.register_oauth_session(
+                account_id,
+                device_session,
+                &client_id,
+                user_agent.as_deref(),
+                session.remote_ip,
+                granted.refresh_token.as_deref(),
+            )
+            .await
+            .caused_by(trc::location!())?;
+        }
+
         Ok(JsonResponse::with_status(
             if response.is_error() {
                 StatusCode::BAD_REQUEST
@@ -291,23 +353,24 @@ impl TokenHandler for Server {
         with_refresh_token: bool,
         with_id_token: bool,
     ) -> trc::Result<OAuthResponse> {
+        // Clients approved by a registration rule may have custom lifetimes
+        let expiry = self.oauth_client_expiry(client_id).await?;
+        let expiry_token = expiry.token.unwrap_or(self.core.oauth.oauth_expiry_token);
+
         Ok(OAuthResponse {
             access_token: self
-                .encode_access_token(
-                    GrantType::AccessToken,
-                    account_id,
-                    client_id,
-                    self.core.oauth.oauth_expiry_token,
-                )
+                .encode_access_token(GrantType::AccessToken, account_id, client_id, expiry_token)
                 .await?,
             token_type: "bearer".to_string(),
-            expires_in: self.core.oauth.oauth_expiry_token,
+            expires_in: expiry_token,
             refresh_token: if with_refresh_token {
                 self.encode_access_token(
                     GrantType::RefreshToken,
                     account_id,
                     client_id,
-                    self.core.oauth.oauth_expiry_refresh_token,
+                    expiry
+                        .refresh_token
+                        .unwrap_or(self.core.oauth.oauth_expiry_refresh_token),
                 )
                 .await?
                 .into()
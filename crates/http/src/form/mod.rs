@@ -200,6 +200,7 @@ impl FormHandler for Server {
                     sender_authenticated: false,
                     recipients: form.rcpt_to.clone(),
                     message_blob,
+                    message_data: None,
                     message_size,
                     session_id: session.session_id,
                 })
@@ -0,0 +1,171 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use common::{KV_HEALTH_PROBE, Server, storage::health::CachedHealthReport};
+use store::{BlobStore, InMemoryStore, Store, dispatch::lookup::KeyValue, write::now};
+
+pub use common::storage::health::{HealthCheck, HealthReport, HealthStatus};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Probe<'x> = Pin<Box<dyn Future<Output = HealthCheck> + Send + 'x>>;
+
+pub trait DeepHealthCheck: Sync + Send {
+    fn deep_health_check(&self) -> impl Future<Output = HealthReport> + Send;
+
+    fn cached_deep_health_check(
+        &self,
+        ttl: Duration,
+    ) -> impl Future<Output = Arc<HealthReport>> + Send;
+}
+
+impl DeepHealthCheck for Server {
+    async fn deep_health_check(&self) -> HealthReport {
+        let storage = &self.core.storage;
+        let probe_id = format!("{}-{}", self.core.network.node_id, now());
+        let mut probes: Vec<Probe<'_>> = Vec::with_capacity(
+            storage.stores.len()
+                + storage.blobs.len()
+                + storage.lookups.len()
+                + storage.directories.len(),
+        );
+
+        for (id, store) in &storage.stores {
+            probes.push(Box::pin(probe(
+                "data",
+                id,
+                probe_data_store(store, &probe_id),
+            )));
+        }
+        for (id, store) in &storage.blobs {
+            probes.push(Box::pin(probe(
+                "blob",
+                id,
+                probe_blob_store(store, &probe_id),
+            )));
+        }
+        for (id, store) in &storage.lookups {
+            probes.push(Box::pin(probe(
+                "lookup",
+                id,
+                probe_in_memory_store(store, &probe_id),
+            )));
+        }
+        for (id, directory) in &storage.directories {
+            // Directories are probed read-only
+            probes.push(Box::pin(probe(
+                "directory",
+                id,
+                directory.is_local_domain("healthz.invalid"),
+            )));
+        }
+
+        // Backends are probed concurrently, so a request takes at most one probe timeout
+        let checks = futures::future::join_all(probes).await;
+
+        HealthReport {
+            status: if checks.iter().all(|check| check.status == HealthStatus::Ok) {
+                HealthStatus::Ok
+            } else {
+                HealthStatus::Error
+            },
+            checks,
+        }
+    }
+
+    async fn cached_deep_health_check(&self, ttl: Duration) -> Arc<HealthReport> {
+        // Concurrent requests wait for the probe in progress instead of starting their own
+        let mut cached = self.inner.data.health_report.lock().await;
+        if let Some(cached) = cached.as_ref()
+            && cached.expires > Instant::now()
+        {
+            return cached.report.clone();
+        }
+
+        let report = Arc::new(self.deep_health_check().await);
+        *cached = Some(CachedHealthReport {
+            expires: Instant::now() + ttl,
+            report: report.clone(),
+        });
+        report
+    }
+}
+
+async fn probe<T>(
+    typ: &'static str,
+    id: &str,
+    check: impl Future<Output = trc::Result<T>>,
+) -> HealthCheck {
+    let time = Instant::now();
+    let status = match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(Ok(_)) => HealthStatus::Ok,
+        Ok(Err(err)) => {
+            trc::error!(
+                err.details("Health check failed")
+                    .ctx(trc::Key::Id, id.to_string())
+                    .caused_by(trc::location!())
+            );
+            HealthStatus::Error
+        }
+        Err(_) => HealthStatus::Timeout,
+    };
+
+    HealthCheck {
+        typ,
+        id: id.to_string(),
+        status,
+        latency_ms: time.elapsed().as_millis() as u64,
+    }
+}
+
+async fn probe_data_store(store: &Store, probe_id: &str) -> trc::Result<()> {
+    probe_in_memory_store(&InMemoryStore::Store(store.clone()), probe_id).await
+}
+
+async fn probe_in_memory_store(store: &InMemoryStore, probe_id: &str) -> trc::Result<()> {
+    match store {
+        InMemoryStore::Static(_) => Ok(()),
+        InMemoryStore::Http(_) => store.key_exists(probe_id.as_bytes()).await.map(|_| ()),
+        _ => {
+            let key = KeyValue::<()>::build_key(KV_HEALTH_PROBE, probe_id);
+            store
+                .key_set(KeyValue::new(key.clone(), probe_id.as_bytes().to_vec()).expires(60))
+                .await?;
+            let value = store.key_get::<String>(key.clone()).await?;
+            store.key_delete(key).await?;
+
+            if value.as_deref() == Some(probe_id) {
+                Ok(())
+            } else {
+                Err(trc::StoreEvent::UnexpectedError
+                    .into_err()
+                    .details("Probe value mismatch"))
+            }
+        }
+    }
+}
+
+async fn probe_blob_store(store: &BlobStore, probe_id: &str) -> trc::Result<()> {
+    let key = format!("healthz-{probe_id}");
+    store.put_blob(key.as_bytes(), probe_id.as_bytes()).await?;
+    let value = store.get_blob(key.as_bytes(), 0..usize::MAX).await?;
+    store.delete_blob(key.as_bytes()).await?;
+
+    if value.as_deref() == Some(probe_id.as_bytes()) {
+        Ok(())
+    } else {
+        Err(trc::StoreEvent::UnexpectedError
+            .into_err()
+            .details("Probe value mismatch"))
+    }
+}
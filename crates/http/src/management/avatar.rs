@@ -0,0 +1,141 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken, manager::webadmin::Resource};
+use directory::{
+    Permission, PrincipalAvatar, Type,
+    backend::internal::{
+        PrincipalField,
+        manage::{self, ChangedPrincipals, ManageDirectory, not_found},
+    },
+};
+use http_proto::{request::decode_path_element, *};
+use hyper::Method;
+use serde_json::json;
+use std::future::Future;
+use trc::AddContext;
+use types::blob_hash::BlobHash;
+
+pub trait ManageAvatar: Sync + Send {
+    fn handle_manage_avatar(
+        &self,
+        req: &HttpRequest,
+        name: &str,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn principal_avatar(
+        &self,
+        account_id: u32,
+        hash: &str,
+    ) -> impl Future<Output = trc::Result<Option<Resource<Vec<u8>>>>> + Send;
+}
+
+impl ManageAvatar for Server {
+    async fn handle_manage_avatar(
+        &self,
+        req: &HttpRequest,
+        name: &str,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        let name = decode_path_element(name);
+        let (account_id, typ) = self
+            .core
+            .storage
+            .data
+            .get_principal_info(name.as_ref())
+            .await?
+            .filter(|p| p.has_tenant_access(access_token.tenant.map(|t| t.id)))
+            .map(|p| (p.id, p.typ))
+            .ok_or_else(|| not_found(name.to_string()))?;
+
+        // Validate the access token
+        access_token.assert_has_permission(match typ {
+            Type::Individual => Permission::IndividualUpdate,
+            Type::Group => Permission::GroupUpdate,
+            Type::List => Permission::MailingListUpdate,
+            Type::Domain => Permission::DomainUpdate,
+            Type::Tenant => Permission::TenantUpdate,
+            Type::Role => Permission::RoleUpdate,
+            Type::ApiKey => Permission::ApiKeyUpdate,
+            Type::OauthClient => Permission::OauthClientUpdate,
+            Type::Resource | Type::Location | Type::Other => Permission::PrincipalUpdate,
+        })?;
+
+        let avatar = match *req.method() {
+            Method::POST | Method::PUT => {
+                let contents = body
+                    .filter(|body| !body.is_empty())
+                    .ok_or_else(|| manage::error("Missing avatar contents", None::<u32>))?;
+                let content_type = self
+                    .core
+                    .network
+                    .avatar
+                    .validate(&contents)
+                    .map_err(|reason| manage::error("Invalid avatar", Some(reason)))?;
+
+                let hash = BlobHash::generate(&contents);
+                self.blob_store()
+                    .put_blob(hash.as_slice(), &contents)
+                    .await
+                    .caused_by(trc::location!())?;
+
+                Some(PrincipalAvatar {
+                    hash,
+                    content_type: content_type.to_string(),
+                })
+            }
+            Method::DELETE => None,
+            _ => return Err(trc::ResourceEvent::NotFound.into_err()),
+        };
+        let url = avatar.as_ref().map(|avatar| avatar.url_path(account_id));
+
+        self.core
+            .storage
+            .data
+            .set_principal_avatar(account_id, avatar)
+            .await?;
+
+        let mut changed_principals = ChangedPrincipals::default();
+        changed_principals.add_change(account_id, typ, PrincipalField::Picture);
+        self.invalidate_principal_caches(changed_principals).await;
+        if matches!(typ, Type::Domain | Type::Tenant) {
+            self.inner.data.logos.lock().clear();
+        }
+
+        Ok(JsonResponse::new(json!({
+            "data": url,
+        }))
+        .into_http_response())
+    }
+
+    async fn principal_avatar(
+        &self,
+        account_id: u32,
+        hash: &str,
+    ) -> trc::Result<Option<Resource<Vec<u8>>>> {
+        // Avatars are only served when the hash matches the principal's current avatar
+        let Some(avatar) = self
+            .core
+            .storage
+            .data
+            .get_principal(account_id)
+            .await?
+            .and_then(|principal| principal.avatar().cloned())
+            .filter(|avatar| avatar.hash.to_hex() == hash)
+        else {
+            return Ok(None);
+        };
+
+        self.blob_store()
+            .get_blob(avatar.hash.as_slice(), 0..usize::MAX)
+            .await
+            .caused_by(trc::location!())
+            .map(|contents| contents.map(|contents| Resource::new(avatar.content_type, contents)))
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken};
+use directory::{Permission, backend::internal::manage};
+use http_proto::{request::decode_path_element, *};
+use hyper::{Method, header::CONTENT_TYPE};
+use serde_json::json;
+use std::future::Future;
+use store::write::{BatchBuilder, BlobOp};
+use trc::AddContext;
+use types::blob_hash::BlobHash;
+
+pub trait ManageBranding: Sync + Send {
+    fn handle_manage_branding(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl ManageBranding for Server {
+    async fn handle_manage_branding(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        let id = path
+            .get(1)
+            .map(|id| decode_path_element(id).into_owned())
+            .filter(|id| !id.is_empty() && !id.contains('.'))
+            .ok_or_else(|| manage::error("Invalid branding id", None::<u32>))?;
+
+        match (path.get(2).copied(), req.method()) {
+            (Some("logo"), &Method::POST | &Method::PUT) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsUpdate)?;
+
+                let contents = body
+                    .filter(|body| !body.is_empty())
+                    .ok_or_else(|| manage::error("Missing logo contents", None::<u32>))?;
+                let content_type = req
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .filter(|value| value.starts_with("image/"))
+                    .ok_or_else(|| manage::error("Invalid logo content type", None::<u32>))?
+                    .to_string();
+
+                // Store the logo and link it to the branding entry
+                let hash = BlobHash::generate(&contents);
+                self.blob_store()
+                    .put_blob(hash.as_slice(), &contents)
+                    .await
+                    .caused_by(trc::location!())?;
+                let mut batch = BatchBuilder::new();
+                if let Some(prev_hash) = self.branding_logo_hash(&id).await? {
+                    batch.clear(BlobOp::LinkId {
+                        hash: prev_hash,
+                        id: logo_link_id(&id),
+                    });
+                }
+                batch
+                    .set(
+                        BlobOp::LinkId {
+                            hash: hash.clone(),
+                            id: logo_link_id(&id),
+                        },
+                        vec![],
+                    )
+                    .set(BlobOp::Commit { hash: hash.clone() }, vec![]);
+                self.store()
+                    .write(batch.build_all())
+                    .await
+                    .caused_by(trc::location!())?;
+
+                self.core
+                    .storage
+                    .config
+                    .set(
+                        [
+                            (format!("branding.{id}.logo.hash"), hash.to_hex()),
+                            (format!("branding.{id}.logo.content-type"), content_type),
+                        ],
+                        true,
+                    )
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": hash.to_hex(),
+                }))
+                .into_http_response())
+            }
+            (Some("logo"), &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsDelete)?;
+
+                if let Some(hash) = self.branding_logo_hash(&id).await? {
+                    let mut batch = BatchBuilder::new();
+                    batch.clear(BlobOp::LinkId {
+                        hash,
+                        id: logo_link_id(&id),
+                    });
+                    self.store()
+                        .write(batch.build_all())
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+                self.core
+                    .storage
+                    .config
+                    .clear_prefix(format!("branding.{id}.logo."))
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
+
+trait ManageBrandingInternal {
+    fn branding_logo_hash(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = trc::Result<Option<BlobHash>>> + Send;
+}
+
+impl ManageBrandingInternal for Server {
+    async fn branding_logo_hash(&self, id: &str) -> trc::Result<Option<BlobHash>> {
+        self.core
+            .storage
+            .config
+            .get(format!("branding.{id}.logo.hash"))
+            .await
+            .map(|hash| hash.and_then(|hash| BlobHash::try_from_hex(&hash)))
+    }
+}
+
+// Logos are linked under an id derived from the branding entry, so that
+// replacing one entry's logo does not unlink a logo shared with another
+fn logo_link_id(id: &str) -> u64 {
+    let hash = BlobHash::generate(format!("branding.{id}"));
+    u64::from_be_bytes(hash.0[..8].try_into().unwrap()) >> 1
+}
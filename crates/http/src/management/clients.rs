@@ -0,0 +1,128 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{future::Future, net::IpAddr};
+
+use common::{
+    Server,
+    auth::AccessToken,
+    listener::clients::{ConnectedClient, ConnectedClientInfo},
+};
+use directory::Permission;
+use hyper::Method;
+use serde_json::json;
+use utils::url_params::UrlParams;
+
+use http_proto::*;
+
+pub trait ManageClients: Sync + Send {
+    fn handle_manage_clients(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+struct ClientFilter<'x> {
+    protocol: Option<&'x str>,
+    account: Option<&'x str>,
+    remote_ip: Option<IpAddr>,
+}
+
+impl ManageClients for Server {
+    async fn handle_manage_clients(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        let params = UrlParams::new(req.uri().query());
+        let filter = ClientFilter {
+            protocol: params.get("protocol"),
+            account: params.get("account"),
+            remote_ip: params.parse("ip"),
+        };
+        let clients = &self.inner.data.connected_clients;
+
+        match (path.get(1).copied(), req.method()) {
+            (None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SessionList)?;
+
+                let page: usize = params.parse("page").unwrap_or_default();
+                let limit: usize = params.parse("limit").unwrap_or_default();
+                let items = clients
+                    .list()
+                    .into_iter()
+                    .filter(|client| filter.matches_info(client))
+                    .collect::<Vec<_>>();
+                let total = items.len();
+                let items = items
+                    .into_iter()
+                    .skip(page.saturating_sub(1) * limit)
+                    .take(if limit > 0 { limit } else { usize::MAX })
+                    .collect::<Vec<_>>();
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "items": items,
+                        "total": total,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some(session_id), &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SessionDisconnect)?;
+
+                if session_id
+                    .parse()
+                    .is_ok_and(|session_id| clients.disconnect(session_id))
+                {
+                    Ok(JsonResponse::new(json!({
+                        "data": (),
+                    }))
+                    .into_http_response())
+                } else {
+                    Err(trc::ResourceEvent::NotFound.into_err())
+                }
+            }
+            (None, &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SessionDisconnect)?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": clients.disconnect_all(|client| filter.matches(client)),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
+
+impl ClientFilter<'_> {
+    fn matches(&self, client: &ConnectedClient) -> bool {
+        self.protocol
+            .is_none_or(|protocol| client.protocol.as_str() == protocol)
+            && self.remote_ip.is_none_or(|ip| client.remote_ip == ip)
+            && self.account.is_none_or(|account| {
+                client
+                    .account()
+                    .is_some_and(|client_account| client_account.name == account)
+            })
+    }
+
+    fn matches_info(&self, client: &ConnectedClientInfo) -> bool {
+        self.protocol
+            .is_none_or(|protocol| client.protocol == protocol)
+            && self.remote_ip.is_none_or(|ip| client.remote_ip == ip)
+            && self
+                .account
+                .is_none_or(|account| client.account_name.as_deref() == Some(account))
+    }
+}
@@ -11,6 +11,7 @@ use directory::{
 };
 
 use hyper::Method;
+use mail_auth::hickory_resolver::proto::rr::RecordType;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sha1::Digest;
@@ -19,7 +20,7 @@ use x509_parser::parse_x509_certificate;
 
 use crate::management::dkim::{Algorithm, obtain_dkim_public_key};
 use http_proto::{request::decode_path_element, *};
-use std::future::Future;
+use std::{collections::HashMap, future::Future};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DnsRecord {
@@ -29,6 +30,28 @@ pub struct DnsRecord {
     content: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct DnsRecordCheck {
+    #[serde(rename = "type")]
+    typ: String,
+    name: String,
+    expected: String,
+    found: Vec<String>,
+    status: DnsCheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsCheckStatus {
+    Pass,
+    Mismatch,
+    Missing,
+    Unused,
+    Error,
+}
+
 pub trait DnsManagement: Sync + Send {
     fn handle_manage_dns(
         &self,
@@ -41,6 +64,11 @@ pub trait DnsManagement: Sync + Send {
         &self,
         domain_name: &str,
     ) -> impl Future<Output = trc::Result<Vec<DnsRecord>>> + Send;
+
+    fn check_dns_records(
+        &self,
+        domain_name: &str,
+    ) -> impl Future<Output = trc::Result<Vec<DnsRecordCheck>>> + Send;
 }
 
 impl DnsManagement for Server {
@@ -66,6 +94,29 @@ impl DnsManagement for Server {
                 }))
                 .into_http_response())
             }
+            ("check", Some(domain), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::DomainGet)?;
+
+                // Verify DNS records against live DNS
+                let domain = decode_path_element(domain);
+                let records = self.check_dns_records(domain.as_ref()).await?;
+                let passed = records
+                    .iter()
+                    .filter(|record| {
+                        matches!(record.status, DnsCheckStatus::Pass | DnsCheckStatus::Unused)
+                    })
+                    .count();
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "passed": passed,
+                        "failed": records.len() - passed,
+                        "records": records,
+                    },
+                }))
+                .into_http_response())
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
@@ -287,4 +338,114 @@ impl DnsManagement for Server {
 
         Ok(records)
     }
+
+    async fn check_dns_records(&self, domain_name: &str) -> trc::Result<Vec<DnsRecordCheck>> {
+        let records = self.build_dns_records(domain_name).await?;
+        let mut lookups: HashMap<(String, String), Result<Vec<String>, String>> = HashMap::new();
+        let mut checks = Vec::with_capacity(records.len());
+
+        for record in records {
+            let key = (record.name.clone(), record.typ.clone());
+            if !lookups.contains_key(&key) {
+                let result = match record.typ.parse::<RecordType>() {
+                    Ok(record_type) => self
+                        .dns_lookup_records(&record.name, record_type)
+                        .await
+                        .map_err(|err| err.to_string()),
+                    Err(err) => Err(err.to_string()),
+                };
+                lookups.insert(key.clone(), result);
+            }
+
+            let (found, status, error) = match &lookups[&key] {
+                Ok(found) => {
+                    let status = if found.iter().any(|value| record.matches(value)) {
+                        DnsCheckStatus::Pass
+                    } else if found.iter().any(|value| record.is_same_kind(value)) {
+                        DnsCheckStatus::Mismatch
+                    } else {
+                        DnsCheckStatus::Missing
+                    };
+                    (found.clone(), status, None)
+                }
+                Err(err) => (vec![], DnsCheckStatus::Error, Some(err.clone())),
+            };
+
+            checks.push(DnsRecordCheck {
+                typ: record.typ,
+                name: record.name,
+                expected: record.content,
+                found,
+                status,
+                error,
+            });
+        }
+
+        // Only one of the suggested TLSA records needs to be published for each name
+        let published_tlsa = checks
+            .iter()
+            .filter(|check| check.typ == "TLSA" && check.status == DnsCheckStatus::Pass)
+            .map(|check| check.name.clone())
+            .collect::<Vec<_>>();
+        for check in &mut checks {
+            if check.typ == "TLSA"
+                && check.status != DnsCheckStatus::Pass
+                && published_tlsa.contains(&check.name)
+            {
+                check.status = DnsCheckStatus::Unused;
+            }
+        }
+
+        Ok(checks)
+    }
+}
+
+impl DnsRecord {
+    fn matches(&self, value: &str) -> bool {
+        match self.typ.as_str() {
+            "TXT" => {
+                if self.content.starts_with("v=DKIM1") {
+                    // Compare the public key only
+                    dkim_public_key(&self.content) == dkim_public_key(value)
+                } else {
+                    normalize_txt(&self.content) == normalize_txt(value)
+                }
+            }
+            _ => self.content.eq_ignore_ascii_case(value),
+        }
+    }
+
+    fn is_same_kind(&self, value: &str) -> bool {
+        match self.typ.as_str() {
+            "TXT" => self
+                .content
+                .split_once(';')
+                .map_or(self.content.as_str(), |(version, _)| version)
+                .split_ascii_whitespace()
+                .next()
+                .is_some_and(|version| {
+                    value
+                        .trim_start()
+                        .get(..version.len())
+                        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(version))
+                }),
+            _ => true,
+        }
+    }
+}
+
+fn normalize_txt(value: &str) -> String {
+    value
+        .split(';')
+        .map(|part| part.split_ascii_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn dkim_public_key(value: &str) -> Option<String> {
+    value.split(';').find_map(|tag| {
+        let (name, value) = tag.split_once('=')?;
+        (name.trim() == "p").then(|| value.split_ascii_whitespace().collect::<String>())
+    })
 }
@@ -0,0 +1,103 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::{Server, auth::AccessToken};
+use directory::Permission;
+use hyper::Method;
+use serde_json::json;
+use services::job_manager::{Job, JobManager};
+use utils::url_params::UrlParams;
+
+use http_proto::*;
+
+pub trait ManageJobs: Sync + Send {
+    fn handle_manage_jobs(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl ManageJobs for Server {
+    async fn handle_manage_jobs(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        let tenant_id = access_token.tenant.map(|t| t.id);
+        let has_access = |job: &Job| tenant_id.is_none() || job.tenant_id == tenant_id;
+
+        match (path.get(1).copied(), req.method()) {
+            (None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::JobList)?;
+
+                let params = UrlParams::new(req.uri().query());
+                let page: usize = params.parse("page").unwrap_or_default();
+                let limit: usize = params.parse("limit").unwrap_or_default();
+                let status = params.get("status");
+                let items = self
+                    .list_jobs()
+                    .await?
+                    .into_iter()
+                    .filter(|job| {
+                        has_access(job) && status.is_none_or(|status| job.status.as_str() == status)
+                    })
+                    .collect::<Vec<_>>();
+                let total = items.len();
+                let items = items
+                    .into_iter()
+                    .rev()
+                    .skip(page.saturating_sub(1) * limit)
+                    .take(if limit > 0 { limit } else { usize::MAX })
+                    .collect::<Vec<_>>();
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "items": items,
+                        "total": total,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some(id), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::JobList)?;
+
+                let job = match id.parse() {
+                    Ok(id) => self.get_job(id).await?.filter(has_access),
+                    Err(_) => None,
+                }
+                .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": job,
+                }))
+                .into_http_response())
+            }
+            (Some(id), &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::JobCancel)?;
+
+                let job = match id.parse() {
+                    Ok(id) => self.get_job(id).await?.filter(has_access),
+                    Err(_) => None,
+                }
+                .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.cancel_job(job.id).await?,
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
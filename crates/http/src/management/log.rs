@@ -11,17 +11,27 @@ use std::{
 };
 
 use chrono::DateTime;
-use common::{Server, auth::AccessToken};
-use directory::{Permission, backend::internal::manage};
+use common::{
+    Server,
+    auth::AccessToken,
+    telemetry::tracers::memory::{RecentEvents, RecentEventsFilter},
+};
+use directory::{
+    Permission,
+    backend::internal::manage::{self, ManageDirectory},
+};
 use rev_lines::RevLines;
 use serde::Serialize;
 use serde_json::json;
 use std::future::Future;
 use tokio::sync::oneshot;
+use trc::serializers::json::JsonEventSerializer;
 use utils::url_params::UrlParams;
 
 use http_proto::*;
 
+use super::Timestamp;
+
 #[derive(Serialize)]
 struct LogEntry {
     timestamp: String,
@@ -37,6 +47,12 @@ pub trait LogManagement: Sync + Send {
         req: &HttpRequest,
         access_token: &AccessToken,
     ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn handle_view_recent_events(
+        &self,
+        req: &HttpRequest,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
 }
 
 impl LogManagement for Server {
@@ -89,6 +105,63 @@ impl LogManagement for Server {
         }))
         .into_http_response())
     }
+
+    async fn handle_view_recent_events(
+        &self,
+        req: &HttpRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        // Validate the access token
+        access_token.assert_has_permission(Permission::LogsView)?;
+
+        if !RecentEvents::is_enabled() {
+            return Err(manage::unsupported("Memory tracer not configured"));
+        }
+
+        let params = UrlParams::new(req.uri().query());
+        let mut filter = RecentEventsFilter::default();
+        for name in params
+            .get("type")
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+        {
+            let event_type = trc::EventType::try_parse(name).ok_or_else(|| {
+                trc::ResourceEvent::BadParameters
+                    .into_err()
+                    .details(format!("Unknown event type {name:?}"))
+            })?;
+            filter.types.push(event_type);
+        }
+        if let Some(account) = params.get("account") {
+            filter.account_id = if let Ok(account_id) = account.parse::<u32>() {
+                account_id
+            } else {
+                self.store()
+                    .get_principal_id(account)
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?
+            }
+            .into();
+        }
+        filter.span_id = params.parse("session");
+        filter.from = params.parse::<Timestamp>("from").map(|t| t.into_inner());
+        filter.to = params.parse::<Timestamp>("to").map(|t| t.into_inner());
+        let page: usize = params.parse("page").unwrap_or(0);
+        let limit: usize = params.parse::<usize>("limit").unwrap_or(100).min(1000);
+        let offset = page.saturating_sub(1) * limit;
+
+        let (total, items) = RecentEvents::query(&filter, offset, limit);
+
+        Ok(JsonResponse::new(json!({
+            "data": {
+                "items": JsonEventSerializer::new(items).with_id().with_spans(),
+                "total": total,
+            },
+        }))
+        .into_http_response())
+    }
 }
 
 fn read_log_files(
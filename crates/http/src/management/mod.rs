@@ -4,14 +4,23 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod avatar;
+pub mod branding;
+pub mod clients;
 pub mod crypto;
 pub mod dkim;
 pub mod dns;
+pub mod jobs;
 pub mod log;
+pub mod openapi;
 pub mod principal;
+pub mod quarantine;
 pub mod queue;
+pub mod registration;
 pub mod reload;
 pub mod report;
+pub mod search;
+pub mod sessions;
 pub mod settings;
 pub mod spam;
 pub mod stores;
@@ -19,6 +28,8 @@ pub mod troubleshoot;
 
 
 use crate::auth::oauth::auth::OAuthApiHandler;
+use branding::ManageBranding;
+use clients::ManageClients;
 use common::{Server, auth::AccessToken};
 use crypto::CryptoHandler;
 use directory::{Permission, backend::internal::manage};
@@ -28,13 +39,18 @@ use http_proto::{request::fetch_body, *};
 use hyper::{Method, StatusCode, header};
 use jmap::api::{ToJmapHttpResponse, ToRequestError};
 use jmap_proto::error::request::RequestError;
+use jobs::ManageJobs;
 use log::LogManagement;
 use mail_parser::DateTime;
 use principal::PrincipalManager;
+use quarantine::ManageQuarantine;
 use queue::QueueManagement;
+use registration::ManageClientRegistrations;
 use reload::ManageReload;
 use report::ManageReports;
+use search::UnifiedSearch;
 use serde::Serialize;
+use sessions::ManageDeviceSessions;
 use settings::ManageSettings;
 use spam::ManageSpamHandler;
 use std::future::Future;
@@ -88,7 +104,10 @@ impl ManagementApi for Server {
         let path = req.uri().path().split('/').skip(2).collect::<Vec<_>>();
 
         match path.first().copied().unwrap_or_default() {
-            "queue" => self.handle_manage_queue(req, path, &access_token).await,
+            "queue" => {
+                self.handle_manage_queue(req, path, body, &access_token)
+                    .await
+            }
             "settings" => {
                 self.handle_manage_settings(req, path, body, &access_token)
                     .await
@@ -104,11 +123,24 @@ impl ManagementApi for Server {
                     .await
             }
             "reload" => self.handle_manage_reload(req, path, &access_token).await,
+            "clients" => self.handle_manage_clients(req, path, &access_token).await,
+            "jobs" => self.handle_manage_jobs(req, path, &access_token).await,
+            "quarantine" => {
+                self.handle_manage_quarantine(req, path, &access_token)
+                    .await
+            }
             "dkim" => {
                 self.handle_manage_dkim(req, path, body, &access_token)
                     .await
             }
+            "branding" => {
+                self.handle_manage_branding(req, path, body, &access_token)
+                    .await
+            }
             "update" => self.handle_manage_update(req, path, &access_token).await,
+            "logs" if req.method() == Method::GET && path.get(1).copied() == Some("recent") => {
+                self.handle_view_recent_events(req, &access_token).await
+            }
             "logs" if req.method() == Method::GET => {
                 self.handle_view_logs(req, &access_token).await
             }
@@ -122,6 +154,10 @@ impl ManagementApi for Server {
 
                 Err(manage::unsupported("Restart is not yet supported"))
             }
+            "oauth" if path.get(1).copied() == Some("registrations") => {
+                self.handle_manage_client_registrations(req, path, &access_token)
+                    .await
+            }
             "oauth" => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::AuthenticateOauth)?;
@@ -153,8 +189,34 @@ impl ManagementApi for Server {
 
                     self.handle_account_auth_post(req, access_token, body).await
                 }
+                ("password", &Method::POST) if path.get(2).copied() == Some("validate") => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManagePasswords)?;
+
+                    self.handle_password_validate(access_token, body).await
+                }
+                ("search", &Method::GET) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::UnifiedSearch)?;
+
+                    self.handle_unified_search(req, &access_token).await
+                }
+                ("sessions", _) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManageDeviceSessions)?;
+
+                    self.handle_manage_device_sessions(
+                        req,
+                        path.get(2).copied(),
+                        access_token.primary_id,
+                    )
+                    .await
+                }
                 _ => Err(trc::ResourceEvent::NotFound.into_err()),
             },
+            "openapi.json" if req.method() == Method::GET => {
+                Ok(JsonResponse::new(openapi::build_openapi()).into_http_response())
+            }
             "troubleshoot" => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::Troubleshoot)?;
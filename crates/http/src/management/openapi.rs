@@ -0,0 +1,950 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::Permission;
+use serde_json::{Map, Value, json};
+
+pub struct ApiRoute {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub permissions: &'static [Permission],
+    pub has_body: bool,
+}
+
+const PRINCIPAL_CREATE: &[Permission] = &[
+    Permission::IndividualCreate,
+    Permission::GroupCreate,
+    Permission::MailingListCreate,
+    Permission::DomainCreate,
+    Permission::TenantCreate,
+    Permission::RoleCreate,
+    Permission::ApiKeyCreate,
+    Permission::OauthClientCreate,
+    Permission::PrincipalCreate,
+];
+const PRINCIPAL_LIST: &[Permission] = &[
+    Permission::IndividualList,
+    Permission::GroupList,
+    Permission::MailingListList,
+    Permission::DomainList,
+    Permission::TenantList,
+    Permission::RoleList,
+    Permission::ApiKeyList,
+    Permission::OauthClientList,
+    Permission::PrincipalList,
+];
+const PRINCIPAL_GET: &[Permission] = &[
+    Permission::IndividualGet,
+    Permission::GroupGet,
+    Permission::MailingListGet,
+    Permission::DomainGet,
+    Permission::TenantGet,
+    Permission::RoleGet,
+    Permission::ApiKeyGet,
+    Permission::OauthClientGet,
+    Permission::PrincipalGet,
+];
+const PRINCIPAL_UPDATE: &[Permission] = &[
+    Permission::IndividualUpdate,
+    Permission::GroupUpdate,
+    Permission::MailingListUpdate,
+    Permission::DomainUpdate,
+    Permission::TenantUpdate,
+    Permission::RoleUpdate,
+    Permission::ApiKeyUpdate,
+    Permission::OauthClientUpdate,
+    Permission::PrincipalUpdate,
+];
+const PRINCIPAL_DELETE: &[Permission] = &[
+    Permission::IndividualDelete,
+    Permission::GroupDelete,
+    Permission::MailingListDelete,
+    Permission::DomainDelete,
+    Permission::TenantDelete,
+    Permission::RoleDelete,
+    Permission::ApiKeyDelete,
+    Permission::OauthClientDelete,
+    Permission::PrincipalDelete,
+];
+
+macro_rules! route {
+    ($method:literal, $path:literal, $summary:literal, [$($perm:ident),*] $(, $body:ident)?) => {
+        ApiRoute {
+            method: $method,
+            path: $path,
+            summary: $summary,
+            permissions: &[$(Permission::$perm),*],
+            has_body: route!(@body $($body)?),
+        }
+    };
+    ($method:literal, $path:literal, $summary:literal, $perms:ident $(, $body:ident)?) => {
+        ApiRoute {
+            method: $method,
+            path: $path,
+            summary: $summary,
+            permissions: $perms,
+            has_body: route!(@body $($body)?),
+        }
+    };
+    (@body body) => { true };
+    (@body) => { false };
+}
+
+// Mirrors the routes dispatched by the management API handlers, keep in sync
+// when adding or changing endpoints.
+pub static API_ROUTES: &[ApiRoute] = &[
+    // Message queue
+    route!(
+        "get",
+        "/api/queue/messages",
+        "List queued messages",
+        [MessageQueueList]
+    ),
+    route!(
+        "patch",
+        "/api/queue/messages",
+        "Reschedule queued messages",
+        [MessageQueueUpdate]
+    ),
+    route!(
+        "delete",
+        "/api/queue/messages",
+        "Cancel queued messages",
+        [MessageQueueDelete]
+    ),
+    route!(
+        "get",
+        "/api/queue/messages/{id}",
+        "Retrieve a queued message",
+        [MessageQueueGet]
+    ),
+    route!(
+        "patch",
+        "/api/queue/messages/{id}",
+        "Reschedule a queued message",
+        [MessageQueueUpdate]
+    ),
+    route!(
+        "delete",
+        "/api/queue/messages/{id}",
+        "Cancel a queued message",
+        [MessageQueueDelete]
+    ),
+    route!(
+        "get",
+        "/api/queue/trace/{id}",
+        "Retrieve the delivery trace of a message",
+        [MessageQueueGet]
+    ),
+    route!(
+        "get",
+        "/api/queue/transcript/{id}",
+        "Retrieve the recorded transcript of an SMTP session",
+        [MessageQueueGet]
+    ),
+    route!(
+        "get",
+        "/api/queue/reports",
+        "List outgoing reports",
+        [OutgoingReportList]
+    ),
+    route!(
+        "delete",
+        "/api/queue/reports",
+        "Cancel outgoing reports",
+        [OutgoingReportDelete]
+    ),
+    route!(
+        "get",
+        "/api/queue/reports/{id}",
+        "Retrieve an outgoing report",
+        [OutgoingReportGet]
+    ),
+    route!(
+        "delete",
+        "/api/queue/reports/{id}",
+        "Cancel an outgoing report",
+        [OutgoingReportDelete]
+    ),
+    route!(
+        "get",
+        "/api/queue/sending-quota/{id}/{key}",
+        "Obtain the sending quota usage of a key",
+        [MessageQueueGet]
+    ),
+    route!(
+        "put",
+        "/api/queue/sending-quota/{id}/{key}",
+        "Override the sending quota of a key",
+        [MessageQueueUpdate],
+        body
+    ),
+    route!(
+        "delete",
+        "/api/queue/sending-quota/{id}/{key}",
+        "Reset the sending quota of a key",
+        [MessageQueueUpdate]
+    ),
+    route!(
+        "get",
+        "/api/queue/status",
+        "Obtain the queue processing status",
+        [MessageQueueGet]
+    ),
+    route!(
+        "patch",
+        "/api/queue/status/{action}",
+        "Start or stop queue processing",
+        [MessageQueueUpdate]
+    ),
+    route!(
+        "post",
+        "/api/queue/spool",
+        "Move queued message bodies to the configured spool",
+        [MessageQueueUpdate]
+    ),
+    // Incoming reports
+    route!(
+        "get",
+        "/api/reports/{class}",
+        "List incoming reports",
+        [IncomingReportList]
+    ),
+    route!(
+        "delete",
+        "/api/reports/{class}",
+        "Delete incoming reports",
+        [IncomingReportDelete]
+    ),
+    route!(
+        "get",
+        "/api/reports/{class}/{id}",
+        "Retrieve an incoming report",
+        [IncomingReportGet]
+    ),
+    route!(
+        "delete",
+        "/api/reports/{class}/{id}",
+        "Delete an incoming report",
+        [IncomingReportDelete]
+    ),
+    route!(
+        "get",
+        "/api/reports/usage",
+        "Obtain the latest usage report",
+        [MetricsList]
+    ),
+    route!(
+        "post",
+        "/api/reports/usage",
+        "Regenerate the usage report",
+        [MetricsList]
+    ),
+    route!(
+        "get",
+        "/api/reports/orphans",
+        "Obtain the latest orphaned data report",
+        [PurgeDataStore]
+    ),
+    route!(
+        "post",
+        "/api/reports/orphans",
+        "Scan for orphaned data, optionally repairing it",
+        [PurgeDataStore]
+    ),
+    // Settings
+    route!(
+        "get",
+        "/api/settings/group",
+        "List settings grouped by prefix",
+        [SettingsList]
+    ),
+    route!(
+        "get",
+        "/api/settings/list",
+        "List settings under a prefix",
+        [SettingsList]
+    ),
+    route!(
+        "get",
+        "/api/settings/keys",
+        "Retrieve setting values",
+        [SettingsList]
+    ),
+    route!(
+        "delete",
+        "/api/settings/{prefix}",
+        "Delete settings under a prefix",
+        [SettingsDelete]
+    ),
+    route!(
+        "post",
+        "/api/settings",
+        "Update settings",
+        [SettingsUpdate],
+        body
+    ),
+    route!(
+        "get",
+        "/api/reload",
+        "Reload the configuration",
+        [SettingsReload]
+    ),
+    route!(
+        "get",
+        "/api/reload/{target}",
+        "Reload lookups, certificates or blocked IPs",
+        [SettingsReload]
+    ),
+    route!("get", "/api/restart", "Restart the server", [Restart]),
+    // Connected clients
+    route!(
+        "get",
+        "/api/clients",
+        "List connected IMAP and POP3 sessions",
+        [SessionList]
+    ),
+    route!(
+        "delete",
+        "/api/clients",
+        "Disconnect all sessions matching a filter",
+        [SessionDisconnect]
+    ),
+    route!(
+        "delete",
+        "/api/clients/{id}",
+        "Disconnect a session",
+        [SessionDisconnect]
+    ),
+    // Background jobs
+    route!("get", "/api/jobs", "List background jobs", [JobList]),
+    route!(
+        "get",
+        "/api/jobs/{id}",
+        "Retrieve a background job and its progress",
+        [JobList]
+    ),
+    route!(
+        "delete",
+        "/api/jobs/{id}",
+        "Cancel a background job",
+        [JobCancel]
+    ),
+    // Upload quarantine
+    route!(
+        "get",
+        "/api/quarantine",
+        "List uploads rejected by content scanning",
+        [QuarantineList]
+    ),
+    route!(
+        "get",
+        "/api/quarantine/{id}",
+        "Retrieve the details of a quarantined upload",
+        [QuarantineGet]
+    ),
+    route!(
+        "get",
+        "/api/quarantine/{id}/contents",
+        "Download the contents of a quarantined upload",
+        [QuarantineGet]
+    ),
+    route!(
+        "delete",
+        "/api/quarantine/{id}",
+        "Remove an upload from the quarantine",
+        [QuarantineDelete]
+    ),
+    // OAuth client registrations
+    route!(
+        "get",
+        "/api/oauth/registrations",
+        "List client registrations awaiting review",
+        [OauthClientList]
+    ),
+    route!(
+        "post",
+        "/api/oauth/registrations/{client_id}",
+        "Approve a pending client registration",
+        [OauthClientCreate]
+    ),
+    route!(
+        "delete",
+        "/api/oauth/registrations/{client_id}",
+        "Reject a pending client registration",
+        [OauthClientDelete]
+    ),
+    // Principals
+    route!(
+        "post",
+        "/api/principal",
+        "Create a principal",
+        PRINCIPAL_CREATE,
+        body
+    ),
+    route!(
+        "post",
+        "/api/principal/deploy",
+        "Create a principal and its resources",
+        PRINCIPAL_CREATE,
+        body
+    ),
+    route!("get", "/api/principal", "List principals", PRINCIPAL_LIST),
+    route!(
+        "delete",
+        "/api/principal",
+        "Delete principals by type",
+        PRINCIPAL_DELETE
+    ),
+    route!(
+        "get",
+        "/api/principal/{name}",
+        "Retrieve a principal",
+        PRINCIPAL_GET
+    ),
+    route!(
+        "patch",
+        "/api/principal/{name}",
+        "Update a principal",
+        PRINCIPAL_UPDATE,
+        body
+    ),
+    route!(
+        "delete",
+        "/api/principal/{name}",
+        "Delete a principal",
+        PRINCIPAL_DELETE
+    ),
+    route!(
+        "put",
+        "/api/principal/{name}/avatar",
+        "Upload the avatar of a principal",
+        PRINCIPAL_UPDATE,
+        body
+    ),
+    route!(
+        "delete",
+        "/api/principal/{name}/avatar",
+        "Remove the avatar of a principal",
+        PRINCIPAL_UPDATE
+    ),
+    route!(
+        "get",
+        "/api/principal/{name}/sessions",
+        "List the login sessions and devices of a principal",
+        [DeviceSessionList]
+    ),
+    route!(
+        "delete",
+        "/api/principal/{name}/sessions",
+        "Revoke all login sessions of a principal",
+        [DeviceSessionRevoke]
+    ),
+    route!(
+        "delete",
+        "/api/principal/{name}/sessions/{id}",
+        "Revoke a login session of a principal",
+        [DeviceSessionRevoke]
+    ),
+    // Domains
+    route!(
+        "get",
+        "/api/dns/records/{domain}",
+        "Obtain the DNS records for a domain",
+        [DomainGet]
+    ),
+    route!(
+        "get",
+        "/api/dns/check/{domain}",
+        "Verify the DNS records of a domain against live DNS",
+        [DomainGet]
+    ),
+    route!(
+        "get",
+        "/api/dkim/{id}",
+        "Retrieve a DKIM public key",
+        [DkimSignatureGet]
+    ),
+    route!(
+        "post",
+        "/api/dkim",
+        "Create a DKIM signature",
+        [DkimSignatureCreate],
+        body
+    ),
+    // Branding
+    route!(
+        "post",
+        "/api/branding/{id}/logo",
+        "Upload the logo of a branding entry",
+        [SettingsUpdate],
+        body
+    ),
+    route!(
+        "delete",
+        "/api/branding/{id}/logo",
+        "Remove the logo of a branding entry",
+        [SettingsDelete]
+    ),
+    // Stores
+    route!(
+        "get",
+        "/api/store/blobs/{hash}",
+        "Download a blob",
+        [BlobFetch]
+    ),
+    route!(
+        "get",
+        "/api/store/purge/blob",
+        "Purge the blob store",
+        [PurgeBlobStore]
+    ),
+    route!(
+        "get",
+        "/api/store/purge/data/{id}",
+        "Purge a data store",
+        [PurgeDataStore]
+    ),
+    route!(
+        "get",
+        "/api/store/purge/in-memory/{id}",
+        "Purge an in-memory store",
+        [PurgeInMemoryStore]
+    ),
+    route!(
+        "get",
+        "/api/store/purge/account/{id}",
+        "Purge an account",
+        [PurgeAccount]
+    ),
+    route!(
+        "get",
+        "/api/store/reindex/{id}",
+        "Rebuild the full-text index",
+        [FtsReindex]
+    ),
+    route!(
+        "delete",
+        "/api/store/uids/{id}",
+        "Reset the IMAP UIDs of an account",
+        []
+    ),
+    route!(
+        "get",
+        "/api/store/quota/{id}",
+        "Obtain the quota usage of an account",
+        []
+    ),
+    route!(
+        "delete",
+        "/api/store/quota/{id}",
+        "Recalculate the quota usage of an account",
+        []
+    ),
+    route!(
+        "get",
+        "/api/store/dumpster/{id}",
+        "List the deleted messages retained in an account's dumpster",
+        [Undelete]
+    ),
+    route!(
+        "post",
+        "/api/store/dumpster/{id}/{messageId}",
+        "Restore a deleted message from an account's dumpster",
+        [Undelete]
+    ),
+    route!(
+        "get",
+        "/api/store/sieve-duplicate/{id}",
+        "List the Sieve duplicate tracking entries of an account",
+        [PurgeInMemoryStore]
+    ),
+    route!(
+        "delete",
+        "/api/store/sieve-duplicate/{id}",
+        "Purge the Sieve duplicate tracking entries of an account",
+        [PurgeInMemoryStore]
+    ),
+    // Spam filter
+    route!(
+        "post",
+        "/api/spam-filter/train/{class}",
+        "Train the spam classifier",
+        [SpamFilterTrain],
+        body
+    ),
+    route!(
+        "post",
+        "/api/spam-filter/classify",
+        "Classify a message",
+        [SpamFilterTrain],
+        body
+    ),
+    route!(
+        "get",
+        "/api/spam-filter/model/export",
+        "Export the Bayes model and reputation data",
+        [SpamFilterTrain]
+    ),
+    route!(
+        "post",
+        "/api/spam-filter/model/import",
+        "Import Bayes model and reputation data",
+        [SpamFilterTrain],
+        body
+    ),
+    route!(
+        "get",
+        "/api/spam-filter/model/stats",
+        "View spam classifier statistics",
+        [SpamFilterTrain]
+    ),
+    route!(
+        "get",
+        "/api/update/spam-filter",
+        "Update the spam filter rules",
+        [SpamFilterUpdate]
+    ),
+    route!(
+        "get",
+        "/api/update/webadmin",
+        "Update the web administration interface",
+        [WebadminUpdate]
+    ),
+    // Logs and troubleshooting
+    route!("get", "/api/logs", "View the server logs", [LogsView]),
+    route!(
+        "get",
+        "/api/logs/recent",
+        "Query the most recent events recorded by this node",
+        [LogsView]
+    ),
+    route!(
+        "get",
+        "/api/troubleshoot/token",
+        "Obtain a troubleshooting token",
+        [Troubleshoot]
+    ),
+    route!(
+        "get",
+        "/api/troubleshoot/delivery/{target}",
+        "Troubleshoot message delivery",
+        [Troubleshoot]
+    ),
+    route!(
+        "post",
+        "/api/troubleshoot/dmarc",
+        "Troubleshoot DMARC verification",
+        [Troubleshoot],
+        body
+    ),
+    route!(
+        "post",
+        "/api/troubleshoot/expression",
+        "Evaluate an expression against test variables",
+        [Troubleshoot],
+        body
+    ),
+    // Account
+    route!(
+        "post",
+        "/api/oauth",
+        "Obtain an OAuth authorization code",
+        [AuthenticateOauth],
+        body
+    ),
+    route!(
+        "get",
+        "/api/account/crypto",
+        "Obtain the encryption-at-rest settings",
+        [ManageEncryption]
+    ),
+    route!(
+        "post",
+        "/api/account/crypto",
+        "Update the encryption-at-rest settings",
+        [ManageEncryption],
+        body
+    ),
+    route!(
+        "get",
+        "/api/account/auth",
+        "Obtain the authentication settings",
+        [ManagePasswords]
+    ),
+    route!(
+        "post",
+        "/api/account/auth",
+        "Update passwords and app passwords",
+        [ManagePasswords],
+        body
+    ),
+    route!(
+        "post",
+        "/api/account/password/validate",
+        "Check a password against the password policy",
+        [ManagePasswords],
+        body
+    ),
+    route!(
+        "get",
+        "/api/account/search",
+        "Search e-mails, calendar events and contacts",
+        [UnifiedSearch]
+    ),
+    route!(
+        "get",
+        "/api/account/sessions",
+        "List own login sessions and devices",
+        [ManageDeviceSessions]
+    ),
+    route!(
+        "delete",
+        "/api/account/sessions",
+        "Revoke all own login sessions",
+        [ManageDeviceSessions]
+    ),
+    route!(
+        "delete",
+        "/api/account/sessions/{id}",
+        "Revoke an own login session",
+        [ManageDeviceSessions]
+    ),
+    route!(
+        "get",
+        "/api/openapi.json",
+        "Obtain this API description",
+        []
+    ),
+    // JMAP
+    route!(
+        "get",
+        "/jmap/session",
+        "Obtain the JMAP session object",
+        [Authenticate]
+    ),
+    route!(
+        "post",
+        "/jmap",
+        "Execute a JMAP request",
+        [Authenticate],
+        body
+    ),
+    route!(
+        "post",
+        "/jmap/upload/{accountId}",
+        "Upload a blob",
+        [Authenticate],
+        body
+    ),
+    route!(
+        "get",
+        "/jmap/download/{accountId}/{blobId}/{name}",
+        "Download a blob",
+        [Authenticate]
+    ),
+    route!(
+        "get",
+        "/jmap/eventsource",
+        "Subscribe to push notifications",
+        [Authenticate]
+    ),
+];
+
+pub fn build_openapi() -> Value {
+    let mut paths = Map::new();
+
+    for route in API_ROUTES {
+        let parameters = route
+            .path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let permissions = route
+            .permissions
+            .iter()
+            .map(|permission| permission.name())
+            .collect::<Vec<_>>();
+        let mut operation = json!({
+            "summary": route.summary,
+            "operationId": operation_id(route),
+            "tags": [route
+                .path
+                .strip_prefix("/api/")
+                .and_then(|path| path.split('/').next())
+                .unwrap_or("jmap")],
+            "parameters": parameters,
+            "x-permissions": permissions,
+            "responses": {
+                "200": {
+                    "description": "Successful response",
+                    "content": { "application/json": { "schema": { "type": "object" } } }
+                },
+                "401": { "description": "Authentication required" },
+                "403": { "description": "Insufficient permissions" },
+                "404": { "description": "Not found" }
+            }
+        });
+        if route.has_body {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": { "type": "object" } } }
+            });
+        }
+
+        paths
+            .entry(route.path)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .unwrap()
+            .insert(route.method.to_string(), operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Stalwart Management API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "components": {
+            "securitySchemes": {
+                "basicAuth": { "type": "http", "scheme": "basic" },
+                "bearerAuth": { "type": "http", "scheme": "bearer" }
+            }
+        },
+        "security": [{ "basicAuth": [] }, { "bearerAuth": [] }],
+        "paths": paths,
+    })
+}
+
+fn operation_id(route: &ApiRoute) -> String {
+    let mut id = String::from(route.method);
+    for segment in route.path.split('/').skip(1) {
+        let (segment, is_param) = match segment.strip_prefix('{') {
+            Some(param) => (param.trim_end_matches('}'), true),
+            None => (segment, false),
+        };
+        if is_param {
+            id.push_str("By");
+        }
+        for part in segment.split(['-', '.']) {
+            let mut chars = part.chars();
+            if let Some(first) = chars.next() {
+                id.extend(first.to_uppercase());
+                id.push_str(chars.as_str());
+            }
+        }
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{API_ROUTES, build_openapi};
+
+    #[test]
+    fn openapi_routes() {
+        // Operation ids must be unique for SDK generators
+        let mut ids = HashSet::new();
+        for route in API_ROUTES {
+            assert!(
+                ids.insert(super::operation_id(route)),
+                "duplicate operation id for {} {}",
+                route.method,
+                route.path
+            );
+        }
+
+        let spec = build_openapi();
+        assert_eq!(
+            spec["paths"]["/api/principal/{name}"]["patch"]["x-permissions"][0],
+            "individual-update"
+        );
+        assert_eq!(
+            spec["paths"]["/api/queue/messages/{id}"]["get"]["parameters"][0]["in"],
+            "path"
+        );
+    }
+
+    #[test]
+    fn openapi_covers_router() {
+        // Top-level segments dispatched by the management API
+        for line in include_str!("mod.rs").lines() {
+            if let Some(segment) = line
+                .strip_prefix("            \"")
+                .filter(|_| line.contains("=>"))
+                .and_then(|line| line.split('"').next())
+            {
+                let path = format!("/api/{segment}");
+                assert!(
+                    API_ROUTES
+                        .iter()
+                        .any(|route| route.path == path
+                            || route.path.starts_with(&format!("{path}/"))),
+                    "{path} is routed but missing from the OpenAPI spec"
+                );
+            }
+        }
+
+        // Routes dispatched on (segment, parameter, method) tuples
+        for (source, prefix) in [
+            (include_str!("mod.rs"), "/api/account"),
+            (include_str!("queue.rs"), "/api/queue"),
+            (include_str!("dns.rs"), "/api/dns"),
+            (include_str!("report.rs"), "/api/reports"),
+            (include_str!("troubleshoot.rs"), "/api/troubleshoot"),
+        ] {
+            for line in source.lines().map(str::trim) {
+                let Some((pattern, _)) = line
+                    .strip_prefix("(\"")
+                    .filter(|_| line.contains("&Method::"))
+                    .and_then(|line| line.split_once("=>"))
+                else {
+                    continue;
+                };
+                let (tuple, guard) = pattern.split_once(" if ").unwrap_or((pattern, ""));
+                let segment = tuple.split('"').next().unwrap();
+                let mut path = format!("{prefix}/{segment}");
+                if let Some(suffix) = guard
+                    .split_once("Some(\"")
+                    .and_then(|(_, suffix)| suffix.split('"').next())
+                {
+                    path = format!("{path}/{suffix}");
+                }
+                let has_param = tuple.contains("Some(");
+
+                for method in tuple.split("&Method::").skip(1) {
+                    let method = method
+                        .split(|ch: char| !ch.is_ascii_alphabetic())
+                        .next()
+                        .unwrap()
+                        .to_ascii_lowercase();
+                    assert!(
+                        API_ROUTES.iter().any(|route| route.method == method
+                            && if has_param {
+                                route.path.starts_with(&format!("{path}/{{"))
+                            } else {
+                                route.path == path
+                            }),
+                        "{method} {path} is routed but missing from the OpenAPI spec"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use crate::management::{avatar::ManageAvatar, sessions::ManageDeviceSessions};
 use common::{KV_BAYES_MODEL_USER, Server, auth::AccessToken};
 use directory::{
     DirectoryInner, Permission, QueryBy, QueryParams, Type,
@@ -19,6 +20,8 @@ use directory::{
 use http_proto::{request::decode_path_element, *};
 use hyper::{Method, header};
 use serde_json::json;
+use services::job_manager::{JobKind, JobManager};
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::sync::Arc;
 use trc::AddContext;
@@ -33,6 +36,13 @@ pub enum AccountAuthRequest {
     DisableOtpAuth { url: Option<String> },
     AddAppPassword { name: String, password: String },
     RemoveAppPassword { name: Option<String> },
+    AddClientCertificate { name: String, fingerprint: String },
+    RemoveClientCertificate { name: Option<String> },
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PasswordValidateRequest {
+    pub password: String,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -41,6 +51,24 @@ pub struct AccountAuthResponse {
     pub otp_auth: bool,
     #[serde(rename = "appPasswords")]
     pub app_passwords: Vec<String>,
+    #[serde(rename = "clientCertificates")]
+    pub client_certificates: Vec<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+pub enum PrincipalPatch {
+    Updates(Vec<PrincipalUpdate>),
+    Fields(PrincipalFieldsPatch),
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct PrincipalFieldsPatch {
+    pub mask: Option<Vec<PrincipalField>>,
+    pub set: BTreeMap<PrincipalField, PrincipalValue>,
+    pub add: BTreeMap<PrincipalField, PrincipalValue>,
+    pub remove: BTreeMap<PrincipalField, PrincipalValue>,
 }
 
 pub trait PrincipalManager: Sync + Send {
@@ -64,6 +92,12 @@ pub trait PrincipalManager: Sync + Send {
         body: Option<Vec<u8>>,
     ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
 
+    fn handle_password_validate(
+        &self,
+        access_token: Arc<AccessToken>,
+        body: Option<Vec<u8>>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
     fn assert_supported_directory(&self, override_: bool) -> trc::Result<()>;
 }
 
@@ -131,6 +165,11 @@ impl PrincipalManager for Server {
                     }
                 }
 
+                // Validate attributes
+                if let Some(attributes) = principal.get_str_array(PrincipalField::Attributes) {
+                    self.assert_attribute_schema(attributes)?;
+                }
+
                 // Set default report domain if missing
                 let report_domain = if principal.typ() == Type::Domain
                     && self
@@ -146,6 +185,18 @@ impl PrincipalManager for Server {
                     None
                 };
 
+                // Validate passwords
+                let mut has_password = false;
+                for secret in principal
+                    .get_str_array(PrincipalField::Secrets)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|secret| !secret.is_empty() && secret.is_password())
+                {
+                    has_password = true;
+                    self.assert_password_policy(None, secret).await?;
+                }
+
                 // Create principal
                 let result = self
                     .core
@@ -154,6 +205,11 @@ impl PrincipalManager for Server {
                     .create_principal(principal, tenant_id, Some(&access_token.permissions))
                     .await?;
 
+                // Start the password history
+                if has_password {
+                    self.record_password_change(result.id, &[]).await?;
+                }
+
                 // Set report domain
                 if let Some(report_domain) = report_domain
                     && let Err(err) = self
@@ -317,57 +373,14 @@ impl PrincipalManager for Server {
 
                 let found = !principals.items.is_empty();
                 if found {
-                    let server = self.clone();
-                    tokio::spawn(async move {
-                        let has_bayes = server
-                            .core
-                            .spam
-                            .bayes
-                            .as_ref()
-                            .is_some_and(|c| c.account_classify);
-                        for principal in principals.items {
-                            // Delete account
-                            match server
-                                .store()
-                                .delete_principal(QueryBy::Id(principal.id()))
-                                .await
-                            {
-                                Ok(changed_principals) => {
-                                    // Increment revision
-                                    server.invalidate_principal_caches(changed_principals).await;
-                                }
-                                Err(err) => {
-                                    trc::error!(err.details("Failed to delete principal"));
-                                    continue;
-                                }
-                            }
-
-                            if matches!(typ, Type::Individual | Type::Group) {
-                                // Remove FTS index
-                                if let Err(err) =
-                                    server.core.storage.fts.remove_all(principal.id()).await
-                                {
-                                    trc::error!(err.details("Failed to delete FTS index"));
-                                }
-
-                                // Delete bayes model
-                                if has_bayes {
-                                    let mut key =
-                                        Vec::with_capacity(std::mem::size_of::<u32>() + 1);
-                                    key.push(KV_BAYES_MODEL_USER);
-                                    key.extend_from_slice(&principal.id().to_be_bytes());
-
-                                    if let Err(err) =
-                                        server.in_memory_store().key_delete_prefix(&key).await
-                                    {
-                                        trc::error!(
-                                            err.details("Failed to delete user bayes model")
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                    });
+                    self.submit_job(
+                        JobKind::DeletePrincipals {
+                            principal_type: typ,
+                        },
+                        principals.items.iter().map(|p| p.id()).collect(),
+                        tenant,
+                    )
+                    .await?;
                 }
 
                 Ok(JsonResponse::new(json!({
@@ -375,6 +388,32 @@ impl PrincipalManager for Server {
                 }))
                 .into_http_response())
             }
+            (Some(name), _) if path.get(2).copied() == Some("avatar") => {
+                self.handle_manage_avatar(req, name, body, access_token)
+                    .await
+            }
+            (Some(name), method) if path.get(2).copied() == Some("sessions") => {
+                // Validate the access token
+                access_token.assert_has_permission(if *method == Method::GET {
+                    Permission::DeviceSessionList
+                } else {
+                    Permission::DeviceSessionRevoke
+                })?;
+
+                let name = decode_path_element(name);
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_info(name.as_ref())
+                    .await?
+                    .filter(|p| p.has_tenant_access(access_token.tenant.map(|t| t.id)))
+                    .map(|p| p.id)
+                    .ok_or_else(|| not_found(name.to_string()))?;
+
+                self.handle_manage_device_sessions(req, path.get(3).copied(), account_id)
+                    .await
+            }
             (Some(name), method) => {
                 // Fetch, update or delete principal
                 let name = decode_path_element(name);
@@ -420,11 +459,18 @@ impl PrincipalManager for Server {
                             .map_principal(principal, &[])
                             .await
                             .caused_by(trc::location!())?;
+                        let revision = self
+                            .core
+                            .storage
+                            .data
+                            .get_principal_revision(account_id)
+                            .await?;
 
                         Ok(JsonResponse::new(json!({
                                 "data": principal,
                         }))
-                        .into_http_response())
+                        .into_http_response()
+                        .with_etag_opt(revision.map(|revision| format!("\"{revision}\""))))
                     }
                     Method::DELETE => {
                         // Validate the access token
@@ -452,6 +498,11 @@ impl PrincipalManager for Server {
                             // Remove FTS index
                             self.core.storage.fts.remove_all(account_id).await?;
 
+                            // Revoke login sessions
+                            if let Err(err) = self.revoke_device_sessions(account_id).await {
+                                trc::error!(err.details("Failed to revoke login sessions"));
+                            }
+
                             // Delete bayes model
                             if self
                                 .core
@@ -497,13 +548,15 @@ impl PrincipalManager for Server {
                         };
                         access_token.assert_has_permission(permission_needed)?;
 
-                        let changes = serde_json::from_slice::<Vec<PrincipalUpdate>>(
+                        let changes = serde_json::from_slice::<PrincipalPatch>(
                             body.as_deref().unwrap_or_default(),
                         )
                         .map_err(|err| {
                             trc::EventType::Resource(trc::ResourceEvent::BadParameters)
                                 .from_json_error(err)
-                        })?;
+                        })?
+                        .into_updates();
+                        let revision = parse_if_match(req)?;
 
                         // Validate changes
                         let mut invalidate_logo_cache = false;
@@ -521,7 +574,9 @@ impl PrincipalManager for Server {
                                 | PrincipalField::Lists
                                 | PrincipalField::Urls
                                 | PrincipalField::ExternalMembers
-                                | PrincipalField::Locale => (),
+                                | PrincipalField::Locale
+                                | PrincipalField::CalendarAutoAdd
+                                | PrincipalField::Attributes => (),
                                 PrincipalField::Picture => {
                                     invalidate_logo_cache |=
                                         matches!(typ, Type::Domain | Type::Tenant);
@@ -593,6 +648,20 @@ impl PrincipalManager for Server {
                             }
                         }
 
+                        // Validate attributes
+                        self.assert_attribute_update_schema(account_id, &changes)
+                            .await?;
+
+                        // Validate passwords
+                        let previous_secrets = if self
+                            .assert_principal_update_policy(Some(account_id), &changes)
+                            .await?
+                        {
+                            Some(self.password_secrets(account_id).await?)
+                        } else {
+                            None
+                        };
+
                         // Update principal
                         let changed_principals = self
                             .core
@@ -602,10 +671,17 @@ impl PrincipalManager for Server {
                                 UpdatePrincipal::by_id(account_id)
                                     .with_updates(changes)
                                     .with_tenant(access_token.tenant.map(|t| t.id))
-                                    .with_allowed_permissions(&access_token.permissions),
+                                    .with_allowed_permissions(&access_token.permissions)
+                                    .with_revision(revision),
                             )
                             .await?;
 
+                        // Update password history
+                        if let Some(previous_secrets) = previous_secrets {
+                            self.record_password_change(account_id, &previous_secrets)
+                                .await?;
+                        }
+
                         // Increment revision
                         self.invalidate_principal_caches(changed_principals).await;
 
@@ -614,10 +690,18 @@ impl PrincipalManager for Server {
                             self.inner.data.logos.lock().clear();
                         }
 
+                        let revision = self
+                            .core
+                            .storage
+                            .data
+                            .get_principal_revision(account_id)
+                            .await?;
+
                         Ok(JsonResponse::new(json!({
                             "data": (),
                         }))
-                        .into_http_response())
+                        .into_http_response()
+                        .with_etag_opt(revision.map(|revision| format!("\"{revision}\""))))
                     }
                     _ => Err(trc::ResourceEvent::NotFound.into_err()),
                 }
@@ -634,6 +718,7 @@ impl PrincipalManager for Server {
         let mut response = AccountAuthResponse {
             otp_auth: false,
             app_passwords: Vec::new(),
+            client_certificates: Vec::new(),
         };
 
         if access_token.primary_id() != u32::MAX {
@@ -650,6 +735,11 @@ impl PrincipalManager for Server {
                     secret.strip_prefix("$app$").and_then(|s| s.split_once('$'))
                 {
                     response.app_passwords.push(app_name.into());
+                } else if let Some((cert_name, _)) = secret
+                    .strip_prefix("$cert$")
+                    .and_then(|s| s.split_once('$'))
+                {
+                    response.client_certificates.push(cert_name.into());
                 }
             }
         }
@@ -703,6 +793,9 @@ impl PrincipalManager for Server {
         if access_token.primary_id() == u32::MAX {
             match requests.into_iter().next().unwrap() {
                 AccountAuthRequest::SetPassword { password } => {
+                    // Validate password
+                    self.assert_password_policy(None, &password).await?;
+
                     self.core
                         .storage
                         .config
@@ -771,6 +864,34 @@ impl PrincipalManager for Server {
                     PrincipalAction::RemoveItem,
                     format!("$app${}", name.unwrap_or_default()),
                 ),
+                AccountAuthRequest::AddClientCertificate { name, fingerprint } => {
+                    let fingerprint = fingerprint
+                        .trim()
+                        .trim_start_matches("sha256:")
+                        .chars()
+                        .filter(|ch| *ch != ':')
+                        .collect::<String>()
+                        .to_ascii_lowercase();
+                    if fingerprint.len() != 64
+                        || !fingerprint.bytes().all(|ch| ch.is_ascii_hexdigit())
+                        || name.contains('$')
+                    {
+                        return Err(manage::error(
+                            "Invalid client certificate",
+                            Some("Expected a name and a hex encoded SHA-256 fingerprint"),
+                        ));
+                    }
+
+                    (
+                        PrincipalAction::AddItem,
+                        format!("$cert${name}${fingerprint}"),
+                    )
+                }
+                AccountAuthRequest::RemoveClientCertificate { name } => (
+                    PrincipalAction::RemoveItem,
+                    // Terminate the name so removing "foo" does not remove "foobar"
+                    name.map_or_else(|| "$cert$".to_string(), |name| format!("$cert${name}$")),
+                ),
             };
 
             actions.push(PrincipalUpdate {
@@ -780,6 +901,16 @@ impl PrincipalManager for Server {
             });
         }
 
+        // Validate passwords
+        let previous_secrets = if self
+            .assert_principal_update_policy(Some(access_token.primary_id()), &actions)
+            .await?
+        {
+            Some(self.password_secrets(access_token.primary_id()).await?)
+        } else {
+            None
+        };
+
         // Update password
         let changed_principals = self
             .core
@@ -792,6 +923,12 @@ impl PrincipalManager for Server {
             )
             .await?;
 
+        // Update password history
+        if let Some(previous_secrets) = previous_secrets {
+            self.record_password_change(access_token.primary_id(), &previous_secrets)
+                .await?;
+        }
+
         // Increment revision
         self.invalidate_principal_caches(changed_principals).await;
 
@@ -801,6 +938,32 @@ impl PrincipalManager for Server {
         .into_http_response())
     }
 
+    async fn handle_password_validate(
+        &self,
+        access_token: Arc<AccessToken>,
+        body: Option<Vec<u8>>,
+    ) -> trc::Result<HttpResponse> {
+        let request =
+            serde_json::from_slice::<PasswordValidateRequest>(body.as_deref().unwrap_or_default())
+                .map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+                })?;
+
+        let account_id = Some(access_token.primary_id()).filter(|id| *id != u32::MAX);
+        let violations = self
+            .validate_password(account_id, &request.password)
+            .await?;
+
+        Ok(JsonResponse::new(json!({
+            "data": {
+                "valid": violations.is_empty(),
+                "messages": violations.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+                "violations": violations,
+            },
+        }))
+        .into_http_response())
+    }
+
     fn assert_supported_directory(&self, override_: bool) -> trc::Result<()> {
         let class = match &self.core.storage.directory.store {
             DirectoryInner::Internal(_) => return Ok(()),
@@ -810,6 +973,8 @@ impl PrincipalManager for Server {
             DirectoryInner::Smtp(_) => "SMTP",
             DirectoryInner::Memory(_) => "In-Memory",
             DirectoryInner::OpenId(_) => "OpenID",
+            DirectoryInner::Rest(_) => "REST",
+            DirectoryInner::Unavailable => "Unavailable",
         };
 
         if !override_ {
@@ -826,3 +991,70 @@ impl PrincipalManager for Server {
         }
     }
 }
+
+impl PrincipalPatch {
+    pub fn into_updates(self) -> Vec<PrincipalUpdate> {
+        match self {
+            PrincipalPatch::Updates(updates) => updates,
+            PrincipalPatch::Fields(patch) => {
+                let mut updates = Vec::new();
+                let in_mask = |field: &PrincipalField| {
+                    patch.mask.as_ref().is_none_or(|mask| mask.contains(field))
+                };
+
+                for (field, value) in patch.set {
+                    if in_mask(&field) {
+                        updates.push(PrincipalUpdate::set(field, value));
+                    }
+                }
+
+                // Removals are applied first so an item can be replaced in a single patch
+                for (action, items) in [
+                    (PrincipalAction::RemoveItem, patch.remove),
+                    (PrincipalAction::AddItem, patch.add),
+                ] {
+                    for (field, value) in items {
+                        if !in_mask(&field) {
+                            continue;
+                        }
+
+                        let values = match value {
+                            PrincipalValue::StringList(values) => {
+                                values.into_iter().map(PrincipalValue::String).collect()
+                            }
+                            PrincipalValue::IntegerList(values) => {
+                                values.into_iter().map(PrincipalValue::Integer).collect()
+                            }
+                            value => vec![value],
+                        };
+
+                        updates.extend(values.into_iter().map(|value| PrincipalUpdate {
+                            action: action.clone(),
+                            field,
+                            value,
+                        }));
+                    }
+                }
+
+                updates
+            }
+        }
+    }
+}
+
+fn parse_if_match(req: &HttpRequest) -> trc::Result<Option<u32>> {
+    match req
+        .headers()
+        .get(header::IF_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim())
+    {
+        None | Some("*") => Ok(None),
+        Some(value) => value
+            .trim_start_matches("W/")
+            .trim_matches('"')
+            .parse::<u32>()
+            .map(Some)
+            .map_err(|_| trc::ManageEvent::AssertFailed.into_err()),
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::{Server, auth::AccessToken, manager::webadmin::Resource};
+use directory::Permission;
+use hyper::Method;
+use serde_json::json;
+use utils::url_params::UrlParams;
+
+use http_proto::*;
+
+pub trait ManageQuarantine: Sync + Send {
+    fn handle_manage_quarantine(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl ManageQuarantine for Server {
+    async fn handle_manage_quarantine(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        match (path.get(1).copied(), req.method()) {
+            (None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::QuarantineList)?;
+
+                let params = UrlParams::new(req.uri().query());
+                let page: usize = params.parse("page").unwrap_or_default();
+                let limit: usize = params.parse("limit").unwrap_or_default();
+                let account_id: Option<u32> = params.parse("accountId");
+                let mut items = self
+                    .quarantined_uploads()
+                    .await?
+                    .into_iter()
+                    .filter(|upload| account_id.is_none_or(|id| upload.account_id == id))
+                    .collect::<Vec<_>>();
+                items.sort_unstable_by_key(|upload| std::cmp::Reverse(upload.id));
+                let total = items.len();
+                let items = items
+                    .into_iter()
+                    .skip(page.saturating_sub(1) * limit)
+                    .take(if limit > 0 { limit } else { usize::MAX })
+                    .collect::<Vec<_>>();
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "items": items,
+                        "total": total,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some(id), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::QuarantineGet)?;
+
+                let upload = match id.parse() {
+                    Ok(id) => self.quarantined_upload(id).await?,
+                    Err(_) => None,
+                }
+                .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+
+                if path.get(2).copied() == Some("contents") {
+                    let contents = self
+                        .quarantined_upload_contents(&upload)
+                        .await?
+                        .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+
+                    Ok(Resource::new("application/octet-stream", contents).into_http_response())
+                } else {
+                    Ok(JsonResponse::new(json!({
+                        "data": upload,
+                    }))
+                    .into_http_response())
+                }
+            }
+            (Some(id), &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::QuarantineDelete)?;
+
+                let upload = match id.parse() {
+                    Ok(id) => self.quarantined_upload(id).await?,
+                    Err(_) => None,
+                }
+                .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+                self.delete_quarantined_upload(&upload).await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
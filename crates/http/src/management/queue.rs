@@ -9,8 +9,11 @@ use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use common::{
     Server,
     auth::AccessToken,
-    config::smtp::queue::{ArchivedQueueExpiry, QueueExpiry, QueueName},
+    config::smtp::queue::{
+        ArchivedQueueExpiry, QueueConfig, QueueExpiry, QueueName, SendingQuotaOverride,
+    },
     ipc::QueueEvent,
+    manager::webadmin::Resource,
 };
 use directory::{Permission, Type, backend::internal::manage::ManageDirectory};
 use http_proto::{request::decode_path_element, *};
@@ -24,8 +27,10 @@ use mail_parser::DateTime;
 use serde::{Deserializer, Serializer};
 use serde_json::json;
 use smtp::{
+    inbound::transcript::SmtpTranscript,
     queue::{
-        self, ArchivedMessage, ArchivedStatus, ErrorDetails, QueueId, Status, spool::SmtpSpool,
+        self, ArchivedMessage, ArchivedStatus, ErrorDetails, QueueId, Status,
+        sending::SendingQuotas, spool::SmtpSpool, storage::QueueStorage, trace::MessageTrace,
     },
     reporting::{dmarc::DmarcReporting, tls::TlsReporting},
 };
@@ -75,6 +80,12 @@ pub struct Recipient {
     #[serde(serialize_with = "serialize_maybe_datetime")]
     pub next_retry: Option<DateTime>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserialize_maybe_datetime")]
+    #[serde(serialize_with = "serialize_maybe_datetime")]
+    #[serde(default)]
+    pub held_until: Option<DateTime>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(deserialize_with = "deserialize_maybe_datetime")]
     #[serde(serialize_with = "serialize_maybe_datetime")]
@@ -87,6 +98,10 @@ pub struct Recipient {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub orcpt: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub bounce_category: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -124,6 +139,7 @@ pub trait QueueManagement: Sync + Send {
         &self,
         req: &HttpRequest,
         path: Vec<&str>,
+        body: Option<Vec<u8>>,
         access_token: &AccessToken,
     ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
 }
@@ -133,6 +149,7 @@ impl QueueManagement for Server {
         &self,
         req: &HttpRequest,
         path: Vec<&str>,
+        body: Option<Vec<u8>>,
         access_token: &AccessToken,
     ) -> trc::Result<HttpResponse> {
         let params = UrlParams::new(req.uri().query());
@@ -180,7 +197,7 @@ impl QueueManagement for Server {
                     let message = message_.unarchive::<queue::Message>()?;
                     if message.is_tenant_domain(&tenant_domains) {
                         return Ok(JsonResponse::new(json!({
-                                "data": Message::from_archive(queue_id, message),
+                                "data": Message::from_archive(queue_id, message, &self.core.smtp.queue),
                         }))
                         .into_http_response());
                     }
@@ -389,6 +406,36 @@ impl QueueManagement for Server {
                     Err(trc::ResourceEvent::NotFound.into_err())
                 }
             }
+            ("trace", Some(queue_id), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MessageQueueGet)?;
+
+                // Trace ids are the hexadecimal queue ids reported to the submitter
+                let entries = self
+                    .message_trace(u64::from_str_radix(&queue_id, 16).unwrap_or_default())
+                    .await?;
+                if !entries.is_empty() {
+                    Ok(JsonResponse::new(json!({
+                            "data": entries,
+                    }))
+                    .into_http_response())
+                } else {
+                    Err(trc::ResourceEvent::NotFound.into_err())
+                }
+            }
+            ("transcript", Some(session_id), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MessageQueueGet)?;
+
+                if let Some(transcript) = self
+                    .session_transcript(session_id.parse().unwrap_or_default())
+                    .await?
+                {
+                    Ok(Resource::new("text/plain", transcript).into_http_response())
+                } else {
+                    Err(trc::ResourceEvent::NotFound.into_err())
+                }
+            }
             ("reports", None, &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::OutgoingReportList)?;
@@ -515,6 +562,70 @@ impl QueueManagement for Server {
                     Err(trc::ResourceEvent::NotFound.into_err())
                 }
             }
+            ("sending-quota", Some(quota_id), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MessageQueueGet)?;
+
+                let key = path.get(3).copied().map(decode_path_element);
+                if let Some(usage) = self
+                    .sending_quota_usage(&quota_id, key.as_deref().unwrap_or_default())
+                    .await?
+                {
+                    Ok(JsonResponse::new(json!({
+                            "data": usage,
+                    }))
+                    .into_http_response())
+                } else {
+                    Err(trc::ResourceEvent::NotFound.into_err())
+                }
+            }
+            ("sending-quota", Some(quota_id), &Method::PUT | &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MessageQueueUpdate)?;
+
+                let key = path
+                    .get(3)
+                    .copied()
+                    .map(decode_path_element)
+                    .filter(|key| !key.is_empty())
+                    .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+                if !self
+                    .core
+                    .smtp
+                    .queue
+                    .sending_quotas
+                    .iter()
+                    .any(|quota| quota.id == quota_id.as_ref())
+                {
+                    return Err(trc::ResourceEvent::NotFound.into_err());
+                }
+
+                if req.method() == Method::PUT {
+                    let override_ = serde_json::from_slice::<SendingQuotaOverride>(
+                        body.as_deref().unwrap_or_default(),
+                    )
+                    .map_err(|err| {
+                        trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                            .from_json_error(err)
+                    })?;
+                    let expires = params
+                        .parse::<FutureTimestamp>("until")
+                        .map(|t| t.into_inner().saturating_sub(now()));
+
+                    self.set_sending_quota_override(&quota_id, &key, override_.into(), expires)
+                        .await?;
+                } else {
+                    // Lift any override and start counting from zero
+                    self.set_sending_quota_override(&quota_id, &key, None, None)
+                        .await?;
+                    self.reset_sending_quota(&quota_id, &key).await?;
+                }
+
+                Ok(JsonResponse::new(json!({
+                        "data": (),
+                }))
+                .into_http_response())
+            }
             ("status", None, &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::MessageQueueGet)?;
@@ -542,13 +653,22 @@ impl QueueManagement for Server {
                 }))
                 .into_http_response())
             }
+            ("spool", None, &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MessageQueueUpdate)?;
+
+                Ok(JsonResponse::new(json!({
+                        "data": self.migrate_queue_spool().await?,
+                }))
+                .into_http_response())
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
 }
 
 impl Message {
-    fn from_archive(id: u64, message: &ArchivedMessage) -> Self {
+    fn from_archive(id: u64, message: &ArchivedMessage, config: &QueueConfig) -> Self {
         let now = now();
 
         Message {
@@ -578,6 +698,16 @@ impl Message {
                     },
                     retry_num: rcpt.retry.inner.into(),
                     next_retry: Some(DateTime::from_timestamp(u64::from(rcpt.retry.due) as i64)),
+                    // Messages released in the future (RFC 4865) are held until
+                    // their first delivery attempt
+                    held_until: if matches!(rcpt.status, ArchivedStatus::Scheduled)
+                        && rcpt.retry.inner == 0
+                        && rcpt.retry.due > now
+                    {
+                        DateTime::from_timestamp(u64::from(rcpt.retry.due) as i64).into()
+                    } else {
+                        None
+                    },
                     next_notify: if rcpt.notify.due > now {
                         DateTime::from_timestamp(u64::from(rcpt.notify.due) as i64).into()
                     } else {
@@ -589,6 +719,16 @@ impl Message {
                         None
                     },
                     orcpt: rcpt.orcpt.as_ref().map(|orcpt| orcpt.to_string()),
+                    bounce_category: match &rcpt.status {
+                        ArchivedStatus::TemporaryFailure(status) => {
+                            Some(status.details.bounce_category(config, false))
+                        }
+                        ArchivedStatus::PermanentFailure(status) => {
+                            Some(status.details.bounce_category(config, true))
+                        }
+                        ArchivedStatus::Scheduled | ArchivedStatus::Completed(_) => None,
+                    }
+                    .map(|category| category.to_string()),
                 })
                 .collect(),
 
@@ -693,7 +833,11 @@ async fn fetch_queued_messages(
                         if limit == 0 || total_returned < limit {
                             let queue_id = key.deserialize_be_u64(0)?;
                             if values {
-                                result.values.push(Message::from_archive(queue_id, message));
+                                result.values.push(Message::from_archive(
+                                    queue_id,
+                                    message,
+                                    &server.core.smtp.queue,
+                                ));
                             } else {
                                 result.ids.push(queue_id);
                             }
@@ -0,0 +1,118 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::{Server, auth::AccessToken};
+use directory::{Permission, backend::internal::manage};
+use hyper::Method;
+use serde_json::json;
+use trc::AuthEvent;
+use utils::url_params::UrlParams;
+
+use http_proto::{request::decode_path_element, *};
+
+pub trait ManageClientRegistrations: Sync + Send {
+    fn handle_manage_client_registrations(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl ManageClientRegistrations for Server {
+    async fn handle_manage_client_registrations(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        match (path.get(2).copied(), req.method()) {
+            (None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::OauthClientList)?;
+
+                let params = UrlParams::new(req.uri().query());
+                let page: usize = params.parse("page").unwrap_or_default();
+                let limit: usize = params.parse("limit").unwrap_or_default();
+                let items = self.pending_client_registrations().await?;
+                let total = items.len();
+                let items = items
+                    .into_iter()
+                    .skip(page.saturating_sub(1) * limit)
+                    .take(if limit > 0 { limit } else { usize::MAX })
+                    .collect::<Vec<_>>();
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "items": items,
+                        "total": total,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some(client_id), &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::OauthClientCreate)?;
+
+                // Lifetimes can be assigned by referencing an auto-approval rule
+                let params = UrlParams::new(req.uri().query());
+                let expiry = if let Some(rule_id) = params.get("rule") {
+                    self.core
+                        .oauth
+                        .client_registration_rules
+                        .iter()
+                        .find(|rule| rule.id == rule_id)
+                        .map(|rule| rule.expiry)
+                        .ok_or_else(|| manage::not_found(rule_id.to_string()))?
+                } else {
+                    Default::default()
+                };
+
+                let client_id = decode_path_element(client_id);
+                let registration = self
+                    .take_pending_client_registration(client_id.as_ref())
+                    .await?
+                    .ok_or_else(|| manage::not_found(client_id.to_string()))?;
+                self.create_oauth_client(&registration.client_id, &registration.request, expiry)
+                    .await?;
+
+                trc::event!(
+                    Auth(AuthEvent::ClientRegistrationApproved),
+                    Id = registration.client_id,
+                    AccountName = access_token.name.clone(),
+                );
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            (Some(client_id), &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::OauthClientDelete)?;
+
+                let client_id = decode_path_element(client_id);
+                self.take_pending_client_registration(client_id.as_ref())
+                    .await?
+                    .ok_or_else(|| manage::not_found(client_id.to_string()))?;
+
+                trc::event!(
+                    Auth(AuthEvent::ClientRegistrationRejected),
+                    Id = client_id.to_string(),
+                    AccountName = access_token.name.clone(),
+                );
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
@@ -4,16 +4,12 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use common::{
-    Server,
-    auth::AccessToken,
-    ipc::{BroadcastEvent, HousekeeperEvent},
-};
+use common::{Server, auth::AccessToken, ipc::BroadcastEvent, manager::reload::ReloadScope};
 use directory::Permission;
 use hyper::Method;
 use serde_json::json;
 use std::future::Future;
-use utils::url_params::UrlParams;
+use utils::{map::bitmap::Bitmap, url_params::UrlParams};
 
 use http_proto::*;
 
@@ -72,37 +68,41 @@ impl ManageReload for Server {
                 .into_http_response())
             }
             (_, &Method::GET) => {
-                let result = self.reload().await?;
-                if !UrlParams::new(req.uri().query()).has_key("dry-run") {
-                    if let Some(core) = result.new_core {
-                        // Update core
-                        self.inner.shared_core.store(core.into());
+                let params = UrlParams::new(req.uri().query());
 
-                        self.cluster_broadcast(BroadcastEvent::ReloadSettings).await;
+                // Reload only the requested subsystems, or the ones with changed settings
+                let scopes = if let Some(scopes) = params.get("scope") {
+                    let mut bitmap = Bitmap::new();
+                    for scope in scopes.split(',').map(str::trim) {
+                        if scope == "all" {
+                            bitmap = Bitmap::all();
+                        } else if let Some(scope) = ReloadScope::parse(scope) {
+                            bitmap.insert(scope);
+                        } else {
+                            return Err(trc::ResourceEvent::BadParameters
+                                .into_err()
+                                .details("Invalid reload scope")
+                                .ctx(trc::Key::Value, scope.to_string()));
+                        }
                     }
-
-                    if let Some(tracers) = result.tracers {
-                        // Update tracers
-
-                        #[cfg(not(feature = "enterprise"))]
-                        tracers.update(false);
+                    Some(bitmap)
+                } else {
+                    None
+                };
+
+                let mut result = self.reload_scoped(scopes).await?;
+
+                if !params.has_key("dry-run") {
+                    if !result.scopes.is_empty() {
+                        self.cluster_broadcast(if scopes.is_some() {
+                            BroadcastEvent::ReloadScopes(result.scopes)
+                        } else {
+                            BroadcastEvent::ReloadSettings
+                        })
+                        .await;
                     }
 
-                    // Reload settings
-                    self.inner
-                        .ipc
-                        .housekeeper_tx
-                        .send(HousekeeperEvent::ReloadSettings)
-                        .await
-                        .map_err(|err| {
-                            trc::EventType::Server(trc::ServerEvent::ThreadError)
-                                .reason(err)
-                                .details(concat!(
-                                    "Failed to send settings reload ",
-                                    "event to housekeeper"
-                                ))
-                                .caused_by(trc::location!())
-                        })?;
+                    self.apply_reload(&mut result).await?;
                 }
 
                 Ok(JsonResponse::new(json!({
@@ -4,7 +4,12 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use common::{Server, auth::AccessToken};
+use crate::management::stores::ManageStore;
+use common::{
+    Server,
+    auth::AccessToken,
+    ipc::{HousekeeperEvent, PurgeType},
+};
 use directory::{Permission, Type, backend::internal::manage::ManageDirectory};
 use http_proto::{request::decode_path_element, *};
 use hyper::Method;
@@ -13,6 +18,7 @@ use mail_auth::report::{
     tlsrpt::{FailureDetails, Policy, TlsReport},
 };
 use serde_json::json;
+use services::housekeeper::{orphans::OrphanScanning, usage::UsageReporting};
 use smtp::reporting::analysis::IncomingReport;
 use std::future::Future;
 use store::{
@@ -240,6 +246,39 @@ impl ManageReports for Server {
                     Err(trc::ResourceEvent::NotFound.into_err())
                 }
             }
+            ("usage", None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MetricsList)?;
+
+                Ok(JsonResponse::new(json!({
+                        "data": self.usage_report().await?,
+                }))
+                .into_http_response())
+            }
+            ("usage", None, &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MetricsList)?;
+
+                self.housekeeper_request(HousekeeperEvent::UsageReport)
+                    .await
+            }
+            ("orphans", None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::PurgeDataStore)?;
+
+                Ok(JsonResponse::new(json!({
+                        "data": self.orphan_report().await?,
+                }))
+                .into_http_response())
+            }
+            ("orphans", None, &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::PurgeDataStore)?;
+
+                let repair = UrlParams::new(req.uri().query()).has_key("repair");
+                self.housekeeper_request(HousekeeperEvent::Purge(PurgeType::Orphans { repair }))
+                    .await
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
@@ -0,0 +1,384 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::{icalendar::ArchivedICalendarProperty, vcard::VCardProperty};
+use common::{DavResourceMetadata, DavResourcePath, Server, auth::AccessToken};
+use directory::backend::internal::manage;
+use email::{
+    cache::{MessageCacheFetch, email::MessageCacheAccess},
+    message::metadata::MessageMetadata,
+};
+use groupware::{
+    cache::GroupwareCache, calendar::CalendarEvent, contact::ContactCard, fts::GroupwareField,
+};
+use http_proto::*;
+use mail_parser::{ArchivedHeaderName, HeaderName, core::rkyv::ArchivedGetHeader};
+use nlp::language::{Language, search_snippet::generate_snippet, stemmer::Stemmer};
+use serde::Serialize;
+use serde_json::{Map, json};
+use std::future::Future;
+use store::{
+    ahash::AHashMap,
+    backend::MAX_TOKEN_LENGTH,
+    fts::{Field, FtsFilter},
+    roaring::RoaringBitmap,
+};
+use trc::AddContext;
+use types::{
+    collection::{Collection, SyncCollection},
+    field::EmailField,
+    id::Id,
+};
+use utils::url_params::UrlParams;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResult {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    href: Option<String>,
+    title: Option<String>,
+    preview: Option<String>,
+}
+
+struct SearchTerms {
+    text: String,
+    terms: Vec<String>,
+    language: Language,
+    is_exact: bool,
+}
+
+pub trait UnifiedSearch: Sync + Send {
+    fn handle_unified_search(
+        &self,
+        req: &HttpRequest,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl UnifiedSearch for Server {
+    async fn handle_unified_search(
+        &self,
+        req: &HttpRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        let params = UrlParams::new(req.uri().query());
+        let text = params
+            .get("text")
+            .map(|text| text.trim())
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| manage::err_missing("text"))?;
+        let types = params
+            .get("types")
+            .unwrap_or("email,calendarEvent,contactCard");
+        let limit = params
+            .parse::<usize>("limit")
+            .filter(|limit| *limit > 0)
+            .unwrap_or(10)
+            .min(self.core.jmap.snippet_max_results);
+        let terms = SearchTerms::new(text, self.core.jmap.default_language);
+        let account_id = access_token.primary_id();
+
+        let mut results = Map::new();
+        for typ in types.split(',').map(|typ| typ.trim()) {
+            let items = match typ {
+                "email" => self.search_emails(account_id, &terms, limit).await?,
+                "calendarEvent" => {
+                    self.search_groupware(access_token, SyncCollection::Calendar, &terms, limit)
+                        .await?
+                }
+                "contactCard" => {
+                    self.search_groupware(access_token, SyncCollection::AddressBook, &terms, limit)
+                        .await?
+                }
+                _ => {
+                    return Err(manage::unsupported(format!(
+                        "Unsupported search type {typ:?}"
+                    )));
+                }
+            };
+            results.insert(typ.to_string(), json!(items));
+        }
+
+        Ok(JsonResponse::new(json!({
+            "data": results,
+        }))
+        .into_http_response())
+    }
+}
+
+trait UnifiedSearchCollection {
+    fn search_emails(
+        &self,
+        account_id: u32,
+        terms: &SearchTerms,
+        limit: usize,
+    ) -> impl Future<Output = trc::Result<Vec<SearchResult>>> + Send;
+
+    fn search_groupware(
+        &self,
+        access_token: &AccessToken,
+        collection: SyncCollection,
+        terms: &SearchTerms,
+        limit: usize,
+    ) -> impl Future<Output = trc::Result<Vec<SearchResult>>> + Send;
+}
+
+impl UnifiedSearchCollection for Server {
+    async fn search_emails(
+        &self,
+        account_id: u32,
+        terms: &SearchTerms,
+        limit: usize,
+    ) -> trc::Result<Vec<SearchResult>> {
+        let default_language = self.core.jmap.default_language;
+        let mut filters = vec![FtsFilter::Or];
+        for field in [
+            Field::Header(HeaderName::Subject),
+            Field::Body,
+            Field::Attachment,
+        ] {
+            filters.push(FtsFilter::has_text_detect(
+                field,
+                terms.text.clone(),
+                default_language,
+            ));
+        }
+        for header in [HeaderName::From, HeaderName::To, HeaderName::Cc] {
+            filters.push(FtsFilter::has_text(
+                Field::Header(header),
+                terms.text.clone(),
+                Language::None,
+            ));
+        }
+        filters.push(FtsFilter::End);
+
+        let cache = self
+            .get_cached_messages(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mut hits = self
+            .fts_store()
+            .query(account_id, Collection::Email, filters)
+            .await?;
+        hits &= cache.email_document_ids();
+
+        let mut results = Vec::with_capacity(limit);
+        for document_id in hits.iter().rev() {
+            let (Some(item), Some(metadata_)) = (
+                cache.email_by_id(&document_id),
+                self.get_archive_by_property(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    EmailField::Metadata.into(),
+                )
+                .await?,
+            ) else {
+                continue;
+            };
+            let metadata = metadata_
+                .unarchive::<MessageMetadata>()
+                .caused_by(trc::location!())?;
+            let subject = metadata.contents[0]
+                .root_part()
+                .headers
+                .header_value(&ArchivedHeaderName::Subject)
+                .and_then(|value| value.as_text());
+
+            results.push(SearchResult {
+                id: Id::from_parts(item.thread_id, document_id).to_string(),
+                href: None,
+                title: subject.map(|subject| terms.snippet_or_text(subject)),
+                preview: Some(terms.snippet_or_text(metadata.preview.as_str()))
+                    .filter(|preview| !preview.is_empty()),
+            });
+
+            if results.len() == limit {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn search_groupware(
+        &self,
+        access_token: &AccessToken,
+        sync_collection: SyncCollection,
+        terms: &SearchTerms,
+        limit: usize,
+    ) -> trc::Result<Vec<SearchResult>> {
+        let account_id = access_token.primary_id();
+        let (collection, fields) = match sync_collection {
+            SyncCollection::Calendar => (
+                Collection::CalendarEvent,
+                [
+                    GroupwareField::Title,
+                    GroupwareField::Description,
+                    GroupwareField::Location,
+                    GroupwareField::Participant,
+                ],
+            ),
+            _ => (
+                Collection::ContactCard,
+                [
+                    GroupwareField::Name,
+                    GroupwareField::Email,
+                    GroupwareField::Organization,
+                    GroupwareField::Note,
+                ],
+            ),
+        };
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, sync_collection)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Only items that still exist are returned, as deleted items are
+        // removed from the full-text index asynchronously
+        let mut hrefs = AHashMap::new();
+        let mut document_ids = RoaringBitmap::new();
+        for path in &resources.paths {
+            let resource = &resources.resources[path.resource_idx];
+            if matches!(
+                resource.data,
+                DavResourceMetadata::CalendarEvent { .. } | DavResourceMetadata::ContactCard { .. }
+            ) {
+                document_ids.insert(resource.document_id);
+                hrefs.entry(resource.document_id).or_insert_with(|| {
+                    resources.format_resource(DavResourcePath { path, resource })
+                });
+            }
+        }
+
+        let mut filters = vec![FtsFilter::Or];
+        for field in fields {
+            filters.push(FtsFilter::has_text_detect(
+                Field::Header(field),
+                terms.text.clone(),
+                self.core.jmap.default_language,
+            ));
+        }
+        filters.push(FtsFilter::End);
+        let mut hits = self
+            .fts_store()
+            .query(account_id, collection, filters)
+            .await?;
+        hits &= document_ids;
+
+        let mut results = Vec::with_capacity(limit);
+        for document_id in hits.iter().rev() {
+            let Some(archive) = self
+                .get_archive(account_id, collection, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+
+            let mut title = None;
+            let mut preview = None;
+            if collection == Collection::CalendarEvent {
+                let event = archive
+                    .unarchive::<CalendarEvent>()
+                    .caused_by(trc::location!())?;
+                for entry in event
+                    .data
+                    .event
+                    .components
+                    .iter()
+                    .flat_map(|component| component.entries.iter())
+                {
+                    let Some(text) = entry.values.first().and_then(|value| value.as_text()) else {
+                        continue;
+                    };
+                    match entry.name {
+                        ArchivedICalendarProperty::Summary if title.is_none() => {
+                            title = Some(terms.snippet_or_text(text));
+                        }
+                        ArchivedICalendarProperty::Description
+                        | ArchivedICalendarProperty::Location
+                            if preview.is_none() =>
+                        {
+                            preview = terms.snippet(text);
+                        }
+                        _ => {}
+                    }
+                }
+            } else {
+                let card = archive
+                    .unarchive::<ContactCard>()
+                    .caused_by(trc::location!())?;
+                for entry in card.card.entries.iter() {
+                    let Some(text) = entry.values.first().and_then(|value| value.as_text()) else {
+                        continue;
+                    };
+                    if entry.name == VCardProperty::Fn {
+                        if title.is_none() {
+                            title = Some(terms.snippet_or_text(text));
+                        }
+                    } else if preview.is_none()
+                        && (entry.name == VCardProperty::Email
+                            || entry.name == VCardProperty::Org
+                            || entry.name == VCardProperty::Note)
+                    {
+                        preview = terms.snippet(text);
+                    }
+                }
+            }
+
+            results.push(SearchResult {
+                id: Id::from(document_id).to_string(),
+                href: hrefs.remove(&document_id),
+                title,
+                preview,
+            });
+
+            if results.len() == limit {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+impl SearchTerms {
+    fn new(text: &str, default_language: Language) -> Self {
+        let (text, language) = Language::detect(text.to_string(), default_language);
+        let mut terms = Vec::new();
+        let is_exact = (text.starts_with('"') && text.ends_with('"'))
+            || (text.starts_with('\'') && text.ends_with('\''));
+        if is_exact {
+            for token in language.tokenize_text(&text, MAX_TOKEN_LENGTH) {
+                terms.push(token.word.into_owned());
+            }
+        } else {
+            for token in Stemmer::new(&text, language, MAX_TOKEN_LENGTH) {
+                terms.push(token.word.into_owned());
+                if let Some(stemmed_word) = token.stemmed_word {
+                    terms.push(stemmed_word.into_owned());
+                }
+            }
+        }
+
+        SearchTerms {
+            text,
+            terms,
+            language,
+            is_exact,
+        }
+    }
+
+    fn snippet(&self, text: &str) -> Option<String> {
+        generate_snippet(text, &self.terms, self.language, self.is_exact)
+    }
+
+    fn snippet_or_text(&self, text: &str) -> String {
+        self.snippet(text).unwrap_or_else(|| text.to_string())
+    }
+}
@@ -0,0 +1,62 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::Server;
+use hyper::Method;
+use serde_json::json;
+
+use http_proto::*;
+
+pub trait ManageDeviceSessions: Sync + Send {
+    fn handle_manage_device_sessions(
+        &self,
+        req: &HttpRequest,
+        session_id: Option<&str>,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl ManageDeviceSessions for Server {
+    async fn handle_manage_device_sessions(
+        &self,
+        req: &HttpRequest,
+        session_id: Option<&str>,
+        account_id: u32,
+    ) -> trc::Result<HttpResponse> {
+        match (session_id, req.method()) {
+            (None, &Method::GET) => {
+                let items = self.list_device_sessions(account_id).await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "total": items.len(),
+                        "items": items,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some(session_id), &Method::DELETE) => {
+                if let Ok(session_id) = session_id.parse::<u64>()
+                    && self.revoke_device_session(account_id, session_id).await?
+                {
+                    Ok(JsonResponse::new(json!({
+                        "data": (),
+                    }))
+                    .into_http_response())
+                } else {
+                    Err(trc::ResourceEvent::NotFound.into_err())
+                }
+            }
+            (None, &Method::DELETE) => Ok(JsonResponse::new(json!({
+                "data": self.revoke_device_sessions(account_id).await?,
+            }))
+            .into_http_response()),
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
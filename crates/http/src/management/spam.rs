@@ -6,7 +6,12 @@
 
 use std::net::IpAddr;
 
-use common::{Server, auth::AccessToken, config::spamfilter::SpamFilterAction, psl};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use common::{
+    KV_BAYES_MODEL_GLOBAL, KV_BAYES_MODEL_USER, KV_REPUTATION_ASN, KV_REPUTATION_DOMAIN,
+    KV_REPUTATION_FROM, KV_REPUTATION_IP, Server, auth::AccessToken,
+    config::spamfilter::SpamFilterAction, psl,
+};
 
 use compact_str::CompactString;
 use directory::{
@@ -18,15 +23,17 @@ use mail_auth::{
     AuthenticatedMessage, DmarcResult, dmarc::verify::DmarcParameters, spf::verify::SpfParameters,
 };
 use mail_parser::{Message, MessageParser};
+use nlp::bayes::Weights;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use spam_filter::{
     SpamFilterInput,
-    analysis::{init::SpamFilterInit, score::SpamFilterAnalyzeScore},
-    modules::bayes::BayesClassifier,
+    analysis::{init::SpamFilterInit, reputation::Reputation, score::SpamFilterAnalyzeScore},
+    modules::bayes::{BayesClassifier, BayesStat},
 };
 use std::future::Future;
-use store::ahash::AHashMap;
+use store::{Deserialize as _, Serialize as _, ahash::AHashMap, dispatch::lookup::KeyValue};
+use trc::AddContext;
 
 use http_proto::{request::decode_path_element, *};
 
@@ -71,6 +78,43 @@ pub struct SpamClassifyResponse {
     pub disposition: SpamFilterDisposition<String>,
 }
 
+// Bayes tokens and reputation data, used to back up the model or seed a new cluster
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpamModel {
+    #[serde(default)]
+    pub bayes: Vec<BayesToken>,
+    #[serde(default)]
+    pub reputation: Vec<ReputationToken>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BayesToken {
+    pub token: String,
+    pub spam: u32,
+    pub ham: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationToken {
+    #[serde(rename = "type")]
+    pub typ: ReputationType,
+    pub key: String,
+    pub count: u32,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReputationType {
+    Ip,
+    From,
+    Domain,
+    Asn,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "action")]
@@ -233,6 +277,7 @@ impl ManageSpamHandler for Server {
                     dmarc_result: Some(&dmarc_result),
                     dmarc_policy: Some(&dmarc_policy),
                     iprev_result: Some(&iprev_result),
+                    helo_validation: None,
                     remote_ip: request.remote_ip,
                     ehlo_domain: Some(ehlo_domain.as_str()),
                     authenticated_as: request.authenticated_as.as_deref(),
@@ -277,11 +322,288 @@ impl ManageSpamHandler for Server {
                 }))
                 .into_http_response())
             }
+            (Some("model"), Some(action), method) => {
+                let account_id =
+                    if let Some(account) = path.get(3).copied().filter(|a| !a.is_empty()) {
+                        self.store()
+                            .get_principal_id(decode_path_element(account).as_ref())
+                            .await?
+                            .ok_or_else(|| manage::not_found(account.to_string()))?
+                            .into()
+                    } else {
+                        None
+                    };
+
+                match (action, method) {
+                    ("export", &Method::GET) => Ok(JsonResponse::new(json!({
+                        "data": export_model(self, account_id).await?,
+                    }))
+                    .into_http_response()),
+                    ("import", &Method::POST) => {
+                        let model = serde_json::from_slice::<SpamModel>(
+                            body.as_deref().unwrap_or_default(),
+                        )
+                        .map_err(|err| {
+                            trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                                .from_json_error(err)
+                        })?;
+
+                        Ok(JsonResponse::new(json!({
+                            "data": import_model(self, account_id, model).await?,
+                        }))
+                        .into_http_response())
+                    }
+                    ("stats", &Method::GET) => Ok(JsonResponse::new(json!({
+                        "data": model_stats(self, account_id).await?,
+                    }))
+                    .into_http_response()),
+                    _ => Err(trc::ResourceEvent::NotFound.into_err()),
+                }
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
 }
 
+impl ReputationType {
+    const ALL: [ReputationType; 4] = [
+        ReputationType::Ip,
+        ReputationType::From,
+        ReputationType::Domain,
+        ReputationType::Asn,
+    ];
+
+    fn prefix(&self) -> u8 {
+        match self {
+            ReputationType::Ip => KV_REPUTATION_IP,
+            ReputationType::From => KV_REPUTATION_FROM,
+            ReputationType::Domain => KV_REPUTATION_DOMAIN,
+            ReputationType::Asn => KV_REPUTATION_ASN,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ReputationType::Ip => "ip",
+            ReputationType::From => "from",
+            ReputationType::Domain => "domain",
+            ReputationType::Asn => "asn",
+        }
+    }
+}
+
+fn bayes_prefix(account_id: Option<u32>) -> Vec<u8> {
+    if let Some(account_id) = account_id {
+        let mut prefix = Vec::with_capacity(std::mem::size_of::<u32>() + 1);
+        prefix.push(KV_BAYES_MODEL_USER);
+        prefix.extend_from_slice(&account_id.to_be_bytes());
+        prefix
+    } else {
+        vec![KV_BAYES_MODEL_GLOBAL]
+    }
+}
+
+// Reputation data is global, it is only included when exporting the global model
+async fn export_model(server: &Server, account_id: Option<u32>) -> trc::Result<SpamModel> {
+    let store = server.in_memory_store();
+    let prefix = bayes_prefix(account_id);
+    let mut model = SpamModel::default();
+
+    for (key, value) in store
+        .counter_get_prefix(&prefix)
+        .await
+        .caused_by(trc::location!())?
+    {
+        let weights = Weights::from(value);
+        model.bayes.push(BayesToken {
+            token: STANDARD.encode(&key[prefix.len()..]),
+            spam: weights.spam,
+            ham: weights.ham,
+        });
+    }
+
+    if account_id.is_none() {
+        for typ in ReputationType::ALL {
+            for (key, value) in store
+                .key_get_prefix(&[typ.prefix()])
+                .await
+                .caused_by(trc::location!())?
+            {
+                let reputation = Reputation::deserialize(&value).caused_by(trc::location!())?;
+                model.reputation.push(ReputationToken {
+                    typ,
+                    key: STANDARD.encode(&key[1..]),
+                    count: reputation.count,
+                    score: reputation.score,
+                });
+            }
+        }
+    }
+
+    Ok(model)
+}
+
+// Imported Bayes weights are added to any existing ones
+async fn import_model(
+    server: &Server,
+    account_id: Option<u32>,
+    model: SpamModel,
+) -> trc::Result<serde_json::Value> {
+    let store = server.in_memory_store();
+    let prefix = bayes_prefix(account_id);
+    let reputation_expiry = server
+        .core
+        .spam
+        .reputation
+        .as_ref()
+        .map_or(2592000, |config| config.expiry);
+    let decode = |value: &str| {
+        STANDARD.decode(value).map_err(|_| {
+            trc::ResourceEvent::BadParameters
+                .into_err()
+                .details("Invalid base64 encoded key")
+                .ctx(trc::Key::Value, value.to_string())
+        })
+    };
+
+    let mut total_bayes = 0;
+    for token in &model.bayes {
+        let mut key = prefix.clone();
+        key.extend_from_slice(&decode(&token.token)?);
+        store
+            .counter_incr(
+                KeyValue::new(
+                    key,
+                    i64::from(Weights {
+                        spam: token.spam,
+                        ham: token.ham,
+                    }),
+                ),
+                false,
+            )
+            .await
+            .caused_by(trc::location!())?;
+        total_bayes += 1;
+    }
+    if account_id.is_none() && total_bayes > 0 {
+        server.inner.cache.bayes.clear();
+    }
+
+    let mut total_reputation = 0;
+    if account_id.is_none() {
+        for token in &model.reputation {
+            store
+                .key_set(
+                    KeyValue::with_prefix(
+                        token.typ.prefix(),
+                        decode(&token.key)?,
+                        Reputation {
+                            count: token.count,
+                            score: token.score,
+                        }
+                        .serialize()?,
+                    )
+                    .expires(reputation_expiry),
+                )
+                .await
+                .caused_by(trc::location!())?;
+            total_reputation += 1;
+        }
+    }
+
+    Ok(json!({
+        "bayes": total_bayes,
+        "reputation": total_reputation,
+    }))
+}
+
+async fn model_stats(server: &Server, account_id: Option<u32>) -> trc::Result<serde_json::Value> {
+    let store = server.in_memory_store();
+    let prefix = bayes_prefix(account_id);
+
+    // Token and training counts
+    let mut tokens = 0u64;
+    let mut learns = Weights::default();
+    for (key, value) in store
+        .counter_get_prefix(&prefix)
+        .await
+        .caused_by(trc::location!())?
+    {
+        if key.len() == prefix.len() {
+            learns = Weights::from(value);
+        } else {
+            tokens += 1;
+        }
+    }
+    let total_learns = learns.spam as u64 + learns.ham as u64;
+    let min_learns = server
+        .core
+        .spam
+        .bayes
+        .as_ref()
+        .map(|config| config.classifier.min_learns);
+
+    let mut stats = serde_json::Map::new();
+    stats.insert("tokens".into(), tokens.into());
+    stats.insert("spamLearns".into(), learns.spam.into());
+    stats.insert("hamLearns".into(), learns.ham.into());
+    stats.insert(
+        "spamRatio".into(),
+        if total_learns > 0 {
+            (learns.spam as f64 / total_learns as f64).into()
+        } else {
+            serde_json::Value::Null
+        },
+    );
+    stats.insert("minLearns".into(), min_learns.into());
+    stats.insert(
+        "isReady".into(),
+        min_learns
+            .is_some_and(|min_learns| learns.spam >= min_learns && learns.ham >= min_learns)
+            .into(),
+    );
+
+    // Training times and classification counters
+    for stat in [BayesStat::LastTrainSpam, BayesStat::LastTrainHam] {
+        stats.insert(
+            stat.name().into(),
+            store
+                .key_get::<i64>(stat.key(account_id))
+                .await
+                .caused_by(trc::location!())?
+                .into(),
+        );
+    }
+    for stat in BayesStat::COUNTERS {
+        stats.insert(
+            stat.name().into(),
+            store
+                .counter_get(stat.key(account_id))
+                .await
+                .caused_by(trc::location!())?
+                .into(),
+        );
+    }
+
+    if account_id.is_none() {
+        let mut reputation = serde_json::Map::new();
+        for typ in ReputationType::ALL {
+            reputation.insert(
+                typ.name().into(),
+                store
+                    .key_get_prefix(&[typ.prefix()])
+                    .await
+                    .caused_by(trc::location!())?
+                    .len()
+                    .into(),
+            );
+        }
+        stats.insert("reputation".into(), reputation.into());
+    }
+
+    Ok(stats.into())
+}
+
 fn parse_message_or_err(bytes: &[u8]) -> trc::Result<Message<'_>> {
     MessageParser::new()
         .parse(bytes)
@@ -13,15 +13,22 @@ use common::{
     *,
 };
 use directory::{
-    Permission,
+    Permission, Type,
     backend::internal::manage::{self, ManageDirectory},
 };
-use email::message::{ingest::EmailIngest, metadata::MessageData};
+use email::{
+    message::{
+        dumpster::{EmailDumpster, RestoreError},
+        ingest::EmailIngest,
+        metadata::MessageData,
+    },
+    sieve::duplicate::SieveDuplicateTracking,
+};
 use http_proto::{request::decode_path_element, *};
 use hyper::Method;
 use serde_json::json;
-use services::task_manager::fts::FtsIndexTask;
-use std::future::Future;
+use services::job_manager::{JobKind, JobManager};
+use std::{future::Future, sync::atomic::Ordering};
 use store::{
     Serialize, rand,
     write::{Archiver, BatchBuilder, ValueClass},
@@ -106,6 +113,61 @@ impl ManageStore for Server {
                 }))
                 .await
             }
+            (Some("scrub"), Some("blob"), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::PurgeBlobStore)?;
+
+                let params = UrlParams::new(req.uri().query());
+                let replica = if let Some(id) = params.get("repair-from") {
+                    Some(
+                        self.core
+                            .storage
+                            .blobs
+                            .get(id)
+                            .cloned()
+                            .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?,
+                    )
+                } else {
+                    None
+                };
+
+                self.housekeeper_request(HousekeeperEvent::Purge(PurgeType::BlobScrub {
+                    store: self.core.storage.data.clone(),
+                    blob_store: self.core.storage.blob.clone(),
+                    sample_rate: params
+                        .parse::<u8>("sample-rate")
+                        .unwrap_or(100)
+                        .clamp(1, 100),
+                    replica,
+                }))
+                .await
+            }
+            (Some("scrub"), Some("status"), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::PurgeBlobStore)?;
+
+                let status = &self.inner.data.blob_scrub;
+                let report = status.last_report.lock().as_ref().map(|report| {
+                    json!({
+                        "startedAt": report.started_at,
+                        "finishedAt": report.finished_at,
+                        "checked": report.checked,
+                        "corrupted": report.corrupted.iter().map(|hash| hash.to_hex()).collect::<Vec<_>>(),
+                        "missing": report.missing.iter().map(|hash| hash.to_hex()).collect::<Vec<_>>(),
+                        "repaired": report.repaired.iter().map(|hash| hash.to_hex()).collect::<Vec<_>>(),
+                    })
+                });
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "running": status.running.load(Ordering::Relaxed),
+                        "total": status.total.load(Ordering::Relaxed),
+                        "checked": status.checked.load(Ordering::Relaxed),
+                        "lastReport": report,
+                    },
+                }))
+                .into_http_response())
+            }
             (Some("purge"), Some("data"), id, &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::PurgeDataStore)?;
@@ -155,7 +217,10 @@ impl ManageStore for Server {
                     Some("reputation-from") => vec![KV_REPUTATION_FROM].into(),
                     Some("reputation-domain") => vec![KV_REPUTATION_DOMAIN].into(),
                     Some("reputation-asn") => vec![KV_REPUTATION_ASN].into(),
+                    Some("reputation-sender") => vec![KV_SENDER_REPUTATION].into(),
+                    Some("reputation-sender-message") => vec![KV_SENDER_REPUTATION_MSG].into(),
                     Some("greylist") => vec![KV_GREYLIST].into(),
+                    Some("sieve-duplicate") => vec![KV_SIEVE_ID].into(),
                     Some("bayes-account") => {
                         if let Some(account) = path.get(5).copied() {
                             let account_id = self
@@ -175,7 +240,10 @@ impl ManageStore for Server {
                         }
                     }
                     Some("bayes-global") => vec![KV_BAYES_MODEL_GLOBAL].into(),
+                    Some("spam-stats") => vec![KV_SPAM_STATS].into(),
                     Some("trusted-reply") => vec![KV_TRUSTED_REPLY].into(),
+                    Some("usage-report") => vec![KV_USAGE_REPORT].into(),
+                    Some("sending-quota") => vec![KV_SENDING_QUOTA].into(),
                     Some("lock-purge-account") => vec![KV_LOCK_PURGE_ACCOUNT].into(),
                     Some("lock-queue-message") => vec![KV_LOCK_QUEUE_MESSAGE].into(),
                     Some("lock-queue-report") => vec![KV_LOCK_QUEUE_REPORT].into(),
@@ -209,29 +277,46 @@ impl ManageStore for Server {
                 self.housekeeper_request(HousekeeperEvent::Purge(PurgeType::Account(account_id)))
                     .await
             }
-            (Some("reindex"), id, None, &Method::GET) => {
+            (Some(action @ ("reindex" | "keywords")), id, None, &Method::GET) => {
                 // Validate the access token
-                access_token.assert_has_permission(Permission::FtsReindex)?;
+                let kind = if action == "reindex" {
+                    access_token.assert_has_permission(Permission::FtsReindex)?;
+                    JobKind::FtsReindex
+                } else {
+                    access_token.assert_has_permission(Permission::PurgeAccount)?;
+                    JobKind::KeywordCleanup
+                };
 
-                let account_id = if let Some(id) = id {
+                let tenant_id = access_token.tenant.map(|t| t.id);
+                let account_ids = if let Some(id) = id {
+                    vec![
+                        self.core
+                            .storage
+                            .data
+                            .get_principal_id(decode_path_element(id).as_ref())
+                            .await?
+                            .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?,
+                    ]
+                } else {
                     self.core
                         .storage
                         .data
-                        .get_principal_id(decode_path_element(id).as_ref())
+                        .list_principals(
+                            None,
+                            tenant_id,
+                            &[Type::Individual, Type::Group],
+                            false,
+                            0,
+                            0,
+                        )
                         .await?
-                        .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?
-                        .into()
-                } else {
-                    None
+                        .items
+                        .into_iter()
+                        .map(|principal| principal.id())
+                        .collect()
                 };
-                let tenant_id = access_token.tenant.map(|t| t.id);
 
-                let jmap = self.clone();
-                tokio::spawn(async move {
-                    if let Err(err) = jmap.fts_reindex(account_id, tenant_id).await {
-                        trc::error!(err.details("Failed to reindex FTS"));
-                    }
-                });
+                self.submit_job(kind, account_ids, tenant_id).await?;
 
                 Ok(JsonResponse::new(json!({
                     "data": (),
@@ -274,6 +359,90 @@ impl ManageStore for Server {
                 }))
                 .into_http_response())
             }
+            (Some("dumpster"), Some(account_id), document_id, method) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Undelete)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account_id).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+
+                match (document_id, method) {
+                    (None, &Method::GET) => {
+                        let items = self.emails_dumpster_list(account_id).await?;
+
+                        Ok(JsonResponse::new(json!({
+                            "data": items,
+                        }))
+                        .into_http_response())
+                    }
+                    (Some(document_id), &Method::POST) => {
+                        let document_id = document_id.parse::<u32>().map_err(|_| {
+                            trc::ResourceEvent::BadParameters
+                                .into_err()
+                                .details("Invalid id")
+                        })?;
+                        let mailbox_id = UrlParams::new(req.uri().query()).parse::<u32>("mailbox");
+
+                        match self
+                            .emails_dumpster_restore(account_id, document_id, mailbox_id)
+                            .await?
+                        {
+                            Ok(ingested) => Ok(JsonResponse::new(json!({
+                                "data": ingested.document_id,
+                            }))
+                            .into_http_response()),
+                            Err(RestoreError::NotFound) => {
+                                Err(trc::ResourceEvent::NotFound.into_err())
+                            }
+                            Err(RestoreError::MailboxNotFound) => {
+                                Err(trc::ResourceEvent::BadParameters
+                                    .into_err()
+                                    .details("Mailbox not found"))
+                            }
+                            Err(RestoreError::OverQuota) => Err(trc::LimitEvent::Quota.into_err()),
+                        }
+                    }
+                    _ => Err(trc::ResourceEvent::NotFound.into_err()),
+                }
+            }
+            (
+                Some("sieve-duplicate"),
+                Some(account_id),
+                None,
+                method @ (&Method::GET | &Method::DELETE),
+            ) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::PurgeInMemoryStore)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account_id).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+
+                if method == Method::DELETE {
+                    self.sieve_duplicate_purge(account_id).await?;
+
+                    Ok(JsonResponse::new(json!({
+                        "data": (),
+                    }))
+                    .into_http_response())
+                } else {
+                    let result = self.sieve_duplicate_list(account_id).await?;
+
+                    Ok(JsonResponse::new(json!({
+                        "data": result,
+                    }))
+                    .into_http_response())
+                }
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
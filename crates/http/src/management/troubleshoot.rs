@@ -5,6 +5,7 @@
  */
 
 use std::{
+    collections::BTreeMap,
     future::Future,
     net::{IpAddr, SocketAddr},
     time::{Duration, Instant},
@@ -17,6 +18,10 @@ use common::{
         queue::MxConfig,
         resolver::{Policy, Tlsa},
     },
+    expr::{
+        StringCow, VARIABLES_MAP, Variable, functions::ResolveVariable, if_block::IfBlock,
+        tokenizer::TokenMap,
+    },
     psl,
 };
 use directory::backend::internal::manage;
@@ -41,7 +46,10 @@ use smtp::outbound::{
     mta_sts::{lookup::MtaStsLookup, verify::VerifyPolicy},
 };
 use tokio::{io::AsyncWriteExt, sync::mpsc};
-use utils::url_params::UrlParams;
+use utils::{
+    config::{Config, ConfigError},
+    url_params::UrlParams,
+};
 
 use http_proto::{request::decode_path_element, *};
 
@@ -121,6 +129,19 @@ impl TroubleshootApi for Server {
                 }))
                 .into_http_response())
             }
+            ("expression", None, &Method::POST) => {
+                let request = serde_json::from_slice::<ExpressionTroubleshootRequest>(
+                    body.as_deref().unwrap_or_default(),
+                )
+                .map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+                })?;
+
+                Ok(JsonResponse::new(json!({
+                        "data": expression_troubleshoot(self, request).await?,
+                }))
+                .into_http_response())
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
@@ -1204,3 +1225,167 @@ impl From<DmarcPolicy> for dmarc::Policy {
         }
     }
 }
+
+#[derive(Debug, Deserialize)]
+struct ExpressionTroubleshootRequest {
+    expression: ExpressionInput,
+    #[serde(default)]
+    variables: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ExpressionInput {
+    Single(String),
+    Block {
+        #[serde(rename = "match")]
+        if_then: Vec<ExpressionCondition>,
+        #[serde(rename = "else")]
+        default: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct ExpressionCondition {
+    #[serde(rename = "if")]
+    expr: String,
+    then: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ExpressionTroubleshootResponse {
+    result: serde_json::Value,
+    #[serde(rename = "matchedCondition")]
+    matched_condition: Option<usize>,
+    trace: Vec<ExpressionTraceStep>,
+    elapsed: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ExpressionTraceStep {
+    condition: String,
+    value: serde_json::Value,
+    matched: bool,
+}
+
+struct ExpressionVariables(Vec<(u32, Variable<'static>)>);
+
+impl ResolveVariable for ExpressionVariables {
+    fn resolve_variable(&self, variable: u32) -> Variable<'_> {
+        self.0
+            .iter()
+            .find_map(|(id, value)| (*id == variable).then(|| value.clone()))
+            .unwrap_or_default()
+    }
+
+    fn resolve_global(&self, _: &str) -> Variable<'_> {
+        Variable::default()
+    }
+}
+
+async fn expression_troubleshoot(
+    server: &Server,
+    request: ExpressionTroubleshootRequest,
+) -> trc::Result<ExpressionTroubleshootResponse> {
+    const KEY: &str = "troubleshoot.expression";
+
+    // Map variables
+    let mut variables = Vec::with_capacity(request.variables.len());
+    for (name, value) in request.variables {
+        let id = VARIABLES_MAP
+            .iter()
+            .find_map(|(var_name, id)| (*var_name == name).then_some(*id))
+            .ok_or_else(|| manage::error("Unknown variable", Some(name)))?;
+        variables.push((id, json_to_variable(value)));
+    }
+
+    // Build configuration
+    let mut config = Config::default();
+    let mut conditions = Vec::new();
+    match request.expression {
+        ExpressionInput::Single(expr) => {
+            config.keys.insert(KEY.to_string(), expr);
+        }
+        ExpressionInput::Block { if_then, default } => {
+            for (pos, condition) in if_then.into_iter().enumerate() {
+                config
+                    .keys
+                    .insert(format!("{KEY}.{pos:04}.if"), condition.expr.clone());
+                config
+                    .keys
+                    .insert(format!("{KEY}.{pos:04}.then"), condition.then);
+                conditions.push(condition.expr);
+            }
+            config
+                .keys
+                .insert(format!("{KEY}.{:04}.else", conditions.len()), default);
+        }
+    }
+
+    // Parse expression
+    let if_block = IfBlock::try_parse(&mut config, KEY, &TokenMap::default().with_all_variables());
+    if let Some((_, err)) = config.errors.iter().next() {
+        let reason = match err {
+            ConfigError::Parse { error }
+            | ConfigError::Build { error }
+            | ConfigError::Macro { error } => error.clone(),
+        };
+        return Err(manage::error("Invalid expression", Some(reason)));
+    }
+    let if_block =
+        if_block.ok_or_else(|| manage::error("Invalid expression", Some("Empty expression")))?;
+
+    // Evaluate
+    let time = Instant::now();
+    let trace = server
+        .eval_if_trace(&if_block, &ExpressionVariables(variables), 0)
+        .await
+        .map_err(|err| {
+            manage::error(
+                "Failed to evaluate expression",
+                err.value_as_str(trc::Key::Reason)
+                    .or_else(|| err.value_as_str(trc::Key::Details))
+                    .map(|v| v.to_string()),
+            )
+        })?;
+
+    Ok(ExpressionTroubleshootResponse {
+        result: variable_to_json(&trace.result),
+        matched_condition: trace.matched,
+        trace: trace
+            .conditions
+            .iter()
+            .zip(conditions)
+            .map(|(step, condition)| ExpressionTraceStep {
+                condition,
+                value: variable_to_json(&step.value),
+                matched: step.matched,
+            })
+            .collect(),
+        elapsed: time.elapsed_ms(),
+    })
+}
+
+fn json_to_variable(value: serde_json::Value) -> Variable<'static> {
+    match value {
+        serde_json::Value::String(v) => Variable::String(StringCow::Owned(v.into())),
+        serde_json::Value::Number(v) => v
+            .as_i64()
+            .map(Variable::Integer)
+            .unwrap_or_else(|| Variable::Float(v.as_f64().unwrap_or_default())),
+        serde_json::Value::Bool(v) => Variable::Integer(v as i64),
+        serde_json::Value::Array(v) => {
+            Variable::Array(v.into_iter().map(json_to_variable).collect())
+        }
+        serde_json::Value::Null | serde_json::Value::Object(_) => Variable::default(),
+    }
+}
+
+fn variable_to_json(value: &Variable<'_>) -> serde_json::Value {
+    match value {
+        Variable::String(v) => serde_json::Value::String(v.as_str().to_string()),
+        Variable::Integer(v) => (*v).into(),
+        Variable::Float(v) => (*v).into(),
+        Variable::Array(v) => v.iter().map(variable_to_json).collect(),
+    }
+}
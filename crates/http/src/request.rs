@@ -15,8 +15,10 @@ use crate::{
     },
     autoconfig::Autoconfig,
     form::FormHandler,
+    health::{DeepHealthCheck, HealthStatus},
     management::{
-        ManagementApi, ToManageHttpResponse, UnauthorizedResponse, troubleshoot::TroubleshootApi,
+        ManagementApi, ToManageHttpResponse, UnauthorizedResponse, avatar::ManageAvatar,
+        troubleshoot::TroubleshootApi,
     },
 };
 use common::{
@@ -32,7 +34,8 @@ use directory::Permission;
 use groupware::{DavResourceName, calendar::itip::ItipIngest};
 use http_proto::{
     DownloadResponse, HtmlResponse, HttpContext, HttpRequest, HttpResponse, HttpResponseBody,
-    HttpSessionData, JsonProblemResponse, ToHttpResponse, form_urlencoded, request::fetch_body,
+    HttpSessionData, JsonProblemResponse, JsonResponse, ToHttpResponse, form_urlencoded,
+    request::{byte_range, decode_path_element, fetch_body, fetch_body_with_budget, if_none_match},
 };
 use hyper::{
     Method, StatusCode, body,
@@ -128,7 +131,12 @@ impl ParseHttp for Server {
                             path.next().and_then(BlobId::from_base32),
                             path.next(),
                         ) {
+                            // Blobs are immutable, the blob id is a strong validator
+                            let etag = format!("\"{blob_id}\"");
                             return match self.blob_download(&blob_id, &access_token).await? {
+                                Some(_) if if_none_match(&req, &etag) => {
+                                    Ok(HttpResponse::new(StatusCode::NOT_MODIFIED).with_etag(etag))
+                                }
                                 Some(blob) => Ok(DownloadResponse {
                                     filename: name.to_string(),
                                     content_type: req
@@ -141,6 +149,8 @@ impl ParseHttp for Server {
                                         })
                                         .unwrap_or("application/octet-stream".to_string()),
                                     blob,
+                                    range: byte_range(&req, &etag),
+                                    etag: Some(etag),
                                 }
                                 .into_http_response()),
                                 None => Err(trc::ResourceEvent::NotFound.into_err()),
@@ -153,7 +163,16 @@ impl ParseHttp for Server {
                             self.authenticate_headers(&req, &session, false).await?;
 
                         if let Some(account_id) = path.next().and_then(|p| Id::from_str(p).ok()) {
-                            return match fetch_body(
+                            let mut buffer_in_flight =
+                                session.instance.message_limiter.is_allowed(0).map_err(
+                                    |limit| {
+                                        trc::LimitEvent::ConcurrentMessage
+                                            .into_err()
+                                            .ctx(trc::Key::Limit, limit)
+                                    },
+                                )?;
+
+                            return match fetch_body_with_budget(
                                 &mut req,
                                 if !access_token.has_permission(Permission::UnlimitedUploads) {
                                     self.core.jmap.upload_max_size
@@ -161,8 +180,9 @@ impl ParseHttp for Server {
                                     0
                                 },
                                 session.session_id,
+                                &mut buffer_in_flight,
                             )
-                            .await
+                            .await?
                             {
                                 Some(bytes) => Ok(self
                                     .blob_upload(
@@ -173,6 +193,7 @@ impl ParseHttp for Server {
                                             .unwrap_or("application/octet-stream"),
                                         &bytes,
                                         access_token,
+                                        &session,
                                     )
                                     .await?
                                     .into_http_response()),
@@ -232,7 +253,8 @@ impl ParseHttp for Server {
                             "DAV",
                             concat!(
                                 "1, 2, 3, access-control, extended-mkcol, calendar-access, ",
-                                "calendar-auto-schedule, calendar-no-timezone, addressbook"
+                                "calendar-auto-schedule, calendar-no-timezone, addressbook, ",
+                                "resource-sharing, calendarserver-sharing"
                             ),
                         )
                         .with_header(
@@ -487,6 +509,63 @@ impl ParseHttp for Server {
                         .await;
                 }
             }
+            "branding" if req.method() == Method::GET => {
+                // Limit anonymous requests
+                self.is_http_anonymous_request_allowed(&session.remote_ip)
+                    .await?;
+
+                let domain = decode_path_element(path.next().unwrap_or_default()).to_lowercase();
+                return match path.next().unwrap_or_default() {
+                    "" => {
+                        if let Some(branding) = self.branding(&domain) {
+                            Ok(JsonResponse::new(serde_json::json!({
+                                "productName": branding.product_name,
+                                "supportUrl": branding.support_url,
+                                "colors": {
+                                    "primary": branding.primary_color,
+                                    "accent": branding.accent_color,
+                                },
+                                "logoUrl": branding.logo.as_ref().map(|_| {
+                                    format!("/branding/{domain}/logo")
+                                }),
+                            }))
+                            .into_http_response())
+                        } else {
+                            Err(trc::ResourceEvent::NotFound.into_err())
+                        }
+                    }
+                    "logo" => {
+                        if let Some(logo) = self.branding_logo(&domain).await? {
+                            Ok(logo.into_http_response())
+                        } else {
+                            Err(trc::ResourceEvent::NotFound.into_err())
+                        }
+                    }
+                    _ => Err(trc::ResourceEvent::NotFound.into_err()),
+                };
+            }
+            "avatar" if req.method() == Method::GET => {
+                // Limit anonymous requests
+                self.is_http_anonymous_request_allowed(&session.remote_ip)
+                    .await?;
+
+                let account_id = path.next().and_then(|id| id.parse::<u32>().ok());
+                let hash = path.next().unwrap_or_default();
+                if let Some(account_id) = account_id {
+                    // Avatars are addressed by their hash, so they never change
+                    let etag = format!("\"{hash}\"");
+                    return match self.principal_avatar(account_id, hash).await? {
+                        Some(_) if if_none_match(&req, &etag) => {
+                            Ok(HttpResponse::new(StatusCode::NOT_MODIFIED).with_etag(etag))
+                        }
+                        Some(avatar) => Ok(avatar
+                            .into_http_response()
+                            .with_etag(etag)
+                            .with_cache_control("public, max-age=31536000, immutable")),
+                        None => Err(trc::ResourceEvent::NotFound.into_err()),
+                    };
+                }
+            }
             "robots.txt" => {
                 // Limit anonymous requests
                 self.is_http_anonymous_request_allowed(&session.remote_ip)
@@ -516,6 +595,32 @@ impl ParseHttp for Server {
                         })
                         .into_http_response());
                     }
+                    "deep" => {
+                        let Some(settings) = &self.core.network.deep_health_check else {
+                            return Err(trc::ResourceEvent::NotFound.into_err());
+                        };
+                        if let Some(auth) = &settings.auth
+                            && req
+                                .authorization_basic()
+                                .is_none_or(|secret| secret != auth)
+                        {
+                            return Err(trc::AuthEvent::Failed
+                                .into_err()
+                                .details("Invalid or missing credentials.")
+                                .caused_by(trc::location!()));
+                        }
+
+                        let report = self.cached_deep_health_check(settings.cache).await;
+                        return Ok(JsonResponse::with_status(
+                            if report.status == HealthStatus::Ok {
+                                StatusCode::OK
+                            } else {
+                                StatusCode::SERVICE_UNAVAILABLE
+                            },
+                            report,
+                        )
+                        .into_http_response());
+                    }
                     _ => (),
                 }
             }
@@ -600,6 +705,7 @@ impl ParseHttp for Server {
 async fn handle_session<T: SessionStream>(inner: Arc<Inner>, session: SessionData<T>) {
     let _in_flight = session.in_flight;
     let is_tls = session.stream.is_tls();
+    let client_cert = session.stream.client_certificate().map(Arc::new);
 
     if let Err(http_err) = http1::Builder::new()
         .keep_alive(true)
@@ -608,6 +714,7 @@ async fn handle_session<T: SessionStream>(inner: Arc<Inner>, session: SessionDat
             service_fn(|req: hyper::Request<body::Incoming>| {
                 let instance = session.instance.clone();
                 let inner = inner.clone();
+                let client_cert = client_cert.clone();
 
                 async move {
                     let server = inner.build_server();
@@ -701,6 +808,7 @@ async fn handle_session<T: SessionStream>(inner: Arc<Inner>, session: SessionDat
                             remote_port: session.remote_port,
                             is_tls,
                             session_id: session.session_id,
+                            client_cert,
                         },
                     ))
                     .await
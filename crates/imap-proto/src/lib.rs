@@ -74,6 +74,10 @@ pub enum Command {
     // RFC 9208
     GetQuota,
     GetQuotaRoot,
+
+    // RFC 5464
+    GetMetadata,
+    SetMetadata,
 }
 
 impl Command {
@@ -136,6 +140,7 @@ pub enum ResponseCode {
     UidValidity,
     Unavailable,
     UnknownCte,
+    TooBig,
 
     // CONDSTORE
     Modified {
@@ -152,6 +157,17 @@ pub enum ResponseCode {
 
     // USEATTR
     UseAttr,
+
+    // RFC 5464
+    Metadata(MetadataCode),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataCode {
+    LongEntries(usize),
+    MaxSize(usize),
+    TooMany,
+    NoPrivate,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -0,0 +1,325 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use compact_str::ToCompactString;
+
+use crate::{
+    Command,
+    protocol::metadata::{self, Depth, is_valid_entry},
+    receiver::{Request, Token, bad},
+    utf7::utf7_maybe_decode,
+};
+
+use super::parse_number;
+
+impl Request<Command> {
+    pub fn parse_get_metadata(self, is_utf8: bool) -> trc::Result<metadata::GetArguments> {
+        if self.tokens.len() < 2 {
+            return Err(self.into_error("Missing arguments."));
+        }
+
+        let mut tokens = self.tokens.into_iter().peekable();
+        let mut max_size = None;
+        let mut depth = Depth::Zero;
+
+        // Parse options
+        if tokens
+            .next_if(|token| token.is_parenthesis_open())
+            .is_some()
+        {
+            loop {
+                match tokens.next() {
+                    Some(Token::Argument(option)) => {
+                        let value = tokens
+                            .next()
+                            .ok_or_else(|| {
+                                bad(self.tag.to_compact_string(), "Missing option value.")
+                            })?
+                            .unwrap_bytes();
+                        if option.eq_ignore_ascii_case(b"MAXSIZE") {
+                            max_size = parse_number::<usize>(&value)
+                                .map_err(|v| bad(self.tag.to_compact_string(), v))?
+                                .into();
+                        } else if option.eq_ignore_ascii_case(b"DEPTH") {
+                            depth = match value.as_slice() {
+                                b"0" => Depth::Zero,
+                                b"1" => Depth::One,
+                                _ if value.eq_ignore_ascii_case(b"infinity") => Depth::Infinity,
+                                _ => {
+                                    return Err(bad(
+                                        self.tag.to_compact_string(),
+                                        "Invalid DEPTH value.",
+                                    ));
+                                }
+                            };
+                        } else {
+                            return Err(bad(
+                                self.tag.to_compact_string(),
+                                format!(
+                                    "Unsupported option {:?}.",
+                                    String::from_utf8_lossy(&option)
+                                ),
+                            ));
+                        }
+                    }
+                    Some(Token::ParenthesisClose) => break,
+                    _ => {
+                        return Err(bad(
+                            self.tag.to_compact_string(),
+                            "Invalid GETMETADATA options.",
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Parse mailbox name
+        let mailbox_name = utf7_maybe_decode(
+            tokens
+                .next()
+                .ok_or_else(|| bad(self.tag.to_compact_string(), "Missing mailbox name."))?
+                .unwrap_string()
+                .map_err(|v| bad(self.tag.to_compact_string(), v))?,
+            is_utf8,
+        );
+
+        // Parse entries
+        let mut entries = Vec::new();
+        let is_list = tokens
+            .next_if(|token| token.is_parenthesis_open())
+            .is_some();
+        #[allow(clippy::while_let_on_iterator)]
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::ParenthesisClose if is_list => break,
+                Token::Argument(_) => {
+                    let entry = token
+                        .unwrap_string()
+                        .map_err(|v| bad(self.tag.to_compact_string(), v))?;
+                    if is_valid_entry(&entry) {
+                        entries.push(entry);
+                    } else {
+                        return Err(bad(
+                            self.tag.to_compact_string(),
+                            format!("Invalid entry name {entry:?}."),
+                        ));
+                    }
+                    if !is_list {
+                        break;
+                    }
+                }
+                _ => {
+                    return Err(bad(self.tag.to_compact_string(), "Invalid entry name."));
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            Err(bad(
+                self.tag.to_compact_string(),
+                "At least one entry is required.",
+            ))
+        } else if tokens.next().is_some() {
+            Err(bad(self.tag.to_compact_string(), "Too many arguments."))
+        } else {
+            Ok(metadata::GetArguments {
+                tag: self.tag,
+                mailbox_name,
+                entries,
+                max_size,
+                depth,
+            })
+        }
+    }
+
+    pub fn parse_set_metadata(self, is_utf8: bool) -> trc::Result<metadata::SetArguments> {
+        if self.tokens.len() < 4 {
+            return Err(self.into_error("Missing arguments."));
+        }
+
+        let mut tokens = self.tokens.into_iter();
+        let mailbox_name = utf7_maybe_decode(
+            tokens
+                .next()
+                .unwrap()
+                .unwrap_string()
+                .map_err(|v| bad(self.tag.to_compact_string(), v))?,
+            is_utf8,
+        );
+
+        if tokens
+            .next()
+            .is_none_or(|token| !token.is_parenthesis_open())
+        {
+            return Err(bad(
+                self.tag.to_compact_string(),
+                "Expected parenthesis after mailbox name.",
+            ));
+        }
+
+        let mut entries = Vec::new();
+        loop {
+            match tokens.next() {
+                Some(token @ Token::Argument(_)) => {
+                    let entry = token
+                        .unwrap_string()
+                        .map_err(|v| bad(self.tag.to_compact_string(), v))?;
+                    if !is_valid_entry(&entry) {
+                        return Err(bad(
+                            self.tag.to_compact_string(),
+                            format!("Invalid entry name {entry:?}."),
+                        ));
+                    }
+                    let value = match tokens.next() {
+                        Some(Token::Nil) => None,
+                        Some(token @ Token::Argument(_)) => Some(
+                            token
+                                .unwrap_string()
+                                .map_err(|v| bad(self.tag.to_compact_string(), v))?,
+                        ),
+                        _ => {
+                            return Err(bad(
+                                self.tag.to_compact_string(),
+                                format!("Missing value for entry {entry:?}."),
+                            ));
+                        }
+                    };
+                    entries.push((entry, value));
+                }
+                Some(Token::ParenthesisClose) => break,
+                _ => {
+                    return Err(bad(
+                        self.tag.to_compact_string(),
+                        "Invalid SETMETADATA arguments.",
+                    ));
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            Err(bad(
+                self.tag.to_compact_string(),
+                "At least one entry is required.",
+            ))
+        } else if tokens.next().is_some() {
+            Err(bad(self.tag.to_compact_string(), "Too many arguments."))
+        } else {
+            Ok(metadata::SetArguments {
+                tag: self.tag,
+                mailbox_name,
+                entries,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        protocol::metadata::{self, Depth},
+        receiver::Receiver,
+    };
+
+    #[test]
+    fn parse_get_metadata() {
+        let mut receiver = Receiver::new();
+
+        for (command, arguments) in [
+            (
+                "a GETMETADATA \"\" /private/comment\r\n",
+                metadata::GetArguments {
+                    tag: "a".into(),
+                    mailbox_name: "".into(),
+                    entries: vec!["/private/comment".into()],
+                    max_size: None,
+                    depth: Depth::Zero,
+                },
+            ),
+            (
+                "a GETMETADATA INBOX (/shared/comment /private/comment)\r\n",
+                metadata::GetArguments {
+                    tag: "a".into(),
+                    mailbox_name: "INBOX".into(),
+                    entries: vec!["/shared/comment".into(), "/private/comment".into()],
+                    max_size: None,
+                    depth: Depth::Zero,
+                },
+            ),
+            (
+                "a GETMETADATA (MAXSIZE 1024 DEPTH infinity) INBOX (/shared/vendor)\r\n",
+                metadata::GetArguments {
+                    tag: "a".into(),
+                    mailbox_name: "INBOX".into(),
+                    entries: vec!["/shared/vendor".into()],
+                    max_size: Some(1024),
+                    depth: Depth::Infinity,
+                },
+            ),
+        ] {
+            assert_eq!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_get_metadata(true)
+                    .unwrap(),
+                arguments,
+                "{command}"
+            );
+        }
+
+        for command in [
+            "a GETMETADATA INBOX /comment\r\n",
+            "a GETMETADATA (DEPTH 2) INBOX /private/comment\r\n",
+            "a GETMETADATA INBOX ()\r\n",
+        ] {
+            assert!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_get_metadata(true)
+                    .is_err(),
+                "{command}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_set_metadata() {
+        let mut receiver = Receiver::new();
+
+        for (command, arguments) in [
+            (
+                "a SETMETADATA INBOX (/private/comment \"My notes\")\r\n",
+                metadata::SetArguments {
+                    tag: "a".into(),
+                    mailbox_name: "INBOX".into(),
+                    entries: vec![("/private/comment".into(), Some("My notes".into()))],
+                },
+            ),
+            (
+                "a SETMETADATA \"\" (/shared/comment NIL /private/color \"#ff0000\")\r\n",
+                metadata::SetArguments {
+                    tag: "a".into(),
+                    mailbox_name: "".into(),
+                    entries: vec![
+                        ("/shared/comment".into(), None),
+                        ("/private/color".into(), Some("#ff0000".into())),
+                    ],
+                },
+            ),
+        ] {
+            assert_eq!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_set_metadata(true)
+                    .unwrap(),
+                arguments,
+                "{command}"
+            );
+        }
+    }
+}
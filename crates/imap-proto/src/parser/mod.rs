@@ -15,6 +15,7 @@ pub mod fetch;
 pub mod list;
 pub mod login;
 pub mod lsub;
+pub mod metadata;
 pub mod quota;
 pub mod rename;
 pub mod search;
@@ -80,6 +81,8 @@ impl CommandParser for Command {
             "ID" => Command::Id,
             "GETQUOTA" => Command::GetQuota,
             "GETQUOTAROOT" => Command::GetQuotaRoot,
+            "GETMETADATA" => Command::GetMetadata,
+            "SETMETADATA" => Command::SetMetadata,
         )
     }
 
@@ -42,7 +42,8 @@ pub enum Capability {
     Move,
     CondStore,
     QResync,
-    LiteralPlus, //LITERAL+
+    LiteralPlus,  //LITERAL+
+    LiteralMinus, //LITERAL-
     UnAuthenticate,
     StatusSize, //STATUS=SIZE
     ObjectId,
@@ -53,6 +54,8 @@ pub enum Capability {
     QuotaResource(QuotaResourceName),
     QuotaSet,
     JmapAccess,
+    Metadata,
+    MetadataServer, //METADATA-SERVER
 }
 
 /*
@@ -87,6 +90,7 @@ impl Capability {
             Capability::CondStore => b"CONDSTORE",
             Capability::QResync => b"QRESYNC",
             Capability::LiteralPlus => b"LITERAL+",
+            Capability::LiteralMinus => b"LITERAL-",
             Capability::UnAuthenticate => b"UNAUTHENTICATE",
             Capability::StatusSize => b"STATUS=SIZE",
             Capability::ObjectId => b"OBJECTID",
@@ -128,16 +132,26 @@ impl Capability {
             }
             Capability::QuotaSet => b"QUOTA=SET",
             Capability::JmapAccess => b"JMAPACCESS",
+            Capability::Metadata => b"METADATA",
+            Capability::MetadataServer => b"METADATA-SERVER",
         });
     }
 
-    pub fn all_capabilities(is_authenticated: bool, offer_tls: bool) -> Vec<Capability> {
+    pub fn all_capabilities(
+        is_authenticated: bool,
+        offer_tls: bool,
+        literal_minus: bool,
+    ) -> Vec<Capability> {
         let mut capabilities = vec![
             Capability::IMAP4rev2,
             Capability::IMAP4rev1,
             Capability::Enable,
             Capability::SASLIR,
-            Capability::LiteralPlus,
+            if literal_minus {
+                Capability::LiteralMinus
+            } else {
+                Capability::LiteralPlus
+            },
             Capability::Id,
             Capability::Utf8Accept,
             Capability::JmapAccess,
@@ -173,6 +187,8 @@ impl Capability {
                 Capability::Preview,
                 Capability::Quota,
                 Capability::QuotaResource(QuotaResourceName::Storage),
+                Capability::Metadata,
+                Capability::MetadataServer,
             ]);
         } else {
             capabilities.extend([
@@ -0,0 +1,131 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{quoted_or_literal_string_or_nil, quoted_string};
+use crate::utf7::utf7_encode;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetArguments {
+    pub tag: String,
+    pub mailbox_name: String,
+    pub entries: Vec<String>,
+    pub max_size: Option<usize>,
+    pub depth: Depth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetArguments {
+    pub tag: String,
+    pub mailbox_name: String,
+    pub entries: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Depth {
+    #[default]
+    Zero,
+    One,
+    Infinity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub mailbox_name: String,
+    pub entries: Vec<(String, Option<String>)>,
+}
+
+impl Response {
+    pub fn serialize(&self, buf: &mut Vec<u8>, is_utf8: bool) {
+        buf.extend_from_slice(b"* METADATA ");
+        if is_utf8 || self.mailbox_name.is_empty() {
+            quoted_string(buf, &self.mailbox_name);
+        } else {
+            quoted_string(buf, &utf7_encode(&self.mailbox_name));
+        }
+        buf.extend_from_slice(b" (");
+        for (pos, (entry, value)) in self.entries.iter().enumerate() {
+            if pos > 0 {
+                buf.push(b' ');
+            }
+            quoted_string(buf, entry);
+            buf.push(b' ');
+            quoted_or_literal_string_or_nil(buf, value.as_deref());
+        }
+        buf.extend_from_slice(b")\r\n");
+    }
+}
+
+impl Depth {
+    pub fn includes(&self, entry: &str, prefix: &str) -> bool {
+        if entry == prefix {
+            true
+        } else if let Some(child) = entry
+            .strip_prefix(prefix)
+            .and_then(|child| child.strip_prefix('/'))
+        {
+            match self {
+                Depth::Zero => false,
+                Depth::One => !child.contains('/'),
+                Depth::Infinity => true,
+            }
+        } else {
+            false
+        }
+    }
+}
+
+pub fn is_valid_entry(entry: &str) -> bool {
+    (entry.starts_with("/private/") || entry.starts_with("/shared/"))
+        && !entry.ends_with('/')
+        && !entry.contains("//")
+        && !entry.contains(['*', '%'])
+        && entry.bytes().all(|ch| ch > 0x19 && ch < 0x7f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Depth, Response};
+
+    #[test]
+    fn serialize_metadata() {
+        let mut buf = Vec::new();
+        Response {
+            mailbox_name: "INBOX".into(),
+            entries: vec![
+                ("/private/comment".into(), Some("My own comment".into())),
+                ("/shared/comment".into(), None),
+                ("/shared/vendor/x".into(), Some("line 1\r\nline 2".into())),
+            ],
+        }
+        .serialize(&mut buf, true);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            concat!(
+                "* METADATA \"INBOX\" (\"/private/comment\" \"My own comment\" ",
+                "\"/shared/comment\" NIL \"/shared/vendor/x\" {14}\r\nline 1\r\nline 2)\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn metadata_depth() {
+        for (depth, entry, expected) in [
+            (Depth::Zero, "/private/comment", true),
+            (Depth::Zero, "/private/comment/a", false),
+            (Depth::One, "/private/comment/a", true),
+            (Depth::One, "/private/comment/a/b", false),
+            (Depth::Infinity, "/private/comment/a/b", true),
+            (Depth::Infinity, "/private/commentary", false),
+        ] {
+            assert_eq!(
+                depth.includes(entry, "/private/comment"),
+                expected,
+                "{depth:?} {entry}"
+            );
+        }
+    }
+}
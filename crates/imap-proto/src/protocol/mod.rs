@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use crate::{Command, ResponseCode, ResponseType, StatusResponse};
+use crate::{Command, MetadataCode, ResponseCode, ResponseType, StatusResponse};
 use ahash::AHashSet;
 use chrono::{DateTime, Utc};
 use compact_str::CompactString;
@@ -23,6 +23,7 @@ pub mod expunge;
 pub mod fetch;
 pub mod list;
 pub mod login;
+pub mod metadata;
 pub mod namespace;
 pub mod quota;
 pub mod rename;
@@ -382,6 +383,7 @@ impl ResponseCode {
             ResponseCode::UidValidity => b"UIDVALIDITY",
             ResponseCode::Unavailable => b"UNAVAILABLE",
             ResponseCode::UnknownCte => b"UNKNOWN-CTE",
+            ResponseCode::TooBig => b"TOOBIG",
             ResponseCode::Modified { ids } => {
                 buf.extend_from_slice(b"MODIFIED ");
                 serialize_sequence(buf, ids);
@@ -399,6 +401,22 @@ impl ResponseCode {
                 return;
             }
             ResponseCode::UseAttr => b"USEATTR",
+            ResponseCode::Metadata(code) => {
+                buf.extend_from_slice(b"METADATA ");
+                match code {
+                    MetadataCode::LongEntries(size) => {
+                        buf.extend_from_slice(b"LONGENTRIES ");
+                        buf.extend_from_slice(size.to_string().as_bytes());
+                    }
+                    MetadataCode::MaxSize(size) => {
+                        buf.extend_from_slice(b"MAXSIZE ");
+                        buf.extend_from_slice(size.to_string().as_bytes());
+                    }
+                    MetadataCode::TooMany => buf.extend_from_slice(b"TOOMANY"),
+                    MetadataCode::NoPrivate => buf.extend_from_slice(b"NOPRIVATE"),
+                }
+                return;
+            }
         });
     }
 
@@ -438,10 +456,17 @@ impl ResponseCode {
             ResponseCode::UidValidity => "UIDVALIDITY",
             ResponseCode::Unavailable => "UNAVAILABLE",
             ResponseCode::UnknownCte => "UNKNOWN-CTE",
+            ResponseCode::TooBig => "TOOBIG",
             ResponseCode::Modified { .. } => "MODIFIED",
             ResponseCode::MailboxId { .. } => "MAILBOXID",
             ResponseCode::HighestModseq { .. } => "HIGHESTMODSEQ",
             ResponseCode::UseAttr => "USEATTR",
+            ResponseCode::Metadata(code) => match code {
+                MetadataCode::LongEntries(_) => "METADATA LONGENTRIES",
+                MetadataCode::MaxSize(_) => "METADATA MAXSIZE",
+                MetadataCode::TooMany => "METADATA TOOMANY",
+                MetadataCode::NoPrivate => "METADATA NOPRIVATE",
+            },
         }
     }
 }
@@ -633,6 +658,8 @@ impl Display for Command {
             Command::Id => write!(f, "ID"),
             Command::GetQuota => write!(f, "GETQUOTA"),
             Command::GetQuotaRoot => write!(f, "GETQUOTAROOT"),
+            Command::GetMetadata => write!(f, "GETMETADATA"),
+            Command::SetMetadata => write!(f, "SETMETADATA"),
         }
     }
 }
@@ -67,10 +67,16 @@ pub struct Receiver<T: CommandParser> {
     pub request: Request<T>,
     pub state: State,
     pub max_request_size: usize,
+    pub max_non_sync_literal: Option<u32>,
     pub current_request_size: usize,
+    // Total size of the literals announced by the request being received
+    pub literal_bytes: usize,
     pub start_state: State,
 }
 
+// Maximum size of a non-synchronizing literal under LITERAL- (RFC 7888)
+pub const LITERAL_MINUS_MAX_SIZE: u32 = 4096;
+
 const ARG_MAX_LEN: usize = 4096;
 
 struct ArgumentBuffer {
@@ -98,6 +104,11 @@ impl<T: CommandParser> Receiver<T> {
         }
     }
 
+    pub fn with_literal_minus(mut self) -> Self {
+        self.max_non_sync_literal = Some(LITERAL_MINUS_MAX_SIZE);
+        self
+    }
+
     pub fn error_reset(&mut self, message: impl Into<trc::Value>) -> Error {
         let request = std::mem::take(&mut self.request);
         let err = Error::err(
@@ -111,6 +122,7 @@ impl<T: CommandParser> Receiver<T> {
         self.buf = ArgumentBuffer::default();
         self.state = self.start_state;
         self.current_request_size = 0;
+        self.literal_bytes = 0;
         err
     }
 
@@ -200,6 +212,7 @@ impl<T: CommandParser> Receiver<T> {
                                 } else {
                                     self.state = self.start_state;
                                     self.current_request_size = 0;
+                                    self.literal_bytes = 0;
                                     return Ok(std::mem::take(&mut self.request));
                                 }
                             } else {
@@ -261,6 +274,7 @@ impl<T: CommandParser> Receiver<T> {
                         self.push_argument(false)?;
                         self.state = self.start_state;
                         self.current_request_size = 0;
+                        self.literal_bytes = 0;
                         return Ok(std::mem::take(&mut self.request));
                     }
                     _ if ch.is_ascii_whitespace() => {
@@ -322,8 +336,22 @@ impl<T: CommandParser> Receiver<T> {
                                         self.max_request_size
                                     )));
                                 }
+                                if non_sync
+                                    && let Some(max_size) = self.max_non_sync_literal
+                                    && size > max_size
+                                {
+                                    let err = self.error_reset(format_compact!(
+                                        "Non-synchronizing literals are limited to {max_size} bytes."
+                                    ));
+                                    return Err(match err {
+                                        Error::Error { response } => Error::Error {
+                                            response: response.code(ResponseCode::TooBig),
+                                        },
+                                        err => err,
+                                    });
+                                }
                                 self.state = State::LiteralSeek { size, non_sync };
-                                self.buf.resize_buffer(size as usize);
+                                self.literal_bytes += size as usize;
                                 self.buf.clear();
                             } else {
                                 return Err(self.error_reset("Invalid empty literal."));
@@ -398,12 +426,6 @@ impl ArgumentBuffer {
         }
     }
 
-    pub fn resize_buffer(&mut self, size: usize) {
-        if self.buf.capacity() < size {
-            self.buf.reserve(size - self.buf.capacity());
-        }
-    }
-
     #[inline(always)]
     pub fn push_checked(&mut self, byte: u8, limit: usize) -> Result<(), ()> {
         if self.buf.len() < limit {
@@ -420,9 +442,7 @@ impl ArgumentBuffer {
     }
 
     pub fn take(&mut self) -> Vec<u8> {
-        let buf = self.buf.clone();
-        self.buf.clear();
-        buf
+        std::mem::replace(&mut self.buf, Vec::with_capacity(10))
     }
 
     #[inline(always)]
@@ -560,7 +580,9 @@ impl<T: CommandParser> Default for Receiver<T> {
             state: State::Start,
             start_state: State::Start,
             max_request_size: 25 * 1024 * 1024,
+            max_non_sync_literal: None,
             current_request_size: 0,
+            literal_bytes: 0,
         }
     }
 }
@@ -620,7 +642,7 @@ DQUOTE         =  %x22 ; " (Double Quote)
 #[cfg(test)]
 mod tests {
 
-    use crate::Command;
+    use crate::{Command, ResponseCode};
 
     use super::{Error, Receiver, Request, Token};
 
@@ -1103,4 +1125,68 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn receiver_parse_literal_minus() {
+        let mut receiver = Receiver::<Command>::new().with_literal_minus();
+        match receiver.parse(&mut "a001 append inbox {4096+}\r\n".as_bytes().iter()) {
+            Err(Error::NeedsMoreData) => {}
+            result => panic!("Expected more data, got: {:?}", result),
+        }
+
+        let mut receiver = Receiver::<Command>::new().with_literal_minus();
+        match receiver.parse(&mut "a001 append inbox {4097+}\r\n".as_bytes().iter()) {
+            Err(Error::Error { response }) => {
+                assert_eq!(
+                    response.value_as_str(trc::Key::Code),
+                    Some(ResponseCode::TooBig.as_str())
+                );
+            }
+            result => panic!("Expected error, got: {:?}", result),
+        }
+
+        // Synchronizing literals are not affected
+        let mut receiver = Receiver::<Command>::new().with_literal_minus();
+        match receiver.parse(&mut "a001 append inbox {4097}\r\n".as_bytes().iter()) {
+            Err(Error::NeedsLiteral { size: 4097 }) => {}
+            result => panic!("Expected literal request, got: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn receiver_literal_bytes() {
+        let mut receiver = Receiver::<Command>::new();
+
+        // Announced literal sizes are added up until the request completes
+        match receiver.parse(&mut "a001 append inbox {5}\r\n".as_bytes().iter()) {
+            Err(Error::NeedsLiteral { size: 5 }) => {}
+            result => panic!("Expected literal request, got: {:?}", result),
+        }
+        assert_eq!(receiver.literal_bytes, 5);
+        match receiver.parse(&mut "hello {3+}\r\nabc".as_bytes().iter()) {
+            Err(Error::NeedsMoreData) => {}
+            result => panic!("Expected more data, got: {:?}", result),
+        }
+        assert_eq!(receiver.literal_bytes, 8);
+        match receiver.parse(&mut "\r\n".as_bytes().iter()) {
+            Ok(request) => assert_eq!(
+                request.tokens,
+                vec![
+                    Token::Argument(b"inbox".to_vec()),
+                    Token::Argument(b"hello".to_vec()),
+                    Token::Argument(b"abc".to_vec())
+                ]
+            ),
+            result => panic!("Expected request, got: {:?}", result),
+        }
+        assert_eq!(receiver.literal_bytes, 0);
+
+        // Errors reset the counter
+        match receiver.parse(&mut "a002 append inbox {10}\r\n".as_bytes().iter()) {
+            Err(Error::NeedsLiteral { size: 10 }) => {}
+            result => panic!("Expected literal request, got: {:?}", result),
+        }
+        receiver.error_reset("Budget exceeded.");
+        assert_eq!(receiver.literal_bytes, 0);
+    }
 }
@@ -26,6 +26,7 @@ impl<T: SessionStream> Session<T> {
             Size = bytes.len(),
             Contents = trc::Value::from_maybe_string(bytes),
         );
+        self.client.touch();
 
         let mut bytes = bytes.iter();
         let mut requests = Vec::with_capacity(2);
@@ -88,6 +89,14 @@ impl<T: SessionStream> Session<T> {
             }
         }
 
+        // Account literals against the message budgets before accepting them
+        if self.receiver.literal_bytes > 0
+            && let Err(err) = self.reserve_literal_buffer()
+        {
+            self.write_error(err).await;
+            return SessionResult::Close;
+        }
+
         let mut requests = requests.into_iter().peekable();
         while let Some(request) = requests.next() {
             let result = match request.command {
@@ -239,6 +248,14 @@ impl<T: SessionStream> Session<T> {
                     .handle_get_quota_root(request)
                     .await
                     .map(|_| SessionResult::Continue),
+                Command::GetMetadata => self
+                    .handle_get_metadata(request)
+                    .await
+                    .map(|_| SessionResult::Continue),
+                Command::SetMetadata => self
+                    .handle_set_metadata(request)
+                    .await
+                    .map(|_| SessionResult::Continue),
                 Command::Unauthenticate => self
                     .handle_unauthenticate(request)
                     .await
@@ -267,6 +284,8 @@ impl<T: SessionStream> Session<T> {
         {
             self.write_error(err).await;
             return SessionResult::Close;
+        } else if self.receiver.literal_bytes == 0 {
+            self.literal_in_flight = None;
         }
 
         SessionResult::Continue
@@ -290,6 +309,32 @@ pub fn group_requests(
 }
 
 impl<T: SessionStream> Session<T> {
+    fn reserve_literal_buffer(&mut self) -> trc::Result<()> {
+        if self.literal_in_flight.is_none() {
+            self.literal_in_flight = Some(self.instance.message_limiter.is_allowed(0).map_err(
+                |limit| {
+                    trc::LimitEvent::ConcurrentMessage
+                        .into_err()
+                        .ctx(trc::Key::Limit, limit)
+                },
+            )?);
+        }
+
+        let literal_bytes = self.receiver.literal_bytes;
+        if let Some(in_flight) = &mut self.literal_in_flight {
+            in_flight
+                .try_resize(literal_bytes as u64)
+                .map_err(|limit| {
+                    trc::LimitEvent::MessageBuffer
+                        .into_err()
+                        .ctx(trc::Key::Size, literal_bytes)
+                        .ctx(trc::Key::Limit, limit)
+                })?;
+        }
+
+        Ok(())
+    }
+
     async fn is_allowed(&self, request: Request<Command>) -> trc::Result<Request<Command>> {
         let state = &self.state;
         // Rate limit request
@@ -379,7 +424,9 @@ impl<T: SessionStream> Session<T> {
             | Command::MyRights
             | Command::Unauthenticate
             | Command::GetQuota
-            | Command::GetQuotaRoot => {
+            | Command::GetQuotaRoot
+            | Command::GetMetadata
+            | Command::SetMetadata => {
                 if let State::Authenticated { .. } | State::Selected { .. } = state {
                     Ok(request)
                 } else {
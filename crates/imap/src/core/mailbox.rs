@@ -9,7 +9,7 @@ use crate::core::Mailbox;
 use ahash::AHashMap;
 use common::{
     auth::AccessToken,
-    listener::{SessionStream, limiter::InFlight},
+    listener::{SessionStream, limiter::InFlight, shaper::BandwidthShaper},
     sharing::EffectiveAcl,
 };
 use directory::backend::internal::manage::ManageDirectory;
@@ -39,6 +39,7 @@ impl<T: SessionStream> SessionData<T> {
             session_id: session.session_id,
             mailboxes: Mutex::new(vec![]),
             state: access_token.state().into(),
+            shaper: access_token.bandwidth_limit.map(BandwidthShaper::new),
             access_token,
             in_flight,
         };
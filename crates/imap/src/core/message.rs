@@ -126,10 +126,12 @@ impl<T: SessionStream> SessionData<T> {
         // Resync mailbox
         let modseq = self.synchronize_messages(mailbox).await?;
         let mut buf = Vec::new();
+        let mut has_deletions = false;
         {
             let mut current_state = mailbox.state.lock();
             if let Some(next_state) = current_state.next_state.take() {
                 if !next_state.deletions.is_empty() {
+                    has_deletions = true;
                     let mut ids = next_state
                         .deletions
                         .into_iter()
@@ -153,6 +155,10 @@ impl<T: SessionStream> SessionData<T> {
                 *current_state = next_state.next_state;
             }
         }
+        if has_deletions {
+            // Remove expunged messages from the SEARCHRES result (RFC 5182)
+            mailbox.prune_saved_search();
+        }
         if !buf.is_empty() {
             self.write_bytes(buf).await?;
         }
@@ -228,7 +234,8 @@ impl SelectedMailbox {
 
             for imap_id in saved_ids.iter() {
                 if let Some(id) = state.uid_to_id.get(&imap_id.uid) {
-                    ids.insert(*id, *imap_id);
+                    // Sequence numbers may have shifted since the search was saved
+                    ids.insert(*id, state.id_to_imap.get(id).copied().unwrap_or(*imap_id));
                 }
             }
 
@@ -13,8 +13,13 @@ use std::{
 use ahash::AHashMap;
 use common::{
     Inner, Server,
-    auth::AccessToken,
-    listener::{ServerInstance, SessionStream, limiter::InFlight},
+    auth::{AccessToken, certificate::ClientCertificate},
+    listener::{
+        ServerInstance, SessionStream,
+        clients::ClientHandle,
+        limiter::{BufferInFlight, InFlight},
+        shaper::BandwidthShaper,
+    },
 };
 
 use imap_proto::{
@@ -57,8 +62,11 @@ pub struct Session<T: SessionStream> {
     pub stream_rx: ReadHalf<T>,
     pub stream_tx: Arc<tokio::sync::Mutex<WriteHalf<T>>>,
     pub in_flight: InFlight,
+    pub literal_in_flight: Option<BufferInFlight>,
     pub remote_addr: IpAddr,
     pub session_id: u64,
+    pub client: ClientHandle,
+    pub client_cert: Option<ClientCertificate>,
 }
 
 pub struct SessionData<T: SessionStream> {
@@ -70,6 +78,7 @@ pub struct SessionData<T: SessionStream> {
     pub stream_tx: Arc<tokio::sync::Mutex<WriteHalf<T>>>,
     pub state: AtomicU32,
     pub in_flight: Option<InFlight>,
+    pub shaper: Option<BandwidthShaper>,
 }
 
 pub struct SelectedMailbox {
@@ -218,6 +227,7 @@ impl<T: SessionStream> SessionData<T> {
             state: self.state,
             in_flight: self.in_flight,
             access_token: self.access_token,
+            shaper: self.shaper,
         }
     }
 }
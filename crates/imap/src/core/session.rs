@@ -4,11 +4,14 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use common::{
     core::BuildServer,
-    listener::{SessionData, SessionManager, SessionResult, SessionStream, stream::NullIo},
+    listener::{
+        SessionData, SessionManager, SessionResult, SessionStream, clients::ClientHandle,
+        stream::NullIo,
+    },
 };
 use imap_proto::{
     protocol::{ProtocolVersion, SerializeResponse},
@@ -17,7 +20,7 @@ use imap_proto::{
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_rustls::server::TlsStream;
 
-use crate::{GREETING_WITH_TLS, GREETING_WITHOUT_TLS};
+use crate::greeting;
 
 use super::{ImapSessionManager, Session, State};
 
@@ -52,11 +55,7 @@ impl<T: SessionStream> Session<T> {
         loop {
             tokio::select! {
                 result = tokio::time::timeout(
-                    if !matches!(self.state, State::NotAuthenticated {..}) {
-                        self.server.core.imap.timeout_auth
-                    } else {
-                        self.server.core.imap.timeout_unauth
-                    },
+                    self.timeout(),
                     self.stream_rx.read(&mut buf)) => {
                     match result {
                         Ok(Ok(bytes_read)) => {
@@ -99,6 +98,16 @@ impl<T: SessionStream> Session<T> {
                         }
                     }
                 },
+                _ = self.client.disconnected() => {
+                    trc::event!(
+                        Network(trc::NetworkEvent::Closed),
+                        SpanId = self.session_id,
+                        Reason = "Disconnected by administrator",
+                        CausedBy = trc::location!()
+                    );
+                    self.write_bytes(&b"* BYE Connection closed by administrator.\r\n"[..]).await.ok();
+                    break;
+                }
                 _ = shutdown_rx.changed() => {
                     trc::event!(
                         Network(trc::NetworkEvent::Closed),
@@ -120,12 +129,11 @@ impl<T: SessionStream> Session<T> {
         manager: ImapSessionManager,
     ) -> Result<Session<T>, ()> {
         // Write greeting
+        let server = manager.inner.build_server();
         let is_tls = session.stream.is_tls();
-        let greeting = if !is_tls && session.instance.acceptor.is_tls() {
-            &GREETING_WITH_TLS
-        } else {
-            &GREETING_WITHOUT_TLS
-        };
+        let client_cert = session.stream.client_certificate();
+        let literal_minus = server.core.imap.literal_minus;
+        let greeting = greeting(!is_tls && session.instance.acceptor.is_tls(), literal_minus);
 
         if let Err(err) = session.stream.write_all(greeting).await {
             trc::event!(
@@ -140,10 +148,21 @@ impl<T: SessionStream> Session<T> {
 
         // Split stream into read and write halves
         let (stream_rx, stream_tx) = tokio::io::split(session.stream);
-        let server = manager.inner.build_server();
+        let mut receiver = Receiver::with_max_request_size(server.core.imap.max_request_size);
+        if literal_minus {
+            receiver = receiver.with_literal_minus();
+        }
+
+        let client = ClientHandle::new(
+            &server,
+            session.session_id,
+            session.instance.protocol,
+            session.remote_ip,
+        );
 
         Ok(Session {
-            receiver: Receiver::with_max_request_size(server.core.imap.max_request_size),
+            receiver,
+            client,
             version: ProtocolVersion::Rev1,
             state: State::NotAuthenticated { auth_failures: 0 },
             is_tls,
@@ -154,7 +173,9 @@ impl<T: SessionStream> Session<T> {
             instance: session.instance,
             session_id: session.session_id,
             in_flight: session.in_flight,
+            literal_in_flight: None,
             remote_addr: session.remote_ip,
+            client_cert,
             stream_rx,
             stream_tx: Arc::new(tokio::sync::Mutex::new(stream_tx)),
         })
@@ -193,8 +214,9 @@ impl<T: SessionStream> Session<T> {
         };
 
         // Upgrade to TLS
-        let (stream_rx, stream_tx) =
-            tokio::io::split(self.instance.tls_accept(stream, self.session_id).await?);
+        let stream = self.instance.tls_accept(stream, self.session_id).await?;
+        let client_cert = stream.client_certificate();
+        let (stream_rx, stream_tx) = tokio::io::split(stream);
         let stream_tx = Arc::new(tokio::sync::Mutex::new(stream_tx));
 
         Ok(Session {
@@ -209,11 +231,25 @@ impl<T: SessionStream> Session<T> {
             is_utf8: self.is_utf8,
             session_id: self.session_id,
             in_flight: self.in_flight,
+            literal_in_flight: self.literal_in_flight,
             remote_addr: self.remote_addr,
+            client: self.client,
+            client_cert,
             stream_rx,
             stream_tx,
         })
     }
+
+    // Inactivity timeout, authenticated sessions may be subject to per-role overrides
+    pub fn timeout(&self) -> Duration {
+        match &self.state {
+            State::NotAuthenticated { .. } => self.server.core.imap.timeout_unauth,
+            State::Authenticated { data } | State::Selected { data, .. } => data
+                .access_token
+                .session_timeout
+                .unwrap_or(self.server.core.imap.timeout_auth),
+        }
+    }
 }
 
 impl<T: SessionStream> Session<T> {
@@ -271,7 +307,12 @@ impl<T: SessionStream> super::SessionData<T> {
         );
 
         let mut stream = self.stream_tx.lock().await;
-        if let Err(err) = stream.write_all(bytes.as_ref()).await {
+        let result = if let Some(shaper) = &self.shaper {
+            shaper.write_all(&mut *stream, bytes).await
+        } else {
+            stream.write_all(bytes).await
+        };
+        if let Err(err) = result {
             Err(trc::NetworkEvent::WriteError
                 .into_err()
                 .reason(err)
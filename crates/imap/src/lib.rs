@@ -13,20 +13,21 @@ pub mod op;
 
 static SERVER_GREETING: &str = "Stalwart IMAP4rev2 at your service.";
 
-pub(crate) static GREETING_WITH_TLS: LazyLock<Vec<u8>> = LazyLock::new(|| {
-    StatusResponse::ok(SERVER_GREETING)
-        .with_code(ResponseCode::Capability {
-            capabilities: Capability::all_capabilities(false, true),
+// Greetings indexed by [offer_tls][literal_minus]
+static GREETINGS: LazyLock<[[Vec<u8>; 2]; 2]> = LazyLock::new(|| {
+    [false, true].map(|offer_tls| {
+        [false, true].map(|literal_minus| {
+            StatusResponse::ok(SERVER_GREETING)
+                .with_code(ResponseCode::Capability {
+                    capabilities: Capability::all_capabilities(false, offer_tls, literal_minus),
+                })
+                .into_bytes()
         })
-        .into_bytes()
+    })
 });
 
-pub(crate) static GREETING_WITHOUT_TLS: LazyLock<Vec<u8>> = LazyLock::new(|| {
-    StatusResponse::ok(SERVER_GREETING)
-        .with_code(ResponseCode::Capability {
-            capabilities: Capability::all_capabilities(false, false),
-        })
-        .into_bytes()
-});
+pub(crate) fn greeting(offer_tls: bool, literal_minus: bool) -> &'static [u8] {
+    &GREETINGS[offer_tls as usize][literal_minus as usize]
+}
 
 pub struct ImapError;
@@ -11,7 +11,13 @@ use crate::{
 };
 use common::listener::SessionStream;
 use directory::Permission;
-use email::message::ingest::{EmailIngest, IngestEmail, IngestSource};
+use email::{
+    cache::MessageCacheFetch,
+    message::{
+        ingest::{EmailIngest, IngestEmail, IngestSource},
+        keywords::KeywordPolicy,
+    },
+};
 use imap_proto::{
     Command, ResponseCode, StatusResponse,
     protocol::{append::Arguments, select::HighestModSeq},
@@ -95,12 +101,37 @@ impl<T: SessionStream> SessionData<T> {
             .await
             .imap_ctx(&arguments.tag, trc::location!())?;
         let spam_train = self.server.email_bayes_can_train(&access_token);
+        let cache = self
+            .server
+            .get_cached_messages(account_id)
+            .await
+            .imap_ctx(&arguments.tag, trc::location!())?;
+        let session_token = self
+            .get_access_token()
+            .await
+            .imap_ctx(&arguments.tag, trc::location!())?;
+        let mut keyword_policy =
+            KeywordPolicy::new(&self.server, &cache, &session_token, account_id);
 
         // Append messages
         let mut response = StatusResponse::completed(Command::Append);
         let mut created_ids = Vec::with_capacity(arguments.messages.len());
         let mut last_change_id = None;
         for message in arguments.messages {
+            // Enforce keyword limits
+            let keywords = message
+                .flags
+                .into_iter()
+                .map(Keyword::from)
+                .collect::<Vec<_>>();
+            if let Err(limit) = keyword_policy.apply_new(&keywords) {
+                return Err(trc::ImapEvent::Error
+                    .into_err()
+                    .details(limit.description())
+                    .code(ResponseCode::Limit)
+                    .id(arguments.tag));
+            }
+
             match self
                 .server
                 .email_ingest(IngestEmail {
@@ -108,7 +139,7 @@ impl<T: SessionStream> SessionData<T> {
                     message: MessageParser::new().parse(&message.message),
                     access_token: &access_token,
                     mailbox_ids: vec![mailbox_id],
-                    keywords: message.flags.into_iter().map(Keyword::from).collect(),
+                    keywords,
                     received_at: message.received_at.map(|d| d as u64),
                     source: IngestSource::Imap,
                     spam_classify: false,
@@ -6,7 +6,7 @@
 
 use common::{
     auth::{
-        AuthRequest,
+        AccessToken, AuthRequest,
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
     },
     listener::{SessionStream, limiter::LimiterResult},
@@ -63,6 +63,38 @@ impl<T: SessionStream> Session<T> {
                     self.write_bytes(b"+ \r\n".to_vec()).await
                 }
             }
+            Mechanism::External if self.client_cert.is_some() => {
+                if let Some(param) = args.params.pop() {
+                    // An empty response or "=" requests the certificate's own identity
+                    let authzid = if !param.is_empty() && param != "=" {
+                        base64_decode(param.as_bytes())
+                            .and_then(|authzid| String::from_utf8(authzid).ok())
+                            .ok_or_else(|| {
+                                trc::AuthEvent::Error
+                                    .into_err()
+                                    .details("Failed to decode authorization identity.")
+                                    .id(args.tag.clone())
+                                    .code(ResponseCode::Parse)
+                            })?
+                            .into()
+                    } else {
+                        None
+                    };
+
+                    self.authenticate_external(authzid, args.tag).await
+                } else {
+                    self.receiver.request = receiver::Request {
+                        tag: args.tag,
+                        command: Command::Authenticate,
+                        tokens: vec![
+                            receiver::Token::Argument(args.mechanism.into_bytes()),
+                            receiver::Token::Argument(b"=".to_vec()),
+                        ],
+                    };
+                    self.receiver.state = receiver::State::Argument { last_ch: b' ' };
+                    self.write_bytes(b"+ \r\n".to_vec()).await
+                }
+            }
             _ => Err(trc::AuthEvent::Error
                 .into_err()
                 .details("Authentication mechanism not supported.")
@@ -77,14 +109,51 @@ impl<T: SessionStream> Session<T> {
         tag: String,
     ) -> trc::Result<()> {
         // Authenticate
-        let access_token = self
+        let result = self
             .server
-            .authenticate(&AuthRequest::from_credentials(
-                credentials,
-                self.session_id,
-                self.remote_addr,
-            ))
-            .await
+            .authenticate(
+                &AuthRequest::from_credentials(credentials, self.session_id, self.remote_addr)
+                    .with_session("imap", None),
+            )
+            .await;
+
+        self.start_authenticated_session(result, tag).await
+    }
+
+    pub async fn authenticate_external(
+        &mut self,
+        authzid: Option<String>,
+        tag: String,
+    ) -> trc::Result<()> {
+        let result = match (
+            &self.client_cert,
+            self.instance.acceptor.client_cert_mapping(),
+        ) {
+            (Some(certificate), Some(mapping)) => {
+                self.server
+                    .authenticate_certificate(
+                        certificate,
+                        mapping,
+                        authzid.as_deref(),
+                        self.session_id,
+                        self.remote_addr,
+                    )
+                    .await
+            }
+            _ => Err(trc::AuthEvent::Error
+                .into_err()
+                .details("Client certificate authentication is not enabled.")),
+        };
+
+        self.start_authenticated_session(result, tag).await
+    }
+
+    async fn start_authenticated_session(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+        tag: String,
+    ) -> trc::Result<()> {
+        let access_token = result
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
                     let auth_failures = self.state.auth_failures();
@@ -117,6 +186,8 @@ impl<T: SessionStream> Session<T> {
         };
 
         // Create session
+        self.client
+            .set_account(access_token.primary_id(), access_token.name.clone());
         self.state = State::Authenticated {
             data: Arc::new(
                 SessionData::new(self, access_token, in_flight)
@@ -130,6 +201,7 @@ impl<T: SessionStream> Session<T> {
                     capabilities: Capability::all_capabilities(
                         true,
                         !self.is_tls && self.instance.acceptor.is_tls(),
+                        self.server.core.imap.literal_minus,
                     ),
                 })
                 .with_tag(tag)
@@ -140,6 +212,7 @@ impl<T: SessionStream> Session<T> {
 
     pub async fn handle_unauthenticate(&mut self, request: Request<Command>) -> trc::Result<()> {
         self.state = State::NotAuthenticated { auth_failures: 0 };
+        self.client.clear_account();
 
         self.write_bytes(
             StatusResponse::completed(Command::Unauthenticate)
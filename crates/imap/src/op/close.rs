@@ -31,6 +31,7 @@ impl<T: SessionStream> Session<T> {
         );
 
         self.state = State::Authenticated { data };
+        self.client.set_mailbox(None);
         self.write_bytes(
             StatusResponse::completed(Command::Close)
                 .with_tag(request.tag)
@@ -12,13 +12,14 @@ use crate::{
 use common::{listener::SessionStream, storage::index::ObjectIndexBuilder};
 use directory::Permission;
 use email::{
-    cache::{MessageCacheFetch, email::MessageCacheAccess},
+    cache::{MessageCacheFetch, email::MessageCacheAccess, mailbox::MailboxCacheAccess},
     mailbox::{JUNK_ID, TRASH_ID, UidMailbox},
     message::{
         bayes::EmailBayesTrain,
         copy::{CopyMessageError, EmailCopy},
-        ingest::EmailIngest,
+        ingest::{EmailIngest, IngestSource},
         metadata::MessageData,
+        strip::EmailStripAttachments,
     },
 };
 use imap_proto::{
@@ -33,6 +34,7 @@ use store::{
 use types::{
     acl::Acl,
     collection::{Collection, VanishedCollection},
+    special_use::SpecialUse,
     type_state::{DataType, StateChange},
 };
 
@@ -200,6 +202,17 @@ impl<T: SessionStream> SessionData<T> {
             .await
             .imap_ctx(&arguments.tag, trc::location!())?;
 
+        // Messages filed into the archive folder have their large attachments stripped
+        let strip_attachments = self.server.core.jmap.mail_archive_on_move
+            && self
+                .server
+                .get_cached_messages(dest_mailbox.account_id)
+                .await
+                .imap_ctx(&arguments.tag, trc::location!())?
+                .mailbox_by_id(&dest_mailbox_id)
+                .is_some_and(|mailbox| mailbox.role == SpecialUse::Archive);
+        let mut strip_ids = Vec::new();
+
         if src_mailbox.id.account_id == dest_mailbox.account_id {
             // Mailboxes are in the same account
             let account_id = src_mailbox.id.account_id;
@@ -281,6 +294,10 @@ impl<T: SessionStream> SessionData<T> {
                     new_data.remove_mailbox(src_mailbox.id.mailbox_id);
                 }
 
+                if strip_attachments {
+                    strip_ids.push(id);
+                }
+
                 // Assign IMAP UIDs
                 for uid_mailbox in &mut new_data.mailboxes {
                     if uid_mailbox.uid == 0 {
@@ -391,6 +408,9 @@ impl<T: SessionStream> SessionData<T> {
                 {
                     Ok(email) => {
                         dest_change_id = email.change_id.into();
+                        if strip_attachments {
+                            strip_ids.push(email.document_id);
+                        }
                         if let Some(assigned_uid) = email.imap_uids.first() {
                             debug_assert!(*assigned_uid > 0);
                             copied_ids.push((imap_id.uid, *assigned_uid));
@@ -447,6 +467,28 @@ impl<T: SessionStream> SessionData<T> {
             }
         }
 
+        if !strip_ids.is_empty() {
+            let server = self.server.clone();
+            let session_id = self.session_id;
+            tokio::spawn(async move {
+                let min_size = server.core.jmap.mail_archive_min_size;
+                for document_id in strip_ids {
+                    if let Err(err) = server
+                        .strip_attachments(
+                            &access_token,
+                            document_id,
+                            min_size,
+                            IngestSource::Imap,
+                            session_id,
+                        )
+                        .await
+                    {
+                        trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+                    }
+                }
+            });
+        }
+
         // Map copied JMAP Ids to IMAP UIDs in the destination folder.
         if copied_ids.is_empty() {
             return if response.rtype != ResponseType::Ok {
@@ -5,7 +5,7 @@
  */
 
 use super::{ImapContext, ToModSeq};
-use crate::core::{ImapId, SavedSearch, SelectedMailbox, Session, SessionData};
+use crate::core::{ImapId, SelectedMailbox, Session, SessionData};
 use ahash::AHashMap;
 use common::{listener::SessionStream, storage::index::ObjectIndexBuilder};
 use directory::Permission;
@@ -90,9 +90,6 @@ impl<T: SessionStream> Session<T> {
             .await
             .imap_ctx(&request.tag, trc::location!())?;
 
-        // Clear saved searches
-        *mailbox.saved_search.lock() = SavedSearch::None;
-
         // Synchronize messages
         let modseq = data
             .write_mailbox_changes(&mailbox, self.is_qresync)
@@ -164,7 +164,7 @@ impl<T: SessionStream> SessionData<T> {
 
             // Process changes
             let mut changed_ids = AHashMap::new();
-            let mut has_vanished = false;
+            let mut has_vanished = changelog.is_truncated;
 
             for change in changelog.changes {
                 match change {
@@ -9,7 +9,7 @@ use crate::{
     op::ImapContext,
 };
 use ahash::AHashSet;
-use common::listener::SessionStream;
+use common::listener::{SessionStream, clients::ClientState};
 use directory::Permission;
 use imap_proto::{
     Command, StatusResponse,
@@ -67,31 +67,41 @@ impl<T: SessionStream> Session<T> {
 
         let op_start = Instant::now();
         let mut buf = vec![0; 4];
-        loop {
+        let prev_state = if mailbox.is_some() {
+            ClientState::Selected
+        } else {
+            ClientState::Authenticated
+        };
+        self.client.set_state(ClientState::Idle);
+        let result = loop {
             tokio::select! {
-                result = tokio::time::timeout(self.server.core.imap.timeout_idle, self.stream_rx.read_exact(&mut buf)) => {
+                result = tokio::time::timeout(data.access_token.session_timeout.unwrap_or(self.server.core.imap.timeout_idle), self.stream_rx.read_exact(&mut buf)) => {
                     match result {
                         Ok(Ok(bytes_read)) => {
                             if bytes_read > 0 {
                                 if (buf[..bytes_read]).windows(4).any(|w| w == b"DONE") {
                                     trc::event!(Imap(trc::ImapEvent::IdleStop), SpanId = self.session_id, Elapsed = op_start.elapsed());
-                                    return self.write_bytes(StatusResponse::completed(Command::Idle)
+                                    break self.write_bytes(StatusResponse::completed(Command::Idle)
                                                                     .with_tag(request.tag)
                                                                     .into_bytes()).await;
                                 }
                             } else {
-                                return Err(trc::NetworkEvent::Closed.into_err().details("IMAP connection closed by client.").id(request.tag));
+                                break Err(trc::NetworkEvent::Closed.into_err().details("IMAP connection closed by client.").id(request.tag));
                             }
                         },
                         Ok(Err(err)) => {
-                            return Err(trc::NetworkEvent::ReadError.into_err().reason(err).details("IMAP connection error.").id(request.tag));
+                            break Err(trc::NetworkEvent::ReadError.into_err().reason(err).details("IMAP connection error.").id(request.tag));
                         },
                         Err(_) => {
                             self.write_bytes(&b"* BYE IDLE timed out.\r\n"[..]).await.ok();
-                            return Err(trc::NetworkEvent::Timeout.into_err().details("IMAP IDLE timed out.").id(request.tag));
+                            break Err(trc::NetworkEvent::Timeout.into_err().details("IMAP IDLE timed out.").id(request.tag));
                         }
                     }
                 }
+                _ = self.client.disconnected() => {
+                    self.write_bytes(&b"* BYE Connection closed by administrator.\r\n"[..]).await.ok();
+                    break Err(trc::NetworkEvent::Closed.into_err().details("Disconnected by administrator.").id(request.tag));
+                }
                 state_change = change_rx.recv() => {
                     if let Some(state_change) = state_change {
                         let mut has_mailbox_changes = false;
@@ -109,16 +119,20 @@ impl<T: SessionStream> Session<T> {
                             }
                         }
 
-                        if has_mailbox_changes || has_email_changes {
-                            data.write_changes(&mailbox, has_mailbox_changes, has_email_changes, is_qresync, is_rev2, is_utf8).await?;
+                        if (has_mailbox_changes || has_email_changes)
+                            && let Err(err) = data.write_changes(&mailbox, has_mailbox_changes, has_email_changes, is_qresync, is_rev2, is_utf8).await {
+                            break Err(err);
                         }
                     } else {
                         self.write_bytes(&b"* BYE Server shutting down.\r\n"[..]).await.ok();
-                        return Err(trc::NetworkEvent::Closed.into_err().details("IDLE channel closed.").id(request.tag));
+                        break Err(trc::NetworkEvent::Closed.into_err().details("IDLE channel closed.").id(request.tag));
                     }
                 }
             }
-        }
+        };
+        self.client.set_state(prev_state);
+
+        result
     }
 }
 
@@ -0,0 +1,388 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    core::{MailboxId, Session, SessionData},
+    op::ImapContext,
+    spawn_op,
+};
+use common::listener::SessionStream;
+use directory::Permission;
+use email::settings::{AccountSettings, SettingsStore};
+use imap_proto::{
+    Command, MetadataCode, ResponseCode, StatusResponse,
+    protocol::metadata::{GetArguments, Response, SetArguments},
+    receiver::Request,
+};
+use store::rand::Rng;
+use trc::AddContext;
+use types::acl::Acl;
+
+// Annotations share the per-account settings storage with JMAP
+const METADATA_NAMESPACE: &str = "imap";
+
+// Shared server annotations are kept in the directory's global account
+const SERVER_ACCOUNT_ID: u32 = u32::MAX;
+
+const MAX_RETRIES: u32 = 10;
+
+struct MetadataScope {
+    mailbox: Option<MailboxId>,
+    private_account_id: u32,
+    shared_account_id: u32,
+    prefix: String,
+}
+
+impl<T: SessionStream> Session<T> {
+    pub async fn handle_get_metadata(&mut self, request: Request<Command>) -> trc::Result<()> {
+        // Validate access
+        self.assert_has_permission(Permission::ImapGetMetadata)?;
+
+        let data = self.state.session_data();
+        let is_utf8 = self.is_utf8;
+
+        spawn_op!(data, {
+            match request.parse_get_metadata(is_utf8) {
+                Ok(arguments) => match data.get_metadata(arguments, is_utf8).await {
+                    Ok(response) => {
+                        data.write_bytes(response).await?;
+                    }
+                    Err(error) => {
+                        data.write_error(error).await?;
+                    }
+                },
+                Err(err) => data.write_error(err).await?,
+            }
+
+            Ok(())
+        })
+    }
+
+    pub async fn handle_set_metadata(&mut self, request: Request<Command>) -> trc::Result<()> {
+        // Validate access
+        self.assert_has_permission(Permission::ImapSetMetadata)?;
+
+        let data = self.state.session_data();
+        let is_utf8 = self.is_utf8;
+
+        spawn_op!(data, {
+            match request.parse_set_metadata(is_utf8) {
+                Ok(arguments) => match data.set_metadata(arguments).await {
+                    Ok(response) => {
+                        data.write_bytes(response).await?;
+                    }
+                    Err(error) => {
+                        data.write_error(error).await?;
+                    }
+                },
+                Err(err) => data.write_error(err).await?,
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl<T: SessionStream> SessionData<T> {
+    pub async fn get_metadata(
+        &self,
+        arguments: GetArguments,
+        is_utf8: bool,
+    ) -> trc::Result<Vec<u8>> {
+        let op_start = Instant::now();
+        let scope = self
+            .metadata_scope(&arguments.mailbox_name, &arguments.tag)
+            .await?;
+
+        // Fetch annotations
+        let private_entries = self
+            .metadata_entries(scope.private_account_id, &scope.prefix)
+            .await
+            .imap_ctx(&arguments.tag, trc::location!())?;
+        let shared_entries = if scope.shared_account_id != scope.private_account_id {
+            self.metadata_entries(scope.shared_account_id, &scope.prefix)
+                .await
+                .imap_ctx(&arguments.tag, trc::location!())?
+        } else {
+            private_entries.clone()
+        };
+
+        // Filter requested entries
+        let mut entries: Vec<(String, Option<String>)> = Vec::new();
+        let mut long_entries = 0;
+        for requested in &arguments.entries {
+            let stored = if requested.starts_with("/private/") {
+                &private_entries
+            } else {
+                &shared_entries
+            };
+            let mut found = false;
+
+            for (entry, value) in stored {
+                if !arguments.depth.includes(entry, requested) {
+                    continue;
+                }
+                found |= entry == requested;
+
+                if arguments
+                    .max_size
+                    .is_some_and(|max_size| value.len() > max_size)
+                {
+                    long_entries = long_entries.max(value.len());
+                } else if !entries.iter().any(|(name, _)| name == entry) {
+                    entries.push((entry.clone(), Some(value.clone())));
+                }
+            }
+
+            if !found && !entries.iter().any(|(name, _)| name == requested) {
+                entries.push((requested.clone(), None));
+            }
+        }
+
+        trc::event!(
+            Imap(trc::ImapEvent::GetMetadata),
+            SpanId = self.session_id,
+            MailboxName = arguments.mailbox_name.clone(),
+            Details = arguments
+                .entries
+                .iter()
+                .map(|entry| trc::Value::from(entry.clone()))
+                .collect::<Vec<_>>(),
+            Elapsed = op_start.elapsed()
+        );
+
+        // Build response
+        let mut response = Vec::with_capacity(64);
+        Response {
+            mailbox_name: arguments.mailbox_name,
+            entries,
+        }
+        .serialize(&mut response, is_utf8);
+        let mut status = StatusResponse::ok("GETMETADATA successful.").with_tag(arguments.tag);
+        if long_entries > 0 {
+            status = status.with_code(ResponseCode::Metadata(MetadataCode::LongEntries(
+                long_entries,
+            )));
+        }
+
+        Ok(status.serialize(response))
+    }
+
+    pub async fn set_metadata(&self, arguments: SetArguments) -> trc::Result<Vec<u8>> {
+        let op_start = Instant::now();
+        let scope = self
+            .metadata_scope(&arguments.mailbox_name, &arguments.tag)
+            .await?;
+
+        // Shared mailbox annotations require write access, shared server
+        // annotations are visible to all users and require admin access
+        if arguments
+            .entries
+            .iter()
+            .any(|(entry, _)| entry.starts_with("/shared/"))
+        {
+            let has_access = if let Some(mailbox) = scope.mailbox {
+                self.check_mailbox_acl(mailbox.account_id, mailbox.mailbox_id, Acl::ModifyItems)
+                    .await
+                    .imap_ctx(&arguments.tag, trc::location!())?
+            } else {
+                self.access_token.has_permission(Permission::SettingsUpdate)
+            };
+
+            if !has_access {
+                return Err(trc::ImapEvent::Error
+                    .into_err()
+                    .details(
+                        "You do not have the required permissions to modify shared annotations.",
+                    )
+                    .code(ResponseCode::NoPerm)
+                    .id(arguments.tag));
+            }
+        }
+
+        // Validate entry sizes
+        let max_entry_size = self.server.core.imap.metadata_max_entry_size;
+        if arguments
+            .entries
+            .iter()
+            .any(|(_, value)| value.as_ref().is_some_and(|v| v.len() > max_entry_size))
+        {
+            return Ok(StatusResponse::no("Annotation value is too large.")
+                .with_tag(arguments.tag)
+                .with_code(ResponseCode::Metadata(MetadataCode::MaxSize(
+                    max_entry_size,
+                )))
+                .into_bytes());
+        }
+
+        // Group changes by the account that stores them
+        let mut private_changes = Vec::new();
+        let mut shared_changes = Vec::new();
+        for (entry, value) in &arguments.entries {
+            let key = format!("{}{entry}", scope.prefix);
+            if entry.starts_with("/private/") {
+                private_changes.push((key, value.clone()));
+            } else {
+                shared_changes.push((key, value.clone()));
+            }
+        }
+        if scope.shared_account_id == scope.private_account_id {
+            private_changes.append(&mut shared_changes);
+        }
+
+        for (account_id, changes) in [
+            (scope.private_account_id, private_changes),
+            (scope.shared_account_id, shared_changes),
+        ] {
+            if changes.is_empty() {
+                continue;
+            }
+            if let Some(code) = self
+                .update_metadata(account_id, changes)
+                .await
+                .imap_ctx(&arguments.tag, trc::location!())?
+            {
+                return Ok(StatusResponse::no("Annotation limits exceeded.")
+                    .with_tag(arguments.tag)
+                    .with_code(code)
+                    .into_bytes());
+            }
+        }
+
+        trc::event!(
+            Imap(trc::ImapEvent::SetMetadata),
+            SpanId = self.session_id,
+            MailboxName = arguments.mailbox_name.clone(),
+            Details = arguments
+                .entries
+                .iter()
+                .map(|(entry, _)| trc::Value::from(entry.clone()))
+                .collect::<Vec<_>>(),
+            Elapsed = op_start.elapsed()
+        );
+
+        Ok(StatusResponse::completed(Command::SetMetadata)
+            .with_tag(arguments.tag)
+            .into_bytes())
+    }
+
+    async fn metadata_scope(&self, mailbox_name: &str, tag: &str) -> trc::Result<MetadataScope> {
+        if mailbox_name.is_empty() {
+            // Private server annotations belong to the user, shared ones to the server
+            return Ok(MetadataScope {
+                mailbox: None,
+                private_account_id: self.account_id,
+                shared_account_id: SERVER_ACCOUNT_ID,
+                prefix: String::new(),
+            });
+        }
+
+        // Refresh mailboxes
+        self.synchronize_mailboxes(false)
+            .await
+            .imap_ctx(tag, trc::location!())?;
+
+        // Validate mailbox
+        let mailbox = self.get_mailbox_by_name(mailbox_name).ok_or_else(|| {
+            trc::ImapEvent::Error
+                .into_err()
+                .details("Mailbox does not exist.")
+                .code(ResponseCode::NonExistent)
+                .id(tag.to_string())
+        })?;
+        if !self
+            .check_mailbox_acl(mailbox.account_id, mailbox.mailbox_id, Acl::Read)
+            .await
+            .imap_ctx(tag, trc::location!())?
+        {
+            return Err(trc::ImapEvent::Error
+                .into_err()
+                .details("You do not have the required permissions to access this mailbox.")
+                .code(ResponseCode::NoPerm)
+                .id(tag.to_string()));
+        }
+
+        // Private annotations belong to the user, shared ones to the mailbox owner
+        Ok(MetadataScope {
+            mailbox: Some(mailbox),
+            private_account_id: self.account_id,
+            shared_account_id: mailbox.account_id,
+            prefix: format!("{}:{}", mailbox.account_id, mailbox.mailbox_id),
+        })
+    }
+
+    async fn metadata_entries(
+        &self,
+        account_id: u32,
+        prefix: &str,
+    ) -> trc::Result<Vec<(String, String)>> {
+        let settings_ = self.server.get_account_settings(account_id).await?;
+        let Some(settings) = settings_
+            .as_ref()
+            .map(|settings| settings.unarchive::<AccountSettings>())
+            .transpose()
+            .caused_by(trc::location!())?
+        else {
+            return Ok(vec![]);
+        };
+
+        Ok(settings
+            .string_entries(METADATA_NAMESPACE)
+            .into_iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(prefix)
+                    .filter(|entry| entry.starts_with('/'))
+                    .map(|entry| (entry.to_string(), value))
+            })
+            .collect())
+    }
+
+    async fn update_metadata(
+        &self,
+        account_id: u32,
+        changes: Vec<(String, Option<String>)>,
+    ) -> trc::Result<Option<ResponseCode>> {
+        let mut try_count = 0;
+
+        loop {
+            let current_settings = self.server.get_account_settings(account_id).await?;
+            let mut settings = current_settings
+                .as_ref()
+                .map(|settings| settings.deserialize::<AccountSettings>())
+                .transpose()
+                .caused_by(trc::location!())?
+                .unwrap_or_default();
+
+            // Enforce quotas
+            if settings.update_entries(METADATA_NAMESPACE, changes.iter().cloned())
+                > self.server.core.imap.metadata_max_entries
+            {
+                return Ok(Some(ResponseCode::Metadata(MetadataCode::TooMany)));
+            } else if settings.namespaces.len() > self.server.core.jmap.settings_max_namespaces
+                || settings.size() > self.server.core.jmap.settings_max_size
+            {
+                return Ok(Some(ResponseCode::OverQuota));
+            }
+
+            // Settings might have been modified by another session
+            match self
+                .server
+                .write_account_settings(account_id, current_settings.as_ref(), settings)
+                .await
+            {
+                Ok(_) => return Ok(None),
+                Err(err) if err.is_assertion_failure() && try_count < MAX_RETRIES => {
+                    let backoff = store::rand::rng().random_range(50..=300);
+                    tokio::time::sleep(Duration::from_millis(backoff)).await;
+                    try_count += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
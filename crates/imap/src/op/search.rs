@@ -702,6 +702,26 @@ impl SelectedMailbox {
         Some(v.clone())
     }
 
+    pub fn prune_saved_search(&self) {
+        let mut saved_search = self.saved_search.lock();
+        if let SavedSearch::Results { items } = &*saved_search {
+            let state = self.state.lock();
+            let items = items
+                .iter()
+                .filter_map(|imap_id| {
+                    state
+                        .uid_to_id
+                        .get(&imap_id.uid)
+                        .and_then(|id| state.id_to_imap.get(id))
+                        .copied()
+                })
+                .collect::<Vec<_>>();
+            *saved_search = SavedSearch::Results {
+                items: Arc::new(items),
+            };
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn map_search_results(
         &self,
@@ -119,7 +119,7 @@ impl<T: SessionStream> Session<T> {
 
             // Build response
             let response = Response {
-                mailbox: ListItem::new(arguments.mailbox_name),
+                mailbox: ListItem::new(arguments.mailbox_name.clone()),
                 total_messages,
                 recent_messages: 0,
                 unseen_seq: 0,
@@ -134,6 +134,7 @@ impl<T: SessionStream> Session<T> {
             };
 
             // Update state
+            self.client.set_mailbox(arguments.mailbox_name.into());
             self.state = State::Selected { data, mailbox };
 
             self.write_bytes(
@@ -161,6 +162,7 @@ impl<T: SessionStream> Session<T> {
         self.state = State::Authenticated {
             data: self.state.session_data(),
         };
+        self.client.set_mailbox(None);
         self.write_bytes(
             StatusResponse::completed(Command::Unselect)
                 .with_tag(request.tag)
@@ -12,7 +12,12 @@ use crate::{
 use ahash::AHashSet;
 use common::{listener::SessionStream, storage::index::ObjectIndexBuilder};
 use directory::Permission;
-use email::message::{bayes::EmailBayesTrain, ingest::EmailIngest, metadata::MessageData};
+use email::{
+    cache::MessageCacheFetch,
+    message::{
+        bayes::EmailBayesTrain, ingest::EmailIngest, keywords::KeywordPolicy, metadata::MessageData,
+    },
+};
 use imap_proto::{
     Command, ResponseCode, ResponseType, StatusResponse,
     protocol::{
@@ -193,6 +198,17 @@ impl<T: SessionStream> SessionData<T> {
             .get_access_token(account_id)
             .await
             .imap_ctx(response.tag.as_ref().unwrap(), trc::location!())?;
+        let cache = self
+            .server
+            .get_cached_messages(account_id)
+            .await
+            .imap_ctx(response.tag.as_ref().unwrap(), trc::location!())?;
+        let session_token = self
+            .get_access_token()
+            .await
+            .imap_ctx(response.tag.as_ref().unwrap(), trc::location!())?;
+        let mut keyword_policy =
+            KeywordPolicy::new(&self.server, &cache, &session_token, account_id);
         let mut changed_mailboxes = AHashSet::new();
         let can_spam_train = self.server.email_bayes_can_train(&access_token);
         let mut has_spam_train_tasks = false;
@@ -243,6 +259,19 @@ impl<T: SessionStream> SessionData<T> {
                 }
             }
 
+            // Enforce keyword limits
+            if let Err(limit) = keyword_policy.apply_update(*id, data.inner, &mut new_data) {
+                return Err(trc::ImapEvent::Error
+                    .into_err()
+                    .details(limit.description())
+                    .id(response.tag.unwrap_or_default())
+                    .code(ResponseCode::Limit)
+                    .caused_by(trc::location!()));
+            }
+            seen_changed = seen_changed
+                && new_data.has_keyword(&Keyword::Seen)
+                    != data.inner.keywords.iter().any(|k| k == &Keyword::Seen);
+
             if !new_data.has_keyword_changes(data.inner) {
                 continue;
             }
@@ -22,6 +22,7 @@ pub mod query;
 pub mod query_changes;
 pub mod search_snippet;
 pub mod set;
+pub mod strip;
 pub mod upload;
 pub mod validate;
 
@@ -50,6 +50,11 @@ pub struct QueryResponse {
     #[serde(rename = "ids")]
     pub ids: Vec<Id>,
 
+    // Non-standard, account of each id when querying all accounts
+    #[serde(rename = "accountIds")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_ids: Option<Vec<Id>>,
+
     #[serde(rename = "total")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total: Option<usize>,
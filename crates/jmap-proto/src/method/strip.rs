@@ -0,0 +1,98 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    error::set::SetError,
+    object::email::{EmailProperty, EmailValue},
+    request::{
+        MaybeInvalid,
+        deserialize::{DeserializeArguments, deserialize_request},
+        reference::{MaybeResultReference, ResultReference},
+    },
+    types::state::State,
+};
+use jmap_tools::Value;
+use serde::{Deserialize, Deserializer};
+use types::id::Id;
+use utils::map::vec_map::VecMap;
+
+#[derive(Debug, Clone)]
+pub struct StripAttachmentsRequest {
+    pub account_id: Id,
+    pub if_in_state: Option<State>,
+    pub email_ids: MaybeResultReference<Vec<MaybeInvalid<Id>>>,
+    pub min_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StripAttachmentsResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "oldState")]
+    pub old_state: State,
+
+    #[serde(rename = "newState")]
+    pub new_state: State,
+
+    #[serde(rename = "stripped")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub stripped: VecMap<Id, Value<'static, EmailProperty, EmailValue>>,
+
+    #[serde(rename = "notStripped")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub not_stripped: VecMap<Id, SetError<EmailProperty>>,
+}
+
+impl<'de> DeserializeArguments<'de> for StripAttachmentsRequest {
+    fn deserialize_argument<A>(&mut self, key: &str, map: &mut A) -> Result<(), A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        hashify::fnc_map!(key.as_bytes(),
+            b"accountId" => {
+                self.account_id = map.next_value()?;
+            },
+            b"ifInState" => {
+                self.if_in_state = map.next_value()?;
+            },
+            b"emailIds" => {
+                self.email_ids = MaybeResultReference::Value(map.next_value::<Vec<MaybeInvalid<Id>>>()?);
+            },
+            b"#emailIds" => {
+                self.email_ids = MaybeResultReference::Reference(map.next_value::<ResultReference>()?);
+            },
+            b"minSize" => {
+                self.min_size = map.next_value()?;
+            },
+            _ => {
+                let _ = map.next_value::<serde::de::IgnoredAny>()?;
+            }
+        );
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for StripAttachmentsRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_request(deserializer)
+    }
+}
+
+impl Default for StripAttachmentsRequest {
+    fn default() -> Self {
+        Self {
+            account_id: Id::default(),
+            if_in_state: None,
+            email_ids: MaybeResultReference::Value(Vec::new()),
+            min_size: None,
+        }
+    }
+}
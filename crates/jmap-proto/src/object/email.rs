@@ -73,6 +73,8 @@ pub enum EmailProperty {
     IsTruncated,
     HasAttachment,
     Preview,
+    ParsedEmail,
+    CalendarPreview,
 
     // Other
     Keyword(Keyword),
@@ -162,6 +164,8 @@ impl Property for EmailProperty {
             EmailProperty::Value => "value",
             EmailProperty::IsEncodingProblem => "isEncodingProblem",
             EmailProperty::IsTruncated => "isTruncated",
+            EmailProperty::ParsedEmail => "parsedEmail",
+            EmailProperty::CalendarPreview => "calendarPreview",
             EmailProperty::Header(header) => return header.to_string().into(),
             EmailProperty::Keyword(keyword) => return keyword.to_string().into(),
             EmailProperty::IdValue(id) => return id.to_string().into(),
@@ -254,7 +258,9 @@ impl EmailProperty {
                 "isEncodingProblem" => EmailProperty::IsEncodingProblem,
                 "isTruncated" => EmailProperty::IsTruncated,
                 "hasAttachment" => EmailProperty::HasAttachment,
-                "preview" => EmailProperty::Preview
+                "preview" => EmailProperty::Preview,
+                "parsedEmail" => EmailProperty::ParsedEmail,
+                "calendarPreview" => EmailProperty::CalendarPreview
         )
         .or_else(|| {
             if let Some(header) = value.strip_prefix("header:") {
@@ -394,6 +400,7 @@ pub struct EmailGetArguments {
 #[derive(Debug, Clone, Default)]
 pub struct EmailQueryArguments {
     pub collapse_threads: Option<bool>,
+    pub all_accounts: Option<bool>,
 }
 
 impl<'de> DeserializeArguments<'de> for EmailGetArguments {
@@ -433,6 +440,8 @@ impl<'de> DeserializeArguments<'de> for EmailQueryArguments {
     {
         if key == "collapseThreads" {
             self.collapse_threads = map.next_value()?;
+        } else if key == "allAccounts" {
+            self.all_accounts = map.next_value()?;
         } else {
             let _ = map.next_value::<serde::de::IgnoredAny>()?;
         }
@@ -19,6 +19,7 @@ pub mod principal;
 pub mod push_subscription;
 pub mod quota;
 pub mod search_snippet;
+pub mod settings;
 pub mod sieve;
 pub mod thread;
 pub mod vacation_response;
@@ -25,6 +25,7 @@ pub enum PrincipalProperty {
     Email,
     Timezone,
     Capabilities,
+    AvatarUrl,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -56,6 +57,7 @@ impl Property for PrincipalProperty {
             PrincipalProperty::Name => "name",
             PrincipalProperty::Timezone => "timezone",
             PrincipalProperty::Type => "type",
+            PrincipalProperty::AvatarUrl => "avatarUrl",
         }
         .into()
     }
@@ -94,6 +96,7 @@ impl PrincipalProperty {
             b"email" => PrincipalProperty::Email,
             b"timezone" => PrincipalProperty::Timezone,
             b"capabilities" => PrincipalProperty::Capabilities,
+            b"avatarUrl" => PrincipalProperty::AvatarUrl,
         )
     }
 }
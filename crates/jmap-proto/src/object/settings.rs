@@ -0,0 +1,162 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::object::{AnyId, JmapObject, JmapObjectId};
+use jmap_tools::{Element, Key, Property};
+use std::{borrow::Cow, str::FromStr};
+use types::id::Id;
+
+#[derive(Debug, Clone, Default)]
+pub struct Settings;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SettingsProperty {
+    Id,
+    Namespace(String),
+    Setting { namespace: String, key: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SettingsValue {
+    Id(Id),
+}
+
+impl Property for SettingsProperty {
+    fn try_parse(key: Option<&Key<'_, Self>>, value: &str) -> Option<Self> {
+        // Keys nested inside a namespace are opaque to the server
+        if key.is_none() {
+            SettingsProperty::parse(value)
+        } else {
+            None
+        }
+    }
+
+    fn to_cow(&self) -> Cow<'static, str> {
+        match self {
+            SettingsProperty::Id => "id".into(),
+            SettingsProperty::Namespace(namespace) => namespace.clone().into(),
+            SettingsProperty::Setting { namespace, key } => {
+                format!("{namespace}/{}", key.replace('~', "~0").replace('/', "~1")).into()
+            }
+        }
+    }
+}
+
+impl Element for SettingsValue {
+    type Property = SettingsProperty;
+
+    fn try_parse<P>(key: &Key<'_, Self::Property>, value: &str) -> Option<Self> {
+        if let Key::Property(SettingsProperty::Id) = key {
+            Id::from_str(value).ok().map(SettingsValue::Id)
+        } else {
+            None
+        }
+    }
+
+    fn to_cow(&self) -> Cow<'static, str> {
+        match self {
+            SettingsValue::Id(id) => id.to_string().into(),
+        }
+    }
+}
+
+impl SettingsProperty {
+    fn parse(value: &str) -> Option<Self> {
+        if value == "id" {
+            Some(SettingsProperty::Id)
+        } else if let Some((namespace, key)) = value.split_once('/') {
+            // Patch a single setting using the "namespace/key" syntax
+            Some(SettingsProperty::Setting {
+                namespace: namespace.to_string(),
+                key: key.replace("~1", "/").replace("~0", "~"),
+            })
+        } else {
+            Some(SettingsProperty::Namespace(value.to_string()))
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            SettingsProperty::Namespace(namespace)
+            | SettingsProperty::Setting { namespace, .. } => Some(namespace),
+            SettingsProperty::Id => None,
+        }
+    }
+}
+
+impl serde::Serialize for SettingsProperty {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_cow().as_ref())
+    }
+}
+
+impl FromStr for SettingsProperty {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SettingsProperty::parse(s).ok_or(())
+    }
+}
+
+impl JmapObject for Settings {
+    type Property = SettingsProperty;
+
+    type Element = SettingsValue;
+
+    type Id = Id;
+
+    type Filter = ();
+
+    type Comparator = ();
+
+    type GetArguments = ();
+
+    type SetArguments<'de> = ();
+
+    type QueryArguments = ();
+
+    type CopyArguments = ();
+
+    const ID_PROPERTY: Self::Property = SettingsProperty::Id;
+}
+
+impl From<Id> for SettingsValue {
+    fn from(id: Id) -> Self {
+        SettingsValue::Id(id)
+    }
+}
+
+impl JmapObjectId for SettingsValue {
+    fn as_id(&self) -> Option<Id> {
+        match self {
+            SettingsValue::Id(id) => Some(*id),
+        }
+    }
+
+    fn as_any_id(&self) -> Option<AnyId> {
+        match self {
+            SettingsValue::Id(id) => Some(AnyId::Id(*id)),
+        }
+    }
+
+    fn as_id_ref(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl TryFrom<AnyId> for SettingsValue {
+    type Error = ();
+
+    fn try_from(value: AnyId) -> Result<Self, Self::Error> {
+        match value {
+            AnyId::Id(id) => Ok(SettingsValue::Id(id)),
+            _ => Err(()),
+        }
+    }
+}
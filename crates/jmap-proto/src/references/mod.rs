@@ -168,6 +168,7 @@ mod tests {
                             can_calculate_changes: Default::default(),
                             position: Default::default(),
                             ids: vec![Id::new(4), Id::new(5)],
+                            account_ids: Default::default(),
                             total: Default::default(),
                             limit: Default::default(),
                         }),
@@ -12,6 +12,7 @@ use crate::{
         import::ImportEmailRequest,
         search_snippet::GetSearchSnippetRequest,
         set::{SetRequest, SetResponse},
+        strip::StripAttachmentsRequest,
         upload::{BlobUploadRequest, DataSourceObject},
     },
     object::{AnyId, JmapObject, JmapObjectId},
@@ -39,6 +40,7 @@ impl Response<'_> {
                 GetRequestMethod::PushSubscription(request) => request.resolve_references(self)?,
                 GetRequestMethod::Sieve(request) => request.resolve_references(self)?,
                 GetRequestMethod::VacationResponse(request) => request.resolve_references(self)?,
+                GetRequestMethod::Settings(request) => request.resolve_references(self)?,
                 GetRequestMethod::Principal(request) => request.resolve_references(self)?,
                 GetRequestMethod::Quota(request) => request.resolve_references(self)?,
                 GetRequestMethod::Blob(request) => request.resolve_references(self)?,
@@ -51,6 +53,7 @@ impl Response<'_> {
                 SetRequestMethod::PushSubscription(request) => request.resolve_references(self)?,
                 SetRequestMethod::Sieve(request) => request.resolve_references(self)?,
                 SetRequestMethod::VacationResponse(request) => request.resolve_references(self)?,
+                SetRequestMethod::Settings(request) => request.resolve_references(self)?,
             },
             RequestMethod::Copy(request) => match request {
                 CopyRequestMethod::Email(request) => request.resolve_references(self)?,
@@ -58,6 +61,7 @@ impl Response<'_> {
             },
             RequestMethod::ImportEmail(request) => request.resolve_references(self)?,
             RequestMethod::SearchSnippet(request) => request.resolve_references(self)?,
+            RequestMethod::StripAttachments(request) => request.resolve_references(self)?,
             RequestMethod::UploadBlob(request) => request.resolve_references(self)?,
             _ => {}
         }
@@ -264,6 +268,23 @@ impl ResolveReference for GetSearchSnippetRequest {
     }
 }
 
+impl ResolveReference for StripAttachmentsRequest {
+    fn resolve_references(&mut self, response: &Response<'_>) -> trc::Result<()> {
+        // Resolve emailIds references
+        if let MaybeResultReference::Reference(reference) = &self.email_ids {
+            self.email_ids = MaybeResultReference::Value(
+                response
+                    .eval_result_references(reference)?
+                    .into_ids::<Id>(reference)
+                    .map(|f| f.map(MaybeInvalid::Value))
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+
+        Ok(())
+    }
+}
+
 impl ResolveReference for BlobUploadRequest {
     fn resolve_references(&mut self, response: &Response<'_>) -> trc::Result<()> {
         let mut graph = HashMap::with_capacity(self.create.len());
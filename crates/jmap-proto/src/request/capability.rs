@@ -70,6 +70,12 @@ pub enum Capability {
     Blob = 1 << 8,
     #[serde(rename(serialize = "urn:ietf:params:jmap:quota"))]
     Quota = 1 << 9,
+    #[serde(rename(serialize = "urn:stalwart:jmap:branding"))]
+    Branding = 1 << 10,
+    #[serde(rename(serialize = "urn:stalwart:jmap:settings"))]
+    Settings = 1 << 11,
+    #[serde(rename(serialize = "urn:stalwart:jmap:archive"))]
+    Archive = 1 << 12,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -87,6 +93,9 @@ pub enum Capabilities {
     SieveAccount(SieveAccountCapabilities),
     SieveSession(SieveSessionCapabilities),
     Blob(BlobCapabilities),
+    Branding(BrandingCapabilities),
+    Settings(SettingsCapabilities),
+    Archive(ArchiveCapabilities),
     Empty(EmptyCapabilities),
 }
 
@@ -178,6 +187,41 @@ pub struct BlobCapabilities {
     pub supported_digest_algorithms: Vec<&'static str>,
 }
 
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct BrandingCapabilities {
+    #[serde(rename(serialize = "productName"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product_name: Option<String>,
+    #[serde(rename(serialize = "supportUrl"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support_url: Option<String>,
+    #[serde(rename(serialize = "primaryColor"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_color: Option<String>,
+    #[serde(rename(serialize = "accentColor"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<String>,
+    #[serde(rename(serialize = "logoUrl"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_url: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SettingsCapabilities {
+    #[serde(rename(serialize = "maxSize"))]
+    pub max_size: usize,
+    #[serde(rename(serialize = "maxNamespaces"))]
+    pub max_namespaces: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArchiveCapabilities {
+    #[serde(rename(serialize = "minAttachmentSize"))]
+    pub min_attachment_size: usize,
+    #[serde(rename(serialize = "keepsOriginals"))]
+    pub keeps_originals: bool,
+}
+
 #[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct EmptyCapabilities {}
 
@@ -258,6 +302,10 @@ impl Session {
         );
     }
 
+    pub fn add_capability(&mut self, capability: Capability, value: Capabilities) {
+        self.capabilities.set(capability, value);
+    }
+
     pub fn set_state(&mut self, state: u32) {
         self.state = state;
     }
@@ -332,6 +380,9 @@ impl Capability {
             "urn:ietf:params:jmap:sieve" => Capability::Sieve,
             "urn:ietf:params:jmap:blob" => Capability::Blob,
             "urn:ietf:params:jmap:quota" => Capability::Quota,
+            "urn:stalwart:jmap:branding" => Capability::Branding,
+            "urn:stalwart:jmap:settings" => Capability::Settings,
+            "urn:stalwart:jmap:archive" => Capability::Archive,
         )
     }
 }
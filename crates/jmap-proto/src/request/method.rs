@@ -27,6 +27,7 @@ pub enum MethodObject {
     SieveScript,
     Principal,
     Quota,
+    Settings,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,6 +40,7 @@ pub enum MethodFunction {
     Copy,
     Import,
     Parse,
+    StripAttachments,
     Validate,
     Lookup,
     Upload,
@@ -85,6 +87,7 @@ impl MethodName {
             (MethodFunction::Copy, MethodObject::Email) => "Email/copy",
             (MethodFunction::Import, MethodObject::Email) => "Email/import",
             (MethodFunction::Parse, MethodObject::Email) => "Email/parse",
+            (MethodFunction::StripAttachments, MethodObject::Email) => "Email/stripAttachments",
 
             (MethodFunction::Get, MethodObject::SearchSnippet) => "SearchSnippet/get",
 
@@ -103,6 +106,9 @@ impl MethodName {
             (MethodFunction::Get, MethodObject::VacationResponse) => "VacationResponse/get",
             (MethodFunction::Set, MethodObject::VacationResponse) => "VacationResponse/set",
 
+            (MethodFunction::Get, MethodObject::Settings) => "Settings/get",
+            (MethodFunction::Set, MethodObject::Settings) => "Settings/set",
+
             (MethodFunction::Get, MethodObject::SieveScript) => "SieveScript/get",
             (MethodFunction::Set, MethodObject::SieveScript) => "SieveScript/set",
             (MethodFunction::Query, MethodObject::SieveScript) => "SieveScript/query",
@@ -149,6 +155,7 @@ impl MethodName {
             "Email/copy" => (MethodObject::Email, MethodFunction::Copy),
             "Email/import" => (MethodObject::Email, MethodFunction::Import),
             "Email/parse" => (MethodObject::Email, MethodFunction::Parse),
+            "Email/stripAttachments" => (MethodObject::Email, MethodFunction::StripAttachments),
 
             "SearchSnippet/get" => (MethodObject::SearchSnippet, MethodFunction::Get),
 
@@ -165,6 +172,9 @@ impl MethodName {
             "VacationResponse/get" => (MethodObject::VacationResponse, MethodFunction::Get),
             "VacationResponse/set" => (MethodObject::VacationResponse, MethodFunction::Set),
 
+            "Settings/get" => (MethodObject::Settings, MethodFunction::Get),
+            "Settings/set" => (MethodObject::Settings, MethodFunction::Set),
+
             "SieveScript/get" => (MethodObject::SieveScript, MethodFunction::Get),
             "SieveScript/set" => (MethodObject::SieveScript, MethodFunction::Set),
             "SieveScript/query" => (MethodObject::SieveScript, MethodFunction::Query),
@@ -207,6 +217,7 @@ impl Display for MethodObject {
             MethodObject::Thread => "Thread",
             MethodObject::Email => "Email",
             MethodObject::Quota => "Quota",
+            MethodObject::Settings => "Settings",
         })
     }
 }
@@ -24,13 +24,14 @@ use crate::{
         query_changes::QueryChangesRequest,
         search_snippet::GetSearchSnippetRequest,
         set::SetRequest,
+        strip::StripAttachmentsRequest,
         upload::BlobUploadRequest,
         validate::ValidateSieveScriptRequest,
     },
     object::{
         AnyId, blob::Blob, email::Email, email_submission::EmailSubmission, identity::Identity,
         mailbox::Mailbox, principal::Principal, push_subscription::PushSubscription, quota::Quota,
-        sieve::Sieve, thread::Thread, vacation_response::VacationResponse,
+        settings::Settings, sieve::Sieve, thread::Thread, vacation_response::VacationResponse,
     },
     request::{capability::CapabilityIds, reference::MaybeIdReference},
 };
@@ -60,6 +61,7 @@ pub enum RequestMethod<'x> {
     Copy(CopyRequestMethod<'x>),
     ImportEmail(ImportEmailRequest),
     ParseEmail(ParseEmailRequest),
+    StripAttachments(StripAttachmentsRequest),
     Query(QueryRequestMethod),
     QueryChanges(QueryChangesRequestMethod),
     SearchSnippet(GetSearchSnippetRequest),
@@ -80,6 +82,7 @@ pub enum GetRequestMethod {
     PushSubscription(GetRequest<PushSubscription>),
     Sieve(GetRequest<Sieve>),
     VacationResponse(GetRequest<VacationResponse>),
+    Settings(GetRequest<Settings>),
     Principal(GetRequest<Principal>),
     Quota(GetRequest<Quota>),
     Blob(GetRequest<Blob>),
@@ -94,6 +97,7 @@ pub enum SetRequestMethod<'x> {
     PushSubscription(SetRequest<'x, PushSubscription>),
     Sieve(SetRequest<'x, Sieve>),
     VacationResponse(SetRequest<'x, VacationResponse>),
+    Settings(SetRequest<'x, Settings>),
 }
 
 #[derive(Debug)]
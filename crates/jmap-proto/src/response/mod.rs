@@ -21,13 +21,14 @@ use crate::{
         query_changes::QueryChangesResponse,
         search_snippet::GetSearchSnippetResponse,
         set::SetResponse,
+        strip::StripAttachmentsResponse,
         upload::BlobUploadResponse,
         validate::ValidateSieveScriptResponse,
     },
     object::{
         AnyId, blob::Blob, email::Email, email_submission::EmailSubmission, identity::Identity,
         mailbox::Mailbox, principal::Principal, push_subscription::PushSubscription, quota::Quota,
-        sieve::Sieve, thread::Thread, vacation_response::VacationResponse,
+        settings::Settings, sieve::Sieve, thread::Thread, vacation_response::VacationResponse,
     },
     request::{Call, method::MethodName},
 };
@@ -43,6 +44,7 @@ pub enum ResponseMethod<'x> {
     Copy(CopyResponseMethod),
     ImportEmail(ImportEmailResponse),
     ParseEmail(ParseEmailResponse),
+    StripAttachments(StripAttachmentsResponse),
     QueryChanges(QueryChangesResponse),
     Query(QueryResponse),
     SearchSnippet(GetSearchSnippetResponse),
@@ -64,6 +66,7 @@ pub enum GetResponseMethod {
     PushSubscription(GetResponse<PushSubscription>),
     Sieve(GetResponse<Sieve>),
     VacationResponse(GetResponse<VacationResponse>),
+    Settings(GetResponse<Settings>),
     Principal(GetResponse<Principal>),
     Quota(GetResponse<Quota>),
     Blob(GetResponse<Blob>),
@@ -79,6 +82,7 @@ pub enum SetResponseMethod {
     PushSubscription(SetResponse<PushSubscription>),
     Sieve(SetResponse<Sieve>),
     VacationResponse(SetResponse<VacationResponse>),
+    Settings(SetResponse<Settings>),
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -211,6 +215,12 @@ impl<'x> From<GetResponse<VacationResponse>> for ResponseMethod<'x> {
     }
 }
 
+impl<'x> From<GetResponse<Settings>> for ResponseMethod<'x> {
+    fn from(value: GetResponse<Settings>) -> Self {
+        ResponseMethod::Get(GetResponseMethod::Settings(value))
+    }
+}
+
 impl<'x> From<GetResponse<Principal>> for ResponseMethod<'x> {
     fn from(value: GetResponse<Principal>) -> Self {
         ResponseMethod::Get(GetResponseMethod::Principal(value))
@@ -272,6 +282,12 @@ impl<'x> From<SetResponse<VacationResponse>> for ResponseMethod<'x> {
     }
 }
 
+impl<'x> From<SetResponse<Settings>> for ResponseMethod<'x> {
+    fn from(value: SetResponse<Settings>) -> Self {
+        ResponseMethod::Set(SetResponseMethod::Settings(value))
+    }
+}
+
 // Direct ChangesResponse conversions to ResponseMethod
 impl<'x> From<ChangesResponse<Email>> for ResponseMethod<'x> {
     fn from(value: ChangesResponse<Email>) -> Self {
@@ -335,6 +351,12 @@ impl<'x> From<ParseEmailResponse> for ResponseMethod<'x> {
     }
 }
 
+impl<'x> From<StripAttachmentsResponse> for ResponseMethod<'x> {
+    fn from(value: StripAttachmentsResponse) -> Self {
+        ResponseMethod::StripAttachments(value)
+    }
+}
+
 impl<'x> From<QueryChangesResponse> for ResponseMethod<'x> {
     fn from(value: QueryChangesResponse) -> Self {
         ResponseMethod::QueryChanges(value)
@@ -64,6 +64,7 @@ impl JmapAuthorization for AccessToken {
                 GetRequestMethod::PushSubscription(_) => Permission::JmapPushSubscriptionGet,
                 GetRequestMethod::Sieve(_) => Permission::JmapSieveScriptGet,
                 GetRequestMethod::VacationResponse(_) => Permission::JmapVacationResponseGet,
+                GetRequestMethod::Settings(_) => Permission::JmapSettingsGet,
                 GetRequestMethod::Principal(_) => Permission::JmapPrincipalGet,
                 GetRequestMethod::Quota(_) => Permission::JmapQuotaGet,
                 GetRequestMethod::Blob(_) => Permission::JmapBlobGet,
@@ -76,6 +77,7 @@ impl JmapAuthorization for AccessToken {
                 SetRequestMethod::PushSubscription(_) => Permission::JmapPushSubscriptionSet,
                 SetRequestMethod::Sieve(_) => Permission::JmapSieveScriptSet,
                 SetRequestMethod::VacationResponse(_) => Permission::JmapVacationResponseSet,
+                SetRequestMethod::Settings(_) => Permission::JmapSettingsSet,
             },
             RequestMethod::Changes(_) => match object {
                 MethodObject::Email => Permission::JmapEmailChanges,
@@ -90,7 +92,8 @@ impl JmapAuthorization for AccessToken {
                 | MethodObject::SearchSnippet
                 | MethodObject::VacationResponse
                 | MethodObject::SieveScript
-                | MethodObject::Principal => Permission::JmapEmailChanges, // Unimplemented
+                | MethodObject::Principal
+                | MethodObject::Settings => Permission::JmapEmailChanges, // Unimplemented
             },
             RequestMethod::Copy(m) => match &m {
                 CopyRequestMethod::Email(_) => Permission::JmapEmailCopy,
@@ -98,6 +101,7 @@ impl JmapAuthorization for AccessToken {
             },
             RequestMethod::ImportEmail(_) => Permission::JmapEmailImport,
             RequestMethod::ParseEmail(_) => Permission::JmapEmailParse,
+            RequestMethod::StripAttachments(_) => Permission::JmapEmailSet,
             RequestMethod::QueryChanges(m) => match m {
                 QueryChangesRequestMethod::Email(_) => Permission::JmapEmailQueryChanges,
                 QueryChangesRequestMethod::Mailbox(_) => Permission::JmapMailboxQueryChanges,
@@ -72,7 +72,9 @@ impl ToRequestError for trc::Error {
                 trc::LimitEvent::SizeRequest => RequestError::limit(RequestLimitError::SizeRequest),
                 trc::LimitEvent::SizeUpload => RequestError::limit(RequestLimitError::SizeUpload),
                 trc::LimitEvent::CallsIn => RequestError::limit(RequestLimitError::CallsIn),
-                trc::LimitEvent::ConcurrentRequest | trc::LimitEvent::ConcurrentConnection => {
+                trc::LimitEvent::ConcurrentRequest
+                | trc::LimitEvent::ConcurrentConnection
+                | trc::LimitEvent::ConcurrentMessage => {
                     RequestError::limit(RequestLimitError::ConcurrentRequest)
                 }
                 trc::LimitEvent::ConcurrentUpload => {
@@ -88,7 +90,9 @@ impl ToRequestError for trc::Error {
                         .and_then(|v| v.to_uint())
                         .unwrap_or_default() as usize,
                 ),
-                trc::LimitEvent::TooManyRequests => RequestError::too_many_requests(),
+                trc::LimitEvent::TooManyRequests | trc::LimitEvent::MessageBuffer => {
+                    RequestError::too_many_requests()
+                }
             },
             trc::EventType::Auth(cause) => match cause {
                 trc::AuthEvent::MissingTotp => {
@@ -103,8 +107,13 @@ impl ToRequestError for trc::Error {
                 | trc::SecurityEvent::AbuseBan
                 | trc::SecurityEvent::LoiterBan
                 | trc::SecurityEvent::IpBlocked => RequestError::too_many_auth_attempts(),
-                trc::SecurityEvent::Unauthorized => RequestError::forbidden(),
+                trc::SecurityEvent::Unauthorized | trc::SecurityEvent::TlsFingerprintBlocked => {
+                    RequestError::forbidden()
+                }
             },
+            trc::EventType::Icap(trc::IcapEvent::UploadRejected) => {
+                RequestError::blank(StatusCode::FORBIDDEN.as_u16(), "Upload rejected", details)
+            }
             trc::EventType::Resource(cause) => match cause {
                 trc::ResourceEvent::NotFound => RequestError::not_found(),
                 trc::ResourceEvent::BadParameters => RequestError::blank(
@@ -12,13 +12,14 @@ use crate::{
     changes::{get::ChangesLookup, query::QueryChanges},
     email::{
         copy::JmapEmailCopy, get::EmailGet, import::EmailImport, parse::EmailParse,
-        query::EmailQuery, set::EmailSet, snippet::EmailSearchSnippet,
+        query::EmailQuery, set::EmailSet, snippet::EmailSearchSnippet, strip::EmailStrip,
     },
     identity::{get::IdentityGet, set::IdentitySet},
     mailbox::{get::MailboxGet, query::MailboxQuery, set::MailboxSet},
     principal::{get::PrincipalGet, query::PrincipalQuery},
     push::{get::PushSubscriptionFetch, set::PushSubscriptionSet},
     quota::{get::QuotaGet, query::QuotaQuery},
+    settings::{get::SettingsGet, set::SettingsSet},
     sieve::{
         get::SieveScriptGet, query::SieveScriptQuery, set::SieveScriptSet,
         validate::SieveScriptValidate,
@@ -126,6 +127,9 @@ impl RequestHandler for Server {
                                     SetResponseMethod::VacationResponse(set_response) => {
                                         set_response.update_created_ids(&mut response);
                                     }
+                                    SetResponseMethod::Settings(set_response) => {
+                                        set_response.update_created_ids(&mut response);
+                                    }
                                 }
                             }
                             ResponseMethod::ImportEmail(import_response) => {
@@ -235,6 +239,12 @@ impl RequestHandler for Server {
 
                     self.vacation_response_get(req).await?.into()
                 }
+                GetRequestMethod::Settings(mut req) => {
+                    set_account_id_if_missing(&mut req.account_id, access_token);
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.settings_get(req).await?.into()
+                }
                 GetRequestMethod::Principal(req) => self.principal_get(req).await?.into(),
                 GetRequestMethod::Quota(mut req) => {
                     set_account_id_if_missing(&mut req.account_id, access_token);
@@ -330,6 +340,12 @@ impl RequestHandler for Server {
 
                     self.vacation_response_set(req, access_token).await?.into()
                 }
+                SetRequestMethod::Settings(mut req) => {
+                    set_account_id_if_missing(&mut req.account_id, access_token);
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.settings_set(req).await?.into()
+                }
             },
             RequestMethod::Changes(mut req) => {
                 set_account_id_if_missing(&mut req.account_id, access_token);
@@ -369,6 +385,14 @@ impl RequestHandler for Server {
 
                 self.email_parse(req, access_token).await?.into()
             }
+            RequestMethod::StripAttachments(mut req) => {
+                set_account_id_if_missing(&mut req.account_id, access_token);
+                access_token.assert_has_access(req.account_id, Collection::Email)?;
+
+                self.email_strip_attachments(req, access_token, session)
+                    .await?
+                    .into()
+            }
             RequestMethod::QueryChanges(req) => self.query_changes(req, access_token).await?.into(),
             RequestMethod::SearchSnippet(mut req) => {
                 set_account_id_if_missing(&mut req.account_id, access_token);
@@ -392,7 +416,9 @@ impl RequestHandler for Server {
                 set_account_id_if_missing(&mut req.account_id, access_token);
                 access_token.assert_is_member(req.account_id)?;
 
-                self.blob_upload_many(req, access_token).await?.into()
+                self.blob_upload_many(req, access_token, session)
+                    .await?
+                    .into()
             }
             RequestMethod::Echo(req) => req.into(),
             RequestMethod::Error(error) => return Err(error),
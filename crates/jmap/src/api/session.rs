@@ -8,7 +8,7 @@ use std::sync::Arc;
 
 use common::{Server, auth::AccessToken};
 use directory::backend::internal::manage::ManageDirectory;
-use jmap_proto::request::capability::{Capability, Session};
+use jmap_proto::request::capability::{BrandingCapabilities, Capabilities, Capability, Session};
 use std::future::Future;
 use trc::AddContext;
 use types::{acl::Acl, collection::Collection, id::Id};
@@ -41,6 +41,33 @@ impl SessionHandler for Server {
             &self.core.jmap.capabilities.account,
         );
 
+        // Add branding for the account's domain
+        if let Some((domain, branding)) = access_token
+            .emails
+            .first()
+            .unwrap_or(&access_token.name)
+            .rsplit_once('@')
+            .and_then(|(_, domain)| {
+                let domain = domain.to_lowercase();
+                self.branding(&domain).cloned().map(|b| (domain, b))
+            })
+        {
+            let logo_url = branding
+                .logo
+                .as_ref()
+                .map(|_| format!("{}/branding/{domain}/logo", session.base_url()));
+            session.add_capability(
+                Capability::Branding,
+                Capabilities::Branding(BrandingCapabilities {
+                    product_name: branding.product_name,
+                    support_url: branding.support_url,
+                    primary_color: branding.primary_color,
+                    accent_color: branding.accent_color,
+                    logo_url,
+                }),
+            );
+        }
+
         // Add secondary accounts
         for id in access_token.secondary_ids() {
             let is_personal = !access_token.is_member(*id);
@@ -7,8 +7,9 @@
 use std::sync::Arc;
 
 use super::{UploadResponse, download::BlobDownload};
-use common::{Server, auth::AccessToken};
+use common::{Server, auth::AccessToken, icap::upload::UploadSource};
 use directory::Permission;
+use http_proto::HttpSessionData;
 use jmap_proto::{
     error::set::SetError,
     method::upload::{
@@ -29,6 +30,7 @@ pub trait BlobUpload: Sync + Send {
         &self,
         request: BlobUploadRequest,
         access_token: &AccessToken,
+        session: &HttpSessionData,
     ) -> impl Future<Output = trc::Result<BlobUploadResponse>> + Send;
 
     fn blob_upload(
@@ -37,6 +39,7 @@ pub trait BlobUpload: Sync + Send {
         content_type: &str,
         data: &[u8],
         access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
     ) -> impl Future<Output = trc::Result<UploadResponse>> + Send;
 }
 
@@ -45,6 +48,7 @@ impl BlobUpload for Server {
         &self,
         request: BlobUploadRequest,
         access_token: &AccessToken,
+        session: &HttpSessionData,
     ) -> trc::Result<BlobUploadResponse> {
         let mut response = BlobUploadResponse {
             account_id: request.account_id,
@@ -189,6 +193,33 @@ impl BlobUpload for Server {
                 continue 'outer;
             }
 
+            // Scan upload
+            if let Err(err) = self
+                .scan_upload(
+                    UploadSource::Jmap,
+                    account_id,
+                    &data,
+                    session.remote_ip,
+                    session.session_id,
+                )
+                .await
+            {
+                if err.matches(trc::EventType::Icap(trc::IcapEvent::UploadRejected)) {
+                    response.not_created.append(
+                        create_id,
+                        SetError::forbidden().with_description(
+                            err.value(trc::Key::Details)
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("Upload rejected by content policy")
+                                .to_string(),
+                        ),
+                    );
+                    continue 'outer;
+                } else {
+                    return Err(err);
+                }
+            }
+
             // Write blob
             response.created.insert(
                 create_id,
@@ -209,6 +240,7 @@ impl BlobUpload for Server {
         content_type: &str,
         data: &[u8],
         access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
     ) -> trc::Result<UploadResponse> {
         // Limit concurrent uploads
         let _in_flight = self
@@ -252,6 +284,16 @@ impl BlobUpload for Server {
             return err;
         }
 
+        // Scan upload
+        self.scan_upload(
+            UploadSource::Jmap,
+            account_id.document_id(),
+            data,
+            session.remote_ip,
+            session.session_id,
+        )
+        .await?;
+
         Ok(UploadResponse {
             account_id,
             blob_id: self
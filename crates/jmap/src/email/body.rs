@@ -22,6 +22,17 @@ pub trait ToBodyPart {
     ) -> Value<'static, EmailProperty, EmailValue>;
 }
 
+pub trait ToParsedBodyPart {
+    fn to_parsed_body_part(
+        &self,
+        part_id: u32,
+        properties: &[EmailProperty],
+        raw_message: &[u8],
+        blob_id: &BlobId,
+        parsed_email: &impl Fn(&MessagePart<'_>) -> Value<'static, EmailProperty, EmailValue>,
+    ) -> Value<'static, EmailProperty, EmailValue>;
+}
+
 impl ToBodyPart for Vec<MessagePart<'_>> {
     fn to_body_part(
         &self,
@@ -29,6 +40,19 @@ impl ToBodyPart for Vec<MessagePart<'_>> {
         properties: &[EmailProperty],
         raw_message: &[u8],
         blob_id: &BlobId,
+    ) -> Value<'static, EmailProperty, EmailValue> {
+        self.to_parsed_body_part(part_id, properties, raw_message, blob_id, &|_| Value::Null)
+    }
+}
+
+impl ToParsedBodyPart for Vec<MessagePart<'_>> {
+    fn to_parsed_body_part(
+        &self,
+        part_id: u32,
+        properties: &[EmailProperty],
+        raw_message: &[u8],
+        blob_id: &BlobId,
+        parsed_email: &impl Fn(&MessagePart<'_>) -> Value<'static, EmailProperty, EmailValue>,
     ) -> Value<'static, EmailProperty, EmailValue> {
         let mut parts = vec![part_id].into_iter();
         let mut parts_stack = Vec::new();
@@ -113,6 +137,7 @@ impl ToBodyPart for Vec<MessagePart<'_>> {
                             part.headers.header_to_value(property, raw_message)
                         }
                         EmailProperty::Headers => part.headers.headers_to_value(raw_message),
+                        EmailProperty::ParsedEmail if multipart.is_none() => parsed_email(part),
                         EmailProperty::SubParts => continue,
                         _ => Value::Null,
                     };
@@ -14,7 +14,10 @@ use crate::{
 use common::{Server, auth::AccessToken};
 use email::{
     cache::{MessageCacheFetch, email::MessageCacheAccess},
-    message::metadata::{ArchivedMetadataPartType, MessageMetadata},
+    message::{
+        calendar::{ArchivedCalendarParticipantPreview, ArchivedCalendarPreview, CalendarPreview},
+        metadata::{ArchivedMetadataPartType, MessageMetadata},
+    },
 };
 use jmap_proto::{
     method::get::{GetRequest, GetResponse},
@@ -24,6 +27,7 @@ use jmap_proto::{
 };
 use jmap_tools::{Key, Map, Value};
 use mail_parser::{ArchivedHeaderName, HeaderValue, core::rkyv::ArchivedGetHeader};
+use rkyv::{Archived, option::ArchivedOption, string::ArchivedString};
 use std::{borrow::Cow, future::Future};
 use trc::{AddContext, StoreEvent};
 use types::{
@@ -414,6 +418,28 @@ impl EmailGet for Server {
                         }
                         email.insert_unchecked(EmailProperty::BodyValues, body_values);
                     }
+                    EmailProperty::CalendarPreview => {
+                        let preview = self
+                            .get_archive_by_property(
+                                account_id,
+                                Collection::Email,
+                                id.document_id(),
+                                EmailField::CalendarPreview.into(),
+                            )
+                            .await?;
+                        email.insert_unchecked(
+                            EmailProperty::CalendarPreview,
+                            if let Some(preview) = preview {
+                                calendar_preview_to_value(
+                                    preview
+                                        .unarchive::<CalendarPreview>()
+                                        .caused_by(trc::location!())?,
+                                )
+                            } else {
+                                Value::Null
+                            },
+                        );
+                    }
 
                     _ => {
                         return Err(trc::JmapEvent::InvalidArguments
@@ -428,3 +454,74 @@ impl EmailGet for Server {
         Ok(response)
     }
 }
+
+fn calendar_preview_to_value(
+    preview: &ArchivedCalendarPreview,
+) -> Value<'static, EmailProperty, EmailValue> {
+    Map::with_capacity(2)
+        .with_key_value(Key::Borrowed("method"), optional_text(&preview.method))
+        .with_key_value(
+            Key::Borrowed("events"),
+            preview
+                .events
+                .iter()
+                .map(|event| {
+                    Map::with_capacity(7)
+                        .with_key_value(Key::Borrowed("uid"), optional_text(&event.uid))
+                        .with_key_value(Key::Borrowed("title"), optional_text(&event.title))
+                        .with_key_value(Key::Borrowed("location"), optional_text(&event.location))
+                        .with_key_value(Key::Borrowed("start"), optional_date(&event.start))
+                        .with_key_value(Key::Borrowed("end"), optional_date(&event.end))
+                        .with_key_value(
+                            Key::Borrowed("organizer"),
+                            event
+                                .organizer
+                                .as_ref()
+                                .map(participant_to_value)
+                                .unwrap_or(Value::Null),
+                        )
+                        .with_key_value(
+                            Key::Borrowed("attendees"),
+                            event
+                                .attendees
+                                .iter()
+                                .map(participant_to_value)
+                                .collect::<Vec<_>>(),
+                        )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .into()
+}
+
+fn participant_to_value(
+    participant: &ArchivedCalendarParticipantPreview,
+) -> Value<'static, EmailProperty, EmailValue> {
+    Map::with_capacity(4)
+        .with_key_value(Key::Borrowed("email"), participant.email.to_string())
+        .with_key_value(Key::Borrowed("name"), optional_text(&participant.name))
+        .with_key_value(
+            Key::Borrowed("partStat"),
+            optional_text(&participant.part_stat),
+        )
+        .with_key_value(Key::Borrowed("rsvp"), participant.rsvp)
+        .into()
+}
+
+fn optional_text(
+    value: &ArchivedOption<ArchivedString>,
+) -> Value<'static, EmailProperty, EmailValue> {
+    value
+        .as_ref()
+        .map(|value| Value::Str(value.to_string().into()))
+        .unwrap_or(Value::Null)
+}
+
+fn optional_date(
+    value: &ArchivedOption<Archived<i64>>,
+) -> Value<'static, EmailProperty, EmailValue> {
+    value
+        .as_ref()
+        .map(|value| EmailValue::Date(UTCDate::from_timestamp(i64::from(*value))).into())
+        .unwrap_or(Value::Null)
+}
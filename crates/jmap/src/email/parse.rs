@@ -5,23 +5,28 @@
  */
 
 use common::{Server, auth::AccessToken};
-use email::message::index::PREVIEW_LENGTH;
+use email::message::{
+    index::PREVIEW_LENGTH,
+    tnef::{TnefMessage, is_tnef_part},
+};
 use jmap_proto::{
     method::parse::{ParseEmailRequest, ParseEmailResponse},
-    object::email::EmailProperty,
+    object::email::{EmailProperty, EmailValue},
     request::IntoValid,
 };
 use jmap_tools::{Key, Map, Value};
 use mail_parser::{
-    MessageParser, PartType, decoders::html::html_to_text, parsers::preview::preview_text,
+    Encoding, Message, MessageParser, MessagePart, PartType, decoders::html::html_to_text,
+    parsers::preview::preview_text,
 };
 use std::future::Future;
+use types::blob::BlobId;
 use utils::map::vec_map::VecMap;
 
 use crate::blob::download::BlobDownload;
 
 use super::{
-    body::{ToBodyPart, TruncateBody},
+    body::{ToParsedBodyPart, TruncateBody, truncate_html, truncate_plain},
     headers::HeaderToValue,
 };
 
@@ -86,10 +91,15 @@ impl EmailParse for Server {
                     EmailProperty::Location,
                 ]
             });
-        let fetch_text_body_values = request.fetch_text_body_values.unwrap_or(false);
-        let fetch_html_body_values = request.fetch_html_body_values.unwrap_or(false);
-        let fetch_all_body_values = request.fetch_all_body_values.unwrap_or(false);
-        let max_body_value_bytes = request.max_body_value_bytes.unwrap_or(0);
+        let options = ParseOptions {
+            properties: &properties,
+            body_properties: &body_properties,
+            fetch_text_body_values: request.fetch_text_body_values.unwrap_or(false),
+            fetch_html_body_values: request.fetch_html_body_values.unwrap_or(false),
+            fetch_all_body_values: request.fetch_all_body_values.unwrap_or(false),
+            max_body_value_bytes: request.max_body_value_bytes.unwrap_or(0),
+            max_depth: self.core.jmap.mail_parse_max_depth,
+        };
 
         let mut response = ParseEmailResponse {
             account_id: request.account_id,
@@ -115,158 +125,360 @@ impl EmailParse for Server {
             };
 
             // Prepare response
-            let mut email = Map::with_capacity(properties.len());
-            for property in &properties {
-                match property {
-                    EmailProperty::BlobId => {
-                        email.insert_unchecked(EmailProperty::BlobId, blob_id.clone());
-                    }
+            let email = options.message_to_value(&message, &raw_message, &blob_id, 0)?;
+            response.parsed.append(blob_id, email);
+        }
 
-                    EmailProperty::Size => {
-                        email.insert_unchecked(
-                            EmailProperty::Size,
-                            Value::Number(raw_message.len().into()),
-                        );
-                    }
-                    EmailProperty::HasAttachment => {
-                        email.insert_unchecked(
-                            EmailProperty::HasAttachment,
-                            Value::Bool(message.parts.iter().enumerate().any(|(part_id, part)| {
-                                let part_id = part_id as u32;
-                                match &part.body {
-                                    PartType::Html(_) | PartType::Text(_) => {
-                                        !message.text_body.contains(&part_id)
-                                            && !message.html_body.contains(&part_id)
-                                    }
-                                    PartType::Binary(_) | PartType::Message(_) => true,
-                                    _ => false,
-                                }
-                            })),
-                        );
-                    }
-                    EmailProperty::Preview => {
-                        email.insert_unchecked(
-                            EmailProperty::Preview,
-                            match message
-                                .text_body
-                                .first()
-                                .or_else(|| message.html_body.first())
-                                .and_then(|idx| message.parts.get(*idx as usize))
-                                .map(|part| &part.body)
-                            {
-                                Some(PartType::Text(text)) => {
-                                    preview_text(text.replace('\r', "").into(), PREVIEW_LENGTH)
-                                        .into()
+        Ok(response)
+    }
+}
+
+struct ParseOptions<'x> {
+    properties: &'x [EmailProperty],
+    body_properties: &'x [EmailProperty],
+    fetch_text_body_values: bool,
+    fetch_html_body_values: bool,
+    fetch_all_body_values: bool,
+    max_body_value_bytes: usize,
+    max_depth: usize,
+}
+
+impl ParseOptions<'_> {
+    fn message_to_value(
+        &self,
+        message: &Message<'_>,
+        raw_message: &[u8],
+        blob_id: &BlobId,
+        depth: usize,
+    ) -> trc::Result<Value<'static, EmailProperty, EmailValue>> {
+        let parsed_email = |part: &MessagePart<'_>| self.nested_to_value(part, blob_id, depth);
+        let mut email = Map::with_capacity(self.properties.len());
+        for property in self.properties {
+            match property {
+                EmailProperty::BlobId => {
+                    email.insert_unchecked(EmailProperty::BlobId, blob_id.clone());
+                }
+
+                EmailProperty::Size => {
+                    email.insert_unchecked(
+                        EmailProperty::Size,
+                        Value::Number(raw_message.len().into()),
+                    );
+                }
+                EmailProperty::HasAttachment => {
+                    email.insert_unchecked(
+                        EmailProperty::HasAttachment,
+                        Value::Bool(message.parts.iter().enumerate().any(|(part_id, part)| {
+                            let part_id = part_id as u32;
+                            match &part.body {
+                                PartType::Html(_) | PartType::Text(_) => {
+                                    !message.text_body.contains(&part_id)
+                                        && !message.html_body.contains(&part_id)
                                 }
-                                Some(PartType::Html(html)) => preview_text(
-                                    html_to_text(html).replace('\r', "").into(),
-                                    PREVIEW_LENGTH,
-                                )
-                                .into(),
-                                _ => Value::Null,
-                            },
-                        );
-                    }
-                    EmailProperty::MessageId
-                    | EmailProperty::InReplyTo
-                    | EmailProperty::References
-                    | EmailProperty::Sender
-                    | EmailProperty::From
-                    | EmailProperty::To
-                    | EmailProperty::Cc
-                    | EmailProperty::Bcc
-                    | EmailProperty::ReplyTo
-                    | EmailProperty::Subject
-                    | EmailProperty::SentAt
-                    | EmailProperty::Header(_) => {
-                        email.insert_unchecked(
-                            property.clone(),
-                            message.parts[0]
-                                .headers
-                                .header_to_value(property, &raw_message),
-                        );
-                    }
-                    EmailProperty::Headers => {
-                        email.insert_unchecked(
-                            EmailProperty::Headers,
-                            message.parts[0].headers.headers_to_value(&raw_message),
-                        );
+                                PartType::Binary(_) | PartType::Message(_) => true,
+                                _ => false,
+                            }
+                        })),
+                    );
+                }
+                EmailProperty::Preview => {
+                    email.insert_unchecked(
+                        EmailProperty::Preview,
+                        match message
+                            .text_body
+                            .first()
+                            .or_else(|| message.html_body.first())
+                            .and_then(|idx| message.parts.get(*idx as usize))
+                            .map(|part| &part.body)
+                        {
+                            Some(PartType::Text(text)) => {
+                                preview_text(text.replace('\r', "").into(), PREVIEW_LENGTH).into()
+                            }
+                            Some(PartType::Html(html)) => preview_text(
+                                html_to_text(html).replace('\r', "").into(),
+                                PREVIEW_LENGTH,
+                            )
+                            .into(),
+                            _ => Value::Null,
+                        },
+                    );
+                }
+                EmailProperty::MessageId
+                | EmailProperty::InReplyTo
+                | EmailProperty::References
+                | EmailProperty::Sender
+                | EmailProperty::From
+                | EmailProperty::To
+                | EmailProperty::Cc
+                | EmailProperty::Bcc
+                | EmailProperty::ReplyTo
+                | EmailProperty::Subject
+                | EmailProperty::SentAt
+                | EmailProperty::Header(_) => {
+                    email.insert_unchecked(
+                        property.clone(),
+                        message.parts[0]
+                            .headers
+                            .header_to_value(property, raw_message),
+                    );
+                }
+                EmailProperty::Headers => {
+                    email.insert_unchecked(
+                        EmailProperty::Headers,
+                        message.parts[0].headers.headers_to_value(raw_message),
+                    );
+                }
+                EmailProperty::TextBody | EmailProperty::HtmlBody | EmailProperty::Attachments => {
+                    let list = match property {
+                        EmailProperty::TextBody => &message.text_body,
+                        EmailProperty::HtmlBody => &message.html_body,
+                        EmailProperty::Attachments => &message.attachments,
+                        _ => unreachable!(),
                     }
-                    EmailProperty::TextBody
-                    | EmailProperty::HtmlBody
-                    | EmailProperty::Attachments => {
-                        let list = match property {
-                            EmailProperty::TextBody => &message.text_body,
-                            EmailProperty::HtmlBody => &message.html_body,
-                            EmailProperty::Attachments => &message.attachments,
-                            _ => unreachable!(),
+                    .iter();
+                    email.insert_unchecked(
+                        property.clone(),
+                        list.map(|part_id| {
+                            message.parts.to_parsed_body_part(
+                                *part_id,
+                                self.body_properties,
+                                raw_message,
+                                blob_id,
+                                &parsed_email,
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                    );
+                }
+                EmailProperty::BodyStructure => {
+                    email.insert_unchecked(
+                        EmailProperty::BodyStructure,
+                        message.parts.to_parsed_body_part(
+                            0,
+                            self.body_properties,
+                            raw_message,
+                            blob_id,
+                            &parsed_email,
+                        ),
+                    );
+                }
+                EmailProperty::BodyValues => {
+                    let mut body_values = Map::with_capacity(message.parts.len());
+                    for (part_id, part) in message.parts.iter().enumerate() {
+                        let part_id = part_id as u32;
+                        if ((message.html_body.contains(&part_id)
+                            && (self.fetch_all_body_values || self.fetch_html_body_values))
+                            || (message.text_body.contains(&part_id)
+                                && (self.fetch_all_body_values || self.fetch_text_body_values)))
+                            && part.is_text()
+                        {
+                            let (is_truncated, value) =
+                                part.body.truncate(self.max_body_value_bytes);
+                            body_values.insert_unchecked(
+                                Key::Owned(part_id.to_string()),
+                                Map::with_capacity(3)
+                                    .with_key_value(
+                                        EmailProperty::IsEncodingProblem,
+                                        part.is_encoding_problem,
+                                    )
+                                    .with_key_value(EmailProperty::IsTruncated, is_truncated)
+                                    .with_key_value(EmailProperty::Value, value),
+                            );
                         }
-                        .iter();
-                        email.insert_unchecked(
-                            property.clone(),
-                            list.map(|part_id| {
-                                message.parts.to_body_part(
-                                    *part_id,
-                                    &body_properties,
-                                    &raw_message,
-                                    &blob_id,
-                                )
-                            })
-                            .collect::<Vec<_>>(),
-                        );
-                    }
-                    EmailProperty::BodyStructure => {
-                        email.insert_unchecked(
-                            EmailProperty::BodyStructure,
-                            message
-                                .parts
-                                .to_body_part(0, &body_properties, &raw_message, &blob_id),
-                        );
                     }
-                    EmailProperty::BodyValues => {
-                        let mut body_values = Map::with_capacity(message.parts.len());
-                        for (part_id, part) in message.parts.iter().enumerate() {
-                            let part_id = part_id as u32;
-                            if ((message.html_body.contains(&part_id)
-                                && (fetch_all_body_values || fetch_html_body_values))
-                                || (message.text_body.contains(&part_id)
-                                    && (fetch_all_body_values || fetch_text_body_values)))
-                                && part.is_text()
-                            {
-                                let (is_truncated, value) =
-                                    part.body.truncate(max_body_value_bytes);
-                                body_values.insert_unchecked(
-                                    Key::Owned(part_id.to_string()),
-                                    Map::with_capacity(3)
-                                        .with_key_value(
-                                            EmailProperty::IsEncodingProblem,
-                                            part.is_encoding_problem,
-                                        )
-                                        .with_key_value(EmailProperty::IsTruncated, is_truncated)
-                                        .with_key_value(EmailProperty::Value, value),
-                                );
-                            }
+                    email.insert_unchecked(EmailProperty::BodyValues, body_values);
+                }
+                EmailProperty::Id
+                | EmailProperty::ThreadId
+                | EmailProperty::Keywords
+                | EmailProperty::MailboxIds
+                | EmailProperty::ReceivedAt => {
+                    email.insert_unchecked(property.clone(), Value::Null);
+                }
+
+                _ => {
+                    return Err(trc::JmapEvent::InvalidArguments
+                        .into_err()
+                        .details(format!("Invalid property {property:?}")));
+                }
+            }
+        }
+        Ok(email.into())
+    }
+    // Parses forwarded messages and TNEF attachments up to the configured depth
+    fn nested_to_value(
+        &self,
+        part: &MessagePart<'_>,
+        blob_id: &BlobId,
+        depth: usize,
+    ) -> Value<'static, EmailProperty, EmailValue> {
+        if depth >= self.max_depth {
+            return Value::Null;
+        }
+
+        match &part.body {
+            PartType::Message(message) if part.encoding == Encoding::None => {
+                let base_offset = blob_id.start_offset();
+                let blob_id = BlobId::new_section(
+                    blob_id.hash.clone(),
+                    blob_id.class.clone(),
+                    part.offset_body as usize + base_offset,
+                    part.offset_end as usize + base_offset,
+                    part.encoding as u8,
+                );
+                self.message_to_value(message, message.raw_message(), &blob_id, depth + 1)
+                    .unwrap_or_default()
+            }
+            PartType::Binary(contents) | PartType::InlineBinary(contents) if is_tnef_part(part) => {
+                TnefMessage::parse(contents)
+                    .map(|message| self.tnef_to_value(&message, contents.len()))
+                    .unwrap_or_default()
+            }
+            _ => Value::Null,
+        }
+    }
+
+    // TNEF contents are not addressable as blob sections, so their parts
+    // are returned without a blobId
+    fn tnef_to_value(
+        &self,
+        message: &TnefMessage,
+        size: usize,
+    ) -> Value<'static, EmailProperty, EmailValue> {
+        let mut parts = Vec::with_capacity(message.attachments.len() + 2);
+        if let Some(text) = &message.text_body {
+            parts.push(TnefPart {
+                content_type: "text/plain",
+                name: None,
+                contents: text.as_bytes(),
+                text: Some(text),
+            });
+        }
+        if let Some(html) = &message.html_body {
+            parts.push(TnefPart {
+                content_type: "text/html",
+                name: None,
+                contents: html.as_bytes(),
+                text: Some(html),
+            });
+        }
+        let num_bodies = parts.len();
+        for attachment in &message.attachments {
+            parts.push(TnefPart {
+                content_type: attachment
+                    .content_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream"),
+                name: attachment.name.as_deref(),
+                contents: &attachment.contents,
+                text: None,
+            });
+        }
+
+        // The plain text body is added first and doubles as the HTML fallback
+        let text_body = (num_bodies > 0).then_some(0);
+        let html_body = parts[..num_bodies]
+            .iter()
+            .position(|part| part.content_type == "text/html")
+            .or(text_body);
+
+        let mut email = Map::with_capacity(self.properties.len());
+        for property in self.properties {
+            let value = match property {
+                EmailProperty::Size => Value::Number(size.into()),
+                EmailProperty::Subject => message.subject.clone().into(),
+                EmailProperty::HasAttachment => Value::Bool(!message.attachments.is_empty()),
+                EmailProperty::Preview => match text_body.map(|idx| &parts[idx]) {
+                    Some(TnefPart {
+                        content_type: "text/html",
+                        text: Some(html),
+                        ..
+                    }) => preview_text(html_to_text(html).replace('\r', "").into(), PREVIEW_LENGTH)
+                        .into(),
+                    Some(TnefPart {
+                        text: Some(text), ..
+                    }) => preview_text(text.replace('\r', "").into(), PREVIEW_LENGTH).into(),
+                    _ => Value::Null,
+                },
+                EmailProperty::TextBody => text_body
+                    .map(|idx| vec![parts[idx].to_body_part(idx, self.body_properties)])
+                    .unwrap_or_default()
+                    .into(),
+                EmailProperty::HtmlBody => html_body
+                    .map(|idx| vec![parts[idx].to_body_part(idx, self.body_properties)])
+                    .unwrap_or_default()
+                    .into(),
+                EmailProperty::Attachments => parts
+                    .iter()
+                    .enumerate()
+                    .skip(num_bodies)
+                    .map(|(idx, part)| part.to_body_part(idx, self.body_properties))
+                    .collect::<Vec<_>>()
+                    .into(),
+                EmailProperty::BodyValues => {
+                    let mut body_values = Map::with_capacity(num_bodies);
+                    for (idx, part) in parts[..num_bodies].iter().enumerate() {
+                        let is_html = part.content_type == "text/html";
+                        if (Some(idx) == html_body
+                            && (self.fetch_all_body_values || self.fetch_html_body_values))
+                            || (Some(idx) == text_body
+                                && (self.fetch_all_body_values || self.fetch_text_body_values))
+                        {
+                            let text = part.text.unwrap_or_default();
+                            let (is_truncated, value) = if is_html {
+                                truncate_html(text, self.max_body_value_bytes)
+                            } else {
+                                truncate_plain(text, self.max_body_value_bytes)
+                            };
+                            body_values.insert_unchecked(
+                                Key::Owned(idx.to_string()),
+                                Map::with_capacity(3)
+                                    .with_key_value(EmailProperty::IsEncodingProblem, false)
+                                    .with_key_value(EmailProperty::IsTruncated, is_truncated)
+                                    .with_key_value(EmailProperty::Value, value),
+                            );
                         }
-                        email.insert_unchecked(EmailProperty::BodyValues, body_values);
-                    }
-                    EmailProperty::Id
-                    | EmailProperty::ThreadId
-                    | EmailProperty::Keywords
-                    | EmailProperty::MailboxIds
-                    | EmailProperty::ReceivedAt => {
-                        email.insert_unchecked(property.clone(), Value::Null);
                     }
+                    body_values.into()
+                }
+                _ => Value::Null,
+            };
+            email.insert_unchecked(property.clone(), value);
+        }
 
-                    _ => {
-                        return Err(trc::JmapEvent::InvalidArguments
-                            .into_err()
-                            .details(format!("Invalid property {property:?}")));
-                    }
+        email.into()
+    }
+}
+
+struct TnefPart<'x> {
+    content_type: &'x str,
+    name: Option<&'x str>,
+    contents: &'x [u8],
+    text: Option<&'x str>,
+}
+
+impl TnefPart<'_> {
+    fn to_body_part(
+        &self,
+        part_id: usize,
+        properties: &[EmailProperty],
+    ) -> Value<'static, EmailProperty, EmailValue> {
+        let mut values = Map::with_capacity(properties.len());
+        for property in properties {
+            let value = match property {
+                EmailProperty::PartId => part_id.to_string().into(),
+                EmailProperty::Size => Value::Number(self.contents.len().into()),
+                EmailProperty::Name => self.name.map(|name| name.to_string()).into(),
+                EmailProperty::Type => self.content_type.to_string().into(),
+                EmailProperty::Charset if self.text.is_some() => "utf-8".to_string().into(),
+                EmailProperty::Disposition if self.text.is_none() => {
+                    "attachment".to_string().into()
                 }
-            }
-            response.parsed.append(blob_id, email.into());
+                _ => Value::Null,
+            };
+            values.insert_unchecked(property.clone(), value);
         }
 
-        Ok(response)
+        values.into()
     }
 }
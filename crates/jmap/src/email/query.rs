@@ -10,19 +10,25 @@ use email::cache::{MessageCacheFetch, email::MessageCacheAccess};
 use jmap_proto::{
     method::query::{Comparator, Filter, QueryRequest, QueryResponse},
     object::email::{Email, EmailComparator, EmailFilter},
+    types::state::State,
 };
 use mail_parser::HeaderName;
 use nlp::language::Language;
-use std::future::Future;
+use std::{
+    cmp::Ordering,
+    future::Future,
+    hash::{DefaultHasher, Hash, Hasher},
+};
 use store::{
-    SerializeInfallible,
-    ahash::AHashMap,
+    IndexKeyPrefix, IterateParams, SerializeInfallible, U32_LEN,
+    ahash::{AHashMap, AHashSet},
     fts::{Field, FilterGroup, FtsFilter, IntoFilterGroup},
     query::{self},
     roaring::RoaringBitmap,
+    write::key::DeserializeBigEndian,
 };
 use trc::AddContext;
-use types::{acl::Acl, collection::Collection, field::EmailField, keyword::Keyword};
+use types::{acl::Acl, collection::Collection, field::EmailField, id::Id, keyword::Keyword};
 
 pub trait EmailQuery: Sync + Send {
     fn email_query(
@@ -30,6 +36,19 @@ pub trait EmailQuery: Sync + Send {
         request: QueryRequest<Email>,
         access_token: &AccessToken,
     ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
+
+    fn email_query_all_accounts(
+        &self,
+        request: QueryRequest<Email>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
+}
+
+struct AggregatedItem {
+    account_id: u32,
+    document_id: u32,
+    thread_id: u32,
+    sort_keys: Vec<Option<Vec<u8>>>,
 }
 
 impl EmailQuery for Server {
@@ -38,287 +57,22 @@ impl EmailQuery for Server {
         mut request: QueryRequest<Email>,
         access_token: &AccessToken,
     ) -> trc::Result<QueryResponse> {
+        if request.arguments.all_accounts.unwrap_or(false) {
+            return self.email_query_all_accounts(request, access_token).await;
+        }
+
         let account_id = request.account_id.document_id();
-        let mut filters = Vec::with_capacity(request.filter.len());
         let cached_messages = self
             .get_cached_messages(account_id)
             .await
             .caused_by(trc::location!())?;
-
-        for cond_group in std::mem::take(&mut request.filter).into_filter_group() {
-            match cond_group {
-                FilterGroup::Fts(conds) => {
-                    let mut fts_filters = Vec::with_capacity(filters.len());
-                    for cond in conds {
-                        match cond {
-                            Filter::Property(cond) => match cond {
-                                EmailFilter::Text(text) => {
-                                    fts_filters.push(FtsFilter::Or);
-                                    fts_filters.push(FtsFilter::has_text(
-                                        Field::Header(HeaderName::From),
-                                        &text,
-                                        Language::None,
-                                    ));
-                                    fts_filters.push(FtsFilter::has_text(
-                                        Field::Header(HeaderName::To),
-                                        &text,
-                                        Language::None,
-                                    ));
-                                    fts_filters.push(FtsFilter::has_text(
-                                        Field::Header(HeaderName::Cc),
-                                        &text,
-                                        Language::None,
-                                    ));
-                                    fts_filters.push(FtsFilter::has_text(
-                                        Field::Header(HeaderName::Bcc),
-                                        &text,
-                                        Language::None,
-                                    ));
-                                    fts_filters.push(FtsFilter::has_text_detect(
-                                        Field::Header(HeaderName::Subject),
-                                        &text,
-                                        self.core.jmap.default_language,
-                                    ));
-                                    fts_filters.push(FtsFilter::has_text_detect(
-                                        Field::Body,
-                                        &text,
-                                        self.core.jmap.default_language,
-                                    ));
-                                    fts_filters.push(FtsFilter::has_text_detect(
-                                        Field::Attachment,
-                                        text,
-                                        self.core.jmap.default_language,
-                                    ));
-                                    fts_filters.push(FtsFilter::End);
-                                }
-                                EmailFilter::From(text) => fts_filters.push(FtsFilter::has_text(
-                                    Field::Header(HeaderName::From),
-                                    text,
-                                    Language::None,
-                                )),
-                                EmailFilter::To(text) => fts_filters.push(FtsFilter::has_text(
-                                    Field::Header(HeaderName::To),
-                                    text,
-                                    Language::None,
-                                )),
-                                EmailFilter::Cc(text) => fts_filters.push(FtsFilter::has_text(
-                                    Field::Header(HeaderName::Cc),
-                                    text,
-                                    Language::None,
-                                )),
-                                EmailFilter::Bcc(text) => fts_filters.push(FtsFilter::has_text(
-                                    Field::Header(HeaderName::Bcc),
-                                    text,
-                                    Language::None,
-                                )),
-                                EmailFilter::Subject(text) => {
-                                    fts_filters.push(FtsFilter::has_text_detect(
-                                        Field::Header(HeaderName::Subject),
-                                        text,
-                                        self.core.jmap.default_language,
-                                    ))
-                                }
-                                EmailFilter::Body(text) => {
-                                    fts_filters.push(FtsFilter::has_text_detect(
-                                        Field::Body,
-                                        text,
-                                        self.core.jmap.default_language,
-                                    ))
-                                }
-                                EmailFilter::Header(header) => {
-                                    let mut header = header.into_iter();
-                                    let header_name = header.next().ok_or_else(|| {
-                                        trc::JmapEvent::InvalidArguments
-                                            .into_err()
-                                            .details("Header name is missing.".to_string())
-                                    })?;
-
-                                    match HeaderName::parse(header_name) {
-                                        Some(HeaderName::Other(header_name)) => {
-                                            return Err(trc::JmapEvent::InvalidArguments
-                                            .into_err()
-                                            .details(format!(
-                                                "Querying header '{header_name}' is not supported.",
-                                            )));
-                                        }
-                                        Some(header_name) => {
-                                            if let Some(header_value) = header.next() {
-                                                if matches!(
-                                                    header_name,
-                                                    HeaderName::MessageId
-                                                        | HeaderName::InReplyTo
-                                                        | HeaderName::References
-                                                        | HeaderName::ResentMessageId
-                                                ) {
-                                                    fts_filters.push(FtsFilter::has_keyword(
-                                                        Field::Header(header_name),
-                                                        header_value,
-                                                    ));
-                                                } else {
-                                                    fts_filters.push(FtsFilter::has_text(
-                                                        Field::Header(header_name),
-                                                        header_value,
-                                                        Language::None,
-                                                    ));
-                                                }
-                                            } else {
-                                                fts_filters.push(FtsFilter::has_keyword(
-                                                    Field::Keyword,
-                                                    header_name.as_str().to_lowercase(),
-                                                ));
-                                            }
-                                        }
-                                        None => (),
-                                    }
-                                }
-                                other => {
-                                    return Err(trc::JmapEvent::UnsupportedFilter
-                                        .into_err()
-                                        .details(other.to_string()));
-                                }
-                            },
-                            Filter::And | Filter::Or | Filter::Not | Filter::Close => {
-                                fts_filters.push(cond.into());
-                            }
-                        }
-                    }
-                    filters.push(query::Filter::is_in_set(
-                        self.fts_filter(account_id, Collection::Email, fts_filters)
-                            .await?,
-                    ));
-                }
-                FilterGroup::Store(cond) => {
-                    match cond {
-                        Filter::Property(cond) => {
-                            match cond {
-                                EmailFilter::InMailbox(mailbox) => filters.push(
-                                    query::Filter::is_in_set(RoaringBitmap::from_iter(
-                                        cached_messages
-                                            .in_mailbox(mailbox.document_id())
-                                            .map(|item| item.document_id),
-                                    )),
-                                ),
-                                EmailFilter::InMailboxOtherThan(mailboxes) => {
-                                    filters.push(query::Filter::Not);
-                                    filters.push(query::Filter::Or);
-                                    for mailbox in mailboxes {
-                                        filters.push(query::Filter::is_in_set(
-                                            RoaringBitmap::from_iter(
-                                                cached_messages
-                                                    .in_mailbox(mailbox.document_id())
-                                                    .map(|item| item.document_id),
-                                            ),
-                                        ));
-                                    }
-                                    filters.push(query::Filter::End);
-                                    filters.push(query::Filter::End);
-                                }
-                                EmailFilter::Before(date) => filters.push(query::Filter::lt(
-                                    EmailField::ReceivedAt,
-                                    date.timestamp().serialize(),
-                                )),
-                                EmailFilter::After(date) => filters.push(query::Filter::gt(
-                                    EmailField::ReceivedAt,
-                                    date.timestamp().serialize(),
-                                )),
-                                EmailFilter::MinSize(size) => filters
-                                    .push(query::Filter::ge(EmailField::Size, size.serialize())),
-                                EmailFilter::MaxSize(size) => filters
-                                    .push(query::Filter::lt(EmailField::Size, size.serialize())),
-                                EmailFilter::AllInThreadHaveKeyword(keyword) => {
-                                    filters.push(query::Filter::is_in_set(thread_keywords(
-                                        &cached_messages,
-                                        keyword,
-                                        true,
-                                    )))
-                                }
-                                EmailFilter::SomeInThreadHaveKeyword(keyword) => {
-                                    filters.push(query::Filter::is_in_set(thread_keywords(
-                                        &cached_messages,
-                                        keyword,
-                                        false,
-                                    )))
-                                }
-                                EmailFilter::NoneInThreadHaveKeyword(keyword) => {
-                                    filters.push(query::Filter::Not);
-                                    filters.push(query::Filter::is_in_set(thread_keywords(
-                                        &cached_messages,
-                                        keyword,
-                                        false,
-                                    )));
-                                    filters.push(query::Filter::End);
-                                }
-                                EmailFilter::HasKeyword(keyword) => {
-                                    filters.push(query::Filter::is_in_set(
-                                        RoaringBitmap::from_iter(
-                                            cached_messages
-                                                .with_keyword(&keyword)
-                                                .map(|item| item.document_id),
-                                        ),
-                                    ));
-                                }
-                                EmailFilter::NotKeyword(keyword) => {
-                                    filters.push(query::Filter::Not);
-                                    filters.push(query::Filter::is_in_set(
-                                        RoaringBitmap::from_iter(
-                                            cached_messages
-                                                .with_keyword(&keyword)
-                                                .map(|item| item.document_id),
-                                        ),
-                                    ));
-                                    filters.push(query::Filter::End);
-                                }
-                                EmailFilter::HasAttachment(has_attach) => {
-                                    if !has_attach {
-                                        filters.push(query::Filter::Not);
-                                    }
-                                    filters.push(query::Filter::is_in_bitmap(
-                                        EmailField::HasAttachment,
-                                        (),
-                                    ));
-                                    if !has_attach {
-                                        filters.push(query::Filter::End);
-                                    }
-                                }
-
-                                // Non-standard
-                                EmailFilter::Id(ids) => {
-                                    let mut set = RoaringBitmap::new();
-                                    for id in ids {
-                                        set.insert(id.document_id());
-                                    }
-                                    filters.push(query::Filter::is_in_set(set));
-                                }
-                                EmailFilter::SentBefore(date) => filters.push(query::Filter::lt(
-                                    EmailField::SentAt,
-                                    date.timestamp().serialize(),
-                                )),
-                                EmailFilter::SentAfter(date) => filters.push(query::Filter::gt(
-                                    EmailField::SentAt,
-                                    date.timestamp().serialize(),
-                                )),
-                                EmailFilter::InThread(id) => filters.push(
-                                    query::Filter::is_in_set(RoaringBitmap::from_iter(
-                                        cached_messages
-                                            .in_thread(id.document_id())
-                                            .map(|item| item.document_id),
-                                    )),
-                                ),
-                                other => {
-                                    return Err(trc::JmapEvent::UnsupportedFilter
-                                        .into_err()
-                                        .details(other.to_string()));
-                                }
-                            }
-                        }
-
-                        Filter::And | Filter::Or | Filter::Not | Filter::Close => {
-                            filters.push(cond.into());
-                        }
-                    }
-                }
-            }
-        }
+        let filters = email_query_filters(
+            self,
+            account_id,
+            std::mem::take(&mut request.filter),
+            &cached_messages,
+        )
+        .await?;
 
         let mut result_set = self.filter(account_id, Collection::Email, filters).await?;
         if access_token.is_shared(account_id) {
@@ -405,6 +159,498 @@ impl EmailQuery for Server {
             Ok(response)
         }
     }
+
+    // Non-standard, queries the user's account and all shared accounts
+    // containing messages readable by the user. Ids are scoped to the account
+    // listed at the same position in "accountIds".
+    async fn email_query_all_accounts(
+        &self,
+        mut request: QueryRequest<Email>,
+        access_token: &AccessToken,
+    ) -> trc::Result<QueryResponse> {
+        // Mailbox, thread and message ids are specific to each account
+        for cond in &request.filter {
+            if let Filter::Property(
+                cond @ (EmailFilter::InMailbox(_)
+                | EmailFilter::InMailboxOtherThan(_)
+                | EmailFilter::InThread(_)
+                | EmailFilter::Id(_)),
+            ) = cond
+            {
+                return Err(trc::JmapEvent::UnsupportedFilter
+                    .into_err()
+                    .details(format!(
+                        "Filter {cond} is not supported when querying all accounts."
+                    )));
+            }
+        }
+
+        // Only indexed properties can be compared across accounts
+        let mut sort_fields = Vec::new();
+        for comparator in request
+            .sort
+            .take()
+            .and_then(|s| if !s.is_empty() { s.into() } else { None })
+            .unwrap_or_else(|| vec![Comparator::descending(EmailComparator::ReceivedAt)])
+        {
+            let field = match comparator.property {
+                EmailComparator::ReceivedAt => EmailField::ReceivedAt,
+                EmailComparator::Size => EmailField::Size,
+                EmailComparator::From => EmailField::From,
+                EmailComparator::To => EmailField::To,
+                EmailComparator::Subject => EmailField::Subject,
+                EmailComparator::SentAt => EmailField::SentAt,
+                EmailComparator::Cc => EmailField::Cc,
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedSort.into_err().details(format!(
+                        "Sorting by {other} is not supported when querying all accounts."
+                    )));
+                }
+            };
+            sort_fields.push((u8::from(field), comparator.is_ascending));
+        }
+
+        let mut account_ids = vec![access_token.primary_id()];
+        for account_id in access_token.shared_accounts(Collection::Email) {
+            if !account_ids.contains(account_id) {
+                account_ids.push(*account_id);
+            }
+        }
+        account_ids.sort_unstable();
+
+        let mut items = Vec::new();
+        let mut state = DefaultHasher::new();
+        for account_id in account_ids {
+            let cached_messages = self
+                .get_cached_messages(account_id)
+                .await
+                .caused_by(trc::location!())?;
+            (account_id, cached_messages.emails.change_id).hash(&mut state);
+
+            let filters =
+                email_query_filters(self, account_id, request.filter.clone(), &cached_messages)
+                    .await?;
+            let mut result_set = self.filter(account_id, Collection::Email, filters).await?;
+            if access_token.is_shared(account_id) {
+                result_set
+                    .apply_mask(cached_messages.shared_messages(access_token, Acl::ReadItems));
+            }
+            if result_set.results.is_empty() {
+                continue;
+            }
+
+            // Obtain the indexed values used for sorting
+            let mut sort_values = Vec::with_capacity(sort_fields.len());
+            for (field, _) in &sort_fields {
+                sort_values
+                    .push(index_values(self, account_id, *field, &result_set.results).await?);
+            }
+
+            for document_id in &result_set.results {
+                if let Some(item) = cached_messages.email_by_id(&document_id) {
+                    items.push(AggregatedItem {
+                        account_id,
+                        document_id,
+                        thread_id: item.thread_id,
+                        sort_keys: sort_values
+                            .iter_mut()
+                            .map(|values| values.remove(&document_id))
+                            .collect(),
+                    });
+                }
+            }
+        }
+
+        // Sort results, documents without an indexed value are always last
+        items.sort_unstable_by(|a, b| {
+            for (pos, (_, ascending)) in sort_fields.iter().enumerate() {
+                let ordering = match (&a.sort_keys[pos], &b.sort_keys[pos]) {
+                    (Some(a), Some(b)) if *ascending => a.cmp(b),
+                    (Some(a), Some(b)) => b.cmp(a),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (a.account_id, a.document_id).cmp(&(b.account_id, b.document_id))
+        });
+        if request.arguments.collapse_threads.unwrap_or(false) {
+            let mut seen_threads = AHashSet::new();
+            items.retain(|item| seen_threads.insert((item.account_id, item.thread_id)));
+        }
+
+        // Paginate
+        let total = items.len();
+        let limit = match request.limit {
+            Some(limit) => std::cmp::min(limit, self.core.jmap.query_max_results),
+            None => self.core.jmap.query_max_results,
+        };
+        let position = if let Some(anchor) = request.anchor {
+            let anchor_pos = items
+                .iter()
+                .position(|item| item.document_id == anchor.document_id())
+                .ok_or_else(|| trc::JmapEvent::AnchorNotFound.into_err())?;
+            (anchor_pos as i64 + request.anchor_offset.unwrap_or(0) as i64).max(0) as usize
+        } else {
+            match request.position.unwrap_or(0) {
+                position if position >= 0 => position as usize,
+                position => total.saturating_sub(position.unsigned_abs() as usize),
+            }
+        };
+        let page = items
+            .into_iter()
+            .skip(position)
+            .take(limit)
+            .collect::<Vec<_>>();
+
+        Ok(QueryResponse {
+            account_id: request.account_id,
+            query_state: State::Exact(state.finish()),
+            can_calculate_changes: false,
+            position: std::cmp::min(position, total) as i32,
+            ids: page
+                .iter()
+                .map(|item| Id::from_parts(item.thread_id, item.document_id))
+                .collect(),
+            account_ids: Some(page.iter().map(|item| Id::from(item.account_id)).collect()),
+            total: if request.calculate_total.unwrap_or(false) {
+                Some(total)
+            } else {
+                None
+            },
+            limit: if total > limit { Some(limit) } else { None },
+        })
+    }
+}
+
+async fn email_query_filters(
+    server: &Server,
+    account_id: u32,
+    filter: Vec<Filter<EmailFilter>>,
+    cached_messages: &MessageStoreCache,
+) -> trc::Result<Vec<query::Filter>> {
+    let mut filters = Vec::with_capacity(filter.len());
+    for cond_group in filter.into_filter_group() {
+        match cond_group {
+            FilterGroup::Fts(conds) => {
+                let mut fts_filters = Vec::with_capacity(filters.len());
+                for cond in conds {
+                    match cond {
+                        Filter::Property(cond) => match cond {
+                            EmailFilter::Text(text) => {
+                                fts_filters.push(FtsFilter::Or);
+                                fts_filters.push(FtsFilter::has_text(
+                                    Field::Header(HeaderName::From),
+                                    &text,
+                                    Language::None,
+                                ));
+                                fts_filters.push(FtsFilter::has_text(
+                                    Field::Header(HeaderName::To),
+                                    &text,
+                                    Language::None,
+                                ));
+                                fts_filters.push(FtsFilter::has_text(
+                                    Field::Header(HeaderName::Cc),
+                                    &text,
+                                    Language::None,
+                                ));
+                                fts_filters.push(FtsFilter::has_text(
+                                    Field::Header(HeaderName::Bcc),
+                                    &text,
+                                    Language::None,
+                                ));
+                                fts_filters.push(FtsFilter::has_text_detect(
+                                    Field::Header(HeaderName::Subject),
+                                    &text,
+                                    server.core.jmap.default_language,
+                                ));
+                                fts_filters.push(FtsFilter::has_text_detect(
+                                    Field::Body,
+                                    &text,
+                                    server.core.jmap.default_language,
+                                ));
+                                fts_filters.push(FtsFilter::has_text_detect(
+                                    Field::Attachment,
+                                    text,
+                                    server.core.jmap.default_language,
+                                ));
+                                fts_filters.push(FtsFilter::End);
+                            }
+                            EmailFilter::From(text) => fts_filters.push(FtsFilter::has_text(
+                                Field::Header(HeaderName::From),
+                                text,
+                                Language::None,
+                            )),
+                            EmailFilter::To(text) => fts_filters.push(FtsFilter::has_text(
+                                Field::Header(HeaderName::To),
+                                text,
+                                Language::None,
+                            )),
+                            EmailFilter::Cc(text) => fts_filters.push(FtsFilter::has_text(
+                                Field::Header(HeaderName::Cc),
+                                text,
+                                Language::None,
+                            )),
+                            EmailFilter::Bcc(text) => fts_filters.push(FtsFilter::has_text(
+                                Field::Header(HeaderName::Bcc),
+                                text,
+                                Language::None,
+                            )),
+                            EmailFilter::Subject(text) => {
+                                fts_filters.push(FtsFilter::has_text_detect(
+                                    Field::Header(HeaderName::Subject),
+                                    text,
+                                    server.core.jmap.default_language,
+                                ))
+                            }
+                            EmailFilter::Body(text) => {
+                                fts_filters.push(FtsFilter::has_text_detect(
+                                    Field::Body,
+                                    text,
+                                    server.core.jmap.default_language,
+                                ))
+                            }
+                            EmailFilter::Header(header) => {
+                                let mut header = header.into_iter();
+                                let header_name = header.next().ok_or_else(|| {
+                                    trc::JmapEvent::InvalidArguments
+                                        .into_err()
+                                        .details("Header name is missing.".to_string())
+                                })?;
+
+                                match HeaderName::parse(header_name) {
+                                    Some(HeaderName::Other(header_name)) => {
+                                        return Err(trc::JmapEvent::InvalidArguments
+                                            .into_err()
+                                            .details(format!(
+                                                "Querying header '{header_name}' is not supported.",
+                                            )));
+                                    }
+                                    Some(header_name) => {
+                                        if let Some(header_value) = header.next() {
+                                            if matches!(
+                                                header_name,
+                                                HeaderName::MessageId
+                                                    | HeaderName::InReplyTo
+                                                    | HeaderName::References
+                                                    | HeaderName::ResentMessageId
+                                            ) {
+                                                fts_filters.push(FtsFilter::has_keyword(
+                                                    Field::Header(header_name),
+                                                    header_value,
+                                                ));
+                                            } else {
+                                                fts_filters.push(FtsFilter::has_text(
+                                                    Field::Header(header_name),
+                                                    header_value,
+                                                    Language::None,
+                                                ));
+                                            }
+                                        } else {
+                                            fts_filters.push(FtsFilter::has_keyword(
+                                                Field::Keyword,
+                                                header_name.as_str().to_lowercase(),
+                                            ));
+                                        }
+                                    }
+                                    None => (),
+                                }
+                            }
+                            other => {
+                                return Err(trc::JmapEvent::UnsupportedFilter
+                                    .into_err()
+                                    .details(other.to_string()));
+                            }
+                        },
+                        Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                            fts_filters.push(cond.into());
+                        }
+                    }
+                }
+                filters.push(query::Filter::is_in_set(
+                    server
+                        .fts_filter(account_id, Collection::Email, fts_filters)
+                        .await?,
+                ));
+            }
+            FilterGroup::Store(cond) => {
+                match cond {
+                    Filter::Property(cond) => {
+                        match cond {
+                            EmailFilter::InMailbox(mailbox) => {
+                                filters.push(query::Filter::is_in_set(RoaringBitmap::from_iter(
+                                    cached_messages
+                                        .in_mailbox(mailbox.document_id())
+                                        .map(|item| item.document_id),
+                                )))
+                            }
+                            EmailFilter::InMailboxOtherThan(mailboxes) => {
+                                filters.push(query::Filter::Not);
+                                filters.push(query::Filter::Or);
+                                for mailbox in mailboxes {
+                                    filters.push(query::Filter::is_in_set(
+                                        RoaringBitmap::from_iter(
+                                            cached_messages
+                                                .in_mailbox(mailbox.document_id())
+                                                .map(|item| item.document_id),
+                                        ),
+                                    ));
+                                }
+                                filters.push(query::Filter::End);
+                                filters.push(query::Filter::End);
+                            }
+                            EmailFilter::Before(date) => filters.push(query::Filter::lt(
+                                EmailField::ReceivedAt,
+                                date.timestamp().serialize(),
+                            )),
+                            EmailFilter::After(date) => filters.push(query::Filter::gt(
+                                EmailField::ReceivedAt,
+                                date.timestamp().serialize(),
+                            )),
+                            EmailFilter::MinSize(size) => {
+                                filters.push(query::Filter::ge(EmailField::Size, size.serialize()))
+                            }
+                            EmailFilter::MaxSize(size) => {
+                                filters.push(query::Filter::lt(EmailField::Size, size.serialize()))
+                            }
+                            EmailFilter::AllInThreadHaveKeyword(keyword) => {
+                                filters.push(query::Filter::is_in_set(thread_keywords(
+                                    cached_messages,
+                                    keyword,
+                                    true,
+                                )))
+                            }
+                            EmailFilter::SomeInThreadHaveKeyword(keyword) => {
+                                filters.push(query::Filter::is_in_set(thread_keywords(
+                                    cached_messages,
+                                    keyword,
+                                    false,
+                                )))
+                            }
+                            EmailFilter::NoneInThreadHaveKeyword(keyword) => {
+                                filters.push(query::Filter::Not);
+                                filters.push(query::Filter::is_in_set(thread_keywords(
+                                    cached_messages,
+                                    keyword,
+                                    false,
+                                )));
+                                filters.push(query::Filter::End);
+                            }
+                            EmailFilter::HasKeyword(keyword) => {
+                                filters.push(query::Filter::is_in_set(RoaringBitmap::from_iter(
+                                    cached_messages
+                                        .with_keyword(&keyword)
+                                        .map(|item| item.document_id),
+                                )));
+                            }
+                            EmailFilter::NotKeyword(keyword) => {
+                                filters.push(query::Filter::Not);
+                                filters.push(query::Filter::is_in_set(RoaringBitmap::from_iter(
+                                    cached_messages
+                                        .with_keyword(&keyword)
+                                        .map(|item| item.document_id),
+                                )));
+                                filters.push(query::Filter::End);
+                            }
+                            EmailFilter::HasAttachment(has_attach) => {
+                                if !has_attach {
+                                    filters.push(query::Filter::Not);
+                                }
+                                filters.push(query::Filter::is_in_bitmap(
+                                    EmailField::HasAttachment,
+                                    (),
+                                ));
+                                if !has_attach {
+                                    filters.push(query::Filter::End);
+                                }
+                            }
+
+                            // Non-standard
+                            EmailFilter::Id(ids) => {
+                                let mut set = RoaringBitmap::new();
+                                for id in ids {
+                                    set.insert(id.document_id());
+                                }
+                                filters.push(query::Filter::is_in_set(set));
+                            }
+                            EmailFilter::SentBefore(date) => filters.push(query::Filter::lt(
+                                EmailField::SentAt,
+                                date.timestamp().serialize(),
+                            )),
+                            EmailFilter::SentAfter(date) => filters.push(query::Filter::gt(
+                                EmailField::SentAt,
+                                date.timestamp().serialize(),
+                            )),
+                            EmailFilter::InThread(id) => {
+                                filters.push(query::Filter::is_in_set(RoaringBitmap::from_iter(
+                                    cached_messages
+                                        .in_thread(id.document_id())
+                                        .map(|item| item.document_id),
+                                )))
+                            }
+                            other => {
+                                return Err(trc::JmapEvent::UnsupportedFilter
+                                    .into_err()
+                                    .details(other.to_string()));
+                            }
+                        }
+                    }
+
+                    Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                        filters.push(cond.into());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(filters)
+}
+
+// Returns the indexed value of a field for each of the requested documents
+async fn index_values(
+    server: &Server,
+    account_id: u32,
+    field: u8,
+    documents: &RoaringBitmap,
+) -> trc::Result<AHashMap<u32, Vec<u8>>> {
+    let collection = u8::from(Collection::Email);
+    let mut values = AHashMap::with_capacity(documents.len() as usize);
+
+    server
+        .store()
+        .iterate(
+            IterateParams::new(
+                IndexKeyPrefix {
+                    account_id,
+                    collection,
+                    field,
+                },
+                IndexKeyPrefix {
+                    account_id,
+                    collection,
+                    field: field + 1,
+                },
+            )
+            .no_values(),
+            |key, _| {
+                let id_pos = key.len() - U32_LEN;
+                let document_id = key.deserialize_be_u32(id_pos)?;
+                if documents.contains(document_id) {
+                    let value = key
+                        .get(IndexKeyPrefix::len()..id_pos)
+                        .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?;
+                    values.insert(document_id, value.to_vec());
+                }
+                Ok(values.len() < documents.len() as usize)
+            },
+        )
+        .await
+        .caused_by(trc::location!())
+        .map(|_| values)
 }
 
 fn thread_keywords(cache: &MessageStoreCache, keyword: Keyword, match_all: bool) -> RoaringBitmap {
@@ -18,6 +18,7 @@ use email::{
     message::{
         delete::EmailDeletion,
         ingest::{EmailIngest, IngestEmail, IngestSource},
+        keywords::KeywordPolicy,
         metadata::MessageData,
     },
 };
@@ -74,6 +75,7 @@ impl EmailSet for Server {
             .prepare_set_response(&request, cache.assert_state(false, &request.if_in_state)?)
             .await?;
         let can_train_spam = self.email_bayes_can_train(access_token);
+        let mut keyword_policy = KeywordPolicy::new(self, &cache, access_token, account_id);
 
         // Obtain mailboxIds
         let (can_add_mailbox_ids, can_delete_mailbox_ids, can_modify_message_ids) =
@@ -708,6 +710,17 @@ impl EmailSet for Server {
                 }
             }
 
+            // Enforce keyword limits
+            if let Err(limit) = keyword_policy.apply_new(&keywords) {
+                response.not_created.append(
+                    id,
+                    SetError::new(SetErrorType::TooManyKeywords)
+                        .with_property(EmailProperty::Keywords)
+                        .with_description(limit.description()),
+                );
+                continue 'create;
+            }
+
             // Make sure the message is not empty
             if builder.headers.is_empty()
                 && builder.body.is_none()
@@ -857,6 +870,18 @@ impl EmailSet for Server {
                 }
             }
 
+            // Enforce keyword limits
+            if let Err(limit) = keyword_policy.apply_update(document_id, data.inner, &mut new_data)
+            {
+                response.not_updated.append(
+                    id,
+                    SetError::new(SetErrorType::TooManyKeywords)
+                        .with_property(EmailProperty::Keywords)
+                        .with_description(limit.description()),
+                );
+                continue 'update;
+            }
+
             let has_keyword_changes = new_data.has_keyword_changes(data.inner);
             let has_mailbox_changes = new_data.has_mailbox_changes(data.inner);
             if !has_keyword_changes && !has_mailbox_changes {
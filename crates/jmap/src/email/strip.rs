@@ -0,0 +1,152 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{changes::state::MessageCacheState, email::ingested_into_object};
+use common::{Server, auth::AccessToken};
+use email::{
+    cache::{MessageCacheFetch, email::MessageCacheAccess},
+    message::{
+        ingest::IngestSource,
+        strip::{EmailStripAttachments, StripAttachmentsError},
+    },
+};
+use http_proto::HttpSessionData;
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::strip::{StripAttachmentsRequest, StripAttachmentsResponse},
+    request::IntoValid,
+};
+use std::future::Future;
+use types::acl::Acl;
+use utils::map::vec_map::VecMap;
+
+pub trait EmailStrip: Sync + Send {
+    fn email_strip_attachments(
+        &self,
+        request: StripAttachmentsRequest,
+        access_token: &AccessToken,
+        session: &HttpSessionData,
+    ) -> impl Future<Output = trc::Result<StripAttachmentsResponse>> + Send;
+}
+
+impl EmailStrip for Server {
+    async fn email_strip_attachments(
+        &self,
+        request: StripAttachmentsRequest,
+        access_token: &AccessToken,
+        session: &HttpSessionData,
+    ) -> trc::Result<StripAttachmentsResponse> {
+        // Validate state
+        let account_id = request.account_id.document_id();
+        let cache = self.get_cached_messages(account_id).await?;
+        let old_state = cache.assert_state(false, &request.if_in_state)?;
+        let email_ids = request.email_ids.unwrap();
+        if email_ids.len() > self.core.jmap.set_max_objects {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+
+        // Stripping replaces the message, so it must be possible to add and remove it
+        let can_strip_message_ids = if access_token.is_shared(account_id) {
+            cache
+                .shared_messages(access_token, vec![Acl::AddItems, Acl::RemoveItems])
+                .into()
+        } else {
+            None
+        };
+
+        // Obtain the account owner's access token
+        let owner_access_token = if account_id != access_token.primary_id() {
+            #[cfg(feature = "test_mode")]
+            {
+                std::sync::Arc::new(AccessToken::from_id(account_id)).into()
+            }
+
+            #[cfg(not(feature = "test_mode"))]
+            {
+                use trc::AddContext;
+                self.get_access_token(account_id)
+                    .await
+                    .caused_by(trc::location!())?
+                    .into()
+            }
+        } else {
+            None
+        };
+
+        let mut response = StripAttachmentsResponse {
+            account_id: request.account_id,
+            new_state: old_state.clone(),
+            old_state,
+            stripped: VecMap::with_capacity(email_ids.len()),
+            not_stripped: VecMap::new(),
+        };
+        let min_size = request
+            .min_size
+            .unwrap_or(self.core.jmap.mail_archive_min_size)
+            .max(1);
+
+        for email_id in email_ids.into_valid() {
+            let document_id = email_id.document_id();
+            if !cache.has_email_id(&document_id) {
+                response
+                    .not_stripped
+                    .append(email_id, SetError::not_found());
+                continue;
+            } else if matches!(&can_strip_message_ids, Some(ids) if !ids.contains(document_id)) {
+                response.not_stripped.append(
+                    email_id,
+                    SetError::forbidden()
+                        .with_description("You are not allowed to modify this message."),
+                );
+                continue;
+            }
+
+            match self
+                .strip_attachments(
+                    owner_access_token.as_deref().unwrap_or(access_token),
+                    document_id,
+                    min_size,
+                    IngestSource::Jmap,
+                    session.session_id,
+                )
+                .await?
+            {
+                Ok(stripped) => {
+                    response
+                        .stripped
+                        .append(email_id, ingested_into_object(stripped.email).into());
+                }
+                Err(StripAttachmentsError::NotFound) => {
+                    response
+                        .not_stripped
+                        .append(email_id, SetError::not_found());
+                }
+                Err(StripAttachmentsError::NothingToStrip) => {
+                    response.not_stripped.append(
+                        email_id,
+                        SetError::invalid_properties().with_description(format!(
+                            "Message {email_id} has no attachments larger than {min_size} bytes."
+                        )),
+                    );
+                }
+                Err(StripAttachmentsError::OverQuota) => {
+                    response.not_stripped.append(
+                        email_id,
+                        SetError::new(SetErrorType::OverQuota)
+                            .with_description("You have exceeded your disk quota."),
+                    );
+                }
+            }
+        }
+
+        // Update state
+        if !response.stripped.is_empty() {
+            response.new_state = self.get_cached_messages(account_id).await?.get_state(false);
+        }
+
+        Ok(response)
+    }
+}
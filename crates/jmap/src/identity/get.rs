@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use super::signature_variables;
 use crate::changes::state::StateManager;
 use common::{Server, storage::index::ObjectIndexBuilder};
 use directory::QueryParams;
@@ -72,6 +73,24 @@ impl IdentityGet for Server {
             not_found: vec![],
         };
 
+        // Obtain principal for server-managed signatures
+        let principal = if !self.core.jmap.identity_signatures.is_empty()
+            && properties.iter().any(|property| {
+                matches!(
+                    property,
+                    IdentityProperty::TextSignature | IdentityProperty::HtmlSignature
+                )
+            }) {
+            self.core
+                .storage
+                .directory
+                .query(QueryParams::id(account_id).with_return_member_of(false))
+                .await
+                .caused_by(trc::location!())?
+        } else {
+            None
+        };
+
         for id in ids {
             // Obtain the identity object
             let document_id = id.document_id();
@@ -91,6 +110,26 @@ impl IdentityGet for Server {
             let identity = _identity
                 .unarchive::<Identity>()
                 .caused_by(trc::location!())?;
+            let mut text_signature = identity.text_signature.to_string();
+            let mut html_signature = identity.html_signature.to_string();
+            if let Some((principal, template)) = principal.as_ref().and_then(|principal| {
+                self.core
+                    .jmap
+                    .identity_signature(identity.email.as_str())
+                    .map(|template| (principal, template))
+            }) {
+                let variables = signature_variables(principal, identity.email.as_str());
+                if (template.is_read_only() || text_signature.is_empty())
+                    && let Some(signature) = template.render_text(&variables)
+                {
+                    text_signature = signature;
+                }
+                if (template.is_read_only() || html_signature.is_empty())
+                    && let Some(signature) = template.render_html(&variables)
+                {
+                    html_signature = signature;
+                }
+            }
             let mut result = Map::with_capacity(properties.len());
             for property in &properties {
                 match property {
@@ -110,13 +149,13 @@ impl IdentityGet for Server {
                     IdentityProperty::TextSignature => {
                         result.insert_unchecked(
                             IdentityProperty::TextSignature,
-                            identity.text_signature.to_string(),
+                            std::mem::take(&mut text_signature),
                         );
                     }
                     IdentityProperty::HtmlSignature => {
                         result.insert_unchecked(
                             IdentityProperty::HtmlSignature,
-                            identity.html_signature.to_string(),
+                            std::mem::take(&mut html_signature),
                         );
                     }
                     IdentityProperty::Bcc => {
@@ -6,3 +6,26 @@
 
 pub mod get;
 pub mod set;
+
+use directory::Principal;
+use utils::template::Variables;
+
+// Variables available to server-managed signature templates
+pub(crate) fn signature_variables(principal: &Principal, email: &str) -> Variables<String, String> {
+    let mut variables = Variables::new();
+    for attribute in principal.attributes() {
+        variables.insert_single(attribute.name.clone(), attribute.value.clone());
+    }
+    variables.insert_single(
+        "name".to_string(),
+        principal
+            .description
+            .as_ref()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&principal.name)
+            .clone(),
+    );
+    variables.insert_single("account".to_string(), principal.name.clone());
+    variables.insert_single("email".to_string(), email.to_string());
+    variables
+}
@@ -88,6 +88,23 @@ impl IdentitySet for Server {
                 continue 'create;
             }
 
+            // Server-managed signatures cannot be set by clients
+            if (!identity.text_signature.is_empty() || !identity.html_signature.is_empty())
+                && self
+                    .core
+                    .jmap
+                    .identity_signature(&identity.email)
+                    .is_some_and(|template| template.is_read_only())
+            {
+                response.not_created.append(
+                    id,
+                    SetError::forbidden().with_description(
+                        "Signatures for this identity are managed by the server.",
+                    ),
+                );
+                continue 'create;
+            }
+
             // Insert record
             let document_id = self
                 .store()
@@ -129,8 +146,29 @@ impl IdentitySet for Server {
             let mut new_identity = identity
                 .deserialize::<Identity>()
                 .caused_by(trc::location!())?;
+            let is_managed = self
+                .core
+                .jmap
+                .identity_signature(&new_identity.email)
+                .is_some_and(|template| template.is_read_only());
 
             for (property, mut value) in object.into_expanded_object() {
+                if is_managed
+                    && let Key::Property(
+                        property @ (IdentityProperty::TextSignature
+                        | IdentityProperty::HtmlSignature),
+                    ) = &property
+                {
+                    response.not_updated.append(
+                        id,
+                        SetError::forbidden()
+                            .with_property(property.clone())
+                            .with_description(
+                                "Signatures for this identity are managed by the server.",
+                            ),
+                    );
+                    continue 'update;
+                }
                 if let Err(err) = response.resolve_self_references(&mut value).and_then(|_| {
                     validate_identity_value(&property, value, &mut new_identity, false)
                 }) {
@@ -33,6 +33,7 @@ pub mod mailbox;
 pub mod principal;
 pub mod push;
 pub mod quota;
+pub mod settings;
 pub mod sieve;
 pub mod submission;
 pub mod thread;
@@ -114,6 +115,7 @@ impl JmapMethods for Server {
                 can_calculate_changes: true,
                 position: 0,
                 ids: vec![],
+                account_ids: None,
                 total: if request.calculate_total.unwrap_or(false) {
                     Some(total)
                 } else {
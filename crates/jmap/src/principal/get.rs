@@ -89,6 +89,10 @@ impl PrincipalGet for Server {
                         .first()
                         .map(|email| Value::Str(email.to_string().into()))
                         .unwrap_or(Value::Null),
+                    PrincipalProperty::AvatarUrl => principal
+                        .avatar()
+                        .map(|avatar| Value::Str(avatar.url_path(principal.id).into()))
+                        .unwrap_or(Value::Null),
                     _ => Value::Null,
                 };
 
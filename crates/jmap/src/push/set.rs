@@ -6,7 +6,7 @@
 
 use super::get::PushSubscriptionFetch;
 use base64::{Engine, engine::general_purpose};
-use common::{Server, auth::AccessToken};
+use common::{Server, auth::AccessToken, config::jmap::settings::JmapConfig};
 use email::push::{Keys, PushSubscription};
 use jmap_proto::{
     error::set::SetError,
@@ -66,10 +66,9 @@ impl PushSubscriptionSet for Server {
             }
 
             for (property, mut value) in object.into_expanded_object() {
-                if let Err(err) = response
-                    .resolve_self_references(&mut value)
-                    .and_then(|_| validate_push_value(&property, value, &mut push, true))
-                {
+                if let Err(err) = response.resolve_self_references(&mut value).and_then(|_| {
+                    validate_push_value(&self.core.jmap, &property, value, &mut push, true)
+                }) {
                     response.not_created.append(id, err);
                     continue 'create;
                 }
@@ -156,10 +155,9 @@ impl PushSubscriptionSet for Server {
             };
 
             for (property, mut value) in object.into_expanded_object() {
-                if let Err(err) = response
-                    .resolve_self_references(&mut value)
-                    .and_then(|_| validate_push_value(&property, value, &mut push, false))
-                {
+                if let Err(err) = response.resolve_self_references(&mut value).and_then(|_| {
+                    validate_push_value(&self.core.jmap, &property, value, &mut push, false)
+                }) {
                     response.not_updated.append(id, err);
                     continue 'update;
                 }
@@ -212,6 +210,7 @@ impl PushSubscriptionSet for Server {
 }
 
 fn validate_push_value(
+    jmap: &JmapConfig,
     property: &Key<PushSubscriptionProperty>,
     value: Value<'_, PushSubscriptionProperty, PushSubscriptionValue>,
     push: &mut PushSubscription,
@@ -230,7 +229,9 @@ fn validate_push_value(
             push.device_client_id = value.into_owned();
         }
         (PushSubscriptionProperty::Url, Value::Str(value))
-            if is_create && value.len() < 512 && value.starts_with("https://") =>
+            if is_create
+                && value.len() < 512
+                && (value.starts_with("https://") || jmap.push_bridge(&value).is_some()) =>
         {
             push.url = value.into_owned();
         }
@@ -37,6 +37,7 @@ impl QuotaQuery for Server {
             } else {
                 vec![]
             },
+            account_ids: None,
             total: Some(1),
             limit: None,
         })
@@ -0,0 +1,105 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::namespace_to_value;
+use common::Server;
+use email::settings::{AccountSettings, SettingsStore};
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse},
+    object::settings::{Settings, SettingsProperty},
+    request::reference::MaybeResultReference,
+    types::state::State,
+};
+use jmap_tools::{Map, Value};
+use std::future::Future;
+use trc::AddContext;
+use types::id::Id;
+
+pub trait SettingsGet: Sync + Send {
+    fn settings_get(
+        &self,
+        request: GetRequest<Settings>,
+    ) -> impl Future<Output = trc::Result<GetResponse<Settings>>> + Send;
+}
+
+impl SettingsGet for Server {
+    async fn settings_get(
+        &self,
+        mut request: GetRequest<Settings>,
+    ) -> trc::Result<GetResponse<Settings>> {
+        let account_id = request.account_id.document_id();
+        let properties = request.unwrap_properties(&[]);
+        let settings_ = self.get_account_settings(account_id).await?;
+        let settings = settings_
+            .as_ref()
+            .map(|settings| settings.unarchive::<AccountSettings>())
+            .transpose()
+            .caused_by(trc::location!())?;
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: State::from(settings.map_or(0, |settings| u64::from(settings.change_id))).into(),
+            list: Vec::with_capacity(1),
+            not_found: vec![],
+        };
+
+        let do_get = if let Some(MaybeResultReference::Value(ids)) = request.ids {
+            let mut do_get = false;
+            for id in ids {
+                match id.try_unwrap() {
+                    Some(id) if id.is_singleton() => {
+                        do_get = true;
+                    }
+                    Some(id) => {
+                        response.not_found.push(id);
+                    }
+                    _ => {}
+                }
+            }
+            do_get
+        } else {
+            true
+        };
+
+        if do_get {
+            // The settings object always exists, even if no namespaces were stored yet
+            let mut result = Map::with_capacity(properties.len().max(1));
+            result.insert_unchecked(SettingsProperty::Id, Id::singleton());
+
+            if let Some(settings) = settings {
+                if properties.is_empty() {
+                    for namespace in settings.namespaces.iter() {
+                        if let Some(value) = namespace_to_value(namespace.value.as_str()) {
+                            result.insert_unchecked(
+                                SettingsProperty::Namespace(namespace.name.to_string()),
+                                value,
+                            );
+                        }
+                    }
+                } else {
+                    for property in properties {
+                        if let SettingsProperty::Namespace(namespace) = &property {
+                            let value = settings
+                                .get(namespace)
+                                .and_then(namespace_to_value)
+                                .unwrap_or(Value::Null);
+                            result.insert_unchecked(property, value);
+                        }
+                    }
+                }
+            } else {
+                for property in properties {
+                    if matches!(property, SettingsProperty::Namespace(_)) {
+                        result.insert_unchecked(property, Value::Null);
+                    }
+                }
+            }
+
+            response.list.push(result.into());
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,51 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::object::settings::{SettingsProperty, SettingsValue};
+use jmap_tools::{Key, Map, Value};
+
+pub mod get;
+pub mod set;
+
+pub(super) fn is_valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty()
+        && namespace.len() <= 64
+        && namespace
+            .bytes()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, b'.' | b'-' | b'_'))
+}
+
+pub(super) fn json_to_value(
+    value: serde_json::Value,
+) -> Value<'static, SettingsProperty, SettingsValue> {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(value) => Value::Bool(value),
+        serde_json::Value::Number(value) => Value::Number(if let Some(value) = value.as_u64() {
+            value.into()
+        } else if let Some(value) = value.as_i64() {
+            value.into()
+        } else {
+            value.as_f64().unwrap_or_default().into()
+        }),
+        serde_json::Value::String(value) => Value::Str(value.into()),
+        serde_json::Value::Array(values) => {
+            Value::Array(values.into_iter().map(json_to_value).collect())
+        }
+        serde_json::Value::Object(values) => Value::Object(Map::from(
+            values
+                .into_iter()
+                .map(|(key, value)| (Key::Owned(key), json_to_value(value)))
+                .collect::<Vec<_>>(),
+        )),
+    }
+}
+
+pub(super) fn namespace_to_value(
+    value: &str,
+) -> Option<Value<'static, SettingsProperty, SettingsValue>> {
+    serde_json::from_str(value).ok().map(json_to_value)
+}
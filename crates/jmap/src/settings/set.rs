@@ -0,0 +1,168 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::is_valid_namespace;
+use crate::JmapMethods;
+use common::Server;
+use email::settings::{AccountSettings, SettingsStore};
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{SetRequest, SetResponse},
+    object::settings::{Settings, SettingsProperty},
+    request::IntoValid,
+    types::state::State,
+};
+use jmap_tools::{Key, Value};
+use std::future::Future;
+use trc::AddContext;
+use types::id::Id;
+
+pub trait SettingsSet: Sync + Send {
+    fn settings_set(
+        &self,
+        request: SetRequest<'_, Settings>,
+    ) -> impl Future<Output = trc::Result<SetResponse<Settings>>> + Send;
+}
+
+impl SettingsSet for Server {
+    async fn settings_set(
+        &self,
+        mut request: SetRequest<'_, Settings>,
+    ) -> trc::Result<SetResponse<Settings>> {
+        let account_id = request.account_id.document_id();
+        let current_settings = self.get_account_settings(account_id).await?;
+        let mut settings = current_settings
+            .as_ref()
+            .map(|settings| settings.deserialize::<AccountSettings>())
+            .transpose()
+            .caused_by(trc::location!())?
+            .unwrap_or_default();
+        let old_state = State::from(settings.change_id);
+        if let Some(if_in_state) = &request.if_in_state
+            && if_in_state != &old_state
+        {
+            return Err(trc::JmapEvent::StateMismatch.into_err());
+        }
+        let mut response = self.prepare_set_response(&request, old_state).await?;
+
+        // Settings is a singleton that always exists
+        for id in request.unwrap_destroy().into_valid() {
+            response.not_destroyed.append(
+                id,
+                SetError::new(SetErrorType::Singleton)
+                    .with_description("Settings cannot be destroyed, set namespaces to null."),
+            );
+        }
+        for (id, _) in request.create.take().into_iter().flatten() {
+            response.not_created.append(
+                id,
+                SetError::new(SetErrorType::Singleton)
+                    .with_description("Settings already exist, use update instead."),
+            );
+        }
+
+        let mut changes = None;
+        for (id, obj) in request
+            .update
+            .take()
+            .into_iter()
+            .flat_map(|u| u.into_valid())
+        {
+            if id.is_singleton() && changes.is_none() {
+                changes = Some(obj);
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+            }
+        }
+        let Some(changes) = changes else {
+            return Ok(response);
+        };
+
+        // Apply changes
+        for (property, value) in changes.into_expanded_object() {
+            match (&property, value) {
+                (Key::Property(SettingsProperty::Namespace(namespace)), Value::Null)
+                    if is_valid_namespace(namespace) =>
+                {
+                    settings.remove(namespace);
+                }
+                (Key::Property(SettingsProperty::Namespace(namespace)), value)
+                    if is_valid_namespace(namespace) && matches!(value, Value::Object(_)) =>
+                {
+                    if let Ok(value) = serde_json::to_string(&value) {
+                        settings.set(namespace, value);
+                    }
+                }
+                (Key::Property(SettingsProperty::Setting { namespace, key }), value)
+                    if is_valid_namespace(namespace) && !key.is_empty() =>
+                {
+                    let mut values = settings
+                        .get(namespace)
+                        .and_then(|values| {
+                            serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(
+                                values,
+                            )
+                            .ok()
+                        })
+                        .unwrap_or_default();
+                    if matches!(value, Value::Null) {
+                        values.remove(key);
+                    } else if let Ok(value) = serde_json::to_value(&value) {
+                        values.insert(key.to_string(), value);
+                    }
+
+                    if values.is_empty() {
+                        settings.remove(namespace);
+                    } else if let Ok(values) = serde_json::to_string(&values) {
+                        settings.set(namespace, values);
+                    }
+                }
+                _ => {
+                    response.not_updated.append(
+                        Id::singleton(),
+                        SetError::invalid_properties()
+                            .with_property(property.into_owned())
+                            .with_description(
+                                "Namespaces must be alphanumeric and contain a JSON object.",
+                            ),
+                    );
+                    return Ok(response);
+                }
+            }
+        }
+
+        // Enforce quotas
+        if settings.namespaces.len() > self.core.jmap.settings_max_namespaces {
+            response.not_updated.append(
+                Id::singleton(),
+                SetError::over_quota().with_description(format!(
+                    "Too many namespaces, the maximum is {}.",
+                    self.core.jmap.settings_max_namespaces
+                )),
+            );
+            return Ok(response);
+        } else if settings.size() > self.core.jmap.settings_max_size {
+            response.not_updated.append(
+                Id::singleton(),
+                SetError::over_quota().with_description(format!(
+                    "Settings exceed the maximum size of {} bytes.",
+                    self.core.jmap.settings_max_size
+                )),
+            );
+            return Ok(response);
+        }
+
+        // Write changes
+        let change_id = self
+            .write_account_settings(account_id, current_settings.as_ref(), settings)
+            .await?;
+
+        response.new_state = Some(State::from(change_id));
+        response.updated.append(Id::singleton(), None);
+
+        Ok(response)
+    }
+}
@@ -283,7 +283,8 @@ fn format_archived_error_details(response: &ArchivedErrorDetails) -> String {
         | ArchivedError::ConnectionError(details)
         | ArchivedError::TlsError(details)
         | ArchivedError::DaneError(details)
-        | ArchivedError::MtaStsError(details) => details.to_string(),
+        | ArchivedError::MtaStsError(details)
+        | ArchivedError::RequireTlsError(details) => details.to_string(),
         ArchivedError::RateLimited => "Rate limited".to_string(),
         ArchivedError::ConcurrencyLimited => "Concurrency limited".to_string(),
     }
@@ -6,7 +6,7 @@
 
 use crate::api::{ToRequestError, request::RequestHandler};
 use common::{Server, auth::AccessToken};
-use futures_util::{SinkExt, StreamExt};
+use futures_util::{FutureExt, SinkExt, StreamExt, stream::FuturesUnordered};
 use http_proto::HttpSessionData;
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
@@ -24,6 +24,12 @@ use tungstenite::Message;
 use types::type_state::DataType;
 use utils::map::bitmap::Bitmap;
 
+enum WebSocketReply {
+    Response(String),
+    Error(String),
+    Push(Bitmap<DataType>),
+}
+
 pub trait WebSocketHandler: Sync + Send {
     fn handle_websocket_stream(
         &self,
@@ -56,6 +62,15 @@ impl WebSocketHandler for Server {
         let mut last_heartbeat = Instant::now() - heartbeat;
         let mut next_event = heartbeat;
 
+        // Flow control
+        let max_in_flight = self.core.jmap.web_socket_max_in_flight;
+        let mut in_flight = FuturesUnordered::new();
+        let mut num_requests = 0u64;
+        let mut bytes_received = 0u64;
+        let session_ = &session;
+        let start_time = Instant::now();
+        let mut stop_reason = "Client disconnected";
+
         // Register with state manager
         let mut change_rx = match self
             .subscribe_state_manager(access_token.primary_id(), Bitmap::all())
@@ -84,53 +99,54 @@ impl WebSocketHandler for Server {
 
         loop {
             tokio::select! {
-                event = tokio::time::timeout(next_event, stream.next()) => {
+                // Stop reading from the socket while too many requests are in flight,
+                // which applies backpressure to clients that pipeline requests.
+                event = tokio::time::timeout(next_event, stream.next()), if in_flight.len() < max_in_flight => {
                     match event {
                         Ok(Some(Ok(event))) => {
                             match event {
                                 Message::Text(text) => {
-                                    let response = match WebSocketMessage::parse(
-                                        text.as_bytes(),
-                                        self.core.jmap.request_max_calls,
-                                        self.core.jmap.request_max_size,
-                                    ) {
-                                        Ok(WebSocketMessage::Request(request)) => {
-                                            let response = self
-                                                .handle_jmap_request(
-                                                    request.request,
-                                                    access_token.clone(),
-                                                    &session,
-                                                )
-                                                .await;
+                                    // The parsed request borrows from the message text,
+                                    // so it is parsed by the future that owns it.
+                                    bytes_received += text.len() as u64;
+                                    let access_token = access_token.clone();
+                                    in_flight.push(async move {
+                                        match WebSocketMessage::parse(
+                                            text.as_bytes(),
+                                            self.core.jmap.request_max_calls,
+                                            self.core.jmap.request_max_size,
+                                        ) {
+                                            Ok(WebSocketMessage::Request(request)) => {
+                                                let response = self
+                                                    .handle_jmap_request(
+                                                        request.request,
+                                                        access_token,
+                                                        session_,
+                                                    )
+                                                    .await;
 
-                                            WebSocketResponse::from_response(response, request.id)
-                                            .to_json()
-                                        }
-                                        Ok(WebSocketMessage::PushEnable(push_enable)) => {
-                                            change_types = if !push_enable.data_types.is_empty() {
-                                                push_enable.data_types.into()
-                                            } else {
-                                                Bitmap::all()
-                                            };
-                                            continue;
-                                        }
-                                        Ok(WebSocketMessage::PushDisable) => {
-                                            change_types = Bitmap::new();
-                                            continue;
+                                                WebSocketReply::Response(
+                                                    WebSocketResponse::from_response(response, request.id)
+                                                    .to_json(),
+                                                )
+                                            }
+                                            Ok(WebSocketMessage::PushEnable(push_enable)) => {
+                                                WebSocketReply::Push(if !push_enable.data_types.is_empty() {
+                                                    push_enable.data_types.into()
+                                                } else {
+                                                    Bitmap::all()
+                                                })
+                                            }
+                                            Ok(WebSocketMessage::PushDisable) => {
+                                                WebSocketReply::Push(Bitmap::new())
+                                            }
+                                            Err(err) => {
+                                                let response = WebSocketRequestError::from(err.to_request_error()).to_json();
+                                                trc::error!(err.details("Failed to parse WebSocket message").span_id(session_.session_id));
+                                                WebSocketReply::Error(response)
+                                            },
                                         }
-                                        Err(err) => {
-                                            let response = WebSocketRequestError::from(err.to_request_error()).to_json();
-                                            trc::error!(err.details("Failed to parse WebSocket message").span_id(session.session_id));
-                                            response
-                                        },
-                                    };
-                                    if let Err(err) = stream.send(Message::Text(response.into())).await {
-                                        trc::event!(Jmap(JmapEvent::WebsocketError),
-                                                    Details = "Failed to send text message",
-                                                    SpanId = session.session_id,
-                                                    Reason = err.to_string()
-                                        );
-                                    }
+                                    });
                                 }
                                 Message::Ping(bytes) => {
                                     if let Err(err) = stream.send(Message::Pong(bytes)).await {
@@ -163,17 +179,45 @@ impl WebSocketHandler for Server {
                         Err(_) => {
                             // Verify timeout
                             if last_request.elapsed() > timeout {
-                                trc::event!(
-                                    Jmap(JmapEvent::WebsocketStop),
-                                    SpanId = session.session_id,
-                                    Reason = "Idle client"
-                                );
-
+                                stop_reason = "Idle client";
                                 break;
                             }
                         }
                     }
                 }
+                Some(reply) = in_flight.next(), if !in_flight.is_empty() => {
+                    // Batch any other completed responses into a single flush
+                    let mut replies = vec![reply];
+                    while let Some(Some(reply)) = in_flight.next().now_or_never() {
+                        replies.push(reply);
+                    }
+                    let mut result = Ok(());
+                    for reply in replies {
+                        let response = match reply {
+                            WebSocketReply::Response(response) => {
+                                num_requests += 1;
+                                response
+                            }
+                            WebSocketReply::Error(response) => response,
+                            WebSocketReply::Push(types) => {
+                                change_types = types;
+                                continue;
+                            }
+                        };
+                        result = stream.feed(Message::Text(response.into())).await;
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                    if let Err(err) = result.and(stream.flush().await) {
+                        trc::event!(Jmap(JmapEvent::WebsocketError),
+                                    Details = "Failed to send text message",
+                                    SpanId = session.session_id,
+                                    Reason = err.to_string()
+                        );
+                    }
+                    last_heartbeat = Instant::now();
+                }
                 state_change = change_rx.recv() => {
                     if let Some(state_change) = state_change {
                         let mut types = state_change.types;
@@ -186,12 +230,7 @@ impl WebSocketHandler for Server {
                                 .set(type_state, state_change.change_id.into());
                         }
                     } else {
-                        trc::event!(
-                            Jmap(JmapEvent::WebsocketStop),
-                            SpanId = session.session_id,
-                            Reason = "State manager channel closed"
-                        );
-
+                        stop_reason = "State manager channel closed";
                         break;
                     }
                 }
@@ -230,5 +269,15 @@ impl WebSocketHandler for Server {
                 next_event = heartbeat;
             }
         }
+
+        trc::event!(
+            Jmap(JmapEvent::WebsocketStop),
+            SpanId = session.session_id,
+            AccountId = access_token.primary_id(),
+            Total = num_requests,
+            Size = bytes_received,
+            Reason = stop_reason,
+            Elapsed = start_time.elapsed(),
+        );
     }
 }
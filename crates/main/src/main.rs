@@ -18,7 +18,7 @@ use services::{StartServices, broadcast::subscriber::spawn_broadcast_subscriber}
 use smtp::{StartQueueManager, core::SmtpSessionManager};
 use std::time::Duration;
 use trc::Collector;
-use utils::wait_for_shutdown;
+use utils::{sd_notify, wait_for_shutdown};
 
 #[cfg(not(target_env = "msvc"))]
 use jemallocator::Jemalloc;
@@ -32,6 +32,18 @@ async fn main() -> std::io::Result<()> {
     // Load config and apply macros
     let mut init = Box::pin(BootManager::init()).await;
 
+    // Report pending migrations without applying them
+    if init.migrate_dry_run {
+        if let Err(err) = migration::registry::migration_dry_run(&init.inner.build_server()).await {
+            trc::event!(
+                Server(trc::ServerEvent::StartupError),
+                Details = "Failed to build migration plan.",
+                Reason = err,
+            );
+        }
+        return Ok(());
+    }
+
     // Migrate database
     if let Err(err) = migration::try_migrate(&init.inner.build_server()).await {
         trc::event!(
@@ -90,8 +102,12 @@ async fn main() -> std::io::Result<()> {
     // Start broadcast subscriber
     spawn_broadcast_subscriber(init.inner, shutdown_rx);
 
+    // Notify the service manager that startup is complete
+    sd_notify("READY=1");
+
     // Wait for shutdown signal
     wait_for_shutdown().await;
+    sd_notify("STOPPING=1");
 
     // Shutdown collector
     Collector::shutdown();
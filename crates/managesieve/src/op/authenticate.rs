@@ -72,11 +72,10 @@ impl<T: SessionStream> Session<T> {
         // Authenticate
         let access_token = self
             .server
-            .authenticate(&AuthRequest::from_credentials(
-                credentials,
-                self.session_id,
-                self.remote_addr,
-            ))
+            .authenticate(
+                &AuthRequest::from_credentials(credentials, self.session_id, self.remote_addr)
+                    .with_session("managesieve", None),
+            )
             .await
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
@@ -14,6 +14,9 @@ use common::{
     DATABASE_SCHEMA_VERSION, KV_LOCK_HOUSEKEEPER, Server, manager::boot::DEFAULT_SETTINGS,
 };
 use principal::{migrate_principal, migrate_principals};
+use registry::{
+    MIGRATIONS, UNVERSIONED, migration_history, plan_steps, schema_version, unsupported_version,
+};
 use report::migrate_reports;
 use std::time::Duration;
 use store::{
@@ -36,6 +39,7 @@ pub mod object;
 pub mod principal;
 pub mod push;
 pub mod queue;
+pub mod registry;
 pub mod report;
 pub mod sieve;
 pub mod submission;
@@ -91,47 +95,50 @@ pub async fn try_migrate(server: &Server) -> trc::Result<()> {
         return Ok(());
     }
 
-    let add_v013_config = match server
-        .store()
-        .get_value::<u32>(AnyKey {
-            subspace: SUBSPACE_PROPERTY,
-            key: vec![0u8],
-        })
-        .await
-        .caused_by(trc::location!())?
-    {
+    let from = match schema_version(server).await? {
         Some(DATABASE_SCHEMA_VERSION) => {
             return Ok(());
         }
-        Some(1) => {
-            migrate_v0_12(server, true)
+        Some(version) => Some(version),
+        None if !is_new_install(server).await.caused_by(trc::location!())? => Some(UNVERSIONED),
+        None => None,
+    };
+
+    let mut batch = BatchBuilder::new();
+    if let Some(from) = from {
+        let mut history = migration_history(server).await?;
+        let steps = plan_steps(MIGRATIONS, from, DATABASE_SCHEMA_VERSION, &history)
+            .map_err(unsupported_version)?;
+
+        // Each step records its completion together with the schema version it
+        // produces, a restart resumes from the first step that did not finish
+        for migration in steps {
+            trc::event!(
+                Server(trc::ServerEvent::Startup),
+                Details = format!(
+                    "Migrating database schema from version {} to {}: {}",
+                    migration.from, migration.to, migration.description
+                )
+            );
+
+            migration
+                .step
+                .run(server)
                 .await
                 .caused_by(trc::location!())?;
-            true
-        }
-        Some(2) => {
-            migrate_v0_12(server, false)
+
+            let mut completion = BatchBuilder::new();
+            history.record_completion(migration, &mut completion);
+            server
+                .store()
+                .write(completion.build_all())
                 .await
                 .caused_by(trc::location!())?;
-            true
         }
-        Some(version) => {
-            panic!(
-                "Unknown database schema version, expected {} or below, found {}",
-                DATABASE_SCHEMA_VERSION, version
-            );
-        }
-        _ => {
-            if !is_new_install(server).await.caused_by(trc::location!())? {
-                migrate_v0_11(server).await.caused_by(trc::location!())?;
-                true
-            } else {
-                false
-            }
-        }
-    };
 
-    let mut batch = BatchBuilder::new();
+        add_v013_config(&mut batch);
+    }
+
     batch.set(
         ValueClass::Any(AnyClass {
             subspace: SUBSPACE_PROPERTY,
@@ -139,24 +146,6 @@ pub async fn try_migrate(server: &Server) -> trc::Result<()> {
         }),
         DATABASE_SCHEMA_VERSION.serialize(),
     );
-
-    if add_v013_config {
-        for (key, value) in DEFAULT_SETTINGS {
-            if key
-                .strip_prefix("queue.")
-                .is_some_and(|s| !s.starts_with("limiter.") && !s.starts_with("quota."))
-            {
-                batch.set(
-                    ValueClass::Any(AnyClass {
-                        subspace: SUBSPACE_SETTINGS,
-                        key: key.as_bytes().to_vec(),
-                    }),
-                    value.as_bytes().to_vec(),
-                );
-            }
-        }
-    }
-
     server
         .store()
         .write(batch.build_all())
@@ -166,6 +155,23 @@ pub async fn try_migrate(server: &Server) -> trc::Result<()> {
     Ok(())
 }
 
+fn add_v013_config(batch: &mut BatchBuilder) {
+    for (key, value) in DEFAULT_SETTINGS {
+        if key
+            .strip_prefix("queue.")
+            .is_some_and(|s| !s.starts_with("limiter.") && !s.starts_with("quota."))
+        {
+            batch.set(
+                ValueClass::Any(AnyClass {
+                    subspace: SUBSPACE_SETTINGS,
+                    key: key.as_bytes().to_vec(),
+                }),
+                value.as_bytes().to_vec(),
+            );
+        }
+    }
+}
+
 async fn migrate_v0_12(server: &Server, migrate_tasks: bool) -> trc::Result<()> {
     let force_lock = std::env::var("FORCE_LOCK").is_ok();
     let in_memory = server.in_memory_store();
@@ -0,0 +1,475 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{DATABASE_SCHEMA_VERSION, Server};
+use store::{
+    Deserialize, IterateParams, SUBSPACE_INDEXES, SUBSPACE_PROPERTY, SUBSPACE_QUEUE_EVENT,
+    SUBSPACE_QUEUE_MESSAGE, SUBSPACE_REPORT_IN, SUBSPACE_REPORT_OUT, SUBSPACE_TASK_QUEUE,
+    SerializeInfallible, U32_LEN, U64_LEN,
+    write::{AnyClass, AnyKey, BatchBuilder, ValueClass, key::DeserializeBigEndian, now},
+};
+use trc::AddContext;
+
+use crate::{is_new_install, migrate_v0_11, migrate_v0_12};
+
+// Schema version 0 is used for databases created before versioning was introduced
+pub const UNVERSIONED: u32 = 0;
+
+// Property keys are always prefixed by a 4-byte account id, single byte keys
+// are reserved for the schema version and the migration history
+const SCHEMA_VERSION_KEY: u8 = 0;
+const MIGRATION_HISTORY_KEY: u8 = 1;
+
+#[derive(Clone)]
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    pub subspaces: &'static [u8],
+    pub step: MigrationStep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationStep {
+    V0_11,
+    V0_12 { migrate_tasks: bool },
+}
+
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: UNVERSIONED,
+        to: DATABASE_SCHEMA_VERSION,
+        description: "Upgrade from v0.11: queue, reports, changelog, principals and account data",
+        subspaces: &[
+            SUBSPACE_QUEUE_MESSAGE,
+            SUBSPACE_QUEUE_EVENT,
+            SUBSPACE_REPORT_IN,
+            SUBSPACE_REPORT_OUT,
+            SUBSPACE_PROPERTY,
+        ],
+        step: MigrationStep::V0_11,
+    },
+    Migration {
+        from: 1,
+        to: DATABASE_SCHEMA_VERSION,
+        description: "Upgrade from v0.12: queue events, scheduled tasks and calendar events",
+        subspaces: &[SUBSPACE_QUEUE_EVENT, SUBSPACE_TASK_QUEUE, SUBSPACE_INDEXES],
+        step: MigrationStep::V0_12 {
+            migrate_tasks: true,
+        },
+    },
+    Migration {
+        from: 2,
+        to: DATABASE_SCHEMA_VERSION,
+        description: "Upgrade queue event format",
+        subspaces: &[SUBSPACE_QUEUE_EVENT],
+        step: MigrationStep::V0_12 {
+            migrate_tasks: false,
+        },
+    },
+];
+
+#[derive(Debug)]
+pub struct MigrationPlan {
+    pub current_version: Option<u32>,
+    pub target_version: u32,
+    pub pending: Vec<PendingMigration>,
+    pub completed: Vec<CompletedMigration>,
+}
+
+#[derive(Debug)]
+pub struct PendingMigration {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    pub estimated_keys: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedMigration {
+    pub from: u32,
+    pub to: u32,
+    pub completed_at: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct MigrationHistory(pub Vec<CompletedMigration>);
+
+impl MigrationStep {
+    pub async fn run(&self, server: &Server) -> trc::Result<()> {
+        match self {
+            MigrationStep::V0_11 => migrate_v0_11(server).await,
+            MigrationStep::V0_12 { migrate_tasks } => migrate_v0_12(server, *migrate_tasks).await,
+        }
+    }
+}
+
+// Returns the steps needed to bring a database from `version` to `target`,
+// in the order they have to be applied. Steps found in the history are skipped,
+// which allows resuming a partially applied upgrade. On failure, the version
+// for which no migration is registered is returned.
+pub fn plan_steps<'x>(
+    migrations: &'x [Migration],
+    mut version: u32,
+    target: u32,
+    history: &MigrationHistory,
+) -> Result<Vec<&'x Migration>, u32> {
+    let mut steps = Vec::new();
+
+    while version != target {
+        let migration = migrations
+            .iter()
+            .filter(|m| m.from == version && m.to > version && m.to <= target)
+            .max_by_key(|m| m.to)
+            .ok_or(version)?;
+        if !history.contains(migration) {
+            steps.push(migration);
+        }
+        version = migration.to;
+    }
+
+    Ok(steps)
+}
+
+impl MigrationHistory {
+    pub fn contains(&self, migration: &Migration) -> bool {
+        self.0
+            .iter()
+            .any(|c| c.from == migration.from && c.to == migration.to)
+    }
+
+    pub fn record_completion(&mut self, migration: &Migration, batch: &mut BatchBuilder) {
+        self.0.push(CompletedMigration {
+            from: migration.from,
+            to: migration.to,
+            completed_at: now(),
+        });
+        batch
+            .set(
+                ValueClass::Any(AnyClass {
+                    subspace: SUBSPACE_PROPERTY,
+                    key: vec![MIGRATION_HISTORY_KEY],
+                }),
+                SerializeInfallible::serialize(self),
+            )
+            .set(
+                ValueClass::Any(AnyClass {
+                    subspace: SUBSPACE_PROPERTY,
+                    key: vec![SCHEMA_VERSION_KEY],
+                }),
+                SerializeInfallible::serialize(&migration.to),
+            );
+    }
+}
+
+impl SerializeInfallible for MigrationHistory {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.len() * (U32_LEN * 2 + U64_LEN));
+        for completed in &self.0 {
+            bytes.extend_from_slice(&completed.from.to_be_bytes());
+            bytes.extend_from_slice(&completed.to.to_be_bytes());
+            bytes.extend_from_slice(&completed.completed_at.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+impl Deserialize for MigrationHistory {
+    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
+        const RECORD_LEN: usize = U32_LEN * 2 + U64_LEN;
+
+        if bytes.len() % RECORD_LEN != 0 {
+            return Err(trc::StoreEvent::DataCorruption.caused_by(trc::location!()));
+        }
+
+        bytes
+            .chunks_exact(RECORD_LEN)
+            .map(|record| {
+                Ok(CompletedMigration {
+                    from: record.deserialize_be_u32(0)?,
+                    to: record.deserialize_be_u32(U32_LEN)?,
+                    completed_at: record.deserialize_be_u64(U32_LEN * 2)?,
+                })
+            })
+            .collect::<trc::Result<Vec<_>>>()
+            .map(MigrationHistory)
+    }
+}
+
+pub async fn schema_version(server: &Server) -> trc::Result<Option<u32>> {
+    server
+        .store()
+        .get_value::<u32>(AnyKey {
+            subspace: SUBSPACE_PROPERTY,
+            key: vec![SCHEMA_VERSION_KEY],
+        })
+        .await
+        .caused_by(trc::location!())
+}
+
+pub async fn migration_history(server: &Server) -> trc::Result<MigrationHistory> {
+    server
+        .store()
+        .get_value::<MigrationHistory>(AnyKey {
+            subspace: SUBSPACE_PROPERTY,
+            key: vec![MIGRATION_HISTORY_KEY],
+        })
+        .await
+        .caused_by(trc::location!())
+        .map(Option::unwrap_or_default)
+}
+
+// Builds the list of migrations that would be applied on startup without
+// modifying any data.
+pub async fn migration_plan(server: &Server) -> trc::Result<MigrationPlan> {
+    let current_version = schema_version(server).await?;
+    let from = match current_version {
+        Some(version) => Some(version),
+        None if !is_new_install(server).await? => Some(UNVERSIONED),
+        None => None,
+    };
+
+    let history = migration_history(server).await?;
+
+    let mut pending = Vec::new();
+    if let Some(from) = from {
+        let steps = plan_steps(MIGRATIONS, from, DATABASE_SCHEMA_VERSION, &history)
+            .map_err(unsupported_version)?;
+        for migration in steps {
+            let mut estimated_keys = 0;
+            for subspace in migration.subspaces {
+                estimated_keys += count_keys(server, *subspace).await?;
+            }
+            pending.push(PendingMigration {
+                from: migration.from,
+                to: migration.to,
+                description: migration.description,
+                estimated_keys,
+            });
+        }
+    }
+
+    Ok(MigrationPlan {
+        current_version,
+        target_version: DATABASE_SCHEMA_VERSION,
+        pending,
+        completed: history.0,
+    })
+}
+
+// Reports the migration plan as events, used by the '--migrate-dry-run' option.
+pub async fn migration_dry_run(server: &Server) -> trc::Result<()> {
+    let plan = migration_plan(server).await?;
+
+    for completed in &plan.completed {
+        trc::event!(
+            Server(trc::ServerEvent::MigrationCompleted),
+            From = completed.from,
+            To = completed.to,
+            Value = completed.completed_at,
+        );
+    }
+
+    for pending in &plan.pending {
+        trc::event!(
+            Server(trc::ServerEvent::MigrationPending),
+            From = pending.from,
+            To = pending.to,
+            Details = pending.description,
+            Total = pending.estimated_keys,
+        );
+    }
+
+    trc::event!(
+        Server(trc::ServerEvent::MigrationPlan),
+        Version = plan.current_version,
+        To = plan.target_version,
+        Total = plan.pending.len(),
+    );
+
+    Ok(())
+}
+
+pub(crate) fn unsupported_version(version: u32) -> trc::Error {
+    trc::StoreEvent::NotSupported
+        .into_err()
+        .details("Unknown database schema version")
+        .ctx(trc::Key::Value, version)
+}
+
+async fn count_keys(server: &Server, subspace: u8) -> trc::Result<u64> {
+    let mut count = 0;
+    server
+        .store()
+        .iterate(
+            IterateParams::new(
+                AnyKey {
+                    subspace,
+                    key: vec![0u8],
+                },
+                AnyKey {
+                    subspace,
+                    key: vec![u8::MAX; 16],
+                },
+            )
+            .no_values(),
+            |_, _| {
+                count += 1;
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())
+        .map(|_| count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN: &[Migration] = &[
+        Migration {
+            from: 2,
+            to: 3,
+            description: "2 to 3",
+            subspaces: &[],
+            step: MigrationStep::V0_12 {
+                migrate_tasks: false,
+            },
+        },
+        Migration {
+            from: 0,
+            to: 1,
+            description: "0 to 1",
+            subspaces: &[],
+            step: MigrationStep::V0_11,
+        },
+        Migration {
+            from: 1,
+            to: 2,
+            description: "1 to 2",
+            subspaces: &[],
+            step: MigrationStep::V0_12 {
+                migrate_tasks: true,
+            },
+        },
+    ];
+
+    fn versions(steps: &[&Migration]) -> Vec<(u32, u32)> {
+        steps.iter().map(|m| (m.from, m.to)).collect()
+    }
+
+    fn history(completed: &[(u32, u32)]) -> MigrationHistory {
+        MigrationHistory(
+            completed
+                .iter()
+                .map(|(from, to)| CompletedMigration {
+                    from: *from,
+                    to: *to,
+                    completed_at: 0,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn migration_order() {
+        // Steps are applied in version order regardless of registry order
+        assert_eq!(
+            versions(&plan_steps(CHAIN, 0, 3, &MigrationHistory::default()).unwrap()),
+            vec![(0, 1), (1, 2), (2, 3)]
+        );
+        assert_eq!(
+            versions(&plan_steps(CHAIN, 2, 3, &MigrationHistory::default()).unwrap()),
+            vec![(2, 3)]
+        );
+        assert!(
+            plan_steps(CHAIN, 3, 3, &MigrationHistory::default())
+                .unwrap()
+                .is_empty()
+        );
+
+        // Direct upgrades are preferred over chained steps
+        let mut migrations = CHAIN.to_vec();
+        migrations.push(Migration {
+            from: 0,
+            to: 3,
+            description: "0 to 3",
+            subspaces: &[],
+            step: MigrationStep::V0_11,
+        });
+        assert_eq!(
+            versions(&plan_steps(&migrations, 0, 3, &MigrationHistory::default()).unwrap()),
+            vec![(0, 3)]
+        );
+
+        // Steps beyond the target are never planned
+        assert_eq!(
+            versions(&plan_steps(CHAIN, 0, 2, &MigrationHistory::default()).unwrap()),
+            vec![(0, 1), (1, 2)]
+        );
+
+        // Unknown versions are reported
+        assert_eq!(
+            plan_steps(CHAIN, 7, 3, &MigrationHistory::default()).unwrap_err(),
+            7
+        );
+        assert_eq!(
+            plan_steps(&CHAIN[1..], 0, 3, &MigrationHistory::default()).unwrap_err(),
+            2
+        );
+    }
+
+    #[test]
+    fn migration_skip_completed() {
+        assert_eq!(
+            versions(&plan_steps(CHAIN, 0, 3, &history(&[(0, 1), (1, 2)])).unwrap()),
+            vec![(2, 3)]
+        );
+        assert!(
+            plan_steps(CHAIN, 0, 3, &history(&[(0, 1), (1, 2), (2, 3)]))
+                .unwrap()
+                .is_empty()
+        );
+
+        // Records of other upgrade paths do not affect the plan
+        assert_eq!(
+            versions(&plan_steps(CHAIN, 1, 3, &history(&[(0, 3)])).unwrap()),
+            vec![(1, 2), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn migration_resume() {
+        let mut history = MigrationHistory::default();
+        let mut batch = BatchBuilder::new();
+
+        // Interrupted after the first step, the schema version was advanced
+        let steps = plan_steps(CHAIN, 0, 3, &history).unwrap();
+        history.record_completion(steps[0], &mut batch);
+        assert_eq!(
+            versions(&plan_steps(CHAIN, steps[0].to, 3, &history).unwrap()),
+            vec![(1, 2), (2, 3)]
+        );
+
+        // A recorded step is not applied twice, even if resuming from a stale version
+        let steps = plan_steps(CHAIN, 1, 3, &history).unwrap();
+        history.record_completion(steps[0], &mut batch);
+        assert_eq!(
+            versions(&plan_steps(CHAIN, 1, 3, &history).unwrap()),
+            vec![(2, 3)]
+        );
+
+        // History survives a round trip through the store
+        let history = MigrationHistory::deserialize(&history.serialize()).unwrap();
+        assert_eq!(
+            history.0.iter().map(|c| (c.from, c.to)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 2)]
+        );
+        assert!(MigrationHistory::deserialize(&[0u8; 15]).is_err());
+        assert!(MigrationHistory::deserialize(&[]).unwrap().0.is_empty());
+    }
+}
@@ -24,6 +24,7 @@ impl<T: SessionStream> Session<T> {
             Size = bytes.len(),
             Contents = trc::Value::from_maybe_string(bytes),
         );
+        self.client.touch();
 
         let mut bytes = bytes.iter();
         let mut requests = Vec::with_capacity(2);
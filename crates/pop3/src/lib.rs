@@ -9,7 +9,10 @@ use std::{net::IpAddr, sync::Arc};
 use common::{
     Inner, Server,
     auth::AccessToken,
-    listener::{ServerInstance, SessionStream, limiter::InFlight},
+    listener::{
+        ServerInstance, SessionStream, clients::ClientHandle, limiter::InFlight,
+        shaper::BandwidthShaper,
+    },
 };
 use mailbox::Mailbox;
 use protocol::request::Parser;
@@ -42,6 +45,7 @@ pub struct Session<T: SessionStream> {
     pub in_flight: InFlight,
     pub remote_addr: IpAddr,
     pub session_id: u64,
+    pub client: ClientHandle,
 }
 
 pub enum State {
@@ -53,6 +57,7 @@ pub enum State {
         mailbox: Mailbox,
         in_flight: Option<InFlight>,
         access_token: Arc<AccessToken>,
+        shaper: Option<BandwidthShaper>,
     },
 }
 
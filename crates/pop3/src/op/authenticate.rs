@@ -9,7 +9,7 @@ use common::{
         AuthRequest,
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
     },
-    listener::{SessionStream, limiter::LimiterResult},
+    listener::{SessionStream, limiter::LimiterResult, shaper::BandwidthShaper},
 };
 use directory::Permission;
 use mail_parser::decoders::base64::base64_decode;
@@ -68,11 +68,10 @@ impl<T: SessionStream> Session<T> {
         // Authenticate
         let access_token = self
             .server
-            .authenticate(&AuthRequest::from_credentials(
-                credentials,
-                self.session_id,
-                self.remote_addr,
-            ))
+            .authenticate(
+                &AuthRequest::from_credentials(credentials, self.session_id, self.remote_addr)
+                    .with_session("pop3", None),
+            )
             .await
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
@@ -113,9 +112,13 @@ impl<T: SessionStream> Session<T> {
         let mailbox = self.fetch_mailbox(access_token.primary_id()).await?;
 
         // Create session
+        self.client
+            .set_account(access_token.primary_id(), access_token.name.clone());
+        self.client.set_mailbox(Some("INBOX".to_string()));
         self.state = State::Authenticated {
             in_flight,
             mailbox,
+            shaper: access_token.bandwidth_limit.map(BandwidthShaper::new),
             access_token,
         };
         self.write_ok("Authentication successful").await
@@ -7,11 +7,13 @@
 use crate::{Session, protocol::response::Response};
 use common::listener::SessionStream;
 use directory::Permission;
-use email::message::metadata::MessageMetadata;
+use email::message::metadata::{ArchivedMessageMetadata, MessageMetadata};
 use std::time::Instant;
 use trc::AddContext;
 use types::{collection::Collection, field::EmailField};
 
+const TOP_CHUNK_SIZE: usize = 16 * 1024;
+
 impl<T: SessionStream> Session<T> {
     pub async fn handle_fetch(&mut self, msg: u32, lines: Option<u32>) -> trc::Result<()> {
         // Validate access
@@ -36,13 +38,17 @@ impl<T: SessionStream> Session<T> {
                 let metadata = metadata_
                     .unarchive::<MessageMetadata>()
                     .caused_by(trc::location!())?;
-                if let Some(bytes) = self
-                    .server
-                    .blob_store()
-                    .get_blob(metadata.blob_hash.0.as_slice(), 0..usize::MAX)
-                    .await
-                    .caused_by(trc::location!())?
-                {
+                let bytes = if let Some(lines) = lines {
+                    self.fetch_top(metadata, lines).await?
+                } else {
+                    self.server
+                        .blob_store()
+                        .get_blob(metadata.blob_hash.0.as_slice(), 0..usize::MAX)
+                        .await
+                        .caused_by(trc::location!())?
+                };
+
+                if let Some(bytes) = bytes {
                     trc::event!(
                         Pop3(trc::Pop3Event::Fetch),
                         SpanId = self.session_id,
@@ -50,14 +56,8 @@ impl<T: SessionStream> Session<T> {
                         Elapsed = op_start.elapsed()
                     );
 
-                    self.write_bytes(
-                        Response::Message::<u32> {
-                            bytes,
-                            lines: lines.unwrap_or(0),
-                        }
-                        .serialize(),
-                    )
-                    .await
+                    self.write_bytes(Response::Message::<u32> { bytes }.serialize())
+                        .await
                 } else {
                     Err(trc::Pop3Event::Error
                         .into_err()
@@ -74,4 +74,49 @@ impl<T: SessionStream> Session<T> {
             Err(trc::Pop3Event::Error.into_err().details("No such message."))
         }
     }
+
+    // Returns the message headers followed by the requested number of body lines,
+    // reading the body from the blob store in chunks rather than as a whole
+    async fn fetch_top(
+        &self,
+        metadata: &ArchivedMessageMetadata,
+        lines: u32,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        let blob_hash = metadata.blob_hash.0.as_slice();
+        let size = u32::from(metadata.size) as usize;
+        let mut bytes = metadata.raw_headers.to_vec();
+        let mut offset = metadata
+            .contents
+            .first()
+            .and_then(|contents| contents.parts.first())
+            .map_or(bytes.len(), |part| u32::from(part.offset_body) as usize);
+        let mut remaining = lines;
+
+        while remaining > 0 && offset < size {
+            let chunk = match self
+                .server
+                .blob_store()
+                .get_blob(blob_hash, offset..(offset + TOP_CHUNK_SIZE).min(size))
+                .await
+                .caused_by(trc::location!())?
+            {
+                Some(chunk) if !chunk.is_empty() => chunk,
+                Some(_) => break,
+                None => return Ok(None),
+            };
+
+            let mut end = chunk.len();
+            for (pos, _) in chunk.iter().enumerate().filter(|(_, ch)| **ch == b'\n') {
+                remaining -= 1;
+                if remaining == 0 {
+                    end = pos + 1;
+                    break;
+                }
+            }
+            bytes.extend_from_slice(&chunk[..end]);
+            offset += chunk.len();
+        }
+
+        Ok(Some(bytes))
+    }
 }
@@ -14,7 +14,6 @@ pub enum Response<T> {
     List(Vec<T>),
     Message {
         bytes: Vec<u8>,
-        lines: u32,
     },
     Capability {
         mechanisms: Vec<Mechanism>,
@@ -51,13 +50,12 @@ impl<T: Display> Response<T> {
                 buf.extend_from_slice(b".\r\n");
                 buf
             }
-            Response::Message { bytes, lines } => {
+            Response::Message { bytes } => {
                 let mut buf = Vec::with_capacity(bytes.len() + 10);
                 buf.extend_from_slice(b"+OK ");
                 buf.extend_from_slice(bytes.len().to_string().as_bytes());
                 buf.extend_from_slice(b" octets\r\n");
 
-                let mut line_count = 0;
                 let mut last_byte = 0;
 
                 // Transparency procedure
@@ -72,13 +70,6 @@ impl<T: Display> Response<T> {
                     }
                     buf.push(byte);
                     last_byte = byte;
-
-                    if *lines > 0 && byte == b'\n' {
-                        line_count += 1;
-                        if line_count == *lines {
-                            break;
-                        }
-                    }
                 }
 
                 if last_byte != b'\n' {
@@ -209,7 +200,6 @@ mod tests {
                     bytes: "Subject: test\r\n\r\n.\r\ntest.\r\n.test\r\na"
                         .as_bytes()
                         .to_vec(),
-                    lines: 0,
                 },
                 "+OK 35 octets\r\nSubject: test\r\n\r\n..\r\ntest.\r\n..test\r\na\r\n.\r\n",
             ),
@@ -8,7 +8,7 @@ use std::borrow::Cow;
 
 use common::{
     core::BuildServer,
-    listener::{SessionData, SessionManager, SessionResult, SessionStream},
+    listener::{SessionData, SessionManager, SessionResult, SessionStream, clients::ClientHandle},
 };
 use tokio_rustls::server::TlsStream;
 
@@ -29,8 +29,15 @@ impl SessionManager for Pop3SessionManager {
         session: SessionData<T>,
     ) -> impl std::future::Future<Output = ()> + Send {
         async move {
+            let server = self.inner.build_server();
+            let client = ClientHandle::new(
+                &server,
+                session.session_id,
+                session.instance.protocol,
+                session.remote_ip,
+            );
             let mut session = Session {
-                server: self.inner.build_server(),
+                server,
                 instance: session.instance,
                 receiver: Parser::default(),
                 state: State::NotAuthenticated {
@@ -41,6 +48,7 @@ impl SessionManager for Pop3SessionManager {
                 in_flight: session.in_flight,
                 remote_addr: session.remote_ip,
                 session_id: session.session_id,
+                client,
             };
 
             if session
@@ -70,10 +78,11 @@ impl<T: SessionStream> Session<T> {
         loop {
             tokio::select! {
                 result = tokio::time::timeout(
-                    if !matches!(self.state, State::NotAuthenticated {..}) {
-                        self.server.core.imap.timeout_auth
-                    } else {
-                        self.server.core.imap.timeout_unauth
+                    match &self.state {
+                        State::NotAuthenticated { .. } => self.server.core.imap.timeout_unauth,
+                        State::Authenticated { access_token, .. } => access_token
+                            .session_timeout
+                            .unwrap_or(self.server.core.imap.timeout_auth),
                     },
                     self.stream.read(&mut buf)) => {
                     match result {
@@ -118,6 +127,17 @@ impl<T: SessionStream> Session<T> {
                         }
                     }
                 },
+                _ = self.client.disconnected() => {
+                    trc::event!(
+                        Network(trc::NetworkEvent::Closed),
+                        SpanId = self.session_id,
+                        Reason = "Disconnected by administrator",
+                        CausedBy = trc::location!()
+                    );
+
+                    self.write_bytes(&b"-ERR Connection closed by administrator.\r\n"[..]).await.ok();
+                    break;
+                }
                 _ = shutdown_rx.changed() => {
                     trc::event!(
                         Network(trc::NetworkEvent::Closed),
@@ -148,6 +168,7 @@ impl<T: SessionStream> Session<T> {
             session_id: self.session_id,
             in_flight: self.in_flight,
             remote_addr: self.remote_addr,
+            client: self.client,
         })
     }
 }
@@ -163,7 +184,16 @@ impl<T: SessionStream> Session<T> {
             Contents = trc::Value::from_maybe_string(bytes),
         );
 
-        self.stream.write_all(bytes.as_ref()).await.map_err(|err| {
+        let result = if let State::Authenticated {
+            shaper: Some(shaper),
+            ..
+        } = &self.state
+        {
+            shaper.write_all(&mut self.stream, bytes).await
+        } else {
+            self.stream.write_all(bytes).await
+        };
+        result.map_err(|err| {
             trc::NetworkEvent::WriteError
                 .into_err()
                 .reason(err)
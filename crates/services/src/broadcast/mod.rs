@@ -67,6 +67,10 @@ impl BroadcastBatch<Vec<BroadcastEvent>> {
                 BroadcastEvent::ReloadBlockedIps => {
                     serialized.push(4u8);
                 }
+                BroadcastEvent::ReloadScopes(scopes) => {
+                    serialized.push(5u8);
+                    let _ = serialized.write_leb128(scopes.bitmap);
+                }
             }
         }
         serialized
@@ -113,6 +117,9 @@ where
                 3 => Ok(Some(BroadcastEvent::ReloadSettings)),
 
                 4 => Ok(Some(BroadcastEvent::ReloadBlockedIps)),
+                5 => Ok(Some(BroadcastEvent::ReloadScopes(Bitmap::from(
+                    self.messages.next_leb128::<u64>().ok_or(())?,
+                )))),
 
                 _ => Err(()),
             }
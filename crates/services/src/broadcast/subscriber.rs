@@ -8,12 +8,14 @@ use crate::broadcast::{BROADCAST_TOPIC, BroadcastBatch};
 use common::{
     Inner,
     core::BuildServer,
-    ipc::{BroadcastEvent, HousekeeperEvent, StateEvent},
+    ipc::{BroadcastEvent, StateEvent},
+    manager::reload::ReloadScope,
 };
 use compact_str::CompactString;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::watch;
 use trc::{ClusterEvent, ServerEvent};
+use utils::map::bitmap::Bitmap;
 
 pub fn spawn_broadcast_subscriber(inner: Arc<Inner>, mut shutdown_rx: watch::Receiver<bool>) {
     let this_node_id = {
@@ -29,7 +31,7 @@ pub fn spawn_broadcast_subscriber(inner: Arc<Inner>, mut shutdown_rx: watch::Rec
 
         trc::event!(Cluster(ClusterEvent::SubscriberStart));
 
-        loop {
+        'outer: loop {
             let pubsub = inner.shared_core.load().storage.pubsub.clone();
             if pubsub.is_none() {
                 trc::event!(
@@ -68,101 +70,92 @@ pub fn spawn_broadcast_subscriber(inner: Arc<Inner>, mut shutdown_rx: watch::Rec
                 }
             };
 
-            tokio::select! {
-                message = stream.next() => {
-                    match message {
-                        Some(message) => {
-                            let mut batch = BroadcastBatch::new(message.payload().iter());
-                            let node_id = match batch.node_id() {
-                                Some(node_id) => {
-                                    if node_id != this_node_id {
-                                        node_id
-                                    } else {
+            loop {
+                tokio::select! {
+                    message = stream.next() => {
+                        match message {
+                            Some(message) => {
+                                let mut batch = BroadcastBatch::new(message.payload().iter());
+                                let node_id = match batch.node_id() {
+                                    Some(node_id) => {
+                                        if node_id != this_node_id {
+                                            node_id
+                                        } else {
+                                            trc::event!(
+                                                Cluster(ClusterEvent::MessageSkipped),
+                                                Details = message.payload()
+                                            );
+                                            continue;
+                                        }
+                                    }
+                                    None => {
                                         trc::event!(
-                                            Cluster(ClusterEvent::MessageSkipped),
+                                            Cluster(ClusterEvent::MessageInvalid),
                                             Details = message.payload()
                                         );
                                         continue;
                                     }
-                                }
-                                None => {
-                                    trc::event!(
-                                        Cluster(ClusterEvent::MessageInvalid),
-                                        Details = message.payload()
-                                    );
-                                    continue;
-                                }
-                            };
+                                };
 
-                            let mut max_timestamp = 0;
+                                let mut max_timestamp = 0;
 
-                            loop {
-                                match batch.next_event() {
-                                    Ok(Some(event)) => {
-                                        trc::event!(
-                                            Cluster(ClusterEvent::MessageReceived),
-                                            From = node_id,
-                                            To = this_node_id,
-                                            Details = log_event(&event),
-                                        );
-                                        match event {
-                                            BroadcastEvent::StateChange(state_change) => {
-                                                max_timestamp =
-                                                    std::cmp::max(max_timestamp, state_change.change_id);
-                                                if inner
-                                                    .ipc
-                                                    .state_tx
-                                                    .send(StateEvent::Publish {
-                                                        state_change,
-                                                        broadcast: false,
-                                                    })
-                                                    .await
-                                                    .is_err()
-                                                {
-                                                    trc::event!(
-                                                        Server(ServerEvent::ThreadError),
-                                                        Details = "Error sending state change.",
-                                                        CausedBy = trc::location!()
-                                                    );
-                                                }
-                                            }
-                                            BroadcastEvent::InvalidateAccessTokens(ids) => {
-                                                for id in &ids {
-                                                    inner.cache.permissions.remove(id);
-                                                    inner.cache.access_tokens.remove(id);
+                                loop {
+                                    match batch.next_event() {
+                                        Ok(Some(event)) => {
+                                            trc::event!(
+                                                Cluster(ClusterEvent::MessageReceived),
+                                                From = node_id,
+                                                To = this_node_id,
+                                                Details = log_event(&event),
+                                            );
+                                            match event {
+                                                BroadcastEvent::StateChange(state_change) => {
+                                                    max_timestamp =
+                                                        std::cmp::max(max_timestamp, state_change.change_id);
+                                                    if inner
+                                                        .ipc
+                                                        .state_tx
+                                                        .send(StateEvent::Publish {
+                                                            state_change,
+                                                            broadcast: false,
+                                                        })
+                                                        .await
+                                                        .is_err()
+                                                    {
+                                                        trc::event!(
+                                                            Server(ServerEvent::ThreadError),
+                                                            Details = "Error sending state change.",
+                                                            CausedBy = trc::location!()
+                                                        );
+                                                    }
                                                 }
-                                            }
-                                            BroadcastEvent::InvalidateDavCache(ids) => {
-                                                for id in &ids {
-                                                    inner.cache.files.remove(id);
-                                                    inner.cache.contacts.remove(id);
-                                                    inner.cache.events.remove(id);
-                                                    inner.cache.scheduling.remove(id);
+                                                BroadcastEvent::InvalidateAccessTokens(ids) => {
+                                                    for id in &ids {
+                                                        inner.cache.permissions.remove(id);
+                                                        inner.cache.access_tokens.remove(id);
+                                                    }
+                                                    inner
+                                                        .shared_core
+                                                        .load()
+                                                        .storage
+                                                        .clear_unknown_principals();
                                                 }
-                                            }
-                                            BroadcastEvent::ReloadSettings => {
-                                                match inner.build_server().reload().await {
-                                                    Ok(result) => {
-                                                        if let Some(new_core) = result.new_core {
-                                                            // Update core
-                                                            inner.shared_core.store(new_core.into());
-
-                                                            if inner
-                                                                .ipc
-                                                                .housekeeper_tx
-                                                                .send(HousekeeperEvent::ReloadSettings)
-                                                                .await
-                                                                .is_err()
-                                                            {
-                                                                trc::event!(
-                                                                    Server(trc::ServerEvent::ThreadError),
-                                                                    Details = "Failed to send setting reload event to housekeeper",
-                                                                    CausedBy = trc::location!(),
-                                                                );
-                                                            }
-                                                        }
+                                                BroadcastEvent::InvalidateDavCache(ids) => {
+                                                    for id in &ids {
+                                                        inner.cache.files.remove(id);
+                                                        inner.cache.contacts.remove(id);
+                                                        inner.cache.events.remove(id);
+                                                        inner.cache.scheduling.remove(id);
                                                     }
-                                                    Err(err) => {
+                                                }
+                                                BroadcastEvent::ReloadSettings => {
+                                                    reload_settings(&inner, None).await;
+                                                }
+                                                BroadcastEvent::ReloadScopes(scopes) => {
+                                                    reload_settings(&inner, Some(scopes)).await;
+                                                }
+                                                BroadcastEvent::ReloadBlockedIps => {
+                                                    if let Err(err) = inner.build_server().reload_blocked_ips().await {
                                                         trc::error!(
                                                             err.details("Failed to reload settings")
                                                                 .caused_by(trc::location!())
@@ -170,38 +163,31 @@ pub fn spawn_broadcast_subscriber(inner: Arc<Inner>, mut shutdown_rx: watch::Rec
                                                     }
                                                 }
                                             }
-                                            BroadcastEvent::ReloadBlockedIps => {
-                                                if let Err(err) = inner.build_server().reload_blocked_ips().await {
-                                                    trc::error!(
-                                                        err.details("Failed to reload settings")
-                                                            .caused_by(trc::location!())
-                                                    );
-                                                }
-                                            }
                                         }
-                                    }
-                                    Ok(None) => break,
-                                    Err(_) => {
-                                        trc::event!(
-                                            Cluster(ClusterEvent::MessageInvalid),
-                                            Details = message.payload()
-                                        );
-                                        break;
+                                        Ok(None) => break,
+                                        Err(_) => {
+                                            trc::event!(
+                                                Cluster(ClusterEvent::MessageInvalid),
+                                                Details = message.payload()
+                                            );
+                                            break;
+                                        }
                                     }
                                 }
                             }
+                            None => {
+                                trc::event!(
+                                    Cluster(ClusterEvent::SubscriberDisconnected),
+                                );
+                                break;
+                            }
                         }
-                        None => {
-                            trc::event!(
-                                Cluster(ClusterEvent::SubscriberDisconnected),
-                            );
-                        }
+                    },
+                    _ = shutdown_rx.changed() => {
+                        break 'outer;
                     }
-                },
-                _ = shutdown_rx.changed() => {
-                    break;
-                }
-            };
+                };
+            }
         }
 
         trc::event!(Cluster(ClusterEvent::SubscriberStop));
@@ -218,6 +204,11 @@ fn log_event(event: &BroadcastEvent) -> trc::Value {
         ]),
         BroadcastEvent::ReloadSettings => CompactString::const_new("ReloadSettings").into(),
         BroadcastEvent::ReloadBlockedIps => CompactString::const_new("ReloadBlockedIps").into(),
+        BroadcastEvent::ReloadScopes(scopes) => {
+            let mut array = vec!["ReloadScopes".into()];
+            array.extend(scopes.map(|scope| trc::Value::from(scope.as_str())));
+            trc::Value::Array(array)
+        }
         BroadcastEvent::InvalidateAccessTokens(items) => {
             let mut array = Vec::with_capacity(items.len() + 1);
             array.push("InvalidateAccessTokens".into());
@@ -236,3 +227,18 @@ fn log_event(event: &BroadcastEvent) -> trc::Value {
         }
     }
 }
+
+async fn reload_settings(inner: &Arc<Inner>, scopes: Option<Bitmap<ReloadScope>>) {
+    let server = inner.build_server();
+    let result = match server.reload_scoped(scopes).await {
+        Ok(mut result) => server.apply_reload(&mut result).await,
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = result {
+        trc::error!(
+            err.details("Failed to reload settings")
+                .caused_by(trc::location!())
+        );
+    }
+}
@@ -9,19 +9,79 @@ use common::{
     config::telemetry::OtelMetrics,
     core::BuildServer,
     ipc::{BroadcastEvent, HousekeeperEvent, PurgeType},
+    manager::reload::ReloadScope,
 };
 use email::message::delete::EmailDeletion;
+use orphans::OrphanScanning;
 use smtp::reporting::SmtpReporting;
 use std::{
     collections::BinaryHeap,
     future::Future,
-    sync::Arc,
+    sync::{Arc, atomic::Ordering},
     time::{Duration, Instant, SystemTime},
 };
 use store::{PurgeStore, write::now};
 use tokio::sync::mpsc;
 use trc::{Collector, MetricType, PurgeEvent};
+use usage::UsageReporting;
+use utils::map::bitmap::Bitmap;
 
+pub mod orphans;
+pub mod usage;
+
+
+#[cfg(not(target_env = "msvc"))]
+pub fn spawn_reload_handler(inner: Arc<Inner>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut h_hup = match signal(SignalKind::hangup()) {
+        Ok(h_hup) => h_hup,
+        Err(err) => {
+            trc::event!(
+                Server(trc::ServerEvent::ThreadError),
+                Details = "Unable to listen for reload signal",
+                Reason = err.to_string(),
+            );
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        while h_hup.recv().await.is_some() {
+            trc::event!(Housekeeper(trc::HousekeeperEvent::Run), Type = "SIGHUP");
+
+            let server = inner.build_server();
+            match server.reload().await {
+                Ok(mut result) => {
+                    if !result.config.errors.is_empty() {
+                        result.config.log_errors();
+                    } else {
+                        if !result.scopes.is_empty() {
+                            server
+                                .cluster_broadcast(BroadcastEvent::ReloadSettings)
+                                .await;
+                        }
+
+                        if let Err(err) = server.apply_reload(&mut result).await {
+                            trc::error!(err.details("Failed to apply settings reload"));
+                        }
+                    }
+                }
+                Err(err) => {
+                    trc::error!(
+                        err.details("Failed to reload settings")
+                            .caused_by(trc::location!())
+                    );
+                }
+            }
+
+            utils::sd_notify("READY=1");
+        }
+    });
+}
+
+#[cfg(target_env = "msvc")]
+pub fn spawn_reload_handler(_inner: Arc<Inner>) {}
 
 #[derive(PartialEq, Eq)]
 struct Action {
@@ -36,6 +96,9 @@ enum ActionClass {
     Acme(String),
     OtelMetrics,
     CalculateMetrics,
+    CalculateCounts,
+    UsageReport,
+    DegradedRetry,
 }
 
 #[derive(Default)]
@@ -80,8 +143,27 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                 queue.schedule(Instant::now() + otel.interval, ActionClass::OtelMetrics);
             }
 
-            // Calculate expensive metrics
+            // Calculate metrics
             queue.schedule(Instant::now(), ActionClass::CalculateMetrics);
+            queue.schedule(Instant::now(), ActionClass::CalculateCounts);
+
+            // Usage report
+            if server.core.network.roles.calculate_metrics
+                && let Some(usage_report) = &server.core.metrics.usage_report
+            {
+                queue.schedule(
+                    Instant::now() + usage_report.frequency.time_to_next(),
+                    ActionClass::UsageReport,
+                );
+            }
+
+            // Retry unavailable backends
+            if !server.core.storage.degraded.is_empty() {
+                queue.schedule(
+                    Instant::now() + server.core.storage.degraded_retry,
+                    ActionClass::DegradedRetry,
+                );
+            }
 
             // Add all ACME renewals to heap
             if server.core.network.roles.renew_acme {
@@ -105,18 +187,27 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
         }
 
 
-        let mut next_metric_update = Instant::now();
-
         loop {
             match tokio::time::timeout(queue.wake_up_time(), rx.recv()).await {
                 Ok(Some(event)) => {
                     match event {
-                        HousekeeperEvent::ReloadSettings => {
+                        HousekeeperEvent::ReloadSettings(scopes) => {
                             let server = inner.build_server();
 
+                            trc::event!(
+                                Housekeeper(trc::HousekeeperEvent::Run),
+                                Type = "reload",
+                                Details = scopes
+                                    .map(|scope| trc::Value::from(scope.as_str()))
+                                    .collect::<Vec<_>>(),
+                            );
+
                             // Reload OTEL push metrics
                             match &server.core.metrics.otel {
-                                Some(otel) if !queue.has_action(&ActionClass::OtelMetrics) => {
+                                Some(otel)
+                                    if scopes.contains(ReloadScope::Telemetry)
+                                        && !queue.has_action(&ActionClass::OtelMetrics) =>
+                                {
                                     OtelMetrics::enable_errors();
 
                                     queue.schedule(
@@ -127,17 +218,44 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                 _ => {}
                             }
 
+                            // Retry unavailable backends
+                            if !server.core.storage.degraded.is_empty()
+                                && !queue.has_action(&ActionClass::DegradedRetry)
+                            {
+                                queue.schedule(
+                                    Instant::now() + server.core.storage.degraded_retry,
+                                    ActionClass::DegradedRetry,
+                                );
+                            }
+
+                            // Schedule usage report
+                            if scopes.contains(ReloadScope::Telemetry)
+                                && server.core.network.roles.calculate_metrics
+                                && !queue.has_action(&ActionClass::UsageReport)
+                                && let Some(usage_report) = &server.core.metrics.usage_report
+                            {
+                                queue.schedule(
+                                    Instant::now() + usage_report.frequency.time_to_next(),
+                                    ActionClass::UsageReport,
+                                );
+                            }
+
 
                             // Reload queue settings
-                            server
-                                .inner
-                                .ipc
-                                .queue_tx
-                                .send(common::ipc::QueueEvent::ReloadSettings)
-                                .await
-                                .ok();
+                            if scopes.contains(ReloadScope::Queue) {
+                                server
+                                    .inner
+                                    .ipc
+                                    .queue_tx
+                                    .send(common::ipc::QueueEvent::ReloadSettings)
+                                    .await
+                                    .ok();
+                            }
 
                             // Reload ACME certificates
+                            if !scopes.contains(ReloadScope::Acme) {
+                                continue;
+                            }
                             tokio::spawn(async move {
                                 for provider in server.core.acme.providers.values() {
                                     match server.init_acme(provider).await {
@@ -176,6 +294,12 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                 server.purge(purge, 0).await;
                             });
                         }
+                        HousekeeperEvent::UsageReport => {
+                            let server = inner.build_server();
+                            tokio::spawn(async move {
+                                server.generate_usage_report().await;
+                            });
+                        }
                         HousekeeperEvent::Exit => {
                             trc::event!(Housekeeper(trc::HousekeeperEvent::Stop));
 
@@ -226,7 +350,9 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                         };
 
                                         server
-                                            .cluster_broadcast(BroadcastEvent::ReloadSettings)
+                                            .cluster_broadcast(BroadcastEvent::ReloadScopes(
+                                                Bitmap::from(ReloadScope::Acme),
+                                            ))
                                             .await;
 
                                         server
@@ -290,6 +416,17 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                                             prefix: None,
                                                         }
                                                     }
+                                                    PurgeStore::BlobScrub {
+                                                        store,
+                                                        blob_store,
+                                                        sample_rate,
+                                                        replica,
+                                                    } => PurgeType::BlobScrub {
+                                                        store,
+                                                        blob_store,
+                                                        sample_rate,
+                                                        replica,
+                                                    },
                                                 },
                                                 idx as u32,
                                             )
@@ -320,65 +457,39 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                     });
                                 }
                             }
-                            ActionClass::CalculateMetrics => {
+                            ActionClass::DegradedRetry => {
+                                // Stop retrying once the backends have recovered
+                                if server.core.storage.degraded.is_empty() {
+                                    continue;
+                                }
+
                                 trc::event!(
                                     Housekeeper(trc::HousekeeperEvent::Run),
-                                    Type = "metrics_calculate"
+                                    Type = "degraded_retry"
                                 );
 
-                                // Calculate expensive metrics every 5 minutes
                                 queue.schedule(
-                                    Instant::now() + Duration::from_secs(5 * 60),
-                                    ActionClass::CalculateMetrics,
+                                    Instant::now() + server.core.storage.degraded_retry,
+                                    ActionClass::DegradedRetry,
                                 );
 
-                                let update_other_metrics = if Instant::now() >= next_metric_update {
-                                    next_metric_update =
-                                        Instant::now() + Duration::from_secs(86400);
-                                    true
-                                } else {
-                                    false
-                                };
-
                                 let server = server.clone();
                                 tokio::spawn(async move {
-                                    if server.core.network.roles.calculate_metrics {
-
-                                        if update_other_metrics {
-                                            match server.total_accounts().await {
-                                                Ok(total) => {
-                                                    Collector::update_gauge(
-                                                        MetricType::UserCount,
-                                                        total,
-                                                    );
-                                                }
-                                                Err(err) => {
-                                                    trc::error!(
-                                                        err.details(
-                                                            "Failed to obtain account count"
-                                                        )
-                                                    );
-                                                }
-                                            }
+                                    server.retry_degraded().await;
+                                });
+                            }
+                            ActionClass::CalculateMetrics => {
+                                trc::event!(
+                                    Housekeeper(trc::HousekeeperEvent::Run),
+                                    Type = "metrics_calculate"
+                                );
 
-                                            match server.total_domains().await {
-                                                Ok(total) => {
-                                                    Collector::update_gauge(
-                                                        MetricType::DomainCount,
-                                                        total,
-                                                    );
-                                                }
-                                                Err(err) => {
-                                                    trc::error!(
-                                                        err.details(
-                                                            "Failed to obtain domain count"
-                                                        )
-                                                    );
-                                                }
-                                            }
-                                        }
-                                    }
+                                queue.schedule(
+                                    Instant::now() + server.core.metrics.calculate_interval,
+                                    ActionClass::CalculateMetrics,
+                                );
 
+                                tokio::spawn(async move {
                                     match tokio::task::spawn_blocking(memory_stats::memory_stats)
                                         .await
                                     {
@@ -402,6 +513,64 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                     }
                                 });
                             }
+                            ActionClass::UsageReport => {
+                                if let Some(usage_report) = &server.core.metrics.usage_report {
+                                    queue.schedule(
+                                        Instant::now() + usage_report.frequency.time_to_next(),
+                                        ActionClass::UsageReport,
+                                    );
+
+                                    let server = server.clone();
+                                    tokio::spawn(async move {
+                                        server.generate_usage_report().await;
+                                    });
+                                }
+                            }
+                            ActionClass::CalculateCounts => {
+                                trc::event!(
+                                    Housekeeper(trc::HousekeeperEvent::Run),
+                                    Type = "metrics_count"
+                                );
+
+                                queue.schedule(
+                                    Instant::now() + server.core.metrics.count_interval,
+                                    ActionClass::CalculateCounts,
+                                );
+
+                                let server = server.clone();
+                                tokio::spawn(async move {
+                                    if server.core.network.roles.calculate_metrics {
+
+                                        match server.total_accounts().await {
+                                            Ok(total) => {
+                                                Collector::update_gauge(
+                                                    MetricType::UserCount,
+                                                    total,
+                                                );
+                                            }
+                                            Err(err) => {
+                                                trc::error!(
+                                                    err.details("Failed to obtain account count")
+                                                );
+                                            }
+                                        }
+
+                                        match server.total_domains().await {
+                                            Ok(total) => {
+                                                Collector::update_gauge(
+                                                    MetricType::DomainCount,
+                                                    total,
+                                                );
+                                            }
+                                            Err(err) => {
+                                                trc::error!(
+                                                    err.details("Failed to obtain domain count")
+                                                );
+                                            }
+                                        }
+                                    }
+                                });
+                            }
 
                         }
                     }
@@ -444,7 +613,16 @@ impl Purge for Server {
                     .into(),
             ),
             PurgeType::Lookup { .. } => ("in-memory-prefix", None),
+            PurgeType::BlobScrub { .. } => (
+                "blob-scrub",
+                [3u8]
+                    .into_iter()
+                    .chain(store_idx.to_be_bytes().into_iter())
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
             PurgeType::Account(_) => ("account", None),
+            PurgeType::Orphans { .. } => ("orphans", vec![4u8].into()),
         };
         if let Some(lock_name) = &lock_name {
             match self
@@ -501,6 +679,32 @@ impl Purge for Server {
                     self.purge_accounts().await;
                 }
             }
+            PurgeType::BlobScrub {
+                store,
+                blob_store,
+                sample_rate,
+                replica,
+            } => {
+                let status = &self.inner.data.blob_scrub;
+                status.running.store(true, Ordering::Relaxed);
+                match store
+                    .scrub_blobs(&blob_store, sample_rate, replica.as_ref(), status)
+                    .await
+                {
+                    Ok(report) => {
+                        *status.last_report.lock() = Some(report);
+                    }
+                    Err(err) => {
+                        trc::error!(err.details("Failed to verify blob store"));
+                    }
+                }
+                status.running.store(false, Ordering::Relaxed);
+            }
+            PurgeType::Orphans { repair } => {
+                if let Err(err) = self.build_orphan_report(repair).await {
+                    trc::error!(err.details("Failed to scan for orphaned data"));
+                }
+            }
         }
 
         trc::event!(
@@ -525,6 +729,41 @@ impl Purge for Server {
     }
 }
 
+pub trait RetryDegraded: Sync + Send {
+    fn retry_degraded(&self) -> impl Future<Output = ()> + Send;
+}
+
+impl RetryDegraded for Server {
+    async fn retry_degraded(&self) {
+        // Rebuild the configuration, which attempts to open the unavailable backends again
+        let result = match self.reload_scoped(Some(Bitmap::all())).await {
+            Ok(result) => result,
+            Err(err) => {
+                trc::error!(err.details("Failed to reload configuration"));
+                return;
+            }
+        };
+
+        if let Some(core) = result.new_core
+            && core.storage.degraded.is_empty()
+        {
+            self.inner.shared_core.store(core.into());
+
+            trc::event!(
+                Server(trc::ServerEvent::Recovered),
+                Details = self.core.storage.degraded.clone(),
+            );
+
+            self.inner
+                .ipc
+                .housekeeper_tx
+                .send(HousekeeperEvent::ReloadSettings(result.scopes))
+                .await
+                .ok();
+        }
+    }
+}
+
 impl Queue {
     pub fn schedule(&mut self, due: Instant, event: ActionClass) {
         trc::event!(
@@ -0,0 +1,308 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{KV_ORPHAN_REPORT, Server};
+use directory::{
+    Type,
+    backend::internal::{
+        PrincipalField,
+        manage::{ChangedPrincipals, ManageDirectory},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, time::Instant};
+use store::{
+    ValueKey,
+    dispatch::lookup::KeyValue,
+    roaring::RoaringBitmap,
+    write::{BatchBuilder, ValueClass, now},
+};
+use trc::AddContext;
+use types::collection::Collection;
+
+const MAX_REPORT_ITEMS: usize = 100;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanReport {
+    pub generated_at: u64,
+    pub repaired: bool,
+    pub blobs: OrphanList<String>,
+    pub accounts: OrphanList<u32>,
+    pub acls: OrphanList<OrphanAcl>,
+    pub fts: OrphanList<OrphanFtsEntries>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanList<T> {
+    pub total: u64,
+    pub items: Vec<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanAcl {
+    pub account_id: u32,
+    pub collection: String,
+    pub document_id: u32,
+    pub grant_account_id: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanFtsEntries {
+    pub account_id: u32,
+    pub documents: u64,
+}
+
+pub trait OrphanScanning: Sync + Send {
+    fn build_orphan_report(
+        &self,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<OrphanReport>> + Send;
+
+    fn orphan_report(&self) -> impl Future<Output = trc::Result<Option<OrphanReport>>> + Send;
+}
+
+impl OrphanScanning for Server {
+    async fn build_orphan_report(&self, repair: bool) -> trc::Result<OrphanReport> {
+        let time = Instant::now();
+        let mut report = OrphanReport {
+            generated_at: now(),
+            repaired: repair,
+            ..Default::default()
+        };
+
+        // Accounts created while scanning are above the counter read before the snapshot,
+        // which keeps them out of the orphan candidates
+        let last_principal_id = self
+            .store()
+            .get_counter(ValueKey {
+                account_id: u32::MAX,
+                collection: Collection::Principal.into(),
+                document_id: 0,
+                class: ValueClass::DocumentId,
+            })
+            .await
+            .caused_by(trc::location!())?
+            .max(0) as u32;
+
+        // Obtain existing principals
+        let principal_ids = self
+            .get_document_ids(u32::MAX, Collection::Principal)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default();
+
+        // Find blobs without any references
+        for hash in self
+            .store()
+            .unreferenced_blobs()
+            .await
+            .caused_by(trc::location!())?
+        {
+            report.blobs.push(hash.to_hex());
+        }
+
+        // Find data belonging to deleted accounts
+        let mut orphan_account_ids = RoaringBitmap::new();
+        for account_id in 0..=last_principal_id {
+            if principal_ids.contains(account_id) {
+                continue;
+            }
+            for collection in [
+                Collection::Mailbox,
+                Collection::Email,
+                Collection::Calendar,
+                Collection::AddressBook,
+                Collection::FileNode,
+                Collection::SieveScript,
+            ] {
+                if self
+                    .get_document_ids(account_id, collection)
+                    .await
+                    .caused_by(trc::location!())?
+                    .is_some_and(|ids| !ids.is_empty())
+                {
+                    orphan_account_ids.insert(account_id);
+                    report.accounts.push(account_id);
+                    break;
+                }
+            }
+        }
+
+        // Find ACL entries referencing deleted principals
+        let mut orphan_acls = Vec::new();
+        for (grant_account_id, acl) in self
+            .store()
+            .acl_list_all()
+            .await
+            .caused_by(trc::location!())?
+        {
+            if !principal_ids.contains(grant_account_id)
+                || !principal_ids.contains(acl.to_account_id)
+            {
+                report.acls.push(OrphanAcl {
+                    account_id: acl.to_account_id,
+                    collection: acl.to_collection.as_str().to_string(),
+                    document_id: acl.to_document_id,
+                    grant_account_id,
+                });
+                orphan_acls.push((grant_account_id, acl));
+            }
+        }
+
+        // Find full-text index entries of purged messages
+        let mut orphan_fts = Vec::new();
+        for account_id in &principal_ids {
+            let Some(indexed_ids) = self
+                .fts_store()
+                .indexed_document_ids(account_id, Collection::Email)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                break;
+            };
+            if indexed_ids.is_empty() {
+                continue;
+            }
+            let document_ids = self
+                .get_document_ids(account_id, Collection::Email)
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default();
+            let purged_ids = indexed_ids - document_ids;
+            if !purged_ids.is_empty() {
+                report.fts.push(OrphanFtsEntries {
+                    account_id,
+                    documents: purged_ids.len(),
+                });
+                orphan_fts.push((account_id, purged_ids));
+            }
+        }
+
+        if repair {
+            // Revoke ACLs
+            let mut changed_principals = ChangedPrincipals::default();
+            let mut batch = BatchBuilder::new();
+            for (grant_account_id, acl) in orphan_acls {
+                if batch.is_large_batch() {
+                    self.store()
+                        .write(batch.build_all())
+                        .await
+                        .caused_by(trc::location!())?;
+                    batch = BatchBuilder::new();
+                }
+                batch
+                    .with_account_id(acl.to_account_id)
+                    .with_collection(acl.to_collection)
+                    .update_document(acl.to_document_id)
+                    .acl_revoke(grant_account_id);
+                if principal_ids.contains(grant_account_id) {
+                    changed_principals.add_change(
+                        grant_account_id,
+                        Type::Individual,
+                        PrincipalField::EnabledPermissions,
+                    );
+                }
+            }
+            if !batch.is_empty() {
+                self.store()
+                    .write(batch.build_all())
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+            self.invalidate_principal_caches(changed_principals).await;
+
+            // Destroy data of deleted accounts
+            for account_id in &orphan_account_ids {
+                if self
+                    .store()
+                    .get_principal(account_id)
+                    .await
+                    .caused_by(trc::location!())?
+                    .is_some()
+                {
+                    continue;
+                }
+                self.store()
+                    .blob_hash_unlink_account(account_id)
+                    .await
+                    .caused_by(trc::location!())?;
+                self.store()
+                    .danger_destroy_account(account_id)
+                    .await
+                    .caused_by(trc::location!())?;
+                self.fts_store()
+                    .remove_all(account_id)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+
+            // Remove stale full-text index entries
+            for (account_id, document_ids) in orphan_fts {
+                self.fts_store()
+                    .remove(account_id, Collection::Email, &document_ids)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+
+            // Delete unreferenced blobs, including the ones unlinked above
+            self.store()
+                .purge_blobs(self.blob_store().clone())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        trc::event!(
+            Housekeeper(trc::HousekeeperEvent::Run),
+            Type = "orphans",
+            Total =
+                report.blobs.total + report.accounts.total + report.acls.total + report.fts.total,
+            Result = repair,
+            Elapsed = time.elapsed()
+        );
+
+        // Cache report
+        self.in_memory_store()
+            .key_set(KeyValue::new(
+                vec![KV_ORPHAN_REPORT],
+                serde_json::to_vec(&report).unwrap_or_default(),
+            ))
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(report)
+    }
+
+    async fn orphan_report(&self) -> trc::Result<Option<OrphanReport>> {
+        Ok(self
+            .in_memory_store()
+            .key_get::<String>(vec![KV_ORPHAN_REPORT])
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|report| serde_json::from_str(&report).ok()))
+    }
+}
+
+impl<T> OrphanList<T> {
+    fn push(&mut self, item: T) {
+        self.total += 1;
+        if self.items.len() < MAX_REPORT_ITEMS {
+            self.items.push(item);
+        }
+    }
+}
+
+impl<T> Default for OrphanList<T> {
+    fn default() -> Self {
+        OrphanList {
+            total: 0,
+            items: Vec::new(),
+        }
+    }
+}
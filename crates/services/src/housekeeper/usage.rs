@@ -0,0 +1,411 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{KV_LOCK_HOUSEKEEPER, KV_USAGE_REPORT, Server, config::telemetry::UsageReportConfig};
+use directory::{
+    QueryParams, Type,
+    backend::internal::{lookup::DirectoryStore, manage::ManageDirectory},
+};
+use mail_builder::{MessageBuilder, headers::HeaderType};
+use serde::{Deserialize, Serialize};
+use smtp::{
+    queue::{ArchivedStatus, Message},
+    reporting::SmtpReporting,
+};
+use std::{collections::BTreeMap, fmt::Write, future::Future, time::Instant};
+use store::{
+    IterateParams, ValueKey,
+    dispatch::lookup::KeyValue,
+    write::{AlignedBytes, Archive, QueueClass, ValueClass, key::DeserializeBigEndian, now},
+};
+use trc::AddContext;
+
+const HISTORY_DAYS: u64 = 30;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageReport {
+    #[serde(rename = "generatedAt")]
+    pub generated_at: u64,
+    pub totals: UsageSnapshot,
+    pub growth: UsageGrowth,
+    pub domains: Vec<DomainUsage>,
+    #[serde(rename = "topAccounts")]
+    pub top_accounts: Vec<AccountUsage>,
+    pub history: Vec<UsageSnapshot>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageSnapshot {
+    pub timestamp: u64,
+    pub accounts: u64,
+    pub storage: i64,
+    pub queued: u64,
+    pub deferred: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub domains: BTreeMap<String, DomainSnapshot>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DomainSnapshot {
+    pub accounts: u64,
+    pub storage: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageGrowth {
+    pub since: Option<u64>,
+    pub accounts: i64,
+    pub storage: i64,
+    pub queued: i64,
+    pub deferred: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DomainUsage {
+    pub name: String,
+    pub accounts: u64,
+    pub storage: i64,
+    #[serde(rename = "accountsGrowth")]
+    pub accounts_growth: i64,
+    #[serde(rename = "storageGrowth")]
+    pub storage_growth: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountUsage {
+    pub name: String,
+    pub storage: i64,
+    pub quota: u64,
+}
+
+pub trait UsageReporting: Sync + Send {
+    fn generate_usage_report(&self) -> impl Future<Output = ()> + Send;
+
+    fn build_usage_report(
+        &self,
+        config: &UsageReportConfig,
+    ) -> impl Future<Output = trc::Result<UsageReport>> + Send;
+
+    fn usage_report(&self) -> impl Future<Output = trc::Result<Option<UsageReport>>> + Send;
+}
+
+impl UsageReporting for Server {
+    async fn generate_usage_report(&self) {
+        let Some(config) = self.core.metrics.usage_report.as_ref() else {
+            return;
+        };
+
+        // Lock task
+        match self
+            .in_memory_store()
+            .try_lock(KV_LOCK_HOUSEKEEPER, b"usage-report", 3600)
+            .await
+        {
+            Ok(true) => (),
+            Ok(false) => {
+                trc::event!(Purge(trc::PurgeEvent::InProgress), Details = "usage-report");
+                return;
+            }
+            Err(err) => {
+                trc::error!(err.details("Failed to lock task.").details("usage-report"));
+                return;
+            }
+        }
+
+        let time = Instant::now();
+        match self.build_usage_report(config).await {
+            Ok(report) => {
+                trc::event!(
+                    Housekeeper(trc::HousekeeperEvent::Run),
+                    Type = "usage_report",
+                    Total = report.totals.accounts,
+                    Elapsed = time.elapsed()
+                );
+
+                if !config.rcpt_to.is_empty() {
+                    let from_address = config.from_address.clone().unwrap_or_else(|| {
+                        format!("noreply-usage@{}", self.core.network.report_domain)
+                    });
+                    let message = MessageBuilder::new()
+                        .from(from_address.as_str())
+                        .to(config
+                            .rcpt_to
+                            .iter()
+                            .map(|rcpt| rcpt.as_str())
+                            .collect::<Vec<_>>())
+                        .header(
+                            "Auto-Submitted",
+                            HeaderType::Text("auto-generated".into()),
+                        )
+                        .subject(format!(
+                            "Usage report for {}",
+                            self.core.network.server_name
+                        ))
+                        .text_body(report.to_text())
+                        .write_to_vec()
+                        .unwrap_or_default();
+
+                    self.send_autogenerated(
+                        from_address.as_str(),
+                        config.rcpt_to.iter(),
+                        message,
+                        None,
+                        0,
+                    )
+                    .await;
+                }
+            }
+            Err(err) => {
+                trc::error!(err.details("Failed to generate usage report"));
+            }
+        }
+
+        // Remove lock
+        if let Err(err) = self
+            .in_memory_store()
+            .remove_lock(KV_LOCK_HOUSEKEEPER, b"usage-report")
+            .await
+        {
+            trc::error!(
+                err.details("Failed to delete task lock.")
+                    .details("usage-report")
+            );
+        }
+    }
+
+    async fn build_usage_report(&self, config: &UsageReportConfig) -> trc::Result<UsageReport> {
+        let timestamp = now();
+        let mut snapshot = UsageSnapshot {
+            timestamp,
+            ..Default::default()
+        };
+        let mut accounts = Vec::new();
+
+        // Obtain per-account usage
+        let principals = self
+            .store()
+            .list_principals(None, None, &[Type::Individual], false, 0, 0)
+            .await
+            .caused_by(trc::location!())?;
+        for principal in principals.items {
+            let account_id = principal.id();
+            let used_quota = self
+                .get_used_quota(account_id)
+                .await
+                .caused_by(trc::location!())?;
+            let Some(principal) = self
+                .store()
+                .query(QueryParams::id(account_id).with_return_member_of(false))
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+
+            snapshot.accounts += 1;
+            snapshot.storage += used_quota;
+            if let Some(domain) = principal
+                .emails
+                .first()
+                .and_then(|email| email.rsplit_once('@'))
+                .map(|(_, domain)| domain.to_lowercase())
+            {
+                let domain = snapshot.domains.entry(domain).or_default();
+                domain.accounts += 1;
+                domain.storage += used_quota;
+            }
+            accounts.push(AccountUsage {
+                name: principal.name().to_string(),
+                storage: used_quota,
+                quota: principal.quota(),
+            });
+        }
+
+        // Obtain queue usage
+        self.store()
+            .iterate(
+                IterateParams::new(
+                    ValueKey::from(ValueClass::Queue(QueueClass::Message(0))),
+                    ValueKey::from(ValueClass::Queue(QueueClass::Message(u64::MAX))),
+                )
+                .ascending(),
+                |_, value| {
+                    let message_ =
+                        <Archive<AlignedBytes> as store::Deserialize>::deserialize(value)
+                            .caused_by(trc::location!())?;
+                    let message = message_
+                        .unarchive::<Message>()
+                        .caused_by(trc::location!())?;
+
+                    snapshot.queued += 1;
+                    if message
+                        .recipients
+                        .iter()
+                        .any(|rcpt| matches!(rcpt.status, ArchivedStatus::TemporaryFailure(_)))
+                    {
+                        snapshot.deferred += 1;
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        // Store daily snapshot and obtain history
+        let mut snapshot_key = Vec::with_capacity(10);
+        snapshot_key.extend_from_slice(&[KV_USAGE_REPORT, 1]);
+        snapshot_key.extend_from_slice(&(timestamp / 86400).to_be_bytes());
+        self.in_memory_store()
+            .key_set(
+                KeyValue::new(
+                    snapshot_key,
+                    serde_json::to_vec(&snapshot).unwrap_or_default(),
+                )
+                .expires((HISTORY_DAYS + 1) * 86400),
+            )
+            .await
+            .caused_by(trc::location!())?;
+        let mut history = self
+            .in_memory_store()
+            .key_get_prefix(&[KV_USAGE_REPORT, 1])
+            .await
+            .caused_by(trc::location!())?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                Some((
+                    key.as_slice().deserialize_be_u64(2).ok()?,
+                    serde_json::from_slice::<UsageSnapshot>(&value).ok()?,
+                ))
+            })
+            .filter(|(day, _)| *day + HISTORY_DAYS >= timestamp / 86400)
+            .map(|(_, snapshot)| snapshot)
+            .collect::<Vec<_>>();
+        history.sort_unstable_by_key(|snapshot| snapshot.timestamp);
+
+        // Calculate growth
+        let mut growth = UsageGrowth::default();
+        let mut domains = snapshot
+            .domains
+            .iter()
+            .map(|(name, usage)| DomainUsage {
+                name: name.clone(),
+                accounts: usage.accounts,
+                storage: usage.storage,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        if let Some(oldest) = history.first().filter(|s| s.timestamp < timestamp) {
+            growth = UsageGrowth {
+                since: Some(oldest.timestamp),
+                accounts: snapshot.accounts as i64 - oldest.accounts as i64,
+                storage: snapshot.storage - oldest.storage,
+                queued: snapshot.queued as i64 - oldest.queued as i64,
+                deferred: snapshot.deferred as i64 - oldest.deferred as i64,
+            };
+            for domain in &mut domains {
+                let previous = oldest.domains.get(&domain.name);
+                domain.accounts_growth =
+                    domain.accounts as i64 - previous.map_or(0, |d| d.accounts as i64);
+                domain.storage_growth = domain.storage - previous.map_or(0, |d| d.storage);
+            }
+        }
+        domains.sort_unstable_by(|a, b| b.storage.cmp(&a.storage));
+
+        // Obtain top accounts
+        accounts.sort_unstable_by(|a, b| b.storage.cmp(&a.storage));
+        accounts.truncate(config.top_accounts);
+
+        let report = UsageReport {
+            generated_at: timestamp,
+            totals: UsageSnapshot {
+                domains: BTreeMap::new(),
+                ..snapshot
+            },
+            growth,
+            domains,
+            top_accounts: accounts,
+            history: history
+                .into_iter()
+                .map(|snapshot| UsageSnapshot {
+                    domains: BTreeMap::new(),
+                    ..snapshot
+                })
+                .collect(),
+        };
+
+        // Cache report
+        self.in_memory_store()
+            .key_set(KeyValue::new(
+                vec![KV_USAGE_REPORT, 0],
+                serde_json::to_vec(&report).unwrap_or_default(),
+            ))
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(report)
+    }
+
+    async fn usage_report(&self) -> trc::Result<Option<UsageReport>> {
+        Ok(self
+            .in_memory_store()
+            .key_get::<String>(vec![KV_USAGE_REPORT, 0])
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|report| serde_json::from_str(&report).ok()))
+    }
+}
+
+impl UsageReport {
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "Accounts: {} ({:+} in the last {} days)",
+            self.totals.accounts, self.growth.accounts, HISTORY_DAYS
+        );
+        let _ = writeln!(
+            text,
+            "Storage: {} bytes ({:+} in the last {} days)",
+            self.totals.storage, self.growth.storage, HISTORY_DAYS
+        );
+        let _ = writeln!(
+            text,
+            "Queue: {} messages, {} deferred",
+            self.totals.queued, self.totals.deferred
+        );
+
+        if !self.domains.is_empty() {
+            let _ = writeln!(text, "\nDomains:");
+            for domain in &self.domains {
+                let _ = writeln!(
+                    text,
+                    "  {}: {} accounts, {} bytes ({:+} bytes)",
+                    domain.name, domain.accounts, domain.storage, domain.storage_growth
+                );
+            }
+        }
+
+        if !self.top_accounts.is_empty() {
+            let _ = writeln!(text, "\nLargest accounts:");
+            for account in &self.top_accounts {
+                let _ = writeln!(
+                    text,
+                    "  {}: {} bytes{}",
+                    account.name,
+                    account.storage,
+                    if account.quota > 0 {
+                        format!(" of {} bytes", account.quota)
+                    } else {
+                        String::new()
+                    }
+                );
+            }
+        }
+
+        text
+    }
+}
@@ -0,0 +1,631 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::task_manager::fts::FtsIndexTask;
+use common::{Inner, KV_BAYES_MODEL_USER, Server, core::BuildServer};
+use directory::{QueryBy, Type, backend::internal::manage::ManageDirectory};
+use email::message::keywords::KeywordCleanup;
+use serde::{Deserialize, Serialize};
+use std::{future::Future, sync::Arc, time::Duration};
+use store::{
+    IterateParams, SerializeInfallible, U64_LEN, ValueKey,
+    ahash::AHashMap,
+    roaring::RoaringBitmap,
+    write::{
+        BatchBuilder, TaskQueueClass, ValueClass, assert::AssertValue, key::DeserializeBigEndian,
+        now,
+    },
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+use trc::{AddContext, TaskQueueEvent};
+
+const JOB_LEASE: u64 = 60 * 5; // 5 minutes
+const JOB_LEASE_RENEW_INTERVAL: u64 = 60; // 1 minute
+const JOB_RETENTION: u64 = 60 * 60 * 24 * 7; // 7 days
+const JOB_REFRESH_INTERVAL: u64 = 60; // 1 minute
+const JOB_RETRY_INTERVAL: u64 = 60; // 1 minute
+const JOB_MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: u64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub tenant_id: Option<u32>,
+    pub created: u64,
+    pub updated: u64,
+    pub due: u64,
+    pub attempts: u32,
+    pub items: Vec<u32>,
+    pub done: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    FtsReindex,
+    DeletePrincipals { principal_type: Type },
+    KeywordCleanup,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+pub fn spawn_job_manager(inner: Arc<Inner>) {
+    tokio::spawn(async move {
+        let rx = inner.ipc.job_tx.clone();
+        let mut running: AHashMap<u64, JoinHandle<()>> = AHashMap::new();
+
+        loop {
+            running.retain(|_, handle| !handle.is_finished());
+
+            let server = inner.build_server();
+            match server.list_jobs().await {
+                Ok(jobs) => {
+                    let now = now();
+                    for job in jobs {
+                        if job.is_finished() && job.updated + JOB_RETENTION <= now {
+                            server.delete_job(job.id).await;
+                        } else if !job.is_finished()
+                            && job.due <= now
+                            && !running.contains_key(&job.id)
+                            && let Some(lease) = server.try_lock_job(job.id).await
+                        {
+                            let server = server.clone();
+                            running.insert(job.id, tokio::spawn(server.run_job(job, lease)));
+                        }
+                    }
+                }
+                Err(err) => {
+                    trc::error!(err.details("Failed to list background jobs"));
+                }
+            }
+
+            // Wait for a new job or until the next refresh
+            let _ = tokio::time::timeout(Duration::from_secs(JOB_REFRESH_INTERVAL), rx.notified())
+                .await;
+        }
+    });
+}
+
+pub trait JobManager: Sync + Send {
+    fn submit_job(
+        &self,
+        kind: JobKind,
+        items: Vec<u32>,
+        tenant_id: Option<u32>,
+    ) -> impl Future<Output = trc::Result<u64>> + Send;
+    fn list_jobs(&self) -> impl Future<Output = trc::Result<Vec<Job>>> + Send;
+    fn get_job(&self, id: u64) -> impl Future<Output = trc::Result<Option<Job>>> + Send;
+    fn cancel_job(&self, id: u64) -> impl Future<Output = trc::Result<bool>> + Send;
+}
+
+trait JobManagerInternal: Sync + Send {
+    fn save_job(&self, job: &Job) -> impl Future<Output = trc::Result<()>> + Send;
+    fn save_locked_job(
+        &self,
+        job: &Job,
+        lease: u64,
+    ) -> impl Future<Output = trc::Result<Option<u64>>> + Send;
+    fn delete_job(&self, id: u64) -> impl Future<Output = ()> + Send;
+    fn try_lock_job(&self, id: u64) -> impl Future<Output = Option<u64>> + Send;
+    fn renew_job_lock(&self, id: u64, lease: u64) -> impl Future<Output = Option<u64>> + Send;
+    fn remove_job_lock(&self, id: u64, lease: u64) -> impl Future<Output = ()> + Send;
+    fn is_job_active(&self, id: u64) -> impl Future<Output = bool> + Send;
+    fn run_job(self, job: Job, lease: u64) -> impl Future<Output = ()> + Send;
+    fn run_job_step(
+        &self,
+        kind: JobKind,
+        items: &[u32],
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl JobManager for Server {
+    async fn submit_job(
+        &self,
+        kind: JobKind,
+        items: Vec<u32>,
+        tenant_id: Option<u32>,
+    ) -> trc::Result<u64> {
+        let now = now();
+        let job = Job {
+            id: self.inner.data.queue_id_gen.generate(),
+            kind,
+            status: JobStatus::Pending,
+            tenant_id,
+            created: now,
+            updated: now,
+            due: now,
+            attempts: 0,
+            items,
+            done: 0,
+            error: None,
+        };
+        self.save_job(&job).await?;
+        self.notify_job_manager();
+
+        Ok(job.id)
+    }
+
+    async fn list_jobs(&self) -> trc::Result<Vec<Job>> {
+        let mut jobs = Vec::new();
+        self.store()
+            .iterate(
+                IterateParams::new(
+                    ValueKey::from(ValueClass::TaskQueue(TaskQueueClass::Job { id: 0 })),
+                    ValueKey::from(ValueClass::TaskQueue(TaskQueueClass::Job { id: u64::MAX })),
+                ),
+                |key, value| {
+                    match serde_json::from_slice::<Job>(value) {
+                        Ok(job) => jobs.push(job),
+                        Err(err) => {
+                            trc::error!(
+                                trc::StoreEvent::DataCorruption
+                                    .into_err()
+                                    .id(key.deserialize_be_u64(key.len().saturating_sub(U64_LEN))?)
+                                    .reason(err)
+                                    .details("Failed to deserialize background job")
+                            );
+                        }
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(jobs)
+    }
+
+    async fn get_job(&self, id: u64) -> trc::Result<Option<Job>> {
+        self.store()
+            .get_value::<Job>(ValueKey::from(ValueClass::TaskQueue(TaskQueueClass::Job {
+                id,
+            })))
+            .await
+            .caused_by(trc::location!())
+    }
+
+    async fn cancel_job(&self, id: u64) -> trc::Result<bool> {
+        // Running jobs replace their lease whenever progress is saved, so the
+        // cancellation is retried until it is not racing with a progress update
+        let class = ValueClass::TaskQueue(TaskQueueClass::JobLock { id });
+        for _ in 0..JOB_MAX_ATTEMPTS {
+            let lease = self
+                .store()
+                .get_value::<u64>(ValueKey::from(class.clone()))
+                .await
+                .caused_by(trc::location!())?;
+            let Some(mut job) = self.get_job(id).await?.filter(|job| !job.is_finished()) else {
+                return Ok(false);
+            };
+
+            job.status = JobStatus::Cancelled;
+            job.updated = now();
+            let value = job.to_json()?;
+
+            // Releasing the lock also stops the lease renewal of a running step
+            let mut batch = BatchBuilder::new();
+            batch
+                .assert_value(
+                    class.clone(),
+                    lease.map_or(AssertValue::None, AssertValue::U64),
+                )
+                .set(ValueClass::TaskQueue(TaskQueueClass::Job { id }), value)
+                .clear(class.clone());
+            match self.store().write(batch.build_all()).await {
+                Ok(_) => {
+                    trc::event!(
+                        TaskQueue(TaskQueueEvent::JobCancelled),
+                        Id = job.id,
+                        Type = job.kind.as_str(),
+                    );
+
+                    return Ok(true);
+                }
+                Err(err) if err.is_assertion_failure() => {}
+                Err(err) => return Err(err.caused_by(trc::location!())),
+            }
+        }
+
+        Err(trc::StoreEvent::AssertValueFailed
+            .into_err()
+            .id(id)
+            .details("Failed to cancel background job")
+            .caused_by(trc::location!()))
+    }
+}
+
+impl JobManagerInternal for Server {
+    async fn save_job(&self, job: &Job) -> trc::Result<()> {
+        let value = job.to_json()?;
+        let mut batch = BatchBuilder::new();
+        batch.set(
+            ValueClass::TaskQueue(TaskQueueClass::Job { id: job.id }),
+            value,
+        );
+
+        self.store()
+            .write(batch.build_all())
+            .await
+            .map(|_| ())
+            .caused_by(trc::location!())
+    }
+
+    async fn save_locked_job(&self, job: &Job, lease: u64) -> trc::Result<Option<u64>> {
+        let value = job.to_json()?;
+        let class = ValueClass::TaskQueue(TaskQueueClass::JobLock { id: job.id });
+        let mut batch = BatchBuilder::new();
+        batch.assert_value(class.clone(), lease).set(
+            ValueClass::TaskQueue(TaskQueueClass::Job { id: job.id }),
+            value,
+        );
+
+        // Unfinished jobs keep the lock, otherwise it is released in the same write
+        let expires = next_job_lease(lease);
+        if job.status == JobStatus::Running {
+            batch.set(class, SerializeInfallible::serialize(&expires));
+        } else {
+            batch.clear(class);
+        }
+
+        match self.store().write(batch.build_all()).await {
+            Ok(_) => Ok(Some(expires)),
+            Err(err) if err.is_assertion_failure() => {
+                trc::event!(TaskQueue(TaskQueueEvent::TaskLocked), Id = job.id);
+                Ok(None)
+            }
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
+
+    async fn delete_job(&self, id: u64) {
+        let mut batch = BatchBuilder::new();
+        batch.clear(ValueClass::TaskQueue(TaskQueueClass::Job { id }));
+
+        if let Err(err) = self.store().write(batch.build_all()).await {
+            trc::error!(err.id(id).details("Failed to delete background job"));
+        }
+    }
+
+    async fn try_lock_job(&self, id: u64) -> Option<u64> {
+        let class = ValueClass::TaskQueue(TaskQueueClass::JobLock { id });
+        let lease = match self
+            .store()
+            .get_value::<u64>(ValueKey::from(class.clone()))
+            .await
+        {
+            Ok(lease) => lease,
+            Err(err) => {
+                trc::error!(err.id(id).details("Failed to lock background job"));
+                return None;
+            }
+        };
+        if lease.is_some_and(|lease| lease > now()) {
+            trc::event!(TaskQueue(TaskQueueEvent::TaskLocked), Id = id);
+            return None;
+        }
+
+        let expires = now() + JOB_LEASE;
+        let mut batch = BatchBuilder::new();
+        batch
+            .assert_value(
+                class.clone(),
+                lease.map_or(AssertValue::None, AssertValue::U64),
+            )
+            .set(class, SerializeInfallible::serialize(&expires));
+        match self.store().write(batch.build_all()).await {
+            Ok(_) => Some(expires),
+            Err(err) if err.is_assertion_failure() => {
+                trc::event!(TaskQueue(TaskQueueEvent::TaskLocked), Id = id);
+                None
+            }
+            Err(err) => {
+                trc::error!(err.id(id).details("Failed to lock background job"));
+                None
+            }
+        }
+    }
+
+    async fn renew_job_lock(&self, id: u64, lease: u64) -> Option<u64> {
+        // The lease is only extended if it has not been taken over since it was acquired
+        let class = ValueClass::TaskQueue(TaskQueueClass::JobLock { id });
+        let expires = next_job_lease(lease);
+        let mut batch = BatchBuilder::new();
+        batch
+            .assert_value(class.clone(), lease)
+            .set(class, SerializeInfallible::serialize(&expires));
+        match self.store().write(batch.build_all()).await {
+            Ok(_) => Some(expires),
+            Err(err) if err.is_assertion_failure() => {
+                trc::event!(TaskQueue(TaskQueueEvent::TaskLocked), Id = id);
+                None
+            }
+            Err(err) => {
+                trc::error!(err.id(id).details("Failed to renew background job lock"));
+                None
+            }
+        }
+    }
+
+    async fn remove_job_lock(&self, id: u64, lease: u64) {
+        let class = ValueClass::TaskQueue(TaskQueueClass::JobLock { id });
+        let mut batch = BatchBuilder::new();
+        batch.assert_value(class.clone(), lease).clear(class);
+        match self.store().write(batch.build_all()).await {
+            Ok(_) => {}
+            Err(err) if err.is_assertion_failure() => {}
+            Err(err) => {
+                trc::error!(err.id(id).details("Failed to unlock background job"));
+            }
+        }
+    }
+
+    async fn is_job_active(&self, id: u64) -> bool {
+        match self.get_job(id).await {
+            Ok(job) => job.is_some_and(|job| !job.is_finished()),
+            Err(err) => {
+                trc::error!(err.id(id).details("Failed to fetch background job"));
+                false
+            }
+        }
+    }
+
+    async fn run_job(self, mut job: Job, mut lease: u64) {
+        let job_id = job.id;
+        let chunk_size = job.kind.chunk_size();
+
+        trc::event!(
+            TaskQueue(TaskQueueEvent::JobStarted),
+            Id = job_id,
+            Type = job.kind.as_str(),
+            Total = job.items.len(),
+        );
+
+        loop {
+            if !self.is_job_active(job_id).await {
+                break;
+            }
+
+            let chunk = job
+                .items
+                .get(job.done..)
+                .unwrap_or_default()
+                .iter()
+                .take(chunk_size)
+                .copied()
+                .collect::<Vec<_>>();
+            let result = if !chunk.is_empty() {
+                job.status = JobStatus::Running;
+
+                // Keep the lease alive while the step is running
+                let (stop_tx, stop_rx) = oneshot::channel();
+                let renewer = tokio::spawn(keep_job_lease(self.clone(), job_id, lease, stop_rx));
+                let result = self.run_job_step(job.kind, &chunk).await;
+                let _ = stop_tx.send(());
+                match renewer.await {
+                    Ok(Some(new_lease)) => lease = new_lease,
+                    _ => return,
+                }
+                result
+            } else {
+                job.status = JobStatus::Completed;
+                Ok(())
+            };
+
+            job.updated = now();
+            match result {
+                Ok(_) => {
+                    job.done += chunk.len();
+                    job.error = None;
+                }
+                Err(err) => {
+                    job.attempts += 1;
+                    job.error = Some(err.to_string());
+                    if job.attempts >= JOB_MAX_ATTEMPTS {
+                        job.status = JobStatus::Failed;
+                    } else {
+                        job.status = JobStatus::Pending;
+                        job.due = job.updated + JOB_RETRY_INTERVAL * job.attempts as u64;
+                    }
+
+                    trc::event!(
+                        TaskQueue(TaskQueueEvent::JobFailed),
+                        Id = job_id,
+                        Type = job.kind.as_str(),
+                        Total = job.attempts,
+                        CausedBy = err,
+                    );
+                }
+            }
+
+            // Progress is only saved while the lease is held, which also means
+            // that cancellations issued while the step was running are not overwritten
+            match self.save_locked_job(&job, lease).await {
+                Ok(Some(new_lease)) => lease = new_lease,
+                Ok(None) => return,
+                Err(err) => {
+                    trc::error!(err.id(job_id).details("Failed to update background job"));
+                    break;
+                }
+            }
+
+            match job.status {
+                JobStatus::Running => {}
+                JobStatus::Completed => {
+                    trc::event!(
+                        TaskQueue(TaskQueueEvent::JobCompleted),
+                        Id = job_id,
+                        Type = job.kind.as_str(),
+                        Total = job.done,
+                    );
+                    return;
+                }
+                _ => return,
+            }
+        }
+
+        self.remove_job_lock(job_id, lease).await;
+    }
+
+    async fn run_job_step(&self, kind: JobKind, items: &[u32]) -> trc::Result<()> {
+        match kind {
+            JobKind::FtsReindex => {
+                self.fts_reindex_accounts(RoaringBitmap::from_iter(items.iter().copied()))
+                    .await
+            }
+            JobKind::DeletePrincipals { principal_type } => {
+                let has_bayes = self
+                    .core
+                    .spam
+                    .bayes
+                    .as_ref()
+                    .is_some_and(|c| c.account_classify);
+                for &principal_id in items {
+                    // Delete account
+                    match self
+                        .store()
+                        .delete_principal(QueryBy::Id(principal_id))
+                        .await
+                    {
+                        Ok(changed_principals) => {
+                            // Increment revision
+                            self.invalidate_principal_caches(changed_principals).await;
+                        }
+                        Err(err) => {
+                            trc::error!(err.details("Failed to delete principal"));
+                            continue;
+                        }
+                    }
+
+                    if matches!(principal_type, Type::Individual | Type::Group) {
+                        // Remove FTS index
+                        if let Err(err) = self.core.storage.fts.remove_all(principal_id).await {
+                            trc::error!(err.details("Failed to delete FTS index"));
+                        }
+
+                        // Delete bayes model
+                        if has_bayes {
+                            let mut key = Vec::with_capacity(std::mem::size_of::<u32>() + 1);
+                            key.push(KV_BAYES_MODEL_USER);
+                            key.extend_from_slice(&principal_id.to_be_bytes());
+
+                            if let Err(err) = self.in_memory_store().key_delete_prefix(&key).await {
+                                trc::error!(err.details("Failed to delete user bayes model"));
+                            }
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+            JobKind::KeywordCleanup => {
+                for &account_id in items {
+                    self.enforce_keyword_limits(account_id)
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn keep_job_lease(
+    server: Server,
+    id: u64,
+    mut lease: u64,
+    mut stop: oneshot::Receiver<()>,
+) -> Option<u64> {
+    loop {
+        tokio::select! {
+            _ = &mut stop => return Some(lease),
+            _ = tokio::time::sleep(Duration::from_secs(JOB_LEASE_RENEW_INTERVAL)) => {
+                lease = server.renew_job_lock(id, lease).await?;
+            }
+        }
+    }
+}
+
+// Leases have a one second resolution, make sure every renewal changes the lock value
+fn next_job_lease(lease: u64) -> u64 {
+    (now() + JOB_LEASE).max(lease + 1)
+}
+
+impl store::Deserialize for Job {
+    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
+        serde_json::from_slice(bytes).map_err(|err| {
+            trc::StoreEvent::DataCorruption
+                .into_err()
+                .reason(err)
+                .caused_by(trc::location!())
+        })
+    }
+}
+
+impl Job {
+    fn to_json(&self) -> trc::Result<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|err| {
+            trc::StoreEvent::UnexpectedError
+                .into_err()
+                .reason(err)
+                .caused_by(trc::location!())
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::FtsReindex => "fts-reindex",
+            JobKind::DeletePrincipals { .. } => "delete-principals",
+            JobKind::KeywordCleanup => "keyword-cleanup",
+        }
+    }
+
+    fn chunk_size(&self) -> usize {
+        match self {
+            JobKind::FtsReindex => 100,
+            JobKind::DeletePrincipals { .. } => 10,
+            JobKind::KeywordCleanup => 25,
+        }
+    }
+}
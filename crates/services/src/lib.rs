@@ -9,13 +9,15 @@ use common::{
     Inner,
     manager::boot::{BootManager, IpcReceivers},
 };
-use housekeeper::spawn_housekeeper;
+use housekeeper::{spawn_housekeeper, spawn_reload_handler};
+use job_manager::spawn_job_manager;
 use state_manager::manager::spawn_state_manager;
 use std::sync::Arc;
 use task_manager::spawn_task_manager;
 
 pub mod broadcast;
 pub mod housekeeper;
+pub mod job_manager;
 pub mod state_manager;
 pub mod task_manager;
 
@@ -45,6 +47,9 @@ impl StartServices for BootManager {
         }
 
         self.ipc_rxs.spawn_services(self.inner.clone());
+
+        // Reload settings on SIGHUP
+        spawn_reload_handler(self.inner.clone());
     }
 }
 
@@ -63,6 +68,9 @@ impl SpawnServices for IpcReceivers {
         }
 
         // Spawn task manager
-        spawn_task_manager(inner);
+        spawn_task_manager(inner.clone());
+
+        // Spawn job manager
+        spawn_job_manager(inner);
     }
 }
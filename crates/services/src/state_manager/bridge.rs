@@ -0,0 +1,218 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::config::jmap::push::{PushBridge, PushProvider};
+use reqwest::{
+    StatusCode,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+};
+use serde::Deserialize;
+use std::{
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+use store::ahash::AHashMap;
+use trc::PushSubscriptionEvent;
+
+// APNs tokens are valid for one hour and must not be refreshed more than once every 20 minutes
+const APNS_TOKEN_TTL: Duration = Duration::from_secs(50 * 60);
+const APNS_MAX_PAYLOAD: usize = 4096;
+const FCM_ASSERTION_TTL: u64 = 3600;
+
+static AUTH_TOKENS: LazyLock<Mutex<AHashMap<String, AuthToken>>> =
+    LazyLock::new(|| Mutex::new(AHashMap::new()));
+
+struct AuthToken {
+    bridge: Arc<PushBridge>,
+    token: String,
+    expires: Instant,
+}
+
+#[derive(Deserialize)]
+struct FcmAccessToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+pub(crate) async fn bridge_request(
+    url: String,
+    bridge: Arc<PushBridge>,
+    device_token: String,
+    body: String,
+    push_timeout: Duration,
+) -> bool {
+    let client_builder = reqwest::Client::builder().timeout(push_timeout);
+
+    #[cfg(feature = "test_mode")]
+    let client_builder = client_builder.danger_accept_invalid_certs(true);
+
+    let client = client_builder.build().unwrap_or_default();
+
+    let token = match auth_token(&client, &bridge).await {
+        Ok(token) => token,
+        Err(err) => {
+            trc::error!(err.ctx(trc::Key::Url, url).caused_by(trc::location!()));
+            return false;
+        }
+    };
+
+    let request = match &bridge.provider {
+        PushProvider::Apns { topic, sandbox, .. } => {
+            // State changes are sent as background notifications, the JMAP payload
+            // is omitted when it would exceed the APNs size limit.
+            let payload = if body.len() + 64 <= APNS_MAX_PAYLOAD {
+                format!("{{\"aps\":{{\"content-available\":1}},\"jmap\":{body}}}")
+            } else {
+                "{\"aps\":{\"content-available\":1}}".to_string()
+            };
+
+            client
+                .post(format!(
+                    "https://{}/3/device/{device_token}",
+                    if *sandbox {
+                        "api.sandbox.push.apple.com"
+                    } else {
+                        "api.push.apple.com"
+                    }
+                ))
+                .header(AUTHORIZATION, format!("bearer {token}"))
+                .header("apns-topic", topic.as_str())
+                .header("apns-push-type", "background")
+                .header("apns-priority", "5")
+                .body(payload)
+        }
+        PushProvider::Fcm { project_id, .. } => client
+            .post(format!(
+                "https://fcm.googleapis.com/v1/projects/{project_id}/messages:send"
+            ))
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .body(
+                serde_json::json!({
+                    "message": {
+                        "token": device_token,
+                        "data": {
+                            "jmap": body,
+                        },
+                        "android": {
+                            "priority": "high",
+                        },
+                    }
+                })
+                .to_string(),
+            ),
+    };
+
+    match request
+        .header(CONTENT_TYPE, "application/json")
+        .send()
+        .await
+    {
+        Ok(response) => {
+            let status = response.status();
+            if status.is_success() {
+                trc::event!(PushSubscription(PushSubscriptionEvent::Success), Url = url);
+
+                true
+            } else {
+                if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+                    invalidate_token(&bridge);
+                }
+
+                trc::event!(
+                    PushSubscription(PushSubscriptionEvent::Error),
+                    Details = "Push bridge request failed",
+                    Url = url,
+                    Code = status.as_u16(),
+                    Reason = response.text().await.unwrap_or_default(),
+                );
+
+                // Do not reattempt rejected device tokens or payloads
+                status.is_client_error()
+                    && !matches!(
+                        status,
+                        StatusCode::UNAUTHORIZED
+                            | StatusCode::FORBIDDEN
+                            | StatusCode::TOO_MANY_REQUESTS
+                    )
+            }
+        }
+        Err(err) => {
+            trc::event!(
+                PushSubscription(PushSubscriptionEvent::Error),
+                Details = "Push bridge request failed",
+                Url = url,
+                Reason = err.to_string()
+            );
+
+            false
+        }
+    }
+}
+
+async fn auth_token(client: &reqwest::Client, bridge: &Arc<PushBridge>) -> trc::Result<String> {
+    if let Some(token) = AUTH_TOKENS
+        .lock()
+        .unwrap()
+        .get(&bridge.id)
+        .filter(|token| Arc::ptr_eq(&token.bridge, bridge) && token.expires > Instant::now())
+    {
+        return Ok(token.token.clone());
+    }
+
+    let (token, expires) = match &bridge.provider {
+        PushProvider::Apns { .. } => (bridge.issue_token(0)?, APNS_TOKEN_TTL),
+        PushProvider::Fcm { token_url, .. } => {
+            let assertion = bridge.issue_token(FCM_ASSERTION_TTL)?;
+            let response = client
+                .post(token_url.as_str())
+                .form(&[
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                    ("assertion", assertion.as_str()),
+                ])
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|err| {
+                    trc::EventType::PushSubscription(trc::PushSubscriptionEvent::Error)
+                        .into_err()
+                        .reason(err)
+                        .details("Failed to obtain FCM access token")
+                })?
+                .bytes()
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| {
+                    serde_json::from_slice::<FcmAccessToken>(&bytes).map_err(|err| err.to_string())
+                })
+                .map_err(|err| {
+                    trc::EventType::PushSubscription(trc::PushSubscriptionEvent::Error)
+                        .into_err()
+                        .reason(err)
+                        .details("Failed to parse FCM access token")
+                })?;
+
+            (
+                response.access_token,
+                Duration::from_secs(response.expires_in.saturating_sub(60)),
+            )
+        }
+    };
+
+    AUTH_TOKENS.lock().unwrap().insert(
+        bridge.id.clone(),
+        AuthToken {
+            bridge: bridge.clone(),
+            token: token.clone(),
+            expires: Instant::now() + expires,
+        },
+    );
+
+    Ok(token)
+}
+
+fn invalidate_token(bridge: &PushBridge) {
+    AUTH_TOKENS.lock().unwrap().remove(&bridge.id);
+}
@@ -4,9 +4,9 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::{Event, PushServer, ece::ece_encrypt};
+use super::{Event, PushServer, bridge::bridge_request, ece::ece_encrypt};
 use base64::Engine;
-use common::ipc::EncryptionKeys;
+use common::{config::jmap::settings::JmapConfig, ipc::EncryptionKeys};
 use jmap_proto::response::status::StateChangeResponse;
 use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
 use std::time::{Duration, Instant};
@@ -15,8 +15,12 @@ use trc::PushSubscriptionEvent;
 use types::id::Id;
 
 impl PushServer {
-    pub fn send(&mut self, id: Id, push_tx: mpsc::Sender<Event>, push_timeout: Duration) {
+    pub fn send(&mut self, id: Id, push_tx: mpsc::Sender<Event>, jmap: &JmapConfig) {
         let url = self.url.clone();
+        let bridge = jmap
+            .push_bridge(&url)
+            .map(|(bridge, device_token)| (bridge, device_token.to_string()));
+        let push_timeout = jmap.push_timeout;
         let keys = self.keys.clone();
         let state_changes = std::mem::take(&mut self.state_changes);
 
@@ -34,21 +38,19 @@ impl PushServer {
                 }
             }
 
+            let body = serde_json::to_string(&response).unwrap();
+            let is_success = if let Some((bridge, device_token)) = bridge {
+                bridge_request(url, bridge, device_token, body, push_timeout).await
+            } else {
+                http_request(url, body, keys, push_timeout).await
+            };
+
             push_tx
-                .send(
-                    if http_request(
-                        url,
-                        serde_json::to_string(&response).unwrap(),
-                        keys,
-                        push_timeout,
-                    )
-                    .await
-                    {
-                        Event::DeliverySuccess { id }
-                    } else {
-                        Event::DeliveryFailure { id, state_changes }
-                    },
-                )
+                .send(if is_success {
+                    Event::DeliverySuccess { id }
+                } else {
+                    Event::DeliveryFailure { id, state_changes }
+                })
                 .await
                 .ok();
         });
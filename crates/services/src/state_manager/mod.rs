@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod bridge;
 pub mod ece;
 pub mod http;
 pub mod manager;
@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::{Event, PushServer, PushUpdate, http::http_request};
+use super::{Event, PushServer, PushUpdate, bridge::bridge_request, http::http_request};
 use common::{IPC_CHANNEL_BUFFER, Inner, LONG_1Y_SLUMBER, core::BuildServer};
 use std::{
     collections::hash_map::Entry,
@@ -70,22 +70,34 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                         })
                                         .unwrap_or(true)
                                     {
+                                        let bridge = server.core.jmap.push_bridge(&url).map(
+                                            |(bridge, device_token)| {
+                                                (bridge, device_token.to_string())
+                                            },
+                                        );
                                         tokio::spawn(async move {
-                                            http_request(
-                                                url,
-                                                format!(
-                                                    concat!(
-                                                        "{{\"@type\":\"PushVerification\",",
-                                                        "\"pushSubscriptionId\":\"{}\",",
-                                                        "\"verificationCode\":\"{}\"}}"
-                                                    ),
-                                                    Id::from(id),
-                                                    code
+                                            let body = format!(
+                                                concat!(
+                                                    "{{\"@type\":\"PushVerification\",",
+                                                    "\"pushSubscriptionId\":\"{}\",",
+                                                    "\"verificationCode\":\"{}\"}}"
                                                 ),
-                                                keys,
-                                                push_timeout,
-                                            )
-                                            .await;
+                                                Id::from(id),
+                                                code
+                                            );
+
+                                            if let Some((bridge, device_token)) = bridge {
+                                                bridge_request(
+                                                    url,
+                                                    bridge,
+                                                    device_token,
+                                                    body,
+                                                    push_timeout,
+                                                )
+                                                .await;
+                                            } else {
+                                                http_request(url, body, keys, push_timeout).await;
+                                            }
                                         });
 
                                         last_verify.insert(account_id, current_time);
@@ -133,7 +145,7 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                             .contains(&subscription.num_attempts)
                                             && last_request > push_attempt_interval))
                                 {
-                                    subscription.send(id, push_tx.clone(), push_timeout);
+                                    subscription.send(id, push_tx.clone(), &server.core.jmap);
                                     retry_ids.remove(&id);
                                 } else {
                                     retry_ids.insert(id);
@@ -189,7 +201,11 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                         && last_request >= push_attempt_interval))
                             {
                                 if subscription.num_attempts < push_attempts_max {
-                                    subscription.send(*retry_id, push_tx.clone(), push_timeout);
+                                    subscription.send(
+                                        *retry_id,
+                                        push_tx.clone(),
+                                        &server.core.jmap,
+                                    );
                                 } else {
                                     trc::event!(
                                         PushSubscription(PushSubscriptionEvent::Error),
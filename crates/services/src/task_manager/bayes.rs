@@ -8,6 +8,7 @@ use super::Task;
 use common::Server;
 use email::message::bayes::EmailBayesTrain;
 use mail_parser::MessageParser;
+use smtp::reporting::abuse::AbuseReporting;
 use std::time::Instant;
 use trc::{SpamEvent, TaskQueueEvent};
 use types::{blob_hash::BlobHash, collection::Collection};
@@ -39,6 +40,20 @@ impl BayesTrainTask for Server {
             )
             .await;
 
+            // Report spam to the originating network
+            if learn_spam && !self.core.smtp.report.abuse.to.is_empty() {
+                match self.get_access_token(task.account_id).await {
+                    Ok(access_token) => {
+                        if let Some(rcpt) = access_token.emails.first() {
+                            self.send_abuse_report(&raw_message, rcpt, 0).await;
+                        }
+                    }
+                    Err(err) => {
+                        trc::error!(err.account_id(task.account_id).caused_by(trc::location!()));
+                    }
+                }
+            }
+
             trc::event!(
                 Spam(SpamEvent::TrainAccount),
                 AccountId = task.account_id,
@@ -8,6 +8,7 @@ use super::Task;
 use common::Server;
 use directory::{Type, backend::internal::manage::ManageDirectory};
 use email::message::{index::IndexMessageText, metadata::MessageMetadata};
+use groupware::{calendar::CalendarEvent, contact::ContactCard, fts::IndexGroupwareText};
 use std::time::Instant;
 use store::{
     IterateParams, SerializeInfallible, U32_LEN, ValueKey,
@@ -25,11 +26,21 @@ use types::{
 
 pub trait FtsIndexTask: Sync + Send {
     fn fts_index(&self, task: &Task, hash: &BlobHash) -> impl Future<Output = bool> + Send;
+    fn fts_index_groupware(
+        &self,
+        task: &Task,
+        collection: Collection,
+        is_update: bool,
+    ) -> impl Future<Output = bool> + Send;
     fn fts_reindex(
         &self,
         account_id: Option<u32>,
         tenant_id: Option<u32>,
     ) -> impl Future<Output = trc::Result<()>> + Send;
+    fn fts_reindex_accounts(
+        &self,
+        accounts: RoaringBitmap,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
 }
 
 impl FtsIndexTask for Server {
@@ -133,6 +144,114 @@ impl FtsIndexTask for Server {
         }
     }
 
+    async fn fts_index_groupware(
+        &self,
+        task: &Task,
+        collection: Collection,
+        is_update: bool,
+    ) -> bool {
+        let op_start = Instant::now();
+        let archive = match self
+            .get_archive(task.account_id, collection, task.document_id)
+            .await
+        {
+            Ok(Some(archive)) => archive,
+            Ok(None) => {
+                // The item was deleted, remove it from the index
+                return match self
+                    .core
+                    .storage
+                    .fts
+                    .remove(task.account_id, collection, &vec![task.document_id])
+                    .await
+                {
+                    Ok(_) => true,
+                    Err(err) => {
+                        trc::error!(
+                            err.account_id(task.account_id)
+                                .document_id(task.document_id)
+                                .details("Failed to remove groupware item from FTS index")
+                        );
+
+                        false
+                    }
+                };
+            }
+            Err(err) => {
+                trc::error!(
+                    err.account_id(task.account_id)
+                        .document_id(task.document_id)
+                        .caused_by(trc::location!())
+                        .details("Failed to retrieve groupware item")
+                );
+                return false;
+            }
+        };
+
+        let document = FtsDocument::with_default_language(self.core.jmap.default_language)
+            .with_account_id(task.account_id)
+            .with_collection(collection)
+            .with_document_id(task.document_id);
+        let document = match collection {
+            Collection::CalendarEvent => archive
+                .unarchive::<CalendarEvent>()
+                .map(|event| document.index_calendar_event(event)),
+            Collection::ContactCard => archive
+                .unarchive::<ContactCard>()
+                .map(|card| document.index_contact_card(card)),
+            _ => return true,
+        };
+        let document = match document {
+            Ok(document) => document,
+            Err(err) => {
+                trc::error!(
+                    err.account_id(task.account_id)
+                        .document_id(task.document_id)
+                        .details("Failed to unarchive groupware item")
+                );
+                return true;
+            }
+        };
+
+        // Remove the terms of the previous version
+        if is_update
+            && let Err(err) = self
+                .core
+                .storage
+                .fts
+                .remove(task.account_id, collection, &vec![task.document_id])
+                .await
+        {
+            trc::error!(
+                err.account_id(task.account_id)
+                    .document_id(task.document_id)
+                    .details("Failed to remove groupware item from FTS index")
+            );
+
+            return false;
+        }
+
+        if let Err(err) = self.core.storage.fts.index(document).await {
+            trc::error!(
+                err.account_id(task.account_id)
+                    .document_id(task.document_id)
+                    .details("Failed to index groupware item in FTS index")
+            );
+
+            return false;
+        }
+
+        trc::event!(
+            MessageIngest(MessageIngestEvent::FtsIndex),
+            AccountId = task.account_id,
+            Collection = collection,
+            DocumentId = task.document_id,
+            Elapsed = op_start.elapsed(),
+        );
+
+        true
+    }
+
     async fn fts_reindex(
         &self,
         account_id: Option<u32>,
@@ -163,6 +282,10 @@ impl FtsIndexTask for Server {
             accounts
         };
 
+        self.fts_reindex_accounts(accounts).await
+    }
+
+    async fn fts_reindex_accounts(&self, accounts: RoaringBitmap) -> trc::Result<()> {
         // Validate linked blobs
         let from_key = ValueKey {
             account_id: 0,
@@ -240,6 +363,46 @@ impl FtsIndexTask for Server {
             }
         }
 
+        // Reindex calendar events and contact cards
+        for account_id in accounts {
+            for collection in [Collection::CalendarEvent, Collection::ContactCard] {
+                let Some(document_ids) = self
+                    .get_document_ids(account_id, collection)
+                    .await
+                    .caused_by(trc::location!())?
+                else {
+                    continue;
+                };
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(collection);
+
+                for document_id in document_ids {
+                    batch.update_document(document_id).set(
+                        ValueClass::TaskQueue(TaskQueueClass::IndexGroupware {
+                            due,
+                            collection: collection.into(),
+                        }),
+                        vec![1u8],
+                    );
+
+                    if batch.len() >= 2000 {
+                        self.core.storage.data.write(batch.build_all()).await?;
+                        batch = BatchBuilder::new();
+                        batch
+                            .with_account_id(account_id)
+                            .with_collection(collection);
+                    }
+                }
+
+                if !batch.is_empty() {
+                    self.core.storage.data.write(batch.build_all()).await?;
+                }
+            }
+        }
+
         // Request indexing
         self.notify_task_queue();
 
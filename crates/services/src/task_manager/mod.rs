@@ -31,7 +31,10 @@ use store::{
 };
 use tokio::sync::{mpsc, watch};
 use trc::TaskQueueEvent;
-use types::blob_hash::{BLOB_HASH_LEN, BlobHash};
+use types::{
+    blob_hash::{BLOB_HASH_LEN, BlobHash},
+    collection::Collection,
+};
 use utils::snowflake::SnowflakeIdGenerator;
 
 pub mod alarm;
@@ -49,10 +52,21 @@ pub struct Task {
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum TaskAction {
-    Index { hash: BlobHash },
-    BayesTrain { hash: BlobHash, learn_spam: bool },
-    SendAlarm { alarm: CalendarAlarm },
+    Index {
+        hash: BlobHash,
+    },
+    BayesTrain {
+        hash: BlobHash,
+        learn_spam: bool,
+    },
+    SendAlarm {
+        alarm: CalendarAlarm,
+    },
     SendImip,
+    IndexGroupware {
+        collection: Collection,
+        is_update: bool,
+    },
 }
 
 const FTS_LOCK_EXPIRY: u64 = 60 * 5; // 5 minutes
@@ -87,6 +101,7 @@ pub fn spawn_task_manager(inner: Arc<Inner>) {
         protocol: ServerProtocol::Smtp,
         acceptor: TcpAcceptor::Plain,
         limiter: ConcurrencyLimiter::new(100),
+        message_limiter: Default::default(),
         shutdown_rx: watch::channel(false).1,
         proxy_networks: vec![],
         span_id_gen: Arc::new(SnowflakeIdGenerator::new()),
@@ -103,6 +118,14 @@ pub fn spawn_task_manager(inner: Arc<Inner>) {
                 if server.try_lock_task(&task).await {
                     let success = match &task.action {
                         TaskAction::Index { hash } => server.fts_index(&task, hash).await,
+                        TaskAction::IndexGroupware {
+                            collection,
+                            is_update,
+                        } => {
+                            server
+                                .fts_index_groupware(&task, *collection, *is_update)
+                                .await
+                        }
                         TaskAction::BayesTrain { hash, learn_spam } => {
                             server.bayes_train(&task, hash, *learn_spam).await
                         }
@@ -264,7 +287,7 @@ impl TaskQueueManager for Server {
 
         for event in tasks {
             let tx = match &event.action {
-                TaskAction::Index { .. } => &ipc.tx_fts,
+                TaskAction::Index { .. } | TaskAction::IndexGroupware { .. } => &ipc.tx_fts,
                 TaskAction::BayesTrain { .. } => &ipc.tx_bayes,
                 TaskAction::SendAlarm { .. } => &ipc.tx_alarm,
                 TaskAction::SendImip => &ipc.tx_imip,
@@ -359,12 +382,21 @@ impl Task {
                 .write_leb128(self.account_id)
                 .write_leb128(self.document_id)
                 .finalize(),
+            TaskAction::IndexGroupware { collection, .. } => {
+                KeySerializer::new((U32_LEN * 2) + U64_LEN + 2)
+                    .write(4u8)
+                    .write(self.due)
+                    .write(u8::from(*collection))
+                    .write_leb128(self.account_id)
+                    .write_leb128(self.document_id)
+                    .finalize()
+            }
         }
     }
 
     fn lock_expiry(&self) -> u64 {
         match self.action {
-            TaskAction::Index { .. } => FTS_LOCK_EXPIRY,
+            TaskAction::Index { .. } | TaskAction::IndexGroupware { .. } => FTS_LOCK_EXPIRY,
             TaskAction::BayesTrain { .. } => BAYES_LOCK_EXPIRY,
             TaskAction::SendAlarm { .. } | TaskAction::SendImip => ALARM_EXPIRY,
         }
@@ -391,6 +423,10 @@ impl Task {
                     due: self.due,
                     is_payload: false,
                 },
+                TaskAction::IndexGroupware { collection, .. } => TaskQueueClass::IndexGroupware {
+                    due: self.due,
+                    collection: u8::from(*collection),
+                },
             })),
             (matches!(self.action, TaskAction::SendImip)).then_some(ValueClass::TaskQueue(
                 TaskQueueClass::SendImip {
@@ -451,6 +487,14 @@ impl Task {
                     },
                 },
                 Some(4) => TaskAction::SendImip,
+                Some(6) => TaskAction::IndexGroupware {
+                    collection: key
+                        .get(U64_LEN + U32_LEN + U32_LEN + 1)
+                        .copied()
+                        .map(Collection::from)
+                        .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?,
+                    is_update: value.first().is_some_and(|v| *v == 1),
+                },
                 _ => return Err(trc::Error::corrupted_key(key, None, trc::location!())),
             },
         })
@@ -4,12 +4,19 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use crate::{inbound::auth::SaslToken, queue::QueueId};
+use crate::{
+    inbound::{auth::SaslToken, transcript::SessionTranscript},
+    queue::QueueId,
+};
 use common::{
     Inner, Server,
     auth::AccessToken,
-    config::smtp::auth::VerifyStrategy,
-    listener::{ServerInstance, asn::AsnGeoLookupResult},
+    config::smtp::{auth::VerifyStrategy, session::EhloValidationResult},
+    listener::{
+        ServerInstance, asn::AsnGeoLookupResult, fingerprint::TlsFingerprint,
+        limiter::BufferInFlight,
+    },
+    scripts::auth_results::AuthResults,
 };
 use directory::Directory;
 use mail_auth::{IprevOutput, SpfOutput};
@@ -76,6 +83,7 @@ pub struct SessionData {
     pub rcpt_errors: usize,
     pub rcpt_oks: usize,
     pub message: Vec<u8>,
+    pub message_in_flight: Option<BufferInFlight>,
 
     pub authenticated_as: Option<Arc<AccessToken>>,
     pub auth_errors: usize,
@@ -89,9 +97,13 @@ pub struct SessionData {
     pub messages_sent: usize,
 
     pub iprev: Option<IprevOutput>,
+    pub helo_validation: Option<EhloValidationResult>,
     pub spf_ehlo: Option<SpfOutput>,
     pub spf_mail_from: Option<SpfOutput>,
+    pub auth_results: Option<AuthResults>,
     pub dnsbl_error: Option<Vec<u8>>,
+    pub tls_fingerprint: Option<TlsFingerprint>,
+    pub transcript: Option<SessionTranscript>,
 }
 
 #[derive(Clone, Debug)]
@@ -111,6 +123,7 @@ pub struct SessionParameters {
     // Ehlo parameters
     pub ehlo_require: bool,
     pub ehlo_reject_non_fqdn: bool,
+    pub ehlo_validate: bool,
 
     // Auth parameters
     pub auth_directory: Option<Arc<Directory>>,
@@ -122,6 +135,7 @@ pub struct SessionParameters {
     pub rcpt_errors_max: usize,
     pub rcpt_errors_wait: Duration,
     pub rcpt_max: usize,
+    pub rcpt_max_expansion_depth: usize,
     pub rcpt_dsn: bool,
     pub can_expn: bool,
     pub can_vrfy: bool,
@@ -160,15 +174,20 @@ impl SessionData {
             rcpt_errors: 0,
             rcpt_oks: 0,
             message: Vec::with_capacity(0),
+            message_in_flight: None,
             auth_errors: 0,
             messages_sent: 0,
             bytes_left: 0,
             delivery_by: 0,
             future_release: 0,
             iprev: None,
+            helo_validation: None,
             spf_ehlo: None,
             spf_mail_from: None,
+            auth_results: None,
             dnsbl_error: None,
+            tls_fingerprint: None,
+            transcript: None,
         }
     }
 }
@@ -225,6 +244,7 @@ impl Session<common::listener::stream::NullIo> {
                 timeout: Default::default(),
                 ehlo_require: Default::default(),
                 ehlo_reject_non_fqdn: Default::default(),
+                ehlo_validate: Default::default(),
                 auth_directory: Default::default(),
                 auth_require: Default::default(),
                 auth_errors_max: Default::default(),
@@ -232,6 +252,7 @@ impl Session<common::listener::stream::NullIo> {
                 rcpt_errors_max: Default::default(),
                 rcpt_errors_wait: Default::default(),
                 rcpt_max: Default::default(),
+                rcpt_max_expansion_depth: Default::default(),
                 rcpt_dsn: Default::default(),
                 max_message_size: Default::default(),
                 iprev: VerifyStrategy::Disable,
@@ -281,6 +302,7 @@ impl SessionData {
             rcpt_errors: 0,
             rcpt_oks: 0,
             message,
+            message_in_flight: None,
             authenticated_as: Some(authenticated_as),
             auth_errors: 0,
             priority: 0,
@@ -290,9 +312,13 @@ impl SessionData {
             bytes_left: 0,
             messages_sent: 0,
             iprev: None,
+            helo_validation: None,
             spf_ehlo: None,
             spf_mail_from: None,
+            auth_results: None,
             dnsbl_error: None,
+            tls_fingerprint: None,
+            transcript: None,
         }
     }
 }
@@ -69,6 +69,11 @@ impl<T: SessionStream> Session<T> {
             .eval_if(&ec.reject_non_fqdn, self, self.data.session_id)
             .await
             .unwrap_or(true);
+        self.params.ehlo_validate = self
+            .server
+            .eval_if(&ec.validate, self, self.data.session_id)
+            .await
+            .unwrap_or(false);
 
         // Auth parameters
         let ac = &self.server.core.smtp.session.auth;
@@ -140,6 +145,11 @@ impl<T: SessionStream> Session<T> {
             .eval_if(&rc.max_recipients, self, self.data.session_id)
             .await
             .unwrap_or(100);
+        self.params.rcpt_max_expansion_depth = self
+            .server
+            .eval_if(&rc.max_expansion_depth, self, self.data.session_id)
+            .await
+            .unwrap_or(5);
         self.params.rcpt_dsn = self
             .server
             .eval_if(
@@ -6,7 +6,8 @@
 
 use common::{
     auth::{
-        AuthRequest,
+        AccessToken, AuthRequest,
+        certificate::{ClientCertMapping, ClientCertificate},
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
     },
     listener::SessionStream,
@@ -15,7 +16,10 @@ use common::{
 use directory::Permission;
 use mail_parser::decoders::base64::base64_decode;
 use mail_send::Credentials;
-use smtp_proto::{AUTH_LOGIN, AUTH_OAUTHBEARER, AUTH_PLAIN, AUTH_XOAUTH2, IntoString};
+use smtp_proto::{
+    AUTH_EXTERNAL, AUTH_LOGIN, AUTH_OAUTHBEARER, AUTH_PLAIN, AUTH_XOAUTH2, IntoString,
+};
+use std::sync::Arc;
 use trc::{AuthEvent, SmtpEvent};
 
 use crate::core::Session;
@@ -23,17 +27,19 @@ use crate::core::Session;
 pub struct SaslToken {
     mechanism: u64,
     credentials: Credentials<String>,
+    is_challenged: bool,
 }
 
 impl SaslToken {
     pub fn from_mechanism(mechanism: u64) -> Option<SaslToken> {
         match mechanism {
-            AUTH_PLAIN | AUTH_LOGIN => SaslToken {
+            AUTH_PLAIN | AUTH_LOGIN | AUTH_EXTERNAL => SaslToken {
                 mechanism,
                 credentials: Credentials::Plain {
                     username: String::new(),
                     secret: String::new(),
                 },
+                is_challenged: false,
             }
             .into(),
             AUTH_OAUTHBEARER | AUTH_XOAUTH2 => SaslToken {
@@ -41,6 +47,7 @@ impl SaslToken {
                 credentials: Credentials::OAuthBearer {
                     token: String::new(),
                 },
+                is_challenged: false,
             }
             .into(),
             _ => None,
@@ -54,7 +61,26 @@ impl<T: SessionStream> Session<T> {
         token: &mut SaslToken,
         response: &[u8],
     ) -> Result<bool, ()> {
-        if response.is_empty() {
+        if token.mechanism == AUTH_EXTERNAL {
+            // An empty response or "=" requests the certificate's own identity
+            return match response {
+                b"" if !token.is_challenged => {
+                    token.is_challenged = true;
+                    self.write(b"334 \r\n").await?;
+                    Ok(true)
+                }
+                b"" | b"=" => self.authenticate_external(None).await,
+                response => {
+                    if let Some(authzid) =
+                        base64_decode(response).and_then(|v| String::from_utf8(v).ok())
+                    {
+                        self.authenticate_external(authzid.into()).await
+                    } else {
+                        self.auth_error(b"500 5.5.6 Invalid challenge.\r\n").await
+                    }
+                }
+            };
+        } else if response.is_empty() {
             match (token.mechanism, &token.credentials) {
                 (AUTH_PLAIN | AUTH_XOAUTH2 | AUTH_OAUTHBEARER, _) => {
                     self.write(b"334 Go ahead.\r\n").await?;
@@ -109,67 +135,114 @@ impl<T: SessionStream> Session<T> {
                         self.data.session_id,
                         self.data.remote_ip,
                     )
-                    .with_directory(directory),
+                    .with_directory(directory)
+                    .with_session("smtp", None),
                 )
+                .await;
+
+            self.handle_auth_result(result).await
+        } else {
+            trc::event!(
+                Smtp(SmtpEvent::MissingAuthDirectory),
+                SpanId = self.data.session_id,
+            );
+            self.write(b"454 4.7.0 Temporary authentication failure\r\n")
+                .await?;
+
+            Ok(false)
+        }
+    }
+
+    pub async fn authenticate_external(&mut self, authzid: Option<String>) -> Result<bool, ()> {
+        if let Some((certificate, mapping)) = self.client_certificate() {
+            let result = self
+                .server
+                .authenticate_certificate(
+                    &certificate,
+                    mapping,
+                    authzid.as_deref(),
+                    self.data.session_id,
+                    self.data.remote_ip,
+                )
+                .await;
+
+            self.handle_auth_result(result).await
+        } else {
+            self.auth_error(b"535 5.7.8 No client certificate was presented.\r\n")
                 .await
-                .and_then(|access_token| {
-                    access_token
-                        .assert_has_permission(Permission::EmailSend)
-                        .map(|_| access_token)
-                });
-
-            match result {
-                Ok(access_token) => {
-                    self.data.authenticated_as = access_token.into();
-                    self.eval_post_auth_params().await;
-                    self.write(b"235 2.7.0 Authentication succeeded.\r\n")
-                        .await?;
-                    return Ok(false);
-                }
-                Err(err) => {
-                    let reason = *err.as_ref();
-
-                    trc::error!(err.span_id(self.data.session_id));
-
-                    match reason {
-                        trc::EventType::Auth(trc::AuthEvent::Failed) => {
-                            return self
-                                .auth_error(b"535 5.7.8 Authentication credentials invalid.\r\n")
-                                .await;
-                        }
-                        trc::EventType::Auth(trc::AuthEvent::TokenExpired) => {
-                            return self.auth_error(b"535 5.7.8 OAuth token expired.\r\n").await;
-                        }
-                        trc::EventType::Auth(trc::AuthEvent::MissingTotp) => {
-                            return self
+        }
+    }
+
+    // Returns the verified client certificate when certificate authentication
+    // is enabled on this listener.
+    pub fn client_certificate(&self) -> Option<(ClientCertificate, ClientCertMapping)> {
+        self.instance
+            .acceptor
+            .client_cert_mapping()
+            .and_then(|mapping| {
+                self.stream
+                    .client_certificate()
+                    .map(|certificate| (certificate, mapping))
+            })
+    }
+
+    async fn handle_auth_result(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+    ) -> Result<bool, ()> {
+        match result.and_then(|access_token| {
+            access_token
+                .assert_has_permission(Permission::EmailSend)
+                .map(|_| access_token)
+        }) {
+            Ok(access_token) => {
+                self.data.authenticated_as = access_token.into();
+                self.eval_post_auth_params().await;
+                self.write(b"235 2.7.0 Authentication succeeded.\r\n")
+                    .await?;
+                return Ok(false);
+            }
+            Err(err) => {
+                let reason = *err.as_ref();
+
+                trc::error!(err.span_id(self.data.session_id));
+
+                match reason {
+                    trc::EventType::Auth(trc::AuthEvent::Failed) => {
+                        return self
+                            .auth_error(b"535 5.7.8 Authentication credentials invalid.\r\n")
+                            .await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::TokenExpired) => {
+                        return self.auth_error(b"535 5.7.8 OAuth token expired.\r\n").await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::PasswordExpired) => {
+                        return self.auth_error(b"535 5.7.8 Password expired.\r\n").await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::MissingTotp) => {
+                        return self
                             .auth_error(
                                 b"334 5.7.8 Missing TOTP token, try with 'secret$totp_code'.\r\n",
                             )
                             .await;
-                        }
-                        trc::EventType::Security(trc::SecurityEvent::Unauthorized) => {
-                            self.write(
-                                concat!(
-                                    "550 5.7.1 Your account is not authorized ",
-                                    "to use this service.\r\n"
-                                )
-                                .as_bytes(),
+                    }
+                    trc::EventType::Security(trc::SecurityEvent::Unauthorized) => {
+                        self.write(
+                            concat!(
+                                "550 5.7.1 Your account is not authorized ",
+                                "to use this service.\r\n"
                             )
-                            .await?;
-                            return Ok(false);
-                        }
-                        trc::EventType::Security(_) => {
-                            return Err(());
-                        }
-                        _ => (),
+                            .as_bytes(),
+                        )
+                        .await?;
+                        return Ok(false);
+                    }
+                    trc::EventType::Security(_) => {
+                        return Err(());
                     }
+                    _ => (),
                 }
             }
-        } else {
-            trc::event!(
-                Smtp(SmtpEvent::MissingAuthDirectory),
-                SpanId = self.data.session_id,
-            );
         }
         self.write(b"454 4.7.0 Temporary authentication failure\r\n")
             .await?;
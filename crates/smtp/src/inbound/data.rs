@@ -7,24 +7,35 @@
 use super::{ArcSeal, AuthResult, DkimSign};
 use crate::{
     core::{Session, SessionAddress, State},
-    inbound::milter::Modification,
-    queue::{self, Message, MessageSource, MessageWrapper, QueueEnvelope, quota::HasQueueQuota},
-    reporting::analysis::AnalyzeReport,
+    inbound::{health::DirectoryHealth, milter::Modification},
+    queue::{
+        self, MESSAGE_TLS_OPTIONAL, MESSAGE_TRACE, Message, MessageSource, MessageWrapper,
+        QueueEnvelope, QueueId,
+        quota::HasQueueQuota,
+        sending::SendingQuotas,
+        trace::{MessageTrace, TRACE_HEADER, TraceStage},
+    },
+    reporting::{abuse::is_authenticated_source, analysis::AnalyzeReport},
     scripts::ScriptResult,
 };
 use common::{
     config::{
         smtp::{
-            auth::VerifyStrategy,
+            auth::{AuthOverride, VerifyStrategy},
             queue::{QueueExpiry, QueueName},
-            session::Stage,
+            session::{QuotaCheck, Stage},
         },
         spamfilter::SpamFilterAction,
     },
+    expr::{self, V_RECIPIENT, V_RECIPIENT_DOMAIN, functions::ResolveVariable},
     listener::SessionStream,
     psl,
-    scripts::ScriptModification,
+    scripts::{
+        ScriptModification,
+        auth_results::{AuthResults, AuthVerdict},
+    },
 };
+use directory::Directory;
 use mail_auth::{
     AuthenticatedMessage, AuthenticationResults, DkimResult, DmarcResult, ReceivedSpf,
     common::{headers::HeaderWriter, verify::VerifySignature},
@@ -34,8 +45,10 @@ use mail_builder::headers::{date::Date, message_id::generate_message_id_header};
 use mail_parser::MessageParser;
 use sieve::runtime::Variable;
 use smtp_proto::{
-    MAIL_BY_RETURN, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS,
+    MAIL_BY_RETURN, MAIL_REQUIRETLS, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER,
+    RCPT_NOTIFY_SUCCESS,
 };
+use spam_filter::modules::dkim::{has_unsigned_body, unsigned_duplicate_header};
 use std::{
     borrow::Cow,
     time::{Instant, SystemTime},
@@ -43,6 +56,8 @@ use std::{
 use trc::SmtpEvent;
 use utils::{DomainPart, config::Rate};
 
+pub const SHADOW_HEADER: &str = "X-Stalwart-Shadow";
+
 impl<T: SessionStream> Session<T> {
     pub async fn queue_message(&mut self) -> Cow<'static, [u8]> {
         // Parse message
@@ -61,6 +76,26 @@ impl<T: SessionStream> Session<T> {
                 return (&b"550 5.7.7 Failed to parse message.\r\n"[..]).into();
             }
         };
+        if let Err(event) = self
+            .server
+            .core
+            .jmap
+            .mail_mime_limits
+            .check(&parsed_message, raw_message.len())
+        {
+            trc::event!(
+                MessageIngest(event),
+                SpanId = self.data.session_id,
+                Size = raw_message.len(),
+            );
+
+            return (&b"550 5.6.0 Message structure exceeds configured limits.\r\n"[..]).into();
+        }
+
+        // RFC 8689 - A TLS-Required: No header relaxes TLS policies on delivery
+        let is_tls_optional = parsed_message
+            .header_raw("TLS-Required")
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("no"));
 
         // Authenticate message
         let auth_message = AuthenticatedMessage::from_parsed(
@@ -116,7 +151,78 @@ impl<T: SessionStream> Session<T> {
                 .iter()
                 .any(|d| matches!(d.result(), DkimResult::Pass));
             let strict = dkim.is_strict();
-            let rejected = strict && !pass;
+            let mut rejected = strict && !pass;
+
+            // Apply policy overrides
+            if !pass
+                && let Some(action) = self
+                    .server
+                    .eval_if::<AuthOverride, _>(
+                        &ac.dkim.policy_override,
+                        self,
+                        self.data.session_id,
+                    )
+                    .await
+            {
+                trc::event!(
+                    Smtp(SmtpEvent::DkimOverride),
+                    SpanId = self.data.session_id,
+                    Strict = strict,
+                    Details = action.as_str(),
+                );
+
+                rejected = action == AuthOverride::Reject;
+            }
+
+            // Detect passing signatures that leave part of the message unsigned
+            let mut rejection: Option<Cow<'static, [u8]>> = None;
+            for output in dkim_output
+                .iter()
+                .filter(|d| matches!(d.result(), DkimResult::Pass))
+            {
+                let Some(signature) = output.signature() else {
+                    continue;
+                };
+
+                if has_unsigned_body(signature, &parsed_message) {
+                    trc::event!(
+                        Smtp(SmtpEvent::DkimUnsignedBody),
+                        SpanId = self.data.session_id,
+                        Domain = signature.d.clone(),
+                        Size = signature.l,
+                    );
+
+                    if ac.dkim.reject_unsigned_body {
+                        rejection = Some(
+                            (&b"550 5.7.20 DKIM signature does not cover the entire body.\r\n"[..])
+                                .into(),
+                        );
+                    }
+                }
+
+                if let Some(header) = unsigned_duplicate_header(signature, &parsed_message) {
+                    trc::event!(
+                        Smtp(SmtpEvent::DkimUnsignedHeader),
+                        SpanId = self.data.session_id,
+                        Domain = signature.d.clone(),
+                        Details = header,
+                    );
+
+                    if ac.dkim.reject_unsigned_headers {
+                        rejection = Some(
+                            format!(
+                                "550 5.7.20 DKIM signature does not cover all {header} headers.\r\n"
+                            )
+                            .into_bytes()
+                            .into(),
+                        );
+                    }
+                }
+            }
+
+            if let Some(rejection) = rejection {
+                return rejection;
+            }
 
             // Send reports for failed signatures
             if let Some(rate) = self
@@ -238,7 +344,7 @@ impl<T: SessionStream> Session<T> {
 
         // Verify DMARC
         let is_report = self.is_report();
-        let (dmarc_result, dmarc_policy) = match &self.data.spf_mail_from {
+        let (dmarc_result, dmarc_policy, dmarc_domain) = match &self.data.spf_mail_from {
             Some(spf_output) if dmarc.verify() => {
                 let time = Instant::now();
                 let dmarc_output =
@@ -267,10 +373,48 @@ impl<T: SessionStream> Session<T> {
                 let pass = matches!(dmarc_output.spf_result(), DmarcResult::Pass)
                     || matches!(dmarc_output.dkim_result(), DmarcResult::Pass);
                 let strict = dmarc.is_strict();
-                let rejected = strict && dmarc_output.policy() == dmarc::Policy::Reject && !pass;
+                let mut rejected =
+                    strict && dmarc_output.policy() == dmarc::Policy::Reject && !pass;
+                let mut dmarc_policy = dmarc_output.policy();
+
+                // Apply policy overrides
+                if !pass
+                    && let Some(action) = self
+                        .server
+                        .eval_if::<AuthOverride, _>(
+                            &ac.dmarc.policy_override,
+                            self,
+                            self.data.session_id,
+                        )
+                        .await
+                {
+                    trc::event!(
+                        Smtp(SmtpEvent::DmarcOverride),
+                        SpanId = self.data.session_id,
+                        Strict = strict,
+                        Domain = dmarc_output.domain().to_string(),
+                        Policy = dmarc_policy.to_string(),
+                        Details = action.as_str(),
+                    );
+
+                    match action {
+                        AuthOverride::Accept => {
+                            rejected = false;
+                        }
+                        AuthOverride::Quarantine => {
+                            rejected = false;
+                            dmarc_policy = dmarc::Policy::Quarantine;
+                        }
+                        AuthOverride::Reject => {
+                            rejected = true;
+                            dmarc_policy = dmarc::Policy::Reject;
+                        }
+                    }
+                }
                 let is_temp_fail = rejected
                     && matches!(dmarc_output.spf_result(), DmarcResult::TempError(_))
                     || matches!(dmarc_output.dkim_result(), DmarcResult::TempError(_));
+                let dmarc_domain = dmarc_output.domain().to_lowercase();
 
                 // Add to DMARC output to the Authentication-Results header
                 auth_results = auth_results.with_dmarc_result(&dmarc_output);
@@ -283,7 +427,6 @@ impl<T: SessionStream> Session<T> {
                 } else {
                     DmarcResult::None
                 };
-                let dmarc_policy = dmarc_output.policy();
 
                 trc::event!(
                     Smtp(if pass {
@@ -294,7 +437,7 @@ impl<T: SessionStream> Session<T> {
                     SpanId = self.data.session_id,
                     Strict = strict,
                     Domain = dmarc_output.domain().to_string(),
-                    Policy = dmarc_policy.to_string(),
+                    Policy = dmarc_output.policy().to_string(),
                     Result = trc::Error::from(&dmarc_result),
                     Elapsed = time.elapsed(),
                 );
@@ -320,13 +463,63 @@ impl<T: SessionStream> Session<T> {
                     };
                 }
 
-                (dmarc_result.into(), dmarc_policy.into())
+                (
+                    dmarc_result.into(),
+                    dmarc_policy.into(),
+                    dmarc_domain.into(),
+                )
             }
-            _ => (None, None),
+            _ => (None, None, None),
         };
 
+        // Keep the structured results available to policy expressions
+        self.data.auth_results = Some(AuthResults {
+            spf: self.data.spf_mail_from.as_ref().map(|spf| AuthVerdict {
+                result: spf.result().as_str().to_string(),
+                domain: mail_from.domain.to_lowercase(),
+            }),
+            dkim: dkim_output
+                .iter()
+                .map(|output| AuthVerdict {
+                    result: output.result().as_str().to_string(),
+                    domain: output
+                        .signature()
+                        .map(|signature| signature.domain().to_lowercase())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            arc: arc_output.as_ref().map(|arc| AuthVerdict {
+                result: arc.result().as_str().to_string(),
+                domain: String::new(),
+            }),
+            dmarc: dmarc_result.as_ref().map(|result| AuthVerdict {
+                result: result.as_str().to_string(),
+                domain: dmarc_domain.unwrap_or_default(),
+            }),
+            iprev: self.data.iprev.as_ref().map(|iprev| AuthVerdict {
+                result: iprev.result().as_str().to_string(),
+                domain: iprev
+                    .ptr
+                    .as_ref()
+                    .and_then(|addrs| addrs.first())
+                    .map(|ptr| ptr.strip_suffix('.').unwrap_or(ptr).to_lowercase())
+                    .unwrap_or_default(),
+            }),
+        });
+
         // Analyze reports
         if is_report {
+            let source = parsed_message
+                .from()
+                .and_then(|from| from.first())
+                .and_then(|from| from.address())
+                .map(|from| from.domain_part().to_lowercase())
+                .filter(|domain| {
+                    self.data
+                        .auth_results
+                        .as_ref()
+                        .is_some_and(|results| is_authenticated_source(results, domain))
+                });
             if !rc.analysis.forward {
                 self.server.analyze_report(
                     mail_parser::Message {
@@ -340,6 +533,7 @@ impl<T: SessionStream> Session<T> {
                             .collect(),
                         raw_message: b"".into(),
                     },
+                    source,
                     self.data.session_id,
                 );
                 self.data.messages_sent += 1;
@@ -357,6 +551,7 @@ impl<T: SessionStream> Session<T> {
                             .collect(),
                         raw_message: b"".into(),
                     },
+                    source,
                     self.data.session_id,
                 );
             }
@@ -365,13 +560,93 @@ impl<T: SessionStream> Session<T> {
         // Add Received header
         let message_id = self.server.inner.data.queue_id_gen.generate();
         let mut headers = Vec::with_capacity(64);
+
+        // Explicit tracing requested by the submitter
+        let trace_id = if parsed_message
+            .header_raw(TRACE_HEADER)
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("on"))
+            && self
+                .server
+                .eval_if(&dc.trace, self, self.data.session_id)
+                .await
+                .unwrap_or(false)
+        {
+            trc::event!(
+                Smtp(SmtpEvent::MessageTraceEnabled),
+                SpanId = self.data.session_id,
+                QueueId = message_id,
+            );
+
+            self.server
+                .trace_message(
+                    message_id,
+                    TraceStage::Received,
+                    format!(
+                        "Received from {} ({}) by session {}, return path <{}>, {} recipient(s), {} bytes, DKIM {}, DMARC {}",
+                        self.data.helo_domain,
+                        self.data.remote_ip,
+                        self.data.session_id,
+                        mail_from.address,
+                        self.data.rcpt_to.len(),
+                        raw_message.len(),
+                        dkim_output
+                            .first()
+                            .map(|r| r.result().as_str())
+                            .unwrap_or("none"),
+                        dmarc_result.as_ref().map_or("none", |r| r.as_str()),
+                    ),
+                )
+                .await;
+
+            Some(message_id)
+        } else {
+            None
+        };
+
+        // Remove client identifying details from authenticated submissions
+        let remove_headers = if self.is_authenticated()
+            && self
+                .server
+                .eval_if(&dc.privacy, self, self.data.session_id)
+                .await
+                .unwrap_or(false)
+        {
+            let remove_headers = dc
+                .privacy_remove_headers
+                .iter()
+                .filter(|name| {
+                    auth_message
+                        .raw_parsed_headers()
+                        .iter()
+                        .any(|(n, _)| n.eq_ignore_ascii_case(name.as_bytes()))
+                })
+                .collect::<Vec<_>>();
+
+            // Keep an audit record linking the message to the originating session
+            trc::event!(
+                Smtp(SmtpEvent::HeadersAnonymized),
+                SpanId = self.data.session_id,
+                QueueId = message_id,
+                RemoteIp = self.data.remote_ip,
+                AccountName = self.authenticated_as().unwrap_or_default().to_string(),
+                Details = remove_headers
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>(),
+            );
+
+            Some(remove_headers)
+        } else {
+            None
+        };
+
         if self
             .server
             .eval_if(&dc.add_received, self, self.data.session_id)
             .await
             .unwrap_or(true)
         {
-            self.write_received(&mut headers, message_id)
+            self.write_received(&mut headers, message_id, remove_headers.is_some())
         }
 
         // Add authentication results header
@@ -440,18 +715,39 @@ impl<T: SessionStream> Session<T> {
                 .await
             {
                 SpamFilterAction::Allow(spam_headers) => {
+                    if let Some(trace_id) = trace_id {
+                        self.server
+                            .trace_message(
+                                trace_id,
+                                TraceStage::Filter,
+                                format!("Spam filter accepted message: {}", spam_headers.trim()),
+                            )
+                            .await;
+                    }
                     if !spam_headers.is_empty() {
                         headers.extend_from_slice(spam_headers.as_bytes());
                     }
                 }
                 SpamFilterAction::Discard => {
                     self.data.messages_sent += 1;
-                    return (b"250 2.0.0 Message queued for delivery.\r\n"[..]).into();
+                    return self
+                        .trace_rejection(
+                            trace_id,
+                            "Spam filter discarded message",
+                            (b"250 2.0.0 Message queued for delivery.\r\n"[..]).into(),
+                        )
+                        .await;
                 }
                 SpamFilterAction::Reject => {
                     self.data.messages_sent += 1;
-                    return (b"550 5.7.1 Message rejected due to excessive spam score.\r\n"[..])
-                        .into();
+                    return self
+                        .trace_rejection(
+                            trace_id,
+                            "Spam filter rejected message",
+                            (b"550 5.7.1 Message rejected due to excessive spam score.\r\n"[..])
+                                .into(),
+                        )
+                        .await;
                 }
             }
         }
@@ -465,7 +761,9 @@ impl<T: SessionStream> Session<T> {
                 }
             }
             Err(response) => {
-                return response.into_bytes();
+                return self
+                    .trace_rejection(trace_id, "Milter rejected message", response.into_bytes())
+                    .await;
             }
         };
 
@@ -481,11 +779,58 @@ impl<T: SessionStream> Session<T> {
                 }
             }
             Err(response) => {
-                return response.into_bytes();
+                return self
+                    .trace_rejection(trace_id, "MTA hook rejected message", response.into_bytes())
+                    .await;
+            }
+        };
+
+        // Run ICAP services
+        match self.run_icap_services(auth_message.raw_message()).await {
+            Ok(modifications_) => {
+                modifications.extend(modifications_);
+            }
+            Err(response) => {
+                return self
+                    .trace_rejection(
+                        trace_id,
+                        "ICAP service rejected message",
+                        response.into_bytes(),
+                    )
+                    .await;
             }
         };
 
         // Apply modifications
+        if let Some(trace_id) = trace_id
+            && !modifications.is_empty()
+        {
+            self.server
+                .trace_message(
+                    trace_id,
+                    TraceStage::Filter,
+                    format!(
+                        "{} modification(s) requested by external filters",
+                        modifications.len()
+                    ),
+                )
+                .await;
+        }
+        if let Some(remove_headers) = &remove_headers {
+            for name in remove_headers {
+                for _ in auth_message
+                    .raw_parsed_headers()
+                    .iter()
+                    .filter(|(n, _)| n.eq_ignore_ascii_case(name.as_bytes()))
+                {
+                    modifications.push(Modification::ChangeHeader {
+                        index: 1,
+                        name: name.to_string(),
+                        value: String::new(),
+                    });
+                }
+            }
+        }
         let mut edited_message = if !modifications.is_empty() {
             self.data
                 .apply_milter_modifications(modifications, &auth_message)
@@ -563,10 +908,22 @@ impl<T: SessionStream> Session<T> {
                     modifications
                 }
                 ScriptResult::Reject(message) => {
-                    return message.as_bytes().to_vec().into();
+                    return self
+                        .trace_rejection(
+                            trace_id,
+                            "Sieve script rejected message",
+                            message.as_bytes().to_vec().into(),
+                        )
+                        .await;
                 }
                 ScriptResult::Discard => {
-                    return (b"250 2.0.0 Message queued for delivery.\r\n"[..]).into();
+                    return self
+                        .trace_rejection(
+                            trace_id,
+                            "Sieve script discarded message",
+                            (b"250 2.0.0 Message queued for delivery.\r\n"[..]).into(),
+                        )
+                        .await;
                 }
             };
 
@@ -588,12 +945,50 @@ impl<T: SessionStream> Session<T> {
             }
         }
 
+        // Verify local recipient quotas
+        if let Some(action) = self
+            .check_recipient_quotas((raw_message.len() + headers.len()) as u64)
+            .await
+        {
+            return self
+                .trace_rejection(
+                    trace_id,
+                    "Recipient quota exceeded",
+                    if action == QuotaCheck::Reject {
+                        (b"552 5.2.2 Mailbox full.\r\n"[..]).into()
+                    } else {
+                        (b"452 4.2.2 Mailbox full, try again later.\r\n"[..]).into()
+                    },
+                )
+                .await;
+        }
+
+        // Select a shadow recipient, if any
+        let shadow_rcpt = if !dc.shadow_rcpt.is_empty()
+            && (dc.shadow_rate >= 100.0 || rand::random_range(0.0..100.0) < dc.shadow_rate)
+        {
+            self.server
+                .eval_if::<String, _>(&dc.shadow_rcpt, self, self.data.session_id)
+                .await
+                .filter(|rcpt| rcpt.contains('@'))
+        } else {
+            None
+        };
+
         // Build message
         let mail_from = self.data.mail_from.clone().unwrap();
         let rcpt_to = std::mem::take(&mut self.data.rcpt_to);
         let mut message = self
             .build_message(mail_from, rcpt_to, message_id, self.data.session_id)
             .await;
+        if trace_id.is_some() {
+            message.message.flags |= MESSAGE_TRACE;
+        }
+
+        // The TLS-Required: No header is ignored when the sender also requested REQUIRETLS
+        if is_tls_optional && (message.message.flags & MAIL_REQUIRETLS) == 0 {
+            message.message.flags |= MESSAGE_TLS_OPTIONAL;
+        }
 
         // Add Return-Path
         if self
@@ -660,8 +1055,21 @@ impl<T: SessionStream> Session<T> {
 
         // Verify queue quota
         if self.server.has_quota(&mut message).await {
+            // Verify outbound sending quotas for authenticated submissions
+            if self.is_authenticated() && !self.server.has_sending_quota(&mut message, self).await {
+                return self
+                    .trace_rejection(
+                        trace_id,
+                        "Sending quota exceeded",
+                        (b"452 4.7.1 Sending quota exceeded, try again later.\r\n"[..]).into(),
+                    )
+                    .await;
+            }
+
             // Prepare webhook event
             let queue_id = message.queue_id;
+            let queued_size = message.message.size;
+            let queued_rcpts = message.message.recipients.len();
 
             // Queue message
             let source = if !self.is_authenticated() {
@@ -692,19 +1100,222 @@ impl<T: SessionStream> Session<T> {
                 )
                 .await
             {
+                if let Some(shadow_rcpt) = shadow_rcpt {
+                    self.queue_shadow_copy(shadow_rcpt, queue_id, &headers, raw_message)
+                        .await;
+                }
+
+                if let Some(trace_id) = trace_id {
+                    self.server
+                        .trace_message(
+                            trace_id,
+                            TraceStage::Queued,
+                            format!(
+                                "Queued {queued_size} bytes for delivery to {queued_rcpts} recipient(s)"
+                            ),
+                        )
+                        .await;
+                }
+
                 self.state = State::Accepted(queue_id);
                 self.data.messages_sent += 1;
                 format!("250 2.0.0 Message queued with id {queue_id:x}.\r\n")
                     .into_bytes()
                     .into()
             } else {
-                (b"451 4.3.5 Unable to accept message at this time.\r\n"[..]).into()
+                self.trace_rejection(
+                    trace_id,
+                    "Failed to write message to the queue",
+                    (b"451 4.3.5 Unable to accept message at this time.\r\n"[..]).into(),
+                )
+                .await
             }
         } else {
-            (b"452 4.3.1 Mail system full, try again later.\r\n"[..]).into()
+            self.trace_rejection(
+                trace_id,
+                "Queue quota exceeded",
+                (b"452 4.3.1 Mail system full, try again later.\r\n"[..]).into(),
+            )
+            .await
         }
     }
 
+    async fn queue_shadow_copy(
+        &self,
+        rcpt: String,
+        parent_id: QueueId,
+        headers: &[u8],
+        raw_message: &[u8],
+    ) {
+        // Shadow copies use a null sender and never request DSNs
+        let address_lcase = rcpt.to_lowercase();
+        let rcpt = SessionAddress {
+            domain: address_lcase.domain_part().into(),
+            address: rcpt,
+            address_lcase: address_lcase.clone(),
+            flags: RCPT_NOTIFY_NEVER,
+            dsn_info: None,
+        };
+        let mail_from = SessionAddress {
+            address: String::new(),
+            address_lcase: String::new(),
+            domain: String::new(),
+            flags: 0,
+            dsn_info: None,
+        };
+        let queue_id = self.server.inner.data.queue_id_gen.generate();
+        let mut message = self
+            .build_message(mail_from, vec![rcpt], queue_id, self.data.session_id)
+            .await;
+
+        let mut shadow_headers = Vec::with_capacity(headers.len() + 64);
+        shadow_headers.extend_from_slice(format!("{SHADOW_HEADER}: {parent_id:x}\r\n").as_bytes());
+        shadow_headers.extend_from_slice(headers);
+        let size = (raw_message.len() + shadow_headers.len()) as u64;
+        message.message.size = size;
+
+        if self.server.has_quota(&mut message).await
+            && message
+                .queue(
+                    Some(&shadow_headers),
+                    raw_message,
+                    self.data.session_id,
+                    &self.server,
+                    MessageSource::Autogenerated,
+                )
+                .await
+        {
+            trc::event!(
+                Queue(trc::QueueEvent::QueueShadowCopy),
+                SpanId = self.data.session_id,
+                QueueId = queue_id,
+                Id = parent_id,
+                To = address_lcase,
+                Size = size,
+            );
+        }
+    }
+
+    async fn check_recipient_quotas(&self, size: u64) -> Option<QuotaCheck> {
+        for rcpt in &self.data.rcpt_to {
+            let resolver = RcptResolver {
+                session: self,
+                rcpt,
+            };
+            let action = self
+                .server
+                .eval_if::<QuotaCheck, _>(
+                    &self.server.core.smtp.session.data.quota_check,
+                    &resolver,
+                    self.data.session_id,
+                )
+                .await
+                .unwrap_or(QuotaCheck::Disable);
+            if action == QuotaCheck::Disable {
+                continue;
+            }
+
+            let Some((directory_id, directory)) = self
+                .server
+                .eval_if::<String, _>(
+                    &self.server.core.smtp.session.rcpt.directory,
+                    &resolver,
+                    self.data.session_id,
+                )
+                .await
+                .and_then(|name| {
+                    self.server
+                        .get_directory(&name)
+                        .map(|directory| (name, directory))
+                })
+            else {
+                continue;
+            };
+
+            // Quotas of recipients on unavailable directories are verified at delivery time
+            if !self.server.is_directory_available(&directory_id) {
+                continue;
+            }
+
+            match self.recipient_quota_usage(directory, rcpt).await {
+                Ok(Some((used, quota))) if used + size > quota => {
+                    trc::event!(
+                        Smtp(SmtpEvent::MessageOverQuota),
+                        SpanId = self.data.session_id,
+                        To = rcpt.address_lcase.clone(),
+                        Size = size,
+                        Total = used,
+                        Limit = quota,
+                    );
+
+                    return Some(action);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    trc::error!(
+                        err.span_id(self.data.session_id)
+                            .caused_by(trc::location!())
+                            .details("Failed to verify recipient quota.")
+                    );
+                    self.server
+                        .directory_failed(&directory_id, directory, self.data.session_id);
+                }
+            }
+        }
+
+        None
+    }
+
+    async fn recipient_quota_usage(
+        &self,
+        directory: &Directory,
+        rcpt: &SessionAddress,
+    ) -> trc::Result<Option<(u64, u64)>> {
+        if !directory.is_local_domain(&rcpt.domain).await? {
+            return Ok(None);
+        }
+
+        let Some(account_id) = self
+            .server
+            .email_to_id(directory, &rcpt.address_lcase, self.data.session_id)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let quota = self.server.get_access_token(account_id).await?.quota;
+        if quota == 0 {
+            return Ok(None);
+        }
+
+        self.server
+            .get_used_quota(account_id)
+            .await
+            .map(|used| Some((used.max(0) as u64, quota)))
+    }
+
+    async fn trace_rejection(
+        &self,
+        trace_id: Option<QueueId>,
+        reason: &str,
+        response: Cow<'static, [u8]>,
+    ) -> Cow<'static, [u8]> {
+        if let Some(trace_id) = trace_id {
+            self.server
+                .trace_message(
+                    trace_id,
+                    TraceStage::Rejected,
+                    format!(
+                        "{reason}: {}",
+                        String::from_utf8_lossy(&response).trim_end()
+                    ),
+                )
+                .await;
+        }
+
+        response
+    }
+
     pub async fn build_message(
         &self,
         mail_from: SessionAddress,
@@ -876,8 +1487,37 @@ impl<T: SessionStream> Session<T> {
         }
     }
 
-    fn write_received(&self, headers: &mut Vec<u8>, id: u64) {
-        headers.extend_from_slice(b"Received: from ");
+    fn write_received(&self, headers: &mut Vec<u8>, id: u64, anonymize: bool) {
+        headers.extend_from_slice(b"Received: ");
+        if !anonymize {
+            self.write_received_from(headers);
+        }
+        if self.stream.is_tls() {
+            let (version, cipher) = self.stream.tls_version_and_cipher();
+            headers.extend_from_slice(b"(using ");
+            headers.extend_from_slice(version.as_bytes());
+            headers.extend_from_slice(b" with cipher ");
+            headers.extend_from_slice(cipher.as_bytes());
+            headers.extend_from_slice(b")\r\n\t");
+        }
+        headers.extend_from_slice(b"by ");
+        headers.extend_from_slice(self.hostname.as_bytes());
+        headers.extend_from_slice(b" (Stalwart SMTP) with ");
+        headers.extend_from_slice(match (self.stream.is_tls(), !self.is_authenticated()) {
+            (true, true) => b"ESMTPS",
+            (true, false) => b"ESMTPSA",
+            (false, true) => b"ESMTP",
+            (false, false) => b"ESMTPA",
+        });
+        headers.extend_from_slice(b" id ");
+        headers.extend_from_slice(format!("{id:X}").as_bytes());
+        headers.extend_from_slice(b";\r\n\t");
+        headers.extend_from_slice(Date::now().to_rfc822().as_bytes());
+        headers.extend_from_slice(b"\r\n");
+    }
+
+    fn write_received_from(&self, headers: &mut Vec<u8>) {
+        headers.extend_from_slice(b"from ");
         headers.extend_from_slice(self.data.helo_domain.as_bytes());
         headers.extend_from_slice(b" (");
         headers.extend_from_slice(
@@ -911,27 +1551,24 @@ impl<T: SessionStream> Session<T> {
             headers.extend_from_slice(b")");
         }
         headers.extend_from_slice(b")\r\n\t");
-        if self.stream.is_tls() {
-            let (version, cipher) = self.stream.tls_version_and_cipher();
-            headers.extend_from_slice(b"(using ");
-            headers.extend_from_slice(version.as_bytes());
-            headers.extend_from_slice(b" with cipher ");
-            headers.extend_from_slice(cipher.as_bytes());
-            headers.extend_from_slice(b")\r\n\t");
+    }
+}
+
+struct RcptResolver<'x, T: SessionStream> {
+    session: &'x Session<T>,
+    rcpt: &'x SessionAddress,
+}
+
+impl<T: SessionStream> ResolveVariable for RcptResolver<'_, T> {
+    fn resolve_variable(&self, variable: u32) -> expr::Variable<'_> {
+        match variable {
+            V_RECIPIENT => self.rcpt.address_lcase.as_str().into(),
+            V_RECIPIENT_DOMAIN => self.rcpt.domain.as_str().into(),
+            _ => self.session.resolve_variable(variable),
         }
-        headers.extend_from_slice(b"by ");
-        headers.extend_from_slice(self.hostname.as_bytes());
-        headers.extend_from_slice(b" (Stalwart SMTP) with ");
-        headers.extend_from_slice(match (self.stream.is_tls(), !self.is_authenticated()) {
-            (true, true) => b"ESMTPS",
-            (true, false) => b"ESMTPSA",
-            (false, true) => b"ESMTP",
-            (false, false) => b"ESMTPA",
-        });
-        headers.extend_from_slice(b" id ");
-        headers.extend_from_slice(format!("{id:X}").as_bytes());
-        headers.extend_from_slice(b";\r\n\t");
-        headers.extend_from_slice(Date::now().to_rfc822().as_bytes());
-        headers.extend_from_slice(b"\r\n");
+    }
+
+    fn resolve_global(&self, variable: &str) -> expr::Variable<'_> {
+        self.session.resolve_global(variable)
     }
 }
@@ -6,16 +6,20 @@
 
 use crate::{core::Session, scripts::ScriptResult};
 use common::{
-    config::smtp::session::{Mechanism, Stage},
+    config::smtp::session::{
+        EhloValidationResult, HELO_ADDRESS_MISMATCH, HELO_BARE_IP, HELO_IP_MISMATCH,
+        HELO_NO_ADDRESS, HELO_NO_FCRDNS, HELO_NOT_FQDN, HELO_PTR_MISMATCH, Mechanism, Stage,
+    },
     listener::SessionStream,
 };
 use mail_auth::{
-    SpfResult,
+    IprevResult, SpfResult,
     spf::verify::{HasValidLabels, SpfParameters},
 };
 use smtp_proto::*;
 use std::{
     borrow::Cow,
+    net::IpAddr,
     time::{Duration, Instant, SystemTime},
 };
 use trc::SmtpEvent;
@@ -42,6 +46,32 @@ impl<T: SessionStream> Session<T> {
                 Domain = domain.as_ref().to_string(),
             );
 
+            // HELO validation
+            let helo_validation = if self.params.ehlo_validate {
+                let validation = self.validate_ehlo(domain.as_ref()).await;
+                if let Some(reject_score) =
+                    self.server.core.smtp.session.ehlo.validation.reject_score
+                    && validation.score >= reject_score
+                {
+                    trc::event!(
+                        Smtp(SmtpEvent::InvalidEhlo),
+                        SpanId = self.data.session_id,
+                        Domain = domain.as_ref().to_string(),
+                        Details = validation.names(),
+                        Reason = "HELO validation score exceeded",
+                    );
+
+                    return self
+                        .write(b"550 5.7.1 EHLO domain failed validation.\r\n")
+                        .await;
+                }
+                Some(validation)
+            } else {
+                None
+            };
+            let prev_helo_validation =
+                std::mem::replace(&mut self.data.helo_validation, helo_validation);
+
             // SPF check
             let prev_helo_domain =
                 std::mem::replace(&mut self.data.helo_domain, domain.into_owned());
@@ -82,6 +112,7 @@ impl<T: SessionStream> Session<T> {
                 } else {
                     self.data.mail_from = None;
                     self.data.helo_domain = prev_helo_domain;
+                    self.data.helo_validation = prev_helo_validation;
                     return Ok(());
                 }
             }
@@ -110,6 +141,7 @@ impl<T: SessionStream> Session<T> {
             {
                 self.data.mail_from = None;
                 self.data.helo_domain = prev_helo_domain;
+                self.data.helo_validation = prev_helo_validation;
                 self.data.spf_ehlo = None;
                 return self.write(message.as_bytes()).await;
             }
@@ -118,6 +150,7 @@ impl<T: SessionStream> Session<T> {
             if let Err(message) = self.run_milters(Stage::Ehlo, None).await {
                 self.data.mail_from = None;
                 self.data.helo_domain = prev_helo_domain;
+                self.data.helo_validation = prev_helo_validation;
                 self.data.spf_ehlo = None;
                 return self.write(message.message.as_bytes()).await;
             }
@@ -126,6 +159,7 @@ impl<T: SessionStream> Session<T> {
             if let Err(message) = self.run_mta_hooks(Stage::Ehlo, None, None).await {
                 self.data.mail_from = None;
                 self.data.helo_domain = prev_helo_domain;
+                self.data.helo_validation = prev_helo_validation;
                 self.data.spf_ehlo = None;
                 return self.write(message.message.as_bytes()).await;
             }
@@ -192,12 +226,13 @@ impl<T: SessionStream> Session<T> {
             response.capabilities |= EXT_VRFY;
         }
 
-        // Require TLS
-        if self
-            .server
-            .eval_if(&ec.requiretls, self, self.data.session_id)
-            .await
-            .unwrap_or(true)
+        // Require TLS, only offered over secure connections (RFC 8689)
+        if self.stream.is_tls()
+            && self
+                .server
+                .eval_if(&ec.requiretls, self, self.data.session_id)
+                .await
+                .unwrap_or(true)
         {
             response.capabilities |= EXT_REQUIRE_TLS;
         }
@@ -220,6 +255,10 @@ impl<T: SessionStream> Session<T> {
                 .await
                 .unwrap_or_default()
                 .into();
+            if response.auth_mechanisms & AUTH_EXTERNAL != 0 && self.client_certificate().is_none()
+            {
+                response.auth_mechanisms &= !AUTH_EXTERNAL;
+            }
             if response.auth_mechanisms != 0 {
                 response.capabilities |= EXT_AUTH;
             }
@@ -290,3 +329,104 @@ impl<T: SessionStream> Session<T> {
         self.write(&buf).await
     }
 }
+
+impl<T: SessionStream> Session<T> {
+    // Scores the EHLO domain using syntax, address and reverse DNS checks,
+    // results are cached per remote IP and EHLO domain.
+    async fn validate_ehlo(&mut self, domain: &str) -> EhloValidationResult {
+        let config = &self.server.core.smtp.session.ehlo.validation;
+        let domain = domain.trim_end_matches('.').to_lowercase();
+        let cache_key = format!("{}/{}", self.data.remote_ip, domain);
+        if let Some(result) = self.server.inner.cache.helo_validation.get(&cache_key) {
+            return result;
+        }
+
+        let time = Instant::now();
+        let resolver = &self.server.core.smtp.resolvers.dns;
+        let remote_ip = self.data.remote_ip;
+        let mut checks = 0;
+
+        if let Some(ip) = parse_address_literal(&domain) {
+            checks |= HELO_BARE_IP;
+            if ip != remote_ip {
+                checks |= HELO_IP_MISMATCH;
+            }
+        } else if !domain.as_str().has_valid_labels() {
+            checks |= HELO_NOT_FQDN;
+        } else {
+            let addresses = match remote_ip {
+                IpAddr::V4(_) => resolver
+                    .ipv4_lookup(&domain, Some(&self.server.inner.cache.dns_ipv4))
+                    .await
+                    .map(|ips| ips.iter().copied().map(IpAddr::from).collect::<Vec<_>>()),
+                IpAddr::V6(_) => resolver
+                    .ipv6_lookup(&domain, Some(&self.server.inner.cache.dns_ipv6))
+                    .await
+                    .map(|ips| ips.iter().copied().map(IpAddr::from).collect::<Vec<_>>()),
+            };
+            match addresses {
+                Ok(addresses) if addresses.contains(&remote_ip) => {}
+                Ok(addresses) if !addresses.is_empty() => checks |= HELO_ADDRESS_MISMATCH,
+                Ok(_) | Err(mail_auth::Error::DnsRecordNotFound(_)) => checks |= HELO_NO_ADDRESS,
+                Err(_) => {}
+            }
+        }
+
+        // Forward-confirmed reverse DNS
+        if self.data.iprev.is_none() {
+            self.data.iprev = resolver
+                .verify_iprev(self.server.inner.cache.build_auth_parameters(remote_ip))
+                .await
+                .into();
+        }
+        if let Some(iprev) = &self.data.iprev {
+            match &iprev.result {
+                IprevResult::Pass => {}
+                IprevResult::TempError(_) => {}
+                _ => checks |= HELO_NO_FCRDNS,
+            }
+            if checks & (HELO_BARE_IP | HELO_NOT_FQDN) == 0
+                && !iprev.ptr.as_ref().is_some_and(|ptr| {
+                    ptr.iter()
+                        .any(|name| name.trim_end_matches('.').eq_ignore_ascii_case(&domain))
+                })
+            {
+                checks |= HELO_PTR_MISMATCH;
+            }
+        }
+
+        let result = EhloValidationResult {
+            checks,
+            score: config.score(checks),
+        };
+
+        trc::event!(
+            Smtp(SmtpEvent::EhloValidation),
+            SpanId = self.data.session_id,
+            Domain = domain,
+            Details = result.names(),
+            Value = result.score,
+            Elapsed = time.elapsed(),
+        );
+
+        self.server
+            .inner
+            .cache
+            .helo_validation
+            .insert(cache_key, result, config.cache_ttl);
+
+        result
+    }
+}
+
+fn parse_address_literal(domain: &str) -> Option<IpAddr> {
+    let address = domain
+        .strip_prefix('[')
+        .and_then(|domain| domain.strip_suffix(']'))
+        .unwrap_or(domain);
+    address
+        .strip_prefix("ipv6:")
+        .unwrap_or(address)
+        .parse()
+        .ok()
+}
@@ -0,0 +1,94 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use common::Server;
+use directory::Directory;
+use trc::SmtpEvent;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub trait DirectoryHealth: Sync + Send {
+    fn is_directory_available(&self, id: &str) -> bool;
+
+    fn directory_succeeded(&self, id: &str);
+
+    fn directory_failed(&self, id: &str, directory: &Arc<Directory>, session_id: u64);
+}
+
+impl DirectoryHealth for Server {
+    fn is_directory_available(&self, id: &str) -> bool {
+        self.core
+            .smtp
+            .session
+            .rcpt
+            .health_check
+            .is_none_or(|health_check| {
+                self.inner
+                    .data
+                    .directory_failures
+                    .lock()
+                    .get(id)
+                    .is_none_or(|failures| *failures < health_check.max_failures)
+            })
+    }
+
+    fn directory_succeeded(&self, id: &str) {
+        if let Some(health_check) = self.core.smtp.session.rcpt.health_check {
+            let mut directory_failures = self.inner.data.directory_failures.lock();
+            // Unavailable directories are only restored by a successful probe
+            if directory_failures
+                .get(id)
+                .is_some_and(|failures| *failures < health_check.max_failures)
+            {
+                directory_failures.remove(id);
+            }
+        }
+    }
+
+    fn directory_failed(&self, id: &str, directory: &Arc<Directory>, session_id: u64) {
+        let Some(health_check) = self.core.smtp.session.rcpt.health_check else {
+            return;
+        };
+        {
+            let mut directory_failures = self.inner.data.directory_failures.lock();
+            let failures = directory_failures.entry(id.to_string()).or_default();
+            *failures = failures.saturating_add(1);
+            if *failures != health_check.max_failures {
+                return;
+            }
+        }
+
+        trc::event!(
+            Smtp(SmtpEvent::DirectoryUnavailable),
+            SpanId = session_id,
+            Id = id.to_string(),
+            Total = health_check.max_failures,
+        );
+
+        // Recipients using this directory are deferred until it answers a probe again
+        let server = self.clone();
+        let directory = directory.clone();
+        let id = id.to_string();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(health_check.interval).await;
+
+                if let Ok(Ok(_)) = tokio::time::timeout(
+                    PROBE_TIMEOUT,
+                    directory.is_local_domain("healthz.invalid"),
+                )
+                .await
+                {
+                    server.inner.data.directory_failures.lock().remove(&id);
+                    trc::event!(Smtp(SmtpEvent::DirectoryRecovered), Id = id);
+                    break;
+                }
+            }
+        });
+    }
+}
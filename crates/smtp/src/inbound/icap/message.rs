@@ -0,0 +1,142 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Instant;
+
+use common::{
+    config::smtp::session::IcapAction,
+    icap::{Verdict, icap_scan},
+    listener::SessionStream,
+};
+use trc::IcapEvent;
+
+use crate::{
+    core::Session,
+    inbound::{FilterResponse, milter::Modification},
+};
+
+impl<T: SessionStream> Session<T> {
+    pub async fn run_icap_services(
+        &self,
+        message: &[u8],
+    ) -> Result<Vec<Modification>, FilterResponse> {
+        let services = &self.server.core.smtp.session.icap;
+        if services.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut modifications = Vec::new();
+        for service in services {
+            if !self
+                .server
+                .eval_if(&service.enable, self, self.data.session_id)
+                .await
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            if message.len() > service.max_message_size {
+                trc::event!(
+                    Icap(IcapEvent::Skipped),
+                    SpanId = self.data.session_id,
+                    Id = service.id.to_string(),
+                    Size = message.len(),
+                    Limit = service.max_message_size,
+                );
+                continue;
+            }
+
+            let time = Instant::now();
+            let (action, response) = match icap_scan(
+                &self.server,
+                service,
+                message,
+                &self.data.remote_ip.to_string(),
+            )
+            .await
+            {
+                Ok(Verdict::Clean) => {
+                    trc::event!(
+                        Icap(IcapEvent::Clean),
+                        SpanId = self.data.session_id,
+                        Id = service.id.to_string(),
+                        Elapsed = time.elapsed(),
+                    );
+                    continue;
+                }
+                Ok(Verdict::Threat(threat)) => {
+                    trc::event!(
+                        Icap(IcapEvent::ThreatFound),
+                        SpanId = self.data.session_id,
+                        Id = service.id.to_string(),
+                        Details = threat.clone(),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    // Do not echo control characters back to the client
+                    let threat = threat
+                        .chars()
+                        .filter(|ch| !ch.is_control())
+                        .take(100)
+                        .collect::<String>();
+                    (
+                        service.action_threat,
+                        format!("550 5.7.1 Message rejected, threat found: {threat}\r\n"),
+                    )
+                }
+                Ok(Verdict::Modified) => {
+                    trc::event!(
+                        Icap(IcapEvent::ContentModified),
+                        SpanId = self.data.session_id,
+                        Id = service.id.to_string(),
+                        Elapsed = time.elapsed(),
+                    );
+                    (
+                        service.action_modified,
+                        "550 5.7.1 Message rejected by content policy.\r\n".to_string(),
+                    )
+                }
+                Err(err) => {
+                    trc::event!(
+                        Icap(IcapEvent::Error),
+                        SpanId = self.data.session_id,
+                        Id = service.id.to_string(),
+                        Reason = err,
+                        Elapsed = time.elapsed(),
+                    );
+
+                    if service.fail_open {
+                        continue;
+                    } else {
+                        return Err(FilterResponse::server_failure());
+                    }
+                }
+            };
+
+            match action {
+                IcapAction::Accept => {}
+                IcapAction::Reject => {
+                    return Err(FilterResponse {
+                        message: response.into(),
+                        disconnect: false,
+                    });
+                }
+                IcapAction::Discard => {
+                    return Err(FilterResponse::accept());
+                }
+                IcapAction::Quarantine => {
+                    modifications.push(Modification::AddHeader {
+                        name: "X-Quarantine".into(),
+                        value: "true".into(),
+                    });
+                }
+            }
+        }
+
+        Ok(modifications)
+    }
+}
@@ -6,10 +6,21 @@
 
 use crate::{
     core::{Session, SessionAddress},
+    reporting::abuse::AbuseReporting,
     scripts::ScriptResult,
 };
-use common::{config::smtp::session::Stage, listener::SessionStream, scripts::ScriptModification};
-use mail_auth::{IprevOutput, IprevResult, SpfOutput, SpfResult, spf::verify::SpfParameters};
+use common::{
+    config::smtp::{
+        auth::AuthOverride,
+        session::{SenderDomainPolicy, Stage},
+    },
+    listener::SessionStream,
+    scripts::ScriptModification,
+};
+use mail_auth::{
+    IprevOutput, IprevResult, SpfOutput, SpfResult, hickory_resolver::proto::rr::RecordType,
+    spf::verify::SpfParameters,
+};
 use smtp_proto::{MAIL_BY_NOTIFY, MAIL_BY_RETURN, MAIL_REQUIRETLS, MailFrom, MtPriority};
 use std::{
     borrow::Cow,
@@ -147,6 +158,60 @@ impl<T: SessionStream> Session<T> {
                 .await;
         }
 
+        // Check whether the sender has been suspended due to abuse complaints
+        if self.is_authenticated()
+            && self
+                .server
+                .is_sender_suspended(
+                    &self.data.mail_from.as_ref().unwrap().address_lcase,
+                    self.data.session_id,
+                )
+                .await
+        {
+            let mail_from = self.data.mail_from.take().unwrap();
+            trc::event!(
+                Smtp(SmtpEvent::MailFromSuspended),
+                From = mail_from.address_lcase,
+                SpanId = self.data.session_id,
+            );
+            return self
+                .write(b"550 5.7.1 Sender suspended due to abuse complaints.\r\n")
+                .await;
+        }
+
+        // Reject senders whose domain declares that it never sends mail
+        if !self.data.mail_from.as_ref().unwrap().domain.is_empty()
+            && self
+                .server
+                .eval_if::<bool, _>(
+                    &self.server.core.smtp.session.mail.reject_non_sending,
+                    self,
+                    self.data.session_id,
+                )
+                .await
+                .unwrap_or(false)
+        {
+            let domain = self.data.mail_from.as_ref().unwrap().domain.clone();
+            let policy = self.sender_domain_policy(&domain).await;
+            if policy != SenderDomainPolicy::Sending {
+                let mail_from = self.data.mail_from.take().unwrap();
+                trc::event!(
+                    Smtp(SmtpEvent::MailFromNonSendingDomain),
+                    From = mail_from.address_lcase,
+                    Domain = domain,
+                    Details = policy.as_str(),
+                    SpanId = self.data.session_id,
+                );
+                return self
+                    .write(if policy == SenderDomainPolicy::NullMx {
+                        &b"550 5.7.27 Sender address has null MX.\r\n"[..]
+                    } else {
+                        &b"550 5.7.1 Sender domain does not send mail.\r\n"[..]
+                    })
+                    .await;
+            }
+        }
+
         // Sieve filtering
         if let Some((script, script_id)) = self
             .server
@@ -290,6 +355,16 @@ impl<T: SessionStream> Session<T> {
                 .write(b"501 5.5.4 REQUIRETLS has been disabled.\r\n")
                 .await;
         }
+        if (from.flags & MAIL_REQUIRETLS) != 0 && !self.stream.is_tls() {
+            trc::event!(
+                Smtp(SmtpEvent::RequireTlsUnencrypted),
+                SpanId = self.data.session_id,
+            );
+            self.data.mail_from = None;
+            return self
+                .write(b"530 5.7.10 REQUIRETLS requires a secure connection.\r\n")
+                .await;
+        }
         if (from.flags & (MAIL_BY_NOTIFY | MAIL_BY_RETURN)) != 0 {
             if let Some(duration) = self
                 .server
@@ -526,6 +601,30 @@ impl<T: SessionStream> Session<T> {
     }
 
     pub async fn handle_spf(&mut self, spf_output: &SpfOutput, strict: bool) -> Result<bool, ()> {
+        // Apply policy overrides
+        let strict = if !matches!(spf_output.result(), SpfResult::Pass)
+            && let Some(action) = self
+                .server
+                .eval_if::<AuthOverride, _>(
+                    &self.server.core.smtp.mail_auth.spf.policy_override,
+                    self,
+                    self.data.session_id,
+                )
+                .await
+        {
+            trc::event!(
+                Smtp(SmtpEvent::SpfOverride),
+                SpanId = self.data.session_id,
+                Strict = strict,
+                Result = trc::Error::from(spf_output),
+                Details = action.as_str(),
+            );
+
+            action == AuthOverride::Reject
+        } else {
+            strict
+        };
+
         let result = match spf_output.result() {
             SpfResult::Pass => true,
             SpfResult::TempError if strict => {
@@ -584,4 +683,75 @@ impl<T: SessionStream> Session<T> {
 
         Ok(result)
     }
+
+    // Determines whether the sender domain declares that it never sends mail,
+    // either by publishing a null MX (RFC 7505) or an SPF policy of "v=spf1 -all"
+    // without any MX or address records. Results are cached per domain.
+    async fn sender_domain_policy(&self, domain: &str) -> SenderDomainPolicy {
+        if let Some(policy) = self.server.inner.cache.sender_domain.get(domain) {
+            return policy;
+        }
+
+        let resolver = &self.server.core.smtp.resolvers.dns;
+        let policy = match resolver
+            .mx_lookup(domain, Some(&self.server.inner.cache.dns_mx))
+            .await
+        {
+            Ok(mxs) => {
+                if mxs.len() == 1
+                    && mxs[0].preference == 0
+                    && mxs[0].exchanges.len() == 1
+                    && mxs[0].exchanges[0] == "."
+                {
+                    SenderDomainPolicy::NullMx
+                } else {
+                    SenderDomainPolicy::Sending
+                }
+            }
+            Err(mail_auth::Error::DnsRecordNotFound(_)) => {
+                match self
+                    .server
+                    .dns_lookup_records(domain, RecordType::TXT)
+                    .await
+                {
+                    Ok(records) => {
+                        let mut spf = records.iter().filter(|record| {
+                            record
+                                .get(..7)
+                                .is_some_and(|v| v.eq_ignore_ascii_case("v=spf1 "))
+                        });
+                        match (spf.next(), spf.next()) {
+                            (Some(record), None)
+                                if record
+                                    .split_ascii_whitespace()
+                                    .map(|term| term.to_ascii_lowercase())
+                                    .eq(["v=spf1", "-all"]) =>
+                            {
+                                match self.server.dns_exists_ip(domain).await {
+                                    Ok(false) => SenderDomainPolicy::SpfRejectAll,
+                                    _ => SenderDomainPolicy::Sending,
+                                }
+                            }
+                            _ => SenderDomainPolicy::Sending,
+                        }
+                    }
+                    Err(_) => {
+                        // Do not cache temporary failures
+                        return SenderDomainPolicy::Sending;
+                    }
+                }
+            }
+            Err(_) => {
+                return SenderDomainPolicy::Sending;
+            }
+        };
+
+        self.server.inner.cache.sender_domain.insert(
+            domain.to_string(),
+            policy,
+            self.server.core.smtp.session.mail.non_sending_cache_ttl,
+        );
+
+        policy
+    }
 }
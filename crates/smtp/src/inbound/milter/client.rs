@@ -14,7 +14,9 @@ use tokio_rustls::{TlsConnector, client::TlsStream};
 use trc::MilterEvent;
 
 use super::{
-    protocol::{SMFIC_CONNECT, SMFIC_HELO, SMFIC_MAIL, SMFIC_RCPT},
+    protocol::{
+        SMFIC_BODYEOB, SMFIC_CONNECT, SMFIC_DATA, SMFIC_EOH, SMFIC_HELO, SMFIC_MAIL, SMFIC_RCPT,
+    },
     receiver::{FrameResult, Receiver},
     *,
 };
@@ -217,7 +219,11 @@ impl<T: AsyncRead + AsyncWrite + Unpin> MilterClient<T> {
         Ok(Action::Accept)
     }
 
-    pub async fn headers<I, H, V>(&mut self, headers: I) -> super::Result<Action>
+    pub async fn headers<I, H, V>(
+        &mut self,
+        headers: I,
+        macros: Macros<'_>,
+    ) -> super::Result<Action>
     where
         I: Iterator<Item = (H, V)>,
         H: AsRef<str>,
@@ -240,6 +246,12 @@ impl<T: AsyncRead + AsyncWrite + Unpin> MilterClient<T> {
             }
 
             // Write EndOfHeaders
+            if !macros.is_empty() {
+                self.write(Command::Macro {
+                    macros: macros.with_cmd_code(SMFIC_EOH),
+                })
+                .await?;
+            }
             self.write(Command::EndOfHeader).await?;
             if !self.has_option(SMFIP_NR_EOH) {
                 return self.read().await?.into_action();
@@ -248,8 +260,14 @@ impl<T: AsyncRead + AsyncWrite + Unpin> MilterClient<T> {
         Ok(Action::Accept)
     }
 
-    pub async fn data(&mut self) -> super::Result<Action> {
+    pub async fn data(&mut self, macros: Macros<'_>) -> super::Result<Action> {
         if matches!(self.version, MilterVersion::V6) && !self.has_option(SMFIP_NODATA) {
+            if !macros.is_empty() {
+                self.write(Command::Macro {
+                    macros: macros.with_cmd_code(SMFIC_DATA),
+                })
+                .await?;
+            }
             self.write(Command::Data).await?;
             if !self.has_option(SMFIP_NR_DATA) {
                 return self.read().await?.into_action();
@@ -258,7 +276,11 @@ impl<T: AsyncRead + AsyncWrite + Unpin> MilterClient<T> {
         Ok(Action::Accept)
     }
 
-    pub async fn body(&mut self, body: &[u8]) -> super::Result<(Action, Vec<Modification>)> {
+    pub async fn body(
+        &mut self,
+        body: &[u8],
+        macros: Macros<'_>,
+    ) -> super::Result<(Action, Vec<Modification>)> {
         if !self.has_option(SMFIP_NOBODY) {
             // Write body chunks
             for value in body.chunks(MILTER_CHUNK_SIZE) {
@@ -277,6 +299,12 @@ impl<T: AsyncRead + AsyncWrite + Unpin> MilterClient<T> {
             }
 
             // Write EndOfBody
+            if !macros.is_empty() {
+                self.write(Command::Macro {
+                    macros: macros.with_cmd_code(SMFIC_BODYEOB),
+                })
+                .await?;
+            }
             self.write(Command::EndOfBody).await?;
 
             // Collect responses
@@ -22,7 +22,11 @@ impl<'x> Macros<'x> {
         self
     }
 
-    pub fn with_macro(mut self, name: &'static [u8], value: impl IntoMacroValue<'x>) -> Self {
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+
+    pub fn with_macro(mut self, name: &'x [u8], value: impl IntoMacroValue<'x>) -> Self {
         self.macros.push(Macro {
             name,
             value: value.into_macro_value(),
@@ -177,6 +181,12 @@ impl<'x> IntoMacroValue<'x> for Vec<u8> {
     }
 }
 
+impl<'x> IntoMacroValue<'x> for Cow<'x, [u8]> {
+    fn into_macro_value(self) -> Cow<'x, [u8]> {
+        self
+    }
+}
+
 impl<'x> IntoMacroValue<'x> for &'x Vec<u8> {
     fn into_macro_value(self) -> Cow<'x, [u8]> {
         Cow::Borrowed(self)
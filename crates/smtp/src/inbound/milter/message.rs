@@ -11,7 +11,7 @@ use crate::{
 };
 use common::{
     DAEMON_NAME,
-    config::smtp::session::{Milter, Stage},
+    config::smtp::session::{Milter, MilterMacroStage, Stage},
     listener::SessionStream,
 };
 use mail_auth::AuthenticatedMessage;
@@ -160,9 +160,10 @@ impl<T: SessionStream> Session<T> {
         // Build client
         let client = MilterClient::connect(milter, self.data.session_id).await?;
         if !milter.tls {
-            self.run(client, message).await
+            self.run(milter, client, message).await
         } else {
             self.run(
+                milter,
                 client
                     .into_tls(
                         if !milter.tls_allow_invalid_certs {
@@ -181,6 +182,7 @@ impl<T: SessionStream> Session<T> {
 
     async fn run<S: AsyncRead + AsyncWrite + Unpin>(
         &self,
+        milter: &Milter,
         mut client: MilterClient<S>,
         message: Option<&AuthenticatedMessage<'_>>,
     ) -> Result<Vec<Modification>, Rejection> {
@@ -188,54 +190,33 @@ impl<T: SessionStream> Session<T> {
         client.init().await?;
 
         // Connect stage
-        let client_ptr = self
-            .data
-            .iprev
-            .as_ref()
-            .and_then(|ip_rev| ip_rev.ptr.as_ref())
-            .and_then(|ptrs| ptrs.first())
-            .map(|s| s.as_str());
+        let client_ptr = self.milter_client_ptr();
         client
             .connection(
                 client_ptr.unwrap_or(self.data.helo_domain.as_str()),
                 self.data.remote_ip,
                 self.data.remote_port,
-                Macros::new()
-                    .with_daemon_name(DAEMON_NAME)
-                    .with_local_hostname(&self.hostname)
-                    .with_client_address(self.data.remote_ip)
-                    .with_client_port(self.data.remote_port)
-                    .with_client_ptr(client_ptr.unwrap_or("unknown")),
+                self.milter_macros(milter, MilterMacroStage::Connect, None),
             )
             .await?
             .assert_continue()?;
 
         // EHLO/HELO
-        let (tls_version, tls_cipher) = self.stream.tls_version_and_cipher();
         client
             .helo(
                 &self.data.helo_domain,
-                Macros::new()
-                    .with_cipher(tls_cipher.as_ref())
-                    .with_tls_version(tls_version.as_ref()),
+                self.milter_macros(milter, MilterMacroStage::Helo, None),
             )
             .await?
             .assert_continue()?;
 
         // Mail from
         if let Some(mail_from) = &self.data.mail_from {
-            let addr = &mail_from.address_lcase;
             client
                 .mail_from(
-                    &format!("<{addr}>"),
+                    &format!("<{}>", mail_from.address_lcase),
                     None::<&[&str]>,
-                    if let Some(name) = self.authenticated_as() {
-                        Macros::new()
-                            .with_mail_address(addr)
-                            .with_sasl_login_name(name)
-                    } else {
-                        Macros::new().with_mail_address(addr)
-                    },
+                    self.milter_macros(milter, MilterMacroStage::Mail, None),
                 )
                 .await?
                 .assert_continue()?;
@@ -246,7 +227,7 @@ impl<T: SessionStream> Session<T> {
                     .rcpt_to(
                         &format!("<{}>", rcpt.address_lcase),
                         None::<&[&str]>,
-                        Macros::new().with_rcpt_address(&rcpt.address_lcase),
+                        self.milter_macros(milter, MilterMacroStage::Rcpt, Some(rcpt)),
                     )
                     .await?
                     .assert_continue()?;
@@ -255,21 +236,32 @@ impl<T: SessionStream> Session<T> {
 
         if let Some(message) = message {
             // Data
-            client.data().await?.assert_continue()?;
+            client
+                .data(self.milter_macros(milter, MilterMacroStage::Data, None))
+                .await?
+                .assert_continue()?;
 
             // Headers
             client
-                .headers(message.raw_parsed_headers().iter().map(|(k, v)| {
-                    (
-                        std::str::from_utf8(k).unwrap_or_default(),
-                        std::str::from_utf8(v).unwrap_or_default(),
-                    )
-                }))
+                .headers(
+                    message.raw_parsed_headers().iter().map(|(k, v)| {
+                        (
+                            std::str::from_utf8(k).unwrap_or_default(),
+                            std::str::from_utf8(v).unwrap_or_default(),
+                        )
+                    }),
+                    self.milter_macros(milter, MilterMacroStage::EndOfHeaders, None),
+                )
                 .await?
                 .assert_continue()?;
 
             // Message body
-            let (action, modifications) = client.body(message.raw_message()).await?;
+            let (action, modifications) = client
+                .body(
+                    message.raw_message(),
+                    self.milter_macros(milter, MilterMacroStage::EndOfMessage, None),
+                )
+                .await?;
             action.assert_continue()?;
 
             // Quit
@@ -284,6 +276,96 @@ impl<T: SessionStream> Session<T> {
             Ok(Vec::new())
         }
     }
+
+    fn milter_macros<'x>(
+        &'x self,
+        milter: &'x Milter,
+        stage: MilterMacroStage,
+        rcpt: Option<&'x SessionAddress>,
+    ) -> Macros<'x> {
+        let mut macros = Macros::new();
+        if let Some(names) = milter.macros.get(&stage) {
+            for name in names {
+                if let Some(value) = self.milter_macro_value(name, rcpt) {
+                    macros = macros.with_macro(name.as_bytes(), value);
+                }
+            }
+        } else {
+            let names: &[&str] = match stage {
+                MilterMacroStage::Connect => &[
+                    "{daemon_name}",
+                    "j",
+                    "{client_addr}",
+                    "{client_port}",
+                    "{client_ptr}",
+                ],
+                MilterMacroStage::Helo => &["{cipher}", "{tls_version}"],
+                MilterMacroStage::Mail => &["{mail_addr}", "{auth_authen}"],
+                MilterMacroStage::Rcpt => &["{rcpt_addr}"],
+                MilterMacroStage::Data
+                | MilterMacroStage::EndOfHeaders
+                | MilterMacroStage::EndOfMessage => &[],
+            };
+            for name in names {
+                if let Some(value) = self.milter_macro_value(name, rcpt) {
+                    macros = macros.with_macro(name.as_bytes(), value);
+                }
+            }
+        }
+
+        macros
+    }
+
+    fn milter_macro_value<'x>(
+        &'x self,
+        name: &str,
+        rcpt: Option<&'x SessionAddress>,
+    ) -> Option<Cow<'x, [u8]>> {
+        let value: Cow<'x, str> = match name {
+            "i" => format!("{:X}", self.data.session_id).into(),
+            "j" => self.hostname.as_str().into(),
+            "_" => format!(
+                "{} [{}]",
+                self.milter_client_ptr()
+                    .unwrap_or(self.data.helo_domain.as_str()),
+                self.data.remote_ip
+            )
+            .into(),
+            "{daemon_name}" => DAEMON_NAME.into(),
+            "{daemon_addr}" => self.data.local_ip_str.as_str().into(),
+            "{daemon_port}" => self.data.local_port.to_string().into(),
+            "{client_addr}" => self.data.remote_ip_str.as_str().into(),
+            "{client_port}" => self.data.remote_port.to_string().into(),
+            "{client_ptr}" => self.milter_client_ptr().unwrap_or("unknown").into(),
+            "{client_name}" => self
+                .milter_client_ptr()
+                .map(Cow::from)
+                .unwrap_or_else(|| format!("[{}]", self.data.remote_ip).into()),
+            "{tls_version}" => self.stream.tls_version_and_cipher().0,
+            "{cipher}" => self.stream.tls_version_and_cipher().1,
+            "{auth_authen}" => self.authenticated_as()?.into(),
+            "{mail_addr}" => self.data.mail_from.as_ref()?.address_lcase.as_str().into(),
+            "{mail_host}" => self.data.mail_from.as_ref()?.domain.as_str().into(),
+            "{rcpt_addr}" => rcpt?.address_lcase.as_str().into(),
+            "{rcpt_host}" => rcpt?.domain.as_str().into(),
+            _ => return None,
+        };
+
+        match value {
+            Cow::Borrowed(value) if !value.is_empty() => Some(Cow::Borrowed(value.as_bytes())),
+            Cow::Owned(value) if !value.is_empty() => Some(Cow::Owned(value.into_bytes())),
+            _ => None,
+        }
+    }
+
+    fn milter_client_ptr(&self) -> Option<&str> {
+        self.data
+            .iprev
+            .as_ref()
+            .and_then(|ip_rev| ip_rev.ptr.as_ref())
+            .and_then(|ptrs| ptrs.first())
+            .map(|s| s.as_str())
+    }
 }
 
 impl SessionData {
@@ -293,8 +375,8 @@ impl SessionData {
         message: &AuthenticatedMessage<'_>,
     ) -> Option<Vec<u8>> {
         let mut body = Vec::new();
+        let mut added_headers = Vec::new();
         let mut header_changes = Vec::new();
-        let mut needs_rewrite = false;
 
         for modification in modifications {
             match modification {
@@ -378,11 +460,10 @@ impl SessionData {
                     body.extend(value);
                 }
                 Modification::AddHeader { name, value } => {
-                    header_changes.push((0, name, value, false));
+                    added_headers.push((name, value));
                 }
                 Modification::InsertHeader { index, name, value } => {
-                    header_changes.push((index, name, value, false));
-                    needs_rewrite = true;
+                    header_changes.push((HeaderChange::Insert(index), name, value));
                 }
                 Modification::ChangeHeader { index, name, value } => {
                     if value.is_empty()
@@ -391,20 +472,19 @@ impl SessionData {
                             .iter()
                             .any(|(n, _)| n.eq_ignore_ascii_case(name.as_bytes()))
                     {
-                        header_changes.push((index, name, value, true));
-                        needs_rewrite = true;
+                        header_changes.push((HeaderChange::Change(index), name, value));
                     } else {
-                        header_changes.push((0, name, value, false));
+                        added_headers.push((name, value));
                     }
                 }
                 Modification::Quarantine { reason } => {
-                    header_changes.push((0, "X-Quarantine".into(), reason, false));
+                    added_headers.push(("X-Quarantine".into(), reason));
                 }
             }
         }
 
         // If there are no header changes return
-        if header_changes.is_empty() {
+        if added_headers.is_empty() && header_changes.is_empty() {
             return if !body.is_empty() {
                 let mut new_message = Vec::with_capacity(body.len() + message.raw_headers().len());
                 new_message.extend_from_slice(message.raw_headers());
@@ -420,8 +500,28 @@ impl SessionData {
         } else {
             message.raw_body()
         };
+        let mut new_message = Vec::with_capacity(
+            new_body.len()
+                + message.raw_headers().len()
+                + added_headers
+                    .iter()
+                    .map(|(h, v)| (h, v))
+                    .chain(header_changes.iter().map(|(_, h, v)| (h, v)))
+                    .map(|(h, v)| h.len() + v.len() + 4)
+                    .sum::<usize>(),
+        );
+
+        // Added headers are prepended in the order they were received
+        for (header, value) in added_headers {
+            new_message.extend_from_slice(header.as_bytes());
+            new_message.extend_from_slice(b": ");
+            new_message.extend_from_slice(value.as_bytes());
+            if !value.ends_with('\n') {
+                new_message.extend_from_slice(b"\r\n");
+            }
+        }
 
-        if needs_rewrite {
+        if !header_changes.is_empty() {
             let mut headers = message
                 .raw_parsed_headers()
                 .iter()
@@ -429,56 +529,38 @@ impl SessionData {
                 .collect::<Vec<_>>();
 
             // Perform changes
-            for (index, header_name, header_value, is_change) in header_changes {
-                if is_change {
-                    let mut header_count = 0;
-                    for (pos, (name, value)) in headers.iter_mut().enumerate() {
-                        if name.eq_ignore_ascii_case(header_name.as_bytes()) {
-                            header_count += 1;
-                            if header_count == index {
-                                if !header_value.is_empty() {
-                                    *value = Cow::from(header_value.as_bytes().to_vec());
-                                } else {
-                                    headers.remove(pos);
-                                }
-                                break;
-                            }
-                        }
-                    }
-                } else {
-                    let mut header_pos = 0;
-                    if index > 0 {
+            for (change, header_name, header_value) in header_changes {
+                match change {
+                    HeaderChange::Change(index) => {
                         let mut header_count = 0;
-                        for (pos, (name, _)) in headers.iter().enumerate() {
+                        for (pos, (name, value)) in headers.iter_mut().enumerate() {
                             if name.eq_ignore_ascii_case(header_name.as_bytes()) {
-                                header_pos = pos;
                                 header_count += 1;
                                 if header_count == index {
+                                    if !header_value.is_empty() {
+                                        *value = Cow::from(header_value.as_bytes().to_vec());
+                                    } else {
+                                        headers.remove(pos);
+                                    }
                                     break;
                                 }
                             }
                         }
                     }
-
-                    headers.insert(
-                        header_pos,
-                        (
-                            Cow::from(header_name.as_bytes().to_vec()),
-                            Cow::from(header_value.as_bytes().to_vec()),
-                        ),
-                    );
+                    HeaderChange::Insert(index) => {
+                        // The index is a position in the header list, 0 being the top
+                        headers.insert(
+                            (index as usize).min(headers.len()),
+                            (
+                                Cow::from(header_name.as_bytes().to_vec()),
+                                Cow::from(header_value.as_bytes().to_vec()),
+                            ),
+                        );
+                    }
                 }
             }
 
             // Write new headers
-            let mut new_message = Vec::with_capacity(
-                new_body.len()
-                    + message.raw_headers().len()
-                    + headers
-                        .iter()
-                        .map(|(h, v)| h.len() + v.len() + 4)
-                        .sum::<usize>(),
-            );
             for (header, value) in headers {
                 new_message.extend_from_slice(header.as_ref());
                 if value.first().is_some_and(|c| c.is_ascii_whitespace()) {
@@ -492,29 +574,12 @@ impl SessionData {
                 }
             }
             new_message.extend_from_slice(b"\r\n");
-            new_message.extend(new_body);
-            Some(new_message)
         } else {
-            let mut new_message = Vec::with_capacity(
-                new_body.len()
-                    + message.raw_headers().len()
-                    + header_changes
-                        .iter()
-                        .map(|(_, h, v, _)| h.len() + v.len() + 4)
-                        .sum::<usize>(),
-            );
-            for (_, header, value, _) in header_changes {
-                new_message.extend_from_slice(header.as_bytes());
-                new_message.extend_from_slice(b": ");
-                new_message.extend_from_slice(value.as_bytes());
-                if !value.ends_with('\n') {
-                    new_message.extend_from_slice(b"\r\n");
-                }
-            }
             new_message.extend_from_slice(message.raw_headers());
-            new_message.extend(new_body);
-            Some(new_message)
         }
+
+        new_message.extend(new_body);
+        Some(new_message)
     }
 }
 
@@ -533,6 +598,11 @@ impl From<Error> for Rejection {
     }
 }
 
+enum HeaderChange {
+    Insert(u32),
+    Change(u32),
+}
+
 fn strip_brackets(addr: &str) -> String {
     let addr = addr.trim();
     if let Some(addr) = addr.strip_prefix('<') {
@@ -15,13 +15,16 @@ use mail_auth::{
 pub mod auth;
 pub mod data;
 pub mod ehlo;
+pub mod health;
 pub mod hooks;
+pub mod icap;
 pub mod mail;
 pub mod milter;
 pub mod rcpt;
 pub mod session;
 pub mod spam;
 pub mod spawn;
+pub mod transcript;
 pub mod vrfy;
 
 #[derive(Debug, Default)]
@@ -6,16 +6,18 @@
 
 use crate::{
     core::{Session, SessionAddress},
+    inbound::health::DirectoryHealth,
+    reporting::abuse::AbuseReporting,
     scripts::ScriptResult,
 };
 use common::{
     KV_GREYLIST, config::smtp::session::Stage, listener::SessionStream, scripts::ScriptModification,
 };
-use directory::backend::RcptType;
+use directory::{Directory, backend::RcptType};
 use smtp_proto::{
     RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS, RcptTo,
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::VecDeque};
 use store::dispatch::lookup::KeyValue;
 use trc::{SecurityEvent, SmtpEvent};
 use utils::DomainPart;
@@ -197,7 +199,7 @@ impl<T: SessionStream> Session<T> {
         // Verify address
         let rcpt = self.data.rcpt_to.last().unwrap();
         let mut rcpt_members = None;
-        if let Some(directory) = self
+        if let Some((directory_id, directory)) = self
             .server
             .eval_if::<String, _>(
                 &self.server.core.smtp.session.rcpt.directory,
@@ -205,8 +207,27 @@ impl<T: SessionStream> Session<T> {
                 self.data.session_id,
             )
             .await
-            .and_then(|name| self.server.get_directory(&name))
+            .and_then(|name| {
+                self.server
+                    .get_directory(&name)
+                    .map(|directory| (name, directory))
+            })
         {
+            // Defer only the recipients whose directory is down
+            if !self.server.is_directory_available(&directory_id) {
+                trc::event!(
+                    Smtp(SmtpEvent::RcptToDeferred),
+                    SpanId = self.data.session_id,
+                    To = rcpt.address_lcase.clone(),
+                    Id = directory_id,
+                );
+
+                self.data.rcpt_to.pop();
+                return self
+                    .write(b"451 4.4.3 Unable to verify address at this time.\r\n")
+                    .await;
+            }
+
             match directory.is_local_domain(&rcpt.domain).await {
                 Ok(true) => {
                     match self
@@ -214,11 +235,48 @@ impl<T: SessionStream> Session<T> {
                         .rcpt(directory, &rcpt.address_lcase, self.data.session_id)
                         .await
                     {
-                        Ok(RcptType::Mailbox) => {}
+                        Ok(RcptType::Mailbox) => {
+                            self.server.directory_succeeded(&directory_id);
+                        }
                         Ok(RcptType::List(members)) => {
-                            rcpt_members = Some(members);
+                            match self
+                                .expand_rcpt_list(directory, &rcpt.address_lcase, members)
+                                .await
+                            {
+                                Ok(Some(members)) => {
+                                    rcpt_members = Some(members);
+                                }
+                                Ok(None) => {
+                                    self.data.rcpt_to.pop();
+                                    return self
+                                        .write(
+                                            b"550 5.4.6 Mailing list expansion loop detected.\r\n",
+                                        )
+                                        .await;
+                                }
+                                Err(err) => {
+                                    trc::error!(
+                                        err.span_id(self.data.session_id)
+                                            .caused_by(trc::location!())
+                                            .details("Failed to expand mailing list.")
+                                    );
+                                    self.server.directory_failed(
+                                        &directory_id,
+                                        directory,
+                                        self.data.session_id,
+                                    );
+
+                                    self.data.rcpt_to.pop();
+                                    return self
+                                        .write(
+                                            b"451 4.4.3 Unable to verify address at this time.\r\n",
+                                        )
+                                        .await;
+                                }
+                            }
                         }
                         Ok(RcptType::Invalid) => {
+                            self.server.directory_succeeded(&directory_id);
                             trc::event!(
                                 Smtp(SmtpEvent::MailboxDoesNotExist),
                                 SpanId = self.data.session_id,
@@ -236,6 +294,11 @@ impl<T: SessionStream> Session<T> {
                                     .caused_by(trc::location!())
                                     .details("Failed to verify address.")
                             );
+                            self.server.directory_failed(
+                                &directory_id,
+                                directory,
+                                self.data.session_id,
+                            );
 
                             self.data.rcpt_to.pop();
                             return self
@@ -245,6 +308,7 @@ impl<T: SessionStream> Session<T> {
                     }
                 }
                 Ok(false) => {
+                    self.server.directory_succeeded(&directory_id);
                     if !self
                         .server
                         .eval_if(
@@ -273,6 +337,8 @@ impl<T: SessionStream> Session<T> {
                             .caused_by(trc::location!())
                             .details("Failed to verify address.")
                     );
+                    self.server
+                        .directory_failed(&directory_id, directory, self.data.session_id);
 
                     self.data.rcpt_to.pop();
                     return self
@@ -302,6 +368,29 @@ impl<T: SessionStream> Session<T> {
                 .await;
         }
 
+        // Do not deliver to recipients that reported this sender as abusive
+        if self.is_authenticated()
+            && self
+                .server
+                .is_abuse_suppressed(
+                    &self.data.mail_from.as_ref().unwrap().address_lcase,
+                    &rcpt.address_lcase,
+                    self.data.session_id,
+                )
+                .await
+        {
+            trc::event!(
+                Smtp(SmtpEvent::RcptToSuppressed),
+                SpanId = self.data.session_id,
+                To = rcpt.address_lcase.clone(),
+            );
+
+            self.data.rcpt_to.pop();
+            return self
+                .write(b"550 5.7.1 Recipient has reported messages from this sender as abuse.\r\n")
+                .await;
+        }
+
         if self.is_allowed().await {
             // Greylist
             if let Some(greylist_duration) = self
@@ -410,6 +499,69 @@ impl<T: SessionStream> Session<T> {
         self.write(b"250 2.1.5 OK\r\n").await
     }
 
+    // Expands nested mailing lists, members pointing back to a list that was already
+    // expanded are skipped and expansions deeper than the configured limit are rejected
+    async fn expand_rcpt_list(
+        &self,
+        directory: &Directory,
+        list_addr: &str,
+        members: Vec<String>,
+    ) -> trc::Result<Option<Vec<String>>> {
+        let max_depth = self.params.rcpt_max_expansion_depth;
+        let mut expanded = Vec::with_capacity(members.len());
+        let mut expanded_lists = vec![list_addr.to_string()];
+        let mut pending = members
+            .into_iter()
+            .map(|member| (member, 1))
+            .collect::<VecDeque<_>>();
+
+        while let Some((member, depth)) = pending.pop_front() {
+            let member_lcase = member.to_lowercase();
+            if expanded_lists.contains(&member_lcase) {
+                trc::event!(
+                    Smtp(SmtpEvent::RcptToLoopDetected),
+                    SpanId = self.data.session_id,
+                    To = list_addr.to_string(),
+                    Details = member_lcase,
+                );
+                continue;
+            } else if !directory
+                .is_local_domain(member_lcase.domain_part())
+                .await?
+            {
+                expanded.push(member);
+                continue;
+            }
+
+            match self
+                .server
+                .rcpt(directory, &member_lcase, self.data.session_id)
+                .await?
+            {
+                RcptType::List(members) => {
+                    if depth >= max_depth {
+                        trc::event!(
+                            Smtp(SmtpEvent::RcptToLoopDetected),
+                            SpanId = self.data.session_id,
+                            To = list_addr.to_string(),
+                            Details = member_lcase,
+                            Limit = max_depth,
+                        );
+                        return Ok(None);
+                    }
+
+                    expanded_lists.push(member_lcase);
+                    pending.extend(members.into_iter().map(|member| (member, depth + 1)));
+                }
+                RcptType::Mailbox | RcptType::Invalid => {
+                    expanded.push(member);
+                }
+            }
+        }
+
+        Ok(Some(expanded))
+    }
+
     async fn rcpt_error(&mut self, response: &[u8], rcpt: String) -> Result<(), ()> {
         tokio::time::sleep(self.params.rcpt_errors_wait).await;
         self.data.rcpt_errors += 1;
@@ -23,7 +23,7 @@ use trc::{NetworkEvent, SecurityEvent, SmtpEvent};
 
 use crate::core::{Session, State};
 
-use super::auth::SaslToken;
+use super::{AuthResult, auth::SaslToken};
 
 impl<T: SessionStream> Session<T> {
     pub async fn ingest(&mut self, bytes: &[u8]) -> Result<bool, ()> {
@@ -55,9 +55,9 @@ impl<T: SessionStream> Session<T> {
                             }
                             Request::Data => {
                                 if self.can_send_data().await? {
+                                    self.reserve_message_buffer(1024).await?;
                                     self.write(b"354 Start mail input; end with <CRLF>.<CRLF>\r\n")
                                         .await?;
-                                    self.data.message = Vec::with_capacity(1024);
                                     state = State::Data(DataReceiver::new());
                                     continue 'outer;
                                 }
@@ -69,11 +69,8 @@ impl<T: SessionStream> Session<T> {
                                 state = if chunk_size + self.data.message.len()
                                     < self.params.max_message_size
                                 {
-                                    if self.data.message.is_empty() {
-                                        self.data.message = Vec::with_capacity(chunk_size);
-                                    } else {
-                                        self.data.message.reserve(chunk_size);
-                                    }
+                                    self.reserve_message_buffer(message_capacity(chunk_size))
+                                        .await?;
                                     State::Bdat(BdatReceiver::new(chunk_size, is_last))
                                 } else {
                                     // Chunk is too large, ignore.
@@ -345,6 +342,7 @@ impl<T: SessionStream> Session<T> {
                 },
                 State::Data(receiver) => {
                     if self.data.message.len() + bytes.len() < self.params.max_message_size {
+                        self.reserve_message_buffer(iter.len()).await?;
                         if receiver.ingest(&mut iter, &mut self.data.message) {
                             let message = self.queue_message().await;
                             let num_responses = if self.instance.protocol == ServerProtocol::Smtp {
@@ -370,6 +368,7 @@ impl<T: SessionStream> Session<T> {
                     }
                 }
                 State::Bdat(receiver) => {
+                    self.reserve_message_buffer(iter.len()).await?;
                     if receiver.ingest(&mut iter, &mut self.data.message) {
                         if self.can_send_data().await? {
                             if receiver.is_last {
@@ -394,6 +393,7 @@ impl<T: SessionStream> Session<T> {
                             }
                         } else {
                             self.data.message = Vec::with_capacity(0);
+                            self.data.message_in_flight = None;
                         }
                         state = State::default();
                     } else {
@@ -435,6 +435,7 @@ impl<T: SessionStream> Session<T> {
                         );
 
                         self.data.message = Vec::with_capacity(0);
+                        self.data.message_in_flight = None;
                         self.write(b"552 5.3.4 Message too big for system.\r\n")
                             .await?;
                         state = State::default();
@@ -464,18 +465,78 @@ impl<T: SessionStream> Session<T> {
     }
 }
 
+// Sizes declared by the client are untrusted, the buffer grows as bytes arrive
+const MAX_PREALLOC_SIZE: usize = 64 * 1024;
+
+#[inline(always)]
+fn message_capacity(declared_size: usize) -> usize {
+    std::cmp::min(declared_size, MAX_PREALLOC_SIZE)
+}
+
 impl<T: AsyncWrite + AsyncRead + Unpin> Session<T> {
     pub fn reset(&mut self) {
         self.data.mail_from = None;
         self.data.spf_mail_from = None;
+        self.data.auth_results = None;
         self.data.rcpt_to.clear();
         self.data.message = Vec::with_capacity(0);
+        self.data.message_in_flight = None;
         self.data.priority = 0;
         self.data.delivery_by = 0;
         self.data.future_release = 0;
         self.data.rcpt_oks = 0;
     }
 
+    async fn reserve_message_buffer(&mut self, additional: usize) -> Result<(), ()> {
+        // Buffer growth is accounted against the global and listener budgets before allocating
+        if self.data.message_in_flight.is_none() {
+            match self.instance.message_limiter.is_allowed(0) {
+                Ok(in_flight) => {
+                    self.data.message_in_flight = Some(in_flight);
+                }
+                Err(limit) => {
+                    trc::event!(
+                        Limit(trc::LimitEvent::ConcurrentMessage),
+                        SpanId = self.data.session_id,
+                        Limit = limit,
+                    );
+
+                    self.reset();
+                    self.write(b"421 4.3.2 Too many messages in progress, try again later.\r\n")
+                        .await?;
+                    return Err(());
+                }
+            }
+        }
+
+        let len = self.data.message.len();
+        let capacity = self.data.message.capacity();
+        if len + additional > capacity {
+            let new_capacity = std::cmp::max(capacity * 2, len + additional);
+            if let Some(Err(limit)) = self
+                .data
+                .message_in_flight
+                .as_mut()
+                .map(|in_flight| in_flight.try_resize(new_capacity as u64))
+            {
+                trc::event!(
+                    Limit(trc::LimitEvent::MessageBuffer),
+                    SpanId = self.data.session_id,
+                    Size = new_capacity,
+                    Limit = limit,
+                );
+
+                self.reset();
+                self.write(b"421 4.3.1 Insufficient system resources, try again later.\r\n")
+                    .await?;
+                return Err(());
+            }
+            self.data.message.reserve_exact(new_capacity - len);
+        }
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub async fn write(&mut self, bytes: &[u8]) -> Result<(), ()> {
         match self.stream.write_all(bytes).await {
@@ -487,6 +548,9 @@ impl<T: AsyncWrite + AsyncRead + Unpin> Session<T> {
                         Size = bytes.len(),
                         Contents = trc::Value::from_maybe_string(bytes),
                     );
+                    if let Some(transcript) = &mut self.data.transcript {
+                        transcript.record_output(bytes);
+                    }
 
                     Ok(())
                 }
@@ -522,6 +586,9 @@ impl<T: AsyncWrite + AsyncRead + Unpin> Session<T> {
                     Contents =
                         String::from_utf8_lossy(bytes.get(0..len).unwrap_or_default()).into_owned(),
                 );
+                if let Some(transcript) = &mut self.data.transcript {
+                    transcript.record_input(bytes.get(0..len).unwrap_or_default());
+                }
 
                 Ok(len)
             }
@@ -577,15 +644,60 @@ impl<T: SessionStream> ResolveVariable for Session<T> {
                 .unwrap_or_default()
                 .into(),
             V_HELO_DOMAIN => self.data.helo_domain.as_str().into(),
+            V_HELO_SCORE => self
+                .data
+                .helo_validation
+                .map(|v| v.score)
+                .unwrap_or_default()
+                .into(),
             V_AUTHENTICATED_AS => self.authenticated_as().unwrap_or_default().into(),
+            V_TENANT_ID => self
+                .data
+                .authenticated_as
+                .as_ref()
+                .and_then(|token| token.tenant)
+                .map(|tenant| tenant.id.to_compact_string())
+                .unwrap_or_default()
+                .into(),
             V_LISTENER => self.instance.id.as_str().into(),
             V_REMOTE_IP => self.data.remote_ip_str.as_str().into(),
             V_REMOTE_PORT => self.data.remote_port.into(),
             V_LOCAL_IP => self.data.local_ip_str.as_str().into(),
             V_LOCAL_PORT => self.data.local_port.into(),
             V_TLS => self.stream.is_tls().into(),
+            V_TLS_JA3 | V_TLS_JA4 => self
+                .data
+                .tls_fingerprint
+                .as_ref()
+                .map(|fingerprint| fingerprint.resolve_variable(variable))
+                .unwrap_or_default(),
+            V_PROXY_ALPN | V_PROXY_SNI | V_PROXY_CLIENT_CERT | V_PROXY_CLIENT_CN => self
+                .stream
+                .proxy_info()
+                .map(|info| info.resolve_variable(variable))
+                .unwrap_or_default(),
             V_PRIORITY => self.data.priority.to_compact_string().into(),
             V_PROTOCOL => self.instance.protocol.as_str().into(),
+            V_SPF_RESULT => self
+                .data
+                .spf_mail_from
+                .as_ref()
+                .map(|spf| spf.result().as_str())
+                .unwrap_or_default()
+                .into(),
+            V_IPREV_RESULT => self
+                .data
+                .iprev
+                .as_ref()
+                .map(|iprev| iprev.result().as_str())
+                .unwrap_or_default()
+                .into(),
+            V_DKIM_RESULT | V_DKIM_DOMAINS | V_ARC_RESULT | V_DMARC_RESULT | V_DMARC_DOMAIN => self
+                .data
+                .auth_results
+                .as_ref()
+                .map(|results| results.resolve_variable(variable))
+                .unwrap_or_default(),
             V_ASN => self
                 .data
                 .asn_geo_data
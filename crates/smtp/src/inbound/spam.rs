@@ -63,6 +63,7 @@ impl<T: SessionStream> Session<T> {
             dmarc_result,
             dmarc_policy,
             iprev_result: self.data.iprev.as_ref(),
+            helo_validation: self.data.helo_validation.as_ref(),
             remote_ip: self.data.remote_ip,
             ehlo_domain: self.data.helo_domain.as_str().into(),
             authenticated_as: self.data.authenticated_as.as_ref().map(|a| a.name.as_str()),
@@ -25,15 +25,17 @@ impl SessionManager for SmtpSessionManager {
         // Build server and create session
         let server = self.inner.build_server();
         let _in_flight = session.in_flight;
+        let mut data = SessionData::new(
+            session.local_ip,
+            session.local_port,
+            session.remote_ip,
+            session.remote_port,
+            server.lookup_asn_country(session.remote_ip).await,
+            session.session_id,
+        );
+        data.tls_fingerprint = session.tls_fingerprint;
         let mut session = Session {
-            data: SessionData::new(
-                session.local_ip,
-                session.local_port,
-                session.remote_ip,
-                session.remote_port,
-                server.lookup_asn_country(session.remote_ip).await,
-                session.session_id,
-            ),
+            data,
             hostname: "".into(),
             server,
             instance: session.instance,
@@ -42,12 +44,16 @@ impl SessionManager for SmtpSessionManager {
             params: SessionParameters::default(),
         };
 
+        // Record the session dialogue if requested
+        session.start_transcript().await;
+
         // Enforce throttle
         if session.is_allowed().await
             && session.init_conn().await
             && session.handle_conn().await
             && session.instance.acceptor.is_tls()
             && let Ok(mut session) = session.into_tls().await
+            && session.is_tls_fingerprint_allowed().await
         {
             session.handle_conn().await;
         }
@@ -74,6 +80,10 @@ impl SessionManager for SmtpSessionManager {
 
 impl<T: SessionStream> Session<T> {
     pub async fn init_conn(&mut self) -> bool {
+        if !self.is_tls_fingerprint_allowed().await {
+            return false;
+        }
+
         self.eval_session_params().await;
 
         let config = &self.server.core.smtp.session.connect;
@@ -253,7 +263,41 @@ impl<T: SessionStream> Session<T> {
         false
     }
 
-    pub async fn into_tls(self) -> Result<Session<TlsStream<T>>, ()> {
+    pub async fn is_tls_fingerprint_allowed(&mut self) -> bool {
+        let config = &self.server.core.smtp.session.connect;
+
+        // TLS clients without a fingerprint cannot be on the allow list
+        let is_blocked = match &self.data.tls_fingerprint {
+            Some(fingerprint) => {
+                fingerprint.matches(&config.fingerprint_deny)
+                    || (!config.fingerprint_allow.is_empty()
+                        && !fingerprint.matches(&config.fingerprint_allow))
+            }
+            None => !config.fingerprint_allow.is_empty() && self.stream.is_tls(),
+        };
+
+        if is_blocked {
+            trc::event!(
+                Security(SecurityEvent::TlsFingerprintBlocked),
+                SpanId = self.data.session_id,
+                RemoteIp = self.data.remote_ip,
+                Id = self.data.tls_fingerprint.as_ref().map(|f| f.ja4.clone()),
+                Details = self.data.tls_fingerprint.as_ref().map(|f| f.ja3.clone()),
+            );
+
+            let _ = self.write(b"554 5.7.1 Connection rejected.\r\n").await;
+            false
+        } else {
+            true
+        }
+    }
+
+    pub async fn into_tls(mut self) -> Result<Session<TlsStream<T>>, ()> {
+        self.data.tls_fingerprint = self.instance.tls_fingerprint(&self.stream).await;
+        if let Some(fingerprint) = &self.data.tls_fingerprint {
+            fingerprint.log(&self.instance, self.data.session_id);
+        }
+
         Ok(Session {
             hostname: self.hostname,
             stream: self
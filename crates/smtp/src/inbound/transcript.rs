@@ -0,0 +1,301 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::core::Session;
+use common::{KV_SMTP_TRANSCRIPT, Server, listener::SessionStream};
+use std::{future::Future, time::Duration};
+use store::{
+    SerializeInfallible,
+    dispatch::lookup::KeyValue,
+    write::{BatchBuilder, BlobOp, now},
+};
+use trc::{AddContext, SmtpEvent};
+use types::blob_hash::BlobHash;
+
+// Records the SMTP dialogue of a session, stored in the blob store when the
+// session ends
+pub struct SessionTranscript {
+    server: Server,
+    session_id: u64,
+    data: Vec<u8>,
+    pending: Vec<u8>,
+    max_body_size: usize,
+    max_size: usize,
+    retention: Duration,
+    redact: bool,
+    redact_next: bool,
+    in_body: bool,
+    body_size: usize,
+    chunk_remaining: usize,
+    chunk_last: bool,
+}
+
+impl<T: SessionStream> Session<T> {
+    pub async fn start_transcript(&mut self) {
+        let config = &self.server.core.smtp.session.transcript;
+        if self
+            .server
+            .eval_if(&config.enable, self, self.data.session_id)
+            .await
+            .unwrap_or(false)
+        {
+            self.data.transcript = Some(SessionTranscript {
+                server: self.server.clone(),
+                session_id: self.data.session_id,
+                data: Vec::with_capacity(1024),
+                pending: Vec::new(),
+                max_body_size: config.max_body_size,
+                max_size: config.max_size,
+                retention: config.retention,
+                redact: config.redact,
+                redact_next: false,
+                in_body: false,
+                body_size: 0,
+                chunk_remaining: 0,
+                chunk_last: false,
+            });
+        }
+    }
+}
+
+impl SessionTranscript {
+    pub fn record_input(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            if self.chunk_remaining > 0 {
+                // BDAT chunk payload
+                let (chunk, rest) = bytes.split_at(bytes.len().min(self.chunk_remaining));
+                self.chunk_remaining -= chunk.len();
+                self.record_body(chunk);
+                if self.chunk_remaining == 0 && self.chunk_last {
+                    self.body_size = 0;
+                    self.chunk_last = false;
+                }
+                bytes = rest;
+            } else if self.in_body {
+                // DATA payload, terminated by the next server response
+                self.record_body(bytes);
+                break;
+            } else if let Some(pos) = bytes.iter().position(|&ch| ch == b'\n') {
+                let (line, rest) = bytes.split_at(pos + 1);
+                self.pending.extend_from_slice(line);
+                let line = std::mem::take(&mut self.pending);
+                self.record_line(&line);
+                bytes = rest;
+            } else {
+                self.pending.extend_from_slice(bytes);
+                break;
+            }
+        }
+    }
+
+    pub fn record_output(&mut self, bytes: &[u8]) {
+        // Flush any incomplete command line
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.record_line(&line);
+        }
+
+        // Output always terminates the message body
+        self.in_body = false;
+
+        for line in bytes.split_inclusive(|&ch| ch == b'\n') {
+            self.append(b"S: ");
+            self.append(line);
+        }
+
+        if self.redact {
+            self.redact_next = bytes.starts_with(b"334");
+        }
+        if bytes.starts_with(b"354") {
+            self.in_body = true;
+            self.body_size = 0;
+        }
+    }
+
+    fn record_body(&mut self, bytes: &[u8]) {
+        let previous_size = self.body_size;
+        self.body_size += bytes.len();
+        if previous_size < self.max_body_size {
+            let remaining = self.max_body_size - previous_size;
+            self.append(&bytes[..bytes.len().min(remaining)]);
+        }
+        if previous_size <= self.max_body_size && self.body_size > self.max_body_size {
+            self.append(b"\r\n[... message body truncated ...]\r\n");
+        }
+    }
+
+    fn record_line(&mut self, line: &[u8]) {
+        self.append(b"C: ");
+        if self.redact_next {
+            // Credentials sent in response to an authentication challenge
+            self.append(b"[redacted]\r\n");
+        } else if self.redact
+            && line
+                .get(..5)
+                .is_some_and(|cmd| cmd.eq_ignore_ascii_case(b"AUTH "))
+        {
+            // Keep the mechanism name, redact the initial response
+            let end = line[5..]
+                .iter()
+                .position(|ch| ch.is_ascii_whitespace())
+                .map_or(line.len(), |pos| pos + 5);
+            self.append(&line[..end]);
+            if line[end..].iter().any(|ch| !ch.is_ascii_whitespace()) {
+                self.append(b" [redacted]");
+            }
+            self.append(b"\r\n");
+        } else {
+            if line
+                .get(..5)
+                .is_some_and(|cmd| cmd.eq_ignore_ascii_case(b"BDAT "))
+            {
+                // The chunk payload follows the command line
+                let mut args = line[5..]
+                    .split(|ch| ch.is_ascii_whitespace())
+                    .filter(|arg| !arg.is_empty());
+                if let Some(size) = args
+                    .next()
+                    .and_then(|size| std::str::from_utf8(size).ok())
+                    .and_then(|size| size.parse::<usize>().ok())
+                {
+                    self.chunk_remaining = size;
+                    self.chunk_last = args
+                        .next()
+                        .is_some_and(|arg| arg.eq_ignore_ascii_case(b"LAST"));
+                }
+            }
+            self.append(line);
+        }
+    }
+
+    fn append(&mut self, bytes: &[u8]) {
+        let remaining = self.max_size.saturating_sub(self.data.len());
+        if remaining >= bytes.len() {
+            self.data.extend_from_slice(bytes);
+        } else if remaining > 0 {
+            self.data.extend_from_slice(&bytes[..remaining]);
+            self.data
+                .extend_from_slice(b"\r\n[... transcript truncated ...]\r\n");
+        }
+    }
+}
+
+impl Drop for SessionTranscript {
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.record_line(&line);
+        }
+        if self.data.is_empty() {
+            return;
+        }
+
+        let server = self.server.clone();
+        let session_id = self.session_id;
+        let data = std::mem::take(&mut self.data);
+        let retention = self.retention.as_secs();
+        tokio::spawn(async move {
+            match server.store_transcript(session_id, &data, retention).await {
+                Ok(_) => {
+                    trc::event!(
+                        Smtp(SmtpEvent::TranscriptStored),
+                        SpanId = session_id,
+                        Size = data.len(),
+                    );
+                }
+                Err(err) => {
+                    trc::error!(
+                        err.span_id(session_id)
+                            .details("Failed to store session transcript")
+                            .caused_by(trc::location!())
+                    );
+                }
+            }
+        });
+    }
+}
+
+pub trait SmtpTranscript: Sync + Send {
+    fn store_transcript(
+        &self,
+        session_id: u64,
+        data: &[u8],
+        retention: u64,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn session_transcript(
+        &self,
+        session_id: u64,
+    ) -> impl Future<Output = trc::Result<Option<Vec<u8>>>> + Send;
+}
+
+impl SmtpTranscript for Server {
+    async fn store_transcript(
+        &self,
+        session_id: u64,
+        data: &[u8],
+        retention: u64,
+    ) -> trc::Result<()> {
+        // Reserve the blob until the transcript expires
+        let hash = BlobHash::generate(data);
+        let mut batch = BatchBuilder::new();
+        batch.set(
+            BlobOp::Reserve {
+                hash: hash.clone(),
+                until: now() + retention,
+            },
+            0u32.serialize(),
+        );
+        self.store()
+            .write(batch.build_all())
+            .await
+            .caused_by(trc::location!())?;
+        self.blob_store()
+            .put_blob(hash.as_slice(), data)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Commit the blob so it is purged once the reservation expires
+        let mut batch = BatchBuilder::new();
+        batch.set(BlobOp::Commit { hash: hash.clone() }, Vec::new());
+        self.store()
+            .write(batch.build_all())
+            .await
+            .caused_by(trc::location!())?;
+
+        self.in_memory_store()
+            .key_set(
+                KeyValue::with_prefix(
+                    KV_SMTP_TRANSCRIPT,
+                    session_id.to_be_bytes(),
+                    hash.to_hex().into_bytes(),
+                )
+                .expires(retention),
+            )
+            .await
+            .caused_by(trc::location!())
+    }
+
+    async fn session_transcript(&self, session_id: u64) -> trc::Result<Option<Vec<u8>>> {
+        let Some(hash) = self
+            .in_memory_store()
+            .key_get::<String>(KeyValue::<()>::build_key(
+                KV_SMTP_TRANSCRIPT,
+                session_id.to_be_bytes(),
+            ))
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|hash| BlobHash::try_from_hex(&hash))
+        else {
+            return Ok(None);
+        };
+
+        self.blob_store()
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .caused_by(trc::location!())
+    }
+}
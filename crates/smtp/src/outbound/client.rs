@@ -4,8 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::session::SessionParams;
-use crate::queue::{Error, ErrorDetails, HostResponse, MessageWrapper, Status};
+use super::{
+    downgrade::{Downgrade, downgrade_message},
+    session::SessionParams,
+};
+use crate::queue::{
+    Error, ErrorDetails, HostResponse, MessageWrapper, Status, storage::QueueStorage,
+};
 use mail_send::{Credentials, smtp::AssertReply};
 use rustls::ClientConnection;
 use rustls_pki_types::ServerName;
@@ -174,17 +179,35 @@ impl<T: AsyncRead + AsyncWrite + Unpin> SmtpClient<T> {
         &mut self,
         message: &MessageWrapper,
         bdat_cmd: &Option<String>,
+        downgrade: Downgrade,
         params: &SessionParams<'_>,
     ) -> Result<(), Status<HostResponse<String>, ErrorDetails>> {
         match params
             .server
-            .blob_store()
-            .get_blob(message.message.blob_hash.as_slice(), 0..usize::MAX)
+            .get_queued_message(message.queue_id, &message.message.blob_hash, 0..usize::MAX)
             .await
         {
             Ok(Some(raw_message)) => {
+                let (raw_message, bdat_cmd) = match downgrade_message(&raw_message, downgrade) {
+                    Some(downgraded) => {
+                        trc::event!(
+                            Delivery(DeliveryEvent::Downgraded),
+                            SpanId = self.session_id,
+                            Hostname = params.hostname.to_string(),
+                            Size = raw_message.len(),
+                            Total = downgraded.len(),
+                        );
+
+                        let bdat_cmd = bdat_cmd
+                            .as_ref()
+                            .map(|_| format!("BDAT {} LAST\r\n", downgraded.len()));
+                        (downgraded, bdat_cmd)
+                    }
+                    None => (raw_message, bdat_cmd.clone()),
+                };
+
                 tokio::time::timeout(params.conn_strategy.timeout_data, async {
-                    if let Some(bdat_cmd) = bdat_cmd {
+                    if let Some(bdat_cmd) = &bdat_cmd {
                         trc::event!(
                             Delivery(DeliveryEvent::RawOutput),
                             SpanId = self.session_id,
@@ -687,5 +710,6 @@ pub(crate) fn from_error_details(err: &Error) -> trc::Error {
         Error::RateLimited => event.details("Rate Limited"),
         Error::ConcurrencyLimited => event.details("Concurrency Limited"),
         Error::Io(err) => event.details("I/O Error").reason(err),
+        Error::RequireTlsError(err) => event.details("REQUIRETLS Error").reason(err),
     }
 }
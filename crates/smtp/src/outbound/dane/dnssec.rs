@@ -22,6 +22,11 @@ pub trait TlsaLookup: Sync + Send {
         &self,
         key: impl IntoFqdn<'x> + Sync + Send,
     ) -> impl Future<Output = mail_auth::Result<Option<Arc<Tlsa>>>> + Send;
+
+    fn is_mx_dnssec_signed<'x>(
+        &self,
+        key: impl IntoFqdn<'x> + Sync + Send,
+    ) -> impl Future<Output = mail_auth::Result<bool>> + Send;
 }
 
 impl TlsaLookup for Server {
@@ -104,4 +109,28 @@ impl TlsaLookup for Server {
             Ok(None)
         }
     }
+
+    async fn is_mx_dnssec_signed<'x>(
+        &self,
+        key: impl IntoFqdn<'x> + Sync + Send,
+    ) -> mail_auth::Result<bool> {
+        let key = key.into_fqdn();
+
+        #[cfg(any(test, feature = "test_mode"))]
+        if true {
+            return mail_auth::common::resolver::mock_resolve(key.as_ref());
+        }
+
+        let mx_lookup = self
+            .core
+            .smtp
+            .resolvers
+            .dnssec
+            .resolver
+            .mx_lookup(Name::from_str_relaxed(key.as_ref())?)
+            .await?;
+        let mut records = mx_lookup.as_lookup().record_iter().peekable();
+
+        Ok(records.peek().is_some() && records.all(|record| record.proof().is_secure()))
+    }
 }
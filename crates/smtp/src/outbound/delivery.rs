@@ -15,18 +15,22 @@ use crate::outbound::mta_sts::lookup::MtaStsLookup;
 use crate::outbound::mta_sts::verify::VerifyPolicy;
 use crate::outbound::{client::StartTlsResult, dane::verify::TlsaVerify};
 use crate::queue::dsn::SendDsn;
+use crate::queue::sending::SendingQuotas;
 use crate::queue::spool::SmtpSpool;
 use crate::queue::throttle::IsAllowed;
+use crate::queue::trace::{MessageTrace, TraceStage};
 use crate::queue::{
-    Error, FROM_REPORT, HostResponse, MessageWrapper, QueueEnvelope, QueuedMessage, Status,
+    Error, ErrorDetails, FROM_REPORT, HostResponse, MESSAGE_TLS_OPTIONAL, MessageWrapper,
+    QueueEnvelope, QueuedMessage, Status,
 };
 use crate::reporting::SmtpReporting;
-use crate::{queue::ErrorDetails, reporting::tls::TlsRptOptions};
+use crate::reporting::tls::TlsRptOptions;
 use ahash::AHashMap;
 use common::Server;
-use common::config::smtp::queue::RoutingStrategy;
+use common::config::smtp::queue::{BounceCategory, QueueConfig, RoutingStrategy};
 use common::config::{server::ServerProtocol, smtp::report::AggregateFrequency};
 use common::ipc::{PolicyType, QueueEvent, QueueEventStatus, TlsEvent};
+use common::telemetry::metrics::labels::LabeledMetric;
 use compact_str::ToCompactString;
 use mail_auth::{
     mta_sts::TlsRpt,
@@ -83,6 +87,19 @@ impl QueuedMessage {
                         Total = message.message.recipients.len(),
                     );
 
+                    if message.is_traced() {
+                        server
+                            .trace_message(
+                                queue_id,
+                                TraceStage::Delivery,
+                                format!(
+                                    "Delivery attempt {span_id} started from queue {}",
+                                    message.queue_name
+                                ),
+                            )
+                            .await;
+                    }
+
                     // Attempt delivery
                     let start_time = Instant::now();
                     let queue_event = self.deliver_task(server.clone(), message).await;
@@ -148,7 +165,7 @@ impl QueuedMessage {
 
     async fn deliver_task(self, server: Server, mut message: MessageWrapper) -> QueueEventStatus {
         // Check that the message still has recipients to be delivered
-        let has_pending_delivery = message.has_pending_delivery();
+        let has_pending_delivery = message.has_pending_delivery(&server.core.smtp.queue);
         let span_id = message.span_id;
 
         // Send any due Delivery Status Notifications
@@ -214,6 +231,29 @@ impl QueuedMessage {
             }
         }
 
+        // Hold back messages whose sending quota was lowered or suspended
+        if let Err(retry_at) = server.is_sending_allowed(&message).await {
+            let now = now();
+            for rcpt in message.message.recipients.iter_mut() {
+                if matches!(
+                    &rcpt.status,
+                    Status::Scheduled | Status::TemporaryFailure(_)
+                ) && rcpt.retry.due <= now
+                    && rcpt.queue == message.queue_name
+                {
+                    rcpt.retry.due = retry_at;
+                    rcpt.status = Status::TemporaryFailure(ErrorDetails {
+                        entity: "localhost".to_string(),
+                        details: Error::RateLimited,
+                    });
+                }
+            }
+
+            message.save_changes(&server, self.due.into()).await;
+
+            return QueueEventStatus::Deferred;
+        }
+
         // Group recipients by route
         let queue_config = &server.core.smtp.queue;
         let now_ = now();
@@ -225,7 +265,8 @@ impl QueuedMessage {
             ) && rcpt.retry.due <= now_
                 && rcpt.queue == message.queue_name
             {
-                let envelope = QueueEnvelope::new(&message.message, rcpt);
+                let envelope =
+                    QueueEnvelope::new(&message.message, rcpt).with_bounce_category(queue_config);
                 let route = server.get_route_or_default(
                     &server
                         .eval_if::<String, _>(&queue_config.route, &envelope, message.span_id)
@@ -252,7 +293,8 @@ impl QueuedMessage {
 
             // Build envelope
             let mut envelope =
-                QueueEnvelope::new(&message.message, &message.message.recipients[rcpt_idxs[0]]);
+                QueueEnvelope::new(&message.message, &message.message.recipients[rcpt_idxs[0]])
+                    .with_bounce_category(queue_config);
 
             // Throttle recipient domain
             for throttle in &queue_config.outbound_limiters.rcpt {
@@ -287,6 +329,26 @@ impl QueuedMessage {
                     None,
                     relay_config.protocol == ServerProtocol::Smtp,
                 ),
+                RoutingStrategy::Unix(unix_config) => {
+                    // Deliver message over a UNIX socket
+                    message
+                        .deliver_unix(
+                            unix_config,
+                            &envelope,
+                            rcpt_idxs,
+                            &mut delivery_results,
+                            &server,
+                        )
+                        .await;
+                    continue 'next_route;
+                }
+                RoutingStrategy::Pipe(pipe_config) => {
+                    // Deliver message to a local command
+                    message
+                        .deliver_pipe(pipe_config, &rcpt_idxs, &mut delivery_results, &server)
+                        .await;
+                    continue 'next_route;
+                }
             };
 
             // Prepare TLS strategy
@@ -371,8 +433,15 @@ impl QueuedMessage {
                     None
                 };
 
-            // Obtain MTA-STS policy for domain
-            let mta_sts_policy = if mx_config.is_some() && tls_strategy.try_mta_sts() && is_smtp {
+            // RFC 8689 - REQUIRETLS takes precedence over a TLS-Required: No header
+            let is_require_tls = (message.message.flags & MAIL_REQUIRETLS) != 0;
+            let is_tls_optional =
+                !is_require_tls && (message.message.flags & MESSAGE_TLS_OPTIONAL) != 0;
+
+            // Obtain MTA-STS policy for domain, TLS-Required: No bypasses recipient policies
+            let mta_sts_policy = if is_tls_optional {
+                None
+            } else if mx_config.is_some() && tls_strategy.try_mta_sts() && is_smtp {
                 let time = Instant::now();
                 match server
                     .lookup_mta_sts_policy(domain, tls_strategy.timeout_mta_sts)
@@ -562,6 +631,38 @@ impl QueuedMessage {
                     ));
                     continue 'next_route;
                 }
+
+                // RFC 8689 Section 4.2.1 - REQUIRETLS needs DNSSEC-signed MX records
+                // or an MTA-STS policy to authenticate the hosts
+                if is_require_tls && mta_sts_policy.is_none() {
+                    let time = Instant::now();
+                    let status = match server.is_mx_dnssec_signed(domain).await {
+                        Ok(true) => None,
+                        Ok(false) | Err(mail_auth::Error::DnsRecordNotFound(_)) => {
+                            Status::PermanentFailure(ErrorDetails {
+                                entity: domain.to_string(),
+                                details: Error::RequireTlsError(
+                                    "MX records are not DNSSEC-signed and no MTA-STS policy was found"
+                                        .into(),
+                                ),
+                            })
+                            .into()
+                        }
+                        Err(err) => Status::from_mail_auth_error(domain, err).into(),
+                    };
+
+                    if let Some(status) = status {
+                        trc::event!(
+                            Delivery(DeliveryEvent::RequireTlsMxUnverified),
+                            SpanId = message.span_id,
+                            Domain = domain.to_string(),
+                            Elapsed = time.elapsed(),
+                        );
+
+                        delivery_results.push(DeliveryResult::domain(status, rcpt_idxs));
+                        continue 'next_route;
+                    }
+                }
             }
 
             // Try delivering message
@@ -672,7 +773,7 @@ impl QueuedMessage {
                 );
 
                 // Lookup DANE policy
-                let dane_policy = if tls_strategy.try_dane() && is_smtp {
+                let dane_policy = if tls_strategy.try_dane() && is_smtp && !is_tls_optional {
                     let time = Instant::now();
                     let strict = tls_strategy.is_dane_required();
                     match server
@@ -939,13 +1040,16 @@ impl QueuedMessage {
 
                     // Prepare TLS connector
                     let is_strict_tls = tls_strategy.is_tls_required()
-                        || (message.message.flags & MAIL_REQUIRETLS) != 0
+                        || is_require_tls
                         || mta_sts_policy.is_some()
                         || dane_policy.is_some();
-                    // As per RFC7671 Section 5.1, DANE-EE(3) allows name mismatch
-                    let tls_connector = if tls_strategy.allow_invalid_certs
-                        || remote_host.allow_invalid_certs()
+                    // As per RFC7671 Section 5.1, DANE-EE(3) allows name mismatch.
+                    // REQUIRETLS messages are never sent to unverified hosts while
+                    // TLS-Required: No accepts any certificate (RFC 8689 Section 5)
+                    let tls_connector = if (!is_require_tls
+                        && (tls_strategy.allow_invalid_certs || remote_host.allow_invalid_certs()))
                         || dane_policy.as_ref().is_some_and(|t| t.has_end_entities)
+                        || is_tls_optional
                     {
                         &server.inner.data.smtp_connectors.dummy_verify
                     } else {
@@ -1127,7 +1231,15 @@ impl QueuedMessage {
                                             .await;
                                     }
 
-                                    if is_strict_tls {
+                                    if is_require_tls && response.is_none() {
+                                        last_status = Status::PermanentFailure(ErrorDetails {
+                                            entity: envelope.mx.to_string(),
+                                            details: Error::RequireTlsError(
+                                                "STARTTLS not supported by remote host".into(),
+                                            ),
+                                        });
+                                        continue 'next_host;
+                                    } else if is_strict_tls {
                                         last_status =
                                             Status::from_starttls_error(envelope.mx, response);
                                         continue 'next_host;
@@ -1192,6 +1304,16 @@ impl QueuedMessage {
                                 Hostname = envelope.mx.to_string(),
                             );
 
+                            if is_require_tls {
+                                last_status = Status::PermanentFailure(ErrorDetails {
+                                    entity: envelope.mx.to_string(),
+                                    details: Error::RequireTlsError(
+                                        "TLS is disabled for this host".into(),
+                                    ),
+                                });
+                                continue 'next_host;
+                            }
+
                             message
                                 .deliver(smtp_client, rcpt_idxs, &mut delivery_results, params)
                                 .await
@@ -1286,6 +1408,23 @@ impl QueuedMessage {
                 Expires = message.message.expires(None).map(trc::Value::Timestamp),
             );
 
+            if message.is_traced() {
+                server
+                    .trace_message(
+                        message.queue_id,
+                        TraceStage::Rescheduled,
+                        format!(
+                            "Next retry at {}, expires at {}",
+                            message
+                                .message
+                                .next_delivery_event(None)
+                                .unwrap_or_default(),
+                            message.message.expires(None).unwrap_or_default()
+                        ),
+                    )
+                    .await;
+            }
+
             // Save changes to disk
             message.save_changes(&server, self.due.into()).await;
 
@@ -1297,6 +1436,33 @@ impl QueuedMessage {
                 Elapsed = trc::Value::Duration((now() - message.message.created) * 1000)
             );
 
+            if message
+                .message
+                .recipients
+                .iter()
+                .any(|rcpt| matches!(rcpt.status, Status::Completed(_)))
+            {
+                server.record_labeled_metric(
+                    LabeledMetric::MessagesOut,
+                    message
+                        .message
+                        .return_path
+                        .rsplit_once('@')
+                        .map(|(_, domain)| domain),
+                    None,
+                );
+            }
+
+            if message.is_traced() {
+                server
+                    .trace_message(
+                        message.queue_id,
+                        TraceStage::Completed,
+                        "Delivery completed for all recipients",
+                    )
+                    .await;
+            }
+
             // Delete message from queue
             message.remove(&server, self.due.into()).await;
 
@@ -1313,7 +1479,7 @@ pub enum PendingDelivery {
 
 impl MessageWrapper {
     /// Marks as failed all domains that reached their expiration time
-    pub fn has_pending_delivery(&mut self) -> PendingDelivery {
+    pub fn has_pending_delivery(&mut self, bounce_config: &QueueConfig) -> PendingDelivery {
         let now = now();
         let mut has_pending_delivery = false;
         let mut matches_queue = false;
@@ -1328,6 +1494,7 @@ impl MessageWrapper {
                         QueueName = self.queue_name.as_str().to_string(),
                         To = rcpt.address().to_string(),
                         Reason = from_error_details(&err.details),
+                        Type = err.details.bounce_category(bounce_config, true).as_str(),
                         Details = trc::Value::Timestamp(now),
                         Expires = rcpt
                             .expiration_time(self.message.created)
@@ -1384,10 +1551,35 @@ impl MessageWrapper {
         server: &Server,
     ) {
         let needs_retry = matches!(&status, Status::TemporaryFailure(_) | Status::Scheduled);
+        if self.is_traced() {
+            server
+                .trace_message(
+                    self.queue_id,
+                    TraceStage::Recipient,
+                    format!("<{}>: {status}", self.message.recipients[rcpt_idx].address),
+                )
+                .await;
+        }
+        if let Some(category) = status.bounce_category(&server.core.smtp.queue) {
+            trc::event!(
+                Delivery(match category {
+                    BounceCategory::BadAddress => DeliveryEvent::BounceBadAddress,
+                    BounceCategory::MailboxFull => DeliveryEvent::BounceMailboxFull,
+                    BounceCategory::ReputationBlock => DeliveryEvent::BounceReputationBlock,
+                    BounceCategory::Policy => DeliveryEvent::BouncePolicy,
+                    BounceCategory::TransientInfra => DeliveryEvent::BounceTransientInfra,
+                }),
+                SpanId = self.span_id,
+                To = self.message.recipients[rcpt_idx].address().to_string(),
+                Type = category.as_str(),
+                Details = status.to_string(),
+            );
+        }
         self.message.recipients[rcpt_idx].status = status;
 
         if needs_retry {
-            let envelope = QueueEnvelope::new(&self.message, &self.message.recipients[rcpt_idx]);
+            let envelope = QueueEnvelope::new(&self.message, &self.message.recipients[rcpt_idx])
+                .with_bounce_category(&server.core.smtp.queue);
             let queue = server.get_queue_or_default(
                 &server
                     .eval_if::<String, _>(&server.core.smtp.queue.queue, &envelope, self.span_id)
@@ -0,0 +1,641 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{borrow::Cow, ops::Range};
+
+use mail_builder::encoders::{
+    base64::base64_encode_mime, quoted_printable::quoted_printable_encode,
+};
+use mail_parser::{Encoding, HeaderName, Message, MessageParser, PartType};
+
+const TOKEN_DELIMITERS: &[char] = &[
+    '<', '>', '"', '(', ')', ',', ';', ':', ' ', '\t', '\r', '\n',
+];
+const MAX_ENCODED_WORD_INPUT: usize = 45;
+
+type Edit = (Range<usize>, Vec<u8>);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Downgrade {
+    pub headers: bool,
+    pub body: bool,
+}
+
+impl Downgrade {
+    pub fn is_enabled(&self) -> bool {
+        self.headers || self.body
+    }
+}
+
+// Converts an address to ASCII by encoding its domain name with IDNA,
+// addresses with a non-ASCII local part cannot be downgraded.
+pub fn ascii_address(address: &str) -> Option<Cow<'_, str>> {
+    if address.is_ascii() {
+        Some(Cow::Borrowed(address))
+    } else {
+        let (local, domain) = address.rsplit_once('@')?;
+        if local.is_ascii() {
+            idna::domain_to_ascii(domain)
+                .ok()
+                .map(|domain| Cow::Owned(format!("{local}@{domain}")))
+        } else {
+            None
+        }
+    }
+}
+
+// Rewrites a message for a next-hop that does not support SMTPUTF8 and/or
+// 8BITMIME. Non-ASCII header values are converted to RFC 2047 encoded-words
+// and 8-bit bodies are re-encoded using quoted-printable (text) or base64.
+// Returns None when the message does not need to be modified.
+pub fn downgrade_message(raw: &[u8], downgrade: Downgrade) -> Option<Vec<u8>> {
+    if !downgrade.is_enabled() || raw.is_ascii() {
+        return None;
+    }
+
+    let message = MessageParser::new().parse(raw)?;
+    let mut edits = Vec::new();
+    collect_edits(&message, downgrade, &mut edits);
+    if edits.is_empty() {
+        return None;
+    }
+    edits.sort_by_key(|(range, _)| range.start);
+
+    let mut result = Vec::with_capacity(raw.len() + (raw.len() / 3));
+    let mut last_pos = 0;
+    for (range, replacement) in edits {
+        // Skip overlapping edits
+        if range.start < last_pos {
+            continue;
+        }
+        result.extend_from_slice(&raw[last_pos..range.start]);
+        result.extend_from_slice(&replacement);
+        last_pos = range.end;
+    }
+    result.extend_from_slice(&raw[last_pos..]);
+
+    Some(result)
+}
+
+// Offsets of nested messages that are not transfer-encoded are relative to
+// the top-level message, encoded ones are ASCII and left untouched.
+fn collect_edits(message: &Message<'_>, downgrade: Downgrade, edits: &mut Vec<Edit>) {
+    let raw = message.raw_message();
+
+    for part in &message.parts {
+        let mut cte_header = None;
+        for header in &part.headers {
+            let start = header.offset_start as usize;
+            let end = header_end(raw, start);
+
+            if header.name == HeaderName::ContentTransferEncoding {
+                cte_header = Some(header.offset_field as usize..end);
+            } else if downgrade.headers
+                && let Some(value) = raw.get(start..end).filter(|value| !value.is_ascii())
+            {
+                edits.push((
+                    start..end,
+                    encode_header_value(&header.name, &String::from_utf8_lossy(value)).into_bytes(),
+                ));
+            }
+        }
+
+        match &part.body {
+            PartType::Message(nested) => {
+                if part.encoding == Encoding::None {
+                    collect_edits(nested, downgrade, edits);
+                }
+            }
+            PartType::Multipart(_) => {}
+            body if downgrade.body && part.encoding == Encoding::None => {
+                let body_start = part.offset_body as usize;
+                let mut body_end = part.offset_end as usize;
+                if raw
+                    .get(body_start..body_end)
+                    .is_none_or(|body| body.is_ascii())
+                {
+                    continue;
+                }
+                if raw[body_start..body_end].ends_with(b"\r\n") {
+                    body_end -= 2;
+                }
+                let contents = &raw[body_start..body_end];
+
+                let mut encoded = Vec::with_capacity(contents.len() * 4 / 3 + 64);
+                let encoding = if matches!(body, PartType::Text(_) | PartType::Html(_)) {
+                    let _ = quoted_printable_encode(contents, &mut encoded, true);
+                    "quoted-printable"
+                } else {
+                    let _ = base64_encode_mime(contents, &mut encoded, false);
+                    "base64"
+                };
+                edits.push((body_start..body_end, encoded));
+
+                match cte_header.take() {
+                    Some(range) => edits.push((
+                        range,
+                        format!("Content-Transfer-Encoding: {encoding}").into_bytes(),
+                    )),
+                    None => {
+                        let pos = part.offset_header as usize;
+                        edits.push((
+                            pos..pos,
+                            format!("Content-Transfer-Encoding: {encoding}\r\n").into_bytes(),
+                        ))
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn encode_header_value(name: &HeaderName<'_>, value: &str) -> String {
+    match name {
+        HeaderName::From
+        | HeaderName::To
+        | HeaderName::Cc
+        | HeaderName::Bcc
+        | HeaderName::ReplyTo
+        | HeaderName::Sender
+        | HeaderName::ResentFrom
+        | HeaderName::ResentTo
+        | HeaderName::ResentCc
+        | HeaderName::ResentBcc
+        | HeaderName::ResentSender => encode_address_list(value),
+        HeaderName::ContentType | HeaderName::ContentDisposition => encode_mime_parameters(value),
+        _ => encode_unstructured(value),
+    }
+}
+
+// Encodes the span between the first and the last non-ASCII word of an
+// unstructured header value.
+fn encode_unstructured(value: &str) -> String {
+    let mut span: Option<Range<usize>> = None;
+    let mut word_start = None;
+
+    for (pos, ch) in value.char_indices().chain([(value.len(), ' ')]) {
+        if matches!(ch, ' ' | '\t' | '\r' | '\n') {
+            if let Some(start) = word_start.take()
+                && !value[start..pos].is_ascii()
+            {
+                span = Some(span.map_or(start, |span| span.start)..pos);
+            }
+        } else if word_start.is_none() {
+            word_start = Some(pos);
+        }
+    }
+
+    match span {
+        Some(span) => {
+            let mut result = String::with_capacity(value.len() * 2);
+            result.push_str(&value[..span.start]);
+            encode_word(&unfold(&value[span.clone()]), &mut result);
+            result.push_str(&value[span.end..]);
+            result
+        }
+        None => value.to_string(),
+    }
+}
+
+// Encodes address lists, display names containing non-ASCII characters are
+// replaced as a whole phrase since RFC 2047 does not allow encoded-words
+// inside quoted strings. Domain names are converted to IDNA.
+fn encode_address_list(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() * 2);
+    let mut phrase = Phrase::default();
+    let mut chars = value.char_indices().peekable();
+
+    while let Some((pos, ch)) = chars.next() {
+        match ch {
+            ' ' | '\t' | '\r' | '\n' => {
+                // Whitespace inside a phrase is written when it is flushed
+                if phrase.is_empty() {
+                    result.push(ch);
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+                let mut end = value.len();
+                let mut is_escaped = false;
+                for (pos, ch) in chars.by_ref() {
+                    if is_escaped {
+                        text.push(ch);
+                        is_escaped = false;
+                    } else if ch == '\\' {
+                        is_escaped = true;
+                    } else if ch == '"' {
+                        end = pos + 1;
+                        break;
+                    } else {
+                        text.push(ch);
+                    }
+                }
+                phrase.push(pos..end, text);
+            }
+            '<' => {
+                phrase.flush(value, pos, &mut result);
+                let end = value[pos..]
+                    .find('>')
+                    .map_or(value.len(), |offset| pos + offset);
+                let address = &value[pos + 1..end];
+                result.push('<');
+                result.push_str(ascii_address(address).as_deref().unwrap_or(address));
+                while chars.next_if(|(next_pos, _)| *next_pos < end).is_some() {}
+            }
+            '>' | ',' | ';' | ':' | '(' | ')' => {
+                phrase.flush(value, pos, &mut result);
+                result.push(ch);
+            }
+            _ => {
+                let end = loop {
+                    match chars.peek().copied() {
+                        Some((next_pos, next_ch)) if TOKEN_DELIMITERS.contains(&next_ch) => {
+                            break next_pos;
+                        }
+                        Some(_) => {
+                            chars.next();
+                        }
+                        None => break value.len(),
+                    }
+                };
+                let word = &value[pos..end];
+                if word.contains('@') {
+                    // Encoded-words are not allowed inside addr-spec
+                    phrase.flush(value, pos, &mut result);
+                    result.push_str(ascii_address(word).as_deref().unwrap_or(word));
+                } else {
+                    phrase.push(pos..end, word.to_string());
+                }
+            }
+        }
+    }
+    phrase.flush(value, value.len(), &mut result);
+
+    result
+}
+
+#[derive(Default)]
+struct Phrase {
+    range: Option<Range<usize>>,
+    words: Vec<String>,
+}
+
+impl Phrase {
+    fn is_empty(&self) -> bool {
+        self.range.is_none()
+    }
+
+    fn push(&mut self, range: Range<usize>, word: String) {
+        self.range = Some(
+            self.range
+                .as_ref()
+                .map_or(range.start, |phrase| phrase.start)..range.end,
+        );
+        self.words.push(word);
+    }
+
+    fn flush(&mut self, value: &str, pos: usize, result: &mut String) {
+        if let Some(range) = self.range.take() {
+            let text = &value[range.clone()];
+            if text.is_ascii() {
+                result.push_str(text);
+            } else {
+                encode_word(&self.words.join(" "), result);
+            }
+            result.push_str(&value[range.end..pos]);
+            self.words.clear();
+        }
+    }
+}
+
+// Encodes non-ASCII MIME parameters using RFC 2231.
+fn encode_mime_parameters(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() * 2);
+    let mut in_quotes = false;
+    let mut is_escaped = false;
+    let mut start = 0;
+
+    for (pos, ch) in value.char_indices().chain([(value.len(), ';')]) {
+        match ch {
+            _ if is_escaped => is_escaped = false,
+            '\\' if in_quotes => is_escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes || pos == value.len() => {
+                encode_mime_parameter(&value[start..pos], &mut result);
+                if pos < value.len() {
+                    result.push(';');
+                }
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+fn encode_mime_parameter(parameter: &str, result: &mut String) {
+    if parameter.is_ascii() {
+        result.push_str(parameter);
+        return;
+    }
+
+    let trimmed = parameter.trim_start();
+    match trimmed.split_once('=') {
+        Some((name, value)) if !name.trim_end().ends_with('*') => {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .map(unquote)
+                .unwrap_or(Cow::Borrowed(value));
+
+            result.push_str(&parameter[..parameter.len() - trimmed.len()]);
+            result.push_str(name.trim_end());
+            result.push_str("*=utf-8''");
+            for &byte in value.as_bytes() {
+                if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+                    result.push(byte as char);
+                } else {
+                    result.push_str(&format!("%{byte:02X}"));
+                }
+            }
+        }
+        _ => result.push_str(&encode_unstructured(parameter)),
+    }
+}
+
+fn unquote(text: &str) -> Cow<'_, str> {
+    if text.contains('\\') {
+        let mut result = String::with_capacity(text.len());
+        let mut is_escaped = false;
+        for ch in text.chars() {
+            if ch == '\\' && !is_escaped {
+                is_escaped = true;
+            } else {
+                result.push(ch);
+                is_escaped = false;
+            }
+        }
+        Cow::Owned(result)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn unfold(text: &str) -> Cow<'_, str> {
+    if text.contains('\n') {
+        Cow::Owned(text.replace("\r\n", "").replace('\n', ""))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+// Encoded-words are folded onto separate lines to stay within the
+// line length limits.
+fn encode_word(text: &str, result: &mut String) {
+    let mut chunk_start = 0;
+
+    for (pos, ch) in text.char_indices() {
+        if pos + ch.len_utf8() - chunk_start > MAX_ENCODED_WORD_INPUT {
+            push_encoded_word(&text[chunk_start..pos], result);
+            result.push_str("\r\n ");
+            chunk_start = pos;
+        }
+    }
+    push_encoded_word(&text[chunk_start..], result);
+}
+
+fn push_encoded_word(text: &str, result: &mut String) {
+    let mut encoded = Vec::with_capacity(text.len() * 4 / 3 + 4);
+    let _ = base64_encode_mime(text.as_bytes(), &mut encoded, true);
+    result.push_str("=?utf-8?B?");
+    result.push_str(std::str::from_utf8(&encoded).unwrap_or_default());
+    result.push_str("?=");
+}
+
+// Returns the end of a header value, including folded lines but excluding
+// the final line break.
+fn header_end(raw: &[u8], start: usize) -> usize {
+    let mut pos = start;
+    while let Some(offset) = raw.get(pos..).and_then(|bytes| memchr(b'\n', bytes)) {
+        let lf_pos = pos + offset;
+        if matches!(raw.get(lf_pos + 1), Some(b' ' | b'\t')) {
+            pos = lf_pos + 1;
+        } else if lf_pos > start && raw[lf_pos - 1] == b'\r' {
+            return lf_pos - 1;
+        } else {
+            return lf_pos;
+        }
+    }
+    raw.len()
+}
+
+#[inline(always)]
+fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&ch| ch == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use mail_parser::{MessageParser, MimeHeaders};
+
+    use super::{Downgrade, downgrade_message};
+
+    const DOWNGRADE_ALL: Downgrade = Downgrade {
+        headers: true,
+        body: true,
+    };
+
+    fn downgrade(raw: &[u8], downgrade: Downgrade) -> String {
+        let result = downgrade_message(raw, downgrade).expect("message was not downgraded");
+        let result = String::from_utf8(result).unwrap();
+        assert!(result.is_ascii(), "{result}");
+        result
+    }
+
+    #[test]
+    fn downgrade_headers() {
+        let raw = concat!(
+            "From: \"Jöhn Doe\" <john@bücher.example>\r\n",
+            "To: Ñame Smith <jane@example.com>, plain@example.com\r\n",
+            "Cc: \"Doe, J.\" Ñ <jd@example.com>\r\n",
+            "Subject: Hello wörld and more\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "\r\n",
+            "Hello\r\n"
+        );
+        let result = downgrade(
+            raw.as_bytes(),
+            Downgrade {
+                headers: true,
+                body: false,
+            },
+        );
+
+        assert_eq!(
+            result,
+            concat!(
+                "From: =?utf-8?B?SsO2aG4gRG9l?= <john@xn--bcher-kva.example>\r\n",
+                "To: =?utf-8?B?w5FhbWUgU21pdGg=?= <jane@example.com>, plain@example.com\r\n",
+                "Cc: =?utf-8?B?RG9lLCBKLiDDkQ==?= <jd@example.com>\r\n",
+                "Subject: Hello =?utf-8?B?d8O2cmxk?= and more\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "\r\n",
+                "Hello\r\n"
+            )
+        );
+
+        let message = MessageParser::new().parse(result.as_bytes()).unwrap();
+        let from = message.from().and_then(|from| from.first()).unwrap();
+        assert_eq!(from.name(), Some("Jöhn Doe"));
+        assert_eq!(from.address(), Some("john@xn--bcher-kva.example"));
+        assert_eq!(
+            message
+                .cc()
+                .and_then(|cc| cc.first())
+                .and_then(|cc| cc.name()),
+            Some("Doe, J. Ñ")
+        );
+        assert_eq!(message.subject(), Some("Hello wörld and more"));
+
+        // Long values are split into multiple encoded-words
+        let subject = "Ünïcödé ".repeat(10);
+        let result = downgrade(
+            format!("Subject: {subject}\r\n\r\nHello\r\n").as_bytes(),
+            DOWNGRADE_ALL,
+        );
+        assert!(result.contains("?=\r\n =?utf-8?B?"), "{result}");
+        assert_eq!(
+            MessageParser::new()
+                .parse(result.as_bytes())
+                .unwrap()
+                .subject(),
+            Some(subject.trim_end())
+        );
+    }
+
+    #[test]
+    fn downgrade_body() {
+        let mut raw = concat!(
+            "From: john@example.com\r\n",
+            "Content-Type: multipart/mixed; boundary=\"b\"\r\n",
+            "\r\n",
+            "--b\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "\r\n",
+            "Grüße\r\n",
+            "--b\r\n",
+            "Content-Type: application/octet-stream\r\n",
+            "Content-Transfer-Encoding: 8bit\r\n",
+            "\r\n",
+        )
+        .as_bytes()
+        .to_vec();
+        raw.extend_from_slice(b"\x00\xff\xfe binary\r\n--b--\r\n");
+
+        let result = downgrade(&raw, DOWNGRADE_ALL);
+        assert!(result.contains("Gr=C3=BC=C3=9Fe\r\n"), "{result}");
+        assert!(
+            result.contains("Content-Transfer-Encoding: quoted-printable\r\n"),
+            "{result}"
+        );
+        assert!(
+            result.contains("Content-Transfer-Encoding: base64\r\n"),
+            "{result}"
+        );
+        assert!(!result.contains("8bit"), "{result}");
+
+        let message = MessageParser::new().parse(result.as_bytes()).unwrap();
+        assert_eq!(message.body_text(0).unwrap(), "Grüße");
+        assert_eq!(
+            message.attachment(0).unwrap().contents(),
+            b"\x00\xff\xfe binary"
+        );
+
+        // Headers are left untouched when only the body is downgraded
+        let result = downgrade_message(
+            "Subject: Grüße\r\n\r\nGrüße\r\n".as_bytes(),
+            Downgrade {
+                headers: false,
+                body: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(result).unwrap(),
+            concat!(
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "Subject: Grüße\r\n",
+                "\r\n",
+                "Gr=C3=BC=C3=9Fe\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn downgrade_nested_message() {
+        let raw = concat!(
+            "From: john@example.com\r\n",
+            "Subject: Outer\r\n",
+            "Content-Type: multipart/mixed; boundary=\"b\"\r\n",
+            "\r\n",
+            "--b\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "See attached\r\n",
+            "--b\r\n",
+            "Content-Type: message/rfc822\r\n",
+            "Content-Disposition: attachment; filename=\"Café.eml\"\r\n",
+            "\r\n",
+            "From: \"Zoë\" <zoe@example.com>\r\n",
+            "Subject: Ça va?\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "\r\n",
+            "Très bien\r\n",
+            "--b--\r\n"
+        );
+        let result = downgrade(raw.as_bytes(), DOWNGRADE_ALL);
+        assert!(
+            result.contains("attachment; filename*=utf-8''Caf%C3%A9.eml\r\n"),
+            "{result}"
+        );
+        assert!(
+            result.contains("From: =?utf-8?B?Wm/Dqw==?= <zoe@example.com>\r\n"),
+            "{result}"
+        );
+        assert!(!result.contains("\"=?"), "{result}");
+
+        let message = MessageParser::new().parse(result.as_bytes()).unwrap();
+        assert_eq!(message.subject(), Some("Outer"));
+        assert_eq!(message.body_text(0).unwrap(), "See attached");
+        let attachment = message.attachment(0).unwrap();
+        assert_eq!(attachment.attachment_name(), Some("Café.eml"));
+        let nested = attachment.message().unwrap();
+        assert_eq!(
+            nested
+                .from()
+                .and_then(|from| from.first())
+                .and_then(|from| from.name()),
+            Some("Zoë")
+        );
+        assert_eq!(nested.subject(), Some("Ça va?"));
+        assert_eq!(nested.body_text(0).unwrap(), "Très bien");
+    }
+
+    #[test]
+    fn downgrade_not_required() {
+        assert!(downgrade_message(b"Subject: Hello\r\n\r\nHello\r\n", DOWNGRADE_ALL).is_none());
+        assert!(
+            downgrade_message(
+                "Subject: Grüße\r\n\r\nGrüße\r\n".as_bytes(),
+                Downgrade::default()
+            )
+            .is_none()
+        );
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
     queue::{
         Error, ErrorDetails, FROM_AUTHENTICATED, FROM_UNAUTHENTICATED_DMARC, HostResponse,
         MessageSource, MessageWrapper, Status, UnexpectedResponse, quota::HasQueueQuota,
-        spool::SmtpSpool,
+        spool::SmtpSpool, storage::QueueStorage,
     },
     reporting::SmtpReporting,
 };
@@ -34,6 +34,23 @@ impl MessageWrapper {
             pending_recipients.push((rcpt_idx, rcpt_addr));
         }
 
+        // Messages spooled to disk are not available in the blob store
+        let message_data = if server.core.smtp.queue.spool.path().is_some() {
+            server
+                .get_queued_message(self.queue_id, &self.message.blob_hash, 0..usize::MAX)
+                .await
+                .map_err(|err| {
+                    trc::error!(
+                        err.span_id(self.span_id)
+                            .details("Failed to fetch spooled message")
+                            .caused_by(trc::location!())
+                    );
+                })
+                .unwrap_or_default()
+        } else {
+            None
+        };
+
         // Deliver message
         let delivery_result = server
             .deliver_message(IngestMessage {
@@ -43,6 +60,7 @@ impl MessageWrapper {
                     != 0,
                 recipients: recipient_addresses,
                 message_blob: self.message.blob_hash.clone(),
+                message_data,
                 message_size: self.message.size,
                 session_id: self.span_id,
             })
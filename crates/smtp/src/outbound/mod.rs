@@ -17,10 +17,12 @@ use std::borrow::Cow;
 pub mod client;
 pub mod dane;
 pub mod delivery;
+pub mod downgrade;
 pub mod local;
 pub mod lookup;
 pub mod mta_sts;
 pub mod session;
+pub mod transport;
 
 pub(super) enum DeliveryResult {
     Domain {
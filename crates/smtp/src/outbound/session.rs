@@ -7,17 +7,18 @@
 use super::client::SmtpClient;
 use crate::outbound::DeliveryResult;
 use crate::outbound::client::{from_error_status, from_mail_send_error};
+use crate::outbound::downgrade::{Downgrade, ascii_address};
 use crate::queue::{Error, MessageWrapper, Recipient, Status};
 use crate::queue::{ErrorDetails, HostResponse, UnexpectedResponse};
 use common::Server;
-use common::config::smtp::queue::ConnectionStrategy;
+use common::config::smtp::queue::{ConnectionStrategy, Utf8Downgrade};
 use mail_send::Credentials;
 use smtp_proto::{
-    EXT_CHUNKING, EXT_DSN, EXT_REQUIRE_TLS, EXT_SIZE, EXT_SMTP_UTF8, EhloResponse, MAIL_REQUIRETLS,
-    MAIL_RET_FULL, MAIL_RET_HDRS, MAIL_SMTPUTF8, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE,
-    RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS, Severity,
+    EXT_8BIT_MIME, EXT_CHUNKING, EXT_DSN, EXT_REQUIRE_TLS, EXT_SIZE, EXT_SMTP_UTF8, EhloResponse,
+    MAIL_REQUIRETLS, MAIL_RET_FULL, MAIL_RET_HDRS, MAIL_SMTPUTF8, RCPT_NOTIFY_DELAY,
+    RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS, Response, Severity,
 };
-use std::{fmt::Write, time::Instant};
+use std::{borrow::Cow, fmt::Write, time::Instant};
 use tokio::io::{AsyncRead, AsyncWrite};
 use trc::DeliveryEvent;
 
@@ -72,6 +73,30 @@ impl MessageWrapper {
             }
         };
 
+        // RFC 8689 - Messages requiring TLS can only be relayed to hosts supporting REQUIRETLS
+        if params.is_smtp
+            && self.has_flag(MAIL_REQUIRETLS)
+            && !capabilities.has_capability(EXT_REQUIRE_TLS)
+        {
+            trc::event!(
+                Delivery(DeliveryEvent::RequireTlsUnavailable),
+                SpanId = params.session_id,
+                Hostname = params.hostname.to_string(),
+            );
+
+            smtp_client.quit().await;
+            statuses.push(DeliveryResult::domain(
+                Status::PermanentFailure(ErrorDetails {
+                    entity: params.hostname.into(),
+                    details: Error::RequireTlsError(
+                        "REQUIRETLS not supported by remote host".into(),
+                    ),
+                }),
+                rcpt_idxs,
+            ));
+            return;
+        }
+
         // Authenticate
         if let Some(credentials) = params.credentials {
             let time = Instant::now();
@@ -117,10 +142,39 @@ impl MessageWrapper {
             };*/
         }
 
+        // Downgrade SMTPUTF8 and 8BITMIME messages for servers lacking support
+        let utf8_downgrade = if capabilities.has_capability(EXT_SMTP_UTF8) {
+            Utf8Downgrade::Disable
+        } else {
+            params.conn_strategy.downgrade_utf8
+        };
+        let downgrade = Downgrade {
+            headers: utf8_downgrade == Utf8Downgrade::Encode,
+            body: params.conn_strategy.downgrade_8bit
+                && !capabilities.has_capability(EXT_8BIT_MIME),
+        };
+        let Some(return_path) =
+            utf8_downgrade.address(&self.message.return_path, self.has_flag(MAIL_SMTPUTF8))
+        else {
+            trc::event!(
+                Delivery(DeliveryEvent::DowngradeFailed),
+                SpanId = params.session_id,
+                Hostname = params.hostname.to_string(),
+                From = self.message.return_path.to_string(),
+            );
+
+            smtp_client.quit().await;
+            statuses.push(DeliveryResult::domain(
+                smtputf8_unsupported(params.hostname, "MAIL FROM"),
+                rcpt_idxs,
+            ));
+            return;
+        };
+
         // MAIL FROM
         let time = Instant::now();
         smtp_client.timeout = params.conn_strategy.timeout_mail;
-        let cmd = self.build_mail_from(&capabilities);
+        let cmd = self.build_mail_from(&return_path, &capabilities);
         match smtp_client.cmd(cmd.as_bytes()).await.and_then(|r| {
             if r.is_positive_completion() {
                 Ok(r)
@@ -170,7 +224,22 @@ impl MessageWrapper {
                 continue;
             }
 
-            let cmd = self.build_rcpt_to(rcpt, &capabilities);
+            let Some(address) = utf8_downgrade.address(rcpt.address(), false) else {
+                trc::event!(
+                    Delivery(DeliveryEvent::DowngradeFailed),
+                    SpanId = params.session_id,
+                    Hostname = params.hostname.to_string(),
+                    To = rcpt.address().to_string(),
+                );
+
+                statuses.push(DeliveryResult::account(
+                    smtputf8_unsupported(params.hostname, "RCPT TO"),
+                    *rcpt_idx,
+                ));
+                continue;
+            };
+
+            let cmd = self.build_rcpt_to(rcpt, &address, &capabilities);
             match smtp_client.cmd(cmd.as_bytes()).await {
                 Ok(response) => match response.severity() {
                     Severity::PositiveCompletion => {
@@ -249,7 +318,10 @@ impl MessageWrapper {
                 .has_capability(EXT_CHUNKING)
                 .then(|| format!("BDAT {} LAST\r\n", self.message.size));
 
-            if let Err(status) = smtp_client.send_message(self, &bdat_cmd, &params).await {
+            if let Err(status) = smtp_client
+                .send_message(self, &bdat_cmd, downgrade, &params)
+                .await
+            {
                 trc::event!(
                     Delivery(DeliveryEvent::MessageRejected),
                     SpanId = params.session_id,
@@ -397,9 +469,9 @@ impl MessageWrapper {
         smtp_client.quit().await;
     }
 
-    fn build_mail_from(&self, capabilities: &EhloResponse<String>) -> String {
-        let mut mail_from = String::with_capacity(self.message.return_path.len() + 60);
-        let _ = write!(mail_from, "MAIL FROM:<{}>", self.message.return_path);
+    fn build_mail_from(&self, return_path: &str, capabilities: &EhloResponse<String>) -> String {
+        let mut mail_from = String::with_capacity(return_path.len() + 60);
+        let _ = write!(mail_from, "MAIL FROM:<{return_path}>");
         if capabilities.has_capability(EXT_SIZE) {
             let _ = write!(mail_from, " SIZE={}", self.message.size);
         }
@@ -424,9 +496,14 @@ impl MessageWrapper {
         mail_from
     }
 
-    fn build_rcpt_to(&self, rcpt: &Recipient, capabilities: &EhloResponse<String>) -> String {
-        let mut rcpt_to = String::with_capacity(rcpt.address().len() + 60);
-        let _ = write!(rcpt_to, "RCPT TO:<{}>", rcpt.address());
+    fn build_rcpt_to(
+        &self,
+        rcpt: &Recipient,
+        address: &str,
+        capabilities: &EhloResponse<String>,
+    ) -> String {
+        let mut rcpt_to = String::with_capacity(address.len() + 60);
+        let _ = write!(rcpt_to, "RCPT TO:<{address}>");
         if capabilities.has_capability(EXT_DSN) {
             if rcpt.has_flag(RCPT_NOTIFY_SUCCESS | RCPT_NOTIFY_FAILURE | RCPT_NOTIFY_DELAY) {
                 rcpt_to.push_str(" NOTIFY=");
@@ -464,6 +541,39 @@ impl MessageWrapper {
     }
 }
 
+trait DowngradeAddress {
+    fn address<'x>(&self, address: &'x str, requires_utf8: bool) -> Option<Cow<'x, str>>;
+}
+
+impl DowngradeAddress for Utf8Downgrade {
+    fn address<'x>(&self, address: &'x str, requires_utf8: bool) -> Option<Cow<'x, str>> {
+        match self {
+            Utf8Downgrade::Encode => ascii_address(address),
+            Utf8Downgrade::Reject if requires_utf8 || !address.is_ascii() => None,
+            _ => Some(Cow::Borrowed(address)),
+        }
+    }
+}
+
+fn smtputf8_unsupported(
+    hostname: &str,
+    command: &str,
+) -> Status<HostResponse<String>, ErrorDetails> {
+    Status::PermanentFailure(ErrorDetails {
+        entity: hostname.into(),
+        details: Error::UnexpectedResponse(UnexpectedResponse {
+            command: command.into(),
+            response: Response {
+                code: 553,
+                esc: [5, 6, 7],
+                message:
+                    "Non-ASCII addresses not permitted, remote server does not support SMTPUTF8"
+                        .into(),
+            },
+        }),
+    })
+}
+
 impl Recipient {
     #[inline(always)]
     pub fn has_flag(&self, flag: u64) -> bool {
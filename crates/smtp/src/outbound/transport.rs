@@ -0,0 +1,338 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{DeliveryResult, client::SmtpClient, session::SessionParams};
+use crate::outbound::client::from_mail_send_error;
+use crate::queue::{
+    Error, ErrorDetails, HostResponse, MessageWrapper, QueueEnvelope, Status, UnexpectedResponse,
+    storage::QueueStorage,
+};
+use common::{
+    Server,
+    config::{
+        server::ServerProtocol,
+        smtp::queue::{PipeConfig, UnixConfig},
+    },
+};
+use smtp_proto::Response;
+use std::{process::Stdio, time::Instant};
+use tokio::{io::AsyncWriteExt, net::UnixStream, process::Command};
+use trc::DeliveryEvent;
+
+impl MessageWrapper {
+    pub(super) async fn deliver_unix(
+        &self,
+        config: &UnixConfig,
+        envelope: &QueueEnvelope<'_>,
+        rcpt_idxs: Vec<usize>,
+        statuses: &mut Vec<DeliveryResult>,
+        server: &Server,
+    ) {
+        let queue_config = &server.core.smtp.queue;
+        let conn_strategy = server.get_connection_or_default(
+            &server
+                .eval_if::<String, _>(&queue_config.connection, envelope, self.span_id)
+                .await
+                .unwrap_or_else(|| "default".to_string()),
+            self.span_id,
+        );
+
+        // Connect
+        let time = Instant::now();
+        let mut smtp_client = match tokio::time::timeout(
+            conn_strategy.timeout_connect,
+            UnixStream::connect(&config.path),
+        )
+        .await
+        .map_err(|_| mail_send::Error::Timeout)
+        .and_then(|result| result.map_err(mail_send::Error::from))
+        {
+            Ok(stream) => {
+                trc::event!(
+                    Delivery(DeliveryEvent::Connect),
+                    SpanId = self.span_id,
+                    Hostname = config.path.clone(),
+                    Elapsed = time.elapsed(),
+                );
+
+                SmtpClient {
+                    stream,
+                    timeout: conn_strategy.timeout_greeting,
+                    session_id: self.span_id,
+                }
+            }
+            Err(err) => {
+                trc::event!(
+                    Delivery(DeliveryEvent::ConnectError),
+                    SpanId = self.span_id,
+                    Hostname = config.path.clone(),
+                    CausedBy = from_mail_send_error(&err),
+                    Elapsed = time.elapsed(),
+                );
+
+                statuses.push(DeliveryResult::domain(
+                    Status::from_smtp_error(&config.path, "", err),
+                    rcpt_idxs,
+                ));
+                return;
+            }
+        };
+
+        // Read greeting
+        if let Err(status) = smtp_client.read_greeting(&config.path).await {
+            trc::event!(
+                Delivery(DeliveryEvent::GreetingFailed),
+                SpanId = self.span_id,
+                Hostname = config.path.clone(),
+                Details = status.to_string(),
+            );
+
+            statuses.push(DeliveryResult::domain(status, rcpt_idxs));
+            return;
+        }
+
+        // Deliver message
+        let params = SessionParams {
+            session_id: self.span_id,
+            server,
+            credentials: None,
+            is_smtp: config.protocol == ServerProtocol::Smtp,
+            hostname: &config.path,
+            local_hostname: conn_strategy
+                .ehlo_hostname
+                .as_deref()
+                .unwrap_or(server.core.network.server_name.as_str()),
+            conn_strategy,
+            capabilities: None,
+        };
+        self.deliver(smtp_client, rcpt_idxs, statuses, params).await;
+    }
+
+    pub(super) async fn deliver_pipe(
+        &self,
+        config: &PipeConfig,
+        rcpt_idxs: &[usize],
+        statuses: &mut Vec<DeliveryResult>,
+        server: &Server,
+    ) {
+        // Fetch message
+        let raw_message = match server
+            .get_queued_message(self.queue_id, &self.message.blob_hash, 0..usize::MAX)
+            .await
+        {
+            Ok(Some(raw_message)) => raw_message,
+            result => {
+                if let Err(err) = result {
+                    trc::error!(
+                        err.span_id(self.span_id)
+                            .details("Failed to fetch blobId")
+                            .caused_by(trc::location!())
+                    );
+                } else {
+                    trc::event!(
+                        Queue(trc::QueueEvent::BlobNotFound),
+                        SpanId = self.span_id,
+                        BlobId = self.message.blob_hash.to_hex(),
+                        CausedBy = trc::location!()
+                    );
+                }
+
+                statuses.push(DeliveryResult::domain(
+                    Status::TemporaryFailure(ErrorDetails {
+                        entity: "localhost".to_string(),
+                        details: Error::Io("Queue system error.".into()),
+                    }),
+                    rcpt_idxs.to_vec(),
+                ));
+                return;
+            }
+        };
+
+        // Commands referencing the recipient are executed once per recipient
+        let batches = if config
+            .arguments
+            .iter()
+            .any(|arg| arg.contains("{recipient}"))
+        {
+            rcpt_idxs.iter().map(|idx| vec![*idx]).collect::<Vec<_>>()
+        } else {
+            vec![rcpt_idxs.to_vec()]
+        };
+
+        for batch in batches {
+            let recipients = batch
+                .iter()
+                .map(|idx| self.message.recipients[*idx].address())
+                .collect::<Vec<_>>()
+                .join(",");
+            let arguments = config
+                .arguments
+                .iter()
+                .map(|arg| {
+                    let value = arg
+                        .replace("{sender}", &self.message.return_path)
+                        .replace("{recipient}", &recipients);
+
+                    // Addresses may start with a dash, which must not turn into an option
+                    if value.starts_with('-') && !arg.starts_with('-') {
+                        None
+                    } else {
+                        Some(value)
+                    }
+                })
+                .collect::<Option<Vec<_>>>();
+            let Some(arguments) = arguments else {
+                trc::event!(
+                    Delivery(DeliveryEvent::PipeFailed),
+                    SpanId = self.span_id,
+                    Hostname = config.command.clone(),
+                    To = recipients.clone(),
+                    Details = "Address cannot be passed as a command argument",
+                );
+
+                statuses.push(DeliveryResult::domain(
+                    Status::PermanentFailure(ErrorDetails {
+                        entity: config.command.clone(),
+                        details: Error::UnexpectedResponse(UnexpectedResponse {
+                            command: config.command.clone(),
+                            response: Response {
+                                code: 550,
+                                esc: [5, 1, 3],
+                                message: "Address cannot be passed as a command argument".into(),
+                            },
+                        }),
+                    }),
+                    batch,
+                ));
+                continue;
+            };
+
+            let time = Instant::now();
+            let result = run_command(config, &arguments, &recipients, self, &raw_message).await;
+            let status = match result {
+                Ok(output) if output.status.success() => {
+                    trc::event!(
+                        Delivery(DeliveryEvent::Delivered),
+                        SpanId = self.span_id,
+                        Hostname = config.command.clone(),
+                        To = recipients.clone(),
+                        Code = 0,
+                        Elapsed = time.elapsed(),
+                    );
+
+                    Status::Completed(HostResponse {
+                        hostname: config.command.clone(),
+                        response: Response {
+                            code: 250,
+                            esc: [2, 0, 0],
+                            message: "OK".into(),
+                        },
+                    })
+                }
+                Ok(output) => {
+                    let reason = String::from_utf8_lossy(&output.stderr)
+                        .lines()
+                        .find(|line| !line.trim().is_empty())
+                        .map(|line| line.trim().to_string())
+                        .unwrap_or_else(|| match output.status.code() {
+                            Some(code) => format!("Command exited with status {code}"),
+                            None => "Command terminated by signal".to_string(),
+                        });
+
+                    trc::event!(
+                        Delivery(DeliveryEvent::PipeFailed),
+                        SpanId = self.span_id,
+                        Hostname = config.command.clone(),
+                        To = recipients.clone(),
+                        Code = output.status.code(),
+                        Details = reason.clone(),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    // Map exit codes following sysexits.h, signals are retried
+                    let is_temporary = output
+                        .status
+                        .code()
+                        .is_none_or(|code| config.temp_fail_codes.contains(&code));
+                    let details = Error::UnexpectedResponse(UnexpectedResponse {
+                        command: config.command.clone(),
+                        response: Response {
+                            code: if is_temporary { 451 } else { 550 },
+                            esc: if is_temporary { [4, 3, 0] } else { [5, 3, 0] },
+                            message: reason,
+                        },
+                    });
+                    if is_temporary {
+                        Status::TemporaryFailure(ErrorDetails {
+                            entity: config.command.clone(),
+                            details,
+                        })
+                    } else {
+                        Status::PermanentFailure(ErrorDetails {
+                            entity: config.command.clone(),
+                            details,
+                        })
+                    }
+                }
+                Err(err) => {
+                    trc::event!(
+                        Delivery(DeliveryEvent::PipeFailed),
+                        SpanId = self.span_id,
+                        Hostname = config.command.clone(),
+                        To = recipients.clone(),
+                        Reason = err.clone(),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    Status::TemporaryFailure(ErrorDetails {
+                        entity: config.command.clone(),
+                        details: Error::Io(err),
+                    })
+                }
+            };
+
+            statuses.push(DeliveryResult::domain(status, batch));
+        }
+    }
+}
+
+async fn run_command(
+    config: &PipeConfig,
+    arguments: &[String],
+    recipients: &str,
+    message: &MessageWrapper,
+    raw_message: &[u8],
+) -> Result<std::process::Output, String> {
+    let mut child = Command::new(&config.command)
+        .args(arguments)
+        .env("SENDER", &message.message.return_path)
+        .env("RECIPIENT", recipients)
+        .env("QUEUE_ID", message.queue_id.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("Failed to execute {:?}: {err}", config.command))?;
+
+    tokio::time::timeout(config.timeout, async move {
+        // Write the message while draining stderr, a command that writes a lot
+        // to stderr before reading its input would otherwise block
+        let stdin = child.stdin.take();
+        let (_, output) = tokio::join!(
+            async move {
+                if let Some(mut stdin) = stdin {
+                    // Commands may exit without reading the whole message
+                    let _ = stdin.write_all(raw_message).await;
+                }
+            },
+            child.wait_with_output()
+        );
+        output.map_err(|err| format!("Failed to wait for {:?}: {err}", config.command))
+    })
+    .await
+    .map_err(|_| format!("Command {:?} timed out", config.command))?
+}
@@ -0,0 +1,62 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{ArchivedError, Error, ErrorDetails, HostResponse, Status};
+use common::config::smtp::queue::{BounceCategory, QueueConfig};
+
+impl Status<HostResponse<String>, ErrorDetails> {
+    pub fn bounce_category(&self, config: &QueueConfig) -> Option<BounceCategory> {
+        match self {
+            Status::TemporaryFailure(err) => err.details.bounce_category(config, false).into(),
+            Status::PermanentFailure(err) => err.details.bounce_category(config, true).into(),
+            Status::Scheduled | Status::Completed(_) => None,
+        }
+    }
+}
+
+impl Error {
+    pub fn bounce_category(&self, config: &QueueConfig, is_permanent: bool) -> BounceCategory {
+        match self {
+            Error::UnexpectedResponse(response) => config.classify_reply(
+                response.response.code,
+                response.response.esc,
+                &response.response.message,
+            ),
+            Error::DnsError(_) if is_permanent => BounceCategory::BadAddress,
+            Error::DaneError(_) | Error::MtaStsError(_) | Error::RequireTlsError(_) => {
+                BounceCategory::Policy
+            }
+            Error::DnsError(_)
+            | Error::ConnectionError(_)
+            | Error::TlsError(_)
+            | Error::RateLimited
+            | Error::ConcurrencyLimited
+            | Error::Io(_) => BounceCategory::TransientInfra,
+        }
+    }
+}
+
+impl ArchivedError {
+    pub fn bounce_category(&self, config: &QueueConfig, is_permanent: bool) -> BounceCategory {
+        match self {
+            ArchivedError::UnexpectedResponse(response) => config.classify_reply(
+                response.response.code.into(),
+                response.response.esc,
+                response.response.message.as_str(),
+            ),
+            ArchivedError::DnsError(_) if is_permanent => BounceCategory::BadAddress,
+            ArchivedError::DaneError(_)
+            | ArchivedError::MtaStsError(_)
+            | ArchivedError::RequireTlsError(_) => BounceCategory::Policy,
+            ArchivedError::DnsError(_)
+            | ArchivedError::ConnectionError(_)
+            | ArchivedError::TlsError(_)
+            | ArchivedError::RateLimited
+            | ArchivedError::ConcurrencyLimited
+            | ArchivedError::Io(_) => BounceCategory::TransientInfra,
+        }
+    }
+}
@@ -5,6 +5,7 @@
  */
 
 use super::spool::SmtpSpool;
+use super::storage::QueueStorage;
 use super::{
     Error, ErrorDetails, HostResponse, Message, MessageSource, QueueEnvelope, RCPT_DSN_SENT,
     RCPT_STATUS_CHANGED, Recipient, Status,
@@ -12,13 +13,15 @@ use super::{
 use crate::queue::{MessageWrapper, UnexpectedResponse};
 use crate::reporting::SmtpReporting;
 use common::Server;
+use common::config::smtp::queue::QueueConfig;
 use mail_builder::MessageBuilder;
 use mail_builder::headers::HeaderType;
 use mail_builder::headers::content_type::ContentType;
 use mail_builder::mime::{BodyPart, MimePart, make_boundary};
 use mail_parser::DateTime;
 use smtp_proto::{
-    RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS, Response,
+    MAIL_REQUIRETLS, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER,
+    RCPT_NOTIFY_SUCCESS, Response,
 };
 use std::fmt::Write;
 use std::future::Future;
@@ -42,6 +45,12 @@ impl SendDsn for Server {
                     .add_recipient(message.message.return_path.as_str(), self)
                     .await;
 
+                // RFC 8689 Section 5 - DSNs carrying the original headers must
+                // also be delivered with REQUIRETLS
+                if (message.message.flags & MAIL_REQUIRETLS) != 0 {
+                    dsn_message.message.flags |= MAIL_REQUIRETLS;
+                }
+
                 // Sign message
                 let signature = self
                     .sign_message(message, &self.core.smtp.queue.dsn.sign, &dsn)
@@ -90,6 +99,10 @@ impl SendDsn for Server {
                         To = rcpt.address.clone(),
                         Hostname = response.entity.clone(),
                         Details = response.details.to_string(),
+                        Type = response
+                            .details
+                            .bounce_category(&self.core.smtp.queue, false)
+                            .as_str(),
                         NextRetry = trc::Value::Timestamp(rcpt.retry.due),
                         Expires = rcpt
                             .expiration_time(message.message.created)
@@ -104,6 +117,10 @@ impl SendDsn for Server {
                         To = rcpt.address.clone(),
                         Hostname = response.entity.clone(),
                         Details = response.details.to_string(),
+                        Type = response
+                            .details
+                            .bounce_category(&self.core.smtp.queue, true)
+                            .as_str(),
                         Total = rcpt.retry.inner,
                     );
                 }
@@ -147,14 +164,14 @@ impl MessageWrapper {
                         continue;
                     }
                     rcpt.write_dsn(&mut dsn);
-                    rcpt.status.write_dsn(&mut dsn);
+                    rcpt.status.write_dsn(config, &mut dsn);
                     response.write_dsn_text(&rcpt.address, &mut txt_success);
                 }
                 Status::TemporaryFailure(response)
                     if rcpt.notify.due <= now && rcpt.has_flag(RCPT_NOTIFY_DELAY) =>
                 {
                     rcpt.write_dsn(&mut dsn);
-                    rcpt.status.write_dsn(&mut dsn);
+                    rcpt.status.write_dsn(config, &mut dsn);
                     rcpt.write_dsn_will_retry_until(self.message.created, &mut dsn);
                     response.write_dsn_text(&rcpt.address, &mut txt_delay);
                 }
@@ -164,13 +181,13 @@ impl MessageWrapper {
                         continue;
                     }
                     rcpt.write_dsn(&mut dsn);
-                    rcpt.status.write_dsn(&mut dsn);
+                    rcpt.status.write_dsn(config, &mut dsn);
                     response.write_dsn_text(&rcpt.address, &mut txt_failed);
                 }
                 Status::Scheduled if rcpt.notify.due <= now && rcpt.has_flag(RCPT_NOTIFY_DELAY) => {
                     // This case should not happen under normal circumstances
                     rcpt.write_dsn(&mut dsn);
-                    rcpt.status.write_dsn(&mut dsn);
+                    rcpt.status.write_dsn(config, &mut dsn);
                     rcpt.write_dsn_will_retry_until(self.message.created, &mut dsn);
                     ErrorDetails {
                         entity: "localhost".into(),
@@ -257,7 +274,8 @@ impl MessageWrapper {
                     Status::TemporaryFailure(_) | Status::Scheduled
                 ) && rcpt.notify.due <= now
                 {
-                    let envelope = QueueEnvelope::new(&self.message, rcpt);
+                    let envelope = QueueEnvelope::new(&self.message, rcpt)
+                        .with_bounce_category(&server.core.smtp.queue);
 
                     let queue_id = server
                         .eval_if::<String, _>(
@@ -312,8 +330,7 @@ impl MessageWrapper {
 
         // Fetch up to 1024 bytes of message headers
         let headers = match server
-            .blob_store()
-            .get_blob(self.message.blob_hash.as_slice(), 0..1024)
+            .get_queued_message(self.queue_id, &self.message.blob_hash, 0..1024)
             .await
         {
             Ok(Some(mut buf)) => {
@@ -503,6 +520,12 @@ impl ErrorDetails {
             Error::Io(err) => {
                 let _ = write!(dsn, "<{addr}> (queue error: {err})\r\n");
             }
+            Error::RequireTlsError(details) => {
+                let _ = write!(
+                    dsn,
+                    "<{addr}> (REQUIRETLS could not be honored by '{entity}': {details})\r\n",
+                );
+            }
         }
     }
 }
@@ -570,11 +593,18 @@ impl<T, E> Status<T, E> {
 }
 
 impl Status<HostResponse<String>, ErrorDetails> {
-    fn write_dsn(&self, dsn: &mut String) {
+    fn write_dsn(&self, config: &QueueConfig, dsn: &mut String) {
         self.write_dsn_action(dsn);
         self.write_dsn_status(dsn);
         self.write_dsn_diagnostic(dsn);
         self.write_dsn_remote_mta(dsn);
+        self.write_dsn_bounce_category(config, dsn);
+    }
+
+    fn write_dsn_bounce_category(&self, config: &QueueConfig, dsn: &mut String) {
+        if let Some(category) = self.bounce_category(config) {
+            let _ = write!(dsn, "X-Bounce-Category: {category}\r\n");
+        }
     }
 
     fn write_dsn_status(&self, dsn: &mut String) {
@@ -586,6 +616,9 @@ impl Status<HostResponse<String>, ErrorDetails> {
             Status::TemporaryFailure(err) | Status::PermanentFailure(err) => {
                 if let Error::UnexpectedResponse(response) = &err.details {
                     response.response.write_dsn_status(dsn);
+                } else if let Error::RequireTlsError(_) = &err.details {
+                    // RFC 8689 Section 5
+                    dsn.push_str("5.7.30");
                 } else {
                     dsn.push_str(if matches!(self, Status::PermanentFailure(_)) {
                         "5.0.0"
@@ -612,7 +645,8 @@ impl Status<HostResponse<String>, ErrorDetails> {
                 Error::UnexpectedResponse(_)
                 | Error::ConnectionError(_)
                 | Error::TlsError(_)
-                | Error::DaneError(_) => {
+                | Error::DaneError(_)
+                | Error::RequireTlsError(_) => {
                     dsn.push_str("Remote-MTA: dns;");
                     dsn.push_str(&err.entity);
                     dsn.push_str("\r\n");
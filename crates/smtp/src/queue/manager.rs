@@ -4,11 +4,11 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::{Message, QueueId, Status, spool::SmtpSpool};
+use super::{Message, QueueId, QueuedMessage, Status, spool::SmtpSpool};
 use crate::queue::{Recipient, spool::LOCK_EXPIRY};
 use ahash::AHashMap;
 use common::{
-    Inner,
+    Inner, Server,
     config::smtp::queue::{QueueExpiry, QueueName},
     core::BuildServer,
     ipc::{QueueEvent, QueueEventStatus},
@@ -21,6 +21,7 @@ use std::{
 };
 use store::write::now;
 use tokio::sync::mpsc;
+use trc::{Collector, MetricType};
 
 pub struct Queue {
     pub core: Arc<Inner>,
@@ -30,6 +31,15 @@ pub struct Queue {
     pub next_refresh: Instant,
     pub rx: mpsc::Receiver<QueueEvent>,
     pub is_paused: bool,
+    pub fair_share: FairShareStats,
+}
+
+// Tracks in-flight deliveries per fair-share key
+#[derive(Debug, Default)]
+pub struct FairShareStats {
+    pub in_flight: AHashMap<u64, usize>,
+    pub keys: AHashMap<(QueueId, QueueName), u64>,
+    pub has_deferred: bool,
 }
 
 #[derive(Debug)]
@@ -37,6 +47,9 @@ pub struct QueueStats {
     pub in_flight: usize,
     pub max_in_flight: usize,
     pub last_warning: Instant,
+    pub last_busy: Instant,
+    pub backlog: usize,
+    pub oldest_due: u64,
 }
 
 #[derive(Debug)]
@@ -64,6 +77,7 @@ impl Queue {
             stats: AHashMap::new(),
             next_refresh: Instant::now() + Duration::from_secs(1),
             is_paused: false,
+            fair_share: FairShareStats::default(),
             rx,
         }
     }
@@ -98,13 +112,10 @@ impl Queue {
                 if refresh_queue || self.next_refresh <= Instant::now() {
                     // Process queue events
                     let server = self.core.build_server();
-                    let mut queue_events = server.next_event(self).await;
-
-                    if queue_events.messages.len() > 3 {
-                        queue_events.messages.shuffle(&mut rand::rng());
-                    }
+                    let queue_events = server.next_event(self).await;
+                    self.fair_share.has_deferred = false;
 
-                    for queue_event in &queue_events.messages {
+                    for queue_event in &fair_share_order(queue_events.messages) {
                         // Fetch queue stats
                         let stats = match self.stats.get_mut(&queue_event.queue_name) {
                             Some(stats) => stats,
@@ -121,10 +132,19 @@ impl Queue {
 
                         // Enforce concurrency limits
                         if stats.has_capacity() {
+                            // Enforce per-key limits
+                            if !self.fair_share.try_acquire(queue_event) {
+                                self.locked
+                                    .remove(&(queue_event.queue_id, queue_event.queue_name));
+                                continue;
+                            }
+
                             // Deliver message
                             stats.in_flight += 1;
                             queue_event.try_deliver(server.clone());
                         } else {
+                            stats.backlog += 1;
+                            stats.oldest_due = stats.oldest_due.min(queue_event.due);
                             if stats.last_warning.elapsed() >= BACK_PRESSURE_WARN_INTERVAL {
                                 stats.last_warning = Instant::now();
                                 trc::event!(
@@ -139,8 +159,11 @@ impl Queue {
                         }
                     }
 
-                    // Remove expired locks
+                    // Adjust the number of workers to the queue depth
                     let now = now();
+                    self.autoscale(&server, now);
+
+                    // Remove expired locks
                     self.locked.retain(|_, locked| {
                         locked.expires > now && locked.revision == self.locked_revision
                     });
@@ -164,11 +187,13 @@ impl Queue {
             } => {
                 let queue_stats = self.stats.get_mut(&queue_name).unwrap();
                 queue_stats.in_flight -= 1;
+                self.fair_share.release(queue_id, queue_name);
+                let has_deferred = self.fair_share.has_deferred;
 
                 match status {
                     QueueEventStatus::Completed => {
                         self.locked.remove(&(queue_id, queue_name));
-                        !self.locked.is_empty() || !queue_stats.has_capacity()
+                        !self.locked.is_empty() || !queue_stats.has_capacity() || has_deferred
                     }
                     QueueEventStatus::Locked => {
                         let expires = LOCK_EXPIRY + rand::rng().random_range(5..10);
@@ -184,7 +209,7 @@ impl Queue {
                                 revision: self.locked_revision,
                             },
                         );
-                        self.locked.len() > 1 || !queue_stats.has_capacity()
+                        self.locked.len() > 1 || !queue_stats.has_capacity() || has_deferred
                     }
                     QueueEventStatus::Deferred => {
                         self.locked.remove(&(queue_id, queue_name));
@@ -205,7 +230,13 @@ impl Queue {
                 let server = self.core.build_server();
                 for (name, settings) in &server.core.smtp.queue.virtual_queues {
                     if let Some(stats) = self.stats.get_mut(name) {
-                        stats.max_in_flight = settings.threads;
+                        stats.max_in_flight = if let Some(autoscale) = &settings.autoscale {
+                            stats
+                                .max_in_flight
+                                .clamp(autoscale.min_threads, autoscale.max_threads)
+                        } else {
+                            settings.threads
+                        };
                     } else {
                         self.stats.insert(*name, QueueStats::new(settings.threads));
                     }
@@ -220,6 +251,64 @@ impl Queue {
             }
         }
     }
+
+    pub fn autoscale(&mut self, server: &Server, now: u64) {
+        let mut total_in_flight = 0;
+        let mut total_max_in_flight = 0;
+
+        for (queue_name, stats) in self.stats.iter_mut() {
+            if let Some(autoscale) = server
+                .core
+                .smtp
+                .queue
+                .virtual_queues
+                .get(queue_name)
+                .and_then(|queue| queue.autoscale.as_ref())
+            {
+                let max_in_flight = stats.max_in_flight;
+                let backlog_age = now.saturating_sub(stats.oldest_due);
+
+                if stats.backlog > 0
+                    && (stats.backlog >= autoscale.scale_up_depth
+                        || backlog_age >= autoscale.scale_up_age)
+                {
+                    // Scale up by half of the current workers
+                    stats.last_busy = Instant::now();
+                    stats.max_in_flight =
+                        (max_in_flight + (max_in_flight / 2).max(1)).min(autoscale.max_threads);
+                } else if stats.backlog > 0 || stats.in_flight * 2 > max_in_flight {
+                    stats.last_busy = Instant::now();
+                } else if stats.last_busy.elapsed() >= autoscale.scale_down_after {
+                    // Scale down by a quarter of the current workers
+                    stats.last_busy = Instant::now();
+                    stats.max_in_flight = max_in_flight
+                        .saturating_sub((max_in_flight / 4).max(1))
+                        .max(autoscale.min_threads);
+                }
+
+                if stats.max_in_flight != max_in_flight {
+                    trc::event!(
+                        Queue(trc::QueueEvent::WorkersScaled),
+                        QueueName = queue_name.to_string(),
+                        Total = stats.max_in_flight,
+                        Limit = vec![
+                            trc::Value::from(autoscale.min_threads),
+                            trc::Value::from(autoscale.max_threads)
+                        ],
+                        Size = stats.backlog,
+                    );
+                }
+            }
+
+            stats.backlog = 0;
+            stats.oldest_due = u64::MAX;
+            total_in_flight += stats.in_flight;
+            total_max_in_flight += stats.max_in_flight;
+        }
+
+        Collector::update_gauge(MetricType::QueueWorkersActive, total_in_flight as u64);
+        Collector::update_gauge(MetricType::QueueWorkersMax, total_max_in_flight as u64);
+    }
 }
 
 impl Message {
@@ -353,16 +442,93 @@ impl Recipient {
     }
 }
 
+impl FairShareStats {
+    fn try_acquire(&mut self, event: &QueuedMessage) -> bool {
+        let max_in_flight = event.fair_share.max_in_flight as usize;
+        if max_in_flight > 0 {
+            let key = event.fair_share.key;
+            let in_flight = self.in_flight.entry(key).or_default();
+            if *in_flight >= max_in_flight {
+                self.has_deferred = true;
+                return false;
+            }
+            *in_flight += 1;
+            self.keys.insert((event.queue_id, event.queue_name), key);
+        }
+        true
+    }
+
+    fn release(&mut self, queue_id: QueueId, queue_name: QueueName) {
+        if let Some(key) = self.keys.remove(&(queue_id, queue_name))
+            && let Entry::Occupied(mut entry) = self.in_flight.entry(key)
+        {
+            if *entry.get() > 1 {
+                *entry.get_mut() -= 1;
+            } else {
+                entry.remove();
+            }
+        }
+    }
+}
+
+// Orders due events using weighted round-robin across fair-share keys, so that
+// a single tenant or sender domain cannot starve the rest of the queue. Events
+// sharing a key are ordered by priority and then by due time.
+fn fair_share_order(messages: Vec<QueuedMessage>) -> Vec<QueuedMessage> {
+    let total = messages.len();
+    let mut groups: AHashMap<u64, Vec<QueuedMessage>> = AHashMap::new();
+    for message in messages {
+        groups
+            .entry(message.fair_share.key)
+            .or_default()
+            .push(message);
+    }
+
+    let mut groups = groups
+        .into_values()
+        .map(|mut group| {
+            group.sort_unstable_by(|a, b| {
+                a.fair_share
+                    .priority
+                    .cmp(&b.fair_share.priority)
+                    .then_with(|| b.due.cmp(&a.due))
+            });
+            group
+        })
+        .collect::<Vec<_>>();
+    groups.shuffle(&mut rand::rng());
+
+    let mut result = Vec::with_capacity(total);
+    while !groups.is_empty() {
+        groups.retain_mut(|group| {
+            let weight = group.last().map_or(1, |message| message.fair_share.weight);
+            for _ in 0..weight {
+                if let Some(message) = group.pop() {
+                    result.push(message);
+                } else {
+                    break;
+                }
+            }
+            !group.is_empty()
+        });
+    }
+
+    result
+}
+
 pub trait SpawnQueue {
     fn spawn(self, core: Arc<Inner>);
 }
 
 impl QueueStats {
-    fn new(max_in_flight: usize) -> Self {
+    pub fn new(max_in_flight: usize) -> Self {
         QueueStats {
             in_flight: 0,
             max_in_flight,
             last_warning: Instant::now() - BACK_PRESSURE_WARN_INTERVAL,
+            last_busy: Instant::now(),
+            backlog: 0,
+            oldest_due: u64::MAX,
         }
     }
 
@@ -371,3 +537,113 @@ impl QueueStats {
         self.in_flight < self.max_in_flight
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::config::smtp::queue::QueueName;
+
+    use super::{FairShareStats, fair_share_order};
+    use crate::queue::{FairShareKey, QueuedMessage};
+
+    fn message(queue_id: u64, key: &str, weight: u64, max_in_flight: u64) -> QueuedMessage {
+        QueuedMessage {
+            due: queue_id,
+            queue_id,
+            queue_name: QueueName::new("default").unwrap(),
+            fair_share: FairShareKey::new(key, weight, max_in_flight, 0),
+        }
+    }
+
+    #[test]
+    fn fair_share_weighted_order() {
+        // Tenant "a" has twice the weight of tenant "b"
+        let messages = (0..6)
+            .map(|id| message(id, "a", 2, 0))
+            .chain((6..9).map(|id| message(id, "b", 1, 0)))
+            .collect::<Vec<_>>();
+        let key_a = messages[0].fair_share.key;
+
+        for _ in 0..10 {
+            let order = fair_share_order(messages.clone())
+                .into_iter()
+                .map(|message| {
+                    (
+                        if message.fair_share.key == key_a {
+                            'a'
+                        } else {
+                            'b'
+                        },
+                        message.queue_id,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let keys = order.iter().map(|(key, _)| *key).collect::<String>();
+            assert!(
+                keys == "aabaabaab" || keys == "baabaabaa",
+                "unexpected order {keys}"
+            );
+
+            // Events sharing a key are delivered by due time
+            for key in ['a', 'b'] {
+                let ids = order
+                    .iter()
+                    .filter(|(k, _)| *k == key)
+                    .map(|(_, id)| *id)
+                    .collect::<Vec<_>>();
+                assert!(ids.is_sorted(), "{key}: {ids:?}");
+            }
+        }
+
+        // Higher priority events are delivered first within a key
+        let mut urgent = message(10, "a", 1, 0);
+        urgent.fair_share.priority = 1;
+        let order = fair_share_order(vec![message(11, "a", 1, 0), urgent]);
+        assert_eq!(
+            order.iter().map(|m| m.queue_id).collect::<Vec<_>>(),
+            [10, 11]
+        );
+    }
+
+    #[test]
+    fn fair_share_in_flight_cap() {
+        let mut stats = FairShareStats::default();
+        let limited = (0..3)
+            .map(|id| message(id, "limited", 1, 2))
+            .collect::<Vec<_>>();
+        let other = message(3, "other", 1, 1);
+        let unlimited = (4..8)
+            .map(|id| message(id, "unlimited", 1, 0))
+            .collect::<Vec<_>>();
+
+        // Each key is capped at its own limit
+        assert!(stats.try_acquire(&limited[0]));
+        assert!(stats.try_acquire(&limited[1]));
+        assert!(!stats.try_acquire(&limited[2]));
+        assert!(stats.has_deferred);
+        assert!(stats.try_acquire(&other));
+        for message in &unlimited {
+            assert!(stats.try_acquire(message));
+        }
+        assert_eq!(stats.in_flight.len(), 2);
+        assert_eq!(stats.keys.len(), 3);
+
+        // Completed deliveries free up the key
+        stats.release(limited[0].queue_id, limited[0].queue_name);
+        assert!(stats.try_acquire(&limited[2]));
+        stats.release(other.queue_id, other.queue_name);
+        assert!(!stats.in_flight.contains_key(&other.fair_share.key));
+        assert!(!stats.try_acquire(&limited[0]));
+    }
+
+    #[test]
+    fn fair_share_key_serialization() {
+        let key = FairShareKey::new("tenant", 3, 10, -1);
+        assert_eq!(FairShareKey::deserialize(&key.serialize()), key);
+
+        // Events written without a per-key limit are not capped
+        let legacy = FairShareKey::deserialize(&key.serialize()[..12]);
+        assert_eq!(legacy.max_in_flight, 0);
+        assert_eq!(legacy.weight, 3);
+        assert_eq!(FairShareKey::deserialize(&[]), FairShareKey::default());
+    }
+}
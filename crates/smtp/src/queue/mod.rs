@@ -5,7 +5,7 @@
  */
 
 use common::{
-    config::smtp::queue::{QueueExpiry, QueueName},
+    config::smtp::queue::{BounceCategory, QueueConfig, QueueExpiry, QueueName},
     expr::{self, functions::ResolveVariable, *},
 };
 use compact_str::ToCompactString;
@@ -19,11 +19,15 @@ use store::write::now;
 use types::blob_hash::BlobHash;
 use utils::DomainPart;
 
+pub mod bounce;
 pub mod dsn;
 pub mod manager;
 pub mod quota;
+pub mod sending;
 pub mod spool;
+pub mod storage;
 pub mod throttle;
+pub mod trace;
 
 pub type QueueId = u64;
 
@@ -38,6 +42,16 @@ pub struct QueuedMessage {
     pub due: u64,
     pub queue_id: QueueId,
     pub queue_name: QueueName,
+    pub fair_share: FairShareKey,
+}
+
+// Fair-share scheduling details, stored as the value of each queue event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FairShareKey {
+    pub key: u64,
+    pub weight: u16,
+    pub max_in_flight: u16,
+    pub priority: i16,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -90,6 +104,7 @@ pub struct MessageWrapper {
 pub enum QuotaKey {
     Size { key: Vec<u8>, id: u64 },
     Count { key: Vec<u8>, id: u64 },
+    Sending { key: Vec<u8>, id: u64 },
 }
 
 #[derive(
@@ -121,6 +136,8 @@ pub const FROM_UNAUTHENTICATED_DMARC: u64 = 1 << 34;
 pub const FROM_DSN: u64 = 1 << 35;
 pub const FROM_REPORT: u64 = 1 << 36;
 pub const FROM_AUTOGENERATED: u64 = 1 << 37;
+pub const MESSAGE_TRACE: u64 = 1 << 38;
+pub const MESSAGE_TLS_OPTIONAL: u64 = 1 << 39;
 
 pub const RCPT_DSN_SENT: u64 = 1 << 32;
 pub const RCPT_STATUS_CHANGED: u64 = 1 << 33;
@@ -184,6 +201,7 @@ pub enum Error {
     #[default]
     ConcurrencyLimited,
     Io(String),
+    RequireTlsError(String),
 }
 
 #[derive(
@@ -260,6 +278,7 @@ pub struct QueueEnvelope<'x> {
     pub rcpt: &'x Recipient,
     pub remote_ip: IpAddr,
     pub local_ip: IpAddr,
+    pub bounce_category: Option<BounceCategory>,
 }
 
 impl<'x> QueueEnvelope<'x> {
@@ -271,8 +290,14 @@ impl<'x> QueueEnvelope<'x> {
             mx: "",
             remote_ip: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
             local_ip: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+            bounce_category: None,
         }
     }
+
+    pub fn with_bounce_category(mut self, config: &QueueConfig) -> Self {
+        self.bounce_category = self.rcpt.status.bounce_category(config);
+        self
+    }
 }
 
 impl<'x> ResolveVariable for QueueEnvelope<'x> {
@@ -312,10 +337,15 @@ impl<'x> ResolveVariable for QueueEnvelope<'x> {
                         Error::RateLimited => "rate",
                         Error::ConcurrencyLimited => "concurrency",
                         Error::Io(_) => "io",
+                        Error::RequireTlsError(_) => "requiretls",
                     }
                 }
             }
             .into(),
+            V_QUEUE_BOUNCE_CATEGORY => self
+                .bounce_category
+                .map_or("none", |category| category.as_str())
+                .into(),
             V_QUEUE_NAME => self.rcpt.queue.as_str().into(),
             V_QUEUE_AGE => now().saturating_sub(self.message.created).into(),
             V_SOURCE => if (self.message.flags & FROM_AUTHENTICATED) != 0 {
@@ -392,6 +422,59 @@ impl<'x> ResolveVariable for RecipientDomain<'x> {
     }
 }
 
+impl FairShareKey {
+    pub fn new(key: &str, weight: u64, max_in_flight: u64, priority: i16) -> Self {
+        let hash = blake3::hash(key.as_bytes());
+        FairShareKey {
+            key: u64::from_be_bytes(hash.as_bytes()[..8].try_into().unwrap()),
+            weight: weight.clamp(1, u16::MAX as u64) as u16,
+            max_in_flight: max_in_flight.min(u16::MAX as u64) as u16,
+            priority,
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(14);
+        bytes.extend_from_slice(&self.key.to_be_bytes());
+        bytes.extend_from_slice(&self.weight.to_be_bytes());
+        bytes.extend_from_slice(&self.priority.to_be_bytes());
+        bytes.extend_from_slice(&self.max_in_flight.to_be_bytes());
+        bytes
+    }
+
+    // Events written by older versions have no value and share the default key
+    pub fn deserialize(bytes: &[u8]) -> Self {
+        if let (Some(key), Some(weight), Some(priority)) = (
+            bytes.get(0..8).and_then(|b| b.try_into().ok()),
+            bytes.get(8..10).and_then(|b| b.try_into().ok()),
+            bytes.get(10..12).and_then(|b| b.try_into().ok()),
+        ) {
+            FairShareKey {
+                key: u64::from_be_bytes(key),
+                weight: u16::from_be_bytes(weight).max(1),
+                max_in_flight: bytes
+                    .get(12..14)
+                    .and_then(|b| b.try_into().ok())
+                    .map_or(0, u16::from_be_bytes),
+                priority: i16::from_be_bytes(priority),
+            }
+        } else {
+            FairShareKey::default()
+        }
+    }
+}
+
+impl Default for FairShareKey {
+    fn default() -> Self {
+        FairShareKey {
+            key: 0,
+            weight: 1,
+            max_in_flight: 0,
+            priority: 0,
+        }
+    }
+}
+
 #[inline(always)]
 pub fn instant_to_timestamp(now: Instant, time: Instant) -> u64 {
     SystemTime::now()
@@ -495,6 +578,9 @@ impl Display for Error {
             Error::Io(err) => {
                 write!(f, "Queue error: {err}")
             }
+            Error::RequireTlsError(details) => {
+                write!(f, "REQUIRETLS failure: {details}")
+            }
         }
     }
 }
@@ -533,6 +619,9 @@ impl Display for ArchivedError {
             ArchivedError::Io(err) => {
                 write!(f, "Queue error: {err}")
             }
+            ArchivedError::RequireTlsError(details) => {
+                write!(f, "REQUIRETLS failure: {details}")
+            }
         }
     }
 }
@@ -0,0 +1,546 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{MessageWrapper, QuotaKey};
+use common::{
+    KV_SENDING_QUOTA, Server,
+    config::smtp::queue::{SendingLimit, SendingQuota, SendingQuotaOverride},
+    expr::functions::ResolveVariable,
+};
+use std::future::Future;
+use store::{dispatch::lookup::KeyValue, write::now};
+use trc::{AddContext, QueueEvent};
+
+const KIND_MESSAGES: u8 = b'm';
+const KIND_RECIPIENTS: u8 = b'r';
+const KIND_OVERRIDE: u8 = b'o';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendingPeriod {
+    Hourly,
+    Daily,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SendingQuotaUsage {
+    pub hourly: SendingUsage,
+    pub daily: SendingUsage,
+    #[serde(rename = "override")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub override_: Option<SendingQuotaOverride>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SendingUsage {
+    pub messages: u64,
+    pub recipients: u64,
+    pub limit: SendingLimit,
+    pub resets: u64,
+}
+
+pub trait SendingQuotas: Sync + Send {
+    fn has_sending_quota(
+        &self,
+        message: &mut MessageWrapper,
+        envelope: &impl ResolveVariable,
+    ) -> impl Future<Output = bool> + Send;
+
+    fn is_sending_allowed(
+        &self,
+        message: &MessageWrapper,
+    ) -> impl Future<Output = Result<(), u64>> + Send;
+
+    fn sending_quota_usage(
+        &self,
+        quota_id: &str,
+        key: &str,
+    ) -> impl Future<Output = trc::Result<Option<SendingQuotaUsage>>> + Send;
+
+    fn sending_quota_override(
+        &self,
+        quota_key: &[u8],
+    ) -> impl Future<Output = trc::Result<Option<SendingQuotaOverride>>> + Send;
+
+    fn set_sending_quota_override(
+        &self,
+        quota_id: &str,
+        key: &str,
+        override_: Option<SendingQuotaOverride>,
+        expires: Option<u64>,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn reset_sending_quota(
+        &self,
+        quota_id: &str,
+        key: &str,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl SendingQuotas for Server {
+    async fn has_sending_quota(
+        &self,
+        message: &mut MessageWrapper,
+        envelope: &impl ResolveVariable,
+    ) -> bool {
+        let mut accounted = Vec::new();
+        let mut warnings = Vec::new();
+        let mut quota_keys = Vec::new();
+        let num_rcpts = message.message.recipients.len() as u64;
+        let now = now();
+
+        for quota in &self.core.smtp.queue.sending_quotas {
+            if !quota.expr.is_empty()
+                && !self
+                    .eval_expr(&quota.expr, envelope, "sending_quota", message.span_id)
+                    .await
+                    .unwrap_or(false)
+            {
+                continue;
+            }
+
+            // Messages without a quota key are not accounted
+            let key = self
+                .eval_if::<String, _>(&quota.key, envelope, message.span_id)
+                .await
+                .unwrap_or_default();
+            if key.is_empty() {
+                continue;
+            }
+            let quota_key = build_quota_key(&quota.id, &key);
+
+            let override_ = match self.sending_quota_override(&quota_key).await {
+                Ok(override_) => override_.unwrap_or_default(),
+                Err(err) => {
+                    trc::error!(err.span_id(message.span_id));
+                    SendingQuotaOverride::default()
+                }
+            };
+            if override_.exempt {
+                continue;
+            }
+
+            // Account the message first and compare the updated usage, so
+            // concurrent messages cannot both fit in the remaining quota
+            for period in [SendingPeriod::Hourly, SendingPeriod::Daily] {
+                let limit = period.limit(quota, Some(&override_));
+                if limit == SendingLimit::default() {
+                    continue;
+                }
+
+                let (messages, recipients) =
+                    match self.sending_incr(period, &quota_key, num_rcpts, now).await {
+                        Ok(usage) => usage,
+                        Err(err) => {
+                            trc::error!(err.span_id(message.span_id));
+                            continue;
+                        }
+                    };
+                accounted.push((period, quota_key.clone()));
+
+                if let Some(max) = [(limit.messages, messages), (limit.recipients, recipients)]
+                    .into_iter()
+                    .find_map(|(max, used)| max.filter(|&max| used > max))
+                {
+                    trc::event!(
+                        Queue(QueueEvent::SendingQuotaExceeded),
+                        SpanId = message.span_id,
+                        Id = quota.id.clone(),
+                        Key = key,
+                        Type = period.as_str(),
+                        Total = messages,
+                        Value = recipients,
+                        Limit = max,
+                    );
+
+                    // Roll back the usage accounted for this message
+                    for (period, quota_key) in accounted {
+                        if let Err(err) =
+                            self.sending_decr(period, &quota_key, num_rcpts, now).await
+                        {
+                            trc::error!(err.span_id(message.span_id));
+                        }
+                    }
+
+                    return false;
+                }
+
+                // Notify once per period when the usage crosses the threshold
+                if quota.notify > 0
+                    && let Some(max) = [
+                        (limit.messages, messages, 1),
+                        (limit.recipients, recipients, num_rcpts),
+                    ]
+                    .into_iter()
+                    .find_map(|(max, used, added)| {
+                        max.filter(|&max| crosses(max, quota.notify, used, added))
+                    })
+                {
+                    warnings.push((quota, key.clone(), period, messages, recipients, max));
+                }
+            }
+
+            quota_keys.push(QuotaKey::Sending {
+                key: quota_key,
+                id: 0,
+            });
+        }
+
+        // Warnings are only emitted once the message has been accepted
+        for (quota, key, period, messages, recipients, max) in warnings {
+            trc::event!(
+                Queue(QueueEvent::SendingQuotaWarning),
+                SpanId = message.span_id,
+                Id = quota.id.clone(),
+                Key = key,
+                Type = period.as_str(),
+                Total = messages,
+                Value = recipients,
+                Limit = max,
+            );
+        }
+        message.message.quota_keys.extend(quota_keys);
+
+        true
+    }
+
+    async fn is_sending_allowed(&self, message: &MessageWrapper) -> Result<(), u64> {
+        let quotas = &self.core.smtp.queue.sending_quotas;
+        if quotas.is_empty() {
+            return Ok(());
+        }
+        let now = now();
+
+        for quota_key in &message.message.quota_keys {
+            let QuotaKey::Sending { key: quota_key, .. } = quota_key else {
+                continue;
+            };
+            let Some(quota) = quota_key
+                .iter()
+                .position(|&ch| ch == 0)
+                .and_then(|pos| std::str::from_utf8(&quota_key[..pos]).ok())
+                .and_then(|quota_id| quotas.iter().find(|quota| quota.id == quota_id))
+            else {
+                continue;
+            };
+
+            let override_ = match self.sending_quota_override(quota_key).await {
+                Ok(override_) => override_.unwrap_or_default(),
+                Err(err) => {
+                    trc::error!(err.span_id(message.span_id));
+                    continue;
+                }
+            };
+            if override_.exempt {
+                continue;
+            }
+
+            // Usage already includes this message, hold it back only if the
+            // limits were lowered or suspended since it was accepted
+            for period in [SendingPeriod::Hourly, SendingPeriod::Daily] {
+                let limit = period.limit(quota, Some(&override_));
+                if limit == SendingLimit::default() {
+                    continue;
+                }
+
+                let (messages, recipients) = match self.sending_usage(period, quota_key, now).await
+                {
+                    Ok(usage) => usage,
+                    Err(err) => {
+                        trc::error!(err.span_id(message.span_id));
+                        continue;
+                    }
+                };
+                if limit.messages.is_some_and(|max| max == 0 || messages > max)
+                    || limit
+                        .recipients
+                        .is_some_and(|max| max == 0 || recipients > max)
+                {
+                    let retry_at = period.window_end(now);
+
+                    trc::event!(
+                        Queue(QueueEvent::SendingQuotaExceeded),
+                        SpanId = message.span_id,
+                        Id = quota.id.clone(),
+                        Type = period.as_str(),
+                        Total = messages,
+                        Value = recipients,
+                        NextRetry = trc::Value::Timestamp(retry_at),
+                    );
+
+                    return Err(retry_at);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn sending_quota_usage(
+        &self,
+        quota_id: &str,
+        key: &str,
+    ) -> trc::Result<Option<SendingQuotaUsage>> {
+        let Some(quota) = self
+            .core
+            .smtp
+            .queue
+            .sending_quotas
+            .iter()
+            .find(|quota| quota.id == quota_id)
+        else {
+            return Ok(None);
+        };
+        let quota_key = build_quota_key(quota_id, key);
+        let override_ = self.sending_quota_override(&quota_key).await?;
+        let now = now();
+
+        let mut usage = SendingQuotaUsage {
+            override_,
+            ..Default::default()
+        };
+        for (period, usage) in [
+            (SendingPeriod::Hourly, &mut usage.hourly),
+            (SendingPeriod::Daily, &mut usage.daily),
+        ] {
+            let (messages, recipients) = self.sending_usage(period, &quota_key, now).await?;
+            usage.messages = messages;
+            usage.recipients = recipients;
+            usage.limit = period.limit(quota, override_.as_ref());
+            usage.resets = period.window_end(now);
+        }
+
+        Ok(Some(usage))
+    }
+
+    async fn sending_quota_override(
+        &self,
+        quota_key: &[u8],
+    ) -> trc::Result<Option<SendingQuotaOverride>> {
+        self.in_memory_store()
+            .key_get::<String>(build_key(KIND_OVERRIDE, quota_key))
+            .await
+            .caused_by(trc::location!())?
+            .map(|value| {
+                serde_json::from_str::<SendingQuotaOverride>(&value).map_err(|err| {
+                    trc::StoreEvent::DataCorruption
+                        .reason(err)
+                        .caused_by(trc::location!())
+                })
+            })
+            .transpose()
+    }
+
+    async fn set_sending_quota_override(
+        &self,
+        quota_id: &str,
+        key: &str,
+        override_: Option<SendingQuotaOverride>,
+        expires: Option<u64>,
+    ) -> trc::Result<()> {
+        let override_key = build_key(KIND_OVERRIDE, &build_quota_key(quota_id, key));
+        if let Some(override_) = override_ {
+            self.in_memory_store()
+                .key_set(
+                    KeyValue::new(
+                        override_key,
+                        serde_json::to_vec(&override_).unwrap_or_default(),
+                    )
+                    .expires_opt(expires),
+                )
+                .await
+        } else {
+            self.in_memory_store().key_delete(override_key).await
+        }
+        .caused_by(trc::location!())
+    }
+
+    async fn reset_sending_quota(&self, quota_id: &str, key: &str) -> trc::Result<()> {
+        let quota_key = build_quota_key(quota_id, key);
+        let now = now();
+
+        for period in [SendingPeriod::Hourly, SendingPeriod::Daily] {
+            for kind in [KIND_MESSAGES, KIND_RECIPIENTS] {
+                self.in_memory_store()
+                    .counter_delete(period.counter_key(kind, &quota_key, now))
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+trait SendingCounters {
+    fn sending_usage(
+        &self,
+        period: SendingPeriod,
+        quota_key: &[u8],
+        now: u64,
+    ) -> impl Future<Output = trc::Result<(u64, u64)>> + Send;
+
+    fn sending_incr(
+        &self,
+        period: SendingPeriod,
+        quota_key: &[u8],
+        num_rcpts: u64,
+        now: u64,
+    ) -> impl Future<Output = trc::Result<(u64, u64)>> + Send;
+
+    fn sending_decr(
+        &self,
+        period: SendingPeriod,
+        quota_key: &[u8],
+        num_rcpts: u64,
+        now: u64,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl SendingCounters for Server {
+    async fn sending_usage(
+        &self,
+        period: SendingPeriod,
+        quota_key: &[u8],
+        now: u64,
+    ) -> trc::Result<(u64, u64)> {
+        let store = self.in_memory_store();
+        let messages = store
+            .counter_get(period.counter_key(KIND_MESSAGES, quota_key, now))
+            .await
+            .caused_by(trc::location!())?;
+        let recipients = store
+            .counter_get(period.counter_key(KIND_RECIPIENTS, quota_key, now))
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok((messages.max(0) as u64, recipients.max(0) as u64))
+    }
+
+    async fn sending_incr(
+        &self,
+        period: SendingPeriod,
+        quota_key: &[u8],
+        num_rcpts: u64,
+        now: u64,
+    ) -> trc::Result<(u64, u64)> {
+        let store = self.in_memory_store();
+        let messages = store
+            .counter_incr(
+                KeyValue::new(period.counter_key(KIND_MESSAGES, quota_key, now), 1)
+                    .expires(period.as_secs()),
+                true,
+            )
+            .await
+            .caused_by(trc::location!())?;
+        let recipients = store
+            .counter_incr(
+                KeyValue::new(
+                    period.counter_key(KIND_RECIPIENTS, quota_key, now),
+                    num_rcpts as i64,
+                )
+                .expires(period.as_secs()),
+                true,
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok((messages.max(0) as u64, recipients.max(0) as u64))
+    }
+
+    async fn sending_decr(
+        &self,
+        period: SendingPeriod,
+        quota_key: &[u8],
+        num_rcpts: u64,
+        now: u64,
+    ) -> trc::Result<()> {
+        let store = self.in_memory_store();
+        store
+            .counter_incr(
+                KeyValue::new(period.counter_key(KIND_MESSAGES, quota_key, now), -1),
+                false,
+            )
+            .await
+            .caused_by(trc::location!())?;
+        store
+            .counter_incr(
+                KeyValue::new(
+                    period.counter_key(KIND_RECIPIENTS, quota_key, now),
+                    -(num_rcpts as i64),
+                ),
+                false,
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(())
+    }
+}
+
+impl SendingPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SendingPeriod::Hourly => "hourly",
+            SendingPeriod::Daily => "daily",
+        }
+    }
+
+    pub fn as_secs(&self) -> u64 {
+        match self {
+            SendingPeriod::Hourly => 3600,
+            SendingPeriod::Daily => 86400,
+        }
+    }
+
+    pub fn window_end(&self, now: u64) -> u64 {
+        (now / self.as_secs() + 1) * self.as_secs()
+    }
+
+    fn limit(
+        &self,
+        quota: &SendingQuota,
+        override_: Option<&SendingQuotaOverride>,
+    ) -> SendingLimit {
+        match self {
+            SendingPeriod::Hourly => override_.and_then(|o| o.hourly).unwrap_or(quota.hourly),
+            SendingPeriod::Daily => override_.and_then(|o| o.daily).unwrap_or(quota.daily),
+        }
+    }
+
+    fn counter_key(&self, kind: u8, quota_key: &[u8], now: u64) -> Vec<u8> {
+        let mut key = Vec::with_capacity(quota_key.len() + 11);
+        key.push(KV_SENDING_QUOTA);
+        key.push(match self {
+            SendingPeriod::Hourly => b'h',
+            SendingPeriod::Daily => b'd',
+        });
+        key.push(kind);
+        key.extend_from_slice(&(now / self.as_secs()).to_be_bytes());
+        key.extend_from_slice(quota_key);
+        key
+    }
+}
+
+fn build_quota_key(quota_id: &str, key: &str) -> Vec<u8> {
+    let mut quota_key = Vec::with_capacity(quota_id.len() + key.len() + 1);
+    quota_key.extend_from_slice(quota_id.as_bytes());
+    quota_key.push(0);
+    quota_key.extend_from_slice(key.as_bytes());
+    quota_key
+}
+
+fn build_key(kind: u8, quota_key: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(quota_key.len() + 2);
+    key.push(KV_SENDING_QUOTA);
+    key.push(kind);
+    key.extend_from_slice(quota_key);
+    key
+}
+
+fn crosses(limit: u64, percent: u64, used: u64, added: u64) -> bool {
+    let threshold = (limit * percent).div_ceil(100).max(1);
+    used >= threshold && used.saturating_sub(added) < threshold
+}
@@ -5,10 +5,11 @@
  */
 
 use super::{
-    ArchivedMessage, ArchivedStatus, Message, MessageSource, QueueEnvelope, QueueId, QueuedMessage,
-    QuotaKey, Recipient, Schedule, Status,
+    ArchivedMessage, ArchivedStatus, FairShareKey, Message, MessageSource, QueueEnvelope, QueueId,
+    QueuedMessage, QuotaKey, Recipient, Schedule, Status,
 };
 use crate::queue::manager::{LockedMessage, Queue};
+use crate::queue::storage::QueueStorage;
 use crate::queue::{
     FROM_AUTHENTICATED, FROM_AUTOGENERATED, FROM_DSN, FROM_REPORT, FROM_UNAUTHENTICATED,
     FROM_UNAUTHENTICATED_DMARC, MessageWrapper,
@@ -121,8 +122,8 @@ impl SmtpSpool for Server {
         let result = self
             .store()
             .iterate(
-                IterateParams::new(from_key, to_key).ascending().no_values(),
-                |key, _| {
+                IterateParams::new(from_key, to_key).ascending(),
+                |key, value| {
                     let due = key.deserialize_be_u64(0)?;
 
                     if due <= now {
@@ -136,10 +137,17 @@ impl SmtpSpool for Server {
                                     .ctx(trc::Key::Key, key)
                             })?;
 
-                        let add_event = queue
-                            .stats
-                            .get(&queue_name)
-                            .is_none_or(|stats| stats.has_capacity())
+                        // Track the backlog of queues running at capacity
+                        let has_capacity = match queue.stats.get_mut(&queue_name) {
+                            Some(stats) if !stats.has_capacity() => {
+                                stats.backlog += 1;
+                                stats.oldest_due = stats.oldest_due.min(due);
+                                false
+                            }
+                            _ => true,
+                        };
+
+                        let add_event = has_capacity
                             && match queue.locked.entry((queue_id, queue_name)) {
                                 Entry::Occupied(mut entry) => {
                                     let locked = entry.get_mut();
@@ -170,6 +178,7 @@ impl SmtpSpool for Server {
                                 due,
                                 queue_id,
                                 queue_name,
+                                fair_share: FairShareKey::deserialize(value),
                             });
                         }
 
@@ -335,28 +344,30 @@ impl MessageWrapper {
             self.message.size = message.len() as u64;
         }
 
-        // Reserve and write blob
-        let mut batch = BatchBuilder::new();
+        // Reserve and write blob, messages spooled to disk are not tracked in the blob store
+        let is_disk_spool = server.core.smtp.queue.spool.is_disk();
         let reserve_until = now() + 120;
-        batch.set(
-            BlobOp::Reserve {
-                hash: self.message.blob_hash.clone(),
-                until: reserve_until,
-            },
-            0u32.serialize(),
-        );
-        if let Err(err) = server.store().write(batch.build_all()).await {
-            trc::error!(
-                err.details("Failed to write to store.")
-                    .span_id(session_id)
-                    .caused_by(trc::location!())
+        if !is_disk_spool {
+            let mut batch = BatchBuilder::new();
+            batch.set(
+                BlobOp::Reserve {
+                    hash: self.message.blob_hash.clone(),
+                    until: reserve_until,
+                },
+                0u32.serialize(),
             );
+            if let Err(err) = server.store().write(batch.build_all()).await {
+                trc::error!(
+                    err.details("Failed to write to store.")
+                        .span_id(session_id)
+                        .caused_by(trc::location!())
+                );
 
-            return false;
+                return false;
+            }
         }
         if let Err(err) = server
-            .blob_store()
-            .put_blob(self.message.blob_hash.as_slice(), message.as_ref())
+            .put_queued_message(self.queue_id, &self.message.blob_hash, message.as_ref())
             .await
         {
             trc::error!(
@@ -407,9 +418,11 @@ impl MessageWrapper {
                         self.message.size as i64,
                     );
                 }
+                QuotaKey::Sending { .. } => {}
             }
         }
 
+        let fair_share = self.fair_share_key(server).await.serialize();
         for (queue_name, due) in self.message.next_events() {
             batch.set(
                 ValueClass::Queue(QueueClass::MessageEvent(store::write::QueueEvent {
@@ -417,42 +430,44 @@ impl MessageWrapper {
                     queue_id: self.queue_id,
                     queue_name: queue_name.into_inner(),
                 })),
-                Vec::new(),
+                fair_share.clone(),
             );
         }
 
-        batch
-            .clear(BlobOp::Reserve {
-                hash: self.message.blob_hash.clone(),
-                until: reserve_until,
-            })
-            .set(
-                BlobOp::LinkId {
+        if !is_disk_spool {
+            batch
+                .clear(BlobOp::Reserve {
                     hash: self.message.blob_hash.clone(),
-                    id: self.queue_id,
-                },
-                vec![],
-            )
-            .set(
-                BlobOp::Commit {
-                    hash: self.message.blob_hash.clone(),
-                },
-                vec![],
-            )
-            .set(
-                ValueClass::Queue(QueueClass::Message(self.queue_id)),
-                match Archiver::new(self.message).serialize() {
-                    Ok(data) => data,
-                    Err(err) => {
-                        trc::error!(
-                            err.details("Failed to serialize message.")
-                                .span_id(session_id)
-                                .caused_by(trc::location!())
-                        );
-                        return false;
-                    }
-                },
-            );
+                    until: reserve_until,
+                })
+                .set(
+                    BlobOp::LinkId {
+                        hash: self.message.blob_hash.clone(),
+                        id: self.queue_id,
+                    },
+                    vec![],
+                )
+                .set(
+                    BlobOp::Commit {
+                        hash: self.message.blob_hash.clone(),
+                    },
+                    vec![],
+                );
+        }
+        batch.set(
+            ValueClass::Queue(QueueClass::Message(self.queue_id)),
+            match Archiver::new(self.message).serialize() {
+                Ok(data) => data,
+                Err(err) => {
+                    trc::error!(
+                        err.details("Failed to serialize message.")
+                            .span_id(session_id)
+                            .caused_by(trc::location!())
+                    );
+                    return false;
+                }
+            },
+        );
 
         if let Err(err) = server.store().write(batch.build_all()).await {
             trc::error!(
@@ -460,6 +475,9 @@ impl MessageWrapper {
                     .span_id(session_id)
                     .caused_by(trc::location!())
             );
+            if is_disk_spool {
+                server.delete_queued_message(self.queue_id).await;
+            }
 
             return false;
         }
@@ -484,6 +502,25 @@ impl MessageWrapper {
         true
     }
 
+    async fn fair_share_key(&self, server: &Server) -> FairShareKey {
+        let config = &server.core.smtp.queue.fair_share;
+        FairShareKey::new(
+            &server
+                .eval_if::<String, _>(&config.key, &self.message, self.span_id)
+                .await
+                .unwrap_or_default(),
+            server
+                .eval_if(&config.weight, &self.message, self.span_id)
+                .await
+                .unwrap_or(1),
+            server
+                .eval_if(&config.max_in_flight, &self.message, self.span_id)
+                .await
+                .unwrap_or(0),
+            self.message.priority,
+        )
+    }
+
     pub async fn add_recipient(&mut self, rcpt: impl AsRef<str>, server: &Server) {
         // Resolve queue
         self.message.recipients.push(Recipient::new(rcpt));
@@ -522,6 +559,7 @@ impl MessageWrapper {
                 },
             )));
         }
+        let fair_share = self.fair_share_key(server).await.serialize();
         for (queue_name, due) in self.message.next_events() {
             batch.set(
                 ValueClass::Queue(QueueClass::MessageEvent(store::write::QueueEvent {
@@ -529,7 +567,7 @@ impl MessageWrapper {
                     queue_id: self.queue_id,
                     queue_name: queue_name.into_inner(),
                 })),
-                Vec::new(),
+                fair_share.clone(),
             );
         }
 
@@ -638,6 +676,7 @@ impl MessageWrapper {
                         -(self.message.size as i64),
                     );
                 }
+                QuotaKey::Sending { .. } => {}
             }
         }
 
@@ -656,6 +695,7 @@ impl MessageWrapper {
             );
             false
         } else {
+            server.delete_queued_message(self.queue_id).await;
             true
         }
     }
@@ -0,0 +1,316 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{Message, QueueId};
+use common::{Server, config::smtp::queue::QueueSpool};
+use std::{
+    future::Future,
+    io::SeekFrom,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use store::{
+    Deserialize, IterateParams, ValueKey,
+    write::{
+        AlignedBytes, Archive, BatchBuilder, BlobOp, QueueClass, ValueClass,
+        key::DeserializeBigEndian,
+    },
+};
+use tokio::{
+    fs::{self, File},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+use trc::AddContext;
+use types::blob_hash::BlobHash;
+
+// Spooled files without a queue entry are only removed after this long,
+// as they might belong to a message that is being queued
+const ORPHAN_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SpoolMigration {
+    pub migrated: usize,
+    pub missing: usize,
+    pub orphans_removed: usize,
+}
+
+pub trait QueueStorage: Sync + Send {
+    fn put_queued_message(
+        &self,
+        queue_id: QueueId,
+        hash: &BlobHash,
+        data: &[u8],
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn get_queued_message(
+        &self,
+        queue_id: QueueId,
+        hash: &BlobHash,
+        range: Range<usize>,
+    ) -> impl Future<Output = trc::Result<Option<Vec<u8>>>> + Send;
+
+    fn delete_queued_message(&self, queue_id: QueueId) -> impl Future<Output = ()> + Send;
+
+    fn migrate_queue_spool(&self) -> impl Future<Output = trc::Result<SpoolMigration>> + Send;
+}
+
+impl QueueStorage for Server {
+    async fn put_queued_message(
+        &self,
+        queue_id: QueueId,
+        hash: &BlobHash,
+        data: &[u8],
+    ) -> trc::Result<()> {
+        if let QueueSpool::Disk { path } = &self.core.smtp.queue.spool {
+            write_spool_file(path, queue_id, data).await
+        } else {
+            self.blob_store()
+                .put_blob(hash.as_slice(), data)
+                .await
+                .caused_by(trc::location!())
+        }
+    }
+
+    async fn get_queued_message(
+        &self,
+        queue_id: QueueId,
+        hash: &BlobHash,
+        range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        // Messages queued before switching backends are read from the previous one
+        match &self.core.smtp.queue.spool {
+            QueueSpool::Disk { path } => {
+                if let Some(data) = read_spool_file(path, queue_id, hash, range.clone()).await? {
+                    return Ok(Some(data));
+                }
+            }
+            QueueSpool::BlobStoreFromDisk { path } => {
+                if let Some(data) = self
+                    .blob_store()
+                    .get_blob(hash.as_slice(), range.clone())
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    return Ok(Some(data));
+                }
+
+                return read_spool_file(path, queue_id, hash, range).await;
+            }
+            QueueSpool::BlobStore => {}
+        }
+
+        self.blob_store()
+            .get_blob(hash.as_slice(), range)
+            .await
+            .caused_by(trc::location!())
+    }
+
+    async fn delete_queued_message(&self, queue_id: QueueId) {
+        if let Some(path) = self.core.smtp.queue.spool.path() {
+            let file_path = spool_file_path(path, queue_id);
+            match fs::remove_file(&file_path).await {
+                Ok(_) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    trc::error!(
+                        into_error(err)
+                            .details("Failed to delete spooled message.")
+                            .ctx(trc::Key::QueueId, queue_id)
+                            .ctx(trc::Key::Path, file_path.to_string_lossy().into_owned())
+                            .caused_by(trc::location!())
+                    );
+                }
+            }
+        }
+    }
+
+    async fn migrate_queue_spool(&self) -> trc::Result<SpoolMigration> {
+        let spool = &self.core.smtp.queue.spool;
+        let Some(path) = spool.path() else {
+            return Ok(SpoolMigration::default());
+        };
+
+        // Obtain all queued messages
+        let mut messages = Vec::new();
+        self.store()
+            .iterate(
+                IterateParams::new(
+                    ValueKey::from(ValueClass::Queue(QueueClass::Message(0))),
+                    ValueKey::from(ValueClass::Queue(QueueClass::Message(u64::MAX))),
+                )
+                .ascending(),
+                |key, value| {
+                    let message = <Archive<AlignedBytes> as Deserialize>::deserialize(value)
+                        .add_context(|ctx| ctx.ctx(trc::Key::Key, key))?;
+                    let message = message
+                        .unarchive::<Message>()
+                        .add_context(|ctx| ctx.ctx(trc::Key::Key, key))?;
+                    messages.push((
+                        key.deserialize_be_u64(0)?,
+                        BlobHash::from(&message.blob_hash),
+                    ));
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        // Copy each message to the active backend before unlinking it from the
+        // previous one, so a crash at any point leaves a readable copy behind
+        let mut result = SpoolMigration::default();
+        for (queue_id, hash) in &messages {
+            let (queue_id, hash) = (*queue_id, hash.clone());
+            if spool.is_disk() {
+                if fs::metadata(spool_file_path(path, queue_id)).await.is_ok() {
+                    continue;
+                }
+                let Some(data) = self
+                    .blob_store()
+                    .get_blob(hash.as_slice(), 0..usize::MAX)
+                    .await
+                    .caused_by(trc::location!())?
+                else {
+                    result.missing += 1;
+                    continue;
+                };
+                write_spool_file(path, queue_id, &data).await?;
+
+                let mut batch = BatchBuilder::new();
+                batch.clear(BlobOp::LinkId { hash, id: queue_id });
+                self.store()
+                    .write(batch.build_all())
+                    .await
+                    .caused_by(trc::location!())?;
+            } else {
+                let Some(data) = read_spool_file(path, queue_id, &hash, 0..usize::MAX).await?
+                else {
+                    continue;
+                };
+                self.blob_store()
+                    .put_blob(hash.as_slice(), &data)
+                    .await
+                    .caused_by(trc::location!())?;
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .set(
+                        BlobOp::LinkId {
+                            hash: hash.clone(),
+                            id: queue_id,
+                        },
+                        vec![],
+                    )
+                    .set(BlobOp::Commit { hash }, vec![]);
+                self.store()
+                    .write(batch.build_all())
+                    .await
+                    .caused_by(trc::location!())?;
+                self.delete_queued_message(queue_id).await;
+            }
+
+            result.migrated += 1;
+        }
+
+        // Remove files left behind by interrupted writes or deliveries
+        let mut entries = fs::read_dir(path).await.map_err(into_error)?;
+        let now = SystemTime::now();
+        while let Some(entry) = entries.next_entry().await.map_err(into_error)? {
+            let file_name = entry.file_name();
+            let Some(queue_id) = file_name.to_str().and_then(|name| {
+                QueueId::from_str_radix(name.strip_suffix(".tmp").unwrap_or(name), 16).ok()
+            }) else {
+                continue;
+            };
+            let is_orphan = file_name.to_string_lossy().ends_with(".tmp")
+                || !messages.iter().any(|(id, _)| *id == queue_id);
+            let is_old = entry
+                .metadata()
+                .await
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| {
+                    now.duration_since(modified)
+                        .is_ok_and(|age| age >= ORPHAN_MIN_AGE)
+                });
+
+            if is_orphan && is_old {
+                fs::remove_file(entry.path()).await.map_err(into_error)?;
+                result.orphans_removed += 1;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+fn spool_file_path(path: &Path, queue_id: QueueId) -> PathBuf {
+    path.join(format!("{queue_id:016x}"))
+}
+
+async fn write_spool_file(path: &Path, queue_id: QueueId, data: &[u8]) -> trc::Result<()> {
+    // Write to a temporary file and rename it once it is on disk, so a
+    // partially written message is never mistaken for a spooled one
+    let file_path = spool_file_path(path, queue_id);
+    let tmp_path = file_path.with_extension("tmp");
+    let mut file = File::create(&tmp_path).await.map_err(into_error)?;
+    file.write_all(data).await.map_err(into_error)?;
+    file.sync_all().await.map_err(into_error)?;
+    fs::rename(&tmp_path, &file_path)
+        .await
+        .map_err(into_error)?;
+
+    // Persist the rename
+    File::open(path)
+        .await
+        .map_err(into_error)?
+        .sync_all()
+        .await
+        .map_err(into_error)
+}
+
+async fn read_spool_file(
+    path: &Path,
+    queue_id: QueueId,
+    hash: &BlobHash,
+    range: Range<usize>,
+) -> trc::Result<Option<Vec<u8>>> {
+    let mut file = match File::open(spool_file_path(path, queue_id)).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(into_error(err)),
+    };
+
+    if range.start != 0 || range.end != usize::MAX {
+        let size = file.metadata().await.map_err(into_error)?.len() as usize;
+        let start = range.start.min(size);
+        let mut buf = vec![0; range.end.min(size) - start];
+        if start > 0 {
+            file.seek(SeekFrom::Start(start as u64))
+                .await
+                .map_err(into_error)?;
+        }
+        file.read_exact(&mut buf).await.map_err(into_error)?;
+        Ok(Some(buf))
+    } else {
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).await.map_err(into_error)?;
+
+        if &BlobHash::generate(&buf) == hash {
+            Ok(Some(buf))
+        } else {
+            Err(trc::StoreEvent::DataCorruption
+                .into_err()
+                .details("Spooled message does not match its hash.")
+                .ctx(trc::Key::QueueId, queue_id)
+                .caused_by(trc::location!()))
+        }
+    }
+}
+
+fn into_error(err: std::io::Error) -> trc::Error {
+    trc::StoreEvent::FilesystemError.reason(err)
+}
@@ -0,0 +1,124 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{MESSAGE_TRACE, MessageWrapper, QueueId};
+use common::{KV_MESSAGE_TRACE, Server};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use store::{
+    dispatch::lookup::KeyValue,
+    write::{key::DeserializeBigEndian, now},
+};
+use trc::AddContext;
+
+pub const TRACE_HEADER: &str = "X-Stalwart-Trace";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TraceStage {
+    Received,
+    Filter,
+    Queued,
+    Rejected,
+    Delivery,
+    Recipient,
+    Rescheduled,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEntry {
+    pub timestamp: u64,
+    pub stage: TraceStage,
+    pub details: String,
+}
+
+pub trait MessageTrace: Sync + Send {
+    fn trace_message(
+        &self,
+        queue_id: QueueId,
+        stage: TraceStage,
+        details: impl Into<String> + Send,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn message_trace(
+        &self,
+        queue_id: QueueId,
+    ) -> impl Future<Output = trc::Result<Vec<TraceEntry>>> + Send;
+}
+
+impl MessageTrace for Server {
+    async fn trace_message(
+        &self,
+        queue_id: QueueId,
+        stage: TraceStage,
+        details: impl Into<String> + Send,
+    ) {
+        // Entries are keyed by queue id and insertion time so they can be
+        // retrieved in order once the message has left the queue.
+        let seq = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let mut key = Vec::with_capacity(17);
+        key.push(KV_MESSAGE_TRACE);
+        key.extend_from_slice(&queue_id.to_be_bytes());
+        key.extend_from_slice(&seq.to_be_bytes());
+
+        let entry = TraceEntry {
+            timestamp: now(),
+            stage,
+            details: details.into(),
+        };
+
+        if let Err(err) = self
+            .in_memory_store()
+            .key_set(
+                KeyValue::new(key, serde_json::to_vec(&entry).unwrap_or_default())
+                    .expires(self.core.smtp.session.data.trace_retention.as_secs()),
+            )
+            .await
+        {
+            trc::error!(
+                err.ctx(trc::Key::QueueId, queue_id)
+                    .details("Failed to store message trace")
+                    .caused_by(trc::location!())
+            );
+        }
+    }
+
+    async fn message_trace(&self, queue_id: QueueId) -> trc::Result<Vec<TraceEntry>> {
+        let mut prefix = Vec::with_capacity(9);
+        prefix.push(KV_MESSAGE_TRACE);
+        prefix.extend_from_slice(&queue_id.to_be_bytes());
+
+        let mut entries = self
+            .in_memory_store()
+            .key_get_prefix(&prefix)
+            .await
+            .caused_by(trc::location!())?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                Some((
+                    key.as_slice().deserialize_be_u64(prefix.len()).ok()?,
+                    serde_json::from_slice::<TraceEntry>(&value).ok()?,
+                ))
+            })
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(seq, _)| *seq);
+
+        Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+    }
+}
+
+impl MessageWrapper {
+    pub fn is_traced(&self) -> bool {
+        (self.message.flags & MESSAGE_TRACE) != 0
+    }
+}
@@ -0,0 +1,386 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{future::Future, net::IpAddr};
+
+use common::{
+    KV_ABUSE_COMPLAINTS, KV_ABUSE_SUPPRESS, Server, USER_AGENT,
+    expr::{
+        V_RECIPIENT, V_RECIPIENT_DOMAIN, V_REMOTE_IP, V_SENDER, V_SENDER_DOMAIN, Variable,
+        functions::ResolveVariable,
+    },
+    scripts::auth_results::AuthResults,
+};
+use compact_str::ToCompactString;
+use mail_auth::report::{Feedback, FeedbackType};
+use mail_parser::{HeaderName, HeaderValue, Message, MessageParser};
+use store::{dispatch::lookup::KeyValue, write::now};
+use trc::{IncomingReportEvent, OutgoingReportEvent};
+use utils::DomainPart;
+
+use super::SmtpReporting;
+
+pub trait AbuseReporting: Sync + Send {
+    fn send_abuse_report(
+        &self,
+        raw_message: &[u8],
+        rcpt: &str,
+        session_id: u64,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn record_abuse_complaint(
+        &self,
+        feedback: &Feedback<'_>,
+        source: Option<&str>,
+        session_id: u64,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn is_abuse_suppressed(
+        &self,
+        sender: &str,
+        rcpt: &str,
+        session_id: u64,
+    ) -> impl Future<Output = bool> + Send;
+
+    fn is_sender_suspended(
+        &self,
+        sender: &str,
+        session_id: u64,
+    ) -> impl Future<Output = bool> + Send;
+}
+
+struct AbuseReportContext<'x> {
+    sender: &'x str,
+    rcpt: &'x str,
+    remote_ip: Option<IpAddr>,
+}
+
+impl AbuseReporting for Server {
+    async fn send_abuse_report(&self, raw_message: &[u8], rcpt: &str, session_id: u64) {
+        let config = &self.core.smtp.report.abuse;
+        if config.to.is_empty() {
+            return;
+        }
+
+        let Some(message) = MessageParser::new().parse_headers(raw_message) else {
+            return;
+        };
+        let sender = original_sender(&message).unwrap_or_default().to_lowercase();
+        let ctx = AbuseReportContext {
+            sender: &sender,
+            rcpt,
+            remote_ip: original_ip(&message),
+        };
+
+        // Obtain the abuse contacts of the originating network
+        let rcpts = self
+            .eval_if::<Vec<String>, _>(&config.to, &ctx, session_id)
+            .await
+            .unwrap_or_default();
+        if rcpts.is_empty() {
+            return;
+        }
+
+        let from_addr = self
+            .eval_if(&config.address, &ctx, session_id)
+            .await
+            .unwrap_or_else(|| "MAILER-DAEMON@localhost".to_string());
+        let mut feedback = Feedback::new(FeedbackType::Abuse)
+            .with_arrival_date(
+                message
+                    .date()
+                    .map(|d| d.to_timestamp())
+                    .unwrap_or_else(|| now() as i64),
+            )
+            .with_reporting_mta(&self.core.network.server_name)
+            .with_user_agent(USER_AGENT)
+            .with_original_rcpt_to(rcpt)
+            .with_message(String::from_utf8_lossy(raw_message));
+        if !sender.is_empty() {
+            feedback = feedback.with_original_mail_from(sender.as_str());
+        }
+        if let Some(remote_ip) = ctx.remote_ip {
+            feedback = feedback.with_source_ip(remote_ip);
+        }
+        let mut report = Vec::with_capacity(raw_message.len() + 128);
+        for rcpt in &rcpts {
+            report.clear();
+            feedback
+                .write_rfc5322(
+                    (
+                        self.eval_if(&config.name, &ctx, session_id)
+                            .await
+                            .unwrap_or_else(|| "Report Subsystem".to_string())
+                            .as_str(),
+                        from_addr.as_str(),
+                    ),
+                    rcpt,
+                    &self
+                        .eval_if(&config.subject, &ctx, session_id)
+                        .await
+                        .unwrap_or_else(|| "Abuse Report".to_string()),
+                    &mut report,
+                )
+                .ok();
+
+            trc::event!(
+                OutgoingReport(OutgoingReportEvent::AbuseReport),
+                SpanId = session_id,
+                From = from_addr.to_string(),
+                To = rcpt.to_string(),
+                RemoteIp = ctx.remote_ip,
+            );
+
+            self.send_report(
+                &from_addr,
+                [rcpt].into_iter(),
+                report.clone(),
+                &config.sign,
+                false,
+                session_id,
+            )
+            .await;
+        }
+    }
+
+    async fn record_abuse_complaint(
+        &self,
+        feedback: &Feedback<'_>,
+        source: Option<&str>,
+        session_id: u64,
+    ) {
+        let config = &self.core.smtp.report.analysis.feedback;
+        if feedback.feedback_type() != FeedbackType::Abuse
+            || (config.suppress.is_none() && config.max_complaints == 0)
+        {
+            return;
+        }
+
+        // Reports are trivial to forge, only act on authenticated trusted feedback loops
+        if !source.is_some_and(|source| {
+            config
+                .trusted_sources
+                .iter()
+                .any(|trusted| is_domain_aligned(source, trusted))
+        }) {
+            trc::event!(
+                IncomingReport(IncomingReportEvent::AbuseComplaintIgnored),
+                SpanId = session_id,
+                Domain = source.map(|source| source.to_string()),
+            );
+            return;
+        }
+
+        // Feedback loops often redact the envelope, fall back to the original message
+        let message = feedback
+            .message()
+            .or_else(|| feedback.headers())
+            .and_then(|message| MessageParser::new().parse_headers(message.as_bytes()));
+        let Some(sender) = feedback
+            .original_mail_from()
+            .map(|sender| sender.trim_matches(['<', '>']).to_lowercase())
+            .or_else(|| {
+                message
+                    .as_ref()
+                    .and_then(original_sender)
+                    .map(|sender| sender.to_lowercase())
+            })
+            .filter(|sender| sender.contains('@'))
+        else {
+            return;
+        };
+
+        // Only complaints about local senders are actionable
+        match self
+            .core
+            .storage
+            .directory
+            .is_local_domain(sender.domain_part())
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+                return;
+            }
+        }
+
+        let rcpt = feedback
+            .original_rcpt_to()
+            .map(|rcpt| rcpt.trim_matches(['<', '>']).to_lowercase())
+            .or_else(|| {
+                message
+                    .as_ref()
+                    .and_then(|message| message.to())
+                    .and_then(|to| to.first())
+                    .and_then(|to| to.address())
+                    .map(|rcpt| rcpt.to_lowercase())
+            })
+            .filter(|rcpt| rcpt.contains('@') && !rcpt.contains("redacted"));
+
+        // Suppress further deliveries from the sender to the complaining recipient
+        if let (Some(suppress), Some(rcpt)) = (config.suppress, &rcpt)
+            && let Err(err) = self
+                .in_memory_store()
+                .key_set(
+                    KeyValue::new(suppress_key(&sender, rcpt), vec![]).expires(suppress.as_secs()),
+                )
+                .await
+        {
+            trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+        }
+
+        // Count complaints received for the sender
+        let complaints = if config.max_complaints > 0 {
+            match self
+                .in_memory_store()
+                .counter_incr(
+                    KeyValue::with_prefix(KV_ABUSE_COMPLAINTS, sender.as_bytes(), 1)
+                        .expires(config.window.as_secs()),
+                    true,
+                )
+                .await
+            {
+                Ok(complaints) => complaints,
+                Err(err) => {
+                    trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+                    0
+                }
+            }
+        } else {
+            0
+        };
+
+        trc::event!(
+            IncomingReport(IncomingReportEvent::AbuseComplaint),
+            SpanId = session_id,
+            From = sender,
+            To = rcpt,
+            Total = complaints,
+        );
+    }
+
+    async fn is_abuse_suppressed(&self, sender: &str, rcpt: &str, session_id: u64) -> bool {
+        if self.core.smtp.report.analysis.feedback.suppress.is_some() {
+            match self
+                .in_memory_store()
+                .key_exists(suppress_key(sender, rcpt))
+                .await
+            {
+                Ok(is_suppressed) => is_suppressed,
+                Err(err) => {
+                    trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+                    false
+                }
+            }
+        } else {
+            false
+        }
+    }
+
+    async fn is_sender_suspended(&self, sender: &str, session_id: u64) -> bool {
+        let max_complaints = self.core.smtp.report.analysis.feedback.max_complaints;
+        if max_complaints > 0 {
+            match self
+                .in_memory_store()
+                .counter_get(KeyValue::<()>::build_key(
+                    KV_ABUSE_COMPLAINTS,
+                    sender.as_bytes(),
+                ))
+                .await
+            {
+                Ok(complaints) => complaints as u64 >= max_complaints,
+                Err(err) => {
+                    trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+                    false
+                }
+            }
+        } else {
+            false
+        }
+    }
+}
+
+// Relaxed alignment of a passing DKIM signature or SPF check with the author domain
+pub fn is_authenticated_source(results: &AuthResults, domain: &str) -> bool {
+    results
+        .dkim
+        .iter()
+        .chain(results.spf.as_ref())
+        .any(|verdict| {
+            verdict.result == "pass"
+                && !verdict.domain.is_empty()
+                && (is_domain_aligned(domain, &verdict.domain)
+                    || is_domain_aligned(&verdict.domain, domain))
+        })
+}
+
+pub fn is_domain_aligned(domain: &str, parent: &str) -> bool {
+    domain
+        .strip_suffix(parent)
+        .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+}
+
+fn original_sender<'x>(message: &'x Message<'x>) -> Option<&'x str> {
+    message
+        .header(HeaderName::ReturnPath)
+        .and_then(|value| match value {
+            HeaderValue::Address(addr) => addr.first().and_then(|addr| addr.address()),
+            HeaderValue::Text(text) => Some(text.trim_matches(['<', '>', ' '])),
+            _ => None,
+        })
+        .filter(|sender| !sender.is_empty())
+        .or_else(|| {
+            message
+                .from()
+                .and_then(|from| from.first())
+                .and_then(|from| from.address())
+        })
+}
+
+fn original_ip(message: &Message<'_>) -> Option<IpAddr> {
+    // The topmost Received header was added when the message was accepted
+    message.headers().iter().find_map(|header| {
+        if let (HeaderName::Received, HeaderValue::Received(received)) =
+            (&header.name, &header.value)
+        {
+            received.from_ip()
+        } else {
+            None
+        }
+    })
+}
+
+fn suppress_key(sender: &str, rcpt: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(sender.len() + rcpt.len() + 2);
+    key.push(KV_ABUSE_SUPPRESS);
+    key.extend_from_slice(sender.as_bytes());
+    key.push(0);
+    key.extend_from_slice(rcpt.as_bytes());
+    key
+}
+
+impl ResolveVariable for AbuseReportContext<'_> {
+    fn resolve_variable(&self, variable: u32) -> Variable<'_> {
+        match variable {
+            V_SENDER => self.sender.into(),
+            V_SENDER_DOMAIN => self.sender.domain_part().into(),
+            V_RECIPIENT => self.rcpt.into(),
+            V_RECIPIENT_DOMAIN => self.rcpt.domain_part().into(),
+            V_REMOTE_IP => self
+                .remote_ip
+                .map(|ip| ip.to_compact_string())
+                .unwrap_or_default()
+                .into(),
+            _ => "".into(),
+        }
+    }
+
+    fn resolve_global(&self, _: &str) -> Variable<'_> {
+        Variable::Integer(0)
+    }
+}
@@ -23,6 +23,8 @@ use store::{
 };
 use trc::IncomingReportEvent;
 
+use super::abuse::AbuseReporting;
+
 enum Compression {
     None,
     Gzip,
@@ -52,11 +54,11 @@ pub struct IncomingReport<T> {
 }
 
 pub trait AnalyzeReport: Sync + Send {
-    fn analyze_report(&self, message: Message<'static>, session_id: u64);
+    fn analyze_report(&self, message: Message<'static>, source: Option<String>, session_id: u64);
 }
 
 impl AnalyzeReport for Server {
-    fn analyze_report(&self, message: Message<'static>, session_id: u64) {
+    fn analyze_report(&self, message: Message<'static>, source: Option<String>, session_id: u64) {
         let core = self.clone();
         tokio::spawn(async move {
             let from: String = message
@@ -256,6 +258,11 @@ impl AnalyzeReport for Server {
                         Some(report) => {
                             // Log
                             report.log();
+
+                            // Process feedback loop complaints
+                            core.record_abuse_complaint(&report, source.as_deref(), session_id)
+                                .await;
+
                             Format::Arf(report.into_owned())
                         }
                         None => {
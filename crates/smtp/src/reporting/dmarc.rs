@@ -9,7 +9,7 @@ use crate::{core::Session, queue::RecipientDomain, reporting::SmtpReporting};
 use ahash::AHashMap;
 use common::{
     Server,
-    config::smtp::report::AggregateFrequency,
+    config::smtp::report::{AggregateFrequency, FailureReportPrivacy},
     ipc::{DmarcEvent, ToHash},
     listener::SessionStream,
 };
@@ -21,9 +21,9 @@ use mail_auth::{
     dmarc::{self, URI},
     report::{AuthFailureType, IdentityAlignment, PolicyPublished, Record, Report, SPFDomainScope},
 };
-use std::{collections::hash_map::Entry, future::Future};
+use std::{borrow::Cow, collections::hash_map::Entry, future::Future};
 use store::{
-    Deserialize, IterateParams, Serialize, ValueKey,
+    Deserialize, IterateParams, Serialize, ValueKey, blake3,
     write::{AlignedBytes, Archive, Archiver, BatchBuilder, QueueClass, ReportEvent, ValueClass},
 };
 use trc::{AddContext, OutgoingReportEvent};
@@ -129,10 +129,16 @@ impl<T: SessionStream> Session<T> {
                     .eval_if(&config.address, self, self.data.session_id)
                     .await
                     .unwrap_or_else(|| "MAILER-DAEMON@localhost".to_compact_string());
+                let privacy = &self.server.core.smtp.report.dmarc_privacy;
+                let original = redact_message(message, privacy);
                 let mut auth_failure = self
                     .new_auth_failure(AuthFailureType::Dmarc, rejected)
-                    .with_authentication_results(auth_results.to_string())
-                    .with_headers(std::str::from_utf8(message.raw_headers()).unwrap_or_default());
+                    .with_authentication_results(auth_results.to_string());
+                auth_failure = if privacy.strip_body {
+                    auth_failure.with_headers(original.as_ref())
+                } else {
+                    auth_failure.with_message(original.as_ref())
+                };
 
                 // Report the first failed signature
                 let dkim_failed = if let (
@@ -689,3 +695,79 @@ impl DmarcReporting for Server {
         }
     }
 }
+
+// Builds the copy of the original message included in a failure report,
+// applying the configured redaction rules (RFC 6591, section 6)
+fn redact_message<'x>(
+    message: &'x AuthenticatedMessage<'_>,
+    privacy: &FailureReportPrivacy,
+) -> Cow<'x, str> {
+    let headers = if privacy.strip_subject || privacy.hash_recipients {
+        let mut headers = String::with_capacity(message.raw_headers().len());
+        for (name, value) in message.raw_parsed_headers() {
+            let name = std::str::from_utf8(name).unwrap_or_default();
+            let value = String::from_utf8_lossy(value);
+
+            if name.eq_ignore_ascii_case("Subject") && privacy.strip_subject {
+                continue;
+            }
+
+            headers.push_str(name);
+            headers.push(':');
+            if privacy.hash_recipients
+                && [
+                    "To",
+                    "Cc",
+                    "Bcc",
+                    "Delivered-To",
+                    "X-Original-To",
+                    "Resent-To",
+                    "Resent-Cc",
+                ]
+                .iter()
+                .any(|header| name.eq_ignore_ascii_case(header))
+            {
+                headers.push(' ');
+                headers.push_str(&redact_addresses(&value));
+                headers.push_str("\r\n");
+            } else {
+                headers.push_str(&value);
+                if !value.ends_with('\n') {
+                    headers.push_str("\r\n");
+                }
+            }
+        }
+        headers.push_str("\r\n");
+        Cow::Owned(headers)
+    } else {
+        String::from_utf8_lossy(message.raw_headers())
+    };
+
+    if privacy.strip_body {
+        headers
+    } else {
+        let mut original = headers.into_owned();
+        original.push_str(&String::from_utf8_lossy(message.raw_body()));
+        Cow::Owned(original)
+    }
+}
+
+// Replaces each address in a header value with a hash of its local part,
+// dropping display names and comments
+fn redact_addresses(value: &str) -> String {
+    value
+        .split(|c: char| {
+            c.is_ascii_whitespace() || matches!(c, '<' | '>' | ',' | ';' | '"' | '(' | ')')
+        })
+        .filter_map(|token| {
+            let (local, domain) = token.rsplit_once('@')?;
+            let hash = blake3::hash(local.to_lowercase().as_bytes());
+            Some(format!(
+                "{}@{}",
+                &hash.to_hex()[..16],
+                domain.to_lowercase()
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -24,6 +24,7 @@ use std::{future::Future, io, time::SystemTime};
 use store::write::{ReportEvent, key::KeySerializer};
 use tokio::io::{AsyncRead, AsyncWrite};
 
+pub mod abuse;
 pub mod analysis;
 pub mod dkim;
 pub mod dmarc;
@@ -8,8 +8,12 @@ use std::future::Future;
 
 use common::Server;
 use mail_auth::{DkimResult, DmarcResult, SpfResult, dmarc::Policy};
+use mail_parser::Message;
 
-use crate::SpamFilterContext;
+use crate::{
+    SpamFilterContext,
+    modules::dkim::{has_unsigned_body, unsigned_duplicate_header},
+};
 
 pub trait SpamFilterAnalyzeDmarc: Sync + Send {
     fn spam_filter_analyze_dmarc(
@@ -20,19 +24,30 @@ pub trait SpamFilterAnalyzeDmarc: Sync + Send {
 
 impl SpamFilterAnalyzeDmarc for Server {
     async fn spam_filter_analyze_dmarc(&self, ctx: &mut SpamFilterContext<'_>) {
-        ctx.result.add_tag(
-            ctx.input
-                .spf_mail_from_result
-                .map_or("SPF_NA", |r| match r.result() {
-                    SpfResult::Pass => "SPF_ALLOW",
-                    SpfResult::Fail => "SPF_FAIL",
-                    SpfResult::SoftFail => "SPF_SOFTFAIL",
-                    SpfResult::Neutral => "SPF_NEUTRAL",
-                    SpfResult::TempError => "SPF_DNSFAIL",
-                    SpfResult::PermError => "SPF_PERMFAIL",
-                    SpfResult::None => "SPF_NA",
-                }),
-        );
+        // A validated ARC chain sealed by a trusted intermediary (such as a
+        // forwarding service or mailing list) overrides SPF and DMARC failures
+        let is_trusted_arc = ctx
+            .input
+            .arc_result
+            .is_some_and(|r| matches!(r.result(), DkimResult::Pass))
+            && arc_sealer(ctx.input.message)
+                .is_some_and(|sealer| self.core.spam.trusted_arc_sealers.contains(&sealer));
+
+        match ctx
+            .input
+            .spf_mail_from_result
+            .map_or("SPF_NA", |r| match r.result() {
+                SpfResult::Pass => "SPF_ALLOW",
+                SpfResult::Fail => "SPF_FAIL",
+                SpfResult::SoftFail => "SPF_SOFTFAIL",
+                SpfResult::Neutral => "SPF_NEUTRAL",
+                SpfResult::TempError => "SPF_DNSFAIL",
+                SpfResult::PermError => "SPF_PERMFAIL",
+                SpfResult::None => "SPF_NA",
+            }) {
+            "SPF_FAIL" | "SPF_SOFTFAIL" | "SPF_NEUTRAL" if is_trusted_arc => (),
+            tag => ctx.result.add_tag(tag),
+        }
 
         ctx.result.add_tag(
             match ctx
@@ -52,6 +67,22 @@ impl SpamFilterAnalyzeDmarc for Server {
             },
         );
 
+        // Passing signatures that leave part of the message unsigned
+        for signature in ctx
+            .input
+            .dkim_result
+            .iter()
+            .filter(|r| matches!(r.result(), DkimResult::Pass))
+            .filter_map(|r| r.signature())
+        {
+            if has_unsigned_body(signature, ctx.input.message) {
+                ctx.result.add_tag("DKIM_UNSIGNED_BODY");
+            }
+            if unsigned_duplicate_header(signature, ctx.input.message).is_some() {
+                ctx.result.add_tag("DKIM_UNSIGNED_HEADER");
+            }
+        }
+
         ctx.result
             .add_tag(ctx.input.arc_result.map_or("ARC_NA", |r| match r.result() {
                 DkimResult::Pass => "ARC_ALLOW",
@@ -61,6 +92,14 @@ impl SpamFilterAnalyzeDmarc for Server {
                 DkimResult::Neutral(_) | DkimResult::None => "ARC_NA",
             }));
 
+        if is_trusted_arc {
+            ctx.result.add_tag("ARC_ALLOW_TRUSTED");
+
+            if matches!(ctx.input.dmarc_result, Some(DmarcResult::Fail(_))) {
+                return;
+            }
+        }
+
         ctx.result
             .add_tag(ctx.input.dmarc_result.map_or("DMARC_NA", |r| match r {
                 DmarcResult::Pass => "DMARC_POLICY_ALLOW",
@@ -78,3 +117,27 @@ impl SpamFilterAnalyzeDmarc for Server {
             }));
     }
 }
+
+// Returns the domain of the most recent ARC-Seal in the chain
+fn arc_sealer(message: &Message<'_>) -> Option<String> {
+    message
+        .headers()
+        .iter()
+        .filter(|header| header.name().eq_ignore_ascii_case("ARC-Seal"))
+        .filter_map(|header| {
+            let mut instance = None;
+            let mut domain = None;
+            for tag in header.value().as_text()?.split(';') {
+                if let Some((name, value)) = tag.split_once('=') {
+                    match name.trim() {
+                        "i" => instance = value.trim().parse::<u32>().ok(),
+                        "d" => domain = Some(value.trim().to_lowercase()),
+                        _ => (),
+                    }
+                }
+            }
+            Some((instance?, domain?))
+        })
+        .max_by_key(|(instance, _)| *instance)
+        .map(|(_, domain)| domain)
+}
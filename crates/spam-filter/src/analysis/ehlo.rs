@@ -6,7 +6,10 @@
 
 use std::future::Future;
 
-use common::Server;
+use common::{
+    Server,
+    config::smtp::session::{HELO_ADDRESS_MISMATCH, HELO_NO_ADDRESS},
+};
 
 use crate::SpamFilterContext;
 
@@ -38,13 +41,26 @@ impl SpamFilterAnalyzeEhlo for Server {
                 ctx.result.add_tag("HELO_IPREV_MISMATCH");
             }
 
-            if matches!(
-                (
-                    self.dns_exists_ip(&ctx.output.ehlo_host.fqdn).await,
-                    self.dns_exists_mx(&ctx.output.ehlo_host.fqdn).await
-                ),
-                (Ok(false), Ok(false))
-            ) {
+            if let Some(validation) = ctx.input.helo_validation
+                && validation.has(HELO_ADDRESS_MISMATCH)
+            {
+                // Helo A/AAAA records do not match the remote IP
+                ctx.result.add_tag("HELO_A_MISMATCH");
+            }
+
+            // Skip the address lookup when the EHLO validation already resolved it
+            if ctx
+                .input
+                .helo_validation
+                .is_none_or(|validation| validation.has(HELO_NO_ADDRESS))
+                && matches!(
+                    (
+                        self.dns_exists_ip(&ctx.output.ehlo_host.fqdn).await,
+                        self.dns_exists_mx(&ctx.output.ehlo_host.fqdn).await
+                    ),
+                    (Ok(false), Ok(false))
+                )
+            {
                 // Helo no resolve to A or MX
                 ctx.result.add_tag("HELO_NORES_A_OR_MX");
             }
@@ -0,0 +1,216 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{future::Future, time::Instant};
+
+use common::{KV_SPAM_FUZZY, Server, config::spamfilter::FuzzyConfig};
+use store::dispatch::lookup::{KeyValue, LookupKey};
+
+use crate::{
+    SpamFilterContext,
+    modules::{
+        fuzzy::{FuzzyDigest, FuzzyEntry, FuzzyRemoteClient, FuzzyText},
+        key_set,
+    },
+};
+
+pub trait SpamFilterAnalyzeFuzzy: Sync + Send {
+    fn spam_filter_analyze_fuzzy(
+        &self,
+        ctx: &mut SpamFilterContext<'_>,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn spam_filter_fuzzy_learn(
+        &self,
+        ctx: &SpamFilterContext<'_>,
+    ) -> impl Future<Output = ()> + Send;
+}
+
+impl SpamFilterAnalyzeFuzzy for Server {
+    async fn spam_filter_analyze_fuzzy(&self, ctx: &mut SpamFilterContext<'_>) {
+        if let Some(config) = &self.core.spam.fuzzy
+            && !ctx.result.has_tag("SPAM_TRAP")
+        {
+            let text = ctx.input.message.fuzzy_text();
+            if text.len() < config.min_length {
+                return;
+            }
+
+            let time = Instant::now();
+            let digest = FuzzyDigest::compute(&text);
+            let mut matches = Vec::with_capacity(2);
+
+            // Local database
+            match fuzzy_lookup(self, &digest).await {
+                Ok(entries) => {
+                    matches.extend(
+                        best_match(&digest, &entries)
+                            .map(|entry| (entry.digest.similarity(&digest), entry.reports)),
+                    );
+                }
+                Err(err) => {
+                    trc::error!(
+                        err.span_id(ctx.input.span_id)
+                            .ctx(trc::Key::Elapsed, time.elapsed())
+                            .caused_by(trc::location!())
+                    );
+                }
+            }
+
+            // Collaborative checksum service
+            if let Some(remote) = &config.remote {
+                match (FuzzyRemoteClient { config: remote }).check(&digest).await {
+                    Ok(result) => {
+                        matches.extend(result);
+                    }
+                    Err(err) => {
+                        trc::error!(
+                            err.span_id(ctx.input.span_id)
+                                .ctx(trc::Key::Elapsed, time.elapsed())
+                                .caused_by(trc::location!())
+                        );
+                    }
+                }
+            }
+
+            if let Some((similarity, reports)) =
+                matches.into_iter().max_by(|a, b| a.0.total_cmp(&b.0))
+            {
+                let is_spam = similarity >= config.min_similarity && reports >= config.min_reports;
+                if is_spam {
+                    ctx.result.add_tag(if similarity >= 0.97 {
+                        "FUZZY_SPAM_HIGH"
+                    } else if similarity >= 0.92 {
+                        "FUZZY_SPAM_MEDIUM"
+                    } else {
+                        "FUZZY_SPAM_LOW"
+                    });
+                }
+                trc::event!(
+                    Spam(trc::SpamEvent::Fuzzy),
+                    Result = is_spam,
+                    Details = vec![trc::Value::from(similarity), trc::Value::from(reports)],
+                    SpanId = ctx.input.span_id,
+                    Elapsed = time.elapsed()
+                );
+            }
+        }
+    }
+
+    async fn spam_filter_fuzzy_learn(&self, ctx: &SpamFilterContext<'_>) {
+        if let Some(config) = &self.core.spam.fuzzy
+            && config.auto_learn
+            && !ctx.input.is_test
+        {
+            let text = ctx.input.message.fuzzy_text();
+            if text.len() < config.min_length {
+                return;
+            }
+
+            let time = Instant::now();
+            let digest = FuzzyDigest::compute(&text);
+            let entries = match fuzzy_lookup(self, &digest).await {
+                Ok(entries) => entries,
+                Err(err) => {
+                    trc::error!(err.span_id(ctx.input.span_id).caused_by(trc::location!()));
+                    return;
+                }
+            };
+
+            // Increase the report count of similar spam already in the database
+            let reports = entries
+                .iter()
+                .flatten()
+                .filter(|entry| is_same_cluster(config, &digest, entry))
+                .map(|entry| entry.reports)
+                .max()
+                .unwrap_or_default()
+                .saturating_add(1);
+
+            // Bands already holding an unrelated digest are left untouched,
+            // otherwise a collision would evict a different spam campaign.
+            let value = FuzzyEntry { digest, reports }.to_text().into_bytes();
+            let mut bands = 0;
+            for ((band_id, band), entry) in digest.bands().zip(entries.iter()) {
+                if entry
+                    .as_ref()
+                    .is_none_or(|entry| is_same_cluster(config, &digest, entry))
+                {
+                    key_set(
+                        self,
+                        ctx.input.span_id,
+                        KeyValue::new(fuzzy_key(band_id, band), value.clone())
+                            .expires(config.expiry),
+                    )
+                    .await;
+                    bands += 1;
+                }
+            }
+
+            // Share the checksum with the collaborative service
+            if let Some(remote) = config.remote.as_ref().filter(|remote| remote.report)
+                && let Err(err) = (FuzzyRemoteClient { config: remote }).report(&digest).await
+            {
+                trc::error!(err.span_id(ctx.input.span_id).caused_by(trc::location!()));
+            }
+
+            trc::event!(
+                Spam(trc::SpamEvent::FuzzyLearn),
+                Details = vec![trc::Value::from(reports), trc::Value::from(bands)],
+                SpanId = ctx.input.span_id,
+                Elapsed = time.elapsed()
+            );
+        }
+    }
+}
+
+// Near-duplicates are located by splitting the digest into bands and storing
+// the entry under each band. Two similar digests are very likely to share at
+// least one identical band, which avoids a full scan.
+async fn fuzzy_lookup(
+    server: &Server,
+    digest: &FuzzyDigest,
+) -> trc::Result<Vec<Option<FuzzyEntry>>> {
+    server
+        .in_memory_store()
+        .key_get_many::<String>(
+            digest
+                .bands()
+                .map(|(band_id, band)| LookupKey::from(fuzzy_key(band_id, band)))
+                .collect(),
+        )
+        .await
+        .map(|entries| {
+            entries
+                .into_iter()
+                .map(|entry| entry.and_then(|entry| FuzzyEntry::from_text(&entry)))
+                .collect()
+        })
+}
+
+fn best_match(digest: &FuzzyDigest, entries: &[Option<FuzzyEntry>]) -> Option<FuzzyEntry> {
+    entries
+        .iter()
+        .flatten()
+        .max_by(|a, b| {
+            a.digest
+                .similarity(digest)
+                .total_cmp(&b.digest.similarity(digest))
+        })
+        .copied()
+}
+
+fn is_same_cluster(config: &FuzzyConfig, digest: &FuzzyDigest, entry: &FuzzyEntry) -> bool {
+    entry.digest.similarity(digest) >= config.min_similarity
+}
+
+fn fuzzy_key(band_id: u8, band: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(band.len() + 2);
+    key.push(KV_SPAM_FUZZY);
+    key.push(band_id);
+    key.extend_from_slice(band);
+    key
+}
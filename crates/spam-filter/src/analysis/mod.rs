@@ -23,6 +23,7 @@ pub mod dmarc;
 pub mod domain;
 pub mod ehlo;
 pub mod from;
+pub mod fuzzy;
 pub mod headers;
 pub mod html;
 pub mod init;
@@ -36,6 +37,7 @@ pub mod replyto;
 pub mod reputation;
 pub mod rules;
 pub mod score;
+pub mod sender_reputation;
 pub mod subject;
 pub mod trusted_reply;
 pub mod url;
@@ -34,9 +34,9 @@ enum Type {
 }
 
 #[derive(Debug)]
-struct Reputation {
-    count: u32,
-    score: f64,
+pub struct Reputation {
+    pub count: u32,
+    pub score: f64,
 }
 
 impl SpamFilterAnalyzeReputation for Server {
@@ -9,11 +9,12 @@ use crate::{
     analysis::{
         bayes::SpamFilterAnalyzeBayes, date::SpamFilterAnalyzeDate, dmarc::SpamFilterAnalyzeDmarc,
         domain::SpamFilterAnalyzeDomain, ehlo::SpamFilterAnalyzeEhlo, from::SpamFilterAnalyzeFrom,
-        headers::SpamFilterAnalyzeHeaders, html::SpamFilterAnalyzeHtml, ip::SpamFilterAnalyzeIp,
-        messageid::SpamFilterAnalyzeMid, mime::SpamFilterAnalyzeMime,
-        pyzor::SpamFilterAnalyzePyzor, received::SpamFilterAnalyzeReceived,
-        recipient::SpamFilterAnalyzeRecipient, replyto::SpamFilterAnalyzeReplyTo,
-        reputation::SpamFilterAnalyzeReputation, rules::SpamFilterAnalyzeRules,
+        fuzzy::SpamFilterAnalyzeFuzzy, headers::SpamFilterAnalyzeHeaders,
+        html::SpamFilterAnalyzeHtml, ip::SpamFilterAnalyzeIp, messageid::SpamFilterAnalyzeMid,
+        mime::SpamFilterAnalyzeMime, pyzor::SpamFilterAnalyzePyzor,
+        received::SpamFilterAnalyzeReceived, recipient::SpamFilterAnalyzeRecipient,
+        replyto::SpamFilterAnalyzeReplyTo, reputation::SpamFilterAnalyzeReputation,
+        rules::SpamFilterAnalyzeRules, sender_reputation::SpamFilterAnalyzeSenderReputation,
         subject::SpamFilterAnalyzeSubject, trusted_reply::SpamFilterAnalyzeTrustedReply,
         url::SpamFilterAnalyzeUrl,
     },
@@ -90,6 +91,11 @@ impl SpamFilterAnalyzeScore for Server {
         &self,
         ctx: &mut SpamFilterContext<'_>,
     ) -> SpamFilterAction<String> {
+        // Learn fuzzy checksums from spam traps
+        if ctx.result.has_tag("SPAM_TRAP") {
+            self.spam_filter_fuzzy_learn(ctx).await;
+        }
+
         // Train Bayes classifier
         if let Some(config) = self
             .core
@@ -197,9 +203,15 @@ impl SpamFilterAnalyzeScore for Server {
         // Pyzor checks
         self.spam_filter_analyze_pyzor(ctx).await;
 
+        // Fuzzy checksum checks
+        self.spam_filter_analyze_fuzzy(ctx).await;
+
         // Bayes classification
         self.spam_filter_analyze_bayes_classify(ctx).await;
 
+        // Sender reputation lookup
+        self.spam_filter_analyze_sender_reputation(ctx).await;
+
         // User-defined rules
         self.spam_filter_analyze_rules(ctx).await;
 
@@ -213,6 +225,9 @@ impl SpamFilterAnalyzeScore for Server {
         // Reputation tracking and adjust score
         self.spam_filter_analyze_reputation(ctx).await;
 
+        // Sender reputation tracking and adjust score
+        self.spam_filter_sender_reputation_update(ctx).await;
+
         // Final score calculation
         self.spam_filter_finalize(ctx).await
     }
@@ -0,0 +1,215 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::{
+    KV_SENDER_REPUTATION, KV_SENDER_REPUTATION_MSG, Server,
+    config::spamfilter::SenderReputationConfig,
+};
+use compact_str::CompactString;
+use mail_auth::{DkimResult, SpfResult};
+use store::{Serialize, dispatch::lookup::KeyValue, write::now};
+use trc::SpamEvent;
+
+use crate::{
+    Hostname, SpamFilterContext,
+    modules::{key_get, key_set, sender_reputation::SenderReputation},
+};
+
+pub trait SpamFilterAnalyzeSenderReputation: Sync + Send {
+    fn spam_filter_analyze_sender_reputation(
+        &self,
+        ctx: &mut SpamFilterContext<'_>,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn spam_filter_sender_reputation_update(
+        &self,
+        ctx: &mut SpamFilterContext<'_>,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn spam_filter_sender_reputation_learn(
+        &self,
+        ctx: &SpamFilterContext<'_>,
+        is_spam: bool,
+    ) -> impl Future<Output = ()> + Send;
+}
+
+impl SpamFilterAnalyzeSenderReputation for Server {
+    async fn spam_filter_analyze_sender_reputation(&self, ctx: &mut SpamFilterContext<'_>) {
+        let Some(config) = &self.core.spam.sender_reputation else {
+            return;
+        };
+        let Some(identity) = sender_identity(ctx) else {
+            return;
+        };
+
+        if let Ok(Some(reputation)) = key_get::<SenderReputation>(
+            self,
+            ctx.input.span_id,
+            KeyValue::<()>::build_key(KV_SENDER_REPUTATION, identity.as_bytes()),
+        )
+        .await
+        {
+            let reputation = reputation.decay(now(), config.half_life);
+            if reputation.count() >= config.min_count {
+                let value = reputation.value();
+                if value >= config.good_threshold {
+                    ctx.result.add_tag("SENDER_REPUTATION_GOOD");
+                } else if value <= config.bad_threshold {
+                    ctx.result.add_tag("SENDER_REPUTATION_BAD");
+                }
+                ctx.result.sender_reputation = Some(value);
+            }
+
+            trc::event!(
+                Spam(SpamEvent::SenderReputation),
+                Domain = identity.to_string(),
+                Details = vec![
+                    trc::Value::from(reputation.value()),
+                    trc::Value::from(reputation.count())
+                ],
+                SpanId = ctx.input.span_id,
+            );
+        }
+
+        ctx.result.sender_identity = Some(identity);
+    }
+
+    async fn spam_filter_sender_reputation_update(&self, ctx: &mut SpamFilterContext<'_>) {
+        let (Some(config), Some(identity)) = (
+            &self.core.spam.sender_reputation,
+            ctx.result.sender_identity.as_ref(),
+        ) else {
+            return;
+        };
+
+        // Adjust score
+        if let Some(value) = ctx.result.sender_reputation {
+            ctx.result.score -= value * config.score;
+        }
+
+        if !ctx.input.is_test {
+            // Record the delivery outcome
+            let is_spam = ctx.result.score >= self.core.spam.scores.spam_threshold;
+            update_reputation(
+                self,
+                config,
+                identity,
+                is_spam,
+                config.delivery_weight,
+                ctx.input.span_id,
+            )
+            .await;
+
+            // Link the message to its sender so user feedback can be attributed later on
+            if let Some(message_id) = ctx.input.message.message_id() {
+                key_set(
+                    self,
+                    ctx.input.span_id,
+                    KeyValue::with_prefix(
+                        KV_SENDER_REPUTATION_MSG,
+                        message_id.as_bytes(),
+                        identity.as_bytes().to_vec(),
+                    )
+                    .expires(config.expiry),
+                )
+                .await;
+            }
+        }
+    }
+
+    async fn spam_filter_sender_reputation_learn(
+        &self,
+        ctx: &SpamFilterContext<'_>,
+        is_spam: bool,
+    ) {
+        let (Some(config), Some(message_id)) = (
+            &self.core.spam.sender_reputation,
+            ctx.input.message.message_id(),
+        ) else {
+            return;
+        };
+        if ctx.input.is_test {
+            return;
+        }
+
+        // Feedback is only accepted for messages whose sender was authenticated on delivery
+        if let Ok(Some(identity)) = key_get::<String>(
+            self,
+            ctx.input.span_id,
+            KeyValue::<()>::build_key(KV_SENDER_REPUTATION_MSG, message_id.as_bytes()),
+        )
+        .await
+        {
+            update_reputation(
+                self,
+                config,
+                &identity,
+                is_spam,
+                config.feedback_weight,
+                ctx.input.span_id,
+            )
+            .await;
+
+            trc::event!(
+                Spam(SpamEvent::SenderReputationLearn),
+                Domain = identity,
+                Details = if is_spam { "spam" } else { "ham" },
+                SpanId = ctx.input.span_id,
+            );
+        }
+    }
+}
+
+async fn update_reputation(
+    server: &Server,
+    config: &SenderReputationConfig,
+    identity: &str,
+    is_spam: bool,
+    weight: f64,
+    span_id: u64,
+) {
+    let key = KeyValue::<()>::build_key(KV_SENDER_REPUTATION, identity.as_bytes());
+    let now = now();
+    let reputation = match key_get::<SenderReputation>(server, span_id, key.clone()).await {
+        Ok(Some(reputation)) => reputation.decay(now, config.half_life),
+        Ok(None) => SenderReputation {
+            updated: now,
+            ..Default::default()
+        },
+        Err(_) => return,
+    }
+    .observe(is_spam, weight);
+
+    key_set(
+        server,
+        span_id,
+        KeyValue::new(key, reputation.serialize().unwrap_or_default()).expires(config.expiry),
+    )
+    .await;
+}
+
+// The sender is identified by the organizational domain of the From header,
+// provided that it was authenticated by an aligned DKIM signature or SPF
+fn sender_identity(ctx: &SpamFilterContext<'_>) -> Option<CompactString> {
+    let from_domain = ctx.output.from.email.domain_part.sld.as_ref()?;
+
+    let is_dkim_aligned = ctx
+        .input
+        .dkim_result
+        .iter()
+        .filter(|r| matches!(r.result(), DkimResult::Pass))
+        .filter_map(|r| r.signature())
+        .any(|signature| Hostname::new(&signature.d).sld.as_ref() == Some(from_domain));
+    let is_spf_aligned = ctx
+        .input
+        .spf_mail_from_result
+        .is_some_and(|r| matches!(r.result(), SpfResult::Pass))
+        && ctx.output.env_from_addr.domain_part.sld.as_ref() == Some(from_domain);
+
+    (is_dkim_aligned || is_spf_aligned).then(|| from_domain.clone())
+}
@@ -14,6 +14,7 @@ use std::net::{IpAddr, Ipv4Addr};
 
 use analysis::ElementLocation;
 use analysis::url::UrlParts;
+use common::config::smtp::session::EhloValidationResult;
 use compact_str::CompactString;
 use mail_auth::{ArcOutput, DkimOutput, DmarcResult, IprevOutput, SpfOutput, dmarc::Policy};
 use mail_parser::Message;
@@ -33,6 +34,7 @@ pub struct SpamFilterInput<'x> {
     pub dmarc_result: Option<&'x DmarcResult>,
     pub dmarc_policy: Option<&'x Policy>,
     pub iprev_result: Option<&'x IprevOutput>,
+    pub helo_validation: Option<&'x EhloValidationResult>,
 
     // Session details
     pub remote_ip: IpAddr,
@@ -107,6 +109,8 @@ pub struct SpamFilterResult {
     pub rbl_domain_checks: usize,
     pub rbl_url_checks: usize,
     pub rbl_email_checks: usize,
+    pub sender_identity: Option<CompactString>,
+    pub sender_reputation: Option<f64>,
     pub header: Option<String>,
 }
 
@@ -150,6 +154,7 @@ impl<'x> SpamFilterInput<'x> {
             iprev_result: None,
             remote_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
             ehlo_domain: None,
+            helo_validation: None,
             authenticated_as: None,
             asn: None,
             country: None,
@@ -175,6 +180,7 @@ impl<'x> SpamFilterInput<'x> {
             iprev_result: None,
             remote_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
             ehlo_domain: None,
+            helo_validation: None,
             authenticated_as: None,
             asn: None,
             country: None,
@@ -6,8 +6,9 @@
 
 use std::{borrow::Cow, collections::HashSet, future::Future, time::Duration};
 
-use common::{KV_BAYES_MODEL_GLOBAL, KV_BAYES_MODEL_USER, Server, ip_to_bytes};
+use common::{KV_BAYES_MODEL_GLOBAL, KV_BAYES_MODEL_USER, KV_SPAM_STATS, Server, ip_to_bytes};
 use mail_auth::DmarcResult;
+use mail_parser::HeaderName;
 use nlp::{
     bayes::{
         BayesModel, TokenHash, Weights,
@@ -18,7 +19,7 @@ use nlp::{
         types::TokenType,
     },
 };
-use store::dispatch::lookup::KeyValue;
+use store::{SerializeInfallible, dispatch::lookup::KeyValue, write::now};
 use trc::AddContext;
 use utils::cache::TtlEntry;
 
@@ -171,8 +172,35 @@ impl BayesClassifier for Server {
                     false,
                 )
                 .await
-                .caused_by(trc::location!())
-                .map(|_| ())
+                .caused_by(trc::location!())?;
+
+            // Update model statistics
+            self.in_memory_store()
+                .key_set(KeyValue::new(
+                    if is_spam {
+                        BayesStat::LastTrainSpam
+                    } else {
+                        BayesStat::LastTrainHam
+                    }
+                    .key(ctx.input.account_id),
+                    (now() as i64).serialize(),
+                ))
+                .await
+                .caused_by(trc::location!())?;
+            if let Some(was_spam) = bayes_previous_verdict(self, ctx) {
+                let stat = match (was_spam, is_spam) {
+                    (true, true) => BayesStat::TruePositive,
+                    (true, false) => BayesStat::FalsePositive,
+                    (false, false) => BayesStat::TrueNegative,
+                    (false, true) => BayesStat::FalseNegative,
+                };
+                self.in_memory_store()
+                    .counter_incr(KeyValue::new(stat.key(ctx.input.account_id), 1), false)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+
+            Ok(())
         } else {
             //TODO: Implement untrain
             Ok(())
@@ -286,6 +314,31 @@ impl BayesClassifier for Server {
 
         let result = classifier.classify(osb_tokens.into_iter(), ham_learns, spam_learns);
 
+        // Update classification counters
+        if let (Some(score), Some(config), false) =
+            (result, &self.core.spam.bayes, ctx.input.is_test)
+        {
+            let (score_spam, score_ham) = if ctx.input.account_id.is_some() {
+                (config.account_score_spam, config.account_score_ham)
+            } else {
+                (config.score_spam, config.score_ham)
+            };
+            let stat = if score > score_spam {
+                BayesStat::ClassifiedSpam
+            } else if score < score_ham {
+                BayesStat::ClassifiedHam
+            } else {
+                BayesStat::ClassifiedUnknown
+            };
+            if let Err(err) = self
+                .in_memory_store()
+                .counter_incr(KeyValue::new(stat.key(ctx.input.account_id), 1), false)
+                .await
+            {
+                trc::error!(err.span_id(ctx.input.span_id).caused_by(trc::location!()));
+            }
+        }
+
         trc::event!(
             Spam(trc::SpamEvent::Classify),
             SpanId = ctx.input.span_id,
@@ -413,6 +466,79 @@ impl BayesClassifier for Server {
     }
 }
 
+// Obtains the verdict previously added to the message by the Bayes classifier
+fn bayes_previous_verdict(server: &Server, ctx: &SpamFilterContext<'_>) -> Option<bool> {
+    let header = server.core.spam.headers.bayes_result.as_deref()?;
+    ctx.input
+        .message
+        .root_part()
+        .headers()
+        .iter()
+        .find_map(|h| match &h.name {
+            HeaderName::Other(name) if name.eq_ignore_ascii_case(header) => {
+                let value = h.value().as_text()?.trim();
+                if value.starts_with("Yes") {
+                    Some(true)
+                } else if value.starts_with("No") {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+}
+
+// Statistics kept alongside the Bayes model to judge its health
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BayesStat {
+    LastTrainSpam = 0,
+    LastTrainHam = 1,
+    ClassifiedSpam = 2,
+    ClassifiedHam = 3,
+    ClassifiedUnknown = 4,
+    TruePositive = 5,
+    FalsePositive = 6,
+    TrueNegative = 7,
+    FalseNegative = 8,
+}
+
+impl BayesStat {
+    pub const COUNTERS: [BayesStat; 7] = [
+        BayesStat::ClassifiedSpam,
+        BayesStat::ClassifiedHam,
+        BayesStat::ClassifiedUnknown,
+        BayesStat::TruePositive,
+        BayesStat::FalsePositive,
+        BayesStat::TrueNegative,
+        BayesStat::FalseNegative,
+    ];
+
+    pub fn key(&self, account_id: Option<u32>) -> Vec<u8> {
+        let mut key = Vec::with_capacity(std::mem::size_of::<u32>() + 2);
+        key.push(KV_SPAM_STATS);
+        key.push(*self as u8);
+        if let Some(account_id) = account_id {
+            key.extend_from_slice(&account_id.to_be_bytes());
+        }
+        key
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BayesStat::LastTrainSpam => "lastTrainSpam",
+            BayesStat::LastTrainHam => "lastTrainHam",
+            BayesStat::ClassifiedSpam => "classifiedSpam",
+            BayesStat::ClassifiedHam => "classifiedHam",
+            BayesStat::ClassifiedUnknown => "classifiedUnknown",
+            BayesStat::TruePositive => "truePositive",
+            BayesStat::FalsePositive => "falsePositive",
+            BayesStat::TrueNegative => "trueNegative",
+            BayesStat::FalseNegative => "falseNegative",
+        }
+    }
+}
+
 const P_FROM_NAME: u8 = 0;
 const P_FROM_EMAIL: u8 = 1;
 const P_FROM_DOMAIN: u8 = 2;
@@ -0,0 +1,152 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_auth::dkim::{Canonicalization, Signature};
+use mail_parser::Message;
+
+// Headers that can be used to mislead the recipient when prepended by a third party
+const CRITICAL_HEADERS: [&str; 8] = [
+    "From",
+    "Sender",
+    "Reply-To",
+    "To",
+    "Cc",
+    "Subject",
+    "Date",
+    "Message-ID",
+];
+
+// Returns true when the body length tag (l=) leaves part of the body unsigned
+pub fn has_unsigned_body(signature: &Signature, message: &Message<'_>) -> bool {
+    signature.l > 0
+        && canonical_body_len(
+            message
+                .raw_message()
+                .get(message.root_part().offset_body as usize..)
+                .unwrap_or_default(),
+            signature.cb,
+        ) > signature.l
+}
+
+// Returns the first critical header that appears more times than it was signed
+pub fn unsigned_duplicate_header(
+    signature: &Signature,
+    message: &Message<'_>,
+) -> Option<&'static str> {
+    CRITICAL_HEADERS.into_iter().find(|name| {
+        let total = message
+            .headers()
+            .iter()
+            .filter(|header| header.name().eq_ignore_ascii_case(name))
+            .count();
+        total > 1
+            && total
+                > signature
+                    .h
+                    .iter()
+                    .filter(|header| header.eq_ignore_ascii_case(name))
+                    .count()
+    })
+}
+
+fn canonical_body_len(body: &[u8], canonicalization: Canonicalization) -> u64 {
+    let mut len = 0;
+    let mut empty_lines = 0;
+
+    for line in body.split_inclusive(|&ch| ch == b'\n') {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line_len = match canonicalization {
+            Canonicalization::Relaxed => {
+                let mut line_len = 0;
+                let mut last_ch = 0;
+                for &ch in line.trim_ascii_end() {
+                    if !matches!((last_ch, ch), (b' ' | b'\t', b' ' | b'\t')) {
+                        line_len += 1;
+                    }
+                    last_ch = ch;
+                }
+                line_len
+            }
+            Canonicalization::Simple => line.len() as u64,
+        };
+
+        // Trailing empty lines are ignored by both canonicalization algorithms
+        if line_len > 0 {
+            len += empty_lines * 2 + line_len + 2;
+            empty_lines = 0;
+        } else {
+            empty_lines += 1;
+        }
+    }
+
+    if len == 0 && matches!(canonicalization, Canonicalization::Simple) {
+        2
+    } else {
+        len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mail_auth::dkim::{Canonicalization, Signature};
+    use mail_parser::MessageParser;
+
+    use super::{canonical_body_len, has_unsigned_body, unsigned_duplicate_header};
+
+    #[test]
+    fn dkim_body_length() {
+        for (body, relaxed, simple) in [
+            ("", 0, 2),
+            ("\r\n\r\n", 0, 2),
+            ("Hello\r\n", 7, 7),
+            ("Hello  \t world \r\n\r\n\r\n", 13, 17),
+            ("Hello\r\n\r\nWorld", 16, 16),
+        ] {
+            assert_eq!(
+                canonical_body_len(body.as_bytes(), Canonicalization::Relaxed),
+                relaxed,
+                "{body:?}"
+            );
+            assert_eq!(
+                canonical_body_len(body.as_bytes(), Canonicalization::Simple),
+                simple,
+                "{body:?}"
+            );
+        }
+
+        let message = MessageParser::new()
+            .parse(b"From: a@b.com\r\nSubject: test\r\n\r\nHello\r\nUnsigned content\r\n")
+            .unwrap();
+        for (l, expected) in [(0, false), (7, true), (25, false)] {
+            let signature = Signature {
+                l,
+                cb: Canonicalization::Relaxed,
+                ..Default::default()
+            };
+            assert_eq!(has_unsigned_body(&signature, &message), expected, "l={l}");
+        }
+    }
+
+    #[test]
+    fn dkim_duplicate_headers() {
+        let message = MessageParser::new()
+            .parse(b"From: evil@b.com\r\nFrom: a@b.com\r\nSubject: test\r\n\r\nHello\r\n")
+            .unwrap();
+
+        for (signed, expected) in [
+            (vec!["From", "Subject"], Some("From")),
+            (vec!["from", "From", "Subject"], None),
+            (vec!["Subject"], Some("From")),
+        ] {
+            let signature = Signature {
+                h: signed.into_iter().map(String::from).collect(),
+                ..Default::default()
+            };
+            assert_eq!(unsigned_duplicate_header(&signature, &message), expected);
+        }
+    }
+}
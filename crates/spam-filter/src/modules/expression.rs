@@ -263,6 +263,16 @@ impl<T: ResolveVariable> ResolveVariable for SpamFilterResolver<'_, T> {
                 })
                 .unwrap_or_default()
                 .into(),
+            V_SPAM_SENDER_IDENTITY => self
+                .ctx
+                .result
+                .sender_identity
+                .as_deref()
+                .unwrap_or_default()
+                .into(),
+            V_SPAM_SENDER_REPUTATION => {
+                self.ctx.result.sender_reputation.unwrap_or_default().into()
+            }
             _ => Variable::Integer(0),
         }
     }
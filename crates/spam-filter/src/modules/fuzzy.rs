@@ -0,0 +1,303 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::borrow::Cow;
+
+use common::config::spamfilter::FuzzyRemoteConfig;
+use mail_parser::{Message, PartType};
+use nlp::tokenizers::types::{TokenType, TypesTokenizer};
+use serde::Deserialize;
+
+use super::pyzor::html_to_text;
+
+pub const FUZZY_DIGEST_LEN: usize = 32;
+pub const FUZZY_BAND_LEN: usize = 2;
+
+const WINDOW_SIZE: usize = 5;
+const TRIGRAMS: &[(usize, usize, usize)] = &[
+    (0, 1, 2),
+    (0, 1, 3),
+    (0, 2, 3),
+    (0, 1, 4),
+    (0, 2, 4),
+    (0, 3, 4),
+    (1, 2, 4),
+    (1, 3, 4),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyDigest(pub [u8; FUZZY_DIGEST_LEN]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyEntry {
+    pub digest: FuzzyDigest,
+    pub reports: u32,
+}
+
+pub struct FuzzyRemoteClient<'x> {
+    pub config: &'x FuzzyRemoteConfig,
+}
+
+#[derive(Debug, Deserialize)]
+struct FuzzyRemoteResponse {
+    similarity: f64,
+    reports: u32,
+}
+
+pub trait FuzzyText {
+    fn fuzzy_text(&self) -> String;
+}
+
+impl FuzzyText for Message<'_> {
+    fn fuzzy_text(&self) -> String {
+        let mut result = String::new();
+
+        for part in &self.parts {
+            let text = match &part.body {
+                PartType::Text(text) => Cow::Borrowed(text.as_ref()),
+                PartType::Html(html) => Cow::Owned(html_to_text(html.as_ref())),
+                _ => continue,
+            };
+
+            // Only keep words, variable tokens such as URLs, numbers or
+            // e-mail addresses are commonly used to defeat checksums.
+            for token in TypesTokenizer::new(text.as_ref()) {
+                if let TokenType::Alphabetic(word) = token.word {
+                    if !result.is_empty() {
+                        result.push(' ');
+                    }
+                    for ch in word.chars() {
+                        result.extend(ch.to_lowercase());
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl FuzzyDigest {
+    // Locality-sensitive digest in the spirit of Nilsimsa: every trigram of a
+    // sliding window is hashed into one of 256 buckets and each digest bit is
+    // set when its bucket is above the mean. Similar texts produce digests
+    // that differ in only a few bits.
+    pub fn compute(text: &str) -> Self {
+        let bytes = text.as_bytes();
+        let mut buckets = [0u32; 256];
+        let mut total = 0u64;
+
+        if bytes.len() >= WINDOW_SIZE {
+            for window in bytes.windows(WINDOW_SIZE) {
+                for (salt, &(a, b, c)) in TRIGRAMS.iter().enumerate() {
+                    buckets[trigram_hash(salt as u8, window[a], window[b], window[c])] += 1;
+                    total += 1;
+                }
+            }
+        }
+
+        let mut digest = [0u8; FUZZY_DIGEST_LEN];
+        let mean = total / 256;
+        for (idx, &count) in buckets.iter().enumerate() {
+            if count as u64 > mean {
+                digest[idx >> 3] |= 1 << (idx & 7);
+            }
+        }
+
+        FuzzyDigest(digest)
+    }
+
+    pub fn similarity(&self, other: &FuzzyDigest) -> f64 {
+        let distance: u32 = self
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+
+        1.0 - (distance as f64 / (FUZZY_DIGEST_LEN * 8) as f64)
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.len() != FUZZY_DIGEST_LEN * 2 {
+            return None;
+        }
+
+        let mut digest = [0u8; FUZZY_DIGEST_LEN];
+        for (byte, chunk) in digest.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        }
+        Some(FuzzyDigest(digest))
+    }
+
+    pub fn bands(&self) -> impl Iterator<Item = (u8, &[u8])> {
+        self.0
+            .chunks_exact(FUZZY_BAND_LEN)
+            .enumerate()
+            .map(|(idx, band)| (idx as u8, band))
+    }
+}
+
+impl FuzzyEntry {
+    // Entries are stored as text so they can be read back from any in-memory store
+    pub fn to_text(&self) -> String {
+        format!("{}:{}", self.digest.to_hex(), self.reports)
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let (digest, reports) = text.split_once(':')?;
+        Some(FuzzyEntry {
+            digest: FuzzyDigest::from_hex(digest)?,
+            reports: reports.parse().ok()?,
+        })
+    }
+}
+
+impl FuzzyRemoteClient<'_> {
+    // Checksums are exchanged as hexadecimal digests: a GET request to
+    // `{url}/{digest}` returns `{"similarity": f64, "reports": u32}` for the
+    // closest known digest or 404 when none is known, and a POST request to
+    // the same location reports the digest as spam.
+    pub async fn check(&self, digest: &FuzzyDigest) -> trc::Result<Option<(f64, u32)>> {
+        let response = self
+            .request(reqwest::Method::GET, digest)?
+            .send()
+            .await
+            .map_err(|err| self.error(err, "Fuzzy checksum lookup failed"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = response
+            .error_for_status()
+            .map_err(|err| self.error(err, "Fuzzy checksum lookup failed"))?
+            .bytes()
+            .await
+            .map_err(|err| self.error(err, "Failed to read fuzzy checksum response"))?;
+        let response = serde_json::from_slice::<FuzzyRemoteResponse>(&response).map_err(|err| {
+            trc::SpamEvent::FuzzyError
+                .into_err()
+                .reason(err)
+                .ctx(trc::Key::Url, self.config.url.clone())
+                .details("Failed to parse fuzzy checksum response")
+        })?;
+
+        Ok(Some((
+            response.similarity.clamp(0.0, 1.0),
+            response.reports,
+        )))
+    }
+
+    pub async fn report(&self, digest: &FuzzyDigest) -> trc::Result<()> {
+        self.request(reqwest::Method::POST, digest)?
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|err| self.error(err, "Fuzzy checksum report failed"))
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        digest: &FuzzyDigest,
+    ) -> trc::Result<reqwest::RequestBuilder> {
+        let url = format!("{}/{}", self.config.url, digest.to_hex());
+        let request = reqwest::Client::builder()
+            .timeout(self.config.timeout)
+            .build()
+            .map_err(|err| self.error(err, "Failed to build fuzzy checksum client"))?
+            .request(method, url);
+
+        Ok(if let Some(auth) = &self.config.auth {
+            request.header(reqwest::header::AUTHORIZATION, auth)
+        } else {
+            request
+        })
+    }
+
+    fn error(&self, err: reqwest::Error, details: &'static str) -> trc::Error {
+        trc::SpamEvent::FuzzyError
+            .into_err()
+            .reason(err)
+            .ctx(trc::Key::Url, self.config.url.clone())
+            .details(details)
+    }
+}
+
+#[inline(always)]
+fn trigram_hash(salt: u8, a: u8, b: u8, c: u8) -> usize {
+    let mut hash: u32 = 0x811c9dc5;
+    for byte in [salt, a, b, c] {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    ((hash >> 24) ^ (hash & 0xff)) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use mail_parser::MessageParser;
+
+    use super::{FuzzyDigest, FuzzyEntry, FuzzyText};
+
+    const SPAM: &str = concat!(
+        "Congratulations! You have been selected to receive an exclusive ",
+        "reward. Claim your prize today by confirming your details with our ",
+        "secure processing center before the offer expires at midnight."
+    );
+
+    #[test]
+    fn fuzzy_similarity() {
+        let digest = FuzzyDigest::compute(SPAM);
+        assert_eq!(digest.similarity(&digest), 1.0);
+
+        // Small variations should keep the digests close
+        let variant =
+            FuzzyDigest::compute(&SPAM.replace("midnight", "noon").replace("today", "now"));
+        assert!(
+            digest.similarity(&variant) >= 0.85,
+            "similarity {}",
+            digest.similarity(&variant)
+        );
+
+        // Unrelated text should be far apart
+        let unrelated = FuzzyDigest::compute(concat!(
+            "Hi team, attached are the meeting notes from yesterday. Please ",
+            "review the action items and let me know if anything is missing ",
+            "before we send the summary to the rest of the department."
+        ));
+        assert!(
+            digest.similarity(&unrelated) < 0.7,
+            "similarity {}",
+            digest.similarity(&unrelated)
+        );
+
+        // Serialization roundtrip
+        let entry = FuzzyEntry { digest, reports: 7 };
+        assert_eq!(FuzzyEntry::from_text(&entry.to_text()), Some(entry));
+        assert_eq!(FuzzyDigest::from_hex(&digest.to_hex()), Some(digest));
+        assert_eq!(FuzzyEntry::from_text("abc:1"), None);
+    }
+
+    #[test]
+    fn fuzzy_text() {
+        let message = MessageParser::new()
+            .parse(concat!(
+                "Subject: test\r\n",
+                "Content-Type: text/html\r\n\r\n",
+                "<html><body><p>Visit https://example.org/abc123 NOW</p>",
+                "<p>Order #12345 for john@example.org</p></body></html>"
+            ))
+            .unwrap();
+
+        assert_eq!(message.fuzzy_text(), "visit now order for");
+    }
+}
@@ -11,11 +11,14 @@ use store::{
 };
 
 pub mod bayes;
+pub mod dkim;
 pub mod dnsbl;
 pub mod expression;
+pub mod fuzzy;
 pub mod html;
 pub mod pyzor;
 pub mod sanitize;
+pub mod sender_reputation;
 
 pub(crate) async fn key_get<T: Deserialize + From<Value<'static>> + std::fmt::Debug + 'static>(
     server: &Server,
@@ -281,7 +281,7 @@ where
     writer
 }
 
-fn html_to_text(input: &str) -> String {
+pub(crate) fn html_to_text(input: &str) -> String {
     let mut result = String::with_capacity(input.len());
     let input = input.as_bytes();
 
@@ -0,0 +1,140 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use store::{Deserialize, Serialize};
+
+const SENDER_REPUTATION_LEN: usize = 24;
+
+// Observations are kept as exponentially decaying ham and spam counters, so
+// that old feedback gradually loses its influence on the reputation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SenderReputation {
+    pub ham: f64,
+    pub spam: f64,
+    pub updated: u64,
+}
+
+impl SenderReputation {
+    pub fn decay(mut self, now: u64, half_life: u64) -> Self {
+        if now > self.updated {
+            let factor = 0.5f64.powf((now - self.updated) as f64 / half_life as f64);
+            self.ham *= factor;
+            self.spam *= factor;
+            self.updated = now;
+        }
+        self
+    }
+
+    pub fn observe(mut self, is_spam: bool, weight: f64) -> Self {
+        if is_spam {
+            self.spam += weight;
+        } else {
+            self.ham += weight;
+        }
+        self
+    }
+
+    pub fn count(&self) -> f64 {
+        self.ham + self.spam
+    }
+
+    // Ranges from -1.0 (spam only) to 1.0 (ham only), the additional
+    // observation keeps senders with little history close to neutral
+    pub fn value(&self) -> f64 {
+        (self.ham - self.spam) / (self.ham + self.spam + 1.0)
+    }
+}
+
+impl Serialize for SenderReputation {
+    fn serialize(&self) -> trc::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(SENDER_REPUTATION_LEN);
+        buf.extend_from_slice(&self.ham.to_be_bytes());
+        buf.extend_from_slice(&self.spam.to_be_bytes());
+        buf.extend_from_slice(&self.updated.to_be_bytes());
+        Ok(buf)
+    }
+}
+
+impl Deserialize for SenderReputation {
+    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
+        if bytes.len() == SENDER_REPUTATION_LEN {
+            Ok(SenderReputation {
+                ham: f64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+                spam: f64::from_be_bytes(bytes[8..16].try_into().unwrap()),
+                updated: u64::from_be_bytes(bytes[16..24].try_into().unwrap()),
+            })
+        } else {
+            Err(trc::StoreEvent::DataCorruption
+                .caused_by(trc::location!())
+                .ctx(trc::Key::Value, bytes))
+        }
+    }
+}
+
+// Values that cannot be decoded are treated as a sender without history
+impl From<store::Value<'_>> for SenderReputation {
+    fn from(value: store::Value<'_>) -> Self {
+        match value {
+            store::Value::Blob(bytes) => Self::deserialize(&bytes).unwrap_or_default(),
+            _ => Self::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use store::{Deserialize, Serialize};
+
+    use super::SenderReputation;
+
+    #[test]
+    fn sender_reputation_decay() {
+        let half_life = 86400;
+        let reputation = SenderReputation {
+            ham: 0.0,
+            spam: 0.0,
+            updated: 1000,
+        }
+        .observe(false, 1.0)
+        .observe(false, 1.0)
+        .observe(false, 1.0)
+        .observe(true, 1.0);
+        assert_eq!(reputation.count(), 4.0);
+        assert_eq!(reputation.value(), 0.4);
+
+        // Counters are halved after each half-life
+        let decayed = reputation.decay(1000 + half_life, half_life);
+        assert_eq!(decayed.ham, 1.5);
+        assert_eq!(decayed.spam, 0.5);
+        assert_eq!(decayed.updated, 1000 + half_life);
+
+        // Recent spam reports outweigh older ham
+        let decayed = decayed
+            .decay(1000 + 3 * half_life, half_life)
+            .observe(true, 1.0)
+            .observe(true, 1.0)
+            .observe(true, 1.0);
+        assert!(decayed.value() < -0.5, "value {}", decayed.value());
+
+        // Timestamps in the past do not alter the counters
+        assert_eq!(decayed.decay(0, half_life), decayed);
+
+        // Serialization roundtrip
+        assert_eq!(
+            SenderReputation::deserialize(&decayed.serialize().unwrap()).unwrap(),
+            decayed
+        );
+        assert!(SenderReputation::deserialize(&[0u8; 12]).is_err());
+        assert_eq!(
+            SenderReputation::from(store::Value::Blob(decayed.serialize().unwrap().into())),
+            decayed
+        );
+        assert_eq!(
+            SenderReputation::from(store::Value::Text("invalid".into())),
+            SenderReputation::default()
+        );
+    }
+}
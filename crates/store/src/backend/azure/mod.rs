@@ -48,11 +48,24 @@ impl AzureStore {
                     return None;
                 }
             },
+            (None, None) => {
+                // Use managed identity, workload identity or environment credentials
+                match azure_identity::create_credential() {
+                    Ok(cred) => StorageCredentials::token_credential(cred),
+                    Err(err) => {
+                        config.new_build_error(
+                            prefix.as_str(),
+                            format!("Failed to obtain Azure identity credentials: {err:?}"),
+                        );
+                        return None;
+                    }
+                }
+            }
             _ => {
                 config.new_build_error(
                     prefix.as_str(),
                     concat!(
-                        "Failed to create credentials: exactly one of ",
+                        "Failed to create credentials: at most one of ",
                         "'azure-access-key' and 'sas-token' must be specified"
                     ),
                 );
@@ -112,11 +112,13 @@ impl FdbStore {
 
         for (chunk_pos, chunk_bytes) in data.chunks(MAX_VALUE_SIZE).enumerate() {
             trx.set(
-                &KeySerializer::new(key.len() + 3)
-                    .write(SUBSPACE_BLOBS)
-                    .write(key)
-                    .write(chunk_pos as u16)
-                    .finalize(),
+                &self.partition_key(
+                    KeySerializer::new(key.len() + 3)
+                        .write(SUBSPACE_BLOBS)
+                        .write(key)
+                        .write(chunk_pos as u16)
+                        .finalize(),
+                ),
                 chunk_bytes,
             );
             if chunk_pos == last_chunk || (chunk_pos > 0 && chunk_pos % N_CHUNKS == 0) {
@@ -139,16 +141,20 @@ impl FdbStore {
 
         let trx = self.db.create_trx().map_err(into_error)?;
         trx.clear_range(
-            &KeySerializer::new(key.len() + 3)
-                .write(SUBSPACE_BLOBS)
-                .write(key)
-                .write(0u16)
-                .finalize(),
-            &KeySerializer::new(key.len() + 3)
-                .write(SUBSPACE_BLOBS)
-                .write(key)
-                .write(u16::MAX)
-                .finalize(),
+            &self.partition_key(
+                KeySerializer::new(key.len() + 3)
+                    .write(SUBSPACE_BLOBS)
+                    .write(key)
+                    .write(0u16)
+                    .finalize(),
+            ),
+            &self.partition_key(
+                KeySerializer::new(key.len() + 3)
+                    .write(SUBSPACE_BLOBS)
+                    .write(key)
+                    .write(u16::MAX)
+                    .finalize(),
+            ),
         );
 
         self.commit(trx, false).await
@@ -6,7 +6,11 @@
 
 use std::time::Duration;
 
-use foundationdb::{Database, api, options::DatabaseOption};
+use foundationdb::{
+    Database, api,
+    directory::{Directory, DirectoryLayer},
+    options::DatabaseOption,
+};
 use utils::config::{Config, utils::AsKey};
 
 use super::FdbStore;
@@ -102,10 +106,54 @@ impl FdbStore {
                 .ok()?;
         }
 
+        // Keys can be isolated inside a directory layer partition, allowing
+        // each partition to be moved, backed up or measured as a single range
+        let key_prefix = if let Some(path) = config.value((&prefix, "directory")) {
+            let path = path
+                .split('/')
+                .map(|item| item.trim())
+                .filter(|item| !item.is_empty())
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>();
+
+            match open_directory(&db, &path).await {
+                Ok(key_prefix) => key_prefix,
+                Err(err) => {
+                    config.new_build_error(
+                        (&prefix, "directory"),
+                        format!("Failed to open FoundationDB directory: {err}"),
+                    );
+                    return None;
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
         Some(Self {
             guard,
             db,
             version: Default::default(),
+            key_prefix,
         })
     }
 }
+
+async fn open_directory(db: &Database, path: &[String]) -> Result<Vec<u8>, String> {
+    if path.is_empty() {
+        return Err("Directory path is empty".to_string());
+    }
+
+    let trx = db.create_trx().map_err(|err| err.to_string())?;
+    let directory = DirectoryLayer::default()
+        .create_or_open(&trx, path, None, None)
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    let key_prefix = directory
+        .bytes()
+        .map_err(|err| format!("{err:?}"))?
+        .to_vec();
+    trx.commit().await.map_err(|err| format!("{err:?}"))?;
+
+    Ok(key_prefix)
+}
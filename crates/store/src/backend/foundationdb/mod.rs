@@ -20,6 +20,7 @@ pub struct FdbStore {
     db: Database,
     guard: NetworkAutoStop,
     version: parking_lot::Mutex<ReadVersion>,
+    key_prefix: Vec<u8>,
 }
 
 pub(crate) struct ReadVersion {
@@ -49,6 +50,27 @@ impl Default for ReadVersion {
     }
 }
 
+impl FdbStore {
+    // Places a serialized key inside the directory partition, if any
+    #[inline(always)]
+    pub(crate) fn partition_key(&self, key: Vec<u8>) -> Vec<u8> {
+        if self.key_prefix.is_empty() {
+            key
+        } else {
+            let mut partition_key = Vec::with_capacity(self.key_prefix.len() + key.len());
+            partition_key.extend_from_slice(&self.key_prefix);
+            partition_key.extend_from_slice(&key);
+            partition_key
+        }
+    }
+
+    // Length of the partition prefix plus the subspace byte
+    #[inline(always)]
+    pub(crate) fn key_offset(&self) -> usize {
+        self.key_prefix.len() + 1
+    }
+}
+
 #[inline(always)]
 fn into_error(error: FdbError) -> trc::Error {
     trc::StoreEvent::FoundationdbError
@@ -33,7 +33,7 @@ impl FdbStore {
     where
         U: Deserialize,
     {
-        let key = key.serialize(WITH_SUBSPACE);
+        let key = self.partition_key(key.serialize(WITH_SUBSPACE));
         let trx = self.read_trx().await?;
 
         match read_chunked_value(&key, &trx, true).await? {
@@ -48,9 +48,9 @@ impl FdbStore {
         mut key: BitmapKey<BitmapClass>,
     ) -> trc::Result<Option<RoaringBitmap>> {
         let mut bm = RoaringBitmap::new();
-        let begin = key.serialize(WITH_SUBSPACE);
+        let begin = self.partition_key(key.serialize(WITH_SUBSPACE));
         key.document_id = u32::MAX;
-        let end = key.serialize(WITH_SUBSPACE);
+        let end = self.partition_key(key.serialize(WITH_SUBSPACE));
         let key_len = begin.len();
         let trx = self.read_trx().await?;
         let mut values = trx.get_ranges_keyvalues(
@@ -79,8 +79,9 @@ impl FdbStore {
         params: IterateParams<T>,
         mut cb: impl for<'x> FnMut(&'x [u8], &'x [u8]) -> trc::Result<bool> + Sync + Send,
     ) -> trc::Result<()> {
-        let begin = params.begin.serialize(WITH_SUBSPACE);
-        let end = params.end.serialize(WITH_SUBSPACE);
+        let begin = self.partition_key(params.begin.serialize(WITH_SUBSPACE));
+        let end = self.partition_key(params.end.serialize(WITH_SUBSPACE));
+        let key_offset = self.key_offset();
 
         if !params.first {
             let mut last_key = vec![];
@@ -111,7 +112,7 @@ impl FdbStore {
                             let mut key = &[] as &[u8];
                             for value in values.iter() {
                                 key = value.key();
-                                if !cb(key.get(1..).unwrap_or_default(), value.value())? {
+                                if !cb(key.get(key_offset..).unwrap_or_default(), value.value())? {
                                     return Ok(());
                                 }
                             }
@@ -149,7 +150,10 @@ impl FdbStore {
             );
 
             if let Some(value) = values.try_next().await.map_err(into_error)? {
-                cb(value.key().get(1..).unwrap_or_default(), value.value())?;
+                cb(
+                    value.key().get(key_offset..).unwrap_or_default(),
+                    value.value(),
+                )?;
             }
         }
 
@@ -160,7 +164,7 @@ impl FdbStore {
         &self,
         key: impl Into<ValueKey<ValueClass>> + Sync + Send,
     ) -> trc::Result<i64> {
-        let key = key.into().serialize(WITH_SUBSPACE);
+        let key = self.partition_key(key.into().serialize(WITH_SUBSPACE));
         if let Some(bytes) = self
             .read_trx()
             .await?
@@ -47,7 +47,12 @@ impl FdbStore {
             if has_changes {
                 for &account_id in batch.changes.keys() {
                     debug_assert!(account_id != u32::MAX);
-                    let key = ValueClass::ChangeId.serialize(account_id, 0, 0, WITH_SUBSPACE);
+                    let key = self.partition_key(ValueClass::ChangeId.serialize(
+                        account_id,
+                        0,
+                        0,
+                        WITH_SUBSPACE,
+                    ));
                     let change_id =
                         if let Some(bytes) = trx.get(&key, false).await.map_err(into_error)? {
                             deserialize_i64_le(&key, &bytes)? + 1
@@ -80,8 +85,12 @@ impl FdbStore {
                         document_id = *document_id_;
                     }
                     Operation::Value { class, op } => {
-                        let mut key =
-                            class.serialize(account_id, collection, document_id, WITH_SUBSPACE);
+                        let mut key = self.partition_key(class.serialize(
+                            account_id,
+                            collection,
+                            document_id,
+                            WITH_SUBSPACE,
+                        ));
                         let do_chunk = !class.is_counter(collection);
 
                         match op {
@@ -165,14 +174,16 @@ impl FdbStore {
                         }
                     }
                     Operation::Index { field, key, set } => {
-                        let key = IndexKey {
-                            account_id,
-                            collection,
-                            document_id,
-                            field: *field,
-                            key: &*key,
-                        }
-                        .serialize(WITH_SUBSPACE);
+                        let key = self.partition_key(
+                            IndexKey {
+                                account_id,
+                                collection,
+                                document_id,
+                                field: *field,
+                                key: &*key,
+                            }
+                            .serialize(WITH_SUBSPACE),
+                        );
 
                         if *set {
                             trx.set(&key, &[]);
@@ -181,8 +192,12 @@ impl FdbStore {
                         }
                     }
                     Operation::Bitmap { class, set } => {
-                        let key =
-                            class.serialize(account_id, collection, document_id, WITH_SUBSPACE);
+                        let key = self.partition_key(class.serialize(
+                            account_id,
+                            collection,
+                            document_id,
+                            WITH_SUBSPACE,
+                        ));
 
                         if *set {
                             trx.set(&key, &[]);
@@ -191,12 +206,14 @@ impl FdbStore {
                         }
                     }
                     Operation::Log { collection, set } => {
-                        let key = LogKey {
-                            account_id,
-                            collection: u8::from(*collection),
-                            change_id,
-                        }
-                        .serialize(WITH_SUBSPACE);
+                        let key = self.partition_key(
+                            LogKey {
+                                account_id,
+                                collection: u8::from(*collection),
+                                change_id,
+                            }
+                            .serialize(WITH_SUBSPACE),
+                        );
 
                         trx.set(&key, set);
                     }
@@ -204,8 +221,12 @@ impl FdbStore {
                         class,
                         assert_value,
                     } => {
-                        let key =
-                            class.serialize(account_id, collection, document_id, WITH_SUBSPACE);
+                        let key = self.partition_key(class.serialize(
+                            account_id,
+                            collection,
+                            document_id,
+                            WITH_SUBSPACE,
+                        ));
 
                         let matches = match read_chunked_value(&key, &trx, false).await {
                             Ok(ChunkedValue::Single(bytes)) => assert_value.matches(bytes.as_ref()),
@@ -266,13 +287,14 @@ impl FdbStore {
         let mut delete_keys = Vec::new();
         for subspace in [SUBSPACE_COUNTER, SUBSPACE_QUOTA, SUBSPACE_IN_MEMORY_COUNTER] {
             let trx = self.db.create_trx().map_err(into_error)?;
-            let from_key = [subspace, 0u8];
-            let to_key = [subspace, u8::MAX, u8::MAX, u8::MAX, u8::MAX, u8::MAX];
+            let from_key = self.partition_key(vec![subspace, 0u8]);
+            let to_key =
+                self.partition_key(vec![subspace, u8::MAX, u8::MAX, u8::MAX, u8::MAX, u8::MAX]);
 
             let mut values = trx.get_ranges_keyvalues(
                 RangeOption {
-                    begin: KeySelector::first_greater_or_equal(&from_key[..]),
-                    end: KeySelector::first_greater_or_equal(&to_key[..]),
+                    begin: KeySelector::first_greater_or_equal(&from_key),
+                    end: KeySelector::first_greater_or_equal(&to_key),
                     mode: options::StreamingMode::WantAll,
                     reverse: false,
                     ..Default::default()
@@ -313,8 +335,8 @@ impl FdbStore {
     }
 
     pub(crate) async fn delete_range(&self, from: impl Key, to: impl Key) -> trc::Result<()> {
-        let from = from.serialize(WITH_SUBSPACE);
-        let to = to.serialize(WITH_SUBSPACE);
+        let from = self.partition_key(from.serialize(WITH_SUBSPACE));
+        let to = self.partition_key(to.serialize(WITH_SUBSPACE));
 
         let trx = self.db.create_trx().map_err(into_error)?;
         trx.clear_range(&from, &to);
@@ -0,0 +1,320 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    fmt::Display,
+    io::Write,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use rand::Rng;
+use reqwest::{Method, RequestBuilder, Response, StatusCode, header};
+use serde::Deserialize;
+use utils::{
+    codec::base32_custom::Base32Writer,
+    config::{Config, utils::AsKey},
+};
+
+const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com";
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+pub struct GcsStore {
+    client: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    prefix: Option<String>,
+    credentials: GcsCredentials,
+    max_retries: u32,
+}
+
+enum GcsCredentials {
+    // Used with emulators and public buckets
+    Anonymous,
+    AccessToken(String),
+    // Tokens obtained from the metadata server (GCE service accounts and GKE workload identity)
+    Metadata {
+        url: String,
+        token: Mutex<Option<CachedToken>>,
+    },
+}
+
+#[derive(Clone)]
+struct CachedToken {
+    token: String,
+    expires: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl GcsStore {
+    pub async fn open(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let bucket = config.value_require((&prefix, "bucket"))?.to_string();
+        let endpoint = config
+            .value((&prefix, "endpoint"))
+            .unwrap_or(DEFAULT_ENDPOINT)
+            .trim_end_matches('/')
+            .to_string();
+
+        let credentials = if let Some(token) = config.value((&prefix, "access-token")) {
+            GcsCredentials::AccessToken(token.to_string())
+        } else if config
+            .property_or_default::<bool>((&prefix, "anonymous"), "false")
+            .unwrap_or(false)
+        {
+            GcsCredentials::Anonymous
+        } else {
+            GcsCredentials::Metadata {
+                url: config
+                    .value((&prefix, "metadata-url"))
+                    .unwrap_or(METADATA_TOKEN_URL)
+                    .to_string(),
+                token: Mutex::new(None),
+            }
+        };
+
+        let timeout = config
+            .property_or_default::<Duration>((&prefix, "timeout"), "30s")
+            .unwrap_or_else(|| Duration::from_secs(30));
+        let client = match reqwest::Client::builder().timeout(timeout).build() {
+            Ok(client) => client,
+            Err(err) => {
+                config.new_build_error(
+                    prefix.as_str(),
+                    format!("Failed to create HTTP client: {err:?}"),
+                );
+                return None;
+            }
+        };
+
+        Some(GcsStore {
+            client,
+            endpoint,
+            bucket,
+            credentials,
+            max_retries: config
+                .property_or_default((&prefix, "max-retries"), "3")
+                .unwrap_or(3),
+            prefix: config.value((&prefix, "key-prefix")).map(|s| s.to_string()),
+        })
+    }
+
+    pub(crate) async fn get_blob(
+        &self,
+        key: &[u8],
+        range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        let url = format!(
+            "{}/storage/v1/b/{}/o/{}?alt=media",
+            self.endpoint,
+            self.bucket,
+            encode_name(&self.build_key(key))
+        );
+        let range = if range.start != 0 || range.end != usize::MAX {
+            Some(if range.end != usize::MAX {
+                format!("bytes={}-{}", range.start, range.end.saturating_sub(1))
+            } else {
+                format!("bytes={}-", range.start)
+            })
+        } else {
+            None
+        };
+
+        let response = self
+            .request(|| {
+                let request = self.client.request(Method::GET, &url);
+                if let Some(range) = &range {
+                    request.header(header::RANGE, range)
+                } else {
+                    request
+                }
+            })
+            .await?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::PARTIAL_CONTENT => response
+                .bytes()
+                .await
+                .map(|bytes| Some(bytes.to_vec()))
+                .map_err(into_error),
+            StatusCode::NOT_FOUND => Ok(None),
+            _ => Err(into_response_error(response).await),
+        }
+    }
+
+    pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        let url = format!(
+            "{}/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+            self.endpoint,
+            self.bucket,
+            encode_name(&self.build_key(key))
+        );
+
+        let response = self
+            .request(|| {
+                self.client
+                    .request(Method::POST, &url)
+                    .header(header::CONTENT_TYPE, "application/octet-stream")
+                    .body(data.to_vec())
+            })
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(into_response_error(response).await)
+        }
+    }
+
+    pub(crate) async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        let url = format!(
+            "{}/storage/v1/b/{}/o/{}",
+            self.endpoint,
+            self.bucket,
+            encode_name(&self.build_key(key))
+        );
+
+        let response = self
+            .request(|| self.client.request(Method::DELETE, &url))
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            _ => Err(into_response_error(response).await),
+        }
+    }
+
+    async fn request(&self, build: impl Fn() -> RequestBuilder) -> trc::Result<Response> {
+        let mut attempt = 0;
+        let mut token_refreshed = false;
+
+        loop {
+            let mut request = build();
+            if let Some(token) = self.access_token().await? {
+                request = request.bearer_auth(token);
+            }
+
+            match request.send().await {
+                Ok(response)
+                    if response.status() == StatusCode::UNAUTHORIZED
+                        && !token_refreshed
+                        && matches!(self.credentials, GcsCredentials::Metadata { .. }) =>
+                {
+                    // The cached token might have been revoked, retry once with a new one
+                    self.clear_token();
+                    token_refreshed = true;
+                    continue;
+                }
+                Ok(response)
+                    if !(response.status() == StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error())
+                        || attempt >= self.max_retries =>
+                {
+                    return Ok(response);
+                }
+                Err(err) if attempt >= self.max_retries => {
+                    return Err(into_error(err));
+                }
+                _ => {}
+            }
+
+            // Exponential backoff with jitter
+            let delay = 500u64.saturating_mul(1 << attempt.min(6)).min(30_000);
+            let delay = rand::rng().random_range(delay / 2..=delay);
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn access_token(&self) -> trc::Result<Option<String>> {
+        match &self.credentials {
+            GcsCredentials::Anonymous => Ok(None),
+            GcsCredentials::AccessToken(token) => Ok(Some(token.clone())),
+            GcsCredentials::Metadata { url, token } => {
+                if let Some(cached) = token.lock().as_ref()
+                    && cached.expires > Instant::now()
+                {
+                    return Ok(Some(cached.token.clone()));
+                }
+
+                let response = self
+                    .client
+                    .get(url)
+                    .header("Metadata-Flavor", "Google")
+                    .send()
+                    .await
+                    .map_err(into_error)?;
+                if !response.status().is_success() {
+                    return Err(into_response_error(response)
+                        .await
+                        .details("Failed to obtain access token from metadata server"));
+                }
+                let response = response.bytes().await.map_err(into_error)?;
+                let response =
+                    serde_json::from_slice::<TokenResponse>(&response).map_err(into_error)?;
+
+                // Refresh tokens one minute before they expire
+                *token.lock() = Some(CachedToken {
+                    token: response.access_token.clone(),
+                    expires: Instant::now()
+                        + Duration::from_secs(response.expires_in.saturating_sub(60)),
+                });
+
+                Ok(Some(response.access_token))
+            }
+        }
+    }
+
+    fn clear_token(&self) {
+        if let GcsCredentials::Metadata { token, .. } = &self.credentials {
+            *token.lock() = None;
+        }
+    }
+
+    fn build_key(&self, key: &[u8]) -> String {
+        if let Some(prefix) = &self.prefix {
+            let mut writer =
+                Base32Writer::with_raw_capacity(prefix.len() + (key.len().div_ceil(4) * 5));
+            writer.push_string(prefix);
+            writer.write_all(key).unwrap();
+            writer.finalize()
+        } else {
+            Base32Writer::from_bytes(key).finalize()
+        }
+    }
+}
+
+fn encode_name(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+async fn into_response_error(response: Response) -> trc::Error {
+    let code = response.status().as_u16();
+    trc::StoreEvent::GcsError
+        .reason(response.text().await.unwrap_or_default())
+        .ctx(trc::Key::Code, code)
+}
+
+#[inline(always)]
+fn into_error(err: impl Display) -> trc::Error {
+    trc::StoreEvent::GcsError.reason(err)
+}
@@ -54,7 +54,12 @@ impl KafkaPubSub {
                     .as_millis()
                     .to_string(),
             )
-            .set("enable.auto.commit", "true");
+            .set(
+                "auto.offset.reset",
+                config.value((&prefix, "offset-reset")).unwrap_or("latest"),
+            )
+            // Offsets are committed once each message has been received
+            .set("enable.auto.commit", "false");
 
         let producer = ClientConfig::new()
             .set(
@@ -11,6 +11,8 @@ pub mod elastic;
 #[cfg(feature = "foundation")]
 pub mod foundationdb;
 pub mod fs;
+#[cfg(feature = "gcs")]
+pub mod gcs;
 pub mod http;
 #[cfg(feature = "kafka")]
 pub mod kafka;
@@ -6,7 +6,8 @@
 
 use std::time::Duration;
 
-use async_nats::Client;
+use async_nats::{Client, jetstream};
+use tokio::sync::OnceCell;
 use utils::config::{Config, utils::AsKey};
 
 pub mod pubsub;
@@ -14,6 +15,18 @@ pub mod pubsub;
 #[derive(Debug)]
 pub struct NatsPubSub {
     client: Client,
+    jetstream: Option<NatsJetStream>,
+}
+
+#[derive(Debug)]
+pub(super) struct NatsJetStream {
+    context: jetstream::Context,
+    stream: String,
+    consumer: Option<String>,
+    max_age: Duration,
+    replicas: usize,
+    ack_wait: Duration,
+    handle: OnceCell<jetstream::stream::Stream>,
 }
 
 impl NatsPubSub {
@@ -94,7 +107,7 @@ impl NatsPubSub {
                 .ok()?;
         }
 
-        async_nats::connect_with_options(urls, opts)
+        let client = async_nats::connect_with_options(urls, opts)
             .await
             .map_err(|err| {
                 config.new_build_error(
@@ -102,7 +115,37 @@ impl NatsPubSub {
                     format!("Failed to connect to Nats: {}", err),
                 );
             })
-            .map(|client| NatsPubSub { client })
-            .ok()
+            .ok()?;
+
+        // JetStream provides persistent at-least-once delivery
+        let jetstream = if config
+            .property_or_default((&prefix, "jetstream.enable"), "false")
+            .unwrap_or(false)
+        {
+            Some(NatsJetStream {
+                context: jetstream::new(client.clone()),
+                stream: config
+                    .value((&prefix, "jetstream.stream"))
+                    .unwrap_or("stalwart")
+                    .to_string(),
+                consumer: config
+                    .value((&prefix, "jetstream.consumer"))
+                    .map(|v| v.to_string()),
+                max_age: config
+                    .property_or_default((&prefix, "jetstream.max-age"), "1h")
+                    .unwrap_or_else(|| Duration::from_secs(3600)),
+                replicas: config
+                    .property_or_default((&prefix, "jetstream.replicas"), "1")
+                    .unwrap_or(1),
+                ack_wait: config
+                    .property_or_default((&prefix, "jetstream.ack-wait"), "30s")
+                    .unwrap_or_else(|| Duration::from_secs(30)),
+                handle: OnceCell::new(),
+            })
+        } else {
+            None
+        };
+
+        Some(NatsPubSub { client, jetstream })
     }
 }
@@ -4,36 +4,135 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::NatsPubSub;
+use super::{NatsJetStream, NatsPubSub};
 use crate::dispatch::pubsub::{Msg, PubSubStream};
+use async_nats::jetstream::{
+    self,
+    consumer::{AckPolicy, DeliverPolicy, pull},
+};
 use futures::StreamExt;
 use trc::{ClusterEvent, Error, EventType};
 
-pub struct NatsPubSubStream {
-    subs: async_nats::Subscriber,
+pub enum NatsPubSubStream {
+    Core(async_nats::Subscriber),
+    JetStream(pull::Stream),
 }
 
 impl NatsPubSub {
     pub async fn publish(&self, topic: &'static str, message: Vec<u8>) -> trc::Result<()> {
-        self.client
-            .publish(topic, message.into())
-            .await
-            .map_err(|err| Error::new(EventType::Cluster(ClusterEvent::PublisherError)).reason(err))
+        if let Some(js) = &self.jetstream {
+            js.stream(topic).await?;
+            js.context
+                .publish(topic, message.into())
+                .await
+                .map_err(|err| {
+                    Error::new(EventType::Cluster(ClusterEvent::PublisherError)).reason(err)
+                })?
+                .await
+                .map(|_| ())
+                .map_err(|err| {
+                    Error::new(EventType::Cluster(ClusterEvent::PublisherError)).reason(err)
+                })
+        } else {
+            self.client
+                .publish(topic, message.into())
+                .await
+                .map_err(|err| {
+                    Error::new(EventType::Cluster(ClusterEvent::PublisherError)).reason(err)
+                })
+        }
     }
 
     pub async fn subscribe(&self, topic: &'static str) -> trc::Result<PubSubStream> {
-        self.client
-            .subscribe(topic)
-            .await
-            .map(|subs| PubSubStream::Nats(NatsPubSubStream { subs }))
+        if let Some(js) = &self.jetstream {
+            let stream = js.stream(topic).await?;
+            let config = pull::Config {
+                durable_name: js.consumer.clone(),
+                // Durable consumers resume from their last acknowledged message,
+                // ephemeral ones only receive messages published from now on
+                deliver_policy: if js.consumer.is_some() {
+                    DeliverPolicy::All
+                } else {
+                    DeliverPolicy::New
+                },
+                ack_policy: AckPolicy::Explicit,
+                ack_wait: js.ack_wait,
+                filter_subject: topic.to_string(),
+                ..Default::default()
+            };
+            let consumer = if let Some(name) = &js.consumer {
+                stream.get_or_create_consumer(name, config).await
+            } else {
+                stream.create_consumer(config).await
+            }
             .map_err(|err| {
                 Error::new(EventType::Cluster(ClusterEvent::SubscriberError)).reason(err)
+            })?;
+
+            consumer
+                .messages()
+                .await
+                .map(|subs| PubSubStream::Nats(NatsPubSubStream::JetStream(subs)))
+                .map_err(|err| {
+                    Error::new(EventType::Cluster(ClusterEvent::SubscriberError)).reason(err)
+                })
+        } else {
+            self.client
+                .subscribe(topic)
+                .await
+                .map(|subs| PubSubStream::Nats(NatsPubSubStream::Core(subs)))
+                .map_err(|err| {
+                    Error::new(EventType::Cluster(ClusterEvent::SubscriberError)).reason(err)
+                })
+        }
+    }
+}
+
+impl NatsJetStream {
+    async fn stream(&self, topic: &'static str) -> trc::Result<&jetstream::stream::Stream> {
+        self.handle
+            .get_or_try_init(|| async {
+                self.context
+                    .get_or_create_stream(jetstream::stream::Config {
+                        name: self.stream.clone(),
+                        subjects: vec![topic.to_string()],
+                        max_age: self.max_age,
+                        num_replicas: self.replicas,
+                        ..Default::default()
+                    })
+                    .await
+                    .map_err(|err| {
+                        Error::new(EventType::Cluster(ClusterEvent::SubscriberError)).reason(err)
+                    })
             })
+            .await
     }
 }
 
 impl NatsPubSubStream {
     pub async fn next(&mut self) -> Option<Msg> {
-        self.subs.next().await.map(Msg::Nats)
+        match self {
+            NatsPubSubStream::Core(subs) => subs.next().await.map(Msg::Nats),
+            NatsPubSubStream::JetStream(subs) => match subs.next().await? {
+                Ok(message) => {
+                    if let Err(err) = message.ack().await {
+                        trc::event!(
+                            Cluster(ClusterEvent::SubscriberError),
+                            Reason = err.to_string(),
+                            Details = "Failed to acknowledge JetStream message"
+                        );
+                    }
+                    Some(Msg::Nats(message.message))
+                }
+                Err(err) => {
+                    trc::event!(
+                        Cluster(ClusterEvent::SubscriberError),
+                        Reason = err.to_string(),
+                        Details = "Failed to receive JetStream message"
+                    );
+                    None
+                }
+            },
+        }
     }
 }
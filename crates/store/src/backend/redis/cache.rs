@@ -0,0 +1,194 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use utils::{
+    cache::{CacheItemWeight, CacheWithTtl},
+    config::Config,
+};
+
+use super::RedisStore;
+
+const INVALIDATION_TOPIC: &str = "stwt.lookup-cache";
+const OP_REMOVE: u8 = 0;
+const OP_CLEAR: u8 = 1;
+
+// In-process cache of hot keys, only populated by key_get and key_exists.
+// Counters (rate limiters, locks) are never cached.
+pub(crate) struct LookupCache {
+    entries: CacheWithTtl<Vec<u8>, CachedValue>,
+    ttl: Duration,
+    node_token: u64,
+}
+
+#[derive(Clone)]
+pub(crate) enum CachedValue {
+    Missing,
+    Exists,
+    Value(Vec<u8>),
+}
+
+impl LookupCache {
+    pub(crate) fn parse(config: &mut Config, prefix: &str) -> Option<Arc<Self>> {
+        if !config
+            .property_or_default::<bool>((prefix, "cache.enable"), "false")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        let size = config
+            .property_or_default::<u64>((prefix, "cache.size"), "16777216")
+            .unwrap_or(16 * 1024 * 1024);
+
+        Some(Arc::new(LookupCache {
+            entries: CacheWithTtl::new(size as usize / 256, size),
+            ttl: config
+                .property_or_default::<Duration>((prefix, "cache.ttl"), "30s")
+                .unwrap_or_else(|| Duration::from_secs(30)),
+            node_token: rand::random(),
+        }))
+    }
+
+    pub(crate) fn get(&self, key: &[u8]) -> Option<CachedValue> {
+        self.entries.get(key)
+    }
+
+    pub(crate) fn insert(&self, key: &[u8], value: CachedValue, expires: Option<u64>) {
+        let ttl = expires.map_or(self.ttl, |expires| {
+            self.ttl.min(Duration::from_secs(expires))
+        });
+        if !ttl.is_zero() {
+            self.entries.insert(key.to_vec(), value, ttl);
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &[u8]) {
+        self.entries.remove(key);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries.clear();
+    }
+
+    fn build_message(&self, op: u8, key: &[u8]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(key.len() + 9);
+        message.extend_from_slice(&self.node_token.to_be_bytes());
+        message.push(op);
+        message.extend_from_slice(key);
+        message
+    }
+
+    fn apply_message(&self, message: &[u8]) {
+        if message.len() < 9 || message[..8] == self.node_token.to_be_bytes()[..] {
+            return;
+        }
+
+        match message[8] {
+            OP_REMOVE => self.remove(&message[9..]),
+            OP_CLEAR => self.clear(),
+            _ => {}
+        }
+    }
+}
+
+impl RedisStore {
+    // Notifies other nodes that a key was modified
+    pub(crate) async fn cache_invalidate(&self, key: &[u8]) {
+        if let Some(cache) = &self.cache {
+            cache.remove(key);
+            self.cache_broadcast(cache.build_message(OP_REMOVE, key))
+                .await;
+        }
+    }
+
+    pub(crate) async fn cache_clear(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+            self.cache_broadcast(cache.build_message(OP_CLEAR, &[]))
+                .await;
+        }
+    }
+
+    async fn cache_broadcast(&self, message: Vec<u8>) {
+        if let Err(err) = self.publish(INVALIDATION_TOPIC, message).await {
+            trc::error!(err.details("Failed to publish lookup cache invalidation"));
+        }
+    }
+
+    pub(crate) fn spawn_cache_subscriber(&self, cache: &Arc<LookupCache>) {
+        let cache = Arc::downgrade(cache);
+        let store = RedisStore {
+            pool: self.pool.clone(),
+            cache: None,
+        };
+
+        tokio::spawn(async move {
+            while is_active(&cache) {
+                let mut stream = match store.subscribe(INVALIDATION_TOPIC).await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        trc::error!(
+                            err.details("Failed to subscribe to lookup cache invalidations")
+                        );
+                        tokio::time::sleep(Duration::from_secs(30)).await;
+                        continue;
+                    }
+                };
+
+                loop {
+                    // Wake up periodically to exit once the store is dropped after a reload
+                    match tokio::time::timeout(Duration::from_secs(60), stream.next()).await {
+                        Ok(Some(message)) => {
+                            if let Some(cache) = cache.upgrade() {
+                                cache.apply_message(message.payload());
+                            } else {
+                                return;
+                            }
+                        }
+                        Ok(None) => {
+                            // Entries might be stale while disconnected
+                            if let Some(cache) = cache.upgrade() {
+                                cache.clear();
+                            }
+                            break;
+                        }
+                        Err(_) => {
+                            if !is_active(&cache) {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl std::fmt::Debug for LookupCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LookupCache")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+fn is_active(cache: &Weak<LookupCache>) -> bool {
+    cache.strong_count() > 0
+}
+
+impl CacheItemWeight for CachedValue {
+    fn weight(&self) -> u64 {
+        match self {
+            CachedValue::Value(value) => value.len() as u64 + std::mem::size_of::<Self>() as u64,
+            CachedValue::Missing | CachedValue::Exists => std::mem::size_of::<Self>() as u64,
+        }
+    }
+}
@@ -4,14 +4,28 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use redis::AsyncCommands;
+use redis::{AsyncCommands, FromRedisValue};
 
 use crate::Deserialize;
 
-use super::{RedisPool, RedisStore, into_error};
+use super::{RedisPool, RedisStore, cache::CachedValue, into_error};
 
 impl RedisStore {
     pub async fn key_set(&self, key: &[u8], value: &[u8], expires: Option<u64>) -> trc::Result<()> {
+        self.key_set_pool(key, value, expires).await?;
+        if let Some(cache) = &self.cache {
+            self.cache_invalidate(key).await;
+            cache.insert(key, CachedValue::Value(value.to_vec()), expires);
+        }
+        Ok(())
+    }
+
+    async fn key_set_pool(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        expires: Option<u64>,
+    ) -> trc::Result<()> {
         match &self.pool {
             RedisPool::Single(pool) => {
                 self.key_set_(
@@ -35,6 +49,11 @@ impl RedisStore {
     }
 
     pub async fn key_incr(&self, key: &[u8], value: i64, expires: Option<u64>) -> trc::Result<i64> {
+        // Counters are not cached, only drop any stale local entry
+        if let Some(cache) = &self.cache {
+            cache.remove(key);
+        }
+
         match &self.pool {
             RedisPool::Single(pool) => {
                 self.key_incr_(
@@ -57,6 +76,25 @@ impl RedisStore {
         }
     }
 
+    pub async fn key_incr_many(&self, keys: &[(&[u8], i64, Option<u64>)]) -> trc::Result<Vec<i64>> {
+        if let Some(cache) = &self.cache {
+            for (key, _, _) in keys {
+                cache.remove(key);
+            }
+        }
+
+        match &self.pool {
+            RedisPool::Single(pool) => {
+                self.key_incr_many_(pool.get().await.map_err(into_error)?.as_mut(), keys)
+                    .await
+            }
+            RedisPool::Cluster(pool) => {
+                self.key_incr_many_(pool.get().await.map_err(into_error)?.as_mut(), keys)
+                    .await
+            }
+        }
+    }
+
     pub async fn key_delete(&self, key: &[u8]) -> trc::Result<()> {
         match &self.pool {
             RedisPool::Single(pool) => {
@@ -67,7 +105,9 @@ impl RedisStore {
                 self.key_delete_(pool.get().await.map_err(into_error)?.as_mut(), key)
                     .await
             }
-        }
+        }?;
+        self.cache_invalidate(key).await;
+        Ok(())
     }
 
     pub async fn key_delete_prefix(&self, prefix: &[u8]) -> trc::Result<()> {
@@ -80,13 +120,71 @@ impl RedisStore {
                 self.key_delete_prefix_(pool.get().await.map_err(into_error)?.as_mut(), prefix)
                     .await
             }
+        }?;
+        self.cache_clear().await;
+        Ok(())
+    }
+
+    pub async fn key_get_prefix(&self, prefix: &[u8]) -> trc::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        match &self.pool {
+            RedisPool::Single(pool) => {
+                self.scan_prefix_(pool.get().await.map_err(into_error)?.as_mut(), prefix)
+                    .await
+            }
+            RedisPool::Cluster(pool) => {
+                self.scan_prefix_(pool.get().await.map_err(into_error)?.as_mut(), prefix)
+                    .await
+            }
         }
     }
 
+    pub async fn counter_get_prefix(&self, prefix: &[u8]) -> trc::Result<Vec<(Vec<u8>, i64)>> {
+        match &self.pool {
+            RedisPool::Single(pool) => {
+                self.scan_prefix_(pool.get().await.map_err(into_error)?.as_mut(), prefix)
+                    .await
+            }
+            RedisPool::Cluster(pool) => {
+                self.scan_prefix_(pool.get().await.map_err(into_error)?.as_mut(), prefix)
+                    .await
+            }
+        }
+        .map(|counters| {
+            counters
+                .into_iter()
+                .filter(|(_, value)| *value != 0)
+                .collect()
+        })
+    }
+
     pub async fn key_get<T: Deserialize + std::fmt::Debug + 'static>(
         &self,
         key: &[u8],
     ) -> trc::Result<Option<T>> {
+        let value = if let Some(cache) = &self.cache {
+            match cache.get(key) {
+                Some(CachedValue::Value(value)) => Some(value),
+                Some(CachedValue::Missing) => None,
+                Some(CachedValue::Exists) | None => {
+                    let value = self.key_get_pool(key).await?;
+                    cache.insert(
+                        key,
+                        value.as_ref().map_or(CachedValue::Missing, |value| {
+                            CachedValue::Value(value.clone())
+                        }),
+                        None,
+                    );
+                    value
+                }
+            }
+        } else {
+            self.key_get_pool(key).await?
+        };
+
+        value.map(T::deserialize_owned).transpose()
+    }
+
+    async fn key_get_pool(&self, key: &[u8]) -> trc::Result<Option<Vec<u8>>> {
         match &self.pool {
             RedisPool::Single(pool) => {
                 self.key_get_(pool.get().await.map_err(into_error)?.as_mut(), key)
@@ -113,6 +211,28 @@ impl RedisStore {
     }
 
     pub async fn key_exists(&self, key: &[u8]) -> trc::Result<bool> {
+        if let Some(cache) = &self.cache {
+            if let Some(value) = cache.get(key) {
+                return Ok(!matches!(value, CachedValue::Missing));
+            }
+
+            let exists = self.key_exists_pool(key).await?;
+            cache.insert(
+                key,
+                if exists {
+                    CachedValue::Exists
+                } else {
+                    CachedValue::Missing
+                },
+                None,
+            );
+            Ok(exists)
+        } else {
+            self.key_exists_pool(key).await
+        }
+    }
+
+    async fn key_exists_pool(&self, key: &[u8]) -> trc::Result<bool> {
         match &self.pool {
             RedisPool::Single(pool) => {
                 self.key_exists_(pool.get().await.map_err(into_error)?.as_mut(), key)
@@ -125,21 +245,81 @@ impl RedisStore {
         }
     }
 
-    async fn key_get_<T: Deserialize + std::fmt::Debug + 'static>(
+    pub async fn key_get_many<T: Deserialize + std::fmt::Debug + 'static>(
+        &self,
+        keys: &[&[u8]],
+    ) -> trc::Result<Vec<Option<T>>> {
+        match &self.pool {
+            RedisPool::Single(pool) => {
+                self.key_get_many_(pool.get().await.map_err(into_error)?.as_mut(), keys)
+                    .await
+            }
+            RedisPool::Cluster(pool) => {
+                self.key_get_many_(pool.get().await.map_err(into_error)?.as_mut(), keys)
+                    .await
+            }
+        }
+    }
+
+    pub async fn key_exists_any(&self, keys: &[&[u8]]) -> trc::Result<bool> {
+        match &self.pool {
+            RedisPool::Single(pool) => {
+                self.key_exists_any_(pool.get().await.map_err(into_error)?.as_mut(), keys)
+                    .await
+            }
+            RedisPool::Cluster(pool) => {
+                self.key_exists_any_(pool.get().await.map_err(into_error)?.as_mut(), keys)
+                    .await
+            }
+        }
+    }
+
+    async fn key_get_(
         &self,
         conn: &mut impl AsyncCommands,
         key: &[u8],
-    ) -> trc::Result<Option<T>> {
-        if let Some(value) = redis::cmd("GET")
+    ) -> trc::Result<Option<Vec<u8>>> {
+        redis::cmd("GET")
             .arg(key)
             .query_async::<Option<Vec<u8>>>(conn)
+            .await
+            .map_err(into_error)
+    }
+
+    async fn key_get_many_<T: Deserialize + std::fmt::Debug + 'static>(
+        &self,
+        conn: &mut impl AsyncCommands,
+        keys: &[&[u8]],
+    ) -> trc::Result<Vec<Option<T>>> {
+        // Pipelined GETs are routed per key, so this also works in cluster mode
+        // where a single MGET would fail with CROSSSLOT.
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.cmd("GET").arg(*key);
+        }
+
+        pipe.query_async::<Vec<Option<Vec<u8>>>>(conn)
             .await
             .map_err(into_error)?
-        {
-            T::deserialize_owned(value).map(Some)
-        } else {
-            Ok(None)
+            .into_iter()
+            .map(|value| value.map(T::deserialize_owned).transpose())
+            .collect()
+    }
+
+    async fn key_exists_any_(
+        &self,
+        conn: &mut impl AsyncCommands,
+        keys: &[&[u8]],
+    ) -> trc::Result<bool> {
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.exists(*key);
         }
+
+        pipe.query_async::<Vec<bool>>(conn)
+            .await
+            .map_err(into_error)
+            .map(|results| results.into_iter().any(|exists| exists))
     }
 
     async fn counter_get_(&self, conn: &mut impl AsyncCommands, key: &[u8]) -> trc::Result<i64> {
@@ -191,6 +371,30 @@ impl RedisStore {
         }
     }
 
+    async fn key_incr_many_(
+        &self,
+        conn: &mut impl AsyncCommands,
+        keys: &[(&[u8], i64, Option<u64>)],
+    ) -> trc::Result<Vec<i64>> {
+        // Not a transaction, keys may be stored in different cluster slots
+        let mut pipe = redis::pipe();
+        for (key, value, expires) in keys {
+            if *value != 0 {
+                pipe.incr(*key, *value);
+                if let Some(expires) = expires {
+                    pipe.expire(*key, *expires as i64).ignore();
+                }
+            } else {
+                pipe.cmd("GET").arg(*key);
+            }
+        }
+
+        pipe.query_async::<Vec<Option<i64>>>(conn)
+            .await
+            .map_err(into_error)
+            .map(|values| values.into_iter().map(|v| v.unwrap_or(0)).collect())
+    }
+
     async fn key_delete_(&self, conn: &mut impl AsyncCommands, key: &[u8]) -> trc::Result<()> {
         conn.del(key).await.map_err(into_error)
     }
@@ -227,4 +431,56 @@ impl RedisStore {
             }
         }
     }
+
+    async fn scan_prefix_<T: FromRedisValue>(
+        &self,
+        conn: &mut impl AsyncCommands,
+        prefix: &[u8],
+    ) -> trc::Result<Vec<(Vec<u8>, T)>> {
+        // Escape glob characters, keys may contain arbitrary bytes
+        let mut pattern = Vec::with_capacity(prefix.len() + 1);
+        for &ch in prefix {
+            if matches!(ch, b'*' | b'?' | b'[' | b']' | b'\\') {
+                pattern.push(b'\\');
+            }
+            pattern.push(ch);
+        }
+        pattern.push(b'*');
+
+        let mut results = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (new_cursor, keys): (u64, Vec<Vec<u8>>) = redis::cmd("SCAN")
+                .cursor_arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(100)
+                .query_async(conn)
+                .await
+                .map_err(into_error)?;
+
+            if !keys.is_empty() {
+                let mut pipe = redis::pipe();
+                for key in &keys {
+                    pipe.get(key);
+                }
+                let values = pipe
+                    .query_async::<Vec<Option<T>>>(conn)
+                    .await
+                    .map_err(into_error)?;
+                results.extend(
+                    keys.into_iter()
+                        .zip(values)
+                        .filter_map(|(key, value)| value.map(|value| (key, value))),
+                );
+            }
+
+            if new_cursor != 0 {
+                cursor = new_cursor;
+            } else {
+                return Ok(results);
+            }
+        }
+    }
 }
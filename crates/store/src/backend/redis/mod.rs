@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{fmt::Display, time::Duration};
+use std::{fmt::Display, sync::Arc, time::Duration};
 
 use deadpool::{
     Runtime,
@@ -16,6 +16,9 @@ use redis::{
 };
 use utils::config::{Config, utils::AsKey};
 
+use self::cache::LookupCache;
+
+pub(crate) mod cache;
 pub mod lookup;
 pub mod pool;
 pub mod pubsub;
@@ -23,6 +26,7 @@ pub mod pubsub;
 #[derive(Debug)]
 pub struct RedisStore {
     pool: RedisPool,
+    cache: Option<Arc<LookupCache>>,
 }
 
 struct RedisConnectionManager {
@@ -35,6 +39,7 @@ struct RedisClusterConnectionManager {
     timeout: Duration,
 }
 
+#[derive(Clone)]
 enum RedisPool {
     Single(Pool<RedisConnectionManager>),
     Cluster(Pool<RedisClusterConnectionManager>),
@@ -42,6 +47,19 @@ enum RedisPool {
 
 impl RedisStore {
     pub async fn open(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let mut store = Self::open_pool(config, prefix.as_str()).await?;
+
+        // Local cache for hot keys, invalidated via pub/sub
+        store.cache = LookupCache::parse(config, &prefix);
+        if let Some(cache) = &store.cache {
+            store.spawn_cache_subscriber(cache);
+        }
+
+        Some(store)
+    }
+
+    async fn open_pool(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
         let prefix = prefix.as_key();
         let urls = config
             .values((&prefix, "urls"))
@@ -68,6 +86,7 @@ impl RedisStore {
                         .unwrap_or_default();
 
                     Self {
+                        cache: None,
                         pool: RedisPool::Single(
                             build_pool(config, &prefix, RedisConnectionManager { client, timeout })
                                 .map_err(|err| {
@@ -128,6 +147,7 @@ impl RedisStore {
                         .unwrap_or_else(|| Duration::from_secs(10));
 
                     Self {
+                        cache: None,
                         pool: RedisPool::Cluster(
                             build_pool(
                                 config,
@@ -4,9 +4,21 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{fmt::Display, io::Write, ops::Range, time::Duration};
+use std::{
+    fmt::Display,
+    io::Write,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
-use s3::{Bucket, Region, creds::Credentials};
+use rand::Rng;
+use s3::{
+    Bucket, Region,
+    creds::Credentials,
+    error::S3Error,
+    request::ResponseData,
+    serde_types::{InitiateMultipartUploadResponse, Part},
+};
 use utils::{
     codec::base32_custom::Base32Writer,
     config::{Config, utils::AsKey},
@@ -16,8 +28,16 @@ pub struct S3Store {
     bucket: Box<Bucket>,
     prefix: Option<String>,
     max_retries: u32,
+    retry_base: Duration,
+    retry_max: Duration,
+    multipart_threshold: usize,
+    multipart_part_size: usize,
 }
 
+// S3 rejects multipart chunks smaller than 5 MiB (except for the last one)
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+const CONTENT_TYPE: &str = "application/octet-stream";
+
 impl S3Store {
     pub async fn open(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
         // Obtain region and endpoint from config
@@ -48,6 +68,10 @@ impl S3Store {
         let timeout = config
             .property_or_default::<Duration>((&prefix, "timeout"), "30s")
             .unwrap_or_else(|| Duration::from_secs(30));
+        let multipart_part_size = config
+            .property_or_default::<usize>((&prefix, "multipart.part-size"), "8388608")
+            .unwrap_or(8 * 1024 * 1024)
+            .max(MIN_PART_SIZE);
 
         Some(S3Store {
             bucket: Bucket::new(
@@ -68,6 +92,17 @@ impl S3Store {
             max_retries: config
                 .property_or_default((&prefix, "max-retries"), "3")
                 .unwrap_or(3),
+            retry_base: config
+                .property_or_default((&prefix, "retry.base-delay"), "500ms")
+                .unwrap_or_else(|| Duration::from_millis(500)),
+            retry_max: config
+                .property_or_default((&prefix, "retry.max-delay"), "30s")
+                .unwrap_or_else(|| Duration::from_secs(30)),
+            multipart_threshold: config
+                .property_or_default::<usize>((&prefix, "multipart.threshold"), "16777216")
+                .unwrap_or(16 * 1024 * 1024)
+                .max(multipart_part_size),
+            multipart_part_size,
             prefix: config.value((&prefix, "key-prefix")).map(|s| s.to_string()),
         })
     }
@@ -78,104 +113,196 @@ impl S3Store {
         range: Range<usize>,
     ) -> trc::Result<Option<Vec<u8>>> {
         let path = self.build_key(key);
-        let mut retries_left = self.max_retries;
+        let response = if range.start != 0 || range.end != usize::MAX {
+            self.request("get_object_range", || {
+                self.bucket.get_object_range(
+                    &path,
+                    range.start as u64,
+                    Some(range.end.saturating_sub(1) as u64),
+                )
+            })
+            .await
+        } else {
+            self.request("get_object", || self.bucket.get_object(&path))
+                .await
+        }?;
 
-        loop {
-            let response = if range.start != 0 || range.end != usize::MAX {
-                self.bucket
-                    .get_object_range(
-                        &path,
-                        range.start as u64,
-                        Some(range.end.saturating_sub(1) as u64),
-                    )
-                    .await
-            } else {
-                self.bucket.get_object(&path).await
-            }
-            .map_err(into_error)?;
-
-            match response.status_code() {
-                200..=299 => return Ok(Some(response.to_vec())),
-                404 => return Ok(None),
-                500..=599 if retries_left > 0 => {
-                    // wait backoff
-                    tokio::time::sleep(Duration::from_secs(
-                        1 << (self.max_retries - retries_left).min(6),
-                    ))
-                    .await;
-
-                    retries_left -= 1;
-                }
-                code => {
-                    return Err(trc::StoreEvent::S3Error
-                        .reason(String::from_utf8_lossy(response.as_slice()))
-                        .ctx(trc::Key::Code, code));
-                }
-            }
+        match response.status_code() {
+            200..=299 => Ok(Some(response.to_vec())),
+            404 => Ok(None),
+            code => Err(trc::StoreEvent::S3Error
+                .reason(String::from_utf8_lossy(response.as_slice()))
+                .ctx(trc::Key::Code, code)),
         }
     }
 
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
-        let mut retries_left = self.max_retries;
+        let path = self.build_key(key);
+        if data.len() > self.multipart_threshold {
+            return self.put_blob_multipart(&path, data).await;
+        }
 
-        loop {
-            let response = self
-                .bucket
-                .put_object(self.build_key(key), data)
+        let response = self
+            .request("put_object", || self.bucket.put_object(&path, data))
+            .await?;
+
+        match response.status_code() {
+            200..=299 => Ok(()),
+            code => Err(trc::StoreEvent::S3Error
+                .reason(String::from_utf8_lossy(response.as_slice()))
+                .ctx(trc::Key::Code, code)),
+        }
+    }
+
+    async fn put_blob_multipart(&self, path: &str, data: &[u8]) -> trc::Result<()> {
+        let upload_id = self
+            .request("initiate_multipart_upload", || {
+                self.bucket.initiate_multipart_upload(path, CONTENT_TYPE)
+            })
+            .await?
+            .upload_id;
+
+        let mut parts = Vec::with_capacity(data.len().div_ceil(self.multipart_part_size));
+        for (part_number, chunk) in data.chunks(self.multipart_part_size).enumerate() {
+            match self
+                .request("put_multipart_chunk", || {
+                    self.bucket.put_multipart_chunk(
+                        chunk.to_vec(),
+                        path,
+                        part_number as u32 + 1,
+                        &upload_id,
+                        CONTENT_TYPE,
+                    )
+                })
                 .await
-                .map_err(into_error)?;
-
-            match response.status_code() {
-                200..=299 => return Ok(()),
-                500..=599 if retries_left > 0 => {
-                    // wait backoff
-                    tokio::time::sleep(Duration::from_secs(
-                        1 << (self.max_retries - retries_left).min(6),
-                    ))
-                    .await;
-
-                    retries_left -= 1;
-                }
-                code => {
-                    return Err(trc::StoreEvent::S3Error
-                        .reason(String::from_utf8_lossy(response.as_slice()))
-                        .ctx(trc::Key::Code, code));
+            {
+                Ok(part) => parts.push(part),
+                Err(err) => {
+                    self.abort_multipart(path, &upload_id).await;
+                    return Err(err);
                 }
             }
         }
+
+        match self
+            .request("complete_multipart_upload", || {
+                self.bucket
+                    .complete_multipart_upload(path, &upload_id, parts.clone())
+            })
+            .await
+        {
+            Ok(response) if (200..=299).contains(&response.status_code()) => Ok(()),
+            Ok(response) => {
+                self.abort_multipart(path, &upload_id).await;
+                Err(trc::StoreEvent::S3Error
+                    .reason(String::from_utf8_lossy(response.as_slice()))
+                    .ctx(trc::Key::Code, response.status_code()))
+            }
+            Err(err) => {
+                self.abort_multipart(path, &upload_id).await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn abort_multipart(&self, path: &str, upload_id: &str) {
+        if let Err(err) = self.bucket.abort_upload(path, upload_id).await {
+            trc::error!(
+                into_error(err)
+                    .details("Failed to abort multipart upload")
+                    .ctx(trc::Key::Key, path.to_string())
+            );
+        }
     }
 
     pub(crate) async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
-        let mut retries_left = self.max_retries;
+        let path = self.build_key(key);
+        let response = self
+            .request("delete_object", || self.bucket.delete_object(&path))
+            .await?;
+
+        match response.status_code() {
+            200..=299 => Ok(true),
+            404 => Ok(false),
+            code => Err(trc::StoreEvent::S3Error
+                .reason(String::from_utf8_lossy(response.as_slice()))
+                .ctx(trc::Key::Code, code)),
+        }
+    }
+
+    // Executes a request, retrying on transport errors, throttling and server errors
+    async fn request<T, F, Fut>(&self, operation: &'static str, request: F) -> trc::Result<T>
+    where
+        T: S3Response,
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, S3Error>>,
+    {
+        let mut attempt = 0;
 
         loop {
-            let response = self
-                .bucket
-                .delete_object(self.build_key(key))
-                .await
-                .map_err(into_error)?;
-
-            match response.status_code() {
-                200..=299 => return Ok(true),
-                404 => return Ok(false),
-                500..=599 if retries_left > 0 => {
-                    // wait backoff
-                    tokio::time::sleep(Duration::from_secs(
-                        1 << (self.max_retries - retries_left).min(6),
-                    ))
-                    .await;
-
-                    retries_left -= 1;
+            let start_time = Instant::now();
+            let result = request().await;
+            let elapsed = start_time.elapsed();
+            let retry_reason = match &result {
+                Ok(response) => {
+                    let code = response.status_code();
+
+                    trc::event!(
+                        Store(trc::StoreEvent::S3Request),
+                        Details = operation,
+                        Code = code,
+                        Elapsed = elapsed,
+                    );
+
+                    if matches!(code, 429 | 500..=599) {
+                        format!("HTTP {code}")
+                    } else {
+                        return result.map_err(into_error);
+                    }
                 }
-                code => {
-                    return Err(trc::StoreEvent::S3Error
-                        .reason(String::from_utf8_lossy(response.as_slice()))
-                        .ctx(trc::Key::Code, code));
+                Err(err) => {
+                    trc::event!(
+                        Store(trc::StoreEvent::S3Request),
+                        Details = operation,
+                        Reason = err.to_string(),
+                        Elapsed = elapsed,
+                    );
+
+                    err.to_string()
                 }
+            };
+
+            if attempt >= self.max_retries {
+                return match result {
+                    Ok(response) => Ok(response),
+                    Err(err) => Err(into_error(err)),
+                };
             }
+
+            let delay = self.retry_delay(attempt);
+            trc::event!(
+                Store(trc::StoreEvent::S3Retry),
+                Details = operation,
+                Reason = retry_reason,
+                Total = attempt + 1,
+                Elapsed = delay,
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
     }
 
+    // Exponential backoff with equal jitter, capped at the configured maximum delay
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .retry_base
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.retry_max)
+            .as_millis() as u64;
+
+        Duration::from_millis(rand::rng().random_range(delay / 2..=delay))
+    }
+
     fn build_key(&self, key: &[u8]) -> String {
         if let Some(prefix) = &self.prefix {
             let mut writer =
@@ -189,6 +316,28 @@ impl S3Store {
     }
 }
 
+trait S3Response {
+    fn status_code(&self) -> u16;
+}
+
+impl S3Response for ResponseData {
+    fn status_code(&self) -> u16 {
+        ResponseData::status_code(self)
+    }
+}
+
+impl S3Response for Part {
+    fn status_code(&self) -> u16 {
+        200
+    }
+}
+
+impl S3Response for InitiateMultipartUploadResponse {
+    fn status_code(&self) -> u16 {
+        200
+    }
+}
+
 #[inline(always)]
 fn into_error(err: impl Display) -> trc::Error {
     trc::StoreEvent::S3Error.reason(err)
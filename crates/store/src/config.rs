@@ -241,6 +241,16 @@ impl Stores {
                             .insert(store_id, db.with_compression(compression_algo));
                     }
                 }
+                #[cfg(feature = "gcs")]
+                "gcs" => {
+                    if let Some(db) = crate::backend::gcs::GcsStore::open(config, prefix)
+                        .await
+                        .map(BlobStore::from)
+                    {
+                        self.blob_stores
+                            .insert(store_id, db.with_compression(compression_algo));
+                    }
+                }
                 unknown => {
                     config.new_parse_warning(
                         ("store", id, "type"),
@@ -294,6 +304,52 @@ impl Stores {
                         blob_store: blob_store.clone(),
                     },
                 });
+
+                // Parse blob integrity verification schedule
+                let store_id = config.value("storage.blob").unwrap().to_string();
+                if config
+                    .property_or_default::<bool>(
+                        ("store", store_id.as_str(), "scrub.enable"),
+                        "false",
+                    )
+                    .unwrap_or(false)
+                {
+                    let replica = config
+                        .value(("store", store_id.as_str(), "scrub.repair-from"))
+                        .map(|id| id.to_string())
+                        .and_then(|id| {
+                            let replica = self.blob_stores.get(&id).cloned();
+                            if replica.is_none() {
+                                config.new_build_error(
+                                    ("store", store_id.as_str(), "scrub.repair-from"),
+                                    format!("Blob store {id:?} not found"),
+                                );
+                            }
+                            replica
+                        });
+
+                    self.purge_schedules.push(PurgeSchedule {
+                        cron: config
+                            .property_or_default::<SimpleCron>(
+                                ("store", store_id.as_str(), "scrub.frequency"),
+                                "0 2 7",
+                            )
+                            .unwrap_or_else(|| SimpleCron::parse_value("0 2 7").unwrap()),
+                        store: PurgeStore::BlobScrub {
+                            store: store.clone(),
+                            blob_store: blob_store.clone(),
+                            sample_rate: config
+                                .property_or_default::<u16>(
+                                    ("store", store_id.as_str(), "scrub.sample-rate"),
+                                    "100",
+                                )
+                                .unwrap_or(100)
+                                .clamp(1, 100) as u8,
+                            replica,
+                        },
+                        store_id,
+                    });
+                }
             }
         }
         for (store_id, store) in &self.in_memory_stores {
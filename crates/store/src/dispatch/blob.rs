@@ -37,6 +37,8 @@ impl BlobStore {
             BlobBackend::S3(store) => store.get_blob(key, read_range).await,
             #[cfg(feature = "azure")]
             BlobBackend::Azure(store) => store.get_blob(key, read_range).await,
+            #[cfg(feature = "gcs")]
+            BlobBackend::Gcs(store) => store.get_blob(key, read_range).await,
         };
 
         trc::event!(
@@ -115,6 +117,8 @@ impl BlobStore {
             BlobBackend::S3(store) => store.put_blob(key, data.as_ref()).await,
             #[cfg(feature = "azure")]
             BlobBackend::Azure(store) => store.put_blob(key, data.as_ref()).await,
+            #[cfg(feature = "gcs")]
+            BlobBackend::Gcs(store) => store.put_blob(key, data.as_ref()).await,
         }
         .caused_by(trc::location!());
 
@@ -149,6 +153,8 @@ impl BlobStore {
             BlobBackend::S3(store) => store.delete_blob(key).await,
             #[cfg(feature = "azure")]
             BlobBackend::Azure(store) => store.delete_blob(key).await,
+            #[cfg(feature = "gcs")]
+            BlobBackend::Gcs(store) => store.delete_blob(key).await,
         }
         .caused_by(trc::location!());
 
@@ -62,6 +62,22 @@ impl FtsStore {
         .caused_by(trc::location!())
     }
 
+    pub async fn indexed_document_ids(
+        &self,
+        account_id: u32,
+        collection: Collection,
+    ) -> trc::Result<Option<RoaringBitmap>> {
+        match self {
+            FtsStore::Store(store) => store
+                .fts_document_ids(account_id, collection)
+                .await
+                .map(Some),
+            #[cfg(feature = "elastic")]
+            FtsStore::ElasticSearch(_) => Ok(None),
+        }
+        .caused_by(trc::location!())
+    }
+
     pub async fn remove_all(&self, account_id: u32) -> trc::Result<()> {
         match self {
             FtsStore::Store(store) => store.fts_remove_all(account_id).await,
@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::borrow::Cow;
+use std::{borrow::Cow, time::SystemTime};
 
 use trc::AddContext;
 use utils::config::Rate;
@@ -21,7 +21,7 @@ use crate::{
 use crate::{
     SerializeInfallible,
     backend::http::lookup::HttpStoreGet,
-    write::{InMemoryClass, assert::AssertValue},
+    write::{AssignedId, InMemoryClass, assert::AssertValue},
 };
 
 pub struct KeyValue<T> {
@@ -102,6 +102,59 @@ impl InMemoryStore {
         .caused_by(trc::location!())
     }
 
+    // Adds to multiple counters in a single round trip and returns their new
+    // values, counters incremented by zero are only read.
+    pub async fn counter_incr_many(&self, counters: Vec<KeyValue<i64>>) -> trc::Result<Vec<i64>> {
+        match self {
+            InMemoryStore::Store(store) => {
+                let mut batch = BatchBuilder::new();
+                for kv in counters {
+                    if let Some(expires) = kv.expires {
+                        batch.any_op(Operation::Value {
+                            class: ValueClass::InMemory(InMemoryClass::Key(kv.key.clone())),
+                            op: ValueOp::Set {
+                                value: KeySerializer::new(U64_LEN * 2)
+                                    .write(0u64)
+                                    .write(now() + expires)
+                                    .finalize(),
+                                version_offset: None,
+                            },
+                        });
+                    }
+                    batch.any_op(Operation::Value {
+                        class: ValueClass::InMemory(InMemoryClass::Counter(kv.key)),
+                        op: ValueOp::AddAndGet(kv.value),
+                    });
+                }
+
+                store.write(batch.build_all()).await.map(|ids| {
+                    ids.ids
+                        .into_iter()
+                        .filter_map(|id| match id {
+                            AssignedId::Counter(value) => Some(value),
+                            _ => None,
+                        })
+                        .collect()
+                })
+            }
+            #[cfg(feature = "redis")]
+            InMemoryStore::Redis(store) => {
+                store
+                    .key_incr_many(
+                        &counters
+                            .iter()
+                            .map(|kv| (kv.key.as_slice(), kv.value, kv.expires))
+                            .collect::<Vec<_>>(),
+                    )
+                    .await
+            }
+            InMemoryStore::Static(_) | InMemoryStore::Http(_) => {
+                Err(trc::StoreEvent::NotSupported.into_err())
+            }
+        }
+        .caused_by(trc::location!())
+    }
+
     pub async fn key_delete(&self, key: impl Into<LookupKey<'_>>) -> trc::Result<()> {
         match self {
             InMemoryStore::Store(store) => {
@@ -179,6 +232,101 @@ impl InMemoryStore {
         .caused_by(trc::location!())
     }
 
+    // Returns the raw value of all non-expired keys starting with the given prefix
+    pub async fn key_get_prefix(&self, prefix: &[u8]) -> trc::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        match self {
+            InMemoryStore::Store(store) => {
+                if prefix.is_empty() {
+                    return Ok(vec![]);
+                }
+
+                let (from_range, to_range) = prefix_range(prefix);
+                let current_time = now();
+                let mut results = Vec::new();
+
+                store
+                    .iterate(
+                        IterateParams::new(
+                            ValueKey::from(ValueClass::InMemory(InMemoryClass::Key(from_range))),
+                            ValueKey::from(ValueClass::InMemory(InMemoryClass::Key(to_range))),
+                        ),
+                        |key, value| {
+                            // Counter expiration entries have an expiry of zero
+                            if value.deserialize_be_u64(0).caused_by(trc::location!())?
+                                > current_time
+                            {
+                                results.push((
+                                    key.to_vec(),
+                                    value.get(U64_LEN..).unwrap_or_default().to_vec(),
+                                ));
+                            }
+                            Ok(true)
+                        },
+                    )
+                    .await
+                    .map(|_| results)
+            }
+            #[cfg(feature = "redis")]
+            InMemoryStore::Redis(store) => store.key_get_prefix(prefix).await,
+            InMemoryStore::Static(_) | InMemoryStore::Http(_) => {
+                Err(trc::StoreEvent::NotSupported.into_err())
+            }
+        }
+        .caused_by(trc::location!())
+    }
+
+    // Returns all non-zero counters starting with the given prefix
+    pub async fn counter_get_prefix(&self, prefix: &[u8]) -> trc::Result<Vec<(Vec<u8>, i64)>> {
+        match self {
+            InMemoryStore::Store(store) => {
+                if prefix.is_empty() {
+                    return Ok(vec![]);
+                }
+
+                let (from_range, to_range) = prefix_range(prefix);
+                let mut keys = Vec::new();
+
+                store
+                    .iterate(
+                        IterateParams::new(
+                            ValueKey::from(ValueClass::InMemory(InMemoryClass::Counter(
+                                from_range,
+                            ))),
+                            ValueKey::from(ValueClass::InMemory(InMemoryClass::Counter(to_range))),
+                        )
+                        .no_values(),
+                        |key, _| {
+                            keys.push(key.to_vec());
+                            Ok(true)
+                        },
+                    )
+                    .await
+                    .caused_by(trc::location!())?;
+
+                let mut results = Vec::with_capacity(keys.len());
+                for key in keys {
+                    let value = store
+                        .get_counter(ValueKey::from(ValueClass::InMemory(
+                            InMemoryClass::Counter(key.clone()),
+                        )))
+                        .await
+                        .caused_by(trc::location!())?;
+                    if value != 0 {
+                        results.push((key, value));
+                    }
+                }
+
+                Ok(results)
+            }
+            #[cfg(feature = "redis")]
+            InMemoryStore::Redis(store) => store.counter_get_prefix(prefix).await,
+            InMemoryStore::Static(_) | InMemoryStore::Http(_) => {
+                Err(trc::StoreEvent::NotSupported.into_err())
+            }
+        }
+        .caused_by(trc::location!())
+    }
+
     pub async fn key_get<T: Deserialize + From<Value<'static>> + std::fmt::Debug + 'static>(
         &self,
         key: impl Into<LookupKey<'_>>,
@@ -202,6 +350,56 @@ impl InMemoryStore {
         .caused_by(trc::location!())
     }
 
+    pub async fn key_get_many<T: Deserialize + From<Value<'static>> + std::fmt::Debug + 'static>(
+        &self,
+        keys: Vec<LookupKey<'_>>,
+    ) -> trc::Result<Vec<Option<T>>> {
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+
+        match self {
+            InMemoryStore::Store(_) | InMemoryStore::Static(_) | InMemoryStore::Http(_) => {
+                let mut results = Vec::with_capacity(keys.len());
+                for key in keys {
+                    results.push(self.key_get(key).await?);
+                }
+                Ok(results)
+            }
+            #[cfg(feature = "redis")]
+            InMemoryStore::Redis(store) => {
+                store
+                    .key_get_many(&keys.iter().map(|k| k.as_bytes()).collect::<Vec<_>>())
+                    .await
+            }
+        }
+        .caused_by(trc::location!())
+    }
+
+    pub async fn key_exists_any(&self, keys: Vec<LookupKey<'_>>) -> trc::Result<bool> {
+        if keys.is_empty() {
+            return Ok(false);
+        }
+
+        match self {
+            InMemoryStore::Store(_) | InMemoryStore::Static(_) | InMemoryStore::Http(_) => {
+                for key in keys {
+                    if self.key_exists(key).await? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            #[cfg(feature = "redis")]
+            InMemoryStore::Redis(store) => {
+                store
+                    .key_exists_any(&keys.iter().map(|k| k.as_bytes()).collect::<Vec<_>>())
+                    .await
+            }
+        }
+        .caused_by(trc::location!())
+    }
+
     pub async fn counter_get(&self, key: impl Into<LookupKey<'_>>) -> trc::Result<i64> {
         match self {
             InMemoryStore::Store(store) => {
@@ -236,6 +434,9 @@ impl InMemoryStore {
         .caused_by(trc::location!())
     }
 
+    // Sliding window limiter: the count of the current window is added to the
+    // count of the previous window weighted by how much of it is still covered.
+    // Both windows are shared counters, so limits hold across all nodes.
     pub async fn is_rate_allowed(
         &self,
         prefix: u8,
@@ -243,25 +444,55 @@ impl InMemoryStore {
         rate: &Rate,
         soft_check: bool,
     ) -> trc::Result<Option<u64>> {
-        let now = now();
-        let range_start = now / rate.period.as_secs();
-        let range_end = (range_start * rate.period.as_secs()) + rate.period.as_secs();
-        let expires_in = range_end - now;
-
-        let mut bucket = Vec::with_capacity(key.len() + U64_LEN + 1);
-        bucket.push(prefix);
-        bucket.extend_from_slice(key);
-        bucket.extend_from_slice(range_start.to_be_bytes().as_slice());
-
-        let requests = if !soft_check {
-            self.counter_incr(KeyValue::new(bucket, 1).expires(expires_in), true)
-                .await
-                .caused_by(trc::location!())?
-        } else {
-            self.counter_get(bucket).await.caused_by(trc::location!())? + 1
+        self.is_rate_allowed_at(
+            prefix,
+            key,
+            rate,
+            soft_check,
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+        )
+        .await
+    }
+
+    pub async fn is_rate_allowed_at(
+        &self,
+        prefix: u8,
+        key: &[u8],
+        rate: &Rate,
+        soft_check: bool,
+        now: u64,
+    ) -> trc::Result<Option<u64>> {
+        let period = (rate.period.as_millis() as u64).max(1);
+        let range_start = now / period;
+        let elapsed = now - (range_start * period);
+        let expires_in = (period - elapsed).div_ceil(1000);
+
+        let bucket = |range: u64| {
+            let mut bucket = Vec::with_capacity(key.len() + U64_LEN + 1);
+            bucket.push(prefix);
+            bucket.extend_from_slice(key);
+            bucket.extend_from_slice(range.to_be_bytes().as_slice());
+            bucket
         };
 
-        if requests <= rate.requests as i64 {
+        // Both windows are fetched in a single round trip, the current counter is
+        // kept alive for the next window, where it is weighted
+        let counters = self
+            .counter_incr_many(vec![
+                KeyValue::new(bucket(range_start), if soft_check { 0 } else { 1 })
+                    .expires(expires_in + period.div_ceil(1000)),
+                KeyValue::new(bucket(range_start.saturating_sub(1)), 0).expires(expires_in),
+            ])
+            .await
+            .caused_by(trc::location!())?;
+        let requests =
+            counters.first().copied().unwrap_or_default().max(0) as u64 + u64::from(soft_check);
+        let previous = counters.get(1).copied().unwrap_or_default().max(0) as u64;
+        let weighted = requests + (previous * (period - elapsed) / period);
+
+        if weighted <= rate.requests {
             Ok(None)
         } else {
             Ok(Some(expires_in))
@@ -556,6 +787,13 @@ impl<T> KeyValue<T> {
     }
 }
 
+fn prefix_range(prefix: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut to_range = Vec::with_capacity(prefix.len() + 3);
+    to_range.extend_from_slice(prefix);
+    to_range.extend_from_slice([u8::MAX, u8::MAX, u8::MAX].as_ref());
+    (prefix.to_vec(), to_range)
+}
+
 enum LookupValue<T> {
     Value(T),
     None,
@@ -15,6 +15,7 @@ use nlp::{
     },
     tokenizers::word::WordTokenizer,
 };
+use roaring::RoaringBitmap;
 use trc::AddContext;
 use types::collection::Collection;
 
@@ -338,6 +339,49 @@ impl Store {
         Ok(())
     }
 
+    pub async fn fts_document_ids(
+        &self,
+        account_id: u32,
+        collection: Collection,
+    ) -> trc::Result<RoaringBitmap> {
+        let mut document_ids = RoaringBitmap::new();
+        let collection = collection as u8;
+        self.iterate(
+            IterateParams::new(
+                ValueKey {
+                    account_id,
+                    collection,
+                    document_id: 0,
+                    class: ValueClass::FtsIndex(BitmapHash {
+                        hash: [0; 8],
+                        len: 1,
+                    }),
+                },
+                ValueKey {
+                    account_id: account_id + 1,
+                    collection,
+                    document_id: 0,
+                    class: ValueClass::FtsIndex(BitmapHash {
+                        hash: [0; 8],
+                        len: 1,
+                    }),
+                },
+            )
+            .no_values(),
+            |key, _| {
+                if key.get(key.len() - U32_LEN - 1) == Some(&collection) {
+                    document_ids.insert(key.deserialize_be_u32(key.len() - U32_LEN)?);
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        Ok(document_ids)
+    }
+
     pub async fn fts_remove_all(&self, _: u32) -> trc::Result<()> {
         // No-op
         // Term indexes are stored in the same key range as the document
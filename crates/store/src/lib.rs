@@ -175,6 +175,8 @@ pub enum BlobBackend {
     S3(Arc<backend::s3::S3Store>),
     #[cfg(feature = "azure")]
     Azure(Arc<backend::azure::AzureStore>),
+    #[cfg(feature = "gcs")]
+    Gcs(Arc<backend::gcs::GcsStore>),
 }
 
 #[derive(Clone)]
@@ -271,6 +273,16 @@ impl From<backend::azure::AzureStore> for BlobStore {
     }
 }
 
+#[cfg(feature = "gcs")]
+impl From<backend::gcs::GcsStore> for BlobStore {
+    fn from(store: backend::gcs::GcsStore) -> Self {
+        BlobStore {
+            backend: BlobBackend::Gcs(Arc::new(store)),
+            compression: CompressionAlgo::None,
+        }
+    }
+}
+
 #[cfg(feature = "elastic")]
 impl From<backend::elastic::ElasticSearchStore> for FtsStore {
     fn from(store: backend::elastic::ElasticSearchStore) -> Self {
@@ -330,8 +342,17 @@ impl Default for FtsStore {
 #[derive(Clone)]
 pub enum PurgeStore {
     Data(Store),
-    Blobs { store: Store, blob_store: BlobStore },
+    Blobs {
+        store: Store,
+        blob_store: BlobStore,
+    },
     Lookup(InMemoryStore),
+    BlobScrub {
+        store: Store,
+        blob_store: BlobStore,
+        sample_rate: u8,
+        replica: Option<BlobStore>,
+    },
 }
 
 #[derive(Clone)]
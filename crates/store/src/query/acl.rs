@@ -81,6 +81,37 @@ impl Store {
         .map(|_| results)
     }
 
+    pub async fn acl_list_all(&self) -> trc::Result<Vec<(u32, AclItem)>> {
+        let from_key = ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Acl(0),
+        };
+        let to_key = ValueKey {
+            account_id: u32::MAX,
+            collection: u8::MAX,
+            document_id: u32::MAX,
+            class: ValueClass::Acl(u32::MAX),
+        };
+
+        let mut results = Vec::new();
+        self.iterate(
+            IterateParams::new(from_key, to_key).ascending(),
+            |key, value| {
+                results.push((
+                    key.deserialize_be_u32(0)?,
+                    AclItem::deserialize(key)?.with_permissions(u64::deserialize(value)?),
+                ));
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())
+        .map(|_| results)
+    }
+
     pub async fn acl_revoke_all(&self, account_id: u32) -> trc::Result<AHashSet<u32>> {
         let from_key = ValueKey {
             account_id: 0,
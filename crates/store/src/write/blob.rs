@@ -9,6 +9,8 @@ use crate::{
     BlobStore, Deserialize, IterateParams, Store, U32_LEN, U64_LEN, ValueKey, write::BatchBuilder,
 };
 use ahash::AHashSet;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use trc::AddContext;
 use types::{
     blob::BlobClass,
@@ -22,6 +24,24 @@ pub struct BlobQuota {
     pub count: usize,
 }
 
+#[derive(Debug, Default, Clone)]
+pub struct BlobScrubReport {
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub checked: u64,
+    pub corrupted: Vec<BlobHash>,
+    pub missing: Vec<BlobHash>,
+    pub repaired: Vec<BlobHash>,
+}
+
+#[derive(Default)]
+pub struct BlobScrubStatus {
+    pub running: AtomicBool,
+    pub total: AtomicU64,
+    pub checked: AtomicU64,
+    pub last_report: Mutex<Option<BlobScrubReport>>,
+}
+
 impl Store {
     pub async fn blob_exists(&self, hash: impl AsRef<BlobHash> + Sync + Send) -> trc::Result<bool> {
         self.get_value::<()>(ValueKey {
@@ -241,6 +261,201 @@ impl Store {
         Ok(())
     }
 
+    pub async fn unreferenced_blobs(&self) -> trc::Result<Vec<BlobHash>> {
+        // Obtain blobs with active reservations
+        let from_key = ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Blob(BlobOp::Reserve {
+                until: 0,
+                hash: BlobHash::default(),
+            }),
+        };
+        let to_key = ValueKey {
+            account_id: u32::MAX,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Blob(BlobOp::Reserve {
+                until: 0,
+                hash: BlobHash::default(),
+            }),
+        };
+        let mut active_hashes = AHashSet::new();
+        let now = now();
+        self.iterate(
+            IterateParams::new(from_key, to_key).ascending().no_values(),
+            |key, _| {
+                if key.deserialize_be_u64(key.len() - U64_LEN)? > now {
+                    active_hashes.insert(
+                        BlobHash::try_from_hash_slice(
+                            key.get(U32_LEN..U32_LEN + BLOB_HASH_LEN).ok_or_else(|| {
+                                trc::Error::corrupted_key(key, None, trc::location!())
+                            })?,
+                        )
+                        .unwrap(),
+                    );
+                }
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        // Find committed blobs without links
+        let from_key = ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Blob(BlobOp::Link {
+                hash: BlobHash::default(),
+            }),
+        };
+        let to_key = ValueKey {
+            account_id: u32::MAX,
+            collection: u8::MAX,
+            document_id: u32::MAX,
+            class: ValueClass::Blob(BlobOp::Link {
+                hash: BlobHash::new_max(),
+            }),
+        };
+        let mut last_hash = BlobHash::default();
+        let mut hashes = Vec::new();
+        self.iterate(
+            IterateParams::new(from_key, to_key).ascending().no_values(),
+            |key, _| {
+                let hash = BlobHash::try_from_hash_slice(
+                    key.get(0..BLOB_HASH_LEN)
+                        .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?,
+                )
+                .unwrap();
+                let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+
+                if document_id != u32::MAX {
+                    last_hash = hash;
+                } else if last_hash != hash && !active_hashes.contains(&hash) {
+                    hashes.push(hash);
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        Ok(hashes)
+    }
+
+    pub async fn scrub_blobs(
+        &self,
+        blob_store: &BlobStore,
+        sample_rate: u8,
+        replica: Option<&BlobStore>,
+        status: &BlobScrubStatus,
+    ) -> trc::Result<BlobScrubReport> {
+        // Obtain committed blob hashes
+        let from_key = ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Blob(BlobOp::Link {
+                hash: BlobHash::default(),
+            }),
+        };
+        let to_key = ValueKey {
+            account_id: u32::MAX,
+            collection: u8::MAX,
+            document_id: u32::MAX,
+            class: ValueClass::Blob(BlobOp::Link {
+                hash: BlobHash::new_max(),
+            }),
+        };
+        let mut hashes = Vec::new();
+        self.iterate(
+            IterateParams::new(from_key, to_key).ascending().no_values(),
+            |key, _| {
+                if key.deserialize_be_u32(key.len() - U32_LEN)? == u32::MAX
+                    && (sample_rate >= 100 || rand::random_range(0..100u8) < sample_rate)
+                {
+                    hashes.push(
+                        BlobHash::try_from_hash_slice(key.get(0..BLOB_HASH_LEN).ok_or_else(
+                            || trc::Error::corrupted_key(key, None, trc::location!()),
+                        )?)
+                        .unwrap(),
+                    );
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        status.total.store(hashes.len() as u64, Ordering::Relaxed);
+        status.checked.store(0, Ordering::Relaxed);
+
+        // Verify blob contents against their hashes
+        let mut report = BlobScrubReport {
+            started_at: now(),
+            ..Default::default()
+        };
+        for hash in hashes {
+            let is_missing = match blob_store
+                .get_blob(hash.as_slice(), 0..usize::MAX)
+                .await
+                .caused_by(trc::location!())?
+            {
+                Some(data) if BlobHash::generate(&data) == hash => {
+                    report.checked += 1;
+                    status.checked.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                Some(_) => false,
+                None => true,
+            };
+
+            // Attempt to restore the blob from the replica
+            let mut is_repaired = false;
+            if let Some(replica) = replica
+                && let Some(data) = replica
+                    .get_blob(hash.as_slice(), 0..usize::MAX)
+                    .await
+                    .caused_by(trc::location!())?
+                && BlobHash::generate(&data) == hash
+            {
+                blob_store
+                    .put_blob(hash.as_slice(), &data)
+                    .await
+                    .caused_by(trc::location!())?;
+                is_repaired = true;
+            }
+
+            trc::event!(
+                Store(if is_missing {
+                    trc::StoreEvent::BlobMissing
+                } else {
+                    trc::StoreEvent::BlobCorrupted
+                }),
+                BlobId = hash.as_slice(),
+                Result = is_repaired,
+            );
+
+            if is_missing {
+                report.missing.push(hash.clone());
+            } else {
+                report.corrupted.push(hash.clone());
+            }
+            if is_repaired {
+                report.repaired.push(hash);
+            }
+            report.checked += 1;
+            status.checked.fetch_add(1, Ordering::Relaxed);
+        }
+        report.finished_at = now();
+
+        Ok(report)
+    }
+
     pub async fn blob_hash_unlink_account(&self, account_id: u32) -> trc::Result<()> {
         // Validate linked blobs
         let from_key = ValueKey {
@@ -325,6 +325,22 @@ impl ValueClass {
                             .write(*due)
                     }
                 }
+                TaskQueueClass::IndexGroupware { due, collection } => serializer
+                    .write(*due)
+                    .write(account_id)
+                    .write(6u8)
+                    .write(document_id)
+                    .write(*collection),
+                TaskQueueClass::Job { id } => serializer
+                    .write(u64::MAX)
+                    .write(u32::MAX)
+                    .write(7u8)
+                    .write(*id),
+                TaskQueueClass::JobLock { id } => serializer
+                    .write(u64::MAX)
+                    .write(u32::MAX)
+                    .write(8u8)
+                    .write(*id),
             },
             ValueClass::Blob(op) => match op {
                 BlobOp::Reserve { hash, until } => serializer
@@ -599,6 +615,10 @@ impl ValueClass {
                     (BLOB_HASH_LEN + U64_LEN * 2) + 1
                 }
                 TaskQueueClass::SendAlarm { .. } => U64_LEN + (U32_LEN * 3) + 1,
+                TaskQueueClass::IndexGroupware { .. } => U64_LEN + (U32_LEN * 2) + 2,
+                TaskQueueClass::Job { .. } | TaskQueueClass::JobLock { .. } => {
+                    (U64_LEN * 2) + U32_LEN + 1
+                }
                 TaskQueueClass::SendImip { is_payload, .. } => {
                     if *is_payload {
                         (U64_LEN * 2) + (U32_LEN * 2) + 1
@@ -218,6 +218,16 @@ pub enum TaskQueueClass {
         due: u64,
         is_payload: bool,
     },
+    IndexGroupware {
+        due: u64,
+        collection: u8,
+    },
+    Job {
+        id: u64,
+    },
+    JobLock {
+        id: u64,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
@@ -42,6 +42,7 @@ impl EventType {
             EventType::TaskQueue(event) => event.description(),
             EventType::Milter(event) => event.description(),
             EventType::MtaHook(event) => event.description(),
+            EventType::Icap(event) => event.description(),
             EventType::Delivery(event) => event.description(),
             EventType::Queue(event) => event.description(),
             EventType::TlsRpt(event) => event.description(),
@@ -92,6 +93,7 @@ impl EventType {
             EventType::TaskQueue(event) => event.explain(),
             EventType::Milter(event) => event.explain(),
             EventType::MtaHook(event) => event.explain(),
+            EventType::Icap(event) => event.explain(),
             EventType::Delivery(event) => event.explain(),
             EventType::Queue(event) => event.explain(),
             EventType::TlsRpt(event) => event.explain(),
@@ -195,6 +197,10 @@ impl TaskQueueEvent {
             TaskQueueEvent::TaskLocked => "Task is locked by another process",
             TaskQueueEvent::BlobNotFound => "Blob not found for task",
             TaskQueueEvent::MetadataNotFound => "Metadata not found for task",
+            TaskQueueEvent::JobStarted => "Background job started",
+            TaskQueueEvent::JobCompleted => "Background job completed",
+            TaskQueueEvent::JobFailed => "Background job failed",
+            TaskQueueEvent::JobCancelled => "Background job cancelled",
         }
     }
 
@@ -204,6 +210,16 @@ impl TaskQueueEvent {
             TaskQueueEvent::TaskLocked => "The task id is locked by another process",
             TaskQueueEvent::BlobNotFound => "The requested blob was not found for task",
             TaskQueueEvent::MetadataNotFound => "The metadata was not found for task",
+            TaskQueueEvent::JobStarted => "A background job has been leased and started running",
+            TaskQueueEvent::JobCompleted => {
+                "A background job has finished processing all its items"
+            }
+            TaskQueueEvent::JobFailed => {
+                "A background job has failed and will be retried if attempts remain"
+            }
+            TaskQueueEvent::JobCancelled => {
+                "A background job has been cancelled by an administrator"
+            }
         }
     }
 }
@@ -248,6 +264,8 @@ impl ImapEvent {
             ImapEvent::ConnectionStart => "IMAP connection started",
             ImapEvent::ConnectionEnd => "IMAP connection ended",
             ImapEvent::GetQuota => "IMAP GETQUOTA command",
+            ImapEvent::GetMetadata => "IMAP GETMETADATA command",
+            ImapEvent::SetMetadata => "IMAP SETMETADATA command",
         }
     }
 
@@ -290,6 +308,8 @@ impl ImapEvent {
             ImapEvent::ConnectionStart => "IMAP connection started",
             ImapEvent::ConnectionEnd => "IMAP connection ended",
             ImapEvent::GetQuota => "Client requested mailbox quota",
+            ImapEvent::GetMetadata => "Client requested mailbox or server annotations",
+            ImapEvent::SetMetadata => "Client modified mailbox or server annotations",
         }
     }
 }
@@ -404,9 +424,13 @@ impl SmtpEvent {
             SmtpEvent::MissingAuthDirectory => "Missing auth directory",
             SmtpEvent::MessageParseFailed => "Message parsing failed",
             SmtpEvent::MessageTooLarge => "Message too large",
+            SmtpEvent::MessageOverQuota => "Message exceeds recipient quota",
             SmtpEvent::LoopDetected => "Mail loop detected",
+            SmtpEvent::MessageTraceEnabled => "Message tracing enabled",
             SmtpEvent::DkimPass => "DKIM verification passed",
             SmtpEvent::DkimFail => "DKIM verification failed",
+            SmtpEvent::DkimUnsignedBody => "DKIM signature does not cover the entire body",
+            SmtpEvent::DkimUnsignedHeader => "DKIM signature does not cover duplicate header",
             SmtpEvent::ArcPass => "ARC verification passed",
             SmtpEvent::ArcFail => "ARC verification failed",
             SmtpEvent::SpfEhloPass => "SPF EHLO check passed",
@@ -415,11 +439,15 @@ impl SmtpEvent {
             SmtpEvent::SpfFromFail => "SPF From check failed",
             SmtpEvent::DmarcPass => "DMARC check passed",
             SmtpEvent::DmarcFail => "DMARC check failed",
+            SmtpEvent::SpfOverride => "SPF result overridden",
+            SmtpEvent::DkimOverride => "DKIM result overridden",
+            SmtpEvent::DmarcOverride => "DMARC result overridden",
             SmtpEvent::IprevPass => "IPREV check passed",
             SmtpEvent::IprevFail => "IPREV check failed",
             SmtpEvent::TooManyMessages => "Too many messages",
             SmtpEvent::Ehlo => "SMTP EHLO command",
             SmtpEvent::InvalidEhlo => "Invalid EHLO command",
+            SmtpEvent::EhloValidation => "EHLO domain validated",
             SmtpEvent::DidNotSayEhlo => "Client did not say EHLO",
             SmtpEvent::EhloExpected => "EHLO command expected",
             SmtpEvent::LhloExpected => "LHLO command expected",
@@ -428,6 +456,8 @@ impl SmtpEvent {
             SmtpEvent::MailFromRewritten => "MAIL FROM address rewritten",
             SmtpEvent::MailFromMissing => "MAIL FROM address missing",
             SmtpEvent::MailFromNotAllowed => "MAIL FROM not allowed",
+            SmtpEvent::MailFromSuspended => "MAIL FROM suspended due to abuse complaints",
+            SmtpEvent::MailFromNonSendingDomain => "MAIL FROM domain does not send mail",
             SmtpEvent::MailFrom => "SMTP MAIL FROM command",
             SmtpEvent::MultipleMailFrom => "Multiple MAIL FROM commands",
             SmtpEvent::MailboxDoesNotExist => "Mailbox does not exist",
@@ -437,6 +467,8 @@ impl SmtpEvent {
             SmtpEvent::RcptToRewritten => "RCPT TO address rewritten",
             SmtpEvent::RcptToMissing => "RCPT TO address missing",
             SmtpEvent::RcptToGreylisted => "RCPT TO greylisted",
+            SmtpEvent::RcptToSuppressed => "RCPT TO suppressed",
+            SmtpEvent::RcptToLoopDetected => "RCPT TO expansion loop detected",
             SmtpEvent::TooManyRecipients => "Too many recipients",
             SmtpEvent::TooManyInvalidRcpt => "Too many invalid recipients",
             SmtpEvent::RawInput => "Raw SMTP input received",
@@ -449,10 +481,12 @@ impl SmtpEvent {
             SmtpEvent::ExpnNotFound => "EXPN address not found",
             SmtpEvent::ExpnDisabled => "EXPN command disabled",
             SmtpEvent::RequireTlsDisabled => "REQUIRETLS extension disabled",
+            SmtpEvent::RequireTlsUnencrypted => "REQUIRETLS requested over plaintext",
             SmtpEvent::DeliverByDisabled => "DELIVERBY extension disabled",
             SmtpEvent::DeliverByInvalid => "Invalid DELIVERBY parameter",
             SmtpEvent::FutureReleaseDisabled => "FUTURE RELEASE extension disabled",
             SmtpEvent::FutureReleaseInvalid => "Invalid FUTURE RELEASE parameter",
+            SmtpEvent::HeadersAnonymized => "Client identifying headers removed",
             SmtpEvent::MtPriorityDisabled => "MT-PRIORITY extension disabled",
             SmtpEvent::MtPriorityInvalid => "Invalid MT-PRIORITY parameter",
             SmtpEvent::DsnDisabled => "DSN extension disabled",
@@ -475,6 +509,10 @@ impl SmtpEvent {
             SmtpEvent::UnsupportedParameter => "Unsupported parameter",
             SmtpEvent::SyntaxError => "Syntax error",
             SmtpEvent::RequestTooLarge => "Request too large",
+            SmtpEvent::TranscriptStored => "SMTP session transcript stored",
+            SmtpEvent::RcptToDeferred => "RCPT TO deferred",
+            SmtpEvent::DirectoryUnavailable => "Recipient directory unavailable",
+            SmtpEvent::DirectoryRecovered => "Recipient directory recovered",
             SmtpEvent::ConnectionStart => "SMTP connection started",
             SmtpEvent::ConnectionEnd => "SMTP connection ended",
         }
@@ -493,11 +531,23 @@ impl SmtpEvent {
             SmtpEvent::MissingAuthDirectory => "The auth directory was missing",
             SmtpEvent::MessageParseFailed => "Failed to parse the message",
             SmtpEvent::MessageTooLarge => "The message was rejected because it was too large",
+            SmtpEvent::MessageOverQuota => {
+                "The message was not accepted because a local recipient is over quota"
+            }
             SmtpEvent::LoopDetected => {
                 "A mail loop was detected, the message contains too many Received headers"
             }
+            SmtpEvent::MessageTraceEnabled => {
+                "The submitter requested detailed tracing for the message"
+            }
             SmtpEvent::DkimPass => "Successful DKIM verification",
             SmtpEvent::DkimFail => "Failed to verify DKIM signature",
+            SmtpEvent::DkimUnsignedBody => {
+                "A passing DKIM signature uses a body length tag that leaves content unsigned"
+            }
+            SmtpEvent::DkimUnsignedHeader => {
+                "A critical header appears more times than it is covered by a passing DKIM signature"
+            }
             SmtpEvent::ArcPass => "Successful ARC verification",
             SmtpEvent::ArcFail => "Failed to verify ARC signature",
             SmtpEvent::SpfEhloPass => "EHLO identity passed SPF check",
@@ -506,6 +556,15 @@ impl SmtpEvent {
             SmtpEvent::SpfFromFail => "MAIL FROM identity failed SPF check",
             SmtpEvent::DmarcPass => "Successful DMARC verification",
             SmtpEvent::DmarcFail => "Failed to verify DMARC policy",
+            SmtpEvent::SpfOverride => {
+                "The outcome of a failed SPF check was changed by an override expression"
+            }
+            SmtpEvent::DkimOverride => {
+                "The outcome of a failed DKIM check was changed by an override expression"
+            }
+            SmtpEvent::DmarcOverride => {
+                "The outcome of a failed DMARC check was changed by an override expression"
+            }
             SmtpEvent::IprevPass => "Reverse IP check passed",
             SmtpEvent::IprevFail => "Reverse IP check failed",
             SmtpEvent::TooManyMessages => {
@@ -513,6 +572,9 @@ impl SmtpEvent {
             }
             SmtpEvent::Ehlo => "The remote server sent an EHLO command",
             SmtpEvent::InvalidEhlo => "The remote server sent an invalid EHLO command",
+            SmtpEvent::EhloValidation => {
+                "The EHLO domain was scored using syntax, address and reverse DNS checks"
+            }
             SmtpEvent::DidNotSayEhlo => "The remote server did not send EHLO command",
             SmtpEvent::EhloExpected => {
                 "The remote server sent a LHLO command while EHLO was expected"
@@ -533,6 +595,12 @@ impl SmtpEvent {
             SmtpEvent::MailFromNotAllowed => {
                 "The remote client is not allowed to send mail from this address"
             }
+            SmtpEvent::MailFromSuspended => {
+                "The sender exceeded the maximum number of abuse complaints allowed"
+            }
+            SmtpEvent::MailFromNonSendingDomain => {
+                "The sender domain publishes a null MX or an SPF policy that rejects all senders"
+            }
             SmtpEvent::MailFrom => "The remote client sent a MAIL FROM command",
             SmtpEvent::MultipleMailFrom => "The remote client already sent a MAIL FROM command",
             SmtpEvent::MailboxDoesNotExist => "The mailbox does not exist on the server",
@@ -544,6 +612,12 @@ impl SmtpEvent {
             SmtpEvent::RcptToRewritten => "The envelope recipient address was rewritten",
             SmtpEvent::RcptToMissing => "The remote client issued a DATA command before RCPT TO",
             SmtpEvent::RcptToGreylisted => "The recipient was greylisted",
+            SmtpEvent::RcptToSuppressed => {
+                "The recipient reported a previous message from this sender as abuse"
+            }
+            SmtpEvent::RcptToLoopDetected => {
+                "Expanding the recipient's mailing list led to a cycle or exceeded the maximum depth"
+            }
             SmtpEvent::TooManyRecipients => {
                 "The remote client exceeded the number of recipients allowed"
             }
@@ -564,10 +638,16 @@ impl SmtpEvent {
             }
             SmtpEvent::ExpnDisabled => "The EXPN command is disabled",
             SmtpEvent::RequireTlsDisabled => "The REQUIRETLS extension is disabled",
+            SmtpEvent::RequireTlsUnencrypted => {
+                "The client requested REQUIRETLS over an unencrypted connection"
+            }
             SmtpEvent::DeliverByDisabled => "The DELIVERBY extension is disabled",
             SmtpEvent::DeliverByInvalid => "The DELIVERBY parameter is invalid",
             SmtpEvent::FutureReleaseDisabled => "The FUTURE RELEASE extension is disabled",
             SmtpEvent::FutureReleaseInvalid => "The FUTURE RELEASE parameter is invalid",
+            SmtpEvent::HeadersAnonymized => {
+                "Client identifying headers were removed from an authenticated submission"
+            }
             SmtpEvent::MtPriorityDisabled => "The MT-PRIORITY extension is disabled",
             SmtpEvent::MtPriorityInvalid => "The MT-PRIORITY parameter is invalid",
             SmtpEvent::DsnDisabled => "The DSN extension is disabled",
@@ -595,6 +675,18 @@ impl SmtpEvent {
             SmtpEvent::UnsupportedParameter => "The command contained an unsupported parameter",
             SmtpEvent::SyntaxError => "The command contained a syntax error",
             SmtpEvent::RequestTooLarge => "The request was too large",
+            SmtpEvent::TranscriptStored => {
+                "The transcript of the SMTP session was stored in the blob store"
+            }
+            SmtpEvent::RcptToDeferred => {
+                "The recipient was temporarily rejected as its directory is unavailable"
+            }
+            SmtpEvent::DirectoryUnavailable => {
+                "A recipient directory failed repeatedly and will be health-checked before being used again"
+            }
+            SmtpEvent::DirectoryRecovered => {
+                "A recipient directory passed its health check and is used again"
+            }
             SmtpEvent::ConnectionStart => "A new SMTP connection was started",
             SmtpEvent::ConnectionEnd => "The SMTP connection was ended",
             SmtpEvent::StartTlsAlready => "TLS is already active",
@@ -634,6 +726,8 @@ impl DeliveryEvent {
             DeliveryEvent::StartTlsUnavailable => "STARTTLS unavailable",
             DeliveryEvent::StartTlsError => "STARTTLS error",
             DeliveryEvent::StartTlsDisabled => "STARTTLS disabled",
+            DeliveryEvent::RequireTlsUnavailable => "REQUIRETLS unavailable",
+            DeliveryEvent::RequireTlsMxUnverified => "REQUIRETLS MX unverified",
             DeliveryEvent::ImplicitTlsError => "Implicit TLS error",
             DeliveryEvent::ConcurrencyLimitExceeded => "Concurrency limit exceeded",
             DeliveryEvent::RateLimitExceeded => "Rate limit exceeded",
@@ -641,6 +735,14 @@ impl DeliveryEvent {
             DeliveryEvent::DsnSuccess => "DSN success notification",
             DeliveryEvent::DsnTempFail => "DSN temporary failure notification",
             DeliveryEvent::DsnPermFail => "DSN permanent failure notification",
+            DeliveryEvent::Downgraded => "Message downgraded for legacy server",
+            DeliveryEvent::DowngradeFailed => "Message downgrade failed",
+            DeliveryEvent::BounceBadAddress => "Delivery failed due to a bad address",
+            DeliveryEvent::BounceMailboxFull => "Delivery failed due to a full mailbox",
+            DeliveryEvent::BounceReputationBlock => "Delivery blocked due to sender reputation",
+            DeliveryEvent::BouncePolicy => "Delivery rejected by policy",
+            DeliveryEvent::BounceTransientInfra => "Delivery failed due to a transient error",
+            DeliveryEvent::PipeFailed => "Pipe delivery failed",
             DeliveryEvent::RawInput => "Raw SMTP input received",
             DeliveryEvent::RawOutput => "Raw SMTP output sent",
         }
@@ -685,6 +787,10 @@ impl DeliveryEvent {
             DeliveryEvent::StartTlsDisabled => {
                 "STARTTLS has been disabled in the configuration for this host"
             }
+            DeliveryEvent::RequireTlsUnavailable => "The remote server does not support REQUIRETLS",
+            DeliveryEvent::RequireTlsMxUnverified => {
+                "The MX records are neither DNSSEC-signed nor covered by an MTA-STS policy"
+            }
             DeliveryEvent::ImplicitTlsError => "Error starting implicit TLS",
             DeliveryEvent::ConcurrencyLimitExceeded => {
                 "The concurrency limit was exceeded for the remote host"
@@ -698,6 +804,28 @@ impl DeliveryEvent {
             DeliveryEvent::DsnPermFail => {
                 "A permanent failure delivery status notification was created"
             }
+            DeliveryEvent::Downgraded => {
+                "The message was converted to 7-bit or ASCII addresses for a server lacking 8BITMIME or SMTPUTF8 support"
+            }
+            DeliveryEvent::DowngradeFailed => {
+                "The message requires SMTPUTF8 but the remote server does not support it"
+            }
+            DeliveryEvent::BounceBadAddress => {
+                "The recipient address does not exist or is no longer valid"
+            }
+            DeliveryEvent::BounceMailboxFull => "The recipient mailbox is full or over quota",
+            DeliveryEvent::BounceReputationBlock => {
+                "The remote server blocked the message due to the sender's IP or domain reputation"
+            }
+            DeliveryEvent::BouncePolicy => {
+                "The remote server rejected the message due to a content or security policy"
+            }
+            DeliveryEvent::BounceTransientInfra => {
+                "The delivery failed due to a network, DNS or remote server error that may resolve itself"
+            }
+            DeliveryEvent::PipeFailed => {
+                "The delivery command could not be executed or exited with an error status"
+            }
             DeliveryEvent::RawInput => "Raw SMTP input received",
             DeliveryEvent::RawOutput => "Raw SMTP output sent",
         }
@@ -713,12 +841,16 @@ impl QueueEvent {
             QueueEvent::RateLimitExceeded => "Rate limit exceeded",
             QueueEvent::ConcurrencyLimitExceeded => "Concurrency limit exceeded",
             QueueEvent::QuotaExceeded => "Quota exceeded",
+            QueueEvent::SendingQuotaExceeded => "Sending quota exceeded",
+            QueueEvent::SendingQuotaWarning => "Sending quota nearly exhausted",
             QueueEvent::QueueMessage => "Queued message for delivery",
             QueueEvent::QueueMessageAuthenticated => "Queued message submission for delivery",
             QueueEvent::QueueReport => "Queued report for delivery",
             QueueEvent::QueueDsn => "Queued DSN for delivery",
             QueueEvent::QueueAutogenerated => "Queued autogenerated message for delivery",
+            QueueEvent::QueueShadowCopy => "Queued shadow copy of message",
             QueueEvent::BackPressure => "Queue backpressure detected",
+            QueueEvent::WorkersScaled => "Queue delivery workers rescaled",
         }
     }
 
@@ -730,6 +862,12 @@ impl QueueEvent {
             QueueEvent::RateLimitExceeded => "The queue rate limit was exceeded",
             QueueEvent::ConcurrencyLimitExceeded => "The queue concurrency limit was exceeded",
             QueueEvent::QuotaExceeded => "The queue quota was exceeded",
+            QueueEvent::SendingQuotaExceeded => {
+                "The hourly or daily outbound sending quota was exceeded"
+            }
+            QueueEvent::SendingQuotaWarning => {
+                "The outbound sending quota usage reached the notification threshold"
+            }
             QueueEvent::QueueMessage => "A new message was queued for delivery",
             QueueEvent::QueueMessageAuthenticated => {
                 "A new message was queued for delivery from an authenticated client"
@@ -737,9 +875,15 @@ impl QueueEvent {
             QueueEvent::QueueReport => "A new report was queued for delivery",
             QueueEvent::QueueDsn => "A delivery status notification was queued for delivery",
             QueueEvent::QueueAutogenerated => "A system generated message was queued for delivery",
+            QueueEvent::QueueShadowCopy => {
+                "A copy of an inbound message was queued for shadow delivery"
+            }
             QueueEvent::BackPressure => {
                 "Queue congested, processing can't keep up with incoming message rate"
             }
+            QueueEvent::WorkersScaled => {
+                "The number of concurrent delivery workers was adjusted to the queue depth"
+            }
         }
     }
 }
@@ -752,6 +896,8 @@ impl IncomingReportEvent {
             IncomingReportEvent::TlsReport => "TLS report received",
             IncomingReportEvent::TlsReportWithWarnings => "TLS report received with warnings",
             IncomingReportEvent::AbuseReport => "Abuse report received",
+            IncomingReportEvent::AbuseComplaint => "Abuse complaint recorded",
+            IncomingReportEvent::AbuseComplaintIgnored => "Abuse complaint ignored",
             IncomingReportEvent::AuthFailureReport => "Authentication failure report received",
             IncomingReportEvent::FraudReport => "Fraud report received",
             IncomingReportEvent::NotSpamReport => "Not spam report received",
@@ -776,6 +922,12 @@ impl IncomingReportEvent {
                 "A TLS report with warnings has been received"
             }
             IncomingReportEvent::AbuseReport => "An abuse report has been received",
+            IncomingReportEvent::AbuseComplaint => {
+                "An abuse complaint against a local sender has been recorded"
+            }
+            IncomingReportEvent::AbuseComplaintIgnored => {
+                "An abuse complaint was ignored as it does not originate from an authenticated trusted feedback loop"
+            }
             IncomingReportEvent::AuthFailureReport => {
                 "An authentication failure report has been received"
             }
@@ -800,6 +952,7 @@ impl OutgoingReportEvent {
             OutgoingReportEvent::SpfReport => "SPF report sent",
             OutgoingReportEvent::SpfRateLimited => "SPF report rate limited",
             OutgoingReportEvent::DkimReport => "DKIM report sent",
+            OutgoingReportEvent::AbuseReport => "Abuse report sent",
             OutgoingReportEvent::DkimRateLimited => "DKIM report rate limited",
             OutgoingReportEvent::DmarcReport => "DMARC report sent",
             OutgoingReportEvent::DmarcRateLimited => "DMARC report rate limited",
@@ -822,6 +975,7 @@ impl OutgoingReportEvent {
             OutgoingReportEvent::SpfReport => "An SPF report has been sent",
             OutgoingReportEvent::SpfRateLimited => "The SPF report was rate limited",
             OutgoingReportEvent::DkimReport => "A DKIM report has been sent",
+            OutgoingReportEvent::AbuseReport => "An abuse report has been sent",
             OutgoingReportEvent::DkimRateLimited => "The DKIM report was rate limited",
             OutgoingReportEvent::DmarcReport => "A DMARC report has been sent",
             OutgoingReportEvent::DmarcRateLimited => "The DMARC report was rate limited",
@@ -986,6 +1140,34 @@ impl MtaHookEvent {
     }
 }
 
+impl IcapEvent {
+    pub fn description(&self) -> &'static str {
+        match self {
+            IcapEvent::Clean => "ICAP scan: Clean",
+            IcapEvent::ThreatFound => "ICAP scan: Threat found",
+            IcapEvent::ContentModified => "ICAP scan: Content modified",
+            IcapEvent::Skipped => "ICAP scan skipped",
+            IcapEvent::Error => "ICAP error",
+            IcapEvent::UploadRejected => "ICAP scan: Upload rejected",
+            IcapEvent::UploadQuarantined => "ICAP scan: Upload quarantined",
+        }
+    }
+
+    pub fn explain(&self) -> &'static str {
+        match self {
+            IcapEvent::Clean => "The ICAP server did not find any threats in the message",
+            IcapEvent::ThreatFound => "The ICAP server reported a threat in the message",
+            IcapEvent::ContentModified => "The ICAP server modified or blocked the message",
+            IcapEvent::Skipped => "The message exceeds the maximum size for ICAP scanning",
+            IcapEvent::Error => "An error occurred while communicating with the ICAP server",
+            IcapEvent::UploadRejected => "An uploaded file was rejected by the ICAP server",
+            IcapEvent::UploadQuarantined => {
+                "An uploaded file was rejected and stored in quarantine for review"
+            }
+        }
+    }
+}
+
 impl PushSubscriptionEvent {
     pub fn description(&self) -> &'static str {
         match self {
@@ -1009,6 +1191,11 @@ impl SpamEvent {
         match self {
             SpamEvent::Pyzor => "Pyzor success",
             SpamEvent::PyzorError => "Pyzor error",
+            SpamEvent::Fuzzy => "Fuzzy checksum lookup",
+            SpamEvent::FuzzyLearn => "Fuzzy checksum learned",
+            SpamEvent::FuzzyError => "Fuzzy checksum error",
+            SpamEvent::SenderReputation => "Sender reputation lookup",
+            SpamEvent::SenderReputationLearn => "Sender reputation updated",
             SpamEvent::Train => "Training spam filter",
             SpamEvent::TrainBalance => "Spam filter model balance verify",
             SpamEvent::TrainError => "Error training spam filter",
@@ -1029,6 +1216,15 @@ impl SpamEvent {
             SpamEvent::Classify => "The message is being classified for spam",
             SpamEvent::ClassifyError => "There is not enough training data for the spam filter",
             SpamEvent::Pyzor => "Pyzor query successful",
+            SpamEvent::Fuzzy => "The message fuzzy checksum was compared against known spam",
+            SpamEvent::FuzzyLearn => "The message fuzzy checksum was added to the spam database",
+            SpamEvent::FuzzyError => "An error occurred while querying the fuzzy checksum service",
+            SpamEvent::SenderReputation => {
+                "The reputation of the authenticated sender domain was looked up"
+            }
+            SpamEvent::SenderReputationLearn => {
+                "The reputation of the authenticated sender domain was updated from user feedback"
+            }
             SpamEvent::Dnsbl => "The DNSBL query was successful",
             SpamEvent::DnsblError => "An error occurred while querying the DNSBL",
             SpamEvent::TrainAccount => "The spam filter has been trained for the account",
@@ -1083,6 +1279,7 @@ impl TlsEvent {
             TlsEvent::CertificateNotFound => "TLS certificate not found",
             TlsEvent::NoCertificatesAvailable => "No TLS certificates available",
             TlsEvent::MultipleCertificatesAvailable => "Multiple TLS certificates available",
+            TlsEvent::ClientFingerprint => "TLS client fingerprint",
         }
     }
 
@@ -1094,6 +1291,7 @@ impl TlsEvent {
             TlsEvent::CertificateNotFound => "The TLS certificate was not found",
             TlsEvent::NoCertificatesAvailable => "No TLS certificates are available",
             TlsEvent::MultipleCertificatesAvailable => "Multiple TLS certificates are available",
+            TlsEvent::ClientFingerprint => "Computed the JA3 and JA4 fingerprints of a TLS client",
         }
     }
 }
@@ -1113,6 +1311,7 @@ impl NetworkEvent {
             NetworkEvent::Timeout => "Network timeout",
             NetworkEvent::Closed => "Network connection closed",
             NetworkEvent::ProxyError => "Proxy protocol error",
+            NetworkEvent::ProxyInfo => "Proxy protocol information received",
             NetworkEvent::SetOptError => "Network set option error",
         }
     }
@@ -1131,6 +1330,9 @@ impl NetworkEvent {
             NetworkEvent::Timeout => "A network timeout occurred",
             NetworkEvent::Closed => "The network connection was closed",
             NetworkEvent::ProxyError => "An error occurred with the proxy protocol",
+            NetworkEvent::ProxyInfo => {
+                "The edge proxy sent TLVs describing the original connection"
+            }
             NetworkEvent::SetOptError => "An error occurred while setting network options",
         }
     }
@@ -1148,6 +1350,13 @@ impl ServerEvent {
             ServerEvent::StartupError => "Server startup error",
             ServerEvent::ThreadError => "Server thread error",
             ServerEvent::Licensing => "Server licensing event",
+            ServerEvent::IpcEventDropped => "Internal event dropped",
+            ServerEvent::IpcEventBuffered => "Internal event moved to overflow buffer",
+            ServerEvent::Degraded => "Server running in degraded mode",
+            ServerEvent::Recovered => "Server recovered from degraded mode",
+            ServerEvent::MigrationPlan => "Database migration plan",
+            ServerEvent::MigrationPending => "Database migration pending",
+            ServerEvent::MigrationCompleted => "Database migration previously completed",
         }
     }
 
@@ -1158,6 +1367,25 @@ impl ServerEvent {
             ServerEvent::StartupError => "An error occurred while starting the server",
             ServerEvent::ThreadError => "An error occurred with a server thread",
             ServerEvent::Licensing => "A licensing event occurred",
+            ServerEvent::IpcEventDropped => {
+                "An internal channel was full and the event was dropped according to its overflow policy"
+            }
+            ServerEvent::IpcEventBuffered => {
+                "An internal channel was full and the event was moved to its in-memory overflow buffer"
+            }
+            ServerEvent::Degraded => {
+                "A non-critical backend is unavailable and a fallback is used until it recovers"
+            }
+            ServerEvent::Recovered => {
+                "All backends are available again and the fallbacks were removed"
+            }
+            ServerEvent::MigrationPlan => {
+                "The current and target database schema versions reported by a migration dry run"
+            }
+            ServerEvent::MigrationPending => {
+                "A database migration that will be applied on the next startup"
+            }
+            ServerEvent::MigrationCompleted => "A database migration that was applied in the past",
         }
     }
 }
@@ -1218,6 +1446,10 @@ impl AcmeEvent {
             AcmeEvent::DnsRecordLookupFailed => "ACME DNS record lookup failed",
             AcmeEvent::DnsRecordPropagated => "ACME DNS record propagated",
             AcmeEvent::DnsRecordPropagationTimeout => "ACME DNS record propagation timeout",
+            AcmeEvent::HttpTokenPushed => "ACME HTTP token pushed",
+            AcmeEvent::HttpTokenPushFailed => "ACME HTTP token push failed",
+            AcmeEvent::HttpTokenDeletionFailed => "ACME HTTP token deletion failed",
+            AcmeEvent::HttpTokenCheckFailed => "ACME HTTP token check failed",
             AcmeEvent::ClientSuppliedSni => "ACME client supplied SNI",
             AcmeEvent::ClientMissingSni => "ACME client missing SNI",
             AcmeEvent::TlsAlpnReceived => "ACME TLS ALPN received",
@@ -1250,6 +1482,18 @@ impl AcmeEvent {
             AcmeEvent::DnsRecordLookupFailed => "Failed to look up ACME DNS record",
             AcmeEvent::DnsRecordPropagated => "ACME DNS record has propagated",
             AcmeEvent::DnsRecordPropagationTimeout => "ACME DNS record propagation timeout",
+            AcmeEvent::HttpTokenPushed => {
+                "ACME HTTP-01 token has been uploaded to the delegated web server"
+            }
+            AcmeEvent::HttpTokenPushFailed => {
+                "Failed to upload ACME HTTP-01 token to the delegated web server"
+            }
+            AcmeEvent::HttpTokenDeletionFailed => {
+                "Failed to delete ACME HTTP-01 token from the delegated web server"
+            }
+            AcmeEvent::HttpTokenCheckFailed => {
+                "ACME HTTP-01 token is not reachable through the domain being validated"
+            }
             AcmeEvent::ClientSuppliedSni => "ACME client supplied SNI",
             AcmeEvent::ClientMissingSni => "ACME client missing SNI",
             AcmeEvent::TlsAlpnReceived => "ACME TLS ALPN received",
@@ -1528,10 +1772,12 @@ impl StoreEvent {
             StoreEvent::RocksdbError => "RocksDB error",
             StoreEvent::SqliteError => "SQLite error",
             StoreEvent::LdapError => "LDAP error",
+            StoreEvent::RestError => "REST directory error",
             StoreEvent::ElasticsearchError => "ElasticSearch error",
             StoreEvent::RedisError => "Redis error",
             StoreEvent::S3Error => "S3 error",
             StoreEvent::AzureError => "Azure error",
+            StoreEvent::GcsError => "Google Cloud Storage error",
             StoreEvent::FilesystemError => "Filesystem error",
             StoreEvent::PoolError => "Connection pool error",
             StoreEvent::DataCorruption => "Data corruption detected",
@@ -1545,6 +1791,7 @@ impl StoreEvent {
             StoreEvent::BlobMissingMarker => "Blob missing marker",
             StoreEvent::SqlQuery => "SQL query executed",
             StoreEvent::LdapQuery => "LDAP query executed",
+            StoreEvent::RestQuery => "REST directory query executed",
             StoreEvent::LdapWarning => "LDAP authentication warning",
             StoreEvent::DataWrite => "Write batch operation",
             StoreEvent::BlobRead => "Blob read operation",
@@ -1557,6 +1804,10 @@ impl StoreEvent {
             StoreEvent::CacheHit => "Cache hit",
             StoreEvent::CacheStale => "Cache is stale",
             StoreEvent::CacheUpdate => "Cache update",
+            StoreEvent::S3Request => "S3 request",
+            StoreEvent::BlobCorrupted => "Blob contents do not match hash",
+            StoreEvent::BlobMissing => "Blob not found in store",
+            StoreEvent::S3Retry => "S3 request retry",
         }
     }
 
@@ -1569,10 +1820,12 @@ impl StoreEvent {
             StoreEvent::RocksdbError => "A RocksDB error occurred",
             StoreEvent::SqliteError => "An SQLite error occurred",
             StoreEvent::LdapError => "An LDAP error occurred",
+            StoreEvent::RestError => "An error occurred while querying a REST directory",
             StoreEvent::ElasticsearchError => "An ElasticSearch error occurred",
             StoreEvent::RedisError => "A Redis error occurred",
             StoreEvent::S3Error => "An S3 error occurred",
             StoreEvent::AzureError => "An Azure error occurred",
+            StoreEvent::GcsError => "A Google Cloud Storage error occurred",
             StoreEvent::FilesystemError => "A filesystem error occurred",
             StoreEvent::PoolError => "A connection pool error occurred",
             StoreEvent::DataCorruption => "Data corruption was detected",
@@ -1586,6 +1839,7 @@ impl StoreEvent {
             StoreEvent::BlobMissingMarker => "The blob is missing a marker",
             StoreEvent::SqlQuery => "An SQL query was executed",
             StoreEvent::LdapQuery => "An LDAP query was executed",
+            StoreEvent::RestQuery => "A REST directory query was executed",
             StoreEvent::LdapWarning => "An LDAP authentication warning occurred",
             StoreEvent::DataWrite => "A write batch operation was executed",
             StoreEvent::BlobRead => "A blob read operation was executed",
@@ -1598,6 +1852,10 @@ impl StoreEvent {
             StoreEvent::CacheHit => "Cache entry found for the account, no update needed",
             StoreEvent::CacheStale => "Cache is too old, rebuilding",
             StoreEvent::CacheUpdate => "Cache updated with latest database changes",
+            StoreEvent::S3Request => "An S3 request was executed",
+            StoreEvent::BlobCorrupted => "A blob failed integrity verification",
+            StoreEvent::BlobMissing => "A committed blob is missing from the blob store",
+            StoreEvent::S3Retry => "An S3 request failed and will be retried",
         }
     }
 }
@@ -1612,6 +1870,15 @@ impl MessageIngestEvent {
             MessageIngestEvent::Duplicate => "Skipping duplicate message",
             MessageIngestEvent::Error => "Message ingestion error",
             MessageIngestEvent::FtsIndex => "Full-text search index updated",
+            MessageIngestEvent::MimePartsExceeded => "Too many MIME parts",
+            MessageIngestEvent::MimeDepthExceeded => "MIME nesting too deep",
+            MessageIngestEvent::MimeHeadersExceeded => "Too many headers",
+            MessageIngestEvent::MimeExpansionExceeded => "Decoded message too large",
+            MessageIngestEvent::MailboxOverflow => "Message redirected to overflow folder",
+            MessageIngestEvent::MailboxExpire => "Oldest messages expired from folder",
+            MessageIngestEvent::AttachmentsConverted => "Legacy attachments converted",
+            MessageIngestEvent::AttachmentsStripped => "Attachments stripped",
+            MessageIngestEvent::ForwardLoop => "Forwarding loop detected",
         }
     }
 
@@ -1624,6 +1891,33 @@ impl MessageIngestEvent {
             MessageIngestEvent::Duplicate => "The message is a duplicate and has been skipped",
             MessageIngestEvent::Error => "An error occurred while ingesting the message",
             MessageIngestEvent::FtsIndex => "The full-text search index has been updated",
+            MessageIngestEvent::MimePartsExceeded => {
+                "The message contains more MIME parts than allowed"
+            }
+            MessageIngestEvent::MimeDepthExceeded => {
+                "The message contains MIME parts nested deeper than allowed"
+            }
+            MessageIngestEvent::MimeHeadersExceeded => {
+                "A MIME part of the message contains more headers than allowed"
+            }
+            MessageIngestEvent::MimeExpansionExceeded => {
+                "The decoded size of the message exceeds the allowed expansion ratio"
+            }
+            MessageIngestEvent::MailboxOverflow => {
+                "The destination folder is full and the message was filed into its overflow folder"
+            }
+            MessageIngestEvent::MailboxExpire => {
+                "The destination folder is full and its oldest messages were removed"
+            }
+            MessageIngestEvent::AttachmentsConverted => {
+                "TNEF or uuencoded contents were converted into MIME attachments"
+            }
+            MessageIngestEvent::AttachmentsStripped => {
+                "Large attachments were replaced with stub references to reclaim storage"
+            }
+            MessageIngestEvent::ForwardLoop => {
+                "The message was already delivered to this recipient and was forwarded back to it"
+            }
         }
     }
 }
@@ -1695,6 +1989,8 @@ impl LimitEvent {
             LimitEvent::ConcurrentRequest => "Concurrent request limit reached",
             LimitEvent::ConcurrentUpload => "Concurrent upload limit reached",
             LimitEvent::ConcurrentConnection => "Concurrent connection limit reached",
+            LimitEvent::ConcurrentMessage => "Concurrent message limit reached",
+            LimitEvent::MessageBuffer => "Message buffer limit reached",
             LimitEvent::Quota => "Quota limit reached",
             LimitEvent::BlobQuota => "Blob quota limit reached",
             LimitEvent::TooManyRequests => "Too many requests",
@@ -1710,6 +2006,12 @@ impl LimitEvent {
             LimitEvent::ConcurrentRequest => "The concurrent request limit has been reached",
             LimitEvent::ConcurrentUpload => "The concurrent upload limit has been reached",
             LimitEvent::ConcurrentConnection => "The concurrent connection limit has been reached",
+            LimitEvent::ConcurrentMessage => {
+                "The limit of messages being received concurrently has been reached"
+            }
+            LimitEvent::MessageBuffer => {
+                "The memory limit for buffering incoming messages has been reached"
+            }
             LimitEvent::Quota => "The quota limit has been reached",
             LimitEvent::BlobQuota => "The blob quota limit has been reached",
             LimitEvent::TooManyRequests => "Too many requests have been made",
@@ -1752,6 +2054,13 @@ impl AuthEvent {
             AuthEvent::Error => "Authentication error",
             AuthEvent::TokenExpired => "OAuth token expired",
             AuthEvent::ClientRegistration => "OAuth Client registration",
+            AuthEvent::ClientRegistrationPending => "OAuth client registration pending review",
+            AuthEvent::ClientRegistrationRejected => "OAuth client registration rejected",
+            AuthEvent::ClientRegistrationApproved => "OAuth client registration approved",
+            AuthEvent::SecretRehashed => "Password hash upgraded",
+            AuthEvent::PasswordExpired => "Password expired",
+            AuthEvent::PasswordPolicyViolation => "Password policy violation",
+            AuthEvent::SessionRevoked => "Session revoked",
         }
     }
 
@@ -1764,6 +2073,25 @@ impl AuthEvent {
             AuthEvent::Error => "An error occurred with authentication",
             AuthEvent::TokenExpired => "OAuth authentication token has expired",
             AuthEvent::ClientRegistration => "OAuth client successfully registered",
+            AuthEvent::ClientRegistrationPending => {
+                "OAuth client registration was queued for administrator review"
+            }
+            AuthEvent::ClientRegistrationRejected => {
+                "OAuth client registration did not match any auto-approval rule"
+            }
+            AuthEvent::ClientRegistrationApproved => {
+                "OAuth client registration was approved by an administrator"
+            }
+            AuthEvent::PasswordExpired => "The password is older than the maximum allowed age",
+            AuthEvent::PasswordPolicyViolation => {
+                "The new password does not meet the password policy requirements"
+            }
+            AuthEvent::SessionRevoked => {
+                "A login session was revoked and its refresh tokens invalidated"
+            }
+            AuthEvent::SecretRehashed => {
+                "A weak password hash was replaced with a stronger one after a successful login"
+            }
         }
     }
 }
@@ -1799,6 +2127,7 @@ impl SecurityEvent {
             SecurityEvent::IpBlocked => "Blocked IP address",
             SecurityEvent::ScanBan => "Banned due to scan",
             SecurityEvent::Unauthorized => "Unauthorized access",
+            SecurityEvent::TlsFingerprintBlocked => "Blocked TLS fingerprint",
         }
     }
 
@@ -1814,6 +2143,9 @@ impl SecurityEvent {
             SecurityEvent::LoiterBan => "IP address was banned due to multiple loitering events",
             SecurityEvent::IpBlocked => "Rejected connection from blocked IP address",
             SecurityEvent::Unauthorized => "Account does not have permission to access resource",
+            SecurityEvent::TlsFingerprintBlocked => {
+                "Rejected connection from a client with a blocked TLS fingerprint"
+            }
         }
     }
 }
@@ -18,7 +18,9 @@ impl EventType {
                 | StoreEvent::BlobWrite
                 | StoreEvent::BlobDelete
                 | StoreEvent::SqlQuery
-                | StoreEvent::LdapQuery => Level::Trace,
+                | StoreEvent::LdapQuery
+                | StoreEvent::RestQuery
+                | StoreEvent::S3Request => Level::Trace,
                 StoreEvent::CacheMiss
                 | StoreEvent::CacheHit
                 | StoreEvent::CacheStale
@@ -33,10 +35,12 @@ impl EventType {
                 | StoreEvent::RocksdbError
                 | StoreEvent::SqliteError
                 | StoreEvent::LdapError
+                | StoreEvent::RestError
                 | StoreEvent::ElasticsearchError
                 | StoreEvent::RedisError
                 | StoreEvent::S3Error
                 | StoreEvent::AzureError
+                | StoreEvent::GcsError
                 | StoreEvent::FilesystemError
                 | StoreEvent::PoolError
                 | StoreEvent::DataCorruption
@@ -46,7 +50,11 @@ impl EventType {
                 | StoreEvent::NotSupported
                 | StoreEvent::UnexpectedError
                 | StoreEvent::CryptoError => Level::Error,
-                StoreEvent::BlobMissingMarker | StoreEvent::HttpStoreError => Level::Warn,
+                StoreEvent::BlobMissingMarker
+                | StoreEvent::BlobCorrupted
+                | StoreEvent::BlobMissing
+                | StoreEvent::HttpStoreError
+                | StoreEvent::S3Retry => Level::Warn,
             },
             EventType::Jmap(_) => Level::Debug,
             EventType::Imap(event) => match event {
@@ -83,7 +91,9 @@ impl EventType {
                 | ImapEvent::Error
                 | ImapEvent::IdleStart
                 | ImapEvent::IdleStop
-                | ImapEvent::GetQuota => Level::Debug,
+                | ImapEvent::GetQuota
+                | ImapEvent::GetMetadata
+                | ImapEvent::SetMetadata => Level::Debug,
                 ImapEvent::RawInput | ImapEvent::RawOutput => Level::Trace,
             },
             EventType::ManageSieve(event) => match event {
@@ -134,10 +144,13 @@ impl EventType {
                 | SmtpEvent::MailFromMissing
                 | SmtpEvent::MultipleMailFrom
                 | SmtpEvent::MailFromNotAllowed
+                | SmtpEvent::MailFromSuspended
+                | SmtpEvent::MailFromNonSendingDomain
                 | SmtpEvent::RcptToDuplicate
                 | SmtpEvent::RcptToRewritten
                 | SmtpEvent::RcptToMissing
                 | SmtpEvent::RequireTlsDisabled
+                | SmtpEvent::RequireTlsUnencrypted
                 | SmtpEvent::DeliverByDisabled
                 | SmtpEvent::DeliverByInvalid
                 | SmtpEvent::FutureReleaseDisabled
@@ -162,7 +175,9 @@ impl EventType {
                 | SmtpEvent::UnsupportedParameter
                 | SmtpEvent::SyntaxError
                 | SmtpEvent::Error => Level::Debug,
-                SmtpEvent::MissingLocalHostname | SmtpEvent::IdNotFound => Level::Warn,
+                SmtpEvent::MissingLocalHostname
+                | SmtpEvent::IdNotFound
+                | SmtpEvent::DirectoryUnavailable => Level::Warn,
                 SmtpEvent::ConcurrencyLimitExceeded
                 | SmtpEvent::TransferLimitExceeded
                 | SmtpEvent::RateLimitExceeded
@@ -170,9 +185,13 @@ impl EventType {
                 | SmtpEvent::MissingAuthDirectory
                 | SmtpEvent::MessageParseFailed
                 | SmtpEvent::MessageTooLarge
+                | SmtpEvent::MessageOverQuota
                 | SmtpEvent::LoopDetected
+                | SmtpEvent::MessageTraceEnabled
                 | SmtpEvent::DkimPass
                 | SmtpEvent::DkimFail
+                | SmtpEvent::DkimUnsignedBody
+                | SmtpEvent::DkimUnsignedHeader
                 | SmtpEvent::ArcPass
                 | SmtpEvent::ArcFail
                 | SmtpEvent::SpfEhloPass
@@ -181,16 +200,22 @@ impl EventType {
                 | SmtpEvent::SpfFromFail
                 | SmtpEvent::DmarcPass
                 | SmtpEvent::DmarcFail
+                | SmtpEvent::SpfOverride
+                | SmtpEvent::DkimOverride
+                | SmtpEvent::DmarcOverride
                 | SmtpEvent::IprevPass
                 | SmtpEvent::IprevFail
                 | SmtpEvent::TooManyMessages
                 | SmtpEvent::Ehlo
+                | SmtpEvent::EhloValidation
                 | SmtpEvent::InvalidEhlo
                 | SmtpEvent::MailFrom
                 | SmtpEvent::MailboxDoesNotExist
                 | SmtpEvent::RelayNotAllowed
                 | SmtpEvent::RcptTo
                 | SmtpEvent::RcptToGreylisted
+                | SmtpEvent::RcptToSuppressed
+                | SmtpEvent::RcptToLoopDetected
                 | SmtpEvent::TooManyInvalidRcpt
                 | SmtpEvent::Vrfy
                 | SmtpEvent::VrfyNotFound
@@ -201,7 +226,11 @@ impl EventType {
                 | SmtpEvent::AuthMechanismNotSupported
                 | SmtpEvent::ExpnDisabled
                 | SmtpEvent::RequestTooLarge
-                | SmtpEvent::TooManyRecipients => Level::Info,
+                | SmtpEvent::TooManyRecipients
+                | SmtpEvent::TranscriptStored
+                | SmtpEvent::RcptToDeferred
+                | SmtpEvent::DirectoryRecovered
+                | SmtpEvent::HeadersAnonymized => Level::Info,
                 SmtpEvent::RawInput | SmtpEvent::RawOutput => Level::Trace,
             },
             EventType::Network(event) => match event {
@@ -216,6 +245,7 @@ impl EventType {
                 | NetworkEvent::SetOptError
                 | NetworkEvent::SplitError => Level::Error,
                 NetworkEvent::ProxyError => Level::Warn,
+                NetworkEvent::ProxyInfo => Level::Debug,
             },
             EventType::Limit(cause) => match cause {
                 LimitEvent::SizeRequest => Level::Debug,
@@ -224,6 +254,8 @@ impl EventType {
                 LimitEvent::ConcurrentRequest => Level::Debug,
                 LimitEvent::ConcurrentUpload => Level::Debug,
                 LimitEvent::ConcurrentConnection => Level::Warn,
+                LimitEvent::ConcurrentMessage => Level::Warn,
+                LimitEvent::MessageBuffer => Level::Warn,
                 LimitEvent::Quota => Level::Debug,
                 LimitEvent::BlobQuota => Level::Debug,
                 LimitEvent::TooManyRequests => Level::Warn,
@@ -231,11 +263,20 @@ impl EventType {
             },
             EventType::Manage(_) => Level::Debug,
             EventType::Auth(cause) => match cause {
-                AuthEvent::Failed | AuthEvent::TokenExpired => Level::Debug,
+                AuthEvent::Failed
+                | AuthEvent::TokenExpired
+                | AuthEvent::PasswordExpired
+                | AuthEvent::PasswordPolicyViolation
+                | AuthEvent::ClientRegistrationRejected => Level::Debug,
                 AuthEvent::MissingTotp => Level::Trace,
                 AuthEvent::TooManyAttempts => Level::Warn,
                 AuthEvent::Error => Level::Error,
-                AuthEvent::Success | AuthEvent::ClientRegistration => Level::Info,
+                AuthEvent::Success
+                | AuthEvent::ClientRegistration
+                | AuthEvent::ClientRegistrationPending
+                | AuthEvent::ClientRegistrationApproved
+                | AuthEvent::SecretRehashed
+                | AuthEvent::SessionRevoked => Level::Info,
             },
             EventType::Config(cause) => match cause {
                 ConfigEvent::ParseError
@@ -287,10 +328,18 @@ impl EventType {
                     Level::Info
                 }
                 ServerEvent::StartupError | ServerEvent::ThreadError => Level::Error,
+                ServerEvent::IpcEventDropped => Level::Warn,
+                ServerEvent::IpcEventBuffered => Level::Debug,
+                ServerEvent::Degraded => Level::Warn,
+                ServerEvent::Recovered
+                | ServerEvent::MigrationPlan
+                | ServerEvent::MigrationPending
+                | ServerEvent::MigrationCompleted => Level::Info,
             },
             EventType::Acme(event) => match event {
                 AcmeEvent::DnsRecordCreated
                 | AcmeEvent::DnsRecordPropagated
+                | AcmeEvent::HttpTokenPushed
                 | AcmeEvent::TlsAlpnReceived
                 | AcmeEvent::AuthStart
                 | AcmeEvent::AuthPending
@@ -309,9 +358,12 @@ impl EventType {
                 | AcmeEvent::TokenNotFound
                 | AcmeEvent::DnsRecordPropagationTimeout
                 | AcmeEvent::TlsAlpnError
-                | AcmeEvent::DnsRecordCreationFailed => Level::Warn,
+                | AcmeEvent::DnsRecordCreationFailed
+                | AcmeEvent::HttpTokenPushFailed
+                | AcmeEvent::HttpTokenCheckFailed => Level::Warn,
                 AcmeEvent::RenewBackoff
                 | AcmeEvent::DnsRecordDeletionFailed
+                | AcmeEvent::HttpTokenDeletionFailed
                 | AcmeEvent::ClientSuppliedSni
                 | AcmeEvent::ClientMissingSni
                 | AcmeEvent::DnsRecordNotPropagated
@@ -319,6 +371,7 @@ impl EventType {
             },
             EventType::Tls(event) => match event {
                 TlsEvent::Handshake => Level::Info,
+                TlsEvent::ClientFingerprint => Level::Debug,
                 TlsEvent::HandshakeError | TlsEvent::CertificateNotFound => Level::Debug,
                 TlsEvent::NotConfigured => Level::Error,
                 TlsEvent::NoCertificatesAvailable | TlsEvent::MultipleCertificatesAvailable => {
@@ -341,9 +394,14 @@ impl EventType {
             },
             EventType::Spam(event) => match event {
                 SpamEvent::PyzorError
+                | SpamEvent::FuzzyError
                 | SpamEvent::TrainError
                 | SpamEvent::DnsblError
                 | SpamEvent::Pyzor
+                | SpamEvent::Fuzzy
+                | SpamEvent::FuzzyLearn
+                | SpamEvent::SenderReputation
+                | SpamEvent::SenderReputationLearn
                 | SpamEvent::Train
                 | SpamEvent::TrainAccount
                 | SpamEvent::Classify
@@ -381,6 +439,10 @@ impl EventType {
                 | TaskQueueEvent::TaskAcquired
                 | TaskQueueEvent::TaskLocked
                 | TaskQueueEvent::MetadataNotFound => Level::Debug,
+                TaskQueueEvent::JobStarted
+                | TaskQueueEvent::JobCompleted
+                | TaskQueueEvent::JobCancelled => Level::Info,
+                TaskQueueEvent::JobFailed => Level::Warn,
             },
             EventType::Dmarc(_) => Level::Debug,
             EventType::Spf(_) => Level::Debug,
@@ -410,6 +472,14 @@ impl EventType {
                 | MtaHookEvent::ActionQuarantine => Level::Info,
                 MtaHookEvent::Error => Level::Warn,
             },
+            EventType::Icap(event) => match event {
+                IcapEvent::Clean | IcapEvent::Skipped => Level::Debug,
+                IcapEvent::ThreatFound
+                | IcapEvent::ContentModified
+                | IcapEvent::UploadRejected
+                | IcapEvent::UploadQuarantined => Level::Info,
+                IcapEvent::Error => Level::Warn,
+            },
             EventType::Dane(event) => match event {
                 DaneEvent::AuthenticationSuccess
                 | DaneEvent::AuthenticationFailure
@@ -445,7 +515,11 @@ impl EventType {
                 | DeliveryEvent::StartTlsUnavailable
                 | DeliveryEvent::StartTlsError
                 | DeliveryEvent::StartTlsDisabled
+                | DeliveryEvent::RequireTlsUnavailable
+                | DeliveryEvent::RequireTlsMxUnverified
                 | DeliveryEvent::ImplicitTlsError
+                | DeliveryEvent::DowngradeFailed
+                | DeliveryEvent::PipeFailed
                 | DeliveryEvent::DoubleBounce => Level::Info,
                 DeliveryEvent::ConcurrencyLimitExceeded
                 | DeliveryEvent::RateLimitExceeded
@@ -453,25 +527,35 @@ impl EventType {
                 DeliveryEvent::DsnSuccess
                 | DeliveryEvent::DsnTempFail
                 | DeliveryEvent::DsnPermFail => Level::Info,
+                DeliveryEvent::BounceBadAddress
+                | DeliveryEvent::BounceMailboxFull
+                | DeliveryEvent::BounceReputationBlock
+                | DeliveryEvent::BouncePolicy
+                | DeliveryEvent::BounceTransientInfra => Level::Debug,
                 DeliveryEvent::MxLookup
                 | DeliveryEvent::IpLookup
                 | DeliveryEvent::Ehlo
                 | DeliveryEvent::Auth
                 | DeliveryEvent::MailFrom
-                | DeliveryEvent::RcptTo => Level::Debug,
+                | DeliveryEvent::RcptTo
+                | DeliveryEvent::Downgraded => Level::Debug,
                 DeliveryEvent::RawInput | DeliveryEvent::RawOutput => Level::Trace,
             },
             EventType::Queue(event) => match event {
                 QueueEvent::BackPressure => Level::Warn,
+                QueueEvent::WorkersScaled => Level::Info,
                 QueueEvent::QueueMessage
                 | QueueEvent::QueueMessageAuthenticated
                 | QueueEvent::QueueReport
                 | QueueEvent::QueueDsn
                 | QueueEvent::QueueAutogenerated
+                | QueueEvent::QueueShadowCopy
                 | QueueEvent::RateLimitExceeded
                 | QueueEvent::ConcurrencyLimitExceeded
                 | QueueEvent::Rescheduled
-                | QueueEvent::QuotaExceeded => Level::Info,
+                | QueueEvent::QuotaExceeded
+                | QueueEvent::SendingQuotaExceeded
+                | QueueEvent::SendingQuotaWarning => Level::Info,
                 QueueEvent::Locked | QueueEvent::BlobNotFound => Level::Debug,
             },
             EventType::TlsRpt(event) => match event {
@@ -493,6 +577,8 @@ impl EventType {
                 IncomingReportEvent::DmarcReport
                 | IncomingReportEvent::TlsReport
                 | IncomingReportEvent::AbuseReport
+                | IncomingReportEvent::AbuseComplaint
+                | IncomingReportEvent::AbuseComplaintIgnored
                 | IncomingReportEvent::AuthFailureReport
                 | IncomingReportEvent::FraudReport
                 | IncomingReportEvent::NotSpamReport
@@ -509,6 +595,7 @@ impl EventType {
                 OutgoingReportEvent::SpfReport
                 | OutgoingReportEvent::SpfRateLimited
                 | OutgoingReportEvent::DkimReport
+                | OutgoingReportEvent::AbuseReport
                 | OutgoingReportEvent::DkimRateLimited
                 | OutgoingReportEvent::DmarcReport
                 | OutgoingReportEvent::DmarcRateLimited
@@ -527,7 +614,16 @@ impl EventType {
                 | MessageIngestEvent::ImapAppend
                 | MessageIngestEvent::JmapAppend
                 | MessageIngestEvent::Duplicate
-                | MessageIngestEvent::FtsIndex => Level::Info,
+                | MessageIngestEvent::FtsIndex
+                | MessageIngestEvent::MailboxOverflow
+                | MessageIngestEvent::MailboxExpire
+                | MessageIngestEvent::AttachmentsConverted
+                | MessageIngestEvent::AttachmentsStripped => Level::Info,
+                MessageIngestEvent::MimePartsExceeded
+                | MessageIngestEvent::MimeDepthExceeded
+                | MessageIngestEvent::MimeHeadersExceeded
+                | MessageIngestEvent::MimeExpansionExceeded
+                | MessageIngestEvent::ForwardLoop => Level::Warn,
                 MessageIngestEvent::Error => Level::Error,
             },
             EventType::Security(_) => Level::Info,
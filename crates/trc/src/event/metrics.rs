@@ -36,6 +36,15 @@ impl MetricType {
             Self::QueueCount => "queue.count",
             Self::UserCount => "user.count",
             Self::DomainCount => "domain.count",
+            Self::IpcStateQueued => "ipc.state.queued",
+            Self::IpcHousekeeperQueued => "ipc.housekeeper.queued",
+            Self::IpcQueueQueued => "ipc.queue.queued",
+            Self::IpcReportQueued => "ipc.report.queued",
+            Self::IpcBroadcastQueued => "ipc.broadcast.queued",
+            Self::QueueWorkersActive => "queue.workers.active",
+            Self::QueueWorkersMax => "queue.workers.max",
+            Self::SmtpMessagesInFlight => "smtp.messages.in-flight",
+            Self::SmtpMessagesBuffered => "smtp.messages.buffered",
         }
     }
 
@@ -68,6 +77,15 @@ impl MetricType {
             Self::QueueCount => "Total number of messages in the queue",
             Self::UserCount => "Total number of users",
             Self::DomainCount => "Total number of domains",
+            Self::IpcStateQueued => "Events waiting in the state manager channel",
+            Self::IpcHousekeeperQueued => "Events waiting in the housekeeper channel",
+            Self::IpcQueueQueued => "Events waiting in the queue manager channel",
+            Self::IpcReportQueued => "Events waiting in the report scheduler channel",
+            Self::IpcBroadcastQueued => "Events waiting in the cluster broadcast channel",
+            Self::QueueWorkersActive => "Outbound delivery workers in use",
+            Self::QueueWorkersMax => "Outbound delivery worker limit",
+            Self::SmtpMessagesInFlight => "Incoming messages being received",
+            Self::SmtpMessagesBuffered => "Memory used by incoming messages being received",
         }
     }
 
@@ -90,16 +108,23 @@ impl MetricType {
             Self::MessageSize
             | Self::MessageAuthSize
             | Self::ReportOutgoingSize
-            | Self::ServerMemory => "bytes",
+            | Self::ServerMemory
+            | Self::SmtpMessagesBuffered => "bytes",
             Self::HttpActiveConnections
             | Self::ImapActiveConnections
             | Self::Pop3ActiveConnections
             | Self::SmtpActiveConnections
             | Self::SieveActiveConnections
             | Self::DeliveryActiveConnections => "connections",
-            Self::QueueCount => "messages",
+            Self::QueueCount | Self::SmtpMessagesInFlight => "messages",
             Self::UserCount => "users",
             Self::DomainCount => "domains",
+            Self::IpcStateQueued
+            | Self::IpcHousekeeperQueued
+            | Self::IpcQueueQueued
+            | Self::IpcReportQueued
+            | Self::IpcBroadcastQueued => "events",
+            Self::QueueWorkersActive | Self::QueueWorkersMax => "workers",
         }
     }
 
@@ -132,6 +157,15 @@ impl MetricType {
             Self::QueueCount => 24,
             Self::UserCount => 25,
             Self::DomainCount => 26,
+            Self::IpcStateQueued => 27,
+            Self::IpcHousekeeperQueued => 28,
+            Self::IpcQueueQueued => 29,
+            Self::IpcReportQueued => 30,
+            Self::IpcBroadcastQueued => 31,
+            Self::QueueWorkersActive => 32,
+            Self::QueueWorkersMax => 33,
+            Self::SmtpMessagesInFlight => 34,
+            Self::SmtpMessagesBuffered => 35,
         }
     }
 
@@ -164,6 +198,15 @@ impl MetricType {
             24 => Some(Self::QueueCount),
             25 => Some(Self::UserCount),
             26 => Some(Self::DomainCount),
+            27 => Some(Self::IpcStateQueued),
+            28 => Some(Self::IpcHousekeeperQueued),
+            29 => Some(Self::IpcQueueQueued),
+            30 => Some(Self::IpcReportQueued),
+            31 => Some(Self::IpcBroadcastQueued),
+            32 => Some(Self::QueueWorkersActive),
+            33 => Some(Self::QueueWorkersMax),
+            34 => Some(Self::SmtpMessagesInFlight),
+            35 => Some(Self::SmtpMessagesBuffered),
             _ => None,
         }
     }
@@ -197,6 +240,15 @@ impl MetricType {
             "queue.count" => Some(Self::QueueCount),
             "user.count" => Some(Self::UserCount),
             "domain.count" => Some(Self::DomainCount),
+            "ipc.state.queued" => Some(Self::IpcStateQueued),
+            "ipc.housekeeper.queued" => Some(Self::IpcHousekeeperQueued),
+            "ipc.queue.queued" => Some(Self::IpcQueueQueued),
+            "ipc.report.queued" => Some(Self::IpcReportQueued),
+            "ipc.broadcast.queued" => Some(Self::IpcBroadcastQueued),
+            "queue.workers.active" => Some(Self::QueueWorkersActive),
+            "queue.workers.max" => Some(Self::QueueWorkersMax),
+            "smtp.messages.in-flight" => Some(Self::SmtpMessagesInFlight),
+            "smtp.messages.buffered" => Some(Self::SmtpMessagesBuffered),
             _ => None,
         }
     }
@@ -230,6 +282,15 @@ impl MetricType {
             Self::QueueCount,
             Self::UserCount,
             Self::DomainCount,
+            Self::IpcStateQueued,
+            Self::IpcHousekeeperQueued,
+            Self::IpcQueueQueued,
+            Self::IpcReportQueued,
+            Self::IpcBroadcastQueued,
+            Self::QueueWorkersActive,
+            Self::QueueWorkersMax,
+            Self::SmtpMessagesInFlight,
+            Self::SmtpMessagesBuffered,
         ]
     }
 }
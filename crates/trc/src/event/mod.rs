@@ -208,7 +208,12 @@ impl Error {
             self.0.inner,
             EventType::Network(_)
                 | EventType::Auth(AuthEvent::TooManyAttempts)
-                | EventType::Limit(LimitEvent::ConcurrentRequest | LimitEvent::TooManyRequests)
+                | EventType::Limit(
+                    LimitEvent::ConcurrentRequest
+                        | LimitEvent::TooManyRequests
+                        | LimitEvent::ConcurrentMessage
+                        | LimitEvent::MessageBuffer
+                )
                 | EventType::Security(_)
         )
     }
@@ -350,10 +355,12 @@ impl StoreEvent {
             Self::RocksdbError => "RocksDB error",
             Self::SqliteError => "SQLite error",
             Self::LdapError => "LDAP error",
+            Self::RestError => "REST directory error",
             Self::ElasticsearchError => "ElasticSearch error",
             Self::RedisError => "Redis error",
             Self::S3Error => "S3 error",
             Self::AzureError => "Azure error",
+            Self::GcsError => "Google Cloud Storage error",
             Self::FilesystemError => "Filesystem error",
             Self::PoolError => "Connection pool error",
             Self::DataCorruption => "Data corruption",
@@ -522,6 +529,8 @@ impl LimitEvent {
             Self::BlobQuota => "Blob quota exceeded",
             Self::TooManyRequests => "Too many requests",
             Self::TenantQuota => "Tenant quota exceeded",
+            Self::ConcurrentMessage => "Too many messages in progress",
+            Self::MessageBuffer => "Message buffer budget exceeded",
         }
     }
 }
@@ -49,6 +49,21 @@ static SERVER_MEMORY: AtomicGauge = AtomicGauge::new(MetricType::ServerMemory);
 static QUEUE_COUNT: AtomicGauge = AtomicGauge::new(MetricType::QueueCount);
 static USER_COUNT: AtomicGauge = AtomicGauge::new(MetricType::UserCount);
 static DOMAIN_COUNT: AtomicGauge = AtomicGauge::new(MetricType::DomainCount);
+static IPC_QUEUED: [AtomicGauge; 5] = [
+    AtomicGauge::new(MetricType::IpcStateQueued),
+    AtomicGauge::new(MetricType::IpcHousekeeperQueued),
+    AtomicGauge::new(MetricType::IpcQueueQueued),
+    AtomicGauge::new(MetricType::IpcReportQueued),
+    AtomicGauge::new(MetricType::IpcBroadcastQueued),
+];
+static QUEUE_WORKERS: [AtomicGauge; 2] = [
+    AtomicGauge::new(MetricType::QueueWorkersActive),
+    AtomicGauge::new(MetricType::QueueWorkersMax),
+];
+static SMTP_MESSAGES: [AtomicGauge; 2] = [
+    AtomicGauge::new(MetricType::SmtpMessagesInFlight),
+    AtomicGauge::new(MetricType::SmtpMessagesBuffered),
+];
 
 const CONN_SMTP_IN: usize = 0;
 const CONN_SMTP_OUT: usize = 1;
@@ -234,6 +249,9 @@ impl Collector {
             .iter()
             .copied()
             .chain(CONNECTION_METRICS.iter().map(|m| &m.active_connections))
+            .chain(IPC_QUEUED.iter())
+            .chain(QUEUE_WORKERS.iter())
+            .chain(SMTP_MESSAGES.iter())
     }
 
     pub fn collect_histograms(
@@ -315,6 +333,15 @@ impl Collector {
             MetricType::SieveRequestTime => CONNECTION_METRICS[CONN_SIEVE].elapsed.average(),
             MetricType::UserCount => USER_COUNT.get() as f64,
             MetricType::DomainCount => DOMAIN_COUNT.get() as f64,
+            MetricType::IpcStateQueued => IPC_QUEUED[0].get() as f64,
+            MetricType::IpcHousekeeperQueued => IPC_QUEUED[1].get() as f64,
+            MetricType::IpcQueueQueued => IPC_QUEUED[2].get() as f64,
+            MetricType::IpcReportQueued => IPC_QUEUED[3].get() as f64,
+            MetricType::IpcBroadcastQueued => IPC_QUEUED[4].get() as f64,
+            MetricType::QueueWorkersActive => QUEUE_WORKERS[0].get() as f64,
+            MetricType::QueueWorkersMax => QUEUE_WORKERS[1].get() as f64,
+            MetricType::SmtpMessagesInFlight => SMTP_MESSAGES[0].get() as f64,
+            MetricType::SmtpMessagesBuffered => SMTP_MESSAGES[1].get() as f64,
         }
     }
 
@@ -324,6 +351,15 @@ impl Collector {
             MetricType::QueueCount => QUEUE_COUNT.set(value),
             MetricType::UserCount => USER_COUNT.set(value),
             MetricType::DomainCount => DOMAIN_COUNT.set(value),
+            MetricType::IpcStateQueued => IPC_QUEUED[0].set(value),
+            MetricType::IpcHousekeeperQueued => IPC_QUEUED[1].set(value),
+            MetricType::IpcQueueQueued => IPC_QUEUED[2].set(value),
+            MetricType::IpcReportQueued => IPC_QUEUED[3].set(value),
+            MetricType::IpcBroadcastQueued => IPC_QUEUED[4].set(value),
+            MetricType::QueueWorkersActive => QUEUE_WORKERS[0].set(value),
+            MetricType::QueueWorkersMax => QUEUE_WORKERS[1].set(value),
+            MetricType::SmtpMessagesInFlight => SMTP_MESSAGES[0].set(value),
+            MetricType::SmtpMessagesBuffered => SMTP_MESSAGES[1].set(value),
             _ => {}
         }
     }
@@ -410,7 +446,12 @@ const fn init_conn_metrics() -> [ConnectionMetrics; TOTAL_CONN_TYPES] {
 impl EventType {
     pub fn is_metric(&self) -> bool {
         match self {
-            EventType::Server(ServerEvent::ThreadError) => true,
+            EventType::Server(
+                ServerEvent::ThreadError
+                | ServerEvent::IpcEventDropped
+                | ServerEvent::IpcEventBuffered
+                | ServerEvent::Degraded,
+            ) => true,
             EventType::Purge(PurgeEvent::Error) => true,
             EventType::Eval(
                 EvalEvent::Error | EvalEvent::StoreNotFound | EvalEvent::DirectoryNotFound,
@@ -423,6 +464,7 @@ impl EventType {
                 | AcmeEvent::DnsRecordCreationFailed
                 | AcmeEvent::DnsRecordDeletionFailed
                 | AcmeEvent::DnsRecordPropagationTimeout
+                | AcmeEvent::HttpTokenPushFailed
                 | AcmeEvent::ClientMissingSni
                 | AcmeEvent::TokenNotFound
                 | AcmeEvent::DnsRecordLookupFailed
@@ -437,10 +479,12 @@ impl EventType {
                 | StoreEvent::RocksdbError
                 | StoreEvent::SqliteError
                 | StoreEvent::LdapError
+                | StoreEvent::RestError
                 | StoreEvent::ElasticsearchError
                 | StoreEvent::RedisError
                 | StoreEvent::S3Error
                 | StoreEvent::AzureError
+                | StoreEvent::GcsError
                 | StoreEvent::FilesystemError
                 | StoreEvent::PoolError
                 | StoreEvent::DataCorruption
@@ -452,12 +496,16 @@ impl EventType {
                 | StoreEvent::UnexpectedError
                 | StoreEvent::CryptoError
                 | StoreEvent::BlobMissingMarker
+                | StoreEvent::BlobCorrupted
+                | StoreEvent::BlobMissing
                 | StoreEvent::DataWrite
                 | StoreEvent::DataIterate
                 | StoreEvent::BlobRead
                 | StoreEvent::BlobWrite
                 | StoreEvent::BlobDelete
-                | StoreEvent::HttpStoreError,
+                | StoreEvent::HttpStoreError
+                | StoreEvent::S3Request
+                | StoreEvent::S3Retry,
             ) => true,
             EventType::MessageIngest(_) => true,
             EventType::Jmap(
@@ -488,7 +536,9 @@ impl EventType {
                 | SmtpEvent::TimeLimitExceeded
                 | SmtpEvent::MessageParseFailed
                 | SmtpEvent::MessageTooLarge
+                | SmtpEvent::MessageOverQuota
                 | SmtpEvent::LoopDetected
+                | SmtpEvent::MessageTraceEnabled
                 | SmtpEvent::DkimPass
                 | SmtpEvent::DkimFail
                 | SmtpEvent::ArcPass
@@ -578,6 +628,9 @@ impl EventType {
             ) => true,
             EventType::Spam(
                 SpamEvent::PyzorError
+                | SpamEvent::FuzzyError
+                | SpamEvent::FuzzyLearn
+                | SpamEvent::SenderReputationLearn
                 | SpamEvent::Train
                 | SpamEvent::TrainError
                 | SpamEvent::Classify
@@ -604,6 +657,7 @@ impl EventType {
                 | MilterEvent::ActionShutdown,
             ) => true,
             EventType::MtaHook(_) => true,
+            EventType::Icap(_) => true,
             EventType::Delivery(
                 DeliveryEvent::AttemptStart
                 | DeliveryEvent::Completed
@@ -628,7 +682,13 @@ impl EventType {
                 | DeliveryEvent::DoubleBounce
                 | DeliveryEvent::DsnSuccess
                 | DeliveryEvent::DsnTempFail
-                | DeliveryEvent::DsnPermFail,
+                | DeliveryEvent::DsnPermFail
+                | DeliveryEvent::BounceBadAddress
+                | DeliveryEvent::BounceMailboxFull
+                | DeliveryEvent::BounceReputationBlock
+                | DeliveryEvent::BouncePolicy
+                | DeliveryEvent::BounceTransientInfra
+                | DeliveryEvent::PipeFailed,
             ) => true,
             EventType::Queue(
                 QueueEvent::QueueMessage
@@ -636,11 +696,15 @@ impl EventType {
                 | QueueEvent::QueueReport
                 | QueueEvent::QueueDsn
                 | QueueEvent::QueueAutogenerated
+                | QueueEvent::QueueShadowCopy
                 | QueueEvent::Rescheduled
                 | QueueEvent::BlobNotFound
                 | QueueEvent::RateLimitExceeded
                 | QueueEvent::ConcurrencyLimitExceeded
-                | QueueEvent::QuotaExceeded,
+                | QueueEvent::QuotaExceeded
+                | QueueEvent::SendingQuotaExceeded
+                | QueueEvent::SendingQuotaWarning
+                | QueueEvent::WorkersScaled,
             ) => true,
             EventType::TlsRpt(_) => false,
             EventType::MtaSts(
@@ -651,6 +715,7 @@ impl EventType {
                 OutgoingReportEvent::SpfReport
                 | OutgoingReportEvent::SpfRateLimited
                 | OutgoingReportEvent::DkimReport
+                | OutgoingReportEvent::AbuseReport
                 | OutgoingReportEvent::DkimRateLimited
                 | OutgoingReportEvent::DmarcReport
                 | OutgoingReportEvent::DmarcRateLimited
@@ -179,6 +179,7 @@ pub enum EventType {
     TaskQueue(TaskQueueEvent),
     Milter(MilterEvent),
     MtaHook(MtaHookEvent),
+    Icap(IcapEvent),
     Delivery(DeliveryEvent),
     Queue(QueueEvent),
     TlsRpt(TlsRptEvent),
@@ -211,6 +212,7 @@ pub enum SecurityEvent {
     LoiterBan,
     IpBlocked,
     Unauthorized,
+    TlsFingerprintBlocked,
 }
 
 #[event_type]
@@ -241,6 +243,10 @@ pub enum TaskQueueEvent {
     TaskLocked,
     BlobNotFound,
     MetadataNotFound,
+    JobStarted,
+    JobCompleted,
+    JobFailed,
+    JobCancelled,
 }
 
 #[event_type]
@@ -281,6 +287,8 @@ pub enum ImapEvent {
     Unsubscribe,
     Thread,
     GetQuota,
+    GetMetadata,
+    SetMetadata,
 
     // Errors
     Error,
@@ -360,9 +368,13 @@ pub enum SmtpEvent {
     MissingAuthDirectory,
     MessageParseFailed,
     MessageTooLarge,
+    MessageOverQuota,
     LoopDetected,
+    MessageTraceEnabled,
     DkimPass,
     DkimFail,
+    DkimUnsignedBody,
+    DkimUnsignedHeader,
     ArcPass,
     ArcFail,
     SpfEhloPass,
@@ -371,17 +383,23 @@ pub enum SmtpEvent {
     SpfFromFail,
     DmarcPass,
     DmarcFail,
+    SpfOverride,
+    DkimOverride,
+    DmarcOverride,
     IprevPass,
     IprevFail,
     TooManyMessages,
     Ehlo,
     InvalidEhlo,
+    EhloValidation,
     DidNotSayEhlo,
     EhloExpected,
     LhloExpected,
     MailFromUnauthenticated,
     MailFromUnauthorized,
     MailFromNotAllowed,
+    MailFromSuspended,
+    MailFromNonSendingDomain,
     MailFromRewritten,
     MailFromMissing,
     MailFrom,
@@ -393,6 +411,8 @@ pub enum SmtpEvent {
     RcptToRewritten,
     RcptToMissing,
     RcptToGreylisted,
+    RcptToSuppressed,
+    RcptToLoopDetected,
     TooManyRecipients,
     TooManyInvalidRcpt,
     RawInput,
@@ -405,10 +425,12 @@ pub enum SmtpEvent {
     ExpnNotFound,
     ExpnDisabled,
     RequireTlsDisabled,
+    RequireTlsUnencrypted,
     DeliverByDisabled,
     DeliverByInvalid,
     FutureReleaseDisabled,
     FutureReleaseInvalid,
+    HeadersAnonymized,
     MtPriorityDisabled,
     MtPriorityInvalid,
     DsnDisabled,
@@ -431,6 +453,10 @@ pub enum SmtpEvent {
     UnsupportedParameter,
     SyntaxError,
     RequestTooLarge,
+    TranscriptStored,
+    RcptToDeferred,
+    DirectoryUnavailable,
+    DirectoryRecovered,
 }
 
 #[event_type]
@@ -464,6 +490,8 @@ pub enum DeliveryEvent {
     StartTlsUnavailable,
     StartTlsError,
     StartTlsDisabled,
+    RequireTlsUnavailable,
+    RequireTlsMxUnverified,
     ImplicitTlsError,
     ConcurrencyLimitExceeded,
     RateLimitExceeded,
@@ -471,6 +499,14 @@ pub enum DeliveryEvent {
     DsnSuccess,
     DsnTempFail,
     DsnPermFail,
+    Downgraded,
+    DowngradeFailed,
+    BounceBadAddress,
+    BounceMailboxFull,
+    BounceReputationBlock,
+    BouncePolicy,
+    BounceTransientInfra,
+    PipeFailed,
     RawInput,
     RawOutput,
 }
@@ -482,13 +518,17 @@ pub enum QueueEvent {
     QueueReport,
     QueueDsn,
     QueueAutogenerated,
+    QueueShadowCopy,
     Rescheduled,
     Locked,
     BlobNotFound,
     RateLimitExceeded,
     ConcurrencyLimitExceeded,
     QuotaExceeded,
+    SendingQuotaExceeded,
+    SendingQuotaWarning,
     BackPressure,
+    WorkersScaled,
 }
 
 #[event_type]
@@ -503,6 +543,8 @@ pub enum IncomingReportEvent {
     NotSpamReport,
     VirusReport,
     OtherReport,
+    AbuseComplaint,
+    AbuseComplaintIgnored,
     MessageParseFailed,
     DmarcParseFailed,
     TlsRpcParseFailed,
@@ -520,6 +562,7 @@ pub enum OutgoingReportEvent {
     DmarcRateLimited,
     DmarcAggregateReport,
     TlsAggregate,
+    AbuseReport,
     HttpSubmission,
     UnauthorizedReportingAddress,
     ReportingAddressValidationError,
@@ -590,6 +633,17 @@ pub enum MtaHookEvent {
     Error,
 }
 
+#[event_type]
+pub enum IcapEvent {
+    Clean,
+    ThreatFound,
+    ContentModified,
+    Skipped,
+    Error,
+    UploadRejected,
+    UploadQuarantined,
+}
+
 #[event_type]
 pub enum PushSubscriptionEvent {
     Success,
@@ -601,6 +655,11 @@ pub enum PushSubscriptionEvent {
 pub enum SpamEvent {
     Pyzor,
     PyzorError,
+    Fuzzy,
+    FuzzyLearn,
+    FuzzyError,
+    SenderReputation,
+    SenderReputationLearn,
     Dnsbl,
     DnsblError,
     Train,
@@ -635,6 +694,7 @@ pub enum TlsEvent {
     CertificateNotFound,
     NoCertificatesAvailable,
     MultipleCertificatesAvailable,
+    ClientFingerprint,
 }
 
 #[event_type]
@@ -651,6 +711,7 @@ pub enum NetworkEvent {
     Timeout,
     Closed,
     ProxyError,
+    ProxyInfo,
     SetOptError,
 }
 
@@ -661,6 +722,13 @@ pub enum ServerEvent {
     StartupError,
     ThreadError,
     Licensing,
+    IpcEventDropped,
+    IpcEventBuffered,
+    Degraded,
+    Recovered,
+    MigrationPlan,
+    MigrationPending,
+    MigrationCompleted,
 }
 
 #[event_type]
@@ -697,6 +765,10 @@ pub enum AcmeEvent {
     DnsRecordLookupFailed,
     DnsRecordPropagated,
     DnsRecordPropagationTimeout,
+    HttpTokenPushed,
+    HttpTokenPushFailed,
+    HttpTokenDeletionFailed,
+    HttpTokenCheckFailed,
     ClientSuppliedSni,
     ClientMissingSni,
     TlsAlpnReceived,
@@ -825,10 +897,12 @@ pub enum StoreEvent {
     RocksdbError,
     SqliteError,
     LdapError,
+    RestError,
     ElasticsearchError,
     RedisError,
     S3Error,
     AzureError,
+    GcsError,
     FilesystemError,
     PoolError,
     DataCorruption,
@@ -849,6 +923,8 @@ pub enum StoreEvent {
 
     // Warnings
     BlobMissingMarker,
+    BlobCorrupted,
+    BlobMissing,
 
     // Traces
     DataWrite,
@@ -859,7 +935,12 @@ pub enum StoreEvent {
     SqlQuery,
     LdapQuery,
     LdapWarning,
+    RestQuery,
     HttpStoreFetch,
+    S3Request,
+
+    // S3 warnings
+    S3Retry,
 }
 
 #[event_type]
@@ -872,6 +953,15 @@ pub enum MessageIngestEvent {
     Duplicate,
     Error,
     FtsIndex,
+    MimePartsExceeded,
+    MimeDepthExceeded,
+    MimeHeadersExceeded,
+    MimeExpansionExceeded,
+    MailboxOverflow,
+    MailboxExpire,
+    AttachmentsConverted,
+    AttachmentsStripped,
+    ForwardLoop,
 }
 
 #[event_type]
@@ -915,6 +1005,8 @@ pub enum LimitEvent {
     ConcurrentRequest,
     ConcurrentUpload,
     ConcurrentConnection, // Used by listener
+    ConcurrentMessage,
+    MessageBuffer,
     Quota,
     BlobQuota,
     TenantQuota,
@@ -939,6 +1031,13 @@ pub enum AuthEvent {
     MissingTotp,
     TooManyAttempts,
     ClientRegistration,
+    ClientRegistrationPending,
+    ClientRegistrationRejected,
+    ClientRegistrationApproved,
+    SecretRehashed,
+    PasswordExpired,
+    PasswordPolicyViolation,
+    SessionRevoked,
     Error,
 }
 
@@ -1023,6 +1122,15 @@ pub enum MetricType {
     SieveRequestTime,
     UserCount,
     DomainCount,
+    IpcStateQueued,
+    IpcHousekeeperQueued,
+    IpcQueueQueued,
+    IpcReportQueued,
+    IpcBroadcastQueued,
+    QueueWorkersActive,
+    QueueWorkersMax,
+    SmtpMessagesInFlight,
+    SmtpMessagesBuffered,
 }
 
 pub const TOTAL_EVENT_COUNT: usize = total_event_count!();
@@ -49,6 +49,18 @@ impl BlobHash {
         }
         hex
     }
+
+    pub fn try_from_hex(value: &str) -> Option<Self> {
+        if value.len() != BLOB_HASH_LEN * 2 {
+            return None;
+        }
+
+        let mut hash = [0u8; BLOB_HASH_LEN];
+        for (byte, chunk) in hash.iter_mut().zip(value.as_bytes().chunks_exact(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        }
+        Some(BlobHash(hash))
+    }
 }
 
 impl From<&ArchivedBlobHash> for BlobHash {
@@ -40,6 +40,8 @@ pub enum EmailField {
     ReceivedAt,
     SentAt,
     HasAttachment,
+    Dumpster,
+    CalendarPreview,
     From,
     To,
     Cc,
@@ -83,6 +85,8 @@ pub enum EmailSubmissionField {
 pub enum PrincipalField {
     Archive,
     EncryptionKeys,
+    VanishedCheckpoints,
+    Settings,
 }
 
 impl From<ContactField> for u8 {
@@ -120,6 +124,8 @@ impl From<EmailField> for u8 {
             EmailField::ReceivedAt => 19,
             EmailField::SentAt => 26,
             EmailField::HasAttachment => 89,
+            EmailField::Dumpster => 45,
+            EmailField::CalendarPreview => 48,
             EmailField::Archive => ARCHIVE_FIELD,
             //EmailField::MessageId => 11,
             //EmailField::ReplyTo => 21,
@@ -167,6 +173,8 @@ impl From<PrincipalField> for u8 {
     fn from(value: PrincipalField) -> Self {
         match value {
             PrincipalField::EncryptionKeys => 46,
+            PrincipalField::VanishedCheckpoints => 47,
+            PrincipalField::Settings => 48,
             PrincipalField::Archive => ARCHIVE_FIELD,
         }
     }
@@ -51,7 +51,9 @@ pub enum DataType {
     ContactCard = 17,
     #[serde(rename = "FileNode")]
     FileNode = 18,
-    None = 19,
+    #[serde(rename = "Settings")]
+    Settings = 19,
+    None = 20,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -116,6 +118,7 @@ impl From<u64> for DataType {
             16 => DataType::AddressBook,
             17 => DataType::ContactCard,
             18 => DataType::FileNode,
+            19 => DataType::Settings,
             _ => {
                 debug_assert!(false, "Invalid type_state value: {}", value);
                 DataType::None
@@ -171,6 +174,7 @@ impl DataType {
             b"AddressBook" => DataType::AddressBook,
             b"ContactCard" => DataType::ContactCard,
             b"FileNode" => DataType::FileNode,
+            b"Settings" => DataType::Settings,
         )
     }
 
@@ -195,6 +199,7 @@ impl DataType {
             DataType::AddressBook => "AddressBook",
             DataType::ContactCard => "ContactCard",
             DataType::FileNode => "FileNode",
+            DataType::Settings => "Settings",
             DataType::None => "",
         }
     }
@@ -299,6 +299,12 @@ impl CacheItemWeight for IpAddr {
     }
 }
 
+impl CacheItemWeight for Vec<u8> {
+    fn weight(&self) -> u64 {
+        self.len() as u64 + std::mem::size_of::<Vec<u8>>() as u64
+    }
+}
+
 impl CacheItemWeight for bool {
     fn weight(&self) -> u64 {
         std::mem::size_of::<bool>() as u64
@@ -141,6 +141,38 @@ pub async fn wait_for_shutdown() {
     trc::event!(Server(trc::ServerEvent::Shutdown), CausedBy = signal);
 }
 
+// Sends a state notification to the service manager, if running under systemd
+#[cfg(target_os = "linux")]
+pub fn sd_notify(state: &str) {
+    use std::os::{
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    };
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let path = path.to_string_lossy();
+    let addr = if let Some(name) = path.strip_prefix('@') {
+        SocketAddr::from_abstract_name(name.as_bytes())
+    } else {
+        SocketAddr::from_pathname(&*path)
+    };
+
+    if let Err(err) = UnixDatagram::unbound()
+        .and_then(|socket| addr.and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr)))
+    {
+        trc::event!(
+            Server(trc::ServerEvent::ThreadError),
+            Details = "Failed to notify service manager",
+            Reason = err.to_string(),
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn sd_notify(_state: &str) {}
+
 pub fn rustls_client_config(allow_invalid_certs: bool) -> ClientConfig {
     let config = ClientConfig::builder();
 
@@ -147,13 +147,14 @@ ConditionPathExists=__PATH__/etc/config.toml
 After=network-online.target
 
 [Service]
-Type=simple
+Type=notify
 LimitNOFILE=65536
 KillMode=process
 KillSignal=SIGINT
 Restart=on-failure
 RestartSec=5
 ExecStart=__PATH__/bin/stalwart --config=__PATH__/etc/config.toml
+ExecReload=/bin/kill -HUP \$MAINPID
 SyslogIdentifier=stalwart
 User=stalwart
 Group=stalwart
@@ -39,6 +39,8 @@ use crate::{
 };
 
 pub mod broadcast;
+#[cfg(feature = "nats")]
+pub mod pubsub;
 pub mod stress;
 
 pub const NUM_NODES: usize = 3;
@@ -67,6 +69,7 @@ async fn init_cluster_tests(delete_if_exists: bool) -> ClusterTest {
     );
     let mut pubsub_config = match pubsub_id.as_str() {
         "nats" => Config::new(SERVER_NATS).unwrap(),
+        "nats-jetstream" => Config::new(SERVER_NATS_JETSTREAM).unwrap(),
         "redis" => Config::new(SERVER_REDIS).unwrap(),
         _ => panic!("Unsupported pubsub type: {}", pubsub_id),
     };
@@ -366,6 +369,14 @@ type = "nats"
 urls = "127.0.0.1:4444"
 "#;
 
+const SERVER_NATS_JETSTREAM: &str = r#"
+[store."nats-jetstream"]
+type = "nats"
+urls = "127.0.0.1:4444"
+jetstream.enable = true
+jetstream.stream = "stalwart-cluster"
+"#;
+
 const SERVER_REDIS: &str = r#"
 [store."redis"]
 type = "redis"
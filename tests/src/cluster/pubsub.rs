@@ -0,0 +1,87 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use store::{Stores, dispatch::pubsub::PubSubStream, write::now};
+use utils::config::Config;
+
+use crate::AssertConfig;
+
+const TOPIC: &str = "stalwart-pubsub-test";
+
+#[tokio::test]
+pub async fn nats_jetstream() {
+    let mut config = Config::new(
+        r#"
+[store."nats-core"]
+type = "nats"
+urls = "127.0.0.1:4444"
+
+[store."nats-durable"]
+type = "nats"
+urls = "127.0.0.1:4444"
+jetstream.enable = true
+jetstream.stream = "stalwart-pubsub-test"
+jetstream.consumer = "stalwart-pubsub-test"
+jetstream.max-age = "5m"
+jetstream.ack-wait = "5s"
+
+[store."nats-ephemeral"]
+type = "nats"
+urls = "127.0.0.1:4444"
+jetstream.enable = true
+jetstream.stream = "stalwart-pubsub-test"
+"#,
+    )
+    .unwrap();
+    let stores = Stores::parse_all(&mut config, false).await;
+    config.assert_no_errors();
+    let core = stores.pubsub_stores.get("nats-core").unwrap();
+    let durable = stores.pubsub_stores.get("nats-durable").unwrap();
+    let ephemeral = stores.pubsub_stores.get("nats-ephemeral").unwrap();
+    let nonce = format!("{}:", now());
+    let message = |name: &str| format!("{nonce}{name}").into_bytes();
+
+    // Durable consumers receive messages published before subscribing
+    durable.publish(TOPIC, message("m1")).await.unwrap();
+    let mut durable_stream = durable.subscribe(TOPIC).await.unwrap();
+    assert_eq!(next_message(&mut durable_stream, &nonce).await, "m1");
+
+    // Ephemeral consumers only receive new messages, which are also
+    // delivered to core NATS subscribers
+    let mut ephemeral_stream = ephemeral.subscribe(TOPIC).await.unwrap();
+    let mut core_stream = core.subscribe(TOPIC).await.unwrap();
+    ephemeral.publish(TOPIC, message("m2")).await.unwrap();
+    assert_eq!(next_message(&mut ephemeral_stream, &nonce).await, "m2");
+    assert_eq!(next_message(&mut core_stream, &nonce).await, "m2");
+    assert_eq!(next_message(&mut durable_stream, &nonce).await, "m2");
+
+    // Acknowledged messages are not redelivered to durable consumers
+    drop(durable_stream);
+    durable.publish(TOPIC, message("m3")).await.unwrap();
+    let mut durable_stream = durable.subscribe(TOPIC).await.unwrap();
+    assert_eq!(next_message(&mut durable_stream, &nonce).await, "m3");
+
+    // Ephemeral consumers keep receiving messages while subscribed
+    assert_eq!(next_message(&mut ephemeral_stream, &nonce).await, "m3");
+    assert_eq!(next_message(&mut core_stream, &nonce).await, "m3");
+}
+
+async fn next_message(stream: &mut PubSubStream, nonce: &str) -> String {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("Timed out waiting for message")
+            .expect("Stream closed");
+        let payload = String::from_utf8(message.payload().to_vec()).unwrap();
+
+        // Skip messages left over from previous runs
+        if let Some(name) = payload.strip_prefix(nonce) {
+            return name.to_string();
+        }
+    }
+}
@@ -23,6 +23,7 @@ use store::{
     roaring::RoaringBitmap,
     write::{BatchBuilder, BitmapClass, ValueClass},
 };
+use totp_rs::TOTP;
 use types::collection::Collection;
 
 #[tokio::test]
@@ -308,6 +309,53 @@ async fn internal_directory() {
             .collect::<AHashSet<_>>()
         );
 
+        // Set, add and remove custom attributes
+        assert!(
+            store
+                .update_principal(UpdatePrincipal::by_name("jane").with_updates(vec![
+                    PrincipalUpdate::set(
+                        PrincipalField::Attributes,
+                        PrincipalValue::StringList(vec![
+                            "costCenter=1234".into(),
+                            "vip=false".into()
+                        ]),
+                    ),
+                    PrincipalUpdate::add_item(
+                        PrincipalField::Attributes,
+                        PrincipalValue::String("vip=true".into()),
+                    ),
+                    PrincipalUpdate::add_item(
+                        PrincipalField::Attributes,
+                        PrincipalValue::String("tags=sales,emea".into()),
+                    ),
+                    PrincipalUpdate::remove_item(
+                        PrincipalField::Attributes,
+                        PrincipalValue::String("costCenter".into()),
+                    ),
+                ]))
+                .await
+                .is_ok()
+        );
+        let principal = store
+            .query(QueryParams::name("jane").with_return_member_of(false))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(principal.attribute("vip"), Some("true"));
+        assert_eq!(principal.attribute("tags"), Some("sales,emea"));
+        assert_eq!(principal.attribute("costCenter"), None);
+        assert!(
+            store
+                .update_principal(UpdatePrincipal::by_name("jane").with_updates(vec![
+                    PrincipalUpdate::add_item(
+                        PrincipalField::Attributes,
+                        PrincipalValue::String("invalid".into()),
+                    ),
+                ]))
+                .await
+                .is_err()
+        );
+
         // Create groups
         store
             .create_principal(
@@ -769,6 +817,145 @@ async fn internal_directory() {
     }
 }
 
+#[tokio::test]
+async fn internal_directory_rehash() {
+    const WEAK_SECRET: &str = "{SHA}jLIjfQZ5yojbZGTqxg2pY0VROWQ=";
+    const OTP_URL: &str =
+        "otpauth://totp/Stalwart:jane?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Stalwart";
+
+    let mut config = DirectoryTest::new("rocksdb".into()).await;
+    let directory = config
+        .directories
+        .directories
+        .remove("rocksdb-rehash")
+        .unwrap();
+    let store = config.stores.stores.get("rocksdb").unwrap();
+    store.destroy().await;
+
+    // Create an account with a SHA-1 password hash
+    let john_id = store
+        .create_principal(
+            TestPrincipal {
+                name: "john".into(),
+                secrets: vec![WEAK_SECRET.into()],
+                ..Default::default()
+            }
+            .into(),
+            None,
+            None,
+        )
+        .await
+        .unwrap()
+        .id;
+    let stored_secrets = async |account_id: u32| {
+        store
+            .query(QueryParams::id(account_id).with_return_member_of(false))
+            .await
+            .unwrap()
+            .unwrap()
+            .secrets
+    };
+
+    // Failed logins keep the existing hash
+    assert!(
+        directory
+            .query(QueryParams::credentials(&Credentials::Plain {
+                username: "john".into(),
+                secret: "wrong".into(),
+            }))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(stored_secrets(john_id).await, vec![WEAK_SECRET.to_string()]);
+
+    // A successful login replaces the weak hash
+    let principal = directory
+        .query(QueryParams::credentials(&Credentials::Plain {
+            username: "john".into(),
+            secret: "12345".into(),
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+    let secrets = stored_secrets(john_id).await;
+    assert_eq!(secrets.len(), 1);
+    assert!(secrets[0].starts_with("$6$"), "{secrets:?}");
+    assert_eq!(principal.secrets, secrets);
+
+    // The new hash is used for subsequent logins
+    assert!(
+        directory
+            .query(QueryParams::credentials(&Credentials::Plain {
+                username: "john".into(),
+                secret: "12345".into(),
+            }))
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(stored_secrets(john_id).await, secrets);
+
+    // Passwords followed by a TOTP token are rehashed without the token
+    let jane_id = store
+        .create_principal(
+            TestPrincipal {
+                name: "jane".into(),
+                secrets: vec![WEAK_SECRET.into(), OTP_URL.into()],
+                ..Default::default()
+            }
+            .into(),
+            None,
+            None,
+        )
+        .await
+        .unwrap()
+        .id;
+    let totp = TOTP::from_url(OTP_URL).unwrap();
+    assert!(
+        directory
+            .query(QueryParams::credentials(&Credentials::Plain {
+                username: "jane".into(),
+                secret: "12345".into(),
+            }))
+            .await
+            .is_err()
+    );
+    let secrets = stored_secrets(jane_id).await;
+    assert_eq!(secrets.len(), 2);
+    assert!(secrets.contains(&WEAK_SECRET.to_string()), "{secrets:?}");
+    assert!(
+        directory
+            .query(QueryParams::credentials(&Credentials::Plain {
+                username: "jane".into(),
+                secret: format!("12345${}", totp.generate_current().unwrap()),
+            }))
+            .await
+            .unwrap()
+            .is_some()
+    );
+    let secrets = stored_secrets(jane_id).await;
+    assert_eq!(secrets.len(), 2);
+    assert!(secrets.contains(&OTP_URL.to_string()), "{secrets:?}");
+    assert!(
+        secrets.iter().any(|secret| secret.starts_with("$6$")),
+        "{secrets:?}"
+    );
+
+    // The new hash matches the password alone
+    assert!(
+        directory
+            .query(QueryParams::credentials(&Credentials::Plain {
+                username: "jane".into(),
+                secret: format!("12345${}", totp.generate_current().unwrap()),
+            }))
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(stored_secrets(jane_id).await, secrets);
+}
+
 #[allow(async_fn_in_trait)]
 pub trait TestInternalDirectory {
     async fn create_test_user(&self, login: &str, secret: &str, name: &str, emails: &[&str])
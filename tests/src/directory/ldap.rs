@@ -46,6 +46,14 @@ async fn ldap_directory() {
                 .remove("ldap-bind-lookup")
                 .unwrap(),
         ),
+        (
+            "Failover",
+            config
+                .directories
+                .directories
+                .remove("ldap-failover")
+                .unwrap(),
+        ),
     ] {
         println!("Testing {auth_type} LDAP authentication...");
         assert_eq!(
@@ -8,6 +8,7 @@ pub mod imap;
 pub mod internal;
 pub mod ldap;
 pub mod oidc;
+pub mod rest;
 pub mod smtp;
 pub mod sql;
 
@@ -31,6 +32,12 @@ const CONFIG: &str = r#"
 type = "internal"
 store = "rocksdb"
 
+[directory."rocksdb-rehash"]
+type = "internal"
+store = "rocksdb"
+password.rehash.enable = true
+password.rehash.algorithm = "sha512-crypt"
+
 [directory."foundationdb"]
 type = "internal"
 store = "foundationdb"
@@ -47,6 +54,20 @@ email = "address"
 quota = "quota"
 class = "type"
 
+[directory."sqlite-rehash"]
+type = "sql"
+store = "sqlite"
+password.rehash.enable = true
+password.rehash.algorithm = "sha512-crypt"
+
+[directory."sqlite-rehash".columns]
+name = "name"
+description = "description"
+secret = "secret"
+email = "address"
+quota = "quota"
+class = "type"
+
 [store."rocksdb"]
 type = "rocksdb"
 path = "{TMP}/rocksdb"
@@ -66,6 +87,7 @@ emails = "SELECT address FROM emails WHERE name = ? AND type != 'list' ORDER BY
 verify = "SELECT address FROM emails WHERE address LIKE '%' || ? || '%' AND type = 'primary' ORDER BY address LIMIT 5"
 expand = "SELECT p.address FROM emails AS p JOIN emails AS l ON p.name = l.name WHERE p.type = 'primary' AND l.address = ? AND l.type = 'list' ORDER BY p.address LIMIT 50"
 domains = "SELECT 1 FROM emails WHERE address LIKE '%@' || ? LIMIT 1"
+update-secret = "UPDATE accounts SET secret = ? WHERE name = ?"
 
 [storage]
 lookup = "sqlite"
@@ -211,6 +233,33 @@ email-alias = "givenName"
 quota = "diskQuota"
 class = "objectClass"
 
+[directory."ldap-failover"]
+type = "ldap"
+url = ["ldap://127.0.0.1:1", "ldap://localhost:3893"]
+base-dn = "dc=example,dc=org"
+referrals = "follow"
+
+[directory."ldap-failover".bind]
+dn = "cn=serviceuser,ou=svcaccts,dc=example,dc=org"
+secret = "mysecret"
+
+[directory."ldap-failover".bind.auth]
+method = "lookup"
+
+[directory."ldap-failover".filter]
+name = "(&(|(objectClass=posixAccount)(objectClass=posixGroup))(uid=?))"
+email = "(&(|(objectClass=posixAccount)(objectClass=posixGroup))(|(mail=?)(givenName=?)(sn=?)))"
+
+[directory."ldap-failover".attributes]
+name = "uid"
+description = ["principalName", "description"]
+secret = "userPassword"
+groups = ["memberOf", "otherGroups"]
+email = "mail"
+email-alias = "givenName"
+quota = "diskQuota"
+class = "objectClass"
+
 ##############################################################################
 
 [directory."imap"]
@@ -348,6 +397,45 @@ fields.email = "email"
 fields.username = "preferred_username"
 fields.full-name = "name"
 
+##############################################################################
+
+[directory."rest"]
+type = "rest"
+store = "rocksdb"
+timeout = "1s"
+tls.allow-invalid-certs = true
+request.name.url = "https://127.0.0.1:9090/users/{name}"
+request.email.url = "https://127.0.0.1:9090/emails/{email}"
+request.members.url = "https://127.0.0.1:9090/lists/{email}"
+fields.name = "$.data.username"
+fields.email = "$.data.mail"
+fields.email-alias = "$.data.aliases"
+fields.description = "$.data.displayName"
+fields.secret = "$.data.password"
+fields.quota = "$.data.quota"
+fields.class = "$.data.role"
+fields.groups = "$.data.groups[*].name"
+fields.members = "$.members"
+
+[directory."rest-auth"]
+type = "rest"
+store = "rocksdb"
+timeout = "1s"
+tls.allow-invalid-certs = true
+request.name.url = "https://127.0.0.1:9090/users/{name}"
+request.email.url = "https://127.0.0.1:9090/emails/{email}"
+request.members.url = "https://127.0.0.1:9090/lists/{email}"
+request.authenticate.url = "https://127.0.0.1:9090/auth"
+request.authenticate.body = '{"user":"{name}","password":"{secret}"}'
+fields.name = "$.data.username"
+fields.email = "$.data.mail"
+fields.email-alias = "$.data.aliases"
+fields.description = "$.data.displayName"
+fields.quota = "$.data.quota"
+fields.class = "$.data.role"
+fields.groups = "$.data.groups[*].name"
+fields.members = "$.members"
+
 "#;
 
 pub struct DirectoryStore {
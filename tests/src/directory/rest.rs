@@ -0,0 +1,193 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use directory::{
+    QueryParams, Type,
+    backend::{RcptType, internal::manage::ManageDirectory},
+};
+use http_proto::{HttpResponse, JsonProblemResponse, JsonResponse, ToHttpResponse};
+use hyper::{Method, StatusCode};
+use mail_send::Credentials;
+use serde_json::json;
+use trc::{EventType, StoreEvent};
+
+use crate::{
+    directory::DirectoryTest,
+    http_server::{HttpMessage, spawn_mock_http_server},
+};
+
+#[tokio::test]
+async fn rest_directory() {
+    // Obtain directory handle
+    let mut config = DirectoryTest::new("rocksdb".into()).await;
+
+    // Spawn mock REST API
+    let _tx = spawn_mock_http_server(Arc::new(|req: HttpMessage| {
+        let mut path = req.uri.path().split('/').skip(1);
+
+        match (req.method.clone(), path.next(), path.next()) {
+            (Method::GET, Some("users"), Some("john")) => JsonResponse::new(json!({
+                "data": {
+                    "username": "john",
+                    "mail": ["John@Example.org"],
+                    "aliases": ["jdoe@example.org"],
+                    "displayName": "John Doe",
+                    "password": "12345",
+                    "quota": 500000,
+                    "role": "user",
+                    "groups": [{"name": "sales"}, {"name": "support"}],
+                }
+            }))
+            .into_http_response(),
+            (Method::GET, Some("users"), Some("noname")) => {
+                JsonResponse::new(json!({"data": {}})).into_http_response()
+            }
+            (Method::GET, Some("users"), Some("broken")) => {
+                HttpResponse::new(StatusCode::OK).with_text_body("not json")
+            }
+            (Method::GET, Some("users"), Some("error")) => {
+                JsonProblemResponse(StatusCode::INTERNAL_SERVER_ERROR).into_http_response()
+            }
+            (Method::GET, Some("emails"), Some("john%40example.org")) => JsonResponse::new(json!({
+                "data": {"username": "john"}
+            }))
+            .into_http_response(),
+            (Method::GET, Some("lists"), Some("info%40example.org")) => JsonResponse::new(json!({
+                "members": ["John@example.org", "jane@example.org"]
+            }))
+            .into_http_response(),
+            (Method::POST, Some("auth"), None) => {
+                assert_eq!(
+                    req.headers.get("content-type").map(|v| v.as_str()),
+                    Some("application/json"),
+                    "{req:?}"
+                );
+                let body = serde_json::from_slice::<serde_json::Value>(
+                    req.body.as_deref().unwrap_or_default(),
+                )
+                .unwrap();
+                if body == json!({"user": "john", "password": "12345"}) {
+                    JsonResponse::new(json!({})).into_http_response()
+                } else {
+                    JsonProblemResponse(StatusCode::UNAUTHORIZED).into_http_response()
+                }
+            }
+            (Method::GET, Some("users" | "emails" | "lists"), Some(_)) => {
+                JsonProblemResponse(StatusCode::NOT_FOUND).into_http_response()
+            }
+            _ => panic!("Unexpected request: {:?}", req),
+        }
+    }))
+    .await;
+
+    for test in ["rest", "rest-auth"] {
+        println!("Running REST directory test {test:?}...");
+        let directory = config.directories.directories.remove(test).unwrap();
+        let base_store = config.stores.stores.get("rocksdb").unwrap();
+
+        // Lookup by name maps the response fields
+        let principal = directory
+            .query(QueryParams::name("john").with_return_member_of(true))
+            .await
+            .unwrap()
+            .unwrap();
+        let john_id = base_store.get_principal_id("john").await.unwrap().unwrap();
+        assert_eq!(principal.id(), john_id);
+        assert_eq!(principal.name(), "john");
+        assert_eq!(principal.typ(), Type::Individual);
+        assert_eq!(principal.description(), Some("John Doe"));
+        assert_eq!(principal.quota(), 500000);
+        assert_eq!(
+            principal.emails,
+            vec![
+                "john@example.org".to_string(),
+                "jdoe@example.org".to_string()
+            ]
+        );
+        assert_eq!(principal.member_of().len(), 2);
+        for group in ["sales", "support"] {
+            let group_id = base_store.get_principal_id(group).await.unwrap().unwrap();
+            assert!(principal.member_of().contains(&group_id), "{group}");
+        }
+
+        // Missing entries and responses without a name are not found
+        for name in ["unknown", "noname"] {
+            assert!(
+                directory
+                    .query(QueryParams::name(name).with_return_member_of(false))
+                    .await
+                    .unwrap()
+                    .is_none(),
+                "{name}"
+            );
+        }
+
+        // Server errors and invalid responses are reported
+        for name in ["error", "broken"] {
+            let err = directory
+                .query(QueryParams::name(name).with_return_member_of(false))
+                .await
+                .unwrap_err();
+            assert!(
+                err.matches(EventType::Store(StoreEvent::RestError)),
+                "Unexpected error for {name}: {err:?}"
+            );
+        }
+
+        // Authentication, either by the secret field or the authentication request
+        for (secret, expected) in [("12345", true), ("wrong", false)] {
+            assert_eq!(
+                directory
+                    .query(
+                        QueryParams::credentials(&Credentials::Plain {
+                            username: "john".into(),
+                            secret: secret.into(),
+                        })
+                        .with_return_member_of(false)
+                    )
+                    .await
+                    .unwrap()
+                    .map(|p| p.id()),
+                expected.then_some(john_id),
+                "{secret}"
+            );
+        }
+
+        // Address lookups
+        assert_eq!(
+            directory.email_to_id("john@example.org").await.unwrap(),
+            Some(john_id)
+        );
+        assert_eq!(
+            directory.email_to_id("nobody@example.org").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            directory.rcpt("john@example.org").await.unwrap(),
+            RcptType::Mailbox
+        );
+        assert_eq!(
+            directory.rcpt("info@example.org").await.unwrap(),
+            RcptType::List(vec![
+                "john@example.org".to_string(),
+                "jane@example.org".to_string()
+            ])
+        );
+        assert_eq!(
+            directory.rcpt("nobody@example.org").await.unwrap(),
+            RcptType::Invalid
+        );
+        assert_eq!(
+            directory.expn("info@example.org").await.unwrap(),
+            vec![
+                "john@example.org".to_string(),
+                "jane@example.org".to_string()
+            ]
+        );
+    }
+}
@@ -5,13 +5,14 @@
  */
 
 use directory::{
-    QueryParams, ROLE_USER, Type,
+    Directories, QueryParams, ROLE_USER, Type,
     backend::{RcptType, internal::manage::ManageDirectory},
 };
 use mail_send::Credentials;
 
 #[allow(unused_imports)]
-use store::{InMemoryStore, Store};
+use store::{InMemoryStore, Store, Stores};
+use utils::config::ConfigWarning;
 
 use crate::directory::{
     DirectoryTest, IntoTestPrincipal, TestPrincipal, map_account_id, map_account_ids,
@@ -353,6 +354,121 @@ async fn sql_directory() {
     }
 }
 
+#[tokio::test]
+async fn sql_directory_rehash() {
+    // SHA-1 digest of "12345"
+    const WEAK_SECRET: &str = "{SHA}jLIjfQZ5yojbZGTqxg2pY0VROWQ=";
+
+    let mut config = DirectoryTest::new("sqlite".into()).await;
+    let handle = config
+        .directories
+        .directories
+        .remove("sqlite-rehash")
+        .unwrap();
+    assert!(handle.rehash_secrets.is_some());
+    let lookup = config.directories.directories.remove("sqlite").unwrap();
+    let store = DirectoryStore {
+        store: config.stores.stores.remove("sqlite").unwrap(),
+    };
+    store.store.destroy().await;
+    store.create_test_directory().await;
+    store
+        .create_test_user("john", WEAK_SECRET, "John Doe")
+        .await;
+    let stored_secrets = async || {
+        lookup
+            .query(QueryParams::name("john").with_return_member_of(false))
+            .await
+            .unwrap()
+            .unwrap()
+            .secrets
+    };
+
+    // Failed logins keep the existing hash
+    assert!(
+        handle
+            .query(QueryParams::credentials(&Credentials::Plain {
+                username: "john".into(),
+                secret: "wrong".into(),
+            }))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(stored_secrets().await, vec![WEAK_SECRET.to_string()]);
+
+    // A successful login replaces the weak hash using the update query
+    let principal = handle
+        .query(QueryParams::credentials(&Credentials::Plain {
+            username: "john".into(),
+            secret: "12345".into(),
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+    let secrets = stored_secrets().await;
+    assert_eq!(secrets.len(), 1);
+    assert!(secrets[0].starts_with("$6$"), "{secrets:?}");
+    assert_eq!(principal.secrets, secrets);
+    assert!(
+        handle
+            .query(QueryParams::credentials(&Credentials::Plain {
+                username: "john".into(),
+                secret: "12345".into(),
+            }))
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(stored_secrets().await, secrets);
+
+    // Rehashing is disabled for SQL directories without an update query
+    let mut config = utils::config::Config::new(format!(
+        r#"
+[store."sqlite"]
+type = "sqlite"
+path = "{}/rehash.db"
+
+[store."sqlite".query]
+name = "SELECT name, type, secret, description, quota FROM accounts WHERE name = ?"
+
+[directory."sqlite"]
+type = "sql"
+store = "sqlite"
+password.rehash.enable = true
+
+[directory."memory"]
+type = "memory"
+password.rehash.enable = true
+"#,
+        config.temp_dir.path.to_string_lossy()
+    ))
+    .unwrap();
+    let stores = Stores::parse_all(&mut config, false).await;
+    let directories = Directories::parse(
+        &mut config,
+        &stores,
+        stores.stores.get("sqlite").unwrap().clone(),
+        true,
+    )
+    .await;
+    for (id, expected) in [
+        ("sqlite", "requires an 'update-secret' query"),
+        ("memory", "only supported by internal and SQL directories"),
+    ] {
+        assert!(directories.directories[id].rehash_secrets.is_none(), "{id}");
+        match config
+            .warnings
+            .get(&format!("directory.{id}.password.rehash.enable"))
+        {
+            Some(ConfigWarning::Build { error }) => {
+                assert!(error.contains(expected), "{id}: {error}")
+            }
+            warning => panic!("{id}: unexpected warning {warning:?}"),
+        }
+    }
+}
+
 impl DirectoryStore {
     pub async fn create_test_directory(&self) {
         // Create tables
@@ -6,9 +6,11 @@
 
 use std::{fs, io};
 
+use common::config::jmap::mailbox::MailboxLimit;
 use imap_proto::ResponseType;
+use utils::config::Config;
 
-use crate::jmap::wait_for_index;
+use crate::{AssertConfig, jmap::wait_for_index};
 
 use super::{AssertResult, IMAPTest, ImapConnection, Type, resources_dir};
 
@@ -65,6 +67,57 @@ pub async fn test(imap: &mut ImapConnection, _imap_check: &mut ImapConnection, h
     }
 
     wait_for_index(&handle.server).await;
+
+    // Enable folder limits
+    let original_core = handle.server.inner.shared_core.load_full();
+    let mut config = Config::new(FOLDER_LIMITS).unwrap();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mailbox_limits = MailboxLimit::parse(&mut config);
+    config.assert_no_errors();
+    handle.server.inner.shared_core.store(core.into());
+
+    // Sessions load the configuration on connect
+    let mut imap_limits = ImapConnection::connect(b"_l ").await;
+    imap_limits
+        .assert_read(Type::Untagged, ResponseType::Ok)
+        .await;
+    imap_limits.authenticate("jdoe@example.com", "secret").await;
+    for mailbox in ["Limited", "Rolling"] {
+        imap_limits.send(&format!("CREATE \"{mailbox}\"")).await;
+        imap_limits
+            .assert_read(Type::Tagged, ResponseType::Ok)
+            .await;
+    }
+
+    // Messages exceeding the limit are rejected
+    let message = "From: limits@example.com\r\nSubject: Folder limits\r\n\r\nTest\r\n";
+    assert_append_message(&mut imap_limits, "Limited", message, ResponseType::Ok).await;
+    assert_append_message(&mut imap_limits, "Limited", message, ResponseType::No)
+        .await
+        .assert_response_code("OVERQUOTA");
+
+    // Or rolled over to the overflow folder, which is created on demand
+    for _ in 0..2 {
+        assert_append_message(&mut imap_limits, "Rolling", message, ResponseType::Ok).await;
+    }
+    for mailbox in ["Rolling", "Rolling Overflow"] {
+        imap_limits
+            .send(&format!("STATUS \"{mailbox}\" (MESSAGES)"))
+            .await;
+        imap_limits
+            .assert_read(Type::Tagged, ResponseType::Ok)
+            .await
+            .assert_contains("MESSAGES 1");
+    }
+
+    // Cleanup
+    handle.server.inner.shared_core.store(original_core);
+    for mailbox in ["Limited", "Rolling", "Rolling Overflow"] {
+        imap_limits.send(&format!("DELETE \"{mailbox}\"")).await;
+        imap_limits
+            .assert_read(Type::Tagged, ResponseType::Ok)
+            .await;
+    }
 }
 
 pub async fn assert_append_message(
@@ -108,3 +161,15 @@ pub fn build_messages() -> Vec<String> {
     }
     messages
 }
+
+const FOLDER_LIMITS: &str = r#"
+[email.folder-limit.limited]
+path = "Limited"
+max-messages = 1
+
+[email.folder-limit.rolling]
+path = "Rolling"
+max-messages = 1
+overflow = "folder"
+overflow-folder = "Rolling Overflow"
+"#;
@@ -0,0 +1,93 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::listener::clients::{ClientState, ConnectedClientInfo};
+use imap_proto::ResponseType;
+
+use crate::imap::{
+    Type,
+    pop::{self, Pop3Connection},
+};
+
+use super::{IMAPTest, ImapConnection};
+
+pub async fn test(handle: &IMAPTest) {
+    println!("Running connected clients tests...");
+    let clients = &handle.server.inner.data.connected_clients;
+
+    // Authenticated IMAP sessions are tracked with their account
+    let mut imap = ImapConnection::connect(b"_x ").await;
+    imap.assert_read(Type::Untagged, ResponseType::Ok).await;
+    imap.authenticate("jane.smith@example.com", "secret").await;
+    let client = client_info(handle, "jane.smith@example.com");
+    assert_eq!(client.protocol, "imap");
+    assert_eq!(client.state, ClientState::Authenticated);
+    assert_eq!(client.mailbox, None);
+    assert!(client.account_id.is_some());
+
+    // Selecting a mailbox updates the session state
+    imap.send("SELECT INBOX").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    let client = client_info(handle, "jane.smith@example.com");
+    assert_eq!(client.state, ClientState::Selected);
+    assert_eq!(client.mailbox.as_deref(), Some("INBOX"));
+
+    // Sessions in IDLE are reported as such
+    imap.send("IDLE").await;
+    imap.assert_read(Type::Continuation, ResponseType::Ok).await;
+    let client = client_info(handle, "jane.smith@example.com");
+    assert_eq!(client.state, ClientState::Idle);
+
+    // Disconnect the session
+    assert!(clients.disconnect(client.session_id));
+    imap.assert_read(Type::Untagged, ResponseType::Bye).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(clients.get(client.session_id).is_none());
+    assert!(!clients.disconnect(client.session_id));
+
+    // POP3 sessions are tracked as well
+    let mut pop3 = Pop3Connection::connect_and_login().await;
+    let client = client_info(handle, "popper@example.com");
+    assert_eq!(client.protocol, "pop3");
+    assert_eq!(client.state, ClientState::Selected);
+    assert_eq!(client.mailbox.as_deref(), Some("INBOX"));
+
+    // Disconnect all sessions belonging to an account
+    let mut imap = ImapConnection::connect(b"_x ").await;
+    imap.assert_read(Type::Untagged, ResponseType::Ok).await;
+    imap.authenticate("popper@example.com", "secret").await;
+    assert_eq!(
+        clients.disconnect_all(|client| client
+            .account()
+            .is_some_and(|account| account.name == "popper@example.com")),
+        2
+    );
+    imap.assert_read(Type::Untagged, ResponseType::Bye).await;
+    pop3.assert_read(pop::ResponseType::Err).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(
+        clients
+            .list()
+            .iter()
+            .all(|client| client.account_name.as_deref() != Some("popper@example.com"))
+    );
+}
+
+fn client_info(handle: &IMAPTest, account: &str) -> ConnectedClientInfo {
+    let mut clients = handle
+        .server
+        .inner
+        .data
+        .connected_clients
+        .list()
+        .into_iter()
+        .filter(|client| client.account_name.as_deref() == Some(account))
+        .collect::<Vec<_>>();
+    assert_eq!(clients.len(), 1, "{clients:?}");
+    clients.pop().unwrap()
+}
@@ -0,0 +1,108 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use imap_proto::ResponseType;
+
+use super::{AssertResult, ImapConnection, Type};
+
+pub async fn test(imap: &mut ImapConnection, imap_check: &mut ImapConnection) {
+    println!("Running METADATA tests...");
+
+    // Shared server annotations can only be modified by administrators
+    imap.send("SETMETADATA \"\" (/shared/comment \"Hi\")").await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_response_code("NOPERM");
+    let mut imap_admin = ImapConnection::connect(b"_a ").await;
+    imap_admin
+        .assert_read(Type::Untagged, ResponseType::Ok)
+        .await;
+    imap_admin
+        .send("AUTHENTICATE PLAIN {20+}\r\nAGFkbWluAHNlY3JldA==")
+        .await;
+    imap_admin.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap_admin
+        .send("SETMETADATA \"\" (/private/vendor/webmail/theme \"light\" /shared/comment \"Hi\")")
+        .await;
+    imap_admin.assert_read(Type::Tagged, ResponseType::Ok).await;
+
+    // Private server annotations belong to each user, shared ones are server-wide
+    imap.send("SETMETADATA \"\" (/private/vendor/webmail/theme \"dark\")")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap_check
+        .send("GETMETADATA \"\" (/private/vendor/webmail/theme /shared/comment)")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\"/private/vendor/webmail/theme\" \"dark\"")
+        .assert_contains("\"/shared/comment\" \"Hi\"");
+    imap_admin
+        .send("GETMETADATA \"\" /private/vendor/webmail/theme")
+        .await;
+    imap_admin
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\"/private/vendor/webmail/theme\" \"light\"");
+    imap_admin
+        .send("SETMETADATA \"\" (/private/vendor/webmail/theme NIL /shared/comment NIL)")
+        .await;
+    imap_admin.assert_read(Type::Tagged, ResponseType::Ok).await;
+
+    // Mailbox annotations
+    imap.send("SETMETADATA INBOX (/private/color \"#ff0000\" /private/color/alt \"red\")")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("GETMETADATA INBOX /private/color").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("* METADATA \"INBOX\" (\"/private/color\" \"#ff0000\")");
+    imap.send("GETMETADATA (DEPTH 1) INBOX /private/color")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\"/private/color/alt\" \"red\"");
+    imap.send("GETMETADATA (MAXSIZE 4) INBOX /private/color")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("[METADATA LONGENTRIES 7]");
+
+    // Missing entries are returned as NIL
+    imap.send("GETMETADATA INBOX /shared/comment").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\"/shared/comment\" NIL");
+
+    // Remove annotations
+    imap.send("SETMETADATA INBOX (/private/color NIL /private/color/alt NIL)")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("GETMETADATA INBOX /private/color").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\"/private/color\" NIL");
+
+    // Invalid entries and mailboxes
+    imap.send("GETMETADATA INBOX /color").await;
+    imap.assert_read(Type::Tagged, ResponseType::Bad).await;
+    imap.send("SETMETADATA \"Does not exist\" (/private/color \"blue\")")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_response_code("NONEXISTENT");
+
+    // Entry size limits
+    imap.send(&format!(
+        "SETMETADATA INBOX (/private/comment \"{}\")",
+        "a".repeat(9000)
+    ))
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_contains("[METADATA MAXSIZE 8192]");
+}
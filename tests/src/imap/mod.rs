@@ -9,12 +9,14 @@ pub mod append;
 pub mod basic;
 pub mod bayes;
 pub mod body_structure;
+pub mod clients;
 pub mod condstore;
 pub mod copy_move;
 pub mod fetch;
 pub mod idle;
 pub mod mailbox;
 pub mod managesieve;
+pub mod metadata;
 pub mod pop;
 pub mod search;
 pub mod store;
@@ -99,6 +101,7 @@ pub async fn imap_tests() {
     idle::test(&mut imap, &mut imap_check, false).await;
     condstore::test(&mut imap, &mut imap_check).await;
     acl::test(&mut imap, &mut imap_check).await;
+    metadata::test(&mut imap, &mut imap_check).await;
 
     // Logout
     for imap in [&mut imap, &mut imap_check] {
@@ -112,6 +115,9 @@ pub async fn imap_tests() {
     // Bayes training
     bayes::test(&handle).await;
 
+    // Connected clients registry
+    clients::test(&handle).await;
+
     // Run ManageSieve tests
     managesieve::test().await;
 
@@ -787,6 +793,9 @@ throttle = "500ms"
 throttle = "500ms"
 attempts.interval = "500ms"
 
+[email.keywords]
+max-per-message = 20
+
 [email.folders.inbox]
 name = "Inbox"
 subscribe = false
@@ -74,4 +74,20 @@ pub async fn test(imap: &mut ImapConnection, _imap_check: &mut ImapConnection, h
         .await
         .assert_count("FLAGS", 3)
         .assert_count("Answered", 0);
+
+    // Keywords over the per-message limit are rejected
+    let flags = (0..21)
+        .map(|i| format!("$label{i}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    imap.send(&format!("UID STORE 1 +FLAGS ({flags})")).await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_contains("[LIMIT]");
+    imap.send("UID STORE 1 +FLAGS ($label0 $label1)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("$label1");
+    imap.send("UID STORE 1 -FLAGS ($label0 $label1)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
 }
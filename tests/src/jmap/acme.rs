@@ -0,0 +1,190 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use common::{
+    KV_ACME,
+    listener::{
+        acme::{ChallengeSettings, Http01Delegate},
+        tls::AcmeProviders,
+    },
+};
+use http_proto::HttpResponse;
+use hyper::{Method, StatusCode};
+use store::{dispatch::lookup::KeyValue, parking_lot::Mutex};
+use utils::config::Config;
+
+use crate::{
+    http_server::{HttpMessage, spawn_mock_http_server},
+    jmap::JMAPTest,
+};
+
+const CONFIG: &str = r#"
+[acme.push]
+directory = "https://acme.example.org/directory"
+contact = "postmaster@example.org"
+domains = ["mx.example.org"]
+challenge = "http-01"
+http-01.delegate = "push"
+http-01.url = "https://127.0.0.1:9090/acme/{token}"
+http-01.headers = ["X-Api-Key: abc"]
+http-01.auth.token = "secret"
+http-01.allow-invalid-certs = true
+http-01.timeout = "5s"
+
+[acme.alias]
+directory = "https://acme.example.org/directory"
+contact = "postmaster@example.org"
+domains = ["mail.example.org"]
+challenge = "http-01"
+http-01.delegate = "alias"
+http-01.alias = "Challenges.Example.NET."
+
+[acme.sftp]
+directory = "https://acme.example.org/directory"
+contact = "postmaster@example.org"
+domains = ["smtp.example.org"]
+challenge = "http-01"
+http-01.delegate = "sftp"
+
+[acme.ftp]
+directory = "https://acme.example.org/directory"
+contact = "postmaster@example.org"
+domains = ["imap.example.org"]
+challenge = "http-01"
+http-01.delegate = "push"
+http-01.url = "ftp://127.0.0.1/acme"
+"#;
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running ACME challenge delegation tests...");
+
+    // Parse delegation settings
+    let mut config = Config::new(CONFIG).unwrap();
+    let acme = AcmeProviders::parse(&mut config);
+    for key in ["acme.sftp.http-01.delegate", "acme.ftp.http-01.url"] {
+        assert!(config.errors.contains_key(key), "{:?}", config.errors);
+    }
+    assert!(!acme.providers.contains_key("sftp"));
+    assert!(!acme.providers.contains_key("ftp"));
+    let provider = &acme.providers["push"];
+    let delegate = match &provider.challenge {
+        ChallengeSettings::Http01 {
+            delegate:
+                Some(
+                    delegate @ Http01Delegate::Push {
+                        url,
+                        headers,
+                        timeout,
+                        tls_allow_invalid_certs,
+                    },
+                ),
+        } => {
+            assert_eq!(url, "https://127.0.0.1:9090/acme/{token}");
+            assert_eq!(headers.get("x-api-key").unwrap(), "abc");
+            assert_eq!(headers.get("authorization").unwrap(), "Bearer secret");
+            assert_eq!(*timeout, Duration::from_secs(5));
+            assert!(*tls_allow_invalid_certs);
+            delegate.clone()
+        }
+        _ => panic!("Expected a push delegation"),
+    };
+    assert!(matches!(
+        &acme.providers["alias"].challenge,
+        ChallengeSettings::Http01 {
+            delegate: Some(Http01Delegate::Alias { hostname }),
+        } if hostname == "challenges.example.net"
+    ));
+
+    // Tokens are uploaded to and removed from the remote web server
+    let requests = Arc::new(Mutex::new(Vec::<HttpMessage>::new()));
+    let requests_ = requests.clone();
+    let _tx = spawn_mock_http_server(Arc::new(move |req: HttpMessage| {
+        let status = if req.uri.path().ends_with("/fail") {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        };
+        requests_.lock().push(req);
+        HttpResponse::new(status)
+    }))
+    .await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    delegate
+        .publish(provider, "localhost", "token1", b"token1.proof")
+        .await
+        .unwrap();
+    delegate.unpublish(provider, "token1").await;
+    assert!(
+        delegate
+            .publish(provider, "localhost", "fail", b"fail.proof")
+            .await
+            .is_err()
+    );
+    {
+        let requests = std::mem::take(&mut *requests.lock());
+        assert_eq!(requests.len(), 3, "{requests:?}");
+        let upload = &requests[0];
+        assert_eq!(upload.method, Method::PUT);
+        assert_eq!(upload.uri.path(), "/acme/token1");
+        assert_eq!(upload.body.as_deref(), Some(&b"token1.proof"[..]));
+        assert_eq!(upload.headers["content-type"], "text/plain");
+        assert_eq!(upload.headers["x-api-key"], "abc");
+        assert_eq!(upload.headers["authorization"], "Bearer secret");
+        let removal = &requests[1];
+        assert_eq!(removal.method, Method::DELETE);
+        assert_eq!(removal.uri.path(), "/acme/token1");
+        assert_eq!(removal.headers["authorization"], "Bearer secret");
+        assert_eq!(requests[2].uri.path(), "/acme/fail");
+    }
+
+    // Challenges redirected to the alias hostname are answered by this server
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.acme = acme;
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.inner.build_server();
+    server
+        .core
+        .storage
+        .lookup
+        .key_set(KeyValue::with_prefix(KV_ACME, "token2", b"token2.proof".to_vec()).expires(60))
+        .await
+        .unwrap();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    for (token, expected) in [("token2", Some("token2.proof")), ("token3", None)] {
+        let response = client
+            .get(format!(
+                "https://127.0.0.1:8899/.well-known/acme-challenge/{token}"
+            ))
+            .header("Host", "challenges.example.net")
+            .send()
+            .await
+            .unwrap();
+        if let Some(expected) = expected {
+            assert_eq!(response.status().as_u16(), 200);
+            assert_eq!(response.text().await.unwrap(), expected);
+        } else {
+            assert_eq!(response.status().as_u16(), 404);
+        }
+    }
+
+    // Cleanup
+    params.server.inner.shared_core.store(original_core);
+    server
+        .core
+        .storage
+        .lookup
+        .key_delete_prefix(&[KV_ACME])
+        .await
+        .unwrap();
+}
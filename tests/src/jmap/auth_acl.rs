@@ -6,7 +6,9 @@
 
 use crate::{
     directory::internal::TestInternalDirectory,
-    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, test_account_login},
+    jmap::{
+        assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes, test_account_login,
+    },
 };
 use ::email::mailbox::{INBOX_ID, TRASH_ID};
 use jmap_client::{
@@ -225,6 +227,55 @@ pub async fn test(params: &mut JMAPTest) {
             .is_none()
     );
 
+    // Querying all accounts should include John's emails and Jane's shared Inbox
+    let response = jmap_json_request(
+        format!(r#"[["Email/query", {{"accountId": "{john_id}", "allAccounts": true}}, "c1"]]"#),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let response = &response["methodResponses"][0][1];
+    let ids = response["ids"].as_array().unwrap();
+    let account_ids = response["accountIds"].as_array().unwrap();
+    assert_eq!(ids.len(), account_ids.len());
+    let mut results = account_ids
+        .iter()
+        .zip(ids)
+        .map(|(account_id, id)| {
+            (
+                account_id.as_str().unwrap().to_string(),
+                id.as_str().unwrap().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    results.sort_unstable();
+    let mut expected = vec![
+        (john_id.to_string(), email_ids["john"][0].clone()),
+        (john_id.to_string(), email_ids["john"][1].clone()),
+        (jane_id.to_string(), email_ids["jane"][0].clone()),
+    ];
+    expected.sort_unstable();
+    assert_eq!(results, expected);
+
+    // Account specific filters are not supported when querying all accounts
+    let response = jmap_json_request(
+        format!(
+            concat!(
+                r#"[["Email/query", {{"accountId": "{}", "allAccounts": true, "#,
+                r#""filter": {{"inMailbox": "{}"}}}}, "c1"]]"#
+            ),
+            john_id, inbox_id
+        ),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response["methodResponses"][0][0], "error");
+    assert_eq!(
+        response["methodResponses"][0][1]["type"],
+        "unsupportedFilter"
+    );
+
     // John should only be able to copy blobs he has access to
     let blob_id = jane_client
         .email_get(
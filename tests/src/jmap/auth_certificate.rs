@@ -0,0 +1,498 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{JMAPTest, ManagementApi};
+use crate::directory::internal::TestInternalDirectory;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use common::auth::certificate::{ClientCertMapping, ClientCertificate};
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use rustls::{
+    SignatureScheme, client::ResolvesClientCert, crypto::ring::sign::any_supported_type,
+    sign::CertifiedKey,
+};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject};
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tokio_rustls::{TlsConnector, client::TlsStream};
+
+const REMOTE_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running client certificate authentication tests...");
+    let server = params.server.clone();
+    let account_id = server
+        .store()
+        .create_test_user(
+            "certuser@example.com",
+            "secret",
+            "Certificate User",
+            &["certuser@example.com"],
+        )
+        .await;
+    server
+        .store()
+        .create_test_user(
+            "certother@example.com",
+            "secret",
+            "Other User",
+            &["certother@example.com"],
+        )
+        .await;
+
+    // Identities are read from the subject alternative names and the subject
+    let user_cert = ClientCertificate::parse(&certificate_der("user")).unwrap();
+    let device_cert = ClientCertificate::parse(&certificate_der("device")).unwrap();
+    assert_eq!(user_cert.emails, vec!["certuser@example.com".to_string()]);
+    assert_eq!(user_cert.common_name.as_deref(), Some("Cert User"));
+    assert!(device_cert.emails.is_empty());
+    assert_eq!(device_cert.common_name.as_deref(), Some("device01"));
+    for cert in [&user_cert, &device_cert] {
+        assert_eq!(cert.fingerprint.len(), 64);
+        assert!(cert.fingerprint.bytes().all(|ch| ch.is_ascii_hexdigit()));
+    }
+    assert_ne!(user_cert.fingerprint, device_cert.fingerprint);
+
+    // Email mapping resolves the principal owning the certificate's address
+    let access_token = server
+        .authenticate_certificate(&user_cert, ClientCertMapping::Email, None, 0, REMOTE_IP)
+        .await
+        .unwrap();
+    assert_eq!(access_token.primary_id(), account_id);
+    assert_eq!(
+        server
+            .authenticate_certificate(
+                &user_cert,
+                ClientCertMapping::Email,
+                Some("CertUser@example.com"),
+                0,
+                REMOTE_IP
+            )
+            .await
+            .unwrap()
+            .primary_id(),
+        account_id
+    );
+
+    // An authorization identity other than the certificate's owner is rejected
+    for authzid in ["certother@example.com", "unknown@example.com"] {
+        assert!(
+            server
+                .authenticate_certificate(
+                    &user_cert,
+                    ClientCertMapping::Email,
+                    Some(authzid),
+                    0,
+                    REMOTE_IP
+                )
+                .await
+                .unwrap_err()
+                .matches(trc::EventType::Auth(trc::AuthEvent::Failed))
+        );
+    }
+    assert!(
+        server
+            .authenticate_certificate(&device_cert, ClientCertMapping::Email, None, 0, REMOTE_IP)
+            .await
+            .is_err()
+    );
+
+    // Fingerprint mapping requires the fingerprint to be registered
+    assert!(
+        server
+            .authenticate_certificate(
+                &user_cert,
+                ClientCertMapping::Fingerprint,
+                None,
+                0,
+                REMOTE_IP
+            )
+            .await
+            .is_err()
+    );
+    let api = ManagementApi::new(8899, "certuser@example.com", "secret");
+    add_certificate(&api, "laptop", &device_cert.fingerprint).await;
+    assert_eq!(
+        server
+            .authenticate_certificate(
+                &device_cert,
+                ClientCertMapping::Fingerprint,
+                Some("certuser@example.com"),
+                0,
+                REMOTE_IP
+            )
+            .await
+            .unwrap()
+            .primary_id(),
+        account_id
+    );
+    assert!(
+        server
+            .authenticate_certificate(
+                &device_cert,
+                ClientCertMapping::Fingerprint,
+                Some("certother@example.com"),
+                0,
+                REMOTE_IP
+            )
+            .await
+            .is_err()
+    );
+
+    // SASL EXTERNAL over SMTP using fingerprint mapping
+    let mut smtp = TestTlsClient::connect(4465, Some("device")).await;
+    smtp.assert_smtp("220").await;
+    smtp.send("EHLO mx.example.org").await;
+    smtp.assert_smtp("250").await.assert_contains("EXTERNAL");
+    smtp.send(&format!(
+        "AUTH EXTERNAL {}",
+        STANDARD.encode("certother@example.com")
+    ))
+    .await;
+    smtp.assert_smtp("535").await;
+    smtp.send("AUTH EXTERNAL").await;
+    smtp.assert_smtp("334").await;
+    smtp.send("=").await;
+    smtp.assert_smtp("535").await;
+    smtp.send("AUTH EXTERNAL").await;
+    smtp.assert_smtp("334").await;
+    smtp.send(&STANDARD.encode("certuser@example.com")).await;
+    smtp.assert_smtp("235").await;
+
+    // The user certificate is not registered yet
+    let mut smtp = TestTlsClient::connect(4465, Some("user")).await;
+    smtp.assert_smtp("220").await;
+    smtp.send("EHLO mx.example.org").await;
+    smtp.assert_smtp("250").await;
+    smtp.send("AUTH EXTERNAL").await;
+    smtp.assert_smtp("334").await;
+    smtp.send("=").await;
+    smtp.assert_smtp("535").await;
+
+    // Removing a certificate does not remove others sharing its name as prefix
+    add_certificate(&api, "laptop2", &user_cert.fingerprint).await;
+    assert_eq!(list_certificates(&api).await, vec!["laptop", "laptop2"]);
+    assert!(
+        server
+            .authenticate_certificate(
+                &user_cert,
+                ClientCertMapping::Fingerprint,
+                Some("certother@example.com"),
+                0,
+                REMOTE_IP
+            )
+            .await
+            .unwrap_err()
+            .matches(trc::EventType::Auth(trc::AuthEvent::Failed))
+    );
+    api.post::<()>(
+        "/api/account/auth",
+        &json!([{"type": "removeClientCertificate", "name": "laptop"}]),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    assert_eq!(list_certificates(&api).await, vec!["laptop2"]);
+
+    let mut smtp = TestTlsClient::connect(4465, Some("user")).await;
+    smtp.assert_smtp("220").await;
+    smtp.send("EHLO mx.example.org").await;
+    smtp.assert_smtp("250").await;
+    smtp.send(&format!(
+        "AUTH EXTERNAL {}",
+        STANDARD.encode("certother@example.com")
+    ))
+    .await;
+    smtp.assert_smtp("535").await;
+    smtp.send("AUTH EXTERNAL").await;
+    smtp.assert_smtp("334").await;
+    smtp.send("").await;
+    smtp.assert_smtp("235").await;
+
+    let mut smtp = TestTlsClient::connect(4465, Some("device")).await;
+    smtp.assert_smtp("220").await;
+    smtp.send("EHLO mx.example.org").await;
+    smtp.assert_smtp("250").await;
+    smtp.send(&format!(
+        "AUTH EXTERNAL {}",
+        STANDARD.encode("certuser@example.com")
+    ))
+    .await;
+    smtp.assert_smtp("535").await;
+
+    // EXTERNAL is not offered without a client certificate
+    let mut smtp = TestTlsClient::connect(4465, None).await;
+    smtp.assert_smtp("220").await;
+    smtp.send("EHLO mx.example.org").await;
+    smtp.assert_smtp("250")
+        .await
+        .assert_not_contains("EXTERNAL");
+
+    // SASL EXTERNAL over IMAP using email mapping
+    let mut imap = TestTlsClient::connect(9993, Some("user")).await;
+    imap.assert_imap("*", "OK").await;
+    imap.send("A1 CAPABILITY").await;
+    imap.assert_imap("A1", "OK")
+        .await
+        .assert_contains("AUTH=EXTERNAL");
+    imap.send(&format!(
+        "A2 AUTHENTICATE EXTERNAL {}",
+        STANDARD.encode("certother@example.com")
+    ))
+    .await;
+    imap.assert_imap("A2", "NO").await;
+    imap.send("A3 AUTHENTICATE EXTERNAL =").await;
+    imap.assert_imap("A3", "OK").await;
+    imap.send("A4 SELECT INBOX").await;
+    imap.assert_imap("A4", "OK").await;
+
+    let mut imap = TestTlsClient::connect(9993, Some("user")).await;
+    imap.assert_imap("*", "OK").await;
+    imap.send(&format!(
+        "A1 AUTHENTICATE EXTERNAL {}",
+        STANDARD.encode("certuser@example.com")
+    ))
+    .await;
+    imap.assert_imap("A1", "OK").await;
+
+    // Certificates without an email address do not map to a principal
+    let mut imap = TestTlsClient::connect(9993, Some("device")).await;
+    imap.assert_imap("*", "OK").await;
+    imap.send("A1 AUTHENTICATE EXTERNAL =").await;
+    imap.assert_imap("A1", "NO").await;
+
+    let mut imap = TestTlsClient::connect(9993, None).await;
+    imap.assert_imap("*", "OK").await;
+    imap.send("A1 CAPABILITY").await;
+    imap.assert_imap("A1", "OK")
+        .await
+        .assert_not_contains("AUTH=EXTERNAL");
+
+    // HTTP requests without credentials are authenticated by the certificate
+    let response = http_get(Some("user"), "/api/account/auth").await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.json::<serde_json::Value>().await.unwrap()["data"]["clientCertificates"],
+        json!(["laptop2"])
+    );
+    assert_eq!(
+        http_get(Some("device"), "/api/account/auth")
+            .await
+            .status()
+            .as_u16(),
+        401
+    );
+    assert_eq!(
+        http_get(None, "/api/account/auth").await.status().as_u16(),
+        401
+    );
+
+    // Clean up
+    api.post::<()>(
+        "/api/account/auth",
+        &json!([{"type": "removeClientCertificate"}]),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    assert!(list_certificates(&api).await.is_empty());
+    for login in ["certuser@example.com", "certother@example.com"] {
+        server
+            .store()
+            .delete_principal(QueryBy::Name(login))
+            .await
+            .unwrap();
+    }
+}
+
+async fn add_certificate(api: &ManagementApi, name: &str, fingerprint: &str) {
+    api.post::<()>(
+        "/api/account/auth",
+        &json!([{"type": "addClientCertificate", "name": name, "fingerprint": fingerprint}]),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+}
+
+async fn list_certificates(api: &ManagementApi) -> Vec<String> {
+    let mut names = api
+        .get::<serde_json::Value>("/api/account/auth")
+        .await
+        .unwrap()
+        .unwrap_data()["clientCertificates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|name| name.as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+async fn http_get(identity: Option<&str>, path: &str) -> reqwest::Response {
+    let mut builder = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .timeout(Duration::from_millis(1000));
+    if let Some(name) = identity {
+        let mut pem = std::fs::read(cert_path(&format!("{name}.key"))).unwrap();
+        pem.extend(std::fs::read(cert_path(&format!("{name}.pem"))).unwrap());
+        builder = builder.identity(reqwest::Identity::from_pem(&pem).unwrap());
+    }
+    builder
+        .build()
+        .unwrap()
+        .get(format!("https://127.0.0.1:8898{path}"))
+        .send()
+        .await
+        .unwrap()
+}
+
+struct TestTlsClient {
+    stream: BufReader<TlsStream<TcpStream>>,
+}
+
+#[derive(Debug)]
+struct ClientIdentity(Arc<CertifiedKey>);
+
+impl ResolvesClientCert for ClientIdentity {
+    fn resolve(&self, _: &[&[u8]], _: &[SignatureScheme]) -> Option<Arc<CertifiedKey>> {
+        Some(self.0.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+impl TestTlsClient {
+    async fn connect(port: u16, identity: Option<&str>) -> Self {
+        let mut config = utils::rustls_client_config(true);
+        if let Some(name) = identity {
+            let certs = CertificateDer::pem_file_iter(cert_path(&format!("{name}.pem")))
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            let key = PrivateKeyDer::from_pem_file(cert_path(&format!("{name}.key"))).unwrap();
+            config.client_auth_cert_resolver = Arc::new(ClientIdentity(Arc::new(
+                CertifiedKey::new(certs, any_supported_type(&key).unwrap()),
+            )));
+        }
+
+        let stream = TlsConnector::from(Arc::new(config))
+            .connect(
+                ServerName::try_from("127.0.0.1").unwrap().to_owned(),
+                TcpStream::connect(format!("127.0.0.1:{port}"))
+                    .await
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        TestTlsClient {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    async fn send(&mut self, line: &str) {
+        let stream = self.stream.get_mut();
+        stream.write_all(line.as_bytes()).await.unwrap();
+        stream.write_all(b"\r\n").await.unwrap();
+        stream.flush().await.unwrap();
+    }
+
+    async fn read_until(&mut self, is_last: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            match tokio::time::timeout(
+                Duration::from_millis(1500),
+                self.stream.read_line(&mut line),
+            )
+            .await
+            {
+                Ok(Ok(0)) => panic!("Connection closed: {lines:?}"),
+                Ok(Ok(_)) => {
+                    let line = line.trim_end().to_string();
+                    let done = is_last(&line);
+                    lines.push(line);
+                    if done {
+                        return lines;
+                    }
+                }
+                Ok(Err(err)) => panic!("Connection broken: {err} ({lines:?})"),
+                Err(_) => panic!("Timeout while waiting for server response: {lines:?}"),
+            }
+        }
+    }
+
+    async fn assert_smtp(&mut self, code: &str) -> Vec<String> {
+        let lines = self
+            .read_until(|line| line.len() < 4 || line.as_bytes()[3] == b' ')
+            .await;
+        assert!(
+            lines.last().unwrap().starts_with(code),
+            "Expected {code}, got {lines:?}"
+        );
+        lines
+    }
+
+    async fn assert_imap(&mut self, tag: &str, status: &str) -> Vec<String> {
+        let prefix = format!("{tag} ");
+        let lines = self.read_until(|line| line.starts_with(&prefix)).await;
+        assert!(
+            lines.last().unwrap()[prefix.len()..].starts_with(status),
+            "Expected {tag} {status}, got {lines:?}"
+        );
+        lines
+    }
+}
+
+trait AssertLines {
+    fn assert_contains(self, text: &str) -> Self;
+    fn assert_not_contains(self, text: &str) -> Self;
+}
+
+impl AssertLines for Vec<String> {
+    fn assert_contains(self, text: &str) -> Self {
+        assert!(
+            self.iter().any(|line| line.contains(text)),
+            "Expected {text:?} in {self:?}"
+        );
+        self
+    }
+
+    fn assert_not_contains(self, text: &str) -> Self {
+        assert!(
+            !self.iter().any(|line| line.contains(text)),
+            "Unexpected {text:?} in {self:?}"
+        );
+        self
+    }
+}
+
+fn certificate_der(name: &str) -> Vec<u8> {
+    CertificateDer::from_pem_file(cert_path(&format!("{name}.pem")))
+        .unwrap()
+        .to_vec()
+}
+
+fn cert_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push("client_cert");
+    path.push(name);
+    path
+}
@@ -12,16 +12,30 @@ use crate::{
         pop::{self, Pop3Connection},
     },
     jmap::{
-        ManagementApi, assert_is_empty, delivery::SmtpConnection, mailbox::destroy_all_mailboxes,
+        ManagementApi, assert_is_empty, delivery::SmtpConnection, enterprise::List,
+        mailbox::destroy_all_mailboxes,
     },
 };
 use base64::{Engine, engine::general_purpose};
 use biscuit::{JWT, SingleOrMultiple, jwk::JWKSet};
 use bytes::Bytes;
-use common::auth::oauth::{
-    introspect::OAuthIntrospect,
-    oidc::StandardClaims,
-    registration::{ClientRegistrationRequest, ClientRegistrationResponse},
+use common::{
+    Server,
+    auth::{
+        oauth::{
+            introspect::OAuthIntrospect,
+            oidc::StandardClaims,
+            registration::{
+                ClientRegistrationRequest, ClientRegistrationResponse, ClientRegistrationRule,
+                ClientTokenExpiry, PendingClientRegistration,
+            },
+        },
+        sessions::DeviceSession,
+    },
+};
+use directory::{
+    QueryBy, QueryParams, Type as PrincipalType,
+    backend::internal::{lookup::DirectoryStore, manage::ManageDirectory},
 };
 use http::auth::oauth::{
     DeviceAuthResponse, ErrorType, OAuthCodeRequest, TokenResponse, auth::OAuthMetadata,
@@ -33,7 +47,11 @@ use jmap_client::{
     mailbox::query::Filter,
 };
 use serde::{Serialize, de::DeserializeOwned};
-use std::time::{Duration, Instant};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use store::ahash::AHashMap;
 use types::id::Id;
 
@@ -391,6 +409,85 @@ pub async fn test(params: &mut JMAPTest) {
         }
     );
 
+    // ------------------------
+    // Session registry
+    // ------------------------
+
+    // Obtain a new token using the device code flow
+    let device_response: DeviceAuthResponse =
+        post(&metadata.device_authorization_endpoint, &device_code_params).await;
+    token_params.insert(
+        "device_code".to_string(),
+        device_response.device_code.to_string(),
+    );
+    assert!(
+        api.post::<bool>(
+            "/api/oauth",
+            &OAuthCodeRequest::Device {
+                code: device_response.user_code.clone(),
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap_data(),
+        "Code is invalid"
+    );
+    let (_, refresh_token, _) =
+        unwrap_token_response(post(&metadata.token_endpoint, &token_params).await);
+    let refresh_params = AHashMap::from_iter([
+        ("client_id".to_string(), client_id.to_string()),
+        ("grant_type".to_string(), "refresh_token".to_string()),
+        ("refresh_token".to_string(), refresh_token.unwrap()),
+    ]);
+
+    // Both the HTTP login and the OAuth grant should be listed
+    let sessions = api
+        .get::<List<DeviceSession>>("/api/account/sessions")
+        .await
+        .unwrap()
+        .unwrap_data()
+        .items;
+    assert!(
+        sessions.iter().any(|session| session.protocol == "http"),
+        "{sessions:?}"
+    );
+    let oauth_session = sessions
+        .iter()
+        .filter(|session| {
+            session.protocol == "oauth" && session.client_id.as_deref() == Some(client_id.as_str())
+        })
+        .max_by_key(|session| session.created_at)
+        .unwrap_or_else(|| panic!("No OAuth session found: {sessions:?}"));
+
+    // Refresh tokens stop working once their session is revoked
+    unwrap_token_response(post(&metadata.token_endpoint, &refresh_params).await);
+    api.delete::<()>(&format!("/api/account/sessions/{}", oauth_session.id))
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(
+        post::<TokenResponse>(&metadata.token_endpoint, &refresh_params).await,
+        TokenResponse::Error {
+            error: ErrorType::InvalidGrant
+        }
+    );
+    assert!(
+        !api.get::<List<DeviceSession>>("/api/account/sessions")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .items
+            .iter()
+            .any(|session| session.id == oauth_session.id)
+    );
+    api.delete::<()>(&format!("/api/account/sessions/{}", oauth_session.id))
+        .await
+        .unwrap()
+        .expect_request_error("Not Found");
+
+    // Test the client registration review queue
+    client_registration_review(&server).await;
+
     // Destroy test accounts
     server
         .core
@@ -404,6 +501,181 @@ pub async fn test(params: &mut JMAPTest) {
     assert_is_empty(server).await;
 }
 
+async fn client_registration_review(server: &Server) {
+    let admin = ManagementApi::new(8899, "admin", "secret");
+    let user = ManagementApi::new(8899, "jdoe@example.com", "12345");
+
+    // Queue registrations for review
+    for client_id in ["pending-client-1", "pending-client-2"] {
+        server
+            .queue_client_registration(
+                client_id,
+                ClientRegistrationRequest {
+                    redirect_uris: vec![format!("https://{client_id}.example.org/cb")],
+                    client_name: Some(format!("Client {client_id}")),
+                    ..Default::default()
+                },
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Only administrators can review registrations
+    user.get::<List<PendingClientRegistration>>("/api/oauth/registrations")
+        .await
+        .unwrap()
+        .expect_request_error("Forbidden");
+    let pending = admin
+        .get::<List<PendingClientRegistration>>("/api/oauth/registrations")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(pending.total, 2);
+    let mut client_ids = pending
+        .items
+        .iter()
+        .map(|registration| registration.client_id.as_str())
+        .collect::<Vec<_>>();
+    client_ids.sort_unstable();
+    assert_eq!(client_ids, vec!["pending-client-1", "pending-client-2"]);
+    assert!(pending.items.iter().all(|registration| {
+        registration.remote_ip == Some(IpAddr::V4(Ipv4Addr::LOCALHOST))
+            && registration.request.redirect_uris
+                == vec![format!("https://{}.example.org/cb", registration.client_id)]
+    }));
+    assert_eq!(
+        admin
+            .get::<List<PendingClientRegistration>>("/api/oauth/registrations?page=2&limit=1")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .items
+            .len(),
+        1
+    );
+
+    // Pending clients cannot be used
+    assert!(
+        server
+            .store()
+            .query(QueryParams::name("pending-client-1").with_return_member_of(false))
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Approving a registration creates the client
+    admin
+        .post::<()>(
+            "/api/oauth/registrations/pending-client-1?rule=unknown",
+            &(),
+        )
+        .await
+        .unwrap()
+        .expect_error("notFound");
+    admin
+        .post::<()>("/api/oauth/registrations/pending-client-1", &())
+        .await
+        .unwrap()
+        .unwrap_data();
+    let client = server
+        .store()
+        .query(QueryParams::name("pending-client-1").with_return_member_of(false))
+        .await
+        .unwrap()
+        .expect("Approved client not found");
+    assert_eq!(client.typ(), PrincipalType::OauthClient);
+    admin
+        .post::<()>("/api/oauth/registrations/pending-client-1", &())
+        .await
+        .unwrap()
+        .expect_error("notFound");
+
+    // Rejecting a registration discards it
+    user.delete::<()>("/api/oauth/registrations/pending-client-2")
+        .await
+        .unwrap()
+        .expect_request_error("Forbidden");
+    admin
+        .delete::<()>("/api/oauth/registrations/pending-client-2")
+        .await
+        .unwrap()
+        .unwrap_data();
+    admin
+        .delete::<()>("/api/oauth/registrations/pending-client-2")
+        .await
+        .unwrap()
+        .expect_error("notFound");
+    assert!(
+        server
+            .store()
+            .query(QueryParams::name("pending-client-2").with_return_member_of(false))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        admin
+            .get::<List<PendingClientRegistration>>("/api/oauth/registrations")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .total,
+        0
+    );
+
+    // Clients approved by a rule keep their token lifetimes
+    let expiry = ClientTokenExpiry {
+        token: Some(600),
+        refresh_token: Some(3600),
+    };
+    let mut core = server.core.as_ref().clone();
+    core.oauth.client_registration_rules = vec![ClientRegistrationRule {
+        id: "local".to_string(),
+        redirect_uris: vec![],
+        expiry,
+    }];
+    let rule_server = Server {
+        inner: server.inner.clone(),
+        core: Arc::new(core),
+    };
+    rule_server
+        .create_oauth_client(
+            "rule-client",
+            &ClientRegistrationRequest {
+                redirect_uris: vec!["http://localhost/cb".to_string()],
+                ..Default::default()
+            },
+            expiry,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        rule_server
+            .oauth_client_expiry("rule-client")
+            .await
+            .unwrap(),
+        expiry
+    );
+    assert_eq!(
+        rule_server
+            .oauth_client_expiry("pending-client-1")
+            .await
+            .unwrap(),
+        ClientTokenExpiry::default()
+    );
+
+    // Remove test clients
+    for client_id in ["pending-client-1", "rule-client"] {
+        server
+            .store()
+            .delete_principal(QueryBy::Name(client_id))
+            .await
+            .unwrap();
+    }
+}
+
 async fn post_bytes(
     url: &str,
     auth_token: Option<&str>,
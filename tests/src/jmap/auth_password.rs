@@ -0,0 +1,192 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+    time::Duration,
+};
+
+use super::JMAPTest;
+use crate::{directory::internal::TestInternalDirectory, jmap::assert_is_empty};
+use common::{
+    KV_PASSWORD_HISTORY, Server,
+    auth::{
+        AccessToken, AuthRequest,
+        password::{PasswordPolicy, PasswordViolation},
+    },
+};
+use directory::{
+    QueryBy,
+    backend::internal::{
+        PrincipalField, PrincipalUpdate, PrincipalValue,
+        manage::{ManageDirectory, UpdatePrincipal},
+    },
+};
+use store::{dispatch::lookup::KeyValue, write::now};
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running password policy tests...");
+
+    // Enable password history and expiration
+    let mut core = params.server.core.as_ref().clone();
+    core.password = PasswordPolicy {
+        min_length: 8,
+        history: 2,
+        max_age: Some(Duration::from_secs(3600)),
+        ..Default::default()
+    };
+    let server = Server {
+        inner: params.server.inner.clone(),
+        core: Arc::new(core),
+    };
+    let account_id = server
+        .store()
+        .create_test_user(
+            "pwpolicy@example.com",
+            "Initial-pass1",
+            "Password Policy",
+            &["pwpolicy@example.com"],
+        )
+        .await;
+
+    // Content rules are enforced
+    assert_eq!(
+        server
+            .validate_password(Some(account_id), "short")
+            .await
+            .unwrap(),
+        vec![PasswordViolation::TooShort { min: 8 }]
+    );
+    assert!(
+        server
+            .assert_password_policy(Some(account_id), "short")
+            .await
+            .is_err()
+    );
+
+    // The expiration clock starts on the first login
+    assert!(login(&server, "Initial-pass1", false).await.is_ok());
+    assert!(!server.is_password_expired(account_id).await.unwrap());
+
+    // Previous passwords cannot be reused
+    for password in ["Second-pass2", "Third-pass3"] {
+        change_password(&server, account_id, password).await;
+    }
+    for (password, expected) in [
+        ("Initial-pass1", vec![PasswordViolation::Reused]),
+        ("Second-pass2", vec![PasswordViolation::Reused]),
+        ("Fourth-pass4", vec![]),
+    ] {
+        assert_eq!(
+            server
+                .validate_password(Some(account_id), password)
+                .await
+                .unwrap(),
+            expected,
+            "{password}"
+        );
+    }
+
+    // Only the configured number of passwords is kept
+    change_password(&server, account_id, "Fourth-pass4").await;
+    for (password, expected) in [
+        ("Initial-pass1", vec![]),
+        ("Second-pass2", vec![PasswordViolation::Reused]),
+        ("Third-pass3", vec![PasswordViolation::Reused]),
+    ] {
+        assert_eq!(
+            server
+                .validate_password(Some(account_id), password)
+                .await
+                .unwrap(),
+            expected,
+            "{password}"
+        );
+    }
+
+    // Expired passwords are rejected, except when changing them
+    server
+        .in_memory_store()
+        .key_set(KeyValue::with_prefix(
+            KV_PASSWORD_HISTORY,
+            account_id.to_be_bytes(),
+            serde_json::to_vec(&serde_json::json!({
+                "changedAt": now() - 7200,
+                "hashes": []
+            }))
+            .unwrap(),
+        ))
+        .await
+        .unwrap();
+    assert!(server.is_password_expired(account_id).await.unwrap());
+    assert!(
+        login(&server, "Fourth-pass4", false)
+            .await
+            .unwrap_err()
+            .matches(trc::EventType::Auth(trc::AuthEvent::PasswordExpired))
+    );
+    assert!(login(&server, "Fourth-pass4", true).await.is_ok());
+    change_password(&server, account_id, "Fifth-pass5").await;
+    assert!(!server.is_password_expired(account_id).await.unwrap());
+    assert!(login(&server, "Fifth-pass5", false).await.is_ok());
+
+    // Expiration is disabled by default
+    assert!(!params.server.is_password_expired(account_id).await.unwrap());
+
+    // Delete account
+    server
+        .store()
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    server
+        .in_memory_store()
+        .key_delete_prefix(&[KV_PASSWORD_HISTORY])
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
+
+async fn change_password(server: &Server, account_id: u32, password: &str) {
+    server
+        .assert_password_policy(Some(account_id), password)
+        .await
+        .unwrap();
+    let previous_secrets = server.password_secrets(account_id).await.unwrap();
+    server
+        .store()
+        .update_principal(UpdatePrincipal::by_id(account_id).with_updates(vec![
+            PrincipalUpdate::set(
+                PrincipalField::Secrets,
+                PrincipalValue::StringList(vec![password.into()]),
+            ),
+        ]))
+        .await
+        .unwrap();
+    server
+        .record_password_change(account_id, &previous_secrets)
+        .await
+        .unwrap();
+}
+
+async fn login(
+    server: &Server,
+    password: &str,
+    allow_expired_password: bool,
+) -> trc::Result<Arc<AccessToken>> {
+    server
+        .authenticate(
+            &AuthRequest::from_plain(
+                "pwpolicy@example.com",
+                password,
+                0,
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+            )
+            .with_expired_password(allow_expired_password),
+        )
+        .await
+}
@@ -0,0 +1,238 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use serde_json::{Value, json};
+use types::id::Id;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{JMAPTest, ManagementApi, assert_is_empty, jmap_json_request},
+};
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running principal avatar tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let account_id = server
+        .store()
+        .create_test_user(
+            "avatar@example.com",
+            "secret",
+            "Avatar",
+            &["avatar@example.com"],
+        )
+        .await;
+
+    // Only small PNG, JPEG, GIF or WebP images are accepted
+    for (contents, expected) in [
+        (vec![], "Missing avatar contents"),
+        (b"not an image".to_vec(), "Invalid avatar"),
+        (png(2048, 16), "Invalid avatar"),
+        (png(0, 16), "Invalid avatar"),
+    ] {
+        let response = upload_avatar(&client, "avatar@example.com", contents).await;
+        assert_eq!(response["error"], "other", "{response}");
+        assert_eq!(response["details"], expected, "{response}");
+    }
+    let mut oversized = png(16, 16);
+    oversized.resize(257 * 1024, 0);
+    let response = upload_avatar(&client, "avatar@example.com", oversized).await;
+    assert_eq!(
+        response["reason"], "Avatar exceeds the maximum allowed size",
+        "{response}"
+    );
+
+    // Upload an avatar
+    let avatar = png(64, 64);
+    let url = upload_avatar(&client, "avatar@example.com", avatar.clone()).await["data"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(url.starts_with(&format!("/avatar/{account_id}/")), "{url}");
+
+    // Avatars are served with caching headers
+    let response = client
+        .get(format!("https://127.0.0.1:8899{url}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    assert!(
+        response.headers()["cache-control"]
+            .to_str()
+            .unwrap()
+            .contains("immutable")
+    );
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert_eq!(response.bytes().await.unwrap().as_ref(), avatar.as_slice());
+    let response = client
+        .get(format!("https://127.0.0.1:8899{url}"))
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 304);
+    for path in [
+        format!("/avatar/{account_id}/{}", "0".repeat(64)),
+        format!(
+            "/avatar/{}/{}",
+            account_id + 1000,
+            url.rsplit('/').next().unwrap()
+        ),
+    ] {
+        assert_eq!(
+            client
+                .get(format!("https://127.0.0.1:8899{path}"))
+                .send()
+                .await
+                .unwrap()
+                .status()
+                .as_u16(),
+            404,
+            "{path}"
+        );
+    }
+
+    // The avatar is returned as the picture of the principal
+    assert_eq!(
+        api.get::<Value>("/api/principal/avatar@example.com")
+            .await
+            .unwrap()
+            .unwrap_data()["picture"],
+        url.as_str()
+    );
+    let response = jmap_json_request(
+        format!(
+            r#"[["Principal/get", {{"ids": ["{}"], "properties": ["name", "avatarUrl"]}}, "c1"]]"#,
+            Id::from(account_id)
+        ),
+        "admin",
+        "secret",
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["list"][0]["avatarUrl"],
+        url.as_str(),
+        "{response}"
+    );
+
+    // Saving the principal with an unchanged picture keeps the avatar
+    api.patch::<Value>(
+        "/api/principal/avatar@example.com",
+        &json!([{"action": "set", "field": "picture", "value": url}]),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    assert_eq!(avatar_status(&client, &url).await, 200);
+
+    // Replacing the avatar stops serving the previous one
+    let new_url = upload_avatar(&client, "avatar@example.com", png(32, 32)).await["data"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_ne!(new_url, url);
+    assert_eq!(avatar_status(&client, &url).await, 404);
+    assert_eq!(avatar_status(&client, &new_url).await, 200);
+
+    // Setting a picture URL replaces the avatar
+    api.patch::<Value>(
+        "/api/principal/avatar@example.com",
+        &json!([{"action": "set", "field": "picture", "value": "https://example.com/me.png"}]),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    assert_eq!(avatar_status(&client, &new_url).await, 404);
+    assert_eq!(
+        api.get::<Value>("/api/principal/avatar@example.com")
+            .await
+            .unwrap()
+            .unwrap_data()["picture"],
+        "https://example.com/me.png"
+    );
+
+    // Remove the avatar
+    let url = upload_avatar(&client, "avatar@example.com", avatar).await["data"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(avatar_status(&client, &url).await, 200);
+    assert_eq!(
+        client
+            .delete("https://127.0.0.1:8899/api/principal/avatar@example.com/avatar")
+            .basic_auth("admin", Some("secret"))
+            .send()
+            .await
+            .unwrap()
+            .json::<Value>()
+            .await
+            .unwrap(),
+        json!({"data": null})
+    );
+    assert_eq!(avatar_status(&client, &url).await, 404);
+    assert_eq!(
+        api.get::<Value>("/api/principal/avatar@example.com")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .get("picture"),
+        None
+    );
+
+    // Deleting the principal unlinks its avatar
+    upload_avatar(&client, "avatar@example.com", png(48, 48)).await;
+    server
+        .store()
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
+
+async fn upload_avatar(client: &reqwest::Client, name: &str, contents: Vec<u8>) -> Value {
+    client
+        .put(format!(
+            "https://127.0.0.1:8899/api/principal/{name}/avatar"
+        ))
+        .basic_auth("admin", Some("secret"))
+        .body(contents)
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap()
+}
+
+async fn avatar_status(client: &reqwest::Client, url: &str) -> u16 {
+    client
+        .get(format!("https://127.0.0.1:8899{url}"))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+// Minimal PNG with a header describing the image dimensions
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+    png.extend_from_slice(&width.to_be_bytes());
+    png.extend_from_slice(&height.to_be_bytes());
+    png.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]);
+    png.extend_from_slice(b"\x00\x00\x00\x00IEND\xae\x42\x60\x82");
+    png
+}
@@ -0,0 +1,219 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::config::network::Branding;
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+use serde_json::Value;
+use utils::config::Config;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{JMAPTest, ManagementApi},
+};
+
+const CONFIG: &str = r##"
+[branding.acme]
+domain = ["example.com", "Example.NET"]
+name = "Acme Mail"
+support-url = "https://support.example.com"
+color.primary = "#112233"
+color.accent = "#445566"
+
+[branding.broken]
+domain = "broken.org"
+logo.hash = "not-a-hash"
+"##;
+
+const LOGO: &[u8] = b"\x89PNG\r\n\x1a\nbranding-test-logo";
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running branding tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+
+    // Parse branding settings
+    let mut config = Config::new(CONFIG).unwrap();
+    let branding = Branding::parse(&mut config);
+    assert!(
+        config.errors.contains_key("branding.broken.logo.hash"),
+        "{:?}",
+        config.errors
+    );
+    assert_eq!(branding["example.com"].id, "acme");
+    assert_eq!(branding["example.net"].id, "acme");
+    assert!(branding["broken.org"].logo.is_none());
+
+    // Upload a logo, only images are accepted
+    let response = client
+        .post("https://127.0.0.1:8899/api/branding/acme/logo")
+        .basic_auth("admin", Some("secret"))
+        .header(CONTENT_TYPE, "text/plain")
+        .body(LOGO)
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response["details"], "Invalid logo content type",
+        "{response}"
+    );
+    let response = client
+        .post("https://127.0.0.1:8899/api/branding/acme/logo")
+        .basic_auth("admin", Some("secret"))
+        .header(CONTENT_TYPE, "image/png")
+        .body(LOGO)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let logo_hash = response.json::<Value>().await.unwrap()["data"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        server
+            .core
+            .storage
+            .config
+            .get("branding.acme.logo.hash")
+            .await
+            .unwrap(),
+        Some(logo_hash.clone())
+    );
+
+    // Apply the updated settings
+    let mut config = Config::new(format!(
+        "{CONFIG}\n[branding.acme.logo]\nhash = \"{logo_hash}\"\ncontent-type = \"image/png\"\n"
+    ))
+    .unwrap();
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.network.branding = Branding::parse(&mut config);
+    params.server.inner.shared_core.store(core.into());
+
+    // Branding is served to unauthenticated clients, subdomains inherit
+    // the branding of their parent domain
+    for domain in ["example.com", "mail.example.com", "EXAMPLE.NET"] {
+        let response = client
+            .get(format!("https://127.0.0.1:8899/branding/{domain}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{domain}");
+        let branding = response.json::<Value>().await.unwrap();
+        assert_eq!(branding["productName"], "Acme Mail", "{branding}");
+        assert_eq!(
+            branding["supportUrl"], "https://support.example.com",
+            "{branding}"
+        );
+        assert_eq!(branding["colors"]["primary"], "#112233", "{branding}");
+        assert_eq!(branding["colors"]["accent"], "#445566", "{branding}");
+        assert_eq!(
+            branding["logoUrl"],
+            format!("/branding/{}/logo", domain.to_lowercase()),
+            "{branding}"
+        );
+    }
+    let response = client
+        .get("https://127.0.0.1:8899/branding/mail.example.com/logo")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "image/png");
+    assert_eq!(response.bytes().await.unwrap().as_ref(), LOGO);
+    for path in ["/branding/unknown.org", "/branding/unknown.org/logo"] {
+        assert_eq!(
+            client
+                .get(format!("https://127.0.0.1:8899{path}"))
+                .send()
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::NOT_FOUND,
+            "{path}"
+        );
+    }
+
+    // The branding of the account's domain is advertised in the JMAP session
+    let account_id = server
+        .store()
+        .create_test_user(
+            "branding@example.com",
+            "secret",
+            "Branding",
+            &["branding@example.com"],
+        )
+        .await;
+    let session = client
+        .get("https://127.0.0.1:8899/jmap/session")
+        .basic_auth("branding@example.com", Some("secret"))
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    let capability = &session["capabilities"]["urn:stalwart:jmap:branding"];
+    assert_eq!(capability["productName"], "Acme Mail", "{session}");
+    assert_eq!(capability["primaryColor"], "#112233", "{session}");
+    assert!(
+        capability["logoUrl"]
+            .as_str()
+            .unwrap()
+            .ends_with("/branding/example.com/logo"),
+        "{session}"
+    );
+    let session = client
+        .get("https://127.0.0.1:8899/jmap/session")
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    assert!(
+        session["capabilities"]
+            .get("urn:stalwart:jmap:branding")
+            .is_none(),
+        "{session}"
+    );
+
+    // Remove the logo
+    api.delete::<Value>("/api/branding/acme/logo")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(
+        server
+            .core
+            .storage
+            .config
+            .get("branding.acme.logo.hash")
+            .await
+            .unwrap(),
+        None
+    );
+
+    // Cleanup
+    params.server.inner.shared_core.store(original_core);
+    server
+        .store()
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+}
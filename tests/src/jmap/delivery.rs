@@ -10,6 +10,11 @@ use crate::{
     jmap::{assert_is_empty, mailbox::destroy_all_mailboxes},
     webdav::DummyWebDavClient,
 };
+use common::expr::if_block::IfBlock;
+use directory::backend::internal::{
+    PrincipalField, PrincipalUpdate, PrincipalValue,
+    manage::{ManageDirectory, UpdatePrincipal},
+};
 use email::{
     cache::{MessageCacheFetch, email::MessageCacheAccess},
     mailbox::{INBOX_ID, JUNK_ID},
@@ -313,6 +318,121 @@ END:VCARD
         );
     }
 
+    // Forwarding loops are rejected for recipients listed in Delivered-To
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.smtp.session.data.add_delivered_to = true;
+    params.server.inner.shared_core.store(core.into());
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "",
+        &["jdoe@example.com", "jane@example.com"],
+        concat!(
+            "Delivered-To: jdoe@example.com\r\n",
+            "From: bill@example.com\r\n",
+            "To: jdoe@example.com, jane@example.com\r\n",
+            "Subject: TPS reports\r\n",
+            "\r\n",
+            "Did you get the memo?"
+        ),
+    )
+    .await;
+
+    for (account_id, num_messages) in [(&account_id_1, 5), (&account_id_2, 4), (&account_id_3, 3)] {
+        assert_eq!(
+            server
+                .get_document_ids(
+                    Id::from_str(account_id).unwrap().document_id(),
+                    Collection::Email
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .len(),
+            num_messages,
+            "for {}",
+            account_id
+        );
+    }
+    params.server.inner.shared_core.store(original_core.clone());
+
+    // Nested mailing lists referencing each other are expanded once
+    for (list, member, external) in [
+        (
+            "loop-a@example.com",
+            "jane@example.com",
+            "loop-b@example.com",
+        ),
+        (
+            "loop-b@example.com",
+            "bill@example.com",
+            "loop-a@example.com",
+        ),
+    ] {
+        server
+            .core
+            .storage
+            .data
+            .create_test_list(list, "Looping List", &[member])
+            .await;
+        server
+            .core
+            .storage
+            .data
+            .update_principal(UpdatePrincipal::by_name(list).with_updates(vec![
+                PrincipalUpdate::set(
+                    PrincipalField::ExternalMembers,
+                    PrincipalValue::StringList(vec![external.into()]),
+                ),
+            ]))
+            .await
+            .unwrap();
+    }
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "bill@example.com",
+        &["loop-a@example.com"],
+        concat!(
+            "From: bill@example.com\r\n",
+            "To: loop-a@example.com\r\n",
+            "Subject: Round and round\r\n",
+            "\r\n",
+            "This message should be delivered only once."
+        ),
+    )
+    .await;
+
+    for (account_id, num_messages) in [(&account_id_1, 5), (&account_id_2, 5), (&account_id_3, 4)] {
+        assert_eq!(
+            server
+                .get_document_ids(
+                    Id::from_str(account_id).unwrap().document_id(),
+                    Collection::Email
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .len(),
+            num_messages,
+            "for {}",
+            account_id
+        );
+    }
+
+    // Lists nested deeper than the maximum expansion depth are rejected
+    let mut core = original_core.as_ref().clone();
+    core.smtp.session.rcpt.max_expansion_depth =
+        IfBlock::new::<()>("session.rcpt.max-expansion-depth", [], "1");
+    params.server.inner.shared_core.store(core.into());
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.mail_from("bill@example.com", 2).await;
+    lmtp.rcpt_to("loop-a@example.com", 5)
+        .await
+        .assert_contains("5.4.6");
+    lmtp.rcpt_to("members@example.com", 2).await;
+    lmtp.rset().await;
+    params.server.inner.shared_core.store(original_core);
+
     // Remove test data
     for account_id in [&account_id_1, &account_id_2, &account_id_3] {
         params.client.set_default_account_id(account_id);
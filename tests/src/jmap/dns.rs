@@ -0,0 +1,54 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use serde_json::{Value, json};
+
+use crate::jmap::ManagementApi;
+
+pub async fn test() {
+    println!("Running DNS records check tests...");
+    let api = ManagementApi::new(8899, "admin", "secret");
+
+    // Reserved domains are never published, so every record check must fail
+    let records = api
+        .get::<Value>("/api/dns/records/dnscheck.invalid")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let records = records.as_array().unwrap();
+    assert!(records.iter().any(|record| record["type"] == "MX"));
+    let report = api
+        .get::<Value>("/api/dns/check/dnscheck.invalid")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let checks = report["records"].as_array().unwrap();
+    assert_eq!(checks.len(), records.len());
+    assert_eq!(report["passed"], json!(0));
+    assert_eq!(report["failed"], json!(checks.len()));
+
+    // Each check reports the expected record next to the published values
+    for (check, record) in checks.iter().zip(records) {
+        assert_eq!(check["type"], record["type"], "{check}");
+        assert_eq!(check["name"], record["name"], "{check}");
+        assert_eq!(check["expected"], record["content"], "{check}");
+        assert_eq!(check["found"], json!([]), "{check}");
+        match check["status"].as_str().unwrap() {
+            "missing" => assert!(check.get("error").is_none(), "{check}"),
+            "error" => assert!(check["error"].is_string(), "{check}"),
+            status => panic!("Unexpected status {status:?}: {check}"),
+        }
+    }
+
+    // A domain name is required
+    assert!(
+        api.get::<Value>("/api/dns/check")
+            .await
+            .unwrap()
+            .try_unwrap_data()
+            .is_none()
+    );
+}
@@ -0,0 +1,180 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::JMAPTest;
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{ManagementApi, assert_is_empty},
+};
+use common::BuildServer;
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use email::{
+    cache::{MessageCacheFetch, email::MessageCacheAccess},
+    mailbox::INBOX_ID,
+    message::delete::EmailDeletion,
+};
+use serde_json::Value;
+use types::id::Id;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running deleted items dumpster tests...");
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mail_dumpster_retention = Some(3600);
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.inner.build_server();
+    let api = ManagementApi::new(8899, "admin", "secret");
+
+    // Create test messages
+    let account_id = server
+        .store()
+        .create_test_user(
+            "dumpster@example.com",
+            "secret",
+            "Dumpster",
+            &["dumpster@example.com"],
+        )
+        .await;
+    let client = &mut params.client;
+    client.set_default_account_id(Id::from(account_id));
+    let inbox_id = Id::from(INBOX_ID).to_string();
+    let mut email_ids = Vec::new();
+    for num in 1..=2 {
+        email_ids.push(
+            client
+                .email_import(
+                    format!(
+                        concat!(
+                            "From: bill@example.com\r\n",
+                            "To: dumpster@example.com\r\n",
+                            "Subject: Dumpster test {}\r\n",
+                            "\r\n",
+                            "Please do not throw this away."
+                        ),
+                        num
+                    )
+                    .into_bytes(),
+                    [&inbox_id],
+                    None::<Vec<&str>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+    let used_quota = server.get_used_quota(account_id).await.unwrap();
+    assert!(used_quota > 0);
+
+    // Deleted messages are retained in the dumpster and release their quota
+    for email_id in &email_ids {
+        client.email_destroy(email_id).await.unwrap();
+    }
+    server.emails_purge_tombstoned(account_id).await.unwrap();
+    assert_eq!(server.get_used_quota(account_id).await.unwrap(), 0);
+    let items = api
+        .get::<Value>("/api/store/dumpster/dumpster@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let mut items = items.as_array().unwrap().clone();
+    items.sort_by_key(|item| item["subject"].as_str().unwrap().to_string());
+    assert_eq!(
+        items
+            .iter()
+            .map(|item| item["subject"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["Dumpster test 1", "Dumpster test 2"]
+    );
+    for item in &items {
+        assert_eq!(
+            item["expiresAt"].as_u64().unwrap(),
+            item["deletedAt"].as_u64().unwrap() + 3600
+        );
+        assert_eq!(item["preview"], "Please do not throw this away.");
+    }
+
+    // Restore a message into the inbox
+    let restored_id = items[0]["id"].as_u64().unwrap();
+    api.post::<Value>(
+        &format!("/api/store/dumpster/dumpster@example.com/{restored_id}"),
+        &(),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    let restored_quota = server.get_used_quota(account_id).await.unwrap();
+    assert!(restored_quota > 0 && restored_quota < used_quota);
+    assert_eq!(
+        server
+            .get_cached_messages(account_id)
+            .await
+            .unwrap()
+            .in_mailbox(INBOX_ID)
+            .count(),
+        1
+    );
+    let items = api
+        .get::<Value>("/api/store/dumpster/dumpster@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(items.as_array().unwrap().len(), 1);
+    assert_eq!(items[0]["subject"], "Dumpster test 2");
+
+    // Restored, unknown and invalid messages are rejected
+    assert!(
+        api.post::<Value>(
+            &format!("/api/store/dumpster/dumpster@example.com/{restored_id}"),
+            &(),
+        )
+        .await
+        .unwrap()
+        .try_unwrap_data()
+        .is_none()
+    );
+    api.post::<Value>(
+        &format!(
+            "/api/store/dumpster/dumpster@example.com/{}?mailbox=12345",
+            items[0]["id"]
+        ),
+        &(),
+    )
+    .await
+    .unwrap()
+    .expect_request_error("Mailbox not found");
+    api.get::<Value>("/api/store/dumpster/unknown@example.com")
+        .await
+        .unwrap()
+        .expect_error("notFound");
+
+    // Expired messages are purged from the dumpster
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mail_dumpster_retention = Some(0);
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.inner.build_server();
+    server.emails_purge_tombstoned(account_id).await.unwrap();
+    assert_eq!(
+        api.get::<Value>("/api/store/dumpster/dumpster@example.com")
+            .await
+            .unwrap()
+            .unwrap_data(),
+        Value::Array(vec![])
+    );
+    assert_eq!(
+        server.get_used_quota(account_id).await.unwrap(),
+        restored_quota
+    );
+
+    // Cleanup
+    params.server.inner.shared_core.store(original_core);
+    server
+        .store()
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(params.server.clone()).await;
+}
@@ -6,8 +6,10 @@
 
 use super::JMAPTest;
 use crate::jmap::{
-    assert_is_empty, email_get::all_headers, mailbox::destroy_all_mailboxes, replace_blob_ids,
+    assert_is_empty, email_get::all_headers, jmap_json_request, mailbox::destroy_all_mailboxes,
+    replace_blob_ids,
 };
+use base64::{Engine, engine::general_purpose};
 use jmap_client::{
     email::{self, Header, HeaderForm},
     mailbox::Role,
@@ -232,6 +234,169 @@ pub async fn test(params: &mut JMAPTest) {
         panic!("Test failed, output saved to {}", test_file.display());
     }
 
+    // Test parsing forwarded messages and TNEF attachments
+    let blob_id = params
+        .client
+        .upload(None, nested_message().into_bytes(), None)
+        .await
+        .unwrap()
+        .take_blob_id();
+    let email = parse_nested(&blob_id).await;
+    assert_eq!(email["subject"], "Fwd: Quarterly report", "{email}");
+    let attachments = email["attachments"].as_array().unwrap();
+    assert_eq!(attachments.len(), 2, "{email}");
+
+    // Forwarded messages are parsed recursively
+    let forwarded = &attachments[0]["parsedEmail"];
+    assert_eq!(attachments[0]["type"], "message/rfc822", "{email}");
+    assert_eq!(forwarded["subject"], "Quarterly report", "{email}");
+    let text_part_id = forwarded["textBody"][0]["partId"].as_str().unwrap();
+    assert_eq!(
+        forwarded["bodyValues"][text_part_id]["value"], "Figures attached.",
+        "{email}"
+    );
+    let original = &forwarded["attachments"][0]["parsedEmail"];
+    assert_eq!(original["subject"], "Draft figures", "{email}");
+    assert!(original["attachments"].as_array().unwrap().is_empty());
+
+    // TNEF attachments expose their bodies and attachments
+    let tnef = &attachments[1]["parsedEmail"];
+    assert_eq!(attachments[1]["name"], "winmail.dat", "{email}");
+    assert_eq!(tnef["subject"], "Quarterly report (TNEF)", "{email}");
+    assert_eq!(tnef["textBody"][0]["type"], "text/plain", "{email}");
+    assert_eq!(
+        tnef["bodyValues"]["0"]["value"], "See the attached figures.",
+        "{email}"
+    );
+    assert_eq!(
+        tnef["attachments"],
+        serde_json::json!([{
+            "partId": "1",
+            "type": "text/csv",
+            "name": "figures.csv",
+            "parsedEmail": null
+        }]),
+        "{email}"
+    );
+
+    // Nesting is limited to the configured depth
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mail_parse_max_depth = 1;
+    params.server.inner.shared_core.store(core.into());
+    let email = parse_nested(&blob_id).await;
+    let forwarded = &email["attachments"][0]["parsedEmail"];
+    assert_eq!(forwarded["subject"], "Quarterly report", "{email}");
+    assert_eq!(
+        forwarded["attachments"][0]["parsedEmail"],
+        serde_json::Value::Null,
+        "{email}"
+    );
+    params.server.inner.shared_core.store(original_core);
+
     destroy_all_mailboxes(params).await;
     assert_is_empty(server).await;
 }
+
+async fn parse_nested(blob_id: &str) -> serde_json::Value {
+    let response = jmap_json_request(
+        format!(
+            r#"[[ "Email/parse", {{
+                "accountId": "{}",
+                "blobIds": ["{blob_id}"],
+                "properties": ["subject", "textBody", "attachments", "bodyValues"],
+                "bodyProperties": ["partId", "type", "name", "parsedEmail"],
+                "fetchTextBodyValues": true
+            }}, "0" ]]"#,
+            Id::new(1)
+        ),
+        "admin",
+        "secret",
+    )
+    .await;
+    response["methodResponses"][0][1]["parsed"][blob_id].clone()
+}
+
+fn nested_message() -> String {
+    format!(
+        concat!(
+            "From: jane@example.com\r\n",
+            "To: bill@example.com\r\n",
+            "Subject: Fwd: Quarterly report\r\n",
+            "Content-Type: multipart/mixed; boundary=\"outer\"\r\n",
+            "\r\n",
+            "--outer\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "See below.\r\n",
+            "--outer\r\n",
+            "Content-Type: message/rfc822\r\n",
+            "\r\n",
+            "From: john@example.com\r\n",
+            "Subject: Quarterly report\r\n",
+            "Content-Type: multipart/mixed; boundary=\"inner\"\r\n",
+            "\r\n",
+            "--inner\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Figures attached.\r\n",
+            "--inner\r\n",
+            "Content-Type: message/rfc822\r\n",
+            "\r\n",
+            "From: mike@example.com\r\n",
+            "Subject: Draft figures\r\n",
+            "\r\n",
+            "Draft.\r\n",
+            "--inner--\r\n",
+            "--outer\r\n",
+            "Content-Type: application/ms-tnef; name=\"winmail.dat\"\r\n",
+            "Content-Disposition: attachment; filename=\"winmail.dat\"\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            "{}\r\n",
+            "--outer--\r\n",
+        ),
+        general_purpose::STANDARD.encode(tnef_message())
+    )
+}
+
+fn tnef_message() -> Vec<u8> {
+    fn attribute(tnef: &mut Vec<u8>, level: u8, id: u32, data: &[u8]) {
+        tnef.push(level);
+        tnef.extend_from_slice(&id.to_le_bytes());
+        tnef.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        tnef.extend_from_slice(data);
+        let checksum = data.iter().fold(0u16, |acc, &b| acc.wrapping_add(b as u16));
+        tnef.extend_from_slice(&checksum.to_le_bytes());
+    }
+
+    fn mapi_prop(props: &mut Vec<u8>, prop_type: u16, prop_id: u16, value: &[u8]) {
+        props.extend_from_slice(&prop_type.to_le_bytes());
+        props.extend_from_slice(&prop_id.to_le_bytes());
+        props.extend_from_slice(&1u32.to_le_bytes());
+        props.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        props.extend_from_slice(value);
+        props.resize(
+            props.len() + value.len().next_multiple_of(4) - value.len(),
+            0,
+        );
+    }
+
+    let mut props = 2u32.to_le_bytes().to_vec();
+    let file_name = "figures.csv\0"
+        .encode_utf16()
+        .flat_map(|ch| ch.to_le_bytes())
+        .collect::<Vec<_>>();
+    mapi_prop(&mut props, 0x001F, 0x3707, &file_name);
+    mapi_prop(&mut props, 0x001E, 0x370E, b"text/csv\0");
+
+    let mut tnef = 0x223E9F78u32.to_le_bytes().to_vec();
+    tnef.extend_from_slice(&0u16.to_le_bytes());
+    attribute(&mut tnef, 0x01, 0x00018004, b"Quarterly report (TNEF)\0");
+    attribute(&mut tnef, 0x01, 0x0002800C, b"See the attached figures.\0");
+    attribute(&mut tnef, 0x02, 0x00069002, &[0; 14]);
+    attribute(&mut tnef, 0x02, 0x00018010, b"FIGURES.CSV\0");
+    attribute(&mut tnef, 0x02, 0x0006800F, b"q1,q2\n10,20\n");
+    attribute(&mut tnef, 0x02, 0x00069005, &props);
+    tnef
+}
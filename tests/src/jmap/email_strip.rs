@@ -0,0 +1,152 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::JMAPTest;
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes},
+};
+use email::mailbox::INBOX_ID;
+use types::id::Id;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Email/stripAttachments tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "strip@example.com",
+                "12345",
+                "Strip Test",
+                &["strip@example.com"],
+            )
+            .await,
+    );
+    let inbox_id = Id::new(INBOX_ID as u64).to_string();
+    params.client.set_default_account_id(account_id.to_string());
+
+    // Import a message with a small and a large attachment
+    let large_attachment = "QUJD".repeat(512 * 1024 / 4);
+    let raw_message = format!(
+        concat!(
+            "From: bill@example.com\r\n",
+            "To: strip@example.com\r\n",
+            "Subject: Quarterly report\r\n",
+            "Content-Type: multipart/mixed; boundary=\"b1\"\r\n",
+            "\r\n",
+            "--b1\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Please find the report attached.\r\n",
+            "--b1\r\n",
+            "Content-Type: text/plain; name=\"notes.txt\"\r\n",
+            "Content-Disposition: attachment; filename=\"notes.txt\"\r\n",
+            "\r\n",
+            "Short notes.\r\n",
+            "--b1\r\n",
+            "Content-Type: application/pdf; name=\"report.pdf\"\r\n",
+            "Content-Disposition: attachment; filename=\"report.pdf\"\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            "{}\r\n",
+            "--b1--\r\n"
+        ),
+        large_attachment
+    );
+    let email = params
+        .client
+        .email_import(
+            raw_message.into_bytes(),
+            [&inbox_id],
+            Some(["$seen"]),
+            Some(311923920),
+        )
+        .await
+        .unwrap();
+    let email_id = email.id().unwrap().to_string();
+
+    // Attachments below the threshold are kept
+    let response = jmap_json_request(
+        r#"[[ "Email/stripAttachments", {
+            "accountId": "$$",
+            "emailIds": [ "%%" ],
+            "minSize": 10485760
+          }, "0" ]]"#
+            .replace("$$", &account_id.to_string())
+            .replace("%%", &email_id),
+        "strip@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer(&format!("/methodResponses/0/1/notStripped/{email_id}/type"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default(),
+        "invalidProperties",
+        "Response: {response:?}"
+    );
+
+    // Strip the large attachment
+    let response = jmap_json_request(
+        r#"[[ "Email/stripAttachments", {
+            "accountId": "$$",
+            "emailIds": [ "%%" ],
+            "minSize": 1024
+          }, "0" ]]"#
+            .replace("$$", &account_id.to_string())
+            .replace("%%", &email_id),
+        "strip@example.com",
+        "12345",
+    )
+    .await;
+    let stripped_id = response
+        .pointer(&format!("/methodResponses/0/1/stripped/{email_id}/id"))
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Response: {response:?}"))
+        .to_string();
+    assert_ne!(stripped_id, email_id);
+
+    // The original message is replaced by the stripped copy
+    assert!(
+        params
+            .client
+            .email_get(&email_id, None::<Vec<_>>)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let stripped = params
+        .client
+        .email_get(&stripped_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stripped.mailbox_ids(), &[&inbox_id]);
+    assert_eq!(stripped.keywords(), &["$seen"]);
+    assert_eq!(stripped.received_at().unwrap(), 311923920);
+    assert!(stripped.size() < 2048, "size: {}", stripped.size());
+
+    let contents = String::from_utf8(
+        params
+            .client
+            .download(stripped.blob_id().unwrap())
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(contents.contains("Please find the report attached."));
+    assert!(contents.contains("Short notes."));
+    assert!(contents.contains("The attachment \"report.pdf\" (application/pdf"));
+    assert!(!contents.contains(&large_attachment));
+
+    // Remove test data
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
@@ -0,0 +1,100 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use common::Server;
+use http::health::{DeepHealthCheck, HealthStatus};
+use hyper::StatusCode;
+use store::Store;
+
+use super::JMAPTest;
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running health check tests...");
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+
+    // Deep checks require credentials when configured
+    for auth in [None, Some("wrong-secret")] {
+        let mut request = client.get("https://127.0.0.1:8899/healthz/deep");
+        if let Some(secret) = auth {
+            request = request.basic_auth("healthz", Some(secret));
+        }
+        assert_eq!(
+            request.send().await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    // All configured backends are probed
+    let response = client
+        .get("https://127.0.0.1:8899/healthz/deep")
+        .basic_auth("healthz", Some("healthz-secret"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report =
+        serde_json::from_slice::<serde_json::Value>(&response.bytes().await.unwrap()).unwrap();
+    assert_eq!(report["status"], "ok", "{report}");
+    let checks = report["checks"].as_array().unwrap();
+    let storage = &params.server.core.storage;
+    assert_eq!(
+        checks.len(),
+        storage.stores.len()
+            + storage.blobs.len()
+            + storage.lookups.len()
+            + storage.directories.len()
+    );
+    for typ in ["data", "blob", "lookup", "directory"] {
+        assert!(
+            checks.iter().any(|check| check["type"] == typ),
+            "{typ}: {report}"
+        );
+    }
+    assert!(checks.iter().all(|check| check["status"] == "ok"));
+
+    // Reports are cached between requests
+    let report = params
+        .server
+        .cached_deep_health_check(Duration::from_secs(60))
+        .await;
+    assert!(Arc::ptr_eq(
+        &report,
+        &params
+            .server
+            .cached_deep_health_check(Duration::from_secs(60))
+            .await
+    ));
+
+    // Failing backends are reported
+    let mut core = params.server.core.as_ref().clone();
+    core.storage
+        .stores
+        .insert("broken".to_string(), Store::None);
+    let server = Server {
+        inner: params.server.inner.clone(),
+        core: Arc::new(core),
+    };
+    let report = server.deep_health_check().await;
+    assert_eq!(report.status, HealthStatus::Error);
+    for check in &report.checks {
+        assert_eq!(
+            check.status,
+            if check.id == "broken" {
+                HealthStatus::Error
+            } else {
+                HealthStatus::Ok
+            },
+            "{check:?}"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::JMAPTest;
+use crate::{directory::internal::TestInternalDirectory, jmap::assert_is_empty};
+use common::config::jmap::identity::{SignatureMode, SignatureTemplate};
+use directory::{
+    QueryBy,
+    backend::internal::{
+        PrincipalField, PrincipalUpdate, PrincipalValue,
+        manage::{ManageDirectory, UpdatePrincipal},
+    },
+};
+use jmap_client::{Error, core::set::SetErrorType};
+use types::id::Id;
+use utils::config::Config;
+
+const CONFIG: &str = r#"
+[email.identity.signature.corporate]
+domains = ["example.com"]
+text = "-- \n{{name}}\n{{title}}"
+html = "<p>{{name}}<br>{{title}}</p>"
+mode = "read-only"
+
+[email.identity.signature.fallback]
+text = "Sent by {{account}} <{{email}}>"
+
+[email.identity.signature.empty]
+mode = "read-only"
+
+[email.identity.signature.invalid-mode]
+text = "Test"
+mode = "mandatory"
+"#;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running identity signature tests...");
+
+    // Parse signature templates
+    let mut config = Config::new(CONFIG).unwrap();
+    let templates = SignatureTemplate::parse(&mut config);
+    assert_eq!(templates.len(), 2);
+    assert_eq!(templates[0].mode, SignatureMode::ReadOnly);
+    assert_eq!(templates[0].domains, vec!["example.com".to_string()]);
+    assert_eq!(templates[1].mode, SignatureMode::Default);
+    assert!(templates[1].html.is_none());
+    for key in [
+        "email.identity.signature.empty",
+        "email.identity.signature.invalid-mode.mode",
+    ] {
+        assert!(config.errors.contains_key(key), "{:?}", config.errors);
+    }
+
+    // Enable signature templates
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.identity_signatures = templates;
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.clone();
+    let account_id = server
+        .store()
+        .create_test_user(
+            "signature@example.com",
+            "secret",
+            "Jane Signature",
+            &["signature@example.com", "signature@example.org"],
+        )
+        .await;
+    server
+        .store()
+        .update_principal(UpdatePrincipal::by_id(account_id).with_updates(vec![
+            PrincipalUpdate::set(
+                PrincipalField::Attributes,
+                PrincipalValue::StringList(vec!["title=Sales & Marketing".into()]),
+            ),
+        ]))
+        .await
+        .unwrap();
+    let client = &mut params.client;
+    client.set_default_account_id(Id::from(account_id));
+
+    // Read-only signatures are rendered from the principal fields
+    let corporate_id = client
+        .identity_create("Jane Signature", "signature@example.com")
+        .await
+        .unwrap()
+        .take_id();
+    let identity = client
+        .identity_get(&corporate_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        identity.text_signature().unwrap(),
+        "-- \nJane Signature\nSales & Marketing"
+    );
+    assert_eq!(
+        identity.html_signature().unwrap(),
+        "<p>Jane Signature<br>Sales &amp; Marketing</p>"
+    );
+
+    // Read-only signatures cannot be set by clients
+    let mut request = client.build();
+    let create_id = request
+        .set_identity()
+        .create()
+        .name("Jane Signature")
+        .email("signature@example.com")
+        .text_signature("My own signature")
+        .create_id()
+        .unwrap();
+    assert_forbidden(
+        request
+            .send_set_identity()
+            .await
+            .unwrap()
+            .created(&create_id),
+    );
+    let mut request = client.build();
+    request
+        .set_identity()
+        .update(&corporate_id)
+        .html_signature("<p>My own signature</p>");
+    assert_forbidden(
+        request
+            .send_set_identity()
+            .await
+            .unwrap()
+            .updated(&corporate_id),
+    );
+
+    // Default signatures are used until the user sets their own
+    let fallback_id = client
+        .identity_create("Jane Signature", "signature@example.org")
+        .await
+        .unwrap()
+        .take_id();
+    let identity = client
+        .identity_get(&fallback_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        identity.text_signature().unwrap(),
+        "Sent by signature@example.com <signature@example.org>"
+    );
+    assert_eq!(identity.html_signature().unwrap(), "");
+    let mut request = client.build();
+    request
+        .set_identity()
+        .update(&fallback_id)
+        .text_signature("Jane");
+    request
+        .send_set_identity()
+        .await
+        .unwrap()
+        .updated(&fallback_id)
+        .unwrap();
+    let identity = client
+        .identity_get(&fallback_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(identity.text_signature().unwrap(), "Jane");
+
+    // Cleanup
+    params.server.inner.shared_core.store(original_core);
+    for identity_id in [corporate_id, fallback_id] {
+        client.identity_destroy(&identity_id).await.unwrap();
+    }
+    server
+        .store()
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
+
+fn assert_forbidden<T: std::fmt::Debug>(result: Result<T, Error>) {
+    match result {
+        Err(Error::Set(err)) if err.error() == &SetErrorType::Forbidden => (),
+        result => panic!("Expected Forbidden SetError, got {result:?}"),
+    }
+}
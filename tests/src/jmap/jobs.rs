@@ -0,0 +1,218 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use directory::backend::internal::manage::ManageDirectory;
+use serde_json::Value;
+use services::job_manager::{Job, JobKind, JobManager, JobStatus};
+use store::{
+    SerializeInfallible,
+    write::{BatchBuilder, TaskQueueClass, ValueClass, now},
+};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{JMAPTest, ManagementApi, assert_is_empty},
+};
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running background job tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+    clear_jobs(params).await;
+
+    // Create test accounts
+    let mut account_ids = Vec::new();
+    for name in ["jobs-a@example.com", "jobs-b@example.com"] {
+        account_ids.push(
+            server
+                .store()
+                .create_test_user(name, "secret", name, &[name])
+                .await,
+        );
+    }
+
+    // Reindexing runs as a background job
+    api.get::<Value>("/api/store/reindex/jobs-a@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let job = wait_for_job(&api, latest_job_id(&api, "ftsReindex").await).await;
+    assert_eq!(job["status"], "completed", "{job}");
+    assert_eq!(job["items"], serde_json::json!([account_ids[0]]), "{job}");
+    assert_eq!(job["done"], 1, "{job}");
+
+    // Bulk deletions run as a background job
+    api.delete::<Value>("/api/principal?type=individual&filter=jobs-&confirm=true")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let job_id = latest_job_id(&api, "deletePrincipals").await;
+    let job = wait_for_job(&api, job_id).await;
+    assert_eq!(job["status"], "completed", "{job}");
+    assert_eq!(job["done"], 2, "{job}");
+    for name in ["jobs-a@example.com", "jobs-b@example.com"] {
+        assert_eq!(server.store().get_principal_id(name).await.unwrap(), None);
+    }
+
+    // Finished jobs cannot be cancelled
+    assert_eq!(
+        api.delete::<Value>(&format!("/api/jobs/{job_id}"))
+            .await
+            .unwrap()
+            .unwrap_data(),
+        Value::Bool(false)
+    );
+
+    // Cancel a job that is not yet due
+    let job = Job {
+        id: server.inner.data.queue_id_gen.generate(),
+        kind: JobKind::FtsReindex,
+        status: JobStatus::Pending,
+        tenant_id: None,
+        created: now(),
+        updated: now(),
+        due: now() + 3600,
+        attempts: 0,
+        items: vec![u32::MAX],
+        done: 0,
+        error: None,
+    };
+    let mut batch = BatchBuilder::new();
+    batch.set(
+        ValueClass::TaskQueue(TaskQueueClass::Job { id: job.id }),
+        serde_json::to_vec(&job).unwrap(),
+    );
+    server.store().write(batch.build_all()).await.unwrap();
+    assert_eq!(
+        api.get::<Value>(&format!("/api/jobs/{}", job.id))
+            .await
+            .unwrap()
+            .unwrap_data()["status"],
+        "pending"
+    );
+    for expected in [true, false] {
+        assert_eq!(
+            api.delete::<Value>(&format!("/api/jobs/{}", job.id))
+                .await
+                .unwrap()
+                .unwrap_data(),
+            Value::Bool(expected)
+        );
+    }
+    let cancelled = server.get_job(job.id).await.unwrap().unwrap();
+    assert_eq!(cancelled.status, JobStatus::Cancelled);
+    assert!(cancelled.is_finished());
+
+    // Jobs can be filtered by status
+    let jobs = api
+        .get::<Value>("/api/jobs?status=cancelled")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(jobs["total"], 1, "{jobs}");
+    assert_eq!(jobs["items"][0]["id"], job.id, "{jobs}");
+    let jobs = api
+        .get::<Value>("/api/jobs?limit=1")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(jobs["total"], 3, "{jobs}");
+    assert_eq!(jobs["items"].as_array().unwrap().len(), 1, "{jobs}");
+    assert_eq!(jobs["items"][0]["id"], job.id, "{jobs}");
+
+    // Jobs leased by another node are not started until the lease expires
+    let job = Job {
+        id: server.inner.data.queue_id_gen.generate(),
+        due: now(),
+        items: vec![],
+        ..job
+    };
+    let mut batch = BatchBuilder::new();
+    batch
+        .set(
+            ValueClass::TaskQueue(TaskQueueClass::Job { id: job.id }),
+            serde_json::to_vec(&job).unwrap(),
+        )
+        .set(
+            ValueClass::TaskQueue(TaskQueueClass::JobLock { id: job.id }),
+            (now() + 3600).serialize(),
+        );
+    server.store().write(batch.build_all()).await.unwrap();
+    server.notify_job_manager();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(
+        server.get_job(job.id).await.unwrap().unwrap().status,
+        JobStatus::Pending
+    );
+    let mut batch = BatchBuilder::new();
+    batch.set(
+        ValueClass::TaskQueue(TaskQueueClass::JobLock { id: job.id }),
+        (now() - 1).serialize(),
+    );
+    server.store().write(batch.build_all()).await.unwrap();
+    server.notify_job_manager();
+    assert_eq!(wait_for_job(&api, job.id).await["status"], "completed");
+
+    // Unknown jobs
+    for path in ["/api/jobs/12345", "/api/jobs/invalid"] {
+        assert!(
+            api.get::<Value>(path)
+                .await
+                .unwrap()
+                .try_unwrap_data()
+                .is_none(),
+            "{path}"
+        );
+    }
+
+    // Cleanup
+    clear_jobs(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn latest_job_id(api: &ManagementApi, kind: &str) -> u64 {
+    api.get::<Value>("/api/jobs").await.unwrap().unwrap_data()["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|job| job["kind"]["type"] == kind)
+        .and_then(|job| job["id"].as_u64())
+        .unwrap_or_else(|| panic!("No {kind} job found"))
+}
+
+async fn wait_for_job(api: &ManagementApi, job_id: u64) -> Value {
+    for _ in 0..100 {
+        let job = api
+            .get::<Value>(&format!("/api/jobs/{job_id}"))
+            .await
+            .unwrap()
+            .unwrap_data();
+        if matches!(job["status"].as_str(), Some("completed" | "failed")) {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Job {job_id} did not finish");
+}
+
+async fn clear_jobs(params: &JMAPTest) {
+    for job in params.server.list_jobs().await.unwrap() {
+        let mut batch = BatchBuilder::new();
+        batch
+            .clear(ValueClass::TaskQueue(TaskQueueClass::Job { id: job.id }))
+            .clear(ValueClass::TaskQueue(TaskQueueClass::JobLock {
+                id: job.id,
+            }));
+        params
+            .server
+            .store()
+            .write(batch.build_all())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::JMAPTest;
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes_no_wait},
+};
+use common::config::jmap::mailbox::{MailboxLimit, MailboxOverflow};
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use email::cache::{MessageCacheFetch, email::MessageCacheAccess};
+use jmap_client::{core::set::SetErrorType, mailbox::Role};
+use std::str::FromStr;
+use types::id::Id;
+use utils::config::Config;
+
+const CONFIG: &str = r#"
+[email.folder-limit.intake]
+path = "Intake"
+max-messages = 1
+
+[email.folder-limit.archive]
+role = "archive"
+max-messages = 2
+overflow = "expire"
+
+[email.folder-limit.no-folder]
+max-messages = 1
+
+[email.folder-limit.no-limit]
+path = "Unlimited"
+
+[email.folder-limit.bad-overflow]
+path = "Invalid"
+max-size = 1024
+overflow = "discard"
+"#;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running mailbox limit tests...");
+
+    // Parse folder limits
+    let mut config = Config::new(CONFIG).unwrap();
+    let limits = MailboxLimit::parse(&mut config);
+    assert_eq!(limits.len(), 2);
+    assert_eq!(limits[0].overflow, MailboxOverflow::ExpireOldest);
+    assert_eq!(limits[1].overflow, MailboxOverflow::Reject);
+    for key in [
+        "email.folder-limit.no-folder",
+        "email.folder-limit.no-limit",
+        "email.folder-limit.bad-overflow.overflow",
+    ] {
+        assert!(config.errors.contains_key(key), "{:?}", config.errors);
+    }
+
+    // Enable folder limits
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mailbox_limits = limits;
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.clone();
+    let account_id = server
+        .store()
+        .create_test_user(
+            "limits@example.com",
+            "secret",
+            "Folder Limits",
+            &["limits@example.com"],
+        )
+        .await;
+    let client = &mut params.client;
+    client.set_default_account_id(Id::from(account_id));
+    let intake_id = client
+        .mailbox_create("Intake", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let archive_id = client
+        .mailbox_create("Old Mail", None::<String>, Role::Archive)
+        .await
+        .unwrap()
+        .take_id();
+
+    // Messages exceeding the limit are rejected
+    let message = |num: usize| {
+        format!(
+            concat!(
+                "From: bill@example.com\r\n",
+                "To: limits@example.com\r\n",
+                "Subject: Folder limit test {}\r\n",
+                "\r\n",
+                "Test message."
+            ),
+            num
+        )
+        .into_bytes()
+    };
+    client
+        .email_import(message(0), [&intake_id], None::<Vec<&str>>, None)
+        .await
+        .unwrap();
+    match client
+        .email_import(message(1), [&intake_id], None::<Vec<&str>>, None)
+        .await
+    {
+        Err(jmap_client::Error::Set(err)) if err.error() == &SetErrorType::OverQuota => (),
+        result => panic!("Expected OverQuota SetError, got {result:?}"),
+    }
+
+    // The oldest messages are expired to make room for new ones
+    let mut archived_ids = Vec::new();
+    for num in 1..=3 {
+        archived_ids.push(
+            client
+                .email_import(message(num), [&archive_id], None::<Vec<&str>>, None)
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+    assert!(
+        client
+            .email_get(&archived_ids[0], None::<Vec<_>>)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    for email_id in &archived_ids[1..] {
+        assert!(
+            client
+                .email_get(email_id, None::<Vec<_>>)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+    let archive_id = Id::from_str(&archive_id).unwrap().document_id();
+    assert_eq!(
+        server
+            .get_cached_messages(account_id)
+            .await
+            .unwrap()
+            .in_mailbox(archive_id)
+            .count(),
+        2
+    );
+
+    // Cleanup
+    params.server.inner.shared_core.store(original_core);
+    destroy_all_mailboxes_no_wait(client).await;
+    server
+        .store()
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
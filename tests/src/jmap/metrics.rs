@@ -0,0 +1,156 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{net::IpAddr, sync::Arc, time::Duration};
+
+use common::{
+    Server,
+    auth::AuthRequest,
+    config::telemetry::Metrics,
+    telemetry::metrics::labels::{LabeledMetric, OTHER_LABEL},
+};
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use utils::config::Config;
+
+use crate::{
+    AssertConfig, directory::internal::TestInternalDirectory, smtp::session::VerifyResponse,
+};
+
+use super::JMAPTest;
+
+const CONFIG: &str = r#"
+[metrics.prometheus]
+enable = true
+
+[metrics.prometheus.labels]
+max-cardinality = 2
+
+[metrics.prometheus.labels.domain]
+metrics = ["messages-in", "messages-out", "auth-failures"]
+
+[metrics.prometheus.labels.tenant]
+metrics = ["messages-in", "storage-bytes"]
+allow = ["metrics-acme"]
+"#;
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running labeled metrics tests...");
+
+    // Parse labeled metrics settings
+    let mut core = params.server.core.as_ref().clone();
+    let mut config = Config::new(CONFIG).unwrap();
+    core.metrics = Metrics::parse(&mut config);
+    config.assert_no_errors();
+    let labels = &core.metrics.prometheus.as_ref().unwrap().labels;
+    assert_eq!(labels.max_cardinality, 2);
+    assert!(
+        labels
+            .tenant
+            .as_ref()
+            .unwrap()
+            .metrics
+            .contains(&LabeledMetric::StorageBytes)
+    );
+    let server = Server {
+        inner: params.server.inner.clone(),
+        core: Arc::new(core),
+    };
+
+    // Calculation intervals default to 5 minutes and 1 day
+    let metrics = server.core.metrics.clone();
+    assert_eq!(metrics.calculate_interval, Duration::from_secs(5 * 60));
+    assert_eq!(metrics.count_interval, Duration::from_secs(86400));
+
+    // Calculation intervals are configurable with a lower bound
+    for (interval, count_interval, expected_interval, expected_count_interval) in
+        [("30s", "1h", 30, 3600), ("0s", "5s", 1, 60)]
+    {
+        let mut config = Config::new(format!(
+            "[metrics.calculate]\ninterval = \"{interval}\"\ncount-interval = \"{count_interval}\"\n"
+        ))
+        .unwrap();
+        let metrics = Metrics::parse(&mut config);
+        config.assert_no_errors();
+        assert_eq!(
+            metrics.calculate_interval,
+            Duration::from_secs(expected_interval)
+        );
+        assert_eq!(
+            metrics.count_interval,
+            Duration::from_secs(expected_count_interval)
+        );
+    }
+
+    // Labeled metrics are only recorded when configured
+    params
+        .server
+        .record_labeled_metric(LabeledMetric::MessagesIn, Some("example.com"), None);
+
+    // Failed logins are counted by domain
+    let remote_ip: IpAddr = "10.9.8.7".parse().unwrap();
+    for login in ["nobody@Example.com", "nobody@example.com"] {
+        assert!(
+            server
+                .authenticate(&AuthRequest::from_plain(login, "wrong", 0, remote_ip))
+                .await
+                .is_err()
+        );
+    }
+
+    // Domains exceeding the cardinality cap are reported as "other"
+    for domain in ["example.com", "example.net", "example.org", "example.edu"] {
+        server.record_labeled_metric(LabeledMetric::MessagesIn, Some(domain), None);
+    }
+    server.record_labeled_metric(LabeledMetric::MessagesOut, Some("example.org"), None);
+
+    // Tenants outside the allowlist are reported as "other"
+    let store = server.core.storage.data.clone();
+    let acme_id = store
+        .create_test_group("metrics-acme", "Acme", &["metrics-acme@example.com"])
+        .await;
+    let other_id = store
+        .create_test_group("metrics-other", "Other", &["metrics-other@example.com"])
+        .await;
+    for tenant_id in [acme_id, acme_id, other_id] {
+        server.record_labeled_metric(LabeledMetric::MessagesIn, None, Some(tenant_id));
+    }
+
+    server
+        .export_prometheus_metrics()
+        .await
+        .unwrap()
+        .lines()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .assert_contains("auth_failures_by_domain{domain=\"example.com\"} 2")
+        .assert_contains("messages_in_by_domain{domain=\"example.com\"} 1")
+        .assert_contains("messages_in_by_domain{domain=\"example.net\"} 1")
+        .assert_contains(&format!(
+            "messages_in_by_domain{{domain=\"{OTHER_LABEL}\"}} 2"
+        ))
+        .assert_contains("messages_out_by_domain{domain=\"example.org\"} 1")
+        .assert_contains("messages_in_by_tenant{tenant=\"metrics-acme\"} 2")
+        .assert_contains(&format!(
+            "messages_in_by_tenant{{tenant=\"{OTHER_LABEL}\"}} 1"
+        ))
+        .assert_contains("storage_bytes_by_tenant{tenant=\"metrics-acme\"} 0")
+        .assert_not_contains("metrics-other");
+
+    // Unconfigured dimensions are not exported
+    assert!(
+        !params
+            .server
+            .export_prometheus_metrics()
+            .await
+            .unwrap()
+            .contains("_by_domain")
+    );
+
+    // Cleanup
+    for name in ["metrics-acme", "metrics-other"] {
+        store.delete_principal(QueryBy::Name(name)).await.unwrap();
+    }
+}
@@ -48,12 +48,19 @@ use types::{blob_hash::BlobHash, id::Id};
 use utils::config::Config;
 use webhooks::{MockWebhookEndpoint, spawn_mock_webhook_endpoint};
 
+pub mod acme;
 pub mod auth_acl;
+pub mod auth_certificate;
 pub mod auth_limits;
 pub mod auth_oauth;
+pub mod auth_password;
+pub mod avatar;
 pub mod blob;
+pub mod branding;
 pub mod crypto;
 pub mod delivery;
+pub mod dns;
+pub mod dumpster;
 pub mod email_changes;
 pub mod email_copy;
 pub mod email_get;
@@ -62,17 +69,29 @@ pub mod email_query;
 pub mod email_query_changes;
 pub mod email_search_snippet;
 pub mod email_set;
+pub mod email_strip;
 pub mod email_submission;
 pub mod enterprise;
 pub mod event_source;
+pub mod health;
+pub mod identity;
+pub mod jobs;
 pub mod mailbox;
+pub mod mailbox_limit;
+pub mod metrics;
+pub mod orphans;
 pub mod permissions;
 pub mod purge;
 pub mod push_subscription;
 pub mod quota;
+pub mod reload;
+pub mod settings;
 pub mod sieve_script;
+pub mod spam_model;
 pub mod thread_get;
 pub mod thread_merge;
+pub mod troubleshoot;
+pub mod usage_report;
 pub mod vacation_response;
 pub mod webhooks;
 pub mod websocket;
@@ -103,17 +122,36 @@ async fn jmap_tests() {
     auth_acl::test(&mut params).await;
     auth_limits::test(&mut params).await;
     auth_oauth::test(&mut params).await;
+    auth_password::test(&mut params).await;
+    auth_certificate::test(&params).await;
     event_source::test(&mut params).await;
     push_subscription::test(&mut params).await;
     sieve_script::test(&mut params).await;
     vacation_response::test(&mut params).await;
     email_submission::test(&mut params).await;
+    identity::test(&mut params).await;
     websocket::test(&mut params).await;
+    health::test(&params).await;
+    metrics::test(&params).await;
+    spam_model::test(&params).await;
+    troubleshoot::test().await;
+    dns::test().await;
+    branding::test(&params).await;
+    usage_report::test(&params).await;
+    jobs::test(&params).await;
+    settings::test(&params).await;
+    reload::test(&params).await;
+    acme::test(&params).await;
+    avatar::test(&params).await;
     quota::test(&mut params).await;
     crypto::test(&mut params).await;
     blob::test(&mut params).await;
+    email_strip::test(&mut params).await;
+    dumpster::test(&mut params).await;
+    mailbox_limit::test(&mut params).await;
     permissions::test(&params).await;
     purge::test(&mut params).await;
+    orphans::test(&params).await;
     enterprise::test(&mut params).await;
 
     if delete {
@@ -603,12 +641,61 @@ impl ManagementApi {
         })
     }
 
+    pub async fn get_with_etag<T: DeserializeOwned>(
+        &self,
+        query: &str,
+    ) -> Result<(Response<T>, Option<String>), String> {
+        self.request_with_etag(Method::GET, query, None, None)
+            .await
+            .map(|(result, etag)| {
+                (
+                    serde_json::from_str::<Response<T>>(&result)
+                        .unwrap_or_else(|err| panic!("{err}: {result}")),
+                    etag,
+                )
+            })
+    }
+
+    pub async fn patch_if_match<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        body: &impl Serialize,
+        if_match: &str,
+    ) -> Result<(Response<T>, Option<String>), String> {
+        self.request_with_etag(
+            Method::PATCH,
+            query,
+            Some(serde_json::to_string(body).unwrap()),
+            Some(if_match),
+        )
+        .await
+        .map(|(result, etag)| {
+            (
+                serde_json::from_str::<Response<T>>(&result)
+                    .unwrap_or_else(|err| panic!("{err}: {result}")),
+                etag,
+            )
+        })
+    }
+
     async fn request_raw(
         &self,
         method: Method,
         query: &str,
         body: Option<String>,
     ) -> Result<String, String> {
+        self.request_with_etag(method, query, body, None)
+            .await
+            .map(|(result, _)| result)
+    }
+
+    async fn request_with_etag(
+        &self,
+        method: Method,
+        query: &str,
+        body: Option<String>,
+        if_match: Option<&str>,
+    ) -> Result<(String, Option<String>), String> {
         let mut request = reqwest::Client::builder()
             .timeout(Duration::from_millis(500))
             .danger_accept_invalid_certs(true)
@@ -619,8 +706,11 @@ impl ManagementApi {
         if let Some(body) = body {
             request = request.body(body);
         }
+        if let Some(if_match) = if_match {
+            request = request.header(header::IF_MATCH, if_match);
+        }
 
-        request
+        let response = request
             .header(
                 AUTHORIZATION,
                 format!(
@@ -630,10 +720,17 @@ impl ManagementApi {
             )
             .send()
             .await
-            .map_err(|err| err.to_string())?
+            .map_err(|err| err.to_string())?;
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        response
             .bytes()
             .await
-            .map(|bytes| String::from_utf8(bytes.to_vec()).unwrap())
+            .map(|bytes| (String::from_utf8(bytes.to_vec()).unwrap(), etag))
             .map_err(|err| err.to_string())
     }
 }
@@ -729,6 +826,11 @@ hostname = "'jmap.example.org'"
 [http]
 url = "'https://127.0.0.1:8899'"
 
+[http.health.deep]
+enable = true
+auth.username = "healthz"
+auth.secret = "healthz-secret"
+
 [server.listener.jmap]
 bind = ["127.0.0.1:8899"]
 protocol = "http"
@@ -740,6 +842,28 @@ bind = ["127.0.0.1:9991"]
 protocol = "imap"
 max-connections = 81920
 
+[server.listener.jmap-cert]
+bind = ["127.0.0.1:8898"]
+protocol = "http"
+tls.implicit = true
+tls.client-cert.policy = "optional"
+tls.client-cert.ca-certificate = "%{file:{CLIENT_CA}}%"
+
+[server.listener.imap-cert]
+bind = ["127.0.0.1:9993"]
+protocol = "imap"
+tls.implicit = true
+tls.client-cert.policy = "optional"
+tls.client-cert.ca-certificate = "%{file:{CLIENT_CA}}%"
+
+[server.listener.submission-cert]
+bind = ["127.0.0.1:4465"]
+protocol = "smtp"
+tls.implicit = true
+tls.client-cert.policy = "optional"
+tls.client-cert.mapping = "fingerprint"
+tls.client-cert.ca-certificate = "%{file:{CLIENT_CA}}%"
+
 [server.listener.lmtp-debug]
 bind = ['127.0.0.1:11200']
 greeting = 'Test LMTP instance'
@@ -779,9 +903,13 @@ total = 5
 wait = "1ms"
 
 [session.auth]
-mechanisms = "[plain, login, oauthbearer]"
+mechanisms = "[plain, login, oauthbearer, external]"
 directory = "'{STORE}'"
 
+[session.auth.errors]
+total = 10
+wait = "1ms"
+
 [session.data]
 spam-filter = "recipients[0] != 'robert@example.com'"
 
@@ -892,10 +1020,20 @@ throttle = "500ms"
 [jmap.web-sockets]
 throttle = "500ms"
 
+[jmap.web-socket]
+max-in-flight = 2
+
 [jmap.push]
 throttle = "500ms"
 attempts.interval = "500ms"
 
+[jmap.push.bridge."android"]
+type = "fcm"
+project-id = "stalwart-test"
+client-email = "push@stalwart-test.iam.gserviceaccount.com"
+private-key = "%{file:{PK}}%"
+token-url = "https://127.0.0.1:9000/fcm/token"
+
 [email]
 auto-expunge = "1s"
 
@@ -0,0 +1,179 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use services::housekeeper::orphans::OrphanScanning;
+use store::{
+    BlobStore, SerializeInfallible, Store,
+    write::{BatchBuilder, BlobOp, now},
+};
+use types::{blob_hash::BlobHash, collection::Collection};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{JMAPTest, assert_is_empty},
+};
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running orphaned data tests...");
+    let server = params.server.clone();
+    let store = server.store().clone();
+    let blob_store = server.blob_store().clone();
+
+    // Create one account that is kept and one that is deleted
+    let account_id = store
+        .create_test_user(
+            "orphans-a@example.com",
+            "secret",
+            "Orphans A",
+            &["orphans-a@example.com"],
+        )
+        .await;
+    let deleted_id = store
+        .create_test_user(
+            "orphans-b@example.com",
+            "secret",
+            "Orphans B",
+            &["orphans-b@example.com"],
+        )
+        .await;
+    store
+        .delete_principal(QueryBy::Id(deleted_id))
+        .await
+        .unwrap();
+
+    // Linked, reserved and unreferenced blobs
+    let linked = commit_blob(&store, &blob_store, b"orphans linked").await;
+    let reserved = commit_blob(&store, &blob_store, b"orphans reserved").await;
+    let unreferenced = commit_blob(&store, &blob_store, b"orphans unreferenced").await;
+    let deleted_blob = commit_blob(&store, &blob_store, b"orphans deleted account").await;
+    let until = now() + 3600;
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::Email)
+        .update_document(0)
+        .set(
+            BlobOp::Link {
+                hash: linked.clone(),
+            },
+            Vec::new(),
+        )
+        .set(
+            BlobOp::Reserve {
+                until,
+                hash: reserved.clone(),
+            },
+            1024u32.serialize(),
+        );
+
+    // Data left behind by the deleted account
+    batch
+        .with_account_id(deleted_id)
+        .with_collection(Collection::Mailbox)
+        .create_document(0)
+        .set(
+            BlobOp::Link {
+                hash: deleted_blob.clone(),
+            },
+            Vec::new(),
+        );
+    store.write(batch.build_all()).await.unwrap();
+
+    // Scanning without repair only reports
+    let report = server.build_orphan_report(false).await.unwrap();
+    assert!(!report.repaired);
+    assert_eq!(report.blobs.total, 1, "{report:?}");
+    assert_eq!(report.blobs.items, vec![unreferenced.to_hex()]);
+    assert_eq!(report.accounts.total, 1, "{report:?}");
+    assert_eq!(report.accounts.items, vec![deleted_id]);
+    assert!(has_blob(&blob_store, &unreferenced).await);
+    assert!(
+        server
+            .get_document_ids(deleted_id, Collection::Mailbox)
+            .await
+            .unwrap()
+            .is_some_and(|ids| !ids.is_empty())
+    );
+    assert_eq!(
+        server
+            .orphan_report()
+            .await
+            .unwrap()
+            .map(|report| report.blobs.items),
+        Some(vec![unreferenced.to_hex()])
+    );
+
+    // Repair removes the orphaned data
+    let report = server.build_orphan_report(true).await.unwrap();
+    assert!(report.repaired);
+    assert_eq!(report.blobs.items, vec![unreferenced.to_hex()]);
+    assert_eq!(report.accounts.items, vec![deleted_id]);
+    for hash in [&unreferenced, &deleted_blob] {
+        assert!(!store.blob_exists(hash).await.unwrap());
+        assert!(!has_blob(&blob_store, hash).await);
+    }
+    assert!(
+        server
+            .get_document_ids(deleted_id, Collection::Mailbox)
+            .await
+            .unwrap()
+            .is_none_or(|ids| ids.is_empty())
+    );
+
+    // Referenced and reserved blobs are kept
+    for hash in [&linked, &reserved] {
+        assert!(store.blob_exists(hash).await.unwrap());
+        assert!(has_blob(&blob_store, hash).await);
+    }
+
+    // Nothing is left to report
+    let report = server.build_orphan_report(false).await.unwrap();
+    assert_eq!(report.blobs.total, 0, "{report:?}");
+    assert_eq!(report.accounts.total, 0, "{report:?}");
+
+    // Cleanup
+    store
+        .write(
+            BatchBuilder::new()
+                .with_account_id(account_id)
+                .clear(BlobOp::Reserve {
+                    until,
+                    hash: reserved,
+                })
+                .build_all(),
+        )
+        .await
+        .unwrap();
+    store
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    store.purge_blobs(blob_store).await.unwrap();
+    assert_is_empty(server).await;
+}
+
+async fn commit_blob(store: &Store, blob_store: &BlobStore, data: &[u8]) -> BlobHash {
+    let hash = BlobHash::generate(data);
+    blob_store.put_blob(hash.as_ref(), data).await.unwrap();
+    store
+        .write(
+            BatchBuilder::new()
+                .set(BlobOp::Commit { hash: hash.clone() }, Vec::new())
+                .build_all(),
+        )
+        .await
+        .unwrap();
+    hash
+}
+
+async fn has_blob(blob_store: &BlobStore, hash: &BlobHash) -> bool {
+    blob_store
+        .get_blob(hash.as_ref(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_some()
+}
@@ -110,6 +110,44 @@ pub async fn test(params: &JMAPTest) {
     .unwrap()
     .unwrap_data();
 
+    // Patch the email_user role using a field mask and its revision
+    let (_, etag) = api
+        .get_with_etag::<serde_json::Value>("/api/principal/email_user")
+        .await
+        .unwrap();
+    let etag = etag.expect("missing ETag");
+    let patch = serde_json::json!({
+        "mask": ["description"],
+        "set": {"description": "Email user"},
+        "add": {"disabledPermissions": [Permission::EmailSend.name()]},
+    });
+    let (response, new_etag) = api
+        .patch_if_match::<()>("/api/principal/email_user", &patch, &etag)
+        .await
+        .unwrap();
+    response.unwrap_data();
+    let new_etag = new_etag.expect("missing ETag");
+    assert_ne!(etag, new_etag);
+    let (principal, current_etag) = api
+        .get_with_etag::<serde_json::Value>("/api/principal/email_user")
+        .await
+        .unwrap();
+    let principal = principal.unwrap_data();
+    assert_eq!(current_etag.as_deref(), Some(new_etag.as_str()));
+    assert_eq!(principal["description"], "Email user");
+    assert!(
+        !principal["disabledPermissions"]
+            .to_string()
+            .contains(Permission::EmailSend.name())
+    );
+
+    // Stale revisions must be rejected
+    api.patch_if_match::<()>("/api/principal/email_user", &patch, &etag)
+        .await
+        .unwrap()
+        .0
+        .expect_error("assertFailed");
+
     // Update the user role to the nested 'email_user' role
     api.patch::<()>(
         "/api/principal/role_player",
@@ -19,6 +19,7 @@ use email::{
     message::delete::EmailDeletion,
 };
 use imap_proto::ResponseType;
+use std::time::Duration;
 use store::{IterateParams, LogKey, U32_LEN, U64_LEN, write::key::DeserializeBigEndian};
 use types::{collection::Collection, id::Id};
 
@@ -157,6 +158,60 @@ pub async fn test(params: &mut JMAPTest) {
         );
     }
 
+    // Expunged UIDs are retained after the changes are purged
+    imap.send("ENABLE CONDSTORE QRESYNC").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("SELECT INBOX").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("UID STORE 1 +FLAGS.SILENT (\\Deleted)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("EXPUNGE").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("VANISHED 1");
+    server.purge_account(account_id).await;
+    imap.send("UID FETCH 1:* (FLAGS) (CHANGEDSINCE 1 VANISHED)")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_count("VANISHED", 1)
+        .assert_contains("VANISHED (EARLIER) 1");
+
+    // Compacting the journal removes entries older than the retention period
+    client
+        .email_import(
+            concat!(
+                "From: bill@example.com\r\n",
+                "To: jdoe@example.com\r\n",
+                "Subject: TPS Report #3\r\n",
+                "\r\n",
+                "Did you get the memo?"
+            )
+            .as_bytes()
+            .to_vec(),
+            [&inbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap();
+    imap.send("UID MOVE 2 \"Deleted Items\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("VANISHED 2");
+    for _ in 0..2 {
+        server
+            .compact_vanished(account_id, Duration::ZERO)
+            .await
+            .unwrap();
+    }
+    imap.send("UID FETCH 1:* (FLAGS) (CHANGEDSINCE 1 VANISHED)")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_count("VANISHED", 1)
+        .assert_contains("VANISHED (EARLIER) 2");
+
     // Delete account
     server
         .core
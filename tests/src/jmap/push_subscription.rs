@@ -8,7 +8,11 @@ use super::JMAPTest;
 use crate::{
     AssertConfig, add_test_certs,
     directory::internal::TestInternalDirectory,
-    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, test_account_login},
+    jmap::{
+        assert_is_empty,
+        mailbox::{destroy_all_mailboxes, destroy_all_mailboxes_for_account},
+        test_account_login,
+    },
 };
 use base64::{Engine, engine::general_purpose};
 use common::{Caches, Core, Data, Inner, config::server::Listeners, listener::SessionData};
@@ -26,7 +30,7 @@ use std::{
     },
     time::Duration,
 };
-use store::ahash::AHashSet;
+use store::ahash::{AHashMap, AHashSet};
 use tokio::sync::mpsc;
 use types::{id::Id, type_state::DataType};
 use utils::config::Config;
@@ -220,6 +224,60 @@ pub async fn test(params: &mut JMAPTest) {
     client.mailbox_destroy(&mailbox_id, true).await.unwrap();
     expect_nothing(&mut event_rx).await;
 
+    // Push bridge urls are only accepted for configured bridges
+    let jane_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jane.smith@example.com",
+                "abcde",
+                "Jane Smith",
+                &["jane.smith@example.com"],
+            )
+            .await,
+    );
+    let jane_client = test_account_login("jane.smith@example.com", "abcde").await;
+    for url in [
+        "fcm://unknown/device-token-1",
+        "apns://android/device-token-1",
+        "fcm://android/invalid token",
+    ] {
+        assert!(
+            jane_client
+                .push_subscription_create("123", url, None)
+                .await
+                .is_err(),
+            "{url}"
+        );
+    }
+
+    // The bridge authenticates with a signed assertion before delivering the verification
+    let push_id = jane_client
+        .push_subscription_create("123", "fcm://android/device-token-1", None)
+        .await
+        .unwrap()
+        .take_id();
+    let assertion = expect_push(&mut event_rx).await.unwrap_bridge_token();
+    let claims = serde_json::from_slice::<serde_json::Value>(
+        &general_purpose::URL_SAFE_NO_PAD
+            .decode(assertion.split('.').nth(1).unwrap())
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(claims["iss"], "push@stalwart-test.iam.gserviceaccount.com");
+    assert_eq!(claims["aud"], "https://127.0.0.1:9000/fcm/token");
+    assert_eq!(
+        claims["scope"],
+        "https://www.googleapis.com/auth/firebase.messaging"
+    );
+    jane_client
+        .push_subscription_destroy(&push_id)
+        .await
+        .unwrap();
+    destroy_all_mailboxes_for_account(jane_id.document_id()).await;
+
     destroy_all_mailboxes(params).await;
     assert_is_empty(server).await;
 }
@@ -246,6 +304,8 @@ pub struct PushServer {
 enum PushMessage {
     StateChange(StateChangeResponse),
     Verification(PushVerification),
+    #[serde(skip)]
+    BridgeToken(String),
 }
 
 impl PushMessage {
@@ -262,6 +322,13 @@ impl PushMessage {
             _ => panic!("Expected Verification"),
         }
     }
+
+    pub fn unwrap_bridge_token(self) -> String {
+        match self {
+            PushMessage::BridgeToken(assertion) => assertion,
+            _ => panic!("Expected BridgeToken"),
+        }
+    }
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -303,6 +370,29 @@ impl common::listener::SessionManager for SessionManager {
                                 .into_http_response()
                                 .build());
                             }
+                            if req.uri().path() == "/fcm/token" {
+                                let body = fetch_body(&mut req, 1024 * 1024, 0).await.unwrap();
+                                let form = form_urlencoded::parse(&body)
+                                    .into_owned()
+                                    .collect::<AHashMap<String, String>>();
+                                assert_eq!(
+                                    form.get("grant_type").map(|v| v.as_str()),
+                                    Some("urn:ietf:params:oauth:grant-type:jwt-bearer")
+                                );
+                                push.tx
+                                    .send(PushMessage::BridgeToken(
+                                        form.get("assertion").unwrap().clone(),
+                                    ))
+                                    .await
+                                    .unwrap();
+
+                                return Ok(HtmlResponse::new(
+                                    r#"{"access_token":"test-token","expires_in":3600}"#
+                                        .to_string(),
+                                )
+                                .into_http_response()
+                                .build());
+                            }
                             let is_encrypted = req
                                 .headers()
                                 .get(CONTENT_ENCODING)
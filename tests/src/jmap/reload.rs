@@ -0,0 +1,85 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::manager::reload::{ConfigFingerprint, ReloadScope};
+use serde_json::Value;
+use utils::{config::Config, map::bitmap::Bitmap};
+
+use crate::jmap::{JMAPTest, ManagementApi};
+
+const CONFIG: &str = r#"
+server.hostname = "mx.example.org"
+server.listener.smtp.bind = "[::]:25"
+server.blocked-ip.10.0.0.1 = ""
+certificate.default.cert = "cert"
+queue.schedule.default.retry = "[2m, 5m]"
+spam-filter.enable = true
+tracer.stdout.level = "info"
+acme.letsencrypt.contact = "admin@example.org"
+"#;
+
+pub async fn test(_params: &JMAPTest) {
+    println!("Running settings reload tests...");
+
+    // Settings are mapped to the subsystems that use them
+    for (key, scope) in [
+        ("server.hostname", ReloadScope::Core),
+        ("server.listener.smtp.bind", ReloadScope::Listeners),
+        ("server.blocked-ip.10.0.0.1", ReloadScope::BlockedIps),
+        ("server.allowed-ip.10.0.0.2", ReloadScope::BlockedIps),
+        ("certificate.default.cert", ReloadScope::Certificates),
+        ("queue.schedule.default.retry", ReloadScope::Queue),
+        ("spam-filter.enable", ReloadScope::SpamFilter),
+        ("tracer.stdout.level", ReloadScope::Telemetry),
+        ("metrics.prometheus.enable", ReloadScope::Telemetry),
+        ("acme.letsencrypt.contact", ReloadScope::Acme),
+        ("session.rcpt.relay", ReloadScope::Core),
+    ] {
+        assert_eq!(ReloadScope::from_key(key), scope, "{key}");
+        assert_eq!(ReloadScope::parse(scope.as_str()), Some(scope), "{key}");
+    }
+
+    // Only the subsystems with modified settings are reloaded
+    let fingerprint = ConfigFingerprint::new(&Config::new(CONFIG).unwrap());
+    assert!(
+        fingerprint
+            .changed(&ConfigFingerprint::new(&Config::new(CONFIG).unwrap()))
+            .is_empty()
+    );
+    let modified = ConfigFingerprint::new(
+        &Config::new(
+            CONFIG
+                .replace("[2m, 5m]", "[1m, 5m]")
+                .replace("10.0.0.1", "10.0.0.3"),
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        fingerprint.changed(&modified).collect::<Vec<_>>(),
+        vec![ReloadScope::Queue, ReloadScope::BlockedIps]
+    );
+
+    // Reloading a single subsystem keeps the pending changes of the others
+    let mut current = fingerprint.clone();
+    current.update(&modified, Bitmap::from(ReloadScope::Queue));
+    assert_eq!(
+        current.changed(&modified).collect::<Vec<_>>(),
+        vec![ReloadScope::BlockedIps]
+    );
+    current.update(&modified, Bitmap::all());
+    assert_eq!(current, modified);
+
+    // Unknown scopes are rejected
+    let api = ManagementApi::new(8899, "admin", "secret");
+    assert_eq!(
+        api.get::<Value>("/api/reload?scope=queue,bogus&dry-run")
+            .await
+            .unwrap()
+            .unwrap_request_error()
+            .status,
+        400
+    );
+}
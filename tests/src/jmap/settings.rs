@@ -0,0 +1,250 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use serde_json::{Value, json};
+use types::id::Id;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{JMAPTest, assert_is_empty, jmap_json_request},
+};
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running account settings tests...");
+    let server = params.server.clone();
+    let settings_id = server
+        .store()
+        .create_test_user(
+            "settings@example.com",
+            "secret",
+            "Settings",
+            &["settings@example.com"],
+        )
+        .await;
+    let account_id = Id::from(settings_id).to_string();
+    let other_id = server
+        .store()
+        .create_test_user(
+            "nosettings@example.com",
+            "secret",
+            "No Settings",
+            &["nosettings@example.com"],
+        )
+        .await;
+
+    // The settings capability is advertised in the session
+    let session = reqwest::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap()
+        .get("https://127.0.0.1:8899/jmap/session")
+        .basic_auth("settings@example.com", Some("secret"))
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    assert!(
+        session["capabilities"]
+            .get("urn:stalwart:jmap:settings")
+            .is_some(),
+        "{session}"
+    );
+    let capability =
+        &session["accounts"][&account_id]["accountCapabilities"]["urn:stalwart:jmap:settings"];
+    assert_eq!(capability["maxSize"], 65536, "{session}");
+    assert_eq!(capability["maxNamespaces"], 32, "{session}");
+
+    // Settings always exist, even before anything was stored
+    let response = settings_request(r#"["Settings/get", {"ids": null}, "c1"]"#).await;
+    assert_eq!(
+        response[1]["list"],
+        json!([{"id": "singleton"}]),
+        "{response}"
+    );
+    let initial_state = response[1]["state"].as_str().unwrap().to_string();
+
+    // Create and destroy are not allowed on the singleton
+    let response = settings_request(concat!(
+        r#"["Settings/set", {"create": {"a": {"webmail": {}}}, "#,
+        r#""destroy": ["singleton"]}, "c1"]"#
+    ))
+    .await;
+    assert_eq!(
+        response[1]["notCreated"]["a"]["type"], "singleton",
+        "{response}"
+    );
+    assert_eq!(
+        response[1]["notDestroyed"]["singleton"]["type"], "singleton",
+        "{response}"
+    );
+
+    // Store a namespace
+    let response = settings_request(concat!(
+        r#"["Settings/set", {"update": {"singleton": {"webmail": "#,
+        r#"{"layout": "vertical", "signatures": {"work": true}}}}}, "c1"]"#
+    ))
+    .await;
+    assert!(
+        response[1]["updated"]
+            .as_object()
+            .unwrap()
+            .contains_key("singleton"),
+        "{response}"
+    );
+    assert_eq!(
+        response[1]["oldState"],
+        initial_state.as_str(),
+        "{response}"
+    );
+    let state = response[1]["newState"].as_str().unwrap().to_string();
+    assert_ne!(state, initial_state);
+
+    let response = settings_request(r#"["Settings/get", {"ids": ["singleton"]}, "c1"]"#).await;
+    assert_eq!(response[1]["state"], state.as_str(), "{response}");
+    assert_eq!(
+        response[1]["list"][0]["webmail"],
+        json!({"layout": "vertical", "signatures": {"work": true}}),
+        "{response}"
+    );
+
+    // Patch individual settings inside a namespace
+    let response = settings_request(concat!(
+        r#"["Settings/set", {"update": {"singleton": {"#,
+        r#""webmail/layout": "horizontal", "webmail/signatures": null, "#,
+        r#""notifications/sound": false}}}, "c1"]"#
+    ))
+    .await;
+    assert!(
+        response[1]["updated"]
+            .as_object()
+            .unwrap()
+            .contains_key("singleton"),
+        "{response}"
+    );
+    let response = settings_request(concat!(
+        r#"["Settings/get", {"ids": ["singleton"], "#,
+        r#""properties": ["webmail", "notifications", "unknown"]}, "c1"]"#
+    ))
+    .await;
+    assert_eq!(
+        response[1]["list"][0],
+        json!({
+            "id": "singleton",
+            "webmail": {"layout": "horizontal"},
+            "notifications": {"sound": false},
+            "unknown": null
+        }),
+        "{response}"
+    );
+
+    // Unknown ids are not found
+    let response = settings_request(r#"["Settings/get", {"ids": ["b"]}, "c1"]"#).await;
+    assert_eq!(response[1]["list"], json!([]), "{response}");
+    assert_eq!(response[1]["notFound"], json!(["b"]), "{response}");
+
+    // Namespaces must be valid names holding JSON objects
+    for update in [
+        r#"{"webmail": "vertical"}"#,
+        r#"{"web mail": {"layout": "vertical"}}"#,
+        r#"{"/layout": "vertical"}"#,
+    ] {
+        let response = settings_request(format!(
+            r#"["Settings/set", {{"update": {{"singleton": {update}}}}}, "c1"]"#
+        ))
+        .await;
+        assert_eq!(
+            response[1]["notUpdated"]["singleton"]["type"], "invalidProperties",
+            "{response}"
+        );
+    }
+
+    // Stale states are rejected
+    let response = settings_request(format!(
+        concat!(
+            r#"["Settings/set", {{"ifInState": "{}", "#,
+            r#""update": {{"singleton": {{"webmail": null}}}}}}, "c1"]"#
+        ),
+        initial_state
+    ))
+    .await;
+    assert_eq!(response[0], "error", "{response}");
+    assert_eq!(response[1]["type"], "stateMismatch", "{response}");
+
+    // Enforce the namespace quota
+    let original_core = params.server.inner.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.settings_max_namespaces = 2;
+    params.server.inner.shared_core.store(core.into());
+    let response = settings_request(concat!(
+        r#"["Settings/set", {"update": {"singleton": {"#,
+        r#""calendar": {"firstDay": 1}}}}, "c1"]"#
+    ))
+    .await;
+    assert_eq!(
+        response[1]["notUpdated"]["singleton"]["type"], "overQuota",
+        "{response}"
+    );
+    params.server.inner.shared_core.store(original_core);
+
+    // Settings are private to the account
+    let response = jmap_json_request(
+        format!(r#"[["Settings/get", {{"accountId": "{account_id}"}}, "c1"]]"#),
+        "nosettings@example.com",
+        "secret",
+    )
+    .await;
+    assert_eq!(response["methodResponses"][0][0], "error", "{response}");
+    let response = jmap_json_request(
+        r#"[["Settings/get", {}, "c1"]]"#,
+        "nosettings@example.com",
+        "secret",
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["list"],
+        json!([{"id": "singleton"}]),
+        "{response}"
+    );
+
+    // Removing a namespace
+    settings_request(concat!(
+        r#"["Settings/set", {"update": {"singleton": {"#,
+        r#""webmail": null, "notifications/sound": null}}}, "c1"]"#
+    ))
+    .await;
+    let response = settings_request(r#"["Settings/get", {}, "c1"]"#).await;
+    assert_eq!(
+        response[1]["list"],
+        json!([{"id": "singleton"}]),
+        "{response}"
+    );
+
+    // Cleanup
+    for account_id in [settings_id, other_id] {
+        server
+            .store()
+            .delete_principal(QueryBy::Id(account_id))
+            .await
+            .unwrap();
+    }
+    assert_is_empty(server).await;
+}
+
+async fn settings_request(call: impl AsRef<str>) -> Value {
+    let mut response = jmap_json_request(
+        format!("[{}]", call.as_ref()),
+        "settings@example.com",
+        "secret",
+    )
+    .await;
+    response["methodResponses"][0].take()
+}
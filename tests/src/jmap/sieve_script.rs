@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use ::email::sieve::{SeenIdHash, duplicate::SieveDuplicateTracking};
+use common::Server;
 use jmap_client::{
     Error,
     core::set::{SetError, SetErrorType},
@@ -11,16 +13,20 @@ use jmap_client::{
     sieve::query::{Comparator, Filter},
 };
 use types::id::Id;
+use serde_json::Value;
 use std::{
     fs,
     path::PathBuf,
+    str::FromStr,
+    sync::Arc,
     time::{Duration, Instant},
 };
+use store::write::now;
 
 use crate::{
     directory::internal::TestInternalDirectory,
     jmap::{
-        assert_is_empty,
+        ManagementApi, assert_is_empty,
         delivery::SmtpConnection,
         email_submission::{MockMessage, assert_message_delivery, spawn_mock_smtp_server},
         mailbox::destroy_all_mailboxes,
@@ -316,6 +322,88 @@ pub async fn test(params: &mut JMAPTest) {
     )
     .await;
 
+    // List tracked duplicate ids
+    let api = ManagementApi::new(8899, "admin", "secret");
+    let list = api
+        .get::<Value>("/api/store/sieve-duplicate/jdoe@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let items = list["items"].as_array().unwrap();
+    assert!(!items.is_empty(), "{list}");
+    assert_eq!(list["total"], items.len());
+    assert_eq!(list["maxIds"], Value::Null);
+    for item in items {
+        assert_eq!(item["id"].as_str().unwrap().len(), 64, "{item}");
+        assert!(item["expiresAt"].as_u64().unwrap() <= now() + 5, "{item}");
+    }
+
+    // Tracking windows and the number of tracked ids are capped
+    let document_id = Id::from_str(&account_id).unwrap().document_id();
+    let mut core = server.core.as_ref().clone();
+    core.jmap.sieve_duplicate_max_expiry = Some(60);
+    core.jmap.sieve_duplicate_max_ids = Some(2);
+    let limited_server = Server {
+        inner: server.inner.clone(),
+        core: Arc::new(core),
+    };
+    limited_server
+        .sieve_duplicate_purge(document_id)
+        .await
+        .unwrap();
+    let id_hashes = ["id-1", "id-2", "id-3"]
+        .into_iter()
+        .map(|id| SeenIdHash::new(document_id, 0, id))
+        .collect::<Vec<_>>();
+    for (id_hash, expiry) in id_hashes.iter().zip([10, 20, 3600]) {
+        limited_server
+            .sieve_duplicate_track(id_hash, expiry)
+            .await
+            .unwrap();
+    }
+    limited_server
+        .sieve_duplicate_enforce_budget(document_id)
+        .await
+        .unwrap();
+    let list = limited_server
+        .sieve_duplicate_list(document_id)
+        .await
+        .unwrap();
+    assert_eq!(list.total, 2);
+    assert_eq!(list.max_ids, Some(2));
+    assert!(list.items[0].expires_at <= now() + 60);
+    assert!(
+        !limited_server
+            .sieve_duplicate_exists(&id_hashes[0])
+            .await
+            .unwrap()
+    );
+    for id_hash in &id_hashes[1..] {
+        assert!(
+            limited_server
+                .sieve_duplicate_exists(id_hash)
+                .await
+                .unwrap()
+        );
+    }
+
+    // Purge tracked duplicate ids
+    api.delete::<Value>("/api/store/sieve-duplicate/jdoe@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(
+        api.get::<Value>("/api/store/sieve-duplicate/jdoe@example.com")
+            .await
+            .unwrap()
+            .unwrap_data()["total"],
+        0
+    );
+    api.get::<Value>("/api/store/sieve-duplicate/unknown@example.com")
+        .await
+        .unwrap()
+        .expect_error("notFound");
+
     // Run include tests
     client
         .sieve_script_create("test_include_this", get_script("test_include_this"), false)
@@ -0,0 +1,189 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{
+    KV_BAYES_MODEL_GLOBAL, KV_BAYES_MODEL_USER, KV_REPUTATION_ASN, KV_REPUTATION_DOMAIN,
+    KV_REPUTATION_FROM, KV_REPUTATION_IP,
+};
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use serde_json::{Value, json};
+
+use crate::{directory::internal::TestInternalDirectory, jmap::ManagementApi};
+
+use super::JMAPTest;
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running spam model import/export tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+    clear_model(params).await;
+
+    // Import a global model
+    let model = json!({
+        "bayes": [
+            {"token": "aGVsbG8=", "spam": 3, "ham": 1},
+            {"token": "", "spam": 10, "ham": 12}
+        ],
+        "reputation": [
+            {"type": "ip", "key": "MTkyLjAuMi4x", "count": 4, "score": -2.5}
+        ]
+    });
+    assert_eq!(
+        api.post::<Value>("/api/spam-filter/model/import", &model)
+            .await
+            .unwrap()
+            .unwrap_data(),
+        json!({"bayes": 2, "reputation": 1})
+    );
+
+    // Export the global model
+    let exported = api
+        .get::<Value>("/api/spam-filter/model/export")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(bayes_token(&exported, "aGVsbG8="), (3, 1));
+    assert_eq!(bayes_token(&exported, ""), (10, 12));
+    assert_eq!(
+        exported["reputation"],
+        json!([{"type": "ip", "key": "MTkyLjAuMi4x", "count": 4, "score": -2.5}])
+    );
+
+    // Imported weights are added to the existing ones
+    api.post::<Value>(
+        "/api/spam-filter/model/import",
+        &json!({"bayes": model["bayes"]}),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    let exported = api
+        .get::<Value>("/api/spam-filter/model/export")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(bayes_token(&exported, "aGVsbG8="), (6, 2));
+    assert_eq!(bayes_token(&exported, ""), (20, 24));
+
+    // Global model statistics
+    let stats = api
+        .get::<Value>("/api/spam-filter/model/stats")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(stats["tokens"], json!(1));
+    assert_eq!(stats["spamLearns"], json!(20));
+    assert_eq!(stats["hamLearns"], json!(24));
+    assert_eq!(stats["spamRatio"].as_f64().unwrap(), 20.0 / 44.0);
+    assert_eq!(
+        stats["reputation"],
+        json!({"ip": 1, "from": 0, "domain": 0, "asn": 0})
+    );
+
+    // Per-account models do not include reputation data
+    let account_id = server
+        .store()
+        .create_test_user(
+            "spam-model@example.com",
+            "secret",
+            "Spam Model",
+            &["spam-model@example.com"],
+        )
+        .await;
+    assert_eq!(
+        api.post::<Value>(
+            "/api/spam-filter/model/import/spam-model@example.com",
+            &model
+        )
+        .await
+        .unwrap()
+        .unwrap_data(),
+        json!({"bayes": 2, "reputation": 0})
+    );
+    let exported = api
+        .get::<Value>("/api/spam-filter/model/export/spam-model@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(bayes_token(&exported, "aGVsbG8="), (3, 1));
+    assert_eq!(exported["reputation"], json!([]));
+    let stats = api
+        .get::<Value>("/api/spam-filter/model/stats/spam-model@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(stats["tokens"], json!(1));
+    assert_eq!(stats["spamLearns"], json!(10));
+    assert!(stats.get("reputation").is_none());
+
+    // The global model is not affected by per-account imports
+    let exported = api
+        .get::<Value>("/api/spam-filter/model/export")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(bayes_token(&exported, "aGVsbG8="), (6, 2));
+
+    // Unknown accounts and invalid keys are rejected
+    api.get::<Value>("/api/spam-filter/model/export/unknown@example.com")
+        .await
+        .unwrap()
+        .expect_error("notFound");
+    api.post::<Value>(
+        "/api/spam-filter/model/import",
+        &json!({"bayes": [{"token": "not base64!", "spam": 1, "ham": 0}]}),
+    )
+    .await
+    .unwrap()
+    .expect_request_error("Invalid base64 encoded key");
+
+    // Cleanup
+    server
+        .store()
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    let mut prefix = vec![KV_BAYES_MODEL_USER];
+    prefix.extend_from_slice(&account_id.to_be_bytes());
+    server
+        .in_memory_store()
+        .key_delete_prefix(&prefix)
+        .await
+        .unwrap();
+    clear_model(params).await;
+}
+
+async fn clear_model(params: &JMAPTest) {
+    for prefix in [
+        KV_BAYES_MODEL_GLOBAL,
+        KV_REPUTATION_IP,
+        KV_REPUTATION_FROM,
+        KV_REPUTATION_DOMAIN,
+        KV_REPUTATION_ASN,
+    ] {
+        params
+            .server
+            .in_memory_store()
+            .key_delete_prefix(&[prefix])
+            .await
+            .unwrap();
+    }
+}
+
+fn bayes_token(model: &Value, token: &str) -> (u64, u64) {
+    model["bayes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["token"] == token)
+        .map(|item| {
+            (
+                item["spam"].as_u64().unwrap(),
+                item["ham"].as_u64().unwrap(),
+            )
+        })
+        .unwrap_or_else(|| panic!("Token {token:?} not found in {model}"))
+}
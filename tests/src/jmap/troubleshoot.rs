@@ -0,0 +1,122 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use serde_json::{Value, json};
+
+use crate::jmap::ManagementApi;
+
+pub async fn test() {
+    println!("Running expression troubleshooting tests...");
+    let api = ManagementApi::new(8899, "admin", "secret");
+
+    // Single expressions are evaluated as the default value
+    let response = api
+        .post::<Value>(
+            "/api/troubleshoot/expression",
+            &json!({
+                "expression": "'hello' + ' world'",
+                "variables": {}
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(response["result"], json!("hello world"));
+    assert_eq!(response["matchedCondition"], Value::Null);
+    assert_eq!(response["trace"], json!([]));
+    let response = api
+        .post::<Value>(
+            "/api/troubleshoot/expression",
+            &json!({
+                "expression": "priority + 2",
+                "variables": {"priority": 1}
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(response["result"], json!(3));
+
+    // Conditions are evaluated in order until one matches
+    let expression = json!({
+        "match": [
+            {"if": "rcpt_domain == 'example.org'", "then": "'local'"},
+            {"if": "starts_with(sender, 'admin')", "then": "'admin'"},
+            {"if": "is_tls", "then": "'tls'"}
+        ],
+        "else": "'remote'"
+    });
+    let response = api
+        .post::<Value>(
+            "/api/troubleshoot/expression",
+            &json!({
+                "expression": expression,
+                "variables": {
+                    "rcpt_domain": "example.com",
+                    "sender": "admin@example.com",
+                    "is_tls": true
+                }
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(response["result"], json!("admin"));
+    assert_eq!(response["matchedCondition"], json!(1));
+    assert_eq!(
+        response["trace"],
+        json!([
+            {"condition": "rcpt_domain == 'example.org'", "value": 0, "matched": false},
+            {"condition": "starts_with(sender, 'admin')", "value": 1, "matched": true}
+        ])
+    );
+
+    // The default value is returned when no condition matches
+    let response = api
+        .post::<Value>(
+            "/api/troubleshoot/expression",
+            &json!({
+                "expression": expression,
+                "variables": {"rcpt_domain": "example.net"}
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(response["result"], json!("remote"));
+    assert_eq!(response["matchedCondition"], Value::Null);
+    assert_eq!(
+        response["trace"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|step| step["matched"].as_bool().unwrap())
+            .collect::<Vec<_>>(),
+        vec![false, false, false]
+    );
+
+    // Unknown variables and invalid expressions are rejected
+    api.post::<Value>(
+        "/api/troubleshoot/expression",
+        &json!({
+            "expression": "rcpt == 'a'",
+            "variables": {"unknown_var": "a"}
+        }),
+    )
+    .await
+    .unwrap()
+    .expect_error("Unknown variable");
+    api.post::<Value>(
+        "/api/troubleshoot/expression",
+        &json!({
+            "expression": "rcpt == ",
+            "variables": {}
+        }),
+    )
+    .await
+    .unwrap()
+    .expect_error("Invalid expression");
+}
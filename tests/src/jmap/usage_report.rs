@@ -0,0 +1,204 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::{KV_USAGE_REPORT, Server, config::telemetry::Metrics};
+use directory::{QueryBy, backend::internal::manage::ManageDirectory};
+use serde_json::Value;
+use services::housekeeper::usage::{UsageReporting, UsageSnapshot};
+use store::{
+    dispatch::lookup::KeyValue,
+    write::{BatchBuilder, DirectoryClass, now},
+};
+use utils::config::{Config, cron::SimpleCron};
+
+use crate::{
+    AssertConfig,
+    directory::internal::TestInternalDirectory,
+    jmap::{JMAPTest, ManagementApi},
+};
+
+const CONFIG: &str = r#"
+[metrics.usage-report]
+frequency = "30 4 *"
+top-accounts = 1
+send-to = ["Admin@usage.example.org", "invalid"]
+"#;
+
+const LARGE_USAGE: i64 = 1 << 40;
+
+pub async fn test(params: &JMAPTest) {
+    println!("Running usage report tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+    clear_reports(&server).await;
+
+    // Parse usage report settings
+    let mut config = Config::new(CONFIG).unwrap();
+    let usage_config = Metrics::parse(&mut config).usage_report.unwrap();
+    config.assert_no_errors();
+    assert_eq!(
+        usage_config.frequency,
+        SimpleCron::Day {
+            hour: 4,
+            minute: 30
+        }
+    );
+    assert_eq!(usage_config.top_accounts, 1);
+    assert_eq!(
+        usage_config.rcpt_to,
+        vec!["admin@usage.example.org".to_string()]
+    );
+    assert!(
+        Metrics::parse(&mut Config::new("[metrics.usage-report]\nenable = false\n").unwrap())
+            .usage_report
+            .is_none()
+    );
+
+    // Reports are generated on demand by the housekeeper
+    assert_eq!(
+        api.get::<Value>("/api/reports/usage")
+            .await
+            .unwrap()
+            .unwrap_data(),
+        Value::Null
+    );
+    api.post::<Value>("/api/reports/usage", &())
+        .await
+        .unwrap()
+        .unwrap_data();
+    let mut report = Value::Null;
+    for _ in 0..50 {
+        report = api
+            .get::<Value>("/api/reports/usage")
+            .await
+            .unwrap()
+            .unwrap_data();
+        if !report.is_null() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(report["generatedAt"].as_u64().is_some(), "{report}");
+
+    // Create test accounts
+    let small_id = server
+        .store()
+        .create_test_user(
+            "usage-small@usage.example.org",
+            "secret",
+            "Small Usage",
+            &["usage-small@usage.example.org"],
+        )
+        .await;
+    let large_id = server
+        .store()
+        .create_test_user(
+            "usage-large@usage.example.org",
+            "secret",
+            "Large Usage",
+            &["usage-large@usage.example.org"],
+        )
+        .await;
+    set_used_quota(&server, large_id, LARGE_USAGE).await;
+
+    // Add a previous snapshot to calculate growth
+    clear_reports(&server).await;
+    let since = now() - 2 * 86400;
+    let mut snapshot_key = vec![KV_USAGE_REPORT, 1];
+    snapshot_key.extend_from_slice(&(since / 86400).to_be_bytes());
+    server
+        .in_memory_store()
+        .key_set(KeyValue::new(
+            snapshot_key,
+            serde_json::to_vec(&UsageSnapshot {
+                timestamp: since,
+                ..Default::default()
+            })
+            .unwrap(),
+        ))
+        .await
+        .unwrap();
+
+    // Build report
+    let report = server.build_usage_report(&usage_config).await.unwrap();
+    assert!(report.totals.accounts >= 2);
+    assert!(report.totals.storage >= LARGE_USAGE);
+    assert_eq!(report.growth.since, Some(since));
+    assert_eq!(report.growth.accounts, report.totals.accounts as i64);
+    assert_eq!(report.growth.storage, report.totals.storage);
+    assert_eq!(report.history.len(), 2);
+    assert_eq!(report.history[0].timestamp, since);
+    assert_eq!(report.history[1].timestamp, report.generated_at);
+    let domain = report
+        .domains
+        .iter()
+        .find(|domain| domain.name == "usage.example.org")
+        .unwrap();
+    assert_eq!(
+        (
+            domain.accounts,
+            domain.storage,
+            domain.accounts_growth,
+            domain.storage_growth
+        ),
+        (2, LARGE_USAGE, 2, LARGE_USAGE)
+    );
+    assert_eq!(report.top_accounts.len(), 1);
+    assert_eq!(report.top_accounts[0].name, "usage-large@usage.example.org");
+    assert_eq!(report.top_accounts[0].storage, LARGE_USAGE);
+    let text = report.to_text();
+    assert!(
+        text.contains(&format!(
+            "usage.example.org: 2 accounts, {LARGE_USAGE} bytes (+{LARGE_USAGE} bytes)"
+        )),
+        "{text}"
+    );
+    assert!(
+        text.contains(&format!(
+            "usage-large@usage.example.org: {LARGE_USAGE} bytes"
+        )),
+        "{text}"
+    );
+
+    // The latest report is cached
+    let cached = api
+        .get::<Value>("/api/reports/usage")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(cached["generatedAt"], report.generated_at);
+    assert_eq!(
+        cached["topAccounts"][0]["name"],
+        "usage-large@usage.example.org"
+    );
+
+    // Cleanup
+    set_used_quota(&server, large_id, -LARGE_USAGE).await;
+    for account_id in [small_id, large_id] {
+        server
+            .store()
+            .delete_principal(QueryBy::Id(account_id))
+            .await
+            .unwrap();
+    }
+    clear_reports(&server).await;
+}
+
+async fn set_used_quota(server: &Server, account_id: u32, value: i64) {
+    let mut batch = BatchBuilder::new();
+    batch.add(DirectoryClass::UsedQuota(account_id), value);
+    server.store().write(batch.build_all()).await.unwrap();
+}
+
+async fn clear_reports(server: &Server) {
+    server
+        .in_memory_store()
+        .key_delete_prefix(&[KV_USAGE_REPORT])
+        .await
+        .unwrap();
+}
@@ -7,21 +7,25 @@
 use std::{fs, net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
 
 use common::{
-    Server,
+    Data, Inner, Server,
     config::{
         server::{Listener, Listeners, ServerProtocol, TcpListener},
         smtp::*,
     },
     expr::{functions::ResolveVariable, if_block::*, tokenizer::TokenMap, *},
+    listener::TcpAcceptor,
 };
 
 use compact_str::ToCompactString;
+use rustls::{CipherSuite, ProtocolVersion};
+use rustls_pki_types::ServerName;
 use throttle::parse_queue_rate_limiter;
 use tokio::net::TcpSocket;
+use tokio_rustls::TlsConnector;
 
 use utils::config::{Config, Rate};
 
-use super::add_test_certs;
+use super::{TempDir, add_test_certs};
 
 struct TestEnvelope {
     pub local_ip: IpAddr,
@@ -397,6 +401,139 @@ fn parse_servers() {
     }
 }
 
+const TLS_CONFIG: &str = r#"
+[server.listener."tls13"]
+bind = ["127.0.0.1:9926"]
+protocol = "smtp"
+tls.implicit = true
+tls.min-version = "TLSv1.3"
+tls.ciphers = ["TLS13_CHACHA20_POLY1305_SHA256", "TLS13_AES_256_GCM_SHA384"]
+tls.alpn = ["smtp", "imap"]
+tls.key-log = "{TMP}/keys.log"
+
+[server.tls]
+enable = true
+
+[certificate."default"]
+cert = "%{file:{CERT}}%"
+private-key = "%{file:{PK}}%"
+default = true
+"#;
+
+#[tokio::test]
+async fn parse_tls_settings() {
+    let tmp_dir = TempDir::new("smtp_tls_settings_test", true);
+    let mut config = Config::new(tmp_dir.update_config(add_test_certs(TLS_CONFIG))).unwrap();
+    config.resolve_all_macros().await;
+    let inner = Arc::new(Inner {
+        data: Data::parse(&mut config),
+        ..Default::default()
+    });
+    let mut servers = Listeners::parse(&mut config);
+    servers.parse_tcp_acceptors(&mut config, inner);
+    assert!(config.errors.is_empty(), "{:?}", config.errors);
+    assert!(
+        config
+            .warnings
+            .contains_key("server.listener.tls13.tls.key-log")
+    );
+
+    let Some(TcpAcceptor::Tls {
+        config: server_config,
+        acceptor,
+        ..
+    }) = servers.tcp_acceptors.remove("tls13")
+    else {
+        panic!("Expected TLS acceptor");
+    };
+
+    // Cipher suites are restricted to the allow-list, in order of preference
+    assert_eq!(
+        server_config
+            .crypto_provider()
+            .cipher_suites
+            .iter()
+            .map(|suite| suite.suite())
+            .collect::<Vec<_>>(),
+        vec![
+            CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+            CipherSuite::TLS13_AES_256_GCM_SHA384
+        ]
+    );
+    assert_eq!(
+        server_config.alpn_protocols,
+        vec![b"smtp".to_vec(), b"imap".to_vec()]
+    );
+
+    // Handshake negotiates TLS 1.3, the allowed cipher and the client's ALPN protocol
+    let mut client_config = utils::rustls_client_config(true);
+    client_config.alpn_protocols = vec![b"imap".to_vec()];
+    let (client_io, server_io) = tokio::io::duplex(16384);
+    let (client, server) = tokio::join!(
+        TlsConnector::from(Arc::new(client_config)).connect(
+            ServerName::try_from("mx.example.org").unwrap().to_owned(),
+            client_io
+        ),
+        acceptor.accept(server_io)
+    );
+    let (client, _server) = (client.unwrap(), server.unwrap());
+    let (_, connection) = client.get_ref();
+    assert_eq!(
+        connection.protocol_version(),
+        Some(ProtocolVersion::TLSv1_3)
+    );
+    assert_eq!(
+        connection.negotiated_cipher_suite().unwrap().suite(),
+        CipherSuite::TLS13_CHACHA20_POLY1305_SHA256
+    );
+    assert_eq!(connection.alpn_protocol(), Some(&b"imap"[..]));
+
+    // Session secrets are written to the key log
+    let key_log = fs::read_to_string(tmp_dir.temp_dir.join("keys.log")).unwrap();
+    assert!(
+        key_log.contains("CLIENT_HANDSHAKE_TRAFFIC_SECRET "),
+        "{key_log}"
+    );
+
+    // Invalid settings are reported
+    for (settings, expected_error) in [
+        (
+            "tls.min-version = \"TLSv1.3\"\ntls.disable-protocols = [\"TLSv1.3\"]\n",
+            "All TLS protocol versions are disabled",
+        ),
+        (
+            "tls.ciphers = [\"TLS13_AES_128_GCM_SHA256\"]\ntls.disable-ciphers = [\"TLS13_AES_128_GCM_SHA256\"]\n",
+            "No TLS cipher suites are enabled",
+        ),
+    ] {
+        let mut config = Config::new(format!(
+            "[server.listener.\"invalid\"]\nbind = [\"127.0.0.1:9927\"]\nprotocol = \"smtp\"\n{settings}"
+        ))
+        .unwrap();
+        let mut servers = Listeners::parse(&mut config);
+        servers.parse_tcp_acceptors(&mut config, Default::default());
+        assert!(!servers.tcp_acceptors.contains_key("invalid"));
+        assert!(
+            format!("{:?}", config.errors.get("server.listener.invalid.tls"))
+                .contains(expected_error),
+            "{:?}",
+            config.errors
+        );
+    }
+
+    let mut config = Config::new(
+        "[server.listener.\"invalid\"]\nbind = [\"127.0.0.1:9927\"]\nprotocol = \"smtp\"\ntls.min-version = \"SSLv3\"\n",
+    )
+    .unwrap();
+    let mut servers = Listeners::parse(&mut config);
+    servers.parse_tcp_acceptors(&mut config, Default::default());
+    assert!(
+        config
+            .errors
+            .contains_key("server.listener.invalid.tls.min-version")
+    );
+}
+
 #[tokio::test]
 async fn eval_if() {
     let mut file = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
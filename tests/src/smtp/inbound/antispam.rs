@@ -39,8 +39,8 @@ use spam_filter::{
         received::SpamFilterAnalyzeReceived, recipient::SpamFilterAnalyzeRecipient,
         replyto::SpamFilterAnalyzeReplyTo, reputation::SpamFilterAnalyzeReputation,
         rules::SpamFilterAnalyzeRules, score::SpamFilterAnalyzeScore,
-        subject::SpamFilterAnalyzeSubject, trusted_reply::SpamFilterAnalyzeTrustedReply,
-        url::SpamFilterAnalyzeUrl,
+        sender_reputation::SpamFilterAnalyzeSenderReputation, subject::SpamFilterAnalyzeSubject,
+        trusted_reply::SpamFilterAnalyzeTrustedReply, url::SpamFilterAnalyzeUrl,
     },
     modules::html::{HtmlToken, html_to_tokens},
 };
@@ -82,6 +82,11 @@ explanation = 2
 [spam-filter.reputation]
 enable = true
 
+[spam-filter.sender-reputation]
+enable = true
+min-count = 1.5
+weight.delivery = 1.0
+
 [session.rcpt]
 relay = true
 
@@ -158,6 +163,9 @@ type = "chat"
 model = "gpt-dummy"
 allow-invalid-certs = true
 
+[spam-filter.arc]
+trusted-sealers = ["*.trusted-forwarder.org"]
+
 [spam-filter.list]
 "file-extensions" = { "html" = "text/html|BAD", 
                 "pdf" = "application/pdf|NZ", 
@@ -336,6 +344,7 @@ async fn antispam() {
         "spamtrap",
         "bayes_classify",
         "reputation",
+        "sender_reputation",
         "pyzor",
         "llm",
     ] {
@@ -667,6 +676,15 @@ async fn antispam() {
                     server.spam_filter_analyze_reputation(&mut spam_ctx).await;
                     assert_eq!(spam_ctx.result.score, score_final);
                 }
+                "sender_reputation" => {
+                    spam_ctx.result.score = score_set;
+                    server
+                        .spam_filter_analyze_sender_reputation(&mut spam_ctx)
+                        .await;
+                    server
+                        .spam_filter_sender_reputation_update(&mut spam_ctx)
+                        .await;
+                }
                 "pyzor" => {
                     server.spam_filter_analyze_pyzor(&mut spam_ctx).await;
                 }
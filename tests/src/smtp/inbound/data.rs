@@ -4,7 +4,9 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use common::Core;
+use std::sync::Arc;
+
+use common::{Core, auth::AccessToken};
 use store::Stores;
 use utils::config::Config;
 
@@ -60,6 +62,27 @@ description = "Mike Foobar"
 secret = "p4ssw0rd"
 email = "mike@test.com"
 
+[[directory."local".principals]]
+name = "tom"
+description = "Tom Quota"
+secret = "p4ssw0rd"
+email = "tom@quota.org"
+quota = 100
+
+[[directory."local".principals]]
+name = "ann"
+description = "Ann Quota"
+secret = "p4ssw0rd"
+email = "ann@quota.org"
+quota = 100
+
+[[directory."local".principals]]
+name = "sue"
+description = "Sue Quota"
+secret = "p4ssw0rd"
+email = "sue@quota.org"
+quota = 1000000
+
 [session.rcpt]
 directory = "'local'"
 
@@ -67,9 +90,15 @@ directory = "'local'"
 messages = [{if = "remote_ip = '10.0.0.1'", then = 1},
             {else = 100}]
 received-headers = 3
+quota = [{if = "rcpt = 'ann@quota.org'", then = "defer"},
+         {if = "rcpt_domain = 'quota.org'", then = "reject"},
+         {else = "disable"}]
+
+[email.limits]
+mime-depth = 4
 
 [session.data.add-headers]
-received = [{if = "remote_ip = '10.0.0.3'", then = true},
+received = [{if = "remote_ip = '10.0.0.3' || remote_ip = '10.0.0.5'", then = true},
             {else = false}]
 received-spf =  [{if = "remote_ip = '10.0.0.3'", then = true},
             {else = false}]
@@ -82,6 +111,15 @@ date = [{if = "remote_ip = '10.0.0.3'", then = true},
 return-path =  [{if = "remote_ip = '10.0.0.3'", then = true},
             {else = false}]
 
+[session.data.shadow]
+recipient = [{if = "remote_ip = '10.0.0.4'", then = "'shadow@lab.test'"},
+             {else = "''"}]
+rate = 100
+
+[session.data.privacy]
+enable = [{if = "remote_ip = '10.0.0.5'", then = true},
+          {else = false}]
+
 [[queue.quota]]
 match = "sender = 'john@doe.org'"
 key = ['sender']
@@ -101,6 +139,19 @@ enable = true
 
 "#;
 
+const PRIVATE_MESSAGE: &str = concat!(
+    "From: john@foobar.org\r\n",
+    "To: mike@test.com\r\n",
+    "Subject: Privacy test\r\n",
+    "user-agent: TestClient/1.0\r\n",
+    "X-Mailer: TestMailer\r\n",
+    "X-Mailer: TestMailer\r\n",
+    "X-Originating-IP: [192.0.2.1]\r\n",
+    "X-Custom: keep\r\n",
+    "\r\n",
+    "Hello"
+);
+
 #[tokio::test]
 async fn data() {
     // Enable logging
@@ -131,6 +182,16 @@ async fn data() {
         .send_message("john@doe.org", &["bill@foobar.org"], "invalid", "550 5.7.7")
         .await;
 
+    // Excessive MIME nesting
+    session
+        .send_message(
+            "john@doe.org",
+            &["bill@foobar.org"],
+            "test:mime_bomb",
+            "550 5.6.0",
+        )
+        .await;
+
     // Naive Loop detection
     session
         .send_message(
@@ -231,6 +292,98 @@ async fn data() {
         )
         .await;
 
+    // Messages from 10.0.0.4 should be shadowed to shadow@lab.test
+    qr.clear_queue(&test.server).await;
+    session.data.remote_ip_str = "10.0.0.4".into();
+    session.eval_session_params().await;
+    session
+        .send_message("jane@foobar.org", &["mike@test.com"], "test:no_dkim", "250")
+        .await;
+    let messages = qr.read_queued_messages().await;
+    assert_eq!(messages.len(), 2);
+    let shadow = messages
+        .iter()
+        .find(|m| m.message.recipients[0].address == "shadow@lab.test")
+        .expect("Shadow copy not found");
+    assert!(shadow.message.return_path.is_empty());
+    shadow
+        .read_lines(&qr)
+        .await
+        .assert_contains("X-Stalwart-Shadow: ");
+    qr.clear_queue(&test.server).await;
+    session.data.remote_ip_str = "10.0.0.2".into();
+    session.eval_session_params().await;
+
+    // Messages exceeding a local recipient's quota should not be accepted
+    session
+        .send_message(
+            "jane@foobar.org",
+            &["tom@quota.org"],
+            "test:no_dkim",
+            "552 5.2.2",
+        )
+        .await;
+    session
+        .send_message(
+            "jane@foobar.org",
+            &["mike@test.com", "ann@quota.org"],
+            "test:no_dkim",
+            "452 4.2.2",
+        )
+        .await;
+    session
+        .send_message("jane@foobar.org", &["sue@quota.org"], "test:no_dkim", "250")
+        .await;
+
+    // Client details are removed from authenticated submissions from 10.0.0.5
+    qr.clear_queue(&test.server).await;
+    session.data.remote_ip_str = "10.0.0.5".into();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session.data.authenticated_as = Some(Arc::new(AccessToken {
+        name: "john".into(),
+        emails: vec!["john@foobar.org".into()],
+        ..Default::default()
+    }));
+    session
+        .send_message(
+            "john@foobar.org",
+            &["mike@test.com"],
+            PRIVATE_MESSAGE,
+            "250",
+        )
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("Received: by localhost (Stalwart SMTP) with ESMTPA id ")
+        .assert_contains("Subject: Privacy test")
+        .assert_contains("X-Custom: keep")
+        .assert_not_contains("from mx.doe.org")
+        .assert_not_contains("10.0.0.5")
+        .assert_not_contains("User-Agent")
+        .assert_not_contains("X-Mailer")
+        .assert_not_contains("X-Originating-IP")
+        .assert_not_contains("192.0.2.1");
+
+    // Unauthenticated messages are left untouched
+    session.data.authenticated_as = None;
+    session
+        .send_message("john@doe.org", &["mike@test.com"], PRIVATE_MESSAGE, "250")
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("Received: from mx.doe.org (")
+        .assert_contains("User-Agent: TestClient/1.0")
+        .assert_contains("X-Mailer: TestMailer")
+        .assert_contains("X-Originating-IP: [192.0.2.1]");
+    session.data.remote_ip_str = "10.0.0.2".into();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+
     // Make sure store is empty
     qr.clear_queue(&test.server).await;
     test.server
@@ -68,6 +68,10 @@ sign = "['rsa']"
 send = "[1, 1s]"
 sign = "['rsa']"
 
+[report.dmarc.privacy]
+strip-subject = true
+hash-recipients = true
+
 [report.dmarc.aggregate]
 send = "daily"
 
@@ -77,8 +81,14 @@ ehlo = [{if = "remote_ip = '10.0.0.2'", then = 'strict'},
 mail-from = [{if = "remote_ip = '10.0.0.2'", then = 'strict'},
              { else = 'relaxed' }]
 
+[auth.spf]
+override = [{if = "sender_domain = 'partner.net' && remote_ip = '10.0.0.2'", then = 'accept'},
+            { else = false }]
+
 [auth.dmarc]
 verify = "strict"
+override = [{if = "sender_domain = 'partner.net'", then = 'quarantine'},
+            { else = false }]
 
 [auth.arc]
 verify = "strict"
@@ -86,6 +96,8 @@ verify = "strict"
 [auth.dkim]
 verify = [{if = "sender_domain = 'test.net'", then = 'relaxed'},
          { else = 'strict' }]
+override = [{if = "sender_domain = 'partner.net'", then = 'accept'},
+            { else = false }]
 
 "#;
 
@@ -267,7 +279,9 @@ async fn dmarc() {
         .assert_contains("To: dmarc-failures@example.com")
         .assert_contains("Feedback-Type: auth-failure")
         .assert_contains("Auth-Failure: dmarc")
-        .assert_contains("dmarc=3Dnone");
+        .assert_contains("dmarc=3Dnone")
+        .assert_not_contains("TPS Report")
+        .assert_not_contains("jdoe@example.com");
 
     // Expect DMARC aggregate report
     let report = rr.read_report().await.unwrap_dmarc();
@@ -304,4 +318,38 @@ async fn dmarc() {
         .assert_contains("spf=pass")
         .assert_contains("dmarc=pass")
         .assert_contains("Received-SPF: pass");
+
+    // Overrides should accept messages failing DKIM and DMARC
+    test.server.txt_add(
+        "partner.net",
+        Spf::parse(b"v=spf1 -all").unwrap(),
+        Instant::now() + Duration::from_secs(5),
+    );
+    session
+        .send_message(
+            "joe@partner.net",
+            &["jdoe@example.com"],
+            "test:invalid_dkim",
+            "250",
+        )
+        .await;
+    loop {
+        let message = qr.expect_message().await;
+        if message.message.recipients.last().unwrap().address() == "jdoe@example.com" {
+            message
+                .read_lines(&qr)
+                .await
+                .assert_contains("dkim=fail")
+                .assert_contains("dmarc=fail");
+            break;
+        }
+    }
+
+    // Overrides should accept messages failing strict SPF checks
+    session.data.remote_ip_str = "10.0.0.2".into();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session.mail_from("joe@partner.net", "250").await;
+    session.rset().await;
+    session.mail_from("bill@example.com", "550 5.7.23").await;
 }
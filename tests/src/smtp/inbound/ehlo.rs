@@ -101,3 +101,94 @@ async fn ehlo() {
         .assert_not_contains("FUTURERELEASE")
         .assert_not_contains("STARTTLS");
 }
+
+const CONFIG_VALIDATION: &str = r#"
+[session.ehlo]
+reject-non-fqdn = false
+
+[session.ehlo.validation]
+enable = true
+reject-score = 4.0
+"#;
+
+#[tokio::test]
+async fn ehlo_validation() {
+    // Enable logging
+    crate::enable_logging();
+
+    let mut config = Config::new(CONFIG_VALIDATION).unwrap();
+    let core = Core::parse(&mut config, Default::default(), Default::default()).await;
+    let server = TestSMTP::from_core(core).server;
+    server.ipv4_add(
+        "mx.foobar.org",
+        vec!["10.0.1.1".parse().unwrap()],
+        Instant::now() + Duration::from_secs(5),
+    );
+    for ip in ["10.0.1.1", "10.0.1.2"] {
+        server.ptr_add(
+            ip.parse().unwrap(),
+            vec!["mx.foobar.org.".to_string()],
+            Instant::now() + Duration::from_secs(5),
+        );
+    }
+
+    // Forward-confirmed hosts using their own name pass all checks
+    let mut session = Session::test(server.clone());
+    session.data.remote_ip_str = "10.0.1.1".into();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session.cmd("EHLO mx.foobar.org", "250").await;
+    let validation = session.data.helo_validation.unwrap();
+    assert_eq!(validation.score, 0.0);
+    assert!(validation.names().is_empty());
+
+    // Address literals are scored, mismatching literals are rejected
+    session.cmd("EHLO [10.0.1.1]", "250").await;
+    let validation = session.data.helo_validation.unwrap();
+    assert_eq!(validation.names(), vec!["bare-ip"]);
+    assert_eq!(validation.score, 2.0);
+    session.cmd("EHLO [10.9.9.9]", "550 5.7.1").await;
+    assert_eq!(session.data.helo_domain, "[10.0.1.1]");
+    session.cmd("EHLO localhost", "250").await;
+    assert_eq!(
+        session.data.helo_validation.unwrap().names(),
+        vec!["not-fqdn"]
+    );
+
+    // Hosts without forward-confirmed reverse DNS accumulate scores
+    let mut session = Session::test(server.clone());
+    session.data.remote_ip_str = "10.0.1.2".into();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session.cmd("EHLO mx.foobar.org", "250").await;
+    let validation = session.data.helo_validation.unwrap();
+    assert_eq!(validation.names(), vec!["address-mismatch", "no-fcrdns"]);
+    assert_eq!(validation.score, 2.5);
+    session.cmd("EHLO localhost", "550 5.7.1").await;
+
+    // Results are cached per remote IP and domain
+    assert_eq!(
+        server
+            .inner
+            .cache
+            .helo_validation
+            .get("10.0.1.2/mx.foobar.org")
+            .map(|result| result.score),
+        Some(2.5)
+    );
+
+    // Validation is disabled by default
+    let core = Core::parse(
+        &mut Config::new("").unwrap(),
+        Default::default(),
+        Default::default(),
+    )
+    .await;
+    let mut session = Session::test(TestSMTP::from_core(core).server);
+    session.data.remote_ip_str = "10.0.1.2".into();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.stream.tls = true;
+    session.eval_session_params().await;
+    session.cmd("EHLO mx.foobar.org", "250").await;
+    assert!(session.data.helo_validation.is_none());
+}
@@ -0,0 +1,309 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::{Core, icap::upload::UploadSource};
+use store::Stores;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::watch,
+};
+use utils::config::Config;
+
+use smtp::core::Session;
+use trc::{EventType, IcapEvent};
+
+use crate::smtp::{
+    TempDir, TestSMTP,
+    inbound::TestMessage,
+    session::{TestSession, VerifyResponse},
+};
+
+const CONFIG: &str = r#"
+[storage]
+data = "rocksdb"
+lookup = "rocksdb"
+blob = "rocksdb"
+fts = "rocksdb"
+
+[store."rocksdb"]
+type = "rocksdb"
+path = "{TMP}/queue.db"
+
+[session.rcpt]
+relay = true
+
+[[session.icap]]
+url = "icap://127.0.0.1:9334/avscan"
+enable = "sender_domain == 'doe.org'"
+options.preview = 16
+action.threat = "reject"
+action.modified = "quarantine"
+
+[[session.icap]]
+url = "icap://127.0.0.1:9335/avscan"
+enable = "sender_domain == 'closed.org'"
+failure-policy = "fail-closed"
+timeout.connect = "1s"
+
+[[session.icap]]
+url = "icap://127.0.0.1:9335/avscan"
+enable = "sender_domain == 'open.org'"
+failure-policy = "fail-open"
+timeout.connect = "1s"
+
+[upload.scan.jmap]
+services = ["0000"]
+max-size = 1024
+
+[upload.scan.dav]
+services = ["0001"]
+"#;
+
+const MESSAGE: &str = "From: john@doe.org\r
+To: bill@foobar.org\r
+Subject: ICAP test\r
+\r
+";
+
+#[tokio::test]
+async fn icap_session() {
+    // Enable logging
+    crate::enable_logging();
+
+    // Configure tests
+    let tmp_dir = TempDir::new("smtp_icap_test", true);
+    let mut config = Config::new(tmp_dir.update_config(CONFIG)).unwrap();
+    let stores = Stores::parse_all(&mut config, false).await;
+    let core = Core::parse(&mut config, stores, Default::default()).await;
+    let _rx = spawn_mock_icap_server();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Build session
+    let test = TestSMTP::from_core(core);
+    let server = test.server.clone();
+    let mut qr = test.queue_receiver;
+    let mut session = Session::test(test.server);
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.ehlo("mx.doe.org").await;
+
+    // Clean messages are accepted
+    session
+        .send_message(
+            "john@doe.org",
+            &["bill@foobar.org"],
+            &format!("{MESSAGE}Nothing to see here, just a clean message.\r\n"),
+            "250 2.0.0",
+        )
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_not_contains("X-Quarantine")
+        .assert_contains("just a clean message");
+
+    // Infected messages are rejected
+    session
+        .send_message(
+            "john@doe.org",
+            &["bill@foobar.org"],
+            &format!("{MESSAGE}This message contains the EICAR test signature.\r\n"),
+            "550 5.7.1",
+        )
+        .await;
+    qr.assert_no_events();
+
+    // Modified messages are quarantined
+    session
+        .send_message(
+            "john@doe.org",
+            &["bill@foobar.org"],
+            &format!("{MESSAGE}This message is CONFIDENTIAL.\r\n"),
+            "250 2.0.0",
+        )
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("X-Quarantine: true");
+
+    // Unreachable services fail closed
+    session
+        .send_message(
+            "john@closed.org",
+            &["bill@foobar.org"],
+            &format!("{MESSAGE}Hello.\r\n"),
+            "451 4.3.5",
+        )
+        .await;
+    qr.assert_no_events();
+
+    // Unreachable services fail open
+    session
+        .send_message(
+            "john@open.org",
+            &["bill@foobar.org"],
+            &format!("{MESSAGE}Hello.\r\n"),
+            "250 2.0.0",
+        )
+        .await;
+    qr.expect_message().await;
+
+    // Uploads are scanned by the services configured for their source
+    let remote_ip = "10.0.0.1".parse().unwrap();
+    assert!(
+        server
+            .scan_upload(
+                UploadSource::Jmap,
+                1,
+                b"Nothing to see here, just a clean attachment.",
+                remote_ip,
+                0
+            )
+            .await
+            .is_ok()
+    );
+    assert!(
+        server
+            .scan_upload(
+                UploadSource::Jmap,
+                1,
+                b"This attachment contains the EICAR test signature.",
+                remote_ip,
+                0
+            )
+            .await
+            .unwrap_err()
+            .matches(EventType::Icap(IcapEvent::UploadRejected))
+    );
+
+    // Rejected uploads are kept in quarantine until removed or expired
+    let uploads = server.quarantined_uploads().await.unwrap();
+    assert_eq!(uploads.len(), 1, "{uploads:?}");
+    let upload = &uploads[0];
+    assert_eq!(upload.source, UploadSource::Jmap);
+    assert_eq!(upload.account_id, 1);
+    assert_eq!(upload.service, "0000");
+    assert_eq!(
+        server.quarantined_upload(upload.id).await.unwrap().as_ref(),
+        Some(upload)
+    );
+    assert_eq!(
+        server
+            .quarantined_upload_contents(upload)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some(b"This attachment contains the EICAR test signature.".as_slice())
+    );
+    server.delete_quarantined_upload(upload).await.unwrap();
+    assert!(server.quarantined_uploads().await.unwrap().is_empty());
+
+    // Uploads too large to scan are rejected unless configured otherwise
+    assert!(
+        server
+            .scan_upload(UploadSource::Jmap, 1, &[b'a'; 2048], remote_ip, 0)
+            .await
+            .unwrap_err()
+            .matches(EventType::Icap(IcapEvent::UploadRejected))
+    );
+    assert!(server.quarantined_uploads().await.unwrap().is_empty());
+
+    assert!(
+        server
+            .scan_upload(UploadSource::Dav, 1, b"Hello.", remote_ip, 0)
+            .await
+            .unwrap_err()
+            .matches(EventType::Icap(IcapEvent::Error))
+    );
+}
+
+pub fn spawn_mock_icap_server() -> watch::Sender<bool> {
+    let (tx, rx) = watch::channel(true);
+
+    tokio::spawn(async move {
+        let listener = TcpListener::bind("127.0.0.1:9334")
+            .await
+            .unwrap_or_else(|e| {
+                panic!("Failed to bind mock ICAP server to 127.0.0.1:9334: {e}");
+            });
+        let mut rx_ = rx.clone();
+        loop {
+            tokio::select! {
+                stream = listener.accept() => {
+                    match stream {
+                        Ok((stream, _)) => {
+                            tokio::spawn(handle_icap(stream));
+                        }
+                        Err(err) => {
+                            panic!("Something went wrong: {err}" );
+                        }
+                    }
+                },
+                _ = rx_.changed() => {
+                    break;
+                }
+            };
+        }
+    });
+
+    tx
+}
+
+async fn handle_icap(mut stream: TcpStream) {
+    let mut request = Vec::new();
+    let mut buf = vec![0u8; 8192];
+    let mut continued = false;
+
+    loop {
+        let bytes_read = stream.read(&mut buf).await.unwrap();
+        if bytes_read == 0 {
+            return;
+        }
+        request.extend_from_slice(&buf[..bytes_read]);
+
+        if request.ends_with(b"0; ieof\r\n\r\n") {
+            break;
+        } else if request.ends_with(b"\r\n0\r\n\r\n") {
+            if !continued && request.windows(9).any(|w| w == b"Preview: ") {
+                continued = true;
+                stream
+                    .write_all(b"ICAP/1.0 100 Continue\r\n\r\n")
+                    .await
+                    .unwrap();
+            } else {
+                break;
+            }
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    assert!(request.starts_with("RESPMOD icap://127.0.0.1:9334/avscan ICAP/1.0\r\n"));
+    assert!(request.contains("X-Client-IP: "));
+
+    let response = if request.contains("EICAR") {
+        concat!(
+            "ICAP/1.0 200 OK\r\n",
+            "X-Infection-Found: Type=0; Resolution=2; Threat=Eicar-Test-Signature;\r\n",
+            "Encapsulated: res-hdr=0, null-body=28\r\n\r\n",
+            "HTTP/1.1 403 Forbidden\r\n\r\n"
+        )
+    } else if request.contains("CONFIDENTIAL") {
+        concat!(
+            "ICAP/1.0 200 OK\r\n",
+            "Encapsulated: res-hdr=0, null-body=28\r\n\r\n",
+            "HTTP/1.1 403 Forbidden\r\n\r\n"
+        )
+    } else {
+        "ICAP/1.0 204 No Content\r\n\r\n"
+    };
+    stream.write_all(response.as_bytes()).await.unwrap();
+}
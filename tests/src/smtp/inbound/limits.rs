@@ -4,17 +4,26 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::{Duration, Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use common::Core;
+use common::{
+    Core,
+    listener::{
+        ServerInstance,
+        limiter::{BufferLimiter, MessageLimiter},
+    },
+};
 use tokio::sync::watch;
 
-use smtp::core::Session;
+use smtp::core::{Session, SessionAddress};
 use utils::config::Config;
 
 use crate::smtp::{
     TestSMTP,
-    session::{TestSession, VerifyResponse},
+    session::{TestSession, VerifyResponse, test_server_instance},
 };
 
 const CONFIG: &str = r#"
@@ -73,3 +82,67 @@ async fn limits() {
     session.handle_conn().await;
     session.response().assert_code("221 2.0.0");
 }
+
+#[tokio::test]
+async fn message_budgets() {
+    // Enable logging
+    crate::enable_logging();
+
+    let mut config = Config::new(CONFIG).unwrap();
+    let core = Core::parse(&mut config, Default::default(), Default::default()).await;
+    let server = TestSMTP::from_core(core).server;
+    let limiter = MessageLimiter {
+        global: BufferLimiter::new(Some(1), None),
+        listener: BufferLimiter::new(None, Some(4096)),
+    };
+    let instance = Arc::new(ServerInstance {
+        message_limiter: limiter.clone(),
+        ..test_server_instance()
+    });
+
+    // Too many messages in progress, DATA is refused before replying 354
+    let in_flight = limiter.is_allowed(0).unwrap();
+    let mut session = Session::test(server.clone());
+    session.instance = instance.clone();
+    session.eval_session_params().await;
+    session
+        .data
+        .rcpt_to
+        .push(SessionAddress::new("bill@foobar.org".into()));
+    assert!(session.ingest(b"DATA\r\n").await.is_err());
+    session
+        .response()
+        .assert_not_contains("354")
+        .assert_code("421 4.3.2");
+    drop(in_flight);
+
+    // The message buffer budget is exhausted while receiving the message
+    let mut session = Session::test(server.clone());
+    session.instance = instance.clone();
+    session.eval_session_params().await;
+    session
+        .data
+        .rcpt_to
+        .push(SessionAddress::new("bill@foobar.org".into()));
+    session.ingest(b"DATA\r\n").await.unwrap();
+    session.response().assert_code("354");
+    assert_eq!(limiter.global.num_concurrent(), 1);
+    assert!(limiter.listener.num_bytes() > 0);
+    assert!(session.ingest(&vec![b'A'; 8192]).await.is_err());
+    session.response().assert_code("421 4.3.1");
+    assert_eq!(limiter.global.num_concurrent(), 0);
+    assert_eq!(limiter.listener.num_bytes(), 0);
+
+    // BDAT chunks are accounted before they are buffered
+    let mut session = Session::test(server);
+    session.instance = instance;
+    session.eval_session_params().await;
+    session
+        .data
+        .rcpt_to
+        .push(SessionAddress::new("bill@foobar.org".into()));
+    assert!(session.ingest(b"BDAT 8192 LAST\r\n").await.is_err());
+    session.response().assert_code("421 4.3.1");
+    assert!(session.data.message.capacity() <= 4096);
+    assert_eq!(limiter.listener.num_bytes(), 0);
+}
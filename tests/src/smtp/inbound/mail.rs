@@ -7,7 +7,7 @@
 use std::time::{Duration, Instant, SystemTime};
 
 use common::Core;
-use mail_auth::{IprevResult, SpfResult, common::parse::TxtRecordParser, spf::Spf};
+use mail_auth::{IprevResult, MX, SpfResult, common::parse::TxtRecordParser, spf::Spf};
 use smtp_proto::{MAIL_BY_NOTIFY, MAIL_BY_RETURN, MAIL_REQUIRETLS};
 
 use smtp::core::Session;
@@ -54,6 +54,7 @@ mt-priority = [{if = "remote_ip = '10.0.0.2'", then = 'nsep'},
 
 [session.mail]
 is-allowed = "sender_domain != 'blocked.com'"
+non-sending-domain.reject = "sender_domain != 'foobar.org'"
 
 [session.data.limits]
 size = [{if = "remote_ip = '10.0.0.2'", then = 2048},
@@ -124,6 +125,21 @@ async fn mail() {
         .unwrap();
     session.response().assert_code("550 5.7.1");
 
+    // Test sender domain with null MX
+    server.mx_add(
+        "nullmx.org",
+        vec![MX {
+            exchanges: vec![".".to_string()],
+            preference: 0,
+        }],
+        Instant::now() + Duration::from_secs(5),
+    );
+    session
+        .ingest(b"MAIL FROM:<bill@nullmx.org>\r\n")
+        .await
+        .unwrap();
+    session.response().assert_code("550 5.7.27");
+
     // Both IPREV and SPF should pass
     session
         .ingest(b"MAIL FROM:<bill@foobar.org>\r\n")
@@ -241,11 +257,18 @@ async fn mail() {
     session.rset().await;
 
     // Test REQUIRETLS extension
+    session
+        .ingest(b"MAIL FROM:<jane@foobar.org> REQUIRETLS\r\n")
+        .await
+        .unwrap();
+    session.response().assert_code("530 5.7.10");
+    session.stream.tls = true;
     session
         .ingest(b"MAIL FROM:<jane@foobar.org> REQUIRETLS\r\n")
         .await
         .unwrap();
     session.response().assert_code("250");
+    session.stream.tls = false;
     assert!((session.data.mail_from.as_ref().unwrap().flags & MAIL_REQUIRETLS) != 0);
     session.rset().await;
 
@@ -6,7 +6,7 @@
 
 use std::{fs, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use common::{
     Core,
     config::smtp::session::{Milter, MilterVersion, Stage},
@@ -73,6 +73,8 @@ enable = true
 options.version = 6
 tls = false
 stages = ["data"]
+macros.connect = ["j", "{client_addr}", "client_ptr"]
+macros.eom = ["i", "{auth_authen}", "{tls_version}"]
 
 "#;
 
@@ -568,6 +570,7 @@ async fn milter_client_test() {
             flags_actions: None,
             flags_protocol: None,
             run_on_stage: AHashSet::from([Stage::Data]),
+            macros: AHashMap::new(),
         },
         0,
     )
@@ -599,12 +602,18 @@ async fn milter_client_test() {
         .unwrap();
     println!("RCPT TO: {:?}", r);
 
-    let r = client.data().await.unwrap();
+    let r = client.data(Macros::new()).await.unwrap();
     println!("DATA: {:?}", r);
-    let r = client.headers(message.headers_raw()).await.unwrap();
+    let r = client
+        .headers(message.headers_raw(), Macros::new())
+        .await
+        .unwrap();
     println!("HEADERS: {:?}", r);
     let r = client
-        .body(&message.raw_message()[message.root_part().raw_body_offset() as usize..])
+        .body(
+            &message.raw_message()[message.root_part().raw_body_offset() as usize..],
+            Macros::new(),
+        )
         .await
         .unwrap();
     println!("BODY: {:?}", r);
@@ -29,14 +29,18 @@ pub mod basic;
 pub mod data;
 pub mod dmarc;
 pub mod ehlo;
+pub mod icap;
 pub mod limits;
 pub mod mail;
 pub mod milter;
+pub mod proxy;
 pub mod rcpt;
 pub mod rewrite;
 pub mod scripts;
+pub mod sending;
 pub mod sign;
 pub mod throttle;
+pub mod transcript;
 pub mod vrfy;
 
 impl QueueReceiver {
@@ -144,6 +148,7 @@ impl QueueReceiver {
             due: self.message_due(queue_id).await,
             queue_id,
             queue_name: Default::default(),
+            fair_share: Default::default(),
         }
     }
 
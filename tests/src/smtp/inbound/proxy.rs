@@ -0,0 +1,88 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Core, listener::ProxyInfo};
+
+use store::Stores;
+use utils::config::Config;
+
+use smtp::core::Session;
+
+use crate::smtp::{
+    TempDir, TestSMTP,
+    session::{TestSession, VerifyResponse},
+};
+
+const CONFIG: &str = r#"
+[storage]
+data = "rocksdb"
+lookup = "rocksdb"
+blob = "rocksdb"
+fts = "rocksdb"
+
+[store."rocksdb"]
+type = "rocksdb"
+path = "{TMP}/queue.db"
+
+[session.ehlo]
+reject-non-fqdn = [{if = "proxy_alpn = 'smtp'", then = false},
+                   {else = true}]
+
+[session.rcpt]
+max-recipients = [{if = "proxy_sni = 'mx.foobar.org'", then = 1},
+                  {else = 5}]
+relay = [{if = "proxy_client_cert && proxy_client_cn = 'relay.foobar.org'", then = true},
+         {else = false}]
+"#;
+
+#[tokio::test]
+async fn proxy_tlvs() {
+    // Enable logging
+    crate::enable_logging();
+
+    let tmp_dir = TempDir::new("smtp_proxy_tlv_test", true);
+    let mut config = Config::new(tmp_dir.update_config(CONFIG)).unwrap();
+    let stores = Stores::parse_all(&mut config, false).await;
+    let core = Core::parse(&mut config, stores, Default::default()).await;
+
+    // Without PROXY protocol details the default rules apply
+    let mut session = Session::test(TestSMTP::from_core(core).server);
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.cmd("EHLO foobar", "550 5.5.0").await;
+    session.ehlo("mx1.foobar.org").await;
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("external@domain.com", "550 5.1.2").await;
+
+    // A verified client certificate reported by the proxy enables relaying
+    session.stream.proxy_info = Some(ProxyInfo {
+        alpn: Some("smtp".into()),
+        sni: Some("mx.foobar.org".into()),
+        client_tls: true,
+        client_cert: true,
+        client_cert_verified: true,
+        client_cert_cn: Some("relay.foobar.org".into()),
+    });
+    session.eval_session_params().await;
+    session.cmd("EHLO foobar", "250").await;
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("external@domain.com", "250").await;
+
+    // The SNI reported by the proxy limits the number of recipients
+    session.rcpt_to("other@domain.com", "455 4.5.3").await;
+
+    // A different certificate CN does not allow relaying
+    session.stream.proxy_info = Some(ProxyInfo {
+        client_tls: true,
+        client_cert: true,
+        client_cert_cn: Some("other.foobar.org".into()),
+        ..Default::default()
+    });
+    session.eval_session_params().await;
+    session.rset().await;
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("external@domain.com", "550 5.1.2").await;
+}
@@ -70,6 +70,10 @@ total = [{if = "remote_ip = '10.0.0.1'", then = 3},
 wait = [{if = "remote_ip = '10.0.0.1'", then = '5ms'},
         {else = '1s'}]
 
+[session.rcpt.health-check]
+interval = "1h"
+failures = 2
+
 [session.extensions]
 dsn = [{if = "remote_ip = '10.0.0.1'", then = false},
        {else = true}]
@@ -162,4 +166,17 @@ async fn rcpt() {
     let rcpt = session.data.rcpt_to.last().unwrap();
     assert!((rcpt.flags & (RCPT_NOTIFY_DELAY | RCPT_NOTIFY_SUCCESS | RCPT_NOTIFY_FAILURE)) != 0);
     assert_eq!(rcpt.dsn_info.as_ref().unwrap(), "Jane.Doe@Foobar.org");
+
+    // Directories are only marked unavailable after consecutive failures
+    let server = session.server.clone();
+    let directory_failures = &server.inner.data.directory_failures;
+    directory_failures.lock().insert("local".to_string(), 1);
+    session.rcpt_to("bill@foobar.org", "250").await;
+    assert!(directory_failures.lock().is_empty());
+
+    // Recipients are deferred while their directory is unavailable
+    directory_failures.lock().insert("local".to_string(), 2);
+    session.rcpt_to("mike@foobar.org", "451 4.4.3").await;
+    directory_failures.lock().clear();
+    session.rcpt_to("mike@foobar.org", "250").await;
 }
@@ -0,0 +1,193 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::{
+    auth::{AccessToken, TenantInfo},
+    config::smtp::queue::{SendingLimit, SendingQuotaOverride},
+};
+use smtp::{core::Session, queue::sending::SendingQuotas};
+use store::write::now;
+
+use crate::smtp::{
+    TempDir, TestSMTP,
+    session::{DummyIo, TestSession},
+};
+
+const CONFIG: &str = r#"
+[session.auth]
+must-match-sender = false
+
+[session.rcpt]
+relay = true
+max-recipients = 100
+
+[queue.sending-quota.tenant]
+key = "tenant_id"
+hourly.messages = 2
+daily.recipients = 4
+notify = 50
+"#;
+
+#[tokio::test]
+async fn sending_quota() {
+    // Enable logging
+    crate::enable_logging();
+
+    let tmp_dir = TempDir::new("smtp_sending_quota", true);
+    let test = TestSMTP::new("smtp_sending_quota", tmp_dir.update_config(CONFIG)).await;
+    let server = test.server.clone();
+
+    let mut session = test.new_session();
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+
+    // Unauthenticated messages are not accounted
+    for _ in 0..3 {
+        session
+            .send_message("john@test.org", &["bill@foobar.org"], "test:no_dkim", "250")
+            .await;
+    }
+
+    // Only two messages per hour are allowed for tenant 7
+    set_tenant(&mut session, 7);
+    session
+        .send_message("john@test.org", &["bill@foobar.org"], "test:no_dkim", "250")
+        .await;
+    session
+        .send_message(
+            "john@test.org",
+            &["jane@foobar.org", "mike@foobar.org"],
+            "test:no_dkim",
+            "250",
+        )
+        .await;
+    session
+        .send_message(
+            "john@test.org",
+            &["bill@foobar.org"],
+            "test:no_dkim",
+            "452 4.7.1",
+        )
+        .await;
+    let usage = server
+        .sending_quota_usage("tenant", "7")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(usage.hourly.messages, 2);
+    assert_eq!(usage.hourly.recipients, 3);
+    assert_eq!(usage.daily.recipients, 3);
+
+    // Only four recipients per day are allowed for tenant 8
+    set_tenant(&mut session, 8);
+    session
+        .send_message(
+            "jane@example.org",
+            &[
+                "a@foobar.org",
+                "b@foobar.org",
+                "c@foobar.org",
+                "d@foobar.org",
+                "e@foobar.org",
+            ],
+            "test:no_dkim",
+            "452 4.7.1",
+        )
+        .await;
+    session
+        .send_message("jane@example.org", &["a@foobar.org"], "test:no_dkim", "250")
+        .await;
+
+    // Exempted tenants are not limited nor accounted
+    server
+        .set_sending_quota_override(
+            "tenant",
+            "7",
+            SendingQuotaOverride {
+                exempt: true,
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await
+        .unwrap();
+    set_tenant(&mut session, 7);
+    session
+        .send_message("john@test.org", &["bill@foobar.org"], "test:no_dkim", "250")
+        .await;
+    assert_eq!(
+        server
+            .sending_quota_usage("tenant", "7")
+            .await
+            .unwrap()
+            .unwrap()
+            .hourly
+            .messages,
+        2
+    );
+
+    // Resetting the quota lifts the override and clears the counters
+    server
+        .set_sending_quota_override("tenant", "7", None, None)
+        .await
+        .unwrap();
+    server.reset_sending_quota("tenant", "7").await.unwrap();
+    let usage = server
+        .sending_quota_usage("tenant", "7")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(usage.hourly.messages, 0);
+    assert_eq!(usage.override_, None);
+    session
+        .send_message("john@test.org", &["bill@foobar.org"], "test:no_dkim", "250")
+        .await;
+
+    // Queued messages are held back once the tenant is suspended
+    let message = test
+        .queue_receiver
+        .read_queued_messages()
+        .await
+        .into_iter()
+        .find(|message| message.message.return_path == "jane@example.org")
+        .unwrap();
+    assert_eq!(server.is_sending_allowed(&message).await, Ok(()));
+    server
+        .set_sending_quota_override(
+            "tenant",
+            "8",
+            SendingQuotaOverride {
+                hourly: SendingLimit {
+                    messages: Some(0),
+                    recipients: None,
+                }
+                .into(),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await
+        .unwrap();
+    let retry_at = server.is_sending_allowed(&message).await.unwrap_err();
+    assert!(retry_at > now() && retry_at <= now() + 3600);
+    test.queue_receiver.clear_queue(&server).await;
+}
+
+fn set_tenant(session: &mut Session<DummyIo>, tenant_id: u32) {
+    session.data.authenticated_as = Some(Arc::new(AccessToken {
+        name: format!("tenant{tenant_id}"),
+        tenant: Some(TenantInfo {
+            id: tenant_id,
+            quota: 0,
+        }),
+        ..Default::default()
+    }));
+}
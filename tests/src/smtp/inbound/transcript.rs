@@ -0,0 +1,211 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::{KV_SMTP_TRANSCRIPT, config::server::ServerProtocol};
+use reqwest::{StatusCode, header::AUTHORIZATION};
+use smtp::inbound::transcript::SmtpTranscript;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, ReadHalf, WriteHalf},
+    net::TcpStream,
+};
+
+use crate::smtp::TestSMTP;
+
+const CONFIG: &str = r#"
+[storage]
+directory = "local"
+
+[directory."local"]
+type = "memory"
+
+[[directory."local".principals]]
+name = "admin"
+type = "admin"
+description = "Superuser"
+secret = "secret"
+class = "admin"
+
+[session.ehlo]
+reject-non-fqdn = false
+
+[session.rcpt]
+relay = true
+
+[session.transcript]
+enable = [{if = "remote_ip = '127.0.0.1'", then = true},
+          {else = false}]
+max-body-size = 32
+retention = "1h"
+"#;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn session_transcript() {
+    // Enable logging
+    crate::enable_logging();
+
+    let test = TestSMTP::new("smtp_session_transcript", CONFIG).await;
+    let _rx = test
+        .start(&[ServerProtocol::Smtp, ServerProtocol::Http])
+        .await;
+
+    // Run an SMTP session
+    let (reader, mut writer) =
+        tokio::io::split(TcpStream::connect("127.0.0.1:9925").await.unwrap());
+    let mut reader = BufReader::new(reader).lines();
+    read_response(&mut reader, "220").await;
+    for (command, expected_code) in [
+        ("EHLO client.example.org", "250"),
+        ("AUTH PLAIN AGpvaG4Ac2VjcmV0", "5"),
+        ("MAIL FROM:<john@example.org>", "250"),
+        ("RCPT TO:<jane@example.net>", "250"),
+        ("DATA", "354"),
+    ] {
+        send(&mut writer, command).await;
+        read_response(&mut reader, expected_code).await;
+    }
+    send(
+        &mut writer,
+        &format!(
+            "Subject: Transcript test\r\n\r\n{}\r\n.",
+            "SECRET-BODY-".repeat(10)
+        ),
+    )
+    .await;
+    read_response(&mut reader, "250").await;
+
+    // BDAT chunks are subject to the same body size limit
+    for (command, expected_code) in [
+        ("MAIL FROM:<john@example.org>", "250"),
+        ("RCPT TO:<jane@example.net>", "250"),
+    ] {
+        send(&mut writer, command).await;
+        read_response(&mut reader, expected_code).await;
+    }
+    let chunk = format!("Subject: Chunked test\r\n\r\n{}", "CHUNK-BODY-".repeat(10));
+    for _ in 0..2 {
+        writer
+            .write_all(format!("BDAT {}\r\n{chunk}", chunk.len()).as_bytes())
+            .await
+            .unwrap();
+        read_response(&mut reader, "250").await;
+    }
+    let chunk = "\r\nLAST-CHUNK\r\n";
+    writer
+        .write_all(format!("BDAT {} LAST\r\n{chunk}", chunk.len()).as_bytes())
+        .await
+        .unwrap();
+    read_response(&mut reader, "250").await;
+
+    send(&mut writer, "QUIT").await;
+    read_response(&mut reader, "221").await;
+    drop(writer);
+    drop(reader);
+
+    // Transcripts are stored once the session ends
+    let server = test.build_smtp();
+    let mut session_id = None;
+    for _ in 0..50 {
+        if let Some((key, _)) = server
+            .in_memory_store()
+            .key_get_prefix(&[KV_SMTP_TRANSCRIPT])
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+        {
+            session_id = Some(u64::from_be_bytes(key[key.len() - 8..].try_into().unwrap()));
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let session_id = session_id.expect("Transcript was not stored");
+    let transcript = String::from_utf8(
+        server
+            .session_transcript(session_id)
+            .await
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+
+    // Credentials are redacted and the message body is truncated
+    for expected in [
+        "S: 220 ",
+        "C: EHLO client.example.org\r\n",
+        "C: AUTH PLAIN [redacted]\r\n",
+        "C: MAIL FROM:<john@example.org>\r\n",
+        "S: 354 ",
+        "Subject: Transcript test\r\n",
+        "[... message body truncated ...]",
+        "C: BDAT 135\r\n",
+        "C: BDAT 14 LAST\r\n",
+        "Subject: Chunked test\r\n",
+        "C: QUIT\r\n",
+        "S: 221 ",
+    ] {
+        assert!(transcript.contains(expected), "{expected:?}: {transcript}");
+    }
+    assert!(!transcript.contains("AGpvaG4Ac2VjcmV0"), "{transcript}");
+    assert!(!transcript.contains("SECRET-BODY-"), "{transcript}");
+    assert!(!transcript.contains("CHUNK-BODY-"), "{transcript}");
+    assert!(!transcript.contains("LAST-CHUNK"), "{transcript}");
+    assert_eq!(
+        transcript
+            .matches("[... message body truncated ...]")
+            .count(),
+        2,
+        "{transcript}"
+    );
+
+    // Transcripts are retrievable by session id from the management API
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    for (session_id, expected_status) in [
+        (session_id, StatusCode::OK),
+        (session_id + 1, StatusCode::NOT_FOUND),
+    ] {
+        let response = client
+            .get(format!(
+                "https://127.0.0.1:9980/api/queue/transcript/{session_id}"
+            ))
+            .header(AUTHORIZATION, "Basic YWRtaW46c2VjcmV0")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected_status);
+        if expected_status == StatusCode::OK {
+            assert_eq!(response.text().await.unwrap(), transcript);
+        }
+    }
+}
+
+async fn send(writer: &mut WriteHalf<TcpStream>, text: &str) {
+    writer.write_all(text.as_bytes()).await.unwrap();
+    writer.write_all(b"\r\n").await.unwrap();
+}
+
+async fn read_response(reader: &mut Lines<BufReader<ReadHalf<TcpStream>>>, expected_code: &str) {
+    loop {
+        let line = tokio::time::timeout(Duration::from_millis(1500), reader.next_line())
+            .await
+            .expect("Timeout waiting for response")
+            .unwrap()
+            .expect("Connection closed");
+        assert!(
+            line.starts_with(expected_code),
+            "Unexpected response: {line}"
+        );
+        if line.as_bytes().get(3) == Some(&b' ') {
+            break;
+        }
+    }
+}
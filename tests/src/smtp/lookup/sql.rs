@@ -89,6 +89,14 @@ expect = "mx.foobar.org"
 expr = "key_get('sql', 'hello') + '-' + key_exists('sql', 'hello') + '-' + key_set('sql', 'hello', 'world') + '-' + key_get('sql', 'hello') + '-' + key_exists('sql', 'hello')"
 expect = "-0-1-world-1"
 
+[test."key_multi"]
+expr = "key_get_many('sql', ['missing', 'hello'])[1] + '-' + key_exists_any('sql', ['missing', 'hello']) + '-' + key_exists_any('sql', ['missing'])"
+expect = "world-1-0"
+
+[test."key_multi_positions"]
+expr = "count(key_get_many('sql', ['missing', '', 'hello'])) + '-' + key_get_many('sql', ['missing', '', 'hello'])[0] + '-' + key_get_many('sql', ['missing', '', 'hello'])[1] + '-' + key_get_many('sql', ['missing', '', 'hello'])[2]"
+expect = "3---world"
+
 [test."counter_get"]
 expr = "counter_get('sql', 'county') + '-' + counter_incr('sql', 'county', 1) + '-' + counter_incr('sql', 'county', 1) + '-' + counter_get('sql', 'county')"
 expect = "0-1-2-2"
@@ -224,6 +224,12 @@ async fn manage_queue() {
                     "retry",
                     &message,
                 );
+                assert_timestamp(
+                    rcpt.held_until.as_ref().unwrap(),
+                    next_retry,
+                    "held",
+                    &message,
+                );
                 assert_timestamp(
                     rcpt.next_notify.as_ref().unwrap(),
                     next_notify,
@@ -234,6 +240,7 @@ async fn manage_queue() {
                 assert_eq!(&rcpt.status, &Status::Scheduled, "{message:#?}");
             } else if rcpt.address == "success@foobar.org" {
                 assert_eq!(rcpt.retry_num, 0);
+                assert_eq!(rcpt.held_until, None);
                 assert!(
                     matches!(&rcpt.status, Status::Completed(_)),
                     "{:?}",
@@ -276,6 +283,15 @@ async fn manage_queue() {
             format!("/api/queue/messages?after={test_search}"),
             vec!["d", "e", "f", "c"],
         ),
+        ("/api/queue/messages?text=bill1".to_string(), vec!["a"]),
+        (
+            "/api/queue/messages?text=example1&queue=default".to_string(),
+            vec!["a", "b", "c"],
+        ),
+        (
+            "/api/queue/messages?text=example1&queue=unknown".to_string(),
+            vec![],
+        ),
     ] {
         let expected_ids = HashSet::from_iter(expected_ids.into_iter().map(|s| s.to_string()));
         let ids = api
@@ -360,6 +376,50 @@ async fn manage_queue() {
         }
     }
 
+    // Hold and release deliveries
+    let id = *id_map.get("d").unwrap();
+    assert!(
+        api.request::<bool>(
+            Method::PATCH,
+            &format!("/api/queue/messages/{id}?filter=delay@foobar.org&at=2200-01-01T00:00:00Z"),
+        )
+        .await
+        .unwrap()
+        .unwrap_data()
+    );
+    let rcpt = api
+        .get_messages(&[id])
+        .await
+        .pop()
+        .flatten()
+        .unwrap()
+        .recipients
+        .remove(0);
+    let held_until = rcpt.held_until.as_ref().unwrap().to_rfc3339();
+    assert!(
+        ["2200-01-01T00:00:00Z", "2199-12-31T23:59:59Z"].contains(&held_until.as_str()),
+        "{held_until}"
+    );
+    assert!(
+        api.request::<bool>(
+            Method::PATCH,
+            &format!("/api/queue/messages/{id}?filter=delay@foobar.org"),
+        )
+        .await
+        .unwrap()
+        .unwrap_data()
+    );
+    let rcpt = api
+        .get_messages(&[id])
+        .await
+        .pop()
+        .flatten()
+        .unwrap()
+        .recipients
+        .remove(0);
+    assert_eq!(rcpt.held_until, None);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
     // Cancel deliveries
     for (id, filter) in [
         ("a", "example2.org"),
@@ -7,7 +7,8 @@
 use std::time::{Duration, Instant};
 
 use common::config::server::ServerProtocol;
-use mail_auth::MX;
+use mail_auth::{MX, common::parse::TxtRecordParser, mta_sts::MtaSts};
+use smtp::outbound::mta_sts::lookup::STS_TEST_POLICY;
 use smtp_proto::{MAIL_REQUIRETLS, MAIL_RET_HDRS, MAIL_SMTPUTF8, RCPT_NOTIFY_NEVER};
 
 use crate::smtp::{
@@ -134,7 +135,49 @@ async fn extensions() {
     local.queue_receiver.read_event().await.assert_done();
     remote.queue_receiver.assert_no_events();
 
+    // REQUIRETLS needs DNSSEC-signed MX records or an MTA-STS policy
+    session.stream.tls = true;
+    session
+        .send_message(
+            "<john@test.org> REQUIRETLS",
+            &["bill@foobar.org"],
+            "test:no_dkim",
+            "250",
+        )
+        .await;
+    local
+        .queue_receiver
+        .expect_message_then_deliver()
+        .await
+        .try_deliver(core.clone());
+    local
+        .queue_receiver
+        .expect_message()
+        .await
+        .read_lines(&local.queue_receiver)
+        .await
+        .assert_contains("<bill@foobar.org> (REQUIRETLS could not be honored")
+        .assert_contains("not DNSSEC-signed")
+        .assert_contains("Status: 5.7.30");
+    local.queue_receiver.read_event().await.assert_done();
+    remote.queue_receiver.assert_no_events();
+
     // Test DSN, SMTPUTF8 and REQUIRETLS extensions
+    core.txt_add(
+        "_mta-sts.foobar.org",
+        MtaSts::parse(b"v=STSv1; id=requiretls;").unwrap(),
+        Instant::now() + Duration::from_secs(10),
+    );
+    STS_TEST_POLICY.lock().clear();
+    STS_TEST_POLICY.lock().extend_from_slice(
+        concat!(
+            "version: STSv1\n",
+            "mode: enforce\n",
+            "mx: *.foobar.org\n",
+            "max_age: 604800\n"
+        )
+        .as_bytes(),
+    );
     session
         .send_message(
             "<john@test.org> ENVID=abc123 RET=HDRS REQUIRETLS SMTPUTF8",
@@ -155,4 +198,5 @@ async fn extensions() {
     assert!((message.message.flags & MAIL_REQUIRETLS) != 0);
     assert!((message.message.flags & MAIL_SMTPUTF8) != 0);
     assert!((message.message.recipients.last().unwrap().flags & RCPT_NOTIFY_NEVER) != 0);
+    STS_TEST_POLICY.lock().clear();
 }
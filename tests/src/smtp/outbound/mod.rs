@@ -10,6 +10,8 @@ pub mod fallback_relay;
 pub mod ip_lookup;
 pub mod lmtp;
 pub mod mta_sts;
+pub mod pipe;
 pub mod smtp;
 pub mod throttle;
 pub mod tls;
+pub mod trace;
@@ -0,0 +1,114 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use smtp::queue::Status;
+
+use crate::smtp::{TempDir, TestSMTP, session::TestSession};
+
+const LOCAL: &str = r#"
+[queue.strategy]
+route = [{if = "rcpt_domain = 'foobar.org'", then = "'pipe'"},
+            {else = "'mx'"}]
+
+[session.rcpt]
+relay = true
+max-recipients = 100
+
+[queue.route.pipe]
+type = "pipe"
+command = "/bin/sh"
+arguments = ["-c",
+             "cat > {TMP}/$1; case $1 in fail@*) echo 'User unknown' >&2; exit 67;; delay@*) exit 75;; esac",
+             "sh",
+             "{recipient}"]
+timeout = "10s"
+"#;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn pipe_delivery() {
+    // Enable logging
+    crate::enable_logging();
+
+    let tmp_dir = TempDir::new("smtp_pipe_delivery", true);
+    let mut local = TestSMTP::new("smtp_pipe_delivery", tmp_dir.update_config(LOCAL)).await;
+    let core = local.build_smtp();
+
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+    session
+        .send_message(
+            "john@test.org",
+            &[
+                "bill@foobar.org",
+                "fail@foobar.org",
+                "delay@foobar.org",
+                "-oQ@foobar.org",
+            ],
+            "test:no_dkim",
+            "250",
+        )
+        .await;
+    local
+        .queue_receiver
+        .expect_message_then_deliver()
+        .await
+        .try_deliver(core.clone());
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // The command received the message on its standard input
+    for rcpt in ["bill@foobar.org", "fail@foobar.org", "delay@foobar.org"] {
+        let contents = std::fs::read_to_string(tmp_dir.temp_dir.join(rcpt)).unwrap();
+        assert!(
+            contents.contains("From: Joe SixPack <joe@football.example.com>"),
+            "{rcpt}: {contents}"
+        );
+    }
+
+    // Addresses starting with a dash are never passed as command arguments
+    assert!(!tmp_dir.temp_dir.join("-oQ@foobar.org").exists());
+
+    // Exit codes are mapped to delivery statuses
+    let message = local
+        .queue_receiver
+        .read_queued_messages()
+        .await
+        .into_iter()
+        .find(|message| !message.message.return_path.is_empty())
+        .unwrap();
+    for rcpt in &message.message.recipients {
+        match rcpt.address() {
+            "bill@foobar.org" => {
+                assert!(matches!(rcpt.status, Status::Completed(_)), "{rcpt:?}")
+            }
+            "fail@foobar.org" => {
+                assert!(
+                    matches!(rcpt.status, Status::PermanentFailure(_)),
+                    "{rcpt:?}"
+                );
+                assert!(format!("{:?}", rcpt.status).contains("User unknown"));
+            }
+            "delay@foobar.org" => {
+                assert!(
+                    matches!(rcpt.status, Status::TemporaryFailure(_)),
+                    "{rcpt:?}"
+                )
+            }
+            "-oQ@foobar.org" => {
+                assert!(
+                    matches!(rcpt.status, Status::PermanentFailure(_)),
+                    "{rcpt:?}"
+                );
+                assert!(format!("{:?}", rcpt.status).contains("command argument"));
+            }
+            _ => unreachable!(),
+        }
+    }
+}
@@ -288,6 +288,7 @@ impl<'x> TestQueueEnvelope<'x> for QueueEnvelope<'x> {
             local_ip: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
             domain: rcpt.domain_part(),
             rcpt,
+            bounce_category: None,
         }
     }
 }
@@ -0,0 +1,145 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::{Duration, Instant};
+
+use common::{Server, config::server::ServerProtocol};
+use mail_auth::MX;
+use smtp::queue::{
+    MESSAGE_TRACE,
+    trace::{MessageTrace, TraceStage},
+};
+
+use crate::smtp::{
+    DnsCache, TestSMTP,
+    inbound::TestQueueEvent,
+    session::{TestSession, VerifyResponse},
+};
+
+const LOCAL: &str = r#"
+[session.rcpt]
+relay = true
+
+[session.data.trace]
+enable = [{if = "remote_ip = '10.0.0.1'", then = true},
+          {else = false}]
+"#;
+
+const REMOTE: &str = r#"
+[session.ehlo]
+reject-non-fqdn = false
+
+[session.rcpt]
+relay = true
+"#;
+
+const MESSAGE: &str = "From: john@test.org
+To: bill@foobar.org
+Subject: Trace test
+X-Stalwart-Trace: on
+
+Please trace this message.
+";
+
+#[tokio::test]
+#[serial_test::serial]
+async fn message_trace() {
+    // Enable logging
+    crate::enable_logging();
+
+    // Start test server
+    let mut remote = TestSMTP::new("smtp_trace_remote", REMOTE).await;
+    let _rx = remote.start(&[ServerProtocol::Smtp]).await;
+    let mut local = TestSMTP::new("smtp_trace_local", LOCAL).await;
+
+    // Add mock DNS entries
+    let core = local.build_smtp();
+    core.mx_add(
+        "foobar.org",
+        vec![MX {
+            exchanges: vec!["mx.foobar.org".to_string()],
+            preference: 10,
+        }],
+        Instant::now() + Duration::from_secs(10),
+    );
+    core.ipv4_add(
+        "mx.foobar.org",
+        vec!["127.0.0.1".parse().unwrap()],
+        Instant::now() + Duration::from_secs(10),
+    );
+
+    // Messages requesting tracing are traced when allowed by the configuration
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+    session.mail_from("john@test.org", "250").await;
+    session.rcpt_to("bill@foobar.org", "250").await;
+    session.data(MESSAGE, "250").await;
+    let message = local.queue_receiver.expect_message().await;
+    let queue_id = message.queue_id;
+    assert!(message.is_traced());
+    assert_ne!(message.message.flags & MESSAGE_TRACE, 0);
+    assert_eq!(
+        trace_stages(&core, queue_id).await,
+        vec![TraceStage::Received, TraceStage::Queued]
+    );
+
+    // Delivery attempts and recipient results are recorded
+    local
+        .queue_receiver
+        .delivery_attempt(queue_id)
+        .await
+        .try_deliver(core.clone());
+    local.queue_receiver.read_event().await.assert_done();
+    remote.queue_receiver.expect_message().await;
+    let entries = core.message_trace(queue_id).await.unwrap();
+    assert_eq!(
+        entries.iter().map(|entry| entry.stage).collect::<Vec<_>>(),
+        vec![
+            TraceStage::Received,
+            TraceStage::Queued,
+            TraceStage::Delivery,
+            TraceStage::Recipient,
+            TraceStage::Completed
+        ]
+    );
+    entries
+        .iter()
+        .map(|entry| entry.details.clone())
+        .collect::<Vec<_>>()
+        .assert_contains("Received from mx.test.org")
+        .assert_contains("<bill@foobar.org>: ");
+
+    // Messages without the trace header are not traced
+    session
+        .send_message("john@test.org", &["bill@foobar.org"], "test:no_dkim", "250")
+        .await;
+    let message = local.queue_receiver.expect_message().await;
+    assert!(!message.is_traced());
+    assert_eq!(trace_stages(&core, message.queue_id).await, vec![]);
+    local.queue_receiver.clear_queue(&core).await;
+
+    // Tracing requests are ignored when not allowed by the configuration
+    session.data.remote_ip_str = "10.0.0.2".into();
+    session.eval_session_params().await;
+    session.mail_from("john@test.org", "250").await;
+    session.rcpt_to("bill@foobar.org", "250").await;
+    session.data(MESSAGE, "250").await;
+    let message = local.queue_receiver.expect_message().await;
+    assert!(!message.is_traced());
+    assert_eq!(trace_stages(&core, message.queue_id).await, vec![]);
+}
+
+async fn trace_stages(server: &Server, queue_id: u64) -> Vec<TraceStage> {
+    server
+        .message_trace(queue_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.stage)
+        .collect()
+}
@@ -19,6 +19,7 @@ pub mod concurrent;
 pub mod dsn;
 pub mod manager;
 pub mod retry;
+pub mod spool;
 pub mod virtualq;
 
 pub fn build_rcpt(address: &str, retry: u64, notify: u64, expires: u64) -> Recipient {
@@ -0,0 +1,108 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::smtp::{TestSMTP, session::TestSession};
+use smtp::queue::storage::{QueueStorage, SpoolMigration};
+
+const CONFIG: &str = r#"
+[spam-filter]
+enable = false
+
+[session.rcpt]
+relay = true
+
+[queue.spool]
+type = "disk"
+path = "{TMP}/spool"
+"#;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn disk_spool() {
+    // Enable logging
+    crate::enable_logging();
+
+    let mut local = TestSMTP::new("smtp_disk_spool_test", CONFIG).await;
+    let server = local.build_smtp();
+    let spool_path = local.temp_dir.as_ref().unwrap().temp_dir.join("spool");
+
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+    session
+        .send_message("john@test.org", &["bill@foobar.org"], "test:no_dkim", "250")
+        .await;
+
+    // The message body should be on disk rather than in the blob store
+    let message = local.queue_receiver.expect_message().await;
+    let file_path = spool_path.join(format!("{:016x}", message.queue_id));
+    let contents = std::fs::read(&file_path).expect("Message not spooled to disk");
+    assert!(
+        server
+            .blob_store()
+            .get_blob(message.message.blob_hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        server
+            .get_queued_message(message.queue_id, &message.message.blob_hash, 0..usize::MAX)
+            .await
+            .unwrap(),
+        Some(contents.clone())
+    );
+    assert_eq!(
+        server
+            .get_queued_message(message.queue_id, &message.message.blob_hash, 0..10)
+            .await
+            .unwrap(),
+        Some(contents[..10].to_vec())
+    );
+
+    // Messages queued before switching to disk are read from the blob store
+    // and moved to disk by the migration
+    std::fs::remove_file(&file_path).unwrap();
+    server
+        .blob_store()
+        .put_blob(message.message.blob_hash.as_slice(), &contents)
+        .await
+        .unwrap();
+    assert_eq!(
+        server
+            .get_queued_message(message.queue_id, &message.message.blob_hash, 0..usize::MAX)
+            .await
+            .unwrap(),
+        Some(contents.clone())
+    );
+    assert_eq!(
+        server.migrate_queue_spool().await.unwrap(),
+        SpoolMigration {
+            migrated: 1,
+            missing: 0,
+            orphans_removed: 0,
+        }
+    );
+    assert_eq!(std::fs::read(&file_path).unwrap(), contents);
+
+    // Corrupted files are detected
+    std::fs::write(&file_path, b"corrupted").unwrap();
+    assert!(
+        server
+            .get_queued_message(message.queue_id, &message.message.blob_hash, 0..usize::MAX)
+            .await
+            .is_err()
+    );
+    std::fs::write(&file_path, &contents).unwrap();
+
+    // Removing the message from the queue deletes the spooled file
+    message
+        .remove(&server, local.queue_receiver.last_queued_due().await.into())
+        .await;
+    assert!(!file_path.exists());
+    local.queue_receiver.assert_queue_is_empty().await;
+}
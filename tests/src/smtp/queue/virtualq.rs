@@ -14,7 +14,9 @@ use common::{
 use mail_auth::MX;
 
 use crate::smtp::{DnsCache, TestSMTP, session::TestSession};
-use smtp::queue::manager::Queue;
+use smtp::queue::manager::{Queue, QueueStats};
+use store::write::now;
+use tokio::sync::mpsc;
 
 const LOCAL: &str = r#"
 [spam-filter]
@@ -211,3 +213,89 @@ async fn virtual_queue() {
         .assert_is_empty(core.core.storage.blob.clone())
         .await;
 }
+
+const AUTOSCALE: &str = r#"
+[queue.virtual.q1]
+threads-per-node = 4
+autoscale.enable = true
+autoscale.min-threads = 2
+autoscale.max-threads = 8
+autoscale.scale-up.depth = 3
+autoscale.scale-up.age = "1h"
+autoscale.scale-down.idle = "0s"
+
+[queue.virtual.q2]
+threads-per-node = 4
+
+[queue.virtual.q3]
+threads-per-node = 4
+autoscale.enable = true
+autoscale.min-threads = 4
+autoscale.max-threads = 2
+"#;
+
+#[tokio::test]
+async fn virtual_queue_autoscale() {
+    // Enable logging
+    crate::enable_logging();
+
+    // Validate parsing
+    let local = TestSMTP::new("smtp_virtual_queue_autoscale", AUTOSCALE).await;
+    let q1 = QueueName::new("q1").unwrap();
+    let q2 = QueueName::new("q2").unwrap();
+    let virtual_queues = &local.server.core.smtp.queue.virtual_queues;
+    let autoscale = virtual_queues.get(&q1).unwrap().autoscale.as_ref().unwrap();
+    assert_eq!(
+        (
+            autoscale.min_threads,
+            autoscale.max_threads,
+            autoscale.scale_up_depth,
+            autoscale.scale_up_age,
+            autoscale.scale_down_after
+        ),
+        (2, 8, 3, 3600, Duration::ZERO)
+    );
+    assert!(virtual_queues.get(&q2).unwrap().autoscale.is_none());
+    assert!(
+        !virtual_queues.contains_key(&QueueName::new("q3").unwrap()),
+        "Queues with invalid autoscale limits should be rejected"
+    );
+
+    // Workers are scaled up when the backlog is deep, up to the maximum
+    let mut queue = Queue::new(local.server.inner.clone(), mpsc::channel(1).1);
+    queue.stats.insert(q1, QueueStats::new(4));
+    queue.stats.insert(q2, QueueStats::new(4));
+    let now = now();
+    for expected in [6, 8, 8] {
+        for name in [q1, q2] {
+            let stats = queue.stats.get_mut(&name).unwrap();
+            stats.backlog = 3;
+            stats.oldest_due = now;
+        }
+        queue.autoscale(&local.server, now);
+        assert_eq!(queue.stats[&q1].max_in_flight, expected);
+        assert_eq!(queue.stats[&q1].backlog, 0);
+    }
+
+    // Workers are not scaled down while busy
+    queue.stats.get_mut(&q1).unwrap().in_flight = 5;
+    queue.autoscale(&local.server, now);
+    assert_eq!(queue.stats[&q1].max_in_flight, 8);
+
+    // Idle workers are scaled down, down to the minimum
+    queue.stats.get_mut(&q1).unwrap().in_flight = 0;
+    for expected in [6, 5, 4, 3, 2, 2] {
+        queue.autoscale(&local.server, now);
+        assert_eq!(queue.stats[&q1].max_in_flight, expected);
+    }
+
+    // Old backlogs trigger a scale up regardless of their depth
+    let stats = queue.stats.get_mut(&q1).unwrap();
+    stats.backlog = 1;
+    stats.oldest_due = now - 3600;
+    queue.autoscale(&local.server, now);
+    assert_eq!(queue.stats[&q1].max_in_flight, 3);
+
+    // Queues without autoscaling keep a fixed number of workers
+    assert_eq!(queue.stats[&q2].max_in_flight, 4);
+}
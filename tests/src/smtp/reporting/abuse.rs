@@ -0,0 +1,271 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use common::auth::AccessToken;
+use mail_auth::{common::parse::TxtRecordParser, spf::Spf};
+use smtp::{core::Session, reporting::abuse::AbuseReporting};
+
+use crate::smtp::{
+    TestSMTP,
+    inbound::sign::SIGNATURES,
+    session::{DummyIo, TestSession, VerifyResponse},
+};
+
+const CONFIG: &str = r#"
+[storage]
+directory = "local"
+
+[directory."local"]
+type = "memory"
+
+[[directory."local".principals]]
+name = "john"
+description = "John Doe"
+secret = "secret"
+email = "john@foobar.org"
+
+[session.rcpt]
+relay = true
+
+[server]
+hostname = "mx.example.org"
+
+[report.analysis]
+addresses = ["fbl@foobar.org"]
+forward = false
+
+[auth.spf.verify]
+mail-from = "relaxed"
+
+[report.analysis.feedback]
+trusted-sources = ["isp.net"]
+suppress = "1d"
+max-complaints = 2
+window = "1d"
+
+[report.abuse]
+from-name = "'Abuse Desk'"
+from-address = "'abuse-reports@example.org'"
+subject = "'Abuse report'"
+sign = "['rsa']"
+to = "['abuse@' + sender_domain]"
+"#;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn report_abuse() {
+    // Enable logging
+    crate::enable_logging();
+
+    let mut local = TestSMTP::new("smtp_report_abuse_test", CONFIG.to_string() + SIGNATURES).await;
+    let server = local.build_smtp();
+    let qr = &mut local.queue_receiver;
+    for (domain, record) in [
+        ("isp.net", "v=spf1 ip4:10.0.0.1 -all"),
+        ("forged.net", "v=spf1 ip4:10.0.0.4 -all"),
+    ] {
+        server.txt_add(
+            domain,
+            Spf::parse(record.as_bytes()).unwrap(),
+            Instant::now() + Duration::from_secs(100),
+        );
+    }
+
+    // Complaints that fail authentication are ignored
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.4".into();
+    session.eval_session_params().await;
+    session.ehlo("mx.forged.net").await;
+    session
+        .send_message(
+            "fbl@isp.net",
+            &["fbl@foobar.org"],
+            &complaint("fbl@isp.net", "john@foobar.org", "user@isp.net"),
+            "250",
+        )
+        .await;
+
+    // Complaints from authenticated but untrusted sources are ignored
+    session
+        .send_message(
+            "fbl@forged.net",
+            &["fbl@foobar.org"],
+            &complaint("fbl@forged.net", "john@foobar.org", "user@isp.net"),
+            "250",
+        )
+        .await;
+    qr.assert_no_events();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(
+        !server
+            .is_abuse_suppressed("john@foobar.org", "user@isp.net", 0)
+            .await
+    );
+
+    // Receive a feedback loop complaint about a local sender
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.ehlo("fbl.isp.net").await;
+    session
+        .send_message(
+            "fbl@isp.net",
+            &["fbl@foobar.org"],
+            &complaint("fbl@isp.net", "john@foobar.org", "user@isp.net"),
+            "250",
+        )
+        .await;
+    qr.assert_no_events();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Further messages to the complaining recipient are refused
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.2".into();
+    session.eval_session_params().await;
+    session.ehlo("client.foobar.org").await;
+    authenticate(&mut session);
+    session.mail_from("john@foobar.org", "250").await;
+    session.rcpt_to("user@isp.net", "550 5.7.1").await;
+    session.rcpt_to("other@isp.net", "250").await;
+    assert!(!server.is_sender_suspended("john@foobar.org", 0).await);
+
+    // Unauthenticated sessions are not affected
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.3".into();
+    session.eval_session_params().await;
+    session.ehlo("mx.other.org").await;
+    session.mail_from("john@foobar.org", "250").await;
+    session.rcpt_to("user@isp.net", "250").await;
+
+    // Complaints about remote senders are ignored
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.1".into();
+    session.eval_session_params().await;
+    session.ehlo("fbl.isp.net").await;
+    for _ in 0..2 {
+        session
+            .send_message(
+                "fbl@isp.net",
+                &["fbl@foobar.org"],
+                &complaint("fbl@isp.net", "spammer@example.net", "user@isp.net"),
+                "250",
+            )
+            .await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!server.is_sender_suspended("spammer@example.net", 0).await);
+    assert!(
+        !server
+            .is_abuse_suppressed("spammer@example.net", "user@isp.net", 0)
+            .await
+    );
+
+    // Senders exceeding the complaint limit are suspended
+    session
+        .send_message(
+            "fbl@isp.net",
+            &["fbl@foobar.org"],
+            &complaint("fbl@isp.net", "john@foobar.org", "other@isp.net"),
+            "250",
+        )
+        .await;
+    qr.assert_no_events();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(server.is_sender_suspended("john@foobar.org", 0).await);
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.2".into();
+    session.eval_session_params().await;
+    session.ehlo("client.foobar.org").await;
+    authenticate(&mut session);
+    session.mail_from("john@foobar.org", "550 5.7.1").await;
+
+    // Messages reported as spam are sent to the originating network
+    server
+        .send_abuse_report(SPAM_MESSAGE.as_bytes(), "john@foobar.org", 0)
+        .await;
+    let message = qr.expect_message().await;
+    qr.assert_no_events();
+    assert_eq!(message.message.recipients.len(), 1);
+    assert_eq!(
+        message.message.recipients.last().unwrap().address(),
+        "abuse@example.net"
+    );
+    assert_eq!(message.message.return_path, "abuse-reports@example.org");
+    message
+        .read_lines(qr)
+        .await
+        .assert_contains("DKIM-Signature: v=1; a=rsa-sha256; s=rsa; d=example.com;")
+        .assert_contains("To: <abuse@example.net>")
+        .assert_contains("Subject: Abuse report")
+        .assert_contains("Feedback-Type: abuse")
+        .assert_contains("john@foobar.org")
+        .assert_contains("spammer@example.net")
+        .assert_contains("192.0.2.1")
+        .assert_contains("Earn money");
+}
+
+fn authenticate(session: &mut Session<DummyIo>) {
+    session.data.authenticated_as = Some(Arc::new(AccessToken {
+        name: "john".to_string(),
+        emails: vec!["john@foobar.org".to_string()],
+        ..Default::default()
+    }));
+}
+
+fn complaint(from: &str, sender: &str, rcpt: &str) -> String {
+    format!(
+        concat!(
+            "From: <{}>\r\n",
+            "To: <fbl@foobar.org>\r\n",
+            "Subject: FW: Earn money\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/report; report-type=feedback-report;\r\n",
+            "    boundary=\"fbl_boundary\"\r\n",
+            "\r\n",
+            "--fbl_boundary\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "This is an email abuse report.\r\n",
+            "\r\n",
+            "--fbl_boundary\r\n",
+            "Content-Type: message/feedback-report\r\n",
+            "\r\n",
+            "Feedback-Type: abuse\r\n",
+            "User-Agent: FBL/1.0\r\n",
+            "Version: 1\r\n",
+            "Original-Mail-From: <{}>\r\n",
+            "Original-Rcpt-To: <{}>\r\n",
+            "\r\n",
+            "--fbl_boundary\r\n",
+            "Content-Type: message/rfc822\r\n",
+            "\r\n",
+            "From: <{}>\r\n",
+            "To: <{}>\r\n",
+            "Subject: Earn money\r\n",
+            "\r\n",
+            "Spam Spam Spam\r\n",
+            "--fbl_boundary--\r\n"
+        ),
+        from, sender, rcpt, sender, rcpt
+    )
+}
+
+const SPAM_MESSAGE: &str = concat!(
+    "Return-Path: <spammer@example.net>\r\n",
+    "Received: from mailserver.example.net (mailserver.example.net [192.0.2.1])\r\n",
+    "    by mx.example.org with ESMTP id M63d4137594e46;\r\n",
+    "    Thu, 08 Mar 2005 14:00:00 -0400\r\n",
+    "From: <spammer@example.net>\r\n",
+    "To: <john@foobar.org>\r\n",
+    "Subject: Earn money\r\n",
+    "Date: Thu, 02 Sep 2004 12:31:03 -0500\r\n",
+    "\r\n",
+    "Spam Spam Spam\r\n"
+);
@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod abuse;
 pub mod analyze;
 pub mod dmarc;
 pub mod scheduler;
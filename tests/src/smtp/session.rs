@@ -9,7 +9,9 @@ use std::{borrow::Cow, path::PathBuf, sync::Arc};
 use common::{
     Server,
     config::server::ServerProtocol,
-    listener::{ServerInstance, SessionStream, TcpAcceptor, limiter::ConcurrencyLimiter},
+    listener::{
+        ProxyInfo, ServerInstance, SessionStream, TcpAcceptor, limiter::ConcurrencyLimiter,
+    },
 };
 use rustls::{ServerConfig, server::ResolvesServerCert};
 use tokio::{
@@ -25,6 +27,7 @@ pub struct DummyIo {
     pub tx_buf: Vec<u8>,
     pub rx_buf: Vec<u8>,
     pub tls: bool,
+    pub proxy_info: Option<ProxyInfo>,
 }
 
 impl AsyncRead for DummyIo {
@@ -76,6 +79,10 @@ impl SessionStream for DummyIo {
     fn tls_version_and_cipher(&self) -> (Cow<'static, str>, Cow<'static, str>) {
         ("".into(), "".into())
     }
+
+    fn proxy_info(&self) -> Option<ProxyInfo> {
+        self.proxy_info.clone()
+    }
 }
 
 impl Unpin for DummyIo {}
@@ -106,6 +113,7 @@ impl TestSession for Session<DummyIo> {
                 rx_buf: vec![],
                 tx_buf: vec![],
                 tls: false,
+                proxy_info: None,
             },
             data: SessionData::new(
                 "127.0.0.1".parse().unwrap(),
@@ -349,8 +357,11 @@ impl TestServerInstance for ServerInstance {
                 config: tls_config.clone(),
                 acceptor: TlsAcceptor::from(tls_config),
                 implicit: false,
+                client_cert: None,
+                fingerprint: false,
             },
             limiter: ConcurrencyLimiter::new(100),
+            message_limiter: Default::default(),
             shutdown_rx,
             proxy_networks: vec![],
             span_id_gen: Arc::new(SnowflakeIdGenerator::new()),
@@ -4,10 +4,16 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::sync::atomic::Ordering;
+
 use ahash::AHashMap;
 use store::{
-    BlobStore, SerializeInfallible, Stores,
-    write::{BatchBuilder, BlobOp, blob::BlobQuota, now},
+    BlobStore, SerializeInfallible, Store, Stores,
+    write::{
+        BatchBuilder, BlobOp,
+        blob::{BlobQuota, BlobScrubStatus},
+        now,
+    },
 };
 use types::{blob::BlobClass, blob_hash::BlobHash, collection::Collection};
 use utils::config::Config;
@@ -26,12 +32,17 @@ pub async fn blob_tests() {
         test_store(blob_store.clone()).await;
     }
 
+    let replica = stores.blob_stores.get("fs").unwrap().clone();
     for (store_id, store) in stores.stores {
         println!("Testing blob management on store {}...", store_id);
 
         // Init store
         store.destroy().await;
 
+        // Test blob integrity verification
+        test_scrub(&store, &replica).await;
+        store.destroy().await;
+
         // Test internal blob store
         let blob_store: BlobStore = store.clone().into();
 
@@ -361,7 +372,12 @@ pub async fn blob_tests() {
 
         // Unlink all blobs from accountId 1 and purge
         store.blob_hash_unlink_account(1).await.unwrap();
+        assert_eq!(
+            store.unreferenced_blobs().await.unwrap(),
+            vec![BlobHash::generate(b"123".as_slice())]
+        );
         store.purge_blobs(blob_store.clone()).await.unwrap();
+        assert_eq!(store.unreferenced_blobs().await.unwrap(), vec![]);
 
         // Make sure only accountId 0's blobs are left
         for (pos, (blob, blob_class)) in [
@@ -416,6 +432,128 @@ pub async fn blob_tests() {
     temp_dir.delete();
 }
 
+async fn test_scrub(store: &Store, replica: &BlobStore) {
+    let blob_store: BlobStore = store.clone().into();
+    let blobs = [
+        b"scrub-intact".as_slice(),
+        b"scrub-corrupted".as_slice(),
+        b"scrub-missing".as_slice(),
+        b"scrub-lost".as_slice(),
+    ];
+    let hashes = blobs
+        .iter()
+        .map(|blob| BlobHash::generate(blob))
+        .collect::<Vec<_>>();
+
+    // Write and commit blobs, keeping a copy in the replica
+    for (blob, hash) in blobs.iter().zip(&hashes) {
+        blob_store.put_blob(hash.as_ref(), blob).await.unwrap();
+        replica.put_blob(hash.as_ref(), blob).await.unwrap();
+        store
+            .write(
+                BatchBuilder::new()
+                    .set(BlobOp::Commit { hash: hash.clone() }, Vec::new())
+                    .build_all(),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Corrupt and delete blobs
+    blob_store
+        .put_blob(hashes[1].as_ref(), b"tampered")
+        .await
+        .unwrap();
+    blob_store.delete_blob(hashes[2].as_ref()).await.unwrap();
+    blob_store.delete_blob(hashes[3].as_ref()).await.unwrap();
+    replica.delete_blob(hashes[3].as_ref()).await.unwrap();
+
+    // Verification without a replica only reports damaged blobs
+    let status = BlobScrubStatus::default();
+    let report = store
+        .scrub_blobs(&blob_store, 100, None, &status)
+        .await
+        .unwrap();
+    assert_eq!(report.checked, 4);
+    assert_eq!(status.total.load(Ordering::Relaxed), 4);
+    assert_eq!(status.checked.load(Ordering::Relaxed), 4);
+    assert_eq!(report.corrupted, vec![hashes[1].clone()]);
+    assert_eq!(
+        sorted(report.missing),
+        sorted(vec![hashes[2].clone(), hashes[3].clone()])
+    );
+    assert!(report.repaired.is_empty());
+
+    // Damaged blobs are restored from the replica when available
+    let report = store
+        .scrub_blobs(&blob_store, 100, Some(replica), &status)
+        .await
+        .unwrap();
+    assert_eq!(
+        sorted(report.repaired),
+        sorted(vec![hashes[1].clone(), hashes[2].clone()])
+    );
+    for (blob, hash) in blobs.iter().zip(&hashes).take(3) {
+        assert_eq!(
+            blob_store
+                .get_blob(hash.as_ref(), 0..usize::MAX)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(*blob)
+        );
+    }
+
+    // Only the blob missing from both stores remains damaged
+    let report = store
+        .scrub_blobs(&blob_store, 100, Some(replica), &status)
+        .await
+        .unwrap();
+    assert!(report.corrupted.is_empty());
+    assert_eq!(report.missing, vec![hashes[3].clone()]);
+    assert!(report.repaired.is_empty());
+
+    for hash in &hashes {
+        replica.delete_blob(hash.as_ref()).await.unwrap();
+    }
+}
+
+fn sorted(mut hashes: Vec<BlobHash>) -> Vec<BlobHash> {
+    hashes.sort_unstable();
+    hashes
+}
+
+#[cfg(feature = "s3")]
+#[tokio::test]
+pub async fn s3_retries() {
+    use std::time::{Duration, Instant};
+
+    let mut config = Config::new(
+        r#"
+[store."s3-unreachable"]
+type = "s3"
+access-key = "minioadmin"
+secret-key = "minioadmin"
+region = "eu-central-1"
+endpoint = "http://127.0.0.1:1"
+bucket = "tmp"
+max-retries = 2
+retry.base-delay = "50ms"
+retry.max-delay = "60ms"
+"#,
+    )
+    .unwrap();
+    let stores = Stores::parse_all(&mut config, false).await;
+    let store = stores.blob_stores.get("s3-unreachable").unwrap().clone();
+
+    // Failed requests are retried with a jittered backoff capped at the maximum delay
+    let start_time = Instant::now();
+    assert!(store.put_blob(b"abc", b"abc").await.is_err());
+    let elapsed = start_time.elapsed();
+    assert!(elapsed >= Duration::from_millis(55), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+}
+
 async fn test_store(store: BlobStore) {
     // Test small blob
     const DATA: &[u8] = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. Fusce erat nisl, dignissim a porttitor id, varius nec arcu. Sed mauris.";
@@ -0,0 +1,109 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Core;
+use directory::DirectoryInner;
+use store::{InMemoryStore, Stores, dispatch::lookup::KeyValue};
+use utils::config::Config;
+
+use crate::store::TempDir;
+
+const CONFIG: &str = r#"
+[store."rocksdb"]
+type = "rocksdb"
+path = "{TMP}/rocksdb"
+
+[store."redis"]
+type = "redis"
+urls = "redis://127.0.0.1"
+redis-type = "single"
+disable = true
+
+[directory."sql"]
+type = "sql"
+store = "missing"
+
+[storage]
+data = "rocksdb"
+blob = "rocksdb"
+fts = "rocksdb"
+lookup = "redis"
+directory = "sql"
+"#;
+
+#[tokio::test]
+pub async fn degraded_boot() {
+    let temp_dir = TempDir::new("degraded_boot_tests", true);
+    let config = CONFIG.replace("{TMP}", &temp_dir.path.to_string_lossy());
+
+    // Unavailable backends are replaced by the data store
+    let mut config_ = Config::new(&config).unwrap();
+    let stores = Stores::parse_all(&mut config_, false).await;
+    let core = Core::parse(&mut config_, stores, Default::default()).await;
+    assert_eq!(
+        core.storage.degraded,
+        vec!["storage.lookup", "storage.directory"]
+    );
+    assert!(matches!(core.storage.lookup, InMemoryStore::Store(_)));
+    assert!(matches!(
+        core.storage.directory.store,
+        DirectoryInner::Unavailable
+    ));
+    assert!(matches!(
+        core.storage.directories.get("sql").map(|d| &d.store),
+        Some(DirectoryInner::Unavailable)
+    ));
+    for key in ["storage.lookup", "storage.directory"] {
+        assert!(config_.warnings.contains_key(key), "{key}");
+        assert!(!config_.errors.contains_key(key), "{key}");
+    }
+
+    // The fallback store is usable
+    core.storage
+        .lookup
+        .key_set(KeyValue::new(b"degraded".to_vec(), b"ok".to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(
+        core.storage
+            .lookup
+            .key_get::<String>(b"degraded".to_vec())
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("ok")
+    );
+
+    // Directory lookups fail temporarily instead of reporting unknown users
+    assert!(
+        core.storage
+            .directory
+            .rcpt("john@example.org")
+            .await
+            .is_err()
+    );
+    assert!(
+        core.storage
+            .directory
+            .is_local_domain("example.org")
+            .await
+            .is_err()
+    );
+
+    drop(core);
+
+    // Degraded boot can be disabled
+    let mut config_ =
+        Config::new(config.clone() + "\n[storage.degraded]\nenable = false\n").unwrap();
+    let stores = Stores::parse_all(&mut config_, false).await;
+    let core = Core::parse(&mut config_, stores, Default::default()).await;
+    assert!(core.storage.degraded.is_empty());
+    for key in ["storage.lookup", "storage.directory"] {
+        assert!(config_.errors.contains_key(key), "{key}");
+    }
+
+    temp_dir.delete();
+}
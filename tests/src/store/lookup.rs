@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use store::{InMemoryStore, Stores, dispatch::lookup::KeyValue};
 use utils::config::{Config, Rate};
@@ -112,7 +112,8 @@ pub async fn lookup_tests() {
                 .unwrap()
                 .is_some()
         );
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        // The previous window is still weighted in the sliding window
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
         assert!(
             store
                 .is_rate_allowed(0, "rate".as_bytes(), &rate, false)
@@ -120,12 +121,61 @@ pub async fn lookup_tests() {
                 .unwrap()
                 .is_none()
         );
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
         store.purge_in_memory_store().await.unwrap();
         if let InMemoryStore::Store(store) = &store {
             store.assert_is_empty(store.clone().into()).await;
         }
 
+        // The previous window is weighted by how much of it is still covered
+        let rate = Rate {
+            requests: 10,
+            period: Duration::from_secs(10),
+        };
+        let window = (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+            / 10_000)
+            * 10_000;
+        for (now, allowed, soft_check) in [
+            // 11 requests near the end of the first window, the last one is rejected
+            (window + 9_000, 10, false),
+            // 75% of the previous window (8 requests) still counts
+            (window + 12_500, 2, false),
+            // 10% of the previous window (1 request) still counts
+            (window + 19_000, 6, false),
+            // Soft checks do not count towards the limit
+            (window + 30_000, 20, true),
+            (window + 30_000, 10, false),
+        ] {
+            for _ in 0..allowed {
+                assert_eq!(
+                    store
+                        .is_rate_allowed_at(0, "window".as_bytes(), &rate, soft_check, now)
+                        .await
+                        .unwrap(),
+                    None,
+                    "{now}"
+                );
+            }
+            if !soft_check {
+                assert_eq!(
+                    store
+                        .is_rate_allowed_at(0, "window".as_bytes(), &rate, false, now)
+                        .await
+                        .unwrap(),
+                    Some((10_000 - (now - window) % 10_000).div_ceil(1000)),
+                    "{now}"
+                );
+            }
+        }
+
+        store
+            .key_delete_prefix(&KeyValue::<()>::build_key(0, "window"))
+            .await
+            .unwrap();
+
         // Test locking
         for iteration in [1, 2] {
             let mut tasks = Vec::new();
@@ -286,6 +336,103 @@ pub async fn lookup_tests() {
     }
 }
 
+#[cfg(feature = "redis")]
+#[tokio::test]
+pub async fn redis_lookup_cache() {
+    let mut config = Config::new(
+        r#"
+[store."redis-uncached"]
+type = "redis"
+urls = "redis://127.0.0.1"
+redis-type = "single"
+
+[store."redis-node1"]
+type = "redis"
+urls = "redis://127.0.0.1"
+redis-type = "single"
+cache.enable = true
+cache.ttl = "1h"
+
+[store."redis-node2"]
+type = "redis"
+urls = "redis://127.0.0.1"
+redis-type = "single"
+cache.enable = true
+cache.ttl = "1h"
+"#,
+    )
+    .unwrap();
+    let stores = Stores::parse_all(&mut config, false).await;
+    config.assert_no_errors();
+    let uncached = stores.in_memory_stores.get("redis-uncached").unwrap();
+    let node1 = stores.in_memory_stores.get("redis-node1").unwrap();
+    let node2 = stores.in_memory_stores.get("redis-node2").unwrap();
+
+    // Wait for the invalidation subscribers to connect
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let key = b"lookup-cache-test".to_vec();
+    uncached.key_delete(key.clone()).await.unwrap();
+
+    // Values are cached locally
+    node1
+        .key_set(KeyValue::new(key.clone(), b"v1".to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(
+        node2.key_get::<String>(key.clone()).await.unwrap(),
+        Some("v1".to_string())
+    );
+    uncached
+        .key_set(KeyValue::new(key.clone(), b"v2".to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(
+        node2.key_get::<String>(key.clone()).await.unwrap(),
+        Some("v1".to_string())
+    );
+    assert!(node2.key_exists(key.clone()).await.unwrap());
+
+    // Writes on other nodes invalidate cached entries
+    node1
+        .key_set(KeyValue::new(key.clone(), b"v3".to_vec()))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(
+        node2.key_get::<String>(key.clone()).await.unwrap(),
+        Some("v3".to_string())
+    );
+    node1.key_delete(key.clone()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node2.key_get::<String>(key.clone()).await.unwrap(), None);
+    assert!(!node2.key_exists(key.clone()).await.unwrap());
+
+    // Missing keys are cached as well, until invalidated
+    uncached
+        .key_set(KeyValue::new(key.clone(), b"v4".to_vec()))
+        .await
+        .unwrap();
+    assert!(!node2.key_exists(key.clone()).await.unwrap());
+    node1.key_delete_prefix(&key).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node2.key_get::<String>(key.clone()).await.unwrap(), None);
+
+    // Counters are never cached
+    let key = b"lookup-cache-counter".to_vec();
+    uncached.key_delete(key.clone()).await.unwrap();
+    node1
+        .counter_incr(KeyValue::new(key.clone(), 1), true)
+        .await
+        .unwrap();
+    assert_eq!(node2.counter_get(key.clone()).await.unwrap(), 1);
+    uncached
+        .counter_incr(KeyValue::new(key.clone(), 2), true)
+        .await
+        .unwrap();
+    assert_eq!(node2.counter_get(key.clone()).await.unwrap(), 3);
+    uncached.key_delete(key).await.unwrap();
+}
+
 fn pack_u32(a: u32, b: u32) -> Vec<u8> {
     (((a as u64) << 32) | b as u64).to_be_bytes().to_vec()
 }
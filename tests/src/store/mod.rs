@@ -5,6 +5,7 @@
  */
 
 pub mod blob;
+pub mod degraded;
 pub mod import_export;
 pub mod lookup;
 pub mod ops;
@@ -29,6 +30,8 @@ secret-key = "minioadmin"
 region = "eu-central-1"
 endpoint = "http://localhost:9000"
 bucket = "tmp"
+multipart.threshold = 6291456
+multipart.part-size = 5242880
 
 [store."fs"]
 type = "fs"
@@ -40,6 +43,7 @@ path = "{TMP}/rocksdb"
 
 [store."foundationdb"]
 type = "foundationdb"
+directory = "stalwart/tests"
 
 [store."sqlite"]
 type = "sqlite"
@@ -99,6 +99,61 @@ pub async fn test(db: Store) {
         db.write(batch.build_all()).await.unwrap();
     }
 
+    // Iterated keys do not include any store prefix (such as a FoundationDB directory partition)
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(Collection::Email)
+        .update_document(0);
+    for n in 0..3 {
+        batch.set(
+            ValueClass::Config(format!("prefix-key{n}").into_bytes()),
+            format!("value{n}").into_bytes(),
+        );
+    }
+    db.write(batch.build_all()).await.unwrap();
+    let mut keys = Vec::new();
+    db.iterate(
+        store::IterateParams::new(
+            ValueKey {
+                account_id: 0,
+                collection: 0,
+                document_id: 0,
+                class: ValueClass::Config(b"prefix-".to_vec()),
+            },
+            ValueKey {
+                account_id: 0,
+                collection: 0,
+                document_id: 0,
+                class: ValueClass::Config(b"prefix-\xFF".to_vec()),
+            },
+        ),
+        |key, value| {
+            keys.push((
+                String::from_utf8(key.to_vec()).unwrap(),
+                String::from_utf8(value.to_vec()).unwrap(),
+            ));
+            Ok(true)
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        keys,
+        (0..3)
+            .map(|n| (format!("prefix-key{n}"), format!("value{n}")))
+            .collect::<Vec<_>>()
+    );
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(Collection::Email)
+        .update_document(0);
+    for n in 0..3 {
+        batch.clear(ValueClass::Config(format!("prefix-key{n}").into_bytes()));
+    }
+    db.write(batch.build_all()).await.unwrap();
+
     // Merge values 1000 times concurrently
     let mut handles = Vec::new();
     println!("Merge values 1000 times concurrently...");
@@ -21,7 +21,8 @@ pub async fn test(test: &WebDavTest) {
             "dav",
             concat!(
                 "1, 2, 3, access-control, extended-mkcol, calendar-access, ",
-                "calendar-auto-schedule, calendar-no-timezone, addressbook"
+                "calendar-auto-schedule, calendar-no-timezone, addressbook, ",
+                "resource-sharing, calendarserver-sharing"
             ),
         )
         .with_header(
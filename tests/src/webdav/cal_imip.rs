@@ -0,0 +1,240 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::WebDavTest;
+use crate::webdav::cal_scheduling::{fetch_and_remove_itips, fetch_icals};
+use directory::backend::internal::{
+    PrincipalField, PrincipalUpdate, PrincipalValue,
+    manage::{ManageDirectory, UpdatePrincipal},
+};
+use groupware::{
+    calendar::itip::{ItipIngest, ItipIngestError},
+    scheduling::ItipError,
+};
+use hyper::StatusCode;
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running iMIP auto-add tests...");
+    let client = test.client("john");
+
+    // Add a known sender to the address book
+    let card_path = "/dav/card/john/default/known.vcf";
+    client
+        .request_with_headers(
+            "PUT",
+            card_path,
+            [("content-type", "text/vcard; charset=utf-8")],
+            TEST_VCARD.replace("\n", "\r\n"),
+        )
+        .await
+        .with_status(StatusCode::CREATED);
+
+    for (idx, (auto_add, sender, expect_event)) in [
+        ("known-senders", "unknown@example.org", false),
+        ("known-senders", "known@example.org", true),
+        ("never", "known@example.org", false),
+        ("always", "unknown@example.org", true),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        // Update the user's auto-add setting
+        test.server
+            .store()
+            .update_principal(UpdatePrincipal::by_id(client.account_id).with_updates(vec![
+                PrincipalUpdate::set(
+                    PrincipalField::CalendarAutoAdd,
+                    PrincipalValue::String(auto_add.into()),
+                ),
+            ]))
+            .await
+            .unwrap();
+        test.server
+            .inner
+            .cache
+            .access_tokens
+            .remove(&client.account_id);
+        let access_token = test
+            .server
+            .get_access_token(client.account_id)
+            .await
+            .unwrap();
+
+        // Deliver the invitation
+        let uid = format!("imip-auto-add-{idx}");
+        let result = test
+            .server
+            .itip_ingest(
+                &access_token,
+                &access_token.as_resource_token(),
+                sender,
+                "jdoe@example.com",
+                &TEST_ITIP
+                    .replace("$UID", &uid)
+                    .replace("$SENDER", sender)
+                    .replace("\n", "\r\n"),
+            )
+            .await;
+        assert!(
+            matches!(result, Ok(None)),
+            "iTIP ingest failed for {auto_add}/{sender}"
+        );
+
+        // The invitation is always available in the scheduling inbox
+        let itips = fetch_and_remove_itips(client).await;
+        assert_eq!(itips.len(), 1, "{auto_add}/{sender}: {itips:?}");
+        assert!(itips[0].contains(&uid), "{auto_add}/{sender}: {itips:?}");
+
+        // Only auto-added invitations create a tentative event
+        let events = fetch_icals(client).await;
+        if expect_event {
+            assert_eq!(events.len(), 1, "{auto_add}/{sender}: {events:?}");
+            let event = &events[0];
+            assert!(event.ical.contains(&uid), "{auto_add}/{sender}: {event:?}");
+            assert!(
+                event.ical.contains("PARTSTAT=TENTATIVE"),
+                "{auto_add}/{sender}: {event:?}"
+            );
+            client
+                .request("DELETE", &event.href, "")
+                .await
+                .with_status(StatusCode::NO_CONTENT);
+        } else {
+            assert!(events.is_empty(), "{auto_add}/{sender}: {events:?}");
+        }
+    }
+
+    // Restore the default setting
+    test.server
+        .store()
+        .update_principal(UpdatePrincipal::by_id(client.account_id).with_updates(vec![
+            PrincipalUpdate::set(
+                PrincipalField::CalendarAutoAdd,
+                PrincipalValue::String(String::new()),
+            ),
+        ]))
+        .await
+        .unwrap();
+    test.server
+        .inner
+        .cache
+        .access_tokens
+        .remove(&client.account_id);
+
+    client
+        .request("DELETE", card_path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
+    // Grant Jane the invite delivery privilege on John's calendar
+    println!("Running iTIP delegation tests...");
+    let delegate_client = test.client("jane");
+    client
+        .request("ACL", "/dav/cal/john/default/", TEST_ACL_DELIVER_INVITE)
+        .await
+        .with_status(StatusCode::OK);
+    let access_token = test
+        .server
+        .get_access_token(client.account_id)
+        .await
+        .unwrap();
+    let itip_sent_by = TEST_ITIP
+        .replace("$UID", "imip-sent-by")
+        .replace("$SENDER", "boss@example.org")
+        .replace(
+            "ORGANIZER:",
+            "ORGANIZER;SENT-BY=\"mailto:assistant@example.org\":",
+        )
+        .replace("\n", "\r\n");
+
+    // Senders acting on behalf of the organizer are not accepted
+    // unless listed in the SENT-BY parameter
+    assert!(matches!(
+        test.server
+            .itip_ingest(
+                &access_token,
+                &access_token.as_resource_token(),
+                "intruder@example.org",
+                "jdoe@example.com",
+                &itip_sent_by,
+            )
+            .await,
+        Err(ItipIngestError::Message(
+            ItipError::SenderIsNotOrganizerNorAttendee
+        ))
+    ));
+    let result = test
+        .server
+        .itip_ingest(
+            &access_token,
+            &access_token.as_resource_token(),
+            "assistant@example.org",
+            "jdoe@example.com",
+            &itip_sent_by,
+        )
+        .await;
+    assert!(matches!(result, Ok(None)), "SENT-BY iTIP ingest failed");
+
+    // The invitation is delivered to John and forwarded to Jane
+    for client in [client, delegate_client] {
+        let itips = fetch_and_remove_itips(client).await;
+        assert_eq!(itips.len(), 1, "{}: {itips:?}", client.name);
+        assert!(
+            itips[0].contains("imip-sent-by"),
+            "{}: {itips:?}",
+            client.name
+        );
+        assert!(
+            itips[0].contains("SENT-BY=\"mailto:assistant@example.org\""),
+            "{}: {itips:?}",
+            client.name
+        );
+    }
+    assert!(fetch_icals(client).await.is_empty());
+    assert!(fetch_icals(delegate_client).await.is_empty());
+
+    client.delete_default_containers().await;
+    delegate_client.delete_default_containers().await;
+    test.assert_is_empty().await;
+}
+
+const TEST_ACL_DELIVER_INVITE: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <D:acl xmlns:D="DAV:" xmlns:A="urn:ietf:params:xml:ns:caldav">
+     <D:ace>
+       <D:principal>
+         <D:href>/dav/pal/jane/</D:href>
+       </D:principal>
+       <D:grant>
+         <D:privilege><D:read/></D:privilege>
+         <D:privilege><A:schedule-deliver-invite/></D:privilege>
+       </D:grant>
+     </D:ace>
+   </D:acl>"#;
+
+const TEST_VCARD: &str = r#"BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:imip-known-sender
+FN:Known Sender
+EMAIL:known@example.org
+END:VCARD
+"#;
+
+const TEST_ITIP: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Stalwart Labs LLC//Stalwart Server//EN
+METHOD:REQUEST
+BEGIN:VEVENT
+UID:$UID
+DTSTAMP:20250101T000000Z
+DTSTART:20300101T100000Z
+DTEND:20300101T110000Z
+SUMMARY:Planning meeting
+ORGANIZER:mailto:$SENDER
+ATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:jdoe@example.com
+SEQUENCE:0
+END:VEVENT
+END:VCALENDAR
+"#;
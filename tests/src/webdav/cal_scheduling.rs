@@ -527,7 +527,7 @@ pub async fn test(test: &WebDavTest) {
     test.assert_is_empty().await;
 }
 
-async fn fetch_and_remove_itips(client: &DummyWebDavClient) -> Vec<String> {
+pub(super) async fn fetch_and_remove_itips(client: &DummyWebDavClient) -> Vec<String> {
     let inbox_href = format!("/dav/itip/{}/inbox/", client.name);
     let response = client
         .propfind_with_headers(&inbox_href, ALL_DAV_PROPERTIES, [("depth", "1")])
@@ -552,13 +552,13 @@ async fn fetch_and_remove_itips(client: &DummyWebDavClient) -> Vec<String> {
 }
 
 #[derive(Debug)]
-struct CalEntry {
-    href: String,
-    ical: String,
+pub(super) struct CalEntry {
+    pub(super) href: String,
+    pub(super) ical: String,
     schedule_tag: String,
 }
 
-async fn fetch_icals(client: &DummyWebDavClient) -> Vec<CalEntry> {
+pub(super) async fn fetch_icals(client: &DummyWebDavClient) -> Vec<CalEntry> {
     let cal_inbox = format!("/dav/cal/{}/default/", client.name);
     let response = client
         .propfind_with_headers(&cal_inbox, ALL_DAV_PROPERTIES, [("depth", "1")])
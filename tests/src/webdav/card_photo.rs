@@ -0,0 +1,123 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::WebDavTest;
+use crate::webdav::*;
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running contact photo tests...");
+    let john = test.client("john");
+    let jane = test.client("jane");
+    let photo = STANDARD.decode(TEST_PHOTO).unwrap();
+
+    // Inline photos are moved to the blob store
+    let path = "/dav/card/john/default/photo.vcf";
+    john.request_with_headers(
+        "PUT",
+        path,
+        [("content-type", "text/vcard; charset=utf-8")],
+        TEST_VCARD_PHOTO
+            .replace("$PHOTO", TEST_PHOTO)
+            .replace("\n", "\r\n"),
+    )
+    .await
+    .with_status(StatusCode::CREATED);
+    let card = john
+        .request("GET", path, "")
+        .await
+        .with_status(StatusCode::OK)
+        .body
+        .unwrap()
+        .replace("\r\n ", "");
+    assert!(!card.contains(TEST_PHOTO), "{card}");
+    let url = photo_url(&card);
+
+    // The photo can be downloaded by the card owner only
+    assert_eq!(download(john, &url).await, Some(photo.clone()));
+    assert_eq!(download(jane, &url).await, None);
+
+    // Keeping the reference on update is allowed
+    john.request_with_headers(
+        "PUT",
+        path,
+        [("content-type", "text/vcard; charset=utf-8")],
+        card.replace("FN:", "NOTE:Updated\r\nFN:"),
+    )
+    .await
+    .with_status(StatusCode::NO_CONTENT);
+    assert_eq!(download(john, &url).await, Some(photo));
+
+    // Referencing photos from other cards is not allowed
+    jane.request_with_headers(
+        "PUT",
+        "/dav/card/jane/default/photo.vcf",
+        [("content-type", "text/vcard; charset=utf-8")],
+        card.clone(),
+    )
+    .await
+    .with_status(StatusCode::PRECONDITION_FAILED)
+    .with_failed_precondition("B:valid-address-data", "");
+    john.request_with_headers(
+        "PUT",
+        "/dav/card/john/default/photo2.vcf",
+        [("content-type", "text/vcard; charset=utf-8")],
+        card.replace("UID:", "UID:other-"),
+    )
+    .await
+    .with_status(StatusCode::PRECONDITION_FAILED)
+    .with_failed_precondition("B:valid-address-data", "");
+
+    // Deleting the card unlinks the photo
+    john.request("DELETE", path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    assert_eq!(download(john, &url).await, None);
+
+    john.delete_default_containers().await;
+    jane.delete_default_containers().await;
+    test.assert_is_empty().await;
+}
+
+fn photo_url(card: &str) -> String {
+    let start = card
+        .find("https://127.0.0.1:8899/jmap/download/")
+        .unwrap_or_else(|| panic!("Photo URL not found in card: {card}"));
+    card[start..]
+        .split_once("\r\n")
+        .map(|(url, _)| url)
+        .unwrap()
+        .to_string()
+}
+
+async fn download(client: &DummyWebDavClient, url: &str) -> Option<Vec<u8>> {
+    let response = reqwest::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap()
+        .get(url)
+        .header(AUTHORIZATION, &client.credentials)
+        .send()
+        .await
+        .unwrap();
+
+    match response.status() {
+        StatusCode::OK => Some(response.bytes().await.unwrap().to_vec()),
+        StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => None,
+        status => panic!("Unexpected status {status} downloading {url}"),
+    }
+}
+
+const TEST_PHOTO: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+const TEST_VCARD_PHOTO: &str = r#"BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:5b2d8e1c-photo-test
+FN:Photo Test
+N:Test;Photo;;;
+PHOTO:data:image/png;base64,$PHOTO
+END:VCARD
+"#;
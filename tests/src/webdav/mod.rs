@@ -49,9 +49,11 @@ use utils::config::Config;
 pub mod acl;
 pub mod basic;
 pub mod cal_alarm;
+pub mod cal_imip;
 pub mod cal_itip;
 pub mod cal_query;
 pub mod cal_scheduling;
+pub mod card_photo;
 pub mod card_query;
 pub mod copy_move;
 pub mod lock;
@@ -60,6 +62,8 @@ pub mod multiget;
 pub mod principals;
 pub mod prop;
 pub mod put_get;
+pub mod search;
+pub mod share;
 pub mod sync;
 
 #[test]
@@ -94,11 +98,15 @@ fn webdav_tests() {
             lock::test(&handle).await;
             principals::test(&handle, assisted_discovery).await;
             acl::test(&handle).await;
+            share::test(&handle).await;
             card_query::test(&handle).await;
+            card_photo::test(&handle).await;
             cal_query::test(&handle).await;
             cal_alarm::test(&handle).await;
+            cal_imip::test(&handle).await;
             cal_itip::test();
             cal_scheduling::test(&handle).await;
+            search::test(&handle).await;
 
             // Print elapsed time
             let elapsed = start_time.elapsed();
@@ -0,0 +1,178 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::WebDavTest;
+use crate::{jmap::wait_for_index, webdav::*};
+use serde_json::{Value, json};
+
+const CARD_PATH: &str = "/dav/card/john/default/search.vcf";
+const EVENT_PATH: &str = "/dav/cal/john/default/search.ics";
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running unified search tests...");
+    let john = test.client("john");
+    let jane = test.client("jane");
+
+    // Create a contact and an event
+    for (path, content_type, contents) in [
+        (CARD_PATH, "text/vcard; charset=utf-8", TEST_VCARD),
+        (EVENT_PATH, "text/calendar; charset=utf-8", TEST_ICAL),
+    ] {
+        john.request_with_headers(
+            "PUT",
+            path,
+            [("content-type", content_type)],
+            contents.replace('\n', "\r\n"),
+        )
+        .await
+        .with_status(StatusCode::CREATED);
+    }
+    wait_for_index(&test.server).await;
+
+    // Calendar events are matched by summary, description and participants
+    for text in ["engine", "babbage"] {
+        let results = search(john, &format!("text={text}&types=calendarEvent")).await;
+        let events = results["data"]["calendarEvent"].as_array().unwrap();
+        assert_eq!(events.len(), 1, "{results}");
+        assert_eq!(events[0]["href"], EVENT_PATH, "{results}");
+        assert!(events[0]["id"].is_string(), "{results}");
+    }
+    let results = search(john, "text=engine&types=calendarEvent").await;
+    assert_eq!(
+        results["data"]["calendarEvent"][0]["title"], "Analytical <mark>engine</mark> review",
+        "{results}"
+    );
+    assert!(
+        results["data"]["calendarEvent"][0]["preview"]
+            .as_str()
+            .unwrap()
+            .contains("<mark>engine</mark>"),
+        "{results}"
+    );
+
+    // Contacts are matched by name, e-mail, organization and notes
+    for text in ["lovelace", "mathematician"] {
+        let results = search(john, &format!("text={text}")).await;
+        assert_eq!(results["data"]["email"], json!([]), "{results}");
+        assert_eq!(results["data"]["calendarEvent"], json!([]), "{results}");
+        let cards = results["data"]["contactCard"].as_array().unwrap();
+        assert_eq!(cards.len(), 1, "{results}");
+        assert_eq!(cards[0]["href"], CARD_PATH, "{results}");
+    }
+    assert_eq!(
+        search(john, "text=lovelace&types=contactCard").await["data"]["contactCard"][0]["title"],
+        "Ada <mark>Lovelace</mark>"
+    );
+
+    // Items from other accounts are not returned
+    let results = search(jane, "text=lovelace&types=contactCard,calendarEvent").await;
+    assert_eq!(
+        results["data"],
+        json!({"contactCard": [], "calendarEvent": []}),
+        "{results}"
+    );
+
+    // Updated items are reindexed
+    john.request_with_headers(
+        "PUT",
+        CARD_PATH,
+        [("content-type", "text/vcard; charset=utf-8")],
+        TEST_VCARD.replace("Lovelace", "King").replace('\n', "\r\n"),
+    )
+    .await
+    .with_status(StatusCode::NO_CONTENT);
+    wait_for_index(&test.server).await;
+    assert_eq!(
+        search(john, "text=lovelace&types=contactCard").await["data"]["contactCard"],
+        json!([])
+    );
+    assert_eq!(
+        search(john, "text=king&types=contactCard").await["data"]["contactCard"][0]["href"],
+        CARD_PATH
+    );
+
+    // Invalid requests
+    assert_eq!(
+        search(john, "types=contactCard").await,
+        json!({"error": "fieldMissing", "field": "text"})
+    );
+    assert_eq!(
+        search(john, "text=lovelace&types=task").await["error"],
+        "unsupported"
+    );
+
+    // Deleted items are removed from the index
+    for path in [CARD_PATH, EVENT_PATH] {
+        john.request("DELETE", path, "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+    }
+    wait_for_index(&test.server).await;
+    for text in ["engine", "king"] {
+        let results = search(
+            john,
+            &format!("text={text}&types=calendarEvent,contactCard"),
+        )
+        .await;
+        assert_eq!(
+            results["data"],
+            json!({"calendarEvent": [], "contactCard": []}),
+            "{results}"
+        );
+    }
+    for collection in [Collection::CalendarEvent, Collection::ContactCard] {
+        assert!(
+            test.server
+                .core
+                .storage
+                .fts
+                .indexed_document_ids(john.account_id, collection)
+                .await
+                .unwrap()
+                .unwrap_or_default()
+                .is_empty(),
+            "{collection:?}"
+        );
+    }
+
+    john.delete_default_containers().await;
+    jane.delete_default_containers().await;
+    test.assert_is_empty().await;
+}
+
+async fn search(client: &DummyWebDavClient, query: &str) -> Value {
+    let response = client
+        .request("GET", &format!("/api/search?{query}"), "")
+        .await
+        .with_status(StatusCode::OK);
+    serde_json::from_str(response.body.as_ref().unwrap()).unwrap()
+}
+
+const TEST_VCARD: &str = r#"BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:7c1f3a52-search-test
+FN:Ada Lovelace
+N:Lovelace;Ada;;;
+EMAIL:ada@example.com
+NOTE:Mathematician and writer
+END:VCARD
+"#;
+
+const TEST_ICAL: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Stalwart Labs//Search Test//EN
+BEGIN:VEVENT
+UID:search-test-event
+DTSTAMP:20250101T090000Z
+DTSTART:20250110T090000Z
+DTEND:20250110T100000Z
+SUMMARY:Analytical engine review
+DESCRIPTION:Bring the notes on the engine design
+ORGANIZER:mailto:john@example.com
+ATTENDEE;CN=Charles Babbage:mailto:charles@example.com
+END:VEVENT
+END:VCALENDAR
+"#;
@@ -0,0 +1,248 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs LLC <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use dav_proto::schema::property::{DavProperty, WebDavProperty};
+use groupware::DavResourceName;
+use hyper::StatusCode;
+
+use crate::webdav::GenerateTestDavResource;
+
+use super::{DavResponse, DummyWebDavClient, WebDavTest};
+
+pub async fn test(test: &WebDavTest) {
+    let owner_client = test.client("bill");
+    let sharee_client = test.client("john");
+
+    for resource_type in [DavResourceName::Cal, DavResourceName::Card] {
+        println!(
+            "Running sharing invitation tests ({})...",
+            resource_type.base_path()
+        );
+        let sharee_base_path = format!("{}/john/", resource_type.base_path());
+        let owner_base_path = format!("{}/bill/", resource_type.base_path());
+        let owner_folder = format!("{owner_base_path}test-invite/");
+        let sharee_file = format!("{owner_folder}test-file-sharee");
+        owner_client
+            .request("MKCOL", &owner_folder, "")
+            .await
+            .with_status(StatusCode::CREATED);
+
+        // Collections are not shared by default
+        let response = owner_client
+            .propfind(
+                &owner_folder,
+                [
+                    DavProperty::WebDav(WebDavProperty::ShareAccess),
+                    DavProperty::WebDav(WebDavProperty::AllowedSharingModes),
+                ],
+            )
+            .await;
+        let props = response.properties(&owner_folder);
+        props
+            .get(DavProperty::WebDav(WebDavProperty::ShareAccess))
+            .with_values(["D:not-shared"]);
+        props
+            .get(DavProperty::WebDav(WebDavProperty::AllowedSharingModes))
+            .with_values(["C:can-be-shared"]);
+
+        // Sharees can only be invited by the owner
+        sharee_client
+            .share(&owner_folder, "mailto:jdoe@example.com", "read")
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+        owner_client
+            .share(&owner_folder, "mailto:unknown@example.com", "read")
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+
+        // Invite the sharee by e-mail address
+        owner_client
+            .share(&owner_folder, "mailto:jdoe@example.com", "read-write")
+            .await
+            .with_status(StatusCode::OK);
+        let response = owner_client
+            .propfind(
+                &owner_folder,
+                [
+                    DavProperty::WebDav(WebDavProperty::ShareAccess),
+                    DavProperty::WebDav(WebDavProperty::Invite),
+                ],
+            )
+            .await;
+        let props = response.properties(&owner_folder);
+        props
+            .get(DavProperty::WebDav(WebDavProperty::ShareAccess))
+            .with_values(["D:shared-owner"]);
+        props
+            .get(DavProperty::WebDav(WebDavProperty::Invite))
+            .with_values([
+                "D:sharee.D:href:mailto:jdoe@example.com",
+                "D:sharee.D:prop.D:displayname:John Doe",
+                "D:sharee.D:share-access.D:read-write",
+                "D:sharee.D:invite-noresponse",
+            ]);
+
+        // The shared collection is visible to the sharee
+        sharee_client
+            .propfind_with_headers(
+                &owner_base_path,
+                [DavProperty::WebDav(WebDavProperty::GetETag)],
+                [("prefer", "depth-noroot")],
+            )
+            .await
+            .with_hrefs([owner_folder.as_str()]);
+        sharee_client
+            .propfind(
+                &owner_folder,
+                [DavProperty::WebDav(WebDavProperty::ShareAccess)],
+            )
+            .await
+            .properties(&owner_folder)
+            .get(DavProperty::WebDav(WebDavProperty::ShareAccess))
+            .with_values(["D:read-write"]);
+        sharee_client
+            .request("PUT", &sharee_file, resource_type.generate())
+            .await
+            .with_status(StatusCode::CREATED);
+
+        // Accept the invitation
+        sharee_client
+            .invite_reply(&owner_folder, true)
+            .await
+            .with_status(StatusCode::OK);
+        owner_client
+            .propfind(
+                &owner_folder,
+                [DavProperty::WebDav(WebDavProperty::CalendarServerInvite)],
+            )
+            .await
+            .properties(&owner_folder)
+            .get(DavProperty::WebDav(WebDavProperty::CalendarServerInvite))
+            .with_values([
+                "C:user.D:href:mailto:jdoe@example.com",
+                "C:user.C:common-name:John Doe",
+                "C:user.C:invite-accepted",
+                "C:user.C:access.C:read-write",
+            ]);
+
+        // Downgrade to read-only access
+        owner_client
+            .share(&owner_folder, "mailto:jdoe@example.com", "read")
+            .await
+            .with_status(StatusCode::OK);
+        sharee_client
+            .request("PUT", &sharee_file, resource_type.generate())
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+
+        // Declining the invitation revokes access
+        sharee_client
+            .invite_reply(&owner_folder, false)
+            .await
+            .with_status(StatusCode::OK);
+        owner_client
+            .propfind(
+                &owner_folder,
+                [
+                    DavProperty::WebDav(WebDavProperty::ShareAccess),
+                    DavProperty::WebDav(WebDavProperty::Invite),
+                ],
+            )
+            .await
+            .properties(&owner_folder)
+            .get(DavProperty::WebDav(WebDavProperty::ShareAccess))
+            .with_values(["D:not-shared"]);
+        sharee_client
+            .propfind_with_headers(
+                resource_type.collection_path(),
+                [DavProperty::WebDav(WebDavProperty::GetETag)],
+                [("prefer", "depth-noroot")],
+            )
+            .await
+            .with_hrefs([sharee_base_path.as_str()]);
+        sharee_client
+            .invite_reply(&owner_folder, true)
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+
+        // Share again and remove the sharee
+        owner_client
+            .share(&owner_folder, "mailto:jdoe@example.com", "read")
+            .await
+            .with_status(StatusCode::OK);
+        owner_client
+            .share(&owner_folder, "mailto:jdoe@example.com", "no-access")
+            .await
+            .with_status(StatusCode::OK);
+        sharee_client
+            .propfind_with_headers(
+                resource_type.collection_path(),
+                [DavProperty::WebDav(WebDavProperty::GetETag)],
+                [("prefer", "depth-noroot")],
+            )
+            .await
+            .with_hrefs([sharee_base_path.as_str()]);
+
+        // Delete resources
+        owner_client
+            .request("DELETE", &owner_folder, "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+    }
+
+    sharee_client.delete_default_containers().await;
+    owner_client.delete_default_containers().await;
+    test.assert_is_empty().await;
+}
+
+impl DummyWebDavClient {
+    pub async fn share(&self, query: &str, sharee_href: &str, access: &str) -> DavResponse {
+        self.request_with_headers(
+            "POST",
+            query,
+            [("content-type", "application/davsharing+xml")],
+            SHARE_QUERY
+                .replace("$HREF", sharee_href)
+                .replace("$ACCESS", access),
+        )
+        .await
+    }
+
+    pub async fn invite_reply(&self, query: &str, accept: bool) -> DavResponse {
+        self.request_with_headers(
+            "POST",
+            query,
+            [("content-type", "text/xml")],
+            INVITE_REPLY_QUERY.replace("$HREF", query).replace(
+                "$REPLY",
+                if accept {
+                    "invite-accepted"
+                } else {
+                    "invite-declined"
+                },
+            ),
+        )
+        .await
+    }
+}
+
+const SHARE_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <D:share-resource xmlns:D="DAV:">
+     <D:sharee>
+       <D:href>$HREF</D:href>
+       <D:share-access>
+         <D:$ACCESS/>
+       </D:share-access>
+     </D:sharee>
+   </D:share-resource>"#;
+
+const INVITE_REPLY_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <C:invite-reply xmlns:D="DAV:" xmlns:C="http://calendarserver.org/ns/">
+     <C:$REPLY/>
+     <C:hosturl>
+       <D:href>$HREF</D:href>
+     </C:hosturl>
+   </C:invite-reply>"#;